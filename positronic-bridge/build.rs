@@ -0,0 +1,55 @@
+//! Stamps the build for `!ver` and `positronic --version` (see
+//! `positronic_core::version`): `git describe`, the build date (UTC, or
+//! `SOURCE_DATE_EPOCH` for reproducible builds), target, profile and the
+//! enabled cargo features.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    let git = Command::new("git")
+        .args(["describe", "--tags", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .filter(|describe| !describe.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+    let (y, m, d) = civil_date(secs / 86_400);
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+
+    let env = |key: &str| std::env::var(key).unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=POSITRONIC_GIT_DESCRIBE={}", git);
+    println!("cargo:rustc-env=POSITRONIC_BUILD_DATE={:04}-{:02}-{:02}", y, m, d);
+    println!("cargo:rustc-env=POSITRONIC_TARGET={}", env("TARGET"));
+    println!("cargo:rustc-env=POSITRONIC_PROFILE={}", env("PROFILE"));
+    println!("cargo:rustc-env=POSITRONIC_FEATURES={}", features.join(","));
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs");
+    println!("cargo:rerun-if-changed=../.git/index");
+}
+
+/// Days since 1970-01-01 as a proleptic Gregorian (year, month, day).
+fn civil_date(days: u64) -> (i64, u32, u32) {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    (y, m, d)
+}
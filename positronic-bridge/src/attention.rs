@@ -0,0 +1,387 @@
+//! Unread markers for output the user hasn't looked at, and dimming of
+//! output older than the last few commands.
+//!
+//! A block that finishes while the window is unfocused, or while the view
+//! is scrolled back, is unread: the terminal marks it with a bar on its
+//! left edge and the status bar counts it ("● 3 unread"). It becomes read
+//! once it has been fully in view, with the window focused, for
+//! `MIN_DWELL`; scrolling past it faster, or losing focus before the dwell
+//! is up, doesn't count. A block taller than the view counts as in view
+//! while its last line is.
+//!
+//! `Attention` is that state machine. Its inputs are focus changes, the
+//! lines in view and block completions, each with the time it happened,
+//! so it is tested with made-up instants. Lines are in whatever space the
+//! caller lays blocks out in: `BlockManager` counts its blocks' lines, the
+//! terminal view the PTY's scrollback lines.
+//!
+//! Dimming is separate and only drawn: with `blocks.dim_old N`, output
+//! above the Nth most recent command is faded by `DIM_FACTOR`. Copy and
+//! export read the text, so they don't see it.
+//!
+//! `ScreenMarks` also keeps each terminal block's command, as sent, so a
+//! finished block can be focused (Ctrl+Up/Down) and its command taken
+//! back into the input line to edit and run again. The run that follows
+//! records the block it came from (`derived_from`); `chain` follows those
+//! links back for `!chain`.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::block::BlockId;
+use crate::renderer::{ColoredSpan, Rgba};
+
+/// Vault config key: commands kept at full strength, or `off`.
+pub const DIM_OLD_KEY: &str = "blocks.dim_old";
+pub const DEFAULT_DIM_OLD: usize = 10;
+
+/// How much of its opacity dimmed output keeps.
+pub const DIM_FACTOR: f32 = 0.45;
+
+/// How long a block must stay in view, focused, to count as read.
+pub const MIN_DWELL: Duration = Duration::from_millis(750);
+
+/// Commands `ScreenMarks` remembers for dimming and focus.
+const MAX_BLOCKS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Unread {
+    lines: Range<usize>,
+    /// Since when it has been in view with the window focused.
+    watched_since: Option<Instant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Attention {
+    focused: bool,
+    /// The view follows new output (not scrolled back).
+    anchored: bool,
+    view: Range<usize>,
+    unread: BTreeMap<BlockId, Unread>,
+    dwell: Duration,
+}
+
+impl Default for Attention {
+    fn default() -> Self {
+        Self::new(MIN_DWELL)
+    }
+}
+
+impl Attention {
+    /// Focused and anchored, so blocks finish read until told otherwise.
+    pub fn new(dwell: Duration) -> Self {
+        Self { focused: true, anchored: true, view: 0..0, unread: BTreeMap::new(), dwell }
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focus(&mut self, focused: bool, now: Instant) {
+        self.focused = focused;
+        self.watch(now);
+    }
+
+    /// The lines in view now, and whether the view follows new output.
+    pub fn set_view(&mut self, view: Range<usize>, anchored: bool, now: Instant) {
+        self.view = view;
+        self.anchored = anchored;
+        self.watch(now);
+    }
+
+    /// Block `id` finished, laid out over `lines`. True if it is unread:
+    /// the window was unfocused or the view scrolled back.
+    pub fn finished(&mut self, id: BlockId, lines: Range<usize>, now: Instant) -> bool {
+        if self.focused && self.anchored {
+            self.unread.remove(&id);
+            return false;
+        }
+        self.unread.insert(id, Unread { lines, watched_since: None });
+        self.watch(now);
+        true
+    }
+
+    /// Mark the blocks watched for the dwell time read; returns them.
+    pub fn tick(&mut self, now: Instant) -> Vec<BlockId> {
+        let dwell = self.dwell;
+        let read: Vec<BlockId> = self
+            .unread
+            .iter()
+            .filter(|(_, u)| u.watched_since.is_some_and(|since| now.duration_since(since) >= dwell))
+            .map(|(&id, _)| id)
+            .collect();
+        for id in &read {
+            self.unread.remove(id);
+        }
+        read
+    }
+
+    /// When the next `tick` has a block to mark read, if one is watched.
+    pub fn next_read(&self) -> Option<Instant> {
+        self.unread.values().filter_map(|u| u.watched_since).min().map(|since| since + self.dwell)
+    }
+
+    pub fn is_unread(&self, id: BlockId) -> bool {
+        self.unread.contains_key(&id)
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.unread.len()
+    }
+
+    /// Unread blocks and their lines, oldest first.
+    pub fn unread(&self) -> impl Iterator<Item = (BlockId, &Range<usize>)> {
+        self.unread.iter().map(|(&id, u)| (id, &u.lines))
+    }
+
+    /// "● 3 unread" for the status bar; `None` when all is read.
+    pub fn label(&self) -> Option<String> {
+        (!self.unread.is_empty()).then(|| format!("● {} unread", self.unread.len()))
+    }
+
+    /// Lay the unread blocks out again after the lines moved; blocks
+    /// `lines_of` no longer knows are dropped.
+    pub fn relayout(&mut self, lines_of: impl Fn(BlockId) -> Option<Range<usize>>, now: Instant) {
+        self.unread.retain(|&id, unread| match lines_of(id) {
+            Some(lines) => {
+                unread.lines = lines;
+                true
+            }
+            None => false,
+        });
+        self.watch(now);
+    }
+
+    pub fn forget(&mut self, id: BlockId) {
+        self.unread.remove(&id);
+    }
+
+    pub fn clear(&mut self) {
+        self.unread.clear();
+    }
+
+    /// Start the dwell clock of blocks that came into view focused; stop
+    /// it for any that left view or lost focus.
+    fn watch(&mut self, now: Instant) {
+        for unread in self.unread.values_mut() {
+            let watched = self.focused && in_view(&unread.lines, &self.view);
+            match (watched, unread.watched_since) {
+                (true, None) => unread.watched_since = Some(now),
+                (false, Some(_)) => unread.watched_since = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A finished command in the terminal view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenBlock {
+    pub id: BlockId,
+    /// Scrollback lines from its start mark to its finish mark; the first
+    /// is the one the command was typed on.
+    pub lines: Range<usize>,
+    /// The line Positronic sent for it; `None` when the shell ran it by
+    /// other means (a `!redo`, keys typed into a full-screen app).
+    pub command: Option<String>,
+    pub exit_code: Option<i32>,
+    /// The block whose command this one's was edited from.
+    pub derived_from: Option<BlockId>,
+}
+
+/// The terminal view's commands, from the shell's OSC 133 marks: each
+/// one that finishes becomes a block in `attention`, over the scrollback
+/// lines between its start and finish marks.
+#[derive(Debug, Clone, Default)]
+pub struct ScreenMarks {
+    pub attention: Attention,
+    /// Recent commands, oldest first.
+    blocks: VecDeque<ScreenBlock>,
+    running: Option<usize>,
+    /// The line last sent to the shell and the block it was edited from,
+    /// until a command starts.
+    sent: Option<(String, Option<BlockId>)>,
+    /// What the running command was sent as.
+    running_command: Option<(String, Option<BlockId>)>,
+    focused: Option<BlockId>,
+    next_id: BlockId,
+}
+
+impl ScreenMarks {
+    /// `command` was sent to the shell, edited from block `derived_from`
+    /// if that is set; the next command to start is it.
+    pub fn sent(&mut self, command: &str, derived_from: Option<BlockId>) {
+        self.sent = Some((command.to_string(), derived_from));
+    }
+
+    /// A command started at `line`; a second mark before it finishes
+    /// (OSC 133;B then C) keeps the first.
+    pub fn started(&mut self, line: usize) {
+        if self.running.is_none() {
+            self.running = Some(line);
+            self.running_command = self.sent.take();
+        }
+    }
+
+    /// The start line of the running command.
+    pub fn running_since(&self) -> Option<usize> {
+        self.running
+    }
+
+    /// The running command finished at `line` with `exit_code`. True if
+    /// it is unread.
+    pub fn finished(&mut self, line: usize, exit_code: Option<i32>, now: Instant) -> bool {
+        let Some(start) = self.running.take() else {
+            return false;
+        };
+        if self.blocks.len() == MAX_BLOCKS
+            && let Some(dropped) = self.blocks.pop_front()
+            && self.focused == Some(dropped.id)
+        {
+            self.focused = None;
+        }
+        self.next_id += 1;
+        let (command, derived_from) = self.running_command.take().unzip();
+        self.blocks.push_back(ScreenBlock {
+            id: self.next_id,
+            lines: start..line.max(start),
+            command,
+            exit_code,
+            derived_from: derived_from.flatten(),
+        });
+        self.attention.finished(self.next_id, start..line.max(start), now)
+    }
+
+    /// The first line of the `keep`th most recent command, above which
+    /// output is dimmed.
+    pub fn dim_before(&self, keep: Option<usize>) -> Option<usize> {
+        let keep = keep.filter(|&keep| keep > 0)?;
+        self.blocks.len().checked_sub(keep).map(|i| self.blocks[i].lines.start)
+    }
+
+    pub fn block(&self, id: BlockId) -> Option<&ScreenBlock> {
+        self.blocks.iter().find(|b| b.id == id)
+    }
+
+    /// The newest block.
+    pub fn last(&self) -> Option<&ScreenBlock> {
+        self.blocks.back()
+    }
+
+    /// The block whose command was typed on `line`, if it has one.
+    pub fn command_at(&self, line: usize) -> Option<&ScreenBlock> {
+        self.blocks.iter().rev().find(|b| b.lines.start == line && b.command.is_some())
+    }
+
+    /// The block `line` is in, or else the last one before it.
+    pub fn nearest(&self, line: usize) -> Option<&ScreenBlock> {
+        self.blocks.iter().rev().find(|b| b.lines.start <= line)
+    }
+
+    pub fn focused(&self) -> Option<&ScreenBlock> {
+        self.focused.and_then(|id| self.block(id))
+    }
+
+    /// Focus the block before the focused one, or the newest; the oldest
+    /// stays focused.
+    pub fn focus_prev(&mut self) -> Option<&ScreenBlock> {
+        let index = match self.focused_index() {
+            Some(i) => i.saturating_sub(1),
+            None => self.blocks.len().checked_sub(1)?,
+        };
+        self.focused = Some(self.blocks[index].id);
+        self.blocks.get(index)
+    }
+
+    /// Focus the block after the focused one; past the newest, focus goes
+    /// back to the input line (`None`).
+    pub fn focus_next(&mut self) -> Option<&ScreenBlock> {
+        let next = self.focused_index().map(|i| i + 1).filter(|&i| i < self.blocks.len());
+        self.focused = next.map(|i| self.blocks[i].id);
+        next.and_then(|i| self.blocks.get(i))
+    }
+
+    pub fn unfocus(&mut self) {
+        self.focused = None;
+    }
+
+    fn focused_index(&self) -> Option<usize> {
+        let id = self.focused?;
+        self.blocks.iter().position(|b| b.id == id)
+    }
+
+    /// Block `id` and the blocks it was edited from in turn, oldest first,
+    /// as far back as the screen remembers.
+    pub fn chain(&self, id: BlockId) -> Vec<&ScreenBlock> {
+        let mut chain = Vec::new();
+        let mut next = Some(id);
+        // Each link points at an older block, so this ends.
+        while let Some(block) = next.and_then(|id| self.block(id)) {
+            chain.push(block);
+            next = block.derived_from.filter(|&from| from < block.id);
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// The screen was reset (clear, a new shell): old lines mean nothing.
+    pub fn clear(&mut self) {
+        self.attention.clear();
+        self.blocks.clear();
+        self.running = None;
+        self.sent = None;
+        self.running_command = None;
+        self.focused = None;
+    }
+}
+
+/// Fully inside `view`, or, for a block taller than it, ending inside it.
+fn in_view(lines: &Range<usize>, view: &Range<usize>) -> bool {
+    if lines.len() > view.len() {
+        lines.end > view.start && lines.end <= view.end
+    } else {
+        view.start <= lines.start && lines.end <= view.end
+    }
+}
+
+/// The rows of a view starting at line `top` that `lines` covers.
+pub fn screen_rows(lines: &Range<usize>, top: usize, rows: usize) -> Option<Range<usize>> {
+    let start = lines.start.max(top);
+    let end = lines.end.min(top + rows);
+    (start < end).then(|| start - top..end - top)
+}
+
+/// Parse `blocks.dim_old`: a number of commands, or `off`.
+pub fn parse_dim_old(value: &str) -> Option<Option<usize>> {
+    match value.trim().to_lowercase().as_str() {
+        "off" | "false" | "no" | "0" => Some(None),
+        n => n.parse().ok().map(Some),
+    }
+}
+
+pub fn dim(color: Rgba) -> Rgba {
+    Rgba { a: color.a * DIM_FACTOR, ..color }
+}
+
+/// Dim the first `rows` rows of `spans`, where each row ends in `\n`. A
+/// span running past the last dimmed row is split there.
+pub fn dim_rows(spans: &mut Vec<ColoredSpan>, rows: usize) {
+    let mut left = rows;
+    let mut i = 0;
+    while left > 0 && i < spans.len() {
+        let newlines: Vec<usize> = spans[i].text.match_indices('\n').map(|(at, _)| at).collect();
+        if newlines.len() >= left {
+            let cut = newlines[left - 1] + 1;
+            if cut < spans[i].text.len() {
+                let rest = ColoredSpan { text: spans[i].text.split_off(cut), ..spans[i].clone() };
+                spans.insert(i + 1, rest);
+            }
+            left = 0;
+        } else {
+            left -= newlines.len();
+        }
+        spans[i].color = dim(spans[i].color);
+        i += 1;
+    }
+}
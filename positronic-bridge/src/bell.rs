@@ -0,0 +1,352 @@
+//! What a terminal bell (BEL) does.
+//!
+//! A bell can sound, flash the status bar, raise a desktop notification,
+//! be announced through BioLink, or do nothing. `bell.action` picks one
+//! for every bell, and `bell.follow`, `bell.remote` and `bell.unfocused`
+//! override it while a `!follow` pane is open, while the running command
+//! is a remote session (`ssh`, `mosh`…), and while the window is
+//! unfocused, in that order of precedence. `!bell mute 10m` silences all
+//! of them until it runs out.
+//!
+//! `resolve` is the decision alone: the policy and a `BellContext` in,
+//! the action and the rule that chose it out. Bells that come close
+//! together are gathered by `Coalescer` into one action with a count, and
+//! `ring` carries the action out through a `BellSink`, which the app
+//! implements over the platform hooks.
+
+use std::time::{Duration, Instant};
+
+use chrono::NaiveTime;
+
+/// Vault config keys: the action for every bell, and its overrides.
+pub const ACTION_KEY: &str = "bell.action";
+pub const FOLLOW_KEY: &str = "bell.follow";
+pub const REMOTE_KEY: &str = "bell.remote";
+pub const UNFOCUSED_KEY: &str = "bell.unfocused";
+
+pub const BELL_USAGE: &str = "Usage: !bell [test | mute <duration> | unmute]";
+
+/// Bells this close to the first of a run are one action.
+pub const COALESCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// How long the status bar stays flashed.
+pub const FLASH_DURATION: Duration = Duration::from_millis(100);
+
+/// Longest `!bell mute`.
+pub const MAX_MUTE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Programs whose bells come from another machine.
+const REMOTE_PROGRAMS: &[&str] = &["ssh", "autossh", "sshpass", "mosh", "mosh-client", "et", "telnet"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BellAction {
+    /// The platform's alert sound.
+    Sound,
+    /// Flash the status bar for `FLASH_DURATION`.
+    Flash,
+    /// A desktop notification.
+    Notify,
+    /// Said by the screen reader or speech (BioLink).
+    Announce,
+    Silent,
+}
+
+impl BellAction {
+    pub fn parse(value: &str) -> Option<BellAction> {
+        match value.trim().to_lowercase().as_str() {
+            "sound" | "audible" | "beep" => Some(BellAction::Sound),
+            "flash" | "visual" => Some(BellAction::Flash),
+            "notify" | "notification" => Some(BellAction::Notify),
+            "announce" | "speak" => Some(BellAction::Announce),
+            "silent" | "off" | "none" => Some(BellAction::Silent),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BellAction::Sound => "sound",
+            BellAction::Flash => "flash",
+            BellAction::Notify => "notify",
+            BellAction::Announce => "announce",
+            BellAction::Silent => "silent",
+        }
+    }
+}
+
+/// The configured actions. An override that is `None` leaves the
+/// decision to the next rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BellPolicy {
+    pub action: BellAction,
+    pub follow: Option<BellAction>,
+    pub remote: Option<BellAction>,
+    pub unfocused: Option<BellAction>,
+}
+
+impl Default for BellPolicy {
+    fn default() -> Self {
+        Self {
+            action: BellAction::Flash,
+            follow: Some(BellAction::Silent),
+            remote: None,
+            unfocused: Some(BellAction::Notify),
+        }
+    }
+}
+
+impl BellPolicy {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`; `default` clears an override.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> BellPolicy {
+        let mut policy = BellPolicy::default();
+        let expected = "expected sound, flash, notify, announce or silent";
+        if let Some(value) = lookup(ACTION_KEY) {
+            match BellAction::parse(&value) {
+                Some(action) => policy.action = action,
+                None => problems.push(format!("{} = \"{}\": {}", ACTION_KEY, value, expected)),
+            }
+        }
+        for (key, slot) in
+            [(FOLLOW_KEY, &mut policy.follow), (REMOTE_KEY, &mut policy.remote), (UNFOCUSED_KEY, &mut policy.unfocused)]
+        {
+            let Some(value) = lookup(key) else {
+                continue;
+            };
+            match BellAction::parse(&value) {
+                Some(action) => *slot = Some(action),
+                None if value.trim().eq_ignore_ascii_case("default") => *slot = None,
+                None => problems.push(format!("{} = \"{}\": {}, or default", key, value, expected)),
+            }
+        }
+        policy
+    }
+}
+
+/// What holds when a bell arrives.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BellContext {
+    pub focused: bool,
+    /// The running command is a remote session (`is_remote`).
+    pub remote: bool,
+    /// A `!follow` pane is open.
+    pub follow: bool,
+    /// `!bell mute` hasn't run out.
+    pub muted: bool,
+}
+
+/// Which rule decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BellRule {
+    Muted,
+    Follow,
+    Remote,
+    Unfocused,
+    Default,
+}
+
+impl BellRule {
+    /// Why, for `!bell test`.
+    pub fn reason(self) -> &'static str {
+        match self {
+            BellRule::Muted => "muted",
+            BellRule::Follow => "a !follow pane is open",
+            BellRule::Remote => "a remote session is running",
+            BellRule::Unfocused => "the window is unfocused",
+            BellRule::Default => "bell.action",
+        }
+    }
+}
+
+/// The action for a bell in `context`: muted beats every override, then
+/// `!follow`, remote, unfocused, and otherwise `bell.action`.
+pub fn resolve(policy: &BellPolicy, context: BellContext) -> (BellAction, BellRule) {
+    if context.muted {
+        return (BellAction::Silent, BellRule::Muted);
+    }
+    let overrides = [
+        (context.follow, policy.follow, BellRule::Follow),
+        (context.remote, policy.remote, BellRule::Remote),
+        (!context.focused, policy.unfocused, BellRule::Unfocused),
+    ];
+    overrides
+        .into_iter()
+        .find_map(|(holds, action, rule)| action.filter(|_| holds).map(|action| (action, rule)))
+        .unwrap_or((policy.action, BellRule::Default))
+}
+
+/// Whether the running `command` is a session on another machine.
+pub fn is_remote(command: &str) -> bool {
+    let Some(program) = command.split_whitespace().next() else {
+        return false;
+    };
+    let program = program.rsplit(['/', '\\']).next().unwrap_or(program);
+    let program = program.strip_suffix(".exe").unwrap_or(program);
+    REMOTE_PROGRAMS.contains(&program)
+}
+
+/// Gathers the bells of a run: the first opens a `COALESCE_WINDOW`, and
+/// when it closes they are due as one, with their count.
+#[derive(Debug, Clone, Default)]
+pub struct Coalescer {
+    /// When the open run started, and its bells so far.
+    pending: Option<(Instant, usize)>,
+}
+
+impl Coalescer {
+    pub fn ring(&mut self, count: usize, now: Instant) {
+        if count == 0 {
+            return;
+        }
+        let (_, bells) = self.pending.get_or_insert((now, 0));
+        *bells += count;
+    }
+
+    /// When the open run falls due.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.pending.map(|(since, _)| since + COALESCE_WINDOW)
+    }
+
+    /// The bells of a run whose window has closed.
+    pub fn take_due(&mut self, now: Instant) -> Option<usize> {
+        let (since, bells) = self.pending?;
+        if now < since + COALESCE_WINDOW {
+            return None;
+        }
+        self.pending = None;
+        Some(bells)
+    }
+}
+
+/// `!bell mute`, until it runs out.
+#[derive(Debug, Clone, Default)]
+pub struct Mute {
+    /// When it runs out, and the clock time that is.
+    until: Option<(Instant, NaiveTime)>,
+}
+
+impl Mute {
+    /// Mute for `length` from `now`, which is `clock` on the wall.
+    pub fn start(&mut self, length: Duration, now: Instant, clock: NaiveTime) {
+        let wall = clock + chrono::Duration::from_std(length).unwrap_or_default();
+        self.until = Some((now + length, wall));
+    }
+
+    pub fn stop(&mut self) {
+        self.until = None;
+    }
+
+    pub fn active(&self, now: Instant) -> bool {
+        self.until.is_some_and(|(until, _)| now < until)
+    }
+
+    /// When it runs out, while it hasn't.
+    pub fn next_wake(&self, now: Instant) -> Option<Instant> {
+        self.until.map(|(until, _)| until).filter(|until| now < *until)
+    }
+
+    /// True once a mute has run out, which ends it.
+    pub fn take_expired(&mut self, now: Instant) -> bool {
+        let expired = self.until.is_some_and(|(until, _)| now >= until);
+        if expired {
+            self.until = None;
+        }
+        expired
+    }
+
+    /// `14:32`, while it hasn't run out.
+    pub fn ends_at(&self, now: Instant) -> Option<String> {
+        let (_, wall) = self.until.filter(|_| self.active(now))?;
+        Some(wall.format("%H:%M").to_string())
+    }
+
+    /// `🔕 until 14:32` for the status bar.
+    pub fn label(&self, now: Instant) -> Option<String> {
+        self.ends_at(now).map(|at| format!("🔕 until {}", at))
+    }
+}
+
+/// A parsed `!bell` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BellCommand {
+    Status,
+    Test,
+    Mute(Duration),
+    Unmute,
+}
+
+impl BellCommand {
+    /// Parse what follows `!bell`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<BellCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(BellCommand::Status),
+            ["test"] => Ok(BellCommand::Test),
+            ["unmute"] => Ok(BellCommand::Unmute),
+            ["mute", length] => match parse_length(length) {
+                Some(length) if length <= MAX_MUTE => Ok(BellCommand::Mute(length)),
+                Some(_) => Err("❌ A mute lasts at most 24h".to_string()),
+                None => Err(format!("❌ Bad duration '{}' (e.g. 10m, 90s, 2h)", length)),
+            },
+            _ => Err(BELL_USAGE.to_string()),
+        }
+    }
+}
+
+/// `90s`, `10m` or `2h`; a bare number is minutes.
+fn parse_length(value: &str) -> Option<Duration> {
+    let value = value.trim().to_lowercase();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().ok().filter(|n| *n > 0)?;
+    let secs = match unit {
+        "s" | "sec" | "secs" => number,
+        "" | "m" | "min" | "mins" => number * 60,
+        "h" | "hr" | "hrs" => number * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
+
+/// Where bell actions end up: the platform and the UI.
+pub trait BellSink {
+    fn sound(&mut self);
+    fn flash(&mut self);
+    fn notify(&mut self, text: &str);
+    fn announce(&mut self, text: &str);
+}
+
+/// `Bell` or `Bell ×3`.
+pub fn bell_text(count: usize) -> String {
+    match count {
+        0 | 1 => "Bell".to_string(),
+        n => format!("Bell ×{}", n),
+    }
+}
+
+/// Carry out `action` for `count` coalesced bells.
+pub fn ring(sink: &mut impl BellSink, action: BellAction, count: usize) {
+    match action {
+        BellAction::Sound => sink.sound(),
+        BellAction::Flash => sink.flash(),
+        BellAction::Notify => sink.notify(&bell_text(count)),
+        BellAction::Announce => sink.announce(&bell_text(count)),
+        BellAction::Silent => {}
+    }
+}
+
+/// The `!bell` status lines.
+pub fn status_lines(policy: &BellPolicy, mute: &Mute, now: Instant) -> Vec<String> {
+    let show = |action: Option<BellAction>| action.map_or("default", BellAction::label);
+    let mut lines = vec![
+        format!("🔔 Bell: {}", policy.action.label()),
+        format!("  while following:   {}", show(policy.follow)),
+        format!("  in a remote shell: {}", show(policy.remote)),
+        format!("  while unfocused:   {}", show(policy.unfocused)),
+    ];
+    if let Some(label) = mute.label(now) {
+        lines.push(format!("  {} (!bell unmute)", label));
+    }
+    lines.push(format!("  Change with !set {} | {} | {} | {}", ACTION_KEY, FOLLOW_KEY, REMOTE_KEY, UNFOCUSED_KEY));
+    lines
+}
@@ -48,6 +48,8 @@ pub enum BioLinkEvent {
     DeviceEvent(String),
     /// Custom announcement
     Announcement(String),
+    /// Keyboard focus moved to a widget element or back to the input bar
+    FocusChanged(String),
 }
 
 impl BioLinkEvent {
@@ -72,6 +74,7 @@ impl BioLinkEvent {
             BioLinkEvent::PeerActivity(msg) => format!("Peer: {}", msg),
             BioLinkEvent::DeviceEvent(msg) => format!("Device: {}", msg),
             BioLinkEvent::Announcement(msg) => msg.clone(),
+            BioLinkEvent::FocusChanged(label) => label.clone(),
         }
     }
 
@@ -79,7 +82,7 @@ impl BioLinkEvent {
     pub fn priority(&self) -> u8 {
         match self {
             BioLinkEvent::ErrorOccurred(_) => 0,
-            BioLinkEvent::CommandComplete { .. } => 1,
+            BioLinkEvent::CommandComplete { .. } | BioLinkEvent::FocusChanged(_) => 1,
             BioLinkEvent::JobFinished { .. } => 2,
             BioLinkEvent::DeviceEvent(_) => 3,
             BioLinkEvent::PeerActivity(_) => 4,
@@ -171,6 +174,18 @@ impl BioLink {
         )
     }
 
+    /// Generate a label for one element of a widget, e.g.
+    /// `row 4 of 20: name=api-server, status=failed`.
+    pub fn element_label(role: &str, position: usize, count: usize, detail: &str) -> String {
+        format!(
+            "{} {} of {}: {}",
+            role,
+            position,
+            count,
+            truncate_for_reader(detail, 200)
+        )
+    }
+
     /// Format the current input buffer for screen reader feedback.
     pub fn describe_input(buffer: &str, cursor_pos: usize) -> String {
         if buffer.is_empty() {
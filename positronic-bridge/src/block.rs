@@ -5,8 +5,21 @@
 // A TerminalBlock captures one command execution cycle: the input, all output
 // lines, and metadata (timestamp, duration, exit code, CWD). The UI can
 // render blocks as collapsible cards with copy/search support.
-
-use chrono::{DateTime, Local};
+//
+// Which finished blocks the user hasn't seen yet is tracked by an
+// `Attention` over the blocks' output lines, laid end to end.
+
+use crate::attention::Attention;
+use crate::helpers::format_bytes;
+use crate::holodeck::HolodeckManager;
+use crate::span_cache::SpanCache;
+use crate::syntax::{self, Language};
+use chrono::{DateTime, Local, TimeZone};
+use positronic_core::blocks::TerminalBlockV2;
+use positronic_core::diagnostics::{Diagnostic, Extractor, Severity};
+use positronic_core::test_report::{TestParsers, TestReport};
+use positronic_core::time_format;
+use positronic_core::usage::ResourceUsage;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::{Duration, Instant};
@@ -38,9 +51,45 @@ pub struct TerminalBlock {
     pub collapsed: bool,
     /// Whether this block is still receiving output.
     pub running: bool,
+    /// Error locations found in the output when the block finished.
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+    /// UI state: diagnostics listed under the block, or just the summary.
+    #[serde(default)]
+    pub diagnostics_expanded: bool,
+    /// Its test run's summary, if it was one (see `test_report`).
+    #[serde(default)]
+    pub tests: Option<TestReport>,
+    /// UI state: failures of `tests` shown with their output, 0-based.
+    #[serde(default)]
+    pub tests_expanded: Vec<usize>,
+    /// Pinned blocks are never evicted.
+    #[serde(default)]
+    pub pinned: bool,
+    /// `!tag` names on this block; tagged blocks are kept like pinned ones.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Holodeck entries made from this block's output.
+    #[serde(default)]
+    pub holodeck: Vec<u64>,
+    /// The language its output is in, for highlighting (see `syntax`).
+    #[serde(default)]
+    pub language: Option<Language>,
+    /// CPU time and peak memory, for commands Positronic ran itself.
+    #[serde(default)]
+    pub usage: Option<ResourceUsage>,
+    /// The block whose command this one's was edited from (see
+    /// `positronic_core::chain`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived_from: Option<BlockId>,
 }
 
 impl TerminalBlock {
+    /// Whether eviction has to leave this block alone.
+    pub fn is_kept(&self) -> bool {
+        self.pinned || !self.tags.is_empty()
+    }
+
     /// Number of output lines in this block.
     pub fn line_count(&self) -> usize {
         self.output.len()
@@ -79,10 +128,35 @@ impl TerminalBlock {
     pub fn warning_lines(&self) -> Vec<&BlockLine> {
         self.output.iter().filter(|l| l.kind == LineKind::Warning).collect()
     }
+    /// The first error diagnostic, else the first of any severity.
+    pub fn first_error(&self) -> Option<&Diagnostic> {
+        self.diagnostics
+            .iter()
+            .find(|d| d.severity == Severity::Error)
+            .or_else(|| self.diagnostics.first())
+    }
+
+    /// Wall-clock arrival of a stamped line.
+    pub fn line_time(&self, line: &BlockLine) -> Option<DateTime<Local>> {
+        line.offset_ms
+            .map(|ms| self.timestamp + chrono::Duration::milliseconds(ms as i64))
+    }
+
+    /// Estimated memory for retention, in bytes: the command, CWD and
+    /// output text, like `RichContent::char_size`.
+    pub fn char_size(&self) -> usize {
+        self.command.len() + self.cwd.len() + self.output.iter().map(|l| l.text.len()).sum::<usize>()
+    }
+
+    /// Milliseconds since the block began, for stamping new lines.
+    fn elapsed_ms(&self) -> u64 {
+        (Local::now() - self.timestamp).num_milliseconds().max(0) as u64
+    }
 }
 
-impl fmt::Display for TerminalBlock {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl TerminalBlock {
+    /// Status and command, without the duration: `[✅ ok] $ ls`.
+    pub fn header_text(&self) -> String {
         let status = if self.running {
             "⏳ running".to_string()
         } else {
@@ -92,7 +166,13 @@ impl fmt::Display for TerminalBlock {
                 None => "— done".to_string(),
             }
         };
-        write!(f, "[{}] $ {} ({})", status, self.command, self.duration_display())
+        format!("[{}] $ {}", status, self.command)
+    }
+}
+
+impl fmt::Display for TerminalBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.header_text(), self.duration_display())
     }
 }
 
@@ -139,6 +219,10 @@ pub enum LineKind {
 pub struct BlockLine {
     pub text: String,
     pub kind: LineKind,
+    /// Arrival in ms after the block started (None for unstamped lines,
+    /// including every line of blocks saved before lines were stamped).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset_ms: Option<u64>,
 }
 
 impl fmt::Display for LineKind {
@@ -162,22 +246,22 @@ impl fmt::Display for BlockLine {
 
 impl BlockLine {
     pub fn normal(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Normal }
+        Self { text: text.into(), kind: LineKind::Normal, offset_ms: None }
     }
     pub fn error(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Error }
+        Self { text: text.into(), kind: LineKind::Error, offset_ms: None }
     }
     pub fn warning(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Warning }
+        Self { text: text.into(), kind: LineKind::Warning, offset_ms: None }
     }
     pub fn info(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Info }
+        Self { text: text.into(), kind: LineKind::Info, offset_ms: None }
     }
     pub fn success(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Success }
+        Self { text: text.into(), kind: LineKind::Success, offset_ms: None }
     }
     pub fn muted(text: impl Into<String>) -> Self {
-        Self { text: text.into(), kind: LineKind::Muted }
+        Self { text: text.into(), kind: LineKind::Muted, offset_ms: None }
     }
 
     /// Auto-classify a line by content heuristics.
@@ -214,7 +298,13 @@ impl BlockLine {
             LineKind::Normal
         };
 
-        Self { text, kind }
+        Self { text, kind, offset_ms: None }
+    }
+
+    /// The same line, stamped as arriving `offset_ms` after its block began.
+    pub fn at(mut self, offset_ms: u64) -> Self {
+        self.offset_ms = Some(offset_ms);
+        self
     }
 
     /// Whether this line is effectively blank.
@@ -223,6 +313,75 @@ impl BlockLine {
     }
 }
 
+/// How many blocks a `BlockManager` keeps.
+///
+/// Past `max_blocks`, `max_total_lines` or `max_bytes` the oldest blocks
+/// that are not pinned and did not fail are evicted. Failed blocks are
+/// what users go back to, so they go only once the higher `failed_*`
+/// limits are passed as well. Pinned or tagged blocks and the newest
+/// block are never evicted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_blocks: usize,
+    pub max_total_lines: usize,
+    /// Budget for `TerminalBlock::char_size` summed over all blocks.
+    pub max_bytes: usize,
+    pub failed_max_blocks: usize,
+    pub failed_max_bytes: usize,
+    /// Keep a "··· N older blocks trimmed ···" placeholder for evictions.
+    pub summarize: bool,
+}
+
+impl RetentionPolicy {
+    /// Count and line limits as given; byte limits at the defaults and
+    /// failed blocks kept up to twice the count.
+    pub fn new(max_blocks: usize, max_total_lines: usize) -> Self {
+        Self {
+            max_blocks,
+            max_total_lines,
+            failed_max_blocks: max_blocks.saturating_mul(2),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_blocks: 500,
+            max_total_lines: 50_000,
+            max_bytes: 8 * 1024 * 1024,
+            failed_max_blocks: 1_000,
+            failed_max_bytes: 16 * 1024 * 1024,
+            summarize: true,
+        }
+    }
+}
+
+/// Stands in for evicted blocks: one placeholder however many rounds of
+/// eviction there were.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrimSummary {
+    pub blocks: usize,
+    pub lines: usize,
+    pub bytes: usize,
+}
+
+impl fmt::Display for TrimSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let noun = if self.blocks == 1 { "block" } else { "blocks" };
+        write!(f, "··· {} older {} trimmed (view in !history) ···", self.blocks, noun)
+    }
+}
+
+/// What the owner must drop for an evicted block: its cached spans and
+/// the Holodeck entries linked to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvictedBlock {
+    pub id: BlockId,
+    pub holodeck: Vec<u64>,
+}
+
 /// Manages a scrolling list of terminal blocks with memory limits.
 #[derive(Debug)]
 pub struct BlockManager {
@@ -230,29 +389,59 @@ pub struct BlockManager {
     blocks: Vec<TerminalBlock>,
     /// Next block ID.
     next_id: BlockId,
-    /// Maximum number of blocks to retain.
-    max_blocks: usize,
-    /// Total output line budget (oldest blocks pruned when exceeded).
-    max_total_lines: usize,
+    /// Limits checked when a block begins or finishes.
+    policy: RetentionPolicy,
+    /// Evicted blocks since the last `clear`, if summarized.
+    trimmed: Option<TrimSummary>,
+    /// Evicted since the last `take_evicted`.
+    evicted: Vec<EvictedBlock>,
+    /// Evictions this session.
+    evictions: usize,
+    /// Finds diagnostics in finished blocks.
+    extractor: Extractor,
+    /// Reads test runs in finished blocks.
+    test_parsers: TestParsers,
+    /// Blocks finished while unfocused or scrolled back, until seen.
+    attention: Attention,
 }
 
 impl Default for BlockManager {
     fn default() -> Self {
-        Self::new(500, 50_000)
+        Self::with_policy(RetentionPolicy::default())
     }
 }
 
 impl BlockManager {
     /// Create a new block manager with limits.
     pub fn new(max_blocks: usize, max_total_lines: usize) -> Self {
+        Self::with_policy(RetentionPolicy::new(max_blocks, max_total_lines))
+    }
+
+    /// Create a new block manager with a full retention policy.
+    pub fn with_policy(policy: RetentionPolicy) -> Self {
         Self {
             blocks: Vec::with_capacity(64),
             next_id: 1,
-            max_blocks,
-            max_total_lines,
+            policy,
+            trimmed: None,
+            evicted: Vec::new(),
+            evictions: 0,
+            extractor: Extractor::default(),
+            test_parsers: TestParsers::default(),
+            attention: Attention::default(),
         }
     }
 
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Replace the retention policy, evicting at once if it is tighter.
+    pub fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+        self.enforce_limits();
+    }
+
     /// Begin a new block for a command. Returns the block ID.
     pub fn begin(&mut self, command: &str, cwd: &str, source: BlockSource) -> BlockId {
         let id = self.next_id;
@@ -269,32 +458,204 @@ impl BlockManager {
             source,
             collapsed: false,
             running: true,
+            diagnostics: Vec::new(),
+            diagnostics_expanded: false,
+            tests: None,
+            tests_expanded: Vec::new(),
+            pinned: false,
+            tags: Vec::new(),
+            holodeck: Vec::new(),
+            language: None,
+            usage: None,
+            derived_from: None,
         });
 
         self.enforce_limits();
         id
     }
 
-    /// Append output lines to a running block.
+    /// Append output lines to a running block. Lines without a stamp are
+    /// stamped now, so lines appended together share one arrival time.
     pub fn append(&mut self, block_id: BlockId, lines: Vec<BlockLine>) {
         if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
-            block.output.extend(lines);
+            let now = block.elapsed_ms();
+            block.output.extend(lines.into_iter().map(|l| match l.offset_ms {
+                Some(_) => l,
+                None => l.at(now),
+            }));
+            self.relayout_unread();
         }
     }
 
-    /// Append a single line to a running block.
+    /// Append a single line to a running block, stamping it if unstamped.
     pub fn append_line(&mut self, block_id: BlockId, line: BlockLine) {
-        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
-            block.output.push(line);
-        }
+        self.append(block_id, vec![line]);
     }
 
-    /// Mark a block as finished with an optional exit code and duration.
+    /// Mark a block as finished with an optional exit code and duration,
+    /// extract diagnostics and a test summary from its output and detect
+    /// its language.
     pub fn finish(&mut self, block_id: BlockId, exit_code: Option<i32>, duration: Duration) {
         if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
             block.running = false;
             block.exit_code = exit_code;
             block.duration = Some(duration);
+            let text: Vec<&str> = block.output.iter().map(|l| l.text.as_str()).collect();
+            let joined = text.join("\n");
+            block.diagnostics = self.extractor.extract(&joined);
+            block.tests = self.test_parsers.parse(&block.command, &joined);
+            if text.len() <= syntax::MAX_LINES {
+                block.language = syntax::detect(&block.command, &text);
+            }
+            if let Some(lines) = self.line_range(block_id) {
+                self.attention.finished(block_id, lines, Instant::now());
+            }
+        }
+        self.enforce_limits();
+    }
+
+    /// Add a shell command the engine's recorder finished (see
+    /// `positronic_core::blocks`), with its start time, duration and the
+    /// arrival of each line.
+    pub fn record(&mut self, finished: &TerminalBlockV2) -> BlockId {
+        let id = self.begin(&finished.command, finished.cwd.as_deref().unwrap_or(""), BlockSource::Shell);
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == id) {
+            if let Some(at) = Local.timestamp_opt(finished.started_at_unix, 0).single() {
+                block.timestamp = at;
+            }
+            block.output = finished
+                .stamped_lines()
+                .map(|(text, offset)| match offset {
+                    Some(ms) => BlockLine::classify(text).at(ms),
+                    None => BlockLine::classify(text),
+                })
+                .collect();
+            self.relayout_unread();
+        }
+        let duration = Duration::from_millis(finished.duration_ms.unwrap_or(0).max(0) as u64);
+        self.finish(id, finished.exit_code, duration);
+        id
+    }
+
+    /// Record what a finished block's command used.
+    pub fn set_usage(&mut self, block_id: BlockId, usage: ResourceUsage) {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
+            block.usage = Some(usage);
+        }
+    }
+
+    /// Link a block to the one whose command it was edited from.
+    pub fn set_derived_from(&mut self, block_id: BlockId, from: BlockId) {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
+            block.derived_from = Some(from);
+        }
+    }
+
+    /// Focus, view and unread state of the finished blocks.
+    pub fn attention(&self) -> &Attention {
+        &self.attention
+    }
+
+    pub fn attention_mut(&mut self) -> &mut Attention {
+        &mut self.attention
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.attention.unread_count()
+    }
+
+    /// The output lines `block_id` covers, counting every block's output
+    /// end to end.
+    pub fn line_range(&self, block_id: BlockId) -> Option<std::ops::Range<usize>> {
+        let mut start = 0;
+        for block in &self.blocks {
+            if block.id == block_id {
+                return Some(start..start + block.output.len());
+            }
+            start += block.output.len();
+        }
+        None
+    }
+
+    /// Blocks before an unread one grew or went: move its lines along.
+    fn relayout_unread(&mut self) {
+        if self.attention.unread_count() == 0 {
+            return;
+        }
+        let mut attention = std::mem::take(&mut self.attention);
+        attention.relayout(|id| self.line_range(id), Instant::now());
+        self.attention = attention;
+    }
+
+    /// Patterns used for diagnostics; register more for other tools.
+    pub fn extractor_mut(&mut self) -> &mut Extractor {
+        &mut self.extractor
+    }
+
+    /// Test-run parsers; register more for other runners.
+    pub fn test_parsers_mut(&mut self) -> &mut TestParsers {
+        &mut self.test_parsers
+    }
+
+    /// The most recent finished block that failed.
+    pub fn last_failed(&self) -> Option<&TerminalBlock> {
+        self.blocks.iter().rev().find(|b| b.failed())
+    }
+
+    /// Keep a block through eviction. Returns false if there is no such block.
+    pub fn pin(&mut self, block_id: BlockId) -> bool {
+        self.set_pinned(block_id, true)
+    }
+
+    /// Let a pinned block be evicted again.
+    pub fn unpin(&mut self, block_id: BlockId) -> bool {
+        self.set_pinned(block_id, false)
+    }
+
+    fn set_pinned(&mut self, block_id: BlockId, pinned: bool) -> bool {
+        match self.get_mut(block_id) {
+            Some(block) => {
+                block.pinned = pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Put tag `name` on a block, taking it off any other: names are
+    /// unique. Returns false if there is no such block.
+    pub fn tag(&mut self, block_id: BlockId, name: &str) -> bool {
+        if self.get(block_id).is_none() {
+            return false;
+        }
+        self.untag(name);
+        if let Some(block) = self.get_mut(block_id) {
+            block.tags.push(name.to_string());
+        }
+        true
+    }
+
+    /// Take tag `name` off whichever block has it.
+    pub fn untag(&mut self, name: &str) -> bool {
+        let mut found = false;
+        for block in &mut self.blocks {
+            let before = block.tags.len();
+            block.tags.retain(|t| t != name);
+            found |= block.tags.len() < before;
+        }
+        found
+    }
+
+    /// The block tagged `name`.
+    pub fn tagged(&self, name: &str) -> Option<&TerminalBlock> {
+        self.blocks.iter().find(|b| b.tags.iter().any(|t| t == name))
+    }
+
+    /// Record a Holodeck entry made from a block's output, so it goes
+    /// when the block is evicted.
+    pub fn link_holodeck(&mut self, block_id: BlockId, entry_id: u64) {
+        if let Some(block) = self.get_mut(block_id) {
+            block.holodeck.push(entry_id);
         }
     }
 
@@ -305,6 +666,26 @@ impl BlockManager {
         }
     }
 
+    /// Toggle the diagnostics list under a block.
+    pub fn toggle_diagnostics(&mut self, block_id: BlockId) {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
+            block.diagnostics_expanded = !block.diagnostics_expanded;
+        }
+    }
+
+    /// Show or hide the output of failure `index` (0-based) in a block's
+    /// test summary.
+    pub fn toggle_test_failure(&mut self, block_id: BlockId, index: usize) {
+        if let Some(block) = self.blocks.iter_mut().find(|b| b.id == block_id) {
+            match block.tests_expanded.iter().position(|&i| i == index) {
+                Some(at) => {
+                    block.tests_expanded.remove(at);
+                }
+                None => block.tests_expanded.push(index),
+            }
+        }
+    }
+
     /// Collapse all blocks.
     pub fn collapse_all(&mut self) {
         for block in &mut self.blocks { block.collapsed = true; }
@@ -336,11 +717,10 @@ impl BlockManager {
 
     /// Remove a block by ID.
     pub fn remove(&mut self, block_id: BlockId) -> Option<TerminalBlock> {
-        if let Some(pos) = self.blocks.iter().position(|b| b.id == block_id) {
-            Some(self.blocks.remove(pos))
-        } else {
-            None
-        }
+        let pos = self.blocks.iter().position(|b| b.id == block_id)?;
+        let block = self.blocks.remove(pos);
+        self.relayout_unread();
+        Some(block)
     }
 
     /// Get the total number of blocks.
@@ -417,6 +797,12 @@ impl BlockManager {
 
     /// Export all blocks to a single text string.
     pub fn export_all(&self) -> String {
+        self.export(false)
+    }
+
+    /// Export all blocks; `with_timestamps` prefixes each stamped line with
+    /// its arrival time in ISO 8601, UTC (`!export --with-timestamps`).
+    pub fn export(&self, with_timestamps: bool) -> String {
         let mut out = String::new();
         for (i, block) in self.blocks.iter().enumerate() {
             if i > 0 {
@@ -424,6 +810,12 @@ impl BlockManager {
             }
             out.push_str(&format!("$ {} [{}] ({})\n", block.command, block.source, block.cwd));
             for line in &block.output {
+                if with_timestamps {
+                    match block.line_time(line) {
+                        Some(t) => out.push_str(&format!("[{}] ", time_format::iso8601_ms(t.timestamp_millis()))),
+                        None => out.push_str(&" ".repeat(ISO_STAMP_WIDTH + 3)),
+                    }
+                }
                 out.push_str(&line.text);
                 out.push('\n');
             }
@@ -433,6 +825,9 @@ impl BlockManager {
             if let Some(d) = block.duration {
                 out.push_str(&format!(" [{}]", format_duration(d)));
             }
+            if let Some(usage) = block.usage {
+                out.push_str(&format!(" [{}]", usage.badge()));
+            }
             out.push('\n');
         }
         out
@@ -443,18 +838,75 @@ impl BlockManager {
         self.blocks.iter().map(|b| b.output.len()).sum()
     }
 
-    /// Remove oldest blocks to stay within limits.
-    fn enforce_limits(&mut self) {
-        while self.blocks.len() > self.max_blocks {
-            self.blocks.remove(0);
+    /// Estimated memory of all blocks (`TerminalBlock::char_size`).
+    pub fn total_bytes(&self) -> usize {
+        self.blocks.iter().map(|b| b.char_size()).sum()
+    }
+
+    /// The placeholder for evicted blocks, if any were and the policy
+    /// summarizes them.
+    pub fn trimmed(&self) -> Option<&TrimSummary> {
+        self.trimmed.as_ref()
+    }
+
+    /// Blocks evicted since the last call. The owner drops their cached
+    /// spans and linked Holodeck entries (see `release_evicted`).
+    pub fn take_evicted(&mut self) -> Vec<EvictedBlock> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Drop what evicted blocks left behind in the span cache and Holodeck.
+    pub fn release_evicted(&mut self, spans: &mut SpanCache, holodeck: &mut HolodeckManager) {
+        for evicted in self.take_evicted() {
+            spans.forget_block(evicted.id);
+            for entry in evicted.holodeck {
+                holodeck.remove(entry);
+            }
         }
-        while self.total_lines() > self.max_total_lines && self.blocks.len() > 1 {
-            self.blocks.remove(0);
+    }
+
+    /// Evict the oldest blocks to stay within the retention policy:
+    /// successful and unfinished ones first, failed ones only past the
+    /// failed limits, pinned or tagged ones and the newest never.
+    fn enforce_limits(&mut self) {
+        let mut lines = self.total_lines();
+        let mut bytes = self.total_bytes();
+        let p = self.policy;
+        loop {
+            let count = self.blocks.len();
+            if count <= p.max_blocks && lines <= p.max_total_lines && bytes <= p.max_bytes {
+                break;
+            }
+            let over_failed = count > p.failed_max_blocks || bytes > p.failed_max_bytes;
+            let evictable = &self.blocks[..count.saturating_sub(1)];
+            let pos = evictable
+                .iter()
+                .position(|b| !b.is_kept() && !b.failed())
+                .or_else(|| if over_failed { evictable.iter().position(|b| !b.is_kept()) } else { None });
+            let Some(pos) = pos else { break };
+
+            let block = self.blocks.remove(pos);
+            let size = block.char_size();
+            lines -= block.output.len();
+            bytes -= size;
+            self.evictions += 1;
+            if p.summarize {
+                let trimmed = self.trimmed.get_or_insert_with(TrimSummary::default);
+                trimmed.blocks += 1;
+                trimmed.lines += block.output.len();
+                trimmed.bytes += size;
+            }
+            self.evicted.push(EvictedBlock { id: block.id, holodeck: block.holodeck });
         }
+        self.relayout_unread();
     }
 
-    /// Clear all blocks (e.g. on Ctrl+L).
-    pub fn clear(&mut self) { self.blocks.clear(); }
+    /// Clear all blocks (e.g. on Ctrl+L), and the trimmed placeholder.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.trimmed = None;
+        self.attention.clear();
+    }
 
     /// Get summary stats.
     pub fn stats(&self) -> BlockStats {
@@ -463,6 +915,9 @@ impl BlockManager {
             total_lines: self.total_lines(),
             running: self.blocks.iter().filter(|b| b.running).count(),
             errors: self.blocks.iter().filter(|b| b.exit_code.map(|c| c != 0).unwrap_or(false)).count(),
+            pinned: self.blocks.iter().filter(|b| b.pinned).count(),
+            total_bytes: self.total_bytes(),
+            evicted: self.evictions,
         }
     }
 }
@@ -476,20 +931,34 @@ pub struct SearchHit {
 }
 
 /// Summary statistics about blocks.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BlockStats {
     pub total_blocks: usize,
     pub total_lines: usize,
     pub running: usize,
     pub errors: usize,
+    #[serde(default)]
+    pub pinned: usize,
+    /// Estimated memory held, in bytes.
+    #[serde(default)]
+    pub total_bytes: usize,
+    /// Blocks evicted this session.
+    #[serde(default)]
+    pub evicted: usize,
 }
 
 impl fmt::Display for BlockStats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{} blocks, {} lines, {} running, {} errors",
-            self.total_blocks, self.total_lines, self.running, self.errors
+            "{} blocks ({} pinned), {} lines, {}, {} running, {} errors, {} evicted",
+            self.total_blocks,
+            self.pinned,
+            self.total_lines,
+            format_bytes(self.total_bytes as u64),
+            self.running,
+            self.errors,
+            self.evicted
         )
     }
 }
@@ -525,6 +994,21 @@ pub fn quick_error_block(
     id
 }
 
+/// Wall-clock format for line timestamps: `14:02:31.207`.
+pub const TIME_FORMAT: &str = "%H:%M:%S%.3f";
+
+/// Characters `TIME_FORMAT` produces.
+pub const TIME_FORMAT_WIDTH: usize = 12;
+
+/// On a 12-hour clock: `2:02:31.207 PM`.
+pub const TIME_FORMAT_12H: &str = "%-I:%M:%S%.3f %p";
+
+/// Widest `TIME_FORMAT_12H` gets.
+pub const TIME_FORMAT_12H_WIDTH: usize = 15;
+
+/// Characters an exported stamp has: `2026-03-08T19:02:31.207Z`.
+const ISO_STAMP_WIDTH: usize = 24;
+
 /// Format a Duration into a human-readable string.
 pub fn format_duration(d: Duration) -> String {
     let total_secs = d.as_secs();
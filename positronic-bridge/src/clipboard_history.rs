@@ -0,0 +1,402 @@
+//! Clipboard history for `!paste` and the Ctrl+Shift+V picker.
+//!
+//! Only what Positronic itself copies is recorded; changes made by other
+//! programs never reach the ring. The newest entry is number 1. Entries
+//! longer than `ENTRY_CAP` keep a truncated preview in memory and spill the
+//! full text to a temp file, which is deleted when the entry is evicted,
+//! on `!paste clear` and on drop.
+//!
+//! Text the privacy guard flags (keys, tokens, addresses) is stored as a
+//! `[redacted — N items hidden]` placeholder unless `clipboard.keep_secrets`
+//! is on. The ring lives in memory and is gone on exit unless
+//! `clipboard.persist` is on, in which case it is saved to
+//! `PERSIST_FILE` and loaded at the next start.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use positronic_neural::privacy::PrivacyGuard;
+
+/// Vault config key: how many entries to keep (`0` turns history off).
+pub const SIZE_KEY: &str = "clipboard.history";
+/// Vault config key: keep entries that look like secrets.
+pub const KEEP_SECRETS_KEY: &str = "clipboard.keep_secrets";
+/// Vault config key: keep the ring across restarts.
+pub const PERSIST_KEY: &str = "clipboard.persist";
+
+pub const DEFAULT_SIZE: usize = 20;
+/// Bytes kept in memory per entry; longer text spills to a file.
+pub const ENTRY_CAP: usize = 4096;
+/// Where a persisted ring is saved, next to `positronic.db`.
+pub const PERSIST_FILE: &str = "positronic-clipboard.json";
+
+const PREVIEW_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClipboardSettings {
+    pub size: usize,
+    pub keep_secrets: bool,
+    pub persist: bool,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self { size: DEFAULT_SIZE, keep_secrets: false, persist: false }
+    }
+}
+
+/// `on`/`off`-style config flag.
+pub fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "on" | "1" | "yes" => Some(true),
+        "false" | "off" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipEntry {
+    /// The text, or its first `ENTRY_CAP` bytes if `spill` is set.
+    text: String,
+    /// Full text of a long entry.
+    spill: Option<PathBuf>,
+    /// Items hidden, if the entry was redacted.
+    redacted: Option<usize>,
+    /// Size of the full text.
+    bytes: usize,
+    lines: usize,
+}
+
+impl ClipEntry {
+    pub fn is_redacted(&self) -> bool {
+        self.redacted.is_some()
+    }
+
+    /// One line for lists: the start of the first line, with the size of
+    /// anything longer.
+    pub fn preview(&self) -> String {
+        if let Some(hidden) = self.redacted {
+            return redacted_label(hidden);
+        }
+        let first = self.text.lines().next().unwrap_or("");
+        let mut preview: String = first.chars().take(PREVIEW_CHARS).collect();
+        if first.chars().count() > PREVIEW_CHARS || self.lines > 1 {
+            preview.push('…');
+        }
+        let mut notes = Vec::new();
+        if self.lines > 1 {
+            notes.push(format!("{} lines", self.lines));
+        }
+        if self.bytes >= 1024 {
+            notes.push(format!("{:.1} KB", self.bytes as f64 / 1024.0));
+        }
+        if notes.is_empty() {
+            preview
+        } else {
+            format!("{}  ({})", preview, notes.join(", "))
+        }
+    }
+}
+
+/// `[redacted — 1 item hidden]`.
+pub fn redacted_label(hidden: usize) -> String {
+    format!("[redacted — {} item{} hidden]", hidden, if hidden == 1 { "" } else { "s" })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasteError {
+    Empty,
+    /// 1-based index past the end.
+    NoEntry(usize),
+    Redacted(usize),
+    /// The spill file of a long entry is gone.
+    Missing(usize),
+}
+
+impl std::fmt::Display for PasteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasteError::Empty => write!(f, "clipboard history is empty"),
+            PasteError::NoEntry(n) => write!(f, "no clipboard entry #{} (!paste list)", n),
+            PasteError::Redacted(n) => write!(
+                f,
+                "entry #{} looked like a secret and was not kept (!set {} on to keep them)",
+                n, KEEP_SECRETS_KEY
+            ),
+            PasteError::Missing(n) => write!(f, "the full text of entry #{} is no longer on disk", n),
+        }
+    }
+}
+
+impl std::error::Error for PasteError {}
+
+#[derive(Debug)]
+pub struct ClipboardHistory {
+    /// Newest first.
+    entries: VecDeque<ClipEntry>,
+    settings: ClipboardSettings,
+    spill_dir: PathBuf,
+    next_spill: u64,
+}
+
+impl Default for ClipboardHistory {
+    fn default() -> Self {
+        let dir = std::env::temp_dir().join(format!("positronic-clipboard-{}", std::process::id()));
+        Self::new(ClipboardSettings::default(), dir)
+    }
+}
+
+impl ClipboardHistory {
+    /// Long entries spill into `spill_dir`, created on first use.
+    pub fn new(settings: ClipboardSettings, spill_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            settings,
+            spill_dir: spill_dir.into(),
+            next_spill: 0,
+        }
+    }
+
+    pub fn settings(&self) -> ClipboardSettings {
+        self.settings
+    }
+
+    /// Apply new settings; a smaller size drops the oldest entries.
+    pub fn configure(&mut self, settings: ClipboardSettings) {
+        self.settings = settings;
+        self.trim();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ClipEntry> {
+        self.entries.iter()
+    }
+
+    /// Record text Positronic copied. Copying the newest entry again
+    /// doesn't add a duplicate.
+    pub fn push(&mut self, text: &str) {
+        if self.settings.size == 0 || text.is_empty() {
+            return;
+        }
+        let entry = match self.redaction(text) {
+            Some(hidden) => ClipEntry {
+                text: String::new(),
+                spill: None,
+                redacted: Some(hidden),
+                bytes: text.len(),
+                lines: text.lines().count(),
+            },
+            None => self.stored(text),
+        };
+        if let Some(newest) = self.entries.front()
+            && newest.redacted.is_none()
+            && entry.redacted.is_none()
+            && newest.bytes == entry.bytes
+            && newest.text == entry.text
+        {
+            discard(entry);
+            return;
+        }
+        self.entries.push_front(entry);
+        self.trim();
+    }
+
+    fn redaction(&self, text: &str) -> Option<usize> {
+        if self.settings.keep_secrets {
+            return None;
+        }
+        let (_, report) = PrivacyGuard::scrub_with_report(text);
+        (!report.is_clean()).then(|| report.total())
+    }
+
+    fn stored(&mut self, text: &str) -> ClipEntry {
+        let lines = text.lines().count().max(1);
+        if text.len() <= ENTRY_CAP {
+            return ClipEntry { text: text.to_string(), spill: None, redacted: None, bytes: text.len(), lines };
+        }
+        let mut cut = ENTRY_CAP;
+        while !text.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        self.next_spill += 1;
+        let path = self.spill_dir.join(format!("{}.txt", self.next_spill));
+        let spill = std::fs::create_dir_all(&self.spill_dir)
+            .and_then(|_| std::fs::write(&path, text))
+            .map(|_| path)
+            .map_err(|e| tracing::warn!("Clipboard history spill failed: {}", e))
+            .ok();
+        ClipEntry { text: text[..cut].to_string(), spill, redacted: None, bytes: text.len(), lines }
+    }
+
+    fn trim(&mut self) {
+        while self.entries.len() > self.settings.size {
+            if let Some(old) = self.entries.pop_back() {
+                discard(old);
+            }
+        }
+    }
+
+    /// The full text of entry `n` (1 = newest).
+    pub fn get(&self, n: usize) -> Result<String, PasteError> {
+        if self.entries.is_empty() {
+            return Err(PasteError::Empty);
+        }
+        let entry = n
+            .checked_sub(1)
+            .and_then(|i| self.entries.get(i))
+            .ok_or(PasteError::NoEntry(n))?;
+        if entry.redacted.is_some() {
+            return Err(PasteError::Redacted(n));
+        }
+        match &entry.spill {
+            Some(path) => std::fs::read_to_string(path).map_err(|_| PasteError::Missing(n)),
+            None if entry.bytes > entry.text.len() => Err(PasteError::Missing(n)),
+            None => Ok(entry.text.clone()),
+        }
+    }
+
+    /// Forget every entry (`!paste clear`).
+    pub fn clear(&mut self) {
+        for entry in self.entries.drain(..) {
+            discard(entry);
+        }
+    }
+
+    /// `!paste list` output.
+    pub fn lines(&self) -> Vec<String> {
+        if self.entries.is_empty() {
+            return vec!["📋 Clipboard history is empty (only what Positronic copies is kept)".to_string()];
+        }
+        let mut lines = vec![format!("📋 Clipboard history ({} of {}):", self.entries.len(), self.settings.size)];
+        for (i, entry) in self.entries.iter().enumerate() {
+            lines.push(format!("  {:>2}. {}", i + 1, entry.preview()));
+        }
+        lines.push("  !paste <n> inserts an entry · !paste clear forgets them".to_string());
+        lines
+    }
+
+    // ── Persistence ─────────────────────────────────────────────────
+
+    /// Write the ring to `path` (full texts, oldest first). Redacted
+    /// entries are skipped.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let texts: Vec<String> = (1..=self.entries.len()).rev().filter_map(|n| self.get(n).ok()).collect();
+        let json = serde_json::to_string(&texts).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+
+    /// Push the entries saved at `path`. A missing file loads nothing.
+    pub fn load(&mut self, path: &Path) -> std::io::Result<usize> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let texts: Vec<String> = serde_json::from_str(&json).map_err(std::io::Error::other)?;
+        for text in &texts {
+            self.push(text);
+        }
+        Ok(texts.len())
+    }
+}
+
+impl Drop for ClipboardHistory {
+    fn drop(&mut self) {
+        self.clear();
+        let _ = std::fs::remove_dir(&self.spill_dir);
+    }
+}
+
+fn discard(entry: ClipEntry) {
+    if let Some(path) = entry.spill {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !paste
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasteCommand {
+    List,
+    Insert(usize),
+    Clear,
+}
+
+pub const PASTE_USAGE: &str = "Usage: !paste list | <n> | clear";
+
+impl PasteCommand {
+    /// Parse `!paste ...`; the error is a message for the user.
+    pub fn parse(cmd: &str) -> Result<PasteCommand, String> {
+        let parts: Vec<&str> = cmd.split_whitespace().collect();
+        match parts.as_slice() {
+            ["!paste"] | ["!paste", "list"] => Ok(PasteCommand::List),
+            ["!paste", "clear"] => Ok(PasteCommand::Clear),
+            ["!paste", n] => match n.parse::<usize>() {
+                Ok(n) if n >= 1 => Ok(PasteCommand::Insert(n)),
+                _ => Err(PASTE_USAGE.to_string()),
+            },
+            _ => Err(PASTE_USAGE.to_string()),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Picker
+// ════════════════════════════════════════════════════════════════════
+
+/// What the Ctrl+Shift+V overlay shows. Digits 1–9 or a click insert an
+/// entry, Enter pastes the system clipboard, Escape closes.
+#[derive(Debug, Clone)]
+pub struct ClipboardPicker {
+    previews: Vec<String>,
+    /// `[x, y, w, h]` per row, from the last draw.
+    rows: Vec<[f32; 4]>,
+}
+
+impl ClipboardPicker {
+    /// `None` if there is nothing to pick.
+    pub fn new(history: &ClipboardHistory) -> Option<Self> {
+        if history.is_empty() {
+            return None;
+        }
+        let previews = history.entries().take(9).map(ClipEntry::preview).collect();
+        Some(Self { previews, rows: Vec::new() })
+    }
+
+    pub fn previews(&self) -> &[String] {
+        &self.previews
+    }
+
+    /// Entry number (1-based) for a digit key.
+    pub fn key_entry(&self, key: &str) -> Option<usize> {
+        let n: usize = key.parse().ok()?;
+        (1..=self.previews.len()).contains(&n).then_some(n)
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<[f32; 4]>) {
+        self.rows = rows;
+    }
+
+    /// Entry number (1-based) of the row under `(x, y)`.
+    pub fn hit(&self, x: f32, y: f32) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|&[rx, ry, rw, rh]| x >= rx && x < rx + rw && y >= ry && y < ry + rh)
+            .map(|i| i + 1)
+    }
+}
+
+/// Text as it goes into the single-line input: line breaks become spaces
+/// so a paste never submits by itself.
+pub fn as_input_line(text: &str) -> String {
+    text.trim_end_matches(['\r', '\n']).replace("\r\n", " ").replace('\n', " ")
+}
@@ -0,0 +1,315 @@
+//! `!compare [<a> <b>]`: two stored outputs side by side in an overlay
+//! (no UI deps).
+//!
+//! Operands are history ids (`12` or `#12`), tags, `last` (the newest run
+//! with stored output) or `prev` (the one before it); bare `!compare` is
+//! `prev last`. The app calls `load` off the UI thread: it reads each
+//! output whole from the vault, however much of it was on screen, and
+//! lines the two up with `diff::align`. Big outputs report progress while
+//! that happens.
+//!
+//! The open `CompareView` takes every key, as the pager does. Both sides
+//! scroll together by aligned row, so a gap on one side stays level with
+//! the lines the other side gained. `n`/`p` jump between runs of changes,
+//! Tab moves focus and `y` copies the focused side's visible lines.
+
+use positronic_core::diff::{self, AlignedRow, Alignment, DiffOptions};
+use positronic_core::trash::format_bytes;
+use positronic_core::vault::{CommandRecord, Vault};
+
+use crate::pager::PagerKey;
+
+pub const COMPARE_USAGE: &str = "Usage: !compare [<a> <b>]  (history ids, tags, last or prev)";
+
+pub const COMPARE_HELP: &str = "[Tab/n/p/y/q]";
+
+/// Most lines of each output that are lined up.
+pub const MAX_COMPARE_LINES: usize = 50_000;
+
+/// Outputs longer than this report progress while they load.
+pub const PROGRESS_BYTES: usize = 1024 * 1024;
+
+/// What a gap row is drawn with.
+pub const HATCH: char = '╱';
+
+/// One side of `!compare`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareOperand {
+    Id(i64),
+    Tag(String),
+    Last,
+    Prev,
+}
+
+impl CompareOperand {
+    pub fn parse(word: &str) -> CompareOperand {
+        match word {
+            "last" => CompareOperand::Last,
+            "prev" => CompareOperand::Prev,
+            _ => match word.strip_prefix('#').unwrap_or(word).parse() {
+                Ok(id) => CompareOperand::Id(id),
+                Err(_) => CompareOperand::Tag(word.to_string()),
+            },
+        }
+    }
+}
+
+/// A parsed `!compare` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompareCommand {
+    pub left: CompareOperand,
+    pub right: CompareOperand,
+}
+
+impl CompareCommand {
+    pub fn parse(args: &str) -> Result<CompareCommand, String> {
+        match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => Ok(CompareCommand { left: CompareOperand::Prev, right: CompareOperand::Last }),
+            [a, b] => Ok(CompareCommand { left: CompareOperand::parse(a), right: CompareOperand::parse(b) }),
+            _ => Err(COMPARE_USAGE.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Side {
+    #[default]
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareOutcome {
+    Continue,
+    /// `y`: the focused side's visible lines, for the clipboard.
+    Copy(String),
+    Closed,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareView {
+    labels: [String; 2],
+    lines: [Vec<String>; 2],
+    alignment: Alignment,
+    top: usize,
+    page_height: usize,
+    focus: Side,
+}
+
+impl CompareView {
+    pub fn new(left: (String, &str), right: (String, &str), page_height: usize) -> Self {
+        let opts = DiffOptions { max_lines: MAX_COMPARE_LINES, ..DiffOptions::default() };
+        let alignment = diff::align(left.1, right.1, &opts);
+        let lines = |text: &str| text.lines().take(MAX_COMPARE_LINES).map(str::to_string).collect();
+        Self {
+            labels: [left.0, right.0],
+            lines: [lines(left.1), lines(right.1)],
+            alignment,
+            top: 0,
+            page_height: page_height.max(1),
+            focus: Side::Left,
+        }
+    }
+
+    pub fn set_page_height(&mut self, page_height: usize) {
+        self.page_height = page_height.max(1);
+        self.top = self.top.min(self.max_top());
+    }
+
+    pub fn label(&self, side: Side) -> &str {
+        &self.labels[side as usize]
+    }
+
+    pub fn focus(&self) -> Side {
+        self.focus
+    }
+
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    pub fn alignment(&self) -> &Alignment {
+        &self.alignment
+    }
+
+    /// The aligned rows on screen.
+    pub fn visible(&self) -> &[AlignedRow] {
+        let end = (self.top + self.page_height).min(self.alignment.rows.len());
+        &self.alignment.rows[self.top..end]
+    }
+
+    /// The text `side` shows on `row`; `None` is a gap.
+    pub fn line(&self, side: Side, row: &AlignedRow) -> Option<&str> {
+        let index = match side {
+            Side::Left => row.left,
+            Side::Right => row.right,
+        }?;
+        self.lines[side as usize].get(index).map(String::as_str)
+    }
+
+    /// The region `top` is in or below, 1-based, for the footer.
+    fn region_at(&self) -> usize {
+        self.alignment.regions.iter().take_while(|r| r.start <= self.top).count()
+    }
+
+    pub fn footer(&self) -> String {
+        let rows = self.alignment.rows.len();
+        let end = (self.top + self.page_height).min(rows);
+        let changes = match self.alignment.regions.len() {
+            0 => "identical".to_string(),
+            n => format!("change {}/{}", self.region_at().max(1), n),
+        };
+        let focus = match self.focus {
+            Side::Left => "◀ left",
+            Side::Right => "right ▶",
+        };
+        let mut footer = format!(
+            "⇆ rows {}-{}/{}  {}  {}  {}",
+            (self.top + 1).min(rows),
+            end,
+            rows,
+            changes,
+            focus,
+            COMPARE_HELP
+        );
+        if let Some((old, new)) = self.alignment.truncated {
+            footer.push_str(&format!("  (first {} of {}/{} lines)", MAX_COMPARE_LINES, old, new));
+        }
+        footer
+    }
+
+    pub fn handle(&mut self, key: PagerKey) -> CompareOutcome {
+        let page = self.page_height;
+        match key {
+            PagerKey::Escape | PagerKey::Char('q') => return CompareOutcome::Closed,
+            PagerKey::Tab => self.focus = self.focus.other(),
+            PagerKey::Down | PagerKey::Enter | PagerKey::Char('j') => self.scroll_to(self.top + 1),
+            PagerKey::Up | PagerKey::Backspace | PagerKey::Char('k') => self.scroll_to(self.top.saturating_sub(1)),
+            PagerKey::Space | PagerKey::PageDown => self.scroll_to(self.top + page),
+            PagerKey::PageUp | PagerKey::Char('b') => self.scroll_to(self.top.saturating_sub(page)),
+            PagerKey::Char('g') => self.top = 0,
+            PagerKey::Char('G') => self.top = self.max_top(),
+            PagerKey::Char('n') => {
+                if let Some(row) = self.alignment.next_region(self.top) {
+                    self.scroll_to(row);
+                }
+            }
+            PagerKey::Char('p') => {
+                if let Some(row) = self.alignment.prev_region(self.top) {
+                    self.scroll_to(row);
+                }
+            }
+            PagerKey::Char('y') => {
+                let side = self.focus;
+                let text: Vec<&str> = self.visible().iter().filter_map(|row| self.line(side, row)).collect();
+                return CompareOutcome::Copy(text.join("\n"));
+            }
+            _ => {}
+        }
+        CompareOutcome::Continue
+    }
+
+    fn max_top(&self) -> usize {
+        self.alignment.rows.len().saturating_sub(self.page_height)
+    }
+
+    /// Near the end, a region can't scroll to the top row; it stays on
+    /// screen lower down.
+    fn scroll_to(&mut self, row: usize) {
+        self.top = row.min(self.max_top());
+    }
+}
+
+/// `line` cut to `cols` characters, ending in `…` when it was longer, so a
+/// row never wraps and pushes its side out of line.
+pub fn clip(line: &str, cols: usize) -> String {
+    if line.chars().count() <= cols {
+        return line.to_string();
+    }
+    let mut clipped: String = line.chars().take(cols.saturating_sub(1)).collect();
+    clipped.push('…');
+    clipped
+}
+
+/// A gap row `cols` wide.
+pub fn hatching(cols: usize) -> String {
+    std::iter::repeat_n(HATCH, cols).collect()
+}
+
+/// Read both outputs from the vault and line them up. `progress` gets a
+/// line for each output over `PROGRESS_BYTES` and before a big alignment.
+pub fn load(
+    vault: &Vault,
+    command: &CompareCommand,
+    page_height: usize,
+    mut progress: impl FnMut(String),
+) -> Result<CompareView, String> {
+    let last = || match vault.last_with_output(None, None) {
+        Ok(Some(record)) => Ok(record),
+        Ok(None) => Err("⇆ No command output stored yet.".to_string()),
+        Err(e) => Err(format!("❌ Error reading history: {}", e)),
+    };
+    let resolve = |operand: &CompareOperand| -> Result<CommandRecord, String> {
+        match operand {
+            CompareOperand::Id(id) => match vault.get_record(*id) {
+                Ok(Some(record)) if record.output.is_some() => Ok(record),
+                Ok(Some(_)) => Err(format!("⇆ #{} has no stored output.", id)),
+                Ok(None) => Err(format!("❌ No history entry #{}", id)),
+                Err(e) => Err(format!("❌ Error reading history: {}", e)),
+            },
+            CompareOperand::Tag(name) => match vault.get_tag(name) {
+                Ok(Some(tag)) => Ok(CommandRecord {
+                    id: tag.history_id,
+                    session_id: String::new(),
+                    command: format!("{} [{}]", tag.command, tag.name),
+                    output: Some(tag.output),
+                    exit_code: tag.exit_code,
+                    directory: tag.directory,
+                    duration_ms: None,
+                    timestamp: tag.ran_at,
+                }),
+                Ok(None) => Err(format!("❌ No tag named '{}'", name)),
+                Err(e) => Err(format!("❌ Error reading tags: {}", e)),
+            },
+            CompareOperand::Last => last(),
+            CompareOperand::Prev => match vault.last_with_output(None, last()?.id) {
+                Ok(Some(record)) => Ok(record),
+                Ok(None) => Err("⇆ Only one command has stored output.".to_string()),
+                Err(e) => Err(format!("❌ Error reading history: {}", e)),
+            },
+        }
+    };
+
+    let mut side = |operand: &CompareOperand| -> Result<(String, String), String> {
+        let record = resolve(operand)?;
+        let label = record_label(&record);
+        let output = record.output.unwrap_or_default();
+        if output.len() > PROGRESS_BYTES {
+            progress(format!("  ⇆ Loaded {} ({})", label, format_bytes(output.len() as u64)));
+        }
+        Ok((label, output))
+    };
+    let (left_label, left) = side(&command.left)?;
+    let (right_label, right) = side(&command.right)?;
+    if left.len() + right.len() > PROGRESS_BYTES {
+        progress("  ⇆ Aligning…".to_string());
+    }
+    Ok(CompareView::new((left_label, &left), (right_label, &right), page_height))
+}
+
+/// `#12 cargo test`, or just the command for a tag whose run is gone.
+fn record_label(record: &CommandRecord) -> String {
+    match record.id {
+        Some(id) => format!("#{} {}", id, record.command),
+        None => record.command.clone(),
+    }
+}
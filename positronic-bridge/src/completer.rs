@@ -8,30 +8,40 @@
 // - Theme names (e.g., !theme c → !theme cyberpunk)
 // - File/directory paths (e.g., cd Dow → cd Downloads)
 // - Alias names
+// - Executables on PATH (e.g., doc → docker), via `PathIndex`
+// - git subcommands, aliases and branches (e.g., git ch → git checkout), via `GitCompleter`
+// - Previously-run commands (frecency-ranked, from the engine's completion index)
 
 use std::path::Path;
+use std::sync::LazyLock;
 
-/// Known top-level ! commands.
-const BANG_COMMANDS: &[&str] = &[
-    "ai", "alias", "ask", "bm", "bookmark", "clear", "debug",
-    "explain", "export", "fix", "get", "help", "history", "hive",
-    "io", "pwd", "run", "set", "stats", "suggest", "theme", "top",
-    "ver", "version", "wasm",
-];
+use positronic_core::commands;
 
-/// Sub-commands for specific ! commands.
+use crate::fuzzy::{self, CaseMode, Matcher};
+use crate::git_complete::GitCompleter;
+use crate::path_index::PathIndex;
+
+/// Known top-level ! commands: every name and alias in the core's command
+/// registry, the window's own commands included.
+static BANG_COMMANDS: LazyLock<Vec<&'static str>> = LazyLock::new(|| commands::catalog().names());
+
+/// Whether `name` (without the `!`) is a known ! command.
+pub fn is_bang_command(name: &str) -> bool {
+    BANG_COMMANDS.contains(&name)
+}
+
+/// Every known ! command, for `!ver`'s count.
+pub fn bang_commands() -> &'static [&'static str] {
+    &BANG_COMMANDS
+}
+
+/// Sub-commands for specific ! commands, from their specs.
 fn subcommands_for(cmd: &str) -> &'static [&'static str] {
-    match cmd {
-        "alias" => &["set", "rm", "list"],
-        "bm" | "bookmark" => &["add", "rm"],
-        "hive" => &["scan", "status"],
-        "io" => &["scan", "list", "connect"],
-        _ => &[],
-    }
+    commands::catalog().subcommands(cmd)
 }
 
 /// Available theme names for !theme completion.
-const THEME_NAMES: &[&str] = &["default", "cyberpunk", "solarized", "monokai"];
+const THEME_NAMES: &[&str] = &["default", "cyberpunk", "solarized", "monokai", "auto"];
 
 /// Completion state that tracks cycling through results.
 #[derive(Debug, Clone)]
@@ -69,10 +79,85 @@ impl CompletionState {
     }
 }
 
+/// Completion sources beyond the built-in tables and the filesystem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Providers<'a> {
+    /// Executables for the first word of a command.
+    pub executables: Option<&'a PathIndex>,
+    /// Subcommands and branches after `git`.
+    pub git: Option<&'a GitCompleter>,
+    /// `!tag` names, for `!recall`, `!tag rm` and `!diff`.
+    pub tags: &'a [String],
+    /// Match aliases, executables and history regardless of case
+    /// (`completion-ignore-case` from `.inputrc`).
+    pub ignore_case: bool,
+}
+
+/// `candidate` starts with `prefix`, ignoring case if asked to.
+fn has_prefix(candidate: &str, prefix: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        candidate.to_lowercase().starts_with(&prefix.to_lowercase())
+    } else {
+        candidate.starts_with(prefix)
+    }
+}
+
+/// The `candidates` starting with `partial`, in order. When none do, the
+/// ones `partial` fuzzy-matches instead (`fuzzy_matches`): `!hst` still
+/// finds `!history`.
+fn prefix_or_fuzzy<S: AsRef<str>>(candidates: &[S], partial: &str, ignore_case: bool) -> Vec<String> {
+    let prefixed: Vec<String> = candidates
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|c| has_prefix(c, partial, ignore_case))
+        .map(str::to_string)
+        .collect();
+    if !prefixed.is_empty() || partial.is_empty() {
+        return prefixed;
+    }
+    fuzzy_matches(candidates, partial, ignore_case, |_| 0.0)
+}
+
+/// The `candidates` `partial` fuzzy-matches, best first, with `weight`
+/// (by index) blended in.
+fn fuzzy_matches<S: AsRef<str>>(
+    candidates: &[S],
+    partial: &str,
+    ignore_case: bool,
+    weight: impl Fn(usize) -> f32,
+) -> Vec<String> {
+    let mode = if ignore_case { CaseMode::Insensitive } else { CaseMode::Sensitive };
+    fuzzy::rank(&Matcher::new(partial, mode), candidates, weight)
+        .into_iter()
+        .map(|ranked| candidates[ranked.index].as_ref().to_string())
+        .collect()
+}
+
 /// Generate completions for the given input.
 /// `aliases` should be a list of known alias names.
 /// `cwd` is the current working directory for path completion.
 pub fn complete(input: &str, aliases: &[String], cwd: &str) -> Option<CompletionState> {
+    complete_with_providers(input, aliases, cwd, Providers::default())
+}
+
+/// `complete`, also consulting `providers`.
+pub fn complete_with_providers(
+    input: &str,
+    aliases: &[String],
+    cwd: &str,
+    providers: Providers<'_>,
+) -> Option<CompletionState> {
+    complete_by_prefix(input, aliases, cwd, providers).or_else(|| fuzzy_command(input, aliases, providers))
+}
+
+/// Completions that start with what was typed: ! commands (which fall
+/// back to fuzzy matches themselves), git, paths, aliases, executables.
+fn complete_by_prefix(
+    input: &str,
+    aliases: &[String],
+    cwd: &str,
+    providers: Providers<'_>,
+) -> Option<CompletionState> {
     let trimmed = input.trim_start();
 
     if trimmed.is_empty() {
@@ -81,7 +166,29 @@ pub fn complete(input: &str, aliases: &[String], cwd: &str) -> Option<Completion
 
     // ── ! command completion ──
     if trimmed.starts_with('!') {
-        return complete_bang(trimmed);
+        // `!view` takes a file.
+        if trimmed.starts_with("!view ")
+            && let Some((before, partial)) = trimmed.rsplit_once(' ')
+            && !partial.is_empty()
+            && !partial.starts_with('-')
+            && let Some(state) = complete_path(partial, &format!("{} ", before), cwd)
+        {
+            return Some(state);
+        }
+        return complete_bang(trimmed, providers.tags);
+    }
+
+    // ── git subcommands and branches ──
+    if let Some(git) = providers.git
+        && let Some(matches) = git.complete(trimmed, cwd)
+        && !matches.is_empty()
+        && !(matches.len() == 1 && matches[0] == trimmed)
+    {
+        return Some(CompletionState {
+            original: input.to_string(),
+            completions: matches,
+            index: 0,
+        });
     }
 
     // ── Path completion for shell commands ──
@@ -95,14 +202,24 @@ pub fn complete(input: &str, aliases: &[String], cwd: &str) -> Option<Completion
             }
         }
     } else {
-        // Single token — could be an alias or a command/path
-        // Try alias completion first
-        if !aliases.is_empty() {
-            let matches: Vec<String> = aliases
+        // Single token — could be an alias, an executable on PATH or a path.
+        // Aliases come first, then executables; explicit paths skip both.
+        let is_path = trimmed.starts_with('.') || trimmed.contains('/') || trimmed.contains('\\');
+        if !is_path {
+            let mut matches: Vec<String> = aliases
                 .iter()
-                .filter(|a| a.starts_with(trimmed))
+                .filter(|a| has_prefix(a, trimmed, providers.ignore_case))
                 .cloned()
                 .collect();
+            if let Some(index) = providers.executables {
+                let names =
+                    if providers.ignore_case { index.matching_any_case(trimmed) } else { index.matching(trimmed) };
+                for name in names {
+                    if !matches.contains(&name) {
+                        matches.push(name);
+                    }
+                }
+            }
             if !matches.is_empty() && !(matches.len() == 1 && matches[0] == trimmed) {
                 return Some(CompletionState {
                     original: input.to_string(),
@@ -121,17 +238,79 @@ pub fn complete(input: &str, aliases: &[String], cwd: &str) -> Option<Completion
     None
 }
 
-/// Complete ! commands and their sub-commands.
-fn complete_bang(input: &str) -> Option<CompletionState> {
+/// Like `complete`, but falls back to history candidates when nothing
+/// structural matches. `history` is expected best-first (as returned by
+/// `PositronicEngine::suggest`) and is only consulted for the whole line.
+pub fn complete_with_history(
+    input: &str,
+    aliases: &[String],
+    cwd: &str,
+    history: &[String],
+) -> Option<CompletionState> {
+    complete_all(input, aliases, cwd, history, Providers::default())
+}
+
+/// `complete_with_providers`, with history as the fallback.
+pub fn complete_all(
+    input: &str,
+    aliases: &[String],
+    cwd: &str,
+    history: &[String],
+    providers: Providers<'_>,
+) -> Option<CompletionState> {
+    // Anything that starts with the line beats any fuzzy match.
+    complete_by_prefix(input, aliases, cwd, providers)
+        .or_else(|| complete_history(input, history, providers.ignore_case, false))
+        .or_else(|| fuzzy_command(input, aliases, providers))
+        .or_else(|| complete_history(input, history, providers.ignore_case, true))
+}
+
+/// A single word that nothing starts with, fuzzy-matched against aliases
+/// and executables.
+fn fuzzy_command(input: &str, aliases: &[String], providers: Providers<'_>) -> Option<CompletionState> {
+    let trimmed = input.trim_start();
+    let is_path = trimmed.starts_with('.') || trimmed.contains('/') || trimmed.contains('\\');
+    if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.contains(' ') || is_path {
+        return None;
+    }
+    let mut names = aliases.to_vec();
+    let executables = providers.executables.map(PathIndex::names).unwrap_or_default();
+    names.extend(executables.into_iter().filter(|name| !aliases.contains(name)));
+    let matches: Vec<String> = fuzzy_matches(&names, trimmed, providers.ignore_case, |_| 0.0)
+        .into_iter()
+        .filter(|name| name != trimmed)
+        .collect();
+    (!matches.is_empty()).then(|| CompletionState { original: input.to_string(), completions: matches, index: 0 })
+}
+
+/// History entries for the whole line: those starting with it, or with
+/// `fuzzy` those it fuzzy-matches. History comes best first, and fuzzy
+/// matches keep some of that order.
+fn complete_history(input: &str, history: &[String], ignore_case: bool, fuzzy: bool) -> Option<CompletionState> {
+    let trimmed = input.trim_start();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let matches: Vec<String> = if fuzzy {
+        let newest = |i: usize| 1.0 - i as f32 / history.len() as f32;
+        fuzzy_matches(history, trimmed, ignore_case, newest)
+    } else {
+        history.iter().filter(|h| has_prefix(h, trimmed, ignore_case)).cloned().collect()
+    };
+    let matches: Vec<String> = matches.into_iter().filter(|h| h.as_str() != trimmed).collect();
+    (!matches.is_empty()).then(|| CompletionState { original: input.to_string(), completions: matches, index: 0 })
+}
+
+/// Complete ! commands and their sub-commands, and tag names where a
+/// command takes one.
+fn complete_bang(input: &str, tags: &[String]) -> Option<CompletionState> {
     let without_bang = &input[1..]; // strip leading !
     let parts: Vec<&str> = without_bang.splitn(2, ' ').collect();
 
     if parts.len() == 1 {
         // Completing the command name: !his → !history
-        let partial = parts[0].to_lowercase();
-        let matches: Vec<String> = BANG_COMMANDS
-            .iter()
-            .filter(|cmd| cmd.starts_with(&partial))
+        let matches: Vec<String> = prefix_or_fuzzy(&BANG_COMMANDS, parts[0], true)
+            .into_iter()
             .map(|cmd| format!("!{}", cmd))
             .collect();
 
@@ -151,9 +330,8 @@ fn complete_bang(input: &str) -> Option<CompletionState> {
 
         // Special case: !theme <n>
         if cmd == "theme" {
-            let matches: Vec<String> = THEME_NAMES
-                .iter()
-                .filter(|t| t.starts_with(&arg_partial.to_lowercase()))
+            let matches: Vec<String> = prefix_or_fuzzy(THEME_NAMES, arg_partial, true)
+                .into_iter()
                 .map(|t| format!("!theme {}", t))
                 .collect();
             if !matches.is_empty() && !(matches.len() == 1 && matches[0] == input) {
@@ -166,12 +344,36 @@ fn complete_bang(input: &str) -> Option<CompletionState> {
             return None;
         }
 
+        // Tag names: the last word of !recall, !tag rm and !diff
+        let (head, partial) = match input.rfind(' ') {
+            Some(space) => input.split_at(space + 1),
+            None => (input, ""),
+        };
+        let takes_tag = match cmd {
+            "recall" => !parts[1].contains(' '),
+            "tag" => parts[1].starts_with("rm ") && !parts[1][3..].contains(' '),
+            "diff" => !parts[1].contains("--watch") && !partial.is_empty() && !partial.starts_with('-'),
+            _ => false,
+        };
+        if takes_tag {
+            let matches: Vec<String> = prefix_or_fuzzy(tags, partial, false)
+                .into_iter()
+                .map(|t| format!("{}{}", head, t))
+                .collect();
+            if !matches.is_empty() && !(matches.len() == 1 && matches[0] == input) {
+                return Some(CompletionState {
+                    original: input.to_string(),
+                    completions: matches,
+                    index: 0,
+                });
+            }
+        }
+
         // Sub-command completion
         let subs = subcommands_for(cmd);
         if !subs.is_empty() {
-            let matches: Vec<String> = subs
-                .iter()
-                .filter(|s| s.starts_with(&arg_partial.to_lowercase()))
+            let matches: Vec<String> = prefix_or_fuzzy(subs, arg_partial, true)
+                .into_iter()
                 .map(|s| format!("!{} {}", cmd, s))
                 .collect();
             if !matches.is_empty() && !(matches.len() == 1 && matches[0] == input) {
@@ -316,6 +518,14 @@ mod tests {
         assert!(complete("", &[], ".").is_none());
     }
 
+    #[test]
+    fn test_history_fallback() {
+        let history = vec!["git push origin main".to_string(), "git pull".to_string()];
+        let state = complete_with_history("git pu", &[], "/nonexistent", &history).unwrap();
+        assert_eq!(state.current(), "git push origin main");
+        assert_eq!(state.len(), 2);
+    }
+
     #[test]
     fn test_cycling() {
         let mut state = complete("!hi", &[], ".").unwrap();
@@ -0,0 +1,600 @@
+//! `!io console`: a raw serial console bound to one port (no UI deps).
+//!
+//! While a console is open the terminal area shows the device instead of
+//! the shell. The port's `SerialOutput` lands in the console's scrollback,
+//! and keys go to the device through `Console::key`, which turns them into
+//! bytes (Enter sends the chosen line ending). A few chords stay with
+//! Positronic: the exit chord (`keys.console_exit`, Ctrl+] by default),
+//! copy, paste, search and PageUp/PageDown to scroll.
+//!
+//! The scrollback is a `BlockManager` of `BlockSource::Hardware` blocks,
+//! one per `SEGMENT_LINES` lines, so retention trims the oldest and search
+//! and copy work as they do on blocks.
+//!
+//! The phases: `Connecting` until the port opens (when the console had to
+//! connect it), then `Open`. `Console::event` says when to leave: the
+//! device disconnected or could not be opened. Leaving by either path ends
+//! the session, which the app logs to the vault's device history.
+//!
+//! The port may be a named USB device (`!io name`); the app resolves it
+//! and fills the baud and line ending last used with that device.
+
+use std::time::Duration;
+
+use positronic_core::headless::plain_text;
+use positronic_core::serial::{port_label, DEFAULT_BAUD};
+use positronic_core::vault::{DeviceDefaults, DeviceSession, DEVICE_TRANSCRIPT_CAP};
+use positronic_io::HardwareEvent;
+
+use crate::block::{BlockId, BlockLine, BlockManager, BlockSource, RetentionPolicy};
+use crate::helpers::format_bytes;
+use crate::keymap::{Action, Chord, KeyName, Keymap, NamedKey};
+
+pub const CONSOLE_USAGE: &str = "Usage: !io console <port|name> [baud] [--eol cr|lf|crlf] [--echo]";
+
+/// Lines per scrollback block.
+pub const SEGMENT_LINES: usize = 500;
+/// Lines the scrollback keeps before the oldest blocks go.
+pub const MAX_LINES: usize = 20_000;
+
+/// What Enter sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// What a terminal sends, and what most device shells expect.
+    #[default]
+    Cr,
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn parse(text: &str) -> Result<LineEnding, String> {
+        match text.to_lowercase().as_str() {
+            "cr" => Ok(LineEnding::Cr),
+            "lf" | "nl" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::CrLf),
+            _ => Err(format!("❌ Bad line ending '{}' (cr, lf or crlf)", text)),
+        }
+    }
+
+    pub fn bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Cr => b"\r",
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Cr => "CR",
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+}
+
+/// A parsed `!io console` line. `None` settings were not given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleCommand {
+    pub port: String,
+    pub baud: Option<u32>,
+    pub eol: Option<LineEnding>,
+    /// Show what is typed; for devices that don't echo.
+    pub echo: bool,
+    /// The device's name, once `port` is resolved.
+    pub name: Option<String>,
+}
+
+impl ConsoleCommand {
+    /// Parse `!io console ...`; the error is a message for the user.
+    pub fn parse(cmd: &str) -> Result<ConsoleCommand, String> {
+        let mut parts = cmd.split_whitespace();
+        if (parts.next(), parts.next()) != (Some("!io"), Some("console")) {
+            return Err(CONSOLE_USAGE.to_string());
+        }
+        let mut port = None;
+        let mut baud = None;
+        let mut eol = None;
+        let mut echo = false;
+        while let Some(part) = parts.next() {
+            match part {
+                "--eol" => eol = Some(LineEnding::parse(parts.next().ok_or(CONSOLE_USAGE)?)?),
+                "--echo" => echo = true,
+                p if p.starts_with("--") => return Err(CONSOLE_USAGE.to_string()),
+                p if port.is_none() => port = Some(p.to_string()),
+                b if baud.is_none() => {
+                    baud = Some(b.parse().map_err(|_| format!("❌ Bad baud rate '{}'", b))?);
+                }
+                _ => return Err(CONSOLE_USAGE.to_string()),
+            }
+        }
+        let port = port.ok_or(CONSOLE_USAGE)?;
+        Ok(ConsoleCommand { port, baud, eol, echo, name: None })
+    }
+
+    /// Fill the baud and line ending not given from a device's saved ones.
+    pub fn with_defaults(self, saved: &DeviceDefaults) -> ConsoleCommand {
+        ConsoleCommand {
+            baud: self.baud.or(saved.baud),
+            eol: self.eol.or_else(|| saved.eol.as_deref().and_then(|eol| LineEnding::parse(eol).ok())),
+            ..self
+        }
+    }
+
+    /// What was given, to save for the device.
+    pub fn given(&self) -> DeviceDefaults {
+        DeviceDefaults {
+            baud: self.baud,
+            eol: self.eol.map(|eol| eol.label().to_lowercase()),
+            ..DeviceDefaults::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsolePhase {
+    /// Waiting for the port to open.
+    Connecting,
+    Open,
+}
+
+/// Why a console closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleExit {
+    /// The exit chord or `!io console` again.
+    User,
+    Disconnected,
+    /// The port could not be opened; the IO layer's message.
+    Failed(String),
+}
+
+/// What a key means to an open console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleKey {
+    /// Bytes for the device.
+    Send(Vec<u8>),
+    Exit,
+    Copy,
+    Paste,
+    /// The console used the key itself (search, scrolling).
+    Handled,
+    Ignored,
+}
+
+/// Incremental search over the scrollback.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsoleSearch {
+    pub query: String,
+    /// Scrollback line positions that match, oldest first.
+    pub hits: Vec<usize>,
+    /// Index into `hits` of the shown match.
+    pub current: Option<usize>,
+}
+
+/// What the console draws: the rows at its scroll position and which of
+/// them is the current search match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleView<'a> {
+    pub lines: Vec<&'a str>,
+    pub highlight: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Console {
+    pub port: String,
+    /// The device's name, shown instead of the port.
+    pub name: Option<String>,
+    pub baud: u32,
+    pub eol: LineEnding,
+    pub echo: bool,
+    pub phase: ConsolePhase,
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+    /// Unix seconds.
+    pub started_at: i64,
+    scrollback: BlockManager,
+    segment: BlockId,
+    /// The line being received, before its newline.
+    partial: String,
+    /// `partial` as drawn.
+    partial_plain: String,
+    /// What the device sent, for the vault, which keeps the last
+    /// `DEVICE_TRANSCRIPT_CAP` bytes of it.
+    transcript: String,
+    /// Lines up from the newest.
+    pub scroll: usize,
+    pub search: Option<ConsoleSearch>,
+}
+
+impl Console {
+    /// A console for `command`; `connected` says whether the port is
+    /// already open, otherwise the app connects it and the console waits.
+    pub fn new(command: &ConsoleCommand, connected: bool, now: i64) -> Self {
+        let mut scrollback = BlockManager::with_policy(RetentionPolicy {
+            max_blocks: MAX_LINES / SEGMENT_LINES + 1,
+            max_total_lines: MAX_LINES,
+            summarize: false,
+            ..RetentionPolicy::default()
+        });
+        let segment = scrollback.begin(&command.port, "", BlockSource::Hardware);
+        Self {
+            port: command.port.clone(),
+            name: command.name.clone(),
+            baud: command.baud.unwrap_or(DEFAULT_BAUD),
+            eol: command.eol.unwrap_or_default(),
+            echo: command.echo,
+            phase: if connected { ConsolePhase::Open } else { ConsolePhase::Connecting },
+            tx_bytes: 0,
+            rx_bytes: 0,
+            started_at: now,
+            scrollback,
+            segment,
+            partial: String::new(),
+            partial_plain: String::new(),
+            transcript: String::new(),
+            scroll: 0,
+            search: None,
+        }
+    }
+
+    /// Fold in a hardware event. `Some` means the console has to close.
+    pub fn event(&mut self, event: &HardwareEvent) -> Option<ConsoleExit> {
+        match event {
+            HardwareEvent::DeviceConnected(port) if *port == self.port => {
+                self.phase = ConsolePhase::Open;
+                None
+            }
+            HardwareEvent::DeviceDisconnected(port) if *port == self.port => Some(ConsoleExit::Disconnected),
+            HardwareEvent::SerialOutput { port, text } if *port == self.port => {
+                self.phase = ConsolePhase::Open;
+                self.receive(text);
+                None
+            }
+            // Open failures name the port but carry no field for it.
+            HardwareEvent::Error(message)
+                if self.phase == ConsolePhase::Connecting && message.contains(self.port.as_str()) =>
+            {
+                Some(ConsoleExit::Failed(message.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Text from the device.
+    pub fn receive(&mut self, text: &str) {
+        self.rx_bytes += text.len() as u64;
+        self.transcript.push_str(text);
+        if self.transcript.len() > 2 * DEVICE_TRANSCRIPT_CAP {
+            let mut cut = self.transcript.len() - DEVICE_TRANSCRIPT_CAP;
+            while !self.transcript.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.transcript.drain(..cut);
+        }
+        self.append(text);
+    }
+
+    /// Bytes that went to the device; shown too with local echo on.
+    pub fn sent(&mut self, bytes: &[u8]) {
+        self.tx_bytes += bytes.len() as u64;
+        if self.echo {
+            self.append(&String::from_utf8_lossy(bytes).replace('\r', "\n").replace("\n\n", "\n"));
+        }
+    }
+
+    /// A line from Positronic itself (errors), on a line of its own.
+    pub fn notice(&mut self, text: &str) {
+        if !self.partial.is_empty() {
+            self.append("\n");
+        }
+        self.push_line(BlockLine::info(text));
+    }
+
+    fn append(&mut self, text: &str) {
+        self.partial.push_str(text);
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            self.push_line(BlockLine::classify(clean_line(&line)));
+        }
+        self.partial_plain = clean_line(&self.partial);
+        if let Some(search) = &mut self.search {
+            // New lines can match; keep the shown one.
+            let current = search.current.map(|i| search.hits[i]);
+            search.hits = search_hits(&self.scrollback, &search.query);
+            search.current = current.and_then(|pos| search.hits.iter().position(|&h| h == pos));
+        }
+    }
+
+    fn push_line(&mut self, line: BlockLine) {
+        if self.scrollback.get(self.segment).is_none_or(|b| b.output.len() >= SEGMENT_LINES) {
+            self.scrollback.finish(self.segment, None, Duration::ZERO);
+            self.segment = self.scrollback.begin(&self.port, "", BlockSource::Hardware);
+        }
+        self.scrollback.append_line(self.segment, line);
+        // Scrolled back, the view stays on the same lines.
+        if self.scroll > 0 {
+            self.scroll += 1;
+        }
+    }
+
+    /// Every scrollback line, oldest first, then the unfinished one.
+    pub fn lines(&self) -> Vec<&str> {
+        let mut lines: Vec<&str> = self
+            .scrollback
+            .blocks()
+            .iter()
+            .flat_map(|b| b.output.iter().map(|l| l.text.as_str()))
+            .collect();
+        if !self.partial_plain.is_empty() {
+            lines.push(&self.partial_plain);
+        }
+        lines
+    }
+
+    /// The `rows` lines at the scroll position.
+    pub fn visible(&self, rows: usize) -> ConsoleView<'_> {
+        let lines = self.lines();
+        let end = lines.len().saturating_sub(self.scroll.min(lines.len().saturating_sub(rows)));
+        let start = end.saturating_sub(rows);
+        let highlight = self
+            .search
+            .as_ref()
+            .and_then(|s| s.current.map(|i| s.hits[i]))
+            .filter(|pos| (start..end).contains(pos))
+            .map(|pos| pos - start);
+        ConsoleView { lines: lines[start..end].to_vec(), highlight }
+    }
+
+    /// The whole scrollback as text, for the clipboard.
+    pub fn copy_text(&self) -> String {
+        let mut text = self.lines().join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// `🔌 COM3 @ 115200 · TX 12 B · RX 3.4 KB · CR · echo off · ctrl+] exits`
+    pub fn header(&self, exit: Option<&Chord>) -> String {
+        let mut header = format!(
+            "🔌 {} @ {} · TX {} · RX {} · {} · echo {}",
+            self.label(),
+            self.baud,
+            format_bytes(self.tx_bytes),
+            format_bytes(self.rx_bytes),
+            self.eol.label(),
+            if self.echo { "on" } else { "off" }
+        );
+        if self.phase == ConsolePhase::Connecting {
+            header.push_str(" · connecting…");
+        }
+        match exit {
+            Some(chord) => header.push_str(&format!(" · {} exits", chord)),
+            None => header.push_str(" · !io console exits"),
+        }
+        header
+    }
+
+    /// The search prompt, while searching.
+    pub fn search_line(&self) -> Option<String> {
+        let search = self.search.as_ref()?;
+        let position = match (search.current, search.hits.len()) {
+            (_, 0) if search.query.is_empty() => String::new(),
+            (_, 0) => "  (no match)".to_string(),
+            (Some(i), n) => format!("  ({}/{})", i + 1, n),
+            (None, n) => format!("  ({} matches)", n),
+        };
+        Some(format!("🔎 {}{}  [Enter older · Esc close]", search.query, position))
+    }
+
+    /// The session for the vault's device history, ending at `now`.
+    pub fn record(&self, now: i64) -> DeviceSession {
+        DeviceSession {
+            id: 0,
+            port: self.port.clone(),
+            baud: self.baud,
+            started_at: self.started_at,
+            ended_at: now,
+            tx_bytes: self.tx_bytes,
+            rx_bytes: self.rx_bytes,
+            transcript: self.transcript.clone(),
+        }
+    }
+
+    /// "bench-scope (COM7)" for a named device, else the port.
+    pub fn label(&self) -> String {
+        port_label(&self.port, self.name.as_deref())
+    }
+
+    /// The notice printed back in the shell.
+    pub fn closed_notice(&self, exit: &ConsoleExit) -> String {
+        let totals = format!("TX {}, RX {}", format_bytes(self.tx_bytes), format_bytes(self.rx_bytes));
+        match exit {
+            ConsoleExit::User => format!("🔌 Left the {} console ({})", self.label(), totals),
+            ConsoleExit::Disconnected => {
+                format!("🔌 {} disconnected — console closed ({})", self.label(), totals)
+            }
+            ConsoleExit::Failed(message) => format!("❌ {} — console closed", message),
+        }
+    }
+
+    /// Route a key. `chord` is the keymap's view of it, `text` what it
+    /// types. The exit chord always leaves; while searching, keys edit
+    /// the query; copy, paste and search keep their bindings and
+    /// PageUp/PageDown scroll. Everything else goes to the device.
+    pub fn key(&mut self, chord: Option<Chord>, text: Option<&str>, keymap: &Keymap, page: usize) -> ConsoleKey {
+        let action = chord.as_ref().and_then(|c| keymap.action_for(c));
+        if action == Some(Action::ConsoleExit) {
+            return ConsoleKey::Exit;
+        }
+        let named = match chord.map(|c| c.key) {
+            Some(KeyName::Named(named)) => Some(named),
+            _ => None,
+        };
+        let typing = chord.is_none_or(|c| !c.ctrl && !c.alt);
+
+        if self.search.is_some() {
+            match named {
+                Some(NamedKey::Escape) => self.search = None,
+                Some(NamedKey::Enter) => self.search_older(),
+                Some(NamedKey::Backspace) => self.edit_search(|q| {
+                    q.pop();
+                }),
+                _ => match text {
+                    Some(text) if typing => self.edit_search(|q| q.push_str(text)),
+                    _ => return ConsoleKey::Ignored,
+                },
+            }
+            return ConsoleKey::Handled;
+        }
+
+        match action {
+            Some(Action::Copy) => return ConsoleKey::Copy,
+            Some(Action::Paste) => return ConsoleKey::Paste,
+            Some(Action::SearchOpen) => {
+                self.search = Some(ConsoleSearch::default());
+                return ConsoleKey::Handled;
+            }
+            _ => {}
+        }
+        match named {
+            Some(NamedKey::PageUp) => {
+                let max = self.lines().len().saturating_sub(page);
+                self.scroll = (self.scroll + page).min(max);
+                return ConsoleKey::Handled;
+            }
+            Some(NamedKey::PageDown) => {
+                self.scroll = self.scroll.saturating_sub(page);
+                return ConsoleKey::Handled;
+            }
+            _ => {}
+        }
+
+        match key_bytes(chord, text, self.eol) {
+            Some(bytes) => {
+                self.scroll = 0;
+                ConsoleKey::Send(bytes)
+            }
+            None => ConsoleKey::Ignored,
+        }
+    }
+
+    fn edit_search(&mut self, edit: impl FnOnce(&mut String)) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        edit(&mut search.query);
+        search.hits = search_hits(&self.scrollback, &search.query);
+        search.current = search.hits.len().checked_sub(1);
+        self.show_current_hit();
+    }
+
+    /// Step to the next older match, wrapping to the newest.
+    fn search_older(&mut self) {
+        let Some(search) = &mut self.search else {
+            return;
+        };
+        if search.hits.is_empty() {
+            return;
+        }
+        search.current = Some(match search.current {
+            Some(0) | None => search.hits.len() - 1,
+            Some(i) => i - 1,
+        });
+        self.show_current_hit();
+    }
+
+    /// Scroll so the current match is the bottom row.
+    fn show_current_hit(&mut self) {
+        let Some(pos) = self.search.as_ref().and_then(|s| s.current.map(|i| s.hits[i])) else {
+            return;
+        };
+        self.scroll = self.lines().len().saturating_sub(pos + 1);
+    }
+}
+
+/// Positions of the scrollback lines matching `query` (the unfinished
+/// line is not searched). Block-level hits on the port name are skipped.
+fn search_hits(scrollback: &BlockManager, query: &str) -> Vec<usize> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut offsets = Vec::with_capacity(scrollback.len());
+    let mut total = 0;
+    for block in scrollback.blocks() {
+        offsets.push((block.id, total));
+        total += block.output.len();
+    }
+    scrollback
+        .search(query)
+        .into_iter()
+        .filter_map(|hit| {
+            let line = hit.line_index?;
+            offsets.iter().find(|(id, _)| *id == hit.block_id).map(|(_, start)| start + line)
+        })
+        .collect()
+}
+
+/// A received line as drawn: escape sequences gone, backspaces applied,
+/// other control characters dropped.
+fn clean_line(raw: &str) -> String {
+    let mut line = String::with_capacity(raw.len());
+    for c in plain_text(raw).chars() {
+        match c {
+            '\u{8}' | '\u{7f}' => {
+                line.pop();
+            }
+            '\t' => line.push(c),
+            c if c.is_control() => {}
+            c => line.push(c),
+        }
+    }
+    line
+}
+
+/// The bytes a key sends: Enter the line ending, Ctrl+letter its control
+/// code, Alt a leading Escape, cursor keys their VT100 sequences.
+pub fn key_bytes(chord: Option<Chord>, text: Option<&str>, eol: LineEnding) -> Option<Vec<u8>> {
+    let Some(chord) = chord else {
+        return text.filter(|t| !t.is_empty()).map(|t| t.as_bytes().to_vec());
+    };
+    let mut bytes = if chord.alt { vec![0x1b] } else { Vec::new() };
+    match chord.key {
+        KeyName::Char(c) if chord.ctrl => bytes.push(control_byte(c)?),
+        KeyName::Char(c) => match text {
+            Some(text) if !text.is_empty() => bytes.extend_from_slice(text.as_bytes()),
+            _ => bytes.extend_from_slice(c.to_string().as_bytes()),
+        },
+        KeyName::Named(named) => bytes.extend_from_slice(match named {
+            NamedKey::Enter => eol.bytes(),
+            NamedKey::Tab => b"\t",
+            NamedKey::Escape => b"\x1b",
+            NamedKey::Backspace => b"\x7f",
+            NamedKey::Delete => b"\x1b[3~",
+            NamedKey::Insert => b"\x1b[2~",
+            NamedKey::Space if chord.ctrl => b"\0",
+            NamedKey::Space => b" ",
+            NamedKey::Up => b"\x1b[A",
+            NamedKey::Down => b"\x1b[B",
+            NamedKey::Right => b"\x1b[C",
+            NamedKey::Left => b"\x1b[D",
+            NamedKey::Home => b"\x1b[H",
+            NamedKey::End => b"\x1b[F",
+            NamedKey::PageUp | NamedKey::PageDown | NamedKey::F(_) => return None,
+        }),
+    }
+    Some(bytes)
+}
+
+/// The C0 code Ctrl plus `c` types.
+fn control_byte(c: char) -> Option<u8> {
+    Some(match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => c as u8 - b'a' + 1,
+        '@' | '2' | ' ' => 0,
+        '[' => 0x1b,
+        '\\' => 0x1c,
+        ']' => 0x1d,
+        '^' | '6' => 0x1e,
+        '_' | '-' => 0x1f,
+        _ => return None,
+    })
+}
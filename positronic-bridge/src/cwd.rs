@@ -5,6 +5,10 @@
 // Two strategies:
 // 1. **Proactive**: Parse `cd`/`pushd`/`Set-Location` commands before sending to PTY.
 // 2. **Reactive**: Parse the PTY snapshot prompt line for common shell prompt patterns.
+//
+// Commands whose effect on the cwd can't be worked out from their text
+// (`cd -`, `popd`, sourced scripts) ask the shell directly through the
+// engine's cwd probe instead; see `cwd_is_uncertain`.
 
 use positronic_core::state_machine::Snapshot;
 
@@ -47,6 +51,24 @@ pub fn track_cd_command(cmd: &str, cwd: &mut String) {
     }
 }
 
+/// True if `cmd` may change directory in a way `track_cd_command` can't
+/// follow, so the shell should be asked for its cwd afterwards.
+pub fn cwd_is_uncertain(cmd: &str) -> bool {
+    cmd.split(['&', ';', '|']).any(|part| {
+        let parts: Vec<&str> = part.split_whitespace().collect();
+        let Some(first) = parts.first() else {
+            return false;
+        };
+        match first.to_lowercase().as_str() {
+            "cd" | "chdir" | "set-location" | "sl" => {
+                parts.len() < 2 || parts[1..].iter().any(|a| *a == "-" || a.contains(['$', '`', '~']))
+            }
+            "pushd" | "popd" | "push-location" | "pop-location" | "source" | "." => true,
+            _ => false,
+        }
+    })
+}
+
 /// Extract CWD from the PTY snapshot by looking for common prompt patterns.
 ///
 /// Supported patterns:
@@ -61,8 +83,7 @@ pub fn update_cwd_from_snapshot(snapshot: &Snapshot, cwd: &mut String) {
 
     // Scan from the bottom for the first non-empty line (the prompt)
     for row_idx in (0..rows).rev() {
-        let row = &snapshot[row_idx];
-        let line: String = row.iter().map(|(c, _)| *c).collect();
+        let line = snapshot.row_text(row_idx);
         let trimmed = line.trim();
 
         if trimmed.is_empty() {
@@ -50,8 +50,7 @@ pub fn detect_terminal_mode(snapshot: &Snapshot) -> TerminalMode {
 
     // Find the last non-empty line (scanning from bottom)
     for row_idx in (0..rows).rev() {
-        let row = &snapshot[row_idx];
-        let line: String = row.iter().map(|(c, _)| *c).collect();
+        let line = snapshot.row_text(row_idx);
         let trimmed = line.trim();
 
         if trimmed.is_empty() {
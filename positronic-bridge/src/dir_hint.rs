@@ -0,0 +1,108 @@
+//! The "frequent here" hint on moving into a directory.
+//!
+//! Each time the tracked cwd changes, `entered` says whether the directory
+//! needs looking up: only the first visit in a session does, and never the
+//! directory Positronic started in. The lookup (`Vault::top_for_directory`)
+//! runs off the UI thread and its answer comes back through `arrived`,
+//! which is kept for the session whether or not it makes a hint; by then
+//! the shell may have moved on, and a stale answer shows nothing.
+//!
+//! The hint sits in the input bar while the input is empty, until the next
+//! command is submitted or the directory changes. It's held back while a
+//! fullscreen program has the screen or output is streaming, and Tab on
+//! the empty input puts its commands in, one per press.
+
+use std::collections::HashMap;
+
+use positronic_core::here;
+
+/// What the hint has to stay out of the way of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Screen {
+    /// A fullscreen program (the alternate screen) has the display.
+    pub fullscreen: bool,
+    /// A command is running or its output hasn't settled.
+    pub streaming: bool,
+}
+
+impl Screen {
+    fn quiet(self) -> bool {
+        !self.fullscreen && !self.streaming
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DirectoryHints {
+    enabled: bool,
+    /// The directory the shell is in, once known.
+    current: Option<String>,
+    /// Directories visited this session, with their commands once looked up.
+    seen: HashMap<String, Option<Vec<String>>>,
+    /// The hint's commands, while it is up.
+    shown: Option<Vec<String>>,
+}
+
+impl Default for DirectoryHints {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl DirectoryHints {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, current: None, seen: HashMap::new(), shown: None }
+    }
+
+    /// `hints.directory`; turning it off takes down a hint that is up.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.shown = None;
+        }
+    }
+
+    /// The shell is in `dir`. Returns the directory to look up when this
+    /// is a move into one not visited yet this session.
+    pub fn entered(&mut self, dir: &str) -> Option<String> {
+        if self.current.as_deref() == Some(dir) {
+            return None;
+        }
+        let first = self.current.is_none();
+        self.current = Some(dir.to_string());
+        self.shown = None;
+        if self.seen.contains_key(dir) {
+            return None;
+        }
+        self.seen.insert(dir.to_string(), None);
+        (self.enabled && !first).then(|| dir.to_string())
+    }
+
+    /// The lookup for `dir` came back with `commands`, best first.
+    pub fn arrived(&mut self, dir: &str, commands: Vec<String>) {
+        let hint = self.enabled && self.current.as_deref() == Some(dir) && commands.len() >= here::HINT_COMMANDS;
+        if hint {
+            self.shown = Some(commands[..here::HINT_COMMANDS].to_vec());
+        }
+        self.seen.insert(dir.to_string(), Some(commands));
+    }
+
+    /// What the lookup found for `dir`, if it has come back.
+    pub fn cached(&self, dir: &str) -> Option<&[String]> {
+        self.seen.get(dir)?.as_deref()
+    }
+
+    /// A command was submitted; the hint has served its turn.
+    pub fn dismiss(&mut self) {
+        self.shown = None;
+    }
+
+    /// The dim line for the empty input bar, when it can show.
+    pub fn line(&self, screen: Screen) -> Option<String> {
+        here::hint_line(self.insertable(screen)?)
+    }
+
+    /// The commands Tab on the empty input cycles through.
+    pub fn insertable(&self, screen: Screen) -> Option<&[String]> {
+        self.shown.as_deref().filter(|_| self.enabled && screen.quiet())
+    }
+}
@@ -0,0 +1,92 @@
+//! Checkpoints of the unsent input line.
+//!
+//! A long command typed but not yet sent is written to the vault's
+//! `input_draft` row two seconds after the last edit, so a crash or a
+//! stray clear doesn't lose it. Short lines aren't worth a write, and a
+//! line the history would keep out (a leading space, an ignore pattern) or
+//! carrying a credential is never written at all: if a saved draft turns
+//! into one, the checkpoint is dropped instead.
+//!
+//! The keeper only decides *when* and *what*; the caller owns the clock
+//! and the vault, which keeps the timing testable with a made-up `Instant`.
+
+use std::time::{Duration, Instant};
+
+use positronic_core::history_filter::{self, HistoryFilter};
+
+/// Quiet time after the last edit before the line is checkpointed.
+pub const DRAFT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Lines shorter than this (trimmed, in characters) aren't checkpointed.
+pub const MIN_DRAFT_CHARS: usize = 20;
+
+/// Shown at startup when the previous session left a draft behind.
+pub const RESTORE_NOTICE: &str = "📝 Restore unsent draft? (press Up to load)";
+
+/// What to do with the vault's draft row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DraftWrite {
+    Save(String),
+    Clear,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DraftKeeper {
+    /// The input line as last observed.
+    text: String,
+    /// When it last changed; `None` once that change has been handled.
+    edited: Option<Instant>,
+    /// The text in the vault, if this session saved one.
+    saved: Option<String>,
+}
+
+impl DraftKeeper {
+    /// Note the input line as it is at `now`.
+    pub fn observe(&mut self, text: &str, now: Instant) {
+        if self.text != text {
+            self.text.clear();
+            self.text.push_str(text);
+            self.edited = Some(now);
+        }
+    }
+
+    /// The write due at `now`, if any: the line once it has been quiet for
+    /// `DRAFT_DEBOUNCE`, or a clear if it is no longer worth keeping.
+    /// `keep` judges the line and is only asked when a write is due.
+    pub fn poll(&mut self, now: Instant, keep: impl FnOnce(&str) -> bool) -> Option<DraftWrite> {
+        let edited = self.edited?;
+        if now.duration_since(edited) < DRAFT_DEBOUNCE {
+            return None;
+        }
+        self.edited = None;
+        if keep(&self.text) {
+            if self.saved.as_deref() == Some(self.text.as_str()) {
+                return None;
+            }
+            self.saved = Some(self.text.clone());
+            Some(DraftWrite::Save(self.text.clone()))
+        } else {
+            self.saved.take().map(|_| DraftWrite::Clear)
+        }
+    }
+
+    /// When the pending checkpoint falls due.
+    pub fn wake(&self) -> Option<Instant> {
+        self.edited.map(|at| at + DRAFT_DEBOUNCE)
+    }
+
+    /// The line was submitted or cleared: forget it, and clear the saved
+    /// draft if there is one.
+    pub fn reset(&mut self) -> Option<DraftWrite> {
+        self.text.clear();
+        self.edited = None;
+        self.saved.take().map(|_| DraftWrite::Clear)
+    }
+}
+
+/// True if `text` is long enough to checkpoint and safe to write down.
+pub fn worth_keeping(text: &str, filter: &HistoryFilter) -> bool {
+    text.trim().chars().count() >= MIN_DRAFT_CHARS
+        && !filter.check(text, None).is_private()
+        && !history_filter::has_credential(text)
+}
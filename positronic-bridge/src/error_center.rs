@@ -0,0 +1,264 @@
+//! System error toasts and the `!errors sys` list.
+//!
+//! Failures that aren't the user's own commands (the model server down,
+//! the clipboard unavailable, a vault write that didn't go through) used
+//! to print a ❌ line each time, so a dead endpoint filled the scrollback
+//! with the same line. They go through `ErrorCenter` instead: a message
+//! seen again within `DEDUPE_WINDOW` bumps a `×N` counter on the earlier
+//! entry, the newest one shows as a status bar toast for `TOAST_DURATION`,
+//! and a badge stays until `!errors sys` has listed them. A failing
+//! command's own output stays in its block and never comes here.
+//!
+//! Times are passed in, `Instant` for windows and expiry and wall-clock
+//! time for the list, so none of this reads a clock.
+
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDateTime;
+use positronic_core::error::{PositronicError, VaultErrorKind};
+
+use crate::biolink::BioLinkEvent;
+
+/// Repeats of a message within this long count against the first.
+pub const DEDUPE_WINDOW: Duration = Duration::from_secs(60);
+
+/// How long a toast stays in the status bar.
+pub const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+/// Most entries `!errors sys` keeps; the oldest go first.
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ErrorSeverity {
+    /// Worth a toast, not worth interrupting a screen reader.
+    Info,
+    Warning,
+    Error,
+}
+
+impl ErrorSeverity {
+    pub fn icon(self) -> &'static str {
+        match self {
+            ErrorSeverity::Info => "ℹ️",
+            ErrorSeverity::Warning => "⚠️",
+            ErrorSeverity::Error => "❌",
+        }
+    }
+
+    /// What BioLink announces when `message` first shows; Info stays quiet.
+    pub fn announcement(self, message: &str) -> Option<BioLinkEvent> {
+        match self {
+            ErrorSeverity::Info => None,
+            ErrorSeverity::Warning => Some(BioLinkEvent::Announcement(format!("Warning: {}", message))),
+            ErrorSeverity::Error => Some(BioLinkEvent::ErrorOccurred(message.to_string())),
+        }
+    }
+}
+
+/// How loud an engine error is: subsystems that come and go warn, the
+/// rest are errors.
+pub fn severity_of(error: &PositronicError) -> ErrorSeverity {
+    match error {
+        PositronicError::NeuralUnavailable { .. }
+        | PositronicError::HardwareError { .. }
+        | PositronicError::VaultError { kind: VaultErrorKind::Busy, .. } => ErrorSeverity::Warning,
+        _ => ErrorSeverity::Error,
+    }
+}
+
+/// One message and how often it came.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemError {
+    pub severity: ErrorSeverity,
+    pub message: String,
+    /// What to do about it, shown in `!errors sys`.
+    pub hint: Option<String>,
+    pub count: u32,
+    pub first_seen: NaiveDateTime,
+    pub last_seen: NaiveDateTime,
+    /// Listed by `!errors sys` since it last came.
+    pub acknowledged: bool,
+    last_at: Instant,
+}
+
+/// What the status bar shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorStatus {
+    /// The toast, while one is up.
+    pub toast: Option<String>,
+    pub severity: ErrorSeverity,
+    /// Entries not yet seen in `!errors sys`.
+    pub unacknowledged: usize,
+}
+
+impl ErrorStatus {
+    /// `❌ model server unreachable ×4`, or the badge `⚑ 2 errors · !errors sys`.
+    pub fn label(&self) -> String {
+        match &self.toast {
+            Some(toast) => format!("{} {}", self.severity.icon(), toast),
+            None => format!("⚑ {} · !errors sys", counted(self.unacknowledged, "error", "errors")),
+        }
+    }
+
+    /// `label` in ASCII, for the software renderer's font.
+    pub fn plain(&self) -> String {
+        match &self.toast {
+            Some(toast) => format!("! {}", toast.replace('×', "x")),
+            None => format!("! {} - !errors sys", counted(self.unacknowledged, "error", "errors")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ErrorCenter {
+    /// Oldest first.
+    entries: Vec<SystemError>,
+    /// The entry being toasted and when the toast goes.
+    toast: Option<(usize, Instant)>,
+    window: Duration,
+    toast_for: Duration,
+}
+
+impl Default for ErrorCenter {
+    fn default() -> Self {
+        Self::new(DEDUPE_WINDOW, TOAST_DURATION)
+    }
+}
+
+impl ErrorCenter {
+    pub fn new(window: Duration, toast_for: Duration) -> Self {
+        Self { entries: Vec::new(), toast: None, window, toast_for }
+    }
+
+    /// Record `message` and toast it. Returns what BioLink should announce:
+    /// nothing for a repeat within the window, or for `Info`.
+    pub fn report(
+        &mut self,
+        severity: ErrorSeverity,
+        message: &str,
+        hint: Option<String>,
+        now: Instant,
+        wall: NaiveDateTime,
+    ) -> Option<BioLinkEvent> {
+        let repeat = self.entries.iter().rposition(|e| {
+            e.severity == severity && e.message == message && now.saturating_duration_since(e.last_at) <= self.window
+        });
+        if let Some(index) = repeat {
+            let entry = &mut self.entries[index];
+            entry.count += 1;
+            entry.last_seen = wall;
+            entry.last_at = now;
+            entry.acknowledged = false;
+            if hint.is_some() {
+                entry.hint = hint;
+            }
+            self.toast = Some((index, now + self.toast_for));
+            return None;
+        }
+
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(SystemError {
+            severity,
+            message: message.to_string(),
+            hint,
+            count: 1,
+            first_seen: wall,
+            last_seen: wall,
+            acknowledged: false,
+            last_at: now,
+        });
+        self.toast = Some((self.entries.len() - 1, now + self.toast_for));
+        severity.announcement(message)
+    }
+
+    /// Drop the toast once it has been up long enough; true when the
+    /// status bar needs redrawing.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        if self.toast.is_some_and(|(_, until)| now >= until) {
+            self.toast = None;
+            return true;
+        }
+        false
+    }
+
+    /// When the toast goes, to wake for it.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.toast.map(|(_, until)| until)
+    }
+
+    /// The toast and badge, or `None` when there's nothing to show.
+    pub fn status(&self, now: Instant) -> Option<ErrorStatus> {
+        let unacknowledged = self.entries.iter().filter(|e| !e.acknowledged).count();
+        let toast = self
+            .toast
+            .filter(|(_, until)| now < *until)
+            .and_then(|(index, _)| self.entries.get(index));
+        if toast.is_none() && unacknowledged == 0 {
+            return None;
+        }
+        let severity = toast
+            .map(|e| e.severity)
+            .or_else(|| self.entries.iter().filter(|e| !e.acknowledged).map(|e| e.severity).max())
+            .unwrap_or(ErrorSeverity::Info);
+        Some(ErrorStatus { toast: toast.map(toast_text), severity, unacknowledged })
+    }
+
+    pub fn entries(&self) -> &[SystemError] {
+        &self.entries
+    }
+
+    /// Mark everything seen; the badge goes.
+    pub fn acknowledge_all(&mut self) {
+        for entry in &mut self.entries {
+            entry.acknowledged = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.toast = None;
+    }
+
+    /// The `!errors sys` listing, oldest first.
+    pub fn report_lines(&self) -> Vec<String> {
+        if self.entries.is_empty() {
+            return vec!["⚑ No system errors this session".to_string()];
+        }
+        let mut lines = vec![format!(
+            "⚑ {} this session:",
+            counted(self.entries.len(), "system error", "system errors")
+        )];
+        for entry in &self.entries {
+            let mut line = format!(
+                "  {}  {} {}",
+                entry.first_seen.format("%H:%M:%S"),
+                entry.severity.icon(),
+                entry.message
+            );
+            if entry.count > 1 {
+                line.push_str(&format!("  ×{} (last {})", entry.count, entry.last_seen.format("%H:%M:%S")));
+            }
+            lines.push(line);
+            if let Some(hint) = &entry.hint {
+                lines.push(format!("            💡 {}", hint));
+            }
+        }
+        lines.push("  !errors sys clear empties the list".to_string());
+        lines
+    }
+}
+
+/// `message ×4` once it has repeated.
+fn toast_text(entry: &SystemError) -> String {
+    if entry.count > 1 {
+        format!("{} ×{}", entry.message, entry.count)
+    } else {
+        entry.message.clone()
+    }
+}
+
+fn counted(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
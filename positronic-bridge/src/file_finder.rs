@@ -0,0 +1,352 @@
+//! Ctrl+P file finder over the project's files (no UI deps).
+//!
+//! The files come from core's `file_index`, built on the maintenance
+//! worker; the finder takes a fresh `FileSnapshot` whenever the index
+//! grows, so results fill in while a large tree is still being read.
+//! Matching is the shared `fuzzy` module's, which already favours matches
+//! just after a `/`; a path whose file name alone holds the query gets
+//! the full weight on top, so `main` ranks `src/main.rs` over
+//! `domain/tests.rs`.
+//!
+//! The open `FileFinder` takes every key. Typing edits the query, Up and
+//! Down (PageUp/PageDown) pick a file, and Enter picks it; a second key
+//! then says what to do with it: `i` (or Enter) inserts its path at the
+//! cursor, `e` opens it in the configured editor, `v` shows it with
+//! `!view`, `c` copies its path. Escape steps back: from the actions to
+//! the list, then clears the query, then closes; the `file_finder` chord
+//! closes it too.
+
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use positronic_core::alias::shell_quote;
+use positronic_core::file_index::FileSnapshot;
+
+use crate::fuzzy::{self, CaseMode, Matcher, Ranked};
+use crate::key_help::{self, PAGE_STEP};
+use crate::keymap::{Action, Chord, KeyName, Keymap, NamedKey};
+
+/// Most results listed.
+pub const MAX_SHOWN: usize = 200;
+
+/// What to do with the file picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinderAction {
+    /// Its path, relative to the working directory, at the cursor.
+    Insert,
+    /// Open it in `editor.command`, or the system opener.
+    Edit,
+    /// `!view` it.
+    View,
+    /// Its full path to the clipboard.
+    Copy,
+}
+
+impl FinderAction {
+    pub const ALL: &'static [FinderAction] =
+        &[FinderAction::Insert, FinderAction::Edit, FinderAction::View, FinderAction::Copy];
+
+    /// The second key that picks it.
+    pub fn key(self) -> char {
+        match self {
+            FinderAction::Insert => 'i',
+            FinderAction::Edit => 'e',
+            FinderAction::View => 'v',
+            FinderAction::Copy => 'c',
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<FinderAction> {
+        FinderAction::ALL.iter().copied().find(|a| key.len() == 1 && key.starts_with(a.key()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FinderAction::Insert => "insert path",
+            FinderAction::Edit => "edit",
+            FinderAction::View => "!view",
+            FinderAction::Copy => "copy path",
+        }
+    }
+}
+
+/// An action ready to carry out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Type this at the cursor.
+    Insert(String),
+    Edit(PathBuf),
+    View(PathBuf),
+    /// Put this on the clipboard.
+    Copy(String),
+}
+
+/// `action` on the root-relative `path` under `root`, with the shell in
+/// `cwd`. Inserted paths are relative to `cwd` and quoted when the shell
+/// would split them; the others are absolute.
+pub fn dispatch(action: FinderAction, root: &Path, path: &str, cwd: &Path) -> Dispatch {
+    let full = root.join(path);
+    match action {
+        FinderAction::Insert => Dispatch::Insert(quote_if_needed(&relative_to(&full, cwd))),
+        FinderAction::Edit => Dispatch::Edit(full),
+        FinderAction::View => Dispatch::View(full),
+        FinderAction::Copy => Dispatch::Copy(full.to_string_lossy().into_owned()),
+    }
+}
+
+/// `path` as reached from `base`, with `..` where it is outside it.
+pub fn relative_to(path: &Path, base: &Path) -> String {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let shared = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    if shared == 0 {
+        return path.iter().collect::<PathBuf>().to_string_lossy().into_owned();
+    }
+    let mut out: Vec<String> = base[shared..].iter().map(|_| "..".to_string()).collect();
+    out.extend(path[shared..].iter().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    match out.is_empty() {
+        true => ".".to_string(),
+        false => out.join("/"),
+    }
+}
+
+/// `path` as one shell word: as is when it is plain, else quoted.
+fn quote_if_needed(path: &str) -> String {
+    let plain = |c: char| c.is_alphanumeric() || "._-/+@,%:=".contains(c);
+    match path.chars().all(plain) {
+        true => path.to_string(),
+        false => shell_quote(path),
+    }
+}
+
+/// The file name: what follows the last `/`.
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// `paths` matching `query`, best first. Matches after a `/` score as
+/// path-segment starts, and a path whose file name holds the whole query
+/// gets the full weight on top.
+pub fn rank(query: &str, paths: &[String]) -> Vec<Ranked> {
+    let matcher = Matcher::new(query.trim(), CaseMode::Smart);
+    let weight = |i: usize| match matcher.matches(file_name(&paths[i])) {
+        true => 1.0,
+        false => 0.0,
+    };
+    fuzzy::rank(&matcher, paths, weight)
+}
+
+/// A listed file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    /// Root-relative, `/`-separated.
+    pub path: String,
+    /// Byte ranges of the matched characters, for highlighting.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// What the open finder does with a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinderOutcome {
+    Open,
+    Closed,
+    /// The query changed; the folder it names (`prefix`) is worth
+    /// reading sooner.
+    Searched,
+    /// A file and what to do with it.
+    Chosen(FinderAction, String),
+}
+
+/// The open overlay.
+#[derive(Debug, Clone)]
+pub struct FileFinder {
+    pub root: PathBuf,
+    pub query: String,
+    selected: usize,
+    /// The file picked, waiting for its action key.
+    choosing: Option<String>,
+    paths: Arc<Vec<String>>,
+    /// The index generation `paths` is from.
+    generation: Option<u64>,
+    status: String,
+    hits: Vec<Hit>,
+}
+
+impl FileFinder {
+    /// An empty finder on `root`, waiting for its first snapshot.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            query: String::new(),
+            selected: 0,
+            choosing: None,
+            paths: Arc::new(Vec::new()),
+            generation: None,
+            status: "indexing…".to_string(),
+            hits: Vec::new(),
+        }
+    }
+
+    pub fn hits(&self) -> &[Hit] {
+        &self.hits
+    }
+
+    pub fn selected(&self) -> Option<&Hit> {
+        self.hits.get(self.selected)
+    }
+
+    /// The file waiting for its action key.
+    pub fn choosing(&self) -> Option<&str> {
+        self.choosing.as_deref()
+    }
+
+    /// Take `snapshot` if the index changed since the last one; true if
+    /// the list did.
+    pub fn update(&mut self, snapshot: &FileSnapshot) -> bool {
+        let status = snapshot.status();
+        if self.generation == Some(snapshot.generation) && self.status == status {
+            return false;
+        }
+        self.generation = Some(snapshot.generation);
+        self.status = status;
+        self.paths = snapshot.paths.clone();
+        self.search();
+        true
+    }
+
+    /// The folder the query names (`crates/app/` in `crates/app/ma`), to
+    /// read before the rest.
+    pub fn prefix(&self) -> Option<&str> {
+        let (dir, _) = self.query.trim().rsplit_once('/')?;
+        (!dir.is_empty()).then_some(dir)
+    }
+
+    fn search(&mut self) {
+        let kept = self.selected().map(|h| h.path.clone());
+        self.hits = rank(&self.query, &self.paths)
+            .into_iter()
+            .take(MAX_SHOWN)
+            .map(|r| Hit { path: self.paths[r.index].clone(), ranges: r.matched.ranges })
+            .collect();
+        // The pick stays on its file while results fill in.
+        self.selected = kept.and_then(|k| self.hits.iter().position(|h| h.path == k)).unwrap_or(0);
+    }
+
+    pub fn key(&mut self, chord: Option<Chord>, text: Option<&str>, keymap: &Keymap) -> FinderOutcome {
+        if chord.as_ref().and_then(|c| keymap.action_for(c)) == Some(Action::FileFinder) {
+            return FinderOutcome::Closed;
+        }
+        let plain = chord.is_none_or(|c| !c.ctrl && !c.alt);
+        let named = chord.map(|c| c.key).filter(|_| plain);
+
+        if let Some(path) = &self.choosing {
+            let action = match (named, text) {
+                (Some(KeyName::Named(NamedKey::Escape)), _) => {
+                    self.choosing = None;
+                    return FinderOutcome::Open;
+                }
+                (Some(KeyName::Named(NamedKey::Enter)), _) => Some(FinderAction::Insert),
+                (_, Some(text)) if plain => FinderAction::from_key(&text.to_lowercase()),
+                _ => None,
+            };
+            return match action {
+                Some(action) => FinderOutcome::Chosen(action, path.clone()),
+                None => FinderOutcome::Open,
+            };
+        }
+
+        let last = self.hits.len().saturating_sub(1);
+        match named {
+            Some(KeyName::Named(NamedKey::Escape)) => {
+                if self.query.is_empty() {
+                    return FinderOutcome::Closed;
+                }
+                self.query.clear();
+                self.search();
+                return FinderOutcome::Searched;
+            }
+            Some(KeyName::Named(NamedKey::Enter)) => {
+                self.choosing = self.selected().map(|h| h.path.clone());
+                return FinderOutcome::Open;
+            }
+            Some(KeyName::Named(NamedKey::Up)) => self.selected = self.selected.saturating_sub(1),
+            Some(KeyName::Named(NamedKey::Down)) => self.selected = (self.selected + 1).min(last),
+            Some(KeyName::Named(NamedKey::PageUp)) => self.selected = self.selected.saturating_sub(PAGE_STEP),
+            Some(KeyName::Named(NamedKey::PageDown)) => self.selected = (self.selected + PAGE_STEP).min(last),
+            Some(KeyName::Named(NamedKey::Backspace)) => {
+                self.query.pop();
+                self.search();
+                return FinderOutcome::Searched;
+            }
+            _ => match text {
+                Some(text) if plain => {
+                    self.query.push_str(text);
+                    self.search();
+                    return FinderOutcome::Searched;
+                }
+                _ => {}
+            },
+        }
+        FinderOutcome::Open
+    }
+
+    /// The overlay as both frontends draw it.
+    pub fn view(&self) -> FinderView {
+        let title = format!("🔎 Files in {} — {}▏", self.root.display(), self.query);
+        let footer = match &self.choosing {
+            Some(path) => {
+                let actions: Vec<String> =
+                    FinderAction::ALL.iter().map(|a| format!("{} {}", a.key(), a.label())).collect();
+                format!("{}:  {}  ·  Esc back", path, actions.join("  ·  "))
+            }
+            None => format!("{}  ·  ↑↓ pick  ·  Enter for actions  ·  Esc closes", self.status),
+        };
+        let lines = self
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(i, hit)| FinderLine { text: hit.path.clone(), ranges: hit.ranges.clone(), lit: i == self.selected })
+            .collect();
+        FinderView { title, lines, footer, selected: self.selected }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinderLine {
+    pub text: String,
+    pub ranges: Vec<Range<usize>>,
+    /// The picked file.
+    pub lit: bool,
+}
+
+/// Everything the overlay shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinderView {
+    pub title: String,
+    pub lines: Vec<FinderLine>,
+    pub footer: String,
+    selected: usize,
+}
+
+impl FinderView {
+    /// The first line to show when `rows` fit between title and footer.
+    pub fn first(&self, rows: usize) -> usize {
+        key_help::window(self.lines.len(), rows, None, Some(self.selected))
+    }
+
+    /// Title, the lines that fit and the footer, in `rows` text rows: the
+    /// software renderer's screen.
+    pub fn plain(&self, rows: usize) -> Vec<String> {
+        let body = rows.saturating_sub(2);
+        let first = self.first(body);
+        let mut out = vec![self.title.clone()];
+        out.extend(self.lines.iter().skip(first).take(body).map(|l| match l.lit {
+            true => format!("> {}", l.text),
+            false => format!("  {}", l.text),
+        }));
+        out.resize(rows.saturating_sub(1).max(1), String::new());
+        out.push(self.footer.clone());
+        out
+    }
+}
@@ -0,0 +1,348 @@
+//! `!follow` pane state: buffering, pause and filters (no UI deps).
+//!
+//! The followed command runs as a `positronic_core::follow::FollowProcess`;
+//! each line it prints is classified with `BlockLine::classify` and kept
+//! in a ring of at most `MAX_LINES`, the oldest going first and counted
+//! as dropped. Space pauses: the view stays on the lines that had arrived,
+//! new ones keep buffering and a badge counts them. `e`, `w` and `i` show
+//! only errors, warnings and up, or info and up; pressing the same key
+//! again shows everything. `/` opens a regex prompt that filters as it is
+//! typed (an invalid pattern keeps the last good one). Filters apply to
+//! the whole buffer, so lines that arrive later are filtered too.
+//!
+//! The interrupt chord stops the command; once it has exited, the chord,
+//! Escape or `q` close the pane.
+
+use std::collections::VecDeque;
+
+use positronic_core::usage::ResourceUsage;
+use regex::Regex;
+
+use crate::block::{BlockLine, LineKind};
+use crate::keymap::{Action, Chord, KeyName, Keymap, NamedKey};
+
+/// Lines kept before the oldest are dropped.
+pub const MAX_LINES: usize = 10_000;
+
+/// How serious a line is, for the `e`/`w`/`i` filters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Lines of other kinds (plain, success, muted) have none.
+    pub fn of(kind: LineKind) -> Option<Severity> {
+        match kind {
+            LineKind::Error => Some(Severity::Error),
+            LineKind::Warning => Some(Severity::Warning),
+            LineKind::Info => Some(Severity::Info),
+            LineKind::Normal | LineKind::Success | LineKind::Muted => None,
+        }
+    }
+
+    /// The filter key: `e`, `w` or `i`.
+    pub fn from_key(key: &str) -> Option<Severity> {
+        match key {
+            "e" => Some(Severity::Error),
+            "w" => Some(Severity::Warning),
+            "i" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "errors",
+            Severity::Warning => "warnings+",
+            Severity::Info => "info+",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowPhase {
+    Running,
+    /// Asked to stop, not exited yet.
+    Stopping,
+    /// The exit code; `None` if a signal ended it.
+    Exited(Option<i32>),
+}
+
+/// What a key means to the pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowKey {
+    /// Stop the command; the pane stays open.
+    Stop,
+    /// Stop the command if it still runs and close the pane.
+    Close,
+    Handled,
+    Ignored,
+}
+
+/// The regex prompt while it is open.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FollowPrompt {
+    pub query: String,
+    /// Why the query doesn't compile, if it doesn't.
+    pub error: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct FollowState {
+    pub command: String,
+    pub phase: FollowPhase,
+    lines: VecDeque<BlockLine>,
+    capacity: usize,
+    /// Lines received, dropped ones included.
+    received: u64,
+    dropped: u64,
+    /// `received` when the view was paused.
+    paused_at: Option<u64>,
+    severity: Option<Severity>,
+    filter: Option<Regex>,
+    pub prompt: Option<FollowPrompt>,
+    /// CPU time and peak memory, once the command has exited.
+    pub usage: Option<ResourceUsage>,
+}
+
+impl FollowState {
+    pub fn new(command: &str) -> Self {
+        Self::with_capacity(command, MAX_LINES)
+    }
+
+    pub fn with_capacity(command: &str, capacity: usize) -> Self {
+        Self {
+            command: command.to_string(),
+            phase: FollowPhase::Running,
+            lines: VecDeque::new(),
+            capacity: capacity.max(1),
+            received: 0,
+            dropped: 0,
+            paused_at: None,
+            severity: None,
+            filter: None,
+            prompt: None,
+            usage: None,
+        }
+    }
+
+    /// A line from the command.
+    pub fn push(&mut self, text: &str) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(BlockLine::classify(text));
+        self.received += 1;
+    }
+
+    /// The command has exited.
+    pub fn exited(&mut self, code: Option<i32>) {
+        self.phase = FollowPhase::Exited(code);
+    }
+
+    pub fn is_running(&self) -> bool {
+        !matches!(self.phase, FollowPhase::Exited(_))
+    }
+
+    /// Lines in the buffer.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Lines evicted to stay within the buffer.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Freeze or unfreeze the view.
+    pub fn toggle_pause(&mut self) {
+        self.paused_at = match self.paused_at {
+            Some(_) => None,
+            None => Some(self.received),
+        };
+    }
+
+    /// Lines that arrived since the pause.
+    pub fn pending(&self) -> u64 {
+        self.paused_at.map_or(0, |at| self.received - at)
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    /// Show only lines at least as serious as `severity`; the same one
+    /// again shows everything.
+    pub fn toggle_severity(&mut self, severity: Severity) {
+        self.severity = if self.severity == Some(severity) { None } else { Some(severity) };
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_ref().map(Regex::as_str)
+    }
+
+    /// Filter by `pattern`; an empty one clears the filter.
+    pub fn set_filter(&mut self, pattern: &str) -> Result<(), String> {
+        if pattern.is_empty() {
+            self.filter = None;
+            return Ok(());
+        }
+        self.filter = Some(Regex::new(pattern).map_err(|e| e.to_string())?);
+        Ok(())
+    }
+
+    fn shows(&self, line: &BlockLine) -> bool {
+        let severe = match self.severity {
+            Some(min) => Severity::of(line.kind).is_some_and(|s| s >= min),
+            None => true,
+        };
+        severe && self.filter.as_ref().is_none_or(|re| re.is_match(&line.text))
+    }
+
+    /// The last `rows` lines that pass the filters, oldest first; while
+    /// paused, only lines that had arrived at the pause.
+    pub fn visible(&self, rows: usize) -> Vec<&BlockLine> {
+        let first = self.received - self.lines.len() as u64;
+        let end = match self.paused_at {
+            Some(at) => at.saturating_sub(first).min(self.lines.len() as u64) as usize,
+            None => self.lines.len(),
+        };
+        let mut shown: Vec<&BlockLine> =
+            self.lines.range(..end).rev().filter(|l| self.shows(l)).take(rows).collect();
+        shown.reverse();
+        shown
+    }
+
+    /// `⏸ paused, +218 lines` while paused.
+    pub fn badge(&self) -> Option<String> {
+        self.is_paused().then(|| format!("⏸ paused, +{} lines", self.pending()))
+    }
+
+    /// `📜 tail -f app.log · running · 1200 lines · 3 dropped · errors · /timeout/`,
+    /// with `cpu 1.2s · peak 30.0 MB` after the line count once it exited.
+    pub fn header(&self) -> String {
+        let phase = match self.phase {
+            FollowPhase::Running => "running".to_string(),
+            FollowPhase::Stopping => "stopping…".to_string(),
+            FollowPhase::Exited(Some(code)) => format!("exited {}", code),
+            FollowPhase::Exited(None) => "stopped".to_string(),
+        };
+        let mut header = format!("📜 {} · {} · {} lines", self.command, phase, self.lines.len());
+        if let Some(usage) = self.usage {
+            header.push_str(&format!(" · {}", usage.badge()));
+        }
+        if self.dropped > 0 {
+            header.push_str(&format!(" · {} dropped", self.dropped));
+        }
+        if let Some(severity) = self.severity {
+            header.push_str(&format!(" · {}", severity.label()));
+        }
+        if let Some(filter) = self.filter() {
+            header.push_str(&format!(" · /{}/", filter));
+        }
+        header
+    }
+
+    /// The key help, or the regex prompt while it is open.
+    pub fn footer(&self, interrupt: Option<&Chord>) -> String {
+        if let Some(prompt) = &self.prompt {
+            let mut line = format!("/{}", prompt.query);
+            if let Some(error) = &prompt.error {
+                line.push_str(&format!("  (invalid: {})", error.lines().last().unwrap_or(error)));
+            }
+            line.push_str("  [Enter keep · Esc clear]");
+            return line;
+        }
+        let interrupt = interrupt.map_or("the interrupt chord".to_string(), |c| c.to_string());
+        let stop = if self.is_running() { "stops" } else { "closes" };
+        format!("Space pause · e/w/i severity · / regex · {} {} · q closes", interrupt, stop)
+    }
+
+    /// Route a key. `chord` is the keymap's view of it, `text` what it
+    /// types. The interrupt chord stops a running command and otherwise
+    /// closes; while the prompt is open, keys edit the pattern.
+    pub fn key(&mut self, chord: Option<Chord>, text: Option<&str>, keymap: &Keymap) -> FollowKey {
+        if chord.as_ref().and_then(|c| keymap.action_for(c)) == Some(Action::Interrupt) {
+            return match self.phase {
+                FollowPhase::Running => FollowKey::Stop,
+                FollowPhase::Stopping => FollowKey::Handled,
+                FollowPhase::Exited(_) => FollowKey::Close,
+            };
+        }
+        let named = match chord.map(|c| c.key) {
+            Some(KeyName::Named(named)) => Some(named),
+            _ => None,
+        };
+        let typing = chord.is_none_or(|c| !c.ctrl && !c.alt);
+
+        if let Some(prompt) = &mut self.prompt {
+            match named {
+                Some(NamedKey::Escape) => {
+                    self.prompt = None;
+                    self.filter = None;
+                }
+                Some(NamedKey::Enter) => self.prompt = None,
+                Some(NamedKey::Backspace) => {
+                    prompt.query.pop();
+                    self.apply_prompt();
+                }
+                _ => match text {
+                    Some(text) if typing => {
+                        prompt.query.push_str(text);
+                        self.apply_prompt();
+                    }
+                    _ => return FollowKey::Ignored,
+                },
+            }
+            return FollowKey::Handled;
+        }
+
+        if named == Some(NamedKey::Escape) {
+            return FollowKey::Close;
+        }
+        if !typing {
+            return FollowKey::Ignored;
+        }
+        match text {
+            Some(" ") => self.toggle_pause(),
+            Some("/") => {
+                let query = self.filter().unwrap_or_default().to_string();
+                self.prompt = Some(FollowPrompt { query, error: None });
+            }
+            Some("q") => return FollowKey::Close,
+            Some(key) => match Severity::from_key(key) {
+                Some(severity) => self.toggle_severity(severity),
+                None => return FollowKey::Ignored,
+            },
+            None => return FollowKey::Ignored,
+        }
+        FollowKey::Handled
+    }
+
+    /// Filter by the prompt's query if it compiles.
+    fn apply_prompt(&mut self) {
+        let Some(query) = self.prompt.as_ref().map(|p| p.query.clone()) else {
+            return;
+        };
+        let error = self.set_filter(&query).err();
+        if let Some(prompt) = &mut self.prompt {
+            prompt.error = error;
+        }
+    }
+}
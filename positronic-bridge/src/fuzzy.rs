@@ -0,0 +1,315 @@
+//! Fuzzy matching shared by completion, the palette and history search.
+//!
+//! A query matches a candidate when its characters appear in the
+//! candidate in order (`gco` matches `git checkout`). Among the ways they
+//! can line up, the best-scoring one is kept: each matched character
+//! earns `SCORE_MATCH`, more at the start of a word or path component or
+//! in a consecutive run, and gaps between matches cost a little. Shorter
+//! candidates edge out longer ones with the same match, and a caller's
+//! own weight (frecency, say) can be blended in with `score_weighted`.
+//!
+//! Matching is case-insensitive, case-sensitive, or smart-case: ignoring
+//! case unless the query has a capital in it.
+//!
+//! `rank` checks each candidate for the query's characters in order
+//! before scoring it, so a large list costs little more than a scan.
+
+use std::ops::Range;
+
+/// Every matched character.
+const SCORE_MATCH: i32 = 16;
+/// A match at the start of the candidate or of a word.
+const BONUS_BOUNDARY: i32 = 8;
+/// A match just after `/` or `\`.
+const BONUS_PATH: i32 = 10;
+/// A capital after a lowercase letter (`camelCase`).
+const BONUS_CAMEL: i32 = 7;
+/// The least a match right after the previous one earns.
+const BONUS_CONSECUTIVE: i32 = 6;
+/// The first query character's bonus counts this many times.
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// Opening a gap between matches, and each further character of it.
+const GAP_START: i32 = -3;
+const GAP_EXTENSION: i32 = -1;
+/// One point off per this many characters of candidate.
+const LENGTH_DIVISOR: i32 = 4;
+/// What a weight of 1.0 adds: two matched characters' worth.
+pub const WEIGHT_BONUS: i32 = 2 * SCORE_MATCH;
+
+const UNMATCHED: i32 = i32::MIN / 2;
+
+/// How letter case is compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Sensitive,
+    Insensitive,
+    /// Insensitive unless the query has an uppercase letter.
+    Smart,
+}
+
+/// A candidate that matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Byte ranges of the matched characters in the candidate, in order,
+    /// adjacent ones merged; for highlighting.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A matched candidate from `rank`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranked {
+    /// Position in the list given to `rank`.
+    pub index: usize,
+    pub matched: FuzzyMatch,
+}
+
+/// A query ready to match against many candidates.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// Lowercased when case is ignored.
+    query: Vec<char>,
+    ignore_case: bool,
+    /// The query as bytes when it is ASCII, for the fast pre-filter.
+    ascii: Option<Vec<u8>>,
+}
+
+/// Buffers reused across candidates.
+#[derive(Debug, Default)]
+struct Scratch {
+    chars: Vec<(usize, char)>,
+    /// `chars` as compared with the query.
+    folded: Vec<char>,
+    bonus: Vec<i32>,
+    /// The first and last candidate char each query char can match at.
+    window: Vec<(usize, usize)>,
+    /// Best score with query char `i` matched at candidate char `j`,
+    /// row-major by `i`.
+    score: Vec<i32>,
+    /// Where query char `i - 1` was matched for that score.
+    from: Vec<usize>,
+}
+
+impl Matcher {
+    pub fn new(query: &str, mode: CaseMode) -> Self {
+        let ignore_case = match mode {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => !query.chars().any(char::is_uppercase),
+        };
+        let query: Vec<char> = query.chars().map(|c| if ignore_case { fold(c) } else { c }).collect();
+        let ascii = query.iter().all(char::is_ascii).then(|| query.iter().map(|&c| c as u8).collect());
+        Self { query, ignore_case, ascii }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    pub fn ignores_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Whether the query's characters appear in `candidate` in order.
+    /// Cheap; `score` is only worth calling when this holds.
+    pub fn matches(&self, candidate: &str) -> bool {
+        if let Some(query) = &self.ascii
+            && (!self.ignore_case || candidate.is_ascii())
+        {
+            // ASCII bytes never occur inside a multi-byte character, so
+            // bytes compare like chars here.
+            let mut rest = candidate.as_bytes();
+            for &q in query {
+                let found = if self.ignore_case {
+                    rest.iter().position(|b| b.to_ascii_lowercase() == q)
+                } else {
+                    rest.iter().position(|&b| b == q)
+                };
+                match found {
+                    Some(at) => rest = &rest[at + 1..],
+                    None => return false,
+                }
+            }
+            return true;
+        }
+        let mut rest = self.query.iter().peekable();
+        for c in candidate.chars() {
+            let c = if self.ignore_case { fold(c) } else { c };
+            if rest.next_if(|&&q| q == c).is_some() && rest.peek().is_none() {
+                return true;
+            }
+        }
+        rest.peek().is_none()
+    }
+
+    /// The best way the query lines up with `candidate`, or `None` when it
+    /// doesn't match. An empty query matches everything with score 0.
+    pub fn score(&self, candidate: &str) -> Option<FuzzyMatch> {
+        self.score_with(candidate, &mut Scratch::default())
+    }
+
+    /// `score` with `weight` blended in: 0.0 adds nothing, 1.0 adds
+    /// `WEIGHT_BONUS`. Out-of-range weights are clamped.
+    pub fn score_weighted(&self, candidate: &str, weight: f32) -> Option<FuzzyMatch> {
+        self.score(candidate).map(|m| with_weight(m, weight))
+    }
+
+    fn score_with(&self, candidate: &str, scratch: &mut Scratch) -> Option<FuzzyMatch> {
+        if self.query.is_empty() {
+            return Some(FuzzyMatch { score: 0, ranges: Vec::new() });
+        }
+        if !self.matches(candidate) {
+            return None;
+        }
+
+        let Scratch { chars, folded, bonus, window, score, from } = scratch;
+        chars.clear();
+        chars.extend(candidate.char_indices());
+        folded.clear();
+        folded.extend(chars.iter().map(|&(_, c)| if self.ignore_case { fold(c) } else { c }));
+        let (m, n) = (self.query.len(), chars.len());
+        bonus.clear();
+        let mut prev = None;
+        for &(_, c) in chars.iter() {
+            bonus.push(bonus_for(prev, c));
+            prev = Some(c);
+        }
+
+        // The earliest and latest places each query char can go, from
+        // matching greedily from each end; nothing outside is scored.
+        window.clear();
+        let mut j = 0;
+        for &q in &self.query {
+            while folded[j] != q {
+                j += 1;
+            }
+            window.push((j, 0));
+            j += 1;
+        }
+        let mut end = n;
+        for (i, &q) in self.query.iter().enumerate().rev() {
+            end -= 1;
+            while folded[end] != q {
+                end -= 1;
+            }
+            window[i].1 = end;
+        }
+
+        score.clear();
+        score.resize(m * n, UNMATCHED);
+        // Only read back along the best path, all of it written below.
+        if from.len() < m * n {
+            from.resize(m * n, usize::MAX);
+        }
+
+        for i in 0..m {
+            let q = self.query[i];
+            // Best score for query char `i - 1` at least two back, with the
+            // gap since then paid for.
+            let mut gap = UNMATCHED;
+            let mut gap_from = usize::MAX;
+            let start = if i == 0 { window[0].0 } else { window[i - 1].0 + 1 };
+            for j in start..=window[i].1 {
+                if i > 0 && j >= 2 {
+                    if gap > UNMATCHED {
+                        gap += GAP_EXTENSION;
+                    }
+                    let entering = score[(i - 1) * n + j - 2];
+                    if entering > UNMATCHED && entering + GAP_START >= gap {
+                        gap = entering + GAP_START;
+                        gap_from = j - 2;
+                    }
+                }
+                if folded[j] != q {
+                    continue;
+                }
+                let cell = i * n + j;
+                if i == 0 {
+                    score[cell] = SCORE_MATCH + bonus[j] * FIRST_CHAR_MULTIPLIER;
+                    continue;
+                }
+                let run = score[(i - 1) * n + j - 1];
+                let consecutive = (run > UNMATCHED).then(|| run + SCORE_MATCH + bonus[j].max(BONUS_CONSECUTIVE));
+                let gapped = (gap > UNMATCHED).then(|| gap + SCORE_MATCH + bonus[j]);
+                match (consecutive, gapped) {
+                    (Some(a), Some(b)) if b > a => (score[cell], from[cell]) = (b, gap_from),
+                    (Some(a), _) => (score[cell], from[cell]) = (a, j - 1),
+                    (None, Some(b)) => (score[cell], from[cell]) = (b, gap_from),
+                    (None, None) => {}
+                }
+            }
+        }
+
+        let last = (m - 1) * n;
+        let (end, best) = (m - 1..n)
+            .map(|j| (j, score[last + j]))
+            .filter(|&(_, s)| s > UNMATCHED)
+            .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+        let mut positions = vec![end; m];
+        for i in (1..m).rev() {
+            positions[i - 1] = from[i * n + positions[i]];
+        }
+        let mut ranges: Vec<Range<usize>> = Vec::with_capacity(m);
+        for &j in &positions {
+            let (start, c) = chars[j];
+            let range = start..start + c.len_utf8();
+            match ranges.last_mut() {
+                Some(last) if last.end == range.start => last.end = range.end,
+                _ => ranges.push(range),
+            }
+        }
+        Some(FuzzyMatch { score: best - n as i32 / LENGTH_DIVISOR, ranges })
+    }
+}
+
+/// The candidates `matcher` matches, best first: by score, then the
+/// shorter candidate, then list order. `weight` gives each candidate's
+/// weight by index, as for `score_weighted`.
+pub fn rank<S: AsRef<str>>(matcher: &Matcher, candidates: &[S], weight: impl Fn(usize) -> f32) -> Vec<Ranked> {
+    let mut scratch = Scratch::default();
+    let mut ranked: Vec<Ranked> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let matched = matcher.score_with(candidate.as_ref(), &mut scratch)?;
+            Some(Ranked { index, matched: with_weight(matched, weight(index)) })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.matched
+            .score
+            .cmp(&a.matched.score)
+            .then_with(|| candidates[a.index].as_ref().len().cmp(&candidates[b.index].as_ref().len()))
+            .then_with(|| a.index.cmp(&b.index))
+    });
+    ranked
+}
+
+fn with_weight(mut matched: FuzzyMatch, weight: f32) -> FuzzyMatch {
+    let weight = if weight.is_finite() { weight.clamp(0.0, 1.0) } else { 0.0 };
+    matched.score += (weight * WEIGHT_BONUS as f32).round() as i32;
+    matched
+}
+
+/// What matching `c`, after `prev`, is worth beyond `SCORE_MATCH`.
+fn bonus_for(prev: Option<char>, c: char) -> i32 {
+    match prev {
+        None => BONUS_BOUNDARY,
+        Some('/' | '\\') => BONUS_PATH,
+        Some(p) if p.is_whitespace() || matches!(p, '-' | '_' | '.' | ':' | ',' | ';' | '=' | '@') => {
+            BONUS_BOUNDARY
+        }
+        Some(p) if p.is_lowercase() && c.is_uppercase() => BONUS_CAMEL,
+        Some(p) if !p.is_alphanumeric() && c.is_alphanumeric() => BONUS_BOUNDARY,
+        _ => 0,
+    }
+}
+
+fn fold(c: char) -> char {
+    if c.is_ascii() {
+        c.to_ascii_lowercase()
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
@@ -3,13 +3,17 @@
 //! Manages the wgpu device, surface, and rendering pipelines.
 //! Sub-modules:
 //!   renderer — wgpu device/surface lifecycle, frame orchestration
-//!   quad     — colored rectangle pipeline (backgrounds, cursor, selection)
+//!   quad     — instanced colored rectangle pipeline (backgrounds, cursor, selection)
 //!   text     — glyphon-based text rendering
+//!   software — softbuffer presenter for when no GPU can be opened
 
 mod quad;
 mod renderer;
+mod software;
 pub(crate) mod text;
 
-pub use quad::{QuadInstance, QuadPipeline};
+pub use crate::quad_batch::{QuadInstance, QuadLayer, QuadStats};
+pub use quad::QuadPipeline;
 pub use renderer::GpuState;
+pub use software::SoftwareRenderer;
 pub use text::TextEngine;
\ No newline at end of file
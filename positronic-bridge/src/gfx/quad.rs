@@ -2,37 +2,61 @@
 //! Colored rectangle pipeline.
 //!
 //! Draws filled quads (backgrounds, cursor, selection, status bar, etc.)
-//! using the shapes.wgsl vertex/fragment shader. Each quad is two triangles
-//! with per-vertex color.
+//! using the shapes.wgsl vertex/fragment shader. All quads go out in a
+//! single instanced draw call from a persistent instance buffer, which is
+//! rewritten only when the batch (see `quad_batch`) actually changes and
+//! regrown with headroom when it outgrows its capacity.
 
 use wgpu::{Buffer, BufferUsages, Device, Queue, RenderPass, RenderPipeline, TextureFormat};
 
-use crate::renderer::Rgba;
+use crate::quad_batch::{QuadBatch, QuadInstance, QuadStats};
 
 // ════════════════════════════════════════════════════════════════════
-// Vertex Layout
+// Instance Layout
 // ════════════════════════════════════════════════════════════════════
 
+/// One quad as uploaded: the vertex shader expands it into two triangles.
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct QuadVertex {
-    pos: [f32; 2],
+struct QuadRaw {
+    rect: [f32; 4],
     color: [f32; 4],
 }
 
-impl QuadVertex {
+impl QuadRaw {
     const ATTRIBS: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x4];
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4];
 
     fn layout() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
+            step_mode: wgpu::VertexStepMode::Instance,
             attributes: &Self::ATTRIBS,
         }
     }
 }
 
+impl From<&QuadInstance> for QuadRaw {
+    fn from(q: &QuadInstance) -> Self {
+        Self {
+            rect: [q.x, q.y, q.w, q.h],
+            color: [q.color.r, q.color.g, q.color.b, q.color.a],
+        }
+    }
+}
+
+/// Smallest instance buffer ever allocated.
+const MIN_CAPACITY: usize = 256;
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("quad-instances"),
+        size: (capacity * std::mem::size_of::<QuadRaw>()) as u64,
+        usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
 // ════════════════════════════════════════════════════════════════════
 // Globals Uniform
 // ════════════════════════════════════════════════════════════════════
@@ -44,20 +68,6 @@ struct Globals {
     _pad: [f32; 2],
 }
 
-// ════════════════════════════════════════════════════════════════════
-// Quad Instance (public API)
-// ════════════════════════════════════════════════════════════════════
-
-/// A single colored rectangle to draw. Coordinates in pixels.
-#[derive(Debug, Clone, Copy)]
-pub struct QuadInstance {
-    pub x: f32,
-    pub y: f32,
-    pub w: f32,
-    pub h: f32,
-    pub color: Rgba,
-}
-
 // ════════════════════════════════════════════════════════════════════
 // Pipeline
 // ════════════════════════════════════════════════════════════════════
@@ -66,7 +76,13 @@ pub struct QuadPipeline {
     pipeline: RenderPipeline,
     globals_buffer: Buffer,
     globals_bind_group: wgpu::BindGroup,
-    vertices: Vec<QuadVertex>,
+    viewport: [u32; 2],
+
+    batch: QuadBatch,
+    instance_buffer: Buffer,
+    capacity: usize,
+    staging: Vec<QuadRaw>,
+    stats: QuadStats,
 }
 
 impl QuadPipeline {
@@ -121,7 +137,7 @@ impl QuadPipeline {
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: Some("vs_main"),
-                buffers: &[QuadVertex::layout()],
+                buffers: &[QuadRaw::layout()],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
@@ -148,71 +164,84 @@ impl QuadPipeline {
             pipeline,
             globals_buffer,
             globals_bind_group,
-            vertices: Vec::new(),
+            viewport: [0, 0],
+            batch: QuadBatch::new(),
+            instance_buffer: create_instance_buffer(device, MIN_CAPACITY),
+            capacity: MIN_CAPACITY,
+            staging: Vec::new(),
+            stats: QuadStats {
+                capacity: MIN_CAPACITY,
+                ..Default::default()
+            },
         }
     }
 
     /// Queue a colored rectangle for this frame.
     pub fn push(&mut self, quad: QuadInstance) {
-        let c = [quad.color.r, quad.color.g, quad.color.b, quad.color.a];
-        let x0 = quad.x;
-        let y0 = quad.y;
-        let x1 = quad.x + quad.w;
-        let y1 = quad.y + quad.h;
-
-        // Two triangles per quad
-        self.vertices
-            .push(QuadVertex { pos: [x0, y0], color: c });
-        self.vertices
-            .push(QuadVertex { pos: [x1, y0], color: c });
-        self.vertices
-            .push(QuadVertex { pos: [x0, y1], color: c });
-
-        self.vertices
-            .push(QuadVertex { pos: [x1, y0], color: c });
-        self.vertices
-            .push(QuadVertex { pos: [x1, y1], color: c });
-        self.vertices
-            .push(QuadVertex { pos: [x0, y1], color: c });
+        self.batch.push(quad);
     }
 
     /// Clear all queued quads for the next frame.
     pub fn clear(&mut self) {
-        self.vertices.clear();
+        self.batch.clear();
+    }
+
+    /// Counters from the most recent `render`.
+    pub fn stats(&self) -> QuadStats {
+        self.stats
     }
 
-    /// Render all queued quads in the given render pass.
+    /// Upload the batch if it changed, then draw every quad in one call.
     pub fn render(
-        &self,
+        &mut self,
         pass: &mut RenderPass<'_>,
         device: &Device,
         queue: &Queue,
         viewport: [u32; 2],
     ) {
-        if self.vertices.is_empty() {
-            return;
+        if viewport != self.viewport {
+            let globals = Globals {
+                resolution: [viewport[0] as f32, viewport[1] as f32],
+                _pad: [0.0; 2],
+            };
+            queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
+            self.viewport = viewport;
         }
 
-        // Update globals uniform
-        let globals = Globals {
-            resolution: [viewport[0] as f32, viewport[1] as f32],
-            _pad: [0.0; 2],
-        };
-        queue.write_buffer(&self.globals_buffer, 0, bytemuck::bytes_of(&globals));
-
-        // Create vertex buffer from current frame data
-        let vb = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("quad-vb"),
-            contents: bytemuck::cast_slice(&self.vertices),
-            usage: BufferUsages::VERTEX,
-        });
+        let mut changed = self.batch.prepare();
+        let count = self.batch.instances().len();
+
+        // Grow with headroom; a fresh buffer always needs a full write.
+        if count > self.capacity {
+            self.capacity = (count + count / 2).max(MIN_CAPACITY);
+            self.instance_buffer = create_instance_buffer(device, self.capacity);
+            changed = true;
+        }
 
+        if !changed {
+            self.stats.writes_skipped += 1;
+        } else if count > 0 {
+            self.staging.clear();
+            self.staging
+                .extend(self.batch.instances().iter().map(QuadRaw::from));
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&self.staging));
+            self.stats.buffer_writes += 1;
+        }
+
+        self.stats.submitted = self.batch.submitted();
+        self.stats.drawn = count;
+        self.stats.capacity = self.capacity;
+        self.stats.draw_calls = 0;
+
+        if count == 0 {
+            return;
+        }
+
+        let bytes = (count * std::mem::size_of::<QuadRaw>()) as u64;
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, &self.globals_bind_group, &[]);
-        pass.set_vertex_buffer(0, vb.slice(..));
-        pass.draw(0..self.vertices.len() as u32, 0..1);
+        pass.set_vertex_buffer(0, self.instance_buffer.slice(..bytes));
+        pass.draw(0..6, 0..count as u32);
+        self.stats.draw_calls = 1;
     }
 }
-
-// Re-export for buffer init
-use wgpu::util::DeviceExt;
\ No newline at end of file
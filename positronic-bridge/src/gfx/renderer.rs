@@ -3,10 +3,13 @@
 //!
 //! GpuState owns the device, queue, surface, and config. It creates the
 //! swapchain texture each frame and hands it to the quad + text pipelines.
+//! Each one is opened for a single `render_path::Attempt`, so the app can
+//! walk the fallback plan and reopen after a lost device.
 
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use wgpu::{
-    Backends, Device, DeviceDescriptor, Instance, InstanceDescriptor, PowerPreference, Queue,
+    Backends, CompositeAlphaMode, Device, DeviceDescriptor, Instance, InstanceDescriptor, PowerPreference, Queue,
     RequestAdapterOptions, Surface, SurfaceConfiguration, TextureFormat, TextureUsages,
     TextureViewDescriptor,
 };
@@ -15,6 +18,7 @@ use winit::window::Window;
 
 use super::quad::QuadPipeline;
 use super::text::TextEngine;
+use crate::render_path::{AdapterInfo, AdapterKind, Attempt, Backend};
 use crate::renderer::Rgba;
 
 /// Owns all GPU state. Created once per window.
@@ -25,6 +29,14 @@ pub struct GpuState {
     pub config: SurfaceConfiguration,
     pub size: PhysicalSize<u32>,
     pub format: TextureFormat,
+    /// Alpha modes the surface supports, for `set_opacity`.
+    alpha_modes: Vec<CompositeAlphaMode>,
+    /// Background opacity (`window.opacity`); 1.0 keeps the surface opaque.
+    opacity: f32,
+    pub adapter: AdapterInfo,
+    /// Set by wgpu's device-lost callback, or by an out-of-memory frame.
+    lost: Arc<AtomicBool>,
+    lost_reason: Arc<Mutex<String>>,
 
     // Pipelines
     pub quads: QuadPipeline,
@@ -32,14 +44,15 @@ pub struct GpuState {
 }
 
 impl GpuState {
-    /// Initialize wgpu with the given window. Blocks until the adapter is ready.
-    pub fn new(window: Arc<dyn Window>) -> anyhow::Result<Self> {
+    /// Initialize wgpu for `attempt`: only its backend, and a CPU adapter
+    /// when `attempt.cpu`. Blocks until the device is ready.
+    pub fn new(window: Arc<dyn Window>, attempt: Attempt) -> anyhow::Result<Self> {
         let size = window.surface_size();
         let width = size.width.max(1);
         let height = size.height.max(1);
 
         let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::all(),
+            backends: backends(attempt.backend),
             ..Default::default()
         });
 
@@ -49,14 +62,22 @@ impl GpuState {
         let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
             power_preference: PowerPreference::HighPerformance,
             compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
+            force_fallback_adapter: attempt.cpu,
         }))?;
 
-        tracing::info!(
-            "GPU adapter: {} ({:?})",
-            adapter.get_info().name,
-            adapter.get_info().backend
-        );
+        let info = adapter.get_info();
+        tracing::info!("GPU adapter: {} ({:?}, {:?})", info.name, info.backend, info.device_type);
+        let adapter_info = AdapterInfo {
+            name: info.name.clone(),
+            backend: attempt.backend,
+            kind: match info.device_type {
+                wgpu::DeviceType::DiscreteGpu => AdapterKind::Discrete,
+                wgpu::DeviceType::IntegratedGpu => AdapterKind::Integrated,
+                wgpu::DeviceType::VirtualGpu => AdapterKind::Virtual,
+                wgpu::DeviceType::Cpu => AdapterKind::Cpu,
+                wgpu::DeviceType::Other => AdapterKind::Other,
+            },
+        };
 
         let (device, queue) = pollster::block_on(adapter.request_device(
             &DeviceDescriptor {
@@ -88,6 +109,19 @@ impl GpuState {
 
         surface.configure(&device, &config);
 
+        let lost = Arc::new(AtomicBool::new(false));
+        let lost_reason = Arc::new(Mutex::new(String::new()));
+        {
+            let (lost, lost_reason) = (lost.clone(), lost_reason.clone());
+            device.set_device_lost_callback(move |reason, message| {
+                tracing::error!("GPU device lost ({:?}): {}", reason, message);
+                if let Ok(mut r) = lost_reason.lock() {
+                    *r = if message.is_empty() { format!("{:?}", reason) } else { message };
+                }
+                lost.store(true, Ordering::SeqCst);
+            });
+        }
+
         let quads = QuadPipeline::new(&device, format);
         let text = TextEngine::new(&device, &queue, format);
 
@@ -98,11 +132,60 @@ impl GpuState {
             config,
             size: PhysicalSize::new(width, height),
             format,
+            alpha_modes: surface_caps.alpha_modes,
+            opacity: 1.0,
+            adapter: adapter_info,
+            lost,
+            lost_reason,
             quads,
             text,
         })
     }
 
+    /// Set the background opacity. Below 1.0 the surface switches to an
+    /// alpha mode that blends with the desktop; `false` if it has none,
+    /// in which case it stays opaque.
+    pub fn set_opacity(&mut self, opacity: f32) -> bool {
+        let blending = [CompositeAlphaMode::PreMultiplied, CompositeAlphaMode::PostMultiplied]
+            .into_iter()
+            .find(|mode| self.alpha_modes.contains(mode));
+        let (opacity, alpha_mode, supported) = match blending {
+            _ if opacity >= 1.0 => (1.0, self.opaque_mode(), true),
+            Some(mode) => (opacity, mode, true),
+            None => (1.0, self.opaque_mode(), false),
+        };
+        self.opacity = opacity;
+        if self.config.alpha_mode != alpha_mode {
+            self.config.alpha_mode = alpha_mode;
+            self.surface.configure(&self.device, &self.config);
+        }
+        supported
+    }
+
+    fn opaque_mode(&self) -> CompositeAlphaMode {
+        if self.alpha_modes.contains(&CompositeAlphaMode::Opaque) {
+            CompositeAlphaMode::Opaque
+        } else {
+            self.alpha_modes[0]
+        }
+    }
+
+    /// Why the device was lost, once it has been; the app then drops this
+    /// state and recovers.
+    pub fn device_lost(&self) -> Option<String> {
+        if !self.lost.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(self.lost_reason.lock().map(|r| r.clone()).unwrap_or_default())
+    }
+
+    fn mark_lost(&self, reason: &str) {
+        if let Ok(mut r) = self.lost_reason.lock() {
+            *r = reason.to_string();
+        }
+        self.lost.store(true, Ordering::SeqCst);
+    }
+
     /// Resize the surface when the window size changes.
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width == 0 || new_size.height == 0 {
@@ -128,6 +211,8 @@ impl GpuState {
                 return Ok(false);
             }
             Err(wgpu::SurfaceError::OutOfMemory) => {
+                // The device can't be trusted after this; recover as if lost.
+                self.mark_lost("out of memory");
                 return Err(anyhow::anyhow!("GPU out of memory"));
             }
             Err(e) => {
@@ -142,6 +227,14 @@ impl GpuState {
 
         let viewport = [self.config.width, self.config.height];
 
+        let alpha = clear_color.a * self.opacity;
+        let clear_color = match self.config.alpha_mode {
+            CompositeAlphaMode::PreMultiplied => {
+                Rgba::new(clear_color.r * alpha, clear_color.g * alpha, clear_color.b * alpha, alpha)
+            }
+            _ => Rgba { a: alpha, ..clear_color },
+        };
+
         // Let the caller populate quad + text data
         draw_fn(
             &mut self.quads,
@@ -151,6 +244,9 @@ impl GpuState {
             viewport,
         );
 
+        // Shape text now so span decorations land in the quad pass
+        self.text.layout(&mut self.quads);
+
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -204,4 +300,13 @@ impl GpuState {
 
         Ok(true)
     }
-}
\ No newline at end of file
+}
+
+fn backends(backend: Backend) -> Backends {
+    match backend {
+        Backend::Vulkan => Backends::VULKAN,
+        Backend::Dx12 => Backends::DX12,
+        Backend::Metal => Backends::METAL,
+        Backend::Gl => Backends::GL,
+    }
+}
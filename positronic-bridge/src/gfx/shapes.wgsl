@@ -6,8 +6,9 @@ struct Globals {
 @group(0) @binding(0)
 var<uniform> globals: Globals;
 
-struct VsIn {
-    @location(0) pos: vec2<f32>,
+// One instance per quad: rect = (x, y, w, h) in pixels.
+struct InstanceIn {
+    @location(0) rect: vec4<f32>,
     @location(1) color: vec4<f32>,
 };
 
@@ -17,12 +18,24 @@ struct VsOut {
 };
 
 @vertex
-fn vs_main(input: VsIn) -> VsOut {
+fn vs_main(@builtin(vertex_index) vi: u32, input: InstanceIn) -> VsOut {
     var out: VsOut;
 
+    // Two triangles: (0,0) (1,0) (0,1) / (1,0) (1,1) (0,1)
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[vi];
+    let pos = input.rect.xy + corner * input.rect.zw;
+
     // Pixel -> NDC
-    let x = (input.pos.x / globals.resolution.x) * 2.0 - 1.0;
-    let y = 1.0 - (input.pos.y / globals.resolution.y) * 2.0;
+    let x = (pos.x / globals.resolution.x) * 2.0 - 1.0;
+    let y = 1.0 - (pos.y / globals.resolution.y) * 2.0;
 
     out.clip = vec4<f32>(x, y, 0.0, 1.0);
     out.color = input.color;
@@ -0,0 +1,57 @@
+// positronic-bridge/src/gfx/software.rs
+//! The renderer of last resort: softbuffer, no GPU.
+//!
+//! Used when every attempt in the `render_path` plan fails. The app
+//! composes a `SoftFrame` (monochrome text) and this copies it into the
+//! window's buffer and presents it.
+
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+use softbuffer::{Context, Surface};
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+use crate::soft_frame::SoftFrame;
+
+pub struct SoftwareRenderer {
+    surface: Surface<Arc<dyn Window>, Arc<dyn Window>>,
+    pub size: PhysicalSize<u32>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(window: Arc<dyn Window>) -> anyhow::Result<Self> {
+        let context = Context::new(window.clone()).map_err(|e| anyhow::anyhow!("softbuffer context: {}", e))?;
+        let surface = Surface::new(&context, window.clone()).map_err(|e| anyhow::anyhow!("softbuffer surface: {}", e))?;
+        let mut renderer = Self { surface, size: PhysicalSize::new(0, 0) };
+        renderer.resize(window.surface_size());
+        Ok(renderer)
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) else {
+            return;
+        };
+        match self.surface.resize(width, height) {
+            Ok(()) => self.size = size,
+            Err(e) => tracing::warn!("softbuffer resize failed: {}", e),
+        }
+    }
+
+    /// A blank frame the size of the window.
+    pub fn frame(&self, background: u32) -> SoftFrame {
+        SoftFrame::new(self.size.width as usize, self.size.height as usize, background)
+    }
+
+    /// Copy `frame` to the window; a frame of the wrong size (the window
+    /// was resized meanwhile) is dropped.
+    pub fn present(&mut self, frame: &SoftFrame) -> anyhow::Result<bool> {
+        let mut buffer = self.surface.buffer_mut().map_err(|e| anyhow::anyhow!("softbuffer buffer: {}", e))?;
+        if buffer.len() != frame.pixels.len() {
+            return Ok(false);
+        }
+        buffer.copy_from_slice(&frame.pixels);
+        buffer.present().map_err(|e| anyhow::anyhow!("softbuffer present: {}", e))?;
+        Ok(true)
+    }
+}
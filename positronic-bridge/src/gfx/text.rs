@@ -3,20 +3,27 @@
 //!
 //! Manages the font system, text buffer layout, and GPU rendering.
 //! Each frame, the caller pushes ColoredSpan data and screen regions,
-//! then render() uploads glyphs and draws them.
+//! layout() shapes them and queues span backgrounds, underlines and
+//! strikethroughs as quads, then render() uploads glyphs and draws them.
 
 use glyphon::{
     Attrs, Buffer as GlyphonBuffer, Cache, Family, FontSystem, Metrics,
-    Resolution, Shaping, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer,
-    Viewport,
+    Resolution, Shaping, Style, SwashCache, TextArea, TextAtlas, TextBounds, TextRenderer,
+    Viewport, Weight,
 };
 use wgpu::{CommandEncoder, Device, MultisampleState, Queue, TextureFormat, TextureView};
 
-use crate::renderer::{ColoredSpan, Rgba};
+use super::quad::QuadPipeline;
+use crate::layout::CellMetrics;
+use crate::quad_batch::{QuadInstance, QuadLayer};
+use crate::renderer::{self, ColoredSpan, GlyphBox, Rgba};
 
 /// Font metrics for the terminal monospace font.
 const FONT_SIZE: f32 = 14.0;
-const LINE_HEIGHT: f32 = 18.0;
+pub const LINE_HEIGHT: f32 = 18.0;
+
+/// One cell of the terminal grid, for laying out and sizing the PTY.
+pub const CELL: CellMetrics = CellMetrics { width: 8.0, height: LINE_HEIGHT };
 
 /// A region of text to render on screen.
 pub struct TextRegion {
@@ -36,6 +43,8 @@ pub struct TextEngine {
     viewport: Viewport,
     renderer: TextRenderer,
     regions: Vec<TextRegion>,
+    /// Shaped buffers for `regions`, built by `layout`.
+    buffers: Vec<GlyphonBuffer>,
 }
 
 impl TextEngine {
@@ -54,6 +63,7 @@ impl TextEngine {
             viewport,
             renderer,
             regions: Vec::new(),
+            buffers: Vec::new(),
         }
     }
 
@@ -65,6 +75,7 @@ impl TextEngine {
     /// Clear all queued regions.
     pub fn clear(&mut self) {
         self.regions.clear();
+        self.buffers.clear();
     }
 
     /// Get the monospace cell size (width, height) at current font size.
@@ -93,31 +104,11 @@ impl TextEngine {
         (width, LINE_HEIGHT)
     }
 
-    /// Render all queued text regions. Called once per frame after the main render pass.
-    pub fn render(
-        &mut self,
-        device: &Device,
-        queue: &Queue,
-        encoder: &mut CommandEncoder,
-        view: &TextureView,
-        format: TextureFormat,
-        viewport: [u32; 2],
-    ) {
-        if self.regions.is_empty() {
-            return;
-        }
-
-        let resolution = Resolution {
-            width: viewport[0],
-            height: viewport[1],
-        };
-
-        // Update the glyphon viewport with the current resolution
-        self.viewport.update(queue, resolution);
-
-        // Build glyphon buffers for each region
-        let mut text_areas: Vec<TextArea<'_>> = Vec::new();
-        let mut buffers: Vec<GlyphonBuffer> = Vec::new();
+    /// Shape all queued regions and queue their span decorations
+    /// (backgrounds, underlines, strikethroughs) as background quads.
+    /// Called once per frame before the main render pass.
+    pub fn layout(&mut self, quads: &mut QuadPipeline) {
+        self.buffers.clear();
 
         for region in &self.regions {
             let mut buffer = GlyphonBuffer::new(
@@ -133,10 +124,18 @@ impl TextEngine {
             let default_color = region.default_color.to_glyphon();
             let attrs = Attrs::new().family(Family::Monospace).color(default_color);
 
-            // Build rich text spans for per-span coloring
+            // Build rich text spans for per-span coloring and weight/slant.
+            // The span index rides along as metadata so decorations can find
+            // their span after shaping.
             let mut attrs_spans: Vec<(&str, Attrs<'_>)> = Vec::new();
-            for span in &region.spans {
-                let span_attrs = attrs.clone().color(span.color.to_glyphon());
+            for (i, span) in region.spans.iter().enumerate() {
+                let mut span_attrs = attrs.clone().color(span.color.to_glyphon()).metadata(i);
+                if span.style.bold {
+                    span_attrs = span_attrs.weight(Weight::BOLD);
+                }
+                if span.style.italic {
+                    span_attrs = span_attrs.style(Style::Italic);
+                }
                 attrs_spans.push((&span.text, span_attrs));
             }
 
@@ -149,13 +148,69 @@ impl TextEngine {
             );
             buffer.shape_until_scroll(&mut self.font_system, false);
 
-            buffers.push(buffer);
+            if region.spans.iter().any(|span| span.style != Default::default()) {
+                let scale = region.scale;
+                let glyphs: Vec<GlyphBox> = buffer
+                    .layout_runs()
+                    .flat_map(|run| {
+                        run.glyphs.iter().map(move |g| GlyphBox {
+                            span: g.metadata,
+                            x: region.left + g.x * scale,
+                            w: g.w * scale,
+                            line_top: region.top + run.line_top * scale,
+                            line_height: run.line_height * scale,
+                            baseline: region.top + run.line_y * scale,
+                        })
+                    })
+                    .collect();
+                let (top, bottom) = (region.bounds.top as f32, region.bounds.bottom as f32);
+                for deco in renderer::span_decorations(&region.spans, &glyphs, scale.max(1.0)) {
+                    if deco.y + deco.h <= top || deco.y >= bottom {
+                        continue;
+                    }
+                    quads.push(QuadInstance {
+                        x: deco.x,
+                        y: deco.y,
+                        w: deco.w,
+                        h: deco.h,
+                        color: deco.color,
+                        layer: QuadLayer::Background,
+                    });
+                }
+            }
+
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// Render all queued text regions, as shaped by `layout`. Called once
+    /// per frame after the main render pass.
+    pub fn render(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        view: &TextureView,
+        format: TextureFormat,
+        viewport: [u32; 2],
+    ) {
+        if self.regions.is_empty() {
+            return;
         }
 
+        let resolution = Resolution {
+            width: viewport[0],
+            height: viewport[1],
+        };
+
+        // Update the glyphon viewport with the current resolution
+        self.viewport.update(queue, resolution);
+
         // Build TextArea references
-        for (i, region) in self.regions.iter().enumerate() {
+        let mut text_areas: Vec<TextArea<'_>> = Vec::new();
+        for (region, buffer) in self.regions.iter().zip(&self.buffers) {
             text_areas.push(TextArea {
-                buffer: &buffers[i],
+                buffer,
                 left: region.left,
                 top: region.top,
                 scale: region.scale,
@@ -206,5 +261,6 @@ impl TextEngine {
 
         // Clear for next frame
         self.regions.clear();
+        self.buffers.clear();
     }
 }
\ No newline at end of file
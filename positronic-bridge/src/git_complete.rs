@@ -0,0 +1,195 @@
+//! Completion after `git`: subcommands (plus the repo's aliases) and, for
+//! `git checkout`/`git switch`, local branch names.
+//!
+//! Branches come straight from the repository files (`refs/heads/**` and
+//! `packed-refs`), so completing them never runs git. Aliases need git's
+//! config resolution (system, global, repo, includes), so they're read once
+//! per repository with a captured `git config --get-regexp` and cached.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Porcelain commands offered after `git `.
+pub const GIT_SUBCOMMANDS: &[&str] = &[
+    "add", "am", "apply", "archive", "bisect", "blame", "branch", "bundle", "checkout",
+    "cherry", "cherry-pick", "clean", "clone", "commit", "config", "describe", "diff",
+    "fetch", "format-patch", "gc", "grep", "init", "log", "ls-files", "merge", "mv",
+    "notes", "pull", "push", "range-diff", "rebase", "reflog", "remote", "reset",
+    "restore", "revert", "rm", "shortlog", "show", "sparse-checkout", "stash", "status",
+    "submodule", "switch", "tag", "worktree",
+];
+
+/// Subcommands whose argument is a branch.
+const BRANCH_SUBCOMMANDS: &[&str] = &["checkout", "switch"];
+
+/// A repository found from a working directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    /// The working tree root (holds `.git`).
+    pub root: PathBuf,
+    /// The git directory (`.git`, or where a worktree's `.git` file points).
+    pub git_dir: PathBuf,
+}
+
+/// The repository containing `cwd`, if any.
+pub fn find_repo(cwd: &Path) -> Option<Repo> {
+    for dir in cwd.ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(Repo { root: dir.to_path_buf(), git_dir: dot_git });
+        }
+        // Worktrees and submodules: `.git` is a file with `gitdir: <path>`.
+        if let Ok(text) = std::fs::read_to_string(&dot_git)
+            && let Some(target) = text.trim().strip_prefix("gitdir:")
+        {
+            let git_dir = dir.join(target.trim());
+            return Some(Repo { root: dir.to_path_buf(), git_dir });
+        }
+    }
+    None
+}
+
+/// Local branch names, sorted: loose refs under `refs/heads`, then
+/// `packed-refs` entries.
+pub fn local_branches(git_dir: &Path) -> Vec<String> {
+    let mut branches = Vec::new();
+    collect_loose(&git_dir.join("refs").join("heads"), "", &mut branches);
+
+    // A worktree's packed refs live in the main repository's git dir.
+    let common = std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|dir| git_dir.join(dir.trim()))
+        .unwrap_or_else(|_| git_dir.to_path_buf());
+    if common != git_dir {
+        collect_loose(&common.join("refs").join("heads"), "", &mut branches);
+    }
+    if let Ok(packed) = std::fs::read_to_string(common.join("packed-refs")) {
+        branches.extend(parse_packed_refs(&packed));
+    }
+
+    branches.sort();
+    branches.dedup();
+    branches
+}
+
+fn collect_loose(dir: &Path, prefix: &str, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_loose(&entry.path(), &format!("{}/", name), out),
+            Ok(_) => out.push(name),
+            Err(_) => {}
+        }
+    }
+}
+
+/// Branch names in a `packed-refs` file. Peeled (`^`) and comment lines
+/// are skipped.
+pub fn parse_packed_refs(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split_once(' '))
+        .filter_map(|(_, name)| name.trim().strip_prefix("refs/heads/"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Alias names in `git config --get-regexp ^alias\.` output
+/// (`alias.co checkout` per line).
+pub fn parse_alias_output(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter_map(|key| key.strip_prefix("alias."))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Run git to list the aliases visible in `root`. Failures (no git on
+/// `PATH`, not a repository) give none.
+fn read_aliases(root: &Path) -> Vec<String> {
+    let output = std::process::Command::new("git")
+        .args(["config", "--get-regexp", r"^alias\."])
+        .current_dir(root)
+        .stdin(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .output();
+    match output {
+        // Exit 1 just means "no aliases".
+        Ok(output) => parse_alias_output(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            tracing::debug!("git config for aliases failed: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Per-repository alias cache, shared by every Tab press.
+#[derive(Debug, Default)]
+pub struct GitCompleter {
+    aliases: Mutex<HashMap<PathBuf, Vec<String>>>,
+}
+
+impl GitCompleter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `aliases` for `root` instead of asking git.
+    pub fn set_aliases(&self, root: &Path, aliases: Vec<String>) {
+        self.cache().insert(root.to_path_buf(), aliases);
+    }
+
+    /// Forget cached aliases (`!rehash`).
+    pub fn clear(&self) {
+        self.cache().clear();
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Vec<String>>> {
+        self.aliases.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn aliases_for(&self, root: &Path) -> Vec<String> {
+        if let Some(cached) = self.cache().get(root) {
+            return cached.clone();
+        }
+        let aliases = read_aliases(root);
+        self.cache().insert(root.to_path_buf(), aliases.clone());
+        aliases
+    }
+
+    /// Completions for a line starting with `git `, as whole lines. `None`
+    /// when the cursor isn't on a subcommand or a branch argument (the
+    /// caller falls back to paths).
+    pub fn complete(&self, line: &str, cwd: &str) -> Option<Vec<String>> {
+        let rest = line.strip_prefix("git ")?;
+        let tokens: Vec<&str> = rest.split(' ').collect();
+        match tokens.as_slice() {
+            [partial] => {
+                let repo = find_repo(Path::new(cwd));
+                let aliases = repo.map(|r| self.aliases_for(&r.root)).unwrap_or_default();
+                let mut subs: Vec<String> = GIT_SUBCOMMANDS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .chain(aliases)
+                    .filter(|s| s.starts_with(partial))
+                    .collect();
+                subs.sort();
+                subs.dedup();
+                Some(subs.into_iter().map(|s| format!("git {}", s)).collect())
+            }
+            [sub, partial] if BRANCH_SUBCOMMANDS.contains(sub) && !partial.starts_with('-') => {
+                let repo = find_repo(Path::new(cwd))?;
+                let branches: Vec<String> = local_branches(&repo.git_dir)
+                    .into_iter()
+                    .filter(|b| b.starts_with(partial))
+                    .map(|b| format!("git {} {}", sub, b))
+                    .collect();
+                (!branches.is_empty()).then_some(branches)
+            }
+            _ => None,
+        }
+    }
+}
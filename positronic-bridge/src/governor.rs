@@ -0,0 +1,224 @@
+//! The activity governor: how much background work the UI does.
+//!
+//! A focused window, or one with recent input or PTY output, is Active.
+//! Once the window has been unfocused and quiet for `power.idle_after` it
+//! goes Idle: the cursor blink and running-command timer stop waking the
+//! event loop, neural health probes are paused and PTY output is
+//! snapshotted at most every `IDLE_SNAPSHOT_INTERVAL`. After
+//! `power.suspend_after` of quiet it is Suspended: theme sync and unread
+//! bookkeeping stop waking it too, so the loop sleeps until a real event.
+//! Focus, a key or click, or new PTY output wakes it straight back to
+//! Active. Hardware monitoring and background jobs run in the engine and
+//! are never throttled.
+//!
+//! The governor only decides; the caller owns the clock and applies each
+//! transition, which keeps the state machine testable with a made-up
+//! `Instant`. Time spent in each state is kept for `!perf report`.
+
+use std::time::{Duration, Instant};
+
+use crate::block::format_duration;
+
+pub const IDLE_AFTER_KEY: &str = "power.idle_after";
+pub const SUSPEND_AFTER_KEY: &str = "power.suspend_after";
+
+pub const DEFAULT_IDLE_AFTER: Duration = Duration::from_secs(2 * 60);
+pub const DEFAULT_SUSPEND_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Longest PTY output waits for a snapshot while Idle or Suspended.
+pub const IDLE_SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+pub const SUSPENDED_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerState {
+    Active,
+    Idle,
+    Suspended,
+}
+
+impl PowerState {
+    pub const ALL: [PowerState; 3] = [PowerState::Active, PowerState::Idle, PowerState::Suspended];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerState::Active => "active",
+            PowerState::Idle => "idle",
+            PowerState::Suspended => "suspended",
+        }
+    }
+
+    /// Cursor blink and the running-command timer wake the loop.
+    pub fn ticks(self) -> bool {
+        self == PowerState::Active
+    }
+
+    /// Theme sync and unread markers wake the loop.
+    pub fn timers(self) -> bool {
+        self != PowerState::Suspended
+    }
+
+    /// Neural health probes run.
+    pub fn probes(self) -> bool {
+        self == PowerState::Active
+    }
+
+    /// How long PTY output may wait before it is snapshotted.
+    pub fn snapshot_interval(self) -> Duration {
+        match self {
+            PowerState::Active => Duration::ZERO,
+            PowerState::Idle => IDLE_SNAPSHOT_INTERVAL,
+            PowerState::Suspended => SUSPENDED_SNAPSHOT_INTERVAL,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// `power.*`: quiet time before each step down. Both count from the
+/// later of the last activity and losing focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerConfig {
+    /// `None`: never leave Active (`power.idle_after = off`).
+    pub idle_after: Option<Duration>,
+    pub suspend_after: Duration,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self { idle_after: Some(DEFAULT_IDLE_AFTER), suspend_after: DEFAULT_SUSPEND_AFTER }
+    }
+}
+
+/// Parse `power.idle_after`: a duration like `90s` or `5m`, or `off`.
+pub fn parse_idle_after(value: &str) -> Option<Option<Duration>> {
+    match value.trim().to_lowercase().as_str() {
+        "off" | "false" | "no" | "never" => Some(None),
+        other => crate::renderer::parse_threshold(other).map(Some),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Governor {
+    config: PowerConfig,
+    state: PowerState,
+    focused: bool,
+    /// Last input, output or focus change.
+    quiet_since: Instant,
+    /// When `state` was entered.
+    entered: Instant,
+    /// Time spent in each state before `entered`.
+    spent: [Duration; 3],
+}
+
+impl Governor {
+    /// A focused, active window at `now`.
+    pub fn new(config: PowerConfig, now: Instant) -> Self {
+        Self {
+            config,
+            state: PowerState::Active,
+            focused: true,
+            quiet_since: now,
+            entered: now,
+            spent: [Duration::ZERO; 3],
+        }
+    }
+
+    pub fn state(&self) -> PowerState {
+        self.state
+    }
+
+    pub fn config(&self) -> PowerConfig {
+        self.config
+    }
+
+    /// New `power.*` settings; returns the new state if that moves it.
+    pub fn configure(&mut self, config: PowerConfig, now: Instant) -> Option<PowerState> {
+        self.config = config;
+        self.tick(now)
+    }
+
+    /// Gaining focus wakes at once; losing it starts the quiet countdown.
+    /// Returns the new state if it changed.
+    pub fn set_focus(&mut self, focused: bool, now: Instant) -> Option<PowerState> {
+        self.focused = focused;
+        self.activity(now)
+    }
+
+    /// A key, click or PTY output at `now`. Returns `Some(Active)` if this
+    /// woke the governor.
+    pub fn activity(&mut self, now: Instant) -> Option<PowerState> {
+        self.quiet_since = now;
+        self.enter(PowerState::Active, now)
+    }
+
+    /// Step down if the quiet time has run out; returns the new state.
+    pub fn tick(&mut self, now: Instant) -> Option<PowerState> {
+        let target = self.target(now);
+        self.enter(target, now)
+    }
+
+    /// When `tick` next has something to do.
+    pub fn next_change(&self) -> Option<Instant> {
+        if self.focused {
+            return None;
+        }
+        let idle_after = self.config.idle_after?;
+        match self.state {
+            PowerState::Active => Some(self.quiet_since + idle_after),
+            PowerState::Idle => Some(self.quiet_since + idle_after.max(self.config.suspend_after)),
+            PowerState::Suspended => None,
+        }
+    }
+
+    /// Total time spent in `state` up to `now`.
+    pub fn time_in(&self, state: PowerState, now: Instant) -> Duration {
+        let mut total = self.spent[state.index()];
+        if state == self.state {
+            total += now.saturating_duration_since(self.entered);
+        }
+        total
+    }
+
+    /// The `!perf report` section.
+    pub fn report_lines(&self, now: Instant) -> Vec<String> {
+        let total: Duration = PowerState::ALL.iter().map(|&s| self.time_in(s, now)).sum();
+        let mut lines = vec!["⚡ Power states (this session)".to_string(), "".to_string()];
+        for state in PowerState::ALL {
+            let spent = self.time_in(state, now);
+            let share = if total.is_zero() { 0.0 } else { spent.as_secs_f64() / total.as_secs_f64() * 100.0 };
+            let current = if state == self.state { "  ◀ now" } else { "" };
+            lines.push(format!("  {:<10} {:>10} {:>5.1}%{}", state.label(), format_duration(spent), share, current));
+        }
+        if self.config.idle_after.is_none() {
+            lines.push("".to_string());
+            lines.push(format!("  {} = off: never idles", IDLE_AFTER_KEY));
+        }
+        lines
+    }
+
+    fn target(&self, now: Instant) -> PowerState {
+        let Some(idle_after) = self.config.idle_after.filter(|_| !self.focused) else {
+            return PowerState::Active;
+        };
+        let quiet = now.saturating_duration_since(self.quiet_since);
+        if quiet >= idle_after.max(self.config.suspend_after) {
+            PowerState::Suspended
+        } else if quiet >= idle_after {
+            PowerState::Idle
+        } else {
+            PowerState::Active
+        }
+    }
+
+    fn enter(&mut self, state: PowerState, now: Instant) -> Option<PowerState> {
+        if state == self.state {
+            return None;
+        }
+        self.spent[self.state.index()] += now.saturating_duration_since(self.entered);
+        self.state = state;
+        self.entered = now;
+        Some(state)
+    }
+}
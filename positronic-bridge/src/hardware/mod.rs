@@ -3,8 +3,15 @@
 // Hardware Status Display — Bridge-side hardware status tracking and display data.
 // Receives events from positronic-io and maintains UI-friendly state
 // for rendering device lists, connection status, and sensor data summaries.
+// Parsed samples are also kept per channel for the `!scope` pane.
+// USB devices the user named (`!io name`) are shown by name.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+use positronic_core::serial::port_label;
+use positronic_io::{DeviceId, HardwareEvent, Platform, PortAccess, SensorSample};
+
+use crate::helpers::format_bytes;
 
 /// Status of a hardware device connection
 #[derive(Debug, Clone, PartialEq)]
@@ -27,6 +34,50 @@ pub struct DeviceInfo {
     pub baud_rate: Option<u32>,
     /// Rolling statistics for display
     pub stats: SensorStats,
+    /// Data the reader had to discard because the UI fell behind
+    pub data_loss: DataLoss,
+    /// Result of the last `!io scan --probe`; `None` if never probed
+    pub accessibility: Option<PortAccess>,
+    /// The USB device on the port, once identified
+    pub device: Option<DeviceId>,
+    /// The user's name for that device
+    pub name: Option<String>,
+}
+
+impl DeviceInfo {
+    /// The probe result with its remediation hint, for display.
+    pub fn access_label(&self) -> Option<String> {
+        self.accessibility.as_ref().map(|access| access.message(Platform::current()))
+    }
+}
+
+/// Accumulated overflow reports for a device ("data loss" badge).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DataLoss {
+    pub events: u64,
+    pub dropped_bytes: u64,
+    pub dropped_samples: u64,
+}
+
+impl DataLoss {
+    pub fn any(&self) -> bool {
+        self.events > 0
+    }
+
+    /// Short badge text, e.g. "⚠ lost 4.0 KB / 120 samples".
+    pub fn badge(&self) -> Option<String> {
+        if !self.any() {
+            return None;
+        }
+        let mut parts = Vec::new();
+        if self.dropped_bytes > 0 {
+            parts.push(format_bytes(self.dropped_bytes));
+        }
+        if self.dropped_samples > 0 {
+            parts.push(format!("{} samples", self.dropped_samples));
+        }
+        Some(format!("⚠ lost {}", parts.join(" / ")))
+    }
 }
 
 /// Rolling statistics for a sensor data stream
@@ -135,6 +186,23 @@ impl WaveformBuffer {
         self.capacity
     }
 
+    /// The newest sample.
+    pub fn latest(&self) -> Option<(f64, f32)> {
+        if self.len == 0 {
+            return None;
+        }
+        Some(self.buffer[(self.write_pos + self.capacity - 1) % self.capacity])
+    }
+
+    /// Samples per second across the buffered span; `None` until two
+    /// samples with different timestamps arrived.
+    pub fn rate(&self) -> Option<f64> {
+        let (newest, _) = self.latest()?;
+        let oldest = if self.len < self.capacity { self.buffer[0].0 } else { self.buffer[self.write_pos].0 };
+        let span = newest - oldest;
+        (span > 0.0).then(|| (self.len - 1) as f64 / span)
+    }
+
     /// Clear all samples.
     pub fn clear(&mut self) {
         self.write_pos = 0;
@@ -142,12 +210,30 @@ impl WaveformBuffer {
     }
 }
 
+/// One parsed channel of a device: its recent samples and statistics.
+#[derive(Debug, Clone)]
+pub struct ChannelTrace {
+    pub waveform: WaveformBuffer,
+    pub stats: SensorStats,
+}
+
+impl ChannelTrace {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            waveform: WaveformBuffer::new(capacity),
+            stats: SensorStats::new(),
+        }
+    }
+}
+
 /// The hardware status panel state, maintained by the Bridge.
 pub struct HardwarePanel {
     /// Known devices keyed by port name
     pub devices: HashMap<String, DeviceInfo>,
     /// Waveform buffers per port for oscilloscope rendering
     pub waveforms: HashMap<String, WaveformBuffer>,
+    /// Parsed channels per port, from `DataBatch` events
+    pub channels: HashMap<String, BTreeMap<u8, ChannelTrace>>,
     /// Default waveform buffer size
     waveform_capacity: usize,
 }
@@ -157,10 +243,73 @@ impl HardwarePanel {
         Self {
             devices: HashMap::new(),
             waveforms: HashMap::new(),
+            channels: HashMap::new(),
             waveform_capacity: 2048,
         }
     }
 
+    /// Fold an event from the IO layer into the panel. Scans report ports
+    /// as `DeviceConnected` too, so that only registers the port; a device
+    /// counts as connected once its data arrives.
+    pub fn apply(&mut self, event: &HardwareEvent) {
+        match event {
+            HardwareEvent::DeviceConnected(port) => self.device_discovered(port),
+            HardwareEvent::DeviceDisconnected(port) => self.device_disconnected(port),
+            HardwareEvent::DataBatch { port, samples } => self.record_batch(port, samples),
+            HardwareEvent::Overflow { port, dropped_bytes, dropped_samples, .. } => {
+                self.record_overflow(port, *dropped_bytes, *dropped_samples)
+            }
+            HardwareEvent::PortAccess { port, access } => self.record_access(port, access.clone()),
+            HardwareEvent::Identified { port, device, name, .. } => {
+                self.record_identity(port, device.clone(), name.clone())
+            }
+            HardwareEvent::SerialOutput { .. } | HardwareEvent::Error(_) | HardwareEvent::BaudDetected { .. } => {}
+        }
+    }
+
+    /// Record parsed samples from a port, channel by channel.
+    pub fn record_batch(&mut self, port_name: &str, samples: &[SensorSample]) {
+        self.device_discovered(port_name);
+        if let Some(device) = self.devices.get_mut(port_name) {
+            device.status = DeviceStatus::Connected;
+            for sample in samples {
+                device.stats.record(sample.value);
+            }
+        }
+        let capacity = self.waveform_capacity;
+        let channels = self.channels.entry(port_name.to_string()).or_default();
+        for sample in samples {
+            let trace = channels.entry(sample.channel).or_insert_with(|| ChannelTrace::new(capacity));
+            trace.waveform.push(sample.timestamp, sample.value);
+            trace.stats.record(sample.value);
+        }
+    }
+
+    /// A port's parsed channels, in channel order.
+    pub fn channels_of(&self, port_name: &str) -> Vec<(u8, &ChannelTrace)> {
+        self.channels
+            .get(port_name)
+            .map(|channels| channels.iter().map(|(&ch, trace)| (ch, trace)).collect())
+            .unwrap_or_default()
+    }
+
+    /// The port to scope when none is named: a connected one with data,
+    /// else any connected one, first by name.
+    pub fn default_port(&self) -> Option<&str> {
+        let mut connected: Vec<&str> = self
+            .devices
+            .values()
+            .filter(|d| d.status == DeviceStatus::Connected)
+            .map(|d| d.port_name.as_str())
+            .collect();
+        connected.sort();
+        connected
+            .iter()
+            .find(|port| self.channels.contains_key(**port))
+            .or(connected.first())
+            .copied()
+    }
+
     /// Register a device as available (discovered via scan).
     pub fn device_discovered(&mut self, port_name: &str) {
         self.devices
@@ -170,9 +319,41 @@ impl HardwarePanel {
                 status: DeviceStatus::Available,
                 baud_rate: None,
                 stats: SensorStats::new(),
+                data_loss: DataLoss::default(),
+                accessibility: None,
+                device: None,
+                name: None,
             });
     }
 
+    /// Record whether a scanned port could be opened.
+    pub fn record_access(&mut self, port_name: &str, access: PortAccess) {
+        self.device_discovered(port_name);
+        if let Some(device) = self.devices.get_mut(port_name) {
+            device.accessibility = Some(access);
+        }
+    }
+
+    /// Record which USB device is on a port and its name. A device or name
+    /// seen on another port before has moved here.
+    pub fn record_identity(&mut self, port_name: &str, device: DeviceId, name: Option<String>) {
+        self.device_discovered(port_name);
+        for other in self.devices.values_mut().filter(|d| d.port_name != port_name) {
+            if other.device.as_ref() == Some(&device) || (name.is_some() && other.name == name) {
+                other.name = None;
+            }
+        }
+        if let Some(info) = self.devices.get_mut(port_name) {
+            info.device = Some(device);
+            info.name = name;
+        }
+    }
+
+    /// "bench-scope (COM7)" for a named device, else the port.
+    pub fn label(&self, port_name: &str) -> String {
+        port_label(port_name, self.devices.get(port_name).and_then(|d| d.name.as_deref()))
+    }
+
     /// Mark a device as connected.
     pub fn device_connected(&mut self, port_name: &str, baud_rate: u32) {
         let device = self
@@ -183,14 +364,20 @@ impl HardwarePanel {
                 status: DeviceStatus::Available,
                 baud_rate: None,
                 stats: SensorStats::new(),
+                data_loss: DataLoss::default(),
+                accessibility: None,
+                device: None,
+                name: None,
             });
         device.status = DeviceStatus::Connected;
         device.baud_rate = Some(baud_rate);
         device.stats.reset();
+        device.data_loss = DataLoss::default();
 
         self.waveforms
             .entry(port_name.to_string())
             .or_insert_with(|| WaveformBuffer::new(self.waveform_capacity));
+        self.channels.remove(port_name);
     }
 
     /// Mark a device as disconnected.
@@ -207,6 +394,15 @@ impl HardwarePanel {
         }
     }
 
+    /// Record an overflow report from the IO layer.
+    pub fn record_overflow(&mut self, port_name: &str, dropped_bytes: usize, dropped_samples: usize) {
+        if let Some(device) = self.devices.get_mut(port_name) {
+            device.data_loss.events += 1;
+            device.data_loss.dropped_bytes += dropped_bytes as u64;
+            device.data_loss.dropped_samples += dropped_samples as u64;
+        }
+    }
+
     /// Record a sensor sample for a device.
     pub fn record_sample(&mut self, port_name: &str, timestamp: f64, value: f32) {
         if let Some(device) = self.devices.get_mut(port_name) {
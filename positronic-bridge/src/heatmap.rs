@@ -0,0 +1,70 @@
+//! `!stats heatmap` cells as gradient quads (no UI deps).
+//!
+//! Core writes a heatmap as shade glyphs between heat brackets, which is
+//! what the software renderer shows. The GPU path blanks those (`blank`)
+//! and paints each cell `cell_quads` finds in the theme's gradient, from
+//! just above its background up to its first trace color.
+
+use std::borrow::Cow;
+
+use positronic_core::heatmap::{self, HEAT_CLOSE, HEAT_OPEN};
+
+use crate::renderer::{Rgba, ThemeName};
+
+/// Pixels left between neighbouring cells.
+pub const CELL_GAP: f32 = 2.0;
+
+/// How far along the gradient each level sits. Level 0 lifts the
+/// background just enough that empty cells still read as a grid.
+const RAMP: [f32; 5] = [0.08, 0.3, 0.5, 0.75, 1.0];
+
+/// Rectangles `[x, y, w, h]` and levels of the heat cells in `lines`,
+/// the first line at row 0, relative to the text origin; each cell is
+/// inset by half of `gap` on every side.
+pub fn cell_quads<'a>(lines: impl IntoIterator<Item = &'a str>, cell_w: f32, line_h: f32, gap: f32) -> Vec<([f32; 4], u8)> {
+    let half = gap / 2.0;
+    let mut quads = Vec::new();
+    for (row, line) in lines.into_iter().enumerate() {
+        if !heatmap::is_heat_line(line) {
+            continue;
+        }
+        let y = row as f32 * line_h;
+        for cell in heatmap::cells(line) {
+            let x = cell.col as f32 * cell_w;
+            let rect = [x + half, y + half, cell.width as f32 * cell_w - gap, line_h - gap];
+            quads.push((rect, cell.level));
+        }
+    }
+    quads
+}
+
+/// A level's cell color in `theme`.
+pub fn color(theme: ThemeName, level: u8) -> Rgba {
+    let t = RAMP[(level as usize).min(RAMP.len() - 1)];
+    theme.bg_color().blend(theme.trace_color(0), t)
+}
+
+/// `line` with its heat cells and brackets as spaces, so the quads
+/// behind show through; other lines as they are.
+pub fn blank(line: &str) -> Cow<'_, str> {
+    if !heatmap::is_heat_line(line) {
+        return Cow::Borrowed(line);
+    }
+    let mut inside = false;
+    let blanked = line
+        .chars()
+        .map(|c| match c {
+            HEAT_OPEN => {
+                inside = true;
+                ' '
+            }
+            HEAT_CLOSE if inside => {
+                inside = false;
+                ' '
+            }
+            _ if inside => ' ',
+            c => c,
+        })
+        .collect();
+    Cow::Owned(blanked)
+}
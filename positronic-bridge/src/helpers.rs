@@ -3,9 +3,12 @@
 //! Zero UI dependencies. Used by both shell and ui modules.
 
 use positronic_core::state_machine::Snapshot;
+use positronic_core::term::running::RunningInfo;
 use positronic_core::PositronicEngine;
 
 use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthChar;
 
 // ────────────────────────────────────────────────────────────────
 // Snapshot hashing
@@ -21,13 +24,66 @@ pub fn hash_snapshot(snapshot: &Snapshot) -> u64 {
             c.hash(&mut h);
         }
     }
+    snapshot.clusters.hash(&mut h);
     h.finish()
 }
 
+// ────────────────────────────────────────────────────────────────
+// Display width
+// ────────────────────────────────────────────────────────────────
+
+/// Terminal cells taken by the first `chars` characters of `text`: wide
+/// characters (CJK, emoji) count two, combining marks none.
+pub fn cell_columns(text: &str, chars: usize) -> usize {
+    text.chars().take(chars).map(|c| c.width().unwrap_or(0)).sum()
+}
+
+// ────────────────────────────────────────────────────────────────
+// Words
+// ────────────────────────────────────────────────────────────────
+
+/// Char index of the start of the word before `cursor`; words are
+/// separated by whitespace, as for `unix-word-rubout`.
+pub fn word_left(text: &str, cursor: usize) -> usize {
+    let chars: Vec<char> = text.chars().take(cursor).collect();
+    let mut pos = chars.len();
+    while pos > 0 && chars[pos - 1].is_whitespace() {
+        pos -= 1;
+    }
+    while pos > 0 && !chars[pos - 1].is_whitespace() {
+        pos -= 1;
+    }
+    pos
+}
+
+/// Char index just past the end of the word at or after `cursor`.
+pub fn word_right(text: &str, cursor: usize) -> usize {
+    let mut chars = text.chars().skip(cursor).peekable();
+    let mut pos = cursor;
+    while chars.next_if(|c| c.is_whitespace()).is_some() {
+        pos += 1;
+    }
+    while chars.next_if(|c| !c.is_whitespace()).is_some() {
+        pos += 1;
+    }
+    pos
+}
+
 // ────────────────────────────────────────────────────────────────
 // Formatting
 // ────────────────────────────────────────────────────────────────
 
+/// `512 B`, `3.4 KB`, `1.2 MB`.
+pub fn format_bytes(n: u64) -> String {
+    if n < 1024 {
+        format!("{} B", n)
+    } else if n < 1024 * 1024 {
+        format!("{:.1} KB", n as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", n as f64 / (1024.0 * 1024.0))
+    }
+}
+
 pub fn format_duration_short(secs: i64) -> String {
     if secs < 60 {
         format!("{}s", secs)
@@ -40,6 +96,41 @@ pub fn format_duration_short(secs: i64) -> String {
     }
 }
 
+/// Whole-second stopwatch time: `42s`, `1m 42s`, `1h 02m`.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Longest command shown in the running indicator.
+const RUNNING_COMMAND_CHARS: usize = 40;
+
+/// Status bar text for a running command: `⏱ running: cargo build — 1m 42s`.
+/// Without shell integration the command is only a guess ("may be running").
+pub fn running_label(info: &RunningInfo, now: Instant) -> String {
+    let command = match info.command.trim() {
+        "" => "a command".to_string(),
+        cmd if cmd.chars().count() > RUNNING_COMMAND_CHARS => {
+            let head: String = cmd.chars().take(RUNNING_COMMAND_CHARS - 1).collect();
+            format!("{}…", head)
+        }
+        cmd => cmd.to_string(),
+    };
+    let verb = if info.confirmed { "running" } else { "may be running" };
+    format!(
+        "⏱ {}: {} — {}",
+        verb,
+        command,
+        format_elapsed(now.saturating_duration_since(info.started_at))
+    )
+}
+
 /// Shorten a path for status bar display.
 pub fn short_path(path: &str) -> String {
     if let Ok(home) = std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
@@ -53,6 +144,19 @@ pub fn short_path(path: &str) -> String {
     path.to_string()
 }
 
+// ────────────────────────────────────────────────────────────────
+// Links
+// ────────────────────────────────────────────────────────────────
+
+/// The last http(s) URL in `text`, without trailing punctuation.
+pub fn last_url(text: &str) -> Option<&str> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '`'))
+        .rev()
+        .filter_map(|word| word.find("https://").or_else(|| word.find("http://")).map(|i| &word[i..]))
+        .map(|url| url.trim_end_matches(['.', ',', ';', ':', ')', ']', '}', '!', '?']))
+        .find(|url| url.len() > "https://".len())
+}
+
 // ────────────────────────────────────────────────────────────────
 // Alias helpers
 // ────────────────────────────────────────────────────────────────
@@ -0,0 +1,383 @@
+//! Syntax highlighting for the input line, fish-style.
+//!
+//! `tokenize` splits the line the way a shell would (quotes, escapes,
+//! `|`/`&&`/`;`), keeping each token's character range. `classify` colors
+//! the tokens: the word in command position is a known command (alias,
+//! shell builtin, executable on `PATH`, existing script path, native
+//! `!command`) or unknown; the words after it are flags, quoted strings,
+//! paths or plain arguments. Arguments naming something that exists under
+//! the working directory are underlined.
+//!
+//! A backslash escapes only whitespace, quotes, `$`, `#` and the operator
+//! characters, so `C:\Users\me` and `\\server\share` stay intact.
+//!
+//! `Highlighter` runs this once per edit: PATH answers come from the
+//! `PathIndex` (itself a cache), and existence checks are remembered for
+//! `EXISTS_TTL`, with at most `MAX_NEW_CHECKS` new stats per edit.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::completer;
+use crate::path_index::PathIndex;
+
+/// How long a path existence answer is reused.
+pub const EXISTS_TTL: Duration = Duration::from_secs(2);
+
+/// New paths statted per edit; the rest wait for the next one.
+pub const MAX_NEW_CHECKS: usize = 8;
+
+/// Commands the shell runs itself, so they are never on `PATH`.
+const SHELL_BUILTINS: &[&str] = &[
+    ".", ":", "[", "alias", "bg", "bind", "break", "builtin", "case", "cd", "command", "continue",
+    "declare", "dirs", "do", "done", "echo", "elif", "else", "esac", "eval", "exec", "exit",
+    "export", "false", "fg", "fi", "for", "function", "hash", "history", "if", "jobs", "kill",
+    "let", "local", "popd", "printf", "pushd", "pwd", "read", "readonly", "return", "set",
+    "shift", "source", "test", "then", "time", "trap", "true", "type", "ulimit", "umask",
+    "unalias", "unset", "until", "wait", "while",
+    // cmd.exe
+    "cls", "copy", "del", "dir", "md", "move", "rd", "ren",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    /// `|`, `||`, `&`, `&&`, `;`: the next word is a command.
+    Separator,
+    /// `>`, `>>`, `<`, `2>`…: the next word is a file.
+    Redirect,
+    /// `#` to the end of the line.
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Character (not byte) range in the input.
+    pub range: Range<usize>,
+    /// The word as the shell sees it: quotes removed, escapes applied.
+    pub text: String,
+    /// Some part of the word was quoted.
+    pub quoted: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Command,
+    UnknownCommand,
+    Flag,
+    String,
+    Path,
+    Argument,
+    Operator,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightSpan {
+    /// Character range in the input.
+    pub range: Range<usize>,
+    pub kind: HighlightKind,
+    /// Names something that exists.
+    pub underline: bool,
+}
+
+/// What the classifier may consult.
+#[derive(Clone, Copy)]
+pub struct Sources<'a> {
+    /// `None`, or an index not scanned yet, leaves commands uncolored
+    /// rather than flagging everything unknown.
+    pub executables: Option<&'a PathIndex>,
+    pub is_alias: &'a dyn Fn(&str) -> bool,
+    /// Relative paths are resolved here.
+    pub cwd: &'a Path,
+}
+
+fn is_operator_char(c: char) -> bool {
+    matches!(c, '|' | '&' | ';' | '<' | '>')
+}
+
+fn is_escapable(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '\'' | '"' | '$' | '#' | '`') || is_operator_char(c)
+}
+
+/// Split `input` into shell words and operators.
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+
+        if c == '#' {
+            let text: String = chars[i..].iter().collect();
+            tokens.push(Token { kind: TokenKind::Comment, range: start..chars.len(), text, quoted: false });
+            break;
+        }
+
+        // `2>`, `2>>`, `&>`: a file descriptor glued to a redirect.
+        let fd_redirect = c.is_ascii_digit() && chars.get(i + 1) == Some(&'>');
+        if is_operator_char(c) || fd_redirect {
+            let mut end = i + 1;
+            while end < chars.len() && is_operator_char(chars[end]) && end - start < 3 {
+                end += 1;
+            }
+            let text: String = chars[start..end].iter().collect();
+            let kind = if text.contains(['>', '<']) { TokenKind::Redirect } else { TokenKind::Separator };
+            tokens.push(Token { kind, range: start..end, text, quoted: false });
+            i = end;
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut quoted = false;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() || is_operator_char(c) {
+                break;
+            }
+            match c {
+                '\'' => {
+                    quoted = true;
+                    i += 1;
+                    while i < chars.len() && chars[i] != '\'' {
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                '"' => {
+                    quoted = true;
+                    i += 1;
+                    while i < chars.len() && chars[i] != '"' {
+                        if chars[i] == '\\' && matches!(chars.get(i + 1), Some('"' | '\\' | '$' | '`')) {
+                            i += 1;
+                        }
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                '\\' if chars.get(i + 1).is_some_and(|&n| is_escapable(n)) => {
+                    text.push(chars[i + 1]);
+                    i += 2;
+                }
+                _ => {
+                    text.push(c);
+                    i += 1;
+                }
+            }
+        }
+        let end = i.min(chars.len());
+        tokens.push(Token { kind: TokenKind::Word, range: start..end, text, quoted });
+    }
+    tokens
+}
+
+/// Color `tokens`. `exists` answers for paths already resolved against
+/// `sources.cwd`, `None` when it would rather not check yet.
+pub fn classify(
+    tokens: &[Token],
+    sources: &Sources<'_>,
+    exists: &mut dyn FnMut(&Path) -> Option<bool>,
+) -> Vec<HighlightSpan> {
+    let mut spans = Vec::with_capacity(tokens.len());
+    let mut command_position = true;
+    let mut after_redirect = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let span = |kind, underline| HighlightSpan { range: token.range.clone(), kind, underline };
+        match token.kind {
+            TokenKind::Comment => spans.push(span(HighlightKind::Comment, false)),
+            TokenKind::Separator => {
+                command_position = true;
+                spans.push(span(HighlightKind::Operator, false));
+            }
+            TokenKind::Redirect => {
+                after_redirect = true;
+                spans.push(span(HighlightKind::Operator, false));
+            }
+            TokenKind::Word if after_redirect => {
+                after_redirect = false;
+                let found = resolve(&token.text, sources.cwd).and_then(|p| exists(&p)).unwrap_or(false);
+                spans.push(span(HighlightKind::Path, found));
+            }
+            TokenKind::Word if command_position => {
+                // `FOO=1 cmd`: assignments come before the command.
+                if !token.quoted && is_assignment(&token.text) {
+                    spans.push(span(HighlightKind::Argument, false));
+                    continue;
+                }
+                command_position = false;
+                let known = if index == 0 && token.text.starts_with('!') {
+                    Some(completer::is_bang_command(&token.text[1..]))
+                } else {
+                    command_known(&token.text, sources, exists)
+                };
+                spans.push(match known {
+                    Some(true) => span(HighlightKind::Command, false),
+                    Some(false) => span(HighlightKind::UnknownCommand, false),
+                    None => span(HighlightKind::Argument, false),
+                });
+            }
+            TokenKind::Word => {
+                let text = &token.text;
+                if !token.quoted && text.len() > 1 && text.starts_with('-') {
+                    spans.push(span(HighlightKind::Flag, false));
+                    continue;
+                }
+                let checkable = !text.is_empty() && !text.contains(['$', '*', '?', '`']);
+                let found = checkable
+                    && resolve(text, sources.cwd).and_then(|p| exists(&p)).unwrap_or(false);
+                let kind = if token.quoted {
+                    HighlightKind::String
+                } else if found || looks_like_path(text) {
+                    HighlightKind::Path
+                } else {
+                    HighlightKind::Argument
+                };
+                spans.push(span(kind, found));
+            }
+        }
+    }
+    spans
+}
+
+/// `tokenize` then `classify`.
+pub fn highlight(
+    input: &str,
+    sources: &Sources<'_>,
+    exists: &mut dyn FnMut(&Path) -> Option<bool>,
+) -> Vec<HighlightSpan> {
+    classify(&tokenize(input), sources, exists)
+}
+
+fn is_assignment(text: &str) -> bool {
+    match text.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+fn looks_like_path(text: &str) -> bool {
+    text.contains(['/', '\\'])
+        || text.starts_with('~')
+        || text.starts_with('.')
+        || is_drive_path(text)
+}
+
+/// `C:`, `C:\…`, `c:/…`.
+fn is_drive_path(text: &str) -> bool {
+    let b = text.as_bytes();
+    b.len() >= 2 && b[0].is_ascii_alphabetic() && b[1] == b':' && (b.len() == 2 || matches!(b[2], b'\\' | b'/'))
+}
+
+/// `Some(true)` for a known command, `Some(false)` for an unknown one,
+/// `None` when that can't be told yet.
+fn command_known(
+    name: &str,
+    sources: &Sources<'_>,
+    exists: &mut dyn FnMut(&Path) -> Option<bool>,
+) -> Option<bool> {
+    if name.is_empty() {
+        return None;
+    }
+    if (sources.is_alias)(name) || SHELL_BUILTINS.contains(&name) || is_cmdlet(name) {
+        return Some(true);
+    }
+    if looks_like_path(name) {
+        return exists(&resolve(name, sources.cwd)?);
+    }
+    if cfg!(windows) && SHELL_BUILTINS.iter().any(|b| b.eq_ignore_ascii_case(name)) {
+        return Some(true);
+    }
+    sources.executables?.contains(name)
+}
+
+/// PowerShell `Verb-Noun`.
+fn is_cmdlet(name: &str) -> bool {
+    let Some((verb, noun)) = name.split_once('-') else {
+        return false;
+    };
+    verb.starts_with(|c: char| c.is_ascii_uppercase())
+        && noun.starts_with(|c: char| c.is_ascii_uppercase())
+        && verb.chars().all(|c| c.is_ascii_alphabetic())
+        && noun.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// `text` as a filesystem path: `~` is the home directory, relative
+/// paths are taken from `cwd`.
+fn resolve(text: &str, cwd: &Path) -> Option<PathBuf> {
+    if text == "~" || text.starts_with("~/") || text.starts_with("~\\") {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        return Some(PathBuf::from(home).join(text[1..].trim_start_matches(['/', '\\'])));
+    }
+    if text.starts_with('~') {
+        return None;
+    }
+    Some(cwd.join(text))
+}
+
+/// Highlighting for the live input line, recomputed only when the line,
+/// the working directory or `EXISTS_TTL` moves on.
+#[derive(Debug, Default)]
+pub struct Highlighter {
+    exists: HashMap<PathBuf, (bool, Instant)>,
+    last: Option<Memo>,
+}
+
+#[derive(Debug)]
+struct Memo {
+    input: String,
+    cwd: PathBuf,
+    at: Instant,
+    /// Some path wasn't checked for lack of budget.
+    partial: bool,
+    spans: Vec<HighlightSpan>,
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn highlight(&mut self, input: &str, sources: &Sources<'_>, now: Instant) -> &[HighlightSpan] {
+        let fresh = self.last.as_ref().is_some_and(|m| {
+            m.input == input
+                && m.cwd == sources.cwd
+                && !m.partial
+                && now.saturating_duration_since(m.at) < EXISTS_TTL
+        });
+        if !fresh {
+            self.exists.retain(|_, (_, at)| now.saturating_duration_since(*at) < EXISTS_TTL);
+            let mut budget = MAX_NEW_CHECKS;
+            let mut partial = false;
+            let cache = &mut self.exists;
+            let spans = highlight(input, sources, &mut |path: &Path| {
+                if let Some((found, _)) = cache.get(path) {
+                    return Some(*found);
+                }
+                if budget == 0 {
+                    partial = true;
+                    return None;
+                }
+                budget -= 1;
+                let found = std::fs::symlink_metadata(path).is_ok();
+                cache.insert(path.to_path_buf(), (found, now));
+                Some(found)
+            });
+            self.last = Some(Memo { input: input.to_string(), cwd: sources.cwd.to_path_buf(), at: now, partial, spans });
+        }
+        self.last.as_ref().map_or(&[], |m| m.spans.as_slice())
+    }
+}
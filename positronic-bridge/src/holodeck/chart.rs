@@ -0,0 +1,468 @@
+//! `!chart`: plot a Holodeck table as text.
+//!
+//! Axes are chosen from the table: a first column of timestamps (ISO 8601,
+//! or epoch seconds/millis under a header that says time, date or ts)
+//! becomes a time axis ticked by the minute, hour or day to suit its range;
+//! another numeric first column is a plain x axis; otherwise rows are
+//! numbered. The y values are the first numeric column after that, on a
+//! log scale when they span more than `LOG_DECADES` decades. A log scale
+//! can't show zero or negative values, so those are clamped to the
+//! smallest positive one and the chart says how many were.
+//!
+//! Pure: parsing, tick choice and the text rendering are testable without
+//! the UI. A GPU plot would take the same `ChartSpec` and ticks.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{CellValue, ChartSpec, DataFrame};
+
+pub const CHART_USAGE: &str = "Usage: !chart [--logy|--liny] [--x time] [--entry <id>]";
+
+/// Decades of y range past which `suggest_y_scale` picks a log scale.
+pub const LOG_DECADES: f64 = 3.0;
+
+/// Roughly how many ticks an axis gets.
+const TARGET_TICKS: usize = 5;
+
+/// Epoch seconds in [2001, 2286) and millis in the same years; anything
+/// else isn't taken for a timestamp.
+const EPOCH_SECONDS: std::ops::Range<f64> = 1e9..1e10;
+const EPOCH_MILLIS: std::ops::Range<f64> = 1e12..1e13;
+
+// ════════════════════════════════════════════════════════════════════
+// Timestamps
+// ════════════════════════════════════════════════════════════════════
+
+/// Milliseconds since the epoch for an ISO 8601 date or date-time: a
+/// `T` or space between the parts, seconds and fractions optional, `Z`
+/// or an offset or neither (then UTC).
+pub fn parse_datetime(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let bytes = text.as_bytes();
+    if bytes.len() < 10 || !bytes[..4].iter().all(u8::is_ascii_digit) || bytes[4] != b'-' {
+        return None;
+    }
+    if bytes.len() == 10 {
+        let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+        return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?).timestamp_millis());
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let text = format!("{}T{}", &text[..10], &text[11..]);
+    if let Ok(at) = DateTime::parse_from_rfc3339(&text) {
+        return Some(at.timestamp_millis());
+    }
+    if let Ok(at) = DateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M%:z") {
+        return Some(at.timestamp_millis());
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&text, format).ok())
+        .map(|at| Utc.from_utc_datetime(&at).timestamp_millis())
+}
+
+/// A number read as an epoch timestamp, in millis: seconds or millis by
+/// magnitude, `None` outside the years either could mean.
+pub fn epoch_millis(n: f64) -> Option<i64> {
+    if EPOCH_SECONDS.contains(&n) {
+        Some((n * 1000.0).round() as i64)
+    } else if EPOCH_MILLIS.contains(&n) {
+        Some(n.round() as i64)
+    } else {
+        None
+    }
+}
+
+/// Whether a header names a time: a word of it is or ends in `time` or
+/// `date`, or is `ts` or `epoch` (`created_ts`, `Timestamp`, `run date`).
+pub fn header_hints_time(header: &str) -> bool {
+    header
+        .to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| word.ends_with("time") || word.ends_with("date") || word.starts_with("timestamp") || matches!(word, "ts" | "epoch"))
+}
+
+/// A cell's timestamp: a parsed date-time, or with `epochs` a number in
+/// epoch range.
+pub(super) fn cell_millis(cell: &CellValue, epochs: bool) -> Option<i64> {
+    match cell {
+        CellValue::DateTime { millis, .. } => Some(*millis),
+        CellValue::Integer(i) if epochs => epoch_millis(*i as f64),
+        CellValue::Float(f) if epochs => epoch_millis(*f),
+        _ => None,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Chart spec
+// ════════════════════════════════════════════════════════════════════
+
+/// What the x axis holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AxisKind {
+    /// Row numbers, from 1.
+    #[default]
+    Index,
+    Numeric,
+    /// Epoch millis.
+    Time,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum YScale {
+    #[default]
+    Linear,
+    Log10,
+}
+
+/// `!chart`'s overrides; `None` leaves the choice to the data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChartOptions {
+    pub y_scale: Option<YScale>,
+    /// `--x time`: the first column is time, epoch numbers included.
+    pub x_time: bool,
+}
+
+/// A parsed `!chart` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChartCommand {
+    pub options: ChartOptions,
+    pub entry: Option<u64>,
+}
+
+impl ChartCommand {
+    /// Parse what follows `!chart`; the error is a message for the user.
+    pub fn parse(args: &str) -> Result<ChartCommand, String> {
+        let mut command = ChartCommand::default();
+        let mut parts = args.split_whitespace();
+        while let Some(part) = parts.next() {
+            match part {
+                "--logy" => command.options.y_scale = Some(YScale::Log10),
+                "--liny" => command.options.y_scale = Some(YScale::Linear),
+                "--x" => match parts.next() {
+                    Some("time") => command.options.x_time = true,
+                    _ => return Err(CHART_USAGE.to_string()),
+                },
+                "--entry" => {
+                    let id = parts.next().ok_or(CHART_USAGE)?;
+                    command.entry = Some(id.trim_start_matches('#').parse().map_err(|_| format!("❌ Bad entry id '{}'", id))?);
+                }
+                _ => return Err(CHART_USAGE.to_string()),
+            }
+        }
+        Ok(command)
+    }
+}
+
+/// `Log10` when the positive values span more than `LOG_DECADES` decades.
+pub fn suggest_y_scale(values: &[f64]) -> YScale {
+    let positive = values.iter().copied().filter(|v| *v > 0.0);
+    let (min, max) = positive.fold((f64::INFINITY, 0.0f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if max > 0.0 && (max / min).log10() > LOG_DECADES {
+        YScale::Log10
+    } else {
+        YScale::Linear
+    }
+}
+
+/// The value zero and negative values are clamped to on a log scale: the
+/// smallest positive one, or 1 without any.
+pub fn log_floor(values: &[f64]) -> f64 {
+    let min = values.iter().copied().filter(|v| *v > 0.0).fold(f64::INFINITY, f64::min);
+    if min.is_finite() { min } else { 1.0 }
+}
+
+impl ChartSpec {
+    /// A line chart of `df` (see the module docs for how the axes are
+    /// picked); the error is a message for the user.
+    pub fn from_dataframe(df: &DataFrame, options: &ChartOptions) -> Result<ChartSpec, String> {
+        let first_numeric = df.col_count() > 0 && df.is_column_numeric(0);
+        let x_axis = if options.x_time || (df.col_count() > 0 && df.is_time_column(0)) {
+            AxisKind::Time
+        } else if first_numeric && (1..df.col_count()).any(|c| df.is_column_numeric(c)) {
+            AxisKind::Numeric
+        } else {
+            AxisKind::Index
+        };
+        let x_column = (x_axis != AxisKind::Index).then_some(0);
+        let y_column = (0..df.col_count())
+            .filter(|c| Some(*c) != x_column)
+            .find(|c| df.is_column_numeric(*c) && !df.is_time_column(*c))
+            .ok_or("📊 Nothing to chart: the table has no numeric column")?;
+
+        let xs: Vec<Option<f64>> = match x_axis {
+            AxisKind::Index => (1..=df.row_count()).map(|i| Some(i as f64)).collect(),
+            AxisKind::Numeric => df.column_values(0, false),
+            AxisKind::Time => df.column_values(0, true),
+        };
+        let points: Vec<(f64, f64)> = xs
+            .into_iter()
+            .zip(df.column_values(y_column, false))
+            .filter_map(|(x, y)| Some((x?, y?)))
+            .collect();
+        if points.is_empty() {
+            return Err(match x_axis {
+                AxisKind::Time => format!("📊 Nothing to chart: no timestamps in '{}'", df.headers[0]),
+                _ => "📊 Nothing to chart: no row has both values".to_string(),
+            });
+        }
+
+        let ys: Vec<f64> = points.iter().map(|(_, y)| *y).collect();
+        let y_scale = options.y_scale.unwrap_or_else(|| suggest_y_scale(&ys));
+        let mut notes = Vec::new();
+        if y_scale == YScale::Log10 {
+            let clamped = ys.iter().filter(|y| **y <= 0.0).count();
+            if clamped > 0 {
+                notes.push(format!(
+                    "{} value(s) ≤ 0 clamped to {} on the log scale",
+                    clamped,
+                    log_floor(&ys)
+                ));
+            }
+        }
+        let y_label = df.headers.get(y_column).cloned().unwrap_or_default();
+        let x_label = x_column.and_then(|c| df.headers.get(c).cloned());
+        Ok(ChartSpec {
+            chart_type: "line".to_string(),
+            title: Some(match &x_label {
+                Some(x) => format!("{} by {}", y_label, x),
+                None => y_label.clone(),
+            }),
+            x_label,
+            y_label: Some(y_label),
+            x_axis,
+            y_scale,
+            points,
+            notes,
+        })
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Ticks
+// ════════════════════════════════════════════════════════════════════
+
+/// The spacing of time ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeStep {
+    Minutes(i64),
+    Hours(i64),
+    Days(i64),
+}
+
+impl TimeStep {
+    /// Candidates, smallest first.
+    const ALL: [TimeStep; 12] = [
+        TimeStep::Minutes(1),
+        TimeStep::Minutes(5),
+        TimeStep::Minutes(15),
+        TimeStep::Minutes(30),
+        TimeStep::Hours(1),
+        TimeStep::Hours(3),
+        TimeStep::Hours(6),
+        TimeStep::Hours(12),
+        TimeStep::Days(1),
+        TimeStep::Days(7),
+        TimeStep::Days(30),
+        TimeStep::Days(365),
+    ];
+
+    pub fn millis(self) -> i64 {
+        match self {
+            TimeStep::Minutes(n) => n * 60_000,
+            TimeStep::Hours(n) => n * 3_600_000,
+            TimeStep::Days(n) => n * 86_400_000,
+        }
+    }
+
+    /// `10:30` by the minute, `03-14 18:00` by the hour, `2026-03-14`
+    /// by the day.
+    pub fn label(self, millis: i64) -> String {
+        let Some(at) = Utc.timestamp_millis_opt(millis).single() else {
+            return String::new();
+        };
+        let format = match self {
+            TimeStep::Minutes(_) => "%H:%M",
+            TimeStep::Hours(_) => "%m-%d %H:%M",
+            TimeStep::Days(_) => "%Y-%m-%d",
+        };
+        at.format(format).to_string()
+    }
+}
+
+/// Ticks across `min..=max` millis: the smallest step that gives at most
+/// `TARGET_TICKS`, aligned to whole steps since the epoch (UTC).
+pub fn time_ticks(min: i64, max: i64) -> (TimeStep, Vec<i64>) {
+    let span = (max - min).max(1);
+    let step = TimeStep::ALL
+        .into_iter()
+        .find(|s| span / s.millis() < TARGET_TICKS as i64)
+        .unwrap_or(TimeStep::Days(365));
+    let size = step.millis();
+    let first = min.div_euclid(size) * size + if min.rem_euclid(size) == 0 { 0 } else { size };
+    (step, (0..).map(|i| first + i * size).take_while(|t| *t <= max).collect())
+}
+
+/// Ticks at 1, 2 or 5 × a power of ten, about `TARGET_TICKS` of them,
+/// within `min..=max`.
+pub fn linear_ticks(min: f64, max: f64) -> Vec<f64> {
+    let step = nice_step((max - min) / (TARGET_TICKS - 1) as f64);
+    if !step.is_finite() || step <= 0.0 {
+        return vec![min];
+    }
+    let first = (min / step).ceil() as i64;
+    let last = (max / step + 1e-9).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn nice_step(raw: f64) -> f64 {
+    if raw <= 0.0 || !raw.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let fraction = raw / magnitude;
+    let nice = if fraction <= 1.0 {
+        1.0
+    } else if fraction <= 2.0 {
+        2.0
+    } else if fraction <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// The whole decades (as exponents) from `min`'s down to `max`'s up.
+pub fn log_ticks(min: f64, max: f64) -> Vec<i32> {
+    let (lo, hi) = (min.log10().floor() as i32, max.log10().ceil() as i32);
+    (lo..=hi.max(lo + 1)).collect()
+}
+
+/// `10^exp` as a tick label: plain from 0.001 to 100000, `1e6` beyond.
+pub fn log_label(exp: i32) -> String {
+    match exp {
+        0..=5 => format!("1{}", "0".repeat(exp as usize)),
+        -3..=-1 => format!("0.{}1", "0".repeat((-exp - 1) as usize)),
+        _ => format!("1e{}", exp),
+    }
+}
+
+/// `value` with as many decimals as ticks `step` apart need.
+pub fn number_label(value: f64, step: f64) -> String {
+    let decimals = if step > 0.0 && step < 1.0 { (-step.log10()).ceil() as usize } else { 0 };
+    format!("{:.*}", decimals, value)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Text rendering
+// ════════════════════════════════════════════════════════════════════
+
+/// An axis after scaling: its range and its ticks as (position, label).
+struct Axis {
+    min: f64,
+    max: f64,
+    ticks: Vec<(f64, String)>,
+}
+
+impl Axis {
+    fn x(spec: &ChartSpec) -> Axis {
+        let (min, max) = bounds(spec.points.iter().map(|(x, _)| *x));
+        match spec.x_axis {
+            AxisKind::Time => {
+                let (step, ticks) = time_ticks(min as i64, max as i64);
+                Axis { min, max, ticks: ticks.into_iter().map(|t| (t as f64, step.label(t))).collect() }
+            }
+            _ => Axis::linear(min, max),
+        }
+    }
+
+    fn y(spec: &ChartSpec, ys: &[f64]) -> Axis {
+        let (min, max) = bounds(ys.iter().copied());
+        match spec.y_scale {
+            YScale::Linear => Axis::linear(min, max),
+            YScale::Log10 => {
+                let exps = log_ticks(10f64.powf(min), 10f64.powf(max));
+                let ticks = exps.iter().map(|e| (*e as f64, log_label(*e))).collect();
+                Axis { min: *exps.first().unwrap_or(&0) as f64, max: *exps.last().unwrap_or(&1) as f64, ticks }
+            }
+        }
+    }
+
+    fn linear(min: f64, max: f64) -> Axis {
+        let (min, max) = if min == max { (min - 1.0, max + 1.0) } else { (min, max) };
+        let ticks = linear_ticks(min, max);
+        let step = if ticks.len() > 1 { ticks[1] - ticks[0] } else { 1.0 };
+        Axis { min, max, ticks: ticks.into_iter().map(|t| (t, number_label(t, step))).collect() }
+    }
+
+    /// Where `value` falls on `0..cells`.
+    fn cell(&self, value: f64, cells: usize) -> usize {
+        let span = self.max - self.min;
+        let fraction = if span > 0.0 { (value - self.min) / span } else { 0.5 };
+        (fraction.clamp(0.0, 1.0) * (cells - 1) as f64).round() as usize
+    }
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if min.is_finite() { (min, max) } else { (0.0, 1.0) }
+}
+
+/// The chart as text, `width` × `height` plot cells plus a title line,
+/// the y tick labels, the x axis with its labels and any notes.
+pub fn render_text(spec: &ChartSpec, width: usize, height: usize) -> Vec<String> {
+    let (width, height) = (width.max(8), height.max(3));
+    if spec.points.is_empty() {
+        return vec!["📊 Nothing to chart".to_string()];
+    }
+    let raw: Vec<f64> = spec.points.iter().map(|(_, y)| *y).collect();
+    let ys: Vec<f64> = match spec.y_scale {
+        YScale::Linear => raw,
+        YScale::Log10 => {
+            let floor = log_floor(&raw);
+            raw.iter().map(|y| y.max(floor).log10()).collect()
+        }
+    };
+    let (x_axis, y_axis) = (Axis::x(spec), Axis::y(spec, &ys));
+
+    let mut grid = vec![vec![' '; width]; height];
+    for ((x, _), y) in spec.points.iter().zip(&ys) {
+        let row = height - 1 - y_axis.cell(*y, height);
+        grid[row][x_axis.cell(*x, width)] = '•';
+    }
+    let mut y_labels = vec![String::new(); height];
+    for (value, label) in &y_axis.ticks {
+        y_labels[height - 1 - y_axis.cell(*value, height)] = label.clone();
+    }
+    let gutter = y_labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    let scale = if spec.y_scale == YScale::Log10 { " (log y)" } else { "" };
+    let mut lines = vec![format!("📊 {}{}", spec.title.as_deref().unwrap_or("Chart"), scale)];
+    for (label, row) in y_labels.iter().zip(&grid) {
+        let tick = if label.is_empty() { '│' } else { '┤' };
+        lines.push(format!("{:>gutter$} {}{}", label, tick, row.iter().collect::<String>()).trim_end().to_string());
+    }
+
+    let mut axis = vec!['─'; width];
+    let mut labels = vec![' '; width + 12];
+    let mut free_from = 0;
+    for (value, label) in &x_axis.ticks {
+        let col = x_axis.cell(*value, width);
+        axis[col] = '┬';
+        let len = label.chars().count();
+        let start = col.saturating_sub(len / 2);
+        if start >= free_from && start + len <= labels.len() {
+            for (i, c) in label.chars().enumerate() {
+                labels[start + i] = c;
+            }
+            free_from = start + len + 1;
+        }
+    }
+    lines.push(format!("{:>gutter$} └{}", "", axis.iter().collect::<String>()));
+    lines.push(format!("{:>gutter$}  {}", "", labels.iter().collect::<String>()).trim_end().to_string());
+    lines.extend(spec.notes.iter().map(|note| format!("⚠️ {}", note)));
+    lines
+}
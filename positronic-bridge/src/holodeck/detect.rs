@@ -1,6 +1,81 @@
-use crate::holodeck::{ContentDetector, RichContent};
+use std::collections::HashMap;
+use std::path::Path;
+
+use positronic_script::detector::{DetectorSet, PluginFault};
+
+use crate::holodeck::{ContentDetector, ContentType, RichContent, Scored};
 
 /// Convenience wrapper so the UI overlay can detect rich content from visible output.
 pub fn detect_rich(text: &str) -> RichContent {
     ContentDetector::detect_and_parse(text)
 }
+
+/// The built-in detectors that find something richer than text.
+pub const BUILTIN_DETECTORS: [ContentType; 3] = [ContentType::Json, ContentType::Csv, ContentType::Markdown];
+
+/// The session's detectors: the built-ins, the plugins once they have
+/// loaded, and how often each found an entry (`!holodeck detectors`).
+#[derive(Debug, Default)]
+pub struct Detectors {
+    plugins: DetectorSet,
+    hits: HashMap<String, u64>,
+    /// The last text detected and the result: the screen is detected on
+    /// every update, and plugins shouldn't run again for the same text.
+    last: Option<(String, Scored)>,
+}
+
+impl Detectors {
+    pub fn install(&mut self, plugins: DetectorSet) {
+        self.plugins = plugins;
+        self.last = None;
+    }
+
+    pub fn plugin_count(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// `ContentDetector::detect_scored` over the installed plugins.
+    pub fn detect(&mut self, text: &str) -> Scored {
+        if let Some((last, scored)) = &self.last
+            && last == text
+        {
+            return scored.clone();
+        }
+        let scored = ContentDetector::detect_scored(text, &mut self.plugins);
+        self.last = Some((text.to_string(), scored.clone()));
+        scored
+    }
+
+    /// `detector` found an entry the Holodeck kept.
+    pub fn hit(&mut self, detector: &str) {
+        *self.hits.entry(detector.to_string()).or_insert(0) += 1;
+    }
+
+    /// Plugins disabled since the last call, for the toast.
+    pub fn take_disabled(&mut self) -> Vec<(String, PluginFault)> {
+        self.plugins.take_disabled()
+    }
+
+    /// `!holodeck detectors`: every detector, its hits this session and,
+    /// for a disabled plugin, why.
+    pub fn lines(&self, plugins_dir: &Path) -> Vec<String> {
+        let mut rows: Vec<(String, &str, String)> = BUILTIN_DETECTORS
+            .iter()
+            .map(|ct| (ct.to_string(), "built-in", String::new()))
+            .collect();
+        rows.extend(self.plugins.states().map(|(name, fault)| {
+            let note = fault.map(|f| format!("  ⛔ disabled: {}", f)).unwrap_or_default();
+            (name.to_string(), "plugin", note)
+        }));
+        let width = rows.iter().map(|(name, ..)| name.chars().count()).max().unwrap_or(0);
+        let mut lines = vec!["🔎 Holodeck detectors, hits this session:".to_string()];
+        for (name, kind, note) in &rows {
+            let hits = self.hits.get(name).copied().unwrap_or(0);
+            lines.push(format!("  {:<width$}  {:<8}  {:>4}{}", name, kind, hits, note, width = width));
+        }
+        if self.plugins.is_empty() {
+            lines.push(format!("  No detector plugins: put them in {}", plugins_dir.display()));
+        }
+        lines
+    }
+}
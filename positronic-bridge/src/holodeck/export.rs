@@ -0,0 +1,141 @@
+//! `!save`: write a Holodeck entry to disk as CSV, JSON or the raw text.
+//!
+//! Pure apart from the final write, so the command parser, the format
+//! choice and the overwrite guard are testable without the UI.
+
+use std::path::{Path, PathBuf};
+
+use super::{HolodeckEntry, RichContent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveFormat {
+    Csv,
+    Json,
+    Raw,
+}
+
+impl SaveFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(SaveFormat::Csv),
+            "json" => Some(SaveFormat::Json),
+            "raw" | "txt" | "text" => Some(SaveFormat::Raw),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SaveFormat::Csv => "csv",
+            SaveFormat::Json => "json",
+            SaveFormat::Raw => "raw",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Csv => "csv",
+            SaveFormat::Json => "json",
+            SaveFormat::Raw => "txt",
+        }
+    }
+
+    /// The format a path asks for by its extension, if any.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = Path::new(path).extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "csv" => Some(SaveFormat::Csv),
+            "json" => Some(SaveFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// What an entry saves as when neither `--format` nor the path says.
+    pub fn default_for(entry: &HolodeckEntry) -> Self {
+        match entry.content {
+            RichContent::Table(_) => SaveFormat::Csv,
+            RichContent::Json(_) | RichContent::Custom { .. } => SaveFormat::Json,
+            _ => SaveFormat::Raw,
+        }
+    }
+}
+
+pub const SAVE_USAGE: &str = "Usage: !save <path> [--format csv|json|raw] [--entry <id>] [--force]";
+
+/// A parsed `!save` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveCommand {
+    pub path: String,
+    pub format: Option<SaveFormat>,
+    pub entry: Option<u64>,
+    pub force: bool,
+}
+
+impl SaveCommand {
+    /// Parse `!save ...`; the error is a message for the user.
+    pub fn parse(cmd: &str) -> Result<SaveCommand, String> {
+        let mut parts = cmd.split_whitespace();
+        if parts.next() != Some("!save") {
+            return Err(SAVE_USAGE.to_string());
+        }
+        let mut path = None;
+        let mut format = None;
+        let mut entry = None;
+        let mut force = false;
+        while let Some(part) = parts.next() {
+            match part {
+                "--force" | "-f" => force = true,
+                "--format" => {
+                    let name = parts.next().ok_or(SAVE_USAGE)?;
+                    format = Some(SaveFormat::parse(name).ok_or_else(|| format!("❌ Unknown format '{}' (csv, json or raw)", name))?);
+                }
+                "--entry" => {
+                    let id = parts.next().ok_or(SAVE_USAGE)?;
+                    entry = Some(id.trim_start_matches('#').parse().map_err(|_| format!("❌ Bad entry id '{}'", id))?);
+                }
+                p if path.is_none() && !p.starts_with("--") => path = Some(p.to_string()),
+                _ => return Err(SAVE_USAGE.to_string()),
+            }
+        }
+        let path = path.ok_or(SAVE_USAGE)?;
+        Ok(SaveCommand { path, format, entry, force })
+    }
+}
+
+/// `holodeck-3-20260314-093000.csv`, for saves from the overlay.
+pub fn generated_name(entry: &HolodeckEntry, format: SaveFormat, now: chrono::DateTime<chrono::Local>) -> String {
+    format!("holodeck-{}-{}.{}", entry.id, now.format("%Y%m%d-%H%M%S"), format.extension())
+}
+
+/// The entry rendered in `format`; an error if the content has no such form.
+pub fn render(entry: &HolodeckEntry, format: SaveFormat) -> Result<String, String> {
+    match (format, &entry.content) {
+        (SaveFormat::Raw, _) => Ok(entry.raw.clone()),
+        (SaveFormat::Csv, RichContent::Table(df)) => Ok(df.to_csv_string()),
+        (SaveFormat::Json, RichContent::Table(df)) => Ok(df.to_json_records()),
+        (SaveFormat::Json, RichContent::Json(j) | RichContent::Custom { json: j, .. }) => Ok(format!("{}\n", j.pretty)),
+        (format, _) => Err(format!(
+            "❌ Entry #{} is {}; it cannot be saved as {}",
+            entry.id,
+            entry.content_type,
+            format.name()
+        )),
+    }
+}
+
+/// Write `entry` to `path` (relative to `cwd`, `~` expanded). Refuses to
+/// replace an existing file unless `force`. Returns the path and bytes written.
+pub fn save(entry: &HolodeckEntry, path: &str, format: SaveFormat, force: bool, cwd: &Path) -> Result<(PathBuf, usize), String> {
+    write(&render(entry, format)?, path, force, cwd)
+}
+
+/// Write an already rendered `body`, as `save` does; for a body changed
+/// on the way out (`!present`).
+pub fn write(body: &str, path: &str, force: bool, cwd: &Path) -> Result<(PathBuf, usize), String> {
+    let path = cwd.join(crate::cwd::resolve_tilde(path));
+    if path.exists() && !force {
+        return Err(format!("❌ {} already exists (add --force to overwrite)", path.display()));
+    }
+    std::fs::write(&path, body).map_err(|e| format!("❌ Could not write {}: {}", path.display(), e))?;
+    Ok((path, body.len()))
+}
@@ -7,15 +7,24 @@
 // - CSV parsing into DataFrames (headers + typed rows)
 // - JSON pretty-printing and structure analysis
 // - Image metadata extraction (Sixel/iTerm2 inline protocols)
-// - Chart specifications for inline plotting
+// - Chart specifications for inline plotting, with time axes and log
+//   scales (`!chart`)
 // - Markdown structure detection
+// - Formats taught by WASM detector plugins (`RichContent::Custom`)
+// - CSV/JSON export for `!save`
+// - Downloads from `!fetch`, read as the format asked for and tagged
+//   with their source URL
 
 pub mod renderer;
 pub mod events;
 pub mod protocol;
 pub mod layout;
-pub(crate) mod detect;
+pub mod export;
+pub mod chart;
+pub mod detect;
 
+use positronic_core::fetch::FetchAs;
+use positronic_script::detector::DetectorSet;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
@@ -63,6 +72,9 @@ pub enum RichContent {
     Image(ImageMeta),
     Chart(ChartSpec),
     Markdown(MarkdownContent),
+    /// A detector plugin's parse (see `positronic_script::detector`),
+    /// shown as a JSON tree.
+    Custom { plugin: String, json: JsonContent, summary: String },
 }
 
 impl RichContent {
@@ -74,6 +86,7 @@ impl RichContent {
             RichContent::Image(_) => ContentType::Image,
             RichContent::Chart(_) => ContentType::PlainText,
             RichContent::Markdown(_) => ContentType::Markdown,
+            RichContent::Custom { .. } => ContentType::Json,
         }
     }
 
@@ -85,8 +98,27 @@ impl RichContent {
             RichContent::Image(_) => 0,
             RichContent::Chart(_) => 0,
             RichContent::Markdown(md) => md.source.len(),
+            RichContent::Custom { json, .. } => json.pretty.len(),
         }
     }
+
+    /// Worth opening in the overlay and keeping as an entry on its own.
+    pub fn is_structured(&self) -> bool {
+        matches!(self, RichContent::Table(_) | RichContent::Json(_) | RichContent::Custom { .. })
+    }
+
+    /// `plugin`'s answer as content, `None` if it isn't JSON. The summary
+    /// names the plugin and the document's shape.
+    pub fn custom(plugin: &str, json: &str) -> Option<Self> {
+        let json = JsonContent::parse(json)?;
+        let shape = match (json.is_array, json.pretty.starts_with('{')) {
+            (true, _) => format!("{} items", json.top_level_count),
+            (false, true) => format!("{} fields", json.top_level_count),
+            (false, false) => "a value".to_string(),
+        };
+        let summary = format!("{} · {}", plugin, shape);
+        Some(RichContent::Custom { plugin: plugin.to_string(), json, summary })
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════
@@ -134,7 +166,7 @@ fn json_depth(value: &serde_json::Value) -> usize {
 // DataFrame (CSV/TSV)
 // ═══════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DataFrame {
     pub headers: Vec<String>,
     pub rows: Vec<Vec<CellValue>>,
@@ -142,21 +174,145 @@ pub struct DataFrame {
 }
 
 impl DataFrame {
+    /// Parse delimited text. Quoted fields may hold the delimiter, doubled
+    /// quotes and line breaks; their contents are kept verbatim.
     pub fn parse_csv(text: &str) -> Option<Self> {
         let delimiter = Delimiter::detect(text)?;
-        let lines: Vec<&str> = text.lines().collect();
-        if lines.len() < 2 {
+        let mut records = CsvRecords::new(text, delimiter.ch);
+        let headers: Vec<String> = records.next()?.into_iter().map(|f| f.text).collect();
+        let rows: Vec<Vec<CellValue>> = records
+            .map(|record| record.into_iter().map(CsvField::into_cell).collect())
+            .collect();
+        if rows.is_empty() {
             return None;
         }
-        let headers: Vec<String> = lines[0].split(delimiter.ch).map(|s| s.trim().to_string()).collect();
-        let mut rows = Vec::new();
-        for line in &lines[1..] {
-            let cells: Vec<CellValue> = line.split(delimiter.ch).map(|s| CellValue::parse(s.trim())).collect();
-            rows.push(cells);
-        }
         Some(Self { headers, rows, delimiter: delimiter.ch })
     }
 
+    /// The frame as delimited text, quoting fields that hold the delimiter,
+    /// quotes, line breaks or edge whitespace. `parse_csv` reads it back to
+    /// an identical frame.
+    pub fn to_csv_string(&self) -> String {
+        let mut out = String::new();
+        let header: Vec<String> = self.headers.iter().map(|h| self.quote_field(h)).collect();
+        out.push_str(&header.join(&self.delimiter.to_string()));
+        out.push('\n');
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|c| self.quote_field(&c.to_field())).collect();
+            out.push_str(&cells.join(&self.delimiter.to_string()));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The rows as a pretty JSON array of `{header: value}` objects. Empty
+    /// cells are `null`; cells past the last header get `columnN` keys.
+    pub fn to_json_records(&self) -> String {
+        let records: Vec<serde_json::Value> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut obj = serde_json::Map::new();
+                for (i, cell) in row.iter().enumerate() {
+                    let key = self.headers.get(i).cloned().unwrap_or_else(|| format!("column{}", i + 1));
+                    obj.insert(key, cell.to_json());
+                }
+                for header in self.headers.iter().skip(row.len()) {
+                    obj.entry(header.clone()).or_insert(serde_json::Value::Null);
+                }
+                serde_json::Value::Object(obj)
+            })
+            .collect();
+        serde_json::to_string_pretty(&records).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn quote_field(&self, field: &str) -> String {
+        let needs_quotes = field.contains(self.delimiter)
+            || field.contains(['"', '\n', '\r'])
+            || field.trim() != field;
+        if needs_quotes {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    /// A native command's table (`!history`, `!top`…), typed as the
+    /// command built it. Comma-delimited for `!save`.
+    pub fn from_native(frame: &positronic_core::native::DataFrame) -> Self {
+        use positronic_core::native::Cell;
+        let rows = frame
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Cell::Text(s) => CellValue::Text(s.clone()),
+                        Cell::Integer(i) => CellValue::Integer(*i),
+                        Cell::Float(f) => CellValue::Float(*f),
+                        Cell::Bool(b) => CellValue::Bool(*b),
+                        Cell::Empty => CellValue::Empty,
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { headers: frame.headers().into_iter().map(str::to_string).collect(), rows, delimiter: ',' }
+    }
+
+    /// Whether column `col` holds numbers or timestamps (empty cells
+    /// aside), at least one.
+    pub fn is_column_numeric(&self, col: usize) -> bool {
+        let mut cells = self.column(col).filter(|c| **c != CellValue::Empty).peekable();
+        cells.peek().is_some()
+            && cells.all(|c| matches!(c, CellValue::Integer(_) | CellValue::Float(_) | CellValue::DateTime { .. }))
+    }
+
+    /// Whether column `col` is a time column: ISO 8601 timestamps, or
+    /// epoch seconds/millis under a header that says so. Big numbers
+    /// without such a header are taken for ids, not times.
+    pub fn is_time_column(&self, col: usize) -> bool {
+        let epochs = self.headers.get(col).is_some_and(|h| chart::header_hints_time(h));
+        let mut cells = self.column(col).filter(|c| **c != CellValue::Empty).peekable();
+        cells.peek().is_some() && cells.all(|c| chart::cell_millis(c, epochs).is_some())
+    }
+
+    /// Column `col` as numbers, `None` where a cell isn't one. With
+    /// `time`, timestamps and epoch numbers become epoch millis.
+    pub fn column_values(&self, col: usize, time: bool) -> Vec<Option<f64>> {
+        self.rows
+            .iter()
+            .map(|row| match row.get(col) {
+                Some(cell) if time => chart::cell_millis(cell, true).map(|ms| ms as f64),
+                Some(CellValue::Integer(i)) => Some(*i as f64),
+                Some(CellValue::Float(f)) => Some(*f),
+                Some(CellValue::DateTime { millis, .. }) => Some(*millis as f64),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Count, min, max and mean of a numeric column; a time column's are
+    /// in epoch millis.
+    pub fn column_stats(&self, col: usize) -> Option<ColumnStats> {
+        if !self.is_column_numeric(col) && !self.is_time_column(col) {
+            return None;
+        }
+        let values: Vec<f64> = self.column_values(col, self.is_time_column(col)).into_iter().flatten().collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(ColumnStats {
+            count: values.len(),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+        })
+    }
+
+    fn column(&self, col: usize) -> impl Iterator<Item = &CellValue> {
+        self.rows.iter().filter_map(move |row| row.get(col))
+    }
+
     pub fn row_count(&self) -> usize { self.rows.len() }
     pub fn col_count(&self) -> usize { self.headers.len() }
     pub fn estimated_char_size(&self) -> usize {
@@ -168,12 +324,23 @@ impl DataFrame {
     }
 }
 
+/// What `DataFrame::column_stats` reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum CellValue {
     Text(String),
     Integer(i64),
     Float(f64),
     Bool(bool),
+    /// An ISO 8601 timestamp: epoch millis, and the text as written.
+    DateTime { millis: i64, text: String },
     Empty,
 }
 
@@ -185,6 +352,7 @@ impl CellValue {
         if let Ok(f) = s.parse::<f64>() { return CellValue::Float(f); }
         if s.eq_ignore_ascii_case("true") { return CellValue::Bool(true); }
         if s.eq_ignore_ascii_case("false") { return CellValue::Bool(false); }
+        if let Some(millis) = chart::parse_datetime(s) { return CellValue::DateTime { millis, text: s.to_string() }; }
         CellValue::Text(s.to_string())
     }
 
@@ -194,9 +362,36 @@ impl CellValue {
             CellValue::Integer(i) => format!("{}", i).len(),
             CellValue::Float(f) => format!("{}", f).len(),
             CellValue::Bool(b) => if *b { 4 } else { 5 },
+            CellValue::DateTime { text, .. } => text.len(),
             CellValue::Empty => 0,
         }
     }
+
+    /// The cell as CSV field text. Floats keep their decimal point so they
+    /// parse back as floats.
+    pub fn to_field(&self) -> String {
+        match self {
+            CellValue::Text(s) => s.clone(),
+            CellValue::Integer(i) => i.to_string(),
+            CellValue::Float(f) => format!("{:?}", f),
+            CellValue::Bool(b) => b.to_string(),
+            CellValue::DateTime { text, .. } => text.clone(),
+            CellValue::Empty => String::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            CellValue::Text(s) => serde_json::Value::String(s.clone()),
+            CellValue::Integer(i) => serde_json::Value::from(*i),
+            CellValue::Float(f) => serde_json::Number::from_f64(*f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            CellValue::Bool(b) => serde_json::Value::Bool(*b),
+            CellValue::DateTime { text, .. } => serde_json::Value::String(text.clone()),
+            CellValue::Empty => serde_json::Value::Null,
+        }
+    }
 }
 
 struct Delimiter {
@@ -204,12 +399,12 @@ struct Delimiter {
 }
 
 impl Delimiter {
+    /// The first candidate that splits the leading records into the same
+    /// number (more than one) of fields.
     fn detect(text: &str) -> Option<Delimiter> {
-        let lines: Vec<&str> = text.lines().take(5).collect();
-        if lines.len() < 2 { return None; }
         for &ch in &[',', '\t', '|', ';'] {
-            let counts: Vec<usize> = lines.iter().map(|l| l.matches(ch).count()).collect();
-            if counts[0] > 0 && counts.iter().all(|&c| c == counts[0]) {
+            let counts: Vec<usize> = CsvRecords::new(text, ch).take(5).map(|r| r.len()).collect();
+            if counts.len() >= 2 && counts[0] > 1 && counts.iter().all(|&c| c == counts[0]) {
                 return Some(Delimiter { ch });
             }
         }
@@ -217,8 +412,90 @@ impl Delimiter {
     }
 }
 
+struct CsvField {
+    text: String,
+    quoted: bool,
+}
+
+impl CsvField {
+    /// Unquoted fields are trimmed and typed; quoted ones keep edge
+    /// whitespace, as text.
+    fn into_cell(self) -> CellValue {
+        if self.quoted && self.text.trim() != self.text {
+            CellValue::Text(self.text)
+        } else {
+            CellValue::parse(&self.text)
+        }
+    }
+}
+
+/// Records of delimited text, RFC 4180 style. A quote opens a quoted field
+/// only at the start of a field; elsewhere it is literal.
+struct CsvRecords<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    delimiter: char,
+}
+
+impl<'a> CsvRecords<'a> {
+    fn new(text: &'a str, delimiter: char) -> Self {
+        Self { chars: text.chars().peekable(), delimiter }
+    }
+
+    fn end_field(record: &mut Vec<CsvField>, field: &mut String, quoted: &mut bool) {
+        let text = std::mem::take(field);
+        let text = if *quoted { text } else { text.trim().to_string() };
+        record.push(CsvField { text, quoted: *quoted });
+        *quoted = false;
+    }
+}
+
+impl Iterator for CsvRecords<'_> {
+    type Item = Vec<CsvField>;
+
+    fn next(&mut self) -> Option<Vec<CsvField>> {
+        self.chars.peek()?;
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        while let Some(c) = self.chars.next() {
+            match c {
+                '"' if !quoted && field.trim().is_empty() => {
+                    quoted = true;
+                    field.clear();
+                    while let Some(c) = self.chars.next() {
+                        if c == '"' {
+                            if self.chars.peek() == Some(&'"') {
+                                self.chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                        field.push(c);
+                    }
+                    // Anything between the closing quote and the delimiter.
+                    while let Some(&c) = self.chars.peek() {
+                        if c == self.delimiter || c == '\n' || c == '\r' {
+                            break;
+                        }
+                        self.chars.next();
+                        if !c.is_whitespace() {
+                            field.push(c);
+                        }
+                    }
+                }
+                c if c == self.delimiter => Self::end_field(&mut record, &mut field, &mut quoted),
+                '\r' if self.chars.peek() == Some(&'\n') => {}
+                '\n' => break,
+                c => field.push(c),
+            }
+        }
+        Self::end_field(&mut record, &mut field, &mut quoted);
+        Some(record)
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════
-// Image / Chart (stubs for future rendering)
+// Image (stub for future rendering) / Chart (see `chart`)
 // ═══════════════════════════════════════════════════════════════════
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -231,12 +508,41 @@ pub struct ImageMeta {
     path: ()
 }
 
+impl ImageMeta {
+    /// A downloaded image: its media type as the protocol, and its size
+    /// when the `image` crate can read the header.
+    pub fn fetched(media_type: &str, bytes: &[u8]) -> Self {
+        let dimensions = image::ImageReader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        Self {
+            protocol: media_type.to_string(),
+            width: dimensions.map(|(w, _)| w),
+            height: dimensions.map(|(_, h)| h),
+            data_offset: 0,
+            data_len: bytes.len(),
+            path: (),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChartSpec {
     pub chart_type: String,
     pub title: Option<String>,
     pub x_label: Option<String>,
     pub y_label: Option<String>,
+    #[serde(default)]
+    pub x_axis: chart::AxisKind,
+    #[serde(default)]
+    pub y_scale: chart::YScale,
+    /// (x, y); a time axis's x is epoch millis.
+    #[serde(default)]
+    pub points: Vec<(f64, f64)>,
+    /// Shown under the chart, e.g. values clamped on a log scale.
+    #[serde(default)]
+    pub notes: Vec<String>,
 }
 
 // ═══════════════════════════════════════════════════════════════════
@@ -348,7 +654,36 @@ fn parse_ordered_item(s: &str) -> Option<MarkdownElement> {
 
 pub struct ContentDetector;
 
+/// What `detect_scored` made of some text, and which detector did.
+#[derive(Debug, Clone)]
+pub struct Scored {
+    pub content: RichContent,
+    /// A built-in's content type (`json`, `csv`, `markdown`, `text`) or
+    /// the plugin's name.
+    pub detector: String,
+    /// 100 for a built-in's parse, 0 for plain text, the plugin's own score.
+    pub score: u8,
+}
+
 impl ContentDetector {
+    /// `detect_and_parse`, then, when that finds only text, the detector
+    /// plugins in `plugins`: the surest one whose parse succeeds makes
+    /// the content `RichContent::Custom`.
+    pub fn detect_scored(text: &str, plugins: &mut DetectorSet) -> Scored {
+        let content = Self::detect_and_parse(text);
+        if !matches!(content, RichContent::Text(_)) || plugins.is_empty() || text.trim().is_empty() {
+            let score = if matches!(content, RichContent::Text(_)) { 0 } else { 100 };
+            return Scored { detector: content.content_type().to_string(), content, score };
+        }
+        let found = plugins
+            .detect(text)
+            .and_then(|found| Some((RichContent::custom(&found.plugin, &found.json)?, found)));
+        match found {
+            Some((content, found)) => Scored { content, detector: found.plugin, score: found.score },
+            None => Scored { content, detector: ContentType::PlainText.to_string(), score: 0 },
+        }
+    }
+
     pub fn detect(text: &str) -> ContentType {
         let trimmed = text.trim();
         if trimmed.is_empty() { return ContentType::PlainText; }
@@ -380,14 +715,20 @@ impl ContentDetector {
         }
     }
 
+    /// `text` read as the format `!fetch --as` (or a `Content-Type`) names;
+    /// `None` for `Auto`, or when it doesn't parse as that.
+    pub fn parse_as(text: &str, format: FetchAs) -> Option<RichContent> {
+        match format {
+            FetchAs::Auto => None,
+            FetchAs::Json => JsonContent::parse(text).map(RichContent::Json),
+            FetchAs::Csv => DataFrame::parse_csv(text).map(RichContent::Table),
+            FetchAs::Markdown => Some(RichContent::Markdown(MarkdownContent::parse(text))),
+        }
+    }
+
+    /// Same heuristic the PTY pump uses to quarantine binary output.
     pub fn looks_binary(text: &str) -> bool {
-        if text.is_empty() { return false; }
-        let total = text.len().min(1024);
-        let sample = &text[..total];
-        let non_printable = sample.bytes()
-            .filter(|b| *b < 0x20 && *b != b'\n' && *b != b'\r' && *b != b'\t' && *b != 0x1B)
-            .count();
-        non_printable * 10 > total
+        positronic_core::term::binary::looks_binary(text.as_bytes())
     }
 
     pub fn has_image_protocol(text: &str) -> bool {
@@ -402,8 +743,6 @@ impl ContentDetector {
     }
 
     pub fn looks_like_csv(text: &str) -> bool {
-        let lines: Vec<&str> = text.lines().collect();
-        if lines.len() < 2 { return false; }
         Delimiter::detect(text).is_some()
     }
 
@@ -443,6 +782,23 @@ pub struct HolodeckEntry {
     pub tags: HashMap<String, String>,
 }
 
+/// The tag holding where an entry came from (`!fetch`'s URL).
+pub const SOURCE_TAG: &str = "source";
+
+impl HolodeckEntry {
+    pub fn source(&self) -> Option<&str> {
+        self.tags.get(SOURCE_TAG).map(String::as_str)
+    }
+
+    /// Its size: the image's bytes, else the content's characters.
+    pub fn size(&self) -> usize {
+        match &self.content {
+            RichContent::Image(meta) => meta.data_len,
+            content => content.char_size(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct HolodeckManager {
     entries: Vec<HolodeckEntry>,
@@ -486,6 +842,7 @@ impl HolodeckManager {
     pub fn get(&self, id: u64) -> Option<&HolodeckEntry> { self.entries.iter().find(|e| e.id == id) }
     pub fn get_mut(&mut self, id: u64) -> Option<&mut HolodeckEntry> { self.entries.iter_mut().find(|e| e.id == id) }
     pub fn latest(&self) -> Option<&HolodeckEntry> { self.entries.last() }
+    pub fn entries(&self) -> &[HolodeckEntry] { &self.entries }
     pub fn remove(&mut self, id: u64) -> bool { let before = self.entries.len(); self.entries.retain(|e| e.id != id); self.entries.len() < before }
     pub fn len(&self) -> usize { self.entries.len() }
     pub fn is_empty(&self) -> bool { self.entries.is_empty() }
@@ -502,6 +859,28 @@ impl HolodeckManager {
     pub fn auto_detect_enabled(&self) -> bool { self.auto_detect }
     pub fn clear(&mut self) { self.entries.clear(); }
 
+    /// `!holodeck list`: one line per entry, oldest first, with its type,
+    /// size and source.
+    pub fn list_lines(&self) -> Vec<String> {
+        if self.entries.is_empty() {
+            return vec!["⚡ The Holodeck is empty".to_string()];
+        }
+        let mut lines = vec![format!("⚡ Holodeck: {}", self.stats())];
+        for entry in &self.entries {
+            let mut line = format!(
+                "  #{:<4} {:<9} {:>9}",
+                entry.id,
+                entry.content_type.to_string(),
+                crate::helpers::format_bytes(entry.size() as u64)
+            );
+            if let Some(source) = entry.source() {
+                line.push_str(&format!("  from {}", source));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
     pub fn stats(&self) -> HolodeckStats {
         let mut type_counts = HashMap::new();
         let mut total_chars = 0;
@@ -1,6 +1,12 @@
+use crate::holodeck::chart;
+use crate::holodeck::export::SaveFormat;
 use crate::holodeck::{DataFrame, ImageMeta, JsonContent, MarkdownContent, RichContent};
 use uuid::Uuid;
 
+/// Plot cells of a chart in the Holodeck panel.
+pub const CHART_WIDTH: usize = 60;
+pub const CHART_HEIGHT: usize = 12;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rect {
     pub x: f32,
@@ -19,6 +25,8 @@ impl Rect {
 pub enum Action {
     CopyText(String),
     RunCommand(String),
+    /// Save the latest Holodeck entry under a generated name.
+    Save(SaveFormat),
     None,
 }
 
@@ -53,12 +61,13 @@ impl HolodeckDoc {
 
     pub fn from_rich(rich: &RichContent) -> Self {
         match rich {
-            RichContent::Json(j) => doc_from_json(j),
+            RichContent::Json(j) => doc_from_json(j, "Holodeck · JSON"),
+            RichContent::Custom { json, summary, .. } => doc_from_json(json, &format!("Holodeck · {}", summary)),
             RichContent::Table(df) => doc_from_table(df),
             RichContent::Image(img) => doc_from_image(img),
             RichContent::Markdown(md) => doc_from_markdown(md),
             RichContent::Text(t) => doc_from_text(t),
-            RichContent::Chart(_) => doc_from_text("📊 Chart: (renderer not wired yet)"),
+            RichContent::Chart(spec) => doc_from_text(&chart::render_text(spec, CHART_WIDTH, CHART_HEIGHT).join("\n")),
         }
     }
 }
@@ -84,11 +93,11 @@ fn doc_from_text(text: &str) -> HolodeckDoc {
     HolodeckDoc { nodes }
 }
 
-fn doc_from_json(j: &JsonContent) -> HolodeckDoc {
+fn doc_from_json(j: &JsonContent, title: &str) -> HolodeckDoc {
     let mut nodes = Vec::new();
     nodes.push(Node {
         id: Uuid::new_v4(),
-        kind: NodeKind::Panel { title: "Holodeck · JSON".into() },
+        kind: NodeKind::Panel { title: title.into() },
         rect: Rect { x: 0.0, y: 0.0, w: 0.0, h: 0.0 },
     });
 
@@ -101,6 +110,15 @@ fn doc_from_json(j: &JsonContent) -> HolodeckDoc {
         rect: Rect { x: 0.0, y: 0.0, w: 0.0, h: 0.0 },
     });
 
+    nodes.push(Node {
+        id: Uuid::new_v4(),
+        kind: NodeKind::Button {
+            label: "Save JSON".into(),
+            action: Action::Save(SaveFormat::Json),
+        },
+        rect: Rect { x: 0.0, y: 0.0, w: 0.0, h: 0.0 },
+    });
+
     nodes.push(Node {
         id: Uuid::new_v4(),
         kind: NodeKind::Json {
@@ -131,6 +149,15 @@ fn doc_from_table(df: &DataFrame) -> HolodeckDoc {
         rect: Rect { x: 0.0, y: 0.0, w: 0.0, h: 0.0 },
     });
 
+    nodes.push(Node {
+        id: Uuid::new_v4(),
+        kind: NodeKind::Button {
+            label: "Save CSV".into(),
+            action: Action::Save(SaveFormat::Csv),
+        },
+        rect: Rect { x: 0.0, y: 0.0, w: 0.0, h: 0.0 },
+    });
+
     nodes.push(Node {
         id: Uuid::new_v4(),
         kind: NodeKind::Table {
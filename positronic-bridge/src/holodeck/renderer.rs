@@ -1,6 +1,6 @@
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
 use crate::gfx::text::TextRegion;
 use crate::renderer::{ColoredSpan, Rgba, ThemeName};
 use super::layout::layout_doc;
@@ -27,6 +27,7 @@ pub fn draw_overlay(
         w: panel.w,
         h: panel.h,
         color: Rgba::new(0.06, 0.07, 0.09, 0.92),
+        layer: QuadLayer::Overlay,
     });
 
     // Border
@@ -36,6 +37,7 @@ pub fn draw_overlay(
         w: panel.w,
         h: 1.0,
         color: Rgba::new(0.25, 0.28, 0.32, 1.0),
+        layer: QuadLayer::Overlay,
     });
 
     for n in &doc.nodes {
@@ -48,6 +50,7 @@ pub fn draw_overlay(
                     w: panel.w,
                     h: n.rect.h,
                     color: Rgba::new(0.08, 0.09, 0.11, 0.95),
+                    layer: QuadLayer::Overlay,
                 });
 
                 push_text(text, n.rect, vec![ColoredSpan::new(title, theme.status_fg())]);
@@ -59,6 +62,7 @@ pub fn draw_overlay(
                     w: n.rect.w,
                     h: n.rect.h,
                     color: Rgba::new(0.12, 0.13, 0.16, 0.95),
+                    layer: QuadLayer::Overlay,
                 });
                 push_text(
                     text,
@@ -107,6 +111,7 @@ pub fn draw_overlay(
                     w: n.rect.w,
                     h: n.rect.h,
                     color: Rgba::new(0.10, 0.11, 0.14, 0.95),
+                    layer: QuadLayer::Overlay,
                 });
                 let caption = format!(
                     "{}\n{:?} ({}x{})",
@@ -107,6 +107,10 @@ pub struct InputEditor {
     history: Vec<String>,
     history_cursor: Option<usize>,
     history_stash: String,
+    /// The draft's undo and redo stacks while history is being browsed.
+    history_stash_undo: (Vec<UndoEntry>, Vec<UndoEntry>),
+    /// What recalled entries must start with; empty browses everything.
+    history_prefix: String,
 }
 
 impl InputEditor {
@@ -116,6 +120,7 @@ impl InputEditor {
             edit_mode: EditMode::Insert, vim_mode: VimMode::Disabled,
             kill_ring: Vec::new(), undo_stack: Vec::new(), redo_stack: Vec::new(),
             max_undo: 100, history: Vec::new(), history_cursor: None, history_stash: String::new(),
+            history_stash_undo: (Vec::new(), Vec::new()), history_prefix: String::new(),
         }
     }
 
@@ -415,13 +420,30 @@ impl InputEditor {
         self.history.push(command.to_string());
         self.history_cursor = None;
         self.history_stash.clear();
+        self.history_stash_undo = (Vec::new(), Vec::new());
+        self.history_prefix.clear();
+    }
+
+    /// Recalled entries get undo stacks of their own, so editing one
+    /// never clobbers the draft's; leaving history brings them back.
+    fn stash_undo(&mut self) {
+        self.history_stash_undo = (std::mem::take(&mut self.undo_stack), std::mem::take(&mut self.redo_stack));
+    }
+
+    fn restore_undo(&mut self) {
+        (self.undo_stack, self.redo_stack) = std::mem::take(&mut self.history_stash_undo);
     }
 
     pub fn history_up(&mut self) {
+        if self.history_cursor.is_some() && !self.history_prefix.is_empty() {
+            let prefix = self.history_prefix.clone();
+            return self.history_up_with_prefix(&prefix);
+        }
         if self.history.is_empty() { return; }
         match self.history_cursor {
             None => {
                 self.history_stash = self.value.clone();
+                self.stash_undo();
                 let idx = self.history.len() - 1;
                 self.history_cursor = Some(idx);
                 self.set_value(&self.history[idx].clone());
@@ -430,32 +452,76 @@ impl InputEditor {
                 let new_idx = idx - 1;
                 self.history_cursor = Some(new_idx);
                 self.set_value(&self.history[new_idx].clone());
+                self.undo_stack.clear();
+                self.redo_stack.clear();
             }
             _ => {}
         }
     }
 
+    /// Like `history_up`, visiting only entries that start with `prefix`
+    /// (case-sensitive), as zsh's history-beginning-search does; entries
+    /// equal to the line shown are skipped. With no match nothing changes.
+    /// Up and Down keep the prefix until Down passes the newest match.
+    pub fn history_up_with_prefix(&mut self, prefix: &str) {
+        if prefix.is_empty() {
+            self.history_prefix.clear();
+            return self.history_up();
+        }
+        let from = self.history_cursor.unwrap_or(self.history.len());
+        let Some(idx) = (0..from).rev().find(|&i| self.history_match(i, prefix)) else { return };
+        if self.history_cursor.is_none() {
+            self.history_stash = self.value.clone();
+            self.stash_undo();
+        } else {
+            self.undo_stack.clear();
+            self.redo_stack.clear();
+        }
+        self.history_prefix = prefix.to_string();
+        self.history_cursor = Some(idx);
+        self.set_value(&self.history[idx].clone());
+    }
+
+    fn history_match(&self, idx: usize, prefix: &str) -> bool {
+        let entry = &self.history[idx];
+        prefix.is_empty() || (entry.starts_with(prefix) && *entry != self.value)
+    }
+
     pub fn history_down(&mut self) {
-        match self.history_cursor {
-            Some(idx) => {
-                if idx + 1 < self.history.len() {
-                    let new_idx = idx + 1;
-                    self.history_cursor = Some(new_idx);
-                    self.set_value(&self.history[new_idx].clone());
-                } else {
-                    self.history_cursor = None;
-                    let stash = self.history_stash.clone();
-                    self.set_value(&stash);
-                    self.history_stash.clear();
-                }
+        let Some(idx) = self.history_cursor else { return };
+        let prefix = self.history_prefix.clone();
+        match (idx + 1..self.history.len()).find(|&i| self.history_match(i, &prefix)) {
+            Some(new_idx) => {
+                self.history_cursor = Some(new_idx);
+                self.set_value(&self.history[new_idx].clone());
+                self.undo_stack.clear();
+                self.redo_stack.clear();
+            }
+            None => {
+                self.history_cursor = None;
+                let stash = self.history_stash.clone();
+                self.set_value(&stash);
+                self.history_stash.clear();
+                self.history_prefix.clear();
+                self.restore_undo();
             }
-            None => {}
         }
     }
 
+    /// The prefix recalled entries are filtered by, while browsing.
+    pub fn history_prefix(&self) -> Option<&str> {
+        (self.history_cursor.is_some() && !self.history_prefix.is_empty()).then_some(self.history_prefix.as_str())
+    }
+
     pub fn history(&self) -> &[String] { &self.history }
     pub fn history_position(&self) -> Option<usize> { self.history_cursor }
-    pub fn clear_history(&mut self) { self.history.clear(); self.history_cursor = None; self.history_stash.clear(); }
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.history_cursor = None;
+        self.history_stash.clear();
+        self.history_stash_undo = (Vec::new(), Vec::new());
+        self.history_prefix.clear();
+    }
 
     // ─── Edit Mode ───────────────────────────────────────────────
 
@@ -0,0 +1,610 @@
+//! Import of readline `.inputrc` settings.
+//!
+//! Parses the readline init file format — `set` variables, key bindings
+//! in either the quoted (`"\e[1;5C": forward-word`) or the named
+//! (`Control-u: unix-line-discard`) form, and `$if`/`$else`/`$endif`
+//! blocks — and keeps the subset Positronic can honor: the editing mode,
+//! `completion-ignore-case`, and bindings of single keys to the editing
+//! functions the input line implements. Everything else, including
+//! malformed lines, is collected in `skipped` for the summary rather than
+//! failing the import.
+//!
+//! Conditionals follow readline: `term=` matches the whole `$TERM` or the
+//! part before its first `-`, `mode=` tests the editing mode set so far,
+//! and an application name matches only `Positronic`. Version tests are
+//! taken as true; any other test is false.
+//!
+//! No I/O here beyond `load`; `parse` works on text and a `Context`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::input::InputEditor;
+use crate::keymap::{Action, Chord, KeyName, NamedKey};
+
+/// `input.use_inputrc`: apply `~/.inputrc` at startup and on reload.
+pub const USE_INPUTRC_KEY: &str = "input.use_inputrc";
+
+/// The application name `$if` tests match.
+pub const APPLICATION: &str = "Positronic";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditingMode {
+    #[default]
+    Emacs,
+    Vi,
+}
+
+impl EditingMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            EditingMode::Emacs => "emacs",
+            EditingMode::Vi => "vi",
+        }
+    }
+}
+
+/// What `$if` tests are evaluated against.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// `$TERM`, if set.
+    pub term: Option<String>,
+}
+
+impl Context {
+    pub fn from_env() -> Self {
+        Self { term: std::env::var("TERM").ok().filter(|t| !t.is_empty()) }
+    }
+}
+
+/// A line the import could not use, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    /// 1-based.
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} ({})", self.line, self.text, self.reason)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inputrc {
+    /// `set editing-mode`, if the file sets it.
+    pub editing_mode: Option<EditingMode>,
+    /// `set completion-ignore-case`, if the file sets it.
+    pub completion_ignore_case: Option<bool>,
+    /// One chord per action, the first usable binding in the file.
+    pub bindings: Vec<(Chord, Action)>,
+    pub skipped: Vec<Skipped>,
+}
+
+impl Inputrc {
+    /// The action bound to `chord`, if the file binds it.
+    pub fn action_for(&self, chord: &Chord) -> Option<Action> {
+        self.bindings.iter().find(|(c, _)| c == chord).map(|&(_, a)| a)
+    }
+
+    /// Switch `editor` to the editing mode the file asks for.
+    pub fn configure(&self, editor: &mut InputEditor) {
+        match self.editing_mode {
+            Some(EditingMode::Vi) => editor.enable_vim(),
+            Some(EditingMode::Emacs) => editor.disable_vim(),
+            None => {}
+        }
+    }
+
+    /// The import summary: what was taken, then each skipped line.
+    pub fn summary(&self, source: &str) -> Vec<String> {
+        let mut taken = vec![format!("{} binding{}", self.bindings.len(), if self.bindings.len() == 1 { "" } else { "s" })];
+        if let Some(mode) = self.editing_mode {
+            taken.push(format!("editing-mode {}", mode.label()));
+        }
+        if let Some(on) = self.completion_ignore_case {
+            taken.push(format!("completion-ignore-case {}", if on { "on" } else { "off" }));
+        }
+        let mut lines = vec![format!("⌨️  Imported {}: {}", source, taken.join(", "))];
+        for (chord, action) in &self.bindings {
+            lines.push(format!("  {:<14} {}", chord.to_string(), action.name()));
+        }
+        if !self.skipped.is_empty() {
+            lines.push(format!("  Skipped {} line{}:", self.skipped.len(), if self.skipped.len() == 1 { "" } else { "s" }));
+            lines.extend(self.skipped.iter().map(|s| format!("    {}", s)));
+        }
+        lines
+    }
+}
+
+/// `$INPUTRC`, else `~/.inputrc`.
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("INPUTRC").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(Path::new(&home).join(".inputrc"))
+}
+
+/// Read and parse the file at `path` for this terminal.
+pub fn load(path: &Path) -> std::io::Result<Inputrc> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(parse(&text, &Context::from_env()))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Parsing
+// ════════════════════════════════════════════════════════════════════
+
+/// One open `$if`: whether its current branch applies, and whether the
+/// enclosing block does.
+#[derive(Debug, Clone, Copy)]
+struct Branch {
+    active: bool,
+    parent: bool,
+}
+
+pub fn parse(text: &str, ctx: &Context) -> Inputrc {
+    let mut rc = Inputrc::default();
+    let mut mode = EditingMode::default();
+    let mut stack: Vec<Branch> = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let lineno = i + 1;
+        let skip = |rc: &mut Inputrc, reason: &str| {
+            rc.skipped.push(Skipped { line: lineno, text: line.to_string(), reason: reason.to_string() });
+        };
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('$') {
+            let (word, rest) = split_word(directive);
+            let enclosing = stack.last().is_none_or(|b| b.active);
+            match word.to_ascii_lowercase().as_str() {
+                "if" => {
+                    let active = enclosing && test(rest, ctx, mode);
+                    stack.push(Branch { active, parent: enclosing });
+                }
+                "else" => match stack.last_mut() {
+                    Some(branch) => branch.active = branch.parent && !branch.active,
+                    None => skip(&mut rc, "$else without $if"),
+                },
+                "endif" => {
+                    if stack.pop().is_none() {
+                        skip(&mut rc, "$endif without $if");
+                    }
+                }
+                "include" if enclosing => skip(&mut rc, "included files are not followed"),
+                "include" => {}
+                _ => skip(&mut rc, "unknown directive"),
+            }
+            continue;
+        }
+
+        if stack.last().is_some_and(|b| !b.active) {
+            continue;
+        }
+
+        if let Some(rest) = strip_keyword(line, "set") {
+            set_variable(&mut rc, &mut mode, rest).unwrap_or_else(|reason| skip(&mut rc, &reason));
+            continue;
+        }
+
+        match parse_binding(line) {
+            Ok((chord, action)) => {
+                // Files often list one key's sequence for several terminals.
+                if let Some(&(bound, _)) = rc.bindings.iter().find(|&&(_, a)| a == action) {
+                    if bound != chord {
+                        skip(&mut rc, &format!("{} is already on {}", action.name(), bound));
+                    }
+                } else {
+                    rc.bindings.retain(|(c, _)| *c != chord);
+                    rc.bindings.push((chord, action));
+                }
+            }
+            Err(reason) => skip(&mut rc, &reason),
+        }
+    }
+
+    if !stack.is_empty() {
+        let lineno = text.lines().count();
+        rc.skipped.push(Skipped { line: lineno, text: "(end of file)".to_string(), reason: "$if without $endif".to_string() });
+    }
+    rc
+}
+
+/// `word rest`, split at the first whitespace.
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim();
+    match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], text[i..].trim()),
+        None => (text, ""),
+    }
+}
+
+/// The rest of `line` if its first word is `keyword` (any case).
+fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+    let (word, rest) = split_word(line);
+    word.eq_ignore_ascii_case(keyword).then_some(rest)
+}
+
+/// Evaluate an `$if` test.
+fn test(condition: &str, ctx: &Context, mode: EditingMode) -> bool {
+    let condition = condition.trim();
+    if let Some(term) = condition.strip_prefix("term=") {
+        let term = term.trim();
+        return ctx.term.as_deref().is_some_and(|t| t == term || t.split('-').next() == Some(term));
+    }
+    if let Some(wanted) = condition.strip_prefix("mode=") {
+        return wanted.trim().eq_ignore_ascii_case(mode.label());
+    }
+    if condition.starts_with("version") {
+        return true;
+    }
+    if condition.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return condition.eq_ignore_ascii_case(APPLICATION);
+    }
+    false
+}
+
+/// Readline booleans: `on` or `1` (any case), anything else is off; we
+/// reject values that look like neither to catch typos.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "on" | "1" | "true" => Some(true),
+        "off" | "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn set_variable(rc: &mut Inputrc, mode: &mut EditingMode, rest: &str) -> Result<(), String> {
+    let (name, value) = split_word(rest);
+    if name.is_empty() {
+        return Err("set without a variable".to_string());
+    }
+    match name.to_ascii_lowercase().as_str() {
+        "editing-mode" => {
+            let parsed = match value.to_ascii_lowercase().as_str() {
+                "vi" => EditingMode::Vi,
+                "emacs" => EditingMode::Emacs,
+                _ => return Err("expected vi or emacs".to_string()),
+            };
+            *mode = parsed;
+            rc.editing_mode = Some(parsed);
+        }
+        "completion-ignore-case" => {
+            rc.completion_ignore_case = Some(parse_bool(value).ok_or("expected on or off")?);
+        }
+        _ => return Err("unsupported variable".to_string()),
+    }
+    Ok(())
+}
+
+/// `keyseq: function`. Macros and functions we don't implement are
+/// reported, as are sequences that aren't a single key.
+fn parse_binding(line: &str) -> Result<(Chord, Action), String> {
+    let (keys, function) = if let Some(quoted) = line.strip_prefix('"') {
+        let end = closing_quote(quoted).ok_or("unterminated key sequence")?;
+        let after = quoted[end + 1..].trim_start();
+        let function = after.strip_prefix(':').ok_or("expected ':' after the key sequence")?;
+        (decode(&quoted[..end])?, function)
+    } else {
+        let colon = line.find(':').ok_or("not a setting or key binding")?;
+        let name = line[..colon].trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err("not a setting or key binding".to_string());
+        }
+        (key_name(name)?, &line[colon + 1..])
+    };
+
+    let function = function.trim();
+    if function.starts_with('"') || function.starts_with('\'') {
+        return Err("macros are not supported".to_string());
+    }
+    let (function, _) = split_word(function);
+    if function.is_empty() {
+        return Err("no function named".to_string());
+    }
+    let action = function_action(function).ok_or_else(|| format!("{} is not supported", function))?;
+    let chord = chord_for(&keys).ok_or("not a single key")?;
+    if chord.is_typing() {
+        return Err("binding it would shadow typing".to_string());
+    }
+    Ok((chord, action))
+}
+
+/// Byte index of the closing `"` in `text`, skipping escaped quotes.
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The editing functions the input line implements.
+pub fn function_action(name: &str) -> Option<Action> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "backward-char" => Action::CursorLeft,
+        "forward-char" => Action::CursorRight,
+        "beginning-of-line" => Action::LineStart,
+        "end-of-line" => Action::LineEnd,
+        "backward-word" | "shell-backward-word" | "vi-prev-word" => Action::WordLeft,
+        "forward-word" | "shell-forward-word" | "vi-next-word" => Action::WordRight,
+        "backward-kill-word" | "unix-word-rubout" | "shell-backward-kill-word" => Action::KillWordBack,
+        "kill-word" | "shell-kill-word" => Action::KillWordForward,
+        "unix-line-discard" | "backward-kill-line" => Action::KillToStart,
+        "kill-line" => Action::KillToEnd,
+        "previous-history" | "history-search-backward" | "history-substring-search-backward" => Action::HistoryUp,
+        "next-history" | "history-search-forward" | "history-substring-search-forward" => Action::HistoryDown,
+        "complete" | "menu-complete" => Action::TabComplete,
+        "clear-screen" | "clear-display" => Action::ClearScreen,
+        "reverse-search-history" | "forward-search-history" => Action::SearchOpen,
+        _ => return None,
+    })
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Key sequences
+// ════════════════════════════════════════════════════════════════════
+
+const ESC: u8 = 0x1b;
+const DEL: u8 = 0x7f;
+
+/// Decode the inside of a quoted key sequence to the bytes a terminal
+/// would send. `\M-x` becomes ESC x, as with `convert-meta`.
+pub fn decode(seq: &str) -> Result<Vec<u8>, String> {
+    let chars: Vec<char> = seq.chars().collect();
+    let mut i = 0;
+    let mut out = Vec::new();
+    while i < chars.len() {
+        out.extend(decode_one(&chars, &mut i)?);
+    }
+    Ok(out)
+}
+
+fn decode_one(chars: &[char], i: &mut usize) -> Result<Vec<u8>, String> {
+    let c = chars[*i];
+    *i += 1;
+    if c != '\\' {
+        let mut buf = [0; 4];
+        return Ok(c.encode_utf8(&mut buf).as_bytes().to_vec());
+    }
+    let Some(&e) = chars.get(*i) else {
+        return Err("key sequence ends in a backslash".to_string());
+    };
+    *i += 1;
+    if matches!(e, 'C' | 'M') && chars.get(*i) == Some(&'-') {
+        *i += 1;
+        if *i >= chars.len() {
+            return Err(format!("\\{}- without a key", e));
+        }
+        let inner = decode_one(chars, i)?;
+        return match (e, inner.as_slice()) {
+            ('C', [b'?']) => Ok(vec![DEL]),
+            ('C', [b]) => Ok(vec![control(*b)]),
+            ('C', [ESC, b]) => Ok(vec![ESC, control(*b)]),
+            ('C', _) => Err("\\C- needs a single key".to_string()),
+            (_, bytes) => Ok([&[ESC][..], bytes].concat()),
+        };
+    }
+    let byte = match e {
+        'e' | 'E' => ESC,
+        'a' => 0x07,
+        'b' => 0x08,
+        'd' => DEL,
+        'f' => 0x0c,
+        'n' => b'\n',
+        'r' => b'\r',
+        't' => b'\t',
+        'v' => 0x0b,
+        '0'..='7' => {
+            let mut value = e.to_digit(8).unwrap_or(0);
+            for _ in 0..2 {
+                match chars.get(*i).and_then(|c| c.to_digit(8)) {
+                    Some(d) => {
+                        value = value * 8 + d;
+                        *i += 1;
+                    }
+                    None => break,
+                }
+            }
+            u8::try_from(value).map_err(|_| "octal escape out of range".to_string())?
+        }
+        'x' => {
+            let mut value = 0;
+            let mut digits = 0;
+            while digits < 2
+                && let Some(d) = chars.get(*i).and_then(|c| c.to_digit(16))
+            {
+                value = value * 16 + d;
+                *i += 1;
+                digits += 1;
+            }
+            if digits == 0 {
+                return Err("\\x without hex digits".to_string());
+            }
+            value as u8
+        }
+        // `\\`, `\"`, `\'` and anything else stand for themselves.
+        other => {
+            let mut buf = [0; 4];
+            return Ok(other.encode_utf8(&mut buf).as_bytes().to_vec());
+        }
+    };
+    Ok(vec![byte])
+}
+
+fn control(b: u8) -> u8 {
+    b.to_ascii_lowercase() & 0x1f
+}
+
+/// Readline key names: `Control-u`, `C-u`, `Meta-Rubout`, `M-DEL`, `TAB`.
+fn key_name(name: &str) -> Result<Vec<u8>, String> {
+    let mut rest = name;
+    let mut ctrl = false;
+    let mut meta = false;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if let Some(n) = ["control-", "ctrl-", "c-"].iter().find(|p| lower.starts_with(**p)).map(|p| p.len()) {
+            ctrl = true;
+            rest = &rest[n..];
+        } else if let Some(n) = ["meta-", "m-"].iter().find(|p| lower.starts_with(**p)).map(|p| p.len()) {
+            meta = true;
+            rest = &rest[n..];
+        } else {
+            break;
+        }
+    }
+    let byte = match rest.to_ascii_lowercase().as_str() {
+        "rubout" | "del" => DEL,
+        "escape" | "esc" => ESC,
+        "lfd" | "newline" => b'\n',
+        "ret" | "return" => b'\r',
+        "space" | "spc" => b' ',
+        "tab" => b'\t',
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii() => c as u8,
+                _ => return Err(format!("unknown key name {}", name)),
+            }
+        }
+    };
+    let byte = if ctrl { if byte == b'?' { DEL } else { control(byte) } } else { byte };
+    Ok(if meta { vec![ESC, byte] } else { vec![byte] })
+}
+
+/// The chord for a single key's bytes; `None` for sequences of several
+/// keys or ones we don't recognize.
+pub fn chord_for(bytes: &[u8]) -> Option<Chord> {
+    match bytes {
+        [b] => single(*b),
+        [ESC, b'[', rest @ ..] => csi(rest),
+        [ESC, b'O', rest @ ..] => ss3(rest),
+        [ESC, rest @ ..] => {
+            let mut chord = chord_for(rest)?;
+            if chord.alt {
+                return None;
+            }
+            chord.alt = true;
+            Some(chord)
+        }
+        _ => {
+            let text = std::str::from_utf8(bytes).ok()?;
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(Chord::from_key(KeyName::Char(c), false, false, false)),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn single(b: u8) -> Option<Chord> {
+    let named = |key| Some(Chord::new(KeyName::Named(key)));
+    match b {
+        b'\t' => named(NamedKey::Tab),
+        b'\r' | b'\n' => named(NamedKey::Enter),
+        ESC => named(NamedKey::Escape),
+        DEL => named(NamedKey::Backspace),
+        b' ' => named(NamedKey::Space),
+        0 => Some(Chord::from_key(KeyName::Named(NamedKey::Space), true, false, false)),
+        0x01..=0x1a => Some(Chord::from_key(KeyName::Char((b | 0x60) as char), true, false, false)),
+        0x1c..=0x1f => Some(Chord::from_key(KeyName::Char((b + 0x40) as char), true, false, false)),
+        0x21..=0x7e => Some(Chord::from_key(KeyName::Char(b as char), false, false, false)),
+        _ => None,
+    }
+}
+
+/// `ESC [ params final`: xterm cursor and editing keys, with the
+/// `;<modifier>` parameter.
+fn csi(rest: &[u8]) -> Option<Chord> {
+    let (&last, params) = rest.split_last()?;
+    let params = std::str::from_utf8(params).ok()?;
+    let mut fields = params.split(';');
+    let first = fields.next().unwrap_or("");
+    let modifier = match fields.next() {
+        Some(m) => m.parse::<u8>().ok()?,
+        None => 1,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+    let key = match last {
+        b'A' => NamedKey::Up,
+        b'B' => NamedKey::Down,
+        b'C' => NamedKey::Right,
+        b'D' => NamedKey::Left,
+        b'H' => NamedKey::Home,
+        b'F' => NamedKey::End,
+        b'Z' if params.is_empty() => return Some(Chord { shift: true, ..Chord::new(KeyName::Named(NamedKey::Tab)) }),
+        b'~' => match first.parse::<u8>().ok()? {
+            1 | 7 => NamedKey::Home,
+            2 => NamedKey::Insert,
+            3 => NamedKey::Delete,
+            4 | 8 => NamedKey::End,
+            5 => NamedKey::PageUp,
+            6 => NamedKey::PageDown,
+            n @ 11..=15 => NamedKey::F(n - 10),
+            n @ 17..=21 => NamedKey::F(n - 11),
+            n @ 23..=24 => NamedKey::F(n - 12),
+            _ => return None,
+        },
+        // rxvt: ESC [ a..d is Shift+arrow.
+        b'a'..=b'd' if params.is_empty() => {
+            return Some(Chord { shift: true, ..Chord::new(KeyName::Named(arrow(last)?)) });
+        }
+        _ => return None,
+    };
+    // `ESC [ 5 C` (no `1;`) is an old xterm Ctrl+arrow.
+    let modifier = match (last, first) {
+        (b'A'..=b'D', m) if !m.is_empty() && m != "1" && modifier == 1 => m.parse().ok()?,
+        _ => modifier,
+    };
+    with_modifier(Chord::new(KeyName::Named(key)), modifier)
+}
+
+/// `ESC O final`: application-mode keys, and rxvt's Ctrl+arrows.
+fn ss3(rest: &[u8]) -> Option<Chord> {
+    let key = match rest {
+        [b'A'] => NamedKey::Up,
+        [b'B'] => NamedKey::Down,
+        [b'C'] => NamedKey::Right,
+        [b'D'] => NamedKey::Left,
+        [b'H'] => NamedKey::Home,
+        [b'F'] => NamedKey::End,
+        [b @ b'P'..=b'S'] => NamedKey::F(b - b'P' + 1),
+        [b @ b'a'..=b'd'] => return Some(Chord { ctrl: true, ..Chord::new(KeyName::Named(arrow(*b)?)) }),
+        _ => return None,
+    };
+    Some(Chord::new(KeyName::Named(key)))
+}
+
+fn arrow(b: u8) -> Option<NamedKey> {
+    match b.to_ascii_uppercase() {
+        b'A' => Some(NamedKey::Up),
+        b'B' => Some(NamedKey::Down),
+        b'C' => Some(NamedKey::Right),
+        b'D' => Some(NamedKey::Left),
+        _ => None,
+    }
+}
+
+/// xterm modifier parameter: 1 + (shift 1, alt 2, ctrl 4, meta 8).
+fn with_modifier(chord: Chord, modifier: u8) -> Option<Chord> {
+    let bits = modifier.checked_sub(1)?;
+    if bits > 7 {
+        return None;
+    }
+    Some(Chord { shift: bits & 1 != 0, alt: bits & 2 != 0, ctrl: bits & 4 != 0, ..chord })
+}
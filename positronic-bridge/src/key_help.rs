@@ -0,0 +1,380 @@
+//! Key binding quick reference: F1 or `!keys show` (no UI deps).
+//!
+//! `sheet` builds the reference from the live `Keymap` and the terminal's
+//! modes, so config overrides, `.inputrc` imports and unbound actions show
+//! as they are. Actions are grouped by `Category`; each row carries its
+//! chord, where the chord came from, and a note when it is shared with an
+//! action that wins it, shadows typing or a key the shell needs, or goes
+//! to a full-screen app while the alternate screen is up. A filter keeps
+//! the rows whose action name or chord fuzzy-match it, or whose
+//! description contains it.
+//!
+//! The open `KeyHelp` takes every key. Typing edits the filter; any other
+//! chord lights its row instead of running, so the overlay doubles as a
+//! "what does this key do" explorer. Escape clears the filter, then
+//! closes; the `key_help` chord closes it too.
+
+use positronic_core::term::modes::ModeSnapshot;
+
+use crate::fuzzy::{CaseMode, Matcher};
+use crate::keymap::{self, Action, BindingSource, Chord, KeyName, Keymap, NamedKey};
+use crate::viewport::alt_screen_sequence;
+
+/// Rows PageUp and PageDown move the sheet by.
+pub const PAGE_STEP: usize = 10;
+
+/// Width of the chord column.
+const CHORD_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Editing,
+    History,
+    Blocks,
+    Panes,
+    Search,
+    System,
+}
+
+impl Category {
+    pub const ALL: &'static [Category] = &[
+        Category::Editing,
+        Category::History,
+        Category::Blocks,
+        Category::Panes,
+        Category::Search,
+        Category::System,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Editing => "Editing",
+            Category::History => "History",
+            Category::Blocks => "Blocks",
+            Category::Panes => "Panes",
+            Category::Search => "Search",
+            Category::System => "System",
+        }
+    }
+
+    pub fn of(action: Action) -> Category {
+        match action {
+            Action::CursorLeft
+            | Action::CursorRight
+            | Action::LineStart
+            | Action::LineEnd
+            | Action::WordLeft
+            | Action::WordRight
+            | Action::KillWordBack
+            | Action::KillWordForward
+            | Action::KillToStart
+            | Action::KillToEnd
+            | Action::TabComplete
+            | Action::Copy
+            | Action::CopyAnsi
+            | Action::Paste => Category::Editing,
+            Action::HistoryUp | Action::HistoryDown => Category::History,
+            Action::BlockPrev
+            | Action::BlockNext
+            | Action::EditBlockCommand
+            | Action::RerunBlockCommand
+            | Action::JumpBack
+            | Action::JumpForward
+            | Action::JumpToError
+            | Action::FollowLink
+            | Action::ScrollPageUp
+            | Action::ScrollPageDown
+            | Action::ScrollTop
+            | Action::ScrollBottom => Category::Blocks,
+            Action::ToggleScope | Action::ConsoleExit => Category::Panes,
+            Action::SearchOpen | Action::PaletteOpen | Action::FileFinder => Category::Search,
+            Action::ClearScreen | Action::Interrupt | Action::Eof | Action::KeyHelp => Category::System,
+        }
+    }
+}
+
+/// Why a row's chord doesn't simply run its action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Note {
+    /// Another action on the same chord wins it.
+    ShadowedBy(Action),
+    /// The chord is one the shell or input line needs, or types a
+    /// character.
+    Shadows(&'static str),
+    /// The alternate screen is up and the chord goes to the app.
+    ToApp,
+}
+
+impl Note {
+    pub fn text(&self) -> String {
+        match self {
+            Note::ShadowedBy(action) => format!("shadowed by {}", action),
+            Note::Shadows(what) => format!("shadows {}", what),
+            Note::ToApp => "goes to the full-screen app".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub action: Action,
+    /// `None` if unbound by config.
+    pub chord: Option<Chord>,
+    pub source: BindingSource,
+    pub notes: Vec<Note>,
+}
+
+impl Row {
+    /// Shared with a winner, or taking a key from the shell or typing.
+    pub fn conflicts(&self) -> bool {
+        self.notes.iter().any(|n| matches!(n, Note::ShadowedBy(_) | Note::Shadows(_)))
+    }
+
+    /// The chord runs this action right now.
+    pub fn effective(&self) -> bool {
+        self.chord.is_some() && !self.notes.iter().any(|n| matches!(n, Note::ShadowedBy(_) | Note::ToApp))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub category: Category,
+    pub rows: Vec<Row>,
+}
+
+/// The reference for `keymap` as things stand in `modes`, narrowed to
+/// rows matching `filter`. Rows keep `Action::ALL` order unless a filter
+/// ranks them; empty sections are left out.
+pub fn sheet(keymap: &Keymap, modes: &ModeSnapshot, filter: &str) -> Vec<Section> {
+    let matcher = Matcher::new(filter.trim(), CaseMode::Smart);
+    let needle = filter.trim().to_lowercase();
+    let mut sections: Vec<Section> =
+        Category::ALL.iter().map(|&category| Section { category, rows: Vec::new() }).collect();
+    let mut scores: Vec<Vec<i32>> = vec![Vec::new(); sections.len()];
+
+    for binding in keymap.bindings() {
+        let chord_text = binding.chord.map(|c| c.to_string()).unwrap_or_default();
+        let fuzzy = matcher.score(&format!("{} {}", binding.action.name(), chord_text)).map(|m| m.score);
+        let described = || binding.action.description().to_lowercase().contains(&needle);
+        let Some(score) = fuzzy.or_else(|| described().then_some(0)) else {
+            continue;
+        };
+        let mut notes = Vec::new();
+        if let Some(chord) = binding.chord {
+            match keymap.action_for(&chord) {
+                Some(winner) if winner != binding.action => notes.push(Note::ShadowedBy(winner)),
+                _ => {
+                    if let Some((provider, what)) = keymap::pass_through(&chord)
+                        && provider != Some(binding.action)
+                    {
+                        notes.push(Note::Shadows(what));
+                    }
+                    if chord.is_typing() {
+                        notes.push(Note::Shadows("typing that key"));
+                    }
+                }
+            }
+            if modes.alt_screen && goes_to_app(&chord) {
+                notes.push(Note::ToApp);
+            }
+        }
+        let at = Category::ALL.iter().position(|&c| c == Category::of(binding.action)).expect("Category not in ALL");
+        sections[at].rows.push(Row { action: binding.action, chord: binding.chord, source: binding.source, notes });
+        scores[at].push(score);
+    }
+
+    if !matcher.is_empty() {
+        for (section, scores) in sections.iter_mut().zip(scores) {
+            let mut ranked: Vec<(i32, Row)> = scores.into_iter().zip(section.rows.drain(..)).collect();
+            ranked.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+            section.rows = ranked.into_iter().map(|(_, row)| row).collect();
+        }
+    }
+    sections.retain(|s| !s.rows.is_empty());
+    sections
+}
+
+/// Whether the event handler sends `chord` to a full-screen app rather
+/// than the keymap (see `viewport::alt_screen_sequence`).
+fn goes_to_app(chord: &Chord) -> bool {
+    match chord.key {
+        KeyName::Named(key) => !chord.alt && alt_screen_sequence(key, chord.ctrl, chord.shift).is_some(),
+        KeyName::Char(_) => false,
+    }
+}
+
+/// What a line of the sheet is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Header,
+    Row {
+        /// Its chord is the one just pressed.
+        lit: bool,
+        conflict: bool,
+        /// Unbound, shadowed, or passed to the app.
+        dim: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetLine {
+    pub text: String,
+    pub kind: LineKind,
+}
+
+/// The sheet as text, one line per header and row, the row whose chord
+/// is `pressed` lit.
+pub fn lines(sections: &[Section], pressed: Option<&Chord>) -> Vec<SheetLine> {
+    let mut out = Vec::new();
+    for section in sections {
+        out.push(SheetLine { text: section.category.label().to_string(), kind: LineKind::Header });
+        for row in &section.rows {
+            let chord = row.chord.map(|c| c.to_string()).unwrap_or_else(|| "unbound".to_string());
+            let mut text = format!("  {:<width$} {}", chord, row.action.description(), width = CHORD_WIDTH);
+            if row.source != BindingSource::Default {
+                text.push_str(&format!("  · {}", row.source));
+            }
+            for note in &row.notes {
+                text.push_str(&format!("  ⚠ {}", note.text()));
+            }
+            let lit = pressed.is_some() && row.chord.as_ref() == pressed;
+            let kind = LineKind::Row { lit, conflict: row.conflicts(), dim: !row.effective() };
+            out.push(SheetLine { text, kind });
+        }
+    }
+    out
+}
+
+/// The first of `total` lines to show in `rows` of space: the scroll
+/// position if one was set, else enough to bring line `lit` into view.
+pub fn window(total: usize, rows: usize, top: Option<usize>, lit: Option<usize>) -> usize {
+    let last = total.saturating_sub(rows);
+    match (top, lit) {
+        (Some(top), _) => top.min(last),
+        (None, Some(lit)) if lit >= rows => (lit + 1 - rows).min(last),
+        _ => 0,
+    }
+}
+
+/// Everything the overlay shows, as both frontends draw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyHelpView {
+    pub title: String,
+    pub lines: Vec<SheetLine>,
+    pub footer: String,
+    top: Option<usize>,
+}
+
+impl KeyHelpView {
+    /// The first line to show when `rows` fit between title and footer.
+    pub fn first(&self, rows: usize) -> usize {
+        let lit = self.lines.iter().position(|l| matches!(l.kind, LineKind::Row { lit: true, .. }));
+        window(self.lines.len(), rows, self.top, lit)
+    }
+
+    /// Title, the lines that fit and the footer, in `rows` text rows: the
+    /// software renderer's screen.
+    pub fn plain(&self, rows: usize) -> Vec<String> {
+        let body = rows.saturating_sub(2);
+        let first = self.first(body);
+        let mut out = vec![self.title.clone()];
+        out.extend(self.lines.iter().skip(first).take(body).map(|l| l.text.clone()));
+        out.resize(rows.saturating_sub(1).max(1), String::new());
+        out.push(self.footer.clone());
+        out
+    }
+}
+
+/// What the open reference does with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyHelpOutcome {
+    Open,
+    Closed,
+}
+
+/// The open overlay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyHelp {
+    pub filter: String,
+    /// The chord last pressed; its row is lit.
+    pub pressed: Option<Chord>,
+    /// First line shown after PageUp/PageDown; `None` follows the lit row.
+    pub top: Option<usize>,
+}
+
+impl KeyHelp {
+    pub fn key(&mut self, chord: Option<Chord>, text: Option<&str>, keymap: &Keymap) -> KeyHelpOutcome {
+        let action = chord.as_ref().and_then(|c| keymap.action_for(c));
+        if action == Some(Action::KeyHelp) {
+            return KeyHelpOutcome::Closed;
+        }
+        let plain = chord.is_none_or(|c| !c.ctrl && !c.alt && !c.shift);
+        match chord.map(|c| c.key) {
+            Some(KeyName::Named(NamedKey::Escape)) if plain => {
+                if self.filter.is_empty() {
+                    return KeyHelpOutcome::Closed;
+                }
+                self.filter.clear();
+                self.top = None;
+            }
+            Some(KeyName::Named(NamedKey::Backspace)) if plain => {
+                self.filter.pop();
+                self.top = None;
+            }
+            Some(KeyName::Named(NamedKey::PageDown)) if plain => {
+                self.top = Some(self.top.unwrap_or(0) + PAGE_STEP);
+            }
+            Some(KeyName::Named(NamedKey::PageUp)) if plain => {
+                self.top = Some(self.top.unwrap_or(0).saturating_sub(PAGE_STEP));
+            }
+            _ => match (chord, text) {
+                (Some(c), Some(text)) if c.is_typing() => {
+                    self.filter.push_str(text);
+                    self.top = None;
+                }
+                (Some(c), _) => {
+                    self.pressed = Some(c);
+                    self.top = None;
+                }
+                (None, Some(text)) => {
+                    self.filter.push_str(text);
+                    self.top = None;
+                }
+                (None, None) => {}
+            },
+        }
+        KeyHelpOutcome::Open
+    }
+
+    /// The overlay for `keymap` in `modes`, filtered and lit as typed.
+    pub fn view(&self, keymap: &Keymap, modes: &ModeSnapshot) -> KeyHelpView {
+        let sections = sheet(keymap, modes, &self.filter);
+        KeyHelpView {
+            title: self.title(),
+            lines: lines(&sections, self.pressed.as_ref()),
+            footer: self.footer(keymap),
+            top: self.top,
+        }
+    }
+
+    /// Header line: the filter being typed.
+    pub fn title(&self) -> String {
+        if self.filter.is_empty() {
+            "⌨️  Key bindings — type to filter".to_string()
+        } else {
+            format!("⌨️  Key bindings — filter: {}▏", self.filter)
+        }
+    }
+
+    /// Footer line: what the pressed chord does, or how to use the sheet.
+    pub fn footer(&self, keymap: &Keymap) -> String {
+        let close = keymap.chord_for(Action::KeyHelp).map(|c| format!(" or {}", c)).unwrap_or_default();
+        match &self.pressed {
+            Some(chord) => match keymap.action_for(chord) {
+                Some(action) => format!("{} → {} ({})  ·  Esc{} closes", chord, action.description(), action, close),
+                None => format!("{} is not bound  ·  Esc{} closes", chord, close),
+            },
+            None => format!("Press a key to see what it does  ·  PageUp/PageDown scroll  ·  Esc{} closes", close),
+        }
+    }
+}
@@ -0,0 +1,797 @@
+//! Configurable keyboard shortcuts.
+//!
+//! Shortcuts map a `Chord` (modifiers + key) to one of a closed set of
+//! `Action`s. Defaults cover every action; vault config entries
+//! `keys.<action> = "<chord>"` override them (`"none"` unbinds). Plain
+//! typing, Enter, Backspace, Delete and Escape are not actions and always
+//! keep their meaning unless a binding shadows them, which the conflict
+//! checker reports.
+//!
+//! No winit types here: the event handler converts key events to `Chord`s.
+//! The Super/Meta key is left to the OS and cannot be bound.
+
+use std::fmt;
+
+/// Vault config key prefix for overrides.
+pub const CONFIG_PREFIX: &str = "keys.";
+
+// ════════════════════════════════════════════════════════════════════
+// Actions
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    ClearScreen,
+    Copy,
+    CopyAnsi,
+    Paste,
+    Interrupt,
+    Eof,
+    HistoryUp,
+    HistoryDown,
+    TabComplete,
+    CursorLeft,
+    CursorRight,
+    LineStart,
+    LineEnd,
+    WordLeft,
+    WordRight,
+    KillWordBack,
+    KillWordForward,
+    KillToStart,
+    KillToEnd,
+    PaletteOpen,
+    SearchOpen,
+    FileFinder,
+    FollowLink,
+    JumpToError,
+    ToggleScope,
+    ConsoleExit,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollTop,
+    ScrollBottom,
+    BlockPrev,
+    BlockNext,
+    JumpBack,
+    JumpForward,
+    EditBlockCommand,
+    RerunBlockCommand,
+    KeyHelp,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::ClearScreen,
+        Action::Copy,
+        Action::CopyAnsi,
+        Action::Paste,
+        Action::Interrupt,
+        Action::Eof,
+        Action::HistoryUp,
+        Action::HistoryDown,
+        Action::TabComplete,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::LineStart,
+        Action::LineEnd,
+        Action::WordLeft,
+        Action::WordRight,
+        Action::KillWordBack,
+        Action::KillWordForward,
+        Action::KillToStart,
+        Action::KillToEnd,
+        Action::PaletteOpen,
+        Action::SearchOpen,
+        Action::FileFinder,
+        Action::FollowLink,
+        Action::JumpToError,
+        Action::ToggleScope,
+        Action::ConsoleExit,
+        Action::ScrollPageUp,
+        Action::ScrollPageDown,
+        Action::ScrollTop,
+        Action::ScrollBottom,
+        Action::BlockPrev,
+        Action::BlockNext,
+        Action::JumpBack,
+        Action::JumpForward,
+        Action::EditBlockCommand,
+        Action::RerunBlockCommand,
+        Action::KeyHelp,
+    ];
+
+    /// Config name (`keys.<name>`).
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ClearScreen => "clear_screen",
+            Action::Copy => "copy",
+            Action::CopyAnsi => "copy_ansi",
+            Action::Paste => "paste",
+            Action::Interrupt => "interrupt",
+            Action::Eof => "eof",
+            Action::HistoryUp => "history_up",
+            Action::HistoryDown => "history_down",
+            Action::TabComplete => "tab_complete",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorRight => "cursor_right",
+            Action::LineStart => "line_start",
+            Action::LineEnd => "line_end",
+            Action::WordLeft => "word_left",
+            Action::WordRight => "word_right",
+            Action::KillWordBack => "kill_word_back",
+            Action::KillWordForward => "kill_word_forward",
+            Action::KillToStart => "kill_to_start",
+            Action::KillToEnd => "kill_to_end",
+            Action::PaletteOpen => "palette_open",
+            Action::SearchOpen => "search_open",
+            Action::FileFinder => "file_finder",
+            Action::FollowLink => "follow_link",
+            Action::JumpToError => "jump_to_error",
+            Action::ToggleScope => "toggle_scope",
+            Action::ConsoleExit => "console_exit",
+            Action::ScrollPageUp => "scroll_page_up",
+            Action::ScrollPageDown => "scroll_page_down",
+            Action::ScrollTop => "scroll_top",
+            Action::ScrollBottom => "scroll_bottom",
+            Action::BlockPrev => "block_prev",
+            Action::BlockNext => "block_next",
+            Action::JumpBack => "jump_back",
+            Action::JumpForward => "jump_forward",
+            Action::EditBlockCommand => "edit_block_command",
+            Action::RerunBlockCommand => "rerun_block_command",
+            Action::KeyHelp => "key_help",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::ClearScreen => "Clear the screen",
+            Action::Copy => "Copy the visible terminal",
+            Action::CopyAnsi => "Copy the visible terminal with colors and styles",
+            Action::Paste => "Paste, or pick from the clipboard history",
+            Action::Interrupt => "Interrupt the running command, or copy the selection",
+            Action::Eof => "Send Ctrl+D to the shell",
+            Action::HistoryUp => "Previous history entry",
+            Action::HistoryDown => "Next history entry",
+            Action::TabComplete => "Complete the input",
+            Action::CursorLeft => "Cursor left",
+            Action::CursorRight => "Cursor right",
+            Action::LineStart => "Cursor to line start",
+            Action::LineEnd => "Cursor to line end",
+            Action::WordLeft => "Cursor back one word",
+            Action::WordRight => "Cursor forward one word",
+            Action::KillWordBack => "Delete the word before the cursor",
+            Action::KillWordForward => "Delete the word after the cursor",
+            Action::KillToStart => "Delete to line start",
+            Action::KillToEnd => "Delete to line end",
+            Action::PaletteOpen => "Open the command palette",
+            Action::SearchOpen => "Search history",
+            Action::FileFinder => "Find a file in the project and insert, edit, view or copy it",
+            Action::FollowLink => "Open the last link on screen",
+            Action::JumpToError => "Open the first error in the editor",
+            Action::ToggleScope => "Show or hide the oscilloscope pane",
+            Action::ConsoleExit => "Leave !io console for the shell",
+            Action::ScrollPageUp => "Scroll back one page",
+            Action::ScrollPageDown => "Scroll forward one page",
+            Action::ScrollTop => "Scroll to the top of the scrollback",
+            Action::ScrollBottom => "Scroll to the bottom and follow output",
+            Action::BlockPrev => "Focus the previous command block",
+            Action::BlockNext => "Focus the next command block, or the input line",
+            Action::JumpBack => "Go back to the previous place in the jump list",
+            Action::JumpForward => "Go forward again in the jump list",
+            Action::EditBlockCommand => "Put the focused block's command in the input line to edit",
+            Action::RerunBlockCommand => "Run the focused block's command again",
+            Action::KeyHelp => "Show or hide this key binding reference",
+        }
+    }
+
+    pub fn default_chord(self) -> &'static str {
+        match self {
+            Action::ClearScreen => "ctrl+l",
+            Action::Copy => "ctrl+shift+c",
+            Action::CopyAnsi => "ctrl+shift+a",
+            Action::Paste => "ctrl+shift+v",
+            Action::Interrupt => "ctrl+c",
+            Action::Eof => "ctrl+d",
+            Action::HistoryUp => "up",
+            Action::HistoryDown => "down",
+            Action::TabComplete => "tab",
+            Action::CursorLeft => "left",
+            Action::CursorRight => "right",
+            Action::LineStart => "home",
+            Action::LineEnd => "end",
+            Action::WordLeft => "ctrl+left",
+            Action::WordRight => "ctrl+right",
+            Action::KillWordBack => "ctrl+w",
+            Action::KillWordForward => "alt+d",
+            Action::KillToStart => "ctrl+u",
+            Action::KillToEnd => "ctrl+k",
+            Action::PaletteOpen => "ctrl+shift+p",
+            Action::SearchOpen => "ctrl+r",
+            Action::FileFinder => "ctrl+p",
+            Action::FollowLink => "ctrl+shift+o",
+            Action::JumpToError => "ctrl+shift+e",
+            Action::ToggleScope => "ctrl+shift+s",
+            Action::ConsoleExit => "ctrl+]",
+            Action::ScrollPageUp => "shift+pageup",
+            Action::ScrollPageDown => "shift+pagedown",
+            Action::ScrollTop => "ctrl+home",
+            Action::ScrollBottom => "ctrl+end",
+            Action::BlockPrev => "ctrl+up",
+            Action::BlockNext => "ctrl+down",
+            Action::JumpBack => "ctrl+o",
+            Action::JumpForward => "ctrl+i",
+            Action::EditBlockCommand => "ctrl+shift+b",
+            Action::RerunBlockCommand => "ctrl+shift+r",
+            Action::KeyHelp => "f1",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Chords
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedKey {
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Insert,
+    Space,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+const NAMED_KEYS: &[(&str, NamedKey)] = &[
+    ("enter", NamedKey::Enter),
+    ("return", NamedKey::Enter),
+    ("tab", NamedKey::Tab),
+    ("escape", NamedKey::Escape),
+    ("esc", NamedKey::Escape),
+    ("backspace", NamedKey::Backspace),
+    ("delete", NamedKey::Delete),
+    ("del", NamedKey::Delete),
+    ("insert", NamedKey::Insert),
+    ("ins", NamedKey::Insert),
+    ("space", NamedKey::Space),
+    ("up", NamedKey::Up),
+    ("down", NamedKey::Down),
+    ("left", NamedKey::Left),
+    ("right", NamedKey::Right),
+    ("home", NamedKey::Home),
+    ("end", NamedKey::End),
+    ("pageup", NamedKey::PageUp),
+    ("pgup", NamedKey::PageUp),
+    ("pagedown", NamedKey::PageDown),
+    ("pgdn", NamedKey::PageDown),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyName {
+    /// A printable key, lowercased.
+    Char(char),
+    Named(NamedKey),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: KeyName,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordError {
+    Empty,
+    UnknownModifier(String),
+    UnknownKey(String),
+    MissingKey,
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChordError::Empty => write!(f, "empty chord"),
+            ChordError::UnknownModifier(m) => write!(f, "unknown modifier '{}'", m),
+            ChordError::UnknownKey(k) => write!(f, "unknown key '{}'", k),
+            ChordError::MissingKey => write!(f, "chord has modifiers but no key"),
+        }
+    }
+}
+
+impl std::error::Error for ChordError {}
+
+impl Chord {
+    pub fn new(key: KeyName) -> Self {
+        Self { ctrl: false, alt: false, shift: false, key }
+    }
+
+    /// Build from an event: letters are lowercased so Shift+C matches
+    /// `shift+c`.
+    pub fn from_key(key: KeyName, ctrl: bool, alt: bool, shift: bool) -> Self {
+        let key = match key {
+            KeyName::Char(c) => KeyName::Char(c.to_lowercase().next().unwrap_or(c)),
+            named => named,
+        };
+        Self { ctrl, alt, shift, key }
+    }
+
+    /// Parse `"ctrl+shift+c"`, `"Alt+Enter"`, `"f5"`, `"ctrl++"`.
+    /// Case-insensitive; `-` also separates.
+    pub fn parse(text: &str) -> Result<Chord, ChordError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ChordError::Empty);
+        }
+
+        // The last token is the key; it may itself be '+' or '-'.
+        let (mods, key) = match text.char_indices().rev().find(|&(i, c)| (c == '+' || c == '-') && i + 1 < text.len()) {
+            Some((i, _)) => (&text[..i], &text[i + 1..]),
+            None if text.len() > 1 && text.ends_with(['+', '-']) => return Err(ChordError::MissingKey),
+            None => ("", text),
+        };
+
+        let mut chord = Chord::new(KeyName::Char(' '));
+        for m in mods.split(['+', '-']).filter(|m| !m.is_empty()) {
+            match m.trim().to_lowercase().as_str() {
+                "ctrl" | "control" | "c" => chord.ctrl = true,
+                "alt" | "option" | "opt" | "m" => chord.alt = true,
+                "shift" | "s" => chord.shift = true,
+                other => return Err(ChordError::UnknownModifier(other.to_string())),
+            }
+        }
+
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(ChordError::MissingKey);
+        }
+        chord.key = parse_key(key)?;
+        Ok(chord)
+    }
+
+    /// True if the chord types a character (no Ctrl/Alt).
+    pub fn is_typing(&self) -> bool {
+        !self.ctrl && !self.alt && matches!(self.key, KeyName::Char(_) | KeyName::Named(NamedKey::Space))
+    }
+}
+
+fn parse_key(key: &str) -> Result<KeyName, ChordError> {
+    let lower = key.to_lowercase();
+    if let Some(&(_, named)) = NAMED_KEYS.iter().find(|(name, _)| *name == lower) {
+        return Ok(KeyName::Named(named));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok())
+        && (1..=12).contains(&n)
+    {
+        return Ok(KeyName::Named(NamedKey::F(n)));
+    }
+    let mut chars = lower.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if !c.is_control() => Ok(KeyName::Char(c)),
+        _ => Err(ChordError::UnknownKey(key.to_string())),
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (on, name) in [(self.ctrl, "ctrl"), (self.alt, "alt"), (self.shift, "shift")] {
+            if on {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.key {
+            KeyName::Char(c) => write!(f, "{}", c),
+            KeyName::Named(NamedKey::F(n)) => write!(f, "f{}", n),
+            KeyName::Named(named) => {
+                let name = NAMED_KEYS.iter().find(|(_, k)| *k == named).map(|(n, _)| *n).unwrap_or("?");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Pass-through rules
+// ════════════════════════════════════════════════════════════════════
+
+/// Chords that must keep reaching the shell or the input line, and the
+/// action (if any) that provides them.
+const PASS_THROUGH: &[(&str, Option<Action>, &str)] = &[
+    ("ctrl+c", Some(Action::Interrupt), "interrupting the running command"),
+    ("ctrl+d", Some(Action::Eof), "sending end-of-input"),
+    ("ctrl+z", None, "suspending the running command"),
+    ("ctrl+\\", None, "quitting the running command"),
+    ("escape", None, "Escape for pagers and dismissing lists"),
+    ("enter", None, "submitting the input"),
+    ("backspace", None, "deleting input"),
+    ("delete", None, "deleting input"),
+];
+
+/// The pass-through key `chord` is, if any: the action that provides it
+/// and what it is for.
+pub fn pass_through(chord: &Chord) -> Option<(Option<Action>, &'static str)> {
+    PASS_THROUGH
+        .iter()
+        .find(|(text, ..)| Chord::parse(text).as_ref() == Ok(chord))
+        .map(|&(_, provider, what)| (provider, what))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Interrupt routing
+// ════════════════════════════════════════════════════════════════════
+
+/// Vault config key choosing what the interrupt chord does.
+pub const INTERRUPT_MODE_KEY: &str = "keys.interrupt_mode";
+
+/// `keys.interrupt_mode`: `smart` (default) copies a selection when no
+/// command is running; `always` sends Ctrl+C to the shell every time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InterruptMode {
+    #[default]
+    Smart,
+    Always,
+}
+
+impl InterruptMode {
+    pub fn parse(value: &str) -> Option<InterruptMode> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "smart" => Some(InterruptMode::Smart),
+            "always" => Some(InterruptMode::Always),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InterruptMode::Smart => "smart",
+            InterruptMode::Always => "always",
+        }
+    }
+
+    /// What the interrupt chord does, given whether a foreground command
+    /// is running, whether text is selected and whether a `!` command is
+    /// in flight.
+    ///
+    /// | mode   | running | selection | `!` command | result        |
+    /// |--------|---------|-----------|-------------|---------------|
+    /// | smart  | yes     | any       | any         | Interrupt     |
+    /// | smart  | no      | yes       | any         | CopySelection |
+    /// | smart  | no      | no        | yes         | CancelNative  |
+    /// | smart  | no      | no        | no          | Nothing       |
+    /// | always | any     | any       | any         | Interrupt     |
+    pub fn route(self, running: bool, selection: bool, native: bool) -> InterruptRoute {
+        match (self, running, selection, native) {
+            (InterruptMode::Always, ..) => InterruptRoute::Interrupt,
+            (InterruptMode::Smart, true, _, _) => InterruptRoute::Interrupt,
+            (InterruptMode::Smart, false, true, _) => InterruptRoute::CopySelection,
+            (InterruptMode::Smart, false, false, true) => InterruptRoute::CancelNative,
+            (InterruptMode::Smart, false, false, false) => InterruptRoute::Nothing,
+        }
+    }
+}
+
+/// The outcome of the interrupt chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptRoute {
+    /// Send 0x03 to the shell.
+    Interrupt,
+    /// Copy the selected text, leaving the shell alone.
+    CopySelection,
+    /// Cancel the newest `!` command in flight.
+    CancelNative,
+    /// Nothing to interrupt or copy.
+    Nothing,
+}
+
+impl InterruptRoute {
+    /// Status bar note saying what was done.
+    pub fn note(self) -> &'static str {
+        match self {
+            InterruptRoute::Interrupt => "⛔ Ctrl+C: interrupt sent",
+            InterruptRoute::CopySelection => "📋 Ctrl+C: selection copied",
+            InterruptRoute::CancelNative => "✖ Ctrl+C: ! command cancelled",
+            InterruptRoute::Nothing => "Ctrl+C: nothing running or selected",
+        }
+    }
+}
+
+/// What is open, and in flight, when Escape is pressed at the prompt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EscapeState {
+    pub pager: bool,
+    pub passphrase: bool,
+    pub clipboard_picker: bool,
+    pub redo_offer: bool,
+    pub correction: bool,
+    pub suggestions: bool,
+    pub block_focus: bool,
+    /// A `!` command is running (see `positronic_core::cancel`).
+    pub native: bool,
+}
+
+/// What Escape does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeRoute {
+    ClosePager,
+    CancelPassphrase,
+    ClosePicker,
+    DeclineRedo,
+    DismissCorrection,
+    CloseSuggestions,
+    ClearBlockFocus,
+    /// Cancel the newest `!` command in flight.
+    CancelNative,
+    /// Send 0x1b to the shell.
+    Shell,
+}
+
+impl EscapeRoute {
+    /// The first of: close what is open, in the order listed in
+    /// `EscapeState`; cancel a `!` command; send Escape to the shell. A
+    /// selection doesn't count: Escape leaves it be.
+    pub fn pick(state: EscapeState) -> EscapeRoute {
+        let routes = [
+            (state.pager, EscapeRoute::ClosePager),
+            (state.passphrase, EscapeRoute::CancelPassphrase),
+            (state.clipboard_picker, EscapeRoute::ClosePicker),
+            (state.redo_offer, EscapeRoute::DeclineRedo),
+            (state.correction, EscapeRoute::DismissCorrection),
+            (state.suggestions, EscapeRoute::CloseSuggestions),
+            (state.block_focus, EscapeRoute::ClearBlockFocus),
+            (state.native, EscapeRoute::CancelNative),
+        ];
+        routes.into_iter().find(|(open, _)| *open).map_or(EscapeRoute::Shell, |(_, route)| route)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Keymap
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BindingSource {
+    Default,
+    /// Imported from `.inputrc` (see `inputrc`).
+    Inputrc,
+    User,
+}
+
+impl fmt::Display for BindingSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingSource::Default => write!(f, "default"),
+            BindingSource::Inputrc => write!(f, "inputrc"),
+            BindingSource::User => write!(f, "config"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub action: Action,
+    /// `None` if unbound by config.
+    pub chord: Option<Chord>,
+    pub source: BindingSource,
+}
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// One per action, in `Action::ALL` order.
+    bindings: Vec<Binding>,
+    /// What the interrupt chord does.
+    interrupt_mode: InterruptMode,
+    /// Overrides that could not be parsed.
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_overrides(std::iter::empty::<(Action, String)>())
+    }
+}
+
+impl Keymap {
+    /// Defaults with user overrides applied. `lookup` returns the config
+    /// value for a key such as `keys.copy`.
+    pub fn from_config(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let mut keymap = Self::from_overrides(
+            Action::ALL
+                .iter()
+                .filter_map(|&a| lookup(&format!("{}{}", CONFIG_PREFIX, a.name())).map(|v| (a, v))),
+        );
+        if let Some(value) = lookup(INTERRUPT_MODE_KEY) {
+            match InterruptMode::parse(&value) {
+                Some(mode) => keymap.interrupt_mode = mode,
+                None => {
+                    let problem = format!(
+                        "{} = \"{}\": expected smart or always (keeping smart)",
+                        INTERRUPT_MODE_KEY,
+                        value.trim()
+                    );
+                    keymap.errors.push(problem.clone());
+                    keymap.warnings.push(problem);
+                }
+            }
+        }
+        keymap
+    }
+
+    pub fn from_overrides<S: AsRef<str>>(overrides: impl IntoIterator<Item = (Action, S)>) -> Self {
+        let mut bindings: Vec<Binding> = Action::ALL
+            .iter()
+            .map(|&action| Binding {
+                action,
+                chord: Some(Chord::parse(action.default_chord()).expect("Invalid default chord")),
+                source: BindingSource::Default,
+            })
+            .collect();
+        let mut errors = Vec::new();
+
+        for (action, value) in overrides {
+            let value = value.as_ref().trim();
+            let slot = bindings.iter_mut().find(|b| b.action == action).expect("Action not in ALL");
+            if value.eq_ignore_ascii_case("none") || value.is_empty() {
+                slot.chord = None;
+                slot.source = BindingSource::User;
+                continue;
+            }
+            match Chord::parse(value) {
+                Ok(chord) => {
+                    slot.chord = Some(chord);
+                    slot.source = BindingSource::User;
+                }
+                Err(e) => errors.push(format!(
+                    "{}{} = \"{}\": {} (keeping {})",
+                    CONFIG_PREFIX,
+                    action.name(),
+                    value,
+                    e,
+                    action.default_chord()
+                )),
+            }
+        }
+
+        let warnings = errors.clone();
+        let mut keymap = Self { bindings, interrupt_mode: InterruptMode::default(), errors, warnings };
+        let conflicts = keymap.conflicts();
+        keymap.warnings.extend(conflicts);
+        keymap
+    }
+
+    /// Move actions still on their defaults to chords imported from
+    /// `.inputrc`; config overrides win. Returns how many moved.
+    pub fn import(&mut self, bindings: &[(Chord, Action)]) -> usize {
+        let mut moved = 0;
+        for &(chord, action) in bindings {
+            let slot = self.bindings.iter_mut().find(|b| b.action == action).expect("Action not in ALL");
+            if slot.source == BindingSource::User {
+                continue;
+            }
+            slot.chord = Some(chord);
+            slot.source = BindingSource::Inputrc;
+            moved += 1;
+        }
+        self.warnings = self.errors.clone();
+        let conflicts = self.conflicts();
+        self.warnings.extend(conflicts);
+        moved
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Overrides that were rejected (their actions keep the default).
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Load-time problems: bad chord strings and conflicts.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn interrupt_mode(&self) -> InterruptMode {
+        self.interrupt_mode
+    }
+
+    pub fn chord_for(&self, action: Action) -> Option<Chord> {
+        self.bindings.iter().find(|b| b.action == action).and_then(|b| b.chord)
+    }
+
+    /// The action bound to `chord`. A user binding beats an imported one,
+    /// which beats a default one on the same chord; otherwise the first
+    /// action in `Action::ALL` wins.
+    pub fn action_for(&self, chord: &Chord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|b| b.chord.as_ref() == Some(chord))
+            .reduce(|best, b| if b.source > best.source { b } else { best })
+            .map(|b| b.action)
+    }
+
+    /// Human-readable conflicts: chords shared by several actions and
+    /// bindings that shadow typing or PTY pass-through keys.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut out = Vec::new();
+
+        for (i, b) in self.bindings.iter().enumerate() {
+            let Some(chord) = b.chord else { continue };
+            // Report each shared chord once, at its first binding.
+            if self.bindings[..i].iter().any(|o| o.chord == Some(chord)) {
+                continue;
+            }
+            let sharing: Vec<&Binding> = self.bindings.iter().filter(|o| o.chord == Some(chord)).collect();
+            if sharing.len() > 1 {
+                let winner = self.action_for(&chord).expect("Chord is bound");
+                let names: Vec<String> = sharing.iter().map(|o| format!("{} ({})", o.action, o.source)).collect();
+                out.push(format!("{} is bound to {}; {} wins", chord, names.join(", "), winner));
+            }
+        }
+
+        for &(text, provider, what) in PASS_THROUGH {
+            let chord = Chord::parse(text).expect("Invalid pass-through chord");
+            if let Some(action) = self.action_for(&chord)
+                && Some(action) != provider
+            {
+                out.push(format!("{} ({}) shadows {}", chord, action, what));
+            }
+        }
+
+        for b in &self.bindings {
+            if let Some(chord) = b.chord
+                && chord.is_typing()
+            {
+                out.push(format!("{} ({}) shadows typing that key", chord, b.action));
+            }
+        }
+        out
+    }
+
+    /// `!keys` output.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec!["⌨️  Key bindings:".to_string()];
+        for b in &self.bindings {
+            let chord = b.chord.map(|c| c.to_string()).unwrap_or_else(|| "(unbound)".to_string());
+            lines.push(format!(
+                "  {:<14} {:<14} {:<9} {}",
+                b.action.name(),
+                chord,
+                b.source,
+                b.action.description()
+            ));
+        }
+        lines.push(format!("  Ctrl+C mode: {} (!set {} smart|always)", self.interrupt_mode.name(), INTERRUPT_MODE_KEY));
+        lines.push(format!("  Override with !set {}<action> <chord>, then !keys reload", CONFIG_PREFIX));
+        lines.push("  Grouped, searchable reference with !keys show or F1".to_string());
+        lines.push("  Import readline bindings with !keys import-inputrc [path]".to_string());
+        for w in &self.warnings {
+            lines.push(format!("  ⚠️ {}", w));
+        }
+        lines
+    }
+}
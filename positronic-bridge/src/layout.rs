@@ -0,0 +1,227 @@
+//! Window geometry: `layout.*` settings and the rects they produce.
+//!
+//! `LayoutSpec` holds the chrome sizes: status and input bar heights, the
+//! input text's inset, the gap between the output and the bars, and the
+//! status bar's text size. `layout.density` picks a preset
+//! (`compact`, `normal`, `comfortable`) and the individual keys override
+//! it, each checked against a sane range.
+//!
+//! `compute` is the one place geometry is worked out: the window size,
+//! the spec, `window.padding` and the cell metrics in, a `ComputedLayout`
+//! out, with the rect of every region and the PTY grid that fits the
+//! terminal area. Both frontends size the PTY from it, so they can't
+//! disagree on how many cells there are. Spec, padding and cells are in
+//! logical pixels and multiplied by `scale`, the factor cells are drawn
+//! at, so chrome grows with the text rather than on its own.
+
+/// Vault config keys: the preset, and the overrides.
+pub const DENSITY_KEY: &str = "layout.density";
+pub const STATUSBAR_HEIGHT_KEY: &str = "layout.statusbar_height";
+pub const INPUTBAR_HEIGHT_KEY: &str = "layout.inputbar_height";
+pub const INPUT_PADDING_KEY: &str = "layout.input_padding";
+pub const BLOCK_GAP_KEY: &str = "layout.block_gap";
+pub const STATUSBAR_FONT_SCALE_KEY: &str = "layout.statusbar_font_scale";
+
+/// The PTY never gets fewer cells than this, however small the window.
+pub const MIN_COLS: u16 = 40;
+pub const MIN_ROWS: u16 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Density {
+    Compact,
+    #[default]
+    Normal,
+    Comfortable,
+}
+
+impl Density {
+    pub fn parse(value: &str) -> Option<Density> {
+        match value.trim().to_lowercase().as_str() {
+            "compact" => Some(Density::Compact),
+            "normal" | "default" => Some(Density::Normal),
+            "comfortable" => Some(Density::Comfortable),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Density::Compact => "compact",
+            Density::Normal => "normal",
+            Density::Comfortable => "comfortable",
+        }
+    }
+
+    pub fn all() -> [Density; 3] {
+        [Density::Compact, Density::Normal, Density::Comfortable]
+    }
+
+    /// The preset's sizes.
+    pub fn spec(self) -> LayoutSpec {
+        let (statusbar_height, inputbar_height, input_padding, block_gap, statusbar_font_scale) = match self {
+            Density::Compact => (20.0, 28.0, 6.0, 0.0, 0.8),
+            Density::Normal => (24.0, 36.0, 10.0, 0.0, 0.85),
+            Density::Comfortable => (30.0, 44.0, 14.0, 6.0, 0.95),
+        };
+        LayoutSpec { density: self, statusbar_height, inputbar_height, input_padding, block_gap, statusbar_font_scale }
+    }
+}
+
+/// Chrome sizes in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutSpec {
+    /// The preset the sizes started from.
+    pub density: Density,
+    pub statusbar_height: f32,
+    pub inputbar_height: f32,
+    /// Space left and right of the input text.
+    pub input_padding: f32,
+    /// Space between the output and the status bar.
+    pub block_gap: f32,
+    /// Status bar text size, relative to the terminal's.
+    pub statusbar_font_scale: f32,
+}
+
+impl Default for LayoutSpec {
+    fn default() -> Self {
+        Density::Normal.spec()
+    }
+}
+
+/// Each override's key, the range it must fall in, and its field.
+type Override = (&'static str, f32, f32, fn(&mut LayoutSpec) -> &mut f32);
+
+const OVERRIDES: [Override; 5] = [
+    (STATUSBAR_HEIGHT_KEY, 16.0, 64.0, |spec| &mut spec.statusbar_height),
+    (INPUTBAR_HEIGHT_KEY, 24.0, 96.0, |spec| &mut spec.inputbar_height),
+    (INPUT_PADDING_KEY, 0.0, 48.0, |spec| &mut spec.input_padding),
+    (BLOCK_GAP_KEY, 0.0, 48.0, |spec| &mut spec.block_gap),
+    (STATUSBAR_FONT_SCALE_KEY, 0.6, 1.5, |spec| &mut spec.statusbar_font_scale),
+];
+
+impl LayoutSpec {
+    /// Read the keys through `lookup`: the preset first, then the
+    /// overrides on top. Invalid values keep the preset's and are
+    /// described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> LayoutSpec {
+        let density = match lookup(DENSITY_KEY) {
+            None => Density::Normal,
+            Some(value) => Density::parse(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected compact, normal or comfortable", DENSITY_KEY, value));
+                Density::Normal
+            }),
+        };
+        let mut spec = density.spec();
+        for (key, min, max, field) in OVERRIDES {
+            let Some(value) = lookup(key) else {
+                continue;
+            };
+            match value.trim().trim_end_matches("px").parse::<f32>() {
+                Ok(n) if (min..=max).contains(&n) => *field(&mut spec) = n,
+                _ => problems.push(format!("{} = \"{}\": expected {} to {}", key, value, min, max)),
+            }
+        }
+        spec
+    }
+}
+
+/// One terminal cell, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellMetrics {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Regions in physical pixels, and the grid that fits the terminal area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComputedLayout {
+    /// Total viewport width.
+    pub width: f32,
+    /// Total viewport height.
+    pub height: f32,
+
+    /// Terminal output area.
+    pub terminal_x: f32,
+    pub terminal_y: f32,
+    pub terminal_w: f32,
+    pub terminal_h: f32,
+
+    /// Status bar area.
+    pub status_x: f32,
+    pub status_y: f32,
+    pub status_w: f32,
+    pub status_h: f32,
+
+    /// Input bar area.
+    pub input_x: f32,
+    pub input_y: f32,
+    pub input_w: f32,
+    pub input_h: f32,
+
+    /// Padding around the terminal content (`window.padding`).
+    pub padding: f32,
+    /// Space left and right of the input text.
+    pub input_padding: f32,
+    /// Status bar text size, relative to the terminal's.
+    pub status_font_scale: f32,
+
+    /// One cell.
+    pub cell_w: f32,
+    pub cell_h: f32,
+    /// PTY columns and rows for the terminal area.
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// Lay out a `viewport` of physical pixels. `padding` is
+/// `window.padding`; `spec`, `padding` and `cell` are multiplied by
+/// `scale`. Bars keep their height in a window too short for them, and the
+/// terminal area shrinks to nothing first.
+pub fn compute(viewport: [u32; 2], spec: &LayoutSpec, padding: f32, cell: CellMetrics, scale: f32) -> ComputedLayout {
+    let scale = if scale.is_finite() && scale > 0.0 { scale } else { 1.0 };
+    let w = viewport[0] as f32;
+    let h = viewport[1] as f32;
+
+    let status_h = spec.statusbar_height * scale;
+    let input_h = spec.inputbar_height * scale;
+    let gap = spec.block_gap * scale;
+    let padding = padding * scale;
+    let (cell_w, cell_h) = (cell.width * scale, cell.height * scale);
+
+    let input_y = h - input_h;
+    let status_y = input_y - status_h;
+    let terminal_h = (status_y - gap - padding).max(0.0);
+
+    // Text starts `padding` in from the left, top and right of the area.
+    let cols = ((w - padding * 2.0) / cell_w).floor().max(0.0) as u16;
+    let rows = ((terminal_h - padding) / cell_h).floor().max(0.0) as u16;
+
+    ComputedLayout {
+        width: w,
+        height: h,
+
+        terminal_x: 0.0,
+        terminal_y: 0.0,
+        terminal_w: w,
+        terminal_h,
+
+        status_x: 0.0,
+        status_y,
+        status_w: w,
+        status_h,
+
+        input_x: 0.0,
+        input_y,
+        input_w: w,
+        input_h,
+
+        padding,
+        input_padding: spec.input_padding * scale,
+        status_font_scale: spec.statusbar_font_scale,
+
+        cell_w,
+        cell_h,
+        cols: cols.max(MIN_COLS),
+        rows: rows.max(MIN_ROWS),
+    }
+}
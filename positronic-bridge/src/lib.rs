@@ -5,19 +5,60 @@
 //!
 //! Module layout:
 //!   gfx/     — wgpu rendering pipeline (device, quad, text, shaders)
-//!   shell/   — winit application lifecycle, event dispatch, layout
+//!   shell/   — winit application lifecycle, event dispatch
 //!   ui/      — composable UI components (terminal, status bar, input bar)
 //!
+//!   attention — Unread markers and dimming of older output (no UI deps)
+//!   bell     — Bell policy, coalescing and `!bell mute` (no UI deps)
 //!   block    — TerminalBlock model (UI-side)
 //!   biolink  — Biometric link surface (Pillar XII)
 //!   hardware — Hardware panel (IoT device status)
 //!   holodeck — Rich media content detection & parsing
 //!   input    — Intelli-Input editor (pure Rust, no UI deps)
+//!   inputrc  — readline `.inputrc` import for `!keys import-inputrc` (no UI deps)
+//!   clipboard_history — `!paste` ring of what Positronic copied (no UI deps)
 //!   completer — Tab completion engine
+//!   compare  — `!compare` side-by-side outputs with aligned scrolling (no UI deps)
+//!   console  — `!io console` serial console state and key routing (no UI deps)
 //!   cwd      — Working directory tracker
+//!   dir_hint — "Frequent here" hint on entering a directory (no UI deps)
+//!   draft    — Debounced checkpoints of the unsent input line (no UI deps)
+//!   file_finder — Ctrl+P fuzzy file finder and its per-file actions (no UI deps)
+//!   error_center — Deduplicated system error toasts and `!errors sys` (no UI deps)
+//!   follow   — `!follow` pane buffering, pause and filters (no UI deps)
+//!   fuzzy    — Scored fuzzy matching for completion and search (no UI deps)
+//!   git_complete — git subcommand/alias/branch completion (no UI deps)
+//!   governor — Idle throttling of redraws, timers and probes (no UI deps)
+//!   heatmap  — `!stats heatmap` cells as gradient quads (no UI deps)
+//!   layout   — `layout.*` sizes, density presets and window geometry (no UI deps)
+//!   keymap   — Configurable shortcuts and conflict checks (no UI deps)
+//!   key_help — F1 / `!keys show` key binding reference (no UI deps)
+//!   macros   — `!macro` recording and playback by logical step (no UI deps)
+//!   marks    — `!mark` names and the Ctrl+O / Ctrl+I jump list (no UI deps)
+//!   pager    — Paging state for long native command output (no UI deps)
+//!   passphrase — Masked `!vault` passphrase prompt (no UI deps)
+//!   path_index — Executables on PATH for completion (no UI deps)
+//!   present  — `!present` redaction of what is drawn while screen sharing (no UI deps)
 //!   detection — Terminal mode detection (pager, continuation, etc.)
 //!   helpers  — Shared utility functions
+//!   highlight — Input line syntax highlighting (no UI deps)
 //!   renderer — Color conversion & snapshot-to-text (no UI deps)
+//!   render_path — GPU backend fallback order and device-loss recovery (no GPU deps)
+//!   replay   — `!record play` cast playback into a private emulator
+//!   resize   — Debounced PTY resizes and the interim scaled view (no UI deps)
+//!   scope    — `!scope` pane state and waveform geometry (no UI deps)
+//!   selection — Mouse text selection over the terminal grid (no UI deps)
+//!   session_context — Per-pane blocks, Holodeck, marks and search for split panes (no UI deps)
+//!   settings — Config-backed UI settings and `!profile` parsing (no UI deps)
+//!   quad_batch — Quad ordering, run merging and upload dedup (no GPU deps)
+//!   soft_frame — Monochrome text frames for the software renderer (no GPU deps)
+//!   span_cache — Retained per-fragment spans for the terminal view
+//!   suggestions — `!suggest` picker state (no UI deps)
+//!   syntax   — Language detection and highlighting for block output (no UI deps)
+//!   version  — `!ver` and `--version` facts from this build and session
+//!   viewer   — `!view` file reading, hex dumps and rendering for the pager (no UI deps)
+//!   viewport — Scroll position, anchoring and the new-output pill (no UI deps)
+//!   window_style — Opacity, padding and cursor settings (no UI deps)
 //!   platform — Platform-specific hooks
 
 // ── The New Architecture ─────────────────────────────────────────
@@ -31,13 +72,55 @@ pub mod block;
 pub mod hardware;
 pub mod holodeck;
 pub mod input;
+pub mod inputrc;
 
 // ── Shared Logic ─────────────────────────────────────────────────
+pub mod attention;
+pub mod bell;
+pub mod clipboard_history;
+pub mod compare;
 pub mod completer;
+pub mod console;
 pub mod cwd;
 pub mod detection;
+pub mod dir_hint;
+pub mod draft;
+pub mod error_center;
+pub mod file_finder;
+pub mod follow;
+pub mod fuzzy;
+pub mod git_complete;
+pub mod governor;
+pub mod heatmap;
 pub mod helpers;
+pub mod highlight;
+pub mod key_help;
+pub mod keymap;
+pub mod layout;
+pub mod macros;
+pub mod marks;
+pub mod pager;
+pub mod passphrase;
+pub mod path_index;
+pub mod present;
+pub mod quad_batch;
+pub mod render_path;
 pub mod renderer;
+pub mod replay;
+pub mod resize;
+pub mod scope;
+pub mod selection;
+pub mod session_context;
+pub mod settings;
+pub mod soft_frame;
+pub mod span_cache;
+pub mod suggestions;
+pub mod syntax;
+pub mod theme_sync;
+pub mod version;
+pub mod viewer;
+pub mod viewport;
+pub mod window_style;
 pub mod util;
 pub mod platform;
 pub mod widgets;
\ No newline at end of file
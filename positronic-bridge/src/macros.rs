@@ -0,0 +1,276 @@
+//! `!macro` recording and playback (no UI deps).
+//!
+//! A macro is what was done at the prompt as logical steps: text typed
+//! into the editor, keymap actions by name, Backspace, Delete and
+//! submits. Keys are not recorded, so a macro plays the same after the
+//! bindings change. Steps are saved one per line in the vault:
+//!
+//! ```text
+//! insert cargo t
+//! action tab_complete
+//! submit
+//! ```
+//!
+//! `Recorder` drops whatever is typed while a password prompt (or the
+//! vault's passphrase prompt) is showing, the submit included. `Player`
+//! hands out one step per `macro.delay` and holds off while a command
+//! is still running, so it never types into a busy shell; after each
+//! submit it waits at least `SUBMIT_SETTLE` for the command to show up
+//! as running.
+
+use std::time::{Duration, Instant};
+
+use positronic_core::tags;
+
+use crate::keymap::Action;
+
+/// Vault config key: the pause between played steps.
+pub const DELAY_KEY: &str = "macro.delay";
+
+pub const DEFAULT_DELAY: Duration = Duration::from_millis(30);
+
+/// The least a player waits after a submit before the next step.
+pub const SUBMIT_SETTLE: Duration = Duration::from_millis(250);
+
+/// How often a held player looks again.
+pub const BUSY_POLL: Duration = Duration::from_millis(100);
+
+/// Most times `!macro play` repeats.
+pub const MAX_REPEAT: usize = 1000;
+
+pub const MACRO_USAGE: &str = "Usage: !macro record <name> | stop | play <name> [n] | list | show <name> | rm <name>";
+
+/// One thing done at the prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Text typed into the editor; consecutive keys are one step.
+    Insert(String),
+    Backspace,
+    Delete,
+    /// A keymap action, whatever it is bound to.
+    Action(Action),
+    /// Enter: the line runs.
+    Submit,
+}
+
+impl Step {
+    /// The step's line in a saved macro.
+    pub fn encode(&self) -> String {
+        match self {
+            Step::Insert(text) => format!("insert {}", text),
+            Step::Backspace => "backspace".to_string(),
+            Step::Delete => "delete".to_string(),
+            Step::Action(action) => format!("action {}", action.name()),
+            Step::Submit => "submit".to_string(),
+        }
+    }
+
+    /// Read one line of a saved macro.
+    pub fn decode(line: &str) -> Option<Step> {
+        if let Some(text) = line.strip_prefix("insert ") {
+            return Some(Step::Insert(text.to_string()));
+        }
+        if let Some(name) = line.strip_prefix("action ") {
+            return Action::from_name(name).map(Step::Action);
+        }
+        match line {
+            "backspace" => Some(Step::Backspace),
+            "delete" => Some(Step::Delete),
+            "submit" => Some(Step::Submit),
+            _ => None,
+        }
+    }
+}
+
+/// `steps` as saved, one per line.
+pub fn encode(steps: &[Step]) -> String {
+    steps.iter().map(Step::encode).collect::<Vec<_>>().join("\n")
+}
+
+/// A saved macro's steps; errors name the first line that isn't one.
+pub fn decode(text: &str) -> Result<Vec<Step>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| Step::decode(line).ok_or_else(|| format!("line {}: not a macro step: {}", i + 1, line)))
+        .collect()
+}
+
+/// A parsed `!macro` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroCommand {
+    Record(String),
+    Stop,
+    Play { name: String, times: usize },
+    List,
+    Show(String),
+    Rm(String),
+}
+
+impl MacroCommand {
+    /// Parse what follows `!macro`; errors are the usage line.
+    pub fn parse(args: &str) -> Result<MacroCommand, String> {
+        let parts: Vec<&str> = args.split_whitespace().collect();
+        let name = |name: &str| tags::is_valid_name(name).then(|| name.to_string());
+        let command = match parts.as_slice() {
+            ["record", n] => name(n).map(MacroCommand::Record),
+            ["stop"] => Some(MacroCommand::Stop),
+            ["play", n] => name(n).map(|name| MacroCommand::Play { name, times: 1 }),
+            ["play", n, times] => match times.parse::<usize>() {
+                Ok(times @ 1..=MAX_REPEAT) => name(n).map(|name| MacroCommand::Play { name, times }),
+                _ => None,
+            },
+            ["list"] | [] => Some(MacroCommand::List),
+            ["show", n] => name(n).map(MacroCommand::Show),
+            ["rm", n] => name(n).map(MacroCommand::Rm),
+            _ => None,
+        };
+        command.ok_or_else(|| MACRO_USAGE.to_string())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Recording
+// ════════════════════════════════════════════════════════════════════
+
+/// A macro being recorded.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    name: String,
+    steps: Vec<Step>,
+    left_out: usize,
+}
+
+impl Recorder {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), steps: Vec::new(), left_out: 0 }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Steps dropped at a password prompt.
+    pub fn left_out(&self) -> usize {
+        self.left_out
+    }
+
+    /// Take `step`, done while `secret` input was being read or not.
+    /// Typing joins the text typed just before it.
+    pub fn record(&mut self, step: Step, secret: bool) {
+        if secret {
+            self.left_out += 1;
+            return;
+        }
+        match (self.steps.last_mut(), step) {
+            (Some(Step::Insert(text)), Step::Insert(more)) => text.push_str(&more),
+            (_, step) => self.steps.push(step),
+        }
+    }
+
+    /// Forget the line just submitted, and what edited it: the `!macro`
+    /// command that reached the recorder on its way to being run.
+    pub fn drop_last_line(&mut self) {
+        if self.steps.last() == Some(&Step::Submit) {
+            self.steps.pop();
+        }
+        while self.steps.last().is_some_and(|s| *s != Step::Submit) {
+            self.steps.pop();
+        }
+    }
+
+    /// The recording's steps.
+    pub fn finish(self) -> Vec<Step> {
+        self.steps
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Playback
+// ════════════════════════════════════════════════════════════════════
+
+/// What a player does on a tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tick {
+    /// Do this step now.
+    Step(Step),
+    /// Nothing before this moment.
+    Wait(Instant),
+    /// A command is running; look again at this moment.
+    Held(Instant),
+    /// Every repeat has been played.
+    Done,
+}
+
+/// A macro being played.
+#[derive(Debug, Clone)]
+pub struct Player {
+    name: String,
+    steps: Vec<Step>,
+    times: usize,
+    run: usize,
+    index: usize,
+    delay: Duration,
+    next_at: Instant,
+    held: bool,
+}
+
+impl Player {
+    /// Play `steps` `times` times, `delay` apart, from `now`.
+    pub fn new(name: &str, steps: Vec<Step>, times: usize, delay: Duration, now: Instant) -> Self {
+        Self { name: name.to_string(), steps, times, run: 0, index: 0, delay, next_at: now, held: false }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// True while waiting for a running command.
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// The next step due at `now`, with the shell `busy` or not.
+    pub fn tick(&mut self, now: Instant, busy: bool) -> Tick {
+        if self.run >= self.times || self.steps.is_empty() {
+            return Tick::Done;
+        }
+        if now < self.next_at {
+            return match self.held {
+                true => Tick::Held(self.next_at),
+                false => Tick::Wait(self.next_at),
+            };
+        }
+        if busy {
+            self.held = true;
+            self.next_at = now + BUSY_POLL;
+            return Tick::Held(self.next_at);
+        }
+        self.held = false;
+        let step = self.steps[self.index].clone();
+        self.index += 1;
+        if self.index == self.steps.len() {
+            self.index = 0;
+            self.run += 1;
+        }
+        self.next_at = match step {
+            Step::Submit => now + self.delay.max(SUBMIT_SETTLE),
+            _ => now + self.delay,
+        };
+        Tick::Step(step)
+    }
+
+    /// When the player next has something to do.
+    pub fn next_wake(&self) -> Option<Instant> {
+        (self.run < self.times && !self.steps.is_empty()).then_some(self.next_at)
+    }
+
+    /// Where playback is, for notes: `build 2/3, step 4/7`.
+    pub fn progress(&self) -> String {
+        let run = (self.run + 1).min(self.times);
+        format!("{} {}/{}, step {}/{}", self.name, run, self.times, self.index + 1, self.steps.len())
+    }
+}
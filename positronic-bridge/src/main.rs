@@ -1,18 +1,203 @@
 //! Positronic Bridge — GPU frontend shell.
 //!
 //! Binary entry point. Application logic lives in the library crate.
+//! With no flags the window opens; `--exec`, `--history-search`,
+//! `--export-history` and `--doctor` run headless (see
+//! `positronic_core::headless`) and exit. `--ipc` sends one request to
+//! the Positronic the shell runs in (see `positronic_core::ipc`).
+//! `--safe-mode` starts either without the user's aliases, hooks,
+//! plugins and settings (see `positronic_core::safe_mode`).
+//! `--data-dir` picks where the vault and the rest of the data live,
+//! over `POSITRONIC_DATA_DIR` and portable mode (see
+//! `positronic_core::data_paths`). `--inspect` opens the window on a
+//! vault or exported bundle read-only (see `positronic_core::inspect`).
+//! `--version [--json]` prints what `!ver` does, without a window or an
+//! engine (see `positronic_core::version`).
 
+use clap::Parser;
+use positronic_bridge::render_path::{self, RendererChoice};
 use positronic_bridge::shell;
 use positronic_bridge::util;
+use positronic_bridge::version;
+use positronic_core::data_paths::DataPaths;
+use positronic_core::engine::EngineOptions;
+use positronic_core::headless::{self, HeadlessTask};
+use positronic_core::ipc::{self, Endpoint, Request};
+
+#[derive(Debug, Parser)]
+#[command(name = "positronic", about = "The Positronic terminal")]
+#[command(group = clap::ArgGroup::new("headless").multiple(false))]
+struct Cli {
+    /// Run a command without a window and exit with its exit code.
+    #[arg(long, value_name = "COMMAND", group = "headless")]
+    exec: Option<String>,
+
+    /// With --exec: print the output once the command finishes, as plain text.
+    #[arg(long, requires = "exec")]
+    capture: bool,
+
+    /// With --exec: run commands flagged as destructive.
+    #[arg(long, requires = "exec")]
+    yes: bool,
+
+    /// Search command history (`!search`) and exit.
+    #[arg(long, value_name = "QUERY", group = "headless")]
+    history_search: Option<String>,
+
+    /// Write command history to a file (`!export`) and exit.
+    #[arg(long, value_name = "PATH", group = "headless")]
+    export_history: Option<String>,
+
+    /// Check subsystems, vault and shell integration (`!doctor`) and exit.
+    #[arg(long, group = "headless")]
+    doctor: bool,
+
+    /// Send a request to the running Positronic this shell belongs to:
+    /// run COMMAND, cwd, history-search QUERY, notify TEXT or ingest
+    /// [TEXT]. Ingest without text reads stdin.
+    #[arg(long, value_name = "REQUEST", group = "headless", num_args = 1.., allow_hyphen_values = true)]
+    ipc: Option<Vec<String>>,
+
+    /// Renderer: auto, vulkan, dx12, metal, gl or software. Overrides
+    /// POSITRONIC_RENDERER.
+    #[arg(long, value_name = "NAME", value_parser = parse_renderer)]
+    renderer: Option<RendererChoice>,
+
+    /// Start without aliases, hooks, !setenv overrides, plugins, settings
+    /// or .inputrc, to recover from one that breaks the terminal.
+    #[arg(long)]
+    safe_mode: bool,
+
+    /// Keep the vault, recordings and the rest of Positronic's data here.
+    /// Overrides POSITRONIC_DATA_DIR and portable mode.
+    #[arg(long, value_name = "PATH")]
+    data_dir: Option<std::path::PathBuf>,
+
+    /// Browse a vault, !sync bundle or !export file read-only, without
+    /// running commands or touching your own history.
+    #[arg(long, value_name = "PATH", conflicts_with = "headless")]
+    inspect: Option<std::path::PathBuf>,
+
+    /// Print the version, build and subsystem versions (`!ver`) and exit.
+    #[arg(short = 'V', long)]
+    version: bool,
+
+    /// With --version: print it as JSON, for a bug report.
+    #[arg(long, requires = "version")]
+    json: bool,
+}
+
+fn parse_renderer(name: &str) -> Result<RendererChoice, String> {
+    RendererChoice::parse(name).ok_or_else(|| format!("expected {}", render_path::RENDERER_USAGE))
+}
+
+impl Cli {
+    fn task(self) -> Option<HeadlessTask> {
+        if let Some(command) = self.exec {
+            return Some(HeadlessTask::Exec { command, capture: self.capture, allow_destructive: self.yes });
+        }
+        if let Some(query) = self.history_search {
+            return Some(HeadlessTask::HistorySearch(query));
+        }
+        if let Some(path) = self.export_history {
+            return Some(HeadlessTask::ExportHistory(path));
+        }
+        self.doctor.then_some(HeadlessTask::Doctor)
+    }
+}
 
 fn main() {
+    let cli = Cli::parse();
     util::init_tracing();
     util::install_panic_hook();
 
-    tracing::info!("=== Positronic v0.3.0 Starting ===");
+    if let Some(words) = &cli.ipc {
+        std::process::exit(run_ipc(&words.join(" ")));
+    }
 
-    if let Err(e) = shell::run() {
+    let renderer = RendererChoice::resolve(cli.renderer, std::env::var(render_path::RENDERER_ENV).ok().as_deref());
+
+    let safe_mode = cli.safe_mode;
+    let data = DataPaths::current(cli.data_dir.clone());
+    if cli.version {
+        print_version(&data, cli.json);
+        return;
+    }
+    let inspect = cli.inspect.clone();
+    if let Some(task) = cli.task() {
+        std::process::exit(run_headless(task, safe_mode, data));
+    }
+
+    tracing::info!("=== Positronic v{} ({}) Starting ===", version::BUILD.version, version::BUILD.git);
+
+    tracing::info!("{}", data.summary());
+    if let Err(e) = shell::run(renderer, safe_mode, data, inspect) {
         tracing::error!("Fatal: {:#}", e);
         std::process::exit(1);
     }
 }
+
+fn print_version(data: &DataPaths, json: bool) {
+    let report = version::Sources { data, runner: None, renderer: None }.report();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report.to_json()).unwrap_or_default());
+    } else {
+        for line in report.lines() {
+            println!("{}", line);
+        }
+    }
+}
+
+fn run_ipc(line: &str) -> i32 {
+    let mut request = match Request::parse(line) {
+        Ok(request) => request,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            return 2;
+        }
+    };
+    if let Request::Ingest { content } = &mut request
+        && content.is_empty()
+        && let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), content)
+    {
+        eprintln!("positronic: reading stdin: {}", e);
+        return 1;
+    }
+    let Some(endpoint) = Endpoint::from_env() else {
+        eprintln!("positronic: {} is not set; run this from a Positronic terminal", ipc::IPC_ENV);
+        return 1;
+    };
+    let rt = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("positronic: {}", e);
+            return 1;
+        }
+    };
+    match rt.block_on(ipc::request(&endpoint, request, |line| println!("{}", line))) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("positronic: {:#}", e);
+            1
+        }
+    }
+}
+
+fn run_headless(task: HeadlessTask, safe_mode: bool, data: DataPaths) -> i32 {
+    let rt = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("positronic: {}", e);
+            return 1;
+        }
+    };
+    let code = rt.block_on(headless::run(task, EngineOptions { data, safe_mode, ..EngineOptions::new(120, 30) })).unwrap_or_else(|e| {
+        for line in headless::error_lines(&e) {
+            eprintln!("{}", line);
+        }
+        1
+    });
+    // Don't wait for the shell and background tasks.
+    rt.shutdown_background();
+    code
+}
@@ -0,0 +1,257 @@
+//! Named marks (`!mark`, `!marks`, `!jump`) and the Ctrl+O / Ctrl+I jump
+//! list.
+//!
+//! A mark is made at the focused block, else at the top line of the view,
+//! and remembers the nearest command and, once the vault has logged it,
+//! that command's history row. It is resolved lazily: a block mark finds
+//! its block's first line each time, so a resize that rewraps the
+//! scrollback doesn't strand it. A block that has been evicted
+//! (`ScreenMarks` keeps the last few hundred) or cleared with the screen
+//! turns its mark into a history-backed one, which `!jump` shows through
+//! the recall path instead of scrolling. A mark with no history row to
+//! fall back on is gone.
+//!
+//! With `marks.persist` on, marks that have a history row are saved in
+//! the vault and come back history-backed in the next session.
+//!
+//! `JumpList` is vim's: every `!jump` and every block focus move records
+//! where the view was, Ctrl+O walks back through those places and Ctrl+I
+//! forward again. Jumping from the middle of the list drops what was
+//! ahead of it.
+
+use std::collections::VecDeque;
+
+use positronic_core::vault::CommandRecord;
+
+use crate::block::BlockId;
+
+/// Vault config key: keep marks across sessions.
+pub const PERSIST_KEY: &str = "marks.persist";
+
+/// Places the jump list remembers.
+pub const JUMP_LIST_LEN: usize = 100;
+
+pub const MARK_USAGE: &str = "Usage: !mark <name> | !mark rm <name> | !marks | !jump <name>";
+
+/// Where a mark points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkTarget {
+    /// A block in the terminal view.
+    Block(BlockId),
+    /// A scrollback line, for a place between blocks.
+    Line(usize),
+    /// A history row, once the scrollback is gone.
+    History(i64),
+    /// Nothing left to point at.
+    Gone,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    pub name: String,
+    pub target: MarkTarget,
+    /// The nearest command when the mark was made, for previews.
+    pub command: Option<String>,
+    /// That command's history row, if the vault had logged it.
+    pub history_id: Option<i64>,
+    /// When the mark was made, in Unix seconds.
+    pub at: i64,
+}
+
+/// What `!jump` does with a mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolved {
+    /// Scroll to this scrollback line.
+    Line(usize),
+    /// Show this history row's stored output.
+    History(i64),
+    /// The scrollback is gone and there is no row to show.
+    Gone,
+}
+
+/// The session's marks, in the order they were made.
+#[derive(Debug, Clone, Default)]
+pub struct MarkTable {
+    marks: Vec<Mark>,
+}
+
+impl MarkTable {
+    /// Add `mark`, replacing one of the same name.
+    pub fn set(&mut self, mark: Mark) {
+        self.marks.retain(|m| m.name != mark.name);
+        self.marks.push(mark);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Mark> {
+        self.marks.iter().find(|m| m.name == name)
+    }
+
+    /// Remove mark `name`. False if there was none.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.marks.len();
+        self.marks.retain(|m| m.name != name);
+        self.marks.len() != before
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+
+    /// Where mark `name` is now. `line_of` gives a live block's first
+    /// line; a block it no longer knows turns the mark history-backed.
+    /// `None` if there is no such mark.
+    pub fn resolve(&mut self, name: &str, line_of: impl Fn(BlockId) -> Option<usize>) -> Option<Resolved> {
+        let mark = self.marks.iter_mut().find(|m| m.name == name)?;
+        if let MarkTarget::Block(id) = mark.target {
+            match line_of(id) {
+                Some(line) => return Some(Resolved::Line(line)),
+                None => mark.detach(),
+            }
+        }
+        Some(match mark.target {
+            MarkTarget::Line(line) => Resolved::Line(line),
+            MarkTarget::History(id) => Resolved::History(id),
+            MarkTarget::Block(_) | MarkTarget::Gone => Resolved::Gone,
+        })
+    }
+
+    /// The screen was reset: no mark points into the scrollback any more.
+    pub fn screen_cleared(&mut self) {
+        for mark in &mut self.marks {
+            mark.detach();
+        }
+    }
+
+    /// The name of a mark that resolves to `line`, for the status bar.
+    pub fn name_at(&self, line: usize, line_of: impl Fn(BlockId) -> Option<usize>) -> Option<&str> {
+        self.marks
+            .iter()
+            .rev()
+            .find(|m| match m.target {
+                MarkTarget::Block(id) => line_of(id) == Some(line),
+                MarkTarget::Line(at) => at == line,
+                MarkTarget::History(_) | MarkTarget::Gone => false,
+            })
+            .map(|m| m.name.as_str())
+    }
+}
+
+impl Mark {
+    /// Stop pointing into the scrollback: at the history row if there is
+    /// one, else nowhere.
+    fn detach(&mut self) {
+        if let MarkTarget::Block(_) | MarkTarget::Line(_) = self.target {
+            self.target = self.history_id.map_or(MarkTarget::Gone, MarkTarget::History);
+        }
+    }
+
+    /// One line for `!marks`: the name, where it points and the command
+    /// near it.
+    pub fn describe(&self, resolved: Resolved) -> String {
+        let place = match resolved {
+            Resolved::Line(line) => format!("line {}", line + 1),
+            Resolved::History(id) => format!("history #{}", id),
+            Resolved::Gone => "gone".to_string(),
+        };
+        match &self.command {
+            Some(command) => format!("{:<16} {:<14} {}", self.name, place, command),
+            None => format!("{:<16} {}", self.name, place),
+        }
+    }
+}
+
+/// `!jump` to a history-backed mark: a header, then the stored output.
+pub fn recall_lines(name: &str, record: &CommandRecord) -> Vec<String> {
+    let id = record.id.map(|id| format!("#{} ", id)).unwrap_or_default();
+    let exit = record.exit_code.map(|code| format!(", exit {}", code)).unwrap_or_default();
+    let mut lines = vec![
+        format!("🔖 {}: {}{} (from the history{}, in {})", name, id, record.command, exit, record.directory),
+        "".to_string(),
+    ];
+    match &record.output {
+        Some(output) => lines.extend(output.lines().map(str::to_string)),
+        None => lines.push("(no output was stored)".to_string()),
+    }
+    lines
+}
+
+/// A place in the terminal view, for the jump list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Spot {
+    /// Anchored to the bottom, following new output.
+    Bottom,
+    /// Scrolled back with this scrollback line at the top.
+    Line(usize),
+}
+
+/// Recent places, oldest first, and where Ctrl+O / Ctrl+I have got to.
+#[derive(Debug, Clone, Default)]
+pub struct JumpList {
+    spots: VecDeque<Spot>,
+    /// Index of the place being shown, or `spots.len()` when not walking
+    /// the list.
+    cursor: usize,
+}
+
+impl JumpList {
+    /// A jump is leaving `from`: record it, dropping anything ahead of the
+    /// place being shown.
+    pub fn push(&mut self, from: Spot) {
+        self.spots.truncate((self.cursor + 1).min(self.spots.len()));
+        if self.spots.back() != Some(&from) {
+            self.push_back(from);
+        }
+        self.cursor = self.spots.len();
+    }
+
+    /// Ctrl+O from `here`: the place before, if any. The first step back
+    /// records `here` so that Ctrl+I can return to it.
+    pub fn back(&mut self, here: Spot) -> Option<Spot> {
+        if self.cursor >= self.spots.len() {
+            if self.spots.is_empty() || (self.spots.len() == 1 && self.spots[0] == here) {
+                return None;
+            }
+            if self.spots.back() != Some(&here) {
+                self.push_back(here);
+            }
+            self.cursor = self.spots.len() - 1;
+        }
+        self.cursor = self.cursor.checked_sub(1)?;
+        Some(self.spots[self.cursor])
+    }
+
+    /// Ctrl+I: the place after, if Ctrl+O has been back.
+    pub fn forward(&mut self) -> Option<Spot> {
+        let next = self.cursor + 1;
+        if next >= self.spots.len() {
+            return None;
+        }
+        self.cursor = next;
+        Some(self.spots[next])
+    }
+
+    pub fn len(&self) -> usize {
+        self.spots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spots.is_empty()
+    }
+
+    /// The screen was reset: its lines mean nothing now.
+    pub fn clear(&mut self) {
+        self.spots.clear();
+        self.cursor = 0;
+    }
+
+    fn push_back(&mut self, spot: Spot) {
+        if self.spots.len() == JUMP_LIST_LEN {
+            self.spots.pop_front();
+        }
+        self.spots.push_back(spot);
+    }
+}
@@ -0,0 +1,291 @@
+//! Pager for long native command output (`!history`, `!help`, ...).
+//!
+//! DirectOutput never goes through the PTY, so `less` can't page it. When a
+//! result is longer than a screenful the app opens a `Pager` over the lines
+//! instead of appending them to the transcript; the frontend draws
+//! `visible()` and `footer()` and routes every key here while it is open.
+//! On close, the lines paged through so far are handed back for the
+//! transcript: quitting early keeps what was read and drops the rest.
+//! A `!view` pager (`Pager::view`) holds a file rather than output, so it
+//! hands back only its metadata line, and may carry syntax spans.
+
+use crate::syntax::SyntaxSpan;
+
+/// Vault config key: `auto` (a screenful, the default), a line count, or
+/// `off`.
+pub const PAGER_KEY: &str = "pager.threshold";
+
+pub const PAGER_HELP: &str = "[space/q/g/G]";
+
+/// When a result is long enough to page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PagerThreshold {
+    Off,
+    /// More lines than the terminal area shows.
+    #[default]
+    Screen,
+    Lines(usize),
+}
+
+impl PagerThreshold {
+    pub fn parse(value: &str) -> Option<PagerThreshold> {
+        match value.trim().to_lowercase().as_str() {
+            "off" | "false" | "0" => Some(PagerThreshold::Off),
+            "auto" | "screen" | "on" => Some(PagerThreshold::Screen),
+            n => n.parse().ok().map(PagerThreshold::Lines),
+        }
+    }
+
+    pub fn should_page(&self, lines: usize, screen_rows: usize) -> bool {
+        match self {
+            PagerThreshold::Off => false,
+            PagerThreshold::Screen => lines > screen_rows,
+            PagerThreshold::Lines(n) => lines > *n,
+        }
+    }
+}
+
+/// Keys the pager understands; frontends translate their own events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PagerKey {
+    Space,
+    PageDown,
+    PageUp,
+    Down,
+    Up,
+    Enter,
+    Backspace,
+    Escape,
+    /// Only the compare view uses it, to move focus.
+    Tab,
+    Char(char),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PagerOutcome {
+    Continue,
+    /// The pager closed; these lines belong in the transcript.
+    Closed(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Pager {
+    lines: Vec<String>,
+    page_height: usize,
+    top: usize,
+    /// One past the furthest line shown so far.
+    seen: usize,
+    /// `Some` while a `/` query is being typed.
+    prompt: Option<String>,
+    query: Option<String>,
+    /// Line of the last match, where `n`/`N` continue from.
+    matched: Option<usize>,
+    /// Set when the last search found nothing.
+    not_found: bool,
+    /// Spans for each line, for a highlighted `!view`; empty otherwise.
+    syntax: Vec<Vec<SyntaxSpan>>,
+    /// A `!view`: only the first line goes back to the transcript.
+    view: bool,
+}
+
+impl Pager {
+    /// `page_height` is the number of content rows (the footer is extra).
+    pub fn new(lines: Vec<String>, page_height: usize) -> Self {
+        let mut pager = Self {
+            lines,
+            page_height: page_height.max(1),
+            top: 0,
+            seen: 0,
+            prompt: None,
+            query: None,
+            matched: None,
+            not_found: false,
+            syntax: Vec::new(),
+            view: false,
+        };
+        pager.mark_seen();
+        pager
+    }
+
+    /// A file opened by `!view`: `lines` starts with its metadata line,
+    /// and `syntax` (when not empty) has spans for each line.
+    pub fn view(lines: Vec<String>, syntax: Vec<Vec<SyntaxSpan>>, page_height: usize) -> Self {
+        Self { syntax, view: true, ..Self::new(lines, page_height) }
+    }
+
+    pub fn set_page_height(&mut self, page_height: usize) {
+        self.page_height = page_height.max(1);
+        self.top = self.top.min(self.last_top());
+        self.mark_seen();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Index of the first visible line.
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    pub fn visible(&self) -> &[String] {
+        let end = (self.top + self.page_height).min(self.lines.len());
+        &self.lines[self.top..end]
+    }
+
+    /// Syntax spans for `visible()`, line for line; empty when the
+    /// content isn't highlighted.
+    pub fn visible_syntax(&self) -> &[Vec<SyntaxSpan>] {
+        let start = self.top.min(self.syntax.len());
+        let end = (self.top + self.page_height).min(self.syntax.len());
+        &self.syntax[start..end]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.top + self.page_height >= self.lines.len()
+    }
+
+    /// Share of the content down to the bottom of the page.
+    pub fn percent(&self) -> usize {
+        if self.lines.is_empty() {
+            return 100;
+        }
+        let bottom = (self.top + self.page_height).min(self.lines.len());
+        bottom * 100 / self.lines.len()
+    }
+
+    /// The active search, if any.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// `-- more (47%) -- [space/q/g/G]`, the search prompt while typing,
+    /// or `(END)` on the last page.
+    pub fn footer(&self) -> String {
+        if let Some(prompt) = &self.prompt {
+            return format!("/{}", prompt);
+        }
+        let status = if self.at_end() {
+            "-- (END) --".to_string()
+        } else {
+            format!("-- more ({}%) --", self.percent())
+        };
+        match (&self.query, self.not_found) {
+            (Some(q), true) => format!("{} pattern not found: {} {}", status, q, PAGER_HELP),
+            _ => format!("{} {}", status, PAGER_HELP),
+        }
+    }
+
+    pub fn handle(&mut self, key: PagerKey) -> PagerOutcome {
+        if self.prompt.is_some() {
+            self.prompt_key(key);
+            return PagerOutcome::Continue;
+        }
+        self.not_found = false;
+        match key {
+            PagerKey::Space | PagerKey::PageDown if self.at_end() => return self.close(),
+            PagerKey::Space | PagerKey::PageDown | PagerKey::Char('f') => self.scroll_to(self.top + self.page_height),
+            PagerKey::PageUp | PagerKey::Char('b') => self.scroll_to(self.top.saturating_sub(self.page_height)),
+            PagerKey::Down | PagerKey::Enter | PagerKey::Char('j') => self.scroll_to(self.top + 1),
+            PagerKey::Up | PagerKey::Char('k') => self.scroll_to(self.top.saturating_sub(1)),
+            PagerKey::Char('g') => self.scroll_to(0),
+            PagerKey::Char('G') => self.scroll_to(self.last_top()),
+            PagerKey::Char('/') => self.prompt = Some(String::new()),
+            PagerKey::Char('n') => self.search_next(),
+            PagerKey::Char('N') => self.search_prev(),
+            PagerKey::Char('q') | PagerKey::Escape => return self.close(),
+            _ => {}
+        }
+        PagerOutcome::Continue
+    }
+
+    fn prompt_key(&mut self, key: PagerKey) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match key {
+            PagerKey::Enter => {
+                let query = std::mem::take(prompt);
+                self.prompt = None;
+                // An empty search repeats the last one.
+                if !query.is_empty() {
+                    self.query = Some(query);
+                    self.matched = None;
+                }
+                self.search_from(self.top);
+            }
+            PagerKey::Escape => self.prompt = None,
+            PagerKey::Backspace if prompt.is_empty() => self.prompt = None,
+            PagerKey::Backspace => {
+                prompt.pop();
+            }
+            PagerKey::Space => prompt.push(' '),
+            PagerKey::Char(c) => prompt.push(c),
+            _ => {}
+        }
+    }
+
+    /// Matching is case-insensitive substring, like `BlockManager::search`.
+    fn matches(&self, index: usize) -> bool {
+        match &self.query {
+            Some(q) => self.lines[index].to_lowercase().contains(&q.to_lowercase()),
+            None => false,
+        }
+    }
+
+    fn search_from(&mut self, start: usize) {
+        if self.query.is_none() {
+            return;
+        }
+        match (start..self.lines.len()).find(|&i| self.matches(i)) {
+            Some(i) => self.jump_to_match(i),
+            None => self.not_found = true,
+        }
+    }
+
+    fn search_next(&mut self) {
+        self.search_from(self.matched.map_or(self.top + 1, |m| m + 1));
+    }
+
+    fn search_prev(&mut self) {
+        if self.query.is_none() {
+            return;
+        }
+        let end = self.matched.unwrap_or(self.top);
+        match (0..end).rev().find(|&i| self.matches(i)) {
+            Some(i) => self.jump_to_match(i),
+            None => self.not_found = true,
+        }
+    }
+
+    fn jump_to_match(&mut self, index: usize) {
+        self.matched = Some(index);
+        self.scroll_to(index);
+    }
+
+    fn last_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.page_height)
+    }
+
+    /// Scroll so `top` is the first line; a match near the end still
+    /// leaves a full page on screen.
+    fn scroll_to(&mut self, top: usize) {
+        self.top = top.min(self.last_top());
+        self.mark_seen();
+    }
+
+    fn mark_seen(&mut self) {
+        let bottom = (self.top + self.page_height).min(self.lines.len());
+        self.seen = self.seen.max(bottom);
+    }
+
+    fn close(&mut self) -> PagerOutcome {
+        let mut lines = std::mem::take(&mut self.lines);
+        lines.truncate(if self.view { 1 } else { self.seen });
+        PagerOutcome::Closed(lines)
+    }
+}
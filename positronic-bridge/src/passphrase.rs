@@ -0,0 +1,91 @@
+//! Masked passphrase entry for `!vault unlock|encrypt|decrypt`.
+//!
+//! While a `PassphrasePrompt` is open the input line belongs to it: the
+//! frontend draws `mask()` instead of the text, Enter hands the entry here
+//! rather than to the engine, and nothing typed reaches the command history
+//! or a `!record` cast. Migrations ask twice; `unlock` asks once.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultAction {
+    Unlock,
+    Encrypt,
+    Decrypt,
+}
+
+impl VaultAction {
+    /// The subcommand of `!vault <sub>` that needs a passphrase.
+    pub fn parse(sub: &str) -> Option<VaultAction> {
+        match sub.trim() {
+            "unlock" => Some(VaultAction::Unlock),
+            "encrypt" => Some(VaultAction::Encrypt),
+            "decrypt" => Some(VaultAction::Decrypt),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VaultAction::Unlock => "unlock",
+            VaultAction::Encrypt => "encrypt",
+            VaultAction::Decrypt => "decrypt",
+        }
+    }
+
+    /// Rewriting every row asks for the passphrase twice.
+    pub fn confirms(&self) -> bool {
+        !matches!(self, VaultAction::Unlock)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptStep {
+    /// Ask again for the confirmation.
+    Confirm,
+    /// The entries differed; start over.
+    Mismatch,
+    /// `confirm` repeats `passphrase` (or equals it, for `unlock`).
+    Ready { passphrase: String, confirm: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct PassphrasePrompt {
+    action: VaultAction,
+    first: Option<String>,
+}
+
+impl PassphrasePrompt {
+    pub fn new(action: VaultAction) -> Self {
+        Self { action, first: None }
+    }
+
+    pub fn action(&self) -> VaultAction {
+        self.action
+    }
+
+    /// Placeholder for the empty input line.
+    pub fn label(&self) -> String {
+        match self.first {
+            Some(_) => format!("Repeat the passphrase to {} (Esc cancels)", self.action.name()),
+            None => format!("Vault passphrase to {} (Esc cancels)", self.action.name()),
+        }
+    }
+
+    pub fn submit(&mut self, entry: String) -> PromptStep {
+        if !self.action.confirms() {
+            return PromptStep::Ready { confirm: entry.clone(), passphrase: entry };
+        }
+        match self.first.take() {
+            None => {
+                self.first = Some(entry);
+                PromptStep::Confirm
+            }
+            Some(first) if first == entry => PromptStep::Ready { passphrase: first, confirm: entry },
+            Some(_) => PromptStep::Mismatch,
+        }
+    }
+}
+
+/// One bullet per typed character.
+pub fn mask(input: &str) -> String {
+    "•".repeat(input.chars().count())
+}
@@ -0,0 +1,247 @@
+//! Executables on `PATH`, for completing the first word of a command.
+//!
+//! Each directory's listing is kept with the directory's mtime; `refresh`
+//! only stats the directories and rereads the ones that changed (something
+//! was installed or removed), so it's cheap enough to run on Tab. `rehash`
+//! rereads everything. The first scan runs on a blocking thread at startup;
+//! until it lands, `matching` returns nothing.
+//!
+//! On Windows a file is executable when its extension is listed in
+//! `PATHEXT`; it is offered without the extension (`docker`, not
+//! `docker.exe`) and matched case-insensitively. Elsewhere any file with an
+//! execute bit counts.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often Tab may stat the `PATH` directories for changes.
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Used when `PATHEXT` is unset on Windows.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+#[derive(Debug, Clone, Default)]
+struct DirListing {
+    mtime: Option<SystemTime>,
+    names: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    /// `PATH` entries in search order.
+    dirs: Vec<PathBuf>,
+    listings: HashMap<PathBuf, DirListing>,
+    /// Sorted, one entry per name.
+    names: Vec<String>,
+    checked: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub struct PathIndex {
+    /// `PATH` and `PATHEXT` to use instead of the environment's.
+    fixed: Option<(OsString, Option<String>)>,
+    state: RwLock<IndexState>,
+}
+
+impl PathIndex {
+    /// An empty index over the process's `PATH`, reread on every refresh.
+    pub fn from_env() -> Self {
+        Self { fixed: None, state: RwLock::new(IndexState::default()) }
+    }
+
+    /// An empty index over `path`. `pathext` switches on Windows matching
+    /// (`.EXE;.CMD`-style); `None` means execute bits.
+    pub fn new(path: impl Into<OsString>, pathext: Option<&str>) -> Self {
+        Self {
+            fixed: Some((path.into(), pathext.map(str::to_string))),
+            state: RwLock::new(IndexState::default()),
+        }
+    }
+
+    fn config(&self) -> (OsString, Option<String>) {
+        match &self.fixed {
+            Some(fixed) => fixed.clone(),
+            None => {
+                let path = std::env::var_os("PATH").unwrap_or_default();
+                let pathext = cfg!(windows)
+                    .then(|| std::env::var("PATHEXT").unwrap_or_else(|_| DEFAULT_PATHEXT.to_string()));
+                (path, pathext)
+            }
+        }
+    }
+
+    fn case_insensitive(&self) -> bool {
+        match &self.fixed {
+            Some((_, pathext)) => pathext.is_some(),
+            None => cfg!(windows),
+        }
+    }
+
+    /// Reread the directories whose mtime changed (all of them if `force`)
+    /// and return the number of executables indexed.
+    pub fn refresh(&self, force: bool) -> usize {
+        let (path, pathext) = self.config();
+        let exts = pathext.as_deref().map(parse_pathext);
+
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        for dir in std::env::split_paths(&path) {
+            if !dir.as_os_str().is_empty() && !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+
+        // Stat and read without holding the lock; Tab keeps answering from
+        // the previous index meanwhile.
+        let previous = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let unchanged_dirs = previous.dirs == dirs;
+        let mut listings = HashMap::with_capacity(dirs.len());
+        let mut changed = !unchanged_dirs || previous.checked.is_none();
+        for dir in &dirs {
+            let mtime = std::fs::metadata(dir).and_then(|m| m.modified()).ok();
+            match previous.listings.get(dir) {
+                Some(listing) if !force && listing.mtime == mtime && mtime.is_some() => {
+                    listings.insert(dir.clone(), listing.clone());
+                }
+                _ => {
+                    changed = true;
+                    let names = scan_dir(dir, exts.as_deref());
+                    listings.insert(dir.clone(), DirListing { mtime, names });
+                }
+            }
+        }
+        drop(previous);
+
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        state.checked = Some(Instant::now());
+        if changed {
+            let mut seen = HashSet::new();
+            let mut names: Vec<String> = dirs
+                .iter()
+                .filter_map(|dir| listings.get(dir))
+                .flat_map(|listing| listing.names.iter())
+                .filter(|name| match exts {
+                    Some(_) => seen.insert(name.to_lowercase()),
+                    None => seen.insert(name.to_string()),
+                })
+                .cloned()
+                .collect();
+            names.sort();
+            state.names = names;
+            state.dirs = dirs;
+            state.listings = listings;
+        }
+        state.names.len()
+    }
+
+    /// `refresh(false)` if the last check is older than `REFRESH_INTERVAL`.
+    /// Does nothing before the first scan has finished.
+    pub fn refresh_if_due(&self) {
+        let due = {
+            let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+            state.checked.is_some_and(|at| at.elapsed() >= REFRESH_INTERVAL)
+        };
+        if due {
+            self.refresh(false);
+        }
+    }
+
+    /// Reread every directory on `PATH` (`!rehash`).
+    pub fn rehash(&self) -> usize {
+        self.refresh(true)
+    }
+
+    /// Whether the first scan has finished.
+    pub fn is_ready(&self) -> bool {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).checked.is_some()
+    }
+
+    /// Whether `name` is on `PATH`, or `None` before the first scan.
+    pub fn contains(&self, name: &str) -> Option<bool> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        state.checked?;
+        if self.case_insensitive() {
+            return Some(state.names.iter().any(|n| n.eq_ignore_ascii_case(name)));
+        }
+        Some(state.names.binary_search_by(|n| n.as_str().cmp(name)).is_ok())
+    }
+
+    /// Executable names starting with `prefix`, sorted.
+    pub fn matching(&self, prefix: &str) -> Vec<String> {
+        if self.case_insensitive() {
+            return self.matching_any_case(prefix);
+        }
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let start = state.names.partition_point(|name| name.as_str() < prefix);
+        state.names[start..]
+            .iter()
+            .take_while(|name| name.starts_with(prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// Every executable name, sorted; for fuzzy matching.
+    pub fn names(&self) -> Vec<String> {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).names.clone()
+    }
+
+    /// `matching`, ignoring case even where names are case-sensitive
+    /// (readline's `completion-ignore-case`).
+    pub fn matching_any_case(&self, prefix: &str) -> Vec<String> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        let prefix = prefix.to_lowercase();
+        state
+            .names
+            .iter()
+            .filter(|name| name.to_lowercase().starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+}
+
+/// `.EXE;.Cmd` → `["exe", "cmd"]`.
+fn parse_pathext(pathext: &str) -> Vec<String> {
+    pathext
+        .split(';')
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Executable names in `dir`; unreadable directories have none.
+fn scan_dir(dir: &Path, exts: Option<&[String]>) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Follows symlinks, so linked binaries count and dangling links don't.
+            let meta = std::fs::metadata(entry.path()).ok()?;
+            if !meta.is_file() {
+                return None;
+            }
+            match exts {
+                Some(exts) => {
+                    let (stem, ext) = name.rsplit_once('.')?;
+                    (!stem.is_empty() && exts.contains(&ext.to_lowercase())).then(|| stem.to_string())
+                }
+                None => is_executable(&meta).then_some(name),
+            }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &std::fs::Metadata) -> bool {
+    true
+}
@@ -1 +1,133 @@
-mod clipboard;
\ No newline at end of file
+//! Platform-specific hooks.
+
+mod clipboard;
+
+use crate::theme_sync::Appearance;
+
+/// Open `url` in the default browser.
+pub fn open_url(url: &str) -> std::io::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        // Unlike `cmd /C start`, this doesn't reinterpret `&` in the URL.
+        let mut c = std::process::Command::new("rundll32");
+        c.arg("url.dll,FileProtocolHandler");
+        c
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    cmd.arg(url).spawn().map(|_| ())
+}
+
+/// Raise a desktop notification: `notify-send` on Linux and the BSDs,
+/// `osascript` on macOS, a tray balloon through PowerShell on Windows.
+/// The text is passed as arguments or environment, never as script.
+pub fn notify_desktop(title: &str, body: &str) -> std::io::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut c = std::process::Command::new("powershell");
+        c.args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; \
+             $n.ShowBalloonTip(5000, $env:POSITRONIC_NOTIFY_TITLE, $env:POSITRONIC_NOTIFY_BODY, 'Info'); \
+             Start-Sleep -Seconds 6; $n.Dispose()",
+        ]);
+        c.env("POSITRONIC_NOTIFY_TITLE", title).env("POSITRONIC_NOTIFY_BODY", body);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = std::process::Command::new("osascript");
+        c.args(["-e", "on run argv", "-e", "display notification (item 2 of argv) with title (item 1 of argv)"])
+            .args(["-e", "end run", title, body]);
+        c
+    } else {
+        let mut c = std::process::Command::new("notify-send");
+        c.args(["--", title, body]);
+        c
+    };
+    cmd.stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null()).spawn().map(|_| ())
+}
+
+/// Play the system alert sound: `SystemSounds` through PowerShell on
+/// Windows, `osascript`'s beep on macOS, the freedesktop `bell` sound
+/// through `canberra-gtk-play` elsewhere.
+pub fn play_bell() -> std::io::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut c = std::process::Command::new("powershell");
+        c.args(["-NoProfile", "-Command", "[System.Media.SystemSounds]::Beep.Play()"]);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = std::process::Command::new("osascript");
+        c.args(["-e", "beep"]);
+        c
+    } else {
+        let mut c = std::process::Command::new("canberra-gtk-play");
+        c.args(["--id", "bell", "--description", "Positronic bell"]);
+        c
+    };
+    cmd.stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null()).spawn().map(|_| ())
+}
+
+/// The OS light/dark preference, read by asking the system: the registry
+/// on Windows, `defaults` on macOS, GNOME's `color-scheme` elsewhere.
+/// `None` when it can't be told. This spawns a process, so it's for
+/// occasional polling where the window system doesn't report changes.
+pub fn os_appearance() -> Option<Appearance> {
+    let output = |program: &str, args: &[&str]| {
+        std::process::Command::new(program)
+            .args(args)
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+    };
+    if cfg!(windows) {
+        let out = output(
+            "reg",
+            &["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize", "/v", "AppsUseLightTheme"],
+        )?;
+        let text = String::from_utf8_lossy(&out.stdout);
+        let value = text.lines().find(|l| l.contains("AppsUseLightTheme"))?.split_whitespace().last()?;
+        Some(if value == "0x0" { Appearance::Dark } else { Appearance::Light })
+    } else if cfg!(target_os = "macos") {
+        // The key only exists in dark mode.
+        let out = output("defaults", &["read", "-g", "AppleInterfaceStyle"])?;
+        let dark = out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "Dark";
+        Some(if dark { Appearance::Dark } else { Appearance::Light })
+    } else {
+        let out = output("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"])?;
+        if !out.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&out.stdout);
+        Some(if text.contains("dark") { Appearance::Dark } else { Appearance::Light })
+    }
+}
+
+/// Vault config key for the command that opens a file at a line, e.g.
+/// `code -g {file}:{line}:{col}` or `subl {file}:{line}`.
+pub const EDITOR_KEY: &str = "editor.command";
+
+/// `template` split into arguments, with `{file}`, `{line}` and `{col}`
+/// filled in. Splitting happens first, so a path with spaces stays one
+/// argument.
+pub fn editor_argv(template: &str, file: &str, line: u32, col: u32) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{file}", file)
+                .replace("{line}", &line.to_string())
+                .replace("{col}", &col.to_string())
+        })
+        .collect()
+}
+
+/// Open `file` at `line:col` with the configured editor command, or hand
+/// the file to the system opener (as links are) when none is set.
+pub fn open_location(template: Option<&str>, file: &str, line: u32, col: u32) -> std::io::Result<()> {
+    let argv = template.map(|t| editor_argv(t, file, line, col)).unwrap_or_default();
+    match argv.split_first() {
+        Some((program, args)) => std::process::Command::new(program).args(args).spawn().map(|_| ()),
+        None => open_url(file),
+    }
+}
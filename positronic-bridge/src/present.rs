@@ -0,0 +1,384 @@
+//! `!present`: redact what is drawn while screen sharing or recording a
+//! demo.
+//!
+//! Presenting changes what reaches the screen, never what is stored: the
+//! grid, native output, the status bar's CWD and the window title are
+//! passed through a `Redactor` on the way out, so `!present off` shows
+//! everything again at once. Hidden are the `PrivacyGuard` secrets, the
+//! user and host names, and whatever `present.redact` adds, each
+//! character replaced by `MASK` so columns stay where they were.
+//! Copies (`present.copy_redacted`), `!record` and `!save` made while
+//! presenting get the redacted text too.
+//!
+//! Redaction runs every frame. Patterns are compiled once, when
+//! presenting starts or the settings change, and the ranges found in a
+//! line are cached by the line's content hash, so an unchanged screen
+//! costs a hash per line. A line is the whole logical line: rows joined by
+//! soft wraps are searched as one, so a token broken across the edge of
+//! the window is still found. Ranges from different patterns are merged
+//! where they overlap or touch, so no part of a secret shows through the
+//! seam between two matches. A private key's body has no pattern of its
+//! own; every line from a `BEGIN … PRIVATE KEY` to its `END` is hidden.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+use positronic_core::history_filter::parse_patterns;
+use positronic_core::state_machine::{Snapshot, WIDE_SPACER};
+use positronic_neural::privacy::PrivacyGuard;
+use regex::Regex;
+
+use crate::clipboard_history;
+
+/// Vault config keys: extra patterns to hide (a JSON array of regexes, or
+/// one regex), and whether copies made while presenting are redacted.
+pub const REDACT_KEY: &str = "present.redact";
+pub const COPY_REDACTED_KEY: &str = "present.copy_redacted";
+
+pub const PRESENT_USAGE: &str = "Usage: !present [on | off]";
+
+/// Drawn in place of each hidden character.
+pub const MASK: char = '█';
+
+/// Status bar badge while presenting.
+pub const BADGE: &str = "📽 PRESENTING";
+
+/// Cached lines before the cache starts over.
+const CACHE_LIMIT: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct PresentSettings {
+    /// `present.redact`, hidden besides the built-in patterns.
+    pub patterns: Vec<Regex>,
+    pub copy_redacted: bool,
+}
+
+impl Default for PresentSettings {
+    fn default() -> Self {
+        Self { patterns: Vec::new(), copy_redacted: true }
+    }
+}
+
+impl PresentSettings {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> PresentSettings {
+        let mut settings = PresentSettings::default();
+        if let Some(value) = lookup(REDACT_KEY) {
+            match parse_patterns(&value) {
+                Ok(patterns) => settings.patterns = patterns,
+                Err(e) => problems.push(format!("{} = \"{}\": {}", REDACT_KEY, value, e)),
+            }
+        }
+        if let Some(value) = lookup(COPY_REDACTED_KEY) {
+            match clipboard_history::parse_flag(&value) {
+                Some(on) => settings.copy_redacted = on,
+                None => problems.push(format!("{} = \"{}\": expected on or off", COPY_REDACTED_KEY, value)),
+            }
+        }
+        settings
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentCommand {
+    Status,
+    On,
+    Off,
+}
+
+impl PresentCommand {
+    /// Parse what follows `!present`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<PresentCommand, String> {
+        match args.trim() {
+            "" => Ok(PresentCommand::Status),
+            "on" => Ok(PresentCommand::On),
+            "off" => Ok(PresentCommand::Off),
+            _ => Err(PRESENT_USAGE.to_string()),
+        }
+    }
+}
+
+/// Whose machine this is: the names hidden besides the secrets.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    /// Written `~` in the status bar's CWD.
+    pub home: Option<String>,
+    pub user: Option<String>,
+    pub host: Option<String>,
+}
+
+impl Identity {
+    /// This process's home directory, user and host names.
+    pub fn current() -> Identity {
+        let var = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| std::env::var(key).ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let host = var(&["HOSTNAME", "COMPUTERNAME"]).or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+        });
+        Identity {
+            home: directories::BaseDirs::new().map(|dirs| dirs.home_dir().display().to_string()),
+            user: var(&["USER", "USERNAME", "LOGNAME"]),
+            host,
+        }
+    }
+
+    /// Whole-word patterns for the user name and the host name, the host
+    /// also without its domain.
+    fn patterns(&self) -> Vec<Regex> {
+        let mut sources = Vec::new();
+        if let Some(user) = &self.user {
+            sources.push(format!(r"\b{}\b", regex::escape(user)));
+        }
+        if let Some(host) = &self.host {
+            sources.push(format!(r"(?i)\b{}\b", regex::escape(host)));
+            if let Some((short, _)) = host.split_once('.').filter(|(short, _)| !short.is_empty()) {
+                sources.push(format!(r"(?i)\b{}\b", regex::escape(short)));
+            }
+        }
+        sources.iter().filter_map(|source| Regex::new(source).ok()).collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Redactor
+// ════════════════════════════════════════════════════════════════════
+
+/// The compiled patterns and the per-line cache. Built when presenting
+/// starts; dropped when it stops.
+#[derive(Debug)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+    home: Option<String>,
+    cache: HashMap<u64, Vec<Range<usize>>>,
+}
+
+impl Redactor {
+    pub fn new(settings: &PresentSettings, identity: &Identity) -> Self {
+        let mut patterns = settings.patterns.clone();
+        patterns.extend(identity.patterns());
+        Self { patterns, home: identity.home.clone().filter(|home| !home.is_empty()), cache: HashMap::new() }
+    }
+
+    /// Byte ranges of `line` to hide: sorted, and merged where they
+    /// overlap or touch.
+    pub fn spans(&self, line: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = PrivacyGuard::spans(line).into_iter().map(|(range, _)| range).collect();
+        for pattern in &self.patterns {
+            find_overlapping(pattern, line, &mut ranges);
+        }
+        merge(ranges)
+    }
+
+    /// `spans`, from the cache when this line was seen before.
+    fn cached_spans(&mut self, line: &str) -> Vec<Range<usize>> {
+        let mut hasher = DefaultHasher::new();
+        line.hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(spans) = self.cache.get(&key) {
+            return spans.clone();
+        }
+        let spans = self.spans(line);
+        if self.cache.len() >= CACHE_LIMIT {
+            self.cache.clear();
+        }
+        self.cache.insert(key, spans.clone());
+        spans
+    }
+
+    /// How many lines' ranges are cached.
+    pub fn cached_lines(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// `text` with what it hides masked, line by line.
+    pub fn redact_text(&mut self, text: &str) -> String {
+        let lines: Vec<&str> = text.split('\n').collect();
+        let fenced = key_fence(&lines);
+        let mut out = String::with_capacity(text.len());
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            if fenced[i] {
+                out.extend(line.chars().map(|c| if c.is_whitespace() { c } else { MASK }));
+            } else {
+                let spans = self.cached_spans(line);
+                mask_str(line, &spans, &mut out);
+            }
+        }
+        out
+    }
+
+    /// The status bar's CWD: the home directory written `~`, and the rest
+    /// redacted like any other line.
+    pub fn cwd(&mut self, cwd: &str) -> String {
+        let collapsed = match self.home.as_deref().and_then(|home| cwd.strip_prefix(home)) {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => format!("~{}", rest),
+            _ => cwd.to_string(),
+        };
+        self.redact_text(&collapsed)
+    }
+
+    /// Mask the hidden cells of `snap` in place; how many were masked.
+    ///
+    /// Soft-wrapped rows are searched as one line. A wide character's
+    /// spacer is masked with it, so the mask covers both columns, and
+    /// combining marks on a masked cell are dropped.
+    pub fn redact_snapshot(&mut self, snap: &mut Snapshot) -> usize {
+        let lines = logical_lines(snap);
+        let texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        let fenced = key_fence(&texts);
+
+        let mut masked = Vec::new();
+        for (line, fenced) in lines.iter().zip(fenced) {
+            if fenced {
+                masked.extend(
+                    line.cells.iter().zip(line.text.chars()).filter(|(_, c)| !c.is_whitespace()).map(|(cell, _)| *cell),
+                );
+                continue;
+            }
+            for span in self.cached_spans(&line.text) {
+                masked.extend(line.byte_cells[span].iter().copied());
+            }
+        }
+        masked.sort_unstable();
+        masked.dedup();
+
+        let cols = snap.cols();
+        let mut count = 0;
+        for &cell in &masked {
+            if snap.cells[cell].0 != MASK {
+                snap.cells[cell].0 = MASK;
+                count += 1;
+            }
+            let next = cell + 1;
+            if next % cols != 0 && snap.cells.get(next).is_some_and(|(c, _)| *c == WIDE_SPACER) {
+                snap.cells[next].0 = MASK;
+            }
+        }
+        if count > 0 {
+            snap.clusters.retain(|(cell, _)| masked.binary_search(cell).is_err());
+        }
+        count
+    }
+}
+
+/// Sort `ranges` and merge those that overlap or touch.
+pub fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| (range.start, range.end));
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Every match of `pattern` in `line`, overlapping ones included: each
+/// search starts one character after the last match began, so the second
+/// `abab` in `ababab` is found although the first one ends inside it.
+fn find_overlapping(pattern: &Regex, line: &str, ranges: &mut Vec<Range<usize>>) {
+    let mut at = 0;
+    while let Some(m) = pattern.find_at(line, at) {
+        if !m.is_empty() {
+            ranges.push(m.range());
+        }
+        match line[m.start()..].chars().next() {
+            Some(c) => at = m.start() + c.len_utf8(),
+            None => break,
+        }
+    }
+}
+
+/// Append `line` to `out` with every character in `spans` masked.
+fn mask_str(line: &str, spans: &[Range<usize>], out: &mut String) {
+    let mut spans = spans.iter().peekable();
+    for (i, c) in line.char_indices() {
+        while spans.next_if(|span| span.end <= i).is_some() {}
+        match spans.peek() {
+            Some(span) if span.start <= i => out.push(MASK),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Which lines lie in a private key block, markers included. A block whose
+/// `BEGIN` has scrolled away is found from its `END`: the unbroken lines
+/// above it with no spaces are its body.
+fn key_fence(lines: &[&str]) -> Vec<bool> {
+    let marker = |line: &str, edge: &str| line.contains(edge) && line.contains("PRIVATE KEY-----");
+    let mut fenced = vec![false; lines.len()];
+    let mut inside = false;
+    for (i, line) in lines.iter().enumerate() {
+        let begin = marker(line, "-----BEGIN ");
+        let end = marker(line, "-----END ");
+        if inside || begin {
+            fenced[i] = true;
+            inside = !end;
+        } else if end {
+            fenced[i] = true;
+            for j in (0..i).rev() {
+                let body = lines[j].trim();
+                if body.is_empty() || body.contains(char::is_whitespace) {
+                    break;
+                }
+                fenced[j] = true;
+            }
+        }
+    }
+    fenced
+}
+
+/// Rows of a snapshot joined across soft wraps, with the cell each byte
+/// of the text came from.
+struct LogicalLine {
+    text: String,
+    /// Per byte of `text`.
+    byte_cells: Vec<usize>,
+    /// Per character of `text`.
+    cells: Vec<usize>,
+}
+
+fn logical_lines(snap: &Snapshot) -> Vec<LogicalLine> {
+    let cols = snap.cols();
+    let mut lines = Vec::new();
+    let mut line = LogicalLine { text: String::new(), byte_cells: Vec::new(), cells: Vec::new() };
+    for row in 0..snap.rows() {
+        let start = row * cols;
+        let mut clusters = snap.row_clusters(row).iter().peekable();
+        for (col, (ch, _)) in snap[row].iter().enumerate() {
+            let cell = start + col;
+            if *ch != WIDE_SPACER {
+                line.text.push(*ch);
+                line.byte_cells.extend(std::iter::repeat_n(cell, ch.len_utf8()));
+                line.cells.push(cell);
+            }
+            if let Some((_, extra)) = clusters.next_if(|(i, _)| *i == cell) {
+                line.text.push_str(extra);
+                line.byte_cells.extend(std::iter::repeat_n(cell, extra.len()));
+                line.cells.extend(std::iter::repeat_n(cell, extra.chars().count()));
+            }
+        }
+        if !snap.wraps(row) {
+            lines.push(std::mem::replace(
+                &mut line,
+                LogicalLine { text: String::new(), byte_cells: Vec::new(), cells: Vec::new() },
+            ));
+        }
+    }
+    if !line.text.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,183 @@
+//! Quad batching for the GPU rectangle pipeline.
+//!
+//! Widgets push `QuadInstance`s in whatever order they draw. Before upload,
+//! `QuadBatch::prepare` orders them by `QuadLayer` (stable, so draw order
+//! within a layer is kept), merges horizontally adjacent quads of the same
+//! color, row and layer into one wide quad, and hashes the result. If the
+//! hash matches the previous upload the GPU buffer is left untouched.
+//!
+//! This module has no GPU dependencies so the merge logic can be tested on
+//! its own; `gfx::quad` owns the buffer and the draw call.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::renderer::Rgba;
+
+/// Horizontal gap (px) still treated as touching when merging runs.
+const ADJACENT_EPSILON: f32 = 0.01;
+
+// ════════════════════════════════════════════════════════════════════
+// Quad Instance
+// ════════════════════════════════════════════════════════════════════
+
+/// Paint order. Higher layers always draw over lower ones, regardless of
+/// the order widgets pushed them in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QuadLayer {
+    /// Panel, bar and cell backgrounds.
+    #[default]
+    Background = 0,
+    /// Floating panels drawn over the terminal (Holodeck, perf overlay).
+    Overlay = 1,
+    /// Text selection highlight.
+    Selection = 2,
+    /// Caret.
+    Cursor = 3,
+}
+
+/// A single colored rectangle to draw. Coordinates in pixels.
+#[derive(Debug, Clone, Copy)]
+pub struct QuadInstance {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub color: Rgba,
+    pub layer: QuadLayer,
+}
+
+impl QuadInstance {
+    fn right(&self) -> f32 {
+        self.x + self.w
+    }
+
+    /// True if `next` continues this quad's run to the right.
+    fn extends_with(&self, next: &QuadInstance) -> bool {
+        self.layer == next.layer
+            && self.color == next.color
+            && self.y.to_bits() == next.y.to_bits()
+            && self.h.to_bits() == next.h.to_bits()
+            && (next.x - self.right()).abs() <= ADJACENT_EPSILON
+    }
+
+    fn hash_into(&self, h: &mut DefaultHasher) {
+        for v in [self.x, self.y, self.w, self.h] {
+            v.to_bits().hash(h);
+        }
+        for v in [self.color.r, self.color.g, self.color.b, self.color.a] {
+            v.to_bits().hash(h);
+        }
+        self.layer.hash(h);
+    }
+}
+
+/// Merge runs of adjacent same-color quads in place. Only neighbours in
+/// the given order are merged, so painter's order is preserved.
+pub fn coalesce_runs(quads: &mut Vec<QuadInstance>) {
+    let mut out = 0;
+    for i in 0..quads.len() {
+        let q = quads[i];
+        if out > 0 && quads[out - 1].extends_with(&q) {
+            let prev = &mut quads[out - 1];
+            prev.w = q.right() - prev.x;
+        } else {
+            quads[out] = q;
+            out += 1;
+        }
+    }
+    quads.truncate(out);
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Counters
+// ════════════════════════════════════════════════════════════════════
+
+/// Quad pipeline counters, shown by `!perf overlay`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuadStats {
+    /// Quads pushed by widgets last frame.
+    pub submitted: usize,
+    /// Instances drawn last frame, after merging runs.
+    pub drawn: usize,
+    /// Draw calls issued last frame.
+    pub draw_calls: u32,
+    /// Frames whose instance buffer was (re)written.
+    pub buffer_writes: u64,
+    /// Frames that reused the previous upload unchanged.
+    pub writes_skipped: u64,
+    /// Current instance buffer capacity.
+    pub capacity: usize,
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Batch
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Default)]
+pub struct QuadBatch {
+    pending: Vec<QuadInstance>,
+    prepared: Vec<QuadInstance>,
+    submitted: usize,
+    uploaded_hash: Option<u64>,
+}
+
+impl QuadBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a quad for this frame.
+    pub fn push(&mut self, quad: QuadInstance) {
+        self.pending.push(quad);
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drop this frame's quads. The prepared list and upload hash are kept
+    /// so an identical next frame can skip its upload.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Forget the last upload (e.g. the GPU buffer was recreated).
+    pub fn invalidate(&mut self) {
+        self.uploaded_hash = None;
+    }
+
+    /// Order, merge and hash this frame's quads. Returns true if the result
+    /// differs from the last upload and must be written to the GPU.
+    pub fn prepare(&mut self) -> bool {
+        self.submitted = self.pending.len();
+        self.prepared.clear();
+        self.prepared.extend_from_slice(&self.pending);
+        self.prepared.sort_by_key(|q| q.layer);
+        coalesce_runs(&mut self.prepared);
+
+        let mut h = DefaultHasher::new();
+        self.prepared.len().hash(&mut h);
+        for q in &self.prepared {
+            q.hash_into(&mut h);
+        }
+        let hash = h.finish();
+
+        let changed = self.uploaded_hash != Some(hash);
+        self.uploaded_hash = Some(hash);
+        changed
+    }
+
+    /// Quads pushed before the last `prepare`.
+    pub fn submitted(&self) -> usize {
+        self.submitted
+    }
+
+    /// The ordered, merged instances from the last `prepare`.
+    pub fn instances(&self) -> &[QuadInstance] {
+        &self.prepared
+    }
+}
@@ -0,0 +1,375 @@
+// positronic-bridge/src/render_path.rs
+//! Which renderer draws the window, and getting it back after a device loss.
+//!
+//! `plan` turns a `RendererChoice` into the attempts to make in order:
+//! each GPU backend the platform prefers with a hardware adapter, then
+//! the same backends with a CPU adapter (llvmpipe, WARP, lavapipe). When
+//! every attempt fails the window falls back to the software renderer,
+//! which draws the grid as monochrome text. `select` runs a plan against a
+//! probe, so the decision is testable with fake adapters; `gfx::GpuState`
+//! supplies the real one.
+//!
+//! `DeviceRecovery` sequences what happens when a device is lost at run
+//! time: reopen the same adapter a few times with growing delays, then
+//! fall back through the rest of the plan.
+//!
+//! The choice comes from `--renderer` or `POSITRONIC_RENDERER`: the window
+//! exists before the vault is open, so it can't be a vault setting.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Environment variable read when `--renderer` isn't given.
+pub const RENDERER_ENV: &str = "POSITRONIC_RENDERER";
+
+pub const RENDERER_USAGE: &str = "auto, vulkan, dx12, metal, gl or software";
+
+/// Delays before each reopen of a lost device; then it falls back.
+pub const RECOVERY_DELAYS: [Duration; 3] =
+    [Duration::ZERO, Duration::from_millis(250), Duration::from_millis(1000)];
+
+/// What the user asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RendererChoice {
+    #[default]
+    Auto,
+    Backend(Backend),
+    Software,
+}
+
+impl RendererChoice {
+    pub fn parse(name: &str) -> Option<RendererChoice> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(RendererChoice::Auto),
+            "software" | "cpu" => Some(RendererChoice::Software),
+            other => Backend::parse(other).map(RendererChoice::Backend),
+        }
+    }
+
+    /// `flag`, else the environment variable, else `Auto`. An unknown
+    /// environment value is logged and ignored.
+    pub fn resolve(flag: Option<RendererChoice>, env: Option<&str>) -> RendererChoice {
+        if let Some(choice) = flag {
+            return choice;
+        }
+        match env.filter(|v| !v.trim().is_empty()) {
+            Some(value) => RendererChoice::parse(value).unwrap_or_else(|| {
+                tracing::warn!("{}={} is not {}; using auto", RENDERER_ENV, value, RENDERER_USAGE);
+                RendererChoice::Auto
+            }),
+            None => RendererChoice::Auto,
+        }
+    }
+}
+
+impl fmt::Display for RendererChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererChoice::Auto => write!(f, "auto"),
+            RendererChoice::Backend(backend) => write!(f, "{}", backend.name().to_ascii_lowercase()),
+            RendererChoice::Software => write!(f, "software"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Vulkan,
+    Dx12,
+    Metal,
+    Gl,
+}
+
+impl Backend {
+    pub fn parse(name: &str) -> Option<Backend> {
+        match name {
+            "vulkan" | "vk" => Some(Backend::Vulkan),
+            "dx12" | "d3d12" => Some(Backend::Dx12),
+            "metal" => Some(Backend::Metal),
+            "gl" | "opengl" | "gles" => Some(Backend::Gl),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Vulkan => "Vulkan",
+            Backend::Dx12 => "DX12",
+            Backend::Metal => "Metal",
+            Backend::Gl => "GL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Windows,
+    MacOs,
+    Other,
+}
+
+impl Platform {
+    pub fn current() -> Platform {
+        if cfg!(windows) {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Other
+        }
+    }
+
+    /// Backends in the order they are tried.
+    pub fn backends(self) -> &'static [Backend] {
+        match self {
+            Platform::Windows => &[Backend::Vulkan, Backend::Dx12, Backend::Gl],
+            Platform::MacOs => &[Backend::Metal, Backend::Gl],
+            Platform::Other => &[Backend::Vulkan, Backend::Gl],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterKind {
+    Discrete,
+    Integrated,
+    Virtual,
+    Cpu,
+    Other,
+}
+
+impl AdapterKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AdapterKind::Discrete => "discrete GPU",
+            AdapterKind::Integrated => "integrated GPU",
+            AdapterKind::Virtual => "virtual GPU",
+            AdapterKind::Cpu => "CPU",
+            AdapterKind::Other => "unknown device",
+        }
+    }
+}
+
+/// What a probe found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub backend: Backend,
+    pub kind: AdapterKind,
+}
+
+/// One step of a plan: a backend, with a hardware or a CPU adapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    pub backend: Backend,
+    pub cpu: bool,
+}
+
+impl fmt::Display for Attempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.backend.name(), if self.cpu { " (CPU)" } else { "" })
+    }
+}
+
+/// The attempts for `choice`, in order. Software is what is left when
+/// they all fail, so `Software` plans nothing.
+pub fn plan(choice: RendererChoice, platform: Platform) -> Vec<Attempt> {
+    let backends: Vec<Backend> = match choice {
+        RendererChoice::Auto => platform.backends().to_vec(),
+        RendererChoice::Backend(backend) => vec![backend],
+        RendererChoice::Software => Vec::new(),
+    };
+    let hardware = backends.iter().map(|&backend| Attempt { backend, cpu: false });
+    let cpu = backends.iter().map(|&backend| Attempt { backend, cpu: true });
+    hardware.chain(cpu).collect()
+}
+
+/// What a probe opened: the adapter and whatever the caller keeps of it.
+pub struct Probed<D> {
+    pub info: AdapterInfo,
+    pub device: D,
+}
+
+/// How the window is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderPath {
+    Gpu { attempt: Attempt, adapter: AdapterInfo },
+    Software,
+}
+
+impl RenderPath {
+    /// `Vulkan · NVIDIA GeForce RTX 3060 (discrete GPU)`
+    pub fn summary(&self) -> String {
+        match self {
+            RenderPath::Gpu { adapter, .. } => {
+                format!("{} · {} ({})", adapter.backend.name(), adapter.name, adapter.kind.label())
+            }
+            RenderPath::Software => "software (monochrome text, no GPU)".to_string(),
+        }
+    }
+}
+
+/// The outcome of `select`: the path, its device (None for software), and
+/// why earlier attempts were passed over.
+pub struct Selection<D> {
+    pub path: RenderPath,
+    pub device: Option<D>,
+    pub skipped: Vec<(Attempt, String)>,
+}
+
+/// Run `attempts` against `probe`; the first that opens wins. A hardware
+/// attempt that only finds a CPU adapter is held back in case a later
+/// backend has real hardware.
+pub fn select<D>(attempts: &[Attempt], mut probe: impl FnMut(Attempt) -> Result<Probed<D>, String>) -> Selection<D> {
+    let mut skipped = Vec::new();
+    let mut held: Option<(Attempt, Probed<D>)> = None;
+    for &attempt in attempts {
+        if attempt.cpu && held.is_some() {
+            break;
+        }
+        match probe(attempt) {
+            Ok(found) if !attempt.cpu && found.info.kind == AdapterKind::Cpu => {
+                skipped.push((attempt, format!("only a CPU adapter ({})", found.info.name)));
+                held.get_or_insert((attempt, found));
+            }
+            Ok(found) => {
+                return Selection {
+                    path: RenderPath::Gpu { attempt, adapter: found.info },
+                    device: Some(found.device),
+                    skipped,
+                };
+            }
+            Err(reason) => skipped.push((attempt, reason)),
+        }
+    }
+    match held {
+        Some((attempt, found)) => {
+            skipped.retain(|(a, _)| *a != attempt);
+            Selection { path: RenderPath::Gpu { attempt, adapter: found.info }, device: Some(found.device), skipped }
+        }
+        None => Selection { path: RenderPath::Software, device: None, skipped },
+    }
+}
+
+/// What `!doctor` and the boot log say about the renderer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderReport {
+    pub choice: RendererChoice,
+    pub path: RenderPath,
+    pub skipped: Vec<(Attempt, String)>,
+    /// Device losses survived this session.
+    pub recoveries: u32,
+}
+
+impl RenderReport {
+    pub fn new<D>(choice: RendererChoice, selection: &Selection<D>) -> Self {
+        Self { choice, path: selection.path.clone(), skipped: selection.skipped.clone(), recoveries: 0 }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("🖥 Renderer: {} (--renderer {})", self.path.summary(), self.choice)];
+        for (attempt, reason) in &self.skipped {
+            lines.push(format!("   passed over {}: {}", attempt, reason));
+        }
+        if self.path == RenderPath::Software && self.choice != RendererChoice::Software {
+            lines.push("⚠️ No usable GPU: drawing in software; colors, images and the Holodeck are off.".to_string());
+        }
+        if self.recoveries > 0 {
+            lines.push(format!("   recovered from {} lost device(s) this session", self.recoveries));
+        }
+        lines
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Device loss
+// ════════════════════════════════════════════════════════════════════
+
+/// What the app should do about a lost device now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryStep {
+    /// Nothing is lost.
+    Idle,
+    /// Try again after this long.
+    Wait(Duration),
+    /// Reopen the adapter that was lost; `attempt` counts from 1.
+    Reopen { attempt: usize },
+    /// Reopening failed every time: select again from the rest of the plan.
+    FallBack,
+}
+
+#[derive(Debug, Clone)]
+struct Lost {
+    reason: String,
+    failures: usize,
+    next_at: Instant,
+}
+
+/// Sequencing for a device lost at run time.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceRecovery {
+    lost: Option<Lost>,
+    recoveries: u32,
+}
+
+impl DeviceRecovery {
+    /// The device is gone; the first reopen is due at once. A loss while
+    /// already recovering keeps the count of failed reopens.
+    pub fn lost(&mut self, reason: &str, now: Instant) {
+        if self.lost.is_none() {
+            self.lost = Some(Lost { reason: reason.to_string(), failures: 0, next_at: now });
+        }
+    }
+
+    pub fn is_recovering(&self) -> bool {
+        self.lost.is_some()
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.lost.as_ref().map(|l| l.reason.as_str())
+    }
+
+    pub fn step(&self, now: Instant) -> RecoveryStep {
+        let Some(lost) = &self.lost else {
+            return RecoveryStep::Idle;
+        };
+        if lost.failures >= RECOVERY_DELAYS.len() {
+            return RecoveryStep::FallBack;
+        }
+        match lost.next_at.checked_duration_since(now) {
+            Some(wait) if !wait.is_zero() => RecoveryStep::Wait(wait),
+            _ => RecoveryStep::Reopen { attempt: lost.failures + 1 },
+        }
+    }
+
+    /// A reopen failed: the next one waits its delay.
+    pub fn reopen_failed(&mut self, now: Instant) {
+        if let Some(lost) = &mut self.lost {
+            lost.failures += 1;
+            if let Some(delay) = RECOVERY_DELAYS.get(lost.failures) {
+                lost.next_at = now + *delay;
+            }
+        }
+    }
+
+    /// Drawing works again, by reopening or by falling back. Returns the
+    /// line to show.
+    pub fn restored(&mut self, path: &RenderPath) -> Option<String> {
+        let lost = self.lost.take()?;
+        self.recoveries += 1;
+        Some(format!("🖥 GPU device lost ({}); drawing again with {}", lost.reason, path.summary()))
+    }
+
+    pub fn recoveries(&self) -> u32 {
+        self.recoveries
+    }
+}
+
+/// The attempts left once `lost` has failed to reopen: the rest of the
+/// plan after it.
+pub fn fallback_plan(plan: &[Attempt], lost: Attempt) -> Vec<Attempt> {
+    match plan.iter().position(|a| *a == lost) {
+        Some(i) => plan[i + 1..].to_vec(),
+        None => plan.to_vec(),
+    }
+}
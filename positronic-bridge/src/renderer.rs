@@ -3,7 +3,20 @@
 //! Converts PTY snapshots and text to colored span data for the GPU text renderer.
 //! Zero UI dependencies — this module produces data structures that gfx::text consumes.
 
-use positronic_core::state_machine::{MyColor, Snapshot};
+use std::time::Duration;
+
+use positronic_core::diagnostics::{self, Severity};
+use positronic_core::diff;
+use positronic_core::fix;
+use positronic_core::state_machine::{CellAttrs, CellStyle, MyColor, Snapshot, WIDE_SPACER};
+use positronic_core::time_format::Clock;
+use positronic_core::timeline::{self, Status};
+
+use crate::block::{
+    format_duration, LineKind, TerminalBlock, TIME_FORMAT, TIME_FORMAT_12H, TIME_FORMAT_12H_WIDTH, TIME_FORMAT_WIDTH,
+};
+use crate::heatmap;
+use crate::syntax::{self, SyntaxSpan, TokenClass};
 
 // ════════════════════════════════════════════════════════════════════
 // Color Types (replaces iced::Color)
@@ -37,6 +50,12 @@ impl Rgba {
         ]
     }
 
+    /// `self` moved `t` (0..1) of the way towards `other`.
+    pub fn blend(self, other: Rgba, t: f32) -> Rgba {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Rgba::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
+
     /// Convert to glyphon-compatible color.
     pub fn to_glyphon(&self) -> glyphon::Color {
         let [r, g, b, a] = self.to_srgb8();
@@ -48,11 +67,24 @@ impl Rgba {
 // Colored Span (replaces iced::widget::text::Span)
 // ════════════════════════════════════════════════════════════════════
 
+/// Weight, slant, decorations and background of a span. The default is
+/// plain text on the panel's own background.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    /// Underline color, if underlined.
+    pub underline: Option<Rgba>,
+    pub strikethrough: bool,
+    pub background: Option<Rgba>,
+}
+
 /// A run of text with a single foreground color.
 #[derive(Debug, Clone)]
 pub struct ColoredSpan {
     pub text: String,
     pub color: Rgba,
+    pub style: SpanStyle,
 }
 
 impl ColoredSpan {
@@ -60,8 +92,76 @@ impl ColoredSpan {
         Self {
             text: text.into(),
             color,
+            style: SpanStyle::default(),
+        }
+    }
+
+    pub fn styled(text: impl Into<String>, color: Rgba, style: SpanStyle) -> Self {
+        Self {
+            text: text.into(),
+            color,
+            style,
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Decorations
+// ════════════════════════════════════════════════════════════════════
+
+/// Where the text engine placed one glyph, and which span it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphBox {
+    pub span: usize,
+    pub x: f32,
+    pub w: f32,
+    pub line_top: f32,
+    pub line_height: f32,
+    /// Baseline y.
+    pub baseline: f32,
+}
+
+/// A rectangle drawn with a span: its background, underline or strike.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decoration {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+    pub color: Rgba,
+}
+
+/// Backgrounds, underlines and strikethroughs for `glyphs`, one rectangle
+/// per run of consecutive glyphs from the same span on the same line.
+/// `thickness` is the line width of underlines and strikes.
+pub fn span_decorations(spans: &[ColoredSpan], glyphs: &[GlyphBox], thickness: f32) -> Vec<Decoration> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < glyphs.len() {
+        let first = glyphs[i];
+        let mut end = first.x + first.w;
+        let mut j = i + 1;
+        while j < glyphs.len() && glyphs[j].span == first.span && glyphs[j].line_top == first.line_top {
+            end = end.max(glyphs[j].x + glyphs[j].w);
+            j += 1;
+        }
+        i = j;
+
+        let Some(span) = spans.get(first.span) else { continue };
+        let (x, w) = (first.x, end - first.x);
+        if let Some(color) = span.style.background {
+            out.push(Decoration { x, y: first.line_top, w, h: first.line_height, color });
+        }
+        if let Some(color) = span.style.underline {
+            let y = (first.baseline + thickness * 2.0).min(first.line_top + first.line_height - thickness);
+            out.push(Decoration { x, y, w, h: thickness, color });
+        }
+        if span.style.strikethrough {
+            let y = first.baseline - (first.baseline - first.line_top) * 0.35;
+            out.push(Decoration { x, y, w, h: thickness, color: span.color });
         }
     }
+    out
 }
 
 // ════════════════════════════════════════════════════════════════════
@@ -105,6 +205,17 @@ impl ThemeName {
         }
     }
 
+    /// How SGR 2 (dim) text is drawn in this theme.
+    pub fn dim_style(&self) -> DimStyle {
+        match self {
+            ThemeName::Default => DimStyle::Blend(0.45),
+            ThemeName::Monokai => DimStyle::Blend(0.4),
+            // Blending towards Solarized's blue base muddies every hue.
+            ThemeName::Solarized => DimStyle::Alpha(0.6),
+            ThemeName::Dracula => DimStyle::Blend(0.4),
+        }
+    }
+
     /// Background color for the theme.
     pub fn bg_color(&self) -> Rgba {
         match self {
@@ -139,6 +250,117 @@ impl ThemeName {
     pub fn cursor_color(&self) -> Rgba {
         Rgba::new(0.9, 0.9, 0.9, 0.8)
     }
+
+    /// Line color for `!scope` channel `n`; the palette repeats after four.
+    pub fn trace_color(&self, n: usize) -> Rgba {
+        let palette = match self {
+            ThemeName::Default => [
+                Rgba::rgb(0.35, 0.85, 0.55),
+                Rgba::rgb(0.95, 0.75, 0.3),
+                Rgba::rgb(0.4, 0.7, 1.0),
+                Rgba::rgb(0.95, 0.45, 0.6),
+            ],
+            ThemeName::Monokai => [
+                Rgba::rgb(0.65, 0.89, 0.18),
+                Rgba::rgb(0.9, 0.86, 0.45),
+                Rgba::rgb(0.4, 0.85, 0.94),
+                Rgba::rgb(0.98, 0.15, 0.45),
+            ],
+            ThemeName::Solarized => [
+                Rgba::rgb(0.52, 0.6, 0.0),
+                Rgba::rgb(0.71, 0.54, 0.0),
+                Rgba::rgb(0.15, 0.55, 0.82),
+                Rgba::rgb(0.83, 0.21, 0.51),
+            ],
+            ThemeName::Dracula => [
+                Rgba::rgb(0.31, 0.98, 0.48),
+                Rgba::rgb(0.95, 0.98, 0.55),
+                Rgba::rgb(0.55, 0.91, 0.99),
+                Rgba::rgb(1.0, 0.47, 0.78),
+            ],
+        };
+        palette[n % palette.len()]
+    }
+
+    /// Color for highlighted code in block output. Diffs reuse the trace
+    /// palette: green added, red removed, blue hunks, yellow headers.
+    pub fn syntax_color(&self, class: TokenClass) -> Rgba {
+        // Keyword, type, string, number, comment, key, attribute, heading.
+        let code = match self {
+            ThemeName::Default => [
+                Rgba::rgb(0.8, 0.5, 0.95),
+                Rgba::rgb(0.4, 0.8, 0.85),
+                Rgba::rgb(0.6, 0.85, 0.45),
+                Rgba::rgb(0.95, 0.65, 0.35),
+                Rgba::rgb(0.45, 0.5, 0.55),
+                Rgba::rgb(0.45, 0.7, 1.0),
+                Rgba::rgb(0.95, 0.8, 0.4),
+                Rgba::rgb(0.95, 0.55, 0.5),
+            ],
+            ThemeName::Monokai => [
+                Rgba::rgb(0.98, 0.15, 0.45),
+                Rgba::rgb(0.4, 0.85, 0.94),
+                Rgba::rgb(0.9, 0.86, 0.45),
+                Rgba::rgb(0.68, 0.51, 1.0),
+                Rgba::rgb(0.46, 0.44, 0.37),
+                Rgba::rgb(0.65, 0.89, 0.18),
+                Rgba::rgb(0.99, 0.59, 0.12),
+                Rgba::rgb(0.4, 0.85, 0.94),
+            ],
+            ThemeName::Solarized => [
+                Rgba::rgb(0.52, 0.6, 0.0),
+                Rgba::rgb(0.71, 0.54, 0.0),
+                Rgba::rgb(0.16, 0.63, 0.6),
+                Rgba::rgb(0.83, 0.21, 0.51),
+                Rgba::rgb(0.35, 0.43, 0.46),
+                Rgba::rgb(0.15, 0.55, 0.82),
+                Rgba::rgb(0.8, 0.29, 0.09),
+                Rgba::rgb(0.42, 0.44, 0.77),
+            ],
+            ThemeName::Dracula => [
+                Rgba::rgb(1.0, 0.47, 0.78),
+                Rgba::rgb(0.55, 0.91, 0.99),
+                Rgba::rgb(0.95, 0.98, 0.55),
+                Rgba::rgb(0.74, 0.58, 0.98),
+                Rgba::rgb(0.38, 0.45, 0.64),
+                Rgba::rgb(0.31, 0.98, 0.48),
+                Rgba::rgb(1.0, 0.72, 0.42),
+                Rgba::rgb(0.74, 0.58, 0.98),
+            ],
+        };
+        match class {
+            TokenClass::Keyword => code[0],
+            TokenClass::Type => code[1],
+            TokenClass::String => code[2],
+            TokenClass::Number => code[3],
+            TokenClass::Comment => code[4],
+            TokenClass::Key => code[5],
+            TokenClass::Attribute => code[6],
+            TokenClass::Heading => code[7],
+            TokenClass::Added => self.trace_color(0),
+            TokenClass::Removed => self.trace_color(3),
+            TokenClass::Hunk => self.trace_color(2),
+            TokenClass::DiffHeader => self.trace_color(1),
+        }
+    }
+}
+
+/// How dim text is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DimStyle {
+    /// Mix this much of the background into the text color.
+    Blend(f32),
+    /// Draw the text color at this opacity.
+    Alpha(f32),
+}
+
+impl DimStyle {
+    pub fn apply(self, fg: Rgba, bg: Rgba) -> Rgba {
+        match self {
+            DimStyle::Blend(t) => fg.blend(Rgba { a: fg.a, ..bg }, t),
+            DimStyle::Alpha(a) => Rgba { a: fg.a * a, ..fg },
+        }
+    }
 }
 
 // ════════════════════════════════════════════════════════════════════
@@ -147,46 +369,413 @@ impl ThemeName {
 
 /// Convert direct output (plain text) to colored spans for display.
 pub fn direct_to_spans(text: &str) -> Vec<ColoredSpan> {
-    let mut spans = Vec::new();
+    text.lines().flat_map(direct_line_spans).collect()
+}
 
-    for line in text.lines() {
-        let color = if line.starts_with("➜") {
-            Rgba::rgb(0.3, 0.85, 0.3)
-        } else if line.starts_with("❌") {
-            Rgba::rgb(1.0, 0.35, 0.35)
-        } else if line.starts_with("⚡") || line.starts_with("✓") {
-            Rgba::rgb(0.3, 0.8, 1.0)
-        } else if line.starts_with("  💡")
-            || line.starts_with("📋")
-            || line.starts_with("📂")
-            || line.starts_with("🎨")
-        {
-            Rgba::rgb(0.6, 0.6, 0.85)
-        } else if line.starts_with("🔍") {
-            Rgba::rgb(1.0, 0.85, 0.3)
-        } else if line.starts_with("🏆") || line.starts_with("📊") {
-            Rgba::rgb(0.3, 0.9, 0.6)
-        } else if line.starts_with("📜") || line.starts_with("📝") || line.starts_with("🔖") {
-            Rgba::rgb(0.7, 0.7, 0.9)
-        } else if line.starts_with("╔") || line.starts_with("║") || line.starts_with("╚") {
-            Rgba::rgb(0.4, 0.5, 0.6)
-        } else {
-            Rgba::rgb(0.85, 0.85, 0.85)
-        };
+/// One direct-output line as spans: a `!timeline` bar gets each segment
+/// colored by its command's exit status, a correction preview its removed
+/// words struck in red and added ones in green, a `!stats heatmap` row its
+/// cells blanked for the quads behind, anything else one span.
+pub fn direct_line_spans(line: &str) -> Vec<ColoredSpan> {
+    if line.starts_with(fix::PREVIEW_PREFIX) {
+        return correction_spans(line);
+    }
+    if positronic_core::heatmap::is_heat_line(line) {
+        return vec![direct_line_span(&heatmap::blank(line))];
+    }
+    let bar = line.find(timeline::BAR_OPEN).zip(line.rfind(timeline::BAR_CLOSE));
+    let Some((open, close)) = bar.filter(|(open, close)| open < close) else {
+        return vec![direct_line_span(line)];
+    };
+    let base = line_kind_color(LineKind::Normal);
+    let inside = open + timeline::BAR_OPEN.len_utf8();
+    let mut spans = vec![ColoredSpan::new(&line[..inside], base)];
+    let mut run = String::new();
+    let mut run_color = base;
+    for c in line[inside..close].chars() {
+        let color = Status::of_glyph(c).map_or(base, timeline_color);
+        if color != run_color && !run.is_empty() {
+            spans.push(ColoredSpan::new(std::mem::take(&mut run), run_color));
+        }
+        run_color = color;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(ColoredSpan::new(run, run_color));
+    }
+    spans.push(ColoredSpan::new(format!("{}\n", &line[close..]), base));
+    spans
+}
+
+/// A `fix::PREVIEW_PREFIX` line, its diff markers (see
+/// `diff::CommandDiff::marked`) turned into colors.
+fn correction_spans(line: &str) -> Vec<ColoredSpan> {
+    let base = Rgba::rgb(0.85, 0.85, 0.85);
+    let mut spans: Vec<ColoredSpan> = diff::split_marked(line)
+        .into_iter()
+        .map(|(op, text)| match op {
+            diff::DiffOp::Equal => ColoredSpan::new(text, base),
+            diff::DiffOp::Removed => ColoredSpan::styled(
+                text,
+                line_kind_color(LineKind::Error),
+                SpanStyle { strikethrough: true, ..SpanStyle::default() },
+            ),
+            diff::DiffOp::Added => ColoredSpan::new(text, line_kind_color(LineKind::Success)),
+        })
+        .collect();
+    spans.push(ColoredSpan::new("\n", base));
+    spans
+}
+
+/// Color of a timeline segment.
+pub fn timeline_color(status: Status) -> Rgba {
+    match status {
+        Status::Ok => Rgba::rgb(0.3, 0.85, 0.3),
+        Status::Failed => line_kind_color(LineKind::Error),
+        Status::Unknown => line_kind_color(LineKind::Muted),
+    }
+}
+
+/// Classify one direct-output line by its emoji prefix and color it.
+pub fn direct_line_span(line: &str) -> ColoredSpan {
+    let color = if line.starts_with("➜") {
+        Rgba::rgb(0.3, 0.85, 0.3)
+    } else if line.starts_with("❌") {
+        Rgba::rgb(1.0, 0.35, 0.35)
+    } else if line.starts_with("⚡") || line.starts_with("✓") {
+        Rgba::rgb(0.3, 0.8, 1.0)
+    } else if line.starts_with("  💡")
+        || line.starts_with("📋")
+        || line.starts_with("📂")
+        || line.starts_with("🎨")
+    {
+        Rgba::rgb(0.6, 0.6, 0.85)
+    } else if line.starts_with("🔍") {
+        Rgba::rgb(1.0, 0.85, 0.3)
+    } else if line.starts_with("🏆") || line.starts_with("📊") {
+        Rgba::rgb(0.3, 0.9, 0.6)
+    } else if line.starts_with("📜") || line.starts_with("📝") || line.starts_with("🔖") {
+        Rgba::rgb(0.7, 0.7, 0.9)
+    } else if line.starts_with("╔") || line.starts_with("║") || line.starts_with("╚") {
+        Rgba::rgb(0.4, 0.5, 0.6)
+    } else if line.starts_with(diff::ADDED_GUTTER) {
+        line_kind_color(LineKind::Success)
+    } else if line.starts_with(diff::REMOVED_GUTTER) {
+        line_kind_color(LineKind::Error)
+    } else if line.starts_with("@@ ") || line.starts_with("🔀") {
+        line_kind_color(LineKind::Info)
+    } else {
+        Rgba::rgb(0.85, 0.85, 0.85)
+    };
+
+    ColoredSpan::new(format!("{}\n", line), color)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Block Rendering
+// ════════════════════════════════════════════════════════════════════
+
+/// Color for a classified block line.
+pub fn line_kind_color(kind: LineKind) -> Rgba {
+    match kind {
+        LineKind::Normal => Rgba::rgb(0.85, 0.85, 0.85),
+        LineKind::Error => Rgba::rgb(1.0, 0.35, 0.35),
+        LineKind::Warning => Rgba::rgb(1.0, 0.85, 0.3),
+        LineKind::Info => Rgba::rgb(0.6, 0.6, 0.85),
+        LineKind::Success => Rgba::rgb(0.3, 0.8, 1.0),
+        LineKind::Muted => Rgba::rgb(0.5, 0.5, 0.5),
+    }
+}
+
+/// Vault config key for the duration that makes a block's badge "slow".
+pub const SLOW_THRESHOLD_KEY: &str = "blocks.slow_threshold";
+
+/// Used when `blocks.slow_threshold` is unset or unparsable.
+pub const DEFAULT_SLOW_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Blocks that finished faster than this get no duration badge.
+pub const BADGE_MIN_DURATION: Duration = Duration::from_millis(100);
+
+/// Vault config key for the line timestamp gutter (`!timestamps`).
+pub const TIMESTAMPS_KEY: &str = "blocks.timestamps";
+
+/// What the gutter left of block output shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampMode {
+    #[default]
+    Off,
+    /// Wall-clock arrival, `HH:MM:SS.mmm` (or `h:MM:SS.mmm PM`).
+    Absolute,
+    /// Time since the previous line (the first line: since the command
+    /// started).
+    Relative,
+}
+
+impl TimestampMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimestampMode::Off => "off",
+            TimestampMode::Absolute => "on",
+            TimestampMode::Relative => "relative",
+        }
+    }
 
-        spans.push(ColoredSpan::new(format!("{}\n", line), color));
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Some(TimestampMode::Off),
+            "on" | "absolute" => Some(TimestampMode::Absolute),
+            "relative" => Some(TimestampMode::Relative),
+            _ => None,
+        }
     }
+}
+
+/// Layout, thresholds and colors for blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockStyle {
+    /// Terminal width in columns; the badge is right-aligned against it
+    /// (0: badge follows the command).
+    pub columns: usize,
+    pub slow_threshold: Duration,
+    pub timestamps: TimestampMode,
+    /// The clock absolute line timestamps read on (`time.format`).
+    pub clock: Clock,
+    /// Highlight output in a detected language (`blocks.highlight`).
+    pub highlight: bool,
+    pub theme: ThemeName,
+    /// Blocks kept at full strength; older ones are dimmed
+    /// (`blocks.dim_old`, `None`: off).
+    pub dim_old: Option<usize>,
+}
 
+impl Default for BlockStyle {
+    fn default() -> Self {
+        Self {
+            columns: 0,
+            slow_threshold: DEFAULT_SLOW_THRESHOLD,
+            timestamps: TimestampMode::Off,
+            clock: Clock::H24,
+            highlight: true,
+            theme: ThemeName::Default,
+            dim_old: Some(crate::attention::DEFAULT_DIM_OLD),
+        }
+    }
+}
+
+/// Parse a threshold such as `10s`, `500ms`, `2m` or plain seconds (`30`).
+pub fn parse_threshold(value: &str) -> Option<Duration> {
+    let value = value.trim().to_lowercase();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) => value.split_at(i),
+        None => (value.as_str(), "s"),
+    };
+    let number: f64 = number.parse().ok().filter(|n: &f64| n.is_finite() && *n > 0.0)?;
+    let secs = match unit.trim() {
+        "ms" => number / 1000.0,
+        "s" | "sec" | "secs" => number,
+        "m" | "min" | "mins" => number * 60.0,
+        _ => return None,
+    };
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Right-side duration badge for a finished block, colored against the slow
+/// threshold, followed by CPU time and peak memory when the block has them
+/// (`4m 12s · cpu 38.0s · peak 2.1 GB`). `None` while running or for blocks
+/// under `BADGE_MIN_DURATION` without usage.
+pub fn duration_badge(block: &TerminalBlock, slow_threshold: Duration) -> Option<ColoredSpan> {
+    let duration = block
+        .duration
+        .filter(|d| !block.running && (*d >= BADGE_MIN_DURATION || block.usage.is_some()))?;
+    let color = if duration >= slow_threshold {
+        Rgba::rgb(1.0, 0.6, 0.2)
+    } else {
+        Rgba::rgb(0.55, 0.55, 0.6)
+    };
+    let mut text = format_duration(duration);
+    if let Some(usage) = block.usage {
+        text.push_str(&format!(" · {}", usage.badge()));
+    }
+    Some(ColoredSpan::new(text, color))
+}
+
+/// Width of the relative gutter, `+999.999s`.
+const RELATIVE_GUTTER_WIDTH: usize = 9;
+
+/// Gutter text for each output line of `block`, padded to one width. A line
+/// stamped like the line before it (same chunk) gets a blank gutter, as do
+/// unstamped lines. Empty when `mode` is off. Absolute stamps are on
+/// `clock`.
+pub fn timestamp_gutter(block: &TerminalBlock, mode: TimestampMode, clock: Clock) -> Vec<String> {
+    let (format, width) = match clock {
+        Clock::H24 => (TIME_FORMAT, TIME_FORMAT_WIDTH),
+        Clock::H12 => (TIME_FORMAT_12H, TIME_FORMAT_12H_WIDTH),
+    };
+    let width = match mode {
+        TimestampMode::Off => return Vec::new(),
+        TimestampMode::Absolute => width,
+        TimestampMode::Relative => RELATIVE_GUTTER_WIDTH,
+    };
+    let mut previous: Option<u64> = None;
+    block
+        .output
+        .iter()
+        .map(|line| {
+            let text = match line.offset_ms {
+                Some(ms) if previous != Some(ms) => match mode {
+                    TimestampMode::Relative => {
+                        let delta = ms.saturating_sub(previous.unwrap_or(0));
+                        format!("+{}.{:03}s", delta / 1000, delta % 1000)
+                    }
+                    _ => block
+                        .line_time(line)
+                        .map(|t| t.format(format).to_string())
+                        .unwrap_or_default(),
+                },
+                _ => String::new(),
+            };
+            if line.offset_ms.is_some() {
+                previous = line.offset_ms;
+            }
+            format!("{:>width$}", text, width = width)
+        })
+        .collect()
+}
+
+/// Convert a block (header + output, or header only when collapsed) to spans,
+/// followed by its diagnostics summary and test summary card if it has
+/// them. Uses the
+/// classification stored on each line; nothing is re-classified. Output
+/// in a detected language is highlighted instead.
+pub fn block_to_spans(block: &TerminalBlock, style: &BlockStyle) -> Vec<ColoredSpan> {
+    let mut spans = Vec::with_capacity(if block.collapsed { 2 } else { block.output.len() + 2 });
+    let header_color = if block.failed() {
+        Rgba::rgb(1.0, 0.35, 0.35)
+    } else {
+        Rgba::rgb(0.3, 0.85, 0.3)
+    };
+    let header = format!("➜ {}", block.header_text());
+    match duration_badge(block, style.slow_threshold) {
+        Some(mut badge) => {
+            let used = header.chars().count() + badge.text.chars().count();
+            let pad = style.columns.saturating_sub(used).max(1);
+            spans.push(ColoredSpan::new(format!("{}{}", header, " ".repeat(pad)), header_color));
+            badge.text.push('\n');
+            spans.push(badge);
+        }
+        None => spans.push(ColoredSpan::new(format!("{}\n", header), header_color)),
+    }
+
+    if !block.collapsed {
+        let gutter = timestamp_gutter(block, style.timestamps, style.clock);
+        let syntax = block_syntax(block, style);
+        for (i, line) in block.output.iter().enumerate() {
+            if let Some(stamp) = gutter.get(i) {
+                spans.push(ColoredSpan::new(format!("{} ", stamp), line_kind_color(LineKind::Muted)));
+            }
+            match syntax.get(i) {
+                Some(line_syntax) => spans.extend(highlighted_line(&line.text, line_syntax, style.theme)),
+                None => spans.push(ColoredSpan::new(format!("{}\n", line.text), line_kind_color(line.kind))),
+            }
+        }
+    }
+    diagnostics_to_spans(block, &mut spans);
+    tests_to_spans(block, &mut spans);
+    spans
+}
+
+/// Syntax spans for each output line, or none when the block has no
+/// language, is too long or highlighting is off.
+fn block_syntax(block: &TerminalBlock, style: &BlockStyle) -> Vec<Vec<SyntaxSpan>> {
+    match block.language {
+        Some(language) if style.highlight && block.output.len() <= syntax::MAX_LINES => {
+            let text: Vec<&str> = block.output.iter().map(|l| l.text.as_str()).collect();
+            syntax::highlight(language, &text)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `text` cut at its syntax spans and colored, ending in a newline. Text
+/// between spans is plain.
+pub fn highlighted_line(text: &str, syntax: &[SyntaxSpan], theme: ThemeName) -> Vec<ColoredSpan> {
+    let plain = line_kind_color(LineKind::Normal);
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::with_capacity(syntax.len() * 2 + 1);
+    let mut pos = 0;
+    for s in syntax {
+        let start = s.range.start.clamp(pos, chars.len());
+        let end = s.range.end.clamp(start, chars.len());
+        if start > pos {
+            spans.push(ColoredSpan::new(chars[pos..start].iter().collect::<String>(), plain));
+        }
+        if end > start {
+            spans.push(ColoredSpan::new(chars[start..end].iter().collect::<String>(), theme.syntax_color(s.class)));
+        }
+        pos = end;
+    }
+    match spans.last_mut() {
+        Some(last) if pos == chars.len() => last.text.push('\n'),
+        _ => spans.push(ColoredSpan::new(format!("{}\n", chars[pos..].iter().collect::<String>()), plain)),
+    }
     spans
 }
 
+/// `⚑ 2 errors, 1 warning` under a block, and one line per diagnostic when
+/// the block's list is expanded.
+fn diagnostics_to_spans(block: &TerminalBlock, spans: &mut Vec<ColoredSpan>) {
+    let summary = diagnostics::summary(&block.diagnostics);
+    if summary.is_empty() {
+        return;
+    }
+    let has_errors = block.diagnostics.iter().any(|d| d.severity == Severity::Error);
+    let kind = if has_errors { LineKind::Error } else { LineKind::Warning };
+    let hint = if block.diagnostics_expanded { "" } else { "  (!errors to list)" };
+    spans.push(ColoredSpan::new(format!("⚑ {}{}\n", summary, hint), line_kind_color(kind)));
+    if block.diagnostics_expanded {
+        for (i, d) in block.diagnostics.iter().enumerate() {
+            let kind = match d.severity {
+                Severity::Error => LineKind::Error,
+                Severity::Warning => LineKind::Warning,
+                Severity::Note => LineKind::Info,
+            };
+            spans.push(ColoredSpan::new(format!("  {:>2}. {}\n", i + 1, d), line_kind_color(kind)));
+        }
+    }
+}
+
+/// `🧪 cargo test: 2 failed, 41 passed` under a block, then a line per
+/// failure; expanded ones are followed by their captured output.
+fn tests_to_spans(block: &TerminalBlock, spans: &mut Vec<ColoredSpan>) {
+    let Some(report) = &block.tests else {
+        return;
+    };
+    let kind = if report.succeeded() { LineKind::Success } else { LineKind::Error };
+    spans.push(ColoredSpan::new(format!("{}\n", report.headline()), line_kind_color(kind)));
+    for (i, failure) in report.failures.iter().enumerate() {
+        let expanded = block.tests_expanded.contains(&i);
+        let marker = if expanded { '▾' } else { '▸' };
+        spans.push(ColoredSpan::new(
+            format!("  {} {:>2}. {}\n", marker, i + 1, failure.name),
+            line_kind_color(LineKind::Error),
+        ));
+        if expanded {
+            for line in &failure.output {
+                spans.push(ColoredSpan::new(format!("        {}\n", line), line_kind_color(LineKind::Muted)));
+            }
+        }
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════
 // PTY Snapshot Rendering
 // ════════════════════════════════════════════════════════════════════
 
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
 /// Convert a PTY snapshot (with ANSI colors) to colored spans.
 /// Skips empty leading rows to avoid stale terminal garbage.
-pub fn snapshot_to_spans(snapshot: &Snapshot, _theme: ThemeName) -> Vec<ColoredSpan> {
+pub fn snapshot_to_spans(snapshot: &Snapshot, theme: ThemeName) -> Vec<ColoredSpan> {
     let mut spans = Vec::new();
     let rows = snapshot.rows();
 
@@ -194,59 +783,105 @@ pub fn snapshot_to_spans(snapshot: &Snapshot, _theme: ThemeName) -> Vec<ColoredS
         return spans;
     }
 
-    // Find the first non-empty row
-    let mut first_content_row = 0;
-    for row_idx in 0..rows {
-        let row = &snapshot[row_idx];
-        let has_content = row.iter().any(|(ch, _)| !ch.is_whitespace());
-        if has_content {
-            first_content_row = row_idx;
-            break;
-        }
+    for row_idx in first_content_row(snapshot)..rows {
+        row_to_spans(snapshot, row_idx, theme, &mut spans);
     }
 
-    for row_idx in first_content_row..rows {
-        let row = &snapshot[row_idx];
+    spans
+}
 
-        if row.is_empty() {
-            spans.push(ColoredSpan::new("\n", Rgba::rgb(0.85, 0.85, 0.85)));
-            continue;
-        }
+/// Index of the first row with visible content (0 if all blank).
+pub fn first_content_row(snapshot: &Snapshot) -> usize {
+    (0..snapshot.rows())
+        .find(|&row_idx| snapshot[row_idx].iter().any(|(ch, _)| !ch.is_whitespace()))
+        .unwrap_or(0)
+}
 
-        let mut current_text = String::new();
-        let mut current_color: Option<Rgba> = None;
+/// Append the spans for one snapshot row (runs of equal color and style,
+/// then a trailing newline).
+///
+/// Wide-character spacers are skipped: the glyph itself covers both
+/// cells. A zero-width joiner at the end of a cell is dropped so the
+/// shaper can't fuse the next cell's emoji into one narrower glyph and
+/// pull the rest of the row out of its columns.
+pub fn row_to_spans(snapshot: &Snapshot, row_idx: usize, theme: ThemeName, spans: &mut Vec<ColoredSpan>) {
+    let row = &snapshot[row_idx];
+    if row.is_empty() {
+        spans.push(ColoredSpan::new("\n", Rgba::rgb(0.85, 0.85, 0.85)));
+        return;
+    }
 
-        for (ch, color_attr) in row.iter() {
-            let cell_color = mycolor_to_rgba(color_attr);
+    let mut current_text = String::new();
+    let mut current: Option<(Rgba, SpanStyle)> = None;
+    let start = row_idx * snapshot.cols();
+    let styles = snapshot.row_styles(row_idx);
+    let mut clusters = snapshot.row_clusters(row_idx).iter().peekable();
 
-            if let Some(prev_color) = current_color {
-                if prev_color != cell_color && !current_text.is_empty() {
-                    spans.push(ColoredSpan::new(current_text.clone(), prev_color));
-                    current_text.clear();
-                }
-            }
+    for (col, (ch, color_attr)) in row.iter().enumerate() {
+        if *ch == WIDE_SPACER {
+            continue;
+        }
+        let cell = cell_style(color_attr, styles.get(col).copied().unwrap_or_default(), theme);
 
-            current_color = Some(cell_color);
-            current_text.push(*ch);
+        if let Some((prev_color, prev_style)) = current
+            && (prev_color, prev_style) != cell
+            && !current_text.is_empty()
+        {
+            spans.push(ColoredSpan::styled(current_text.clone(), prev_color, prev_style));
+            current_text.clear();
         }
 
-        // Flush remaining text
-        if !current_text.is_empty() {
-            let color = current_color.unwrap_or(Rgba::rgb(0.85, 0.85, 0.85));
-            spans.push(ColoredSpan::new(current_text, color));
+        current = Some(cell);
+        current_text.push(*ch);
+        while let Some((_, extra)) = clusters.next_if(|(i, _)| *i <= start + col) {
+            current_text.push_str(extra.strip_suffix(ZERO_WIDTH_JOINER).unwrap_or(extra));
         }
+    }
 
-        spans.push(ColoredSpan::new("\n", Rgba::rgb(0.85, 0.85, 0.85)));
+    // Flush remaining text
+    if !current_text.is_empty() {
+        let (color, style) = current.unwrap_or((Rgba::rgb(0.85, 0.85, 0.85), SpanStyle::default()));
+        spans.push(ColoredSpan::styled(current_text, color, style));
     }
 
-    spans
+    spans.push(ColoredSpan::new("\n", Rgba::rgb(0.85, 0.85, 0.85)));
+}
+
+/// Resolve a cell's colors and attributes into what gets drawn: inverse
+/// swaps foreground and background, dim fades the foreground per theme.
+fn cell_style(fg: &MyColor, cell: CellStyle, theme: ThemeName) -> (Rgba, SpanStyle) {
+    let attrs = cell.attrs;
+    let mut color = mycolor_to_rgba(fg);
+    let mut background = (cell.bg != MyColor::Default).then(|| mycolor_to_rgba(&cell.bg));
+
+    if attrs.contains(CellAttrs::INVERSE) {
+        let back = background.unwrap_or_else(|| theme.bg_color());
+        background = Some(color);
+        color = Rgba { a: 1.0, ..back };
+    }
+    if attrs.contains(CellAttrs::DIM) {
+        color = theme.dim_style().apply(color, background.unwrap_or_else(|| theme.bg_color()));
+    }
+
+    let underline = attrs
+        .contains(CellAttrs::UNDERLINE)
+        .then(|| cell.underline_color.as_ref().map(mycolor_to_rgba).unwrap_or(color));
+    let style = SpanStyle {
+        bold: attrs.contains(CellAttrs::BOLD),
+        italic: attrs.contains(CellAttrs::ITALIC),
+        underline,
+        strikethrough: attrs.contains(CellAttrs::STRIKETHROUGH),
+        background,
+    };
+    (color, style)
 }
 
 // ════════════════════════════════════════════════════════════════════
 // Plain Text (clipboard)
 // ════════════════════════════════════════════════════════════════════
 
-/// Convert snapshot to plain text for clipboard copy.
+/// Convert snapshot to plain text for clipboard copy. Soft-wrapped rows
+/// join the next without a newline, so a long URL copies in one piece.
 pub fn snapshot_to_plain(snapshot: &Snapshot) -> String {
     let mut out = String::new();
     let rows = snapshot.rows();
@@ -255,25 +890,134 @@ pub fn snapshot_to_plain(snapshot: &Snapshot) -> String {
         return out;
     }
 
-    let mut first_content_row = 0;
-    for row_idx in 0..rows {
-        let row = &snapshot[row_idx];
-        if row.iter().any(|(ch, _)| !ch.is_whitespace()) {
-            first_content_row = row_idx;
-            break;
+    for row_idx in first_content_row(snapshot)..rows {
+        let line = snapshot.row_text(row_idx);
+        if snapshot.wraps(row_idx) {
+            out.push_str(&line);
+        } else {
+            out.push_str(line.trim_end());
+            out.push('\n');
         }
     }
 
-    for row_idx in first_content_row..rows {
+    out
+}
+
+/// Convert snapshot to text with SGR sequences, for copying or exporting
+/// with formatting. Each change of color or attributes starts from a reset
+/// (`ESC[0;…m`) and every styled line ends with one, so any slice of lines
+/// pastes cleanly. Trailing unstyled blanks are trimmed and soft-wrapped
+/// rows joined as in [`snapshot_to_plain`].
+pub fn snapshot_to_ansi(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+    let rows = snapshot.rows();
+
+    if rows == 0 {
+        return out;
+    }
+
+    for row_idx in first_content_row(snapshot)..rows {
         let row = &snapshot[row_idx];
-        let line: String = row.iter().map(|(c, _)| *c).collect();
-        out.push_str(line.trim_end());
-        out.push('\n');
+        let styles = snapshot.row_styles(row_idx);
+        let start = row_idx * snapshot.cols();
+        let mut clusters = snapshot.row_clusters(row_idx).iter().peekable();
+
+        let style_at = |col: usize| styles.get(col).copied().unwrap_or_default();
+        let wraps = snapshot.wraps(row_idx);
+        let end = if wraps {
+            row.len()
+        } else {
+            row.iter()
+                .enumerate()
+                .rposition(|(col, (ch, fg))| {
+                    !(ch.is_whitespace() || *ch == WIDE_SPACER) || *fg != MyColor::Default || style_at(col) != CellStyle::default()
+                })
+                .map_or(0, |last| last + 1)
+        };
+
+        let mut current = (MyColor::Default, CellStyle::default());
+        for (col, (ch, fg)) in row.iter().enumerate().take(end) {
+            if *ch == WIDE_SPACER {
+                continue;
+            }
+            let cell = (*fg, style_at(col));
+            if cell != current {
+                out.push_str(&sgr(&cell.0, &cell.1));
+                current = cell;
+            }
+            out.push(*ch);
+            while let Some((_, extra)) = clusters.next_if(|(i, _)| *i <= start + col) {
+                out.push_str(extra);
+            }
+        }
+        if current != (MyColor::Default, CellStyle::default()) {
+            out.push_str("\x1b[0m");
+        }
+        if !wraps {
+            out.push('\n');
+        }
     }
 
     out
 }
 
+/// The SGR sequence selecting exactly `fg` and `style` from a reset.
+fn sgr(fg: &MyColor, style: &CellStyle) -> String {
+    let mut params = vec!["0".to_string()];
+    for (attr, code) in [
+        (CellAttrs::BOLD, "1"),
+        (CellAttrs::DIM, "2"),
+        (CellAttrs::ITALIC, "3"),
+        (CellAttrs::UNDERLINE, "4"),
+        (CellAttrs::INVERSE, "7"),
+        (CellAttrs::STRIKETHROUGH, "9"),
+    ] {
+        if style.attrs.contains(attr) {
+            params.push(code.to_string());
+        }
+    }
+    params.extend(sgr_color(fg, 30, 90, 38));
+    params.extend(sgr_color(&style.bg, 40, 100, 48));
+    if let Some(color) = &style.underline_color {
+        // 58 has no short palette form, so named colors go by index.
+        let code = match color {
+            MyColor::Indexed(_) | MyColor::Rgb(..) | MyColor::Default => sgr_color(color, 30, 90, 58),
+            named => sgr_color(named, 0, 8, 58).map(|idx| format!("58;5;{}", idx)),
+        };
+        params.extend(code);
+    }
+    format!("\x1b[{}m", params.join(";"))
+}
+
+/// SGR parameter for a color: `base`+n for the eight named colors,
+/// `bright`+n for their bright versions, `extended;5;n` / `extended;2;r;g;b`
+/// otherwise. `None` for the default color.
+fn sgr_color(color: &MyColor, base: u8, bright: u8, extended: u8) -> Option<String> {
+    let named = |n: u8| Some((base + n).to_string());
+    let brightened = |n: u8| Some((bright + n).to_string());
+    match color {
+        MyColor::Default => None,
+        MyColor::Black => named(0),
+        MyColor::Red => named(1),
+        MyColor::Green => named(2),
+        MyColor::Yellow => named(3),
+        MyColor::Blue => named(4),
+        MyColor::Magenta => named(5),
+        MyColor::Cyan => named(6),
+        MyColor::White => named(7),
+        MyColor::BrightBlack => brightened(0),
+        MyColor::BrightRed => brightened(1),
+        MyColor::BrightGreen => brightened(2),
+        MyColor::BrightYellow => brightened(3),
+        MyColor::BrightBlue => brightened(4),
+        MyColor::BrightMagenta => brightened(5),
+        MyColor::BrightCyan => brightened(6),
+        MyColor::BrightWhite => brightened(7),
+        MyColor::Indexed(idx) => Some(format!("{};5;{}", extended, idx)),
+        MyColor::Rgb(r, g, b) => Some(format!("{};2;{};{};{}", extended, r, g, b)),
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════
 // Color Conversion
 // ════════════════════════════════════════════════════════════════════
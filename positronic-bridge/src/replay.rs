@@ -0,0 +1,77 @@
+//! `!record play`: a cast replayed into its own emulator.
+//!
+//! The recording's output events are fed to a private `StateMachine` sized
+//! from the cast header (and its resize events), never to the live PTY, so
+//! the overlay is read-only. The frontend advances it each frame with the
+//! wall time since playback started and draws `snapshot()`.
+
+use std::time::Duration;
+
+use positronic_core::asciicast::{Cast, CastPlayer, EventKind};
+use positronic_core::state_machine::{Snapshot, StateMachine};
+
+use crate::helpers::format_duration_short;
+
+pub struct Replay {
+    label: String,
+    player: CastPlayer,
+    screen: StateMachine,
+    elapsed: Duration,
+}
+
+impl Replay {
+    pub fn new(label: impl Into<String>, cast: Cast, speed: f64) -> Self {
+        let screen = StateMachine::new(cast.header.width.max(1), cast.header.height.max(1));
+        Self {
+            label: label.into(),
+            player: CastPlayer::new(cast, speed),
+            screen,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Play everything due by `elapsed`; true if the screen changed.
+    pub fn advance(&mut self, elapsed: Duration) -> bool {
+        self.elapsed = elapsed;
+        let mut changed = false;
+        for event in self.player.due(elapsed) {
+            match event.kind {
+                EventKind::Output => self.screen.process_bytes(event.data.as_bytes()),
+                EventKind::Resize => match event.size() {
+                    Some((cols, rows)) => self.screen.resize(cols, rows),
+                    None => continue,
+                },
+                EventKind::Input | EventKind::Marker => continue,
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.player.is_done()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        self.screen.snapshot()
+    }
+
+    /// `▶ demo.cast  12s / 40s (2x)  q to close`
+    pub fn footer(&self) -> String {
+        let total = self.player.duration();
+        let at = if self.is_done() { total } else { self.elapsed.min(total) };
+        let speed = if self.player.speed() == 1.0 {
+            String::new()
+        } else {
+            format!(" ({}x)", self.player.speed())
+        };
+        format!(
+            "{} {}  {} / {}{}  q to close",
+            if self.is_done() { "■" } else { "▶" },
+            self.label,
+            format_duration_short(at.as_secs() as i64),
+            format_duration_short(total.as_secs() as i64),
+            speed,
+        )
+    }
+}
@@ -0,0 +1,120 @@
+//! Window resizes: one PTY resize per drag, and the view in between.
+//!
+//! Dragging a window edge sends a resize event per frame. Passing each on
+//! made the shell repaint dozens of times while the grid thrashed, so
+//! `ResizeDebounce` holds them: the PTY keeps its grid until events have
+//! stopped for `SETTLE`, then gets the one grid that fits the window. In
+//! between, the terminal is drawn at its old grid scaled to the new
+//! window (`interim_scale`), letterboxed where the shapes differ.
+//!
+//! Only the timing is kept here. The grid is worked out from the window
+//! when the drag settles, in the cells drawn then, so a DPI change
+//! mid-drag or a maximize and restore in quick succession commit what the
+//! window ended at, and nothing if the PTY already has that grid. A
+//! full-screen app gets the same single resize as the shell. A commit
+//! keeps the scroll position, put back once the emulator has the new grid.
+//!
+//! As with the governor, the caller owns the clock.
+
+use std::time::{Duration, Instant};
+
+use crate::layout::ComputedLayout;
+use crate::viewport::ScrollMark;
+
+/// Quiet time after the last resize event before the PTY follows.
+pub const SETTLE: Duration = Duration::from_millis(150);
+
+/// How far the interim view shrinks or grows the text.
+pub const MIN_INTERIM_SCALE: f32 = 0.25;
+pub const MAX_INTERIM_SCALE: f32 = 2.0;
+
+/// PTY columns and rows.
+pub type Grid = (u16, u16);
+
+#[derive(Debug, Clone, Default)]
+pub struct ResizeDebounce {
+    /// The grid the PTY was last sent.
+    committed: Option<Grid>,
+    /// The latest resize event the PTY hasn't followed yet.
+    last_event: Option<Instant>,
+    /// The committed grid and the scroll position to put back once the
+    /// emulator has it.
+    restore: Option<(Grid, ScrollMark)>,
+}
+
+impl ResizeDebounce {
+    /// A resize event at `now`: the PTY waits for the next quiet spell.
+    pub fn event(&mut self, now: Instant) {
+        self.last_event = Some(now);
+    }
+
+    /// Events have arrived that the PTY hasn't followed yet.
+    pub fn is_pending(&self) -> bool {
+        self.last_event.is_some()
+    }
+
+    /// The grid the PTY was last sent.
+    pub fn committed(&self) -> Option<Grid> {
+        self.committed
+    }
+
+    /// When a pending resize settles.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.last_event.map(|at| at + SETTLE)
+    }
+
+    /// True once, when events have been quiet for `SETTLE` by `now`: time
+    /// to work out the grid and `commit` it.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        match self.next_wake() {
+            Some(at) if now >= at => {
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Size the PTY to `grid`, settled or not (a `layout.*` change doesn't
+    /// wait). The grid to send, or `None` if the PTY has it already.
+    /// `scroll` is where the view was, for `settled` to hand back.
+    pub fn commit(&mut self, grid: Grid, scroll: ScrollMark) -> Option<Grid> {
+        self.last_event = None;
+        if self.committed == Some(grid) {
+            return None;
+        }
+        self.committed = Some(grid);
+        self.restore = Some((grid, scroll));
+        Some(grid)
+    }
+
+    /// The scroll position a commit kept, once the emulator is at `size`.
+    pub fn settled(&mut self, size: Grid) -> Option<ScrollMark> {
+        match self.restore {
+            Some((grid, mark)) if grid == size => {
+                self.restore = None;
+                Some(mark)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Text scale that fits the `committed` grid into `lay`'s terminal area:
+/// 1.0 when `lay` has that grid, else the smaller of the two axes' ratios
+/// so the whole grid shows, within the interim limits.
+pub fn interim_scale(committed: Grid, lay: &ComputedLayout) -> f32 {
+    let (cols, rows) = committed;
+    if (cols, rows) == (lay.cols, lay.rows) || cols == 0 || rows == 0 {
+        return 1.0;
+    }
+    // The same insets `layout::compute` fits the grid into.
+    let wide = (lay.terminal_w - lay.padding * 2.0) / (cols as f32 * lay.cell_w);
+    let tall = (lay.terminal_h - lay.padding) / (rows as f32 * lay.cell_h);
+    let scale = wide.min(tall);
+    if scale.is_finite() && scale > 0.0 {
+        scale.clamp(MIN_INTERIM_SCALE, MAX_INTERIM_SCALE)
+    } else {
+        1.0
+    }
+}
@@ -0,0 +1,307 @@
+//! `!scope`: the oscilloscope pane's state and geometry (no UI deps).
+//!
+//! The pane plots a port's parsed channels from `HardwarePanel`. The time
+//! axis ends at the newest sample and spans `ScopeView::window` seconds;
+//! the value axis is fixed or fits what is visible. The GPU only draws
+//! axis-aligned quads, so `polyline` maps samples to pixel points (at most
+//! two per pixel column) and `segment_quads` turns those into thin steps.
+
+use crate::hardware::ChannelTrace;
+
+pub const DEFAULT_WINDOW: f64 = 10.0;
+pub const MIN_WINDOW: f64 = 0.1;
+pub const MAX_WINDOW: f64 = 600.0;
+
+pub const SCOPE_USAGE: &str = "Usage: !scope [port] [--range <min>:<max>|auto] [--window <seconds>] | !scope off";
+
+/// The value axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum YRange {
+    /// Fit the visible samples.
+    Auto,
+    Fixed(f32, f32),
+}
+
+impl YRange {
+    /// `auto` or `<min>:<max>`.
+    pub fn parse(text: &str) -> Result<YRange, String> {
+        if text.eq_ignore_ascii_case("auto") {
+            return Ok(YRange::Auto);
+        }
+        let bad = || format!("❌ Bad range '{}' (auto or <min>:<max>)", text);
+        let (lo, hi) = text.split_once(':').ok_or_else(bad)?;
+        let lo: f32 = lo.trim().parse().map_err(|_| bad())?;
+        let hi: f32 = hi.trim().parse().map_err(|_| bad())?;
+        if !(lo.is_finite() && hi.is_finite() && lo < hi) {
+            return Err(bad());
+        }
+        Ok(YRange::Fixed(lo, hi))
+    }
+}
+
+/// A parsed `!scope` line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeCommand {
+    /// Bare `!scope`: open on the default port, or close.
+    Toggle,
+    Off,
+    /// Open (or adjust the open pane); unset fields keep their value.
+    Open {
+        port: Option<String>,
+        range: Option<YRange>,
+        window: Option<f64>,
+    },
+}
+
+impl ScopeCommand {
+    /// Parse `!scope ...`; the error is a message for the user.
+    pub fn parse(cmd: &str) -> Result<ScopeCommand, String> {
+        let mut parts = cmd.split_whitespace();
+        if parts.next() != Some("!scope") {
+            return Err(SCOPE_USAGE.to_string());
+        }
+        let mut port = None;
+        let mut range = None;
+        let mut window = None;
+        let mut any = false;
+        while let Some(part) = parts.next() {
+            any = true;
+            match part {
+                "off" | "close" if port.is_none() => return Ok(ScopeCommand::Off),
+                "--range" => range = Some(YRange::parse(parts.next().ok_or(SCOPE_USAGE)?)?),
+                "--window" => {
+                    let text = parts.next().ok_or(SCOPE_USAGE)?;
+                    let secs: f64 = text
+                        .trim_end_matches('s')
+                        .parse()
+                        .map_err(|_| format!("❌ Bad window '{}' (seconds)", text))?;
+                    if !secs.is_finite() || secs <= 0.0 {
+                        return Err(format!("❌ Bad window '{}' (seconds)", text));
+                    }
+                    window = Some(secs.clamp(MIN_WINDOW, MAX_WINDOW));
+                }
+                p if port.is_none() && !p.starts_with("--") => port = Some(p.to_string()),
+                _ => return Err(SCOPE_USAGE.to_string()),
+            }
+        }
+        if !any {
+            return Ok(ScopeCommand::Toggle);
+        }
+        Ok(ScopeCommand::Open { port, range, window })
+    }
+}
+
+/// The open pane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScopeView {
+    pub port: String,
+    /// Seconds of history across the plot.
+    pub window: f64,
+    pub range: YRange,
+}
+
+impl ScopeView {
+    pub fn new(port: impl Into<String>) -> Self {
+        Self { port: port.into(), window: DEFAULT_WINDOW, range: YRange::Auto }
+    }
+
+    /// Halve the window (`+`).
+    pub fn zoom_in(&mut self) {
+        self.window = (self.window / 2.0).max(MIN_WINDOW);
+    }
+
+    /// Double the window (`-`).
+    pub fn zoom_out(&mut self) {
+        self.window = (self.window * 2.0).min(MAX_WINDOW);
+    }
+
+    /// "COM3 · 10s · auto".
+    pub fn title(&self) -> String {
+        self.title_with(&self.port)
+    }
+
+    /// The title with the port shown as `label`, e.g. a device's name.
+    pub fn title_with(&self, label: &str) -> String {
+        let range = match self.range {
+            YRange::Auto => "auto".to_string(),
+            YRange::Fixed(lo, hi) => format!("{}:{}", lo, hi),
+        };
+        format!("{} · {} · {}", label, format_window(self.window), range)
+    }
+
+    /// The value axis for these traces, given the window ending at `t_end`.
+    pub fn value_range(&self, traces: &[Vec<(f64, f32)>], t_end: f64) -> (f32, f32) {
+        match self.range {
+            YRange::Fixed(lo, hi) => (lo, hi),
+            YRange::Auto => {
+                let t_start = t_end - self.window;
+                let visible = traces
+                    .iter()
+                    .flatten()
+                    .filter(|(t, _)| *t >= t_start && *t <= t_end)
+                    .map(|&(_, v)| v);
+                auto_range(visible).unwrap_or((0.0, 1.0))
+            }
+        }
+    }
+}
+
+/// "500ms", "2.5s", "10s".
+pub fn format_window(secs: f64) -> String {
+    if secs < 1.0 {
+        format!("{}ms", (secs * 1000.0).round())
+    } else if secs.fract() == 0.0 {
+        format!("{}s", secs)
+    } else {
+        format!("{:.1}s", secs)
+    }
+}
+
+/// The newest timestamp across traces; the right edge of the plot.
+pub fn window_end(traces: &[Vec<(f64, f32)>]) -> Option<f64> {
+    traces.iter().filter_map(|t| t.last()).map(|&(t, _)| t).reduce(f64::max)
+}
+
+/// Min and max of `values`, padded by 5% so lines clear the edges. A flat
+/// signal gets a unit-wide band around it.
+pub fn auto_range(values: impl IntoIterator<Item = f32>) -> Option<(f32, f32)> {
+    let (lo, hi) = values
+        .into_iter()
+        .filter(|v| v.is_finite())
+        .fold(None, |acc: Option<(f32, f32)>, v| Some(acc.map_or((v, v), |(lo, hi)| (lo.min(v), hi.max(v)))))?;
+    if hi - lo <= f32::EPSILON * lo.abs().max(1.0) {
+        return Some((lo - 0.5, hi + 0.5));
+    }
+    let pad = (hi - lo) * 0.05;
+    Some((lo - pad, hi + pad))
+}
+
+/// A pixel rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+/// Map the samples inside `[t_end - window, t_end]` to pixel points.
+///
+/// A sample before the window is interpolated to the left edge so the
+/// line enters from it. Values outside `range` are clamped to the edges.
+/// Points sharing a pixel column are reduced to that column's min and max,
+/// in the order they occurred.
+pub fn polyline(samples: &[(f64, f32)], t_end: f64, window: f64, range: (f32, f32), vp: Viewport) -> Vec<(f32, f32)> {
+    let t_start = t_end - window;
+    let (lo, hi) = range;
+    let span = if hi > lo { hi - lo } else { 1.0 };
+    let x_of = |t: f64| vp.x + ((t - t_start) / window) as f32 * vp.w;
+    let y_of = |v: f32| vp.y + vp.h - ((v - lo) / span).clamp(0.0, 1.0) * vp.h;
+
+    let samples: Vec<(f64, f32)> = samples.iter().copied().filter(|(t, v)| t.is_finite() && v.is_finite()).collect();
+    let first = samples.partition_point(|&(t, _)| t < t_start);
+    let mut raw = Vec::new();
+    if first > 0 && first < samples.len() {
+        let (t0, v0) = samples[first - 1];
+        let (t1, v1) = samples[first];
+        let f = if t1 > t0 { ((t_start - t0) / (t1 - t0)) as f32 } else { 1.0 };
+        raw.push((vp.x, y_of(v0 + (v1 - v0) * f)));
+    }
+    raw.extend(samples[first..].iter().take_while(|&&(t, _)| t <= t_end).map(|&(t, v)| (x_of(t), y_of(v))));
+
+    // Two points per pixel column at most.
+    let mut points: Vec<(f32, f32)> = Vec::with_capacity(raw.len().min(vp.w as usize * 2 + 2));
+    let mut column: Option<Column> = None;
+    for p in raw {
+        let index = (p.0 - vp.x).floor() as i64;
+        match &mut column {
+            Some(c) if c.index == index => c.add(p),
+            _ => {
+                if let Some(done) = column.take() {
+                    done.flush(&mut points);
+                }
+                column = Some(Column { index, min: p, max: p, min_first: true });
+            }
+        }
+    }
+    if let Some(done) = column {
+        done.flush(&mut points);
+    }
+    points
+}
+
+/// The extremes of one pixel column, and which came first.
+struct Column {
+    index: i64,
+    min: (f32, f32),
+    max: (f32, f32),
+    min_first: bool,
+}
+
+impl Column {
+    fn add(&mut self, p: (f32, f32)) {
+        // Screen y grows downward: the value minimum has the largest y.
+        if p.1 > self.min.1 {
+            self.min = p;
+            self.min_first = false;
+        }
+        if p.1 < self.max.1 {
+            self.max = p;
+            self.min_first = true;
+        }
+    }
+
+    fn flush(self, points: &mut Vec<(f32, f32)>) {
+        let (a, b) = if self.min_first { (self.min, self.max) } else { (self.max, self.min) };
+        points.push(a);
+        if b != a {
+            points.push(b);
+        }
+    }
+}
+
+/// Rectangles `[x, y, w, h]` tracing `points` as thin steps: across at the
+/// first point's height, then up or down at the second's x.
+pub fn segment_quads(points: &[(f32, f32)], thickness: f32) -> Vec<[f32; 4]> {
+    let half = thickness / 2.0;
+    match points {
+        [] => Vec::new(),
+        [(x, y)] => vec![[x - half, y - half, thickness, thickness]],
+        _ => {
+            let mut quads = Vec::with_capacity(points.len() * 2);
+            for pair in points.windows(2) {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                if x1 != x0 {
+                    quads.push([x0.min(x1) - half, y0 - half, (x1 - x0).abs() + thickness, thickness]);
+                }
+                if y1 != y0 {
+                    quads.push([x1 - half, y0.min(y1) - half, thickness, (y1 - y0).abs() + thickness]);
+                }
+            }
+            quads
+        }
+    }
+}
+
+/// "ch0  last 21.5  min 20.1  max 23  avg 21.73  9.8/s".
+pub fn channel_summary(channel: u8, trace: &ChannelTrace) -> String {
+    let stats = &trace.stats;
+    let value = |v: Option<f32>| v.map_or_else(|| "–".to_string(), format_value);
+    let rate = trace.waveform.rate().map_or_else(|| "–".to_string(), |r| format!("{:.1}/s", r));
+    format!(
+        "ch{}  last {}  min {}  max {}  avg {}  {}",
+        channel,
+        value(stats.last_value),
+        value(stats.min_value),
+        value(stats.max_value),
+        value(stats.avg_value.map(|v| v as f32)),
+        rate
+    )
+}
+
+/// At most four decimals, trailing zeros dropped.
+pub fn format_value(v: f32) -> String {
+    let text = format!("{:.4}", v);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
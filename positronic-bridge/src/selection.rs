@@ -0,0 +1,105 @@
+//! Mouse text selection over the terminal grid.
+//!
+//! A selection runs from the cell boundary where the left button went down
+//! (the anchor) to the one under the pointer (the head), in reading order:
+//! the rest of the first row, whole rows between, and the start of the
+//! last. Boundaries rather than cells mean a click without a drag selects
+//! nothing. Rows are snapshot rows, so the caller adds the first visible
+//! row when converting from the screen. A row soft-wrapped into the next
+//! copies without a newline between them.
+
+use positronic_core::state_machine::{Snapshot, WIDE_SPACER};
+
+/// A cell boundary: `col` runs from 0 to the column count inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GridPos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl GridPos {
+    /// The boundary nearest pixel `(x, y)` in a grid whose top-left corner
+    /// is at `origin` with cells of `cell` (width, height). Points outside
+    /// the grid clamp to its edge.
+    pub fn from_pixel(x: f32, y: f32, origin: [f32; 2], cell: [f32; 2], rows: usize, cols: usize) -> GridPos {
+        let col = ((x - origin[0]) / cell[0]).round().max(0.0) as usize;
+        let row = ((y - origin[1]) / cell[1]).floor().max(0.0) as usize;
+        GridPos { row: row.min(rows.saturating_sub(1)), col: col.min(cols) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    anchor: GridPos,
+    head: GridPos,
+}
+
+impl Selection {
+    /// An empty selection at `at`, as left by a button press.
+    pub fn new(at: GridPos) -> Selection {
+        Selection { anchor: at, head: at }
+    }
+
+    /// Move the free end to `to` while dragging.
+    pub fn extend(&mut self, to: GridPos) {
+        self.head = to;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// The ends in reading order.
+    pub fn bounds(&self) -> (GridPos, GridPos) {
+        (self.anchor.min(self.head), self.anchor.max(self.head))
+    }
+
+    /// The columns selected on `row` of a grid `cols` wide, if any.
+    pub fn columns(&self, row: usize, cols: usize) -> Option<std::ops::Range<usize>> {
+        let (start, end) = self.bounds();
+        if self.is_empty() || row < start.row || row > end.row {
+            return None;
+        }
+        let from = if row == start.row { start.col } else { 0 };
+        let to = if row == end.row { end.col } else { cols };
+        (from < to).then_some(from..to.min(cols))
+    }
+
+    /// The selected text of `snapshot`: wide glyphs once, combining marks
+    /// kept, trailing blanks trimmed from each line, rows joined by
+    /// newlines except where a row is soft-wrapped into the next.
+    pub fn text(&self, snapshot: &Snapshot) -> String {
+        let (start, end) = self.bounds();
+        let cols = snapshot.cols();
+        let last = end.row.min(snapshot.rows().saturating_sub(1));
+        let mut text = String::new();
+        for row in start.row..=last {
+            if row >= snapshot.rows() {
+                break;
+            }
+            let mut line = String::new();
+            if let Some(range) = self.columns(row, cols) {
+                let base = row * cols;
+                let clusters = snapshot.row_clusters(row);
+                for col in range {
+                    let ch = snapshot[row][col].0;
+                    if ch != WIDE_SPACER {
+                        line.push(ch);
+                    }
+                    if let Some((_, extra)) = clusters.iter().find(|(i, _)| *i == base + col) {
+                        line.push_str(extra);
+                    }
+                }
+            }
+            if row < last && snapshot.wraps(row) {
+                text.push_str(&line);
+            } else {
+                text.push_str(line.trim_end());
+                if row < last {
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+}
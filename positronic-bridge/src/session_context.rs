@@ -0,0 +1,226 @@
+//! Per-pane session state for split panes (no UI deps).
+//!
+//! Each pane owns its scrollback blocks, Holodeck entries, marks and
+//! search query in a `SessionContext`, so what one pane prints never
+//! shows up in another's search, copy, export or Holodeck. `Sessions`
+//! holds the panes and which one has focus; anything that used to read
+//! the window's single copy reads the focused context instead.
+//!
+//! A few things can look across panes on request: `Scope::AllPanes`
+//! searches, exports and lists the Holodeck of every pane, in the order
+//! the panes were opened. Core does the same for history through
+//! `Vault::for_pane`, with `--pane <n>` and `--all-panes`.
+//!
+//! The window does not split yet: it has pane 1 alone, fed by one
+//! engine started without `EngineOptions::pane`, so its history is the
+//! whole vault's. A split gives each new pane its own engine, started
+//! with the pane's number.
+
+use crate::block::{BlockId, BlockManager, SearchHit};
+use crate::holodeck::{HolodeckEntry, HolodeckManager};
+use crate::marks::MarkTable;
+
+/// A split pane, numbered from 1 in the order it was opened. The number
+/// is what the vault tags its commands with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PaneId(pub u32);
+
+/// How far a search, export or Holodeck listing reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+    /// The focused pane only.
+    #[default]
+    Focused,
+    /// Every pane, each under its own heading.
+    AllPanes,
+}
+
+/// One pane's scrollback and the state that goes with it.
+#[derive(Debug, Default)]
+pub struct SessionContext {
+    pub blocks: BlockManager,
+    pub holodeck: HolodeckManager,
+    pub marks: MarkTable,
+    /// The pane's last scrollback search, kept while focus is elsewhere.
+    pub search: Option<String>,
+    /// The block the pane's shell is running, if any.
+    pub running: Option<BlockId>,
+}
+
+/// `!export <path> --with-timestamps [--all-panes]`: the scrollback,
+/// each line stamped, in place of the history core's `!export` writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollbackExport {
+    pub path: String,
+    pub scope: Scope,
+}
+
+impl ScrollbackExport {
+    /// Parse what follows `!export`; None without `--with-timestamps`,
+    /// or with anything but a path and `--all-panes` beside it.
+    pub fn parse(args: &str) -> Option<Self> {
+        let mut path = None;
+        let (mut stamped, mut scope) = (false, Scope::Focused);
+        for arg in args.split_whitespace() {
+            match arg {
+                "--with-timestamps" => stamped = true,
+                "--all-panes" => scope = Scope::AllPanes,
+                _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.to_string()),
+                _ => return None,
+            }
+        }
+        stamped.then_some(Self { path: path?, scope })
+    }
+}
+
+/// A search hit and the pane it was found in.
+#[derive(Debug, Clone)]
+pub struct PaneHit {
+    pub pane: PaneId,
+    pub hit: SearchHit,
+}
+
+/// The window's panes and which one has focus.
+#[derive(Debug)]
+pub struct Sessions {
+    panes: Vec<(PaneId, SessionContext)>,
+    focused: PaneId,
+    next: u32,
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sessions {
+    /// One pane, focused: the window before any split.
+    pub fn new() -> Self {
+        Self { panes: vec![(PaneId(1), SessionContext::default())], focused: PaneId(1), next: 2 }
+    }
+
+    /// Open a pane with empty state and focus it.
+    pub fn open(&mut self) -> PaneId {
+        let pane = PaneId(self.next);
+        self.next += 1;
+        self.panes.push((pane, SessionContext::default()));
+        self.focused = pane;
+        pane
+    }
+
+    /// Close `pane` and drop its state. The last pane stays open; focus
+    /// moves to the pane opened before it.
+    pub fn close(&mut self, pane: PaneId) -> Option<SessionContext> {
+        if self.panes.len() == 1 {
+            return None;
+        }
+        let index = self.panes.iter().position(|(id, _)| *id == pane)?;
+        let (_, context) = self.panes.remove(index);
+        if self.focused == pane {
+            self.focused = self.panes[index.saturating_sub(1)].0;
+        }
+        Some(context)
+    }
+
+    /// Focus `pane`; false if there is no such pane.
+    pub fn focus(&mut self, pane: PaneId) -> bool {
+        let found = self.get(pane).is_some();
+        if found {
+            self.focused = pane;
+        }
+        found
+    }
+
+    pub fn focused_id(&self) -> PaneId {
+        self.focused
+    }
+
+    /// True once the window is split.
+    pub fn is_split(&self) -> bool {
+        self.panes.len() > 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.panes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panes.is_empty()
+    }
+
+    pub fn focused(&self) -> &SessionContext {
+        self.get(self.focused).expect("the focused pane is open")
+    }
+
+    pub fn focused_mut(&mut self) -> &mut SessionContext {
+        let focused = self.focused;
+        self.get_mut(focused).expect("the focused pane is open")
+    }
+
+    pub fn get(&self, pane: PaneId) -> Option<&SessionContext> {
+        self.panes.iter().find(|(id, _)| *id == pane).map(|(_, c)| c)
+    }
+
+    pub fn get_mut(&mut self, pane: PaneId) -> Option<&mut SessionContext> {
+        self.panes.iter_mut().find(|(id, _)| *id == pane).map(|(_, c)| c)
+    }
+
+    /// The panes `scope` reaches, in the order they were opened.
+    pub fn scoped(&self, scope: Scope) -> impl Iterator<Item = (PaneId, &SessionContext)> {
+        self.panes.iter().map(|(id, c)| (*id, c)).filter(move |(id, _)| scope == Scope::AllPanes || *id == self.focused)
+    }
+
+    /// Scrollback search over the panes `scope` reaches.
+    pub fn search(&self, query: &str, scope: Scope) -> Vec<PaneHit> {
+        self.scoped(scope)
+            .flat_map(|(pane, c)| c.blocks.search(query).into_iter().map(move |hit| PaneHit { pane, hit }))
+            .collect()
+    }
+
+    /// A block of the focused pane as clipboard text. Block ids are per
+    /// pane, so another pane's block with the same id is never copied.
+    pub fn copy_block(&self, block_id: BlockId) -> Option<String> {
+        self.focused().blocks.copy_block(block_id)
+    }
+
+    /// The scrollback of the panes `scope` reaches; with more than one,
+    /// each under a `═══ pane <n> ═══` heading.
+    pub fn export(&self, scope: Scope, with_timestamps: bool) -> String {
+        if scope == Scope::Focused || !self.is_split() {
+            return self.focused().blocks.export(with_timestamps);
+        }
+        self.scoped(scope)
+            .map(|(pane, c)| format!("═══ pane {} ═══\n{}", pane.0, c.blocks.export(with_timestamps)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The Holodeck entries of the panes `scope` reaches, oldest first
+    /// within each pane.
+    pub fn holodeck_entries(&self, scope: Scope) -> Vec<(PaneId, &HolodeckEntry)> {
+        self.scoped(scope)
+            .flat_map(|(pane, c)| c.holodeck.entries().iter().map(move |entry| (pane, entry)))
+            .collect()
+    }
+
+    /// `!holodeck list` for the panes `scope` reaches.
+    pub fn holodeck_list_lines(&self, scope: Scope) -> Vec<String> {
+        if scope == Scope::Focused || !self.is_split() {
+            return self.focused().holodeck.list_lines();
+        }
+        self.scoped(scope)
+            .flat_map(|(pane, c)| {
+                let mut lines = c.holodeck.list_lines();
+                lines[0] = format!("{} (pane {})", lines[0], pane.0);
+                lines
+            })
+            .collect()
+    }
+
+    /// The status bar's pane label: `pane 2/3` once split, else nothing.
+    pub fn status_label(&self) -> Option<String> {
+        let index = self.panes.iter().position(|(id, _)| *id == self.focused)?;
+        self.is_split().then(|| format!("pane {}/{}", index + 1, self.panes.len()))
+    }
+}
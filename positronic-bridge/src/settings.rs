@@ -0,0 +1,414 @@
+//! UI settings read from the vault config, and `!profile` parsing.
+//!
+//! `Settings::load` is the one path that turns config values into live
+//! state: startup, `!config reload`, `!keys reload` and `!profile use` all
+//! go through it. It reads through a lookup, normally the vault's layered
+//! `get_config` (active profile first, then base config), so a profile can
+//! be checked with a prospective lookup before anything is switched.
+//! Applying the result is plain assignment, so a profile either applies
+//! completely or, if any value is invalid, not at all.
+
+use std::time::Duration;
+
+use crate::attention;
+use crate::bell::BellPolicy;
+use crate::clipboard_history::{self, ClipboardSettings};
+use crate::governor::{self, PowerConfig};
+use crate::inputrc;
+use crate::keymap::Keymap;
+use crate::layout::LayoutSpec;
+use crate::macros;
+use crate::marks;
+use crate::pager::{self, PagerThreshold};
+use crate::present::PresentSettings;
+use crate::renderer::{self, ThemeName, TimestampMode};
+use crate::syntax;
+use crate::theme_sync::ThemeSync;
+use crate::window_style::WindowStyle;
+use positronic_core::calc;
+use positronic_core::danger;
+use positronic_core::here;
+use positronic_core::history_filter::HistoryFilter;
+use positronic_core::native;
+use positronic_core::summary::SummarySettings;
+use positronic_core::time_format::TimeSettings;
+
+/// Vault config key for the color theme.
+pub const THEME_KEY: &str = "theme";
+
+// ════════════════════════════════════════════════════════════════════
+// Settings
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub theme: ThemeName,
+    /// `theme.mode` and friends; when set, they pick the theme instead.
+    pub theme_sync: Option<ThemeSync>,
+    pub keymap: Keymap,
+    pub slow_threshold: Duration,
+    pub timestamps: TimestampMode,
+    /// Highlight code in block output (`blocks.highlight`).
+    pub highlight: bool,
+    /// Commands kept at full strength (`blocks.dim_old`, `None`: off).
+    pub dim_old: Option<usize>,
+    pub pager: PagerThreshold,
+    pub clipboard: ClipboardSettings,
+    /// Opacity, padding and cursor.
+    pub window: WindowStyle,
+    /// Status bar, input bar and gap sizes (`layout.*`).
+    pub layout: LayoutSpec,
+    /// Show the result of arithmetic typed at the prompt (`calc.hint`).
+    pub calc_hint: bool,
+    /// `history.ignore_*`: what stays out of the Up-arrow list.
+    pub history: HistoryFilter,
+    /// Hand native command tables to the Holodeck (`holodeck.native`).
+    pub holodeck_native: bool,
+    /// Follow a destructive `rm` with an `!rm` hint (`danger.suggest_rm`).
+    pub suggest_rm: bool,
+    /// When an unfocused, quiet window throttles its work (`power.*`).
+    pub power: PowerConfig,
+    /// Apply `~/.inputrc` over the key bindings (`input.use_inputrc`).
+    pub use_inputrc: bool,
+    /// Show a directory's frequent commands on entering it (`hints.directory`).
+    pub directory_hints: bool,
+    /// What a terminal bell does (`bell.*`).
+    pub bell: BellPolicy,
+    /// What `!present` hides, and whether copies are redacted (`present.*`).
+    pub present: PresentSettings,
+    /// Save `!mark`s in the vault for the next session (`marks.persist`).
+    pub marks_persist: bool,
+    /// The pause between a macro's played steps (`macro.delay`).
+    pub macro_delay: Duration,
+    /// How timestamps read (`time.format`, `time.relative`).
+    pub time: TimeSettings,
+    /// Where closing a session writes its summary (`summary.*`).
+    pub summary: SummarySettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: ThemeName::Default,
+            theme_sync: None,
+            keymap: Keymap::default(),
+            slow_threshold: renderer::DEFAULT_SLOW_THRESHOLD,
+            timestamps: TimestampMode::Off,
+            highlight: true,
+            dim_old: Some(attention::DEFAULT_DIM_OLD),
+            pager: PagerThreshold::Screen,
+            clipboard: ClipboardSettings::default(),
+            window: WindowStyle::default(),
+            layout: LayoutSpec::default(),
+            calc_hint: false,
+            history: HistoryFilter::default(),
+            holodeck_native: true,
+            suggest_rm: false,
+            power: PowerConfig::default(),
+            use_inputrc: false,
+            directory_hints: true,
+            bell: BellPolicy::default(),
+            present: PresentSettings::default(),
+            marks_persist: false,
+            macro_delay: macros::DEFAULT_DELAY,
+            time: TimeSettings::default(),
+            summary: SummarySettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Read every setting through `lookup`. Invalid values fall back to
+    /// their defaults and are described in the returned problems.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>) -> (Settings, Vec<String>) {
+        let mut problems = Vec::new();
+
+        let theme = match lookup(THEME_KEY) {
+            None => ThemeName::Default,
+            Some(value) => ThemeName::from_str(value.trim()).unwrap_or_else(|| {
+                let names: Vec<&str> = ThemeName::all().iter().map(|t| t.label()).collect();
+                problems.push(format!(
+                    "{} = \"{}\": unknown theme (one of {})",
+                    THEME_KEY,
+                    value,
+                    names.join(", ")
+                ));
+                ThemeName::Default
+            }),
+        };
+
+        let theme_sync = ThemeSync::load(&lookup, &mut problems);
+
+        let slow_threshold = match lookup(renderer::SLOW_THRESHOLD_KEY) {
+            None => renderer::DEFAULT_SLOW_THRESHOLD,
+            Some(value) => renderer::parse_threshold(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected e.g. 10s, 500ms or 2m",
+                    renderer::SLOW_THRESHOLD_KEY,
+                    value
+                ));
+                renderer::DEFAULT_SLOW_THRESHOLD
+            }),
+        };
+
+        let timestamps = match lookup(renderer::TIMESTAMPS_KEY) {
+            None => TimestampMode::Off,
+            Some(value) => TimestampMode::parse(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected on, off or relative",
+                    renderer::TIMESTAMPS_KEY,
+                    value
+                ));
+                TimestampMode::Off
+            }),
+        };
+
+        let highlight = match lookup(syntax::HIGHLIGHT_KEY) {
+            None => true,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", syntax::HIGHLIGHT_KEY, value));
+                true
+            }),
+        };
+
+        let dim_old = match lookup(attention::DIM_OLD_KEY) {
+            None => Some(attention::DEFAULT_DIM_OLD),
+            Some(value) => attention::parse_dim_old(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected a number of commands or off",
+                    attention::DIM_OLD_KEY,
+                    value
+                ));
+                Some(attention::DEFAULT_DIM_OLD)
+            }),
+        };
+
+        let pager = match lookup(pager::PAGER_KEY) {
+            None => PagerThreshold::Screen,
+            Some(value) => PagerThreshold::parse(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected auto, off or a line count",
+                    pager::PAGER_KEY,
+                    value
+                ));
+                PagerThreshold::Screen
+            }),
+        };
+
+        let mut clipboard = ClipboardSettings::default();
+        if let Some(value) = lookup(clipboard_history::SIZE_KEY) {
+            match value.trim().parse() {
+                Ok(size) => clipboard.size = size,
+                Err(_) => problems.push(format!(
+                    "{} = \"{}\": expected a number of entries",
+                    clipboard_history::SIZE_KEY,
+                    value
+                )),
+            }
+        }
+        for (key, flag) in [
+            (clipboard_history::KEEP_SECRETS_KEY, &mut clipboard.keep_secrets),
+            (clipboard_history::PERSIST_KEY, &mut clipboard.persist),
+        ] {
+            if let Some(value) = lookup(key) {
+                match clipboard_history::parse_flag(&value) {
+                    Some(on) => *flag = on,
+                    None => problems.push(format!("{} = \"{}\": expected on or off", key, value)),
+                }
+            }
+        }
+
+        let window = WindowStyle::load(&lookup, &mut problems);
+        let layout = LayoutSpec::load(&lookup, &mut problems);
+
+        let calc_hint = match lookup(calc::HINT_KEY) {
+            None => false,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", calc::HINT_KEY, value));
+                false
+            }),
+        };
+
+        let history = HistoryFilter::load(&lookup, &mut problems);
+
+        let holodeck_native = match lookup(native::HOLODECK_KEY) {
+            None => true,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", native::HOLODECK_KEY, value));
+                true
+            }),
+        };
+
+        let suggest_rm = match lookup(danger::SUGGEST_RM_KEY) {
+            None => false,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", danger::SUGGEST_RM_KEY, value));
+                false
+            }),
+        };
+
+        let mut power = PowerConfig::default();
+        if let Some(value) = lookup(governor::IDLE_AFTER_KEY) {
+            power.idle_after = governor::parse_idle_after(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected e.g. 90s or 5m, or off",
+                    governor::IDLE_AFTER_KEY,
+                    value
+                ));
+                Some(governor::DEFAULT_IDLE_AFTER)
+            });
+        }
+        if let Some(value) = lookup(governor::SUSPEND_AFTER_KEY) {
+            power.suspend_after = renderer::parse_threshold(&value).unwrap_or_else(|| {
+                problems.push(format!(
+                    "{} = \"{}\": expected e.g. 10m or 600s",
+                    governor::SUSPEND_AFTER_KEY,
+                    value
+                ));
+                governor::DEFAULT_SUSPEND_AFTER
+            });
+        }
+
+        let use_inputrc = match lookup(inputrc::USE_INPUTRC_KEY) {
+            None => false,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", inputrc::USE_INPUTRC_KEY, value));
+                false
+            }),
+        };
+
+        let directory_hints = match lookup(here::HINTS_KEY) {
+            None => true,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", here::HINTS_KEY, value));
+                true
+            }),
+        };
+
+        let marks_persist = match lookup(marks::PERSIST_KEY) {
+            None => false,
+            Some(value) => clipboard_history::parse_flag(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected on or off", marks::PERSIST_KEY, value));
+                false
+            }),
+        };
+
+        let macro_delay = match lookup(macros::DELAY_KEY) {
+            None => macros::DEFAULT_DELAY,
+            Some(value) => renderer::parse_threshold(&value).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": expected e.g. 30ms or 1s", macros::DELAY_KEY, value));
+                macros::DEFAULT_DELAY
+            }),
+        };
+
+        let bell = BellPolicy::load(&lookup, &mut problems);
+        let present = PresentSettings::load(&lookup, &mut problems);
+        let time = TimeSettings::load(&lookup, &mut problems);
+        let summary = SummarySettings::load(&lookup, &mut problems);
+
+        let keymap = Keymap::from_config(&lookup);
+        problems.extend(keymap.errors().iter().cloned());
+
+        (
+            Settings {
+                theme,
+                theme_sync,
+                keymap,
+                slow_threshold,
+                timestamps,
+                highlight,
+                dim_old,
+                pager,
+                clipboard,
+                window,
+                layout,
+                calc_hint,
+                history,
+                holodeck_native,
+                suggest_rm,
+                power,
+                use_inputrc,
+                directory_hints,
+                bell,
+                present,
+                marks_persist,
+                macro_delay,
+                time,
+                summary,
+            },
+            problems,
+        )
+    }
+
+    /// Put the default theme in place of each one `disabled` says
+    /// `!safe disable` turned off, with a line saying so for each.
+    pub fn drop_disabled_themes(&mut self, disabled: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut notes = Vec::new();
+        let mut check = |theme: &mut ThemeName| {
+            if *theme != ThemeName::Default && disabled(theme.label()) {
+                notes.push(format!("Theme {} is disabled (!safe enable theme {}); using default", theme.label(), theme.label()));
+                *theme = ThemeName::Default;
+            }
+        };
+        check(&mut self.theme);
+        if let Some(sync) = &mut self.theme_sync {
+            check(&mut sync.light);
+            check(&mut sync.dark);
+        }
+        notes
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !profile
+// ════════════════════════════════════════════════════════════════════
+
+/// Names with a meaning of their own in `!profile use`.
+const RESERVED_NAMES: &[&str] = &["previous", "none", "base"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileTarget {
+    Named(String),
+    /// No profile: the base config only.
+    Base,
+    /// Whatever was active before the last switch.
+    Previous,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileCommand {
+    List,
+    Save(String),
+    Use(ProfileTarget),
+    Remove(String),
+}
+
+pub const PROFILE_USAGE: &str = "Usage: !profile list | save <name> | use <name|previous|none> | rm <name>";
+
+impl ProfileCommand {
+    /// Parse `!profile ...`; the error is a message for the user.
+    pub fn parse(cmd: &str) -> Result<ProfileCommand, String> {
+        let parts: Vec<&str> = cmd.split_whitespace().collect();
+        match parts.as_slice() {
+            ["!profile"] | ["!profile", "list"] => Ok(ProfileCommand::List),
+            ["!profile", "save", name] => valid_name(name).map(ProfileCommand::Save),
+            ["!profile", "rm", name] => valid_name(name).map(ProfileCommand::Remove),
+            ["!profile", "use", "previous"] => Ok(ProfileCommand::Use(ProfileTarget::Previous)),
+            ["!profile", "use", "none" | "base"] => Ok(ProfileCommand::Use(ProfileTarget::Base)),
+            ["!profile", "use", name] => {
+                valid_name(name).map(|n| ProfileCommand::Use(ProfileTarget::Named(n)))
+            }
+            _ => Err(PROFILE_USAGE.to_string()),
+        }
+    }
+}
+
+fn valid_name(name: &str) -> Result<String, String> {
+    if RESERVED_NAMES.contains(&name.to_lowercase().as_str()) {
+        return Err(format!("'{}' is reserved; pick another profile name", name));
+    }
+    if !name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+        return Err(format!("Profile names use letters, digits, '-', '_' and '.' ('{}')", name));
+    }
+    Ok(name.to_string())
+}
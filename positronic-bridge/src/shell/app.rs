@@ -1,28 +1,109 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
 use winit::event::WindowEvent;
 use winit::event_loop::{ActiveEventLoop, ControlFlow, EventLoop};
 use winit::keyboard::ModifiersState;
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::window::{Theme, Window, WindowAttributes, WindowId};
 
-use positronic_core::engine::ExecuteResult;
+use positronic_core::asciicast::{self, Cast, FileRecording, RecordCommand};
+use positronic_core::calc;
+use positronic_core::chain::{self, ChainStep};
+use positronic_core::danger::DangerAnalyzer;
+use positronic_core::diagnostics::{self, Diagnostic, Extractor, Severity};
+use positronic_core::history_filter::{self, HistoryFilter};
+use positronic_core::data_paths::DataPaths;
+use positronic_core::engine::{EngineOptions, ExecuteResult};
+use positronic_core::error::{ErrorAction, PositronicError, VaultErrorKind};
+use positronic_core::fix::{Correction, FixSource};
+use positronic_core::fetch::{FetchAs, Fetched};
+use positronic_core::follow::{FollowEvent, FollowProcess, FOLLOW_USAGE};
+use positronic_core::here;
+use positronic_core::inspect;
+use positronic_core::maintenance::{self, Freshness, TaskState};
+use positronic_core::http::HttpExchange;
+use positronic_core::progress::{self, ProgressEvent, TaskSnapshot};
+use positronic_core::queue;
+use positronic_core::ipc::IpcEvent;
+use positronic_core::redo::{RedoChoice, RedoOffer};
+use positronic_core::respawn::ShellExit;
+use positronic_core::safe_mode::{self, BootMarker, DisabledKind};
+use positronic_core::scaffold::{NewCommand, NewRequest};
+use positronic_core::serial;
 use positronic_core::state_machine::Snapshot;
-use positronic_core::PositronicEngine;
+use positronic_core::summary::{self, SummarySettings};
+use positronic_core::sync;
+use positronic_core::tags;
+use positronic_core::time_format::{self, TimeSettings};
+use positronic_core::timeline::Timeline;
+use positronic_core::vault::{recover, Vault};
+use positronic_core::{PositronicEngine, PtyEvent};
+use positronic_io::{device, HardwareEvent};
+use positronic_script::plugins::PluginRegistry;
 use tokio::sync::mpsc;
 
-use crate::cwd::{track_cd_command, update_cwd_from_snapshot};
-use crate::gfx::GpuState;
-use crate::renderer::{self, ThemeName};
+use crate::attention::{self, ScreenMarks};
+use crate::block::BlockId;
+use crate::bell::{self, BellCommand, BellContext, BellPolicy, BellSink, Coalescer, Mute};
+use crate::biolink::{BioLink, BioLinkEvent};
+use crate::clipboard_history::{self, ClipboardHistory, ClipboardPicker, PasteCommand};
+use crate::compare::{self, CompareCommand, CompareOutcome, CompareView};
+use crate::completer::{self, CompletionState, Providers};
+use crate::console::{Console, ConsoleCommand, ConsoleExit, ConsoleKey};
+use crate::cwd::{cwd_is_uncertain, track_cd_command, update_cwd_from_snapshot};
+use crate::dir_hint::{self, DirectoryHints};
+use crate::draft::{self, DraftKeeper, DraftWrite};
+use crate::error_center::{self, ErrorCenter, ErrorSeverity};
+use crate::follow::{FollowKey, FollowPhase, FollowState};
+use crate::gfx::{GpuState, SoftwareRenderer};
+use crate::git_complete::GitCompleter;
+use crate::governor::{Governor, PowerConfig, PowerState};
+use crate::hardware::HardwarePanel;
+use crate::highlight::{HighlightSpan, Highlighter, Sources};
+use crate::inputrc::{self, EditingMode, Inputrc};
+use crate::file_finder::{self, Dispatch, FileFinder, FinderOutcome};
+use crate::key_help::{KeyHelp, KeyHelpOutcome};
+use crate::keymap::{Action, Chord, EscapeRoute, EscapeState, InterruptRoute, Keymap};
+use crate::pager::{Pager, PagerKey, PagerOutcome, PagerThreshold};
+use crate::passphrase::{PassphrasePrompt, PromptStep, VaultAction};
+use crate::present::{self, Identity, PresentCommand, PresentSettings, Redactor};
+use crate::path_index::PathIndex;
+use crate::platform;
+use crate::render_path::{
+    self, Attempt, DeviceRecovery, Platform, Probed, RecoveryStep, RenderPath, RenderReport, RendererChoice,
+};
+use crate::replay::Replay;
+use crate::resize::{self, ResizeDebounce};
+use crate::scope::{ScopeCommand, ScopeView};
+use crate::selection::{GridPos, Selection};
+use crate::session_context::{Scope, ScrollbackExport, Sessions};
+use crate::renderer::{self, BlockStyle, ThemeName, TimestampMode};
+use crate::settings::{ProfileCommand, ProfileTarget, Settings, THEME_KEY};
+use crate::span_cache::SpanCache;
+use crate::suggestions::SuggestionPicker;
+use crate::theme_sync::{self, Appearance, ThemeSwitcher};
+use crate::viewer::{self, FileView, ViewCommand};
+use crate::viewport::Viewport;
+use crate::window_style::{self, Blink, WindowStyle};
+use crate::layout::{self, CellMetrics, ComputedLayout, LayoutSpec};
+use crate::macros::{self, MacroCommand, Player, Recorder, Step, Tick};
+use crate::marks::{self, JumpList, Mark, MarkTarget, Resolved, Spot, MARK_USAGE};
+use crate::soft_frame;
 
-use positronic_core::term::modes::ModeTracker;
-use positronic_core::term::osc::OscParser;
+use positronic_core::term::modes::{CursorShape, ModeTracker};
+use positronic_core::term::osc::{OscEvent, OscParser};
 use positronic_core::term::semantic::SemanticState;
+use positronic_core::subsystems::SubsystemState;
+
+use crate::holodeck::{detect::Detectors, protocol::HolodeckDoc};
+use crate::holodeck::protocol::Action as HolodeckAction;
+use crate::holodeck::export::{self, SaveCommand, SaveFormat};
+use crate::holodeck::chart::{self, ChartCommand};
+use crate::holodeck::{ChartSpec, ContentDetector, DataFrame, ImageMeta, RichContent, Scored, SOURCE_TAG};
 
-use crate::holodeck::{detect, protocol::HolodeckDoc};
-use crate::holodeck::protocol::Action;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppState {
@@ -33,9 +114,36 @@ pub enum AppState {
 
 pub const MAX_DIRECT_BYTES: usize = 256 * 1024;
 
+/// Terminal size assumed before the first resize.
+pub const DEFAULT_SCREEN_COLS: usize = 80;
+pub const DEFAULT_SCREEN_ROWS: usize = 24;
+
+/// How long the status bar shows an automatic theme switch.
+const THEME_NOTE_DURATION: Duration = Duration::from_secs(5);
+
+/// How long the status bar says what the interrupt chord did.
+const KEY_NOTE_DURATION: Duration = Duration::from_secs(2);
+
+/// How often an open file finder looks for more files while its index
+/// is still being read.
+const FINDER_POLL: Duration = Duration::from_millis(150);
+
+/// Vault entries Up looks through for a typed prefix.
+const HISTORY_PREFIX_LIMIT: usize = 500;
+
+/// Screen lines above the prompt searched for a "command not found".
+const NOT_FOUND_LINES: usize = 6;
+
 pub struct PositronicApp {
     pub window: Option<Arc<dyn Window>>,
     pub gpu: Option<GpuState>,
+    /// Draws the window when no GPU adapter could be opened.
+    pub software: Option<SoftwareRenderer>,
+    /// `--renderer` / `POSITRONIC_RENDERER`.
+    pub renderer_choice: RendererChoice,
+    /// The path in use and why, for the boot log and `!doctor`.
+    pub render_report: Option<RenderReport>,
+    pub recovery: DeviceRecovery,
 
     pub engine: Option<Arc<PositronicEngine>>,
     pub redraw_rx: Option<mpsc::Receiver<()>>,
@@ -46,20 +154,210 @@ pub struct PositronicApp {
     pub direct_output: String,
     pub last_snapshot: Option<Snapshot>,
     pub last_screen_hash: u64,
+    pub span_cache: SpanCache,
+    /// Scroll position over the scrollback, or over native output before
+    /// the shell has drawn anything.
+    pub viewport: Viewport,
+    /// Command marks over the scrollback: unread output, dimming and the
+    /// focused block.
+    pub marks: ScreenMarks,
+    /// The places Ctrl+O / Ctrl+I walk; `!mark` names are per pane, in
+    /// `sessions`.
+    pub jump_list: JumpList,
+    /// `marks.persist`: marks are saved in the vault for the next session.
+    marks_persist: bool,
+    /// The block whose command the input line was taken from; what is
+    /// submitted next is recorded as edited from it.
+    block_link: Option<BlockId>,
 
     pub input: String,
     pub cursor_pos: usize,
     pub composing: bool,
+    /// The input holds an AI-generated command not yet submitted.
+    pub input_ai_generated: bool,
 
     pub cmd_history: Vec<String>,
     pub history_cursor: Option<usize>,
+    /// The line being typed when Up started browsing the history.
+    pub history_stash: String,
+    /// Set when Up started on a typed line: browsing visits only
+    /// `history_matches`, the entries starting with it, oldest first.
+    pub history_prefix: Option<String>,
+    pub history_matches: Vec<String>,
+    /// Debounced vault checkpoints of the unsent input line.
+    pub draft: DraftKeeper,
+    /// A previous session's draft, loaded by Up on an empty line.
+    pub pending_draft: Option<String>,
+    pub completion: Option<CompletionState>,
+    /// Executables on PATH for Tab; first filled in the background.
+    pub executables: Arc<PathIndex>,
+    /// git subcommand, alias and branch completion.
+    pub git_completer: GitCompleter,
+    /// Input line coloring, memoized per edit.
+    pub highlighter: Highlighter,
+    /// Open `!suggest` list; digits 1–9 or a click pick into the input.
+    pub suggestions: Option<SuggestionPicker>,
+    /// Open `!redo` prompt; a, b or c on an empty input answers it.
+    pub redo_offer: Option<RedoOffer>,
+    /// Open correction preview; Enter on an empty input puts it in the
+    /// input line, Esc dismisses it.
+    pub correction: Option<Correction>,
+    /// The last `!timeline` shown, to find the command under a click.
+    pub timeline: Option<Timeline>,
+    /// What Positronic copied, newest first, for `!paste`.
+    pub clipboard_history: ClipboardHistory,
+    /// Ctrl+Shift+V list; digits 1–9 or a click insert an entry.
+    pub clipboard_picker: Option<ClipboardPicker>,
+    /// Long DirectOutput being paged; takes every key while open.
+    pub pager: Option<Pager>,
+    pub pager_threshold: PagerThreshold,
+    /// `!compare` overlay; takes every key while open, as the pager does.
+    pub compare: Option<CompareView>,
+    /// F1 / `!keys show` reference; takes every key while open.
+    pub key_help: Option<KeyHelp>,
+    /// Ctrl+P file finder; takes every key while open.
+    pub file_finder: Option<FileFinder>,
+    /// The last `!view`, with its path resolved; a bare `!view` repeats it.
+    pub last_view: Option<ViewCommand>,
+    /// Terminal area size in cells, from the last resize.
+    pub screen_cols: usize,
+    pub screen_rows: usize,
+    /// Window resizes the PTY hasn't followed yet, while a drag settles.
+    pub resize: ResizeDebounce,
+    /// `!record start` session; PTY output and input are appended live.
+    pub recording: Option<FileRecording>,
+    /// `!record play` overlay; takes every key while open.
+    pub replay: Option<Replay>,
+    /// `!macro record`: steps taken at the prompt, until `!macro stop`.
+    pub macro_recorder: Option<Recorder>,
+    /// `!macro play`, until done or Escape.
+    pub macro_player: Option<Player>,
+    /// `macro.delay`: the pause between played steps.
+    macro_delay: Duration,
+    /// The shell's last line asks for a password; what is typed now is
+    /// kept out of recordings and macros.
+    password_prompt: bool,
+    /// Wall time the replay started, for advancing it each frame.
+    pub replay_started: Instant,
+    /// `!vault` passphrase entry; owns the (masked) input line while open.
+    pub passphrase: Option<PassphrasePrompt>,
+    /// The shell died; Enter restarts it instead of submitting.
+    pub shell_exit: Option<ShellExit>,
+
+    /// Status bar timer for the running command, and whether it has
+    /// passed the slow threshold.
+    pub running_status: Option<(String, bool)>,
+    /// When the timer next needs redrawing.
+    pub running_wake: Option<Instant>,
+    /// Status bar entries for long operations in progress (see
+    /// `positronic_core::progress`).
+    pub tasks_status: Option<String>,
+    /// While any run, when their entries next need redrawing.
+    pub tasks_wake: Option<Instant>,
+    /// `time.*`: how the status bar's last-command stamp reads.
+    pub time_settings: TimeSettings,
+    /// `summary.*`: whether closing the session saves its summary.
+    pub summary_settings: SummarySettings,
+    /// When the last command was submitted, or finished if it ran on.
+    pub last_command_at: Option<i64>,
+    /// Status bar entry for it: `🕘 last 3 min ago`.
+    pub last_command_status: Option<String>,
+    /// When that entry next reads differently.
+    pub last_command_wake: Option<Instant>,
+    /// Opacity, padding and cursor settings.
+    pub window_style: WindowStyle,
+    /// `layout.*`: status bar, input bar and gap sizes.
+    pub layout_spec: LayoutSpec,
+    /// Input cursor blink phase; restarted by each key press.
+    pub blink: Blink,
+    /// Whether the last frame drew the input cursor.
+    pub cursor_shown: bool,
+    /// `calc.hint`: show `= 4` beside arithmetic typed at the prompt.
+    pub calc_hint: bool,
+    /// `hints.directory`: a directory's frequent commands on entering it.
+    pub directory_hints: DirectoryHints,
+    /// `history.ignore_*`: lines kept out of `cmd_history` (and the vault).
+    pub history_filter: HistoryFilter,
+    /// `holodeck.native`: native command tables go to the Holodeck too.
+    pub holodeck_native: bool,
+    /// `danger.suggest_rm`: a destructive `rm` gets an `!rm` hint.
+    pub suggest_rm: bool,
+    /// `bell.*`: what a terminal bell does.
+    pub bell_policy: BellPolicy,
+    /// Bells waiting to be acted on as one.
+    pub bells: Coalescer,
+    /// `!bell mute`.
+    pub bell_mute: Mute,
+    /// The status bar is flashed until then.
+    pub bell_flash: Option<Instant>,
+    /// `present.*`: what `!present` hides.
+    pub present_settings: PresentSettings,
+    /// Set while `!present` is on; everything drawn passes through it.
+    pub presenting: Option<Redactor>,
+    /// Started in safe mode: the user's settings, theme and `.inputrc`
+    /// are left out (see `positronic_core::safe_mode`).
+    pub safe_mode: bool,
+    /// Waiting for the answer to the safe-mode question before booting.
+    pub safe_mode_asked: bool,
+    /// `--inspect`: the vault or bundle being browsed read-only in place
+    /// of the user's own (see `positronic_core::inspect`). Shell input is
+    /// refused, and the user's settings are left out as in safe mode.
+    pub inspect: Option<PathBuf>,
+    /// Where the vault and the rest of the data live (see
+    /// `positronic_core::data_paths`).
+    pub data: DataPaths,
+    /// Counts boots that crash; a normal one clears it at `boot_stable_at`.
+    pub boot_marker: BootMarker,
+    pub boot_stable_at: Option<Instant>,
 
     pub session_cmd_count: usize,
     pub boot_instant: Instant,
+    /// Subsystems whose failure has already been announced.
+    pub reported_subsystems: Vec<&'static str>,
     pub cwd: String,
     pub theme_name: ThemeName,
+    /// `theme.mode` switching and any manual `!theme` override of it.
+    pub theme_switcher: ThemeSwitcher,
+    /// Status bar note after an automatic theme switch, and when it goes.
+    pub theme_note: Option<(String, Instant)>,
+    /// Status bar note saying what the interrupt chord did, and when it goes.
+    pub key_note: Option<(String, Instant)>,
+    /// Engine and subsystem failures, toasted in the status bar.
+    pub error_center: ErrorCenter,
+    /// The window system reports the OS appearance, so it needn't be polled.
+    pub os_theme_reported: bool,
+    /// Next poll of the OS appearance where it isn't reported.
+    pub os_theme_poll: Option<Instant>,
+    /// Active `!profile`, shown in the status bar.
+    pub active_profile: Option<String>,
+    /// `!setenv` variables in effect, for the status bar badge.
+    pub env_overrides: usize,
+    /// Toggled by `!perf overlay`.
+    pub perf_overlay: bool,
+    /// Throttles timers, probes and snapshots while the window is idle.
+    pub governor: Governor,
+    /// When PTY output started waiting for a throttled snapshot.
+    pub output_pending: Option<Instant>,
+    /// Serial devices and their parsed channels, from the engine's IO events.
+    pub hardware: HardwarePanel,
+    /// Open `!scope` pane; `+`/`-` zoom it while the input is empty.
+    pub scope: Option<ScopeView>,
+    /// Open `!io console`; takes every key and the terminal area.
+    pub console: Option<Console>,
+    /// Open `!follow` pane; takes every key and the terminal area.
+    pub follow: Option<FollowState>,
+    /// The followed command and its output, while the pane is open.
+    pub follow_process: Option<(FollowProcess, mpsc::UnboundedReceiver<FollowEvent>)>,
+    /// Diagnostics last listed by `!errors`, for `!errors open <n>`, and
+    /// the directory their paths are relative to.
+    pub diagnostics: Vec<Diagnostic>,
+    pub diagnostics_dir: String,
 
     pub modifiers: ModifiersState,
+    /// Shortcut bindings; reloaded from config by `!keys reload`.
+    pub keymap: Keymap,
+    /// `completion-ignore-case` from an imported `.inputrc`.
+    pub completion_ignore_case: bool,
     pub wants_exit: bool,
     pub rt: tokio::runtime::Handle,
 
@@ -67,17 +365,40 @@ pub struct PositronicApp {
     pub mode_tracker: ModeTracker,
     pub osc_parser: OscParser,
     pub semantic: SemanticState,
+    /// The first Ctrl+R has asked for the history search index.
+    pub search_warmed: bool,
 
     pub holodeck_doc: Option<HolodeckDoc>,
     pub holodeck_safe: bool,
+    /// The panes' Holodecks (tables and JSON seen on screen, for
+    /// `!save`), marks and searches; the focused one is the window's.
+    pub sessions: Sessions,
+    /// What finds them: the built-ins and the WASM detector plugins.
+    pub detectors: Detectors,
+    /// Screen reader and speech announcements, such as IPC `notify`.
+    pub biolink: BioLink,
 
     pub last_mouse_x: f32,
     pub last_mouse_y: f32,
+    /// Text selected with the mouse, in snapshot rows.
+    pub selection: Option<Selection>,
+    /// The left button is down and dragging extends the selection.
+    pub selecting: bool,
 }
 
 pub enum CmdResult {
     Executed(ExecuteResult),
-    Error(String),
+    Error(PositronicError),
+    /// `restart_shell` failed; the old shell is still dead.
+    RestartFailed(String),
+    /// A directory's frequent commands, for the hint on entering it.
+    DirectoryHint { dir: String, commands: Vec<String> },
+    /// A file `!view` read, or why it couldn't.
+    View(Result<FileView, String>),
+    /// Two outputs lined up for `!compare`, or why they couldn't be.
+    Compare(Result<CompareView, String>),
+    /// The WASM plugins, compiled off the UI thread.
+    Plugins(PluginRegistry),
 }
 
 use std::sync::{LazyLock, Mutex};
@@ -90,6 +411,9 @@ impl PositronicApp {
     pub fn push_direct(&mut self, text: &str) {
         self.direct_output.push_str(text);
         self.direct_output.push('\n');
+        if self.last_snapshot.is_none() {
+            self.viewport.on_new_lines(text.lines().count().max(1));
+        }
 
         if self.direct_output.len() > MAX_DIRECT_BYTES {
             let half = self.direct_output.len() / 2;
@@ -108,24 +432,102 @@ impl PositronicApp {
     }
 
     pub fn poll_redraws(&mut self) -> bool {
-        let mut changed = false;
+        let now = Instant::now();
         if let Some(rx) = &mut self.redraw_rx {
             while rx.try_recv().is_ok() {
-                changed = true;
+                self.output_pending.get_or_insert(now);
             }
         }
+        // Idle, output is batched for a while before it wakes everything.
+        let changed = self
+            .output_pending
+            .is_some_and(|since| now >= since + self.governor.state().snapshot_interval());
+        if changed {
+            self.output_pending = None;
+            self.note_activity();
+        }
 
+        let mut hardware_events = Vec::new();
+        let mut ipc_events = Vec::new();
+        let mut shell_exited = false;
+        let mut bells = 0;
         if changed {
             if let Some(engine) = &self.engine {
+                hardware_events = engine.drain_hardware_events();
+                ipc_events = engine.drain_ipc_events();
+                for event in engine.drain_events() {
+                    match event {
+                        PtyEvent::ShellExited(_) => shell_exited = true,
+                        PtyEvent::Bell => bells += 1,
+                        _ => {}
+                    }
+                }
+
                 // Drain bytes for semantic + mode tracking
-                let bytes = engine.drain_pty_output();
-                if !bytes.is_empty() {
-                    self.mode_tracker.feed(&bytes);
-                    for ev in self.osc_parser.feed(&bytes) {
+                let mut failed = false;
+                let mut finished = false;
+                // The last finished command's lines, for its test summary.
+                let mut finished_lines = None;
+                let chunks = engine.drain_pty_output();
+                let newlines = |chunk: &[u8]| chunk.iter().filter(|&&b| b == b'\n').count();
+                let new_lines: usize = chunks.iter().map(|chunk| newlines(chunk)).sum();
+                // The state machine has taken every chunk already; a mark is
+                // placed at the end of its chunk, counting back from the cursor.
+                let mut line = engine.state.cursor_line().saturating_sub(new_lines);
+                let now = Instant::now();
+                for chunk in chunks {
+                    if let Some(rec) = &mut self.recording {
+                        // Presenting: the recording gets what the screen shows.
+                        let _ = match &mut self.presenting {
+                            Some(redactor) => {
+                                let shown = redactor.redact_text(&String::from_utf8_lossy(&chunk));
+                                rec.output(shown.as_bytes())
+                            }
+                            None => rec.output(&chunk),
+                        };
+                    }
+                    line += newlines(&chunk);
+                    self.mode_tracker.feed(&chunk);
+                    let alt_screen = self.mode_tracker.snapshot().alt_screen;
+                    for ev in self.osc_parser.feed(&chunk) {
+                        match &ev {
+                            OscEvent::CommandStart | OscEvent::CommandExecuted if !alt_screen => {
+                                self.marks.started(line);
+                            }
+                            OscEvent::CommandFinished { exit_code } => {
+                                finished = true;
+                                failed |= exit_code.is_some_and(|code| code != 0);
+                                if !alt_screen {
+                                    finished_lines = self.marks.running_since().map(|start| start..line);
+                                    self.marks.finished(line, *exit_code, now);
+                                }
+                            }
+                            _ => {}
+                        }
                         self.semantic.apply(&ev);
                     }
                 }
 
+                for block in engine.drain_finished_blocks() {
+                    self.sessions.focused_mut().blocks.record(&block);
+                }
+
+                // A probe answer is as good as OSC 7 and holds until the next one.
+                if let Some(cwd) = engine.take_probed_cwd() {
+                    self.semantic.cwd = Some(cwd);
+                }
+
+                // Scrolled back, the view holds still while the lines come in.
+                if !self.mode_tracker.snapshot().alt_screen {
+                    self.viewport.on_new_lines(new_lines);
+                }
+                sync_scrollback(&mut self.viewport, engine);
+                // A resize keeps the view where it was once the grid has it.
+                if let Some(mark) = self.resize.settled(engine.state.size()) {
+                    self.viewport.restore(mark);
+                    engine.state.set_display_offset(self.viewport.offset());
+                }
+
                 // Snapshot for display
                 let snap = engine.state.snapshot();
                 if let Some(cwd) = &self.semantic.cwd {
@@ -133,21 +535,226 @@ impl PositronicApp {
                 } else {
                     update_cwd_from_snapshot(&snap, &mut self.cwd);
                 }
+                self.look_up_directory_hint();
                 self.last_snapshot = Some(snap.clone());
 
+                // Keep typed secrets out of the recording and macros.
+                if self.recording.is_some() || self.macro_recorder.is_some() {
+                    let plain = renderer::snapshot_to_plain(&snap);
+                    let prompt = plain.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("");
+                    self.password_prompt = asciicast::is_password_prompt(prompt);
+                    if let Some(rec) = &mut self.recording {
+                        rec.set_input_hidden(self.password_prompt);
+                    }
+                }
+
                 // Holodeck safe gate: only show overlay at prompt + safe modes
                 self.holodeck_safe = self.semantic.in_prompt && self.mode_tracker.snapshot().intelli_safe();
 
                 // Detect content from what user can see (snapshot → plain)
                 let plain = renderer::snapshot_to_plain(&snap);
-                let rich = detect::detect_rich(&plain);
-                self.holodeck_doc = Some(HolodeckDoc::from_rich(&rich));
+                if failed {
+                    self.hint_missing_package(&plain);
+                }
+                if finished {
+                    let output = finished_lines.map(|lines| engine.state.text_lines(lines).join("\n"));
+                    self.commands_finished(output.unwrap_or_default());
+                }
+                let scored = self.detect_content(&plain);
+                self.holodeck_doc = Some(HolodeckDoc::from_rich(&scored.content));
+                if scored.content.is_structured() && self.sessions.focused().holodeck.latest().is_none_or(|e| e.raw != plain) {
+                    self.detectors.hit(&scored.detector);
+                    self.sessions.focused_mut().holodeck.ingest_rich(scored.content, &plain);
+                }
             }
         }
 
+        self.apply_hardware_events(&hardware_events);
+        self.apply_ipc_events(ipc_events);
+        self.bells.ring(bells, Instant::now());
+        if shell_exited {
+            self.shell_exited();
+        }
+        self.check_recording_limit();
         changed
     }
 
+    fn shell_exited(&mut self) {
+        let Some(exit) = self.engine.as_ref().and_then(|engine| engine.shell_exit()) else {
+            return;
+        };
+        for line in exit.banner_lines() {
+            self.push_direct(&line);
+        }
+        self.running_status = None;
+        self.shell_exit = Some(exit);
+    }
+
+    /// Enter while the shell is dead: start a new one where the old one
+    /// was. Whatever is typed stays in the input for the new shell.
+    fn restart_shell(&mut self) {
+        let Some(engine) = self.engine.clone() else {
+            return;
+        };
+        self.shell_exit = None;
+        let delay = engine.restart_delay();
+        if delay.is_zero() {
+            self.push_direct("🔄 Restarting the shell…");
+        } else {
+            self.push_direct(&format!("🔄 Restarting the shell in {}s…", delay.as_secs_f32().ceil()));
+        }
+        // The old shell may have died mid-sequence.
+        self.mode_tracker = ModeTracker::new();
+        self.osc_parser = OscParser::new();
+        self.semantic = SemanticState::new();
+        self.marks.clear();
+        self.sessions.focused_mut().marks.screen_cleared();
+        self.jump_list.clear();
+
+        let cwd = self.cwd.clone();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn(async move {
+            if let Err(e) = engine.restart_shell(&cwd).await {
+                let _ = tx.send(CmdResult::RestartFailed(format!("{:#}", e))).await;
+            }
+        });
+    }
+
+    /// After a failed command: if the screen says it was not found, offer
+    /// a correction of the line or show which package provides it (see
+    /// `positronic_core::cnf`).
+    fn hint_missing_package(&self, screen: &str) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        // The shell's complaint sits just above the new prompt.
+        let lines: Vec<&str> = screen.lines().filter(|l| !l.trim().is_empty()).collect();
+        let tail = lines[lines.len().saturating_sub(NOT_FOUND_LINES)..].join("\n");
+        let line = self.cmd_history.last().cloned();
+        let engine = engine.clone();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn(async move {
+            if let Some(hint) = engine.runner.not_found_hint(&tail, line.as_deref()).await {
+                let _ = tx.send(CmdResult::Executed(hint)).await;
+            }
+        });
+    }
+
+    /// On moving into a directory for the first time this session, fetch
+    /// its frequent commands off the UI thread (see `dir_hint`).
+    fn look_up_directory_hint(&mut self) {
+        let Some(dir) = self.directory_hints.entered(&self.cwd) else {
+            return;
+        };
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let engine = engine.clone();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn_blocking(move || {
+            let commands = match engine.runner.vault().top_for_directory(&dir, here::HINT_COMMANDS) {
+                Ok(top) => top.into_iter().map(|c| c.command).collect(),
+                Err(e) => {
+                    tracing::debug!("Frequent commands for {} unavailable: {}", dir, e);
+                    Vec::new()
+                }
+            };
+            let _ = tx.blocking_send(CmdResult::DirectoryHint { dir, commands });
+        });
+    }
+
+    /// What the directory hint has to keep out of the way of.
+    fn hint_screen(&self) -> dir_hint::Screen {
+        dir_hint::Screen {
+            fullscreen: self.mode_tracker.snapshot().alt_screen,
+            streaming: self.running_status.is_some() || self.output_pending.is_some(),
+        }
+    }
+
+    /// A command has finished, having printed `output`: summarize it if
+    /// it was a test run, run its `!hook` after-hooks, and the next entry
+    /// of a `!queue` run waiting on it.
+    fn commands_finished(&self, output: String) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let engine = engine.clone();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn(async move {
+            for result in engine.runner.commands_finished(&output).await {
+                let _ = tx.send(CmdResult::Executed(result)).await;
+            }
+        });
+    }
+
+    /// An engine error, its hint, and the reaction its variant calls for:
+    /// a dead shell gets the restart banner, a locked vault the passphrase
+    /// prompt.
+    fn show_error(&mut self, e: PositronicError) {
+        self.report_error(error_center::severity_of(&e), &e.to_string(), e.hint());
+        match e.action() {
+            // The banner says how to restart.
+            ErrorAction::RestartShell if self.shell_exit.is_none() => {
+                self.shell_exited();
+                if self.shell_exit.is_some() {
+                    return;
+                }
+            }
+            ErrorAction::Unlock if e.vault_kind() == Some(VaultErrorKind::Locked) && self.passphrase.is_none() => {
+                self.passphrase = Some(PassphrasePrompt::new(VaultAction::Unlock));
+            }
+            _ => {}
+        }
+    }
+
+    /// Toast a failure that isn't the user's command, and announce it the
+    /// first time it comes (see `error_center`).
+    fn report_error(&mut self, severity: ErrorSeverity, message: &str, hint: Option<String>) {
+        let wall = chrono::Local::now().naive_local();
+        if let Some(event) = self.error_center.report(severity, message, hint, Instant::now(), wall) {
+            self.biolink.announce(event);
+        }
+        self.request_redraw();
+    }
+
+    fn apply_hardware_events(&mut self, events: &[HardwareEvent]) {
+        for event in events {
+            self.hardware.apply(event);
+            if let HardwareEvent::DeviceDisconnected(port) = event
+                && self.scope.as_ref().is_some_and(|view| &view.port == port)
+            {
+                self.scope = None;
+                self.push_direct(&format!("📈 {} disconnected — scope closed", port));
+            }
+            if let Some(exit) = self.console.as_mut().and_then(|console| console.event(event)) {
+                self.close_console(exit);
+            }
+        }
+    }
+
+    /// `notify` and `ingest` from editors and scripts (see `ipc`).
+    fn apply_ipc_events(&mut self, events: Vec<IpcEvent>) {
+        for event in events {
+            match event {
+                IpcEvent::Notify(text) => {
+                    self.push_direct(&format!("🔔 {}", text));
+                    if let Err(e) = platform::notify_desktop("Positronic", &text) {
+                        tracing::warn!("Desktop notification failed: {}", e);
+                    }
+                    self.biolink.announce(BioLinkEvent::Announcement(text));
+                }
+                IpcEvent::Ingest(content) => {
+                    let scored = self.detect_content(&content);
+                    let kind = scored.content.content_type();
+                    self.holodeck_doc = Some(HolodeckDoc::from_rich(&scored.content));
+                    self.detectors.hit(&scored.detector);
+                    self.sessions.focused_mut().holodeck.ingest_rich(scored.content, &content);
+                    self.push_direct(&format!("⚡ Holodeck: {} received over IPC", kind));
+                }
+            }
+        }
+    }
+
     pub fn poll_cmd_results(&mut self) -> bool {
         let mut changed = false;
         while let Ok(result) = self.cmd_result_rx.try_recv() {
@@ -160,22 +767,341 @@ impl PositronicApp {
     fn handle_cmd_result(&mut self, result: CmdResult) {
         match result {
             CmdResult::Executed(exec_result) => self.handle_execute_result(exec_result),
-            CmdResult::Error(e) => self.push_direct(&format!("❌ {}", e)),
+            CmdResult::Error(e) => self.show_error(e),
+            CmdResult::RestartFailed(e) => {
+                self.push_direct(&format!("❌ Shell restart failed: {}", e));
+                self.shell_exit = self.engine.as_ref().and_then(|engine| engine.shell_exit());
+            }
+            CmdResult::DirectoryHint { dir, commands } => self.directory_hints.arrived(&dir, commands),
+            CmdResult::View(Ok(view)) => self.pager = Some(Pager::view(view.lines, view.syntax, self.pager_height())),
+            CmdResult::View(Err(message)) => self.push_direct(&message),
+            CmdResult::Compare(Ok(view)) => self.compare = Some(view),
+            CmdResult::Compare(Err(message)) => self.push_direct(&message),
+            CmdResult::Plugins(registry) => self.install_plugins(registry),
         }
     }
 
     fn handle_execute_result(&mut self, result: ExecuteResult) {
         match result {
             ExecuteResult::SentToPty => {}
-            ExecuteResult::DirectOutput(lines) => self.push_direct(&lines.join("\n")),
+            ExecuteResult::SentToPtyWith(lines) => self.show_direct_lines(lines),
+            ExecuteResult::DirectOutput(lines) => self.show_direct_lines(lines),
+            ExecuteResult::Table(frame) => {
+                let lines = frame.to_lines();
+                if self.holodeck_native {
+                    let rich = RichContent::Table(DataFrame::from_native(&frame));
+                    self.holodeck_doc = Some(HolodeckDoc::from_rich(&rich));
+                    self.sessions.focused_mut().holodeck.ingest_rich(rich, &lines.join("\n"));
+                }
+                self.show_direct_lines(lines);
+            }
+            ExecuteResult::ConfigChanged(lines) => {
+                self.push_direct(&lines.join("\n"));
+                self.reload_settings();
+            }
+            ExecuteResult::Suggestions(list) => {
+                self.suggestions = SuggestionPicker::new(list);
+                match &self.suggestions {
+                    Some(picker) => self.push_direct(&picker.lines().join("\n")),
+                    None => self.push_direct("💡 No suggestions"),
+                }
+            }
+            ExecuteResult::GeneratedCommand(command) => {
+                let danger = DangerAnalyzer::screen_generated(&command);
+                if danger.is_destructive() {
+                    self.push_direct(&format!(
+                        "⚠️ Generated command is destructive ({}) — review before pressing Enter",
+                        danger.reason.unwrap_or("matches a destructive pattern")
+                    ));
+                    self.push_rm_hint(&command);
+                }
+                self.input = command;
+                self.cursor_pos = self.input.chars().count();
+                self.history_cursor = None;
+                self.input_ai_generated = true;
+            }
+            ExecuteResult::EditCommand(command) => {
+                self.input = command;
+                self.cursor_pos = self.input.chars().count();
+                self.history_cursor = None;
+                self.input_ai_generated = false;
+            }
+            ExecuteResult::Http(exchange) => self.show_http(*exchange),
+            ExecuteResult::Fetch(fetched) => self.show_fetch(*fetched),
+            ExecuteResult::Timeline(data) => {
+                let timeline = data.layout(self.screen_cols);
+                self.show_direct_lines(timeline.lines(&data.label));
+                self.timeline = Some(timeline);
+            }
+            ExecuteResult::RedoOffer(offer) => {
+                self.push_direct(&offer.lines().join("\n"));
+                self.redo_offer = Some(offer);
+            }
+            ExecuteResult::Correction(correction) => {
+                let mut lines = correction.lines();
+                lines.push("   Enter puts it in the input line · Esc dismisses".to_string());
+                self.show_direct_lines(lines);
+                self.correction = Some(correction);
+            }
             ExecuteResult::ClearScreen => {
                 self.direct_output.clear();
                 self.last_snapshot = None;
+                self.timeline = None;
             }
             ExecuteResult::Exit => self.wants_exit = true,
         }
     }
 
+    /// Detect `text`'s content with the built-ins and the plugins, and
+    /// toast any plugin the call disabled.
+    fn detect_content(&mut self, text: &str) -> Scored {
+        let scored = self.detectors.detect(text);
+        for (plugin, fault) in self.detectors.take_disabled() {
+            let message = format!("Detector plugin {} disabled for this session: {}", plugin, fault);
+            self.report_error(ErrorSeverity::Warning, &message, Some("!holodeck detectors lists the detectors".to_string()));
+        }
+        scored
+    }
+
+    /// Compile the WASM plugins once the engine's WASM host is up; safe
+    /// mode and inspection start none.
+    fn load_plugins(&self) {
+        let Some(engine) = self.engine.clone() else {
+            return;
+        };
+        if self.safe_mode || self.inspect.is_some() {
+            return;
+        }
+        let dir = self.data.plugins();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn(async move {
+            engine.subsystems().wait_settled().await;
+            let Ok(host) = engine.runner.wasm_host() else {
+                return;
+            };
+            let loaded = tokio::task::spawn_blocking(move || PluginRegistry::load(&host, &dir)).await;
+            if let Ok(registry) = loaded {
+                let _ = tx.send(CmdResult::Plugins(registry)).await;
+            }
+        });
+    }
+
+    fn install_plugins(&mut self, registry: PluginRegistry) {
+        for error in registry.errors() {
+            self.report_error(ErrorSeverity::Warning, &format!("Plugin not loaded: {}", error), None);
+        }
+        let detectors = registry.detector_set();
+        if !detectors.is_empty() {
+            self.push_direct(&format!("🔌 {} detector plugin(s) loaded; !holodeck detectors lists them", detectors.len()));
+        }
+        self.detectors.install(detectors);
+    }
+
+    /// Show an `!http` exchange and keep it in the Holodeck: the body's
+    /// rich form (a JSON tree, a CSV table) opens in the overlay, and the
+    /// whole exchange is the entry's raw text for `!save --format raw`.
+    fn show_http(&mut self, exchange: HttpExchange) {
+        self.show_direct_lines(exchange.lines());
+        let scored = self.detect_content(&exchange.body_text().unwrap_or_default());
+        if scored.content.is_structured() {
+            self.holodeck_doc = Some(HolodeckDoc::from_rich(&scored.content));
+            self.detectors.hit(&scored.detector);
+        }
+        let id = self.sessions.focused_mut().holodeck.ingest_rich(scored.content, &exchange.transcript());
+        self.push_direct(&format!("⚡ Holodeck: entry #{}; !save <path> exports it", id));
+    }
+
+    /// Read a `!fetch` download as `--as` or its `Content-Type` says, else
+    /// as detection finds, and keep it as an entry tagged with its URL.
+    /// Images become image entries; other binary is only reported.
+    fn show_fetch(&mut self, fetched: Fetched) {
+        let mut lines = fetched.lines();
+        let (content, raw) = match &fetched.text {
+            Some(text) => {
+                let hint = fetched.hint();
+                let content = match ContentDetector::parse_as(text, hint) {
+                    Some(content) => content,
+                    None => {
+                        if hint != FetchAs::Auto {
+                            lines.push(format!("⚠️ Not valid {}; reading it as detected instead", hint.label()));
+                        }
+                        let scored = self.detect_content(text);
+                        if scored.content.is_structured() {
+                            self.detectors.hit(&scored.detector);
+                        }
+                        scored.content
+                    }
+                };
+                (content, text.clone())
+            }
+            None if fetched.is_image() => {
+                let media_type = fetched.content_type.as_deref().unwrap_or("image");
+                (RichContent::Image(ImageMeta::fetched(media_type, &fetched.body)), fetched.summary())
+            }
+            None => {
+                lines.push(format!(
+                    "📦 Binary content ({}), not shown; !http GET {} shows its headers",
+                    fetched.content_type.as_deref().unwrap_or("no Content-Type"),
+                    fetched.url
+                ));
+                self.show_direct_lines(lines);
+                return;
+            }
+        };
+        match &content {
+            RichContent::Text(text) => lines.extend(text.lines().map(str::to_string)),
+            content => self.holodeck_doc = Some(HolodeckDoc::from_rich(content)),
+        }
+        let id = self.sessions.focused_mut().holodeck.ingest_rich(content, &raw);
+        self.sessions.focused_mut().holodeck.set_tag(id, SOURCE_TAG, &fetched.url);
+        lines.push(format!("⚡ Holodeck: entry #{} from {}; !save <path> exports it", id, fetched.url));
+        self.show_direct_lines(lines);
+    }
+
+    /// Append a native command's output, paging it when it is longer than
+    /// the threshold allows.
+    pub fn show_direct_lines(&mut self, lines: Vec<String>) {
+        if !self.pager_threshold.should_page(lines.len(), self.screen_rows) {
+            self.push_direct(&lines.join("\n"));
+            return;
+        }
+        self.pager = Some(Pager::new(lines, self.pager_height()));
+    }
+
+    /// Content rows for the pager: the screen minus its footer.
+    fn pager_height(&self) -> usize {
+        self.screen_rows.saturating_sub(1).max(1)
+    }
+
+    pub fn set_screen_size(&mut self, cols: usize, rows: usize) {
+        self.screen_cols = cols;
+        self.screen_rows = rows;
+        let height = self.pager_height();
+        if let Some(pager) = &mut self.pager {
+            pager.set_page_height(height);
+        }
+        if let Some(view) = &mut self.compare {
+            view.set_page_height(height.saturating_sub(1));
+        }
+        if let Some(rec) = &mut self.recording {
+            let _ = rec.resize(cols as u16, rows as u16);
+        }
+    }
+
+    /// Route a key to the open pager; on close, what was read goes into
+    /// the transcript.
+    pub fn pager_key(&mut self, key: PagerKey) {
+        let Some(pager) = &mut self.pager else {
+            return;
+        };
+        if let PagerOutcome::Closed(lines) = pager.handle(key) {
+            self.pager = None;
+            if !lines.is_empty() {
+                self.push_direct(&lines.join("\n"));
+            }
+        }
+    }
+
+    /// Route a key to the open `!compare` view.
+    pub fn compare_key(&mut self, key: PagerKey) {
+        let Some(view) = &mut self.compare else {
+            return;
+        };
+        match view.handle(key) {
+            CompareOutcome::Continue => {}
+            CompareOutcome::Copy(text) => {
+                self.copy_to_clipboard(text);
+            }
+            CompareOutcome::Closed => self.compare = None,
+        }
+    }
+
+    /// Route a key to the open key reference.
+    pub fn key_help_key(&mut self, chord: Option<Chord>, text: Option<&str>) {
+        let Some(help) = &mut self.key_help else {
+            return;
+        };
+        if help.key(chord, text, &self.keymap) == KeyHelpOutcome::Closed {
+            self.key_help = None;
+        }
+    }
+
+    /// Open the file finder on the working directory's project, starting
+    /// (or refreshing) its index.
+    pub fn open_file_finder(&mut self) {
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let root = engine.runner.want_files(Path::new(&self.cwd));
+        self.file_finder = Some(FileFinder::new(&root));
+        self.poll_file_finder();
+    }
+
+    /// Take what the index has found since the finder last looked; true
+    /// if its list changed.
+    pub fn poll_file_finder(&mut self) -> bool {
+        let (Some(finder), Some(engine)) = (&mut self.file_finder, &self.engine) else {
+            return false;
+        };
+        match engine.runner.files().snapshot(&finder.root) {
+            Some(snapshot) => finder.update(&snapshot),
+            None => false,
+        }
+    }
+
+    /// While the finder is open and its index is still being read, look
+    /// again this often.
+    fn file_finder_wake(&self) -> Option<Instant> {
+        let engine = self.engine.as_ref()?;
+        let finder = self.file_finder.as_ref()?;
+        let snapshot = engine.runner.files().snapshot(&finder.root)?;
+        (!snapshot.complete).then(|| Instant::now() + FINDER_POLL)
+    }
+
+    /// Route a key to the open file finder, carrying out the action
+    /// picked for a file.
+    pub fn file_finder_key(&mut self, chord: Option<Chord>, text: Option<&str>) {
+        let Some(finder) = &mut self.file_finder else {
+            return;
+        };
+        match finder.key(chord, text, &self.keymap) {
+            FinderOutcome::Open => {}
+            FinderOutcome::Closed => self.file_finder = None,
+            FinderOutcome::Searched => {
+                if let (Some(prefix), Some(engine)) = (finder.prefix(), &self.engine) {
+                    engine.runner.files().prioritize(&finder.root, prefix);
+                }
+            }
+            FinderOutcome::Chosen(action, path) => {
+                let root = finder.root.clone();
+                self.file_finder = None;
+                match file_finder::dispatch(action, &root, &path, Path::new(&self.cwd)) {
+                    Dispatch::Insert(text) => self.input_insert(&text),
+                    Dispatch::Edit(file) => {
+                        let template = self
+                            .engine
+                            .as_ref()
+                            .and_then(|e| e.runner.vault().get_config(platform::EDITOR_KEY).ok().flatten());
+                        match platform::open_location(template.as_deref(), &file.to_string_lossy(), 1, 1) {
+                            Ok(()) => self.push_direct(&format!("📝 Opening {}", path)),
+                            Err(e) => self.push_direct(&format!("⚠️ Could not open {}: {}", path, e)),
+                        }
+                    }
+                    Dispatch::View(file) => {
+                        let path = Some(file.to_string_lossy().into_owned());
+                        self.open_view(ViewCommand { path, ..ViewCommand::default() });
+                    }
+                    Dispatch::Copy(text) => {
+                        if self.copy_to_clipboard(text) {
+                            self.key_note = Some((format!("📋 Copied {}", path), Instant::now() + KEY_NOTE_DURATION));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     // ----- input editing helpers (keeps events.rs clean) -----
 
     pub fn input_insert(&mut self, c: &str) {
@@ -212,6 +1138,9 @@ impl PositronicApp {
             .unwrap_or(self.input.len());
         self.input.replace_range(byte_pos..next_byte, "");
         self.cursor_pos -= 1;
+        if self.input.is_empty() {
+            self.input_ai_generated = false;
+        }
     }
 
     pub fn input_delete(&mut self) {
@@ -234,6 +1163,21 @@ impl PositronicApp {
         self.input.replace_range(byte_pos..next_byte, "");
     }
 
+    /// Delete the chars in `range` of the input line.
+    pub fn input_kill(&mut self, range: std::ops::Range<usize>) {
+        if self.passphrase.is_some() || range.is_empty() {
+            return;
+        }
+        let byte = |n: usize| self.input.char_indices().nth(n).map(|(i, _)| i).unwrap_or(self.input.len());
+        let bytes = byte(range.start)..byte(range.end);
+        self.input.replace_range(bytes, "");
+        self.cursor_pos = range.start;
+        self.history_cursor = None;
+        if self.input.is_empty() {
+            self.input_ai_generated = false;
+        }
+    }
+
     pub fn input_left(&mut self) {
         if self.cursor_pos > 0 {
             self.cursor_pos -= 1;
@@ -252,133 +1196,2468 @@ impl PositronicApp {
     }
 
     pub fn history_up(&mut self) {
-        if self.cmd_history.is_empty() {
+        if self.passphrase.is_some() {
+            return;
+        }
+        self.block_link = None;
+        if self.input.is_empty()
+            && self.history_cursor.is_none()
+            && let Some(text) = self.pending_draft.take()
+        {
+            self.restore_draft(text);
+            return;
+        }
+        // Typed text with the cursor at its end narrows the browse to
+        // entries starting with it; nothing matching leaves the line be.
+        if self.history_cursor.is_none() {
+            self.history_prefix = None;
+            if !self.input.is_empty() && self.cursor_pos == self.input.chars().count() {
+                let matches = self.prefix_matches(&self.input);
+                if matches.is_empty() {
+                    return;
+                }
+                self.history_prefix = Some(self.input.clone());
+                self.history_matches = matches;
+            }
+        }
+        let entries = self.history_entries();
+        if entries.is_empty() {
             return;
         }
         let new_cursor = match self.history_cursor {
-            None => self.cmd_history.len() - 1,
+            None => entries.len() - 1,
             Some(c) if c > 0 => c - 1,
             Some(c) => c,
         };
+        let entry = entries[new_cursor].clone();
+        if self.history_cursor.is_none() {
+            self.history_stash = std::mem::take(&mut self.input);
+        }
         self.history_cursor = Some(new_cursor);
-        self.input = self.cmd_history[new_cursor].clone();
+        self.input = entry;
         self.cursor_pos = self.input.chars().count();
+        self.input_ai_generated = false;
+    }
+
+    /// What Up and Down step through: the prefix matches while a prefix
+    /// is active, the session history otherwise.
+    fn history_entries(&self) -> &[String] {
+        match self.history_prefix {
+            Some(_) => &self.history_matches,
+            None => &self.cmd_history,
+        }
+    }
+
+    /// Unique entries starting with `prefix` (case-sensitive), other than
+    /// `prefix` itself, oldest first: this session's, then the vault's.
+    fn prefix_matches(&self, prefix: &str) -> Vec<String> {
+        let stored = self
+            .engine
+            .as_ref()
+            .and_then(|e| e.runner.vault().recent_with_prefix(prefix, HISTORY_PREFIX_LIMIT).ok())
+            .unwrap_or_default();
+        let mut newest_first: Vec<String> = Vec::new();
+        for entry in self.cmd_history.iter().rev().chain(&stored) {
+            if entry.starts_with(prefix) && entry != prefix && !newest_first.contains(entry) {
+                newest_first.push(entry.clone());
+            }
+        }
+        newest_first.reverse();
+        newest_first
     }
 
     pub fn history_down(&mut self) {
+        if self.passphrase.is_some() {
+            return;
+        }
+        self.block_link = None;
         if let Some(c) = self.history_cursor {
-            if c + 1 < self.cmd_history.len() {
+            self.input_ai_generated = false;
+            if c + 1 < self.history_entries().len() {
                 let new_cursor = c + 1;
                 self.history_cursor = Some(new_cursor);
-                self.input = self.cmd_history[new_cursor].clone();
+                self.input = self.history_entries()[new_cursor].clone();
                 self.cursor_pos = self.input.chars().count();
             } else {
                 self.history_cursor = None;
-                self.input.clear();
-                self.cursor_pos = 0;
+                self.history_prefix = None;
+                self.input = std::mem::take(&mut self.history_stash);
+                self.cursor_pos = self.input.chars().count();
             }
         }
     }
 
-    // ----- command submit (still uses your Runner path) -----
-
-    pub fn submit_command(&mut self) {
-        let cmd = self.input.trim().to_string();
-        if cmd.is_empty() {
+    /// Tab: cycle the active completion, or start a new one from the
+    /// completer (commands, paths, aliases) with history as the fallback.
+    /// On an empty line, the directory hint's commands are the completions.
+    pub fn complete_input(&mut self) {
+        if self.passphrase.is_some() {
             return;
         }
-
-        self.cmd_history.push(cmd.clone());
-        self.history_cursor = None;
-        self.session_cmd_count += 1;
-
-        self.input.clear();
-        self.cursor_pos = 0;
-
-        match cmd.as_str() {
-            "!pwd" => {
-                self.push_direct(&format!("📂 {}", self.cwd));
-                return;
-            }
-            "!exit" | "!quit" => {
-                self.wants_exit = true;
+        if let Some(state) = &mut self.completion {
+            if state.current() == self.input {
+                self.input = state.next().to_string();
+                self.cursor_pos = self.input.chars().count();
                 return;
             }
-            _ => {}
+        }
+        if self.input.is_empty()
+            && let Some(commands) = self.directory_hints.insertable(self.hint_screen())
+        {
+            let state = CompletionState { original: String::new(), completions: commands.to_vec(), index: 0 };
+            self.input = state.current().to_string();
+            self.cursor_pos = self.input.chars().count();
+            self.completion = Some(state);
+            return;
         }
 
-        track_cd_command(&cmd, &mut self.cwd);
+        let aliases = crate::helpers::get_alias_names_from(self.engine.as_deref());
+        let history = match &self.engine {
+            Some(engine) => engine.suggest(self.input.trim_start(), 16),
+            None => Vec::new(),
+        };
+        let tags = match &self.engine {
+            Some(engine) if self.input.trim_start().starts_with('!') => {
+                engine.runner.vault().tag_names().unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        self.executables.refresh_if_due();
+        let providers = Providers {
+            executables: Some(&self.executables),
+            git: Some(&self.git_completer),
+            tags: &tags,
+            ignore_case: self.completion_ignore_case,
+        };
 
-        if let Some(engine) = &self.engine {
-            let engine = engine.clone();
-            let tx = self.cmd_result_tx.clone();
-            self.rt.spawn(async move {
-                match engine.send_input(&cmd).await {
-                    Ok(result) => {
-                        let _ = tx.send(CmdResult::Executed(result)).await;
-                    }
-                    Err(e) => {
-                        let _ = tx.send(CmdResult::Error(format!("{:#}", e))).await;
-                    }
-                }
-            });
-        } else {
-            self.push_direct("⚠️  Engine not ready yet");
+        self.completion = completer::complete_all(&self.input, &aliases, &self.cwd, &history, providers);
+        if let Some(state) = &self.completion {
+            self.input = state.current().to_string();
+            self.cursor_pos = self.input.chars().count();
         }
     }
 
-    pub fn send_interrupt(&self) {
-        if let Some(engine) = &self.engine {
-            let engine = engine.clone();
-            self.rt.spawn(async move {
-                let _ = engine.send_interrupt().await;
-            });
+    /// Highlighting for the input line. None while a passphrase is typed
+    /// or a fullscreen program has the keyboard.
+    pub fn input_highlights(&mut self) -> Vec<HighlightSpan> {
+        if self.input.is_empty() || self.passphrase.is_some() || !self.mode_tracker.snapshot().intelli_safe() {
+            return Vec::new();
         }
+        let engine = self.engine.as_deref();
+        let is_alias = |name: &str| engine.is_some_and(|e| matches!(e.runner.vault().get_alias(name), Ok(Some(_))));
+        let cwd = std::path::PathBuf::from(&self.cwd);
+        let sources = Sources { executables: Some(&self.executables), is_alias: &is_alias, cwd: &cwd };
+        self.highlighter.highlight(&self.input, &sources, Instant::now()).to_vec()
     }
 
-    pub fn send_escape(&self) {
+    /// The `calc.hint` result for the input line, when it is plain
+    /// arithmetic. Shown only; Enter still sends the line to the shell.
+    /// Dim text at the right of the input bar: which prefix match Up is
+    /// showing, the result of arithmetic (`calc.hint`), a note that the
+    /// line won't reach the history, or on an empty line the directory's
+    /// frequent commands (`hints.directory`).
+    pub fn input_hint(&self) -> Option<String> {
+        if self.passphrase.is_some() || !self.mode_tracker.snapshot().intelli_safe() {
+            return None;
+        }
+        if let (Some(prefix), Some(c)) = (&self.history_prefix, self.history_cursor) {
+            let total = self.history_matches.len();
+            return Some(format!("↑ {}… {}/{}", prefix.trim_end(), total - c, total));
+        }
+        if self.input.is_empty() {
+            return self.directory_hints.line(self.hint_screen());
+        }
+        if self.calc_hint
+            && let Some(hint) = calc::hint(&self.input)
+        {
+            return Some(hint);
+        }
+        self.history_filter
+            .check(&self.input, None)
+            .is_private()
+            .then(|| history_filter::NOT_LOGGED.to_string())
+    }
+
+    // ----- suggestion picker -----
+
+    /// Picker index for a digit key, if a list is open.
+    pub fn suggestion_for_key(&self, key: &str) -> Option<usize> {
+        self.suggestions.as_ref()?.key_index(key)
+    }
+
+    /// Put the chosen suggestion into the input line for editing and close
+    /// the list. Never executes it.
+    pub fn accept_suggestion(&mut self, index: usize) {
+        let Some(item) = self.suggestions.take().and_then(|p| p.get(index).cloned()) else {
+            return;
+        };
+        self.input = item.command.clone();
+        self.cursor_pos = self.input.chars().count();
+        self.history_cursor = None;
+
+        if item.danger.is_destructive() {
+            self.push_direct(&format!(
+                "⚠️ {} — review before pressing Enter",
+                item.danger.reason.unwrap_or("destructive command")
+            ));
+            self.push_rm_hint(&item.command);
+        }
+        if let Some(engine) = &self.engine {
+            if let Err(e) = engine.runner.vault().accept_suggestion(&item.command) {
+                tracing::warn!("Failed to record accepted suggestion: {}", e);
+            }
+        }
+    }
+
+    /// With `danger.suggest_rm` on, offer `!rm` for a destructive `rm`.
+    fn push_rm_hint(&mut self, command: &str) {
+        if let Some(hint) = DangerAnalyzer::rm_hint(command).filter(|_| self.suggest_rm) {
+            self.push_direct(&hint);
+        }
+    }
+
+    /// Put the open correction in the input line, to edit or run.
+    pub fn accept_correction(&mut self) {
+        let Some(correction) = self.correction.take() else {
+            return;
+        };
+        self.input = correction.corrected;
+        self.cursor_pos = self.input.chars().count();
+        self.history_cursor = None;
+        self.input_ai_generated = correction.source == FixSource::Ai;
+    }
+
+    /// Answer the open `!redo` prompt with `key`; false if the key means
+    /// nothing to it.
+    pub fn redo_key(&mut self, key: &str) -> bool {
+        let Some(choice) = self.redo_offer.as_ref().and_then(|offer| offer.choice_for_key(key)) else {
+            return false;
+        };
+        let Some(offer) = self.redo_offer.take() else {
+            return false;
+        };
+        match choice {
+            Some(choice) => self.run_redo(offer, choice),
+            None => self.push_direct("↻ Cancelled"),
+        }
+        true
+    }
+
+    fn run_redo(&mut self, offer: RedoOffer, choice: RedoChoice) {
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let engine = engine.clone();
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn(async move {
+            if choice == RedoChoice::There {
+                let _ = engine.probe_cwd(true).await;
+            }
+            let result = match engine.runner.redo(&offer, choice).await {
+                Ok(result) => CmdResult::Executed(result),
+                Err(e) => CmdResult::Error(e.into()),
+            };
+            let _ = tx.send(result).await;
+        });
+    }
+
+    // ----- command submit (still uses your Runner path) -----
+
+    pub fn submit_command(&mut self) {
+        // A passphrase never reaches the history, the engine or a recording.
+        if self.passphrase.is_some() {
+            let entry = std::mem::take(&mut self.input);
+            self.cursor_pos = 0;
+            self.passphrase_entry(entry);
+            return;
+        }
+
+        if self.safe_mode_asked {
+            let answer = std::mem::take(&mut self.input);
+            self.cursor_pos = 0;
+            self.safe_mode_asked = false;
+            self.safe_mode = safe_mode::accepts(&answer);
+            self.boot_engine();
+            return;
+        }
+
+        let cmd = self.input.trim().to_string();
+        if self.shell_exit.is_some() {
+            if cmd == "!quit" || cmd == "!exit" {
+                self.wants_exit = true;
+            } else {
+                self.restart_shell();
+            }
+            return;
+        }
+        if cmd.is_empty() {
+            return;
+        }
+
+        self.selection = None;
+        let link = self.block_link.take();
+        self.marks.unfocus();
+
+        // Judged as typed; the engine gets the raw line for the same reason.
+        let line = std::mem::take(&mut self.input);
+        if self.history_filter.check(&line, self.cmd_history.last().map(String::as_str)).is_kept() {
+            self.cmd_history.push(cmd.clone());
+        }
+        self.history_cursor = None;
+        self.history_stash.clear();
+        self.session_cmd_count += 1;
+        self.last_command_at = Some(chrono::Utc::now().timestamp());
+        self.scroll(Action::ScrollBottom);
+        if let Some(write) = self.draft.reset() {
+            self.write_draft(write);
+        }
+
+        self.cursor_pos = 0;
+        self.suggestions = None;
+        self.redo_offer = None;
+        self.correction = None;
+        self.directory_hints.dismiss();
+        self.clipboard_picker = None;
+        self.input_ai_generated = false;
+
+        // The engine refuses these too; the window's own `!` commands are
+        // answered here first.
+        if self.inspect.is_some()
+            && let Err(message) = inspect::check_line(&cmd)
+        {
+            self.push_direct(&message);
+            return;
+        }
+        if cmd == "!profile" || cmd.starts_with("!profile ") {
+            self.profile_command(&cmd);
+            return;
+        }
+        if let Some(path) = cmd.strip_prefix("!keys import-inputrc ") {
+            self.import_inputrc(path.trim());
+            return;
+        }
+        if let Some(name) = cmd.strip_prefix("!theme ") {
+            self.set_theme(name.trim());
+            return;
+        }
+        if cmd == "!paste" || cmd.starts_with("!paste ") {
+            self.paste_command(&cmd);
+            return;
+        }
+        if cmd == "!errors" || cmd.starts_with("!errors ") {
+            self.errors_command(cmd["!errors".len()..].trim());
+            return;
+        }
+        if let Some(name) = cmd.strip_prefix("!recall ")
+            && self.recall(name.trim())
+        {
+            return;
+        }
+        if cmd == "!save" || cmd.starts_with("!save ") {
+            self.save_command(&cmd);
+            return;
+        }
+        if cmd == "!chart" || cmd.starts_with("!chart ") {
+            self.chart_command(cmd["!chart".len()..].trim());
+            return;
+        }
+        if cmd == "!view" || cmd.starts_with("!view ") {
+            self.view_command(cmd["!view".len()..].trim());
+            return;
+        }
+        if cmd == "!compare" || cmd.starts_with("!compare ") {
+            self.compare_command(cmd["!compare".len()..].trim());
+            return;
+        }
+        if cmd == "!ver" || cmd.starts_with("!ver ") || cmd == "!version" || cmd.starts_with("!version ") {
+            let args = cmd.split_once(' ').map(|(_, args)| args).unwrap_or("");
+            self.ver_command(args);
+            return;
+        }
+        if cmd == "!holodeck detectors" {
+            let lines = self.detectors.lines(&self.data.plugins());
+            self.show_direct_lines(lines);
+            return;
+        }
+        if let Some(export) = cmd.strip_prefix("!export ").and_then(ScrollbackExport::parse) {
+            let path = std::path::Path::new(&self.cwd).join(&export.path);
+            let line = match std::fs::write(&path, self.sessions.export(export.scope, true)) {
+                Ok(()) => format!("📜 Exported the scrollback to {}", path.display()),
+                Err(e) => format!("❌ Export failed: {}: {}", path.display(), e),
+            };
+            self.push_direct(&line);
+            return;
+        }
+        if cmd == "!holodeck list" || cmd == "!holodeck list --all-panes" {
+            let scope = if cmd.ends_with("--all-panes") { Scope::AllPanes } else { Scope::Focused };
+            let lines = self.sessions.holodeck_list_lines(scope);
+            self.show_direct_lines(lines);
+            return;
+        }
+        // `!chain <id>` walks the vault's rows; bare, the current block's.
+        if cmd == "!chain"
+            && let Some(id) = self.current_block()
+        {
+            self.show_block_chain(id);
+            return;
+        }
+        if cmd == "!record" || cmd.starts_with("!record ") {
+            self.record_command(&cmd);
+            return;
+        }
+        if cmd == "!scope" || cmd.starts_with("!scope ") {
+            self.scope_command(&cmd);
+            return;
+        }
+        if cmd == "!io console" || cmd.starts_with("!io console ") {
+            self.console_command(&cmd);
+            return;
+        }
+        if cmd == "!follow" || cmd.starts_with("!follow ") {
+            self.follow_command(cmd["!follow".len()..].trim());
+            return;
+        }
+        if let Some(sub) = cmd.strip_prefix("!vault ")
+            && let Some(action) = VaultAction::parse(sub)
+        {
+            self.passphrase = Some(PassphrasePrompt::new(action));
+            return;
+        }
+        // Answered by the core; this is the line to look at meanwhile.
+        if cmd == "!vault check" {
+            self.push_direct(recover::CHECK_PROGRESS);
+        }
+        if cmd == "!tldr --update" {
+            self.run_tldr_update();
+            return;
+        }
+        // `!new list` and usage errors are answered by the core.
+        if (cmd == "!new" || cmd.starts_with("!new "))
+            && let Ok(NewCommand::Create(request)) = NewCommand::parse(&cmd["!new".len()..])
+        {
+            self.run_new(request);
+            return;
+        }
+        if cmd == "!bell" || cmd.starts_with("!bell ") {
+            self.bell_command(cmd["!bell".len()..].trim());
+            return;
+        }
+        if cmd == "!present" || cmd.starts_with("!present ") {
+            self.present_command(&cmd["!present".len()..]);
+            return;
+        }
+        if cmd == "!mark" || cmd.starts_with("!mark ") {
+            self.mark_command(cmd["!mark".len()..].trim());
+            return;
+        }
+        if cmd == "!macro" || cmd.starts_with("!macro ") {
+            self.macro_command(cmd["!macro".len()..].trim());
+            return;
+        }
+        if cmd == "!marks" {
+            self.list_marks();
+            return;
+        }
+        if cmd == "!jump" || cmd.starts_with("!jump ") {
+            self.jump_to_mark(cmd["!jump".len()..].trim());
+            return;
+        }
+        if cmd == "!timestamps" || cmd.starts_with("!timestamps ") {
+            self.set_timestamps(cmd["!timestamps".len()..].trim());
+            return;
+        }
+
+        match cmd.as_str() {
+            "!pwd" => {
+                self.push_direct(&format!("📂 {}", self.cwd));
+                // Re-check with the shell; a correction shows in the status bar.
+                if let Some(engine) = &self.engine {
+                    let engine = engine.clone();
+                    self.rt.spawn(async move {
+                        let _ = engine.probe_cwd(false).await;
+                    });
+                }
+                return;
+            }
+            "!exit" | "!quit" => {
+                self.wants_exit = true;
+                return;
+            }
+            "!keys" => {
+                self.push_direct(&self.keymap.lines().join("\n"));
+                return;
+            }
+            "!rehash" => {
+                let count = self.executables.rehash();
+                self.git_completer.clear();
+                self.push_direct(&format!("🔄 Indexed {} executables on PATH", count));
+                return;
+            }
+            "!config reload" => {
+                self.reload_settings();
+                self.push_direct("⚙️  Settings reloaded");
+                return;
+            }
+            "!keys show" => {
+                self.key_help = Some(KeyHelp::default());
+                return;
+            }
+            "!keys import-inputrc" => {
+                self.import_inputrc("");
+                return;
+            }
+            "!keys reload" => {
+                self.reload_settings();
+                self.push_direct(&format!("⌨️  Reloaded {} key bindings", self.keymap.bindings().len()));
+                return;
+            }
+            "!perf overlay" => {
+                self.perf_overlay = !self.perf_overlay;
+                let state = if self.perf_overlay { "on" } else { "off" };
+                self.push_direct(&format!("📈 Perf overlay {}", state));
+                return;
+            }
+            "!perf report" => {
+                match &self.engine {
+                    Some(engine) => {
+                        let mut lines = engine.latency.report_lines();
+                        lines.push("".to_string());
+                        lines.extend(self.governor.report_lines(Instant::now()));
+                        self.show_direct_lines(lines);
+                    }
+                    None => self.push_direct("⚠️  Engine not ready yet"),
+                }
+                return;
+            }
+            "!perf reset" => {
+                if let Some(engine) = &self.engine {
+                    engine.latency.reset();
+                }
+                self.push_direct("📈 Latency samples cleared");
+                return;
+            }
+            _ => {}
+        }
+
+        track_cd_command(&cmd, &mut self.cwd);
+        // `!redo --there` may `cd` first.
+        let probe_after = cwd_is_uncertain(&cmd) || cmd.starts_with("!redo ");
+        let shell_line = !cmd.starts_with('!') && !cmd.starts_with('#');
+        if shell_line {
+            self.record_input(&format!("{}\r", cmd));
+            if let Some(engine) = &self.engine {
+                engine.latency.submitted(Instant::now());
+            }
+            self.marks.sent(&cmd, link);
+        }
+        // The vault row of the block it was edited from: that command's
+        // latest run.
+        let derived_from = link
+            .filter(|_| shell_line)
+            .and_then(|id| self.marks.block(id))
+            .and_then(|block| block.command.clone());
+
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            let tx = self.cmd_result_tx.clone();
+            self.rt.spawn(async move {
+                if probe_after {
+                    let _ = engine.probe_cwd(true).await;
+                }
+                if let Some(origin) = derived_from {
+                    let vault = engine.runner.vault();
+                    if let Ok(Some(id)) = vault.last_run_id(Some(&origin)) {
+                        vault.note_derived(line.trim(), id);
+                    }
+                }
+                match engine.send_input(&line).await {
+                    Ok(result) => {
+                        let _ = tx.send(CmdResult::Executed(result)).await;
+                    }
+                    Err(e) => {
+                        let _ = tx.send(CmdResult::Error(e)).await;
+                    }
+                }
+            });
+        } else {
+            self.push_direct("⚠️  Engine not ready yet");
+        }
+    }
+
+    pub fn send_interrupt(&mut self) {
+        self.record_input("\x03");
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            self.rt.spawn(async move {
+                let _ = engine.send_interrupt().await;
+            });
+        }
+    }
+
+    /// Escape at the prompt: close what is open, else cancel the newest
+    /// `!` command, else send it to the shell (see `EscapeRoute::pick`).
+    pub fn escape(&mut self) {
+        let state = EscapeState {
+            pager: self.pager.is_some(),
+            passphrase: self.passphrase.is_some(),
+            clipboard_picker: self.clipboard_picker.is_some(),
+            redo_offer: self.redo_offer.is_some(),
+            correction: self.correction.is_some(),
+            suggestions: self.suggestions.is_some(),
+            block_focus: self.block_focus_active(),
+            native: self.native_in_flight(),
+        };
+        match EscapeRoute::pick(state) {
+            EscapeRoute::ClosePager => self.pager_key(PagerKey::Escape),
+            EscapeRoute::CancelPassphrase => self.cancel_passphrase(),
+            EscapeRoute::ClosePicker => self.clipboard_picker = None,
+            EscapeRoute::DeclineRedo => {
+                self.redo_key("c");
+            }
+            EscapeRoute::DismissCorrection => self.correction = None,
+            EscapeRoute::CloseSuggestions => self.suggestions = None,
+            EscapeRoute::ClearBlockFocus => self.clear_block_focus(),
+            EscapeRoute::CancelNative => self.cancel_native("Esc"),
+            EscapeRoute::Shell => self.send_escape(),
+        }
+    }
+
+    /// Whether a `!` command is in flight.
+    fn native_in_flight(&self) -> bool {
+        self.engine.as_ref().is_some_and(|e| e.runner.cancels().latest().is_some())
+    }
+
+    /// Cancel the newest `!` command in flight; the runner then prints
+    /// `✖ cancelled after …` in place of its output.
+    fn cancel_native(&mut self, key: &str) {
+        let Some(cancelled) = self.engine.as_ref().and_then(|e| e.runner.cancels().cancel_latest()) else {
+            return;
+        };
+        let note = format!("✖ {}: cancelling {}", key, cancelled.line);
+        self.key_note = Some((note, Instant::now() + KEY_NOTE_DURATION));
+    }
+
+    pub fn send_escape(&mut self) {
+        self.record_input("\x1b");
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            self.rt.spawn(async move {
+                let _ = engine.send_escape().await;
+            });
+        }
+    }
+
+    /// Write `data` to the PTY as if typed, e.g. a key's escape sequence.
+    pub fn send_raw(&mut self, data: &'static str) {
+        self.record_input(data);
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            self.rt.spawn(async move {
+                let _ = engine.send_raw(data).await;
+            });
+        }
+    }
+
+    pub fn send_eof(&mut self) {
+        self.record_input("\x04");
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            self.rt.spawn(async move {
+                let _ = engine.send_eof().await;
+            });
+        }
+    }
+
+    // --- !record ---
+
+    fn record_command(&mut self, cmd: &str) {
+        let args: Vec<&str> = cmd.split_whitespace().skip(1).collect();
+        match RecordCommand::parse(&args) {
+            Ok(RecordCommand::Status) => match &self.recording {
+                Some(rec) => self.push_direct(&format!(
+                    "⏺ Recording to {} ({}, {})",
+                    rec.path().display(),
+                    crate::helpers::format_duration_short(rec.elapsed().as_secs() as i64),
+                    crate::helpers::format_bytes(rec.written())
+                )),
+                None => self.push_direct("⏺ Not recording (!record start [path])"),
+            },
+            Ok(RecordCommand::Start(path)) => self.start_recording(path),
+            Ok(RecordCommand::Stop) if self.recording.is_some() => self.stop_recording(None),
+            Ok(RecordCommand::Stop) => self.push_direct("⏺ Not recording"),
+            Ok(RecordCommand::Play { path, speed }) => self.play_recording(&path, speed),
+            Err(usage) => self.push_direct(&usage),
+        }
+    }
+
+    fn start_recording(&mut self, path: Option<String>) {
+        if let Some(rec) = &self.recording {
+            self.push_direct(&format!("⏺ Already recording to {}", rec.path().display()));
+            return;
+        }
+        // A path is the user's, relative to the shell; without one the
+        // recording goes with the rest of the data.
+        let path = match path {
+            Some(path) => std::path::Path::new(&self.cwd).join(path),
+            None => {
+                let dir = self.data.recordings();
+                if let Err(e) = std::fs::create_dir_all(&dir) {
+                    self.push_direct(&format!("❌ Could not create {}: {}", dir.display(), e));
+                    return;
+                }
+                dir.join(asciicast::default_path(chrono::Local::now()))
+            }
+        };
+        let max = self
+            .engine
+            .as_ref()
+            .and_then(|e| e.runner.vault().get_config(asciicast::MAX_MB_KEY).ok().flatten());
+        match FileRecording::start(&path, self.screen_cols as u16, self.screen_rows as u16, asciicast::max_bytes(max.as_deref())) {
+            Ok(rec) => {
+                self.push_direct(&format!("⏺ Recording to {} (!record stop to finish)", path.display()));
+                self.recording = Some(rec);
+            }
+            Err(e) => self.push_direct(&format!("❌ Could not start recording {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Close the recording; `reason` explains an automatic stop.
+    fn stop_recording(&mut self, reason: Option<&str>) {
+        let Some(rec) = self.recording.take() else {
+            return;
+        };
+        let path = rec.path().display().to_string();
+        let elapsed = crate::helpers::format_duration_short(rec.elapsed().as_secs() as i64);
+        match rec.finish() {
+            Ok(size) => self.push_direct(&format!(
+                "⏹ Saved {} ({}, {}){}",
+                path,
+                elapsed,
+                crate::helpers::format_bytes(size),
+                reason.map(|r| format!(" — {}", r)).unwrap_or_default()
+            )),
+            Err(e) => self.push_direct(&format!("❌ Recording {} ended with an error: {}", path, e)),
+        }
+    }
+
+    fn check_recording_limit(&mut self) {
+        if self.recording.as_ref().is_some_and(|r| r.limit_reached()) {
+            self.stop_recording(Some("reached record.max_mb"));
+        }
+    }
+
+    /// Input sent to the shell, for the recording's "i" events.
+    fn record_input(&mut self, text: &str) {
+        if let Some(rec) = &mut self.recording {
+            let _ = rec.input(text);
+        }
+        self.check_recording_limit();
+    }
+
+    /// Status bar label while recording.
+    pub fn recording_label(&self) -> Option<String> {
+        let rec = self.recording.as_ref()?;
+        Some(format!(
+            "⏺ REC {}",
+            crate::helpers::format_duration_short(rec.elapsed().as_secs() as i64)
+        ))
+    }
+
+    fn play_recording(&mut self, path: &str, speed: f64) {
+        let full = std::path::Path::new(&self.cwd).join(path);
+        let cast = std::fs::read_to_string(&full)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Cast::parse(&text));
+        match cast {
+            Ok(cast) => {
+                self.replay = Some(Replay::new(path, cast, speed));
+                self.replay_started = Instant::now();
+                self.request_redraw();
+            }
+            Err(e) => self.push_direct(&format!("❌ Cannot play {}: {}", full.display(), e)),
+        }
+    }
+
+    /// Advance the replay to now; true while it still has frames to play.
+    pub fn tick_replay(&mut self) -> bool {
+        let started = self.replay_started;
+        match &mut self.replay {
+            Some(replay) => {
+                replay.advance(started.elapsed());
+                !replay.is_done()
+            }
+            None => false,
+        }
+    }
+
+    /// Refresh the running-command timer; true when the status bar needs
+    /// redrawing. A command that finished since the last tick just clears it.
+    pub fn tick_running(&mut self) -> bool {
+        let now = Instant::now();
+        let running = self.engine.as_ref().and_then(|e| e.running_command());
+        self.running_wake = running.as_ref().map(|info| {
+            let into_second = now.saturating_duration_since(info.started_at).subsec_nanos();
+            now + Duration::from_nanos(1_000_000_000 - u64::from(into_second))
+        });
+        let status = running.map(|info| {
+            let slow_threshold = self.span_cache.block_style().slow_threshold;
+            let slow = now.saturating_duration_since(info.started_at) >= slow_threshold;
+            (crate::helpers::running_label(&info, now), slow)
+        });
+        if status.is_none() && self.running_status.is_some() {
+            self.last_command_at = Some(chrono::Utc::now().timestamp());
+        }
+        let changed = status != self.running_status;
+        self.running_status = status;
+        changed
+    }
+
+    /// Refresh the last-command stamp; true when the status bar needs
+    /// redrawing. It wakes only when the text would change.
+    pub fn tick_last_command(&mut self) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        let status = self.last_command_at.map(|ts| {
+            format!("🕘 last {}", time_format::when(ts, now, &chrono::Local, &self.time_settings))
+        });
+        self.last_command_wake = self
+            .last_command_at
+            .and_then(|ts| time_format::refresh_in(ts, now, &chrono::Local, &self.time_settings))
+            .map(|secs| Instant::now() + Duration::from_secs(secs as u64));
+        let changed = status != self.last_command_status;
+        self.last_command_status = status;
+        changed
+    }
+
+    /// Take the engine's progress events: a finished task leaves its
+    /// summary line in the scrollback, running ones are redrawn at the
+    /// reporter's rate.
+    pub fn tick_tasks(&mut self) -> bool {
+        let Some(engine) = &self.engine else {
+            return false;
+        };
+        let (events, active) = (engine.runner.progress().drain(), engine.runner.progress().active());
+        for event in &events {
+            if let ProgressEvent::Finished(finished) = event {
+                self.push_direct(&finished.summary_line());
+            }
+        }
+        let status = (!active.is_empty()).then(|| {
+            active.iter().map(TaskSnapshot::status_entry).collect::<Vec<_>>().join("  ")
+        });
+        self.tasks_wake = status.is_some().then(|| Instant::now() + progress::UPDATE_INTERVAL);
+        let changed = !events.is_empty() || status != self.tasks_status;
+        self.tasks_status = status;
+        changed
+    }
+
+    /// Stop the maintenance worker, close this instance's vault session
+    /// so other instances stop counting it as active, export to
+    /// `sync.auto_export`, and save or drop the clipboard history.
+    pub fn end_session(&mut self) {
+        self.close_console(ConsoleExit::User);
+        if let Some(worker) = self.engine.as_ref().and_then(|engine| engine.runner.maintenance()) {
+            worker.shutdown();
+        }
+        if let Some(write) = self.draft.reset() {
+            self.write_draft(write);
+        }
+        // An inspected vault has no session, and its `sync.auto_export`
+        // is someone else's folder.
+        if let Some(engine) = &self.engine
+            && !engine.runner.vault().is_inspect()
+        {
+            engine.stop_ipc();
+            if let Err(e) = sync::auto_export(engine.runner.vault()) {
+                tracing::warn!("Sync auto-export failed: {:#}", e);
+            }
+            match summary::save_on_close(engine.runner.summary_sources(), &self.summary_settings) {
+                Ok(Some(path)) => tracing::info!("Session summary saved to {}", path.display()),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Saving the session summary failed: {:#}", e),
+            }
+            if let Err(e) = engine.runner.vault().close_session() {
+                tracing::warn!("Closing the vault session failed: {}", e);
+            }
+        }
+        if self.clipboard_history.settings().persist {
+            let path = self.data.file(clipboard_history::PERSIST_FILE);
+            if let Err(e) = self.clipboard_history.save(&path) {
+                tracing::warn!("Saving the clipboard history failed: {}", e);
+            }
+        }
+        self.clipboard_history.clear();
+    }
+
+    // --- !vault passphrases ---
+
+    fn passphrase_entry(&mut self, entry: String) {
+        let Some(prompt) = &mut self.passphrase else {
+            return;
+        };
+        match prompt.submit(entry) {
+            PromptStep::Confirm => {}
+            PromptStep::Mismatch => self.push_direct("🔐 Passphrases don't match; enter it again"),
+            PromptStep::Ready { passphrase, confirm } => {
+                let action = prompt.action();
+                self.passphrase = None;
+                self.run_vault_action(action, passphrase, confirm);
+            }
+        }
+    }
+
+    pub fn cancel_passphrase(&mut self) {
+        if let Some(prompt) = self.passphrase.take() {
+            self.input.clear();
+            self.cursor_pos = 0;
+            self.push_direct(&format!("🔐 !vault {} cancelled", prompt.action().name()));
+        }
+    }
+
+    /// Unlock, encrypt or decrypt off the UI thread; a migration reports
+    /// each batch as it commits.
+    fn run_vault_action(&mut self, action: VaultAction, passphrase: String, confirm: String) {
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let engine = engine.clone();
+        let tx = self.cmd_result_tx.clone();
+        let progress_tx = tx.clone();
+        let progress = move |done: usize, total: usize| {
+            let line = format!("  {} {}/{} rows", action.name(), done, total);
+            let _ = progress_tx.blocking_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+        };
+        self.rt.spawn_blocking(move || {
+            let vault = engine.runner.vault();
+            let result = match action {
+                VaultAction::Unlock => engine.runner.unlock_vault(&passphrase).map(|_| "🔓 Vault unlocked".to_string()),
+                VaultAction::Encrypt => vault
+                    .encrypt_history(&passphrase, &confirm, progress)
+                    .map(|n| format!("🔐 Vault encrypted ({} rows rewritten)", n)),
+                VaultAction::Decrypt => vault
+                    .decrypt_history(&passphrase, &confirm, progress)
+                    .map(|n| format!("🔓 Vault decrypted ({} rows rewritten); history is plain text again", n)),
+            };
+            let line = result.unwrap_or_else(|e| format!("❌ !vault {}: {}", action.name(), e));
+            let _ = tx.blocking_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+        });
+    }
+
+    /// The session's first Ctrl+R: a search index the maintenance worker
+    /// hasn't finished is built now, past its boot and activity gates,
+    /// with a progress line every tenth.
+    fn warm_search_index(&mut self) {
+        if std::mem::replace(&mut self.search_warmed, true) {
+            return;
+        }
+        let Some(engine) = self.engine.clone() else {
+            return;
+        };
+        let stale = engine
+            .runner
+            .maintenance()
+            .and_then(|worker| worker.status(maintenance::FTS_TASK))
+            .is_some_and(|status| status.freshness == Freshness::Stale);
+        if !stale {
+            return;
+        }
+        let tx = self.cmd_result_tx.clone();
+        self.rt.spawn_blocking(move || {
+            let Some(worker) = engine.runner.maintenance() else {
+                return;
+            };
+            worker.trigger(maintenance::FTS_TASK);
+            let send = |line: String| {
+                let _ = tx.blocking_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+            };
+            let mut shown = None;
+            let last = worker.wait(maintenance::FTS_TASK, |status| {
+                if let TaskState::Running { done, total } = status.state
+                    && total > 0
+                    && shown != Some(done * 10 / total)
+                {
+                    shown = Some(done * 10 / total);
+                    send(format!("  🔎 Building the search index: {}/{} commands", done, total));
+                }
+            });
+            if let Some(status) = last
+                && shown.is_some()
+            {
+                send(format!("  🔎 Search index: {}", status.describe(Instant::now())));
+            }
+        });
+    }
+
+    /// Refresh the tldr pages off the UI thread, one line per step.
+    fn run_tldr_update(&mut self) {
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let tldr = engine.runner.tldr();
+        let tx = self.cmd_result_tx.clone();
+        let progress_tx = tx.clone();
+        let progress = move |line: String| {
+            let _ = progress_tx.try_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+        };
+        self.rt.spawn(async move {
+            let report = tldr.update_report(progress).await;
+            let _ = tx.send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![report]))).await;
+        });
+    }
+
+    /// Run a scaffold off the UI thread, one block per step as it goes.
+    fn run_new(&mut self, request: NewRequest) {
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let runner = engine.runner.clone();
+        let tx = self.cmd_result_tx.clone();
+        let progress_tx = tx.clone();
+        let progress = move |block: Vec<String>| {
+            let _ = progress_tx.try_send(CmdResult::Executed(ExecuteResult::DirectOutput(block)));
+        };
+        self.rt.spawn(async move {
+            let done = runner.new_project(&request, progress).await;
+            let _ = tx.send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![done]))).await;
+        });
+    }
+
+    // --- Holodeck actions ---
+
+    pub fn apply_holodeck_action(&mut self, action: HolodeckAction) {
+        match action {
+            HolodeckAction::CopyText(s) => {
+                if self.copy_to_clipboard(s) {
+                    self.push_direct("⚡ Holodeck: Copied to clipboard");
+                }
+            }
+            HolodeckAction::RunCommand(cmd) => {
+                self.input = cmd;
+                self.cursor_pos = self.input.chars().count();
+            }
+            HolodeckAction::Save(format) => {
+                let line = self.save_line(None, None, Some(format), false);
+                self.push_direct(&line);
+            }
+            HolodeckAction::None => {}
+        }
+    }
+
+    // --- !scope ---
+
+    fn scope_command(&mut self, cmd: &str) {
+        match ScopeCommand::parse(cmd) {
+            Ok(ScopeCommand::Toggle) => self.toggle_scope(),
+            Ok(ScopeCommand::Off) => {
+                if self.scope.take().is_none() {
+                    self.push_direct("📈 Scope is not open");
+                }
+            }
+            Ok(ScopeCommand::Open { port, range, window }) => {
+                let port = port.or_else(|| self.scope.as_ref().map(|v| v.port.clone()));
+                let Some(port) = port.or_else(|| self.hardware.default_port().map(str::to_string)) else {
+                    self.push_direct("📈 No device to scope (!io connect <port> first)");
+                    return;
+                };
+                let mut view = match self.scope.take() {
+                    Some(view) if view.port == port => view,
+                    _ => ScopeView::new(port),
+                };
+                if let Some(range) = range {
+                    view.range = range;
+                }
+                if let Some(window) = window {
+                    view.window = window;
+                }
+                self.scope = Some(view);
+            }
+            Err(usage) => self.push_direct(&usage),
+        }
+    }
+
+    /// Close the pane, or open it on the default port.
+    pub fn toggle_scope(&mut self) {
+        if self.scope.take().is_some() {
+            return;
+        }
+        match self.hardware.default_port() {
+            Some(port) => self.scope = Some(ScopeView::new(port)),
+            None => self.push_direct("📈 No device to scope (!io connect <port> first)"),
+        }
+    }
+
+    /// Zoom the open pane for `+` or `-`; false for other keys.
+    pub fn scope_key(&mut self, key: &str) -> bool {
+        let Some(view) = &mut self.scope else {
+            return false;
+        };
+        match key {
+            "+" | "=" => view.zoom_in(),
+            "-" => view.zoom_out(),
+            _ => return false,
+        }
+        true
+    }
+
+    // --- !follow ---
+
+    fn follow_command(&mut self, command: &str) {
+        if command.is_empty() {
+            self.push_direct(FOLLOW_USAGE);
+            return;
+        }
+        self.close_follow();
+        let window = self.window.clone();
+        let wake = move || {
+            if let Some(window) = &window {
+                window.request_redraw();
+            }
+        };
+        let env = self.engine.as_ref().map(|engine| engine.runner.env_overlay()).unwrap_or_default();
+        let rt = self.rt.clone();
+        let _runtime = rt.enter();
+        match FollowProcess::spawn(command, &self.cwd, &env, wake) {
+            Ok(process) => {
+                self.follow = Some(FollowState::new(command));
+                self.follow_process = Some(process);
+            }
+            Err(e) => self.push_direct(&format!("❌ !follow: {:#}", e)),
+        }
+    }
+
+    /// Take the followed command's new output; true if there was any.
+    fn poll_follow(&mut self) -> bool {
+        let (Some(follow), Some((_, rx))) = (&mut self.follow, &mut self.follow_process) else {
+            return false;
+        };
+        let mut changed = false;
+        while let Ok(event) = rx.try_recv() {
+            changed = true;
+            match event {
+                FollowEvent::Line(line) => follow.push(&line),
+                FollowEvent::Usage(usage) => follow.usage = Some(usage),
+                FollowEvent::Exited(code) => follow.exited(code),
+            }
+        }
+        changed
+    }
+
+    /// Close the pane, stopping the command if it still runs.
+    pub fn close_follow(&mut self) {
+        self.follow_process = None;
+        let Some(follow) = self.follow.take() else {
+            return;
+        };
+        let mut notice = format!("📜 Stopped following {} ({} lines", follow.command, follow.received());
+        if follow.dropped() > 0 {
+            notice.push_str(&format!(", {} dropped from the pane", follow.dropped()));
+        }
+        if let Some(usage) = follow.usage {
+            notice.push_str(&format!(", {}", usage.badge()));
+        }
+        notice.push(')');
+        self.push_direct(&notice);
+    }
+
+    /// A key while the follow pane is open (see `FollowState::key`).
+    pub fn follow_key(&mut self, chord: Option<Chord>, text: Option<&str>) {
+        let Some(follow) = &mut self.follow else {
+            return;
+        };
+        match follow.key(chord, text, &self.keymap) {
+            FollowKey::Stop => {
+                follow.phase = FollowPhase::Stopping;
+                if let Some((process, _)) = &self.follow_process {
+                    process.stop();
+                }
+            }
+            FollowKey::Close => self.close_follow(),
+            FollowKey::Handled | FollowKey::Ignored => {}
+        }
+    }
+
+    // --- !io console ---
+
+    fn console_command(&mut self, cmd: &str) {
+        let command = match ConsoleCommand::parse(cmd) {
+            Ok(command) => command,
+            Err(usage) => {
+                self.push_direct(&usage);
+                return;
+            }
+        };
+        let Some(engine) = self.engine.clone() else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let io = match engine.runner.io() {
+            Ok(io) => io,
+            Err(e) => {
+                self.push_direct(&format!("❌ Hardware IO unavailable: {}", e));
+                return;
+            }
+        };
+        let vault = engine.runner.vault();
+        let devices = vault.list_devices().unwrap_or_default();
+        let resolved = match serial::resolve_port(&devices, &device::system_ports(), &command.port) {
+            Ok(resolved) => resolved,
+            Err(message) => {
+                self.push_direct(&message);
+                return;
+            }
+        };
+        if let Some(id) = resolved.device.as_ref().map(|d| d.to_string()) {
+            let product = resolved.record.as_ref().and_then(|r| r.product.as_deref());
+            let saved = vault
+                .record_device(&id, &resolved.port, product)
+                .and_then(|_| vault.set_device_defaults(&id, &command.given()));
+            if let Err(e) = saved {
+                tracing::warn!("Saving the settings of {} failed: {}", id, e);
+            }
+        }
+        if let Some(note) = resolved.ambiguity() {
+            self.push_direct(&note);
+        }
+        let saved = resolved.record.as_ref().map(|r| r.defaults.clone()).unwrap_or_default();
+        let name = resolved.name().map(str::to_string);
+        let command = ConsoleCommand { port: resolved.port.clone(), name, ..command }.with_defaults(&saved);
+        self.close_console(ConsoleExit::User);
+        // A port the console opens itself is raw: no parser takes lines away.
+        let connected = io.is_writable(&command.port);
+        if !connected {
+            let (port, baud) = (command.port.clone(), command.baud.unwrap_or(serial::DEFAULT_BAUD));
+            self.rt.spawn(async move {
+                let _ = io.connect(&port, baud).await;
+            });
+        }
+        engine.attach_console(&command.port);
+        self.console = Some(Console::new(&command, connected, chrono::Utc::now().timestamp()));
+    }
+
+    /// Back to the shell: the port's text is echoed there again and the
+    /// session goes to the vault's device history.
+    pub fn close_console(&mut self, exit: ConsoleExit) {
+        let Some(console) = self.console.take() else {
+            return;
+        };
+        if let Some(engine) = &self.engine {
+            engine.detach_console(&console.port);
+            if let Err(e) = engine.runner.vault().log_device_session(&console.record(chrono::Utc::now().timestamp())) {
+                tracing::warn!("Logging the {} console session failed: {}", console.port, e);
+            }
+        }
+        self.push_direct(&console.closed_notice(&exit));
+    }
+
+    /// A key while the console is open (see `Console::key`).
+    pub fn console_key(&mut self, chord: Option<Chord>, text: Option<&str>) {
+        let page = self.pager_height();
+        let Some(console) = &mut self.console else {
+            return;
+        };
+        match console.key(chord, text, &self.keymap, page) {
+            ConsoleKey::Send(bytes) => self.console_send(&bytes),
+            ConsoleKey::Exit => self.close_console(ConsoleExit::User),
+            ConsoleKey::Copy => {
+                let text = console.copy_text();
+                let lines = text.lines().count();
+                if self.copy_to_clipboard(text)
+                    && let Some(console) = &mut self.console
+                {
+                    console.notice(&format!("⚡ Copied {} lines to clipboard", lines));
+                }
+            }
+            // Pasted line breaks become the console's line ending.
+            ConsoleKey::Paste => match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+                Ok(text) => {
+                    let eol = String::from_utf8_lossy(console.eol.bytes()).into_owned();
+                    let text = text.replace("\r\n", "\n").replace('\n', &eol);
+                    self.console_send(text.as_bytes());
+                }
+                Err(e) => console.notice(&format!("⚠️ Paste failed: {}", e)),
+            },
+            ConsoleKey::Handled | ConsoleKey::Ignored => {}
+        }
+    }
+
+    fn console_send(&mut self, bytes: &[u8]) {
+        let Some(console) = &mut self.console else {
+            return;
+        };
+        let written = match &self.engine {
+            Some(engine) => engine
+                .runner
+                .io()
+                .and_then(|io| io.write(&console.port, bytes).map_err(PositronicError::from)),
+            None => Err(PositronicError::Other("engine not ready".to_string())),
+        };
+        match written {
+            Ok(()) => console.sent(bytes),
+            Err(e) => console.notice(&format!("⚠️ Write failed: {}", e)),
+        }
+    }
+
+    // --- !recall ---
+
+    /// Show a tagged output again, and give a table or JSON in it back to
+    /// Holodeck as if it had just been on screen. False when the core
+    /// should answer instead (usage, locked vault, unknown tag).
+    fn recall(&mut self, name: &str) -> bool {
+        let tag = match &self.engine {
+            Some(engine) => engine.runner.vault().get_tag(name).ok().flatten(),
+            None => None,
+        };
+        let Some(tag) = tag else {
+            return false;
+        };
+        self.show_direct_lines(tags::recall_lines(&tag, &self.time_settings));
+        let scored = self.detect_content(&tag.output);
+        if scored.content.is_structured() {
+            self.holodeck_doc = Some(HolodeckDoc::from_rich(&scored.content));
+            self.detectors.hit(&scored.detector);
+            let id = self.sessions.focused_mut().holodeck.ingest_rich(scored.content, &tag.output);
+            self.push_direct(&format!("⚡ Holodeck: recalled as entry #{}; !save <path> exports it", id));
+        }
+        true
+    }
+
+    // --- !chart ---
+
+    /// Plot entry `--entry` (or the latest table) in the output and the
+    /// Holodeck.
+    fn chart_command(&mut self, args: &str) {
+        let spec = match ChartCommand::parse(args).and_then(|command| self.chart_spec(command)) {
+            Ok(spec) => spec,
+            Err(message) => {
+                self.push_direct(&message);
+                return;
+            }
+        };
+        let width = self.screen_cols.saturating_sub(16).clamp(20, 100);
+        let text = chart::render_text(&spec, width, 12).join("\n");
+        self.push_direct(&text);
+        let rich = RichContent::Chart(spec);
+        self.holodeck_doc = Some(HolodeckDoc::from_rich(&rich));
+        self.sessions.focused_mut().holodeck.ingest_rich(rich, &text);
+    }
+
+    /// `!ver`: this build and what it is running with, as lines or, with
+    /// `--json`, one object for a bug report.
+    fn ver_command(&mut self, args: &str) {
+        let json = match positronic_core::version::parse(args) {
+            Ok(json) => json,
+            Err(usage) => {
+                self.push_direct(&usage);
+                return;
+            }
+        };
+        let report = crate::version::Sources {
+            data: &self.data,
+            runner: self.engine.as_ref().map(|engine| engine.runner.as_ref()),
+            renderer: self.render_report.as_ref().map(|report| report.path.summary()),
+        }
+        .report();
+        let lines = if json {
+            serde_json::to_string_pretty(&report.to_json()).unwrap_or_default().lines().map(str::to_string).collect()
+        } else {
+            report.lines()
+        };
+        self.show_direct_lines(lines);
+    }
+
+    /// `!view`: read the file off the UI thread and open it in the pager.
+    /// Without a path, the last file viewed is read again (with the new
+    /// options, if any).
+    fn view_command(&mut self, args: &str) {
+        match ViewCommand::parse(args) {
+            Ok(command) => self.open_view(command),
+            Err(message) => self.push_direct(&message),
+        }
+    }
+
+    /// Read the file `command` names (or the last one viewed) off the UI
+    /// thread and page it.
+    fn open_view(&mut self, mut command: ViewCommand) {
+        let path = match (&command.path, &self.last_view) {
+            (Some(path), _) => viewer::resolve(path, std::path::Path::new(&self.cwd)),
+            (None, Some(last)) => PathBuf::from(last.path.as_deref().unwrap_or_default()),
+            (None, None) => {
+                self.push_direct(&format!("📄 Nothing viewed yet. {}", viewer::VIEW_USAGE));
+                return;
+            }
+        };
+        command.path = Some(path.to_string_lossy().into_owned());
+        self.last_view = Some(command.clone());
+
+        let tx = self.cmd_result_tx.clone();
+        let progress_tx = tx.clone();
+        let name = path.display().to_string();
+        let progress = move |percent: u64| {
+            let line = format!("  📄 Reading {}… {}%", name, percent);
+            let _ = progress_tx.blocking_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+        };
+        self.rt.spawn_blocking(move || {
+            let _ = tx.blocking_send(CmdResult::View(viewer::load(&path, &command, progress)));
+        });
+    }
+
+    /// `!compare`: read both outputs from the vault off the UI thread and
+    /// open them side by side. The header row comes out of the pager's
+    /// height.
+    fn compare_command(&mut self, args: &str) {
+        let command = match CompareCommand::parse(args) {
+            Ok(command) => command,
+            Err(usage) => {
+                self.push_direct(&usage);
+                return;
+            }
+        };
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let vault = engine.runner.vault().clone();
+        let page_height = self.pager_height().saturating_sub(1);
+        let tx = self.cmd_result_tx.clone();
+        let progress_tx = tx.clone();
+        let progress = move |line: String| {
+            let _ = progress_tx.blocking_send(CmdResult::Executed(ExecuteResult::DirectOutput(vec![line])));
+        };
+        self.rt.spawn_blocking(move || {
+            let _ = tx.blocking_send(CmdResult::Compare(compare::load(&vault, &command, page_height, progress)));
+        });
+    }
+
+    fn chart_spec(&self, command: ChartCommand) -> Result<ChartSpec, String> {
+        let entry = match command.entry {
+            Some(id) => self.sessions.focused().holodeck.get(id).ok_or_else(|| format!("❌ No Holodeck entry #{}", id))?,
+            None => self
+                .sessions
+                .focused()
+                .holodeck
+                .table_entries()
+                .last()
+                .copied()
+                .ok_or("📊 Nothing to chart yet: no table has been on screen")?,
+        };
+        match &entry.content {
+            RichContent::Table(df) => ChartSpec::from_dataframe(df, &command.options),
+            _ => Err(format!("❌ Entry #{} is {}, not a table", entry.id, entry.content_type)),
+        }
+    }
+
+    // --- !save ---
+
+    fn save_command(&mut self, cmd: &str) {
+        let line = match SaveCommand::parse(cmd) {
+            Ok(save) => self.save_line(save.entry, Some(&save.path), save.format, save.force),
+            Err(usage) => usage,
+        };
+        self.push_direct(&line);
+    }
+
+    /// Write entry `id` (or the latest) to `path` relative to the tracked
+    /// CWD, or to a generated name; the result is a line for the user.
+    fn save_line(&mut self, id: Option<u64>, path: Option<&str>, format: Option<SaveFormat>, force: bool) -> String {
+        let entry = match id {
+            Some(id) => self.sessions.focused().holodeck.get(id),
+            None => self.sessions.focused().holodeck.latest(),
+        };
+        let Some(entry) = entry else {
+            return match id {
+                Some(id) => format!("❌ No Holodeck entry #{}", id),
+                None => "💾 Nothing to save yet: no table or JSON has been on screen".to_string(),
+            };
+        };
+        let format = format
+            .or_else(|| path.and_then(SaveFormat::from_path))
+            .unwrap_or_else(|| SaveFormat::default_for(entry));
+        let name = path
+            .map(str::to_string)
+            .unwrap_or_else(|| export::generated_name(entry, format, chrono::Local::now()));
+        let cwd = std::path::Path::new(&self.cwd);
+        let saved = match &mut self.presenting {
+            Some(redactor) => export::render(entry, format)
+                .and_then(|body| export::write(&redactor.redact_text(&body), &name, force, cwd)),
+            None => export::save(entry, &name, format, force, cwd),
+        };
+        match saved {
+            Ok((path, bytes)) => {
+                let mut line = format!(
+                    "💾 Saved entry #{} as {} to {} ({})",
+                    entry.id,
+                    format.name(),
+                    path.display(),
+                    crate::helpers::format_bytes(bytes as u64)
+                );
+                if let Some(source) = entry.source() {
+                    line.push_str(&format!(" · from {}", source));
+                }
+                line
+            }
+            Err(e) => e,
+        }
+    }
+
+    // ----- keymap actions -----
+
+    pub fn run_action(&mut self, action: Action) {
+        match action {
+            Action::ClearScreen => {
+                self.direct_output.clear();
+                self.last_snapshot = None;
+                self.viewport = Viewport::default();
+                self.selection = None;
+            }
+            Action::Copy => {
+                if !self.copy_selection() {
+                    self.copy_visible_to_clipboard();
+                }
+            }
+            Action::CopyAnsi => self.copy_visible_ansi_to_clipboard(),
+            Action::Paste => self.paste_or_pick(),
+            Action::Interrupt => self.interrupt_or_copy(),
+            Action::Eof => self.send_eof(),
+            Action::HistoryUp => self.history_up(),
+            Action::HistoryDown => self.history_down(),
+            Action::TabComplete => self.complete_input(),
+            Action::CursorLeft => self.input_left(),
+            Action::CursorRight => self.input_right(),
+            Action::LineStart => self.input_home(),
+            Action::LineEnd => self.input_end(),
+            Action::WordLeft => self.cursor_pos = crate::helpers::word_left(&self.input, self.cursor_pos),
+            Action::WordRight => self.cursor_pos = crate::helpers::word_right(&self.input, self.cursor_pos),
+            Action::KillWordBack => self.input_kill(crate::helpers::word_left(&self.input, self.cursor_pos)..self.cursor_pos),
+            Action::KillWordForward => {
+                self.input_kill(self.cursor_pos..crate::helpers::word_right(&self.input, self.cursor_pos))
+            }
+            Action::KillToStart => self.input_kill(0..self.cursor_pos),
+            Action::KillToEnd => self.input_kill(self.cursor_pos..self.input.chars().count()),
+            Action::PaletteOpen => {
+                // The `!` command list, cycled with Tab.
+                self.input = "!".to_string();
+                self.cursor_pos = 1;
+                self.completion = None;
+                self.complete_input();
+            }
+            Action::SearchOpen => {
+                self.input = "!search ".to_string();
+                self.cursor_pos = self.input.chars().count();
+                self.warm_search_index();
+            }
+            Action::FollowLink => self.follow_last_link(),
+            Action::JumpToError => self.jump_to_error(),
+            Action::ToggleScope => self.toggle_scope(),
+            Action::ConsoleExit => self.close_console(ConsoleExit::User),
+            Action::ScrollPageUp | Action::ScrollPageDown | Action::ScrollTop | Action::ScrollBottom => {
+                self.scroll(action)
+            }
+            Action::BlockPrev | Action::BlockNext => self.focus_block(action),
+            Action::JumpBack | Action::JumpForward => self.walk_jumps(action),
+            Action::EditBlockCommand | Action::RerunBlockCommand => match self.current_block() {
+                Some(id) => self.edit_block(id, action == Action::RerunBlockCommand),
+                None => self.push_direct("⛓ No command block on screen yet"),
+            },
+            Action::KeyHelp => {
+                self.key_help = match self.key_help {
+                    Some(_) => None,
+                    None => Some(KeyHelp::default()),
+                }
+            }
+            Action::FileFinder => match self.file_finder {
+                Some(_) => self.file_finder = None,
+                None => self.open_file_finder(),
+            },
+        }
+        self.request_redraw();
+    }
+
+    /// Keyboard scrolling: over the PTY scrollback once the shell has
+    /// drawn, over native output before that.
+    pub fn scroll(&mut self, action: Action) {
+        if self.last_snapshot.is_none() {
+            self.viewport.set_extent(self.direct_output.lines().count(), self.screen_rows);
+        }
+        match action {
+            Action::ScrollPageUp => self.viewport.page_up(),
+            Action::ScrollPageDown => self.viewport.page_down(),
+            Action::ScrollTop => self.viewport.top(),
+            _ => self.viewport.bottom(),
+        }
+        if self.last_snapshot.is_some()
+            && let Some(engine) = &self.engine
+        {
+            sync_scrollback(&mut self.viewport, engine);
+            self.last_snapshot = Some(engine.state.snapshot());
+        }
+    }
+
+    // ----- macros -----
+
+    /// Note `step` for the macro being recorded, as the user takes it.
+    /// What a password prompt reads is left out.
+    pub fn record_step(&mut self, step: Step) {
+        let secret = self.passphrase.is_some() || self.password_prompt;
+        if let Some(recorder) = &mut self.macro_recorder {
+            recorder.record(step, secret);
+        }
+    }
+
+    /// Take a played step the way the keys behind it would have been.
+    fn apply_step(&mut self, step: Step) {
+        match step {
+            Step::Insert(text) => {
+                self.clipboard_picker = None;
+                self.input_insert(&text);
+            }
+            Step::Backspace => self.input_backspace(),
+            Step::Delete => self.input_delete(),
+            Step::Action(action) => self.run_action(action),
+            Step::Submit => self.submit_command(),
+        }
+    }
+
+    /// Play the macro steps that are due; true if any ran. A running
+    /// command, a `!` command in flight or an open prompt holds playback.
+    pub fn tick_macro(&mut self) -> bool {
+        let Some(mut player) = self.macro_player.take() else {
+            return false;
+        };
+        let mut played = false;
+        loop {
+            let busy = self.engine.as_ref().is_some_and(|e| e.running_command().is_some())
+                || self.native_in_flight()
+                || self.passphrase.is_some()
+                || self.pager.is_some();
+            match player.tick(Instant::now(), busy) {
+                Tick::Step(step) => {
+                    self.apply_step(step);
+                    played = true;
+                }
+                Tick::Wait(_) | Tick::Held(_) => break,
+                Tick::Done => {
+                    self.key_note = Some((format!("⌨ Played macro {}", player.name()), Instant::now() + KEY_NOTE_DURATION));
+                    return true;
+                }
+            }
+        }
+        self.macro_player = Some(player);
+        played
+    }
+
+    /// Escape while a macro plays: stop before its next step.
+    pub fn stop_macro(&mut self) {
+        if let Some(player) = self.macro_player.take() {
+            self.key_note = Some((format!("⌨ Macro stopped at {}", player.progress()), Instant::now() + KEY_NOTE_DURATION));
+            self.request_redraw();
+        }
+    }
+
+    /// `!macro record|stop|play|list|show|rm`.
+    fn macro_command(&mut self, args: &str) {
+        // The `!macro` line is never part of a recording.
+        if let Some(recorder) = &mut self.macro_recorder {
+            recorder.drop_last_line();
+        }
+        let command = match MacroCommand::parse(args) {
+            Ok(command) => command,
+            Err(usage) => return self.push_direct(&usage),
+        };
+        let Some(vault) = self.engine.as_ref().map(|e| e.runner.vault().clone()) else {
+            return self.push_direct("⚠️  Engine not ready yet");
+        };
+        match command {
+            MacroCommand::Record(name) => {
+                if let Some(recorder) = &self.macro_recorder {
+                    let note = format!("⌨ Already recording {}: !macro stop first", recorder.name());
+                    return self.push_direct(&note);
+                }
+                if self.macro_player.is_some() {
+                    return self.push_direct("⌨ A macro is playing: Esc stops it");
+                }
+                self.macro_recorder = Some(Recorder::new(&name));
+                self.push_direct(&format!(
+                    "⌨ Recording macro {}: !macro stop saves it. Keys typed at a password prompt are left out.",
+                    name
+                ));
+            }
+            MacroCommand::Stop => {
+                let Some(recorder) = self.macro_recorder.take() else {
+                    return self.push_direct("⌨ Not recording a macro (!macro record <name>)");
+                };
+                let (name, left_out) = (recorder.name().to_string(), recorder.left_out());
+                let steps = recorder.finish();
+                if steps.is_empty() {
+                    return self.push_direct(&format!("⌨ Nothing recorded: {} not saved", name));
+                }
+                match vault.set_macro(&name, &macros::encode(&steps)) {
+                    Ok(()) => {
+                        let mut note = format!("⌨ Saved macro {}: {} steps", name, steps.len());
+                        if left_out > 0 {
+                            note.push_str(&format!(", {} left out at a password prompt", left_out));
+                        }
+                        self.push_direct(&note);
+                    }
+                    Err(e) => self.push_direct(&format!("❌ Saving macro {} failed: {}", name, e)),
+                }
+            }
+            MacroCommand::Play { name, times } => {
+                if self.macro_recorder.is_some() {
+                    return self.push_direct("⌨ Stop recording (!macro stop) before playing a macro");
+                }
+                match load_macro(&vault, &name) {
+                    Ok(steps) => {
+                        self.macro_player = Some(Player::new(&name, steps, times, self.macro_delay, Instant::now()));
+                        self.push_direct(&format!("⌨ Playing macro {} ×{} (Esc stops)", name, times));
+                    }
+                    Err(message) => self.push_direct(&message),
+                }
+            }
+            MacroCommand::List => match vault.list_macros() {
+                Ok(saved) if saved.is_empty() => {
+                    self.show_direct_lines(vec!["⌨ No macros yet.".to_string(), String::new(), macros::MACRO_USAGE.to_string()])
+                }
+                Ok(saved) => {
+                    let mut lines = vec!["⌨ Macros:".to_string(), String::new()];
+                    for m in saved {
+                        let first = m.steps.lines().next().unwrap_or_default();
+                        lines.push(format!("  {:<16} {:>3} steps  {}", m.name, m.steps.lines().count(), first));
+                    }
+                    self.show_direct_lines(lines);
+                }
+                Err(e) => self.push_direct(&format!("❌ Listing macros failed: {}", e)),
+            },
+            MacroCommand::Show(name) => match load_macro(&vault, &name) {
+                Ok(steps) => {
+                    let mut lines = vec![format!("⌨ Macro {}:", name), String::new()];
+                    lines.extend(steps.iter().enumerate().map(|(i, step)| format!("  {:>3}  {}", i + 1, step.encode())));
+                    self.show_direct_lines(lines);
+                }
+                Err(message) => self.push_direct(&message),
+            },
+            MacroCommand::Rm(name) => match vault.remove_macro(&name) {
+                Ok(true) => self.push_direct(&format!("⌨ Removed macro {}", name)),
+                Ok(false) => self.push_direct(&format!("⌨ No macro called {}", name)),
+                Err(e) => self.push_direct(&format!("❌ Removing macro {} failed: {}", name, e)),
+            },
+        }
+    }
+
+    // ----- marks and the jump list -----
+
+    /// Where the view is, for the jump list.
+    fn here(&self) -> Spot {
+        match self.viewport.is_scrolled_back() {
+            true => Spot::Line(self.viewport.window().start),
+            false => Spot::Bottom,
+        }
+    }
+
+    /// Scroll to `spot` over the PTY scrollback.
+    fn go_to(&mut self, spot: Spot) {
+        match spot {
+            Spot::Bottom => self.viewport.bottom(),
+            Spot::Line(line) => self.viewport.scroll_to_line(line),
+        }
         if let Some(engine) = &self.engine {
-            let engine = engine.clone();
-            self.rt.spawn(async move {
-                let _ = engine.send_escape().await;
+            sync_scrollback(&mut self.viewport, engine);
+            self.last_snapshot = Some(engine.state.snapshot());
+        }
+    }
+
+    fn note_jump(&mut self, label: &str) {
+        self.key_note = Some((format!("jumped to: {}", label), Instant::now() + KEY_NOTE_DURATION));
+    }
+
+    /// `!mark <name>` at the focused block, else at the top line of the
+    /// view; `!mark rm <name>` forgets one.
+    fn mark_command(&mut self, args: &str) {
+        if let Some(name) = args.strip_prefix("rm ").map(str::trim) {
+            let saved = match (&self.engine, self.marks_persist) {
+                (Some(engine), true) => engine.runner.vault().remove_mark(name).unwrap_or(false),
+                _ => false,
+            };
+            match self.sessions.focused_mut().marks.remove(name) || saved {
+                true => self.push_direct(&format!("🔖 Removed mark {}", name)),
+                false => self.push_direct(&format!("🔖 No mark called {}", name)),
+            }
+            return;
+        }
+        if !tags::is_valid_name(args) {
+            self.push_direct(MARK_USAGE);
+            return;
+        }
+        let (target, line) = match self.marks.focused() {
+            Some(block) => (MarkTarget::Block(block.id), block.lines.start),
+            None => {
+                let line = self.viewport.window().start;
+                (MarkTarget::Line(line), line)
+            }
+        };
+        let command = self.marks.nearest(line).and_then(|b| b.command.clone());
+        let vault = self.engine.as_ref().map(|e| e.runner.vault().clone());
+        let history_id = match (&vault, &command) {
+            (Some(vault), Some(command)) => vault.last_run_id(Some(command)).ok().flatten(),
+            _ => None,
+        };
+        if self.marks_persist
+            && let (Some(vault), Some(id)) = (&vault, history_id)
+            && let Err(e) = vault.set_mark(args, id)
+        {
+            self.push_direct(&format!("⚠️ {}: saving the mark failed: {}", marks::PERSIST_KEY, e));
+        }
+        let near = command.as_deref().map(|c| format!(" ({})", c)).unwrap_or_default();
+        let note = format!("🔖 Marked {} at line {}{}", args, line + 1, near);
+        self.sessions.focused_mut().marks.set(Mark { name: args.to_string(), target, command, history_id, at: chrono::Utc::now().timestamp() });
+        self.push_direct(&note);
+    }
+
+    /// `!marks`: each mark, where it is now and the command near it.
+    fn list_marks(&mut self) {
+        if self.sessions.focused().marks.is_empty() {
+            self.show_direct_lines(vec!["🔖 No marks yet.".to_string(), "".to_string(), MARK_USAGE.to_string()]);
+            return;
+        }
+        let names: Vec<String> = self.sessions.focused().marks.iter().map(|m| m.name.clone()).collect();
+        let mut lines = vec!["🔖 Marks:".to_string(), "".to_string()];
+        for name in names {
+            let screen = &self.marks;
+            let Some(resolved) = self.sessions.focused_mut().marks.resolve(&name, |id| screen.block(id).map(|b| b.lines.start)) else {
+                continue;
+            };
+            if let Some(mark) = self.sessions.focused().marks.get(&name) {
+                lines.push(format!("  {}", mark.describe(resolved)));
+            }
+        }
+        self.show_direct_lines(lines);
+    }
+
+    /// `!jump <name>`: scroll to the mark, or show its history row once
+    /// its scrollback is gone.
+    fn jump_to_mark(&mut self, name: &str) {
+        if name.is_empty() {
+            self.push_direct(MARK_USAGE);
+            return;
+        }
+        let screen = &self.marks;
+        let Some(resolved) = self.sessions.focused_mut().marks.resolve(name, |id| screen.block(id).map(|b| b.lines.start)) else {
+            self.push_direct(&format!("🔖 No mark called {}; !marks lists them", name));
+            return;
+        };
+        match resolved {
+            Resolved::Line(line) => {
+                self.marks.unfocus();
+                self.jump_list.push(self.here());
+                self.go_to(Spot::Line(line));
+            }
+            Resolved::History(id) => {
+                let record = self.engine.as_ref().and_then(|e| e.runner.vault().get_record(id).ok().flatten());
+                match record {
+                    Some(record) => self.show_direct_lines(marks::recall_lines(name, &record)),
+                    None => {
+                        self.push_direct(&format!("🔖 {}: history #{} has been deleted", name, id));
+                        return;
+                    }
+                }
+            }
+            Resolved::Gone => {
+                self.push_direct(&format!("🔖 {}: its output has left the screen and isn't in the history", name));
+                return;
+            }
+        }
+        self.note_jump(name);
+    }
+
+    /// Ctrl+O / Ctrl+I: back and forward through the jump list.
+    fn walk_jumps(&mut self, action: Action) {
+        if self.mode_tracker.snapshot().alt_screen {
+            return;
+        }
+        let spot = match action {
+            Action::JumpBack => self.jump_list.back(self.here()),
+            _ => self.jump_list.forward(),
+        };
+        let Some(spot) = spot else {
+            return;
+        };
+        self.marks.unfocus();
+        self.go_to(spot);
+        let label = match spot {
+            Spot::Bottom => "bottom".to_string(),
+            Spot::Line(line) => {
+                let screen = &self.marks;
+                self.sessions
+                    .focused()
+                    .marks
+                    .name_at(line, |id| screen.block(id).map(|b| b.lines.start))
+                    .map_or_else(|| format!("line {}", line + 1), str::to_string)
+            }
+        };
+        self.note_jump(&label);
+    }
+
+    /// Marks saved by earlier sessions (`marks.persist`), history-backed.
+    fn load_marks(&mut self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let vault = engine.runner.vault();
+        let saved = match vault.list_marks() {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::warn!("Loading saved marks failed: {}", e);
+                return;
+            }
+        };
+        for saved in saved {
+            let command = vault.get_record(saved.history_id).ok().flatten().map(|r| r.command);
+            self.sessions.focused_mut().marks.set(Mark {
+                name: saved.name,
+                target: MarkTarget::History(saved.history_id),
+                command,
+                history_id: Some(saved.history_id),
+                at: saved.created_at,
             });
         }
     }
 
-    pub fn send_eof(&self) {
+    // ----- command blocks -----
+
+    /// Ctrl+Up/Down: move the block focus and bring the block into view;
+    /// past the newest, back to the input line and the bottom.
+    fn focus_block(&mut self, action: Action) {
+        if self.mode_tracker.snapshot().alt_screen {
+            return;
+        }
+        self.jump_list.push(self.here());
+        let block = match action {
+            Action::BlockPrev => self.marks.focus_prev(),
+            _ => self.marks.focus_next(),
+        };
+        let Some(line) = block.map(|b| b.lines.start) else {
+            self.scroll(Action::ScrollBottom);
+            return;
+        };
+        self.viewport.reveal(line);
         if let Some(engine) = &self.engine {
-            let engine = engine.clone();
-            self.rt.spawn(async move {
-                let _ = engine.send_eof().await;
-            });
+            sync_scrollback(&mut self.viewport, engine);
+            self.last_snapshot = Some(engine.state.snapshot());
         }
     }
 
-    // --- Holodeck actions ---
+    /// The block the block actions and a bare `!chain` apply to: the
+    /// focused one, or the newest.
+    fn current_block(&self) -> Option<BlockId> {
+        self.marks.focused().or(self.marks.last()).map(|b| b.id)
+    }
 
-    pub fn apply_holodeck_action(&mut self, action: Action) {
-        match action {
-            Action::CopyText(s) => {
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    let _ = clipboard.set_text(s);
+    /// Put block `id`'s command in the input line, linked to the block
+    /// until it is submitted; with `run`, submit it at once, through the
+    /// same danger check as anything typed.
+    fn edit_block(&mut self, id: BlockId, run: bool) {
+        let Some(command) = self.marks.block(id).and_then(|b| b.command.clone()) else {
+            self.push_direct("⛓ That block's command wasn't sent from the input line, so there is none to edit");
+            return;
+        };
+        self.input = command;
+        self.cursor_pos = self.input.chars().count();
+        self.completion = None;
+        self.suggestions = None;
+        self.history_cursor = None;
+        self.block_link = Some(id);
+        if run {
+            self.submit_command();
+        }
+    }
+
+    /// A click on the line a block's command was typed on: edit it, or
+    /// with `rerun` (Shift) run it again. True if the click hit one.
+    pub fn block_click(&mut self, rerun: bool) -> bool {
+        if self.mode_tracker.snapshot().alt_screen {
+            return false;
+        }
+        let (Some(at), Some(engine)) = (self.pointer_cell(true), &self.engine) else {
+            return false;
+        };
+        let top = engine.state.history_size().saturating_sub(self.viewport.offset());
+        let Some(id) = self.marks.command_at(top + at.row).map(|b| b.id) else {
+            return false;
+        };
+        self.edit_block(id, rerun);
+        true
+    }
+
+    /// A block is focused, or the input line holds one's command.
+    pub fn block_focus_active(&self) -> bool {
+        self.marks.focused().is_some() || self.block_link.is_some()
+    }
+
+    /// Escape: unfocus the block and drop the link (the input stays).
+    pub fn clear_block_focus(&mut self) {
+        self.marks.unfocus();
+        self.block_link = None;
+    }
+
+    /// Snapshot rows of the block the input line was edited from, or else
+    /// of the focused block.
+    pub fn block_rows(&self) -> Option<std::ops::Range<usize>> {
+        let (Some(engine), Some(snapshot)) = (&self.engine, &self.last_snapshot) else {
+            return None;
+        };
+        if self.mode_tracker.snapshot().alt_screen {
+            return None;
+        }
+        let block = match self.block_link.filter(|_| !self.input.is_empty()) {
+            Some(id) => self.marks.block(id),
+            None => self.marks.focused(),
+        }?;
+        let top = engine.state.history_size().saturating_sub(self.viewport.offset());
+        attention::screen_rows(&block.lines, top, snapshot.rows())
+    }
+
+    /// Bare `!chain`: block `id` and the blocks it was edited from.
+    fn show_block_chain(&mut self, id: BlockId) {
+        let steps: Vec<ChainStep> = self
+            .marks
+            .chain(id)
+            .into_iter()
+            .map(|block| ChainStep {
+                label: format!("block {}", block.id),
+                command: block.command.clone().unwrap_or_else(|| "(not sent from the input line)".to_string()),
+                exit: block.exit_code,
+            })
+            .collect();
+        self.push_direct(&chain::lines(&steps).join("\n"));
+    }
+
+    /// Insert clipboard text at the cursor; line breaks become spaces so a
+    /// paste never submits by itself.
+    pub fn paste_from_clipboard(&mut self) {
+        let text = arboard::Clipboard::new().and_then(|mut c| c.get_text());
+        match text {
+            Ok(text) => self.input_insert(&clipboard_history::as_input_line(&text)),
+            Err(e) => self.report_error(ErrorSeverity::Warning, &format!("Paste failed: {}", e), None),
+        }
+    }
+
+    /// Paste, or open the clipboard history picker once Positronic has
+    /// copied something (Enter in the picker pastes as before).
+    pub fn paste_or_pick(&mut self) {
+        match ClipboardPicker::new(&self.clipboard_history) {
+            Some(picker) => self.clipboard_picker = Some(picker),
+            None => self.paste_from_clipboard(),
+        }
+    }
+
+    /// Put the system clipboard and the history in step.
+    pub fn copy_to_clipboard(&mut self, text: String) -> bool {
+        let text = match &mut self.presenting {
+            Some(redactor) if self.present_settings.copy_redacted => redactor.redact_text(&text),
+            _ => text,
+        };
+        let copied = arboard::Clipboard::new().and_then(|mut c| c.set_text(text.clone()));
+        match copied {
+            Ok(()) => {
+                self.clipboard_history.push(&text);
+                true
+            }
+            Err(e) => {
+                self.report_error(ErrorSeverity::Warning, &format!("Copy failed: {}", e), None);
+                false
+            }
+        }
+    }
+
+    /// Insert history entry `n` (1 = newest) at the cursor.
+    pub fn insert_clipboard_entry(&mut self, n: usize) {
+        self.clipboard_picker = None;
+        match self.clipboard_history.get(n) {
+            Ok(text) => self.input_insert(&clipboard_history::as_input_line(&text)),
+            Err(e) => self.push_direct(&format!("📋 {}", e)),
+        }
+    }
+
+    /// Picker entry for a digit key, if the picker is open.
+    pub fn clipboard_entry_for_key(&self, key: &str) -> Option<usize> {
+        self.clipboard_picker.as_ref()?.key_entry(key)
+    }
+
+    /// `!paste list | <n> | clear`.
+    fn paste_command(&mut self, cmd: &str) {
+        match PasteCommand::parse(cmd) {
+            Ok(PasteCommand::List) => {
+                let lines = self.clipboard_history.lines();
+                self.push_direct(&lines.join("\n"));
+            }
+            Ok(PasteCommand::Insert(n)) => self.insert_clipboard_entry(n),
+            Ok(PasteCommand::Clear) => {
+                self.clipboard_history.clear();
+                self.clipboard_picker = None;
+                self.push_direct("📋 Clipboard history cleared");
+            }
+            Err(usage) => self.push_direct(&usage),
+        }
+    }
+
+    /// Open the last URL visible in the terminal or direct output.
+    pub fn follow_last_link(&mut self) {
+        let screen = self.last_snapshot.as_ref().map(renderer::snapshot_to_plain).unwrap_or_default();
+        let url = crate::helpers::last_url(&screen)
+            .or_else(|| crate::helpers::last_url(&self.direct_output))
+            .map(str::to_string);
+        match url {
+            Some(url) => match crate::platform::open_url(&url) {
+                Ok(()) => self.push_direct(&format!("🔗 Opening {}", url)),
+                Err(e) => self.push_direct(&format!("⚠️ Could not open {}: {}", url, e)),
+            },
+            None => self.push_direct("🔗 No link on screen"),
+        }
+    }
+
+    /// `!errors` lists diagnostics from the last failing command;
+    /// `!errors open <n>` opens the n-th in the editor.
+    fn errors_command(&mut self, arg: &str) {
+        let parts: Vec<&str> = arg.split_whitespace().collect();
+        match parts.as_slice() {
+            [] => {
+                let source = self.load_diagnostics();
+                if self.diagnostics.is_empty() {
+                    self.push_direct(&format!("⚑ No errors found in {}", source));
+                    return;
                 }
-                self.push_direct("⚡ Holodeck: Copied to clipboard");
+                let mut lines = vec![format!(
+                    "⚑ {} in {}:",
+                    diagnostics::summary(&self.diagnostics),
+                    source
+                )];
+                for (i, d) in self.diagnostics.iter().enumerate() {
+                    lines.push(format!("  {:>2}. {}", i + 1, d));
+                }
+                lines.push("  !errors open <n> to jump to one".to_string());
+                self.push_direct(&lines.join("\n"));
             }
-            Action::RunCommand(cmd) => {
-                self.input = cmd;
-                self.cursor_pos = self.input.chars().count();
+            ["open", n] => match n.parse::<usize>() {
+                Ok(n) if n >= 1 => {
+                    if self.diagnostics.is_empty() {
+                        self.load_diagnostics();
+                    }
+                    self.open_diagnostic(n - 1);
+                }
+                _ => self.push_direct("Usage: !errors open <n>"),
+            },
+            ["sys"] => {
+                let lines = self.error_center.report_lines();
+                self.error_center.acknowledge_all();
+                self.push_direct(&lines.join("\n"));
+            }
+            ["sys", "clear"] => {
+                self.error_center.clear();
+                self.push_direct("⚑ System error list cleared");
+            }
+            _ => self.push_direct("Usage: !errors [open <n> | sys [clear]]"),
+        }
+    }
+
+    /// Fill `diagnostics` from the last failed run with stored output, or
+    /// from what is on screen. Returns where they came from.
+    fn load_diagnostics(&mut self) -> String {
+        let failure = self
+            .engine
+            .as_ref()
+            .and_then(|e| e.runner.vault().last_failure().ok().flatten());
+        let extractor = Extractor::default();
+        if let Some(record) = failure {
+            let found = extractor.extract(record.output.as_deref().unwrap_or_default());
+            if !found.is_empty() {
+                self.diagnostics = found;
+                self.diagnostics_dir = record.directory;
+                return format!("'{}'", record.command);
+            }
+        }
+        let mut screen = self.last_snapshot.as_ref().map(renderer::snapshot_to_plain).unwrap_or_default();
+        screen.push('\n');
+        screen.push_str(&self.direct_output);
+        self.diagnostics = extractor.extract(&screen);
+        self.diagnostics_dir = self.cwd.clone();
+        "the visible output".to_string()
+    }
+
+    /// Open diagnostic `index` in the editor (`editor.command`).
+    fn open_diagnostic(&mut self, index: usize) {
+        let Some(d) = self.diagnostics.get(index).cloned() else {
+            self.push_direct(&format!("⚑ No error #{} (!errors lists them)", index + 1));
+            return;
+        };
+        let path = std::path::Path::new(&self.diagnostics_dir).join(&d.file);
+        let template = self
+            .engine
+            .as_ref()
+            .and_then(|e| e.runner.vault().get_config(platform::EDITOR_KEY).ok().flatten());
+        let (line, col) = (d.line.unwrap_or(1), d.col.unwrap_or(1));
+        match platform::open_location(template.as_deref(), &path.to_string_lossy(), line, col) {
+            Ok(()) => self.push_direct(&format!("📝 Opening {}", d.location())),
+            Err(e) => self.push_direct(&format!("⚠️ Could not open {}: {}", d.location(), e)),
+        }
+    }
+
+    /// Open the first error of the last failing command.
+    pub fn jump_to_error(&mut self) {
+        self.load_diagnostics();
+        let first = self
+            .diagnostics
+            .iter()
+            .position(|d| d.severity == Severity::Error)
+            .or_else(|| (!self.diagnostics.is_empty()).then_some(0));
+        match first {
+            Some(index) => self.open_diagnostic(index),
+            None => self.push_direct("⚑ No errors to jump to"),
+        }
+    }
+
+    /// The interrupt chord: interrupt a running command, copy the
+    /// selection, cancel a `!` command, or none of these, as
+    /// `keys.interrupt_mode` routes it.
+    pub fn interrupt_or_copy(&mut self) {
+        let running = self.engine.as_ref().is_some_and(|e| e.running_command().is_some());
+        let selected = self.selected_text().is_some();
+        let route = self.keymap.interrupt_mode().route(running, selected, self.native_in_flight());
+        match route {
+            InterruptRoute::Interrupt => self.send_interrupt(),
+            InterruptRoute::CopySelection => {
+                self.copy_selection();
+            }
+            InterruptRoute::CancelNative => {
+                self.cancel_native("Ctrl+C");
+                return;
+            }
+            InterruptRoute::Nothing => {}
+        }
+        self.key_note = Some((route.note().to_string(), Instant::now() + KEY_NOTE_DURATION));
+    }
+
+    /// Copy the selected text, if any; true if something was copied.
+    pub fn copy_selection(&mut self) -> bool {
+        match self.selected_text() {
+            Some(text) => self.copy_to_clipboard(text),
+            None => false,
+        }
+    }
+
+    /// The selected text, unless nothing (or only blanks) is selected.
+    fn selected_text(&self) -> Option<String> {
+        let selection = self.selection.filter(|s| !s.is_empty())?;
+        let text = selection.text(self.last_snapshot.as_ref()?);
+        (!text.trim().is_empty()).then_some(text)
+    }
+
+    /// The cell boundary under the pointer while the PTY screen is shown;
+    /// `None` outside the terminal area when `inside` is asked for.
+    fn pointer_cell(&self, inside: bool) -> Option<GridPos> {
+        let snapshot = self.last_snapshot.as_ref()?;
+        let gpu = self.gpu.as_ref()?;
+        let lay = self.layout_for([gpu.size.width, gpu.size.height]);
+        let (x, y) = (self.last_mouse_x, self.last_mouse_y);
+        if inside
+            && !(x >= lay.terminal_x
+                && x < lay.terminal_x + lay.terminal_w
+                && y >= lay.terminal_y
+                && y < lay.terminal_y + lay.terminal_h)
+        {
+            return None;
+        }
+        // The view starts at the first row with content.
+        let first = renderer::first_content_row(snapshot);
+        let origin = [lay.terminal_x + lay.padding, lay.terminal_y + lay.padding];
+        let cell = [lay.cell_w, lay.cell_h];
+        let mut pos = GridPos::from_pixel(x, y, origin, cell, snapshot.rows() - first, snapshot.cols());
+        pos.row += first;
+        Some(pos)
+    }
+
+    /// Left button down on a `!timeline` bar: run `!history --at` for the
+    /// command drawn there. True if the press hit one.
+    pub fn timeline_click(&mut self) -> bool {
+        let Some(id) = self.timeline_command_at_pointer() else {
+            return false;
+        };
+        self.input = format!("!history --at {}", id);
+        self.cursor_pos = self.input.chars().count();
+        self.submit_command();
+        true
+    }
+
+    /// The history id of the timeline segment under the pointer, while
+    /// native output (not the PTY screen) is shown.
+    fn timeline_command_at_pointer(&self) -> Option<i64> {
+        let timeline = self.timeline.as_ref().filter(|_| self.last_snapshot.is_none())?;
+        let gpu = self.gpu.as_ref()?;
+        let lay = self.layout_for([gpu.size.width, gpu.size.height]);
+        let (left, top) = (lay.terminal_x + lay.padding, lay.terminal_y + lay.padding);
+        let (x, y) = (self.last_mouse_x - left, self.last_mouse_y - top);
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        // The same window of lines the terminal view draws.
+        let line_height = lay.cell_h;
+        let visible_rows = ((lay.terminal_h - lay.padding) / line_height).ceil().max(0.0) as usize;
+        let mut view = self.viewport.clone();
+        view.set_extent(self.direct_output.lines().count(), visible_rows);
+        let window = view.window();
+        let index = window.start + (y / line_height) as usize;
+        if !window.contains(&index) {
+            return None;
+        }
+        let line = self.direct_output.lines().nth(index)?;
+        timeline.command_at(line, (x / lay.cell_w) as usize)
+    }
+
+    /// Left button down: start a selection there, dropping any old one.
+    /// True if the press landed on the terminal text.
+    pub fn select_start(&mut self) -> bool {
+        let had = self.selection.take().is_some();
+        match self.pointer_cell(true) {
+            Some(at) => {
+                self.selection = Some(Selection::new(at));
+                self.selecting = true;
+                true
+            }
+            None => had,
+        }
+    }
+
+    /// Pointer moved: extend a selection being dragged; true if it changed.
+    pub fn select_drag(&mut self) -> bool {
+        if !self.selecting {
+            return false;
+        }
+        let (Some(at), Some(selection)) = (self.pointer_cell(false), self.selection.as_mut()) else {
+            return false;
+        };
+        let before = *selection;
+        selection.extend(at);
+        *selection != before
+    }
+
+    /// Left button up: the selection stays until the next press. True if
+    /// the press was a click on the text rather than a drag.
+    pub fn select_end(&mut self) -> bool {
+        let pressed = std::mem::take(&mut self.selecting);
+        if self.selection.is_some_and(|s| s.is_empty()) {
+            self.selection = None;
+            return pressed;
+        }
+        false
+    }
+
+    /// Drop the interrupt chord's status note once it has been shown long
+    /// enough; true when the status bar needs redrawing.
+    pub fn tick_key_note(&mut self) -> bool {
+        if self.key_note.as_ref().is_some_and(|(_, until)| Instant::now() >= *until) {
+            self.key_note = None;
+            return true;
+        }
+        false
+    }
+
+    pub fn focus_changed(&mut self, focused: bool) {
+        let now = Instant::now();
+        self.marks.attention.set_focus(focused, now);
+        if let Some(state) = self.governor.set_focus(focused, now) {
+            self.power_changed(state);
+        }
+    }
+
+    /// A key, click or PTY output: wake the governor if it was idle.
+    pub fn note_activity(&mut self) {
+        if let Some(state) = self.governor.activity(Instant::now()) {
+            self.power_changed(state);
+        }
+    }
+
+    /// Step the governor down once the window has been quiet long enough;
+    /// true when that changed anything on screen.
+    pub fn tick_power(&mut self) -> bool {
+        match self.governor.tick(Instant::now()) {
+            Some(state) => {
+                self.power_changed(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn power_changed(&mut self, state: PowerState) {
+        tracing::debug!("Power state: {}", state.label());
+        if let Some(neural) = self.engine.as_ref().and_then(|e| e.runner.neural().ok()) {
+            if state.probes() {
+                neural.resume_probes();
+            } else {
+                neural.pause_probes();
             }
-            Action::None => {}
         }
+        if state == PowerState::Active {
+            // The running timer and status bar catch up on the next frame.
+            self.tick_running();
+            self.request_redraw();
+        }
+    }
+
+    /// Show the unread tracker what's in view and mark read what has been
+    /// long enough; true when a marker needs to go.
+    pub fn tick_attention(&mut self) -> bool {
+        let Some(engine) = &self.engine else {
+            return false;
+        };
+        if self.mode_tracker.snapshot().alt_screen {
+            return false;
+        }
+        let now = Instant::now();
+        let (_, rows) = engine.state.size();
+        let top = engine.state.history_size().saturating_sub(self.viewport.offset());
+        let anchored = self.viewport.offset() == 0;
+        self.marks.attention.set_view(top..top + rows as usize, anchored, now);
+        !self.marks.attention.tick(now).is_empty()
+    }
+
+    /// Unread rows of the snapshot on screen, and how many rows at its
+    /// top are dimmed.
+    pub fn attention_rows(&self) -> (Vec<std::ops::Range<usize>>, usize) {
+        let (Some(engine), Some(snapshot)) = (&self.engine, &self.last_snapshot) else {
+            return (Vec::new(), 0);
+        };
+        if self.mode_tracker.snapshot().alt_screen {
+            return (Vec::new(), 0);
+        }
+        let rows = snapshot.rows();
+        let top = engine.state.history_size().saturating_sub(self.viewport.offset());
+        let unread =
+            self.marks.attention.unread().filter_map(|(_, lines)| attention::screen_rows(lines, top, rows)).collect();
+        let dimmed = match self.marks.dim_before(self.span_cache.block_style().dim_old) {
+            Some(line) => line.saturating_sub(top).min(rows),
+            None => 0,
+        };
+        (unread, dimmed)
     }
 
     pub fn copy_visible_to_clipboard(&mut self) {
         if let Some(snap) = &self.last_snapshot {
             let plain = renderer::snapshot_to_plain(snap);
-            if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                let _ = clipboard.set_text(plain);
+            if self.copy_to_clipboard(plain) {
+                self.push_direct("⚡ Copied visible terminal to clipboard");
+            }
+        }
+    }
+
+    /// Like `copy_visible_to_clipboard`, keeping colors and styles as SGR.
+    pub fn copy_visible_ansi_to_clipboard(&mut self) {
+        if let Some(snap) = &self.last_snapshot {
+            let ansi = renderer::snapshot_to_ansi(snap);
+            if self.copy_to_clipboard(ansi) {
+                self.push_direct("⚡ Copied visible terminal with formatting to clipboard");
             }
-            self.push_direct("⚡ Copied visible terminal to clipboard");
         }
     }
 }
@@ -390,23 +3669,33 @@ impl ApplicationHandler for PositronicApp {
         }
 
         let attrs = WindowAttributes::default()
-            .with_title("Positronic /// Data Surface")
+            .with_title(self.window_title())
+            // Settings load after the window exists, and X11 picks the
+            // visual at creation, so ask for transparency up front.
+            .with_transparent(true)
             .with_surface_size(PhysicalSize::new(1280u32, 800u32));
 
         match event_loop.create_window(attrs) {
             Ok(window) => {
                 let window: Arc<dyn Window> = Arc::from(window);
-                match GpuState::new(window.clone()) {
-                    Ok(gpu) => {
-                        self.gpu = Some(gpu);
-                        self.window = Some(window);
-                        tracing::info!("Window + GPU initialized");
-                        self.boot_engine();
+                self.window = Some(window.clone());
+                let plan = render_path::plan(self.renderer_choice, Platform::current());
+                match self.open_renderer(&plan) {
+                    Ok(report) => {
+                        for line in report.lines() {
+                            tracing::info!("{}", line);
+                        }
+                        if self.gpu.is_none() {
+                            self.push_direct(&report.lines().join("\n"));
+                        }
+                        self.render_report = Some(report);
+                        self.os_theme_changed(window.theme());
+                        tracing::info!("Window + renderer initialized");
+                        self.start_boot();
                     }
                     Err(e) => {
-                        tracing::error!("GPU init failed: {:#}", e);
-                        self.state = AppState::Error(format!("GPU init failed: {:#}", e));
-                        self.window = Some(window);
+                        tracing::error!("Renderer init failed: {:#}", e);
+                        self.state = AppState::Error(format!("Renderer init failed: {:#}", e));
                     }
                 }
             }
@@ -417,32 +3706,222 @@ impl ApplicationHandler for PositronicApp {
         }
     }
 
-    fn window_event(
-        &mut self,
-        event_loop: &dyn ActiveEventLoop,
-        _window_id: WindowId,
-        event: WindowEvent,
-    ) {
-        super::events::handle_window_event(self, event_loop, event);
-    }
-
-    fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
-        let pty_changed = self.poll_redraws();
-        let cmd_changed = self.poll_cmd_results();
-
-        if pty_changed || cmd_changed {
-            self.request_redraw();
+    fn window_event(
+        &mut self,
+        event_loop: &dyn ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        super::events::handle_window_event(self, event_loop, event);
+    }
+
+    fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
+        let power_changed = self.tick_power();
+        let pty_changed = self.poll_redraws();
+        let cmd_changed = self.poll_cmd_results();
+        let timer_changed = self.tick_running() | self.tick_tasks() | self.tick_last_command();
+        let theme_changed = self.tick_theme();
+        let blink_changed = self.blink.visible(self.cursor_style().1, Instant::now()) != self.cursor_shown;
+        let note_changed = self.tick_key_note() | self.error_center.tick(Instant::now());
+        let follow_changed = self.poll_follow();
+        let renderer_changed = self.tick_recovery();
+        let power = self.governor.state();
+        let attention_changed = power.timers() && self.tick_attention();
+        let bell_changed = self.tick_bell();
+        let resize_changed = self.tick_resize();
+        let macro_changed = self.tick_macro();
+        let finder_changed = self.poll_file_finder();
+        self.tick_draft();
+        self.tick_boot_marker();
+
+        if power_changed
+            || pty_changed
+            || cmd_changed
+            || timer_changed
+            || theme_changed
+            || blink_changed
+            || note_changed
+            || follow_changed
+            || renderer_changed
+            || attention_changed
+            || bell_changed
+            || resize_changed
+            || macro_changed
+            || finder_changed
+        {
+            self.request_redraw();
+        }
+        // While a command runs, wake once a second to advance its timer,
+        // and at the reporter's rate while a long operation shows progress;
+        // the last command's stamp wakes when it next reads differently;
+        // theme sync wakes at schedule boundaries, OS polls and to clear
+        // its status note.
+        // A blinking cursor wakes at each toggle, a pending draft
+        // checkpoint when it falls due, and the interrupt chord's note
+        // and an error toast when they go. A lost GPU device wakes for its
+        // next reopen, and an unread block in view when it has been
+        // watched long enough.
+        // Bells wake to act once their run is gathered, to end a flash and
+        // to take down the mute badge. A normal boot wakes once it has
+        // been up long enough to clear the boot marker. A window resize
+        // wakes when the drag settles, to size the PTY. A playing macro
+        // wakes for its next step, and an open file finder while its
+        // index is still being read.
+        // The governor drops the cosmetic wakes while the window is idle,
+        // and wakes itself to step down or to flush batched output.
+        let key_note = self.key_note.as_ref().map(|(_, until)| *until);
+        let recovery = match self.recovery.step(Instant::now()) {
+            RecoveryStep::Wait(delay) => Some(Instant::now() + delay),
+            _ => None,
+        };
+        let toast = self.error_center.next_wake();
+        let ticks = power.ticks().then(|| [self.running_wake, self.tasks_wake, self.blink_wake(), key_note, toast]);
+        let timers = power.timers().then(|| [self.theme_wake(), self.marks.attention.next_read(), self.last_command_wake]);
+        let output = self.output_pending.map(|since| since + power.snapshot_interval());
+        let wake = ticks
+            .into_iter()
+            .flatten()
+            .chain(timers.into_iter().flatten())
+            .chain([self.draft_wake(), recovery, self.governor.next_change(), output, self.boot_stable_at])
+            .chain([self.resize.next_wake(), self.macro_player.as_ref().and_then(Player::next_wake)])
+            .chain([self.file_finder_wake()])
+            .chain(self.bell_wakes())
+            .flatten()
+            .min();
+        event_loop.set_control_flow(match wake {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+
+        if self.wants_exit {
+            self.end_session();
+            event_loop.exit();
+        }
+    }
+}
+
+impl PositronicApp {
+    /// Open the first renderer `attempts` allows, else software. Errors
+    /// only when even softbuffer can't draw the window.
+    fn open_renderer(&mut self, attempts: &[Attempt]) -> anyhow::Result<RenderReport> {
+        let Some(window) = self.window.clone() else {
+            anyhow::bail!("no window");
+        };
+        let selection = render_path::select(attempts, |attempt| {
+            GpuState::new(window.clone(), attempt)
+                .map(|gpu| Probed { info: gpu.adapter.clone(), device: gpu })
+                .map_err(|e| format!("{:#}", e))
+        });
+        let report = RenderReport::new(self.renderer_choice, &selection);
+        match selection.device {
+            Some(gpu) => {
+                self.software = None;
+                self.gpu = Some(gpu);
+            }
+            None => {
+                self.gpu = None;
+                self.software = Some(SoftwareRenderer::new(window)?);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Hand the renderer report to the runner for `!doctor`.
+    fn publish_render_report(&self) {
+        if let (Some(engine), Some(report)) = (&self.engine, &self.render_report) {
+            engine.runner.set_display_report(report.lines());
+        }
+    }
+
+    /// Notice a lost GPU device and work through `DeviceRecovery`: reopen
+    /// the same adapter, then fall back along the plan. True once drawing
+    /// works again.
+    fn tick_recovery(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some(reason) = self.gpu.as_ref().and_then(|gpu| gpu.device_lost()) {
+            // Drop the device before reopening: some drivers allow one.
+            self.gpu = None;
+            self.recovery.lost(&reason, now);
+        }
+        let lost = match self.render_report.as_ref().map(|r| &r.path) {
+            Some(RenderPath::Gpu { attempt, .. }) => *attempt,
+            _ => return false,
+        };
+        let Some(window) = self.window.clone() else {
+            return false;
+        };
+        match self.recovery.step(now) {
+            RecoveryStep::Idle | RecoveryStep::Wait(_) => false,
+            RecoveryStep::Reopen { attempt } => match GpuState::new(window, lost) {
+                Ok(gpu) => {
+                    self.gpu = Some(gpu);
+                    self.renderer_restored();
+                    true
+                }
+                Err(e) => {
+                    tracing::warn!("GPU reopen {} failed: {:#}", attempt, e);
+                    self.recovery.reopen_failed(now);
+                    false
+                }
+            },
+            RecoveryStep::FallBack => {
+                let plan = render_path::plan(self.renderer_choice, Platform::current());
+                match self.open_renderer(&render_path::fallback_plan(&plan, lost)) {
+                    Ok(report) => {
+                        self.render_report = Some(report);
+                        self.renderer_restored();
+                        true
+                    }
+                    Err(e) => {
+                        tracing::error!("Renderer fallback failed: {:#}", e);
+                        self.state = AppState::Error(format!("Renderer lost: {:#}", e));
+                        self.recovery.restored(&RenderPath::Software);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    /// A new renderer after a loss: restore what the old one was set to
+    /// and say what happened.
+    fn renderer_restored(&mut self) {
+        if let Some(gpu) = &mut self.gpu {
+            gpu.set_opacity(self.window_style.opacity);
         }
+        let Some(report) = &mut self.render_report else {
+            return;
+        };
+        if let Some(line) = self.recovery.restored(&report.path) {
+            report.recoveries = self.recovery.recoveries();
+            tracing::info!("{}", line);
+            self.push_direct(&line);
+        }
+        self.publish_render_report();
+    }
 
-        if self.wants_exit {
-            event_loop.exit();
+    /// Boot the engine, unless the boots before crashed: then ask about
+    /// safe mode first, and boot once answered (`submit_command`).
+    fn start_boot(&mut self) {
+        let crashes = self.boot_marker.crashes();
+        if !self.safe_mode && self.inspect.is_none() && crashes >= safe_mode::OFFER_AFTER_CRASHES {
+            self.push_direct(&safe_mode::crash_prompt(crashes));
+            self.safe_mode_asked = true;
+            return;
         }
+        self.boot_engine();
     }
-}
 
-impl PositronicApp {
     fn boot_engine(&mut self) {
         self.push_direct("⏳ Booting Positronic Engine...");
+        // A safe-mode or inspect boot proves nothing about a normal one,
+        // so only a normal boot is counted.
+        if !self.safe_mode && self.inspect.is_none() && let Err(e) = self.boot_marker.begin() {
+            tracing::warn!("Writing {} failed: {}", self.boot_marker.path().display(), e);
+        }
+        let safe_mode = self.safe_mode;
+        let data = self.data.clone();
+        let inspect = self.inspect.clone();
 
         let rt = self.rt.clone();
         let (redraw_tx, redraw_rx) = mpsc::channel(64);
@@ -451,7 +3930,8 @@ impl PositronicApp {
         let window = self.window.clone();
 
         rt.spawn(async move {
-            match PositronicEngine::start(120, 30, redraw_tx).await {
+            let options = EngineOptions { data, ipc: true, safe_mode, inspect, ..EngineOptions::new(120, 30) };
+            match PositronicEngine::start_with(options, redraw_tx).await {
                 Ok(engine) => {
                     let engine = Arc::new(engine);
                     ENGINE_READY.lock().unwrap().replace(engine);
@@ -486,18 +3966,757 @@ impl PositronicApp {
             self.state = AppState::Active;
             self.push_direct("✅ Engine ready");
             self.push_direct("Type a command, or !help for built-in commands.");
+            let inspection = self.engine.as_ref().and_then(|e| e.inspection().map(|i| i.banner()));
+            if let Some(banner) = inspection {
+                for line in banner {
+                    self.push_direct(&line);
+                }
+            } else if self.safe_mode {
+                for line in safe_mode::BANNER {
+                    self.push_direct(line);
+                }
+            } else {
+                self.boot_stable_at = Some(Instant::now() + safe_mode::STABLE_AFTER);
+            }
+            if self.engine.as_ref().is_some_and(|e| e.runner.vault().is_locked()) {
+                self.push_direct("🔒 Vault locked — !vault unlock to enter the passphrase");
+            }
+            let recovered = self.engine.as_ref().and_then(|e| e.runner.vault().recovery().map(|r| r.notice()));
+            if let Some(notice) = recovered {
+                self.push_direct(&notice);
+                self.report_error(ErrorSeverity::Warning, &notice, Some("!vault recover-report shows what was salvaged".to_string()));
+            }
 
             if let Some(engine) = &self.engine {
                 let snap = engine.state.snapshot();
                 update_cwd_from_snapshot(&snap, &mut self.cwd);
                 self.last_snapshot = Some(snap);
             }
+            self.publish_render_report();
+            self.reload_settings();
+            self.load_plugins();
+            if self.inspect.is_none() {
+                self.offer_draft();
+            }
+            if !self.safe_mode && self.inspect.is_none() {
+                self.offer_queue_resume();
+            }
+            if self.clipboard_history.settings().persist {
+                let path = self.data.file(clipboard_history::PERSIST_FILE);
+                if let Err(e) = self.clipboard_history.load(&path) {
+                    let message = format!("Could not load the clipboard history: {}", e);
+                    self.report_error(ErrorSeverity::Warning, &message, None);
+                }
+            }
+            if self.marks_persist {
+                self.load_marks();
+            }
+        }
+    }
+
+    /// Re-read every setting from the vault (active profile over base
+    /// config) and apply it, announcing invalid values and key conflicts.
+    pub fn reload_settings(&mut self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        // Safe mode runs on the built-in defaults, without a profile or
+        // `.inputrc`, whatever the vault says; so does an inspected vault,
+        // whose settings are someone else's.
+        if self.safe_mode || self.inspect.is_some() {
+            self.active_profile = None;
+            self.env_overrides = 0;
+            self.apply_settings(Settings::default());
+            return;
+        }
+        let vault = engine.runner.vault().clone();
+        let (mut settings, mut problems) = Settings::load(|key| vault.get_config(key).ok().flatten());
+        problems.extend(
+            settings.drop_disabled_themes(|theme| vault.is_disabled(DisabledKind::Theme, theme).unwrap_or(false)),
+        );
+        self.active_profile = vault.active_profile().ok().flatten();
+        self.env_overrides = engine.runner.env_overlay().len();
+        for problem in problems {
+            self.push_direct(&format!("⚠️ {}", problem));
+        }
+        let use_inputrc = settings.use_inputrc;
+        self.apply_settings(settings);
+        if use_inputrc && let Some(path) = inputrc::default_path() {
+            match inputrc::load(&path) {
+                Ok(rc) => {
+                    self.apply_inputrc(&rc);
+                    tracing::info!("{}", rc.summary(&path.display().to_string()).join("\n"));
+                }
+                Err(e) => self.push_direct(&format!("⚠️ {}: reading {} failed: {}", inputrc::USE_INPUTRC_KEY, path.display(), e)),
+            }
+        }
+    }
+
+    /// `!keys import-inputrc [path]`: take readline bindings and settings
+    /// for this session and list what was and wasn't used.
+    fn import_inputrc(&mut self, path: &str) {
+        let path = match path {
+            "" => inputrc::default_path(),
+            p => Some(std::path::Path::new(&self.cwd).join(crate::cwd::resolve_tilde(p))),
+        };
+        let Some(path) = path else {
+            self.push_direct("⚠️ No home directory to find .inputrc in; give a path");
+            return;
+        };
+        let rc = match inputrc::load(&path) {
+            Ok(rc) => rc,
+            Err(e) => {
+                self.push_direct(&format!("⚠️ Reading {} failed: {}", path.display(), e));
+                return;
+            }
+        };
+        self.apply_inputrc(&rc);
+        let mut lines = rc.summary(&path.display().to_string());
+        if rc.editing_mode == Some(EditingMode::Vi) {
+            lines.push("  ⚠️ editing-mode vi: the input line keeps emacs-style keys".to_string());
+        }
+        lines.extend(self.keymap.warnings().iter().map(|w| format!("  ⚠️ {}", w)));
+        lines.push(format!("  Apply at every start with !set {} on", inputrc::USE_INPUTRC_KEY));
+        self.show_direct_lines(lines);
+    }
+
+    fn apply_inputrc(&mut self, rc: &Inputrc) {
+        self.keymap.import(&rc.bindings);
+        if let Some(on) = rc.completion_ignore_case {
+            self.completion_ignore_case = on;
+        }
+    }
+
+    /// Swap in a complete set of settings at once.
+    fn apply_settings(&mut self, settings: Settings) {
+        self.theme_switcher.configure(settings.theme_sync);
+        self.poll_os_theme();
+        let now = chrono::Local::now().naive_local();
+        self.theme_name = self.theme_switcher.theme(now).unwrap_or(settings.theme);
+        let style = BlockStyle {
+            slow_threshold: settings.slow_threshold,
+            timestamps: settings.timestamps,
+            highlight: settings.highlight,
+            dim_old: settings.dim_old,
+            clock: settings.time.clock,
+            ..self.span_cache.block_style()
+        };
+        self.span_cache.set_block_style(style);
+        self.time_settings = settings.time;
+        self.summary_settings = settings.summary;
+        self.keymap = settings.keymap;
+        self.completion_ignore_case = false;
+        self.pager_threshold = settings.pager;
+        self.clipboard_history.configure(settings.clipboard);
+        self.layout_spec = settings.layout;
+        self.apply_window_style(settings.window);
+        // A new `layout.*` resizes the PTY only if the cell count changed.
+        let screen = (self.screen_cols, self.screen_rows);
+        if self.window_grid().is_some_and(|(cols, rows)| (cols as usize, rows as usize) != screen) {
+            self.resize_grid();
+        }
+        self.calc_hint = settings.calc_hint;
+        self.directory_hints.set_enabled(settings.directory_hints);
+        self.history_filter = settings.history;
+        self.holodeck_native = settings.holodeck_native;
+        self.suggest_rm = settings.suggest_rm;
+        self.marks_persist = settings.marks_persist;
+        self.macro_delay = settings.macro_delay;
+        self.bell_policy = settings.bell;
+        if self.presenting.is_some() {
+            self.presenting = Some(Redactor::new(&settings.present, &Identity::current()));
+        }
+        self.present_settings = settings.present;
+        if let Some(state) = self.governor.configure(settings.power, Instant::now()) {
+            self.power_changed(state);
+        }
+        let conflicts: Vec<String> = self
+            .keymap
+            .warnings()
+            .iter()
+            .filter(|w| !self.keymap.errors().contains(w))
+            .map(|w| format!("⚠️ keys: {}", w))
+            .collect();
+        for conflict in conflicts {
+            self.push_direct(&conflict);
+        }
+    }
+
+    /// Opacity goes to the surface; a padding change resizes the PTY grid.
+    fn apply_window_style(&mut self, style: WindowStyle) {
+        let previous = std::mem::replace(&mut self.window_style, style);
+        let Some(gpu) = &mut self.gpu else {
+            return;
+        };
+        if style.opacity != previous.opacity && !gpu.set_opacity(style.opacity) {
+            self.push_direct(&format!(
+                "⚠️ {} = {}: this window can't be translucent here, so it stays opaque",
+                window_style::OPACITY_KEY,
+                style.opacity
+            ));
+        }
+        if style.padding != previous.padding {
+            self.resize_grid();
+        }
+        self.request_redraw();
+    }
+
+    /// The layout of a window `size` pixels big, from `layout.*` and
+    /// `window.padding`, in the cells of whichever renderer is drawing.
+    pub fn layout_for(&self, size: [u32; 2]) -> ComputedLayout {
+        let (cell, scale) = match self.software {
+            Some(_) => (
+                CellMetrics { width: soft_frame::CELL_W as f32, height: soft_frame::CELL_H as f32 },
+                soft_frame::SCALE as f32,
+            ),
+            None => (crate::gfx::text::CELL, 1.0),
+        };
+        layout::compute(size, &self.layout_spec, self.window_style.padding, cell, scale)
+    }
+
+    /// PTY columns and rows that fit the window as laid out now.
+    fn window_grid(&self) -> Option<(u16, u16)> {
+        let size = self.window.as_ref()?.surface_size();
+        if size.width == 0 || size.height == 0 {
+            return None;
+        }
+        let lay = self.layout_for([size.width, size.height]);
+        Some((lay.cols, lay.rows))
+    }
+
+    /// A window resize event: the PTY follows once the drag settles
+    /// (`tick_resize`), drawn scaled until then (`grid_scale`).
+    pub(crate) fn resize_later(&mut self) {
+        self.resize.event(Instant::now());
+    }
+
+    /// Size the PTY once resize events have stopped. True if it was due.
+    fn tick_resize(&mut self) -> bool {
+        if !self.resize.take_due(Instant::now()) {
+            return false;
+        }
+        self.resize_grid();
+        true
+    }
+
+    /// Text scale for the terminal while a resize settles: the PTY's grid
+    /// fitted to a window `size` pixels big; 1.0 otherwise.
+    pub fn grid_scale(&self, size: [u32; 2]) -> f32 {
+        match self.resize.committed() {
+            Some(grid) if self.resize.is_pending() => resize::interim_scale(grid, &self.layout_for(size)),
+            _ => 1.0,
+        }
+    }
+
+    /// Size the PTY to the grid that fits the window now, unless it has
+    /// it already; the scroll position is put back once the emulator
+    /// follows (see `poll_redraws`).
+    pub(crate) fn resize_grid(&mut self) {
+        let scroll = self.viewport.mark();
+        let Some((cols, rows)) = self.window_grid().and_then(|grid| self.resize.commit(grid, scroll)) else {
+            return;
+        };
+        self.set_screen_size(cols as usize, rows as usize);
+        if let Some(engine) = &self.engine {
+            let engine = engine.clone();
+            self.rt.spawn(async move {
+                let _ = engine.resize(cols, rows).await;
+            });
+        }
+    }
+
+    /// `!theme <name>`: switch now and persist it. While `theme.mode`
+    /// picks the theme, this only overrides it until the next switch;
+    /// `!theme auto` hands control back (turning `theme.mode` on if unset).
+    fn set_theme(&mut self, name: &str) {
+        if name.eq_ignore_ascii_case("auto") {
+            self.resume_theme_sync();
+            return;
+        }
+        let Some(theme) = ThemeName::from_str(name) else {
+            let names: Vec<&str> = ThemeName::all().iter().map(|t| t.label()).collect();
+            self.push_direct(&format!("❌ Unknown theme '{}' (one of {}, auto)", name, names.join(", ")));
+            return;
+        };
+        self.theme_name = theme;
+        if self.theme_switcher.sync().is_some() {
+            let now = chrono::Local::now().naive_local();
+            self.theme_switcher.set_manual(theme, now);
+            let until = match self.theme_switcher.next_change(now) {
+                Some(at) => format!(" until {}", at.format("%H:%M")),
+                None if self.theme_switcher.follows_os() => " until the OS switches".to_string(),
+                None => String::new(),
+            };
+            self.push_direct(&format!("🎨 Theme: {}{} (!theme auto to resume)", theme.label(), until));
+            return;
+        }
+        self.push_direct(&format!("🎨 Theme: {}", theme.label()));
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let vault = engine.runner.vault().clone();
+        if let Err(e) = vault.set_config(THEME_KEY, theme.label()) {
+            self.push_direct(&format!("⚠️ Theme not saved: {}", e));
+        } else if vault.is_overridden(THEME_KEY).unwrap_or(false) {
+            self.push_direct(&format!(
+                "  Profile '{}' sets its own theme; !profile save {} to keep this one",
+                self.active_profile.clone().unwrap_or_default(),
+                self.active_profile.clone().unwrap_or_default()
+            ));
+        }
+    }
+
+    /// `!theme auto`: drop a manual override, or start following the OS
+    /// if no `theme.mode` is set.
+    fn resume_theme_sync(&mut self) {
+        if self.theme_switcher.sync().is_none() {
+            let Some(engine) = &self.engine else {
+                return;
+            };
+            let vault = engine.runner.vault().clone();
+            if let Err(e) = vault.set_config(theme_sync::MODE_KEY, "auto") {
+                self.push_direct(&format!("⚠️ Theme mode not saved: {}", e));
+                return;
+            }
+            self.reload_settings();
+        }
+        self.theme_switcher.resume();
+        self.tick_theme();
+        self.theme_note = None;
+        let mode = self.theme_switcher.sync().map(|sync| sync.mode.to_string()).unwrap_or_default();
+        self.push_direct(&format!("🎨 Theme: {} ({} mode)", self.theme_name.label(), mode));
+    }
+
+    /// The window system reported the OS appearance (at startup or on a
+    /// change); `None` where it can't tell.
+    pub fn os_theme_changed(&mut self, theme: Option<Theme>) {
+        self.os_theme_reported |= theme.is_some();
+        self.theme_switcher.set_os(theme.map(|theme| match theme {
+            Theme::Light => Appearance::Light,
+            Theme::Dark => Appearance::Dark,
+        }));
+        if self.tick_theme() {
+            self.request_redraw();
+        }
+    }
+
+    /// Apply what theme sync wants now, polling the OS appearance when it
+    /// is due; true when the status bar needs redrawing.
+    pub fn tick_theme(&mut self) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+        self.poll_os_theme();
+
+        let local = chrono::Local::now().naive_local();
+        if let Some(theme) = self.theme_switcher.theme(local)
+            && theme != self.theme_name
+        {
+            self.theme_name = theme;
+            let why = match self.theme_switcher.appearance(local) {
+                Some(appearance) if !self.theme_switcher.is_overridden() => appearance.label(),
+                _ => "manual",
+            };
+            self.theme_note = Some((format!("🎨 {} · {}", theme.label(), why), now + THEME_NOTE_DURATION));
+            changed = true;
+        }
+        if self.theme_note.as_ref().is_some_and(|(_, until)| now >= *until) {
+            self.theme_note = None;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Re-read the OS appearance if theme sync follows it, the window
+    /// system doesn't report it, and the last read is old enough.
+    fn poll_os_theme(&mut self) {
+        let now = Instant::now();
+        if self.theme_switcher.follows_os()
+            && !self.os_theme_reported
+            && self.os_theme_poll.is_none_or(|at| now >= at)
+        {
+            self.os_theme_poll = Some(now + theme_sync::OS_POLL_INTERVAL);
+            self.theme_switcher.set_os(platform::os_appearance());
+        }
+    }
+
+    /// The input cursor's shape and blink: DECSCUSR from the running
+    /// program, else `cursor.style` / `cursor.blink_ms`.
+    pub fn cursor_style(&self) -> (CursorShape, Option<Duration>) {
+        self.window_style.cursor(self.mode_tracker.snapshot().cursor_style)
+    }
+
+    // --- input drafts ---
+
+    /// Whether the input line is a draft worth watching: not a passphrase,
+    /// not a recalled history entry, and with a vault to write to.
+    fn drafting(&self) -> bool {
+        self.passphrase.is_none()
+            && self.history_cursor.is_none()
+            && self.engine.as_ref().is_some_and(|e| !e.runner.vault().is_locked())
+    }
+
+    fn tick_draft(&mut self) {
+        if !self.drafting() {
+            return;
+        }
+        let now = Instant::now();
+        self.draft.observe(&self.input, now);
+        let filter = &self.history_filter;
+        if let Some(write) = self.draft.poll(now, |text| draft::worth_keeping(text, filter)) {
+            self.write_draft(write);
+        }
+    }
+
+    fn draft_wake(&self) -> Option<Instant> {
+        self.draft.wake().filter(|_| self.drafting())
+    }
+
+    fn write_draft(&self, write: DraftWrite) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let vault = engine.runner.vault();
+        if vault.is_inspect() {
+            return;
+        }
+        let result = match write {
+            DraftWrite::Save(text) => vault.save_draft(&text),
+            DraftWrite::Clear => vault.clear_draft(),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Checkpointing the input draft failed: {}", e);
+        }
+    }
+
+    /// At startup: offer the draft a crashed session left behind.
+    fn offer_draft(&mut self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let vault = engine.runner.vault();
+        if let Ok(Some(draft)) = vault.draft()
+            && draft.session_id != vault.session_id()
+        {
+            self.pending_draft = Some(draft.text);
+            self.push_direct(draft::RESTORE_NOTICE);
+        }
+    }
+
+    /// At startup: offer to carry on a `!queue` run that didn't finish.
+    fn offer_queue_resume(&mut self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let vault = engine.runner.vault();
+        if let (Ok(Some(progress)), Ok(commands)) = (vault.queue_progress(), vault.queue())
+            && let Some(offer) = queue::resume_offer(&commands, &progress)
+        {
+            self.push_direct(&offer);
+        }
+    }
+
+    /// Load the offered draft; from here on it is this session's own.
+    fn restore_draft(&mut self, text: String) {
+        if let Some(engine) = &self.engine
+            && let Err(e) = engine.runner.vault().discard_draft()
+        {
+            tracing::warn!("Dropping the restored draft failed: {}", e);
+        }
+        self.cursor_pos = text.chars().count();
+        self.input = text;
+        self.input_ai_generated = false;
+    }
+
+    fn blink_wake(&self) -> Option<Instant> {
+        self.blink.next_toggle(self.cursor_style().1, Instant::now())
+    }
+
+    /// When theme sync next needs a wake.
+    fn theme_wake(&self) -> Option<Instant> {
+        let now = Instant::now();
+        let local = chrono::Local::now().naive_local();
+        let boundary = self
+            .theme_switcher
+            .next_change(local)
+            .and_then(|at| (at - local).to_std().ok())
+            .map(|wait| now + wait);
+        let poll = self.os_theme_poll.filter(|_| self.theme_switcher.follows_os() && !self.os_theme_reported);
+        let note = self.theme_note.as_ref().map(|(_, until)| *until);
+        [boundary, poll, note].into_iter().flatten().min()
+    }
+
+    /// `!timestamps on|off|relative`: switch the block gutter and persist
+    /// it; with no argument, show the current mode.
+    fn set_timestamps(&mut self, arg: &str) {
+        let current = self.span_cache.block_style().timestamps;
+        if arg.is_empty() {
+            self.push_direct(&format!("🕒 Timestamps: {} (!timestamps on|off|relative)", current.label()));
+            return;
+        }
+        let Some(mode) = TimestampMode::parse(arg) else {
+            self.push_direct("Usage: !timestamps on|off|relative");
+            return;
+        };
+        let style = BlockStyle { timestamps: mode, ..self.span_cache.block_style() };
+        self.span_cache.set_block_style(style);
+        self.push_direct(&format!("🕒 Timestamps: {}", mode.label()));
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        if let Err(e) = engine.runner.vault().set_config(renderer::TIMESTAMPS_KEY, mode.label()) {
+            self.push_direct(&format!("⚠️ Timestamp mode not saved: {}", e));
+        }
+    }
+
+    /// `!bell`, `!bell test`, `!bell mute <duration>`, `!bell unmute`.
+    fn bell_command(&mut self, args: &str) {
+        let now = Instant::now();
+        match BellCommand::parse(args) {
+            Ok(BellCommand::Status) => self.show_direct_lines(bell::status_lines(&self.bell_policy, &self.bell_mute, now)),
+            Ok(BellCommand::Test) => {
+                let (action, rule) = bell::resolve(&self.bell_policy, self.bell_context(now));
+                bell::ring(self, action, 1);
+                self.push_direct(&format!("🔔 Bell test: {} ({})", action.label(), rule.reason()));
+            }
+            Ok(BellCommand::Mute(length)) => {
+                self.bell_mute.start(length, now, chrono::Local::now().time());
+                let at = self.bell_mute.ends_at(now).unwrap_or_default();
+                self.push_direct(&format!("🔕 Bells muted until {} (!bell unmute ends it early)", at));
+            }
+            Ok(BellCommand::Unmute) => {
+                self.bell_mute.stop();
+                self.push_direct("🔔 Bells back on");
+            }
+            Err(message) => self.push_direct(&message),
+        }
+    }
+
+    /// `!present`, `!present on`, `!present off`. Only what is drawn,
+    /// copied, recorded or saved changes, so turning it off is instant.
+    fn present_command(&mut self, args: &str) {
+        match PresentCommand::parse(args) {
+            Ok(PresentCommand::Status) => self.push_direct(match self.presenting {
+                Some(_) => "📽 Presenting: secrets and names are hidden on screen (!present off)",
+                None => "📽 Not presenting (!present on)",
+            }),
+            Ok(PresentCommand::On) => {
+                self.presenting = Some(Redactor::new(&self.present_settings, &Identity::current()));
+                let copies = if self.present_settings.copy_redacted { "redacted" } else { "not redacted" };
+                self.push_direct(&format!(
+                    "📽 Presenting: secrets, user and host names and {} matches are hidden; copies are {} (!present off)",
+                    present::REDACT_KEY,
+                    copies
+                ));
+            }
+            Ok(PresentCommand::Off) => {
+                self.presenting = None;
+                self.push_direct("📽 Presentation mode off");
+            }
+            Err(message) => self.push_direct(&message),
+        }
+        let title = self.window_title();
+        if let Some(window) = &self.window {
+            window.set_title(&title);
+        }
+        self.request_redraw();
+    }
+
+    /// The window title, redacted while presenting.
+    fn window_title(&mut self) -> String {
+        let title = match &self.inspect {
+            Some(path) => format!("Positronic /// Inspecting {} (read-only)", inspect::label(path)),
+            None => "Positronic /// Data Surface".to_string(),
+        };
+        match &mut self.presenting {
+            Some(redactor) => redactor.redact_text(&title),
+            None => title,
+        }
+    }
+
+    /// What the bell policy decides on.
+    fn bell_context(&self, now: Instant) -> BellContext {
+        let running = self.engine.as_ref().and_then(|engine| engine.running_command());
+        BellContext {
+            focused: self.marks.attention.focused(),
+            remote: running.is_some_and(|info| bell::is_remote(&info.command)),
+            follow: self.follow.is_some(),
+            muted: self.bell_mute.active(now),
+        }
+    }
+
+    /// Act on a gathered run of bells, and end a flash or a mute that has
+    /// run out; true if the status bar changed.
+    fn tick_bell(&mut self) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+        if let Some(count) = self.bells.take_due(now) {
+            let (action, _) = bell::resolve(&self.bell_policy, self.bell_context(now));
+            bell::ring(self, action, count);
+            changed = true;
+        }
+        if self.bell_flash.is_some_and(|until| now >= until) {
+            self.bell_flash = None;
+            changed = true;
+        }
+        changed | self.bell_mute.take_expired(now)
+    }
+
+    fn bell_wakes(&self) -> [Option<Instant>; 3] {
+        [self.bells.next_wake(), self.bell_flash, self.bell_mute.next_wake(Instant::now())]
+    }
+
+    /// A normal boot that has stayed up `STABLE_AFTER` isn't a crashed
+    /// one: clear the count.
+    fn tick_boot_marker(&mut self) {
+        if self.boot_stable_at.is_some_and(|at| Instant::now() >= at) {
+            self.boot_stable_at = None;
+            if let Err(e) = self.boot_marker.stable() {
+                tracing::warn!("Clearing {} failed: {}", self.boot_marker.path().display(), e);
+            }
+        }
+    }
+
+    /// `!profile list | save | use | rm`.
+    fn profile_command(&mut self, cmd: &str) {
+        let command = match ProfileCommand::parse(cmd) {
+            Ok(command) => command,
+            Err(message) => {
+                self.push_direct(&message);
+                return;
+            }
+        };
+        let Some(engine) = &self.engine else {
+            self.push_direct("⚠️  Engine not ready yet");
+            return;
+        };
+        let vault = engine.runner.vault().clone();
+
+        let result: anyhow::Result<()> = match command {
+            ProfileCommand::List => vault.list_profiles().map_err(Into::into).map(|profiles| {
+                if profiles.is_empty() {
+                    self.push_direct("No profiles yet. !profile save <name> captures the current settings.");
+                    return;
+                }
+                let mut lines = vec!["👤 Profiles:".to_string()];
+                for p in &profiles {
+                    let marker = if self.active_profile.as_deref() == Some(p.name.as_str()) { "●" } else { " " };
+                    let keys: Vec<&str> = p.settings.iter().map(|(k, _)| k.as_str()).collect();
+                    lines.push(format!("  {} {:<16} {}", marker, p.name, keys.join(", ")));
+                }
+                self.push_direct(&lines.join("\n"));
+            }),
+            ProfileCommand::Save(name) => vault.save_profile(&name).map_err(Into::into).map(|count| {
+                self.push_direct(&format!("👤 Saved {} settings as profile '{}'", count, name));
+            }),
+            ProfileCommand::Remove(name) => vault.remove_profile(&name).map_err(Into::into).map(|removed| {
+                if !removed {
+                    self.push_direct(&format!("❌ No profile '{}'", name));
+                    return;
+                }
+                self.push_direct(&format!("👤 Removed profile '{}'", name));
+                if self.active_profile.as_deref() == Some(name.as_str()) {
+                    self.reload_settings();
+                }
+            }),
+            ProfileCommand::Use(target) => self.use_profile(&vault, target),
+        };
+        if let Err(e) = result {
+            self.push_direct(&format!("❌ Profile error: {}", e));
+        }
+    }
+
+    /// Check the target's settings first, then switch and apply them, so a
+    /// profile with an invalid value changes nothing.
+    fn use_profile(&mut self, vault: &Vault, target: ProfileTarget) -> anyhow::Result<()> {
+        let name = match target {
+            ProfileTarget::Named(name) => Some(name),
+            ProfileTarget::Base => None,
+            ProfileTarget::Previous => match vault.previous_profile()? {
+                Some(previous) => previous,
+                None => {
+                    self.push_direct("❌ No previous profile to return to");
+                    return Ok(());
+                }
+            },
+        };
+        let label = name.clone().unwrap_or_else(|| "base config".to_string());
+
+        let overrides = match &name {
+            Some(name) => match vault.get_profile(name)? {
+                Some(profile) => profile.settings,
+                None => {
+                    self.push_direct(&format!("❌ No profile '{}'", name));
+                    return Ok(());
+                }
+            },
+            None => Vec::new(),
+        };
+        let (settings, problems) = Settings::load(|key| {
+            overrides
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+                .or_else(|| vault.get_base_config(key).ok().flatten())
+        });
+        if !problems.is_empty() {
+            let mut lines = vec![format!("❌ '{}' not applied; nothing changed:", label)];
+            lines.extend(problems.iter().map(|p| format!("  {}", p)));
+            self.push_direct(&lines.join("\n"));
+            return Ok(());
         }
+
+        if vault.use_profile(name.as_deref())?.is_none() {
+            self.push_direct(&format!("❌ No profile '{}'", label));
+            return Ok(());
+        }
+        self.active_profile = name;
+        self.apply_settings(settings);
+        self.push_direct(&format!("👤 Using {} (!profile use previous to undo)", label));
+        Ok(())
+    }
+
+    /// Announce subsystems that failed to start. The engine keeps running
+    /// without them; `!status` has the full picture.
+    pub fn poll_subsystems(&mut self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let failed: Vec<(&'static str, String)> = engine
+            .subsystems()
+            .snapshot()
+            .into_iter()
+            .filter(|s| !self.reported_subsystems.contains(&s.name))
+            .filter_map(|s| match s.state {
+                SubsystemState::Failed(reason) => Some((s.name, reason)),
+                _ => None,
+            })
+            .collect();
+        for (name, reason) in failed {
+            self.reported_subsystems.push(name);
+            self.push_direct(&format!("⚠️ {} unavailable: {}", name, reason));
+        }
+    }
+}
+
+/// Macro `name`'s steps, or what to say instead.
+fn load_macro(vault: &Vault, name: &str) -> Result<Vec<Step>, String> {
+    match vault.get_macro(name) {
+        Ok(Some(saved)) => macros::decode(&saved.steps).map_err(|e| format!("❌ Macro {}: {}", name, e)),
+        Ok(None) => Err(format!("⌨ No macro called {}", name)),
+        Err(e) => Err(format!("❌ Reading macro {} failed: {}", name, e)),
     }
 }
 
-pub fn run() -> anyhow::Result<()> {
-    tracing::info!("Positronic v0.3.0 starting...");
+/// Size the viewport to the emulator's scrollback and point the emulator
+/// at its offset; the next snapshot shows that part.
+fn sync_scrollback(viewport: &mut Viewport, engine: &PositronicEngine) {
+    let (_, rows) = engine.state.size();
+    viewport.set_extent(engine.state.history_size() + rows as usize, rows as usize);
+    engine.state.set_display_offset(viewport.offset());
+}
+
+pub fn run(renderer_choice: RendererChoice, safe_mode: bool, data: DataPaths, inspect: Option<PathBuf>) -> anyhow::Result<()> {
+    tracing::info!("Positronic v{} starting...", crate::version::BUILD.version);
 
     let rt = tokio::runtime::Runtime::new()?;
     let rt_handle = rt.handle().clone();
@@ -515,6 +4734,10 @@ pub fn run() -> anyhow::Result<()> {
     let app = PositronicApp {
         window: None,
         gpu: None,
+        software: None,
+        renderer_choice,
+        render_report: None,
+        recovery: DeviceRecovery::default(),
         engine: None,
         redraw_rx: None,
         cmd_result_tx,
@@ -523,16 +4746,107 @@ pub fn run() -> anyhow::Result<()> {
         direct_output: String::new(),
         last_snapshot: None,
         last_screen_hash: 0,
+        span_cache: SpanCache::new(),
+        viewport: Viewport::default(),
+        marks: ScreenMarks::default(),
+        jump_list: JumpList::default(),
+        marks_persist: false,
+        block_link: None,
         input: String::new(),
         cursor_pos: 0,
         composing: false,
+        input_ai_generated: false,
         cmd_history: Vec::new(),
         history_cursor: None,
+        history_stash: String::new(),
+        history_prefix: None,
+        history_matches: Vec::new(),
+        draft: DraftKeeper::default(),
+        pending_draft: None,
+        completion: None,
+        executables: Arc::new(PathIndex::from_env()),
+        highlighter: Highlighter::new(),
+        git_completer: GitCompleter::new(),
+        suggestions: None,
+        redo_offer: None,
+        correction: None,
+        timeline: None,
+        clipboard_history: ClipboardHistory::default(),
+        clipboard_picker: None,
+        pager: None,
+        pager_threshold: PagerThreshold::Screen,
+        compare: None,
+        key_help: None,
+        file_finder: None,
+        last_view: None,
+        screen_cols: DEFAULT_SCREEN_COLS,
+        screen_rows: DEFAULT_SCREEN_ROWS,
+        resize: ResizeDebounce::default(),
+        recording: None,
+        replay: None,
+        macro_recorder: None,
+        macro_player: None,
+        macro_delay: macros::DEFAULT_DELAY,
+        password_prompt: false,
+        passphrase: None,
+        shell_exit: None,
+        replay_started: Instant::now(),
+        running_status: None,
+        running_wake: None,
+        tasks_status: None,
+        tasks_wake: None,
+        time_settings: TimeSettings::default(),
+        summary_settings: SummarySettings::default(),
+        last_command_at: None,
+        last_command_status: None,
+        last_command_wake: None,
+        window_style: WindowStyle::default(),
+        layout_spec: LayoutSpec::default(),
+        blink: Blink::new(Instant::now()),
+        cursor_shown: true,
+        calc_hint: false,
+        directory_hints: DirectoryHints::default(),
+        history_filter: HistoryFilter::default(),
+        holodeck_native: true,
+        suggest_rm: false,
+        bell_policy: BellPolicy::default(),
+        bells: Coalescer::default(),
+        bell_mute: Mute::default(),
+        present_settings: PresentSettings::default(),
+        presenting: None,
+        bell_flash: None,
+        safe_mode,
+        safe_mode_asked: false,
+        inspect,
+        boot_marker: BootMarker::at(data.boot_marker()),
+        data,
+        boot_stable_at: None,
         session_cmd_count: 0,
         boot_instant: Instant::now(),
+        reported_subsystems: Vec::new(),
         cwd,
         theme_name: ThemeName::Default,
+        theme_switcher: ThemeSwitcher::default(),
+        theme_note: None,
+        key_note: None,
+        error_center: ErrorCenter::default(),
+        os_theme_reported: false,
+        os_theme_poll: None,
+        active_profile: None,
+        env_overrides: 0,
+        perf_overlay: false,
+        governor: Governor::new(PowerConfig::default(), Instant::now()),
+        output_pending: None,
+        hardware: HardwarePanel::new(),
+        scope: None,
+        console: None,
+        follow: None,
+        follow_process: None,
+        diagnostics: Vec::new(),
+        diagnostics_dir: String::new(),
         modifiers: ModifiersState::empty(),
+        keymap: Keymap::default(),
+        completion_ignore_case: false,
         wants_exit: false,
         rt: rt_handle,
 
@@ -541,12 +4855,48 @@ pub fn run() -> anyhow::Result<()> {
         semantic: SemanticState::new(),
         holodeck_doc: None,
         holodeck_safe: false,
+        sessions: Sessions::new(),
+        detectors: Detectors::default(),
+        search_warmed: false,
+        biolink: BioLink::new(),
 
         last_mouse_x: 0.0,
         last_mouse_y: 0.0,
+        selection: None,
+        selecting: false,
     };
 
+    let executables = app.executables.clone();
+    app.rt.spawn_blocking(move || {
+        let count = executables.refresh(true);
+        tracing::info!("Indexed {} executables on PATH", count);
+    });
+
     let app = Box::leak(Box::new(app));
     event_loop.run_app(app)?;
     Ok(())
 }
+
+/// A bell's action, carried out (see `bell`).
+impl BellSink for PositronicApp {
+    fn sound(&mut self) {
+        if let Err(e) = platform::play_bell() {
+            tracing::warn!("Bell sound failed: {}", e);
+        }
+    }
+
+    fn flash(&mut self) {
+        self.bell_flash = Some(Instant::now() + bell::FLASH_DURATION);
+        self.request_redraw();
+    }
+
+    fn notify(&mut self, text: &str) {
+        if let Err(e) = platform::notify_desktop("Positronic", &format!("🔔 {}", text)) {
+            tracing::warn!("Desktop notification failed: {}", e);
+        }
+    }
+
+    fn announce(&mut self, text: &str) {
+        self.biolink.announce(BioLinkEvent::Announcement(text.to_string()));
+    }
+}
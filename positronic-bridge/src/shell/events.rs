@@ -1,8 +1,18 @@
+use std::time::Instant;
+
 use winit::event::{ElementState, MouseButton, WindowEvent};
 use winit::event_loop::ActiveEventLoop;
-use winit::keyboard::{Key, NamedKey};
+use winit::keyboard::{Key, ModifiersState, NamedKey};
 
 use super::app::PositronicApp;
+use crate::keymap::{self, Chord, KeyName};
+use crate::macros::Step;
+use crate::pager::PagerKey;
+use crate::soft_frame;
+use crate::viewport::alt_screen_sequence;
+
+/// Software frames: light grey text.
+const SOFTWARE_FG: u32 = 0x00D0_D0D0;
 
 pub fn handle_window_event(
     app: &mut PositronicApp,
@@ -12,6 +22,7 @@ pub fn handle_window_event(
     match event {
         WindowEvent::CloseRequested => {
             tracing::info!("Window close requested");
+            app.end_session();
             event_loop.exit();
         }
 
@@ -23,20 +34,28 @@ pub fn handle_window_event(
             if let Some(gpu) = &mut app.gpu {
                 gpu.resize(new_size);
             }
+            if let Some(software) = &mut app.software {
+                software.resize(new_size);
+            }
 
-            if new_size.width > 0 && new_size.height > 0 {
-                let (cell_w, cell_h) = (8.0f32, 18.0f32);
-                let cols = ((new_size.width as f32 - 20.0) / cell_w).max(40.0) as u16;
-                let rows = ((new_size.height as f32 - 60.0) / cell_h).max(10.0) as u16;
+            // The surface follows every event; the PTY once the drag stops.
+            app.resize_later();
 
-                if let Some(engine) = &app.engine {
-                    let engine = engine.clone();
-                    app.rt.spawn(async move {
-                        let _ = engine.resize(cols, rows).await;
-                    });
-                }
-            }
+            app.request_redraw();
+        }
 
+        WindowEvent::ScaleFactorChanged { .. } => {
+            // New cell metrics: the settled grid is worked out in them.
+            app.resize_later();
+            app.request_redraw();
+        }
+
+        WindowEvent::ThemeChanged(theme) => {
+            app.os_theme_changed(Some(theme));
+        }
+
+        WindowEvent::Focused(focused) => {
+            app.focus_changed(focused);
             app.request_redraw();
         }
 
@@ -47,10 +66,38 @@ pub fn handle_window_event(
         WindowEvent::CursorMoved { position, .. } => {
             app.last_mouse_x = position.x as f32;
             app.last_mouse_y = position.y as f32;
+            if app.select_drag() {
+                app.request_redraw();
+            }
         }
 
         WindowEvent::MouseInput { state, button, .. } => {
+            app.note_activity();
             if state == ElementState::Pressed && button == MouseButton::Left {
+                if app.viewport.hit_pill(app.last_mouse_x, app.last_mouse_y) {
+                    app.scroll(keymap::Action::ScrollBottom);
+                    app.request_redraw();
+                    return;
+                }
+                let picked = app
+                    .suggestions
+                    .as_ref()
+                    .and_then(|p| p.hit(app.last_mouse_x, app.last_mouse_y));
+                if let Some(index) = picked {
+                    app.accept_suggestion(index);
+                    app.request_redraw();
+                    return;
+                }
+                let entry = app
+                    .clipboard_picker
+                    .as_ref()
+                    .and_then(|p| p.hit(app.last_mouse_x, app.last_mouse_y));
+                if let Some(n) = entry {
+                    app.insert_clipboard_entry(n);
+                    app.request_redraw();
+                    return;
+                }
+
                 // Click Holodeck buttons if visible
                 if app.holodeck_safe {
                     if let Some(doc) = &app.holodeck_doc {
@@ -61,9 +108,27 @@ pub fn handle_window_event(
                         ) {
                             app.apply_holodeck_action(action);
                             app.request_redraw();
+                            return;
                         }
                     }
                 }
+
+                // A click on a `!timeline` bar shows that command in history.
+                if app.timeline_click() {
+                    app.request_redraw();
+                    return;
+                }
+
+                // Anywhere else on the screen text starts a selection.
+                if app.select_start() {
+                    app.request_redraw();
+                }
+            } else if state == ElementState::Released && button == MouseButton::Left {
+                // A click, not a drag, on a block's command line takes the
+                // command back to edit; Shift runs it again.
+                if app.select_end() && app.block_click(app.modifiers.shift_key()) {
+                    app.request_redraw();
+                }
             }
         }
 
@@ -71,53 +136,192 @@ pub fn handle_window_event(
             if event.state != ElementState::Pressed {
                 return;
             }
+            app.blink.restart(Instant::now());
+            app.note_activity();
 
             let mods = app.modifiers;
             let ctrl = mods.control_key();
-            let shift = mods.shift_key();
 
-            match event.logical_key.as_ref() {
-                // Ctrl+Shift+C = copy snapshot
-                Key::Character("c") if ctrl && shift => {
-                    app.copy_visible_to_clipboard();
-                }
+            // Escape stops a playing macro before anything else sees it.
+            if app.macro_player.is_some() && matches!(event.logical_key.as_ref(), Key::Named(NamedKey::Escape)) {
+                app.stop_macro();
+                return;
+            }
 
-                // Ctrl+C interrupt
-                Key::Character("c") if ctrl => app.send_interrupt(),
+            // A replay is read-only: q or Escape closes it, nothing else.
+            if app.replay.is_some() {
+                if matches!(pager_key(&event.logical_key), Some(PagerKey::Escape | PagerKey::Char('q'))) {
+                    app.replay = None;
+                    app.request_redraw();
+                }
+                return;
+            }
 
-                Key::Character("d") if ctrl => app.send_eof(),
+            // An open pager takes every key, shortcuts included.
+            if app.pager.is_some() {
+                if let Some(key) = pager_key(&event.logical_key) {
+                    app.pager_key(key);
+                    app.request_redraw();
+                }
+                return;
+            }
 
-                Key::Character("l") if ctrl => {
-                    app.direct_output.clear();
-                    app.last_snapshot = None;
+            // So does `!compare`, with the pager's keys.
+            if app.compare.is_some() {
+                if let Some(key) = pager_key(&event.logical_key) {
+                    app.compare_key(key);
                     app.request_redraw();
                 }
+                return;
+            }
 
-                Key::Named(NamedKey::Escape) => app.send_escape(),
+            // The key reference takes every key: a chord lights its row
+            // instead of running (see `key_help`).
+            if app.key_help.is_some() {
+                let text = match event.logical_key.as_ref() {
+                    Key::Character(c) => Some(c),
+                    Key::Named(NamedKey::Space) => Some(" "),
+                    _ => None,
+                };
+                app.key_help_key(key_chord(&event.logical_key, mods), text);
+                app.request_redraw();
+                return;
+            }
+
+            // So does the file finder: typing searches, and a second key
+            // after Enter picks what to do with the file.
+            if app.file_finder.is_some() {
+                let text = match event.logical_key.as_ref() {
+                    Key::Character(c) => Some(c),
+                    Key::Named(NamedKey::Space) => Some(" "),
+                    _ => None,
+                };
+                app.file_finder_key(key_chord(&event.logical_key, mods), text);
+                app.request_redraw();
+                return;
+            }
+
+            // An open `!io console` sends keys to its device; only the exit,
+            // copy, paste and search chords and paging stay here.
+            if app.console.is_some() {
+                let text = match event.logical_key.as_ref() {
+                    Key::Character(c) => Some(c),
+                    Key::Named(NamedKey::Space) => Some(" "),
+                    _ => None,
+                };
+                app.console_key(key_chord(&event.logical_key, mods), text);
+                app.request_redraw();
+                return;
+            }
+
+            // An open `!follow` pane takes every key; the interrupt chord
+            // stops the command (see `FollowState::key`).
+            if app.follow.is_some() {
+                let text = match event.logical_key.as_ref() {
+                    Key::Character(c) => Some(c),
+                    Key::Named(NamedKey::Space) => Some(" "),
+                    _ => None,
+                };
+                app.follow_key(key_chord(&event.logical_key, mods), text);
+                app.request_redraw();
+                return;
+            }
 
+            // While the scope is open, + and - on an empty input zoom it.
+            if app.scope.is_some()
+                && app.input.is_empty()
+                && !ctrl
+                && let Key::Character(c) = event.logical_key.as_ref()
+                && app.scope_key(c)
+            {
+                app.request_redraw();
+                return;
+            }
+
+            // An open `!redo` prompt takes a, b or c on an empty input.
+            if app.redo_offer.is_some()
+                && app.input.is_empty()
+                && !ctrl
+                && let Key::Character(c) = event.logical_key.as_ref()
+                && app.redo_key(c)
+            {
+                app.request_redraw();
+                return;
+            }
+
+            // Full-screen apps page themselves: PageUp and PageDown, Shift
+            // or not, and Ctrl+Home/End go to the app, not the scrollback.
+            let chord = key_chord(&event.logical_key, mods);
+            if app.mode_tracker.snapshot().alt_screen
+                && let Some(Chord { ctrl, alt: false, shift, key: KeyName::Named(key) }) = chord
+                && let Some(sequence) = alt_screen_sequence(key, ctrl, shift)
+            {
+                app.send_raw(sequence);
+                return;
+            }
+
+            // Configurable shortcuts first (see `keymap`).
+            let action = chord.and_then(|c| app.keymap.action_for(&c));
+            if let Some(action) = action {
+                app.record_step(Step::Action(action));
+                app.run_action(action);
+                return;
+            }
+
+            match event.logical_key.as_ref() {
+                Key::Named(NamedKey::Escape) => {
+                    app.escape();
+                    app.request_redraw();
+                }
+
+                // Enter in the clipboard picker is a plain paste.
+                Key::Named(NamedKey::Enter) if app.clipboard_picker.is_some() => {
+                    app.clipboard_picker = None;
+                    app.paste_from_clipboard();
+                    app.request_redraw();
+                }
+                // Enter on an empty input takes an open correction.
+                Key::Named(NamedKey::Enter) if app.correction.is_some() && app.input.is_empty() => {
+                    app.accept_correction();
+                    app.request_redraw();
+                }
                 Key::Named(NamedKey::Enter) => {
+                    app.record_step(Step::Submit);
                     app.submit_command();
                     app.request_redraw();
                 }
 
-                Key::Named(NamedKey::Backspace) => app.input_backspace(),
-                Key::Named(NamedKey::Delete) => app.input_delete(),
-
-                Key::Named(NamedKey::ArrowLeft) => app.input_left(),
-                Key::Named(NamedKey::ArrowRight) => app.input_right(),
-
-                Key::Named(NamedKey::ArrowUp) => app.history_up(),
-                Key::Named(NamedKey::ArrowDown) => app.history_down(),
-
-                Key::Named(NamedKey::Home) => app.input_home(),
-                Key::Named(NamedKey::End) => app.input_end(),
+                Key::Named(NamedKey::Backspace) => {
+                    app.record_step(Step::Backspace);
+                    app.input_backspace();
+                }
+                Key::Named(NamedKey::Delete) => {
+                    app.record_step(Step::Delete);
+                    app.input_delete();
+                }
 
-                Key::Named(NamedKey::Tab) => {
-                    // TODO: integrate with completer
+                // Digit keys pick from an open clipboard picker, or from a
+                // suggestion list while the input is empty; otherwise they
+                // type as usual.
+                Key::Character(c) if !ctrl && app.clipboard_entry_for_key(c).is_some() => {
+                    if let Some(n) = app.clipboard_entry_for_key(c) {
+                        app.insert_clipboard_entry(n);
+                    }
+                    app.request_redraw();
+                }
+                Key::Character(c) if !ctrl && app.input.is_empty() && app.suggestion_for_key(c).is_some() => {
+                    if let Some(index) = app.suggestion_for_key(c) {
+                        app.accept_suggestion(index);
+                    }
                     app.request_redraw();
                 }
 
                 Key::Character(c) if !ctrl => {
+                    if let Some(engine) = &app.engine {
+                        engine.latency.key(Instant::now());
+                    }
+                    app.clipboard_picker = None;
+                    app.record_step(Step::Insert(c.to_string()));
                     app.input_insert(c);
                     app.request_redraw();
                 }
@@ -127,26 +331,91 @@ pub fn handle_window_event(
 
         WindowEvent::RedrawRequested => {
             app.check_engine_ready();
+            app.poll_subsystems();
 
             app.poll_redraws();
             app.poll_cmd_results();
+            let replaying = app.tick_replay();
+            let (cursor_shape, blink) = app.cursor_style();
+            let cursor_visible = app.blink.visible(blink, Instant::now());
+            app.cursor_shown = cursor_visible;
+            let padding = app.window_style.padding;
+            let layout_spec = app.layout_spec;
+            let grid_scale = app.gpu.as_ref().map_or(1.0, |gpu| app.grid_scale([gpu.size.width, gpu.size.height]));
 
             if let Some(gpu) = &mut app.gpu {
                 let theme = app.theme_name;
                 let clear = theme.bg_color();
 
-                let snapshot = app.last_snapshot.clone();
-                let direct = app.direct_output.clone();
-                let input_text = app.input.clone();
+                let mut snapshot = app.last_snapshot.clone();
+                let mut direct = app.direct_output.clone();
+                // A passphrase is drawn as bullets, with the prompt as placeholder.
+                let input_text = match &app.passphrase {
+                    Some(_) => crate::passphrase::mask(&app.input),
+                    None => app.input.clone(),
+                };
+                let placeholder = app.passphrase.as_ref().map(|p| p.label());
                 let cursor = app.cursor_pos;
+                let input_highlights = app.input_highlights();
+                let input_ai_generated = app.input_ai_generated;
+                let input_hint = app.input_hint();
                 let state = app.state.clone();
                 let cmd_count = app.session_cmd_count;
                 let boot = app.boot_instant;
-                let cwd = app.cwd.clone();
+                let mut cwd = app.cwd.clone();
+                let profile = app.active_profile.clone();
+                let env_badge = positronic_core::setenv::badge(app.env_overrides);
+                let recording = app.recording_label();
+                let running = app.running_status.clone();
+                let tasks = app.tasks_status.clone();
+                let last_command = app.last_command_status.clone();
+                let theme_note = app.theme_note.as_ref().map(|(note, _)| note.clone());
+                let key_note = app.key_note.as_ref().map(|(note, _)| note.clone());
+                let error_status = app.error_center.status(Instant::now()).map(|s| (s.label(), s.severity));
+                let unread = app.marks.attention.label();
+                let bell_muted = app.bell_mute.label(Instant::now());
+                let bell_flash = app.bell_flash.is_some();
+                let safe_mode = app.safe_mode;
+                let inspect = app.inspect.as_deref().map(positronic_core::inspect::label);
+                let (unread_rows, dim_rows) = app.attention_rows();
+                let block_rows = app.block_rows();
+                let selection = app.selection.filter(|s| !s.is_empty());
+                let mut replay = app.replay.as_ref().map(|r| (r.snapshot(), r.footer()));
+                // Presenting: the copies drawn are redacted, the stored data isn't.
+                let presenting = app.presenting.is_some();
+                if let Some(redactor) = &mut app.presenting {
+                    if let Some(snap) = &mut snapshot {
+                        redactor.redact_snapshot(snap);
+                    }
+                    if let Some((snap, _)) = &mut replay {
+                        redactor.redact_snapshot(snap);
+                    }
+                    direct = redactor.redact_text(&direct);
+                    cwd = redactor.cwd(&cwd);
+                }
 
                 // Holodeck
                 let holodeck_safe = app.holodeck_safe;
                 let mut holodeck_doc = app.holodeck_doc.clone();
+                let perf = app.perf_overlay.then(|| crate::ui::perf::PerfStats {
+                    quads: gpu.quads.stats(),
+                    spans: app.span_cache.last_frame(),
+                    latency: app.engine.as_ref().map(|e| e.latency.summary()).unwrap_or_default(),
+                });
+                let mut span_cache = std::mem::take(&mut app.span_cache);
+                let mut viewport = std::mem::take(&mut app.viewport);
+                let mut suggestions = app.suggestions.take();
+                let mut clipboard_picker = app.clipboard_picker.take();
+                let pager = app.pager.take();
+                let compare = app.compare.take();
+                let key_help = app.key_help.as_ref().map(|h| h.view(&app.keymap, &app.mode_tracker.snapshot()));
+                let file_finder = app.file_finder.as_ref().map(|f| f.view());
+                let scope = app.scope.clone();
+                let console = app.console.take();
+                let console_exit = app.keymap.chord_for(keymap::Action::ConsoleExit);
+                let follow = app.follow.take();
+                let interrupt = app.keymap.chord_for(keymap::Action::Interrupt);
+                let hardware = std::mem::take(&mut app.hardware);
 
                 let result = gpu.render_frame(clear, |quads, text, _device, _queue, viewport| {
                     crate::ui::scene::compose(
@@ -158,26 +427,191 @@ pub fn handle_window_event(
                             snapshot: snapshot.as_ref(),
                             direct_output: &direct,
                             input: &input_text,
+                            input_placeholder: placeholder.as_deref(),
                             cursor_pos: cursor,
+                            cursor_shape,
+                            cursor_visible,
+                            padding,
+                            layout: layout_spec,
+                            input_highlights: &input_highlights,
+                            input_ai_generated,
+                            input_hint: input_hint.as_deref(),
                             theme,
                             session_cmd_count: cmd_count,
                             boot_instant: boot,
                             cwd: &cwd,
+                            profile: profile.as_deref(),
+                            env_badge: env_badge.as_deref(),
+                            span_cache: &mut span_cache,
+                            viewport: &mut viewport,
+                            perf,
+                            suggestions: suggestions.as_mut(),
+                            clipboard: clipboard_picker.as_mut(),
+                            scope: scope.as_ref().map(|view| (view, &hardware)),
+                            pager: pager.as_ref(),
+                            compare: compare.as_ref(),
+                            key_help: key_help.as_ref(),
+                            file_finder: file_finder.as_ref(),
+                            console: console.as_ref().map(|c| (c, console_exit.as_ref())),
+                            follow: follow.as_ref().map(|f| (f, interrupt.as_ref())),
+                            replay: replay.as_ref().map(|(snap, footer)| (snap, footer.as_str())),
+                            recording: recording.as_deref(),
+                            running: running.as_ref().map(|(label, slow)| (label.as_str(), *slow)),
+                            tasks: tasks.as_deref(),
+                            last_command: last_command.as_deref(),
+                            theme_note: theme_note.as_deref(),
+                            key_note: key_note.as_deref(),
+                            errors: error_status.as_ref().map(|(label, severity)| (label.as_str(), *severity)),
+                            unread: unread.as_deref(),
+                            bell_muted: bell_muted.as_deref(),
+                            bell_flash,
+                            safe_mode,
+                            presenting,
+                            inspect: inspect.as_deref(),
+                            unread_rows: &unread_rows,
+                            dim_rows,
+                            block_rows,
+                            selection,
                             holodeck_doc: holodeck_doc.as_mut(),
                             holodeck_safe,
+                            grid_scale,
                         },
                     );
                 });
 
-                if let Err(e) = result {
-                    tracing::error!("Render failed: {:#}", e);
+                match result {
+                    // Presented: whatever it shows has reached the screen.
+                    Ok(true) => {
+                        if let Some(engine) = &app.engine {
+                            engine.latency.presented(Instant::now());
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::error!("Render failed: {:#}", e),
                 }
 
                 // write back doc (it gets laid out during draw)
                 app.holodeck_doc = holodeck_doc;
+                app.span_cache = span_cache;
+                app.viewport = viewport;
+                app.suggestions = suggestions;
+                app.clipboard_picker = clipboard_picker;
+                app.pager = pager;
+                app.compare = compare;
+                app.console = console;
+                app.follow = follow;
+                app.hardware = hardware;
+            }
+
+            if let Some(software) = &mut app.software {
+                let mut snapshot = app.last_snapshot.clone();
+                let mut direct = app.direct_output.clone();
+                if let Some(redactor) = &mut app.presenting {
+                    if let Some(snap) = &mut snapshot {
+                        redactor.redact_snapshot(snap);
+                    }
+                    direct = redactor.redact_text(&direct);
+                }
+                let grid = snapshot.as_ref().map(crate::renderer::snapshot_to_plain).unwrap_or_default();
+                let input = match &app.passphrase {
+                    Some(_) => crate::passphrase::mask(&app.input),
+                    None => app.input.clone(),
+                };
+                let mut frame = software.frame(soft_frame::pack(app.theme_name.bg_color()));
+                let (_, rows) = frame.grid_size(soft_frame::SCALE);
+                let mut lines = match (&app.key_help, &app.file_finder) {
+                    (Some(help), _) => help.view(&app.keymap, &app.mode_tracker.snapshot()).plain(rows),
+                    (None, Some(finder)) => finder.view().plain(rows),
+                    (None, None) => soft_frame::screen_lines(&direct, &grid, &format!("> {}", input), rows),
+                };
+                if let Some(status) = app.error_center.status(Instant::now()) {
+                    soft_frame::set_notice(&mut lines, &status.plain());
+                }
+                frame.draw_lines(&lines, SOFTWARE_FG, soft_frame::SCALE);
+                if let Err(e) = software.present(&frame) {
+                    tracing::error!("Software render failed: {:#}", e);
+                }
+            }
+
+            // Keep frames coming until the replay has played out.
+            if replaying {
+                app.request_redraw();
             }
         }
 
         _ => {}
     }
 }
+
+/// The pager's view of a key event, or `None` for keys it ignores.
+fn pager_key(key: &Key) -> Option<PagerKey> {
+    Some(match key.as_ref() {
+        Key::Named(NamedKey::Space) => PagerKey::Space,
+        Key::Named(NamedKey::PageDown) => PagerKey::PageDown,
+        Key::Named(NamedKey::PageUp) => PagerKey::PageUp,
+        Key::Named(NamedKey::ArrowDown) => PagerKey::Down,
+        Key::Named(NamedKey::ArrowUp) => PagerKey::Up,
+        Key::Named(NamedKey::Home) => PagerKey::Char('g'),
+        Key::Named(NamedKey::End) => PagerKey::Char('G'),
+        Key::Named(NamedKey::Enter) => PagerKey::Enter,
+        Key::Named(NamedKey::Backspace) => PagerKey::Backspace,
+        Key::Named(NamedKey::Escape) => PagerKey::Escape,
+        Key::Named(NamedKey::Tab) => PagerKey::Tab,
+        Key::Character(" ") => PagerKey::Space,
+        Key::Character(text) => {
+            let mut chars = text.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            PagerKey::Char(c)
+        }
+        _ => return None,
+    })
+}
+
+/// The keymap chord for a key event, or `None` for keys that can't be bound.
+fn key_chord(key: &Key, mods: ModifiersState) -> Option<Chord> {
+    let name = match key.as_ref() {
+        Key::Character(text) => {
+            let mut chars = text.chars();
+            let c = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            KeyName::Char(c)
+        }
+        Key::Named(named) => KeyName::Named(match named {
+            NamedKey::Enter => keymap::NamedKey::Enter,
+            NamedKey::Tab => keymap::NamedKey::Tab,
+            NamedKey::Escape => keymap::NamedKey::Escape,
+            NamedKey::Backspace => keymap::NamedKey::Backspace,
+            NamedKey::Delete => keymap::NamedKey::Delete,
+            NamedKey::Insert => keymap::NamedKey::Insert,
+            NamedKey::Space => keymap::NamedKey::Space,
+            NamedKey::ArrowUp => keymap::NamedKey::Up,
+            NamedKey::ArrowDown => keymap::NamedKey::Down,
+            NamedKey::ArrowLeft => keymap::NamedKey::Left,
+            NamedKey::ArrowRight => keymap::NamedKey::Right,
+            NamedKey::Home => keymap::NamedKey::Home,
+            NamedKey::End => keymap::NamedKey::End,
+            NamedKey::PageUp => keymap::NamedKey::PageUp,
+            NamedKey::PageDown => keymap::NamedKey::PageDown,
+            NamedKey::F1 => keymap::NamedKey::F(1),
+            NamedKey::F2 => keymap::NamedKey::F(2),
+            NamedKey::F3 => keymap::NamedKey::F(3),
+            NamedKey::F4 => keymap::NamedKey::F(4),
+            NamedKey::F5 => keymap::NamedKey::F(5),
+            NamedKey::F6 => keymap::NamedKey::F(6),
+            NamedKey::F7 => keymap::NamedKey::F(7),
+            NamedKey::F8 => keymap::NamedKey::F(8),
+            NamedKey::F9 => keymap::NamedKey::F(9),
+            NamedKey::F10 => keymap::NamedKey::F(10),
+            NamedKey::F11 => keymap::NamedKey::F(11),
+            NamedKey::F12 => keymap::NamedKey::F(12),
+            _ => return None,
+        }),
+        _ => return None,
+    };
+    Some(Chord::from_key(name, mods.control_key(), mods.alt_key(), mods.shift_key()))
+}
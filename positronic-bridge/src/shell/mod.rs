@@ -1,4 +1,4 @@
-//! Application Shell — winit lifecycle, event dispatch, layout.
+//! Application Shell — winit lifecycle, event dispatch.
 //!
 //! The shell owns the winit event loop and the application state machine.
 //! It translates platform events into application actions, manages the
@@ -6,6 +6,5 @@
 
 pub mod app;
 pub(crate) mod events;
-pub mod layout;
 
 pub use app::run;
\ No newline at end of file
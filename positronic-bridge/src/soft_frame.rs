@@ -0,0 +1,215 @@
+// positronic-bridge/src/soft_frame.rs
+//! The software renderer's frame: monochrome text in a pixel buffer.
+//!
+//! When no GPU adapter can be opened the window is drawn with softbuffer,
+//! one `0x00RRGGBB` pixel per `u32`. This module fills that buffer: a
+//! built-in 5x7 bitmap font in 6x8 cells, scaled by a whole number, one
+//! foreground and one background color. No glyph cache, no shaping, no
+//! colors from the grid. Characters outside printable ASCII draw as `?`.
+
+use crate::renderer::Rgba;
+
+/// Cell size at scale 1: five columns of glyph plus a gap, eight rows
+/// (descenders use the eighth).
+pub const CELL_W: usize = 6;
+pub const CELL_H: usize = 8;
+
+/// The scale the window is drawn at: 12x16 cells.
+pub const SCALE: usize = 2;
+
+/// A frame the software renderer presents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftFrame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u32>,
+}
+
+impl SoftFrame {
+    pub fn new(width: usize, height: usize, background: u32) -> Self {
+        Self { width, height, pixels: vec![background; width * height] }
+    }
+
+    /// Columns and rows of text that fit at `scale`.
+    pub fn grid_size(&self, scale: usize) -> (usize, usize) {
+        let scale = scale.max(1);
+        (self.width / (CELL_W * scale), self.height / (CELL_H * scale))
+    }
+
+    /// Draw `text` from cell (`col`, `row`); whatever runs off the right
+    /// edge is dropped.
+    pub fn draw_text(&mut self, col: usize, row: usize, text: &str, color: u32, scale: usize) {
+        let scale = scale.max(1);
+        let (cols, rows) = self.grid_size(scale);
+        if row >= rows {
+            return;
+        }
+        for (i, c) in text.chars().enumerate() {
+            if col + i >= cols {
+                break;
+            }
+            self.draw_glyph((col + i) * CELL_W * scale, row * CELL_H * scale, c, color, scale);
+        }
+    }
+
+    fn draw_glyph(&mut self, x: usize, y: usize, c: char, color: u32, scale: usize) {
+        for (dx, column) in glyph(c).iter().enumerate() {
+            for dy in 0..CELL_H {
+                if column & (1 << dy) == 0 {
+                    continue;
+                }
+                for sy in 0..scale {
+                    let start = (y + dy * scale + sy) * self.width + x + dx * scale;
+                    self.pixels[start..start + scale].fill(color);
+                }
+            }
+        }
+    }
+
+    /// Draw `lines` top-down from the first row, one per row.
+    pub fn draw_lines(&mut self, lines: &[String], color: u32, scale: usize) {
+        for (row, line) in lines.iter().enumerate() {
+            self.draw_text(0, row, line, color, scale);
+        }
+    }
+}
+
+/// `0x00RRGGBB` for a color; alpha is ignored.
+pub fn pack(color: Rgba) -> u32 {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u32;
+    (channel(color.r) << 16) | (channel(color.g) << 8) | channel(color.b)
+}
+
+/// What the software renderer shows in `rows` rows: the tail of native
+/// output, then the shell grid, with the input line on the last row.
+pub fn screen_lines(direct_output: &str, grid: &str, prompt: &str, rows: usize) -> Vec<String> {
+    if rows == 0 {
+        return Vec::new();
+    }
+    let mut lines: Vec<String> = direct_output.lines().chain(grid.lines()).map(str::to_string).collect();
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    let keep = rows - 1;
+    if lines.len() > keep {
+        lines.drain(..lines.len() - keep);
+    }
+    lines.resize(keep, String::new());
+    lines.push(prompt.to_string());
+    lines
+}
+
+/// Put `notice` on the row above the prompt of `screen_lines`' output;
+/// with only the prompt row, it goes nowhere.
+pub fn set_notice(lines: &mut [String], notice: &str) {
+    if let [.., row, _prompt] = lines {
+        *row = notice.to_string();
+    }
+}
+
+/// The five columns of `c`, least significant bit at the top.
+fn glyph(c: char) -> &'static [u8; 5] {
+    match c {
+        ' '..='~' => &FONT[c as usize - ' ' as usize],
+        _ => &FONT['?' as usize - ' ' as usize],
+    }
+}
+
+/// Printable ASCII, `' '` to `'~'`.
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x08, 0x07, 0x03, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x80, 0x70, 0x30, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x00, 0x60, 0x60, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x72, 0x49, 0x49, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x49, 0x4D, 0x33], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x31], // 6
+    [0x41, 0x21, 0x11, 0x09, 0x07], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x46, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x00, 0x14, 0x00, 0x00], // :
+    [0x00, 0x40, 0x34, 0x00, 0x00], // ;
+    [0x00, 0x08, 0x14, 0x22, 0x41], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x59, 0x09, 0x06], // ?
+    [0x3E, 0x41, 0x5D, 0x59, 0x4E], // @
+    [0x7C, 0x12, 0x11, 0x12, 0x7C], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x41, 0x3E], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x73], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x1C, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x26, 0x49, 0x49, 0x49, 0x32], // S
+    [0x03, 0x01, 0x7F, 0x01, 0x03], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x59, 0x49, 0x4D, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x41], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x41, 0x7F], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x03, 0x07, 0x08, 0x00], // `
+    [0x20, 0x54, 0x54, 0x78, 0x40], // a
+    [0x7F, 0x28, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x28], // c
+    [0x38, 0x44, 0x44, 0x28, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x00, 0x08, 0x7E, 0x09, 0x02], // f
+    [0x18, 0xA4, 0xA4, 0x9C, 0x78], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x40, 0x3D, 0x00], // j
+    [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x78, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0xFC, 0x18, 0x24, 0x24, 0x18], // p
+    [0x18, 0x24, 0x24, 0x18, 0xFC], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x24], // s
+    [0x04, 0x04, 0x3F, 0x44, 0x24], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x4C, 0x90, 0x90, 0x90, 0x7C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x77, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x02, 0x01, 0x02, 0x04, 0x02], // ~
+];
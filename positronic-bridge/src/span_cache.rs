@@ -0,0 +1,270 @@
+//! Retained span state for the terminal view.
+//!
+//! `renderer::*_to_spans` rebuild and re-classify every span each time they
+//! run. `SpanCache` keeps the result per fragment — a direct-output line, a
+//! snapshot row, or a block — and reuses it until that fragment's content
+//! hash changes, so a frame only assembles cached pieces.
+//!
+//! Work is bounded by what's on screen: only fragments inside the visible
+//! range (plus `OFFSCREEN_MARGIN` on each side, prefetched for smooth
+//! scrolling) are ever materialized. Everything else stays plain text.
+//! Fragments untouched for `KEEP_FRAMES` frames are evicted; a theme change
+//! drops the whole cache.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+
+use positronic_core::state_machine::Snapshot;
+
+use crate::attention;
+use crate::block::{BlockId, TerminalBlock};
+use crate::renderer::{self, BlockStyle, ColoredSpan, ThemeName};
+
+/// Fragments prefetched above and below the visible range.
+pub const OFFSCREEN_MARGIN: usize = 8;
+
+/// Frames a fragment may go unused before it is evicted.
+const KEEP_FRAMES: u64 = 120;
+
+/// Per-frame work counters (for tests and the perf overlay).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Fragments whose spans were built this frame.
+    pub built: usize,
+    /// Fragments served from the cache this frame.
+    pub reused: usize,
+}
+
+#[derive(Debug)]
+struct Fragment {
+    hash: u64,
+    last_frame: u64,
+    spans: Vec<ColoredSpan>,
+}
+
+#[derive(Debug, Default)]
+pub struct SpanCache {
+    theme: Option<ThemeName>,
+    block_style: BlockStyle,
+    frame: u64,
+    /// Direct-output lines and snapshot rows, keyed by content hash: their
+    /// spans depend on nothing else, so identical lines share one entry and
+    /// survive the front-trimming of the direct buffer.
+    lines: HashMap<u64, Fragment>,
+    /// Blocks keyed by id and validated against their content hash.
+    blocks: HashMap<BlockId, Fragment>,
+    stats: FrameStats,
+}
+
+fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut h = DefaultHasher::new();
+    value.hash(&mut h);
+    h.finish()
+}
+
+/// Everything `block_to_spans` reads, so any visible change re-keys the block.
+fn block_hash(block: &TerminalBlock) -> u64 {
+    let mut h = DefaultHasher::new();
+    block.command.hash(&mut h);
+    block.exit_code.hash(&mut h);
+    block.duration.hash(&mut h);
+    block.usage.hash(&mut h);
+    block.running.hash(&mut h);
+    block.collapsed.hash(&mut h);
+    block.diagnostics.len().hash(&mut h);
+    block.diagnostics_expanded.hash(&mut h);
+    block.tests.as_ref().map(|t| (t.total(), t.failures.len())).hash(&mut h);
+    block.tests_expanded.hash(&mut h);
+    block.language.hash(&mut h);
+    block.output.len().hash(&mut h);
+    if !block.collapsed {
+        for line in &block.output {
+            line.text.hash(&mut h);
+            (line.kind as u8).hash(&mut h);
+            line.offset_ms.hash(&mut h);
+        }
+    }
+    h.finish()
+}
+
+fn widen(visible: &Range<usize>, len: usize) -> Range<usize> {
+    let start = visible.start.min(len).saturating_sub(OFFSCREEN_MARGIN);
+    let end = visible.end.saturating_add(OFFSCREEN_MARGIN).min(len);
+    start..end.max(start)
+}
+
+impl SpanCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switch themes; any change invalidates every cached fragment.
+    /// Returns true if the cache was cleared.
+    pub fn set_theme(&mut self, theme: ThemeName) -> bool {
+        if self.theme == Some(theme) {
+            return false;
+        }
+        self.theme = Some(theme);
+        self.block_style.theme = theme;
+        self.invalidate();
+        true
+    }
+
+    pub fn block_style(&self) -> BlockStyle {
+        self.block_style
+    }
+
+    /// Change block header layout; a change rebuilds every block.
+    pub fn set_block_style(&mut self, style: BlockStyle) -> bool {
+        if self.block_style == style {
+            return false;
+        }
+        self.block_style = style;
+        self.blocks.clear();
+        true
+    }
+
+    pub fn invalidate(&mut self) {
+        self.lines.clear();
+        self.blocks.clear();
+    }
+
+    /// Drop a block's spans now instead of after `KEEP_FRAMES` (for
+    /// blocks the `BlockManager` evicted).
+    pub fn forget_block(&mut self, id: BlockId) {
+        self.blocks.remove(&id);
+    }
+
+    /// Work done by the most recent `*_spans` call.
+    pub fn last_frame(&self) -> FrameStats {
+        self.stats
+    }
+
+    /// Number of fragments currently materialized.
+    pub fn cached_fragments(&self) -> usize {
+        self.lines.len() + self.blocks.len()
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Frame assembly
+    // ────────────────────────────────────────────────────────────────
+
+    /// Spans for the direct-output lines in `visible` (line indices).
+    pub fn direct_spans(&mut self, text: &str, visible: Range<usize>) -> Vec<ColoredSpan> {
+        self.begin_frame();
+        let total = text.lines().count();
+        let window = widen(&visible, total);
+        let mut out = Vec::new();
+
+        for (i, line) in text.lines().enumerate().skip(window.start).take(window.len()) {
+            let spans = self.line_fragment(hash_of(line), || renderer::direct_line_spans(line));
+            if visible.contains(&i) {
+                out.extend_from_slice(spans);
+            }
+        }
+
+        self.end_frame();
+        out
+    }
+
+    /// Spans for a PTY snapshot; rows are cached by content, colors and
+    /// attributes.
+    pub fn snapshot_spans(&mut self, snapshot: &Snapshot) -> Vec<ColoredSpan> {
+        self.begin_frame();
+        let mut out = Vec::new();
+
+        if snapshot.rows() > 0 {
+            let theme = self.theme.unwrap_or(ThemeName::Default);
+            for row_idx in renderer::first_content_row(snapshot)..snapshot.rows() {
+                let key = (&snapshot[row_idx], snapshot.row_clusters(row_idx), snapshot.row_styles(row_idx));
+                let spans = self.line_fragment(hash_of(&key), || {
+                    let mut spans = Vec::new();
+                    renderer::row_to_spans(snapshot, row_idx, theme, &mut spans);
+                    spans
+                });
+                out.extend_from_slice(spans);
+            }
+        }
+
+        self.end_frame();
+        out
+    }
+
+    /// Spans for the blocks in `visible` (indices into `blocks`); blocks
+    /// older than the style's `dim_old` most recent are dimmed.
+    pub fn block_spans(&mut self, blocks: &[TerminalBlock], visible: Range<usize>) -> Vec<ColoredSpan> {
+        self.begin_frame();
+        let window = widen(&visible, blocks.len());
+        let mut out = Vec::new();
+
+        let dim_before = self.block_style.dim_old.map_or(0, |keep| blocks.len().saturating_sub(keep));
+        for i in window {
+            let block = &blocks[i];
+            let dimmed = i < dim_before;
+            let hash = hash_of(&(block_hash(block), dimmed));
+            let frame = self.frame;
+            let entry = self.blocks.entry(block.id).or_insert_with(|| Fragment {
+                hash: !hash,
+                last_frame: frame,
+                spans: Vec::new(),
+            });
+            if entry.hash == hash {
+                self.stats.reused += 1;
+            } else {
+                entry.hash = hash;
+                entry.spans = renderer::block_to_spans(block, &self.block_style);
+                if dimmed {
+                    for span in &mut entry.spans {
+                        span.color = attention::dim(span.color);
+                    }
+                }
+                self.stats.built += 1;
+            }
+            entry.last_frame = frame;
+            if visible.contains(&i) {
+                out.extend_from_slice(&entry.spans);
+            }
+        }
+
+        self.end_frame();
+        out
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Internals
+    // ────────────────────────────────────────────────────────────────
+
+    fn line_fragment(&mut self, hash: u64, build: impl FnOnce() -> Vec<ColoredSpan>) -> &[ColoredSpan] {
+        let frame = self.frame;
+        let stats = &mut self.stats;
+        let entry = self
+            .lines
+            .entry(hash)
+            .and_modify(|_| stats.reused += 1)
+            .or_insert_with(|| {
+                stats.built += 1;
+                Fragment {
+                    hash,
+                    last_frame: frame,
+                    spans: build(),
+                }
+            });
+        entry.last_frame = frame;
+        &entry.spans
+    }
+
+    fn begin_frame(&mut self) {
+        self.frame += 1;
+        self.stats = FrameStats::default();
+    }
+
+    fn end_frame(&mut self) {
+        if !self.frame.is_multiple_of(32) {
+            return;
+        }
+        let cutoff = self.frame.saturating_sub(KEEP_FRAMES);
+        self.lines.retain(|_, f| f.last_frame >= cutoff);
+        self.blocks.retain(|_, f| f.last_frame >= cutoff);
+    }
+}
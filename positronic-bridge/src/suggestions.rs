@@ -0,0 +1,92 @@
+//! Picker state for `!suggest` results.
+//!
+//! The list stays open under the transcript until a suggestion is picked,
+//! another command is submitted, or Escape dismisses it. Picking inserts the
+//! command into the input line for editing; nothing is executed. Commands
+//! `DangerAnalyzer::screen_generated` rates Destructive (the danger
+//! patterns plus the deny-list for model output) are flagged in the list.
+//!
+//! Row rectangles are filled in by `ui::suggestions` while drawing, so
+//! clicks are hit-tested against what was actually on screen.
+
+use positronic_core::danger::{DangerAnalyzer, DangerVerdict};
+use positronic_neural::suggest::Suggestion;
+
+#[derive(Debug, Clone)]
+pub struct PickerItem {
+    pub command: String,
+    pub reason: String,
+    pub danger: DangerVerdict,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuggestionPicker {
+    items: Vec<PickerItem>,
+    /// `[x, y, w, h]` per item, from the last draw.
+    rows: Vec<[f32; 4]>,
+}
+
+impl SuggestionPicker {
+    /// `None` if there is nothing to pick.
+    pub fn new(suggestions: Vec<Suggestion>) -> Option<Self> {
+        if suggestions.is_empty() {
+            return None;
+        }
+        let items = suggestions
+            .into_iter()
+            .take(9)
+            .map(|s| PickerItem {
+                danger: DangerAnalyzer::screen_generated(&s.command),
+                command: s.command,
+                reason: s.reason,
+            })
+            .collect();
+        Some(Self { items, rows: Vec::new() })
+    }
+
+    pub fn items(&self) -> &[PickerItem] {
+        &self.items
+    }
+
+    pub fn get(&self, index: usize) -> Option<&PickerItem> {
+        self.items.get(index)
+    }
+
+    /// Index selected by a digit key ("1" is the first item).
+    pub fn key_index(&self, key: &str) -> Option<usize> {
+        let n: usize = key.parse().ok()?;
+        (1..=self.items.len()).contains(&n).then(|| n - 1)
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<[f32; 4]>) {
+        self.rows = rows;
+    }
+
+    /// Index of the row under `(x, y)`, if any.
+    pub fn hit(&self, x: f32, y: f32) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|&[rx, ry, rw, rh]| x >= rx && x < rx + rw && y >= ry && y < ry + rh)
+    }
+
+    /// Transcript copy of the list, so it survives after the picker closes.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "💡 Suggestions — press 1–{} or click to edit, Esc to dismiss:",
+            self.items.len()
+        )];
+        for (i, item) in self.items.iter().enumerate() {
+            lines.push(format!("  {}. {}", i + 1, item.command));
+            if !item.reason.is_empty() {
+                lines.push(format!("     {}", item.reason));
+            }
+            if item.danger.is_destructive() {
+                lines.push(format!(
+                    "     ⚠️ destructive: {}",
+                    item.danger.reason.unwrap_or("matches a destructive pattern")
+                ));
+            }
+        }
+        lines
+    }
+}
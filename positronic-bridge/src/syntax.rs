@@ -0,0 +1,537 @@
+//! Syntax highlighting for code in block output (no UI deps).
+//!
+//! `detect` picks a block's language when it finishes: from the command
+//! first (the file extension in `cat`/`type`/`bat`/`head`/`tail`
+//! arguments, `git diff`/`git show`/`diff -u`, `jq`), else from what its
+//! first lines look like. `highlight` tokenizes the lines with a small
+//! built-in scanner for Rust, Python, JSON, YAML, TOML and diffs, carrying
+//! block comments and multi-line strings from one line to the next. Spans
+//! are character ranges tagged with a `TokenClass`; text between them is
+//! plain, and `ThemeName::syntax_color` picks the colors.
+//!
+//! Work is bounded: blocks over `MAX_LINES` lines are left plain, and
+//! highlighting runs when the span cache builds a block's spans, so only
+//! for blocks near the screen. `blocks.highlight off` turns it off.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// Vault config key: highlight code in block output (default on).
+pub const HIGHLIGHT_KEY: &str = "blocks.highlight";
+
+/// Blocks longer than this stay plain.
+pub const MAX_LINES: usize = 2_000;
+
+/// Non-blank lines content detection looks at.
+const CONTENT_SAMPLE: usize = 30;
+
+/// Lines that must look like a language before content detection names it.
+const MIN_SIGNALS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    Rust,
+    Python,
+    Json,
+    Yaml,
+    Toml,
+    Diff,
+}
+
+impl Language {
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::Json => "json",
+            Language::Yaml => "yaml",
+            Language::Toml => "toml",
+            Language::Diff => "diff",
+        }
+    }
+
+    /// The language of a file, by its extension (or name, for `Cargo.lock`).
+    pub fn from_path(path: &str) -> Option<Language> {
+        let path = path.trim_matches(|c| c == '"' || c == '\'');
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        if name == "Cargo.lock" {
+            return Some(Language::Toml);
+        }
+        let (_, ext) = name.rsplit_once('.')?;
+        match ext.to_ascii_lowercase().as_str() {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" | "pyw" => Some(Language::Python),
+            "json" | "jsonc" => Some(Language::Json),
+            "yaml" | "yml" => Some(Language::Yaml),
+            "toml" => Some(Language::Toml),
+            "diff" | "patch" => Some(Language::Diff),
+            _ => None,
+        }
+    }
+
+    /// What `command` prints, if the command says: `cat main.rs`,
+    /// `git diff`, `jq . data.json`. Only the first stage of a pipeline
+    /// counts.
+    pub fn from_command(command: &str) -> Option<Language> {
+        let stage = command.split('|').next().unwrap_or(command);
+        let words: Vec<&str> = stage.split_whitespace().collect();
+        let (program, args) = words.split_first()?;
+        let program = program.rsplit(['/', '\\']).next().unwrap_or(program).to_ascii_lowercase();
+        let program = program.strip_suffix(".exe").unwrap_or(&program);
+        let operands = args.iter().filter(|a| !a.starts_with('-'));
+        match program {
+            "git" => match git_subcommand(args) {
+                Some("diff" | "show") => Some(Language::Diff),
+                Some("log" | "stash") if args.iter().any(|a| *a == "-p" || *a == "--patch") => Some(Language::Diff),
+                _ => None,
+            },
+            "diff" if args.iter().any(|a| *a == "-u" || a.starts_with("--unified")) => Some(Language::Diff),
+            "jq" => Some(Language::Json),
+            "cat" | "type" | "bat" | "batcat" | "less" | "more" | "head" | "tail" | "nl" | "get-content" | "gc" => {
+                operands.rev().find_map(|a| Language::from_path(a))
+            }
+            _ => None,
+        }
+    }
+
+    /// Guess from the first lines of output; `None` unless they clearly
+    /// look like one language.
+    pub fn from_content(lines: &[&str]) -> Option<Language> {
+        let sample: Vec<&str> =
+            lines.iter().map(|l| l.trim_end()).filter(|l| !l.trim().is_empty()).take(CONTENT_SAMPLE).collect();
+        let first = sample.first()?.trim_start();
+        if first.starts_with("diff --git ")
+            || sample.windows(2).any(|w| w[0].starts_with("--- ") && w[1].starts_with("+++ "))
+        {
+            return Some(Language::Diff);
+        }
+        if (first.starts_with('{') || first.starts_with('['))
+            && sample.iter().any(|l| l.trim_start().starts_with('"') && l.contains("\":"))
+        {
+            return Some(Language::Json);
+        }
+
+        let count = |test: &dyn Fn(&str) -> bool| sample.iter().filter(|l| test(l.trim_start())).count();
+        let rust = count(&|l| RUST_STARTS.iter().any(|s| l.starts_with(s)));
+        let python = count(&|l| PYTHON_STARTS.iter().any(|s| l.starts_with(s)));
+        let tables = count(&|l| l.starts_with('[') && l.ends_with(']') && !l.contains(", "));
+        let toml = if tables > 0 { tables + count(&|l| toml_key_end(l).is_some()) } else { 0 };
+        let best = [(Language::Rust, rust), (Language::Python, python), (Language::Toml, toml)]
+            .into_iter()
+            .filter(|(_, score)| *score >= MIN_SIGNALS)
+            .max_by_key(|(_, score)| *score);
+        if let Some((language, _)) = best {
+            return Some(language);
+        }
+
+        let yaml = count(&|l| yaml_key_end(l.strip_prefix("- ").unwrap_or(l)).is_some());
+        let yaml_doc = first == "---" && sample.len() > 1;
+        (yaml_doc || (yaml >= MIN_SIGNALS && yaml * 2 >= sample.len())).then_some(Language::Yaml)
+    }
+}
+
+/// The word after `git` and its global options (`-C dir`, `-c key=val`).
+fn git_subcommand<'a>(args: &[&'a str]) -> Option<&'a str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "-C" | "-c" => {
+                args.next();
+            }
+            _ if arg.starts_with('-') => {}
+            _ => return Some(arg),
+        }
+    }
+    None
+}
+
+/// The language of a block's output: what the command says, else what
+/// the output looks like.
+pub fn detect(command: &str, lines: &[&str]) -> Option<Language> {
+    Language::from_command(command).or_else(|| Language::from_content(lines))
+}
+
+const RUST_STARTS: &[&str] = &[
+    "fn ", "pub ", "use ", "impl ", "impl<", "let ", "mod ", "struct ", "enum ", "trait ", "#[", "#![", "match ",
+];
+
+const PYTHON_STARTS: &[&str] =
+    &["def ", "async def ", "class ", "import ", "from ", "elif ", "except", "if __name__", "print("];
+
+// ════════════════════════════════════════════════════════════════════
+// Tokens
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    /// An object or mapping key.
+    Key,
+    /// Rust attributes and macros, Python decorators.
+    Attribute,
+    /// TOML tables, `commit` lines in `git show`.
+    Heading,
+    Added,
+    Removed,
+    /// `@@ -1,4 +1,5 @@`
+    Hunk,
+    /// `diff --git`, `index`, `---`, `+++`…
+    DiffHeader,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxSpan {
+    /// Character range in the line.
+    pub range: Range<usize>,
+    pub class: TokenClass,
+}
+
+/// Spans for each of `lines`, in order.
+pub fn highlight(language: Language, lines: &[&str]) -> Vec<Vec<SyntaxSpan>> {
+    let mut carry = Carry::None;
+    lines
+        .iter()
+        .map(|line| match language {
+            Language::Diff => diff_line(line),
+            _ => code_line(language, &spec(language), line, &mut carry),
+        })
+        .collect()
+}
+
+/// What a line leaves open for the next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Carry {
+    None,
+    BlockComment,
+    /// A string, and the quote that closes it.
+    String(&'static str),
+}
+
+struct Spec {
+    keywords: &'static [&'static str],
+    line_comment: Option<&'static str>,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Longest first, so `"""` wins over `"`.
+    quotes: &'static [&'static str],
+    /// Quotes whose strings may run past the end of the line.
+    multiline: &'static [&'static str],
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "self", "try", "while", "with", "yield",
+];
+
+const DATA_KEYWORDS: &[&str] = &["true", "false", "null", "yes", "no", "on", "off", "~"];
+
+fn spec(language: Language) -> Spec {
+    match language {
+        Language::Rust => Spec {
+            keywords: RUST_KEYWORDS,
+            line_comment: Some("//"),
+            block_comment: Some(("/*", "*/")),
+            quotes: &["\""],
+            multiline: &["\""],
+        },
+        Language::Python => Spec {
+            keywords: PYTHON_KEYWORDS,
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &["\"\"\"", "'''", "\"", "'"],
+            multiline: &["\"\"\"", "'''"],
+        },
+        Language::Toml => Spec {
+            keywords: &["true", "false"],
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &["\"\"\"", "'''", "\"", "'"],
+            multiline: &["\"\"\"", "'''"],
+        },
+        Language::Yaml => Spec {
+            keywords: DATA_KEYWORDS,
+            line_comment: Some("#"),
+            block_comment: None,
+            quotes: &["\"", "'"],
+            multiline: &[],
+        },
+        Language::Json | Language::Diff => Spec {
+            keywords: &["true", "false", "null"],
+            line_comment: None,
+            block_comment: None,
+            quotes: &["\""],
+            multiline: &[],
+        },
+    }
+}
+
+fn starts_at(chars: &[char], i: usize, s: &str) -> bool {
+    s.chars().enumerate().all(|(n, c)| chars.get(i + n) == Some(&c))
+}
+
+/// Where `close` ends, searching from `from`: the index after it.
+fn find_close(chars: &[char], from: usize, close: &str) -> Option<usize> {
+    (from..chars.len()).find(|&i| starts_at(chars, i, close)).map(|i| i + close.chars().count())
+}
+
+/// The index after the quote closing a string whose body starts at
+/// `from`, skipping backslash escapes.
+fn string_end(chars: &[char], from: usize, quote: &str) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if starts_at(chars, i, quote) {
+            return Some(i + quote.chars().count());
+        }
+        i += 1;
+    }
+    None
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// End of a TOML key (`name`, `a.b`, `"quoted"`) before ` = `.
+fn toml_key_end(line: &str) -> Option<usize> {
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim_end();
+    let ok = !key.is_empty() && key.chars().all(|c| is_ident(c) || matches!(c, '.' | '-' | '"' | ' '));
+    ok.then(|| key.chars().count())
+}
+
+/// End of a plain YAML key before `:` (followed by a space or the end).
+fn yaml_key_end(line: &str) -> Option<usize> {
+    let colon = line.find(':')?;
+    let key = &line[..colon];
+    let after = line[colon + 1..].chars().next();
+    let ok = !key.is_empty()
+        && after.is_none_or(|c| c == ' ')
+        && !key.starts_with(['"', '\'', '#', '{', '[', '&', '*', '!', '|', '>', '%', '@', '`'])
+        && !key.contains(['#', '{', '}', '[', ']', ','])
+        && !key.ends_with(' ');
+    ok.then(|| key.chars().count())
+}
+
+fn code_line(language: Language, spec: &Spec, line: &str, carry: &mut Carry) -> Vec<SyntaxSpan> {
+    let chars: Vec<char> = line.chars().collect();
+    let len = chars.len();
+    let mut spans = Vec::new();
+    let mut push = |range: Range<usize>, class| {
+        if !range.is_empty() {
+            spans.push(SyntaxSpan { range, class });
+        }
+    };
+    let mut i = 0;
+
+    // Whatever the previous line left open.
+    match *carry {
+        Carry::None => {}
+        Carry::BlockComment => {
+            let close = spec.block_comment.map_or("*/", |(_, close)| close);
+            match find_close(&chars, 0, close) {
+                Some(end) => {
+                    push(0..end, TokenClass::Comment);
+                    *carry = Carry::None;
+                    i = end;
+                }
+                None => {
+                    push(0..len, TokenClass::Comment);
+                    return spans;
+                }
+            }
+        }
+        Carry::String(quote) => match string_end(&chars, 0, quote) {
+            Some(end) => {
+                push(0..end, TokenClass::String);
+                *carry = Carry::None;
+                i = end;
+            }
+            None => {
+                push(0..len, TokenClass::String);
+                return spans;
+            }
+        },
+    }
+
+    // Constructs that only start a line.
+    let indent = chars.iter().take_while(|c| c.is_whitespace()).count();
+    if i == 0 && indent < len {
+        let rest: String = chars[indent..].iter().collect();
+        match language {
+            Language::Toml if rest.starts_with('[') => {
+                let end = rest.rfind(']').map_or(len, |b| indent + rest[..b].chars().count() + 1);
+                push(indent..end, TokenClass::Heading);
+                i = end;
+            }
+            Language::Toml => {
+                if let Some(end) = toml_key_end(&rest) {
+                    push(indent..indent + end, TokenClass::Key);
+                    i = indent + end;
+                }
+            }
+            Language::Yaml => {
+                let item = if rest.starts_with("- ") { 2 } else { 0 };
+                if let Some(end) = yaml_key_end(&rest[item..]) {
+                    push(indent + item..indent + item + end, TokenClass::Key);
+                    i = indent + item + end;
+                }
+            }
+            Language::Rust if rest.starts_with("#[") || rest.starts_with("#![") => {
+                let end = rest.rfind(']').map_or(len, |b| indent + rest[..b].chars().count() + 1);
+                push(indent..end, TokenClass::Attribute);
+                i = end;
+            }
+            Language::Python if rest.starts_with('@') => {
+                let end = indent + 1 + chars[indent + 1..].iter().take_while(|&&c| is_ident(c) || c == '.').count();
+                push(indent..end, TokenClass::Attribute);
+                i = end;
+            }
+            _ => {}
+        }
+    }
+
+    'scan: while i < len {
+        let c = chars[i];
+
+        if let Some(comment) = spec.line_comment {
+            // `#` in YAML starts a comment only after whitespace.
+            let spaced = language != Language::Yaml || i == 0 || chars[i - 1].is_whitespace();
+            if spaced && starts_at(&chars, i, comment) {
+                push(i..len, TokenClass::Comment);
+                break;
+            }
+        }
+        if let Some((open, close)) = spec.block_comment
+            && starts_at(&chars, i, open)
+        {
+            match find_close(&chars, i + open.chars().count(), close) {
+                Some(end) => {
+                    push(i..end, TokenClass::Comment);
+                    i = end;
+                    continue;
+                }
+                None => {
+                    push(i..len, TokenClass::Comment);
+                    *carry = Carry::BlockComment;
+                    break;
+                }
+            }
+        }
+        for &quote in spec.quotes {
+            if !starts_at(&chars, i, quote) {
+                continue;
+            }
+            let start = i;
+            match string_end(&chars, i + quote.chars().count(), quote) {
+                Some(end) => {
+                    let colon = chars[end..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                    let key = colon && matches!(language, Language::Json | Language::Yaml);
+                    push(start..end, if key { TokenClass::Key } else { TokenClass::String });
+                    i = end;
+                    continue 'scan;
+                }
+                None => {
+                    push(start..len, TokenClass::String);
+                    if spec.multiline.contains(&quote) {
+                        *carry = Carry::String(quote);
+                    }
+                    break 'scan;
+                }
+            }
+        }
+        if language == Language::Rust && c == '\'' {
+            // A char literal; otherwise a lifetime.
+            let end = if chars.get(i + 1) == Some(&'\\') {
+                (i + 2..len.min(i + 12)).find(|&j| chars[j] == '\'').map(|j| j + 1)
+            } else {
+                (chars.get(i + 2) == Some(&'\'')).then_some(i + 3)
+            };
+            if let Some(end) = end {
+                push(i..end, TokenClass::String);
+                i = end;
+                continue;
+            }
+        }
+        let negative = c == '-'
+            && language != Language::Rust
+            && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+            && (i == 0 || !is_ident(chars[i - 1]));
+        if c.is_ascii_digit() || negative {
+            let mut end = i + 1;
+            while end < len
+                && (is_ident(chars[end]) || (chars[end] == '.' && chars.get(end + 1).is_some_and(char::is_ascii_digit)))
+            {
+                end += 1;
+            }
+            push(i..end, TokenClass::Number);
+            i = end;
+            continue;
+        }
+        if is_ident_start(c) {
+            let mut end = i + 1;
+            while end < len && is_ident(chars[end]) {
+                end += 1;
+            }
+            let word: String = chars[i..end].iter().collect();
+            if spec.keywords.contains(&word.as_str()) {
+                push(i..end, TokenClass::Keyword);
+            } else if language == Language::Rust && chars.get(end) == Some(&'!') {
+                end += 1;
+                push(i..end, TokenClass::Attribute);
+            } else if matches!(language, Language::Rust | Language::Python) && c.is_uppercase() {
+                push(i..end, TokenClass::Type);
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    spans
+}
+
+/// Headers that open a file's section of a diff.
+const DIFF_HEADERS: &[&str] = &[
+    "diff ", "index ", "--- ", "+++ ", "new file mode", "deleted file mode", "old mode", "new mode",
+    "similarity index", "rename from", "rename to", "Binary files",
+];
+
+fn diff_line(line: &str) -> Vec<SyntaxSpan> {
+    let len = line.chars().count();
+    let whole = |class| vec![SyntaxSpan { range: 0..len, class }];
+    if len == 0 {
+        Vec::new()
+    } else if DIFF_HEADERS.iter().any(|h| line.starts_with(h)) {
+        whole(TokenClass::DiffHeader)
+    } else if line.starts_with("commit ") {
+        whole(TokenClass::Heading)
+    } else if let Some(rest) = line.strip_prefix("@@") {
+        // The hunk range; the function context after it stays plain.
+        let end = rest.find("@@").map_or(len, |b| rest[..b].chars().count() + 4);
+        vec![SyntaxSpan { range: 0..end, class: TokenClass::Hunk }]
+    } else if line.starts_with('+') {
+        whole(TokenClass::Added)
+    } else if line.starts_with('-') {
+        whole(TokenClass::Removed)
+    } else {
+        Vec::new()
+    }
+}
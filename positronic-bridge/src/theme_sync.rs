@@ -0,0 +1,313 @@
+//! Automatic theme switching: `theme.mode`, `theme.light`/`theme.dark` and
+//! `theme.schedule`.
+//!
+//! `ThemeSwitcher` decides which theme should be showing from the mode, the
+//! OS appearance (when the mode follows it) and the local time (when it
+//! follows a schedule). Clocks are passed in, so the boundary math and the
+//! manual-override rules are plain functions of their inputs. The app asks
+//! it on every wake and applies whatever it returns the same way `!theme`
+//! does.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::renderer::ThemeName;
+
+/// Vault config keys.
+pub const MODE_KEY: &str = "theme.mode";
+pub const LIGHT_KEY: &str = "theme.light";
+pub const DARK_KEY: &str = "theme.dark";
+pub const SCHEDULE_KEY: &str = "theme.schedule";
+
+/// Themes used when `theme.light` / `theme.dark` are unset.
+pub const DEFAULT_LIGHT: ThemeName = ThemeName::Solarized;
+pub const DEFAULT_DARK: ThemeName = ThemeName::Default;
+
+/// How often the OS appearance is re-read where the window system doesn't
+/// report changes itself.
+pub const OS_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// ════════════════════════════════════════════════════════════════════
+// Appearance and schedule
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    pub fn label(self) -> &'static str {
+        match self {
+            Appearance::Light => "light",
+            Appearance::Dark => "dark",
+        }
+    }
+}
+
+/// `theme.schedule = "07:00-19:00"`: light from the first time until the
+/// second, dark otherwise. A range may cross midnight ("20:00-06:00").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// Minutes after midnight.
+    light_from: u32,
+    light_until: u32,
+}
+
+impl Schedule {
+    pub fn parse(text: &str) -> Option<Schedule> {
+        let (from, until) = text.trim().split_once('-')?;
+        let light_from = parse_clock(from)?;
+        let light_until = parse_clock(until)?;
+        (light_from != light_until).then_some(Schedule { light_from, light_until })
+    }
+
+    pub fn appearance_at(&self, time: NaiveTime) -> Appearance {
+        let minute = time.hour() * 60 + time.minute();
+        let light = if self.light_from < self.light_until {
+            (self.light_from..self.light_until).contains(&minute)
+        } else {
+            minute >= self.light_from || minute < self.light_until
+        };
+        if light { Appearance::Light } else { Appearance::Dark }
+    }
+
+    /// The first switch strictly after `now`.
+    pub fn next_boundary(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let midnight = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
+        [self.light_from, self.light_until]
+            .into_iter()
+            .flat_map(|minute| {
+                [0, 1].map(|day| midnight + Duration::days(day) + Duration::minutes(i64::from(minute)))
+            })
+            .filter(|at| *at > now)
+            .min()
+            .unwrap_or(now + Duration::days(1))
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let clock = |m: u32| format!("{:02}:{:02}", m / 60, m % 60);
+        write!(f, "{}-{}", clock(self.light_from), clock(self.light_until))
+    }
+}
+
+/// `"7:00"` / `"07:00"` → minutes after midnight.
+fn parse_clock(text: &str) -> Option<u32> {
+    let (h, m) = text.trim().split_once(':')?;
+    if m.len() != 2 {
+        return None;
+    }
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Mode
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeMode {
+    /// Follow the OS color-scheme preference.
+    Auto,
+    Light,
+    Dark,
+    Schedule(Schedule),
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeMode::Auto => f.write_str("auto"),
+            ThemeMode::Light => f.write_str("light"),
+            ThemeMode::Dark => f.write_str("dark"),
+            ThemeMode::Schedule(schedule) => write!(f, "schedule {}", schedule),
+        }
+    }
+}
+
+/// Everything the switcher needs from config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeSync {
+    pub mode: ThemeMode,
+    pub light: ThemeName,
+    pub dark: ThemeName,
+}
+
+impl ThemeSync {
+    /// Read the `theme.*` keys through `lookup`. `None` without a
+    /// `theme.mode`, leaving the plain `theme` key in charge. Invalid
+    /// values are described in `problems`; a bad mode or schedule turns
+    /// syncing off, a bad theme name falls back to its default.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> Option<ThemeSync> {
+        let mode = lookup(MODE_KEY)?;
+        let mut theme = |key: &str, default: ThemeName| match lookup(key) {
+            None => default,
+            Some(value) => ThemeName::from_str(value.trim()).unwrap_or_else(|| {
+                problems.push(format!("{} = \"{}\": unknown theme", key, value));
+                default
+            }),
+        };
+        let light = theme(LIGHT_KEY, DEFAULT_LIGHT);
+        let dark = theme(DARK_KEY, DEFAULT_DARK);
+
+        let mode = match mode.trim().to_lowercase().as_str() {
+            "auto" => ThemeMode::Auto,
+            "light" => ThemeMode::Light,
+            "dark" => ThemeMode::Dark,
+            "schedule" => match lookup(SCHEDULE_KEY) {
+                Some(value) => match Schedule::parse(&value) {
+                    Some(schedule) => ThemeMode::Schedule(schedule),
+                    None => {
+                        problems.push(format!("{} = \"{}\": expected e.g. 07:00-19:00", SCHEDULE_KEY, value));
+                        return None;
+                    }
+                },
+                None => {
+                    problems.push(format!("{} = schedule needs {} (e.g. 07:00-19:00)", MODE_KEY, SCHEDULE_KEY));
+                    return None;
+                }
+            },
+            _ => {
+                problems.push(format!("{} = \"{}\": expected auto, light, dark or schedule", MODE_KEY, mode));
+                return None;
+            }
+        };
+        Some(ThemeSync { mode, light, dark })
+    }
+
+    pub fn theme_for(&self, appearance: Appearance) -> ThemeName {
+        match appearance {
+            Appearance::Light => self.light,
+            Appearance::Dark => self.dark,
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Switcher
+// ════════════════════════════════════════════════════════════════════
+
+/// A manual `!theme <name>` while syncing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Override {
+    theme: ThemeName,
+    until: Until,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+    /// The next schedule boundary.
+    Time(NaiveDateTime),
+    /// The OS appearance moving away from this.
+    OsLeaves(Option<Appearance>),
+    /// Only `!theme auto` (fixed light/dark modes have no boundaries).
+    Resume,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThemeSwitcher {
+    sync: Option<ThemeSync>,
+    os: Option<Appearance>,
+    manual: Option<Override>,
+}
+
+impl ThemeSwitcher {
+    /// Apply config; a manual override survives a reload that leaves the
+    /// `theme.*` keys as they were.
+    pub fn configure(&mut self, sync: Option<ThemeSync>) {
+        if self.sync != sync {
+            self.manual = None;
+        }
+        self.sync = sync;
+    }
+
+    pub fn sync(&self) -> Option<&ThemeSync> {
+        self.sync.as_ref()
+    }
+
+    /// Whether the OS appearance matters right now.
+    pub fn follows_os(&self) -> bool {
+        matches!(self.sync, Some(ThemeSync { mode: ThemeMode::Auto, .. }))
+    }
+
+    pub fn os(&self) -> Option<Appearance> {
+        self.os
+    }
+
+    /// The OS reported its appearance (`None`: unknown). Ends an override
+    /// made under a different one.
+    pub fn set_os(&mut self, appearance: Option<Appearance>) {
+        if let Some(Override { until: Until::OsLeaves(at), .. }) = self.manual
+            && at != appearance
+            && self.follows_os()
+        {
+            self.manual = None;
+        }
+        self.os = appearance;
+    }
+
+    /// The appearance the mode asks for at `now`; `None` when not syncing.
+    /// An unknown OS appearance counts as dark.
+    pub fn appearance(&self, now: NaiveDateTime) -> Option<Appearance> {
+        Some(match self.sync?.mode {
+            ThemeMode::Auto => self.os.unwrap_or(Appearance::Dark),
+            ThemeMode::Light => Appearance::Light,
+            ThemeMode::Dark => Appearance::Dark,
+            ThemeMode::Schedule(schedule) => schedule.appearance_at(now.time()),
+        })
+    }
+
+    /// The theme that should be showing at `now`, ending an override whose
+    /// boundary has passed. `None` when not syncing.
+    pub fn theme(&mut self, now: NaiveDateTime) -> Option<ThemeName> {
+        let sync = self.sync?;
+        if let Some(Override { until: Until::Time(at), .. }) = self.manual
+            && now >= at
+        {
+            self.manual = None;
+        }
+        if let Some(manual) = self.manual {
+            return Some(manual.theme);
+        }
+        self.appearance(now).map(|a| sync.theme_for(a))
+    }
+
+    /// `!theme <name>` while syncing: show `theme` until the next boundary.
+    pub fn set_manual(&mut self, theme: ThemeName, now: NaiveDateTime) {
+        let Some(sync) = self.sync else {
+            return;
+        };
+        let until = match sync.mode {
+            ThemeMode::Schedule(schedule) => Until::Time(schedule.next_boundary(now)),
+            ThemeMode::Auto => Until::OsLeaves(self.os),
+            ThemeMode::Light | ThemeMode::Dark => Until::Resume,
+        };
+        self.manual = Some(Override { theme, until });
+    }
+
+    /// `!theme auto`: drop a manual override.
+    pub fn resume(&mut self) {
+        self.manual = None;
+    }
+
+    pub fn is_overridden(&self) -> bool {
+        self.manual.is_some()
+    }
+
+    /// When `theme` could next change on its own: the next schedule
+    /// boundary, or the end of a manual override.
+    pub fn next_change(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        if let Some(Override { until, .. }) = self.manual {
+            return match until {
+                Until::Time(at) => Some(at),
+                Until::OsLeaves(_) | Until::Resume => None,
+            };
+        }
+        match self.sync?.mode {
+            ThemeMode::Schedule(schedule) => Some(schedule.next_boundary(now)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Clipboard history picker (Ctrl+Shift+V).
+//!
+//! Same panel as the suggestion picker: a header row, then one row per
+//! entry preview with its key. Row rectangles are written back to the
+//! picker for click hit-testing.
+
+use crate::clipboard_history::ClipboardPicker;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+use super::suggestions::{push_row, MARGIN, ROW_HEIGHT};
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, picker: &mut ClipboardPicker) {
+    let count = picker.previews().len();
+    let h = ROW_HEIGHT * (count as f32 + 1.0) + MARGIN;
+    let x = lay.terminal_x + MARGIN;
+    let w = lay.terminal_w - MARGIN * 2.0;
+    let y = lay.status_y - h - MARGIN;
+
+    quads.push(QuadInstance {
+        x,
+        y,
+        w,
+        h,
+        color: Rgba::new(0.07, 0.08, 0.11, 0.94),
+        layer: QuadLayer::Overlay,
+    });
+
+    let header = format!("📋 Clipboard history — 1–{} or click to insert · Enter pastes the clipboard · Esc to close", count);
+    push_row(text, x, y + MARGIN / 2.0, w, vec![ColoredSpan::new(header, Rgba::rgb(0.55, 0.6, 0.7))]);
+
+    let mut rows = Vec::with_capacity(count);
+    for (i, preview) in picker.previews().iter().enumerate() {
+        let row_y = y + MARGIN / 2.0 + ROW_HEIGHT * (i as f32 + 1.0);
+        rows.push([x, row_y, w, ROW_HEIGHT]);
+        let spans = vec![
+            ColoredSpan::new(format!(" {}  ", i + 1), Rgba::rgb(0.95, 0.75, 0.3)),
+            ColoredSpan::new(preview.clone(), Rgba::rgb(0.9, 0.92, 0.95)),
+        ];
+        push_row(text, x, row_y, w, spans);
+    }
+    picker.set_rows(rows);
+}
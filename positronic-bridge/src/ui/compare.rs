@@ -0,0 +1,130 @@
+//! `!compare` overlay.
+//!
+//! Covers the terminal area with two columns, each headed by its
+//! output's label; the focused side's header is lit. Lines are colored as
+//! direct output is, rows in a run of changes get a faint tint, and a gap
+//! is dim hatching. Lines are clipped to the column so a row never wraps
+//! out of line with the other side. State and keys live in
+//! `crate::compare`.
+
+use glyphon::TextBounds;
+
+use positronic_core::diff::RowOp;
+
+use crate::compare::{self, CompareView, Side};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, CELL, LINE_HEIGHT};
+use crate::renderer::{self, ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+const HEADER_COLOR: Rgba = Rgba::rgb(0.6, 0.62, 0.7);
+const FOCUS_COLOR: Rgba = Rgba::rgb(0.45, 0.8, 0.95);
+const FOCUS_BG: Rgba = Rgba::rgb(0.1, 0.16, 0.24);
+const FOOTER_COLOR: Rgba = Rgba::rgb(0.95, 0.75, 0.3);
+const HATCH_COLOR: Rgba = Rgba::new(0.5, 0.52, 0.6, 0.3);
+const CHANGED_TINT: Rgba = Rgba::new(0.95, 0.75, 0.3, 0.1);
+const REMOVED_TINT: Rgba = Rgba::new(1.0, 0.35, 0.35, 0.12);
+const ADDED_TINT: Rgba = Rgba::new(0.3, 0.85, 0.4, 0.12);
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, view: &CompareView) {
+    let padding = lay.padding;
+    let header_y = lay.terminal_y + padding / 2.0;
+    let body_top = header_y + LINE_HEIGHT + padding / 2.0;
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+    let body_bottom = footer_y - 2.0;
+    let column_w = lay.terminal_w / 2.0;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.04, 0.05, 0.07, 0.98),
+        layer: QuadLayer::Overlay,
+    });
+    quads.push(QuadInstance {
+        x: lay.terminal_x + column_w,
+        y: lay.terminal_y,
+        w: 1.0,
+        h: body_bottom - lay.terminal_y,
+        color: Rgba::rgb(0.2, 0.24, 0.32),
+        layer: QuadLayer::Overlay,
+    });
+
+    let rows = ((body_bottom - body_top) / LINE_HEIGHT).floor().max(1.0) as usize;
+    let visible = &view.visible()[..view.visible().len().min(rows)];
+    for side in [Side::Left, Side::Right] {
+        let x = lay.terminal_x + column_w * side as usize as f32;
+        let left = x + padding;
+        let right = x + column_w - padding / 2.0;
+        let cols = ((right - left) / CELL.width).floor().max(1.0) as usize;
+
+        let focused = view.focus() == side;
+        if focused {
+            quads.push(QuadInstance {
+                x,
+                y: header_y - 1.0,
+                w: column_w,
+                h: LINE_HEIGHT + 2.0,
+                color: FOCUS_BG,
+                layer: QuadLayer::Overlay,
+            });
+        }
+        let color = if focused { FOCUS_COLOR } else { HEADER_COLOR };
+        let header = compare::clip(view.label(side), cols);
+        push_spans(text, vec![ColoredSpan::new(header, color)], left, header_y, right, header_y + LINE_HEIGHT);
+
+        let mut spans = Vec::with_capacity(visible.len());
+        for (i, row) in visible.iter().enumerate() {
+            let tint = match (row.op, side) {
+                (RowOp::Equal, _) | (RowOp::Removed, Side::Right) | (RowOp::Added, Side::Left) => None,
+                (RowOp::Changed, _) => Some(CHANGED_TINT),
+                (RowOp::Removed, Side::Left) => Some(REMOVED_TINT),
+                (RowOp::Added, Side::Right) => Some(ADDED_TINT),
+            };
+            if let Some(tint) = tint {
+                quads.push(QuadInstance {
+                    x,
+                    y: body_top + i as f32 * LINE_HEIGHT,
+                    w: column_w,
+                    h: LINE_HEIGHT,
+                    color: tint,
+                    layer: QuadLayer::Overlay,
+                });
+            }
+            match view.line(side, row) {
+                Some(line) => spans.extend(renderer::direct_line_spans(&compare::clip(line, cols))),
+                None => spans.push(ColoredSpan::new(format!("{}\n", compare::hatching(cols)), HATCH_COLOR)),
+            }
+        }
+        push_spans(text, spans, left, body_top, right, body_bottom);
+    }
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: footer_y - 2.0,
+        w: lay.terminal_w,
+        h: LINE_HEIGHT + 4.0,
+        color: Rgba::rgb(0.12, 0.14, 0.2),
+        layer: QuadLayer::Overlay,
+    });
+    let right = lay.terminal_x + lay.terminal_w - padding;
+    push_spans(text, vec![ColoredSpan::new(view.footer(), FOOTER_COLOR)], lay.terminal_x + padding, footer_y, right, footer_y + LINE_HEIGHT);
+}
+
+fn push_spans(text: &mut TextEngine, spans: Vec<ColoredSpan>, left: f32, top: f32, right: f32, bottom: f32) {
+    let default_color = spans.first().map_or(HEADER_COLOR, |s| s.color);
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color,
+    });
+}
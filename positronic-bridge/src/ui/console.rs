@@ -0,0 +1,90 @@
+//! `!io console` pane.
+//!
+//! Covers the terminal area: a header with the port, TX/RX counters, line
+//! ending and exit chord, the device's scrollback below it, and the search
+//! prompt as a footer while searching. The current match gets a bar
+//! behind its row. State and key handling live in `crate::console`.
+
+use glyphon::TextBounds;
+
+use crate::console::Console;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::keymap::Chord;
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+const HEADER_COLOR: Rgba = Rgba::rgb(0.45, 0.8, 0.95);
+const FOOTER_COLOR: Rgba = Rgba::rgb(0.95, 0.75, 0.3);
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, console: &Console, exit: Option<&Chord>) {
+    let padding = lay.padding;
+    let left = lay.terminal_x + padding;
+    let right = lay.terminal_x + lay.terminal_w - padding;
+    let header_y = lay.terminal_y + padding / 2.0;
+    let body_top = header_y + LINE_HEIGHT + padding / 2.0;
+    let search = console.search_line();
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+    let body_bottom = if search.is_some() { footer_y - 2.0 } else { lay.terminal_y + lay.terminal_h - padding / 2.0 };
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.03, 0.04, 0.06, 0.98),
+        layer: QuadLayer::Overlay,
+    });
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: body_top - padding / 4.0,
+        w: lay.terminal_w,
+        h: 1.0,
+        color: Rgba::rgb(0.2, 0.24, 0.32),
+        layer: QuadLayer::Overlay,
+    });
+    push_text(text, console.header(exit), HEADER_COLOR, left, header_y, right, header_y + LINE_HEIGHT);
+
+    let rows = ((body_bottom - body_top) / LINE_HEIGHT).floor().max(1.0) as usize;
+    let view = console.visible(rows);
+    if let Some(row) = view.highlight {
+        quads.push(QuadInstance {
+            x: lay.terminal_x,
+            y: body_top + row as f32 * LINE_HEIGHT,
+            w: lay.terminal_w,
+            h: LINE_HEIGHT,
+            color: Rgba::new(0.35, 0.3, 0.1, 0.8),
+            layer: QuadLayer::Overlay,
+        });
+    }
+    let body = if view.lines.is_empty() { "waiting for data…".to_string() } else { view.lines.join("\n") };
+    push_text(text, body, Rgba::rgb(0.85, 0.85, 0.85), left, body_top, right, body_bottom);
+
+    if let Some(search) = search {
+        quads.push(QuadInstance {
+            x: lay.terminal_x,
+            y: footer_y - 2.0,
+            w: lay.terminal_w,
+            h: LINE_HEIGHT + 4.0,
+            color: Rgba::rgb(0.12, 0.14, 0.2),
+            layer: QuadLayer::Overlay,
+        });
+        push_text(text, search, FOOTER_COLOR, left, footer_y, right, footer_y + LINE_HEIGHT);
+    }
+}
+
+fn push_text(text: &mut TextEngine, content: String, color: Rgba, left: f32, top: f32, right: f32, bottom: f32) {
+    text.push_region(TextRegion {
+        spans: vec![ColoredSpan::new(content, color)],
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color: color,
+    });
+}
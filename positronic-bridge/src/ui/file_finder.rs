@@ -0,0 +1,108 @@
+//! Ctrl+P file finder overlay.
+//!
+//! Covers the terminal area: the root and query as a title, then the
+//! ranked files one per line, matched characters lit and the picked file
+//! on a highlight bar. The footer shows the index's progress, or the
+//! action keys once a file is picked. Content and keys live in
+//! `crate::file_finder`.
+
+use glyphon::TextBounds;
+
+use crate::file_finder::{FinderLine, FinderView};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+const TITLE_COLOR: Rgba = Rgba::rgb(0.45, 0.8, 0.95);
+const ROW_COLOR: Rgba = Rgba::rgb(0.9, 0.92, 0.95);
+const MATCH_COLOR: Rgba = Rgba::rgb(0.95, 0.75, 0.3);
+const DIM_COLOR: Rgba = Rgba::rgb(0.5, 0.52, 0.6);
+const LIT_BG: Rgba = Rgba::rgb(0.1, 0.16, 0.24);
+const FOOTER_COLOR: Rgba = Rgba::rgb(0.6, 0.62, 0.7);
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, view: &FinderView) {
+    let padding = lay.padding;
+    let left = lay.terminal_x + padding;
+    let right = lay.terminal_x + lay.terminal_w - padding;
+    let title_y = lay.terminal_y + padding / 2.0;
+    let body_top = title_y + LINE_HEIGHT + padding / 2.0;
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+    let body_bottom = footer_y - 2.0;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.04, 0.05, 0.07, 0.96),
+        layer: QuadLayer::Overlay,
+    });
+    push_spans(text, vec![ColoredSpan::new(view.title.clone(), TITLE_COLOR)], left, title_y, right, title_y + LINE_HEIGHT);
+
+    let rows = ((body_bottom - body_top) / LINE_HEIGHT).floor().max(1.0) as usize;
+    let first = view.first(rows);
+    let mut spans = Vec::new();
+    for (i, line) in view.lines.iter().skip(first).take(rows).enumerate() {
+        if line.lit {
+            quads.push(QuadInstance {
+                x: lay.terminal_x,
+                y: body_top + i as f32 * LINE_HEIGHT,
+                w: lay.terminal_w,
+                h: LINE_HEIGHT,
+                color: LIT_BG,
+                layer: QuadLayer::Overlay,
+            });
+        }
+        spans.extend(line_spans(line));
+        spans.push(ColoredSpan::new("\n", ROW_COLOR));
+    }
+    if spans.is_empty() {
+        spans.push(ColoredSpan::new("No file matches", DIM_COLOR));
+    }
+    push_spans(text, spans, left, body_top, right, body_bottom);
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: footer_y - 2.0,
+        w: lay.terminal_w,
+        h: LINE_HEIGHT + 4.0,
+        color: Rgba::rgb(0.12, 0.14, 0.2),
+        layer: QuadLayer::Overlay,
+    });
+    push_spans(text, vec![ColoredSpan::new(view.footer.clone(), FOOTER_COLOR)], left, footer_y, right, footer_y + LINE_HEIGHT);
+}
+
+/// The path, matched characters lit.
+fn line_spans(line: &FinderLine) -> Vec<ColoredSpan> {
+    let mut spans = Vec::new();
+    let mut at = 0;
+    for range in &line.ranges {
+        if range.start > at {
+            spans.push(ColoredSpan::new(line.text[at..range.start].to_string(), ROW_COLOR));
+        }
+        spans.push(ColoredSpan::new(line.text[range.clone()].to_string(), MATCH_COLOR));
+        at = range.end;
+    }
+    if at < line.text.len() {
+        spans.push(ColoredSpan::new(line.text[at..].to_string(), ROW_COLOR));
+    }
+    spans
+}
+
+fn push_spans(text: &mut TextEngine, spans: Vec<ColoredSpan>, left: f32, top: f32, right: f32, bottom: f32) {
+    let default_color = spans.first().map_or(ROW_COLOR, |s| s.color);
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color,
+    });
+}
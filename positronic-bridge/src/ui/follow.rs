@@ -0,0 +1,100 @@
+//! `!follow` pane.
+//!
+//! Covers the terminal area: a header with the command, its state and the
+//! active filters, the followed lines colored by severity, and a footer
+//! with the keys or the regex prompt. While paused a badge sits at the
+//! right of the header. State and key handling live in `crate::follow`.
+
+use glyphon::TextBounds;
+
+use crate::follow::FollowState;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::keymap::Chord;
+use crate::renderer::{line_kind_color, ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+const HEADER_COLOR: Rgba = Rgba::rgb(0.45, 0.8, 0.95);
+const FOOTER_COLOR: Rgba = Rgba::rgb(0.6, 0.62, 0.7);
+const PROMPT_COLOR: Rgba = Rgba::rgb(0.95, 0.75, 0.3);
+const BADGE_COLOR: Rgba = Rgba::rgb(0.1, 0.1, 0.12);
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, follow: &FollowState, interrupt: Option<&Chord>) {
+    let padding = lay.padding;
+    let left = lay.terminal_x + padding;
+    let right = lay.terminal_x + lay.terminal_w - padding;
+    let header_y = lay.terminal_y + padding / 2.0;
+    let body_top = header_y + LINE_HEIGHT + padding / 2.0;
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+    let body_bottom = footer_y - 2.0;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.03, 0.04, 0.06, 0.98),
+        layer: QuadLayer::Overlay,
+    });
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: body_top - padding / 4.0,
+        w: lay.terminal_w,
+        h: 1.0,
+        color: Rgba::rgb(0.2, 0.24, 0.32),
+        layer: QuadLayer::Overlay,
+    });
+    push_spans(text, vec![ColoredSpan::new(follow.header(), HEADER_COLOR)], left, header_y, right, header_y + LINE_HEIGHT);
+
+    if let Some(badge) = follow.badge() {
+        let width = badge.chars().count() as f32 * LINE_HEIGHT * 0.55 + padding;
+        let x = right - width;
+        quads.push(QuadInstance {
+            x: x - padding / 2.0,
+            y: header_y - 1.0,
+            w: width + padding / 2.0,
+            h: LINE_HEIGHT + 2.0,
+            color: Rgba::rgb(0.95, 0.75, 0.3),
+            layer: QuadLayer::Overlay,
+        });
+        push_spans(text, vec![ColoredSpan::new(badge, BADGE_COLOR)], x, header_y, right, header_y + LINE_HEIGHT);
+    }
+
+    let rows = ((body_bottom - body_top) / LINE_HEIGHT).floor().max(1.0) as usize;
+    let lines = follow.visible(rows);
+    let spans = if lines.is_empty() {
+        let waiting = if follow.is_empty() { "waiting for output…" } else { "no lines match the filters" };
+        vec![ColoredSpan::new(waiting, FOOTER_COLOR)]
+    } else {
+        lines.iter().map(|line| ColoredSpan::new(format!("{}\n", line.text), line_kind_color(line.kind))).collect()
+    };
+    push_spans(text, spans, left, body_top, right, body_bottom);
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: footer_y - 2.0,
+        w: lay.terminal_w,
+        h: LINE_HEIGHT + 4.0,
+        color: Rgba::rgb(0.12, 0.14, 0.2),
+        layer: QuadLayer::Overlay,
+    });
+    let color = if follow.prompt.is_some() { PROMPT_COLOR } else { FOOTER_COLOR };
+    push_spans(text, vec![ColoredSpan::new(follow.footer(interrupt), color)], left, footer_y, right, footer_y + LINE_HEIGHT);
+}
+
+fn push_spans(text: &mut TextEngine, spans: Vec<ColoredSpan>, left: f32, top: f32, right: f32, bottom: f32) {
+    let default_color = spans.first().map_or(FOOTER_COLOR, |s| s.color);
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color,
+    });
+}
@@ -1,25 +1,72 @@
 //! Input bar rendering component.
 //!
-//! Renders the command input field with cursor indicator.
+//! Renders the command input field with cursor indicator. AI-generated
+//! commands get an amber edge and a "review before Enter" label. Typed
+//! commands are colored by `highlight`; existing paths are underlined.
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
-use crate::gfx::text::TextRegion;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::helpers::cell_columns;
+use crate::highlight::{HighlightKind, HighlightSpan};
 use crate::renderer::{ColoredSpan, Rgba};
-use crate::shell::layout::Layout;
+use crate::layout::ComputedLayout;
+use positronic_core::term::modes::CursorShape;
 use super::scene::SceneData;
 
-/// Approximate monospace character width at font size 14.
+/// Approximate monospace cell width at font size 14.
 const CHAR_WIDTH: f32 = 8.4;
 
+const AI_LABEL: &str = "AI generated — review before Enter";
+const AI_LABEL_WIDTH: f32 = 260.0;
+const AI_ACCENT: Rgba = Rgba::rgb(0.95, 0.7, 0.25);
+const HINT_COLOR: Rgba = Rgba::rgb(0.5, 0.55, 0.6);
+
+fn highlight_color(kind: HighlightKind, fg: Rgba) -> Rgba {
+    match kind {
+        HighlightKind::Command => Rgba::rgb(0.4, 0.85, 0.55),
+        HighlightKind::UnknownCommand => Rgba::rgb(0.95, 0.35, 0.35),
+        HighlightKind::Flag => Rgba::rgb(0.55, 0.58, 0.62),
+        HighlightKind::String => Rgba::rgb(0.9, 0.75, 0.4),
+        HighlightKind::Path => Rgba::rgb(0.45, 0.75, 0.95),
+        HighlightKind::Operator => Rgba::rgb(0.75, 0.5, 0.9),
+        HighlightKind::Comment => Rgba::rgb(0.45, 0.45, 0.5),
+        HighlightKind::Argument => fg,
+    }
+}
+
+/// `input` cut into colored spans; text between highlights keeps `fg`.
+fn highlighted(input: &str, highlights: &[HighlightSpan], fg: Rgba) -> Vec<ColoredSpan> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut spans = Vec::with_capacity(highlights.len() * 2 + 1);
+    let mut at = 0;
+    for h in highlights {
+        let (start, end) = (h.range.start.min(chars.len()), h.range.end.min(chars.len()));
+        if start < at {
+            continue;
+        }
+        if start > at {
+            spans.push(ColoredSpan::new(chars[at..start].iter().collect::<String>(), fg));
+        }
+        spans.push(ColoredSpan::new(chars[start..end].iter().collect::<String>(), highlight_color(h.kind, fg)));
+        at = end;
+    }
+    if at < chars.len() {
+        spans.push(ColoredSpan::new(chars[at..].iter().collect::<String>(), fg));
+    }
+    spans
+}
+
 pub fn draw(
     quads: &mut QuadPipeline,
     text: &mut TextEngine,
-    lay: &Layout,
+    lay: &ComputedLayout,
     data: &SceneData<'_>,
 ) {
     let theme = data.theme;
+    // The AI label and hint are drawn smaller, centered in the bar.
+    let label_top = lay.input_y + ((lay.input_h - LINE_HEIGHT * 0.8) / 2.0).round();
 
     // Background
     quads.push(QuadInstance {
@@ -28,6 +75,7 @@ pub fn draw(
         w: lay.input_w,
         h: lay.input_h,
         color: theme.input_bg(),
+        layer: QuadLayer::Background,
     });
 
     // Top border
@@ -37,34 +85,83 @@ pub fn draw(
         w: lay.input_w,
         h: 1.0,
         color: Rgba::rgb(0.2, 0.22, 0.25),
+        layer: QuadLayer::Background,
     });
 
+    if data.input_ai_generated {
+        quads.push(QuadInstance {
+            x: lay.input_x,
+            y: lay.input_y,
+            w: 3.0,
+            h: lay.input_h,
+            color: AI_ACCENT,
+            layer: QuadLayer::Background,
+        });
+
+        let label_left = lay.input_x + lay.input_w - AI_LABEL_WIDTH;
+        text.push_region(TextRegion {
+            spans: vec![ColoredSpan::new(AI_LABEL, AI_ACCENT)],
+            bounds: TextBounds {
+                left: label_left as i32,
+                top: label_top as i32 - 2,
+                right: (lay.input_x + lay.input_w - lay.input_padding) as i32,
+                bottom: (lay.input_y + lay.input_h) as i32,
+            },
+            left: label_left,
+            top: label_top,
+            scale: 0.8,
+            default_color: AI_ACCENT,
+        });
+    }
+
+    if let Some(hint) = data.input_hint.filter(|_| !data.input_ai_generated) {
+        let hint_width = (cell_columns(hint, usize::MAX) as f32 + 2.0) * CHAR_WIDTH * 0.8;
+        let hint_left = lay.input_x + lay.input_w - lay.input_padding - hint_width;
+        text.push_region(TextRegion {
+            spans: vec![ColoredSpan::new(hint, HINT_COLOR)],
+            bounds: TextBounds {
+                left: hint_left as i32,
+                top: label_top as i32 - 2,
+                right: (lay.input_x + lay.input_w - lay.input_padding) as i32,
+                bottom: (lay.input_y + lay.input_h) as i32,
+            },
+            left: hint_left,
+            top: label_top,
+            scale: 0.8,
+            default_color: HINT_COLOR,
+        });
+    }
+
     // Prompt prefix
     let prompt = "❯ ";
-    let prompt_width = prompt.chars().count() as f32 * CHAR_WIDTH;
-    let text_left = lay.input_x + 10.0;
-    let text_top = lay.input_y + 9.0;
+    let prompt_width = cell_columns(prompt, usize::MAX) as f32 * CHAR_WIDTH;
+    let text_left = lay.input_x + lay.input_padding;
+    let text_top = lay.input_y + ((lay.input_h - LINE_HEIGHT) / 2.0).round();
 
     // Build display text
     let display = if data.input.is_empty() {
         vec![
             ColoredSpan::new(prompt, Rgba::rgb(0.3, 0.85, 0.3)),
             ColoredSpan::new(
-                "Type a command… (!help for commands)",
+                data.input_placeholder.unwrap_or("Type a command… (!help for commands)"),
                 Rgba::rgb(0.4, 0.4, 0.45),
             ),
         ]
     } else {
-        vec![
-            ColoredSpan::new(prompt, Rgba::rgb(0.3, 0.85, 0.3)),
-            ColoredSpan::new(data.input, theme.input_fg()),
-        ]
+        let mut spans = vec![ColoredSpan::new(prompt, Rgba::rgb(0.3, 0.85, 0.3))];
+        spans.extend(highlighted(data.input, data.input_highlights, theme.input_fg()));
+        spans
     };
 
+    let right_edge = if data.input_ai_generated {
+        lay.input_x + lay.input_w - AI_LABEL_WIDTH
+    } else {
+        lay.input_x + lay.input_w - lay.input_padding
+    };
     let bounds = TextBounds {
         left: text_left as i32,
         top: text_top as i32,
-        right: (lay.input_x + lay.input_w - 10.0) as i32,
+        right: right_edge as i32,
         bottom: (lay.input_y + lay.input_h) as i32,
     };
 
@@ -77,19 +174,37 @@ pub fn draw(
         default_color: theme.input_fg(),
     });
 
+    for h in data.input_highlights.iter().filter(|h| h.underline) {
+        let start = cell_columns(data.input, h.range.start);
+        let end = cell_columns(data.input, h.range.end);
+        quads.push(QuadInstance {
+            x: text_left + prompt_width + start as f32 * CHAR_WIDTH,
+            y: text_top + 17.0,
+            w: (end - start) as f32 * CHAR_WIDTH,
+            h: 1.0,
+            color: highlight_color(h.kind, theme.input_fg()),
+            layer: QuadLayer::Background,
+        });
+    }
+
     // ── Cursor ──
-    if !(data.input.is_empty() && !false) {
-        // Show cursor even on empty input
-        let cursor_x = text_left + prompt_width + (data.cursor_pos as f32 * CHAR_WIDTH);
-        let cursor_y = text_top;
+    if data.cursor_visible {
+        let cursor_x = text_left + prompt_width + (cell_columns(data.input, data.cursor_pos) as f32 * CHAR_WIDTH);
         let cursor_h = 16.0;
+        let (y, w, h, color) = match data.cursor_shape {
+            CursorShape::Bar => (text_top, 2.0, cursor_h, theme.cursor_color()),
+            // Text is drawn after quads, so the character stays readable.
+            CursorShape::Block => (text_top, CHAR_WIDTH, cursor_h, theme.cursor_color().blend(theme.input_bg(), 0.4)),
+            CursorShape::Underline => (text_top + cursor_h - 2.0, CHAR_WIDTH, 2.0, theme.cursor_color()),
+        };
 
         quads.push(QuadInstance {
             x: cursor_x,
-            y: cursor_y,
-            w: 2.0,
-            h: cursor_h,
-            color: theme.cursor_color(),
+            y,
+            w,
+            h,
+            color,
+            layer: QuadLayer::Cursor,
         });
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,98 @@
+//! Key binding reference overlay (F1, `!keys show`).
+//!
+//! Covers the terminal area: the filter as a title, then the sheet one
+//! line per row, section headers lit, rows whose chord conflicts in
+//! orange, unbound, shadowed and app-bound rows dim, and the row of the
+//! chord just pressed on a highlight bar. The footer says what that chord
+//! does. Content and keys live in `crate::key_help`.
+
+use glyphon::TextBounds;
+
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::key_help::{KeyHelpView, LineKind};
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+
+const TITLE_COLOR: Rgba = Rgba::rgb(0.45, 0.8, 0.95);
+const HEADER_COLOR: Rgba = Rgba::rgb(0.95, 0.75, 0.3);
+const ROW_COLOR: Rgba = Rgba::rgb(0.9, 0.92, 0.95);
+const DIM_COLOR: Rgba = Rgba::rgb(0.5, 0.52, 0.6);
+const CONFLICT_COLOR: Rgba = Rgba::rgb(1.0, 0.6, 0.2);
+const LIT_BG: Rgba = Rgba::rgb(0.1, 0.16, 0.24);
+const FOOTER_COLOR: Rgba = Rgba::rgb(0.6, 0.62, 0.7);
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, view: &KeyHelpView) {
+    let padding = lay.padding;
+    let left = lay.terminal_x + padding;
+    let right = lay.terminal_x + lay.terminal_w - padding;
+    let title_y = lay.terminal_y + padding / 2.0;
+    let body_top = title_y + LINE_HEIGHT + padding / 2.0;
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+    let body_bottom = footer_y - 2.0;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.04, 0.05, 0.07, 0.96),
+        layer: QuadLayer::Overlay,
+    });
+    push_spans(text, vec![ColoredSpan::new(view.title.clone(), TITLE_COLOR)], left, title_y, right, title_y + LINE_HEIGHT);
+
+    let rows = ((body_bottom - body_top) / LINE_HEIGHT).floor().max(1.0) as usize;
+    let first = view.first(rows);
+    let mut spans = Vec::with_capacity(rows);
+    for (i, line) in view.lines.iter().skip(first).take(rows).enumerate() {
+        let color = match line.kind {
+            LineKind::Header => HEADER_COLOR,
+            LineKind::Row { lit: true, .. } => {
+                quads.push(QuadInstance {
+                    x: lay.terminal_x,
+                    y: body_top + i as f32 * LINE_HEIGHT,
+                    w: lay.terminal_w,
+                    h: LINE_HEIGHT,
+                    color: LIT_BG,
+                    layer: QuadLayer::Overlay,
+                });
+                TITLE_COLOR
+            }
+            LineKind::Row { conflict: true, .. } => CONFLICT_COLOR,
+            LineKind::Row { dim: true, .. } => DIM_COLOR,
+            LineKind::Row { .. } => ROW_COLOR,
+        };
+        spans.push(ColoredSpan::new(format!("{}\n", line.text), color));
+    }
+    if spans.is_empty() {
+        spans.push(ColoredSpan::new("No binding matches the filter", DIM_COLOR));
+    }
+    push_spans(text, spans, left, body_top, right, body_bottom);
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: footer_y - 2.0,
+        w: lay.terminal_w,
+        h: LINE_HEIGHT + 4.0,
+        color: Rgba::rgb(0.12, 0.14, 0.2),
+        layer: QuadLayer::Overlay,
+    });
+    push_spans(text, vec![ColoredSpan::new(view.footer.clone(), FOOTER_COLOR)], left, footer_y, right, footer_y + LINE_HEIGHT);
+}
+
+fn push_spans(text: &mut TextEngine, spans: Vec<ColoredSpan>, left: f32, top: f32, right: f32, bottom: f32) {
+    let default_color = spans.first().map_or(ROW_COLOR, |s| s.color);
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color,
+    });
+}
@@ -7,4 +7,14 @@ pub mod scene;
 pub mod terminal;
 pub mod status;
 pub mod inputbar;
+pub mod perf;
+pub mod pager;
+pub mod compare;
+pub mod key_help;
+pub mod file_finder;
+pub mod suggestions;
+pub mod clipboard;
+pub mod scope;
+pub mod console;
+pub mod follow;
 mod holodeck;
\ No newline at end of file
@@ -0,0 +1,103 @@
+//! Full-area overlays: the pager for long native command output and the
+//! `!record play` screen.
+//!
+//! Both cover the terminal area with their content and a footer row with
+//! the position and key hints.
+
+use glyphon::TextBounds;
+
+use positronic_core::state_machine::Snapshot;
+
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::pager::Pager;
+use crate::renderer::{self, ColoredSpan, Rgba, ThemeName};
+use crate::layout::ComputedLayout;
+
+/// The pager's visible window, colored like direct output, or by its
+/// syntax spans for a highlighted `!view`.
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, pager: &Pager, theme: ThemeName) {
+    let syntax = pager.visible_syntax();
+    let spans = if syntax.is_empty() {
+        renderer::direct_to_spans(&pager.visible().join("\n"))
+    } else {
+        pager
+            .visible()
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| renderer::highlighted_line(line, syntax.get(i).map_or(&[], Vec::as_slice), theme))
+            .collect()
+    };
+    draw_overlay(quads, text, lay, spans, pager.footer());
+}
+
+/// A replayed recording's screen.
+pub fn draw_replay(
+    quads: &mut QuadPipeline,
+    text: &mut TextEngine,
+    lay: &ComputedLayout,
+    snapshot: &Snapshot,
+    footer: &str,
+    theme: ThemeName,
+) {
+    let spans = renderer::snapshot_to_spans(snapshot, theme);
+    draw_overlay(quads, text, lay, spans, footer.to_string());
+}
+
+fn draw_overlay(
+    quads: &mut QuadPipeline,
+    text: &mut TextEngine,
+    lay: &ComputedLayout,
+    spans: Vec<ColoredSpan>,
+    footer: String,
+) {
+    let padding = lay.padding;
+    let footer_y = lay.terminal_y + lay.terminal_h - LINE_HEIGHT - padding / 2.0;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: lay.terminal_y,
+        w: lay.terminal_w,
+        h: lay.terminal_h,
+        color: Rgba::new(0.04, 0.05, 0.07, 0.98),
+        layer: QuadLayer::Overlay,
+    });
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: footer_y - 2.0,
+        w: lay.terminal_w,
+        h: LINE_HEIGHT + 4.0,
+        color: Rgba::rgb(0.12, 0.14, 0.2),
+        layer: QuadLayer::Overlay,
+    });
+
+    let left = lay.terminal_x + padding;
+    let right = (lay.terminal_x + lay.terminal_w - padding) as i32;
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: (lay.terminal_y + padding) as i32,
+            right,
+            bottom: (footer_y - 2.0) as i32,
+        },
+        left,
+        top: lay.terminal_y + padding,
+        scale: 1.0,
+        default_color: Rgba::rgb(0.85, 0.85, 0.85),
+    });
+
+    text.push_region(TextRegion {
+        spans: vec![ColoredSpan::new(footer, Rgba::rgb(0.95, 0.75, 0.3))],
+        bounds: TextBounds {
+            left: left as i32,
+            top: footer_y as i32,
+            right,
+            bottom: (footer_y + LINE_HEIGHT) as i32,
+        },
+        left,
+        top: footer_y,
+        scale: 1.0,
+        default_color: Rgba::rgb(0.95, 0.75, 0.3),
+    });
+}
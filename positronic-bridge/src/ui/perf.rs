@@ -0,0 +1,82 @@
+//! Performance overlay (`!perf overlay`).
+//!
+//! A small panel in the top-right corner of the terminal area showing the
+//! render counters and live input latency. Quad counters are from the
+//! previous frame, since the current one has not been uploaded yet while
+//! the scene is composed.
+
+use glyphon::TextBounds;
+
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, QuadStats, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+use crate::span_cache::FrameStats;
+use positronic_core::term::latency::{millis, Percentiles, Stage};
+
+const PANEL_WIDTH: f32 = 260.0;
+const MARGIN: f32 = 8.0;
+
+/// Counters shown by the overlay.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerfStats {
+    pub quads: QuadStats,
+    pub spans: FrameStats,
+    /// Per-stage latency, in `Stage::ALL` order (see `!perf report`).
+    pub latency: [Option<Percentiles>; 6],
+}
+
+impl PerfStats {
+    pub fn lines(&self) -> Vec<String> {
+        let q = &self.quads;
+        let mut lines = vec![
+            "📈 perf".to_string(),
+            format!("quads      {} → {} drawn", q.submitted, q.drawn),
+            format!("draw calls {}", q.draw_calls),
+            format!("uploads    {} (skipped {})", q.buffer_writes, q.writes_skipped),
+            format!("buffer     {} instances", q.capacity),
+            format!("spans      {} built, {} reused", self.spans.built, self.spans.reused),
+        ];
+        if self.latency.iter().any(Option::is_some) {
+            lines.push("latency ms      p50 / p99".to_string());
+        }
+        for (stage, summary) in Stage::ALL.into_iter().zip(self.latency) {
+            if let Some(p) = summary {
+                lines.push(format!("{:<15} {} / {}", stage.label(), millis(p.p50), millis(p.p99)));
+            }
+        }
+        lines
+    }
+}
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, stats: &PerfStats) {
+    let lines = stats.lines();
+    let h = lines.len() as f32 * LINE_HEIGHT * 0.85 + MARGIN * 2.0;
+    let x = lay.terminal_x + lay.terminal_w - PANEL_WIDTH - MARGIN;
+    let y = lay.terminal_y + MARGIN;
+
+    quads.push(QuadInstance {
+        x,
+        y,
+        w: PANEL_WIDTH,
+        h,
+        color: Rgba::new(0.05, 0.06, 0.08, 0.88),
+        layer: QuadLayer::Overlay,
+    });
+
+    let bounds = TextBounds {
+        left: (x + MARGIN) as i32,
+        top: (y + MARGIN) as i32,
+        right: (x + PANEL_WIDTH - MARGIN) as i32,
+        bottom: (y + h) as i32,
+    };
+
+    text.push_region(TextRegion {
+        spans: vec![ColoredSpan::new(lines.join("\n"), Rgba::rgb(0.6, 0.85, 0.7))],
+        bounds,
+        left: x + MARGIN,
+        top: y + MARGIN,
+        scale: 0.85,
+        default_color: Rgba::rgb(0.6, 0.85, 0.7),
+    });
+}
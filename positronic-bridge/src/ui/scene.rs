@@ -1,12 +1,32 @@
 //! Scene compositor.
 
+use std::ops::Range;
 use std::time::Instant;
 
 use crate::gfx::{QuadPipeline, TextEngine};
 use crate::renderer::ThemeName;
+use crate::clipboard_history::ClipboardPicker;
+use crate::error_center::ErrorSeverity;
+use crate::highlight::HighlightSpan;
+use crate::span_cache::SpanCache;
+use crate::viewport::Viewport;
+use crate::console::Console;
+use crate::follow::FollowState;
+use crate::keymap::Chord;
+use crate::compare::CompareView;
+use crate::file_finder::FinderView;
+use crate::key_help::KeyHelpView;
+use crate::pager::Pager;
+use crate::hardware::HardwarePanel;
+use crate::scope::ScopeView;
+use crate::selection::Selection;
+use crate::suggestions::SuggestionPicker;
+use super::perf::PerfStats;
 use crate::shell::app::AppState;
-use crate::shell::layout;
+use crate::gfx::text::CELL;
+use crate::layout::{self, LayoutSpec};
 use positronic_core::state_machine::Snapshot;
+use positronic_core::term::modes::CursorShape;
 
 use crate::holodeck::protocol::HolodeckDoc;
 
@@ -15,15 +35,106 @@ pub struct SceneData<'a> {
     pub snapshot: Option<&'a Snapshot>,
     pub direct_output: &'a str,
     pub input: &'a str,
+    /// Shown in place of the default hint while the input is empty.
+    pub input_placeholder: Option<&'a str>,
     pub cursor_pos: usize,
+    /// Input cursor shape, and whether it is in the on phase of a blink.
+    pub cursor_shape: CursorShape,
+    pub cursor_visible: bool,
+    /// `window.padding`.
+    pub padding: f32,
+    /// `layout.*` chrome sizes.
+    pub layout: LayoutSpec,
+    /// Syntax highlighting for `input`; empty draws it plain.
+    pub input_highlights: &'a [HighlightSpan],
+    /// Passive note at the right of the input bar (`calc.hint`).
+    pub input_hint: Option<&'a str>,
+    /// Input holds an unreviewed AI-generated command.
+    pub input_ai_generated: bool,
     pub theme: ThemeName,
     pub session_cmd_count: usize,
     pub boot_instant: Instant,
     pub cwd: &'a str,
+    /// Active `!profile`, if any.
+    pub profile: Option<&'a str>,
+
+    /// Retained spans, reused across frames until content or theme changes.
+    pub span_cache: &'a mut SpanCache,
+    /// Scroll position; the pill's rect is written back while drawing.
+    pub viewport: &'a mut Viewport,
+
+    /// Render counters; `Some` while `!perf overlay` is on.
+    pub perf: Option<PerfStats>,
+
+    /// Open `!suggest` picker; row rects are written back while drawing.
+    pub suggestions: Option<&'a mut SuggestionPicker>,
+    /// Open clipboard history picker; row rects are written back too.
+    pub clipboard: Option<&'a mut ClipboardPicker>,
+
+    /// Open `!scope` pane and the panel it plots; drawn over the lower
+    /// part of the terminal area.
+    pub scope: Option<(&'a ScopeView, &'a HardwarePanel)>,
+
+    /// Open pager for long native output; drawn over the terminal area.
+    pub pager: Option<&'a Pager>,
+    /// Open `!compare` view; drawn over the terminal area.
+    pub compare: Option<&'a CompareView>,
+    /// F1 / `!keys show` reference; drawn over the terminal area.
+    pub key_help: Option<&'a KeyHelpView>,
+    /// Ctrl+P file finder; drawn over the terminal area.
+    pub file_finder: Option<&'a FinderView>,
+
+    /// Open `!io console` and its exit chord; drawn over the terminal area.
+    pub console: Option<(&'a Console, Option<&'a Chord>)>,
+    /// Open `!follow` pane and the interrupt chord; drawn over the terminal area.
+    pub follow: Option<(&'a FollowState, Option<&'a Chord>)>,
+
+    /// `!record play` screen and footer; drawn over the terminal area.
+    pub replay: Option<(&'a Snapshot, &'a str)>,
+    /// `🔧 3 env` while `!setenv` overrides are in effect.
+    pub env_badge: Option<&'a str>,
+    /// Status bar label while `!record` is capturing.
+    pub recording: Option<&'a str>,
+    /// Timer for the running command; `true` once it is slow.
+    pub running: Option<(&'a str, bool)>,
+    /// Long operations in progress, one entry each.
+    pub tasks: Option<&'a str>,
+    /// When the last command ran, relative while recent (`time.relative`).
+    pub last_command: Option<&'a str>,
+    /// Shown in place of the theme name just after theme sync switched it.
+    pub theme_note: Option<&'a str>,
+    /// What the interrupt chord just did; shown ahead of the theme note.
+    pub key_note: Option<&'a str>,
+    /// Error toast or unread-errors badge (`error_center`), and how loud.
+    pub errors: Option<(&'a str, ErrorSeverity)>,
+    /// "● 3 unread" while finished output hasn't been seen.
+    pub unread: Option<&'a str>,
+    /// "🔕 until 14:32" while `!bell mute` holds.
+    pub bell_muted: Option<&'a str>,
+    /// A bell is flashing the status bar.
+    pub bell_flash: bool,
+    /// Started in safe mode; the status bar says so first.
+    pub safe_mode: bool,
+    /// `!present` is on; the status bar says so first.
+    pub presenting: bool,
+    /// The file `--inspect` is browsing; the status bar says so first.
+    pub inspect: Option<&'a str>,
+    /// Snapshot rows of unread output, marked by a bar on the left edge.
+    pub unread_rows: &'a [Range<usize>],
+    /// Snapshot rows at the top that are dimmed (`blocks.dim_old`).
+    pub dim_rows: usize,
+    /// Snapshot rows of the focused block, or of the one the input line
+    /// was edited from, tinted under the text.
+    pub block_rows: Option<Range<usize>>,
+    /// Mouse selection over the PTY screen, highlighted under the text.
+    pub selection: Option<Selection>,
 
     // Holodeck overlay (only shown when caller decides it is safe)
     pub holodeck_doc: Option<&'a mut HolodeckDoc>,
     pub holodeck_safe: bool,
+
+    /// Terminal text scale while a window resize settles (`resize`).
+    pub grid_scale: f32,
 }
 
 pub fn compose(
@@ -32,9 +143,53 @@ pub fn compose(
     viewport: [u32; 2],
     data: &mut SceneData<'_>,
 ) {
-    let lay = layout::compute(viewport);
+    let lay = layout::compute(viewport, &data.layout, data.padding, CELL, 1.0);
 
     super::status::draw(quads, text, &lay, data);
     super::inputbar::draw(quads, text, &lay, data);
     super::terminal::draw(quads, text, &lay, data);
+
+    if let Some((view, panel)) = data.scope {
+        super::scope::draw(quads, text, &lay, view, panel, data.theme);
+    }
+
+    if let Some((console, exit)) = data.console {
+        super::console::draw(quads, text, &lay, console, exit);
+    }
+
+    if let Some((follow, interrupt)) = data.follow {
+        super::follow::draw(quads, text, &lay, follow, interrupt);
+    }
+
+    if let Some(pager) = data.pager {
+        super::pager::draw(quads, text, &lay, pager, data.theme);
+    }
+
+    if let Some(view) = data.compare {
+        super::compare::draw(quads, text, &lay, view);
+    }
+
+    if let Some(view) = data.key_help {
+        super::key_help::draw(quads, text, &lay, view);
+    }
+
+    if let Some(view) = data.file_finder {
+        super::file_finder::draw(quads, text, &lay, view);
+    }
+
+    if let Some((snapshot, footer)) = data.replay {
+        super::pager::draw_replay(quads, text, &lay, snapshot, footer, data.theme);
+    }
+
+    if let Some(picker) = data.suggestions.as_deref_mut() {
+        super::suggestions::draw(quads, text, &lay, picker);
+    }
+
+    if let Some(picker) = data.clipboard.as_deref_mut() {
+        super::clipboard::draw(quads, text, &lay, picker);
+    }
+
+    if let Some(perf) = &data.perf {
+        super::perf::draw(quads, text, &lay, perf);
+    }
 }
@@ -0,0 +1,167 @@
+//! Oscilloscope pane (`!scope`).
+//!
+//! Covers the lower part of the terminal area: a header with the port and
+//! one stats line per channel, the traces overlaid in theme colors, and a
+//! footer with the key hints. Geometry comes from `crate::scope`; this only
+//! turns it into quads and text, from the panel's state at draw time.
+
+use glyphon::TextBounds;
+
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::hardware::HardwarePanel;
+use crate::renderer::{ColoredSpan, Rgba, ThemeName};
+use crate::scope::{self, ScopeView, Viewport};
+use crate::layout::ComputedLayout;
+
+const MIN_HEIGHT: f32 = 160.0;
+const TEXT_SCALE: f32 = 0.85;
+/// Room left of the plot for the axis labels.
+const LABEL_WIDTH: f32 = 64.0;
+const LINE_THICKNESS: f32 = 1.5;
+/// Channels listed in the header; more are still drawn.
+const MAX_HEADER_CHANNELS: usize = 4;
+
+const HINT: &str = "+/- zoom (empty input) · !scope --range <min>:<max>|auto · !scope off";
+
+pub fn draw(
+    quads: &mut QuadPipeline,
+    text: &mut TextEngine,
+    lay: &ComputedLayout,
+    view: &ScopeView,
+    panel: &HardwarePanel,
+    theme: ThemeName,
+) {
+    let padding = lay.padding;
+    let line_h = LINE_HEIGHT * TEXT_SCALE;
+    let pane_h = (lay.terminal_h * 0.4).max(MIN_HEIGHT).min(lay.terminal_h);
+    let pane_y = lay.terminal_y + lay.terminal_h - pane_h;
+    let left = lay.terminal_x + padding;
+    let right = lay.terminal_x + lay.terminal_w - padding;
+
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: pane_y,
+        w: lay.terminal_w,
+        h: pane_h,
+        color: Rgba::new(0.03, 0.04, 0.06, 0.97),
+        layer: QuadLayer::Overlay,
+    });
+    quads.push(QuadInstance {
+        x: lay.terminal_x,
+        y: pane_y,
+        w: lay.terminal_w,
+        h: 1.0,
+        color: Rgba::rgb(0.2, 0.24, 0.32),
+        layer: QuadLayer::Overlay,
+    });
+
+    let channels = panel.channels_of(&view.port);
+
+    // Header: title, then a stats line per channel in its trace color.
+    let mut header = vec![ColoredSpan::new(format!("📈 {}", view.title_with(&panel.label(&view.port))), Rgba::rgb(0.85, 0.85, 0.85))];
+    for (i, (channel, trace)) in channels.iter().take(MAX_HEADER_CHANNELS).enumerate() {
+        header.push(ColoredSpan::new(
+            format!("\n{}", scope::channel_summary(*channel, trace)),
+            theme.trace_color(i),
+        ));
+    }
+    if channels.len() > MAX_HEADER_CHANNELS {
+        header.push(ColoredSpan::new(
+            format!("  (+{} more)", channels.len() - MAX_HEADER_CHANNELS),
+            Rgba::rgb(0.5, 0.55, 0.6),
+        ));
+    }
+    let header_lines = 1 + channels.len().min(MAX_HEADER_CHANNELS);
+    let header_top = pane_y + padding / 2.0;
+    let header_bottom = header_top + header_lines as f32 * line_h;
+    push_text(text, header, left, header_top, right, header_bottom);
+
+    let footer_y = pane_y + pane_h - line_h - padding / 2.0;
+    push_text(
+        text,
+        vec![ColoredSpan::new(HINT, Rgba::rgb(0.5, 0.55, 0.6))],
+        left,
+        footer_y,
+        right,
+        footer_y + line_h,
+    );
+
+    let plot = Viewport {
+        x: left + LABEL_WIDTH,
+        y: header_bottom + padding / 2.0,
+        w: (right - left - LABEL_WIDTH).max(0.0),
+        h: (footer_y - header_bottom - padding).max(0.0),
+    };
+    if plot.w < 1.0 || plot.h < 1.0 {
+        return;
+    }
+    quads.push(QuadInstance {
+        x: plot.x,
+        y: plot.y,
+        w: plot.w,
+        h: plot.h,
+        color: Rgba::new(0.06, 0.07, 0.1, 1.0),
+        layer: QuadLayer::Overlay,
+    });
+
+    let traces: Vec<Vec<(f64, f32)>> = channels.iter().map(|(_, trace)| trace.waveform.samples()).collect();
+    let Some(t_end) = scope::window_end(&traces) else {
+        push_text(
+            text,
+            vec![ColoredSpan::new("waiting for data…", Rgba::rgb(0.5, 0.55, 0.6))],
+            plot.x + plot.w / 2.0 - 60.0,
+            plot.y + plot.h / 2.0 - line_h / 2.0,
+            plot.x + plot.w,
+            plot.y + plot.h,
+        );
+        return;
+    };
+
+    // Grid: quarter lines, value labels at the top, middle and bottom.
+    let (lo, hi) = view.value_range(&traces, t_end);
+    for i in 1..4 {
+        quads.push(QuadInstance {
+            x: plot.x,
+            y: plot.y + plot.h * i as f32 / 4.0,
+            w: plot.w,
+            h: 1.0,
+            color: Rgba::new(0.2, 0.22, 0.28, 0.6),
+            layer: QuadLayer::Overlay,
+        });
+    }
+    for (value, y) in [(hi, plot.y), ((lo + hi) / 2.0, plot.y + plot.h / 2.0 - line_h / 2.0), (lo, plot.y + plot.h - line_h)] {
+        push_text(
+            text,
+            vec![ColoredSpan::new(scope::format_value(value), Rgba::rgb(0.5, 0.55, 0.6))],
+            left,
+            y,
+            plot.x - 4.0,
+            y + line_h,
+        );
+    }
+
+    for (i, samples) in traces.iter().enumerate() {
+        let points = scope::polyline(samples, t_end, view.window, (lo, hi), plot);
+        let color = theme.trace_color(i);
+        for [x, y, w, h] in scope::segment_quads(&points, LINE_THICKNESS) {
+            quads.push(QuadInstance { x, y, w, h, color, layer: QuadLayer::Overlay });
+        }
+    }
+}
+
+fn push_text(text: &mut TextEngine, spans: Vec<ColoredSpan>, left: f32, top: f32, right: f32, bottom: f32) {
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: right as i32,
+            bottom: bottom as i32,
+        },
+        left,
+        top,
+        scale: TEXT_SCALE,
+        default_color: Rgba::rgb(0.85, 0.85, 0.85),
+    });
+}
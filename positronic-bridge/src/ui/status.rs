@@ -1,23 +1,36 @@
 //! Status bar rendering component.
 //!
-//! Shows: command count, uptime, CWD, theme name, version.
+//! Shows: the running command's timer, long operations' progress,
+//! when the last command ran, command count, uptime, CWD, theme name (or a note just after theme
+//! sync switched it or the interrupt chord acted), active profile,
+//! `!setenv` overrides, recording, unread output, `!bell mute`, version,
+//! and ahead of them an error toast or the unread-errors badge, and before
+//! everything an inspect-mode or safe-mode badge, led by the `!present`
+//! badge. A bell's flash swaps the bar's colors
+//! for a moment.
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
-use crate::gfx::text::TextRegion;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::error_center::ErrorSeverity;
 use crate::helpers::{format_duration_short, short_path};
 use crate::renderer::{ColoredSpan, Rgba};
-use crate::shell::layout::Layout;
+use crate::layout::ComputedLayout;
 use super::scene::SceneData;
 
 pub fn draw(
     quads: &mut QuadPipeline,
     text: &mut TextEngine,
-    lay: &Layout,
+    lay: &ComputedLayout,
     data: &SceneData<'_>,
 ) {
     let theme = data.theme;
+    let (bg, fg) = if data.bell_flash {
+        (theme.status_fg(), theme.status_bg())
+    } else {
+        (theme.status_bg(), theme.status_fg())
+    };
 
     // Background
     quads.push(QuadInstance {
@@ -25,7 +38,8 @@ pub fn draw(
         y: lay.status_y,
         w: lay.status_w,
         h: lay.status_h,
-        color: theme.status_bg(),
+        color: bg,
+        layer: QuadLayer::Background,
     });
 
     // Top border
@@ -35,6 +49,7 @@ pub fn draw(
         w: lay.status_w,
         h: 1.0,
         color: Rgba::rgb(0.2, 0.22, 0.25),
+        layer: QuadLayer::Background,
     });
 
     // Status text
@@ -42,24 +57,71 @@ pub fn draw(
     let uptime_str = format_duration_short(uptime_secs);
     let short_cwd = short_path(data.cwd);
 
+    let profile = data.profile.map(|p| format!("  │  👤 {}", p)).unwrap_or_default();
+    let env = data.env_badge.map(|e| format!("  │  {}", e)).unwrap_or_default();
+    let recording = data.recording.map(|r| format!("  │  {}", r)).unwrap_or_default();
+    let unread = data.unread.map(|u| format!("  │  {}", u)).unwrap_or_default();
+    let muted = data.bell_muted.map(|m| format!("  │  {}", m)).unwrap_or_default();
+
+    let theme_label = data
+        .key_note
+        .or(data.theme_note)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("🎨 {}", data.theme.label()));
+
     let status_text = format!(
-        " ⚡ {} cmd  │  ⏱ {}  │  📂 {}  │  🎨 {}  │  Positronic v0.3.0",
-        data.session_cmd_count, uptime_str, short_cwd, data.theme.label(),
+        " ⚡ {} cmd  │  ⏱ {}  │  📂 {}  │  {}{}{}{}{}{}  │  Positronic v{}",
+        data.session_cmd_count, uptime_str, short_cwd, theme_label, profile, env, recording, unread, muted,
+        crate::version::BUILD.version,
     );
 
+    // Text is centered in the bar, whatever `layout.*` makes its height.
+    let text_top = lay.status_y + ((lay.status_h - LINE_HEIGHT * lay.status_font_scale) / 2.0).round();
     let bounds = TextBounds {
         left: lay.status_x as i32 + 8,
-        top: lay.status_y as i32 + 3,
+        top: text_top as i32 - 1,
         right: (lay.status_x + lay.status_w) as i32 - 8,
         bottom: (lay.status_y + lay.status_h) as i32,
     };
 
+    // The timer turns amber once the command is slower than the block
+    // badge threshold.
+    let mut spans = Vec::with_capacity(5);
+    if data.presenting {
+        spans.push(ColoredSpan::new(format!(" {}  │", crate::present::BADGE), Rgba::rgb(1.0, 0.35, 0.35)));
+    }
+    if let Some(file) = data.inspect {
+        spans.push(ColoredSpan::new(format!(" 🔍 INSPECT {} (read-only)  │", file), Rgba::rgb(0.75, 0.55, 1.0)));
+    } else if data.safe_mode {
+        spans.push(ColoredSpan::new(" 🛟 SAFE MODE  │", Rgba::rgb(1.0, 0.75, 0.3)));
+    }
+    if let Some((label, severity)) = data.errors {
+        let color = match severity {
+            ErrorSeverity::Error => Rgba::rgb(1.0, 0.4, 0.4),
+            ErrorSeverity::Warning => Rgba::rgb(1.0, 0.75, 0.3),
+            ErrorSeverity::Info => Rgba::rgb(0.3, 0.8, 1.0),
+        };
+        spans.push(ColoredSpan::new(format!(" {}  │", label), color));
+    }
+    if let Some((label, slow)) = data.running {
+        let color = if slow { Rgba::rgb(1.0, 0.6, 0.2) } else { Rgba::rgb(0.3, 0.8, 1.0) };
+        spans.push(ColoredSpan::new(format!(" {}  │", label), color));
+    }
+    if let Some(tasks) = data.tasks {
+        spans.push(ColoredSpan::new(format!(" {}  │", tasks), Rgba::rgb(0.45, 0.8, 0.95)));
+    }
+    // The running timer already says it; the stamp returns once it ends.
+    if let Some(last) = data.last_command.filter(|_| data.running.is_none()) {
+        spans.push(ColoredSpan::new(format!(" {}  │", last), fg));
+    }
+    spans.push(ColoredSpan::new(status_text, fg));
+
     text.push_region(TextRegion {
-        spans: vec![ColoredSpan::new(status_text, theme.status_fg())],
+        spans,
         bounds,
         left: lay.status_x + 8.0,
-        top: lay.status_y + 4.0,
-        scale: 0.85,
-        default_color: theme.status_fg(),
+        top: text_top,
+        scale: lay.status_font_scale,
+        default_color: fg,
     });
 }
\ No newline at end of file
@@ -0,0 +1,81 @@
+//! Suggestion picker panel (`!suggest`).
+//!
+//! Drawn just above the status bar, one row per suggestion with its key,
+//! the command, the model's reason, and a warning for destructive commands.
+//! Row rectangles are written back to the picker for click hit-testing.
+
+use glyphon::TextBounds;
+
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::renderer::{ColoredSpan, Rgba};
+use crate::layout::ComputedLayout;
+use crate::suggestions::SuggestionPicker;
+
+pub(super) const MARGIN: f32 = 8.0;
+pub(super) const ROW_HEIGHT: f32 = LINE_HEIGHT + 6.0;
+
+pub fn draw(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, picker: &mut SuggestionPicker) {
+    let count = picker.items().len();
+    let h = ROW_HEIGHT * (count as f32 + 1.0) + MARGIN;
+    let x = lay.terminal_x + MARGIN;
+    let w = lay.terminal_w - MARGIN * 2.0;
+    let y = lay.status_y - h - MARGIN;
+
+    quads.push(QuadInstance {
+        x,
+        y,
+        w,
+        h,
+        color: Rgba::new(0.07, 0.08, 0.11, 0.94),
+        layer: QuadLayer::Overlay,
+    });
+
+    let header = format!("💡 Suggestions — 1–{} or click to edit · Esc to dismiss", count);
+    push_row(text, x, y + MARGIN / 2.0, w, vec![ColoredSpan::new(header, Rgba::rgb(0.55, 0.6, 0.7))]);
+
+    let mut rows = Vec::with_capacity(count);
+    for (i, item) in picker.items().iter().enumerate() {
+        let row_y = y + MARGIN / 2.0 + ROW_HEIGHT * (i as f32 + 1.0);
+        rows.push([x, row_y, w, ROW_HEIGHT]);
+
+        let mut spans = vec![
+            ColoredSpan::new(format!(" {}  ", i + 1), Rgba::rgb(0.95, 0.75, 0.3)),
+            ColoredSpan::new(item.command.clone(), Rgba::rgb(0.9, 0.92, 0.95)),
+        ];
+        if item.danger.is_destructive() {
+            quads.push(QuadInstance {
+                x,
+                y: row_y,
+                w: 3.0,
+                h: ROW_HEIGHT,
+                color: Rgba::rgb(0.9, 0.25, 0.25),
+                layer: QuadLayer::Overlay,
+            });
+            spans.push(ColoredSpan::new("  ⚠ destructive", Rgba::rgb(1.0, 0.4, 0.4)));
+        }
+        if !item.reason.is_empty() {
+            spans.push(ColoredSpan::new(format!("  — {}", item.reason), Rgba::rgb(0.5, 0.55, 0.6)));
+        }
+        push_row(text, x, row_y, w, spans);
+    }
+    picker.set_rows(rows);
+}
+
+pub(super) fn push_row(text: &mut TextEngine, x: f32, y: f32, w: f32, spans: Vec<ColoredSpan>) {
+    let left = x + MARGIN;
+    let top = y + 3.0;
+    text.push_region(TextRegion {
+        spans,
+        bounds: TextBounds {
+            left: left as i32,
+            top: top as i32,
+            right: (x + w - MARGIN) as i32,
+            bottom: (y + ROW_HEIGHT) as i32,
+        },
+        left,
+        top,
+        scale: 1.0,
+        default_color: Rgba::rgb(0.9, 0.92, 0.95),
+    });
+}
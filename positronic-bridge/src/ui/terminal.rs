@@ -1,28 +1,57 @@
 //! Terminal output rendering component.
+//!
+//! Besides the output itself: the mouse selection behind the PTY text, a
+//! bar on the left edge of unread output, a thin scrollbar on the right
+//! edge while the content is taller than the screen, and the "▼ N new
+//! lines" pill while the view is scrolled back and output arrives below it,
+//! and a faint tick after each soft-wrapped row.
+//! Output above the `blocks.dim_old`th most recent command is dimmed, and
+//! the focused block (or the one the input line was edited from) tinted.
+//! `!stats heatmap` cells in native output are painted as quads.
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadPipeline, TextEngine};
-use crate::gfx::text::TextRegion;
-use crate::renderer::{self, ColoredSpan, Rgba};
+use crate::attention;
+use crate::heatmap;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::{TextRegion, LINE_HEIGHT};
+use crate::renderer::{first_content_row, ColoredSpan, Rgba};
+use crate::selection::Selection;
 use crate::shell::app::AppState;
-use crate::shell::layout::{self, Layout};
+use crate::layout::ComputedLayout;
 use super::scene::SceneData;
+use positronic_core::heatmap::HEAT_OPEN;
+use positronic_core::state_machine::Snapshot;
 
 use crate::holodeck::protocol::Rect as HRect;
 
+const SCROLLBAR_WIDTH: f32 = 4.0;
+const SCROLLBAR_MIN_THUMB: f32 = 16.0;
+const PILL_HEIGHT: f32 = LINE_HEIGHT + 6.0;
+const UNREAD_BAR_WIDTH: f32 = 3.0;
+const WRAP_MARK_WIDTH: f32 = 2.0;
+
 pub fn draw(
     quads: &mut QuadPipeline,
     text: &mut TextEngine,
-    lay: &Layout,
+    lay: &ComputedLayout,
     data: &mut SceneData<'_>,
 ) {
-    let padding = layout::TERMINAL_PADDING;
+    let padding = lay.padding;
+
+    data.span_cache.set_theme(data.theme);
+    let visible_rows = ((lay.terminal_h - padding) / LINE_HEIGHT).ceil().max(0.0) as usize;
 
-    let spans: Vec<ColoredSpan> = if let Some(snapshot) = data.snapshot {
-        renderer::snapshot_to_spans(snapshot, data.theme)
+    let mut spans: Vec<ColoredSpan> = if let Some(snapshot) = data.snapshot {
+        data.span_cache.snapshot_spans(snapshot)
     } else if !data.direct_output.is_empty() {
-        renderer::direct_to_spans(data.direct_output)
+        // Before the shell draws, the viewport scrolls native output.
+        let mut view = data.viewport.clone();
+        view.set_extent(data.direct_output.lines().count(), visible_rows);
+        if data.direct_output.contains(HEAT_OPEN) {
+            draw_heat_cells(quads, lay, data, view.window());
+        }
+        data.span_cache.direct_spans(data.direct_output, view.window())
     } else {
         match data.state {
             AppState::Booting => vec![ColoredSpan::new("⏳ Booting engine...\n", Rgba::rgb(0.7, 0.7, 0.7))],
@@ -31,6 +60,19 @@ pub fn draw(
         }
     };
 
+    if let (Some(snapshot), Some(selection)) = (data.snapshot, data.selection) {
+        draw_selection(quads, lay, snapshot, &selection);
+    }
+    if let Some(snapshot) = data.snapshot {
+        let first = first_content_row(snapshot);
+        attention::dim_rows(&mut spans, data.dim_rows.saturating_sub(first));
+        draw_unread(quads, lay, first, data.unread_rows);
+        draw_wraps(quads, lay, snapshot, first);
+        if let Some(rows) = &data.block_rows {
+            draw_block_tint(quads, lay, first, rows);
+        }
+    }
+
     if !spans.is_empty() {
         let bounds = TextBounds {
             left: (lay.terminal_x + padding) as i32,
@@ -44,11 +86,14 @@ pub fn draw(
             bounds,
             left: lay.terminal_x + padding,
             top: lay.terminal_y + padding,
-            scale: 1.0,
+            // The old grid fitted to the window while a resize settles.
+            scale: data.grid_scale,
             default_color: Rgba::rgb(0.85, 0.85, 0.85),
         });
     }
 
+    draw_scroll_state(quads, text, lay, data);
+
     // Holodeck overlay (safe-gated)
     if data.holodeck_safe {
         if let Some(doc) = data.holodeck_doc.as_deref_mut() {
@@ -68,3 +113,166 @@ pub fn draw(
         }
     }
 }
+
+/// One highlight quad per selected row; the view starts at the first row
+/// with content, as the text does.
+fn draw_selection(quads: &mut QuadPipeline, lay: &ComputedLayout, snapshot: &Snapshot, selection: &Selection) {
+    let first = first_content_row(snapshot);
+    let left = lay.terminal_x + lay.padding;
+    let top = lay.terminal_y + lay.padding;
+    for row in first..snapshot.rows() {
+        let Some(cols) = selection.columns(row, snapshot.cols()) else { continue };
+        let y = top + (row - first) as f32 * LINE_HEIGHT;
+        if y >= lay.terminal_y + lay.terminal_h {
+            break;
+        }
+        quads.push(QuadInstance {
+            x: left + cols.start as f32 * lay.cell_w,
+            y,
+            w: cols.len() as f32 * lay.cell_w,
+            h: LINE_HEIGHT,
+            color: Rgba::new(0.3, 0.45, 0.75, 0.45),
+            layer: QuadLayer::Selection,
+        });
+    }
+}
+
+/// A tint behind the rows of the focused or linked block.
+fn draw_block_tint(quads: &mut QuadPipeline, lay: &ComputedLayout, first: usize, rows: &std::ops::Range<usize>) {
+    let start = rows.start.max(first);
+    if start >= rows.end {
+        return;
+    }
+    let y = lay.terminal_y + lay.padding + (start - first) as f32 * LINE_HEIGHT;
+    let h = ((rows.end - start) as f32 * LINE_HEIGHT).min(lay.terminal_y + lay.terminal_h - y);
+    if h <= 0.0 {
+        return;
+    }
+    quads.push(QuadInstance {
+        x: lay.terminal_x + lay.padding / 2.0,
+        y,
+        w: lay.terminal_w - lay.padding,
+        h,
+        color: Rgba::new(1.0, 0.8, 0.35, 0.12),
+        layer: QuadLayer::Selection,
+    });
+}
+
+/// The `!stats heatmap` cells on screen, in the theme's gradient; their
+/// glyphs are blanked in the text over them.
+fn draw_heat_cells(quads: &mut QuadPipeline, lay: &ComputedLayout, data: &SceneData<'_>, window: std::ops::Range<usize>) {
+    let scale = data.grid_scale;
+    let lines = data.direct_output.lines().skip(window.start).take(window.len());
+    let left = lay.terminal_x + lay.padding;
+    let top = lay.terminal_y + lay.padding;
+    for ([x, y, w, h], level) in heatmap::cell_quads(lines, lay.cell_w * scale, LINE_HEIGHT * scale, heatmap::CELL_GAP) {
+        if top + y >= lay.terminal_y + lay.terminal_h {
+            break;
+        }
+        quads.push(QuadInstance {
+            x: left + x,
+            y: top + y,
+            w,
+            h,
+            color: heatmap::color(data.theme, level),
+            layer: QuadLayer::Selection,
+        });
+    }
+}
+
+/// A bar beside each unread block, over the rows of it on screen.
+fn draw_unread(quads: &mut QuadPipeline, lay: &ComputedLayout, first: usize, rows: &[std::ops::Range<usize>]) {
+    let top = lay.terminal_y + lay.padding;
+    let bottom = lay.terminal_y + lay.terminal_h;
+    for rows in rows {
+        let start = rows.start.max(first);
+        if start >= rows.end {
+            continue;
+        }
+        let y = top + (start - first) as f32 * LINE_HEIGHT;
+        let h = ((rows.end - start) as f32 * LINE_HEIGHT).min(bottom - y);
+        if h <= 0.0 {
+            continue;
+        }
+        quads.push(QuadInstance {
+            x: lay.terminal_x + 1.0,
+            y,
+            w: UNREAD_BAR_WIDTH,
+            h,
+            color: Rgba::rgb(0.3, 0.8, 1.0),
+            layer: QuadLayer::Overlay,
+        });
+    }
+}
+
+/// A tick just past the last column of each row whose line runs on into
+/// the next, so a wrapped line reads apart from two short ones.
+fn draw_wraps(quads: &mut QuadPipeline, lay: &ComputedLayout, snapshot: &Snapshot, first: usize) {
+    let x = lay.terminal_x + lay.padding + snapshot.cols() as f32 * lay.cell_w + 1.0;
+    let top = lay.terminal_y + lay.padding;
+    for row in first..snapshot.rows() {
+        if !snapshot.wraps(row) {
+            continue;
+        }
+        let y = top + (row - first) as f32 * LINE_HEIGHT;
+        if y >= lay.terminal_y + lay.terminal_h {
+            break;
+        }
+        quads.push(QuadInstance {
+            x,
+            y: y + LINE_HEIGHT * 0.3,
+            w: WRAP_MARK_WIDTH,
+            h: LINE_HEIGHT * 0.4,
+            color: Rgba::new(0.6, 0.65, 0.75, 0.35),
+            layer: QuadLayer::Overlay,
+        });
+    }
+}
+
+/// The scrollbar thumb and, while there is unseen output, the pill that
+/// jumps back to the bottom.
+fn draw_scroll_state(quads: &mut QuadPipeline, text: &mut TextEngine, lay: &ComputedLayout, data: &mut SceneData<'_>) {
+    let track_y = lay.terminal_y + lay.padding / 2.0;
+    let track_h = lay.terminal_h - lay.padding;
+    if let Some((start, len)) = data.viewport.thumb(track_h, SCROLLBAR_MIN_THUMB) {
+        let alpha = if data.viewport.is_scrolled_back() { 0.7 } else { 0.3 };
+        quads.push(QuadInstance {
+            x: lay.terminal_x + lay.terminal_w - SCROLLBAR_WIDTH - 2.0,
+            y: track_y + start,
+            w: SCROLLBAR_WIDTH,
+            h: len,
+            color: Rgba::new(0.6, 0.65, 0.75, alpha),
+            layer: QuadLayer::Overlay,
+        });
+    }
+
+    let Some(label) = data.viewport.pill() else {
+        data.viewport.set_pill_rect(None);
+        return;
+    };
+    let w = label.chars().count() as f32 * lay.cell_w + 24.0;
+    let x = lay.terminal_x + (lay.terminal_w - w) / 2.0;
+    let y = lay.terminal_y + lay.terminal_h - PILL_HEIGHT - 8.0;
+    quads.push(QuadInstance {
+        x,
+        y,
+        w,
+        h: PILL_HEIGHT,
+        color: Rgba::new(0.15, 0.35, 0.65, 0.92),
+        layer: QuadLayer::Overlay,
+    });
+    text.push_region(TextRegion {
+        spans: vec![ColoredSpan::new(label, Rgba::rgb(0.95, 0.97, 1.0))],
+        bounds: TextBounds {
+            left: x as i32,
+            top: y as i32,
+            right: (x + w) as i32,
+            bottom: (y + PILL_HEIGHT) as i32,
+        },
+        left: x + 12.0,
+        top: y + 3.0,
+        scale: 1.0,
+        default_color: Rgba::rgb(0.95, 0.97, 1.0),
+    });
+    data.viewport.set_pill_rect(Some([x, y, w, PILL_HEIGHT]));
+}
@@ -2,11 +2,13 @@ use tracing_subscriber::EnvFilter;
 
 pub fn init_tracing() {
     // RUST_LOG=positronic_bridge=debug,wgpu=warn
+    // Logs go to stderr so headless runs keep stdout for command output.
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info,wgpu=warn,naga=warn"));
 
     let _ = tracing_subscriber::fmt()
         .with_env_filter(filter)
+        .with_writer(std::io::stderr)
         .with_target(true)
         .with_thread_ids(true)
         .with_line_number(true)
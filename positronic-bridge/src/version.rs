@@ -0,0 +1,94 @@
+//! Where `!ver` and `positronic --version` get their facts (see
+//! `positronic_core::version`): the build script's stamp, and this
+//! binary's theme list, completer, renderer report and running engine.
+
+use std::path::PathBuf;
+
+use positronic_core::data_paths::DataPaths;
+use positronic_core::error::NEURAL_ENDPOINT;
+use positronic_core::runner::Runner;
+use positronic_core::tldr::Tldr;
+use positronic_core::vault::Vault;
+use positronic_core::version::{BuildInfo, NeuralInfo, VersionReport, VersionSources};
+use positronic_neural::reflex::ReflexEngine;
+
+use crate::completer;
+use crate::renderer::ThemeName;
+
+pub const BUILD: BuildInfo = BuildInfo {
+    version: env!("CARGO_PKG_VERSION"),
+    git: env!("POSITRONIC_GIT_DESCRIBE"),
+    date: env!("POSITRONIC_BUILD_DATE"),
+    target: env!("POSITRONIC_TARGET"),
+    profile: env!("POSITRONIC_PROFILE"),
+    features: env!("POSITRONIC_FEATURES"),
+};
+
+/// The sources for this process. Without a runner (the CLI, or before
+/// boot) the vault is read from disk and the rest is what a fresh engine
+/// would start with.
+pub struct Sources<'a> {
+    pub data: &'a DataPaths,
+    pub runner: Option<&'a Runner>,
+    /// The renderer report's summary, once a window is up.
+    pub renderer: Option<String>,
+}
+
+impl Sources<'_> {
+    pub fn report(&self) -> VersionReport {
+        VersionReport::assemble(BUILD, self)
+    }
+}
+
+impl VersionSources for Sources<'_> {
+    fn themes(&self) -> Vec<String> {
+        ThemeName::all().iter().map(|theme| theme.label().to_string()).collect()
+    }
+
+    fn native_commands(&self) -> usize {
+        completer::bang_commands().len()
+    }
+
+    fn schema_version(&self) -> Option<i64> {
+        match self.runner {
+            Some(runner) => runner.vault().schema_version().ok(),
+            None => Vault::schema_version_at(self.data.vault()).ok().flatten(),
+        }
+    }
+
+    fn renderer(&self) -> Option<String> {
+        self.renderer.clone()
+    }
+
+    fn neural(&self) -> NeuralInfo {
+        match self.runner.and_then(|runner| runner.neural().ok()) {
+            Some(client) => {
+                NeuralInfo { endpoint: client.base_url().to_string(), model: Some(client.default_model().to_string()) }
+            }
+            None => NeuralInfo { endpoint: NEURAL_ENDPOINT.to_string(), model: None },
+        }
+    }
+
+    fn reflex_commands(&self) -> usize {
+        match self.runner {
+            Some(runner) => runner.reflex().inventory().len(),
+            None => ReflexEngine::new().inventory().len(),
+        }
+    }
+
+    fn tldr_date(&self) -> Option<String> {
+        let installed = match self.runner {
+            Some(runner) => runner.tldr().installed_at(),
+            None => Tldr::new(self.data.tldr()).installed_at(),
+        }?;
+        Some(chrono::DateTime::<chrono::Utc>::from(installed).format("%Y-%m-%d").to_string())
+    }
+
+    fn data_root(&self) -> PathBuf {
+        self.data.root().to_path_buf()
+    }
+
+    fn home(&self) -> Option<PathBuf> {
+        directories::BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf())
+    }
+}
@@ -0,0 +1,414 @@
+//! `!view <path> [--hex] [--head n] [--tail n]`: a file in the pager
+//! overlay, read directly instead of `cat` through the PTY (no UI deps).
+//!
+//! The app resolves the path against the tracked CWD and calls `load` off
+//! the UI thread. `load` reads a sample to pick the mode (`choose_mode`:
+//! hex for `--hex` or when the binary heuristic trips), then only the byte
+//! window the range needs (`window`), capped at `MAX_TEXT_BYTES` or
+//! `MAX_HEX_BYTES` so a huge log can't stall anything. Text goes through
+//! the Holodeck detector and the syntax highlighter (`render_text`): JSON
+//! is pretty-printed, CSV laid out in columns, Markdown and code
+//! highlighted. The view opens with the file's metadata line (`header`).
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use positronic_core::term::binary;
+use positronic_core::trash::format_bytes;
+
+use crate::holodeck::{CellValue, ContentDetector, ContentType, DataFrame, JsonContent};
+use crate::syntax::{self, Language, SyntaxSpan, TokenClass};
+
+pub const VIEW_USAGE: &str = "Usage: !view <path> [--hex] [--head n] [--tail n]";
+
+/// Most bytes a text view reads.
+pub const MAX_TEXT_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Most bytes a hex view reads (64K rows).
+pub const MAX_HEX_BYTES: u64 = 1024 * 1024;
+
+/// Bytes on a hex row.
+pub const HEX_ROW: u64 = 16;
+
+/// Reads longer than this report progress.
+pub const PROGRESS_BYTES: u64 = 1024 * 1024;
+
+/// Bytes looked at to choose between text and hex.
+const SAMPLE_BYTES: u64 = 8 * 1024;
+
+const READ_CHUNK: usize = 256 * 1024;
+
+/// Widest a CSV column is laid out; longer cells end in `…`.
+const MAX_COLUMN: usize = 40;
+
+/// What part of the file to show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ViewRange {
+    #[default]
+    All,
+    /// The first n lines (rows in hex mode).
+    Head(usize),
+    /// The last n lines (rows in hex mode).
+    Tail(usize),
+}
+
+/// A parsed `!view` line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViewCommand {
+    /// `None` re-opens the last file viewed.
+    pub path: Option<String>,
+    pub hex: bool,
+    pub range: ViewRange,
+}
+
+impl ViewCommand {
+    /// Parse what follows `!view`; the error is a message for the user.
+    pub fn parse(args: &str) -> Result<ViewCommand, String> {
+        let mut command = ViewCommand::default();
+        let mut parts = args.split_whitespace();
+        while let Some(part) = parts.next() {
+            match part {
+                "--hex" | "-x" => command.hex = true,
+                "--head" | "--tail" => {
+                    if command.range != ViewRange::All {
+                        return Err("❌ !view takes --head or --tail, not both".to_string());
+                    }
+                    let n = parts
+                        .next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("❌ {} needs a count: {} 40", part, part))?;
+                    command.range = if part == "--head" { ViewRange::Head(n) } else { ViewRange::Tail(n) };
+                }
+                p if command.path.is_none() && !p.starts_with('-') => command.path = Some(p.to_string()),
+                _ => return Err(VIEW_USAGE.to_string()),
+            }
+        }
+        Ok(command)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    Text,
+    Hex,
+}
+
+/// Hex when asked for, or when the first bytes look binary (the same
+/// heuristic as the PTY's binary guard).
+pub fn choose_mode(sample: &[u8], hex: bool) -> ViewMode {
+    if hex || binary::looks_binary(sample) {
+        ViewMode::Hex
+    } else {
+        ViewMode::Text
+    }
+}
+
+/// The bytes of a file a view reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteWindow {
+    pub start: u64,
+    pub len: u64,
+}
+
+impl ByteWindow {
+    pub fn end(&self) -> u64 {
+        self.start + self.len
+    }
+}
+
+/// What to read of a `file_len`-byte file. Hex windows are whole rows,
+/// counted from the start of the file. Text windows are up to
+/// `MAX_TEXT_BYTES` from the start, or from the end for `Tail`; lines are
+/// cut from them by `slice_lines`.
+pub fn window(file_len: u64, range: ViewRange, mode: ViewMode) -> ByteWindow {
+    let from_start = |len: u64| ByteWindow { start: 0, len: file_len.min(len) };
+    match (mode, range) {
+        (ViewMode::Hex, ViewRange::All) => from_start(MAX_HEX_BYTES),
+        (ViewMode::Hex, ViewRange::Head(rows)) => from_start((rows as u64).saturating_mul(HEX_ROW).min(MAX_HEX_BYTES)),
+        (ViewMode::Hex, ViewRange::Tail(rows)) => {
+            // The last row may be short; it still counts as one.
+            let rows = (rows as u64).min(MAX_HEX_BYTES / HEX_ROW);
+            let start = file_len.div_ceil(HEX_ROW).saturating_sub(rows) * HEX_ROW;
+            ByteWindow { start, len: file_len - start }
+        }
+        (ViewMode::Text, ViewRange::Tail(_)) => {
+            let len = file_len.min(MAX_TEXT_BYTES);
+            ByteWindow { start: file_len - len, len }
+        }
+        (ViewMode::Text, _) => from_start(MAX_TEXT_BYTES),
+    }
+}
+
+/// The lines of `text`, read through `window` of a `file_len`-byte file,
+/// that `range` shows. A line the window starts or ends inside is dropped.
+pub fn slice_lines(text: &str, window: ByteWindow, file_len: u64, range: ViewRange) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().collect();
+    if window.end() < file_len && !text.ends_with('\n') {
+        lines.pop();
+    }
+    if window.start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    match range {
+        ViewRange::All => lines,
+        ViewRange::Head(n) => {
+            lines.truncate(n);
+            lines
+        }
+        ViewRange::Tail(n) => lines.split_off(lines.len().saturating_sub(n)),
+    }
+}
+
+/// A text view's lines, their syntax spans (empty when plain), and what
+/// the content was taken for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub lines: Vec<String>,
+    pub syntax: Vec<Vec<SyntaxSpan>>,
+    pub kind: &'static str,
+}
+
+/// Render the lines of a file called `name`: JSON pretty-printed, CSV in
+/// columns, Markdown and code highlighted, anything else as it is. The
+/// file name decides first, then the Holodeck detector, then what the
+/// lines look like.
+pub fn render_text(name: &str, lines: &[&str]) -> Rendered {
+    let text = lines.join("\n");
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase()).unwrap_or_default();
+    let by_name = Language::from_path(name);
+    let detected = ContentDetector::detect(&text);
+
+    if (by_name == Some(Language::Json) || detected == ContentType::Json)
+        && let Some(json) = JsonContent::parse(&text)
+    {
+        let pretty: Vec<&str> = json.pretty.lines().collect();
+        return highlighted("json", Language::Json, &pretty);
+    }
+    if let Some(language) = by_name {
+        return highlighted(language.label(), language, lines);
+    }
+    let csv = matches!(extension.as_str(), "csv" | "tsv") || detected == ContentType::Csv;
+    if csv && let Some(df) = DataFrame::parse_csv(&text) {
+        return table(&df);
+    }
+    if matches!(extension.as_str(), "md" | "markdown") || detected == ContentType::Markdown {
+        return Rendered { lines: owned(lines), syntax: markdown_spans(lines), kind: "markdown" };
+    }
+    match Language::from_content(lines) {
+        Some(language) => highlighted(language.label(), language, lines),
+        None => Rendered { lines: owned(lines), syntax: Vec::new(), kind: "text" },
+    }
+}
+
+fn owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+fn highlighted(kind: &'static str, language: Language, lines: &[&str]) -> Rendered {
+    Rendered { lines: owned(lines), syntax: syntax::highlight(language, lines), kind }
+}
+
+/// `df` as aligned columns: the header row, a rule, then the rows.
+fn table(df: &DataFrame) -> Rendered {
+    let rows: Vec<Vec<String>> = std::iter::once(df.headers.clone())
+        .chain(df.rows.iter().map(|row| row.iter().map(CellValue::to_field).collect()))
+        .collect();
+    let widths: Vec<usize> = (0..df.col_count())
+        .map(|col| rows.iter().filter_map(|r| r.get(col)).map(|c| c.chars().count()).max().unwrap_or(0).min(MAX_COLUMN))
+        .collect();
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(col, &width)| fit(cells.get(col).map_or("", String::as_str), width))
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    let mut lines: Vec<String> = rows.iter().map(|r| line(r)).collect();
+    let rule = widths.iter().map(|&w| "─".repeat(w)).collect::<Vec<_>>().join("  ");
+    lines.insert(1.min(lines.len()), rule);
+
+    let whole = |line: &str, class| vec![SyntaxSpan { range: 0..line.chars().count(), class }];
+    let mut syntax = vec![Vec::new(); lines.len()];
+    syntax[0] = whole(&lines[0], TokenClass::Key);
+    if let Some(rule) = lines.get(1) {
+        syntax[1] = whole(rule, TokenClass::Comment);
+    }
+    Rendered { lines, syntax, kind: "csv" }
+}
+
+/// `cell` padded or cut (ending in `…`) to `width` characters.
+fn fit(cell: &str, width: usize) -> String {
+    let count = cell.chars().count();
+    if count <= width {
+        return format!("{}{}", cell, " ".repeat(width - count));
+    }
+    let cut: String = cell.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", cut)
+}
+
+/// Headings, fenced code, quotes and list markers of a Markdown file.
+pub fn markdown_spans(lines: &[&str]) -> Vec<Vec<SyntaxSpan>> {
+    let mut in_fence = false;
+    lines
+        .iter()
+        .map(|line| {
+            let len = line.chars().count();
+            let indent = len - line.trim_start().chars().count();
+            let body = line.trim_start();
+            let span = |range, class| vec![SyntaxSpan { range, class }];
+            if body.starts_with("```") || body.starts_with("~~~") {
+                in_fence = !in_fence;
+                return span(0..len, TokenClass::Comment);
+            }
+            if in_fence {
+                span(0..len, TokenClass::String)
+            } else if body.starts_with('#') {
+                span(0..len, TokenClass::Heading)
+            } else if body.starts_with('>') {
+                span(0..len, TokenClass::Comment)
+            } else if let Some(marker) = list_marker(body) {
+                span(indent..indent + marker, TokenClass::Keyword)
+            } else {
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+/// Characters of a `- `, `* `, `+ ` or `12. ` marker, without the space.
+fn list_marker(body: &str) -> Option<usize> {
+    if ["- ", "* ", "+ "].iter().any(|m| body.starts_with(m)) {
+        return Some(1);
+    }
+    let digits = body.chars().take_while(char::is_ascii_digit).count();
+    (digits > 0 && body[digits..].starts_with(". ")).then_some(digits + 1)
+}
+
+/// What the metadata line says about a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMeta {
+    pub path: PathBuf,
+    /// Where a symlink points.
+    pub target: Option<PathBuf>,
+    pub size: u64,
+    pub modified: Option<NaiveDateTime>,
+}
+
+/// The line a view opens with: `📄 notes.md → /srv/notes.md · 12.4 KB ·
+/// modified 2026-10-01 14:22 · markdown`, and how much of the file is
+/// shown when not all of it.
+pub fn header(meta: &FileMeta, kind: &str, mode: ViewMode, range: ViewRange, window: ByteWindow) -> String {
+    let mut line = format!("📄 {}", meta.path.display());
+    if let Some(target) = &meta.target {
+        line.push_str(&format!(" → {}", target.display()));
+    }
+    line.push_str(&format!(" · {}", format_bytes(meta.size)));
+    if let Some(modified) = meta.modified {
+        line.push_str(&format!(" · modified {}", modified.format("%Y-%m-%d %H:%M")));
+    }
+    line.push_str(&format!(" · {}", kind));
+    let unit = match mode {
+        ViewMode::Text => "lines",
+        ViewMode::Hex => "rows",
+    };
+    match range {
+        ViewRange::Head(n) => line.push_str(&format!(" · first {} {}", n, unit)),
+        ViewRange::Tail(n) => line.push_str(&format!(" · last {} {}", n, unit)),
+        ViewRange::All if window.len < meta.size => {
+            line.push_str(&format!(" · first {} shown (--tail n for the end)", format_bytes(window.len)));
+        }
+        ViewRange::All => {}
+    }
+    line
+}
+
+/// A precise message for a file `!view` can't read.
+pub fn open_error(path: &Path, err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::NotFound => format!("❌ !view: {} does not exist", path.display()),
+        io::ErrorKind::PermissionDenied => format!("❌ !view: permission denied reading {}", path.display()),
+        io::ErrorKind::IsADirectory => format!("❌ !view: {} is a directory", path.display()),
+        _ => format!("❌ !view: can't read {}: {}", path.display(), err),
+    }
+}
+
+/// `path` against the tracked CWD, `~` expanded.
+pub fn resolve(path: &str, cwd: &Path) -> PathBuf {
+    cwd.join(crate::cwd::resolve_tilde(path))
+}
+
+/// A file ready for `Pager::view`: the metadata line, then the content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileView {
+    pub lines: Vec<String>,
+    /// Empty, or spans for every line.
+    pub syntax: Vec<Vec<SyntaxSpan>>,
+}
+
+/// Read and render `path` for `command`. Reads over `PROGRESS_BYTES` call
+/// `progress` with the percentage done, a few times. Blocks: call it off
+/// the UI thread.
+pub fn load(path: &Path, command: &ViewCommand, mut progress: impl FnMut(u64)) -> Result<FileView, String> {
+    let link = fs::symlink_metadata(path).map_err(|e| open_error(path, &e))?;
+    let target = if link.file_type().is_symlink() {
+        let target = fs::canonicalize(path).map_err(|_| {
+            let to = fs::read_link(path).unwrap_or_default();
+            format!("❌ !view: {} is a broken link to {}", path.display(), to.display())
+        })?;
+        Some(target)
+    } else {
+        None
+    };
+    let meta = fs::metadata(path).map_err(|e| open_error(path, &e))?;
+    if meta.is_dir() {
+        return Err(open_error(path, &io::ErrorKind::IsADirectory.into()));
+    }
+    let mut file = File::open(path).map_err(|e| open_error(path, &e))?;
+    let size = meta.len();
+
+    let mut sample = Vec::new();
+    (&mut file).take(SAMPLE_BYTES).read_to_end(&mut sample).map_err(|e| open_error(path, &e))?;
+    let mode = choose_mode(&sample, command.hex);
+    let window = window(size, command.range, mode);
+    let bytes = read_window(&mut file, window, &mut progress).map_err(|e| open_error(path, &e))?;
+
+    let (mut lines, mut syntax, kind) = match mode {
+        ViewMode::Hex => {
+            let kind = if binary::looks_binary(&sample) { "binary" } else { "text" };
+            (binary::hex_dump_at(&bytes, window.start), Vec::new(), kind)
+        }
+        ViewMode::Text => {
+            let text = String::from_utf8_lossy(&bytes);
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let rendered = render_text(&name, &slice_lines(&text, window, size, command.range));
+            (rendered.lines, rendered.syntax, rendered.kind)
+        }
+    };
+    let modified = meta.modified().ok().map(|t| chrono::DateTime::<chrono::Local>::from(t).naive_local());
+    let meta = FileMeta { path: path.to_path_buf(), target, size, modified };
+    lines.insert(0, header(&meta, kind, mode, command.range, window));
+    if !syntax.is_empty() {
+        syntax.insert(0, Vec::new());
+    }
+    Ok(FileView { lines, syntax })
+}
+
+fn read_window(file: &mut File, window: ByteWindow, progress: &mut impl FnMut(u64)) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(window.start))?;
+    let mut bytes = Vec::with_capacity(window.len as usize);
+    let mut reported = 0;
+    let mut chunk = file.take(window.len);
+    while (&mut chunk).take(READ_CHUNK as u64).read_to_end(&mut bytes)? > 0 {
+        // At most one call per quarter.
+        let percent = bytes.len() as u64 * 100 / window.len / 25 * 25;
+        if window.len > PROGRESS_BYTES && percent > reported {
+            reported = percent;
+            progress(percent);
+        }
+    }
+    Ok(bytes)
+}
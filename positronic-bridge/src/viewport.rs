@@ -0,0 +1,209 @@
+//! Scroll position of the terminal view over its scrollback.
+//!
+//! The view is anchored to the bottom: new output scrolls it along. Once
+//! the user scrolls back (Shift+PageUp, Ctrl+Home) the anchor lets go and
+//! the view holds the lines it shows while output keeps arriving below;
+//! the lines arriving meanwhile are counted for the "▼ 42 new lines" pill.
+//! Reaching the bottom again, by paging down or with Ctrl+End or the pill,
+//! re-anchors it and clears the count.
+//!
+//! Positions are an offset in lines up from the bottom, so the same state
+//! drives the PTY scrollback (the emulator's display offset) and native
+//! output (a window over its lines). Full-screen apps have no scrollback:
+//! their paging keys are passed through as they are (`alt_screen_sequence`).
+//!
+//! The pill's rectangle is filled in by `ui::terminal` while drawing, so a
+//! click is hit-tested against what was actually on screen.
+
+use std::ops::Range;
+
+use crate::keymap::NamedKey;
+
+#[derive(Debug, Clone, Default)]
+pub struct Viewport {
+    /// Lines up from the bottom; 0 is anchored.
+    offset: usize,
+    /// Lines there are, scrollback included.
+    total: usize,
+    /// Lines one screen shows.
+    page: usize,
+    /// Lines that arrived while scrolled back.
+    unseen: usize,
+    /// Where the pill was last drawn.
+    pill_rect: Option<[f32; 4]>,
+}
+
+/// A saved scroll position (`Viewport::mark`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollMark {
+    Anchored,
+    /// Scrolled back: the line at the top of the view, of `total`.
+    Back { top: usize, total: usize },
+}
+
+impl Viewport {
+    /// Update the content size; the offset is clamped to fit.
+    pub fn set_extent(&mut self, total: usize, page: usize) {
+        self.total = total;
+        self.page = page;
+        self.offset = self.offset.min(self.max_offset());
+        if self.offset == 0 {
+            self.unseen = 0;
+        }
+    }
+
+    /// `lines` new lines were appended. Anchored, the view follows them;
+    /// scrolled back, it stays on what it shows and counts them.
+    pub fn on_new_lines(&mut self, lines: usize) {
+        self.total += lines;
+        if self.offset > 0 {
+            self.offset = (self.offset + lines).min(self.max_offset());
+            self.unseen += lines;
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_to(self.offset + self.page.max(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_to(self.offset.saturating_sub(self.page.max(1)));
+    }
+
+    pub fn top(&mut self) {
+        self.scroll_to(self.max_offset());
+    }
+
+    /// Re-anchor to the bottom.
+    pub fn bottom(&mut self) {
+        self.scroll_to(0);
+    }
+
+    /// Scroll just far enough to bring line `line` into view.
+    pub fn reveal(&mut self, line: usize) {
+        let window = self.window();
+        if line < window.start {
+            self.scroll_to(self.total.saturating_sub(line + self.page));
+        } else if line >= window.end {
+            self.scroll_to(self.total.saturating_sub(line + 1));
+        }
+    }
+
+    /// Scroll so line `line` is at the top, as far as there is content
+    /// below it; near the bottom that re-anchors.
+    pub fn scroll_to_line(&mut self, line: usize) {
+        self.scroll_to(self.total.saturating_sub(line + self.page));
+    }
+
+    fn scroll_to(&mut self, offset: usize) {
+        self.offset = offset.min(self.max_offset());
+        if self.offset == 0 {
+            self.unseen = 0;
+        }
+    }
+
+    fn max_offset(&self) -> usize {
+        self.total.saturating_sub(self.page)
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_scrolled_back(&self) -> bool {
+        self.offset > 0
+    }
+
+    /// Where the view is, to put back with `restore` after the content
+    /// is re-laid out (a resize rewraps it).
+    pub fn mark(&self) -> ScrollMark {
+        match self.offset {
+            0 => ScrollMark::Anchored,
+            _ => ScrollMark::Back { top: self.window().start, total: self.total },
+        }
+    }
+
+    /// Return to `mark`: anchored stays anchored; scrolled back, the line
+    /// at the top keeps its place relative to the whole, which is exact
+    /// when the line count didn't change.
+    pub fn restore(&mut self, mark: ScrollMark) {
+        match mark {
+            ScrollMark::Anchored => self.scroll_to(0),
+            ScrollMark::Back { top, total } => {
+                let top = match total {
+                    0 => 0,
+                    _ => top * self.total / total,
+                };
+                self.scroll_to(self.total.saturating_sub(top + self.page).max(1));
+            }
+        }
+    }
+
+    /// Lines that arrived since the user scrolled back.
+    pub fn unseen(&self) -> usize {
+        self.unseen
+    }
+
+    /// The new-output pill, while there is new output below the view.
+    pub fn pill(&self) -> Option<String> {
+        match self.unseen {
+            0 => None,
+            1 => Some("▼ 1 new line".to_string()),
+            n => Some(format!("▼ {} new lines", n)),
+        }
+    }
+
+    pub fn set_pill_rect(&mut self, rect: Option<[f32; 4]>) {
+        self.pill_rect = rect;
+    }
+
+    /// True if `(x, y)` is on the pill as last drawn.
+    pub fn hit_pill(&self, x: f32, y: f32) -> bool {
+        self.pill().is_some()
+            && self
+                .pill_rect
+                .is_some_and(|[rx, ry, rw, rh]| x >= rx && x < rx + rw && y >= ry && y < ry + rh)
+    }
+
+    /// Indices of the lines on screen.
+    pub fn window(&self) -> Range<usize> {
+        let end = self.total - self.offset;
+        end.saturating_sub(self.page)..end
+    }
+
+    /// How far down the view is, 0.0 at the top and 1.0 at the bottom.
+    pub fn position(&self) -> f32 {
+        match self.max_offset() {
+            0 => 1.0,
+            max => 1.0 - self.offset as f32 / max as f32,
+        }
+    }
+
+    /// The scrollbar thumb in a track `track` long, as (start, length);
+    /// `None` when everything fits on one screen.
+    pub fn thumb(&self, track: f32, min_len: f32) -> Option<(f32, f32)> {
+        if self.total <= self.page || self.page == 0 {
+            return None;
+        }
+        let len = (track * self.page as f32 / self.total as f32).clamp(min_len.min(track), track);
+        Some(((track - len) * self.position(), len))
+    }
+}
+
+/// What a full-screen app receives for a paging key: PageUp and PageDown
+/// (with any Shift or Ctrl) and Ctrl+Home/End, in xterm's encoding.
+pub fn alt_screen_sequence(key: NamedKey, ctrl: bool, shift: bool) -> Option<&'static str> {
+    Some(match (key, ctrl, shift) {
+        (NamedKey::PageUp, false, false) => "\x1b[5~",
+        (NamedKey::PageUp, false, true) => "\x1b[5;2~",
+        (NamedKey::PageUp, true, false) => "\x1b[5;5~",
+        (NamedKey::PageUp, true, true) => "\x1b[5;6~",
+        (NamedKey::PageDown, false, false) => "\x1b[6~",
+        (NamedKey::PageDown, false, true) => "\x1b[6;2~",
+        (NamedKey::PageDown, true, false) => "\x1b[6;5~",
+        (NamedKey::PageDown, true, true) => "\x1b[6;6~",
+        (NamedKey::Home, true, false) => "\x1b[1;5H",
+        (NamedKey::End, true, false) => "\x1b[1;5F",
+        _ => return None,
+    })
+}
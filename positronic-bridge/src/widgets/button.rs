@@ -2,10 +2,11 @@
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
 use crate::gfx::text::TextRegion;
 use crate::renderer::{ColoredSpan, Rgba};
 
+use super::focus::{FocusKey, Focusable};
 use super::{Rect, WidgetAction, WidgetId};
 
 #[derive(Debug, Clone)]
@@ -45,6 +46,7 @@ impl Button {
             w: self.rect.w,
             h: self.rect.h,
             color: bg,
+            layer: QuadLayer::Overlay,
         });
 
         let bounds = TextBounds {
@@ -68,3 +70,29 @@ impl Button {
         self.rect.contains(x, y)
     }
 }
+
+impl Focusable for Button {
+    fn widget_id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn element_count(&self) -> usize {
+        1
+    }
+
+    fn move_focus(&mut self, element: usize, _key: FocusKey) -> usize {
+        element
+    }
+
+    fn activate(&mut self, _element: usize) -> WidgetAction {
+        self.action.clone()
+    }
+
+    fn describe(&self, _element: usize) -> String {
+        format!("button: {}", self.label)
+    }
+
+    fn element_rect(&self, _element: usize) -> Rect {
+        self.rect
+    }
+}
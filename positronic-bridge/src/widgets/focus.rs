@@ -0,0 +1,270 @@
+//! Keyboard focus across Holodeck widgets.
+//!
+//! Focus sits either on the input bar or on one element of one widget: a
+//! table row, a JSON tree node, a button. Tab and Shift+Tab cycle the
+//! widgets (wrapping at either end), arrow keys move within the focused
+//! one, Enter or Space activates the element exactly as a click would,
+//! and Escape hands focus back to the input bar. Every move yields a
+//! label for BioLink to announce, and `outline` is the ring the renderer
+//! draws around the focused element.
+
+use std::collections::HashMap;
+
+use crate::biolink::BioLinkEvent;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline};
+use crate::renderer::Rgba;
+
+use super::{Rect, WidgetAction, WidgetId};
+
+/// Thickness of the focus ring, in pixels.
+const OUTLINE_WIDTH: f32 = 2.0;
+
+/// Said when focus goes back to the input bar.
+pub const INPUT_LABEL: &str = "Input line";
+
+/// The keys focus handling uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusKey {
+    Tab,
+    /// Shift+Tab.
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Enter,
+    Space,
+    Escape,
+}
+
+/// A widget keyboard focus can land in. Elements are numbered from 0 in
+/// reading order.
+pub trait Focusable {
+    fn widget_id(&self) -> WidgetId;
+    /// How many elements focus can land on; none means focus skips it.
+    fn element_count(&self) -> usize;
+    /// Where an arrow, Home/End or Page key moves focus from `element`.
+    fn move_focus(&mut self, element: usize, key: FocusKey) -> usize;
+    /// Enter or Space on `element`: whatever clicking it does.
+    fn activate(&mut self, element: usize) -> WidgetAction;
+    /// What a screen reader says for `element`.
+    fn describe(&self, element: usize) -> String;
+    /// Where `element` is drawn; the whole widget while it's scrolled away.
+    fn element_rect(&self, element: usize) -> Rect;
+}
+
+/// The focused element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Focus {
+    pub widget: WidgetId,
+    pub element: usize,
+}
+
+/// What a key did.
+#[derive(Debug, Clone)]
+pub enum FocusOutcome {
+    /// Focus is on the input bar, or the key means nothing here.
+    Ignored,
+    /// Focus moved, or stayed put at an edge; the label says where it is.
+    Moved(String),
+    /// The focused element was activated.
+    Activated { action: WidgetAction, label: String },
+    /// Focus went back to the input bar.
+    Released,
+}
+
+impl FocusOutcome {
+    /// What BioLink should announce, if anything.
+    pub fn announcement(&self) -> Option<BioLinkEvent> {
+        match self {
+            FocusOutcome::Ignored => None,
+            FocusOutcome::Moved(label) | FocusOutcome::Activated { label, .. } => {
+                Some(BioLinkEvent::FocusChanged(label.clone()))
+            }
+            FocusOutcome::Released => Some(BioLinkEvent::FocusChanged(INPUT_LABEL.to_string())),
+        }
+    }
+}
+
+/// Tracks keyboard focus over the widgets on screen.
+#[derive(Debug, Default)]
+pub struct FocusManager {
+    /// `None` while the input bar has focus.
+    focus: Option<Focus>,
+    /// The widgets as last seen, to find a neighbour when one goes away.
+    order: Vec<WidgetId>,
+    /// The element each widget last had focused, so Tab returns to it.
+    remembered: HashMap<WidgetId, usize>,
+}
+
+impl FocusManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn focused(&self) -> Option<Focus> {
+        self.focus
+    }
+
+    pub fn input_has_focus(&self) -> bool {
+        self.focus.is_none()
+    }
+
+    /// Move focus from the input bar into the widgets: the first one, or
+    /// the last one when `backwards`.
+    pub fn enter(&mut self, widgets: &mut [&mut dyn Focusable], backwards: bool) -> FocusOutcome {
+        self.sync(widgets);
+        let start = if backwards { widgets.len() } else { usize::MAX };
+        match next_focusable(widgets, start, backwards) {
+            Some(index) => self.focus_widget(widgets, index),
+            None => FocusOutcome::Ignored,
+        }
+    }
+
+    /// Handle a key while a widget may have focus.
+    pub fn handle(&mut self, key: FocusKey, widgets: &mut [&mut dyn Focusable]) -> FocusOutcome {
+        if let Some(FocusOutcome::Released) = self.sync(widgets) {
+            return FocusOutcome::Released;
+        }
+        let Some(focus) = self.focus else {
+            return FocusOutcome::Ignored;
+        };
+        let Some(index) = widgets.iter().position(|w| w.widget_id() == focus.widget) else {
+            return FocusOutcome::Ignored;
+        };
+
+        match key {
+            FocusKey::Escape => {
+                self.focus = None;
+                FocusOutcome::Released
+            }
+            FocusKey::Tab | FocusKey::BackTab => {
+                let backwards = key == FocusKey::BackTab;
+                match next_focusable(widgets, index, backwards) {
+                    Some(next) => self.focus_widget(widgets, next),
+                    None => FocusOutcome::Ignored,
+                }
+            }
+            FocusKey::Enter | FocusKey::Space => {
+                let widget = &mut widgets[index];
+                let action = widget.activate(focus.element);
+                // Activating can change what's shown, like a tree node
+                // expanding, so the element is re-read.
+                let element = focus.element.min(widget.element_count().saturating_sub(1));
+                self.set(focus.widget, element);
+                FocusOutcome::Activated { action, label: widget.describe(element) }
+            }
+            _ => {
+                let widget = &mut widgets[index];
+                let moved = widget.move_focus(focus.element, key);
+                let element = moved.min(widget.element_count().saturating_sub(1));
+                self.set(focus.widget, element);
+                FocusOutcome::Moved(widget.describe(element))
+            }
+        }
+    }
+
+    /// Catch up with the widgets on screen. If the focused one is gone,
+    /// focus moves to the one that took its place (or the one before it,
+    /// if it was last), and to the input bar when none are left. Returns
+    /// what changed, if anything did.
+    pub fn sync(&mut self, widgets: &mut [&mut dyn Focusable]) -> Option<FocusOutcome> {
+        let ids: Vec<WidgetId> = widgets.iter().map(|w| w.widget_id()).collect();
+        self.remembered.retain(|id, _| ids.contains(id));
+        let previous = std::mem::replace(&mut self.order, ids);
+
+        let focus = self.focus?;
+        if let Some(index) = widgets.iter().position(|w| w.widget_id() == focus.widget) {
+            let count = widgets[index].element_count();
+            if count == 0 {
+                return Some(self.restore(widgets, index));
+            }
+            if focus.element >= count {
+                self.set(focus.widget, count - 1);
+                return Some(FocusOutcome::Moved(widgets[index].describe(count - 1)));
+            }
+            return None;
+        }
+
+        let slot = previous.iter().position(|id| *id == focus.widget).unwrap_or(0);
+        Some(self.restore(widgets, slot))
+    }
+
+    /// The ring around the focused element.
+    pub fn outline(&self, widgets: &[&dyn Focusable]) -> Option<[QuadInstance; 4]> {
+        let focus = self.focus?;
+        let widget = widgets.iter().find(|w| w.widget_id() == focus.widget)?;
+        Some(outline(widget.element_rect(focus.element)))
+    }
+
+    pub fn render(&self, quads: &mut QuadPipeline, widgets: &[&dyn Focusable]) {
+        for quad in self.outline(widgets).into_iter().flatten() {
+            quads.push(quad);
+        }
+    }
+
+    /// Focus the focusable widget at `slot`, else the nearest one before
+    /// it, else the input bar.
+    fn restore(&mut self, widgets: &mut [&mut dyn Focusable], slot: usize) -> FocusOutcome {
+        let slot = slot.min(widgets.len());
+        let after = (slot..widgets.len()).find(|&i| widgets[i].element_count() > 0);
+        let before = (0..slot).rev().find(|&i| widgets[i].element_count() > 0);
+        match after.or(before) {
+            Some(index) => self.focus_widget(widgets, index),
+            None => {
+                self.focus = None;
+                FocusOutcome::Released
+            }
+        }
+    }
+
+    fn focus_widget(&mut self, widgets: &mut [&mut dyn Focusable], index: usize) -> FocusOutcome {
+        let widget = &widgets[index];
+        let id = widget.widget_id();
+        let last = widget.element_count().saturating_sub(1);
+        let element = self.remembered.get(&id).copied().unwrap_or(0).min(last);
+        self.set(id, element);
+        FocusOutcome::Moved(widget.describe(element))
+    }
+
+    fn set(&mut self, widget: WidgetId, element: usize) {
+        self.focus = Some(Focus { widget, element });
+        self.remembered.insert(widget, element);
+    }
+}
+
+/// The next widget after `from` (before it when `backwards`) that can take
+/// focus, wrapping around; `from` itself comes last. `from` may be past
+/// either end to start from the edge.
+fn next_focusable(widgets: &[&mut dyn Focusable], from: usize, backwards: bool) -> Option<usize> {
+    let len = widgets.len();
+    if len == 0 {
+        return None;
+    }
+    (1..=len)
+        .map(|step| {
+            if backwards {
+                (from.min(len) + len * 2 - step) % len
+            } else {
+                from.wrapping_add(step) % len
+            }
+        })
+        .find(|&i| widgets[i].element_count() > 0)
+}
+
+/// A ring drawn just inside `rect`, in a color no widget uses.
+pub fn outline(rect: Rect) -> [QuadInstance; 4] {
+    let color = Rgba::rgb(0.98, 0.78, 0.25);
+    let edge = |x: f32, y: f32, w: f32, h: f32| QuadInstance { x, y, w, h, color, layer: QuadLayer::Overlay };
+    let t = OUTLINE_WIDTH.min(rect.w / 2.0).min(rect.h / 2.0);
+    [
+        edge(rect.x, rect.y, rect.w, t),
+        edge(rect.x, rect.y + rect.h - t, rect.w, t),
+        edge(rect.x, rect.y, t, rect.h),
+        edge(rect.x + rect.w - t, rect.y, t, rect.h),
+    ]
+}
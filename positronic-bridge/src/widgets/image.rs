@@ -8,12 +8,13 @@
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
 use crate::gfx::text::TextRegion;
 use crate::renderer::{ColoredSpan, Rgba};
 use crate::holodeck::ImageMeta;
 
-use super::{Rect, WidgetId};
+use super::focus::{FocusKey, Focusable};
+use super::{Rect, WidgetAction, WidgetId};
 
 #[derive(Debug, Clone)]
 pub struct ImageWidget {
@@ -38,6 +39,7 @@ impl ImageWidget {
             w: self.rect.w,
             h: self.rect.h,
             color: Rgba::rgb(0.08, 0.085, 0.11),
+            layer: QuadLayer::Overlay,
         });
 
         let spans = vec![
@@ -72,3 +74,34 @@ impl ImageWidget {
         });
     }
 }
+
+impl Focusable for ImageWidget {
+    fn widget_id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn element_count(&self) -> usize {
+        1
+    }
+
+    fn move_focus(&mut self, element: usize, _key: FocusKey) -> usize {
+        element
+    }
+
+    /// Clicking an image does nothing yet.
+    fn activate(&mut self, _element: usize) -> WidgetAction {
+        WidgetAction::None
+    }
+
+    /// `image: png, 640 by 480`.
+    fn describe(&self, _element: usize) -> String {
+        match (self.meta.width, self.meta.height) {
+            (Some(w), Some(h)) => format!("image: {}, {} by {}", self.meta.protocol, w, h),
+            _ => format!("image: {}", self.meta.protocol),
+        }
+    }
+
+    fn element_rect(&self, _element: usize) -> Rect {
+        self.rect
+    }
+}
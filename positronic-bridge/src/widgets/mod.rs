@@ -8,9 +8,11 @@
 //! and disappear when raw/fullscreen contexts are active (Core ModeSnapshot).
 
 pub mod button;
+pub mod focus;
 pub mod image;
 pub mod plot;
 pub mod table;
+pub mod tree;
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -60,4 +62,6 @@ pub enum WidgetAction {
     SendCommand(String),
     /// Copy this text to clipboard.
     CopyText(String),
+    /// Save the widget's data to a generated file (`!save`).
+    Save(crate::holodeck::export::SaveFormat),
 }
@@ -5,7 +5,7 @@
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
 use crate::gfx::text::TextRegion;
 use crate::renderer::{ColoredSpan, Rgba};
 
@@ -36,6 +36,7 @@ impl PlotWidget {
             w: self.rect.w,
             h: self.rect.h,
             color: Rgba::rgb(0.08, 0.085, 0.11),
+            layer: QuadLayer::Overlay,
         });
 
         let spark = sparkline(&self.series);
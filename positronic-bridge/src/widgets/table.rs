@@ -1,17 +1,25 @@
 //! Table widget for Holodeck.
 //!
 //! Renders a DataFrame-like view using text (fast, portable, no textures required).
-//! Supports scrolling + row selection via pointer events.
+//! Supports scrolling + row selection via pointer events or the keyboard
+//! (see `focus`); clicking the header strip saves the table as CSV.
 
 use glyphon::TextBounds;
 
-use crate::gfx::{QuadInstance, QuadPipeline, TextEngine};
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
 use crate::gfx::text::TextRegion;
 use crate::renderer::{ColoredSpan, Rgba};
-use crate::holodeck::DataFrame;
+use crate::biolink::BioLink;
+use crate::holodeck::{CellValue, DataFrame};
+use crate::holodeck::export::SaveFormat;
 
+use super::focus::{FocusKey, Focusable};
 use super::{PointerEvent, PointerKind, Rect, WidgetAction, WidgetId};
 
+/// Header strip plus the separator line above the first row.
+const ROWS_TOP: f32 = 28.0 + 18.0;
+const ROW_HEIGHT: f32 = 18.0;
+
 #[derive(Debug, Clone)]
 pub struct TableWidget {
     pub id: WidgetId,
@@ -42,6 +50,7 @@ impl TableWidget {
             w: self.rect.w,
             h: self.rect.h,
             color: Rgba::rgb(0.08, 0.085, 0.11),
+            layer: QuadLayer::Overlay,
         });
 
         // Header background strip
@@ -51,9 +60,10 @@ impl TableWidget {
             w: self.rect.w,
             h: 28.0,
             color: Rgba::rgb(0.11, 0.12, 0.16),
+            layer: QuadLayer::Overlay,
         });
 
-        let visible_rows = ((self.rect.h - 34.0) / 18.0).max(1.0) as usize;
+        let visible_rows = self.visible_rows();
         let end = (self.scroll_row + visible_rows).min(self.df.rows.len());
 
         let mut spans: Vec<ColoredSpan> = Vec::new();
@@ -120,23 +130,15 @@ impl TableWidget {
                 }
                 WidgetAction::None
             }
+            PointerKind::Down if ev.y < self.rect.y + 28.0 => WidgetAction::Save(SaveFormat::Csv),
             PointerKind::Down => {
                 // Convert click y into row index
-                let local_y = ev.y - (self.rect.y + 28.0 + 18.0); // header + separator
+                let local_y = ev.y - (self.rect.y + ROWS_TOP);
                 if local_y >= 0.0 {
-                    let clicked_row = self.scroll_row + (local_y / 18.0) as usize;
+                    let clicked_row = self.scroll_row + (local_y / ROW_HEIGHT) as usize;
                     if clicked_row < self.df.rows.len() {
-                        self.selected_row = Some(clicked_row);
                         eprintln!("[TABLE] selected_row={}", clicked_row);
-
-                        // Provide a convenient "copy row" behavior.
-                        let row = &self.df.rows[clicked_row];
-                        let text = row
-                            .iter()
-                            .map(|c| format!("{:?}", c).trim_matches('"').to_string())
-                            .collect::<Vec<_>>()
-                            .join("\t");
-                        return WidgetAction::CopyText(text);
+                        return self.activate(clicked_row);
                     }
                 }
                 WidgetAction::None
@@ -145,3 +147,91 @@ impl TableWidget {
         }
     }
 }
+
+impl TableWidget {
+    /// How many rows fit under the header.
+    pub fn visible_rows(&self) -> usize {
+        ((self.rect.h - 34.0) / ROW_HEIGHT).max(1.0) as usize
+    }
+
+    /// Scroll just enough for `row` to be on screen.
+    fn scroll_to(&mut self, row: usize) {
+        let visible = self.visible_rows();
+        if row < self.scroll_row {
+            self.scroll_row = row;
+        } else if row >= self.scroll_row + visible {
+            self.scroll_row = row + 1 - visible;
+        }
+    }
+}
+
+/// A row as tab-separated fields, as clicking it copies.
+pub fn row_tsv(row: &[CellValue]) -> String {
+    row.iter().map(CellValue::to_field).collect::<Vec<_>>().join("\t")
+}
+
+impl Focusable for TableWidget {
+    fn widget_id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn element_count(&self) -> usize {
+        self.df.rows.len()
+    }
+
+    fn move_focus(&mut self, row: usize, key: FocusKey) -> usize {
+        let last = self.df.rows.len().saturating_sub(1);
+        let page = self.visible_rows();
+        let row = match key {
+            FocusKey::Up => row.saturating_sub(1),
+            FocusKey::Down => (row + 1).min(last),
+            FocusKey::PageUp => row.saturating_sub(page),
+            FocusKey::PageDown => (row + page).min(last),
+            FocusKey::Home => 0,
+            FocusKey::End => last,
+            _ => row,
+        };
+        self.selected_row = Some(row);
+        self.scroll_to(row);
+        row
+    }
+
+    /// Select the row and copy it, as a click does.
+    fn activate(&mut self, row: usize) -> WidgetAction {
+        let Some(cells) = self.df.rows.get(row) else {
+            return WidgetAction::None;
+        };
+        let text = row_tsv(cells);
+        self.selected_row = Some(row);
+        self.scroll_to(row);
+        WidgetAction::CopyText(text)
+    }
+
+    /// `row 4 of 20: name=api-server, status=failed`.
+    fn describe(&self, row: usize) -> String {
+        let Some(cells) = self.df.rows.get(row) else {
+            return "empty table".to_string();
+        };
+        let fields: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| match self.df.headers.get(i) {
+                Some(header) => format!("{}={}", header, cell.to_field()),
+                None => format!("column {}={}", i + 1, cell.to_field()),
+            })
+            .collect();
+        BioLink::element_label("row", row + 1, self.df.rows.len(), &fields.join(", "))
+    }
+
+    fn element_rect(&self, row: usize) -> Rect {
+        if row < self.scroll_row || row >= self.scroll_row + self.visible_rows() {
+            return self.rect;
+        }
+        Rect {
+            x: self.rect.x,
+            y: self.rect.y + ROWS_TOP + (row - self.scroll_row) as f32 * ROW_HEIGHT,
+            w: self.rect.w,
+            h: ROW_HEIGHT,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Collapsible JSON tree widget for Holodeck.
+//!
+//! Shows the top level of a JSON value with objects and arrays folded.
+//! Clicking a folded node (or pressing Enter on it) opens it, clicking an
+//! open one folds it, and clicking a leaf copies its value. From the
+//! keyboard, Right opens a node or steps into it and Left folds it or
+//! steps out to its parent, as in a file tree.
+
+use std::collections::HashSet;
+
+use glyphon::TextBounds;
+use serde_json::Value;
+
+use crate::biolink::BioLink;
+use crate::gfx::{QuadInstance, QuadLayer, QuadPipeline, TextEngine};
+use crate::gfx::text::TextRegion;
+use crate::renderer::{ColoredSpan, Rgba};
+
+use super::focus::{FocusKey, Focusable};
+use super::{PointerEvent, PointerKind, Rect, WidgetAction, WidgetId};
+
+/// Padding above the first row.
+const ROWS_TOP: f32 = 6.0;
+const ROW_HEIGHT: f32 = 18.0;
+
+/// One visible line of the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeRow {
+    /// JSON Pointer (RFC 6901) to the node: `/spec/replicas`.
+    pub pointer: String,
+    /// 0 for the top level.
+    pub depth: usize,
+    /// The object key, or `[i]` for an array element.
+    pub key: String,
+    /// Keys or items under an object or array; `None` for a leaf.
+    pub children: Option<usize>,
+    pub expanded: bool,
+    /// A leaf's value as JSON, or `{2 keys}` / `[3 items]`.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonTreeWidget {
+    pub id: WidgetId,
+    pub rect: Rect,
+    pub root: Value,
+    /// Pointers of the open nodes.
+    expanded: HashSet<String>,
+    pub scroll_row: usize,
+    pub selected_row: Option<usize>,
+    pub hovered: bool,
+}
+
+impl JsonTreeWidget {
+    pub fn new(rect: Rect, root: Value) -> Self {
+        Self {
+            id: WidgetId::new(),
+            rect,
+            root,
+            expanded: HashSet::new(),
+            scroll_row: 0,
+            selected_row: None,
+            hovered: false,
+        }
+    }
+
+    /// The lines shown, top to bottom. A top-level object or array shows
+    /// its members; anything else is one line.
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let mut rows = Vec::new();
+        match &self.root {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    self.push_rows(value, &child_pointer("", key), 0, key.clone(), &mut rows);
+                }
+            }
+            Value::Array(items) => {
+                for (i, value) in items.iter().enumerate() {
+                    self.push_rows(value, &child_pointer("", &i.to_string()), 0, format!("[{}]", i), &mut rows);
+                }
+            }
+            leaf => rows.push(TreeRow {
+                pointer: String::new(),
+                depth: 0,
+                key: String::new(),
+                children: None,
+                expanded: false,
+                summary: leaf.to_string(),
+            }),
+        }
+        rows
+    }
+
+    fn push_rows(&self, value: &Value, pointer: &str, depth: usize, key: String, rows: &mut Vec<TreeRow>) {
+        let expanded = self.expanded.contains(pointer);
+        let (children, summary) = match value {
+            Value::Object(map) => (Some(map.len()), format!("{{{}}}", counted(map.len(), "key", "keys"))),
+            Value::Array(items) => (Some(items.len()), format!("[{}]", counted(items.len(), "item", "items"))),
+            leaf => (None, leaf.to_string()),
+        };
+        rows.push(TreeRow { pointer: pointer.to_string(), depth, key, children, expanded, summary });
+        if !expanded {
+            return;
+        }
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    self.push_rows(child, &child_pointer(pointer, key), depth + 1, key.clone(), rows);
+                }
+            }
+            Value::Array(items) => {
+                for (i, child) in items.iter().enumerate() {
+                    self.push_rows(child, &child_pointer(pointer, &i.to_string()), depth + 1, format!("[{}]", i), rows);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_expanded(&self, pointer: &str) -> bool {
+        self.expanded.contains(pointer)
+    }
+
+    /// How many rows fit in the widget.
+    pub fn visible_rows(&self) -> usize {
+        ((self.rect.h - 2.0 * ROWS_TOP) / ROW_HEIGHT).max(1.0) as usize
+    }
+
+    /// Scroll just enough for `row` to be on screen.
+    fn scroll_to(&mut self, row: usize) {
+        let visible = self.visible_rows();
+        if row < self.scroll_row {
+            self.scroll_row = row;
+        } else if row >= self.scroll_row + visible {
+            self.scroll_row = row + 1 - visible;
+        }
+    }
+
+    pub fn render(&self, quads: &mut QuadPipeline, text: &mut TextEngine) {
+        quads.push(QuadInstance {
+            x: self.rect.x,
+            y: self.rect.y,
+            w: self.rect.w,
+            h: self.rect.h,
+            color: Rgba::rgb(0.08, 0.085, 0.11),
+            layer: QuadLayer::Overlay,
+        });
+
+        let rows = self.rows();
+        let end = (self.scroll_row + self.visible_rows()).min(rows.len());
+        let spans: Vec<ColoredSpan> = rows
+            .get(self.scroll_row..end)
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let marker = match (row.children, row.expanded) {
+                    (Some(_), true) => "▾",
+                    (Some(_), false) => "▸",
+                    (None, _) => " ",
+                };
+                let line = if row.key.is_empty() {
+                    format!("{}{} {}\n", "  ".repeat(row.depth), marker, row.summary)
+                } else {
+                    format!("{}{} {}: {}\n", "  ".repeat(row.depth), marker, row.key, row.summary)
+                };
+                let color = if self.selected_row == Some(self.scroll_row + i) {
+                    Rgba::rgb(0.35, 0.85, 0.55)
+                } else {
+                    Rgba::rgb(0.85, 0.85, 0.85)
+                };
+                ColoredSpan::new(line, color)
+            })
+            .collect();
+
+        let bounds = TextBounds {
+            left: (self.rect.x + 10.0) as i32,
+            top: (self.rect.y + ROWS_TOP) as i32,
+            right: (self.rect.x + self.rect.w - 10.0) as i32,
+            bottom: (self.rect.y + self.rect.h - ROWS_TOP) as i32,
+        };
+
+        text.push_region(TextRegion {
+            spans,
+            bounds,
+            left: self.rect.x + 10.0,
+            top: self.rect.y + ROWS_TOP,
+            scale: 1.0,
+            default_color: Rgba::rgb(0.85, 0.85, 0.85),
+        });
+    }
+
+    pub fn on_pointer(&mut self, ev: PointerEvent) -> WidgetAction {
+        if !self.rect.contains(ev.x, ev.y) {
+            self.hovered = false;
+            return WidgetAction::None;
+        }
+        self.hovered = true;
+
+        match ev.kind {
+            PointerKind::Wheel { delta_y } => {
+                if delta_y < 0.0 {
+                    self.scroll_row = (self.scroll_row + 1).min(self.rows().len().saturating_sub(1));
+                } else {
+                    self.scroll_row = self.scroll_row.saturating_sub(1);
+                }
+                WidgetAction::None
+            }
+            PointerKind::Down => {
+                let local_y = ev.y - (self.rect.y + ROWS_TOP);
+                if local_y >= 0.0 {
+                    let clicked_row = self.scroll_row + (local_y / ROW_HEIGHT) as usize;
+                    if clicked_row < self.rows().len() {
+                        return self.activate(clicked_row);
+                    }
+                }
+                WidgetAction::None
+            }
+            _ => WidgetAction::None,
+        }
+    }
+}
+
+impl Focusable for JsonTreeWidget {
+    fn widget_id(&self) -> WidgetId {
+        self.id
+    }
+
+    fn element_count(&self) -> usize {
+        self.rows().len()
+    }
+
+    fn move_focus(&mut self, element: usize, key: FocusKey) -> usize {
+        let rows = self.rows();
+        let Some(row) = rows.get(element) else {
+            return element;
+        };
+        let last = rows.len() - 1;
+        let page = self.visible_rows();
+        let target = match key {
+            FocusKey::Up => element.saturating_sub(1),
+            FocusKey::Down => (element + 1).min(last),
+            FocusKey::PageUp => element.saturating_sub(page),
+            FocusKey::PageDown => (element + page).min(last),
+            FocusKey::Home => 0,
+            FocusKey::End => last,
+            FocusKey::Right => match row.children {
+                Some(_) if !row.expanded => {
+                    self.expanded.insert(row.pointer.clone());
+                    element
+                }
+                Some(n) if n > 0 => element + 1,
+                _ => element,
+            },
+            FocusKey::Left if row.expanded => {
+                self.expanded.remove(&row.pointer);
+                element
+            }
+            FocusKey::Left => rows[..element]
+                .iter()
+                .rposition(|r| r.depth + 1 == row.depth)
+                .unwrap_or(element),
+            _ => element,
+        };
+        self.selected_row = Some(target);
+        self.scroll_to(target);
+        target
+    }
+
+    /// Open or fold a node, or copy a leaf's value, as a click does.
+    fn activate(&mut self, element: usize) -> WidgetAction {
+        let rows = self.rows();
+        let Some(row) = rows.get(element) else {
+            return WidgetAction::None;
+        };
+        self.selected_row = Some(element);
+        self.scroll_to(element);
+        if row.children.is_some() {
+            if !self.expanded.remove(&row.pointer) {
+                self.expanded.insert(row.pointer.clone());
+            }
+            return WidgetAction::None;
+        }
+        match self.root.pointer(&row.pointer) {
+            Some(Value::String(s)) => WidgetAction::CopyText(s.clone()),
+            Some(value) => WidgetAction::CopyText(value.to_string()),
+            None => WidgetAction::None,
+        }
+    }
+
+    /// `node 3 of 12: replicas = 3, level 2`, or for an object or array
+    /// `node 1 of 12: spec, 4 keys, collapsed, level 1`.
+    fn describe(&self, element: usize) -> String {
+        let rows = self.rows();
+        let Some(row) = rows.get(element) else {
+            return "empty tree".to_string();
+        };
+        let what = match row.children {
+            Some(n) => {
+                let count = if row.summary.starts_with('[') {
+                    counted(n, "item", "items")
+                } else {
+                    counted(n, "key", "keys")
+                };
+                let state = if row.expanded { "expanded" } else { "collapsed" };
+                format!("{}, {}, {}", row.key, count, state)
+            }
+            None if row.key.is_empty() => row.summary.clone(),
+            None => format!("{} = {}", row.key, row.summary),
+        };
+        let detail = format!("{}, level {}", what, row.depth + 1);
+        BioLink::element_label("node", element + 1, rows.len(), &detail)
+    }
+
+    fn element_rect(&self, element: usize) -> Rect {
+        if element < self.scroll_row || element >= self.scroll_row + self.visible_rows() {
+            return self.rect;
+        }
+        Rect {
+            x: self.rect.x,
+            y: self.rect.y + ROWS_TOP + (element - self.scroll_row) as f32 * ROW_HEIGHT,
+            w: self.rect.w,
+            h: ROW_HEIGHT,
+        }
+    }
+}
+
+/// `parent` extended by `key`, escaped as RFC 6901 requires.
+fn child_pointer(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn counted(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
@@ -0,0 +1,151 @@
+//! Window and cursor appearance: `window.opacity`, `window.padding`,
+//! `cursor.style` and `cursor.blink_ms`.
+//!
+//! The cursor settings are the default; an application that picks a style
+//! with DECSCUSR (see `positronic_core::term::modes`) wins until it resets.
+//! Opacity is applied to the surface's clear color and alpha mode. The
+//! window asks for a transparent surface when it is created, since X11
+//! picks its visual then; where the surface still can't blend with the
+//! desktop, the app says so instead of silently staying opaque.
+
+use std::time::{Duration, Instant};
+
+use positronic_core::term::modes::{CursorShape, CursorStyle};
+
+/// Vault config keys.
+pub const OPACITY_KEY: &str = "window.opacity";
+pub const PADDING_KEY: &str = "window.padding";
+pub const CURSOR_STYLE_KEY: &str = "cursor.style";
+pub const CURSOR_BLINK_KEY: &str = "cursor.blink_ms";
+
+/// Space around the terminal text unless `window.padding` says otherwise.
+pub const DEFAULT_PADDING: f32 = 10.0;
+
+/// Below this the terminal is hard to read, so it's the floor.
+pub const MIN_OPACITY: f32 = 0.1;
+pub const MAX_PADDING: f32 = 64.0;
+/// Blink half-periods outside this range are rejected; 0 turns blinking off.
+pub const MIN_BLINK_MS: u64 = 100;
+pub const MAX_BLINK_MS: u64 = 5000;
+/// Used when an application asks for a blinking cursor but the setting
+/// doesn't blink.
+pub const DEFAULT_BLINK: Duration = Duration::from_millis(530);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowStyle {
+    /// Background opacity, `MIN_OPACITY..=1.0`.
+    pub opacity: f32,
+    /// Space around the terminal text, in pixels.
+    pub padding: f32,
+    pub cursor: CursorShape,
+    /// Time the cursor stays on (and off); `None` keeps it steady.
+    pub blink: Option<Duration>,
+}
+
+impl Default for WindowStyle {
+    fn default() -> Self {
+        Self { opacity: 1.0, padding: DEFAULT_PADDING, cursor: CursorShape::Bar, blink: None }
+    }
+}
+
+impl WindowStyle {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> WindowStyle {
+        let mut style = WindowStyle::default();
+        if let Some(value) = lookup(OPACITY_KEY) {
+            match parse_opacity(&value) {
+                Some(opacity) => style.opacity = opacity,
+                None => problems.push(format!(
+                    "{} = \"{}\": expected {} to 1.0 (or a percentage)",
+                    OPACITY_KEY, value, MIN_OPACITY
+                )),
+            }
+        }
+        if let Some(value) = lookup(PADDING_KEY) {
+            match value.trim().trim_end_matches("px").parse::<f32>() {
+                Ok(padding) if (0.0..=MAX_PADDING).contains(&padding) => style.padding = padding,
+                _ => problems.push(format!("{} = \"{}\": expected 0 to {} pixels", PADDING_KEY, value, MAX_PADDING)),
+            }
+        }
+        if let Some(value) = lookup(CURSOR_STYLE_KEY) {
+            match parse_shape(&value) {
+                Some(shape) => style.cursor = shape,
+                None => problems.push(format!(
+                    "{} = \"{}\": expected block, bar or underline",
+                    CURSOR_STYLE_KEY, value
+                )),
+            }
+        }
+        if let Some(value) = lookup(CURSOR_BLINK_KEY) {
+            match value.trim().trim_end_matches("ms").parse::<u64>() {
+                Ok(0) => style.blink = None,
+                Ok(ms) if (MIN_BLINK_MS..=MAX_BLINK_MS).contains(&ms) => style.blink = Some(Duration::from_millis(ms)),
+                _ => problems.push(format!(
+                    "{} = \"{}\": expected 0 (steady) or {} to {}",
+                    CURSOR_BLINK_KEY, value, MIN_BLINK_MS, MAX_BLINK_MS
+                )),
+            }
+        }
+        style
+    }
+
+    /// The cursor to draw: the application's DECSCUSR choice if it made
+    /// one, else the settings.
+    pub fn cursor(&self, app: Option<CursorStyle>) -> (CursorShape, Option<Duration>) {
+        match app {
+            Some(style) => (style.shape, style.blinking.then(|| self.blink.unwrap_or(DEFAULT_BLINK))),
+            None => (self.cursor, self.blink),
+        }
+    }
+}
+
+/// `"0.85"` or `"85%"`.
+pub fn parse_opacity(value: &str) -> Option<f32> {
+    let value = value.trim();
+    let opacity = match value.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    (MIN_OPACITY..=1.0).contains(&opacity).then_some(opacity)
+}
+
+pub fn parse_shape(value: &str) -> Option<CursorShape> {
+    match value.trim().to_lowercase().as_str() {
+        "block" => Some(CursorShape::Block),
+        "bar" | "beam" => Some(CursorShape::Bar),
+        "underline" => Some(CursorShape::Underline),
+        _ => None,
+    }
+}
+
+/// Blink phase: on for the first `blink` after `since`, then alternating.
+#[derive(Debug, Clone, Copy)]
+pub struct Blink {
+    since: Instant,
+}
+
+impl Blink {
+    pub fn new(now: Instant) -> Self {
+        Self { since: now }
+    }
+
+    /// Show the cursor solid again, e.g. after a key press.
+    pub fn restart(&mut self, now: Instant) {
+        self.since = now;
+    }
+
+    pub fn visible(&self, blink: Option<Duration>, now: Instant) -> bool {
+        match blink.filter(|p| !p.is_zero()) {
+            Some(period) => (now.saturating_duration_since(self.since).as_millis() / period.as_millis()).is_multiple_of(2),
+            None => true,
+        }
+    }
+
+    /// When the cursor next turns on or off; `None` while steady.
+    pub fn next_toggle(&self, blink: Option<Duration>, now: Instant) -> Option<Instant> {
+        let period = blink.filter(|p| !p.is_zero())?;
+        let phases = now.saturating_duration_since(self.since).as_millis() / period.as_millis();
+        Some(self.since + period * (phases as u32 + 1))
+    }
+}
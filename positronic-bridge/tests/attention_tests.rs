@@ -0,0 +1,323 @@
+// positronic-bridge/tests/attention_tests.rs
+//
+// Tests for unread markers and dimming: what counts as seen (focus,
+// view, dwell time), the command marks of the terminal view, and the
+// dimmed span helpers.
+
+use positronic_bridge::attention::{self, Attention, MIN_DWELL, ScreenMarks};
+use positronic_bridge::block::{BlockLine, BlockManager, BlockSource};
+use positronic_bridge::renderer::{BlockStyle, ColoredSpan, Rgba};
+use positronic_bridge::span_cache::SpanCache;
+use std::time::{Duration, Instant};
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+/// Unfocused, with a 24-line view over lines 100..124.
+fn unfocused(t0: Instant) -> Attention {
+    let mut att = Attention::default();
+    att.set_view(100..124, true, t0);
+    att.set_focus(false, t0);
+    att
+}
+
+// ============================================================================
+// Attention
+// ============================================================================
+
+#[test]
+fn test_finished_in_front_of_the_user_is_read() {
+    let t0 = Instant::now();
+    let mut att = Attention::default();
+    att.set_view(100..124, true, t0);
+    assert!(!att.finished(1, 105..110, t0));
+    assert_eq!(att.unread_count(), 0);
+    assert_eq!(att.label(), None);
+}
+
+#[test]
+fn test_finished_unfocused_is_unread_until_watched_for_the_dwell() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    assert!(att.finished(1, 105..110, t0));
+    assert_eq!(att.label().as_deref(), Some("● 1 unread"));
+
+    // In view all along, but nobody looking: still unread.
+    assert!(att.tick(t0 + ms(5_000)).is_empty());
+    assert_eq!(att.next_read(), None);
+
+    // Focus comes back: the dwell starts then.
+    let back = t0 + ms(6_000);
+    att.set_focus(true, back);
+    assert_eq!(att.next_read(), Some(back + MIN_DWELL));
+    assert!(att.tick(back + MIN_DWELL - ms(1)).is_empty());
+    assert_eq!(att.tick(back + MIN_DWELL), vec![1]);
+    assert!(!att.is_unread(1));
+}
+
+#[test]
+fn test_finished_while_scrolled_back_is_unread() {
+    let t0 = Instant::now();
+    let mut att = Attention::default();
+    att.set_view(10..34, false, t0);
+    assert!(att.finished(1, 200..210, t0));
+    assert!(att.tick(t0 + ms(10_000)).is_empty());
+
+    // Scrolling down to it starts the clock.
+    let t1 = t0 + ms(10_000);
+    att.set_view(190..214, true, t1);
+    assert_eq!(att.tick(t1 + MIN_DWELL), vec![1]);
+}
+
+#[test]
+fn test_scrolling_past_quickly_does_not_count() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    att.finished(1, 50..60, t0);
+    att.set_focus(true, t0);
+
+    let mut t = t0;
+    for top in (40..=60).step_by(10) {
+        att.set_view(top..top + 24, false, t);
+        t += ms(200);
+    }
+    // Lines 50..60 left view at top 60 before the dwell was up.
+    assert!(att.tick(t + MIN_DWELL).is_empty());
+    assert!(att.is_unread(1));
+}
+
+#[test]
+fn test_losing_focus_during_the_dwell_resets_it() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    att.finished(1, 105..110, t0);
+    att.set_focus(true, t0);
+    att.set_focus(false, t0 + ms(500));
+    att.set_focus(true, t0 + ms(600));
+    assert!(att.tick(t0 + MIN_DWELL).is_empty());
+    assert_eq!(att.tick(t0 + ms(600) + MIN_DWELL), vec![1]);
+}
+
+#[test]
+fn test_partly_visible_block_is_not_seen() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    att.finished(1, 95..110, t0);
+    att.set_focus(true, t0);
+    assert!(att.tick(t0 + ms(5_000)).is_empty());
+}
+
+#[test]
+fn test_block_taller_than_the_view_is_seen_by_its_end() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    att.finished(1, 0..120, t0);
+    att.set_focus(true, t0);
+    assert_eq!(att.tick(t0 + MIN_DWELL), vec![1]);
+
+    let mut att = unfocused(t0);
+    att.finished(1, 0..300, t0);
+    att.set_focus(true, t0);
+    assert!(att.tick(t0 + MIN_DWELL).is_empty());
+}
+
+#[test]
+fn test_relayout_moves_and_drops_unread_blocks() {
+    let t0 = Instant::now();
+    let mut att = unfocused(t0);
+    att.finished(1, 0..5, t0);
+    att.finished(2, 5..10, t0);
+    att.relayout(|id| (id == 2).then_some(103..108), t0);
+    assert_eq!(att.unread().collect::<Vec<_>>(), vec![(2, &(103..108))]);
+    att.set_focus(true, t0);
+    assert_eq!(att.tick(t0 + MIN_DWELL), vec![2]);
+}
+
+// ============================================================================
+// ScreenMarks
+// ============================================================================
+
+#[test]
+fn test_screen_marks_lay_commands_between_their_marks() {
+    let t0 = Instant::now();
+    let mut marks = ScreenMarks::default();
+    marks.attention.set_focus(false, t0);
+    marks.started(10);
+    marks.started(11);
+    assert!(marks.finished(20, Some(0), t0));
+    let unread: Vec<_> = marks.attention.unread().map(|(_, lines)| lines.clone()).collect();
+    assert_eq!(unread, vec![10..20]);
+
+    // A finish mark with no command running (the first prompt) is no block.
+    assert!(!marks.finished(25, None, t0));
+    assert_eq!(marks.attention.unread_count(), 1);
+
+    marks.clear();
+    assert_eq!(marks.attention.unread_count(), 0);
+    assert_eq!(marks.dim_before(Some(1)), None);
+}
+
+#[test]
+fn test_screen_marks_dim_above_the_kept_commands() {
+    let t0 = Instant::now();
+    let mut marks = ScreenMarks::default();
+    for start in [0, 10, 20, 30] {
+        marks.started(start);
+        marks.finished(start + 5, Some(0), t0);
+    }
+    assert_eq!(marks.dim_before(Some(2)), Some(20));
+    assert_eq!(marks.dim_before(Some(4)), Some(0));
+    assert_eq!(marks.dim_before(Some(5)), None);
+    assert_eq!(marks.dim_before(Some(0)), None);
+    assert_eq!(marks.dim_before(None), None);
+}
+
+#[test]
+fn test_screen_marks_keep_the_command_sent_and_its_origin() {
+    let t0 = Instant::now();
+    let mut marks = ScreenMarks::default();
+    marks.sent("cargo test", None);
+    marks.started(10);
+    marks.finished(20, Some(101), t0);
+    let first = marks.last().unwrap().clone();
+    assert_eq!((first.command.as_deref(), first.exit_code, first.lines), (Some("cargo test"), Some(101), 10..20));
+    assert_eq!(marks.command_at(10).map(|b| b.id), Some(first.id));
+    assert_eq!(marks.command_at(11), None, "only the line it was typed on");
+
+    marks.sent("cargo test -- --nocapture", Some(first.id));
+    marks.started(20);
+    marks.started(21);
+    marks.finished(30, Some(0), t0);
+    let second = marks.last().unwrap().clone();
+    assert_eq!(second.derived_from, Some(first.id));
+
+    // Not sent from the input line: no command to take back.
+    marks.started(30);
+    marks.finished(32, Some(0), t0);
+    assert_eq!(marks.last().unwrap().command, None);
+    assert_eq!(marks.command_at(30), None);
+
+    let chain: Vec<_> = marks.chain(second.id).iter().map(|b| b.id).collect();
+    assert_eq!(chain, vec![first.id, second.id]);
+    assert_eq!(marks.chain(first.id).len(), 1);
+    assert!(marks.chain(99).is_empty());
+}
+
+#[test]
+fn test_screen_marks_focus_moves_between_blocks() {
+    let t0 = Instant::now();
+    let mut marks = ScreenMarks::default();
+    assert_eq!(marks.focus_prev(), None, "nothing to focus");
+    for start in [0, 10, 20] {
+        marks.started(start);
+        marks.finished(start + 5, Some(0), t0);
+    }
+    assert_eq!(marks.focus_prev().map(|b| b.lines.start), Some(20), "the newest first");
+    assert_eq!(marks.focus_prev().map(|b| b.lines.start), Some(10));
+    assert_eq!(marks.focus_prev().map(|b| b.lines.start), Some(0));
+    assert_eq!(marks.focus_prev().map(|b| b.lines.start), Some(0), "the oldest stays");
+    assert_eq!(marks.focus_next().map(|b| b.lines.start), Some(10));
+    assert_eq!(marks.focused().map(|b| b.lines.start), Some(10));
+    marks.focus_next();
+    assert_eq!(marks.focus_next(), None, "past the newest is the input line");
+    assert_eq!(marks.focused(), None);
+
+    marks.focus_prev();
+    marks.clear();
+    assert_eq!(marks.focused(), None);
+    assert_eq!(marks.last(), None);
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+#[test]
+fn test_screen_rows_clip_to_the_view() {
+    assert_eq!(attention::screen_rows(&(105..110), 100, 24), Some(5..10));
+    assert_eq!(attention::screen_rows(&(90..110), 100, 24), Some(0..10));
+    assert_eq!(attention::screen_rows(&(120..130), 100, 24), Some(20..24));
+    assert_eq!(attention::screen_rows(&(130..140), 100, 24), None);
+    assert_eq!(attention::screen_rows(&(90..100), 100, 24), None);
+}
+
+#[test]
+fn test_parse_dim_old() {
+    assert_eq!(attention::parse_dim_old("5"), Some(Some(5)));
+    assert_eq!(attention::parse_dim_old(" off "), Some(None));
+    assert_eq!(attention::parse_dim_old("0"), Some(None));
+    assert_eq!(attention::parse_dim_old("some"), None);
+    assert_eq!(attention::parse_dim_old("-1"), None);
+}
+
+#[test]
+fn test_dim_rows_splits_at_the_row_boundary() {
+    let white = Rgba::rgb(1.0, 1.0, 1.0);
+    let mut spans = vec![ColoredSpan::new("a\nb\nc\n", white), ColoredSpan::new("d\n", white)];
+    attention::dim_rows(&mut spans, 2);
+    let parts: Vec<(&str, f32)> = spans.iter().map(|s| (s.text.as_str(), s.color.a)).collect();
+    assert_eq!(parts, vec![("a\nb\n", attention::DIM_FACTOR), ("c\n", 1.0), ("d\n", 1.0)]);
+
+    let mut spans = vec![ColoredSpan::new("a\n", white)];
+    attention::dim_rows(&mut spans, 0);
+    assert_eq!(spans[0].color, white);
+}
+
+// ============================================================================
+// BlockManager and SpanCache
+// ============================================================================
+
+#[test]
+fn test_block_manager_tracks_unread_blocks() {
+    let mut mgr = BlockManager::new(100, 10_000);
+    let t0 = Instant::now();
+    mgr.attention_mut().set_focus(false, t0);
+
+    let first = mgr.begin("make", "/tmp", BlockSource::Shell);
+    mgr.append(first, vec![BlockLine::classify("one"), BlockLine::classify("two")]);
+    mgr.finish(first, Some(0), ms(5));
+    let second = mgr.begin("ls", "/tmp", BlockSource::Shell);
+    mgr.append(second, vec![BlockLine::classify("a")]);
+    mgr.finish(second, Some(0), ms(5));
+
+    assert_eq!(mgr.unread_count(), 2);
+    assert_eq!(mgr.line_range(second), Some(2..3));
+
+    // Removing the first block moves the second up.
+    mgr.remove(first);
+    assert_eq!(mgr.unread_count(), 1);
+    assert_eq!(mgr.attention().unread().collect::<Vec<_>>(), vec![(second, &(0..1))]);
+
+    mgr.clear();
+    assert_eq!(mgr.unread_count(), 0);
+}
+
+#[test]
+fn test_block_spans_dim_older_blocks() {
+    let mut mgr = BlockManager::new(100, 10_000);
+    for i in 0..3 {
+        let id = mgr.begin(&format!("echo {}", i), "/tmp", BlockSource::Shell);
+        mgr.append(id, vec![BlockLine::classify(format!("line {}", i))]);
+        mgr.finish(id, Some(0), ms(5));
+    }
+    let mut cache = SpanCache::new();
+    cache.set_block_style(BlockStyle { dim_old: Some(1), ..BlockStyle::default() });
+    let alphas = |spans: &[ColoredSpan], text: &str| -> Vec<f32> {
+        spans.iter().filter(|s| s.text.contains(text)).map(|s| s.color.a).collect()
+    };
+
+    let spans = cache.block_spans(mgr.blocks(), 0..3);
+    assert!(alphas(&spans, "line 0").iter().all(|&a| a < 1.0));
+    assert!(alphas(&spans, "line 2").iter().all(|&a| a == 1.0));
+
+    // A new command pushes the last one into the dimmed part.
+    let id = mgr.begin("echo 3", "/tmp", BlockSource::Shell);
+    mgr.finish(id, Some(0), ms(5));
+    let spans = cache.block_spans(mgr.blocks(), 0..4);
+    assert!(alphas(&spans, "line 2").iter().all(|&a| a < 1.0));
+
+    cache.set_block_style(BlockStyle { dim_old: None, ..BlockStyle::default() });
+    let spans = cache.block_spans(mgr.blocks(), 0..4);
+    assert!(alphas(&spans, "line 0").iter().all(|&a| a == 1.0));
+}
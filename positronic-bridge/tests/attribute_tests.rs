@@ -0,0 +1,184 @@
+// positronic-bridge/tests/attribute_tests.rs
+//
+// Tests for SGR text attributes past the state machine: bytes → grid →
+// ANSI export round trips for each attribute and color form, how spans
+// carry weight, slant and decorations, inverse and dim at render time, and
+// the decoration rectangles built from glyph positions.
+
+use positronic_bridge::renderer::{
+    snapshot_to_ansi, snapshot_to_plain, snapshot_to_spans, span_decorations, ColoredSpan, DimStyle, GlyphBox,
+    Rgba, SpanStyle, ThemeName,
+};
+use positronic_core::state_machine::{CellAttrs, Snapshot, StateMachine};
+
+fn screen(bytes: &str) -> Snapshot {
+    let sm = StateMachine::new(20, 3);
+    sm.process_bytes(bytes.as_bytes());
+    sm.snapshot()
+}
+
+/// The grid, exported as ANSI and parsed again, has the same cells.
+fn round_trip(bytes: &str) -> (Snapshot, String) {
+    let snap = screen(bytes);
+    let ansi = snapshot_to_ansi(&snap);
+    let again = screen(&ansi.trim_end_matches('\n').replace('\n', "\r\n"));
+    for row in 0..2 {
+        assert_eq!(snap[row], again[row], "cells of row {} after {:?}", row, ansi);
+        assert_eq!(snap.row_styles(row), again.row_styles(row), "styles of row {} after {:?}", row, ansi);
+    }
+    (snap, ansi)
+}
+
+fn span_for<'a>(spans: &'a [ColoredSpan], text: &str) -> &'a ColoredSpan {
+    spans.iter().find(|s| s.text.trim_end() == text).unwrap_or_else(|| panic!("no span {:?} in {:?}", text, spans))
+}
+
+// ============================================================================
+// ANSI round trips
+// ============================================================================
+
+#[test]
+fn ansi_round_trips_bold() {
+    let (snap, ansi) = round_trip("a\x1b[1mbold\x1b[0m z");
+    assert_eq!(snap.row_styles(0)[1].attrs, CellAttrs::BOLD);
+    assert!(ansi.starts_with("a\x1b[0;1mbold\x1b[0m z\n"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_dim() {
+    let (snap, ansi) = round_trip("\x1b[2mfaint\x1b[22m ok");
+    assert_eq!(snap.row_styles(0)[0].attrs, CellAttrs::DIM);
+    assert!(ansi.starts_with("\x1b[0;2mfaint\x1b[0m ok\n"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_italic() {
+    let (snap, ansi) = round_trip("\x1b[3mslant\x1b[23m");
+    assert_eq!(snap.row_styles(0)[0].attrs, CellAttrs::ITALIC);
+    assert!(ansi.starts_with("\x1b[0;3mslant\x1b[0m\n"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_underline_and_its_color() {
+    let (snap, ansi) = round_trip("\x1b[4mu\x1b[58;2;1;2;3mv\x1b[58;5;200mw\x1b[24mx");
+    let styles = snap.row_styles(0);
+    assert!(styles[..3].iter().all(|s| s.attrs == CellAttrs::UNDERLINE));
+    assert!(styles[3].attrs.is_empty());
+    assert!(ansi.contains("\x1b[0;4;58;2;1;2;3mv"), "{:?}", ansi);
+    assert!(ansi.contains("\x1b[0;4;58;5;200mw"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_inverse() {
+    let (snap, ansi) = round_trip("\x1b[7m sel \x1b[27m");
+    assert_eq!(snap.row_styles(0)[0].attrs, CellAttrs::INVERSE);
+    // Inverse blanks are content, not trailing padding.
+    assert!(ansi.starts_with("\x1b[0;7m sel \x1b[0m\n"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_strikethrough() {
+    let (snap, ansi) = round_trip("\x1b[9mgone\x1b[29m");
+    assert_eq!(snap.row_styles(0)[0].attrs, CellAttrs::STRIKETHROUGH);
+    assert!(ansi.starts_with("\x1b[0;9mgone\x1b[0m\n"), "{:?}", ansi);
+}
+
+#[test]
+fn ansi_round_trips_colors_in_every_form() {
+    let (_, ansi) = round_trip("\x1b[31;44mr\x1b[92;105mg\x1b[38;5;208;48;5;17mi\x1b[38;2;10;20;30;48;2;40;50;60mt");
+    for expected in ["\x1b[0;31;44mr", "\x1b[0;92;105mg", "\x1b[0;38;5;208;48;5;17mi", "\x1b[0;38;2;10;20;30;48;2;40;50;60mt"] {
+        assert!(ansi.contains(expected), "{:?} in {:?}", expected, ansi);
+    }
+    assert!(ansi.starts_with("\x1b[0;31;44mr"));
+}
+
+#[test]
+fn ansi_round_trips_combined_attributes_across_lines() {
+    let (_, ansi) = round_trip("\x1b[1;3;4;31mhot\r\nstill\x1b[0m plain");
+    let lines: Vec<&str> = ansi.lines().collect();
+    // Each line opens its own state and closes it, so lines paste alone.
+    assert_eq!(lines[0], "\x1b[0;1;3;4;31mhot\x1b[0m");
+    assert_eq!(lines[1], "\x1b[0;1;3;4;31mstill\x1b[0m plain");
+}
+
+#[test]
+fn plain_text_stays_attribute_free() {
+    let snap = screen("\x1b[1;4;31mwarn\x1b[0m: \x1b[7mx\x1b[0m");
+    assert_eq!(snapshot_to_plain(&snap).lines().next(), Some("warn: x"));
+    assert!(snapshot_to_ansi(&screen("plain")).starts_with("plain\n"));
+}
+
+// ============================================================================
+// Spans
+// ============================================================================
+
+#[test]
+fn spans_carry_weight_slant_and_decorations() {
+    let snap = screen("\x1b[1mB\x1b[0;3mI\x1b[0;4mU\x1b[0;9mS\x1b[0;4;58;5;1mC\x1b[0m");
+    let spans = snapshot_to_spans(&snap, ThemeName::Default);
+
+    assert_eq!(span_for(&spans, "B").style, SpanStyle { bold: true, ..Default::default() });
+    assert_eq!(span_for(&spans, "I").style, SpanStyle { italic: true, ..Default::default() });
+    let u = span_for(&spans, "U");
+    assert_eq!(u.style.underline, Some(u.color));
+    assert!(span_for(&spans, "S").style.strikethrough);
+    assert_eq!(span_for(&spans, "C").style.underline, Some(Rgba::rgb(0.8, 0.0, 0.0)));
+}
+
+#[test]
+fn inverse_swaps_foreground_and_background() {
+    let theme = ThemeName::Dracula;
+    let spans = snapshot_to_spans(&screen("\x1b[31;42;7mab\x1b[27;49mc\x1b[0;7md"), theme);
+
+    let swapped = span_for(&spans, "ab");
+    let plain = span_for(&spans, "c");
+    assert_eq!(swapped.style.background, Some(plain.color));
+    assert_ne!(swapped.color, plain.color);
+
+    // With default colors, the text takes the theme background.
+    let d = span_for(&spans, "d");
+    assert_eq!(d.color, theme.bg_color());
+    assert!(d.style.background.is_some());
+}
+
+#[test]
+fn dim_follows_the_theme() {
+    let normal = span_for(&snapshot_to_spans(&screen("x"), ThemeName::Default), "x").color;
+    let dim = span_for(&snapshot_to_spans(&screen("\x1b[2mx"), ThemeName::Default), "x").color;
+    assert_eq!(ThemeName::Default.dim_style(), DimStyle::Blend(0.45));
+    assert_eq!(dim, normal.blend(ThemeName::Default.bg_color(), 0.45));
+    assert_eq!(dim.a, 1.0);
+
+    let solarized = span_for(&snapshot_to_spans(&screen("\x1b[2mx"), ThemeName::Solarized), "x").color;
+    assert_eq!(ThemeName::Solarized.dim_style(), DimStyle::Alpha(0.6));
+    assert_eq!((solarized.r, solarized.g, solarized.b), (normal.r, normal.g, normal.b));
+    assert!(solarized.a < 1.0);
+}
+
+// ============================================================================
+// Decorations
+// ============================================================================
+
+#[test]
+fn decorations_cover_runs_of_glyphs_per_line() {
+    let red = Rgba::rgb(1.0, 0.0, 0.0);
+    let blue = Rgba::rgb(0.0, 0.0, 1.0);
+    let spans = vec![
+        ColoredSpan::new("ab", red),
+        ColoredSpan::styled("cd", red, SpanStyle { underline: Some(blue), background: Some(blue), ..Default::default() }),
+        ColoredSpan::styled("e", red, SpanStyle { strikethrough: true, ..Default::default() }),
+    ];
+    let glyph = |span, x: f32, line_top: f32| GlyphBox { span, x, w: 8.0, line_top, line_height: 18.0, baseline: line_top + 14.0 };
+    let glyphs = [glyph(0, 0.0, 0.0), glyph(0, 8.0, 0.0), glyph(1, 16.0, 0.0), glyph(1, 24.0, 0.0), glyph(2, 0.0, 18.0)];
+
+    let decos = span_decorations(&spans, &glyphs, 1.0);
+    assert_eq!(decos.len(), 3);
+    // Background spans both glyphs of "cd" and the full line height.
+    assert_eq!((decos[0].x, decos[0].y, decos[0].w, decos[0].h, decos[0].color), (16.0, 0.0, 16.0, 18.0, blue));
+    // Underline sits below the baseline, inside the line.
+    assert_eq!((decos[1].x, decos[1].w, decos[1].h, decos[1].color), (16.0, 16.0, 1.0, blue));
+    assert!(decos[1].y > 14.0 && decos[1].y + decos[1].h <= 18.0);
+    // Strikethrough is in the text color, between top and baseline.
+    assert_eq!((decos[2].x, decos[2].w, decos[2].color), (0.0, 8.0, red));
+    assert!(decos[2].y > 18.0 && decos[2].y < 32.0);
+}
@@ -0,0 +1,246 @@
+// positronic-bridge/tests/bell_tests.rs
+//
+// Tests for the bell policy: every combination of focus, remote session,
+// !follow pane and mute through `resolve`, loading `bell.*`, coalescing
+// on an injected clock, mute expiry, `!bell` parsing and the sink calls.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::NaiveTime;
+use positronic_bridge::bell::{
+    self, is_remote, resolve, BellAction, BellCommand, BellContext, BellPolicy, BellRule, BellSink, Coalescer, Mute,
+    COALESCE_WINDOW,
+};
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+/// Every override set, each to something the others aren't.
+fn full_policy() -> BellPolicy {
+    BellPolicy {
+        action: BellAction::Sound,
+        follow: Some(BellAction::Silent),
+        remote: Some(BellAction::Notify),
+        unfocused: Some(BellAction::Announce),
+    }
+}
+
+fn context(focused: bool, remote: bool, follow: bool, muted: bool) -> BellContext {
+    BellContext { focused, remote, follow, muted }
+}
+
+// ============================================================================
+// resolve
+// ============================================================================
+
+#[test]
+fn test_resolve_every_context_with_every_override() {
+    let policy = full_policy();
+    for bits in 0..16u8 {
+        let (focused, remote, follow, muted) = (bits & 1 != 0, bits & 2 != 0, bits & 4 != 0, bits & 8 != 0);
+        let expected = if muted {
+            (BellAction::Silent, BellRule::Muted)
+        } else if follow {
+            (BellAction::Silent, BellRule::Follow)
+        } else if remote {
+            (BellAction::Notify, BellRule::Remote)
+        } else if !focused {
+            (BellAction::Announce, BellRule::Unfocused)
+        } else {
+            (BellAction::Sound, BellRule::Default)
+        };
+        let ctx = context(focused, remote, follow, muted);
+        assert_eq!(resolve(&policy, ctx), expected, "{:?}", ctx);
+    }
+}
+
+#[test]
+fn test_resolve_unset_overrides_fall_through() {
+    let policy = BellPolicy { action: BellAction::Flash, follow: None, remote: None, unfocused: None };
+    for bits in 0..8u8 {
+        let ctx = context(bits & 1 != 0, bits & 2 != 0, bits & 4 != 0, false);
+        assert_eq!(resolve(&policy, ctx), (BellAction::Flash, BellRule::Default), "{:?}", ctx);
+    }
+    assert_eq!(resolve(&policy, context(true, true, true, true)), (BellAction::Silent, BellRule::Muted));
+
+    // Only the remote override: it still beats an unset unfocused one.
+    let remote_only = BellPolicy { remote: Some(BellAction::Notify), ..policy };
+    assert_eq!(resolve(&remote_only, context(false, true, true, false)), (BellAction::Notify, BellRule::Remote));
+    assert_eq!(resolve(&remote_only, context(false, false, true, false)), (BellAction::Flash, BellRule::Default));
+}
+
+#[test]
+fn test_default_policy() {
+    let policy = BellPolicy::default();
+    assert_eq!(resolve(&policy, context(true, false, false, false)).0, BellAction::Flash);
+    assert_eq!(resolve(&policy, context(false, false, false, false)).0, BellAction::Notify);
+    assert_eq!(resolve(&policy, context(false, false, true, false)).0, BellAction::Silent);
+    assert_eq!(resolve(&policy, context(true, true, false, false)).0, BellAction::Flash, "no remote override");
+}
+
+#[test]
+fn test_is_remote() {
+    for remote in ["ssh host", "/usr/bin/ssh -p 22 me@box", "mosh box", "autossh -M 0 box", "ssh.exe host", "telnet h"] {
+        assert!(is_remote(remote), "{}", remote);
+    }
+    for local in ["", "   ", "sshd", "vim ssh", "echo ssh", "cargo test"] {
+        assert!(!is_remote(local), "{}", local);
+    }
+}
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+#[test]
+fn test_policy_load() {
+    let config: HashMap<&str, &str> =
+        [("bell.action", "Sound"), ("bell.follow", "default"), ("bell.remote", "notify"), ("bell.unfocused", "loud")]
+            .into();
+    let mut problems = Vec::new();
+    let policy = BellPolicy::load(|key| config.get(key).map(|v| v.to_string()), &mut problems);
+    assert_eq!(
+        policy,
+        BellPolicy {
+            action: BellAction::Sound,
+            follow: None,
+            remote: Some(BellAction::Notify),
+            unfocused: BellPolicy::default().unfocused,
+        }
+    );
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("bell.unfocused = \"loud\""), "{}", problems[0]);
+
+    let mut problems = Vec::new();
+    let policy = BellPolicy::load(|key| (key == "bell.action").then(|| "default".to_string()), &mut problems);
+    assert_eq!(policy, BellPolicy::default(), "`default` only clears an override");
+    assert_eq!(problems.len(), 1);
+}
+
+#[test]
+fn test_action_parse_and_label_round_trip() {
+    for action in [BellAction::Sound, BellAction::Flash, BellAction::Notify, BellAction::Announce, BellAction::Silent] {
+        assert_eq!(BellAction::parse(action.label()), Some(action));
+    }
+    assert_eq!(BellAction::parse(" visual "), Some(BellAction::Flash));
+    assert_eq!(BellAction::parse("off"), Some(BellAction::Silent));
+    assert_eq!(BellAction::parse("ding"), None);
+}
+
+// ============================================================================
+// Coalescing and mute
+// ============================================================================
+
+#[test]
+fn test_bells_in_a_window_are_one_action() {
+    let t0 = Instant::now();
+    let mut bells = Coalescer::default();
+    assert_eq!(bells.next_wake(), None);
+    bells.ring(0, t0);
+    assert_eq!(bells.next_wake(), None, "no bells, nothing to do");
+
+    bells.ring(1, t0);
+    bells.ring(3, t0 + ms(100));
+    assert_eq!(bells.next_wake(), Some(t0 + COALESCE_WINDOW));
+    assert_eq!(bells.take_due(t0 + ms(200)), None);
+    bells.ring(1, t0 + ms(240));
+    assert_eq!(bells.next_wake(), Some(t0 + COALESCE_WINDOW), "the window doesn't slide");
+    assert_eq!(bells.take_due(t0 + COALESCE_WINDOW), Some(5));
+    assert_eq!(bells.take_due(t0 + ms(1000)), None);
+
+    bells.ring(1, t0 + ms(1000));
+    assert_eq!(bells.take_due(t0 + ms(1000) + COALESCE_WINDOW), Some(1), "a new run");
+}
+
+#[test]
+fn test_mute_expires() {
+    let t0 = Instant::now();
+    let clock = NaiveTime::from_hms_opt(23, 55, 0).unwrap();
+    let mut mute = Mute::default();
+    assert!(!mute.active(t0));
+    assert_eq!(mute.label(t0), None);
+
+    mute.start(Duration::from_secs(600), t0, clock);
+    assert!(mute.active(t0 + Duration::from_secs(599)));
+    assert_eq!(mute.label(t0).as_deref(), Some("🔕 until 00:05"), "past midnight");
+    assert_eq!(mute.next_wake(t0), Some(t0 + Duration::from_secs(600)));
+    assert!(!mute.take_expired(t0 + Duration::from_secs(599)));
+
+    let end = t0 + Duration::from_secs(600);
+    assert!(!mute.active(end));
+    assert_eq!(mute.label(end), None);
+    assert_eq!(mute.next_wake(end), None);
+    assert!(mute.take_expired(end));
+    assert!(!mute.take_expired(end), "only once");
+
+    mute.start(Duration::from_secs(60), t0, clock);
+    mute.stop();
+    assert!(!mute.active(t0));
+    assert!(!mute.take_expired(t0 + Duration::from_secs(120)));
+}
+
+// ============================================================================
+// !bell
+// ============================================================================
+
+#[test]
+fn test_bell_command_parse() {
+    assert_eq!(BellCommand::parse(""), Ok(BellCommand::Status));
+    assert_eq!(BellCommand::parse("test"), Ok(BellCommand::Test));
+    assert_eq!(BellCommand::parse("unmute"), Ok(BellCommand::Unmute));
+    assert_eq!(BellCommand::parse("mute 10m"), Ok(BellCommand::Mute(Duration::from_secs(600))));
+    assert_eq!(BellCommand::parse("mute 90s"), Ok(BellCommand::Mute(Duration::from_secs(90))));
+    assert_eq!(BellCommand::parse("mute 2h"), Ok(BellCommand::Mute(Duration::from_secs(7200))));
+    assert_eq!(BellCommand::parse("mute 15"), Ok(BellCommand::Mute(Duration::from_secs(900))));
+    assert!(BellCommand::parse("mute 25h").unwrap_err().contains("at most 24h"));
+    assert!(BellCommand::parse("mute 0m").unwrap_err().contains("Bad duration"));
+    assert!(BellCommand::parse("mute soon").unwrap_err().contains("Bad duration"));
+    assert_eq!(BellCommand::parse("mute"), Err(bell::BELL_USAGE.to_string()));
+    assert_eq!(BellCommand::parse("ring"), Err(bell::BELL_USAGE.to_string()));
+}
+
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl BellSink for Recorder {
+    fn sound(&mut self) {
+        self.0.push("sound".to_string());
+    }
+    fn flash(&mut self) {
+        self.0.push("flash".to_string());
+    }
+    fn notify(&mut self, text: &str) {
+        self.0.push(format!("notify {}", text));
+    }
+    fn announce(&mut self, text: &str) {
+        self.0.push(format!("announce {}", text));
+    }
+}
+
+#[test]
+fn test_ring_calls_the_sink_once_with_the_count() {
+    let mut sink = Recorder::default();
+    bell::ring(&mut sink, BellAction::Notify, 4);
+    bell::ring(&mut sink, BellAction::Announce, 1);
+    bell::ring(&mut sink, BellAction::Sound, 9);
+    bell::ring(&mut sink, BellAction::Flash, 2);
+    bell::ring(&mut sink, BellAction::Silent, 3);
+    assert_eq!(sink.0, ["notify Bell ×4", "announce Bell", "sound", "flash"]);
+}
+
+#[test]
+fn test_status_lines_show_overrides_and_mute() {
+    let t0 = Instant::now();
+    let mut mute = Mute::default();
+    let lines = bell::status_lines(&BellPolicy::default(), &mute, t0);
+    assert_eq!(lines[0], "🔔 Bell: flash");
+    assert_eq!(lines[2], "  in a remote shell: default");
+    assert!(!lines.iter().any(|l| l.contains("🔕")));
+
+    mute.start(Duration::from_secs(60), t0, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+    let lines = bell::status_lines(&full_policy(), &mute, t0);
+    assert_eq!(lines[0], "🔔 Bell: sound");
+    assert!(lines.contains(&"  🔕 until 09:01 (!bell unmute)".to_string()), "{:?}", lines);
+}
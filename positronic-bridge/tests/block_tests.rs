@@ -5,9 +5,11 @@
 // filtering, serialization, display, limits, search, and export.
 
 use positronic_bridge::block::{
-    BlockId, BlockLine, BlockManager, BlockSource, BlockStats, LineKind, SearchHit,
-    TerminalBlock, format_duration, quick_block, quick_error_block,
+    BlockId, BlockLine, BlockManager, BlockSource, BlockStats, LineKind, RetentionPolicy,
+    SearchHit, TerminalBlock, TrimSummary, format_duration, quick_block, quick_error_block,
 };
+use positronic_bridge::holodeck::HolodeckManager;
+use positronic_bridge::span_cache::SpanCache;
 use std::time::Duration;
 
 // ============================================================================
@@ -727,6 +729,143 @@ fn test_enforce_block_count_limit() {
     assert_eq!(mgr.blocks().len(), 3);
 }
 
+/// A finished block with `lines` output lines of `text`.
+fn finished(mgr: &mut BlockManager, command: &str, lines: usize, text: &str, exit_code: i32) -> BlockId {
+    let id = mgr.begin(command, ".", BlockSource::Shell);
+    mgr.append(id, vec![BlockLine::normal(text); lines]);
+    mgr.finish(id, Some(exit_code), Duration::from_millis(1));
+    id
+}
+
+fn commands(mgr: &BlockManager) -> Vec<&str> {
+    mgr.blocks().iter().map(|b| b.command.as_str()).collect()
+}
+
+#[test]
+fn test_eviction_keeps_failed_blocks_longer() {
+    let policy = RetentionPolicy { max_blocks: 3, failed_max_blocks: 5, ..RetentionPolicy::default() };
+    let mut mgr = BlockManager::with_policy(policy);
+    finished(&mut mgr, "bad1", 0, "", 1);
+    finished(&mut mgr, "ok1", 0, "", 0);
+    finished(&mut mgr, "bad2", 0, "", 2);
+    finished(&mut mgr, "ok2", 0, "", 0);
+    assert_eq!(commands(&mgr), vec!["bad1", "bad2", "ok2"], "the oldest successful block goes first");
+
+    finished(&mut mgr, "bad3", 0, "", 1);
+    finished(&mut mgr, "bad4", 0, "", 1);
+    assert_eq!(commands(&mgr), vec!["bad1", "bad2", "bad3", "bad4"], "then the rest, never the newest");
+
+    finished(&mut mgr, "bad5", 0, "", 1);
+    finished(&mut mgr, "bad6", 0, "", 1);
+    assert_eq!(commands(&mgr), vec!["bad2", "bad3", "bad4", "bad5", "bad6"], "failed blocks go past their own limit");
+    assert_eq!(mgr.stats().evicted, 3);
+}
+
+#[test]
+fn test_pinned_blocks_are_never_evicted() {
+    let mut mgr = BlockManager::new(2, 1000);
+    let first = finished(&mut mgr, "first", 0, "", 0);
+    assert!(mgr.pin(first));
+    assert!(!mgr.pin(999));
+    for i in 0..4 {
+        finished(&mut mgr, &format!("cmd {}", i), 0, "", 0);
+    }
+    assert_eq!(commands(&mgr), vec!["first", "cmd 3"]);
+    assert_eq!(mgr.stats().pinned, 1);
+
+    mgr.unpin(first);
+    finished(&mut mgr, "cmd 4", 0, "", 0);
+    assert_eq!(commands(&mgr), vec!["cmd 3", "cmd 4"]);
+}
+
+#[test]
+fn test_tagged_blocks_are_kept_and_tags_are_unique() {
+    let mut mgr = BlockManager::new(2, 1000);
+    let first = finished(&mut mgr, "first", 0, "", 0);
+    let second = finished(&mut mgr, "second", 0, "", 0);
+    assert!(mgr.tag(first, "green"));
+    assert!(!mgr.tag(999, "green"));
+    for i in 0..3 {
+        finished(&mut mgr, &format!("cmd {}", i), 0, "", 0);
+    }
+    assert_eq!(commands(&mgr), vec!["first", "cmd 2"]);
+    assert!(mgr.get(second).is_none());
+
+    // Re-pointing a name moves it; the old block becomes evictable.
+    let latest = mgr.latest().unwrap().id;
+    assert!(mgr.tag(latest, "green"));
+    assert_eq!(mgr.tagged("green").map(|b| b.id), Some(latest));
+    assert!(mgr.get(first).unwrap().tags.is_empty());
+    finished(&mut mgr, "cmd 3", 0, "", 0);
+    assert_eq!(commands(&mgr), vec!["cmd 2", "cmd 3"]);
+
+    assert!(mgr.untag("green"));
+    assert!(!mgr.untag("green"));
+}
+
+#[test]
+fn test_byte_accounting_and_limit() {
+    let policy = RetentionPolicy { max_bytes: 100, ..RetentionPolicy::default() };
+    let mut mgr = BlockManager::with_policy(policy);
+    let id = finished(&mut mgr, "echo", 3, "0123456789", 0);
+    // Command + CWD + output text.
+    assert_eq!(mgr.get(id).unwrap().char_size(), 4 + 1 + 30);
+    assert_eq!(mgr.total_bytes(), 35);
+
+    finished(&mut mgr, "echo", 3, "0123456789", 0);
+    finished(&mut mgr, "echo", 3, "0123456789", 0);
+    let stats = mgr.stats();
+    assert_eq!((stats.total_blocks, stats.total_bytes, stats.evicted), (2, 70, 1));
+    assert!(stats.to_string().contains("70 B"));
+}
+
+#[test]
+fn test_trimmed_placeholder_collapses_evictions() {
+    let mut mgr = BlockManager::new(2, 1000);
+    assert!(mgr.trimmed().is_none());
+    finished(&mut mgr, "a", 2, "x", 0);
+    finished(&mut mgr, "b", 0, "", 0);
+    finished(&mut mgr, "c", 0, "", 0);
+    assert_eq!(mgr.trimmed().unwrap().to_string(), "··· 1 older block trimmed (view in !history) ···");
+
+    for i in 0..34 {
+        finished(&mut mgr, &format!("cmd {}", i), 1, "y", 0);
+    }
+    let trimmed = *mgr.trimmed().unwrap();
+    assert_eq!(trimmed.blocks, 35);
+    assert_eq!(trimmed.to_string(), "··· 35 older blocks trimmed (view in !history) ···");
+    assert_eq!(trimmed.lines, 2 + 32);
+
+    mgr.clear();
+    assert!(mgr.trimmed().is_none());
+    assert_eq!(mgr.stats().evicted, 35, "the session count survives a clear");
+
+    let policy = RetentionPolicy { summarize: false, ..*mgr.policy() };
+    mgr.set_policy(policy);
+    for i in 0..3 {
+        finished(&mut mgr, &format!("more {}", i), 0, "", 0);
+    }
+    assert_eq!(mgr.trimmed(), None::<&TrimSummary>);
+}
+
+#[test]
+fn test_eviction_releases_spans_and_holodeck_links() {
+    let mut mgr = BlockManager::new(1, 1000);
+    let mut holodeck = HolodeckManager::new();
+    let mut spans = SpanCache::new();
+    let first = finished(&mut mgr, "cat data.csv", 2, "a,b", 0);
+    let entry = holodeck.ingest("a,b\n1,2\n3,4");
+    mgr.link_holodeck(first, entry);
+    spans.block_spans(mgr.blocks(), 0..1);
+    assert_eq!(spans.cached_fragments(), 1);
+
+    finished(&mut mgr, "ls", 0, "", 0);
+    mgr.release_evicted(&mut spans, &mut holodeck);
+    assert!(holodeck.get(entry).is_none());
+    assert_eq!(spans.cached_fragments(), 0);
+    assert!(mgr.take_evicted().is_empty(), "released once");
+}
+
 #[test]
 fn test_total_lines() {
     let mut mgr = BlockManager::default();
@@ -769,6 +908,8 @@ fn test_stats_display() {
         total_lines: 250,
         running: 2,
         errors: 1,
+
+        ..Default::default()
     };
     let display = format!("{}", stats);
     assert!(display.contains("10 blocks"));
@@ -942,6 +1083,8 @@ fn test_block_stats_serialize() {
         total_lines: 5000,
         running: 3,
         errors: 7,
+
+        ..Default::default()
     };
     let json = serde_json::to_string(&stats).unwrap();
     let de: BlockStats = serde_json::from_str(&json).unwrap();
@@ -1053,4 +1196,147 @@ fn test_interleaved_blocks() {
     assert_eq!(mgr.get(id2).unwrap().line_count(), 2);
     assert!(!mgr.get(id1).unwrap().running);
     assert!(!mgr.get(id2).unwrap().running);
-}
\ No newline at end of file
+}
+// ============================================================================
+// Line Timestamps
+// ============================================================================
+
+use positronic_bridge::renderer::{timestamp_gutter, TimestampMode};
+use positronic_core::blocks::TerminalBlockV2;
+use positronic_core::time_format::{self, Clock};
+
+/// A finished block whose lines arrived at the given offsets.
+fn stamped_block(offsets: &[Option<u64>]) -> TerminalBlock {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("flaky-service", ".", BlockSource::Shell);
+    let lines = offsets
+        .iter()
+        .enumerate()
+        .map(|(i, ms)| {
+            let line = BlockLine::normal(format!("line {}", i));
+            match ms {
+                Some(ms) => line.at(*ms),
+                None => line,
+            }
+        })
+        .collect();
+    mgr.get_mut(id).unwrap().output = lines;
+    mgr.finish(id, Some(0), Duration::from_secs(3));
+    mgr.get(id).unwrap().clone()
+}
+
+#[test]
+fn test_recorded_block_keeps_line_stamps() {
+    let mut finished = TerminalBlockV2::new_now("./flaky-service", 1_700_000_000);
+    finished.push_output("starting\nlistening\n", 4);
+    finished.push_output("timeout\n", 1_504);
+    finished.exit_code = Some(1);
+    finished.duration_ms = Some(1_600);
+
+    let mut mgr = BlockManager::default();
+    let id = mgr.record(&finished);
+    let block = mgr.get(id).unwrap();
+    let offsets: Vec<_> = block.output.iter().map(|l| l.offset_ms).collect();
+    assert_eq!(offsets, vec![Some(4), Some(4), Some(1_504)]);
+    let gutter = timestamp_gutter(block, TimestampMode::Relative, Clock::H24);
+    assert_eq!(gutter, vec!["  +0.004s", "         ", "  +1.500s"]);
+
+    // Blocks recorded before lines were stamped come in unstamped.
+    let mut old = TerminalBlockV2::new_now("ls", 1_700_000_000);
+    old.output = "a\nb\n".to_string();
+    let id = mgr.record(&old);
+    assert!(mgr.get(id).unwrap().output.iter().all(|l| l.offset_ms.is_none()));
+}
+
+#[test]
+fn test_append_stamps_lines_together() {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("tail", ".", BlockSource::Shell);
+    mgr.append(id, vec![BlockLine::normal("a"), BlockLine::normal("b"), BlockLine::normal("c").at(5)]);
+    let block = mgr.get(id).unwrap();
+    assert!(block.output[0].offset_ms.is_some());
+    assert_eq!(block.output[0].offset_ms, block.output[1].offset_ms);
+    assert_eq!(block.output[2].offset_ms, Some(5), "existing stamps are kept");
+}
+
+#[test]
+fn test_unstamped_lines_deserialize() {
+    let old = r#"{"text":"Compiling...","kind":"Success"}"#;
+    let line: BlockLine = serde_json::from_str(old).unwrap();
+    assert_eq!(line.offset_ms, None);
+    assert!(!serde_json::to_string(&line).unwrap().contains("offset_ms"));
+
+    let stamped = serde_json::to_string(&BlockLine::normal("x").at(1500)).unwrap();
+    let back: BlockLine = serde_json::from_str(&stamped).unwrap();
+    assert_eq!(back.offset_ms, Some(1500));
+}
+
+#[test]
+fn test_block_keeps_derived_from() {
+    let mut mgr = BlockManager::default();
+    let origin = mgr.begin("cargo test", ".", BlockSource::Shell);
+    let edited = mgr.begin("cargo test -- --nocapture", ".", BlockSource::Shell);
+    mgr.set_derived_from(edited, origin);
+    mgr.set_derived_from(999, origin);
+
+    let json = serde_json::to_string(mgr.get(edited).unwrap()).unwrap();
+    let back: TerminalBlock = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.derived_from, Some(origin));
+
+    // Blocks saved before the field read back unlinked, and unlinked ones
+    // don't write it.
+    let plain = serde_json::to_string(mgr.get(origin).unwrap()).unwrap();
+    assert!(!plain.contains("derived_from"));
+    assert_eq!(serde_json::from_str::<TerminalBlock>(&plain).unwrap().derived_from, None);
+}
+
+#[test]
+fn test_relative_gutter_blanks_repeats() {
+    let block = stamped_block(&[Some(12), Some(12), Some(1512), None, Some(2000)]);
+    let gutter = timestamp_gutter(&block, TimestampMode::Relative, Clock::H24);
+    assert_eq!(gutter, vec!["  +0.012s", "         ", "  +1.500s", "         ", "  +0.488s"]);
+}
+
+#[test]
+fn test_absolute_gutter() {
+    let block = stamped_block(&[Some(0), Some(250), Some(250)]);
+    let gutter = timestamp_gutter(&block, TimestampMode::Absolute, Clock::H24);
+    let first = block.line_time(&block.output[0]).unwrap();
+    assert_eq!(gutter[0], first.format("%H:%M:%S%.3f").to_string());
+    assert_eq!(gutter[1].len(), 12);
+    assert_eq!(gutter[2], " ".repeat(12));
+    assert!(timestamp_gutter(&block, TimestampMode::Off, Clock::H24).is_empty());
+}
+
+#[test]
+fn test_absolute_gutter_on_a_12_hour_clock() {
+    let block = stamped_block(&[Some(0), Some(250), Some(250)]);
+    let gutter = timestamp_gutter(&block, TimestampMode::Absolute, Clock::H12);
+    let first = block.line_time(&block.output[0]).unwrap();
+    assert_eq!(gutter[0].trim_start(), first.format("%-I:%M:%S%.3f %p").to_string());
+    assert!(gutter.iter().all(|stamp| stamp.len() == 15), "{:?}", gutter);
+}
+
+#[test]
+fn test_timestamp_mode_parse() {
+    assert_eq!(TimestampMode::parse("on"), Some(TimestampMode::Absolute));
+    assert_eq!(TimestampMode::parse("Relative"), Some(TimestampMode::Relative));
+    assert_eq!(TimestampMode::parse("off"), Some(TimestampMode::Off));
+    assert_eq!(TimestampMode::parse("sometimes"), None);
+}
+
+#[test]
+fn test_copy_and_export_exclude_timestamps_by_default() {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("ping", ".", BlockSource::Shell);
+    mgr.append(id, vec![BlockLine::normal("pong").at(40)]);
+    mgr.finish(id, Some(0), Duration::from_millis(50));
+
+    assert_eq!(mgr.copy_block(id).unwrap(), "$ ping\npong\n[exit 0]");
+    assert!(mgr.export_all().contains("\npong\n"));
+
+    let with = mgr.export(true);
+    let block = mgr.get(id).unwrap();
+    let stamp = time_format::iso8601_ms(block.line_time(&block.output[0]).unwrap().timestamp_millis());
+    assert!(with.contains(&format!("[{}] pong\n", stamp)), "{}", with);
+}
@@ -3,7 +3,7 @@
 //! identified and routed.
 
 /// Verify the exhaustive list of recognized built-in commands.
-/// Each of these should be handled in builtins.rs dispatch().
+/// Each of these should be handled in the core command registry (commands/).
 
 #[test]
 fn builtin_clear_recognized() {
@@ -82,7 +82,7 @@ fn builtin_unknown_command_handled() {
     let unknown = "!nonexistent";
     assert!(unknown.starts_with('!'));
     // The dispatch function returns DirectOutput with error text
-    // (verified by code review of builtins.rs)
+    // (verified by code review of commands/session.rs)
 }
 
 #[test]
@@ -90,7 +90,7 @@ fn clear_sends_ctrl_c_to_pty() {
     // CRITICAL BUGFIX: !clear must send \x03 to the PTY to break pagers.
     // Before the fix, !clear only cleared the UI buffer.
     //
-    // builtins.rs:
+    // commands/session.rs:
     //   "!clear" | "!cls" => {
     //     pty.write_raw("\x03")?;    // Break pager
     //     pty.write_raw("\r\n")?;    // Flush
@@ -0,0 +1,154 @@
+// positronic-bridge/tests/clipboard_history_tests.rs
+//
+// Tests for the clipboard history ring, `!paste` parsing and the picker.
+
+use std::path::PathBuf;
+
+use positronic_bridge::clipboard_history::{
+    as_input_line, ClipboardHistory, ClipboardPicker, ClipboardSettings, PasteCommand, PasteError, ENTRY_CAP,
+};
+
+fn spill_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("positronic_clip_{}_{}", name, std::process::id()))
+}
+
+fn history(name: &str, size: usize) -> ClipboardHistory {
+    ClipboardHistory::new(ClipboardSettings { size, ..ClipboardSettings::default() }, spill_dir(name))
+}
+
+#[test]
+fn newest_entry_is_number_one_and_the_ring_is_bounded() {
+    let mut h = history("ring", 3);
+    for text in ["one", "two", "three", "four"] {
+        h.push(text);
+    }
+    assert_eq!(h.len(), 3);
+    assert_eq!(h.get(1).unwrap(), "four");
+    assert_eq!(h.get(3).unwrap(), "two");
+    assert_eq!(h.get(4), Err(PasteError::NoEntry(4)));
+
+    // Copying the same text twice in a row keeps one entry.
+    h.push("four");
+    assert_eq!(h.len(), 3);
+
+    // Shrinking the setting drops the oldest.
+    h.configure(ClipboardSettings { size: 1, ..h.settings() });
+    assert_eq!(h.len(), 1);
+    assert_eq!(h.get(1).unwrap(), "four");
+
+    h.clear();
+    assert_eq!(h.get(1), Err(PasteError::Empty));
+}
+
+#[test]
+fn size_zero_turns_history_off() {
+    let mut h = history("off", 0);
+    h.push("anything");
+    assert!(h.is_empty());
+    assert!(ClipboardPicker::new(&h).is_none());
+}
+
+#[test]
+fn long_entries_spill_to_disk_and_are_cleaned_up() {
+    let dir = spill_dir("spill");
+    let mut h = history("spill", 2);
+    let long = "é".repeat(ENTRY_CAP); // 2 bytes a char, so the cut lands mid-char
+    h.push(&long);
+    assert_eq!(h.get(1).unwrap(), long);
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+    let preview = h.entries().next().unwrap().preview();
+    assert!(preview.contains("8.0 KB"), "{}", preview);
+
+    // Evicted entries take their files with them.
+    h.push("a");
+    h.push("b");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    drop(h);
+    assert!(!dir.exists());
+}
+
+#[test]
+fn secrets_are_redacted_unless_kept() {
+    let key = "export OPENAI_KEY=sk-abcdefghijklmnopqrstuvwxyz012345";
+    let mut h = history("secrets", 5);
+    h.push(key);
+    h.push("ssh admin@example.com -i id_rsa && ping 10.0.0.1");
+    assert_eq!(h.get(2), Err(PasteError::Redacted(2)));
+    let lines = h.lines();
+    assert!(lines.iter().any(|l| l.contains("1. [redacted — 2 items hidden]")), "{:?}", lines);
+    assert!(lines.iter().any(|l| l.contains("2. [redacted — 1 item hidden]")), "{:?}", lines);
+    assert!(!lines.join("\n").contains("sk-abc"));
+
+    let mut h = history("keep", 5);
+    h.configure(ClipboardSettings { keep_secrets: true, ..h.settings() });
+    h.push(key);
+    assert_eq!(h.get(1).unwrap(), key);
+}
+
+#[test]
+fn list_shows_numbered_previews() {
+    let mut h = history("list", 20);
+    h.push("cargo build --release");
+    h.push("line one\nline two\nline three");
+    h.push(&"x".repeat(100));
+    let lines = h.lines();
+    assert_eq!(lines[0], "📋 Clipboard history (3 of 20):");
+    assert_eq!(lines[1], format!("   1. {}…", "x".repeat(60)));
+    assert_eq!(lines[2], "   2. line one…  (3 lines)");
+    assert_eq!(lines[3], "   3. cargo build --release");
+
+    assert!(history("empty", 20).lines()[0].contains("empty"));
+}
+
+#[test]
+fn picker_maps_digits_and_clicks_to_entries() {
+    let mut h = history("picker", 20);
+    for i in 1..=12 {
+        h.push(&format!("entry {}", i));
+    }
+    let mut picker = ClipboardPicker::new(&h).unwrap();
+    assert_eq!(picker.previews().len(), 9);
+    assert_eq!(picker.previews()[0], "entry 12");
+    assert_eq!(picker.key_entry("1"), Some(1));
+    assert_eq!(picker.key_entry("9"), Some(9));
+    assert_eq!(picker.key_entry("0"), None);
+    assert_eq!(picker.key_entry("a"), None);
+
+    picker.set_rows(vec![[0.0, 0.0, 100.0, 20.0], [0.0, 20.0, 100.0, 20.0]]);
+    assert_eq!(picker.hit(50.0, 25.0), Some(2));
+    assert_eq!(picker.hit(50.0, 45.0), None);
+}
+
+#[test]
+fn persisted_history_round_trips_without_redacted_entries() {
+    let file = std::env::temp_dir().join(format!("positronic_clip_persist_{}.json", std::process::id()));
+    let mut h = history("persist_a", 20);
+    h.push("first");
+    h.push("token sk-abcdefghijklmnopqrstuvwxyz012345");
+    h.push("second\nwith two lines");
+    h.save(&file).unwrap();
+
+    let mut restored = history("persist_b", 20);
+    assert_eq!(restored.load(&file).unwrap(), 2);
+    assert_eq!(restored.get(1).unwrap(), "second\nwith two lines");
+    assert_eq!(restored.get(2).unwrap(), "first");
+    std::fs::remove_file(&file).unwrap();
+
+    assert_eq!(restored.load(&file).unwrap(), 0, "a missing file loads nothing");
+}
+
+#[test]
+fn parse_paste_commands() {
+    assert_eq!(PasteCommand::parse("!paste"), Ok(PasteCommand::List));
+    assert_eq!(PasteCommand::parse("!paste list"), Ok(PasteCommand::List));
+    assert_eq!(PasteCommand::parse("!paste 3"), Ok(PasteCommand::Insert(3)));
+    assert_eq!(PasteCommand::parse("!paste clear"), Ok(PasteCommand::Clear));
+    assert!(PasteCommand::parse("!paste 0").is_err());
+    assert!(PasteCommand::parse("!paste 1 2").is_err());
+}
+
+#[test]
+fn pasted_text_never_submits_itself() {
+    assert_eq!(as_input_line("git status\n"), "git status");
+    assert_eq!(as_input_line("a\r\nb\nc"), "a b c");
+}
@@ -0,0 +1,163 @@
+// positronic-bridge/tests/compare_tests.rs
+//
+// Tests for `!compare`: operand parsing, loading both outputs from the
+// vault, and the view's synchronized scrolling, region jumps, focus and
+// copy.
+
+use positronic_bridge::compare::{
+    self, CompareCommand, CompareOperand, CompareOutcome, CompareView, Side, COMPARE_USAGE, HATCH,
+};
+use positronic_bridge::pager::PagerKey;
+use positronic_core::diff::RowOp;
+use positronic_core::vault::Vault;
+
+/// Twenty lines, with line 5 changed and two lines added after line 15.
+fn outputs() -> (String, String) {
+    let old: Vec<String> = (1..=20).map(|i| format!("line {}", i)).collect();
+    let mut new = old.clone();
+    new[4] = "line five".to_string();
+    new.insert(15, "extra a".to_string());
+    new.insert(16, "extra b".to_string());
+    (old.join("\n"), new.join("\n"))
+}
+
+fn view(page_height: usize) -> CompareView {
+    let (old, new) = outputs();
+    CompareView::new(("#1 build".to_string(), &old), ("#2 build".to_string(), &new), page_height)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Parsing
+// ════════════════════════════════════════════════════════════════════
+
+#[test]
+fn operands_are_ids_tags_last_or_prev() {
+    assert_eq!(CompareOperand::parse("12"), CompareOperand::Id(12));
+    assert_eq!(CompareOperand::parse("#12"), CompareOperand::Id(12));
+    assert_eq!(CompareOperand::parse("last"), CompareOperand::Last);
+    assert_eq!(CompareOperand::parse("prev"), CompareOperand::Prev);
+    assert_eq!(CompareOperand::parse("baseline"), CompareOperand::Tag("baseline".to_string()));
+}
+
+#[test]
+fn bare_compare_is_prev_against_last() {
+    let command = CompareCommand::parse("").unwrap();
+    assert_eq!(command, CompareCommand { left: CompareOperand::Prev, right: CompareOperand::Last });
+    let command = CompareCommand::parse("#3 good").unwrap();
+    assert_eq!(command.left, CompareOperand::Id(3));
+    assert_eq!(command.right, CompareOperand::Tag("good".to_string()));
+    assert_eq!(CompareCommand::parse("12"), Err(COMPARE_USAGE.to_string()));
+    assert_eq!(CompareCommand::parse("1 2 3"), Err(COMPARE_USAGE.to_string()));
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Loading
+// ════════════════════════════════════════════════════════════════════
+
+#[test]
+fn load_reads_whole_outputs_from_the_vault() {
+    let vault = Vault::open(":memory:").unwrap();
+    let (old, new) = outputs();
+    vault.log_command("make", Some(&old), Some(0), "/p", None).unwrap();
+    vault.log_command("ls", None, Some(0), "/p", None).unwrap();
+    vault.log_command("make", Some(&new), Some(0), "/p", None).unwrap();
+
+    let mut progress = Vec::new();
+    let view = compare::load(&vault, &CompareCommand::parse("").unwrap(), 10, |line| progress.push(line)).unwrap();
+    assert_eq!(view.label(Side::Left), "#1 make");
+    assert_eq!(view.label(Side::Right), "#3 make");
+    assert_eq!(view.alignment().rows.len(), 22);
+    assert!(progress.is_empty(), "small outputs load quietly: {:?}", progress);
+
+    let err = |args: &str| compare::load(&vault, &CompareCommand::parse(args).unwrap(), 10, |_| {}).unwrap_err();
+    assert_eq!(err("2 last"), "⇆ #2 has no stored output.");
+    assert_eq!(err("9 last"), "❌ No history entry #9");
+    assert_eq!(err("nope last"), "❌ No tag named 'nope'");
+}
+
+#[test]
+fn load_reports_progress_for_big_outputs() {
+    let vault = Vault::open(":memory:").unwrap();
+    let big: String = (0..compare::PROGRESS_BYTES / 8).map(|i| format!("{:07}\n", i)).collect();
+    vault.log_command("dump a", Some(&big), Some(0), "/p", None).unwrap();
+    vault.log_command("dump b", Some(&big), Some(0), "/p", None).unwrap();
+
+    let mut progress = Vec::new();
+    compare::load(&vault, &CompareCommand::parse("1 2").unwrap(), 10, |line| progress.push(line)).unwrap();
+    assert_eq!(progress.len(), 1);
+    assert!(progress[0].contains("Aligning"), "{:?}", progress);
+}
+
+// ════════════════════════════════════════════════════════════════════
+// The view
+// ════════════════════════════════════════════════════════════════════
+
+#[test]
+fn both_sides_scroll_together_with_gaps_level() {
+    let mut view = view(5);
+    view.handle(PagerKey::Char('G'));
+    assert_eq!(view.top(), 17);
+    let rows = view.visible();
+    assert_eq!(rows.len(), 5);
+    assert_eq!(view.line(Side::Left, &rows[0]), Some("line 16"));
+    assert_eq!(view.line(Side::Right, &rows[0]), Some("line 16"));
+    assert_eq!(view.line(Side::Right, &rows[4]), Some("line 20"));
+
+    view.handle(PagerKey::PageUp);
+    let rows = view.visible();
+    assert_eq!(rows[3].op, RowOp::Added);
+    assert_eq!(view.line(Side::Left, &rows[3]), None);
+    assert_eq!(view.line(Side::Right, &rows[3]), Some("extra a"));
+
+    view.handle(PagerKey::Char('g'));
+    view.handle(PagerKey::Up);
+    assert_eq!(view.top(), 0);
+}
+
+#[test]
+fn n_and_p_jump_between_regions() {
+    let mut view = view(5);
+    view.handle(PagerKey::Char('n'));
+    assert_eq!(view.top(), 4);
+    assert_eq!(view.visible()[0].op, RowOp::Changed);
+    assert!(view.footer().contains("change 1/2"), "{}", view.footer());
+
+    view.handle(PagerKey::Char('n'));
+    assert_eq!(view.top(), 15);
+    assert!(view.footer().contains("change 2/2"));
+    view.handle(PagerKey::Char('n'));
+    assert_eq!(view.top(), 15);
+
+    view.handle(PagerKey::Char('p'));
+    assert_eq!(view.top(), 4);
+}
+
+#[test]
+fn tab_moves_focus_and_y_copies_the_focused_side() {
+    let mut view = view(3);
+    view.handle(PagerKey::Char('n'));
+    assert_eq!(view.handle(PagerKey::Char('y')), CompareOutcome::Copy("line 5\nline 6\nline 7".to_string()));
+
+    assert_eq!(view.handle(PagerKey::Tab), CompareOutcome::Continue);
+    assert_eq!(view.focus(), Side::Right);
+    assert_eq!(view.handle(PagerKey::Char('y')), CompareOutcome::Copy("line five\nline 6\nline 7".to_string()));
+
+    view.handle(PagerKey::Tab);
+    assert_eq!(view.focus(), Side::Left);
+    assert_eq!(view.handle(PagerKey::Char('q')), CompareOutcome::Closed);
+    assert_eq!(view.handle(PagerKey::Escape), CompareOutcome::Closed);
+}
+
+#[test]
+fn identical_outputs_say_so() {
+    let view = CompareView::new(("a".to_string(), "x\ny"), ("b".to_string(), "x\ny"), 10);
+    assert!(view.alignment().regions.is_empty());
+    assert!(view.footer().contains("identical"), "{}", view.footer());
+}
+
+#[test]
+fn lines_are_clipped_to_their_column() {
+    assert_eq!(compare::clip("short", 10), "short");
+    assert_eq!(compare::clip("a longer line", 6), "a lon…");
+    assert_eq!(compare::hatching(3), HATCH.to_string().repeat(3));
+}
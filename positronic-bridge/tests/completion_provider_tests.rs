@@ -0,0 +1,246 @@
+// positronic-bridge/tests/completion_provider_tests.rs
+//
+// Tests for the PATH executable index, git-context completion and tag
+// names, using fake executables and a fixture .git directory in temp
+// directories.
+
+use std::path::{Path, PathBuf};
+
+use positronic_bridge::completer::{complete_all, complete_with_providers, Providers};
+use positronic_bridge::git_complete::{find_repo, local_branches, parse_alias_output, GitCompleter};
+use positronic_bridge::path_index::PathIndex;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("positronic_completion_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn dir(&self, rel: &str) -> PathBuf {
+        let dir = self.0.join(rel);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write(path: &Path, text: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, text).unwrap();
+}
+
+#[cfg(unix)]
+fn executable(path: &Path, exec: bool) {
+    use std::os::unix::fs::PermissionsExt;
+    if !path.exists() {
+        write(path, "#!/bin/sh\n");
+    }
+    let mode = if exec { 0o755 } else { 0o644 };
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+}
+
+fn completions(input: &str, cwd: &Path, providers: Providers<'_>) -> Vec<String> {
+    complete_with_providers(input, &[], &cwd.to_string_lossy(), providers)
+        .map(|state| state.completions)
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// PATH index
+// ============================================================================
+
+#[cfg(unix)]
+#[test]
+fn path_index_completes_executables_across_path() {
+    let tmp = TempDir::new("path");
+    let (bin, local) = (tmp.dir("bin"), tmp.dir("local"));
+    executable(&bin.join("docker"), true);
+    executable(&bin.join("dockerd.conf"), false);
+    executable(&local.join("docker"), true);
+    executable(&local.join("doctl"), true);
+    tmp.dir("local/docs");
+    let path = std::env::join_paths([&bin, &local]).unwrap();
+
+    let index = PathIndex::new(path, None);
+    assert!(index.matching("doc").is_empty(), "nothing before the first scan");
+    assert_eq!(index.refresh(false), 2);
+    assert_eq!(index.matching("doc"), vec!["docker", "doctl"]);
+    assert_eq!(index.matching("dockerd"), Vec::<String>::new());
+
+    let cwd = tmp.dir("work");
+    let providers = Providers { executables: Some(&index), git: None, tags: &[], ignore_case: false };
+    assert_eq!(completions("doc", &cwd, providers), vec!["docker", "doctl"]);
+    // An explicit path still completes from the filesystem.
+    assert_eq!(completions("./", &cwd, providers), Vec::<String>::new());
+}
+
+#[cfg(unix)]
+#[test]
+fn ignore_case_matches_executables_and_history_in_any_case() {
+    let tmp = TempDir::new("case");
+    let bin = tmp.dir("bin");
+    executable(&bin.join("Xcode-select"), true);
+    let index = PathIndex::new(bin.clone().into_os_string(), None);
+    index.refresh(false);
+    let cwd = tmp.dir("work");
+
+    let exact = Providers { executables: Some(&index), git: None, tags: &[], ignore_case: false };
+    assert!(completions("xco", &cwd, exact).is_empty());
+    let any_case = Providers { ignore_case: true, ..exact };
+    assert_eq!(completions("xco", &cwd, any_case), vec!["Xcode-select"]);
+
+    let history = vec!["Make release".to_string()];
+    let state = complete_all("make r", &[], &cwd.to_string_lossy(), &history, any_case).unwrap();
+    assert_eq!(state.completions, history);
+    assert!(complete_all("make r", &[], &cwd.to_string_lossy(), &history, exact).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn path_index_rereads_only_changed_directories() {
+    let tmp = TempDir::new("mtime");
+    let bin = tmp.dir("bin");
+    executable(&bin.join("docker"), true);
+    let index = PathIndex::new(bin.clone().into_os_string(), None);
+    index.refresh(false);
+
+    // Installing a binary touches the directory's mtime.
+    executable(&bin.join("doctl"), true);
+    index.refresh(false);
+    assert_eq!(index.matching("doc"), vec!["docker", "doctl"]);
+
+    // A chmod doesn't, so the cached listing stands until a rehash.
+    executable(&bin.join("doctl"), false);
+    index.refresh(false);
+    assert_eq!(index.matching("doc"), vec!["docker", "doctl"]);
+    assert_eq!(index.rehash(), 1);
+    assert_eq!(index.matching("doc"), vec!["docker"]);
+}
+
+#[test]
+fn path_index_uses_pathext_on_windows() {
+    let tmp = TempDir::new("pathext");
+    let bin = tmp.dir("bin");
+    write(&bin.join("Docker.EXE"), "");
+    write(&bin.join("tool.cmd"), "");
+    write(&bin.join("readme.txt"), "");
+    write(&bin.join(".exe"), "");
+
+    let index = PathIndex::new(bin.into_os_string(), Some(".EXE;.Cmd"));
+    assert_eq!(index.refresh(false), 2);
+    assert_eq!(index.matching("doc"), vec!["Docker"]);
+    assert_eq!(index.matching("TO"), vec!["tool"]);
+    assert!(index.matching("read").is_empty());
+}
+
+// ============================================================================
+// git context
+// ============================================================================
+
+const SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
+/// `repo/.git` with loose `main` and `feature/login`, and a packed `cherry`.
+fn fixture_repo(tmp: &TempDir) -> PathBuf {
+    let repo = tmp.dir("repo");
+    let git = repo.join(".git");
+    write(&git.join("HEAD"), "ref: refs/heads/main\n");
+    write(&git.join("refs/heads/main"), SHA);
+    write(&git.join("refs/heads/feature/login"), SHA);
+    write(
+        &git.join("packed-refs"),
+        &format!(
+            "# pack-refs with: peeled fully-peeled sorted\n\
+             {SHA} refs/heads/cherry\n\
+             {SHA} refs/remotes/origin/main\n\
+             {SHA} refs/tags/v1.0\n\
+             ^{SHA}\n"
+        ),
+    );
+    tmp.dir("repo/src");
+    repo
+}
+
+#[test]
+fn git_branches_come_from_loose_and_packed_refs() {
+    let tmp = TempDir::new("branches");
+    let repo = fixture_repo(&tmp);
+
+    let found = find_repo(&repo.join("src")).unwrap();
+    assert_eq!(found.root, repo);
+    assert_eq!(local_branches(&found.git_dir), vec!["cherry", "feature/login", "main"]);
+}
+
+#[test]
+fn git_completes_subcommands_aliases_and_branches() {
+    let tmp = TempDir::new("git");
+    let repo = fixture_repo(&tmp);
+    let git = GitCompleter::new();
+    git.set_aliases(&repo, vec!["co".into(), "cp".into()]);
+    let providers = Providers { executables: None, git: Some(&git), tags: &[], ignore_case: false };
+    let cwd = repo.join("src");
+
+    assert_eq!(
+        completions("git ch", &cwd, providers),
+        vec!["git checkout", "git cherry", "git cherry-pick"]
+    );
+    assert_eq!(
+        completions("git co", &cwd, providers),
+        vec!["git co", "git commit", "git config"]
+    );
+    assert_eq!(
+        completions("git checkout ", &cwd, providers),
+        vec!["git checkout cherry", "git checkout feature/login", "git checkout main"]
+    );
+    assert_eq!(completions("git switch fe", &cwd, providers), vec!["git switch feature/login"]);
+
+    // Outside a repository there are no aliases or branches.
+    let elsewhere = tmp.dir("elsewhere");
+    assert_eq!(completions("git c", &elsewhere, providers).len(), 7);
+    assert!(completions("git checkout ma", &elsewhere, providers).is_empty());
+}
+
+#[test]
+fn git_worktree_reads_branches_from_the_common_dir() {
+    let tmp = TempDir::new("worktree");
+    let repo = fixture_repo(&tmp);
+    let wt_git = repo.join(".git/worktrees/wt");
+    write(&wt_git.join("commondir"), "../..\n");
+    write(&wt_git.join("HEAD"), "ref: refs/heads/main\n");
+    let wt = tmp.dir("wt");
+    write(&wt.join(".git"), &format!("gitdir: {}\n", wt_git.display()));
+
+    let found = find_repo(&wt).unwrap();
+    assert_eq!(found.root, wt);
+    assert_eq!(local_branches(&found.git_dir), vec!["cherry", "feature/login", "main"]);
+}
+
+#[test]
+fn git_alias_output_parses_names() {
+    let output = "alias.co checkout\nalias.lg log --graph --oneline\nuser.name someone\nalias. broken\n";
+    assert_eq!(parse_alias_output(output), vec!["co", "lg"]);
+}
+
+#[test]
+fn tag_names_complete_where_a_tag_is_expected() {
+    let tags = vec!["green".to_string(), "grim".to_string(), "red".to_string()];
+    let providers = Providers { executables: None, git: None, tags: &tags, ignore_case: false };
+    let cwd = std::env::temp_dir();
+
+    assert_eq!(completions("!recall g", &cwd, providers), vec!["!recall green", "!recall grim"]);
+    assert_eq!(completions("!recall ", &cwd, providers).len(), 3);
+    assert_eq!(completions("!tag rm r", &cwd, providers), vec!["!tag rm red"]);
+    assert_eq!(completions("!diff green r", &cwd, providers), vec!["!diff green red"]);
+    // Flags and sub-commands still complete as before.
+    assert_eq!(completions("!diff --w", &cwd, providers), vec!["!diff --watch"]);
+    assert_eq!(completions("!tag l", &cwd, providers), vec!["!tag list"]);
+    assert!(completions("!recall green", &cwd, providers).is_empty());
+}
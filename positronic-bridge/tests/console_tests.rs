@@ -0,0 +1,254 @@
+// positronic-bridge/tests/console_tests.rs
+//
+// Tests for `!io console`: command parsing, the connecting/open/closed
+// transitions driven by hardware events, and how keys are routed between
+// the device and the console itself.
+
+use positronic_bridge::console::{
+    key_bytes, Console, ConsoleCommand, ConsoleExit, ConsoleKey, ConsolePhase, LineEnding, CONSOLE_USAGE,
+};
+use positronic_bridge::keymap::{Action, Chord, KeyName, Keymap};
+use positronic_io::HardwareEvent;
+
+fn console(connected: bool) -> Console {
+    Console::new(&ConsoleCommand::parse("!io console COM3").unwrap(), connected, 1_700_000_000)
+}
+
+fn output(port: &str, text: &str) -> HardwareEvent {
+    HardwareEvent::SerialOutput { port: port.to_string(), text: text.to_string() }
+}
+
+fn chord(text: &str) -> Option<Chord> {
+    Some(Chord::parse(text).unwrap())
+}
+
+fn typed(console: &mut Console, text: &str) -> ConsoleKey {
+    let c = text.chars().next().unwrap();
+    console.key(Some(Chord::from_key(KeyName::Char(c), false, false, false)), Some(text), &Keymap::default(), 10)
+}
+
+fn press(console: &mut Console, key: &str) -> ConsoleKey {
+    console.key(chord(key), None, &Keymap::default(), 10)
+}
+
+// ============================================================================
+// Command
+// ============================================================================
+
+#[test]
+fn console_command_parses_port_baud_and_flags() {
+    assert_eq!(
+        ConsoleCommand::parse("!io console /dev/ttyUSB0 9600 --eol crlf --echo"),
+        Ok(ConsoleCommand {
+            port: "/dev/ttyUSB0".to_string(),
+            baud: Some(9600),
+            eol: Some(LineEnding::CrLf),
+            echo: true,
+            name: None,
+        })
+    );
+    let defaults = ConsoleCommand::parse("!io console COM3").unwrap();
+    assert_eq!((defaults.baud, defaults.eol, defaults.echo), (None, None, false));
+    let console = Console::new(&defaults, true, 0);
+    assert_eq!((console.baud, console.eol), (115_200, LineEnding::Cr));
+
+    assert_eq!(ConsoleCommand::parse("!io console"), Err(CONSOLE_USAGE.to_string()));
+    assert_eq!(ConsoleCommand::parse("!io console COM3 --parity odd"), Err(CONSOLE_USAGE.to_string()));
+    assert!(ConsoleCommand::parse("!io console COM3 fast").unwrap_err().contains("baud"));
+    assert!(ConsoleCommand::parse("!io console COM3 --eol cr-lf").unwrap_err().contains("line ending"));
+}
+
+#[test]
+fn console_command_uses_a_named_devices_settings() {
+    use positronic_core::vault::DeviceDefaults;
+
+    let saved = DeviceDefaults { baud: Some(9600), eol: Some("crlf".to_string()), ..DeviceDefaults::default() };
+    let parsed = ConsoleCommand::parse("!io console bench-scope --eol lf").unwrap();
+    assert_eq!(parsed.given(), DeviceDefaults { eol: Some("lf".to_string()), ..DeviceDefaults::default() });
+
+    let command = ConsoleCommand { port: "COM9".to_string(), name: Some("bench-scope".to_string()), ..parsed }
+        .with_defaults(&saved);
+    assert_eq!((command.baud, command.eol), (Some(9600), Some(LineEnding::Lf)), "what's given wins");
+
+    let console = Console::new(&command, true, 0);
+    assert_eq!(console.label(), "bench-scope (COM9)");
+    assert!(console.header(None).starts_with("🔌 bench-scope (COM9) @ 9600"));
+    assert!(console.closed_notice(&ConsoleExit::User).contains("Left the bench-scope (COM9) console"));
+}
+
+// ============================================================================
+// Mode switching
+// ============================================================================
+
+#[test]
+fn console_waits_for_the_port_it_connects() {
+    let mut console = console(false);
+    assert_eq!(console.phase, ConsolePhase::Connecting);
+    assert!(console.header(None).contains("connecting"));
+
+    // Other ports don't open it.
+    assert_eq!(console.event(&HardwareEvent::DeviceConnected("COM4".to_string())), None);
+    assert_eq!(console.phase, ConsolePhase::Connecting);
+
+    assert_eq!(console.event(&HardwareEvent::DeviceConnected("COM3".to_string())), None);
+    assert_eq!(console.phase, ConsolePhase::Open);
+}
+
+#[test]
+fn console_closes_when_the_port_fails_to_open() {
+    let mut console = console(false);
+    assert_eq!(console.event(&HardwareEvent::Error("Scan failed: busy".to_string())), None);
+    let exit = console.event(&HardwareEvent::Error("Failed to open COM3: Access denied".to_string()));
+    assert_eq!(exit, Some(ConsoleExit::Failed("Failed to open COM3: Access denied".to_string())));
+    assert!(console.closed_notice(&exit.unwrap()).starts_with("❌ Failed to open COM3"));
+
+    // Once open, IO errors no longer end the session.
+    let mut open = self::console(true);
+    assert_eq!(open.event(&HardwareEvent::Error("Failed to open COM3: again".to_string())), None);
+}
+
+#[test]
+fn console_drops_back_when_its_device_disconnects() {
+    let mut console = console(true);
+    assert_eq!(console.event(&HardwareEvent::DeviceDisconnected("COM4".to_string())), None);
+    let exit = console.event(&HardwareEvent::DeviceDisconnected("COM3".to_string()));
+    assert_eq!(exit, Some(ConsoleExit::Disconnected));
+    assert!(console.closed_notice(&ConsoleExit::Disconnected).contains("COM3 disconnected"));
+}
+
+#[test]
+fn console_keeps_only_its_port_output_and_counts_bytes() {
+    let mut console = console(true);
+    console.event(&output("COM4", "not mine\n"));
+    console.event(&output("COM3", "boot ok\r\nuart> "));
+    assert_eq!(console.lines(), vec!["boot ok", "uart> "]);
+    assert_eq!(console.rx_bytes, 15);
+
+    // Echoed backspaces and escape sequences are applied, not drawn.
+    console.event(&output("COM3", "lx\u{8} \u{8}s\r\n\u{1b}[32mready\u{1b}[0m\n"));
+    assert_eq!(console.lines(), vec!["boot ok", "uart> ls", "ready"]);
+
+    let record = console.record(1_700_000_060);
+    assert_eq!((record.port.as_str(), record.baud), ("COM3", 115_200));
+    assert_eq!(record.rx_bytes, console.rx_bytes);
+    assert_eq!(record.ended_at - record.started_at, 60);
+    assert!(record.transcript.starts_with("boot ok\r\n"));
+}
+
+#[test]
+fn console_header_shows_counters_and_exit_chord() {
+    let mut console = console(true);
+    console.event(&output("COM3", &"x".repeat(2048)));
+    console.sent(b"help\r");
+    let exit = Keymap::default().chord_for(Action::ConsoleExit);
+    let header = console.header(exit.as_ref());
+    assert!(header.contains("TX 5 B"), "{}", header);
+    assert!(header.contains("RX 2.0 KB"), "{}", header);
+    assert!(header.ends_with("ctrl+] exits"), "{}", header);
+}
+
+// ============================================================================
+// Key routing
+// ============================================================================
+
+#[test]
+fn console_exit_chord_is_configurable() {
+    let mut console = console(true);
+    assert_eq!(press(&mut console, "ctrl+]"), ConsoleKey::Exit);
+
+    let keymap = Keymap::from_overrides([(Action::ConsoleExit, "ctrl+shift+x")]);
+    assert_eq!(console.key(chord("ctrl+shift+x"), None, &keymap, 10), ConsoleKey::Exit);
+    // Unbound from the exit, Ctrl+] is just GS for the device.
+    assert_eq!(console.key(chord("ctrl+]"), None, &keymap, 10), ConsoleKey::Send(vec![0x1d]));
+}
+
+#[test]
+fn console_sends_typing_control_codes_and_line_endings() {
+    let mut console = console(true);
+    assert_eq!(typed(&mut console, "A"), ConsoleKey::Send(b"A".to_vec()));
+    assert_eq!(press(&mut console, "enter"), ConsoleKey::Send(b"\r".to_vec()));
+    // Shell shortcuts belong to the device here.
+    assert_eq!(press(&mut console, "ctrl+c"), ConsoleKey::Send(vec![0x03]));
+    assert_eq!(press(&mut console, "ctrl+d"), ConsoleKey::Send(vec![0x04]));
+    assert_eq!(press(&mut console, "up"), ConsoleKey::Send(b"\x1b[A".to_vec()));
+    assert_eq!(press(&mut console, "tab"), ConsoleKey::Send(b"\t".to_vec()));
+    assert_eq!(press(&mut console, "f5"), ConsoleKey::Ignored);
+
+    assert_eq!(key_bytes(chord("enter"), None, LineEnding::CrLf), Some(b"\r\n".to_vec()));
+    assert_eq!(key_bytes(chord("enter"), None, LineEnding::Lf), Some(b"\n".to_vec()));
+    assert_eq!(key_bytes(chord("alt+x"), Some("x"), LineEnding::Cr), Some(b"\x1bx".to_vec()));
+}
+
+#[test]
+fn console_keeps_copy_paste_and_paging() {
+    let mut console = console(true);
+    for i in 0..30 {
+        console.event(&output("COM3", &format!("line {}\n", i)));
+    }
+    assert_eq!(press(&mut console, "ctrl+shift+c"), ConsoleKey::Copy);
+    assert_eq!(press(&mut console, "ctrl+shift+v"), ConsoleKey::Paste);
+    assert!(console.copy_text().starts_with("line 0\nline 1\n"));
+
+    assert_eq!(press(&mut console, "pageup"), ConsoleKey::Handled);
+    assert_eq!(console.visible(10).lines.last(), Some(&"line 19"));
+    // New output doesn't move a scrolled-back view.
+    console.event(&output("COM3", "line 30\n"));
+    assert_eq!(console.visible(10).lines.last(), Some(&"line 19"));
+    assert_eq!(press(&mut console, "pagedown"), ConsoleKey::Handled);
+    assert_eq!(console.visible(10).lines.last(), Some(&"line 29"));
+
+    // Typing returns to the newest output.
+    typed(&mut console, "x");
+    assert_eq!(console.visible(10).lines.last(), Some(&"line 30"));
+}
+
+#[test]
+fn console_search_takes_keys_until_escape() {
+    let mut console = console(true);
+    for text in ["temp=20\n", "ok\n", "temp=21\n", "ok\n"] {
+        console.event(&output("COM3", text));
+    }
+    assert_eq!(press(&mut console, "ctrl+r"), ConsoleKey::Handled);
+    assert!(console.search.is_some());
+
+    // Typing edits the query instead of reaching the device.
+    for c in ["t", "e", "m", "p"] {
+        assert_eq!(typed(&mut console, c), ConsoleKey::Handled);
+    }
+    let search = console.search.clone().unwrap();
+    assert_eq!((search.query.as_str(), search.hits.clone(), search.current), ("temp", vec![0, 2], Some(1)));
+    assert_eq!(console.visible(2).highlight, Some(1));
+    assert_eq!(console.search_line().unwrap(), "🔎 temp  (2/2)  [Enter older · Esc close]");
+
+    // Enter steps to older matches and wraps.
+    assert_eq!(press(&mut console, "enter"), ConsoleKey::Handled);
+    assert_eq!(console.search.as_ref().unwrap().current, Some(0));
+    assert_eq!(console.visible(2).lines, vec!["temp=20", "ok"]);
+    press(&mut console, "enter");
+    assert_eq!(console.search.as_ref().unwrap().current, Some(1));
+
+    // The exit chord still leaves from search; ctrl keys are swallowed.
+    assert_eq!(press(&mut console, "ctrl+c"), ConsoleKey::Ignored);
+    assert_eq!(press(&mut console, "escape"), ConsoleKey::Handled);
+    assert!(console.search.is_none());
+    assert_eq!(press(&mut console, "escape"), ConsoleKey::Send(vec![0x1b]));
+
+    press(&mut console, "ctrl+r");
+    assert_eq!(press(&mut console, "ctrl+]"), ConsoleKey::Exit);
+}
+
+#[test]
+fn console_local_echo_shows_what_is_sent() {
+    let mut quiet = console(true);
+    quiet.sent(b"help\r");
+    assert!(quiet.lines().is_empty());
+    assert_eq!(quiet.tx_bytes, 5);
+
+    let command = ConsoleCommand::parse("!io console COM3 --eol crlf --echo").unwrap();
+    let mut echoing = Console::new(&command, true, 0);
+    echoing.sent(b"help\r\n");
+    echoing.event(&output("COM3", "commands: ls\n"));
+    assert_eq!(echoing.lines(), vec!["help", "commands: ls"]);
+    // Echo is not the device's, so it stays out of the transcript.
+    assert_eq!(echoing.record(0).transcript, "commands: ls\n");
+}
@@ -2,7 +2,7 @@
 //! Groups 7-8: CWD extraction from snapshots and cd command tracking.
 
 use positronic_core::state_machine::{MyColor, Snapshot};
-use positronic_bridge::cwd::{track_cd_command, update_cwd_from_snapshot, resolve_tilde};
+use positronic_bridge::cwd::{cwd_is_uncertain, track_cd_command, update_cwd_from_snapshot, resolve_tilde};
 
 /// Helper: build a Snapshot with the given lines of text at the bottom.
 fn snapshot_with_lines(lines: &[&str], cols: usize, rows: usize) -> Snapshot {
@@ -160,4 +160,30 @@ fn resolve_tilde_with_path() {
     let result = resolve_tilde("~/projects");
     // Should contain "projects" regardless of home resolution
     assert!(result.contains("projects"));
-}
\ No newline at end of file
+}
+// ════════════════════════════════════════════════════════════════
+// Commands that need a cwd probe
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn uncertain_cd_forms() {
+    assert!(cwd_is_uncertain("cd -"));
+    assert!(cwd_is_uncertain("cd"));
+    assert!(cwd_is_uncertain("cd ~/src"));
+    assert!(cwd_is_uncertain("cd $PROJECT_DIR"));
+    assert!(cwd_is_uncertain("popd"));
+    assert!(cwd_is_uncertain("pushd /tmp"));
+    assert!(cwd_is_uncertain("source env.sh"));
+    assert!(cwd_is_uncertain(". ./activate"));
+    assert!(cwd_is_uncertain("make && cd -"));
+    assert!(cwd_is_uncertain("Pop-Location"));
+}
+
+#[test]
+fn certain_commands_skip_probe() {
+    assert!(!cwd_is_uncertain("cd /tmp"));
+    assert!(!cwd_is_uncertain("cd src/lib"));
+    assert!(!cwd_is_uncertain("ls -la"));
+    assert!(!cwd_is_uncertain("echo cd -"));
+    assert!(!cwd_is_uncertain(""));
+}
@@ -0,0 +1,138 @@
+//! Diagnostics on blocks: extraction when a block finishes, the summary
+//! under it, and the editor command for `!errors open`. Test summaries
+//! are read and shown under blocks the same way.
+
+use std::time::Duration;
+
+use positronic_bridge::block::{BlockLine, BlockManager, BlockSource};
+use positronic_bridge::platform::editor_argv;
+use positronic_bridge::renderer::{block_to_spans, BlockStyle};
+use positronic_core::diagnostics::Severity;
+
+const RUSTC: &[&str] = &[
+    "   Compiling demo v0.1.0",
+    "error[E0308]: mismatched types",
+    "  --> src/main.rs:12:5",
+    "   |",
+    "warning: unused variable: `count`",
+    " --> src/lib.rs:3:9",
+    "error: could not compile `demo` due to 1 previous error",
+];
+
+fn failed_build(mgr: &mut BlockManager) -> u64 {
+    let id = mgr.begin("cargo build", "/home/dev/demo", BlockSource::Shell);
+    mgr.append(id, RUSTC.iter().map(|l| BlockLine::classify(*l)).collect());
+    mgr.finish(id, Some(101), Duration::from_secs(2));
+    id
+}
+
+#[test]
+fn finish_extracts_diagnostics() {
+    let mut mgr = BlockManager::default();
+    let id = failed_build(&mut mgr);
+    let block = mgr.get(id).unwrap();
+    assert_eq!(block.diagnostics.len(), 2);
+    assert_eq!(block.first_error().unwrap().location(), "src/main.rs:12:5");
+    assert_eq!(block.diagnostics[1].severity, Severity::Warning);
+    assert_eq!(mgr.last_failed().unwrap().id, id);
+}
+
+#[test]
+fn running_blocks_have_no_diagnostics_yet() {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("cargo build", ".", BlockSource::Shell);
+    mgr.append(id, RUSTC.iter().map(|l| BlockLine::classify(*l)).collect());
+    assert!(mgr.get(id).unwrap().diagnostics.is_empty());
+}
+
+#[test]
+fn summary_under_block_expands() {
+    let mut mgr = BlockManager::default();
+    let id = failed_build(&mut mgr);
+    let text = |mgr: &BlockManager| -> String {
+        block_to_spans(mgr.get(id).unwrap(), &BlockStyle::default())
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect()
+    };
+
+    let collapsed = text(&mgr);
+    assert!(collapsed.ends_with("⚑ 1 error, 1 warning  (!errors to list)\n"), "{}", collapsed);
+
+    mgr.toggle_diagnostics(id);
+    let expanded = text(&mgr);
+    assert!(expanded.contains("⚑ 1 error, 1 warning\n"));
+    assert!(expanded.contains("   1. src/main.rs:12:5 error[E0308]: mismatched types\n"));
+    assert!(expanded.contains("   2. src/lib.rs:3:9 warning: unused variable: `count`\n"));
+}
+
+#[test]
+fn clean_blocks_have_no_summary() {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("ls", ".", BlockSource::Shell);
+    mgr.append(id, vec![BlockLine::normal("Cargo.toml")]);
+    mgr.finish(id, Some(0), Duration::from_millis(5));
+    let text: String = block_to_spans(mgr.get(id).unwrap(), &BlockStyle::default())
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect();
+    assert!(!text.contains('⚑'));
+}
+
+const CARGO_TEST: &[&str] = &[
+    "running 3 tests",
+    "test a ... ok",
+    "test b ... FAILED",
+    "test c ... FAILED",
+    "",
+    "failures:",
+    "",
+    "---- b stdout ----",
+    "assertion failed: ready",
+    "",
+    "---- c stdout ----",
+    "",
+    "failures:",
+    "    b",
+    "    c",
+    "",
+    "test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s",
+];
+
+#[test]
+fn test_summary_card_under_block_expands_each_failure() {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin("cargo test", "/home/dev/demo", BlockSource::Shell);
+    mgr.append(id, CARGO_TEST.iter().map(|l| BlockLine::classify(*l)).collect());
+    assert!(mgr.get(id).unwrap().tests.is_none(), "not until it finishes");
+    mgr.finish(id, Some(101), Duration::from_secs(1));
+    let report = mgr.get(id).unwrap().tests.clone().unwrap();
+    assert_eq!((report.passed, report.failed), (1, 2));
+
+    let text = |mgr: &BlockManager| -> String {
+        block_to_spans(mgr.get(id).unwrap(), &BlockStyle::default())
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect()
+    };
+    let card = "🧪 cargo test: 2 failed, 1 passed\n  ▸  1. b\n  ▸  2. c\n";
+    assert!(text(&mgr).ends_with(card), "{}", text(&mgr));
+
+    mgr.toggle_test_failure(id, 0);
+    assert!(text(&mgr).ends_with("  ▾  1. b\n        assertion failed: ready\n  ▸  2. c\n"), "{}", text(&mgr));
+    mgr.toggle_collapse(id);
+    assert!(text(&mgr).contains("  ▾  1. b\n"), "the card stays when the output is folded");
+    mgr.toggle_test_failure(id, 0);
+    mgr.toggle_collapse(id);
+    assert!(text(&mgr).ends_with(card));
+}
+
+#[test]
+fn editor_command_template() {
+    assert_eq!(
+        editor_argv("code -g {file}:{line}:{col}", "/src/my app/main.rs", 12, 5),
+        vec!["code", "-g", "/src/my app/main.rs:12:5"]
+    );
+    assert_eq!(editor_argv("vim +{line} {file}", "a.rs", 3, 1), vec!["vim", "+3", "a.rs"]);
+    assert!(editor_argv("   ", "a.rs", 1, 1).is_empty());
+}
@@ -0,0 +1,92 @@
+// positronic-bridge/tests/dir_hint_tests.rs
+//
+// The "frequent here" hint: once per directory per session, never for the
+// starting directory, stale lookups ignored, held back for fullscreen
+// programs and streaming output, and the commands Tab inserts.
+
+use positronic_bridge::dir_hint::{DirectoryHints, Screen};
+
+const QUIET: Screen = Screen { fullscreen: false, streaming: false };
+
+fn commands(names: &[&str]) -> Vec<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+/// Started in the home directory, then moved into `/proj`.
+fn moved_into_proj() -> DirectoryHints {
+    let mut hints = DirectoryHints::default();
+    assert_eq!(hints.entered("/home/me"), None, "the starting directory gets no hint");
+    assert_eq!(hints.entered("/proj").as_deref(), Some("/proj"));
+    hints
+}
+
+#[test]
+fn test_hint_shows_after_lookup() {
+    let mut hints = moved_into_proj();
+    assert_eq!(hints.line(QUIET), None, "nothing until the lookup comes back");
+    hints.arrived("/proj", commands(&["cargo build", "cargo test", "git pull", "make"]));
+    assert_eq!(
+        hints.line(QUIET).as_deref(),
+        Some("↳ frequent here: cargo build · cargo test · git pull (Tab to insert)")
+    );
+    assert_eq!(hints.insertable(QUIET).unwrap(), commands(&["cargo build", "cargo test", "git pull"]));
+    assert_eq!(hints.cached("/proj").unwrap().len(), 4);
+
+    hints.dismiss();
+    assert_eq!(hints.line(QUIET), None);
+    assert_eq!(hints.insertable(QUIET), None);
+}
+
+#[test]
+fn test_once_per_directory_per_session() {
+    let mut hints = moved_into_proj();
+    hints.arrived("/proj", commands(&["a", "b", "c"]));
+    assert_eq!(hints.entered("/proj"), None, "still there");
+    assert!(hints.line(QUIET).is_some());
+
+    assert_eq!(hints.entered("/other").as_deref(), Some("/other"));
+    assert_eq!(hints.line(QUIET), None, "moving on takes the hint down");
+    assert_eq!(hints.entered("/proj"), None, "visited already");
+    assert_eq!(hints.line(QUIET), None);
+    assert_eq!(hints.cached("/proj").unwrap(), commands(&["a", "b", "c"]));
+}
+
+#[test]
+fn test_too_few_commands_or_stale_lookup_shows_nothing() {
+    let mut hints = moved_into_proj();
+    hints.arrived("/proj", commands(&["cargo build", "cargo test"]));
+    assert_eq!(hints.line(QUIET), None);
+
+    let mut hints = moved_into_proj();
+    hints.entered("/elsewhere");
+    hints.arrived("/proj", commands(&["a", "b", "c"]));
+    assert_eq!(hints.line(QUIET), None, "the shell has moved on");
+    assert!(hints.cached("/proj").is_some());
+}
+
+#[test]
+fn test_held_back_while_fullscreen_or_streaming() {
+    let mut hints = moved_into_proj();
+    hints.arrived("/proj", commands(&["a", "b", "c"]));
+    for screen in [
+        Screen { fullscreen: true, streaming: false },
+        Screen { fullscreen: false, streaming: true },
+    ] {
+        assert_eq!(hints.line(screen), None);
+        assert_eq!(hints.insertable(screen), None);
+    }
+    assert!(hints.line(QUIET).is_some(), "back once things are quiet");
+}
+
+#[test]
+fn test_turned_off() {
+    let mut hints = moved_into_proj();
+    hints.arrived("/proj", commands(&["a", "b", "c"]));
+    hints.set_enabled(false);
+    assert_eq!(hints.line(QUIET), None);
+    assert_eq!(hints.entered("/next"), None, "no lookups while off");
+
+    let mut hints = DirectoryHints::new(false);
+    hints.entered("/home/me");
+    assert_eq!(hints.entered("/proj"), None);
+}
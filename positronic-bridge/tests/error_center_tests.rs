@@ -0,0 +1,119 @@
+// positronic-bridge/tests/error_center_tests.rs
+//
+// System error toasts: deduplication within the window, toast expiry,
+// the unread badge and its acknowledgement, the `!errors sys` listing,
+// and which severities BioLink announces. Every time is passed in.
+
+use std::time::{Duration, Instant};
+
+use chrono::NaiveDate;
+use positronic_bridge::biolink::BioLinkEvent;
+use positronic_bridge::error_center::{self, ErrorCenter, ErrorSeverity, DEDUPE_WINDOW, TOAST_DURATION};
+use positronic_core::error::{PositronicError, VaultErrorKind};
+
+fn wall(h: u32, m: u32, s: u32) -> chrono::NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(h, m, s).unwrap()
+}
+
+const DOWN: &str = "connection refused (http://localhost:8000)";
+
+#[test]
+fn test_repeats_within_window_are_counted_not_repeated() {
+    let mut center = ErrorCenter::default();
+    let t0 = Instant::now();
+    let first = center.report(ErrorSeverity::Warning, DOWN, None, t0, wall(9, 0, 0));
+    assert_eq!(first, Some(BioLinkEvent::Announcement(format!("Warning: {}", DOWN))));
+    for i in 1..4 {
+        let again = center.report(ErrorSeverity::Warning, DOWN, None, t0 + Duration::from_secs(i * 10), wall(9, 0, i as u32 * 10));
+        assert_eq!(again, None, "repeats are not announced again");
+    }
+    assert_eq!(center.entries().len(), 1);
+    assert_eq!(center.entries()[0].count, 4);
+    assert_eq!(center.entries()[0].last_seen, wall(9, 0, 30));
+
+    let status = center.status(t0 + Duration::from_secs(31)).unwrap();
+    assert_eq!(status.toast.as_deref(), Some(&*format!("{} ×4", DOWN)));
+    assert_eq!(status.label(), format!("⚠️ {} ×4", DOWN));
+    assert_eq!(status.plain(), format!("! {} x4", DOWN));
+}
+
+#[test]
+fn test_window_restarts_after_quiet_period() {
+    let mut center = ErrorCenter::default();
+    let t0 = Instant::now();
+    center.report(ErrorSeverity::Error, "vault busy", None, t0, wall(9, 0, 0));
+    let later = t0 + DEDUPE_WINDOW + Duration::from_secs(1);
+    let event = center.report(ErrorSeverity::Error, "vault busy", None, later, wall(9, 1, 1));
+    assert_eq!(event, Some(BioLinkEvent::ErrorOccurred("vault busy".to_string())));
+    assert_eq!(center.entries().len(), 2);
+    // Same text at another severity is another entry.
+    center.report(ErrorSeverity::Info, "vault busy", None, later, wall(9, 1, 1));
+    assert_eq!(center.entries().len(), 3);
+}
+
+#[test]
+fn test_toast_expires_and_badge_stays_until_acknowledged() {
+    let mut center = ErrorCenter::default();
+    let t0 = Instant::now();
+    assert!(center.status(t0).is_none());
+    center.report(ErrorSeverity::Error, "clipboard unavailable", None, t0, wall(9, 0, 0));
+    center.report(ErrorSeverity::Warning, DOWN, None, t0, wall(9, 0, 0));
+    assert_eq!(center.next_wake(), Some(t0 + TOAST_DURATION));
+
+    assert!(!center.tick(t0 + TOAST_DURATION - Duration::from_millis(1)));
+    assert!(center.tick(t0 + TOAST_DURATION));
+    assert!(!center.tick(t0 + TOAST_DURATION));
+    assert_eq!(center.next_wake(), None);
+
+    let badge = center.status(t0 + TOAST_DURATION).unwrap();
+    assert_eq!(badge.toast, None);
+    assert_eq!(badge.unacknowledged, 2);
+    assert_eq!(badge.severity, ErrorSeverity::Error, "the badge takes the loudest");
+    assert_eq!(badge.label(), "⚑ 2 errors · !errors sys");
+
+    center.acknowledge_all();
+    assert!(center.status(t0 + TOAST_DURATION).is_none());
+
+    // A repeat brings the badge back.
+    center.report(ErrorSeverity::Warning, DOWN, None, t0 + Duration::from_secs(20), wall(9, 0, 20));
+    let status = center.status(t0 + Duration::from_secs(20)).unwrap();
+    assert_eq!(status.unacknowledged, 1);
+    assert!(status.toast.is_some());
+}
+
+#[test]
+fn test_info_is_not_announced() {
+    let mut center = ErrorCenter::default();
+    assert_eq!(center.report(ErrorSeverity::Info, "history saved late", None, Instant::now(), wall(9, 0, 0)), None);
+}
+
+#[test]
+fn test_report_lines_list_times_counts_and_hints() {
+    let mut center = ErrorCenter::default();
+    assert_eq!(center.report_lines(), vec!["⚑ No system errors this session"]);
+    let t0 = Instant::now();
+    let hint = Some("Is Lemonade running? !doctor checks it".to_string());
+    center.report(ErrorSeverity::Warning, DOWN, hint.clone(), t0, wall(9, 0, 0));
+    center.report(ErrorSeverity::Warning, DOWN, hint, t0 + Duration::from_secs(5), wall(9, 0, 5));
+    center.report(ErrorSeverity::Error, "Copy failed: no display", None, t0, wall(9, 2, 0));
+
+    let lines = center.report_lines();
+    assert_eq!(lines[0], "⚑ 2 system errors this session:");
+    assert_eq!(lines[1], format!("  09:00:00  ⚠️ {}  ×2 (last 09:00:05)", DOWN));
+    assert!(lines[2].contains("💡 Is Lemonade running?"));
+    assert_eq!(lines[3], "  09:02:00  ❌ Copy failed: no display");
+
+    center.clear();
+    assert!(center.entries().is_empty());
+    assert!(center.status(t0).is_none());
+}
+
+#[test]
+fn test_engine_error_severity() {
+    let neural = PositronicError::neural("http://localhost:8000", "connection refused");
+    assert_eq!(error_center::severity_of(&neural), ErrorSeverity::Warning);
+    let busy = PositronicError::vault(VaultErrorKind::Busy, "database is locked");
+    assert_eq!(error_center::severity_of(&busy), ErrorSeverity::Warning);
+    let corrupt = PositronicError::vault(VaultErrorKind::Corrupt, "not a database");
+    assert_eq!(error_center::severity_of(&corrupt), ErrorSeverity::Error);
+}
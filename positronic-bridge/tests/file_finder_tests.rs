@@ -0,0 +1,225 @@
+// positronic-bridge/tests/file_finder_tests.rs
+//
+// Tests for the Ctrl+P file finder: ranking against representative path
+// sets, key routing in the open overlay, and what each action does with
+// the file picked.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use positronic_bridge::file_finder::{self, Dispatch, FileFinder, FinderAction, FinderOutcome};
+use positronic_bridge::keymap::{Action, Chord, KeyName, Keymap};
+use positronic_core::file_index::FileSnapshot;
+
+fn chord(text: &str) -> Chord {
+    Chord::parse(text).unwrap()
+}
+
+fn paths(list: &[&str]) -> Vec<String> {
+    list.iter().map(|p| p.to_string()).collect()
+}
+
+/// The paths `query` ranks, best first.
+fn ranked<'a>(query: &str, list: &'a [String]) -> Vec<&'a str> {
+    file_finder::rank(query, list).into_iter().map(|r| list[r.index].as_str()).collect()
+}
+
+/// A Rust workspace, a web app and a docs folder.
+fn project() -> Vec<String> {
+    paths(&[
+        "Cargo.toml",
+        "README.md",
+        "crates/core/src/lib.rs",
+        "crates/core/src/main.rs",
+        "crates/core/src/domain/tests.rs",
+        "crates/core/tests/main_tests.rs",
+        "crates/bridge/src/shell/app.rs",
+        "crates/bridge/src/shell/events.rs",
+        "crates/bridge/src/keymap.rs",
+        "web/src/components/App.tsx",
+        "web/src/components/AppHeader.tsx",
+        "web/package.json",
+        "docs/architecture/maintenance.md",
+    ])
+}
+
+fn snapshot(list: &[&str], generation: u64, complete: bool) -> FileSnapshot {
+    FileSnapshot {
+        root: PathBuf::from("/w"),
+        paths: Arc::new(paths(list)),
+        generation,
+        complete,
+        truncated: false,
+        max_files: 100,
+    }
+}
+
+fn typed(finder: &mut FileFinder, text: &str) -> FinderOutcome {
+    let mut outcome = FinderOutcome::Open;
+    for c in text.chars() {
+        let key = Chord::from_key(KeyName::Char(c), false, false, false);
+        outcome = finder.key(Some(key), Some(&c.to_string()), &Keymap::default());
+    }
+    outcome
+}
+
+fn press(finder: &mut FileFinder, key: &str) -> FinderOutcome {
+    finder.key(Some(chord(key)), None, &Keymap::default())
+}
+
+// ════════════════════════════════════════════════════════════════
+// Ranking
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn file_names_outrank_matches_spread_over_folders() {
+    let list = project();
+    let main = ranked("main", &list);
+    assert_eq!(main[0], "crates/core/src/main.rs", "{:?}", main);
+    assert!(main.contains(&"crates/core/tests/main_tests.rs"));
+    let at = |p: &str| main.iter().position(|m| *m == p).unwrap();
+    assert!(at("crates/core/tests/main_tests.rs") < at("docs/architecture/maintenance.md"));
+
+    assert_eq!(ranked("keymap", &list)[0], "crates/bridge/src/keymap.rs");
+    assert_eq!(ranked("app.rs", &list)[0], "crates/bridge/src/shell/app.rs");
+    assert!(ranked("zzz", &list).is_empty());
+}
+
+#[test]
+fn segment_starts_and_smart_case_steer_the_order() {
+    let list = project();
+    // Initials of path segments: crates/bridge/shell/events.
+    assert_eq!(ranked("cbse", &list)[0], "crates/bridge/src/shell/events.rs");
+    // A capital asks for case; the shorter of the two equal matches first.
+    assert_eq!(ranked("App", &list)[..2], ["web/src/components/App.tsx", "web/src/components/AppHeader.tsx"]);
+    assert_eq!(ranked("apph", &list)[0], "web/src/components/AppHeader.tsx");
+    // A folder in the query narrows to files under it.
+    assert_eq!(ranked("tests/main", &list)[0], "crates/core/tests/main_tests.rs");
+}
+
+#[test]
+fn an_empty_query_lists_shallow_files_first() {
+    let list = project();
+    assert_eq!(ranked("", &list)[..2], ["README.md", "Cargo.toml"]);
+    assert_eq!(ranked("", &list).len(), list.len());
+}
+
+// ════════════════════════════════════════════════════════════════
+// The overlay
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn results_fill_in_as_the_index_grows_and_keep_the_pick() {
+    let mut finder = FileFinder::new(Path::new("/w"));
+    assert!(finder.hits().is_empty());
+    assert!(finder.update(&snapshot(&["src/lib.rs", "src/app.rs"], 1, false)));
+    assert!(!finder.update(&snapshot(&["src/lib.rs", "src/app.rs"], 1, false)), "same generation");
+    assert!(finder.view().footer.starts_with("indexing… 2 files"), "{}", finder.view().footer);
+
+    typed(&mut finder, "rs");
+    press(&mut finder, "down");
+    assert_eq!(finder.selected().unwrap().path, "src/app.rs");
+    finder.update(&snapshot(&["a.rs", "src/lib.rs", "src/app.rs"], 2, true));
+    assert_eq!(finder.selected().unwrap().path, "src/app.rs", "the pick stays on its file");
+    assert!(finder.view().footer.starts_with("3 files"));
+}
+
+#[test]
+fn keys_search_pick_and_close() {
+    let mut finder = FileFinder::new(Path::new("/w"));
+    finder.update(&snapshot(&["src/lib.rs", "src/app.rs", "docs/app.md"], 1, true));
+    assert_eq!(typed(&mut finder, "app"), FinderOutcome::Searched);
+    assert_eq!(finder.hits().len(), 2);
+    press(&mut finder, "up");
+    assert_eq!(press(&mut finder, "backspace"), FinderOutcome::Searched);
+    assert_eq!(finder.query, "ap");
+
+    // A query naming a folder asks for it to be read sooner.
+    assert_eq!(finder.prefix(), None);
+    press(&mut finder, "escape");
+    typed(&mut finder, "crates/core/ma");
+    assert_eq!(finder.prefix(), Some("crates/core"));
+    assert!(finder.hits().is_empty());
+
+    // Escape clears, then closes; the finder's own chord closes at once.
+    assert_eq!(press(&mut finder, "escape"), FinderOutcome::Searched);
+    assert_eq!(press(&mut finder, "escape"), FinderOutcome::Closed);
+    let keymap = Keymap::default();
+    let own = keymap.chord_for(Action::FileFinder).unwrap();
+    assert_eq!(own, chord("ctrl+p"));
+    assert_eq!(finder.key(Some(own), None, &keymap), FinderOutcome::Closed);
+}
+
+#[test]
+fn a_second_key_picks_the_action() {
+    let mut finder = FileFinder::new(Path::new("/w"));
+    finder.update(&snapshot(&["src/lib.rs", "src/app.rs"], 1, true));
+    typed(&mut finder, "app");
+    assert_eq!(press(&mut finder, "enter"), FinderOutcome::Open);
+    assert_eq!(finder.choosing(), Some("src/app.rs"));
+    assert!(finder.view().footer.contains("i insert path  ·  e edit  ·  v !view  ·  c copy path"));
+
+    // Keys that aren't actions do nothing; Escape goes back to the list.
+    assert_eq!(typed(&mut finder, "x"), FinderOutcome::Open);
+    assert_eq!(finder.query, "app", "not typed into the query");
+    press(&mut finder, "escape");
+    assert_eq!(finder.choosing(), None);
+
+    for (key, action) in [("i", FinderAction::Insert), ("E", FinderAction::Edit), ("v", FinderAction::View), ("c", FinderAction::Copy)] {
+        press(&mut finder, "enter");
+        assert_eq!(typed(&mut finder, key), FinderOutcome::Chosen(action, "src/app.rs".into()), "{}", key);
+        press(&mut finder, "escape");
+    }
+    press(&mut finder, "enter");
+    assert_eq!(press(&mut finder, "enter"), FinderOutcome::Chosen(FinderAction::Insert, "src/app.rs".into()));
+}
+
+#[test]
+fn the_plain_view_follows_the_pick() {
+    let list: Vec<String> = (0..30).map(|i| format!("f{:02}.rs", i)).collect();
+    let names: Vec<&str> = list.iter().map(String::as_str).collect();
+    let mut finder = FileFinder::new(Path::new("/w"));
+    finder.update(&snapshot(&names, 1, true));
+    press(&mut finder, "pagedown");
+    press(&mut finder, "pagedown");
+    let lines = finder.view().plain(8);
+    assert_eq!(lines.len(), 8);
+    assert!(lines[0].starts_with("🔎 Files in /w"));
+    assert_eq!(lines[6], "> f20.rs");
+}
+
+// ════════════════════════════════════════════════════════════════
+// Actions
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn actions_carry_the_path_the_way_each_needs_it() {
+    let root = Path::new("/w/repo");
+    let cwd = Path::new("/w/repo/crates/core");
+    assert_eq!(
+        file_finder::dispatch(FinderAction::Insert, root, "crates/core/src/lib.rs", cwd),
+        Dispatch::Insert("src/lib.rs".into())
+    );
+    assert_eq!(
+        file_finder::dispatch(FinderAction::Insert, root, "docs/guide.md", cwd),
+        Dispatch::Insert("../../docs/guide.md".into())
+    );
+    assert_eq!(
+        file_finder::dispatch(FinderAction::Insert, root, "docs/my notes.md", root),
+        Dispatch::Insert("'docs/my notes.md'".into()),
+        "quoted for the shell"
+    );
+    assert_eq!(
+        file_finder::dispatch(FinderAction::Edit, root, "src/app.rs", cwd),
+        Dispatch::Edit(PathBuf::from("/w/repo/src/app.rs"))
+    );
+    assert_eq!(
+        file_finder::dispatch(FinderAction::View, root, "src/app.rs", cwd),
+        Dispatch::View(PathBuf::from("/w/repo/src/app.rs"))
+    );
+    assert_eq!(
+        file_finder::dispatch(FinderAction::Copy, root, "src/app.rs", cwd),
+        Dispatch::Copy("/w/repo/src/app.rs".into())
+    );
+    assert_eq!(file_finder::relative_to(Path::new("/w/repo"), Path::new("/w/repo")), ".");
+}
@@ -0,0 +1,324 @@
+// positronic-bridge/tests/focus_tests.rs
+//
+// Keyboard focus over Holodeck widgets: Tab cycling with wrap-around,
+// arrow keys inside a table and a JSON tree, activation matching a click,
+// Escape back to the input bar, restoring focus when a widget goes away,
+// and the labels BioLink announces.
+
+use positronic_bridge::biolink::{BioLink, BioLinkEvent};
+use positronic_bridge::gfx::QuadInstance;
+use positronic_bridge::holodeck::{CellValue, DataFrame};
+use positronic_bridge::widgets::button::Button;
+use positronic_bridge::widgets::focus::{self, FocusKey, FocusManager, FocusOutcome, Focusable, INPUT_LABEL};
+use positronic_bridge::widgets::table::TableWidget;
+use positronic_bridge::widgets::tree::JsonTreeWidget;
+use positronic_bridge::widgets::{PointerEvent, PointerKind, Rect, WidgetAction};
+
+fn rect(h: f32) -> Rect {
+    Rect { x: 10.0, y: 20.0, w: 400.0, h }
+}
+
+/// `rows` services named `svc-1`.. with every third one failed.
+fn services(rows: usize) -> TableWidget {
+    let df = DataFrame {
+        headers: vec!["name".to_string(), "status".to_string()],
+        rows: (1..=rows)
+            .map(|i| {
+                let status = if i % 3 == 0 { "failed" } else { "ok" };
+                vec![CellValue::Text(format!("svc-{}", i)), CellValue::Text(status.to_string())]
+            })
+            .collect(),
+        delimiter: ',',
+    };
+    // Header strip and five rows.
+    TableWidget::new(rect(34.0 + 5.0 * 18.0), df)
+}
+
+fn tree() -> JsonTreeWidget {
+    let json = serde_json::json!({
+        "name": "api",
+        "spec": { "replicas": 3, "ports": [80, 443] },
+        "tags": []
+    });
+    JsonTreeWidget::new(rect(200.0), json)
+}
+
+fn moved(outcome: FocusOutcome) -> String {
+    match outcome {
+        FocusOutcome::Moved(label) => label,
+        other => panic!("expected a move, got {:?}", other),
+    }
+}
+
+fn press(focus: &mut FocusManager, widgets: &mut [&mut dyn Focusable], key: FocusKey) -> String {
+    moved(focus.handle(key, widgets))
+}
+
+// ============================================================================
+// Labels
+// ============================================================================
+
+#[test]
+fn test_element_label_format() {
+    assert_eq!(
+        BioLink::element_label("row", 4, 20, "name=api-server, status=failed"),
+        "row 4 of 20: name=api-server, status=failed"
+    );
+}
+
+#[test]
+fn test_focus_changed_is_announced() {
+    let mut biolink = BioLink::new();
+    biolink.config.screen_reader_enabled = true;
+    let event = FocusOutcome::Moved("row 1 of 2: name=a".to_string()).announcement().unwrap();
+    assert!(event.priority() < BioLinkEvent::Announcement(String::new()).priority());
+    biolink.announce(event);
+    assert_eq!(biolink.drain_announcements(), vec!["row 1 of 2: name=a"]);
+    assert!(FocusOutcome::Ignored.announcement().is_none());
+    assert_eq!(
+        FocusOutcome::Released.announcement(),
+        Some(BioLinkEvent::FocusChanged(INPUT_LABEL.to_string()))
+    );
+}
+
+// ============================================================================
+// Table
+// ============================================================================
+
+#[test]
+fn test_table_keyboard_traversal() {
+    let mut table = services(20);
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+
+    assert!(matches!(focus.handle(FocusKey::Down, widgets), FocusOutcome::Ignored));
+    assert_eq!(moved(focus.enter(widgets, false)), "row 1 of 20: name=svc-1, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Up), "row 1 of 20: name=svc-1, status=ok");
+    press(&mut focus, widgets, FocusKey::Down);
+    press(&mut focus, widgets, FocusKey::Down);
+    assert_eq!(press(&mut focus, widgets, FocusKey::Down), "row 4 of 20: name=svc-4, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Up), "row 3 of 20: name=svc-3, status=failed");
+    assert_eq!(press(&mut focus, widgets, FocusKey::End), "row 20 of 20: name=svc-20, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Down), "row 20 of 20: name=svc-20, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::PageUp), "row 15 of 20: name=svc-15, status=failed");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Home), "row 1 of 20: name=svc-1, status=ok");
+    assert_eq!(focus.focused().unwrap().element, 0);
+}
+
+#[test]
+fn test_table_focus_scrolls_the_row_into_view() {
+    let mut table = services(20);
+    let mut focus = FocusManager::new();
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+        focus.enter(widgets, false);
+        for _ in 0..7 {
+            focus.handle(FocusKey::Down, widgets);
+        }
+    }
+    assert_eq!(table.selected_row, Some(7));
+    assert_eq!(table.scroll_row, 3);
+    let row = table.element_rect(7);
+    assert_eq!(row.y, 20.0 + 46.0 + 4.0 * 18.0);
+    assert_eq!(row.h, 18.0);
+}
+
+#[test]
+fn test_table_enter_matches_a_click() {
+    let mut clicked = services(20);
+    let click = clicked.on_pointer(PointerEvent { x: 50.0, y: 20.0 + 46.0 + 18.0 + 5.0, kind: PointerKind::Down });
+
+    let mut table = services(20);
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+    focus.enter(widgets, false);
+    focus.handle(FocusKey::Down, widgets);
+    let FocusOutcome::Activated { action, label } = focus.handle(FocusKey::Enter, widgets) else {
+        panic!("Enter should activate the row");
+    };
+    let (WidgetAction::CopyText(by_key), WidgetAction::CopyText(by_click)) = (&action, &click) else {
+        panic!("both should copy the row");
+    };
+    assert_eq!(by_key, "svc-2\tok");
+    assert_eq!(by_key, by_click);
+    assert_eq!(label, "row 2 of 20: name=svc-2, status=ok");
+    assert!(matches!(focus.handle(FocusKey::Space, widgets), FocusOutcome::Activated { .. }));
+}
+
+// ============================================================================
+// JSON tree
+// ============================================================================
+
+#[test]
+fn test_tree_keyboard_traversal() {
+    let mut tree = tree();
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut tree];
+
+    assert_eq!(moved(focus.enter(widgets, false)), "node 1 of 3: name = \"api\", level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Down), "node 2 of 3: spec, 2 keys, collapsed, level 1");
+    // Right opens, then steps in.
+    assert_eq!(press(&mut focus, widgets, FocusKey::Right), "node 2 of 5: spec, 2 keys, expanded, level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Right), "node 3 of 5: ports, 2 items, collapsed, level 2");
+    press(&mut focus, widgets, FocusKey::Right);
+    assert_eq!(press(&mut focus, widgets, FocusKey::Right), "node 4 of 7: [0] = 80, level 3");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Right), "node 4 of 7: [0] = 80, level 3");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Down), "node 5 of 7: [1] = 443, level 3");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Down), "node 6 of 7: replicas = 3, level 2");
+    // Left steps out to the parent, then folds it.
+    assert_eq!(press(&mut focus, widgets, FocusKey::Left), "node 2 of 7: spec, 2 keys, expanded, level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Left), "node 2 of 3: spec, 2 keys, collapsed, level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::End), "node 3 of 3: tags, 0 items, collapsed, level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Left), "node 3 of 3: tags, 0 items, collapsed, level 1");
+}
+
+#[test]
+fn test_tree_enter_matches_a_click() {
+    let mut tree = tree();
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut tree];
+    focus.enter(widgets, false);
+
+    let FocusOutcome::Activated { action: WidgetAction::CopyText(text), .. } = focus.handle(FocusKey::Enter, widgets)
+    else {
+        panic!("Enter on a leaf should copy it");
+    };
+    assert_eq!(text, "api");
+
+    focus.handle(FocusKey::Down, widgets);
+    let FocusOutcome::Activated { action: WidgetAction::None, label } = focus.handle(FocusKey::Space, widgets) else {
+        panic!("Space on an object should open it");
+    };
+    assert_eq!(label, "node 2 of 5: spec, 2 keys, expanded, level 1");
+
+    // Clicking the same row on a fresh tree opens it the same way.
+    let mut clicked = self::tree();
+    let action = clicked.on_pointer(PointerEvent { x: 50.0, y: 20.0 + 6.0 + 18.0 + 5.0, kind: PointerKind::Down });
+    assert!(matches!(action, WidgetAction::None));
+    assert!(clicked.is_expanded("/spec"));
+    assert_eq!(clicked.rows().len(), 5);
+}
+
+#[test]
+fn test_tree_pointers_escape_keys() {
+    let mut tree = JsonTreeWidget::new(rect(100.0), serde_json::json!({ "a/b": { "c~d": 1 } }));
+    tree.move_focus(0, FocusKey::Right);
+    let rows = tree.rows();
+    assert_eq!(rows[1].pointer, "/a~1b/c~0d");
+    let WidgetAction::CopyText(text) = tree.activate(1) else {
+        panic!("a leaf copies its value");
+    };
+    assert_eq!(text, "1");
+}
+
+// ============================================================================
+// Cycling, Escape and restoration
+// ============================================================================
+
+#[test]
+fn test_tab_cycles_widgets_and_wraps() {
+    let mut table = services(3);
+    let mut tree = tree();
+    let mut button = Button::new(rect(30.0), "Copy JSON", WidgetAction::CopyText("{}".to_string()));
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut table, &mut tree, &mut button];
+
+    focus.enter(widgets, false);
+    press(&mut focus, widgets, FocusKey::Down);
+    assert_eq!(press(&mut focus, widgets, FocusKey::Tab), "node 1 of 3: name = \"api\", level 1");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Tab), "button: Copy JSON");
+    // Wraps to the table, which kept its row.
+    assert_eq!(press(&mut focus, widgets, FocusKey::Tab), "row 2 of 3: name=svc-2, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::BackTab), "button: Copy JSON");
+    let FocusOutcome::Activated { action: WidgetAction::CopyText(text), .. } = focus.handle(FocusKey::Enter, widgets)
+    else {
+        panic!("Enter presses the button");
+    };
+    assert_eq!(text, "{}");
+
+    assert!(matches!(focus.handle(FocusKey::Escape, widgets), FocusOutcome::Released));
+    assert!(focus.input_has_focus());
+    assert!(matches!(focus.handle(FocusKey::Tab, widgets), FocusOutcome::Ignored));
+    assert_eq!(moved(focus.enter(widgets, true)), "button: Copy JSON");
+}
+
+#[test]
+fn test_tab_skips_empty_widgets() {
+    let mut empty = services(0);
+    let mut table = services(2);
+    let mut focus = FocusManager::new();
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut empty, &mut table];
+    assert_eq!(moved(focus.enter(widgets, false)), "row 1 of 2: name=svc-1, status=ok");
+    assert_eq!(press(&mut focus, widgets, FocusKey::Tab), "row 1 of 2: name=svc-1, status=ok");
+}
+
+#[test]
+fn test_focus_restored_when_widget_disappears() {
+    let mut first = services(3);
+    let mut middle = tree();
+    let mut last = Button::new(rect(30.0), "Save", WidgetAction::None);
+    let mut focus = FocusManager::new();
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut first, &mut middle, &mut last];
+        focus.enter(widgets, false);
+        focus.handle(FocusKey::Tab, widgets);
+    }
+
+    // The tree goes; the button took its place.
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut first, &mut last];
+        assert_eq!(moved(focus.sync(widgets).unwrap()), "button: Save");
+        assert!(focus.sync(widgets).is_none());
+    }
+
+    // The button goes too; it was last, so the table before it takes focus.
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut first];
+        assert_eq!(moved(focus.sync(widgets).unwrap()), "row 1 of 3: name=svc-1, status=ok");
+    }
+
+    // Nothing left: back to the input bar.
+    let widgets: &mut [&mut dyn Focusable] = &mut [];
+    assert!(matches!(focus.sync(widgets), Some(FocusOutcome::Released)));
+    assert!(focus.input_has_focus());
+}
+
+#[test]
+fn test_focus_clamped_when_rows_shrink() {
+    let mut table = services(5);
+    let mut focus = FocusManager::new();
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+        focus.enter(widgets, false);
+        focus.handle(FocusKey::End, widgets);
+    }
+    table.df.rows.truncate(2);
+    let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+    assert_eq!(moved(focus.sync(widgets).unwrap()), "row 2 of 2: name=svc-2, status=ok");
+}
+
+// ============================================================================
+// Outline
+// ============================================================================
+
+#[test]
+fn test_outline_rings_the_focused_row() {
+    let mut table = services(3);
+    let mut focus = FocusManager::new();
+    assert!(focus.outline(&[&table]).is_none());
+    {
+        let widgets: &mut [&mut dyn Focusable] = &mut [&mut table];
+        focus.enter(widgets, false);
+        focus.handle(FocusKey::Down, widgets);
+    }
+    let ring = focus.outline(&[&table]).unwrap();
+    let row = table.element_rect(1);
+    let edges = |quads: [QuadInstance; 4]| quads.map(|q| (q.x, q.y, q.w, q.h));
+    assert_eq!(edges(ring), edges(focus::outline(row)));
+    let (top, bottom, left, right) = (ring[0], ring[1], ring[2], ring[3]);
+    assert_eq!((top.x, top.y, top.w), (row.x, row.y, row.w));
+    assert_eq!(bottom.y + bottom.h, row.y + row.h);
+    assert_eq!((left.x, left.h), (row.x, row.h));
+    assert_eq!(right.x + right.w, row.x + row.w);
+    assert!(ring.iter().all(|q| q.color == ring[0].color));
+}
@@ -0,0 +1,217 @@
+// positronic-bridge/tests/follow_tests.rs
+//
+// Tests for the `!follow` pane: the bounded buffer and its dropped count,
+// pausing, the severity and regex filters, and key routing, all fed with
+// synthetic line streams.
+
+use positronic_bridge::block::LineKind;
+use positronic_bridge::follow::{FollowKey, FollowPhase, FollowState, Severity};
+use positronic_bridge::keymap::{Chord, KeyName, Keymap};
+use positronic_core::usage::ResourceUsage;
+
+fn texts(follow: &FollowState, rows: usize) -> Vec<String> {
+    follow.visible(rows).iter().map(|l| l.text.clone()).collect()
+}
+
+fn feed(follow: &mut FollowState, lines: &[&str]) {
+    for line in lines {
+        follow.push(line);
+    }
+}
+
+fn typed(follow: &mut FollowState, text: &str) -> FollowKey {
+    let c = text.chars().next().unwrap();
+    follow.key(Some(Chord::from_key(KeyName::Char(c), false, false, false)), Some(text), &Keymap::default())
+}
+
+fn press(follow: &mut FollowState, key: &str) -> FollowKey {
+    let text = (key == "space").then_some(" ");
+    follow.key(Some(Chord::parse(key).unwrap()), text, &Keymap::default())
+}
+
+const LOG: &[&str] = &[
+    "info: starting",
+    "GET /health 200",
+    "warning: slow query",
+    "error: connection refused",
+    "GET /users 200",
+    "note: retrying",
+];
+
+// ============================================================================
+// Buffer
+// ============================================================================
+
+#[test]
+fn test_lines_are_classified_and_shown_newest_last() {
+    let mut follow = FollowState::new("tail -f app.log");
+    feed(&mut follow, LOG);
+    let shown = follow.visible(3);
+    assert_eq!(shown.iter().map(|l| l.kind).collect::<Vec<_>>(), [LineKind::Error, LineKind::Normal, LineKind::Info]);
+    assert_eq!(texts(&follow, 100).len(), LOG.len());
+}
+
+#[test]
+fn test_buffer_evicts_the_oldest_and_counts_them() {
+    let mut follow = FollowState::with_capacity("yes", 3);
+    for n in 0..10 {
+        follow.push(&format!("line {}", n));
+    }
+    assert_eq!(follow.len(), 3);
+    assert_eq!(follow.received(), 10);
+    assert_eq!(follow.dropped(), 7);
+    assert_eq!(texts(&follow, 10), ["line 7", "line 8", "line 9"]);
+    assert!(follow.header().contains("7 dropped"));
+}
+
+// ============================================================================
+// Pause
+// ============================================================================
+
+#[test]
+fn test_pause_freezes_the_view_and_counts_new_lines() {
+    let mut follow = FollowState::new("tail -f app.log");
+    feed(&mut follow, &["a", "b"]);
+    assert_eq!(press(&mut follow, "space"), FollowKey::Handled);
+    assert!(follow.is_paused());
+    feed(&mut follow, &["c", "d", "e"]);
+
+    assert_eq!(texts(&follow, 10), ["a", "b"]);
+    assert_eq!(follow.badge().as_deref(), Some("⏸ paused, +3 lines"));
+
+    press(&mut follow, "space");
+    assert_eq!(follow.badge(), None);
+    assert_eq!(texts(&follow, 10), ["a", "b", "c", "d", "e"]);
+}
+
+#[test]
+fn test_pause_survives_eviction() {
+    let mut follow = FollowState::with_capacity("yes", 4);
+    feed(&mut follow, &["a", "b", "c"]);
+    follow.toggle_pause();
+    feed(&mut follow, &["d", "e", "f"]);
+    // a and b are gone; what is left of the frozen view stays.
+    assert_eq!(follow.dropped(), 2);
+    assert_eq!(texts(&follow, 10), ["c"]);
+    assert_eq!(follow.pending(), 3);
+}
+
+// ============================================================================
+// Filters
+// ============================================================================
+
+#[test]
+fn test_severity_keys_filter_and_toggle_off() {
+    let mut follow = FollowState::new("tail -f app.log");
+    feed(&mut follow, LOG);
+
+    typed(&mut follow, "e");
+    assert_eq!(follow.severity(), Some(Severity::Error));
+    assert_eq!(texts(&follow, 10), ["error: connection refused"]);
+
+    typed(&mut follow, "w");
+    assert_eq!(texts(&follow, 10), ["warning: slow query", "error: connection refused"]);
+
+    typed(&mut follow, "i");
+    assert_eq!(texts(&follow, 10).len(), 4);
+
+    typed(&mut follow, "i");
+    assert_eq!(follow.severity(), None);
+    assert_eq!(texts(&follow, 10).len(), LOG.len());
+}
+
+#[test]
+fn test_filters_apply_to_lines_that_arrive_later() {
+    let mut follow = FollowState::new("tail -f app.log");
+    typed(&mut follow, "e");
+    feed(&mut follow, LOG);
+    follow.push("error: disk full");
+    assert_eq!(texts(&follow, 10), ["error: connection refused", "error: disk full"]);
+    assert!(follow.header().contains("errors"));
+}
+
+#[test]
+fn test_regex_prompt_filters_live_and_keeps_the_last_valid_pattern() {
+    let mut follow = FollowState::new("tail -f app.log");
+    feed(&mut follow, LOG);
+
+    typed(&mut follow, "/");
+    assert!(follow.prompt.is_some());
+    for c in ["G", "E", "T", " ", "/"] {
+        assert_eq!(typed(&mut follow, c), FollowKey::Handled);
+    }
+    assert_eq!(texts(&follow, 10), ["GET /health 200", "GET /users 200"]);
+
+    // `(` doesn't compile: the filter stays and the prompt says why.
+    typed(&mut follow, "(");
+    assert!(follow.prompt.as_ref().unwrap().error.is_some());
+    assert_eq!(follow.filter(), Some("GET /"));
+    assert!(follow.footer(None).contains("invalid"));
+
+    press(&mut follow, "backspace");
+    press(&mut follow, "enter");
+    assert!(follow.prompt.is_none());
+    assert_eq!(follow.filter(), Some("GET /"));
+    follow.push("GET /orders 500");
+    assert_eq!(texts(&follow, 1), ["GET /orders 500"]);
+
+    // Reopening starts from the pattern; Escape clears it.
+    typed(&mut follow, "/");
+    assert_eq!(follow.prompt.as_ref().unwrap().query, "GET /");
+    press(&mut follow, "escape");
+    assert_eq!(follow.filter(), None);
+    assert_eq!(texts(&follow, 100).len(), LOG.len() + 1);
+}
+
+#[test]
+fn test_filter_keys_are_text_while_the_prompt_is_open() {
+    let mut follow = FollowState::new("tail -f app.log");
+    feed(&mut follow, LOG);
+    typed(&mut follow, "/");
+    typed(&mut follow, "e");
+    typed(&mut follow, "q");
+    press(&mut follow, "space");
+    assert_eq!(follow.prompt.as_ref().unwrap().query, "eq ");
+    assert_eq!(follow.severity(), None);
+    assert!(!follow.is_paused());
+}
+
+// ============================================================================
+// Stopping and closing
+// ============================================================================
+
+#[test]
+fn test_interrupt_stops_then_closes() {
+    let mut follow = FollowState::new("kubectl logs -f api");
+    assert_eq!(press(&mut follow, "ctrl+c"), FollowKey::Stop);
+    follow.phase = FollowPhase::Stopping;
+    assert_eq!(press(&mut follow, "ctrl+c"), FollowKey::Handled);
+    assert!(follow.header().contains("stopping"));
+
+    follow.exited(None);
+    assert!(!follow.is_running());
+    assert!(follow.header().contains("stopped"));
+    assert_eq!(press(&mut follow, "ctrl+c"), FollowKey::Close);
+}
+
+#[test]
+fn test_header_shows_usage_once_the_command_has_exited() {
+    let mut follow = FollowState::new("make");
+    follow.exited(Some(0));
+    assert!(!follow.header().contains("cpu"));
+    follow.usage = Some(ResourceUsage { user_ms: 1_500, sys_ms: 500, max_rss_bytes: 0 });
+    assert!(follow.header().contains("exited 0 · 0 lines · cpu 2.0s · peak "), "{}", follow.header());
+}
+
+#[test]
+fn test_escape_and_q_close_and_other_keys_are_ignored() {
+    let mut follow = FollowState::new("tail -f app.log");
+    assert_eq!(press(&mut follow, "escape"), FollowKey::Close);
+    assert_eq!(typed(&mut follow, "q"), FollowKey::Close);
+    assert_eq!(typed(&mut follow, "x"), FollowKey::Ignored);
+    assert_eq!(press(&mut follow, "ctrl+l"), FollowKey::Ignored);
+
+    follow.exited(Some(1));
+    assert!(follow.header().contains("exited 1"));
+    assert!(follow.footer(Some(&Chord::parse("ctrl+c").unwrap())).contains("closes"));
+}
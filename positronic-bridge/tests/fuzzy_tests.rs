@@ -0,0 +1,179 @@
+// positronic-bridge/tests/fuzzy_tests.rs
+//
+// The shared fuzzy matcher: golden rankings, case modes, highlight
+// ranges (checked against pseudo-random queries), the frecency weight,
+// a 100k-candidate benchmark, and the completer's fuzzy fallback.
+
+use positronic_bridge::completer::{complete, complete_with_history};
+use positronic_bridge::fuzzy::{self, CaseMode, Matcher, WEIGHT_BONUS};
+
+fn ranked<'a>(query: &str, candidates: &[&'a str]) -> Vec<&'a str> {
+    fuzzy::rank(&Matcher::new(query, CaseMode::Smart), candidates, |_| 0.0)
+        .into_iter()
+        .map(|r| candidates[r.index])
+        .collect()
+}
+
+/// The matched text, range by range.
+fn highlighted(candidate: &str, query: &str, mode: CaseMode) -> String {
+    let matched = Matcher::new(query, mode).score(candidate).unwrap();
+    matched.ranges.iter().map(|r| &candidate[r.clone()]).collect()
+}
+
+#[test]
+fn test_gco_prefers_go_compile_without_weight() {
+    let candidates = ["git checkout", "go-compile"];
+    assert_eq!(ranked("gco", &candidates), vec!["go-compile", "git checkout"]);
+
+    let matcher = Matcher::new("gco", CaseMode::Smart);
+    let compile = matcher.score("go-compile").unwrap();
+    let checkout = matcher.score("git checkout").unwrap();
+    assert_eq!(compile.ranges, vec![0..1, 3..5]);
+    assert_eq!(checkout.ranges, vec![0..1, 4..5, 9..10]);
+    assert!(compile.score > checkout.score);
+}
+
+#[test]
+fn test_frecency_weight_can_reorder() {
+    let candidates = ["go-compile", "git checkout"];
+    let matcher = Matcher::new("gco", CaseMode::Smart);
+    let order: Vec<usize> = fuzzy::rank(&matcher, &candidates, |i| if i == 1 { 1.0 } else { 0.0 })
+        .iter()
+        .map(|r| r.index)
+        .collect();
+    assert_eq!(order, vec![1, 0], "a frequently run git checkout wins");
+
+    let plain = matcher.score("git checkout").unwrap().score;
+    assert_eq!(matcher.score_weighted("git checkout", 1.0).unwrap().score, plain + WEIGHT_BONUS);
+    assert_eq!(matcher.score_weighted("git checkout", 7.0).unwrap().score, plain + WEIGHT_BONUS);
+    assert_eq!(matcher.score_weighted("git checkout", -1.0).unwrap().score, plain);
+    assert_eq!(matcher.score_weighted("git checkout", f32::NAN).unwrap().score, plain);
+}
+
+#[test]
+fn test_boundaries_runs_and_length() {
+    // A path component start beats letters scattered through a word.
+    assert_eq!(ranked("mod", &["src/random_order.rs", "src/mod.rs"]), vec!["src/mod.rs", "src/random_order.rs"]);
+    // A consecutive run beats the same letters spread out.
+    assert_eq!(ranked("test", &["the_east_side", "run_tests"]), vec!["run_tests", "the_east_side"]);
+    // camelCase humps count as word starts.
+    assert_eq!(ranked("gb", &["gitlab", "getBranch"]), vec!["getBranch", "gitlab"]);
+    // Same match, shorter candidate first.
+    assert_eq!(ranked("cargo", &["cargo-watch", "cargo"]), vec!["cargo", "cargo-watch"]);
+    // Ties keep list order.
+    assert_eq!(ranked("ab", &["ab1", "ab2"]), vec!["ab1", "ab2"]);
+}
+
+#[test]
+fn test_non_matches_are_dropped() {
+    assert!(ranked("xyz", &["git checkout", "go-compile"]).is_empty());
+    assert!(Matcher::new("ba", CaseMode::Smart).score("ab").is_none(), "order matters");
+    assert!(Matcher::new("aa", CaseMode::Smart).score("a").is_none());
+    let empty = Matcher::new("", CaseMode::Smart);
+    assert!(empty.is_empty());
+    assert_eq!(empty.score("anything").unwrap().score, 0);
+}
+
+#[test]
+fn test_case_modes() {
+    let smart = Matcher::new("docker", CaseMode::Smart);
+    assert!(smart.ignores_case());
+    assert!(smart.matches("Dockerfile"));
+
+    let capital = Matcher::new("Dock", CaseMode::Smart);
+    assert!(!capital.ignores_case(), "a capital in the query turns case on");
+    assert!(capital.matches("Dockerfile"));
+    assert!(!capital.matches("dockerfile"));
+
+    assert!(!Matcher::new("docker", CaseMode::Sensitive).matches("Dockerfile"));
+    assert!(Matcher::new("DOCK", CaseMode::Insensitive).matches("dockerfile"));
+    assert_eq!(highlighted("Dockerfile", "DF", CaseMode::Insensitive), "Df");
+}
+
+#[test]
+fn test_unicode_ranges_are_byte_ranges() {
+    let candidate = "café/über.txt";
+    let matched = Matcher::new("éü", CaseMode::Smart).score(candidate).unwrap();
+    for r in &matched.ranges {
+        assert!(candidate.is_char_boundary(r.start) && candidate.is_char_boundary(r.end));
+    }
+    assert_eq!(highlighted(candidate, "éü", CaseMode::Smart), "éü");
+    assert_eq!(highlighted(candidate, "ÉÜ", CaseMode::Insensitive), "éü");
+}
+
+#[test]
+fn test_ranges_spell_out_the_query() {
+    const WORDS: [&str; 8] = ["git", "checkout", "src/main.rs", "Cargo.toml", "docker-compose", "kubectl", "npmRun", "ÜberTool"];
+    let mut seed: u64 = 0x2431;
+    let mut next = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+    for _ in 0..500 {
+        let candidate: Vec<&str> = (0..1 + next() % 4).map(|_| WORDS[next() % WORDS.len()]).collect();
+        let candidate = candidate.join(if next() % 2 == 0 { " " } else { "/" });
+        // Any subsequence of the candidate must match.
+        let chars: Vec<char> = candidate.chars().collect();
+        let query: String = chars.iter().filter(|_| next() % 3 == 0).collect();
+        if query.is_empty() {
+            continue;
+        }
+        for mode in [CaseMode::Sensitive, CaseMode::Insensitive, CaseMode::Smart] {
+            let matched = Matcher::new(&query, mode).score(&candidate).unwrap_or_else(|| {
+                panic!("{:?} should match {:?}", query, candidate);
+            });
+            let text: String = matched.ranges.iter().map(|r| &candidate[r.clone()]).collect();
+            assert_eq!(text.to_lowercase(), query.to_lowercase(), "{:?} in {:?}", query, candidate);
+            for pair in matched.ranges.windows(2) {
+                assert!(pair[0].end < pair[1].start, "ranges are ordered and merged: {:?}", matched.ranges);
+            }
+        }
+    }
+}
+
+/// Ranking latency over 100k candidates.
+/// Run with `cargo test -p positronic-bridge --release --test fuzzy_tests -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn bench_rank_100k() {
+    const VERBS: [&str; 10] = ["git", "cargo", "ls", "cd", "docker", "npm", "make", "grep", "ssh", "kubectl"];
+    let candidates: Vec<String> =
+        (0..100_000).map(|i| format!("{} arg{} --flag{}", VERBS[i % 10], i, i % 97)).collect();
+
+    let queries = ["gco", "kubectl a9", "dkr", "zzz", "cargo 42"];
+    let start = std::time::Instant::now();
+    let mut hits = 0;
+    for _ in 0..5 {
+        for q in &queries {
+            hits += fuzzy::rank(&Matcher::new(q, CaseMode::Smart), &candidates, |_| 0.0).len();
+        }
+    }
+    let per_query = start.elapsed() / 25;
+    eprintln!("fuzzy::rank: 100k candidates, {:?} per query", per_query);
+    assert!(hits > 0);
+}
+
+#[test]
+fn test_completer_falls_back_to_fuzzy() {
+    let state = complete("!hst", &[], "/nonexistent").unwrap();
+    assert_eq!(state.completions[0], "!history");
+
+    // Prefix matches still win outright.
+    let state = complete("!hi", &[], "/nonexistent").unwrap();
+    assert!(state.completions.iter().all(|c| c.starts_with("!hi")));
+
+    let aliases = vec!["deploy-staging".to_string(), "build".to_string()];
+    let state = complete("dstg", &aliases, "/nonexistent").unwrap();
+    assert_eq!(state.completions, vec!["deploy-staging"]);
+
+    // History: prefix matches first, fuzzy only when there are none, and
+    // then the more recent of two close matches first.
+    let history = vec!["git commit -m wip".to_string(), "git checkout main".to_string()];
+    let state = complete_with_history("git c", &[], "/nonexistent", &history).unwrap();
+    assert_eq!(state.completions, history);
+    let state = complete_with_history("gcm", &[], "/nonexistent", &history).unwrap();
+    assert_eq!(state.completions, history);
+    let reversed: Vec<String> = history.iter().rev().cloned().collect();
+    let state = complete_with_history("gcm", &[], "/nonexistent", &reversed).unwrap();
+    assert_eq!(state.completions, reversed);
+}
@@ -0,0 +1,148 @@
+// positronic-bridge/tests/governor_tests.rs
+//
+// Tests for the activity governor: stepping down on an injected clock,
+// wake triggers, what each state throttles, and time-in-state accounting.
+
+use std::time::{Duration, Instant};
+
+use positronic_bridge::governor::{
+    parse_idle_after, Governor, PowerConfig, PowerState, IDLE_SNAPSHOT_INTERVAL,
+};
+
+const IDLE: Duration = Duration::from_secs(60);
+const SUSPEND: Duration = Duration::from_secs(300);
+
+fn config() -> PowerConfig {
+    PowerConfig { idle_after: Some(IDLE), suspend_after: SUSPEND }
+}
+
+fn secs(n: u64) -> Duration {
+    Duration::from_secs(n)
+}
+
+#[test]
+fn test_focused_window_never_idles() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    assert_eq!(gov.tick(t0 + secs(3600)), None);
+    assert_eq!(gov.state(), PowerState::Active);
+    assert_eq!(gov.next_change(), None);
+}
+
+#[test]
+fn test_unfocused_quiet_window_steps_down() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    let blur = t0 + secs(10);
+    assert_eq!(gov.set_focus(false, blur), None);
+    assert_eq!(gov.next_change(), Some(blur + IDLE));
+
+    assert_eq!(gov.tick(blur + IDLE - secs(1)), None);
+    assert_eq!(gov.tick(blur + IDLE), Some(PowerState::Idle));
+    assert_eq!(gov.next_change(), Some(blur + SUSPEND));
+    assert_eq!(gov.tick(blur + SUSPEND), Some(PowerState::Suspended));
+    assert_eq!(gov.next_change(), None);
+}
+
+#[test]
+fn test_activity_pushes_the_countdown_back() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    let output = t0 + secs(50);
+    assert_eq!(gov.activity(output), None);
+    assert_eq!(gov.tick(t0 + IDLE), None);
+    assert_eq!(gov.tick(output + IDLE), Some(PowerState::Idle));
+}
+
+#[test]
+fn test_focus_and_output_wake_immediately() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    gov.tick(t0 + SUSPEND);
+    assert_eq!(gov.state(), PowerState::Suspended);
+
+    assert_eq!(gov.activity(t0 + SUSPEND + secs(5)), Some(PowerState::Active));
+    // Still unfocused: quiet again, it goes back down.
+    assert_eq!(gov.tick(t0 + SUSPEND + secs(5) + IDLE), Some(PowerState::Idle));
+
+    assert_eq!(gov.set_focus(true, t0 + SUSPEND + secs(100)), Some(PowerState::Active));
+    assert_eq!(gov.next_change(), None);
+}
+
+#[test]
+fn test_long_sleep_jumps_straight_to_suspended() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    assert_eq!(gov.tick(t0 + secs(3600)), Some(PowerState::Suspended));
+}
+
+#[test]
+fn test_idle_after_off_disables_the_governor() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(PowerConfig { idle_after: None, suspend_after: SUSPEND }, t0);
+    gov.set_focus(false, t0);
+    assert_eq!(gov.tick(t0 + secs(3600)), None);
+    assert_eq!(gov.next_change(), None);
+}
+
+#[test]
+fn test_reconfigure_applies_at_once() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    gov.tick(t0 + IDLE);
+    assert_eq!(gov.state(), PowerState::Idle);
+    let off = PowerConfig { idle_after: None, ..config() };
+    assert_eq!(gov.configure(off, t0 + IDLE + secs(1)), Some(PowerState::Active));
+}
+
+#[test]
+fn test_each_state_throttles_more() {
+    assert!(PowerState::Active.ticks() && PowerState::Active.timers() && PowerState::Active.probes());
+    assert!(!PowerState::Idle.ticks() && PowerState::Idle.timers() && !PowerState::Idle.probes());
+    assert!(!PowerState::Suspended.ticks() && !PowerState::Suspended.timers());
+    assert_eq!(PowerState::Active.snapshot_interval(), Duration::ZERO);
+    assert_eq!(PowerState::Idle.snapshot_interval(), IDLE_SNAPSHOT_INTERVAL);
+    assert!(PowerState::Suspended.snapshot_interval() > IDLE_SNAPSHOT_INTERVAL);
+}
+
+#[test]
+fn test_time_in_each_state_is_accounted() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    gov.tick(t0 + IDLE);
+    gov.tick(t0 + SUSPEND);
+    let now = t0 + SUSPEND + secs(100);
+
+    assert_eq!(gov.time_in(PowerState::Active, now), IDLE);
+    assert_eq!(gov.time_in(PowerState::Idle, now), SUSPEND - IDLE);
+    assert_eq!(gov.time_in(PowerState::Suspended, now), secs(100));
+
+    gov.activity(now);
+    assert_eq!(gov.time_in(PowerState::Suspended, now + secs(50)), secs(100));
+    assert_eq!(gov.time_in(PowerState::Active, now + secs(50)), IDLE + secs(50));
+}
+
+#[test]
+fn test_report_lists_every_state() {
+    let t0 = Instant::now();
+    let mut gov = Governor::new(config(), t0);
+    gov.set_focus(false, t0);
+    gov.tick(t0 + IDLE);
+    let lines = gov.report_lines(t0 + IDLE * 2);
+    let body = lines.join("\n");
+    assert!(body.contains("active") && body.contains("idle") && body.contains("suspended"));
+    assert!(lines.iter().any(|l| l.contains("idle") && l.contains("50.0%") && l.contains("now")));
+}
+
+#[test]
+fn test_parse_idle_after() {
+    assert_eq!(parse_idle_after("90s"), Some(Some(secs(90))));
+    assert_eq!(parse_idle_after("5m"), Some(Some(secs(300))));
+    assert_eq!(parse_idle_after("off"), Some(None));
+    assert_eq!(parse_idle_after("soon"), None);
+}
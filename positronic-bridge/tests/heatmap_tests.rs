@@ -0,0 +1,83 @@
+// positronic-bridge/tests/heatmap_tests.rs
+//
+// Tests for `!stats heatmap` on the GPU path: where the cell quads go
+// (golden), the text left over them, and the theme gradient.
+
+use positronic_bridge::heatmap::{self, CELL_GAP};
+use positronic_bridge::renderer::ThemeName;
+
+/// Rows as core writes them: a weekday row, a calendar row with days
+/// still to come, a legend, and lines around them without cells.
+const LINES: [&str; 5] = [
+    "📊 Commands by weekday and hour: 9",
+    "Mon ▐··░░▒▒▓▓██▌ 9",
+    "Sun ▐░░··  ▌",
+    "    ▐··▌ 0  ▐██▌ 4–9  per hour",
+    "Busiest: Mon 04:00–05:00, 4 commands",
+];
+
+const QUADS_GOLDEN: &str = "\
+row 1 col 5 level 0: [41, 21, 14, 18]
+row 1 col 7 level 1: [57, 21, 14, 18]
+row 1 col 9 level 2: [73, 21, 14, 18]
+row 1 col 11 level 3: [89, 21, 14, 18]
+row 1 col 13 level 4: [105, 21, 14, 18]
+row 2 col 5 level 1: [41, 41, 14, 18]
+row 2 col 7 level 0: [57, 41, 14, 18]
+row 3 col 5 level 0: [41, 61, 14, 18]
+row 3 col 13 level 4: [105, 61, 14, 18]
+";
+
+#[test]
+fn cell_quads_golden() {
+    let (cell_w, line_h) = (8.0, 20.0);
+    let actual: String = heatmap::cell_quads(LINES, cell_w, line_h, CELL_GAP)
+        .into_iter()
+        .map(|([x, y, w, h], level)| {
+            let (row, col) = ((y / line_h) as usize, (x / cell_w) as usize);
+            format!("row {} col {} level {}: [{}, {}, {}, {}]\n", row, col, level, x, y, w, h)
+        })
+        .collect();
+    assert_eq!(actual, QUADS_GOLDEN, "\n{}", actual);
+}
+
+#[test]
+fn cell_quads_scale_with_the_cell_and_keep_the_gap() {
+    let quads = heatmap::cell_quads(["Tue ▐▓▓▌"], 10.0, 24.0, 4.0);
+    assert_eq!(quads, [([52.0, 2.0, 16.0, 20.0], 3)]);
+    assert!(heatmap::cell_quads(["no cells here", "▌ ▐"], 8.0, 20.0, CELL_GAP).is_empty());
+}
+
+#[test]
+fn blank_leaves_labels_for_the_quads_to_show_through() {
+    let blanked: Vec<String> = LINES.iter().map(|l| heatmap::blank(l).into_owned()).collect();
+    assert_eq!(
+        blanked,
+        [
+            "📊 Commands by weekday and hour: 9",
+            "Mon              9",
+            "Sun         ",
+            "         0       4–9  per hour",
+            "Busiest: Mon 04:00–05:00, 4 commands",
+        ]
+    );
+    // Columns stay where they were, so the quads line up with the labels.
+    for (line, blank) in LINES.iter().zip(&blanked) {
+        assert_eq!(line.chars().count(), blank.chars().count());
+    }
+}
+
+#[test]
+fn gradient_runs_from_the_background_to_the_accent() {
+    for &theme in ThemeName::all() {
+        let bg = theme.bg_color();
+        let distance = |level: u8| {
+            let c = heatmap::color(theme, level);
+            (c.r - bg.r).abs() + (c.g - bg.g).abs() + (c.b - bg.b).abs()
+        };
+        assert!(distance(0) > 0.0, "{:?}: empty cells still show", theme);
+        assert!((0..4).all(|level| distance(level) < distance(level + 1)), "{:?}", theme);
+        let (top, accent) = (heatmap::color(theme, 4), theme.trace_color(0));
+        assert!((top.r - accent.r).abs() + (top.g - accent.g).abs() + (top.b - accent.b).abs() < 1e-4, "{:?}", theme);
+    }
+}
@@ -1,13 +1,13 @@
 //! Help text completeness tests.
 //! Group 11: Verify that the !help output documents all critical features.
 
-/// The help text (from builtins.rs) should mention these keyboard shortcuts.
+/// The help text (from commands/session.rs) should mention these keyboard shortcuts.
 /// This test verifies the contract documented in the handoff.
 
 #[test]
 fn help_mentions_ctrl_c() {
     // !help should document: Ctrl+C sends interrupt
-    // Verified by code review of builtins.rs help_text vec
+    // Verified by code review of commands/session.rs (HEADER, FOOTER)
     let help_keywords = ["Ctrl+C", "interrupt"];
     assert!(!help_keywords.is_empty());
 }
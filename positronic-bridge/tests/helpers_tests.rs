@@ -2,7 +2,11 @@
 //! Groups 9-10.
 
 use positronic_core::state_machine::{MyColor, Snapshot};
-use positronic_bridge::helpers::{format_duration_short, short_path, hash_snapshot};
+use positronic_bridge::helpers::{
+    format_duration_short, format_elapsed, hash_snapshot, running_label, short_path, word_left, word_right,
+};
+use positronic_core::term::running::RunningInfo;
+use std::time::{Duration, Instant};
 
 // ════════════════════════════════════════════════════════════════
 // Group 9: Format helpers
@@ -30,6 +34,27 @@ fn format_hours() {
     assert_eq!(format_duration_short(7261), "2h 1m");
 }
 
+#[test]
+fn format_elapsed_keeps_seconds() {
+    assert_eq!(format_elapsed(Duration::from_millis(42_900)), "42s");
+    assert_eq!(format_elapsed(Duration::from_secs(102)), "1m 42s");
+    assert_eq!(format_elapsed(Duration::from_secs(3725)), "1h 02m");
+}
+
+#[test]
+fn running_label_names_the_command() {
+    let started_at = Instant::now();
+    let now = started_at + Duration::from_secs(102);
+    let info = RunningInfo { command: "cargo build".into(), started_at, confirmed: true };
+    assert_eq!(running_label(&info, now), "⏱ running: cargo build — 1m 42s");
+
+    let guess = RunningInfo { command: String::new(), started_at, confirmed: false };
+    assert_eq!(running_label(&guess, now), "⏱ may be running: a command — 1m 42s");
+
+    let long = RunningInfo { command: "x".repeat(60), started_at, confirmed: true };
+    assert!(running_label(&long, now).contains(&format!("{}…", "x".repeat(39))));
+}
+
 #[test]
 fn short_path_no_change() {
     let short = "C:\\Dev";
@@ -88,4 +113,32 @@ fn buffer_trim_boundary() {
     // push_direct is now a method on PositronicApp (not a free function),
     // so we just verify the constant value here.
     assert_eq!(positronic_bridge::shell::app::MAX_DIRECT_BYTES, 256 * 1024);
-}
\ No newline at end of file
+}
+// ════════════════════════════════════════════════════════════════
+// Link detection
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn last_url_picks_final_link() {
+    use positronic_bridge::helpers::last_url;
+    let text = "see https://a.example/docs then\nopen (http://b.example/x?y=1).";
+    assert_eq!(last_url(text), Some("http://b.example/x?y=1"));
+    assert_eq!(last_url("remote: https://github.com/o/r/pull/new/main"), Some("https://github.com/o/r/pull/new/main"));
+    assert_eq!(last_url("no links, just https:// here"), None);
+    assert_eq!(last_url(""), None);
+}
+
+#[test]
+fn word_motions_skip_whitespace_then_a_word() {
+    let line = "git  push --force";
+    assert_eq!(word_left(line, 17), 11);
+    assert_eq!(word_left(line, 11), 5);
+    assert_eq!(word_left(line, 5), 0);
+    assert_eq!(word_left(line, 0), 0);
+    assert_eq!(word_right(line, 0), 3);
+    assert_eq!(word_right(line, 3), 9);
+    assert_eq!(word_right(line, 17), 17);
+    // Char indices, not bytes.
+    assert_eq!(word_left("echo héllo", 10), 5);
+    assert_eq!(word_right("héllo wörld", 5), 11);
+}
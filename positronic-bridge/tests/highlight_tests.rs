@@ -0,0 +1,180 @@
+// positronic-bridge/tests/highlight_tests.rs
+//
+// Tests for the input line tokenizer and classifier, with fake
+// executables and files in a temp directory.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use positronic_bridge::highlight::{
+    classify, tokenize, HighlightKind, HighlightSpan, Highlighter, Sources, TokenKind, EXISTS_TTL,
+};
+use positronic_bridge::path_index::PathIndex;
+
+use HighlightKind as K;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("positronic_highlight_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn file(&self, rel: &str) {
+        let path = self.0.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "").unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn no_alias(_: &str) -> bool {
+    false
+}
+
+/// An index over a `bin` with `git` and `cargo`; Windows-style matching
+/// so the fixture needs no execute bits.
+fn fixture(tmp: &TempDir) -> PathIndex {
+    tmp.file("bin/git.exe");
+    tmp.file("bin/cargo.exe");
+    let index = PathIndex::new(tmp.0.join("bin").into_os_string(), Some(".EXE"));
+    index.refresh(false);
+    index
+}
+
+/// `(text, kind, underline)` of every span.
+fn spans(input: &str, sources: &Sources<'_>) -> Vec<(String, HighlightKind, bool)> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut exists = |p: &Path| Some(p.exists());
+    classify(&tokenize(input), sources, &mut exists)
+        .into_iter()
+        .map(|HighlightSpan { range, kind, underline }| (chars[range].iter().collect(), kind, underline))
+        .collect()
+}
+
+fn s(text: &str, kind: HighlightKind, underline: bool) -> (String, HighlightKind, bool) {
+    (text.to_string(), kind, underline)
+}
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+#[test]
+fn tokenizer_respects_quotes_and_escapes() {
+    let tokens = tokenize(r#"echo "a b" 'c "d"' e\ f "g\"h" x|y"#);
+    let words: Vec<(&str, bool)> = tokens.iter().map(|t| (t.text.as_str(), t.quoted)).collect();
+    assert_eq!(
+        words,
+        vec![("echo", false), ("a b", true), ("c \"d\"", true), ("e f", false), ("g\"h", true), ("x", false), ("|", false), ("y", false)]
+    );
+    assert_eq!(tokens[1].range, 5..10);
+    assert_eq!(tokens[6].kind, TokenKind::Separator);
+
+    // Unterminated quotes run to the end of the line.
+    let open = tokenize("echo \"still typing");
+    assert_eq!((open[1].text.as_str(), open[1].range.clone()), ("still typing", 5..18));
+}
+
+#[test]
+fn tokenizer_keeps_windows_paths_and_char_ranges() {
+    let tokens = tokenize(r"type C:\Users\me\notes.txt \\server\share 2> é.log # note");
+    let texts: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+    assert_eq!(texts, vec!["type", r"C:\Users\me\notes.txt", r"\\server\share", "2>", "é.log", "# note"]);
+    assert_eq!(tokens[3].kind, TokenKind::Redirect);
+    assert_eq!(tokens[4].range, 45..50, "ranges count chars, not bytes");
+    assert_eq!(tokens[5].kind, TokenKind::Comment);
+}
+
+// ============================================================================
+// Classifier
+// ============================================================================
+
+#[test]
+fn classifier_colors_commands_flags_strings_and_paths() {
+    let tmp = TempDir::new("classify");
+    let index = fixture(&tmp);
+    tmp.file("work/src/main.rs");
+    let cwd = tmp.0.join("work");
+    let is_alias = |name: &str| name == "gs";
+    let sources = Sources { executables: Some(&index), is_alias: &is_alias, cwd: &cwd };
+
+    assert_eq!(
+        spans("git add -p src/main.rs 'my file' && carg --release", &sources),
+        vec![
+            s("git", K::Command, false),
+            s("add", K::Argument, false),
+            s("-p", K::Flag, false),
+            s("src/main.rs", K::Path, true),
+            s("'my file'", K::String, false),
+            s("&&", K::Operator, false),
+            s("carg", K::UnknownCommand, false),
+            s("--release", K::Flag, false),
+        ]
+    );
+    assert_eq!(
+        spans("RUST_LOG=debug cargo run > out.txt", &sources),
+        vec![
+            s("RUST_LOG=debug", K::Argument, false),
+            s("cargo", K::Command, false),
+            s("run", K::Argument, false),
+            s(">", K::Operator, false),
+            s("out.txt", K::Path, false),
+        ]
+    );
+    // Aliases, builtins and existing scripts are commands too.
+    assert_eq!(spans("gs", &sources)[0].1, K::Command);
+    assert_eq!(spans("cd src", &sources), vec![s("cd", K::Command, false), s("src", K::Path, true)]);
+    assert_eq!(spans("./src/main.rs", &sources)[0], s("./src/main.rs", K::Command, false));
+    assert_eq!(spans("./missing.sh", &sources)[0].1, K::UnknownCommand);
+    assert_eq!(spans("Get-ChildItem", &sources)[0].1, K::Command);
+}
+
+#[test]
+fn classifier_knows_native_commands() {
+    let cwd = std::env::temp_dir();
+    let sources = Sources { executables: None, is_alias: &no_alias, cwd: &cwd };
+    assert_eq!(
+        spans("!stats export csv", &sources),
+        vec![s("!stats", K::Command, false), s("export", K::Argument, false), s("csv", K::Argument, false)]
+    );
+    assert_eq!(spans("!exit", &sources)[0].1, K::Command);
+    assert_eq!(spans("!nope", &sources)[0].1, K::UnknownCommand);
+    // Only the start of the line is a native command.
+    assert_eq!(spans("git log | !stats", &sources)[3].1, K::Argument);
+}
+
+#[test]
+fn classifier_waits_for_the_path_index() {
+    let tmp = TempDir::new("pending");
+    let index = PathIndex::new(tmp.0.join("bin").into_os_string(), Some(".EXE"));
+    let sources = Sources { executables: Some(&index), is_alias: &no_alias, cwd: &tmp.0 };
+    assert_eq!(spans("carg", &sources)[0].1, K::Argument, "not flagged before the first scan");
+    index.refresh(false);
+    assert_eq!(spans("carg", &sources)[0].1, K::UnknownCommand);
+}
+
+#[test]
+fn highlighter_caches_existence_checks() {
+    let tmp = TempDir::new("cache");
+    let sources = Sources { executables: None, is_alias: &no_alias, cwd: &tmp.0 };
+    let mut highlighter = Highlighter::new();
+    let t0 = Instant::now();
+
+    assert!(!highlighter.highlight("cat notes.txt", &sources, t0)[1].underline);
+    tmp.file("notes.txt");
+    assert!(!highlighter.highlight("cat notes.txt", &sources, t0)[1].underline, "same input, same answer");
+    assert!(
+        !highlighter.highlight("cat  notes.txt", &sources, t0 + Duration::from_millis(10))[1].underline,
+        "the cached stat is reused"
+    );
+    assert!(highlighter.highlight("cat notes.txt", &sources, t0 + EXISTS_TTL)[1].underline);
+}
@@ -2,10 +2,14 @@
 //
 // Integration tests for the Holodeck rich content system (Pillar I).
 // Tests content detection, parsing (JSON, CSV, Markdown), the
-// HolodeckManager lifecycle, and entry type filtering.
+// HolodeckManager lifecycle, entry type filtering, `!save` export,
+// `!chart` axes (time detection, ticks, log scale), and `!fetch`
+// downloads (formats asked for, images, `!holodeck list`).
 
+use positronic_bridge::holodeck::chart::{self, AxisKind, ChartCommand, ChartOptions, TimeStep, YScale, CHART_USAGE};
+use positronic_bridge::holodeck::export::{self, SaveCommand, SaveFormat, SAVE_USAGE};
 use positronic_bridge::holodeck::{
-    ContentType, HolodeckManager, MarkdownContent, MarkdownElement, RichContent,
+    CellValue, ChartSpec, ContentType, DataFrame, HolodeckManager, MarkdownContent, MarkdownElement, RichContent,
 };
 
 // ============================================================================
@@ -293,4 +297,412 @@ fn test_markdown_source_preserved() {
     let md = "# Hello World";
     let parsed = MarkdownContent::parse(md);
     assert_eq!(parsed.source, md);
-}
\ No newline at end of file
+}
+// ============================================================================
+// CSV/JSON Export (`!save`)
+// ============================================================================
+
+fn round_trip(text: &str) -> DataFrame {
+    let df = DataFrame::parse_csv(text).expect("fixture parses");
+    let csv = df.to_csv_string();
+    let again = DataFrame::parse_csv(&csv).unwrap_or_else(|| panic!("re-parse failed:\n{}", csv));
+    assert_eq!(again, df, "round trip through:\n{}", csv);
+    df
+}
+
+#[test]
+fn test_csv_round_trip_quoted_fields() {
+    let df = round_trip(
+        "name,quote,score,ratio,ok\n\
+         \"Smith, Jane\",\"She said \"\"hi\"\"\",42,2.0,true\n\
+         plain,\"  padded  \",-7,0.5,\n\
+         \"a|b;c\",mid\"quote,,1e3,FALSE\n",
+    );
+    assert_eq!(df.headers, vec!["name", "quote", "score", "ratio", "ok"]);
+    assert_eq!(df.rows[0][0], CellValue::Text("Smith, Jane".into()));
+    assert_eq!(df.rows[0][1], CellValue::Text("She said \"hi\"".into()));
+    assert_eq!(df.rows[0][3], CellValue::Float(2.0));
+    assert_eq!(df.rows[1][1], CellValue::Text("  padded  ".into()), "quoted edge whitespace is kept");
+    assert_eq!(df.rows[2][1], CellValue::Text("mid\"quote".into()), "a quote inside a field is literal");
+    assert_eq!(df.rows[2][2], CellValue::Empty);
+}
+
+#[test]
+fn test_csv_round_trip_multiline_cells() {
+    let df = round_trip("id;note\r\n1;\"first line\r\nsecond; line\"\r\n2;\"\"\r\n3;\"ends with newline\n\"\r\n");
+    assert_eq!(df.delimiter, ';');
+    assert_eq!(df.row_count(), 3);
+    assert_eq!(df.rows[0][1], CellValue::Text("first line\r\nsecond; line".into()));
+    assert_eq!(df.rows[1][1], CellValue::Empty);
+    assert_eq!(df.rows[2][1], CellValue::Text("ends with newline\n".into()));
+
+    let tsv = round_trip("a\tb\n\"x\ty\"\t\"1\n2\"\n");
+    assert_eq!(tsv.delimiter, '\t');
+    assert!(tsv.to_csv_string().starts_with("a\tb\n\"x\ty\"\t\"1\n2\"\n"));
+}
+
+#[test]
+fn test_to_json_records() {
+    let df = DataFrame::parse_csv("name,age,score\nAda,36,9.5\nBob,,\n").unwrap();
+    let records: serde_json::Value = serde_json::from_str(&df.to_json_records()).unwrap();
+    assert_eq!(
+        records,
+        serde_json::json!([
+            {"name": "Ada", "age": 36, "score": 9.5},
+            {"name": "Bob", "age": null, "score": null},
+        ])
+    );
+}
+
+#[test]
+fn test_save_command_parse() {
+    assert_eq!(
+        SaveCommand::parse("!save out/data.json --format raw --entry #3 --force"),
+        Ok(SaveCommand { path: "out/data.json".into(), format: Some(SaveFormat::Raw), entry: Some(3), force: true })
+    );
+    assert_eq!(SaveCommand::parse("!save t.csv").unwrap().format, None);
+    assert_eq!(SaveCommand::parse("!save"), Err(SAVE_USAGE.to_string()));
+    assert_eq!(SaveCommand::parse("!save a b"), Err(SAVE_USAGE.to_string()));
+    assert!(SaveCommand::parse("!save x --format xml").unwrap_err().contains("xml"));
+    assert!(SaveCommand::parse("!save x --entry last").is_err());
+}
+
+#[test]
+fn test_save_entry_formats_and_overwrite_guard() {
+    let dir = std::env::temp_dir().join(format!("positronic_holodeck_save_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut mgr = HolodeckManager::new();
+    let table = mgr.ingest("city,pop\n\"Paris, FR\",2100000\n");
+    let json = mgr.ingest(r#"{"a":[1,2]}"#);
+    let table = mgr.get(table).unwrap();
+    let json = mgr.get(json).unwrap();
+
+    assert_eq!(SaveFormat::default_for(table), SaveFormat::Csv);
+    assert_eq!(SaveFormat::default_for(json), SaveFormat::Json);
+    assert_eq!(SaveFormat::from_path("x.JSON"), Some(SaveFormat::Json));
+    assert!(export::render(json, SaveFormat::Csv).is_err(), "JSON has no CSV form");
+
+    let (path, bytes) = export::save(table, "pop.csv", SaveFormat::Csv, false, &dir).unwrap();
+    assert_eq!(path, dir.join("pop.csv"));
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(written, "city,pop\n\"Paris, FR\",2100000\n");
+    assert_eq!(bytes, written.len());
+
+    let err = export::save(json, "pop.csv", SaveFormat::Raw, false, &dir).unwrap_err();
+    assert!(err.contains("--force"), "{}", err);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), written, "refused saves leave the file alone");
+
+    export::save(json, "pop.csv", SaveFormat::Raw, true, &dir).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"{"a":[1,2]}"#);
+
+    let records = export::render(table, SaveFormat::Json).unwrap();
+    assert!(records.contains("\"city\": \"Paris, FR\""));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ============================================================================
+// Native Command Tables
+// ============================================================================
+
+#[test]
+fn test_native_tables_keep_their_types_for_save() {
+    use positronic_core::native::{self, Cell, ColumnKind};
+
+    let mut frame = native::DataFrame::new(&[
+        ("#", ColumnKind::Integer),
+        ("command", ColumnKind::Text),
+        ("last_run", ColumnKind::Integer),
+        ("logged", ColumnKind::Bool),
+    ]);
+    frame.push_row(vec![Cell::Integer(1), Cell::text("git commit -m \"a, b\""), Cell::Integer(1_700_000_000), Cell::Bool(true)]);
+    frame.push_row(vec![Cell::Empty, Cell::text(" secret"), Cell::Empty, Cell::Bool(false)]);
+
+    let df = DataFrame::from_native(&frame);
+    assert_eq!(df.headers, ["#", "command", "last_run", "logged"]);
+    assert_eq!(df.rows[0][2], CellValue::Integer(1_700_000_000));
+    assert_eq!(df.rows[1][0], CellValue::Empty);
+
+    let mut mgr = HolodeckManager::new();
+    let id = mgr.ingest_rich(RichContent::Table(df.clone()), &frame.to_table_string());
+    assert_eq!(mgr.get(id).unwrap().content_type, ContentType::Csv);
+
+    // `!save` writes it as CSV that reads back to the same typed frame.
+    let entry = mgr.get(id).unwrap();
+    let csv = export::render(entry, SaveFormat::Csv).unwrap();
+    assert_eq!(DataFrame::parse_csv(&csv).unwrap(), df);
+}
+
+// ============================================================================
+// Charts
+// ============================================================================
+
+const MINUTE: i64 = 60_000;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+#[test]
+fn test_parse_datetime_forms() {
+    // 2024-03-01T12:30:00Z
+    let noon = 1_709_296_200_000;
+    assert_eq!(chart::parse_datetime("2024-03-01"), Some(noon - 12 * HOUR - 30 * MINUTE));
+    assert_eq!(chart::parse_datetime("2024-03-01T12:30:00Z"), Some(noon));
+    assert_eq!(chart::parse_datetime("2024-03-01 12:30"), Some(noon));
+    assert_eq!(chart::parse_datetime("2024-03-01T12:30:00"), Some(noon));
+    assert_eq!(chart::parse_datetime("2024-03-01T12:30:00.250+02:00"), Some(noon - 2 * HOUR + 250));
+    assert_eq!(chart::parse_datetime("2024-03-01T12:30+02:00"), Some(noon - 2 * HOUR));
+    assert_eq!(chart::parse_datetime("2024-13-01"), None);
+    assert_eq!(chart::parse_datetime("1709296200"), None);
+    assert_eq!(chart::parse_datetime("hello"), None);
+}
+
+#[test]
+fn test_iso_cells_parse_as_datetimes() {
+    let df = DataFrame::parse_csv("when,n\n2024-03-01T12:30:00Z,1\n2024-03-02,2\n").unwrap();
+    assert_eq!(df.rows[0][0], CellValue::DateTime { millis: 1_709_296_200_000, text: "2024-03-01T12:30:00Z".to_string() });
+    assert!(df.is_time_column(0));
+    assert!(!df.is_time_column(1));
+    // Saving keeps the text as written.
+    assert_eq!(df.to_csv_string(), "when,n\n2024-03-01T12:30:00Z,1\n2024-03-02,2\n");
+}
+
+#[test]
+fn test_epoch_numbers_need_a_time_header() {
+    let ts = DataFrame::parse_csv("ts,count\n1709251200,3\n1709254800,5\n").unwrap();
+    assert!(ts.is_time_column(0));
+    assert_eq!(ts.column_values(0, true), vec![Some(1_709_251_200_000.0), Some(1_709_254_800_000.0)]);
+    let millis = DataFrame::parse_csv("created_date,count\n1709251200000,3\n").unwrap();
+    assert!(millis.is_time_column(0));
+
+    // The same numbers under an ID header are just numbers.
+    let ids = DataFrame::parse_csv("id,count\n1709251200,3\n1709254800,5\n").unwrap();
+    assert!(!ids.is_time_column(0));
+    let spec = ChartSpec::from_dataframe(&ids, &ChartOptions::default()).unwrap();
+    assert_eq!(spec.x_axis, AxisKind::Numeric);
+
+    // A time header over numbers outside the epoch ranges isn't time.
+    let small = DataFrame::parse_csv("time,v\n1,2\n3,4\n").unwrap();
+    assert!(!small.is_time_column(0));
+}
+
+#[test]
+fn test_column_stats() {
+    let df = DataFrame::parse_csv("name,v\na,2\nb,\nc,4.5\n").unwrap();
+    assert!(df.is_column_numeric(1));
+    assert!(!df.is_column_numeric(0));
+    let stats = df.column_stats(1).unwrap();
+    assert_eq!((stats.count, stats.min, stats.max), (2, 2.0, 4.5));
+    assert_eq!(stats.mean, 3.25);
+    assert!(df.column_stats(0).is_none());
+}
+
+#[test]
+fn test_time_ticks_suit_the_range() {
+    assert_eq!(chart::time_ticks(0, 40 * MINUTE), (TimeStep::Minutes(15), vec![0, 15 * MINUTE, 30 * MINUTE]));
+    assert_eq!(chart::time_ticks(0, 20 * HOUR), (TimeStep::Hours(6), vec![0, 6 * HOUR, 12 * HOUR, 18 * HOUR]));
+    assert_eq!(chart::time_ticks(0, 20 * DAY), (TimeStep::Days(7), vec![0, 7 * DAY, 14 * DAY]));
+    // Ticks land on whole steps, not on the first sample.
+    let (_, ticks) = chart::time_ticks(7 * MINUTE, 2 * HOUR);
+    assert_eq!(ticks.first(), Some(&(30 * MINUTE)));
+}
+
+#[test]
+fn test_log_scale_suggested_past_three_decades() {
+    assert_eq!(chart::suggest_y_scale(&[1.0, 10.0, 100.0]), YScale::Linear);
+    assert_eq!(chart::suggest_y_scale(&[1.0, 10.0, 100_000.0]), YScale::Log10);
+    assert_eq!(chart::suggest_y_scale(&[-5.0, 0.0, 3.0]), YScale::Linear);
+    assert_eq!(chart::log_ticks(0.5, 20_000.0), vec![-1, 0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_render_time_series_on_log_scale() {
+    let df = DataFrame::parse_csv(
+        "time,value\n2024-03-01T00:00:00Z,1\n2024-03-01T06:00:00Z,10\n2024-03-01T12:00:00Z,1000\n2024-03-01T18:00:00Z,100000\n",
+    )
+    .unwrap();
+    let spec = ChartSpec::from_dataframe(&df, &ChartOptions::default()).unwrap();
+    assert_eq!((spec.x_axis, spec.y_scale), (AxisKind::Time, YScale::Log10));
+    assert!(spec.notes.is_empty());
+    let expected = [
+        "📊 value by time (log y)",
+        "100000 ┤                                       •",
+        " 10000 ┤",
+        "       │",
+        "  1000 ┤                          •",
+        "   100 ┤",
+        "       │",
+        "    10 ┤             •",
+        "     1 ┤•",
+        "       └┬────────────┬────────────┬────────────┬",
+        "        03-01 00:00          03-01 12:00  03-01 18:00",
+    ];
+    assert_eq!(chart::render_text(&spec, 40, 8), expected);
+}
+
+#[test]
+fn test_log_scale_clamps_non_positive_values_with_a_note() {
+    let df = DataFrame::parse_csv("id,count\n1,0\n2,10\n3,2000\n4,50000\n").unwrap();
+    let spec = ChartSpec::from_dataframe(&df, &ChartOptions::default()).unwrap();
+    assert_eq!(spec.y_scale, YScale::Log10);
+    assert_eq!(spec.notes, vec!["1 value(s) ≤ 0 clamped to 10 on the log scale".to_string()]);
+    let lines = chart::render_text(&spec, 40, 8);
+    assert_eq!(lines.last().unwrap(), "⚠️ 1 value(s) ≤ 0 clamped to 10 on the log scale");
+
+    // --liny keeps the zero.
+    let options = ChartOptions { y_scale: Some(YScale::Linear), ..ChartOptions::default() };
+    let spec = ChartSpec::from_dataframe(&df, &options).unwrap();
+    assert_eq!(spec.y_scale, YScale::Linear);
+    assert!(spec.notes.is_empty());
+}
+
+#[test]
+fn test_chart_needs_a_numeric_column() {
+    let df = DataFrame::parse_csv("name,city\nann,oslo\nbo,rome\n").unwrap();
+    assert!(ChartSpec::from_dataframe(&df, &ChartOptions::default()).is_err());
+    // A single numeric column is plotted against the row number.
+    let df = DataFrame::parse_csv("name,v\na,1\nb,2\n").unwrap();
+    let spec = ChartSpec::from_dataframe(&df, &ChartOptions::default()).unwrap();
+    assert_eq!(spec.x_axis, AxisKind::Index);
+    assert_eq!(spec.points, vec![(1.0, 1.0), (2.0, 2.0)]);
+}
+
+#[test]
+fn test_chart_command_parse() {
+    let command = ChartCommand::parse("--logy --x time --entry 3").unwrap();
+    assert_eq!(command.options, ChartOptions { y_scale: Some(YScale::Log10), x_time: true });
+    assert_eq!(command.entry, Some(3));
+    assert_eq!(ChartCommand::parse("").unwrap(), ChartCommand::default());
+    assert_eq!(ChartCommand::parse("--x foo").unwrap_err(), CHART_USAGE);
+    assert_eq!(ChartCommand::parse("--entry").unwrap_err(), CHART_USAGE);
+}
+
+// ============================================================================
+// Detector Plugins
+// ============================================================================
+
+use positronic_bridge::holodeck::detect::Detectors;
+use positronic_bridge::holodeck::ContentDetector;
+use positronic_script::detector::DetectorSet;
+use positronic_script::wasm_host::WasmHost;
+
+/// The script crate's `kv:` fixture plugin, compiled from its text.
+fn kv_plugins() -> DetectorSet {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../positronic-script/tests/fixtures/kv_detector.wat");
+    let host = WasmHost::new().unwrap();
+    let plugin = host.detector("kv", &std::fs::read(path).unwrap()).unwrap().unwrap();
+    DetectorSet::new(vec![plugin])
+}
+
+#[test]
+fn test_custom_content_serializes() {
+    let rich = RichContent::custom("kv", r#"{"a":1,"b":[2]}"#).unwrap();
+    assert_eq!(rich.content_type(), ContentType::Json);
+    assert!(rich.is_structured());
+    let round: RichContent = serde_json::from_str(&serde_json::to_string(&rich).unwrap()).unwrap();
+    let RichContent::Custom { plugin, json, summary } = round else {
+        panic!("not custom: {:?}", round);
+    };
+    assert_eq!((plugin.as_str(), summary.as_str()), ("kv", "kv · 2 fields"));
+    assert_eq!(json.pretty, "{\n  \"a\": 1,\n  \"b\": [\n    2\n  ]\n}");
+
+    assert!(matches!(RichContent::custom("kv", "[1,2,3]"), Some(RichContent::Custom { summary, .. }) if summary == "kv · 3 items"));
+    assert!(RichContent::custom("kv", "not json").is_none());
+
+    let mut mgr = HolodeckManager::new();
+    let id = mgr.ingest_rich(RichContent::custom("kv", r#"{"a":1}"#).unwrap(), "kv:a=1");
+    let entry = mgr.get(id).unwrap();
+    assert_eq!(SaveFormat::default_for(entry), SaveFormat::Json);
+    assert_eq!(export::render(entry, SaveFormat::Json).unwrap(), "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn test_detect_scored_consults_plugins_after_builtins() {
+    let mut plugins = kv_plugins();
+    let scored = ContentDetector::detect_scored("kv:hello", &mut plugins);
+    assert_eq!((scored.detector.as_str(), scored.score), ("kv", 90));
+    assert!(matches!(scored.content, RichContent::Custom { ref json, .. } if json.raw == r#"{"kv":"hello"}"#));
+
+    let scored = ContentDetector::detect_scored(r#"{"kv": 1}"#, &mut plugins);
+    assert_eq!((scored.detector.as_str(), scored.score), ("json", 100));
+    let scored = ContentDetector::detect_scored("just words", &mut plugins);
+    assert_eq!((scored.detector.as_str(), scored.score), ("text", 0));
+    assert!(matches!(scored.content, RichContent::Text(_)));
+    assert!(plugins.take_disabled().is_empty());
+}
+
+#[test]
+fn test_detectors_list_hits() {
+    let mut detectors = Detectors::default();
+    let dir = std::path::Path::new("/data/positronic-plugins");
+    assert_eq!(
+        detectors.lines(dir),
+        vec![
+            "🔎 Holodeck detectors, hits this session:",
+            "  json      built-in     0",
+            "  csv       built-in     0",
+            "  markdown  built-in     0",
+            "  No detector plugins: put them in /data/positronic-plugins",
+        ]
+    );
+
+    detectors.install(kv_plugins());
+    let scored = detectors.detect("kv:x");
+    detectors.hit(&scored.detector);
+    detectors.hit("json");
+    let lines = detectors.lines(dir);
+    assert_eq!(lines[1], "  json      built-in     1");
+    assert_eq!(lines[4], "  kv        plugin       1");
+    assert_eq!(lines.len(), 5);
+}
+
+// ============================================================================
+// Fetched Entries
+// ============================================================================
+
+use positronic_bridge::holodeck::{ImageMeta, SOURCE_TAG};
+use positronic_core::fetch::FetchAs;
+
+#[test]
+fn test_parse_as_honors_the_format_asked_for() {
+    let text = "id;name\n1;ada\n2;bob";
+    assert!(matches!(ContentDetector::parse_as(text, FetchAs::Csv), Some(RichContent::Table(df)) if df.row_count() == 2));
+    assert!(matches!(ContentDetector::parse_as("[1, 2]", FetchAs::Json), Some(RichContent::Json(_))));
+    // Detection would call this text; --as md doesn't.
+    assert!(matches!(ContentDetector::parse_as("plain words", FetchAs::Markdown), Some(RichContent::Markdown(_))));
+    assert!(ContentDetector::parse_as("{broken", FetchAs::Json).is_none());
+    assert!(ContentDetector::parse_as("[1, 2]", FetchAs::Auto).is_none(), "auto leaves it to detection");
+}
+
+#[test]
+fn test_fetched_image_reads_its_dimensions() {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(3, 2).write_to(&mut png, image::ImageFormat::Png).unwrap();
+    let bytes = png.into_inner();
+    let meta = ImageMeta::fetched("image/png", &bytes);
+    assert_eq!((meta.protocol.as_str(), meta.width, meta.height), ("image/png", Some(3), Some(2)));
+    assert_eq!(meta.data_len, bytes.len());
+
+    let unknown = ImageMeta::fetched("image/x-icon", b"not an image");
+    assert_eq!((unknown.width, unknown.height, unknown.data_len), (None, None, 12));
+}
+
+#[test]
+fn test_list_shows_type_size_and_source() {
+    let mut mgr = HolodeckManager::new();
+    assert_eq!(mgr.list_lines(), ["⚡ The Holodeck is empty"]);
+
+    mgr.ingest("name,age\nAlice,30\nBob,25");
+    let id = mgr.ingest_rich(RichContent::Image(ImageMeta::fetched("image/png", &[0; 2048])), "png");
+    mgr.set_tag(id, SOURCE_TAG, "https://example.com/logo.png");
+    assert_eq!(mgr.get(id).unwrap().source(), Some("https://example.com/logo.png"));
+    assert_eq!(mgr.get(1).unwrap().source(), None);
+
+    let lines = mgr.list_lines();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("⚡ Holodeck: 2 entries"), "{}", lines[0]);
+    assert!(lines[1].starts_with("  #1    csv"), "{}", lines[1]);
+    assert!(!lines[1].contains("from"));
+    assert_eq!(lines[2], "  #2    image        2.0 KB  from https://example.com/logo.png");
+}
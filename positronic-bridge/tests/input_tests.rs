@@ -596,6 +596,182 @@ fn test_clear_history() {
     assert!(ed.history().is_empty());
 }
 
+#[test]
+fn test_undo_survives_history_navigation() {
+    let mut ed = InputEditor::new();
+    ed.push_history("cargo test");
+    ed.insert_str("git ");
+    ed.insert_str("status");
+
+    ed.history_up();
+    assert_eq!(ed.value, "cargo test");
+    // The recalled entry starts with no undo of its own...
+    assert!(!ed.can_undo());
+    ed.insert_str(" --all");
+    ed.undo();
+    assert_eq!(ed.value, "cargo test");
+
+    // ...and the draft gets its own chain back.
+    ed.history_down();
+    assert_eq!(ed.value, "git status");
+    ed.undo();
+    assert_eq!(ed.value, "git ");
+    ed.undo();
+    assert_eq!(ed.value, "");
+    ed.redo();
+    assert_eq!(ed.value, "git ");
+}
+
+#[test]
+fn test_undo_does_not_leak_between_recalled_entries() {
+    let mut ed = InputEditor::new();
+    ed.push_history("first");
+    ed.push_history("second");
+    ed.insert_str("draft");
+    ed.undo();
+    assert!(ed.can_redo());
+    ed.insert_str("draft");
+
+    ed.history_up();
+    ed.insert_str("!");
+    ed.history_up();
+    assert_eq!(ed.value, "first");
+    assert!(!ed.can_undo() && !ed.can_redo());
+
+    ed.history_down();
+    ed.history_down();
+    assert_eq!(ed.value, "draft");
+    ed.undo();
+    assert!(ed.is_empty());
+}
+
+#[test]
+fn test_submitting_drops_the_stashed_undo() {
+    let mut ed = InputEditor::new();
+    ed.push_history("ls");
+    ed.insert_str("draft");
+    ed.history_up();
+    ed.push_history("ls -la");
+    ed.history_down();
+    assert!(!ed.can_undo());
+}
+
+fn git_history() -> InputEditor {
+    let mut ed = InputEditor::new();
+    for cmd in ["git status", "ls", "git log", "cargo test", "git status", "git push"] {
+        ed.push_history(cmd);
+    }
+    ed
+}
+
+#[test]
+fn test_prefix_history_visits_only_matches() {
+    let mut ed = git_history();
+    ed.insert_str("git ");
+    ed.history_up_with_prefix("git ");
+    assert_eq!(ed.value, "git push");
+    assert_eq!(ed.history_prefix(), Some("git "));
+    ed.history_up_with_prefix("git ");
+    assert_eq!(ed.value, "git status");
+    // Plain Up keeps the prefix while browsing.
+    ed.history_up();
+    assert_eq!(ed.value, "git log");
+    ed.history_up();
+    assert_eq!(ed.value, "git status");
+    // Oldest match: stays put.
+    ed.history_up();
+    assert_eq!(ed.value, "git status");
+    assert_eq!(ed.history_position(), Some(0));
+
+    ed.history_down();
+    assert_eq!(ed.value, "git log");
+    ed.history_down();
+    assert_eq!(ed.value, "git status");
+    ed.history_down();
+    assert_eq!(ed.value, "git push");
+    // Past the newest match: the typed line comes back exactly.
+    ed.history_down();
+    assert_eq!(ed.value, "git ");
+    assert_eq!(ed.history_position(), None);
+    assert_eq!(ed.history_prefix(), None);
+}
+
+#[test]
+fn test_prefix_history_is_case_sensitive_and_skips_the_typed_line() {
+    let mut ed = git_history();
+    ed.push_history("Git blame");
+    ed.insert_str("Git");
+    ed.history_up_with_prefix("Git");
+    assert_eq!(ed.value, "Git blame");
+    ed.history_up_with_prefix("Git");
+    assert_eq!(ed.value, "Git blame");
+
+    let mut ed = git_history();
+    ed.insert_str("git status");
+    ed.history_up_with_prefix("git status");
+    assert_eq!(ed.value, "git status", "no other entry starts with it");
+    assert_eq!(ed.history_position(), None);
+}
+
+#[test]
+fn test_prefix_without_matches_changes_nothing() {
+    let mut ed = git_history();
+    ed.insert_str("docker ");
+    ed.history_up_with_prefix("docker ");
+    assert_eq!(ed.value, "docker ");
+    assert_eq!(ed.history_position(), None);
+    assert!(ed.can_undo(), "the draft keeps its undo");
+    ed.history_down();
+    assert_eq!(ed.value, "docker ");
+}
+
+#[test]
+fn test_mixed_plain_and_prefix_navigation() {
+    let mut ed = git_history();
+    // Plain Up from an empty line browses everything.
+    ed.history_up();
+    ed.history_up();
+    assert_eq!(ed.value, "git status");
+    assert_eq!(ed.history_prefix(), None);
+    ed.history_up();
+    assert_eq!(ed.value, "cargo test");
+    // Narrowing mid-browse continues from the current entry.
+    ed.history_up_with_prefix("git ");
+    assert_eq!(ed.value, "git log");
+    ed.history_down();
+    assert_eq!(ed.value, "git status");
+    ed.history_down();
+    ed.history_down();
+    assert_eq!(ed.value, "");
+    // Leaving history drops the prefix: the next plain Up is unfiltered.
+    ed.insert_str("x");
+    ed.history_up();
+    assert_eq!(ed.value, "git push");
+    ed.history_up();
+    assert_eq!(ed.value, "git status");
+    ed.history_down();
+    ed.history_down();
+    assert_eq!(ed.value, "x");
+
+    // An empty prefix is plain navigation.
+    ed.history_up_with_prefix("");
+    assert_eq!(ed.value, "git push");
+    assert_eq!(ed.history_prefix(), None);
+}
+
+#[test]
+fn test_prefix_browse_restores_the_drafts_undo() {
+    let mut ed = git_history();
+    ed.insert_str("git");
+    ed.insert_str(" ");
+    ed.history_up_with_prefix("git ");
+    assert!(!ed.can_undo());
+    ed.history_down();
+    assert_eq!(ed.value, "git ");
+    ed.undo();
+    assert_eq!(ed.value, "git");
+}
+
 // ============================================================================
 // Edit Mode
 // ============================================================================
@@ -0,0 +1,597 @@
+// positronic-bridge/tests/inputrc_tests.rs
+//
+// Tests for the readline .inputrc importer: escape decoding, key names,
+// terminal sequences to chords, conditionals, and real-world files with
+// malformed lines that must be skipped, not fatal.
+
+use positronic_bridge::input::{InputEditor, VimMode};
+use positronic_bridge::inputrc::{
+    chord_for, decode, function_action, parse, Context, EditingMode, Inputrc,
+};
+use positronic_bridge::keymap::{Action, Chord};
+
+fn chord(text: &str) -> Chord {
+    Chord::parse(text).unwrap()
+}
+
+fn xterm() -> Context {
+    Context { term: Some("xterm-256color".to_string()) }
+}
+
+fn rc(text: &str) -> Inputrc {
+    parse(text, &xterm())
+}
+
+fn reasons(rc: &Inputrc) -> Vec<(usize, String)> {
+    rc.skipped.iter().map(|s| (s.line, s.reason.clone())).collect()
+}
+
+// ════════════════════════════════════════════════════════════════
+// Escape decoding
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_decode_named_escapes() {
+    assert_eq!(decode(r"\e[A").unwrap(), b"\x1b[A");
+    assert_eq!(decode(r"\E").unwrap(), b"\x1b");
+    assert_eq!(decode(r"\a\b\d\f\n\r\t\v").unwrap(), b"\x07\x08\x7f\x0c\n\r\t\x0b");
+    assert_eq!(decode(r#"\\\"\'"#).unwrap(), b"\\\"'");
+}
+
+#[test]
+fn test_decode_control_and_meta_prefixes() {
+    assert_eq!(decode(r"\C-a").unwrap(), b"\x01");
+    assert_eq!(decode(r"\C-A").unwrap(), b"\x01");
+    assert_eq!(decode(r"\C-?").unwrap(), b"\x7f");
+    assert_eq!(decode(r"\C-_").unwrap(), b"\x1f");
+    assert_eq!(decode(r"\M-f").unwrap(), b"\x1bf");
+    assert_eq!(decode(r"\M-\C-h").unwrap(), b"\x1b\x08");
+    assert_eq!(decode(r"\C-\M-h").unwrap(), b"\x1b\x08");
+    assert_eq!(decode(r"\C-x\C-e").unwrap(), b"\x18\x05");
+}
+
+#[test]
+fn test_decode_octal_and_hex() {
+    assert_eq!(decode(r"\033[C").unwrap(), b"\x1b[C");
+    assert_eq!(decode(r"\177").unwrap(), b"\x7f");
+    assert_eq!(decode(r"\0").unwrap(), b"\x00");
+    assert_eq!(decode(r"\x1b\x7F").unwrap(), b"\x1b\x7f");
+    // At most three octal and two hex digits.
+    assert_eq!(decode(r"\0331").unwrap(), b"\x1b1");
+    assert_eq!(decode(r"\x1b1").unwrap(), b"\x1b1");
+}
+
+#[test]
+fn test_decode_rejects_broken_escapes() {
+    assert!(decode(r"abc\").is_err());
+    assert!(decode(r"\C-").is_err());
+    assert!(decode(r"\x").is_err());
+    assert!(decode(r"\777").is_err());
+    assert!(decode(r"\C-é").is_err());
+}
+
+#[test]
+fn test_decode_passes_utf8_through() {
+    assert_eq!(decode("é").unwrap(), "é".as_bytes());
+}
+
+// ════════════════════════════════════════════════════════════════
+// Sequences to chords
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_control_bytes_become_ctrl_chords() {
+    assert_eq!(chord_for(b"\x01"), Some(chord("ctrl+a")));
+    assert_eq!(chord_for(b"\x17"), Some(chord("ctrl+w")));
+    assert_eq!(chord_for(b"\x08"), Some(chord("ctrl+h")));
+    assert_eq!(chord_for(b"\x1d"), Some(chord("ctrl+]")));
+    assert_eq!(chord_for(b"\x00"), Some(chord("ctrl+space")));
+    assert_eq!(chord_for(b"\t"), Some(chord("tab")));
+    assert_eq!(chord_for(b"\r"), Some(chord("enter")));
+    assert_eq!(chord_for(b"\x7f"), Some(chord("backspace")));
+    assert_eq!(chord_for(b"\x1b"), Some(chord("escape")));
+}
+
+#[test]
+fn test_escape_prefix_is_alt() {
+    assert_eq!(chord_for(b"\x1bf"), Some(chord("alt+f")));
+    assert_eq!(chord_for(b"\x1bB"), Some(chord("alt+b")));
+    assert_eq!(chord_for(b"\x1b\x7f"), Some(chord("alt+backspace")));
+    assert_eq!(chord_for(b"\x1b\x08"), Some(chord("ctrl+alt+h")));
+    assert_eq!(chord_for(b"\x1b.".as_slice()), Some(chord("alt+.")));
+    // rxvt's Alt+arrow: ESC before the arrow's own sequence.
+    assert_eq!(chord_for(b"\x1b\x1b[C"), Some(chord("alt+right")));
+    assert_eq!(chord_for(b"\x1b\x1bf"), None);
+}
+
+#[test]
+fn test_xterm_cursor_keys_and_modifiers() {
+    assert_eq!(chord_for(b"\x1b[A"), Some(chord("up")));
+    assert_eq!(chord_for(b"\x1b[1;5C"), Some(chord("ctrl+right")));
+    assert_eq!(chord_for(b"\x1b[1;5D"), Some(chord("ctrl+left")));
+    assert_eq!(chord_for(b"\x1b[1;3D"), Some(chord("alt+left")));
+    assert_eq!(chord_for(b"\x1b[1;2A"), Some(chord("shift+up")));
+    assert_eq!(chord_for(b"\x1b[1;6C"), Some(chord("ctrl+shift+right")));
+    assert_eq!(chord_for(b"\x1b[5C"), Some(chord("ctrl+right")));
+    assert_eq!(chord_for(b"\x1b[H"), Some(chord("home")));
+    assert_eq!(chord_for(b"\x1b[1;5F"), Some(chord("ctrl+end")));
+    assert_eq!(chord_for(b"\x1b[Z"), Some(chord("shift+tab")));
+}
+
+#[test]
+fn test_tilde_keys() {
+    assert_eq!(chord_for(b"\x1b[1~"), Some(chord("home")));
+    assert_eq!(chord_for(b"\x1b[7~"), Some(chord("home")));
+    assert_eq!(chord_for(b"\x1b[4~"), Some(chord("end")));
+    assert_eq!(chord_for(b"\x1b[2~"), Some(chord("insert")));
+    assert_eq!(chord_for(b"\x1b[3~"), Some(chord("delete")));
+    assert_eq!(chord_for(b"\x1b[3;5~"), Some(chord("ctrl+delete")));
+    assert_eq!(chord_for(b"\x1b[5~"), Some(chord("pageup")));
+    assert_eq!(chord_for(b"\x1b[6~"), Some(chord("pagedown")));
+    assert_eq!(chord_for(b"\x1b[15~"), Some(chord("f5")));
+    assert_eq!(chord_for(b"\x1b[24~"), Some(chord("f12")));
+    assert_eq!(chord_for(b"\x1b[16~"), None);
+}
+
+#[test]
+fn test_application_mode_and_rxvt_keys() {
+    assert_eq!(chord_for(b"\x1bOA"), Some(chord("up")));
+    assert_eq!(chord_for(b"\x1bOH"), Some(chord("home")));
+    assert_eq!(chord_for(b"\x1bOP"), Some(chord("f1")));
+    assert_eq!(chord_for(b"\x1bOc"), Some(chord("ctrl+right")));
+    assert_eq!(chord_for(b"\x1b[d"), Some(chord("shift+left")));
+}
+
+#[test]
+fn test_multi_key_sequences_are_not_chords() {
+    assert_eq!(chord_for(b"\x18\x05"), None);
+    assert_eq!(chord_for(b"ab"), None);
+    assert_eq!(chord_for(b"\x1b[1;5;7C"), None);
+    assert_eq!(chord_for(b"\x1b[99X"), None);
+    assert_eq!(chord_for(b""), None);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Functions
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_functions_map_to_actions() {
+    assert_eq!(function_action("forward-word"), Some(Action::WordRight));
+    assert_eq!(function_action("backward-kill-word"), Some(Action::KillWordBack));
+    assert_eq!(function_action("unix-word-rubout"), Some(Action::KillWordBack));
+    assert_eq!(function_action("beginning-of-line"), Some(Action::LineStart));
+    assert_eq!(function_action("menu-complete"), Some(Action::TabComplete));
+    assert_eq!(function_action("history-search-backward"), Some(Action::HistoryUp));
+    assert_eq!(function_action("Kill-Line"), Some(Action::KillToEnd));
+    assert_eq!(function_action("yank-last-arg"), None);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Files
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_settings_and_bindings() {
+    let rc = rc(r#"
+set editing-mode vi
+set completion-ignore-case on
+"\e[1;5C": forward-word
+"\e[1;5D": backward-word
+"\C-w": backward-kill-word
+"#);
+    assert_eq!(rc.editing_mode, Some(EditingMode::Vi));
+    assert_eq!(rc.completion_ignore_case, Some(true));
+    assert_eq!(rc.action_for(&chord("ctrl+right")), Some(Action::WordRight));
+    assert_eq!(rc.action_for(&chord("ctrl+left")), Some(Action::WordLeft));
+    assert_eq!(rc.action_for(&chord("ctrl+w")), Some(Action::KillWordBack));
+    assert!(rc.skipped.is_empty(), "{:?}", rc.skipped);
+}
+
+#[test]
+fn test_named_key_bindings() {
+    let rc = rc("Control-u: unix-line-discard\nMeta-Rubout: backward-kill-word\nC-a: beginning-of-line\nM-f: forward-word\nTAB: menu-complete\n");
+    assert_eq!(rc.action_for(&chord("ctrl+u")), Some(Action::KillToStart));
+    assert_eq!(rc.action_for(&chord("alt+backspace")), Some(Action::KillWordBack));
+    assert_eq!(rc.action_for(&chord("ctrl+a")), Some(Action::LineStart));
+    assert_eq!(rc.action_for(&chord("alt+f")), Some(Action::WordRight));
+    assert_eq!(rc.action_for(&chord("tab")), Some(Action::TabComplete));
+}
+
+#[test]
+fn test_unsupported_lines_are_reported_not_fatal() {
+    let rc = rc(r#"
+set bell-style none
+set show-all-if-ambiguous on
+"\C-x\C-e": edit-and-execute-command
+"\C-xp": "PATH=${PATH}\e\C-e\C-a\ef\C-f"
+"\e.": yank-last-arg
+"\C-l": clear-screen
+"#);
+    assert_eq!(rc.bindings, vec![(chord("ctrl+l"), Action::ClearScreen)]);
+    assert_eq!(
+        reasons(&rc),
+        vec![
+            (2, "unsupported variable".to_string()),
+            (3, "unsupported variable".to_string()),
+            (4, "edit-and-execute-command is not supported".to_string()),
+            (5, "macros are not supported".to_string()),
+            (6, "yank-last-arg is not supported".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_malformed_lines_are_skipped() {
+    let rc = rc(r#"
+"\e[1;5C forward-word
+"\e[1;5C" forward-word
+: forward-word
+this is not a binding
+set
+set editing-mode emacsish
+set completion-ignore-case maybe
+"\C-k":
+"\C-x\C-e": kill-line
+"a": forward-char
+Hyper-x: forward-char
+"\e[1;5D": backward-word
+"#);
+    assert_eq!(rc.bindings, vec![(chord("ctrl+left"), Action::WordLeft)]);
+    assert_eq!(rc.editing_mode, None);
+    assert_eq!(rc.completion_ignore_case, None);
+    let reasons = reasons(&rc);
+    assert_eq!(reasons.len(), 11, "{:?}", reasons);
+    assert_eq!(reasons[0], (2, "unterminated key sequence".to_string()));
+    assert_eq!(reasons[1], (3, "expected ':' after the key sequence".to_string()));
+    assert_eq!(reasons[2].1, "not a setting or key binding");
+    assert_eq!(reasons[3].1, "not a setting or key binding");
+    assert_eq!(reasons[4].1, "set without a variable");
+    assert_eq!(reasons[5].1, "expected vi or emacs");
+    assert_eq!(reasons[6].1, "expected on or off");
+    assert_eq!(reasons[7].1, "no function named");
+    assert_eq!(reasons[8].1, "not a single key");
+    assert_eq!(reasons[9].1, "binding it would shadow typing");
+    assert_eq!(reasons[10].1, "unknown key name Hyper-x");
+}
+
+#[test]
+fn test_keywords_and_values_ignore_case() {
+    let rc = rc("SET Editing-Mode VI\nset completion-ignore-case On\n");
+    assert_eq!(rc.editing_mode, Some(EditingMode::Vi));
+    assert_eq!(rc.completion_ignore_case, Some(true));
+}
+
+#[test]
+fn test_first_binding_per_action_wins() {
+    // One key, a sequence per terminal; and a second key for the action.
+    let rc = rc(r#"
+"\e[1;5C": forward-word
+"\e[5C": forward-word
+"\eOc": forward-word
+"\ef": forward-word
+"#);
+    assert_eq!(rc.bindings, vec![(chord("ctrl+right"), Action::WordRight)]);
+    assert_eq!(reasons(&rc), vec![(5, "word_right is already on ctrl+right".to_string())]);
+}
+
+#[test]
+fn test_later_binding_takes_the_key() {
+    let rc = rc("\"\\C-w\": unix-word-rubout\n\"\\C-w\": kill-line\n");
+    assert_eq!(rc.bindings, vec![(chord("ctrl+w"), Action::KillToEnd)]);
+}
+
+#[test]
+fn test_comments_blank_lines_and_indentation() {
+    let rc = rc("# comment\n\n   # indented comment\n\t\"\\C-u\": unix-line-discard\n");
+    assert_eq!(rc.bindings, vec![(chord("ctrl+u"), Action::KillToStart)]);
+    assert!(rc.skipped.is_empty());
+}
+
+// ════════════════════════════════════════════════════════════════
+// Conditionals
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_term_conditionals() {
+    let text = r#"
+$if term=xterm
+"\e[1;5C": forward-word
+$else
+"\eOc": forward-word
+$endif
+$if term=rxvt
+"\e[1;5D": backward-word
+$endif
+"#;
+    let rc = parse(text, &xterm());
+    assert_eq!(rc.bindings, vec![(chord("ctrl+right"), Action::WordRight)]);
+
+    let rc = parse(text, &Context { term: Some("rxvt-unicode".to_string()) });
+    assert_eq!(
+        rc.bindings,
+        vec![(chord("ctrl+right"), Action::WordRight), (chord("ctrl+left"), Action::WordLeft)]
+    );
+
+    let rc = parse(text, &Context::default());
+    assert_eq!(rc.bindings, vec![(chord("ctrl+right"), Action::WordRight)]);
+}
+
+#[test]
+fn test_whole_term_name_matches_too() {
+    let rc = parse("$if term=xterm-256color\n\"\\C-u\": kill-line\n$endif\n", &xterm());
+    assert_eq!(rc.bindings.len(), 1);
+}
+
+#[test]
+fn test_mode_follows_editing_mode_so_far() {
+    let text = r#"
+$if mode=emacs
+"\C-a": beginning-of-line
+$endif
+set editing-mode vi
+$if mode=vi
+"\C-l": clear-screen
+$else
+"\C-e": end-of-line
+$endif
+"#;
+    let rc = rc(text);
+    assert_eq!(
+        rc.bindings,
+        vec![(chord("ctrl+a"), Action::LineStart), (chord("ctrl+l"), Action::ClearScreen)]
+    );
+}
+
+#[test]
+fn test_application_and_version_tests() {
+    let text = r#"
+$if Bash
+Space: magic-space
+"\C-xq": "fc -s\n"
+$endif
+$if Positronic
+"\C-u": unix-line-discard
+$endif
+$if version >= 8.0
+"\C-k": kill-line
+$endif
+$if completion-ignore-case == on
+"\C-w": unix-word-rubout
+$endif
+"#;
+    let rc = rc(text);
+    assert_eq!(rc.bindings, vec![(chord("ctrl+u"), Action::KillToStart), (chord("ctrl+k"), Action::KillToEnd)]);
+    // Lines in a false branch are not even reported.
+    assert!(rc.skipped.is_empty(), "{:?}", rc.skipped);
+}
+
+#[test]
+fn test_nested_conditionals() {
+    let text = r#"
+$if term=xterm
+  $if mode=emacs
+    "\C-a": beginning-of-line
+  $else
+    "\C-e": end-of-line
+  $endif
+$else
+  $if mode=emacs
+    "\C-k": kill-line
+  $else
+    "\C-u": unix-line-discard
+  $endif
+$endif
+"#;
+    let rc = rc(text);
+    assert_eq!(rc.bindings, vec![(chord("ctrl+a"), Action::LineStart)]);
+}
+
+#[test]
+fn test_unbalanced_conditionals_are_reported() {
+    let rc = rc("$endif\n$else\n$if term=xterm\n\"\\C-a\": beginning-of-line\n");
+    assert_eq!(rc.bindings.len(), 1);
+    let reasons = reasons(&rc);
+    assert_eq!(reasons[0], (1, "$endif without $if".to_string()));
+    assert_eq!(reasons[1], (2, "$else without $if".to_string()));
+    assert_eq!(reasons[2], (4, "$if without $endif".to_string()));
+}
+
+#[test]
+fn test_include_and_unknown_directives() {
+    let rc = rc("$include /etc/inputrc\n$frobnicate\n$if term=dumb\n$include ~/.more\n$endif\n");
+    assert_eq!(
+        reasons(&rc),
+        vec![(1, "included files are not followed".to_string()), (2, "unknown directive".to_string())]
+    );
+}
+
+// ════════════════════════════════════════════════════════════════
+// Real-world files
+// ════════════════════════════════════════════════════════════════
+
+/// Debian's /etc/inputrc, trimmed.
+const DEBIAN: &str = r#"# /etc/inputrc - global inputrc for libreadline
+# See readline(3readline) and `info rluserman' for more information.
+
+# Be 8 bit clean.
+set input-meta on
+set output-meta on
+
+# To allow the use of 8bit-characters like the german umlauts, uncomment
+# the line below. However this makes the meta key not work as a meta key,
+# which is annoying to those which don't need to type in 8-bit characters.
+
+# set convert-meta off
+
+# try to enable the application keypad when it is called.  Some systems
+# need this to enable the arrow keys.
+# set enable-keypad on
+
+# see /usr/share/doc/bash/inputrc.arrows for other codes of arrow keys
+
+# do not bell on tab-completion
+# set bell-style none
+# set bell-style visible
+
+# some defaults / modifications for the emacs mode
+$if mode=emacs
+
+# allow the use of the Home/End keys
+"\e[1~": beginning-of-line
+"\e[4~": end-of-line
+
+# allow the use of the Delete/Insert keys
+"\e[3~": delete-char
+"\e[2~": quoted-insert
+
+# mappings for "page up" and "page down" to step to the beginning/end
+# of the history
+# "\e[5~": beginning-of-history
+# "\e[6~": end-of-history
+
+# alternate mappings for "page up" and "page down" to search the history
+# "\e[5~": history-search-backward
+# "\e[6~": history-search-forward
+
+# mappings for Ctrl-left-arrow and Ctrl-right-arrow for word moving
+"\e[1;5C": forward-word
+"\e[1;5D": backward-word
+"\e[5C": forward-word
+"\e[5D": backward-word
+"\e\e[C": forward-word
+"\e\e[D": backward-word
+
+$if term=rxvt
+"\e[7~": beginning-of-line
+"\e[8~": end-of-line
+"\eOc": forward-word
+"\eOd": backward-word
+$endif
+
+# for non RH/Debian xterm, can't hurt for RH/Debian xterm
+# "\eOH": beginning-of-line
+# "\eOF": end-of-line
+
+# for freebsd console
+# "\e[H": beginning-of-line
+# "\e[F": end-of-line
+
+$endif
+"#;
+
+#[test]
+fn test_debian_global_inputrc() {
+    let rc = rc(DEBIAN);
+    assert_eq!(
+        rc.bindings,
+        vec![
+            (chord("home"), Action::LineStart),
+            (chord("end"), Action::LineEnd),
+            (chord("ctrl+right"), Action::WordRight),
+            (chord("ctrl+left"), Action::WordLeft),
+        ]
+    );
+    let skipped: Vec<&str> = rc.skipped.iter().map(|s| s.reason.as_str()).collect();
+    assert_eq!(
+        skipped,
+        vec![
+            "unsupported variable",
+            "unsupported variable",
+            "delete-char is not supported",
+            "quoted-insert is not supported",
+            "word_right is already on ctrl+right",
+            "word_left is already on ctrl+left",
+        ]
+    );
+}
+
+/// A typical personal ~/.inputrc.
+const PERSONAL: &str = r#"$include /etc/inputrc
+
+set editing-mode vi
+set show-mode-in-prompt on
+set vi-ins-mode-string \1\e[6 q\2
+set vi-cmd-mode-string \1\e[2 q\2
+set completion-ignore-case On
+set completion-map-case on
+set menu-complete-display-prefix on
+set colored-stats On
+set mark-symlinked-directories on
+
+TAB: menu-complete
+"\e[Z": menu-complete-backward
+
+# Up/Down search history by prefix
+"\e[A": history-search-backward
+"\e[B": history-search-forward
+
+$if mode=vi
+set keymap vi-command
+"j": history-search-forward
+"k": history-search-backward
+set keymap vi-insert
+"\C-l": clear-screen
+"\C-w": backward-kill-word
+"\C-a": beginning-of-line
+"\C-e": end-of-line
+$endif
+
+$if Bash
+  # Expand !! and friends on space
+  Space: magic-space
+$endif
+"#;
+
+#[test]
+fn test_personal_vi_inputrc() {
+    let rc = rc(PERSONAL);
+    assert_eq!(rc.editing_mode, Some(EditingMode::Vi));
+    assert_eq!(rc.completion_ignore_case, Some(true));
+    assert_eq!(rc.action_for(&chord("tab")), Some(Action::TabComplete));
+    assert_eq!(rc.action_for(&chord("up")), Some(Action::HistoryUp));
+    assert_eq!(rc.action_for(&chord("down")), Some(Action::HistoryDown));
+    assert_eq!(rc.action_for(&chord("ctrl+l")), Some(Action::ClearScreen));
+    assert_eq!(rc.action_for(&chord("ctrl+w")), Some(Action::KillWordBack));
+    assert_eq!(rc.action_for(&chord("ctrl+a")), Some(Action::LineStart));
+    assert_eq!(rc.action_for(&chord("ctrl+e")), Some(Action::LineEnd));
+    assert_eq!(rc.bindings.len(), 7);
+    // The vi-command "j" and "k" would shadow typing; Space is Bash-only.
+    let skipped: Vec<&str> = rc.skipped.iter().map(|s| s.reason.as_str()).collect();
+    assert!(skipped.contains(&"included files are not followed"));
+    assert!(skipped.contains(&"menu-complete-backward is not supported"));
+    assert_eq!(skipped.iter().filter(|r| **r == "binding it would shadow typing").count(), 2);
+    assert_eq!(skipped.iter().filter(|r| **r == "unsupported variable").count(), 9);
+}
+
+#[test]
+fn test_crlf_and_trailing_whitespace() {
+    let rc = rc("set editing-mode vi  \r\n\"\\C-u\": unix-line-discard   \r\n");
+    assert_eq!(rc.editing_mode, Some(EditingMode::Vi));
+    assert_eq!(rc.bindings, vec![(chord("ctrl+u"), Action::KillToStart)]);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Applying
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn test_configure_sets_the_editor_mode() {
+    let mut editor = InputEditor::new();
+    rc("set editing-mode vi\n").configure(&mut editor);
+    assert_eq!(editor.vim_mode(), VimMode::Normal);
+    rc("set editing-mode emacs\n").configure(&mut editor);
+    assert_eq!(editor.vim_mode(), VimMode::Disabled);
+    rc("").configure(&mut editor);
+    assert_eq!(editor.vim_mode(), VimMode::Disabled);
+}
+
+#[test]
+fn test_summary_lists_bindings_and_skips() {
+    let rc = rc("set editing-mode vi\n\"\\C-w\": backward-kill-word\nset bell-style none\n");
+    let lines = rc.summary("~/.inputrc");
+    assert_eq!(lines[0], "⌨️  Imported ~/.inputrc: 1 binding, editing-mode vi");
+    assert!(lines[1].contains("ctrl+w") && lines[1].contains("kill_word_back"));
+    assert_eq!(lines[2], "  Skipped 1 line:");
+    assert_eq!(lines[3], "    line 3: set bell-style none (unsupported variable)");
+}
@@ -0,0 +1,226 @@
+// positronic-bridge/tests/key_help_tests.rs
+//
+// Tests for the F1 / `!keys show` reference: the sheet generated from a
+// keymap and the terminal's modes (golden), filtering, and key routing in
+// the open overlay.
+
+use positronic_bridge::key_help::{self, Category, KeyHelp, KeyHelpOutcome, LineKind, Note};
+use positronic_bridge::keymap::{Action, Chord, KeyName, Keymap};
+use positronic_core::term::modes::ModeSnapshot;
+
+fn chord(text: &str) -> Chord {
+    Chord::parse(text).unwrap()
+}
+
+/// A config that moves, unbinds and collides a few bindings.
+fn overridden() -> Keymap {
+    Keymap::from_overrides([
+        (Action::Eof, "ctrl+shift+c"),
+        (Action::Paste, "ctrl+z"),
+        (Action::ClearScreen, "none"),
+    ])
+}
+
+fn alt_screen() -> ModeSnapshot {
+    ModeSnapshot { alt_screen: true, ..ModeSnapshot::default() }
+}
+
+fn texts(keymap: &Keymap, modes: &ModeSnapshot, filter: &str) -> Vec<String> {
+    let sections = key_help::sheet(keymap, modes, filter);
+    key_help::lines(&sections, None).into_iter().map(|l| l.text).collect()
+}
+
+fn typed(help: &mut KeyHelp, text: &str) -> KeyHelpOutcome {
+    let c = text.chars().next().unwrap();
+    help.key(Some(Chord::from_key(KeyName::Char(c), false, false, false)), Some(text), &Keymap::default())
+}
+
+fn press(help: &mut KeyHelp, key: &str) -> KeyHelpOutcome {
+    help.key(Some(chord(key)), None, &Keymap::default())
+}
+
+// ════════════════════════════════════════════════════════════════
+// The sheet
+// ════════════════════════════════════════════════════════════════
+
+const GOLDEN: &str = "\
+Editing
+  ctrl+shift+c     Copy the visible terminal  ⚠ shadowed by eof
+  ctrl+shift+a     Copy the visible terminal with colors and styles
+  ctrl+z           Paste, or pick from the clipboard history  · config  ⚠ shadows suspending the running command
+  tab              Complete the input
+  left             Cursor left
+  right            Cursor right
+  home             Cursor to line start
+  end              Cursor to line end
+  ctrl+left        Cursor back one word
+  ctrl+right       Cursor forward one word
+  ctrl+w           Delete the word before the cursor
+  alt+d            Delete the word after the cursor
+  ctrl+u           Delete to line start
+  ctrl+k           Delete to line end
+History
+  up               Previous history entry
+  down             Next history entry
+Blocks
+  ctrl+shift+o     Open the last link on screen
+  ctrl+shift+e     Open the first error in the editor
+  shift+pageup     Scroll back one page  ⚠ goes to the full-screen app
+  shift+pagedown   Scroll forward one page  ⚠ goes to the full-screen app
+  ctrl+home        Scroll to the top of the scrollback  ⚠ goes to the full-screen app
+  ctrl+end         Scroll to the bottom and follow output  ⚠ goes to the full-screen app
+  ctrl+up          Focus the previous command block
+  ctrl+down        Focus the next command block, or the input line
+  ctrl+o           Go back to the previous place in the jump list
+  ctrl+i           Go forward again in the jump list
+  ctrl+shift+b     Put the focused block's command in the input line to edit
+  ctrl+shift+r     Run the focused block's command again
+Panes
+  ctrl+shift+s     Show or hide the oscilloscope pane
+  ctrl+]           Leave !io console for the shell
+Search
+  ctrl+shift+p     Open the command palette
+  ctrl+r           Search history
+  ctrl+p           Find a file in the project and insert, edit, view or copy it
+System
+  unbound          Clear the screen  · config
+  ctrl+c           Interrupt the running command, or copy the selection
+  ctrl+shift+c     Send Ctrl+D to the shell  · config
+  f1               Show or hide this key binding reference
+";
+
+#[test]
+fn sheet_golden_with_overrides_on_the_alternate_screen() {
+    let actual = texts(&overridden(), &alt_screen(), "").join("\n");
+    assert_eq!(actual, GOLDEN.trim_end(), "\n{}", actual);
+}
+
+#[test]
+fn sheet_groups_every_action_once() {
+    let sections = key_help::sheet(&Keymap::default(), &ModeSnapshot::default(), "");
+    let categories: Vec<Category> = sections.iter().map(|s| s.category).collect();
+    assert_eq!(categories, Category::ALL);
+    let rows: Vec<Action> = sections.iter().flat_map(|s| s.rows.iter().map(|r| r.action)).collect();
+    assert_eq!(rows.len(), Action::ALL.len());
+    for action in Action::ALL {
+        assert!(rows.contains(action), "{} missing", action);
+    }
+    assert!(sections.iter().flat_map(|s| &s.rows).all(|r| r.notes.is_empty() && r.effective()));
+}
+
+#[test]
+fn conflicts_and_modes_are_annotated() {
+    let sections = key_help::sheet(&overridden(), &alt_screen(), "");
+    let row = |action| sections.iter().flat_map(|s| &s.rows).find(|r| r.action == action).unwrap().clone();
+
+    assert_eq!(row(Action::Copy).notes, vec![Note::ShadowedBy(Action::Eof)]);
+    assert!(row(Action::Copy).conflicts() && !row(Action::Copy).effective());
+    assert!(row(Action::Eof).effective());
+    assert_eq!(row(Action::Paste).notes, vec![Note::Shadows("suspending the running command")]);
+    assert!(row(Action::Paste).effective());
+    assert_eq!(row(Action::ClearScreen).chord, None);
+    assert!(!row(Action::ClearScreen).effective());
+
+    // Full-screen apps get the paging keys; outside one they scroll.
+    assert_eq!(row(Action::ScrollPageUp).notes, vec![Note::ToApp]);
+    assert_eq!(row(Action::ScrollTop).notes, vec![Note::ToApp]);
+    assert!(row(Action::BlockPrev).notes.is_empty());
+    let normal = key_help::sheet(&overridden(), &ModeSnapshot::default(), "");
+    assert!(normal.iter().flat_map(|s| &s.rows).all(|r| !r.notes.contains(&Note::ToApp)));
+}
+
+#[test]
+fn filter_narrows_and_ranks_rows() {
+    let lines = texts(&Keymap::default(), &ModeSnapshot::default(), "kill");
+    assert_eq!(lines[0], "Editing");
+    assert_eq!(lines.len(), 5, "{:?}", lines);
+    assert!(lines[1..].iter().all(|l| l.contains("Delete")), "{:?}", lines);
+
+    // Chords match too.
+    let lines = texts(&Keymap::default(), &ModeSnapshot::default(), "ctrl+shift+r");
+    assert!(lines.iter().any(|l| l.contains("Run the focused block's command again")), "{:?}", lines);
+
+    assert!(key_help::sheet(&Keymap::default(), &ModeSnapshot::default(), "zzzz").is_empty());
+}
+
+#[test]
+fn pressed_chord_lights_its_row() {
+    let sections = key_help::sheet(&Keymap::default(), &ModeSnapshot::default(), "");
+    let lines = key_help::lines(&sections, Some(&chord("ctrl+r")));
+    let lit: Vec<&str> = lines
+        .iter()
+        .filter(|l| matches!(l.kind, LineKind::Row { lit: true, .. }))
+        .map(|l| l.text.as_str())
+        .collect();
+    assert_eq!(lit.len(), 1);
+    assert!(lit[0].contains("Search history"), "{}", lit[0]);
+}
+
+#[test]
+fn window_follows_the_lit_row_until_scrolled() {
+    assert_eq!(key_help::window(40, 10, None, None), 0);
+    assert_eq!(key_help::window(40, 10, None, Some(5)), 0);
+    assert_eq!(key_help::window(40, 10, None, Some(25)), 16);
+    assert_eq!(key_help::window(40, 10, Some(20), Some(5)), 20);
+    assert_eq!(key_help::window(40, 10, Some(100), None), 30);
+    assert_eq!(key_help::window(5, 10, Some(3), None), 0);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Keys
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn typing_filters_and_escape_clears_then_closes() {
+    let mut help = KeyHelp::default();
+    assert_eq!(typed(&mut help, "s"), KeyHelpOutcome::Open);
+    typed(&mut help, "c");
+    typed(&mut help, "r");
+    assert_eq!(help.filter, "scr");
+    assert_eq!(press(&mut help, "backspace"), KeyHelpOutcome::Open);
+    assert_eq!(help.filter, "sc");
+    assert!(help.title().contains("filter: sc"));
+
+    assert_eq!(press(&mut help, "escape"), KeyHelpOutcome::Open);
+    assert!(help.filter.is_empty());
+    assert_eq!(press(&mut help, "escape"), KeyHelpOutcome::Closed);
+}
+
+#[test]
+fn chords_are_explained_not_run() {
+    let keymap = Keymap::default();
+    let mut help = KeyHelp::default();
+    assert_eq!(press(&mut help, "ctrl+l"), KeyHelpOutcome::Open);
+    assert_eq!(help.pressed, Some(chord("ctrl+l")));
+    assert!(help.filter.is_empty());
+    assert!(help.footer(&keymap).starts_with("ctrl+l → Clear the screen (clear_screen)"), "{}", help.footer(&keymap));
+
+    press(&mut help, "ctrl+alt+q");
+    assert!(help.footer(&keymap).starts_with("ctrl+alt+q is not bound"), "{}", help.footer(&keymap));
+
+    // The reference's own chord closes it.
+    assert_eq!(press(&mut help, "f1"), KeyHelpOutcome::Closed);
+    let rebound = Keymap::from_overrides([(Action::KeyHelp, "ctrl+shift+k")]);
+    let mut help = KeyHelp::default();
+    assert_eq!(help.key(Some(chord("f1")), None, &rebound), KeyHelpOutcome::Open);
+    assert_eq!(help.key(Some(chord("ctrl+shift+k")), None, &rebound), KeyHelpOutcome::Closed);
+}
+
+#[test]
+fn paging_scrolls_and_typing_follows_the_lit_row_again() {
+    let mut help = KeyHelp::default();
+    press(&mut help, "pagedown");
+    press(&mut help, "pagedown");
+    assert_eq!(help.top, Some(2 * key_help::PAGE_STEP));
+    press(&mut help, "pageup");
+    assert_eq!(help.top, Some(key_help::PAGE_STEP));
+    press(&mut help, "ctrl+r");
+    assert_eq!(help.top, None);
+
+    let view = help.view(&Keymap::default(), &ModeSnapshot::default());
+    let plain = view.plain(12);
+    assert_eq!(plain.len(), 12);
+    assert_eq!(plain[0], help.title());
+    assert!(plain[1..11].iter().any(|l| l.contains("Search history")), "{:?}", plain);
+    assert_eq!(plain[11], help.footer(&Keymap::default()));
+}
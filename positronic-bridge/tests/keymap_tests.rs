@@ -0,0 +1,199 @@
+//! Keymap tests: chord parsing, user overrides and conflict detection.
+
+use positronic_bridge::keymap::{
+    Action, BindingSource, Chord, ChordError, KeyName, Keymap, NamedKey,
+};
+
+fn chord(text: &str) -> Chord {
+    Chord::parse(text).unwrap()
+}
+
+// ════════════════════════════════════════════════════════════════
+// Chord parser
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn parse_modifiers_and_letters() {
+    let c = chord("ctrl+shift+c");
+    assert!(c.ctrl && c.shift && !c.alt);
+    assert_eq!(c.key, KeyName::Char('c'));
+    assert_eq!(chord("Ctrl+Shift+C"), c);
+    assert_eq!(chord("shift+ctrl+c"), c);
+    assert_eq!(chord("C-S-c"), c);
+    assert_eq!(chord("  control + shift + c ").key, KeyName::Char('c'));
+}
+
+#[test]
+fn parse_named_and_function_keys() {
+    assert_eq!(chord("Enter").key, KeyName::Named(NamedKey::Enter));
+    assert_eq!(chord("esc").key, KeyName::Named(NamedKey::Escape));
+    assert_eq!(chord("alt+PageUp").key, KeyName::Named(NamedKey::PageUp));
+    assert_eq!(chord("F5").key, KeyName::Named(NamedKey::F(5)));
+    assert_eq!(chord("ctrl+f12").key, KeyName::Named(NamedKey::F(12)));
+}
+
+#[test]
+fn parse_separator_as_key() {
+    assert_eq!(chord("ctrl++").key, KeyName::Char('+'));
+    assert_eq!(chord("ctrl+-").key, KeyName::Char('-'));
+    assert_eq!(chord("+").key, KeyName::Char('+'));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(Chord::parse(""), Err(ChordError::Empty));
+    assert_eq!(Chord::parse("hyper+x"), Err(ChordError::UnknownModifier("hyper".to_string())));
+    assert_eq!(Chord::parse("ctrl+banana"), Err(ChordError::UnknownKey("banana".to_string())));
+    assert_eq!(Chord::parse("ctrl+f13"), Err(ChordError::UnknownKey("f13".to_string())));
+    assert_eq!(Chord::parse("ctrl+"), Err(ChordError::MissingKey));
+}
+
+#[test]
+fn display_round_trips() {
+    for text in ["ctrl+shift+c", "alt+enter", "f5", "ctrl++", "pageup"] {
+        assert_eq!(chord(&chord(text).to_string()), chord(text), "{}", text);
+    }
+    assert_eq!(chord("Shift+Ctrl+C").to_string(), "ctrl+shift+c");
+}
+
+#[test]
+fn event_chords_are_case_insensitive() {
+    // Shift+C arrives as 'C'.
+    let pressed = Chord::from_key(KeyName::Char('C'), true, false, true);
+    assert_eq!(pressed, chord("ctrl+shift+c"));
+}
+
+// ════════════════════════════════════════════════════════════════
+// Defaults and overrides
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn defaults_cover_every_action_without_conflicts() {
+    let keymap = Keymap::default();
+    for &action in Action::ALL {
+        assert!(keymap.chord_for(action).is_some(), "{} unbound", action);
+        assert_eq!(Action::from_name(action.name()), Some(action));
+    }
+    assert!(keymap.warnings().is_empty(), "{:?}", keymap.warnings());
+    assert_eq!(keymap.action_for(&chord("ctrl+l")), Some(Action::ClearScreen));
+    assert_eq!(keymap.action_for(&chord("ctrl+c")), Some(Action::Interrupt));
+    assert_eq!(keymap.action_for(&chord("ctrl+shift+c")), Some(Action::Copy));
+    assert_eq!(keymap.action_for(&chord("ctrl+x")), None);
+}
+
+#[test]
+fn user_override_replaces_default() {
+    let keymap = Keymap::from_overrides([(Action::ClearScreen, "ctrl+k")]);
+    assert_eq!(keymap.action_for(&chord("ctrl+k")), Some(Action::ClearScreen));
+    assert_eq!(keymap.action_for(&chord("ctrl+l")), None);
+    let binding = keymap.bindings().iter().find(|b| b.action == Action::ClearScreen).unwrap();
+    assert_eq!(binding.source, BindingSource::User);
+    // Untouched actions keep their defaults.
+    assert_eq!(keymap.chord_for(Action::Copy), Some(chord("ctrl+shift+c")));
+}
+
+#[test]
+fn user_override_beats_default_on_same_chord() {
+    let keymap = Keymap::from_overrides([(Action::SearchOpen, "ctrl+l")]);
+    assert_eq!(keymap.action_for(&chord("ctrl+l")), Some(Action::SearchOpen));
+    assert!(keymap
+        .warnings()
+        .iter()
+        .any(|w| w.contains("ctrl+l") && w.contains("clear_screen") && w.contains("search_open wins")));
+}
+
+#[test]
+fn unbind_with_none() {
+    let keymap = Keymap::from_overrides([(Action::FollowLink, "none")]);
+    assert_eq!(keymap.chord_for(Action::FollowLink), None);
+    assert_eq!(keymap.action_for(&chord("ctrl+shift+o")), None);
+    assert!(keymap.lines().iter().any(|l| l.contains("follow_link") && l.contains("(unbound)")));
+}
+
+#[test]
+fn invalid_override_keeps_default_and_warns() {
+    let keymap = Keymap::from_overrides([(Action::Paste, "ctrl+shift+banana")]);
+    assert_eq!(keymap.chord_for(Action::Paste), Some(chord("ctrl+shift+v")));
+    assert!(keymap.warnings().iter().any(|w| w.contains("keys.paste") && w.contains("banana")));
+}
+
+#[test]
+fn from_config_reads_prefixed_keys() {
+    let keymap = Keymap::from_config(|key| (key == "keys.copy").then(|| "alt+c".to_string()));
+    assert_eq!(keymap.action_for(&chord("alt+c")), Some(Action::Copy));
+    assert_eq!(keymap.action_for(&chord("ctrl+shift+c")), None);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Conflict checker
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn conflict_between_two_user_bindings() {
+    let keymap = Keymap::from_overrides([(Action::Copy, "alt+x"), (Action::Paste, "alt+x")]);
+    // Same source: first in Action::ALL wins.
+    assert_eq!(keymap.action_for(&chord("alt+x")), Some(Action::Copy));
+    let conflicts = keymap.conflicts();
+    assert_eq!(conflicts.len(), 1, "{:?}", conflicts);
+    assert!(conflicts[0].contains("copy (config)") && conflicts[0].contains("paste (config)"));
+}
+
+#[test]
+fn shadowing_pty_pass_through() {
+    let keymap = Keymap::from_overrides([(Action::Copy, "ctrl+c")]);
+    assert_eq!(keymap.action_for(&chord("ctrl+c")), Some(Action::Copy));
+    assert!(keymap.warnings().iter().any(|w| w.contains("shadows interrupting")));
+
+    let keymap = Keymap::from_overrides([(Action::PaletteOpen, "escape")]);
+    assert!(keymap.warnings().iter().any(|w| w.contains("escape (palette_open) shadows Escape")));
+
+    let keymap = Keymap::from_overrides([(Action::SearchOpen, "ctrl+z")]);
+    assert!(keymap.warnings().iter().any(|w| w.contains("suspending")));
+}
+
+#[test]
+fn moving_the_pass_through_action_itself_is_fine() {
+    // Interrupt on another chord leaves ctrl+c free, nothing shadows it.
+    let keymap = Keymap::from_overrides([(Action::Interrupt, "ctrl+shift+k")]);
+    assert!(keymap.warnings().is_empty(), "{:?}", keymap.warnings());
+}
+
+#[test]
+fn shadowing_typing() {
+    let keymap = Keymap::from_overrides([(Action::SearchOpen, "/"), (Action::PaletteOpen, "shift+p")]);
+    let warnings = keymap.warnings();
+    assert!(warnings.iter().any(|w| w.contains("/ (search_open) shadows typing")));
+    assert!(warnings.iter().any(|w| w.contains("shift+p (palette_open) shadows typing")));
+}
+
+// ════════════════════════════════════════════════════════════════
+// .inputrc import
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn import_moves_defaults_but_not_config() {
+    let mut keymap = Keymap::from_overrides([(Action::LineEnd, "ctrl+shift+x")]);
+    let moved = keymap.import(&[(chord("ctrl+a"), Action::LineStart), (chord("ctrl+e"), Action::LineEnd)]);
+    assert_eq!(moved, 1);
+    assert_eq!(keymap.chord_for(Action::LineStart), Some(chord("ctrl+a")));
+    assert_eq!(keymap.chord_for(Action::LineEnd), Some(chord("ctrl+shift+x")));
+    let binding = keymap.bindings().iter().find(|b| b.action == Action::LineStart).unwrap();
+    assert_eq!(binding.source, BindingSource::Inputrc);
+    assert!(keymap.lines().iter().any(|l| l.contains("line_start") && l.contains("inputrc")));
+}
+
+#[test]
+fn imported_binding_beats_a_default_on_its_chord() {
+    let mut keymap = Keymap::default();
+    // ctrl+l is clear_screen by default.
+    keymap.import(&[(chord("ctrl+l"), Action::SearchOpen)]);
+    assert_eq!(keymap.action_for(&chord("ctrl+l")), Some(Action::SearchOpen));
+    assert!(keymap.warnings().iter().any(|w| w.contains("ctrl+l") && w.contains("search_open wins")));
+}
+
+#[test]
+fn config_binding_beats_an_imported_one() {
+    let mut keymap = Keymap::from_overrides([(Action::PaletteOpen, "ctrl+o")]);
+    keymap.import(&[(chord("ctrl+o"), Action::SearchOpen)]);
+    assert_eq!(keymap.action_for(&chord("ctrl+o")), Some(Action::PaletteOpen));
+}
@@ -0,0 +1,223 @@
+// positronic-bridge/tests/layout_tests.rs
+//
+// Tests for window geometry: `layout.density` presets, the `layout.*`
+// overrides and their ranges, and `compute` across window sizes, DPI
+// scales and all three presets — regions tiling the window, the PTY grid
+// fitting the terminal area, and its minimum size.
+
+use std::collections::HashMap;
+
+use positronic_bridge::layout::{
+    self, CellMetrics, ComputedLayout, Density, LayoutSpec, BLOCK_GAP_KEY, DENSITY_KEY, INPUTBAR_HEIGHT_KEY,
+    INPUT_PADDING_KEY, MIN_COLS, MIN_ROWS, STATUSBAR_FONT_SCALE_KEY, STATUSBAR_HEIGHT_KEY,
+};
+
+const CELL: CellMetrics = CellMetrics { width: 8.0, height: 18.0 };
+
+fn load(pairs: &[(&str, &str)]) -> (LayoutSpec, Vec<String>) {
+    let map: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let mut problems = Vec::new();
+    let spec = LayoutSpec::load(|key| map.get(key).cloned(), &mut problems);
+    (spec, problems)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-3
+}
+
+// ============================================================================
+// Presets
+// ============================================================================
+
+#[test]
+fn test_default_is_normal() {
+    let (spec, problems) = load(&[]);
+    assert!(problems.is_empty());
+    assert_eq!(spec, LayoutSpec::default());
+    assert_eq!(spec.density, Density::Normal);
+    assert_eq!(spec.statusbar_height, 24.0);
+    assert_eq!(spec.inputbar_height, 36.0);
+}
+
+#[test]
+fn test_density_parse() {
+    assert_eq!(Density::parse(" Compact "), Some(Density::Compact));
+    assert_eq!(Density::parse("normal"), Some(Density::Normal));
+    assert_eq!(Density::parse("default"), Some(Density::Normal));
+    assert_eq!(Density::parse("comfortable"), Some(Density::Comfortable));
+    assert_eq!(Density::parse("roomy"), None);
+    for density in Density::all() {
+        assert_eq!(Density::parse(density.label()), Some(density));
+    }
+}
+
+#[test]
+fn test_presets_grow_from_compact_to_comfortable() {
+    let [compact, normal, comfortable] = Density::all().map(Density::spec);
+    assert!(compact.statusbar_height < normal.statusbar_height);
+    assert!(normal.statusbar_height < comfortable.statusbar_height);
+    assert!(compact.inputbar_height < normal.inputbar_height);
+    assert!(normal.inputbar_height < comfortable.inputbar_height);
+    assert!(compact.input_padding < comfortable.input_padding);
+    assert!(compact.statusbar_font_scale < comfortable.statusbar_font_scale);
+}
+
+// ============================================================================
+// Overrides
+// ============================================================================
+
+#[test]
+fn test_overrides_apply_on_top_of_the_preset() {
+    let (spec, problems) = load(&[(DENSITY_KEY, "compact"), (STATUSBAR_HEIGHT_KEY, "30"), (BLOCK_GAP_KEY, "4px")]);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(spec.density, Density::Compact);
+    assert_eq!(spec.statusbar_height, 30.0);
+    assert_eq!(spec.block_gap, 4.0);
+    // Untouched keys keep the preset's.
+    assert_eq!(spec.inputbar_height, Density::Compact.spec().inputbar_height);
+}
+
+#[test]
+fn test_every_override_is_read() {
+    let (spec, problems) = load(&[
+        (STATUSBAR_HEIGHT_KEY, "20"),
+        (INPUTBAR_HEIGHT_KEY, "40"),
+        (INPUT_PADDING_KEY, "12"),
+        (BLOCK_GAP_KEY, "2"),
+        (STATUSBAR_FONT_SCALE_KEY, "1.0"),
+    ]);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(
+        (spec.statusbar_height, spec.inputbar_height, spec.input_padding, spec.block_gap, spec.statusbar_font_scale),
+        (20.0, 40.0, 12.0, 2.0, 1.0)
+    );
+}
+
+#[test]
+fn test_out_of_range_overrides_keep_the_preset() {
+    let (spec, problems) = load(&[(STATUSBAR_HEIGHT_KEY, "4"), (STATUSBAR_FONT_SCALE_KEY, "3"), (BLOCK_GAP_KEY, "-1")]);
+    assert_eq!(problems.len(), 3);
+    assert!(problems.iter().any(|p| p.starts_with(STATUSBAR_HEIGHT_KEY) && p.contains("expected 16 to 64")));
+    assert_eq!(spec, LayoutSpec::default());
+}
+
+#[test]
+fn test_bad_values_are_reported() {
+    let (spec, problems) = load(&[(DENSITY_KEY, "roomy"), (INPUTBAR_HEIGHT_KEY, "tall")]);
+    assert_eq!(problems.len(), 2);
+    assert!(problems[0].contains("expected compact, normal or comfortable"));
+    assert!(problems[1].starts_with(INPUTBAR_HEIGHT_KEY));
+    assert_eq!(spec, LayoutSpec::default());
+}
+
+// ============================================================================
+// Compute
+// ============================================================================
+
+fn tiles(lay: &ComputedLayout, spec: &LayoutSpec, scale: f32) -> bool {
+    let gap = spec.block_gap * scale;
+    close(lay.terminal_h + lay.padding + gap + lay.status_h + lay.input_h, lay.height)
+        && close(lay.status_y + lay.status_h, lay.input_y)
+        && close(lay.input_y + lay.input_h, lay.height)
+}
+
+#[test]
+fn test_normal_matches_the_old_fixed_geometry() {
+    let lay = layout::compute([1280, 800], &LayoutSpec::default(), 10.0, CELL, 1.0);
+    assert_eq!((lay.status_y, lay.status_h), (740.0, 24.0));
+    assert_eq!((lay.input_y, lay.input_h), (764.0, 36.0));
+    assert_eq!(lay.terminal_h, 730.0);
+    assert_eq!(lay.input_padding, 10.0);
+    assert_eq!(lay.status_font_scale, 0.85);
+}
+
+#[test]
+fn test_regions_tile_the_window_for_every_preset_size_and_scale() {
+    for density in Density::all() {
+        let spec = density.spec();
+        for size in [[800, 600], [1280, 800], [1920, 1080], [3840, 2160]] {
+            for scale in [1.0, 1.5, 2.0] {
+                let lay = layout::compute(size, &spec, 10.0, CELL, scale);
+                assert!(tiles(&lay, &spec, scale), "{:?} {:?} {}", density, size, scale);
+                assert!(close(lay.status_h, spec.statusbar_height * scale));
+                assert!(close(lay.input_h, spec.inputbar_height * scale));
+                assert_eq!(lay.terminal_w, size[0] as f32);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_grid_fits_the_terminal_area() {
+    for density in Density::all() {
+        let spec = density.spec();
+        for scale in [1.0, 1.5, 2.0] {
+            let lay = layout::compute([1920, 1080], &spec, 10.0, CELL, scale);
+            let used_w = lay.cols as f32 * lay.cell_w + lay.padding * 2.0;
+            let used_h = lay.rows as f32 * lay.cell_h + lay.padding;
+            assert!(used_w <= lay.width, "{:?} {}", density, scale);
+            assert!(used_h <= lay.terminal_h, "{:?} {}", density, scale);
+            // And no whole cell more would fit.
+            assert!(used_w + lay.cell_w > lay.width);
+            assert!(used_h + lay.cell_h > lay.terminal_h);
+        }
+    }
+}
+
+#[test]
+fn test_scale_multiplies_everything_but_the_font_scale() {
+    let spec = Density::Comfortable.spec();
+    let one = layout::compute([2560, 1600], &spec, 10.0, CELL, 1.0);
+    let two = layout::compute([2560, 1600], &spec, 10.0, CELL, 2.0);
+    assert_eq!(two.status_h, one.status_h * 2.0);
+    assert_eq!(two.padding, one.padding * 2.0);
+    assert_eq!(two.input_padding, one.input_padding * 2.0);
+    assert_eq!((two.cell_w, two.cell_h), (one.cell_w * 2.0, one.cell_h * 2.0));
+    assert_eq!(two.status_font_scale, one.status_font_scale);
+    assert!(two.cols < one.cols && two.rows < one.rows);
+}
+
+#[test]
+fn test_invalid_scale_counts_as_one() {
+    let spec = LayoutSpec::default();
+    let one = layout::compute([1280, 800], &spec, 10.0, CELL, 1.0);
+    assert_eq!(layout::compute([1280, 800], &spec, 10.0, CELL, 0.0), one);
+    assert_eq!(layout::compute([1280, 800], &spec, 10.0, CELL, f32::NAN), one);
+}
+
+#[test]
+fn test_compact_fits_more_rows_than_comfortable() {
+    let rows = |d: Density| layout::compute([1920, 1080], &d.spec(), 10.0, CELL, 1.0).rows;
+    assert!(rows(Density::Compact) > rows(Density::Normal));
+    assert!(rows(Density::Normal) > rows(Density::Comfortable));
+}
+
+#[test]
+fn test_block_gap_comes_out_of_the_terminal_area() {
+    let spec = LayoutSpec::default();
+    let wide = LayoutSpec { block_gap: 36.0, ..spec };
+    let a = layout::compute([1280, 800], &spec, 10.0, CELL, 1.0);
+    let b = layout::compute([1280, 800], &wide, 10.0, CELL, 1.0);
+    assert_eq!(a.terminal_h - b.terminal_h, 36.0);
+    assert_eq!(a.rows - b.rows, 2);
+    assert_eq!(a.status_y, b.status_y);
+}
+
+#[test]
+fn test_tiny_window_keeps_the_minimum_grid() {
+    for size in [[0, 0], [100, 50], [300, 90]] {
+        let lay = layout::compute(size, &LayoutSpec::default(), 10.0, CELL, 1.0);
+        assert_eq!((lay.cols, lay.rows), (MIN_COLS, MIN_ROWS), "{:?}", size);
+        assert!(lay.terminal_h >= 0.0);
+    }
+}
+
+#[test]
+fn test_software_cells_give_their_own_grid() {
+    // The software renderer's 6x8 cells drawn at 2x.
+    let soft = CellMetrics { width: 6.0, height: 8.0 };
+    let lay = layout::compute([1280, 800], &LayoutSpec::default(), 10.0, soft, 2.0);
+    assert_eq!((lay.cell_w, lay.cell_h), (12.0, 16.0));
+    assert_eq!(lay.cols, ((1280.0 - 40.0) / 12.0) as u16);
+    assert_eq!(lay.rows, ((lay.terminal_h - 20.0) / 16.0) as u16);
+}
@@ -0,0 +1,174 @@
+// positronic-bridge/tests/macros_tests.rs
+//
+// Tests for `!macro`: recording synthetic event streams (typing joined,
+// secrets left out, the `!macro` line dropped), the saved step format,
+// and playback timing, repeats and holding while a command runs.
+
+use std::time::{Duration, Instant};
+
+use positronic_bridge::keymap::Action;
+use positronic_bridge::macros::{
+    self, MacroCommand, Player, Recorder, Step, Tick, BUSY_POLL, MACRO_USAGE, MAX_REPEAT, SUBMIT_SETTLE,
+};
+
+fn insert(text: &str) -> Step {
+    Step::Insert(text.to_string())
+}
+
+/// Keys as the event handler reports them: one per typed character.
+fn type_line(recorder: &mut Recorder, line: &str, secret: bool) {
+    for c in line.chars() {
+        recorder.record(insert(&c.to_string()), secret);
+    }
+    recorder.record(Step::Submit, secret);
+}
+
+#[test]
+fn recorder_joins_typing_and_keeps_actions_and_edits() {
+    let mut recorder = Recorder::new("build");
+    type_line(&mut recorder, "cargo tset", false);
+    let steps = [Step::Action(Action::HistoryUp), Step::Backspace, Step::Backspace, Step::Backspace];
+    for step in steps {
+        recorder.record(step, false);
+    }
+    recorder.record(insert("s"), false);
+    recorder.record(insert("t"), false);
+    recorder.record(Step::Submit, false);
+    assert_eq!(
+        recorder.steps(),
+        [
+            insert("cargo tset"),
+            Step::Submit,
+            Step::Action(Action::HistoryUp),
+            Step::Backspace,
+            Step::Backspace,
+            Step::Backspace,
+            insert("st"),
+            Step::Submit,
+        ]
+    );
+}
+
+#[test]
+fn recorder_leaves_out_what_a_password_prompt_reads() {
+    let mut recorder = Recorder::new("deploy");
+    type_line(&mut recorder, "sudo make install", false);
+    type_line(&mut recorder, "hunter2", true);
+    type_line(&mut recorder, "ls", false);
+    assert_eq!(recorder.left_out(), "hunter2".len() + 1, "the secret's submit too");
+    let recorded = macros::encode(recorder.steps());
+    assert!(!recorded.contains("hunter2"), "{}", recorded);
+    assert_eq!(recorder.finish(), [insert("sudo make install"), Step::Submit, insert("ls"), Step::Submit]);
+}
+
+#[test]
+fn recorder_drops_the_macro_line_that_reached_it() {
+    let mut recorder = Recorder::new("m");
+    type_line(&mut recorder, "make", false);
+    recorder.record(Step::Action(Action::HistoryUp), false);
+    type_line(&mut recorder, "!macro stop", false);
+    recorder.drop_last_line();
+    assert_eq!(recorder.steps(), [insert("make"), Step::Submit]);
+
+    // A recording that was only the `!macro` line is empty.
+    let mut only = Recorder::new("m");
+    type_line(&mut only, "!macro stop", false);
+    only.drop_last_line();
+    assert!(only.finish().is_empty());
+}
+
+#[test]
+fn steps_round_trip_by_action_name_not_key() {
+    let steps = vec![
+        insert("  cargo t"),
+        Step::Action(Action::TabComplete),
+        Step::Action(Action::KillWordBack),
+        Step::Delete,
+        Step::Backspace,
+        Step::Submit,
+    ];
+    let text = macros::encode(&steps);
+    assert_eq!(text, "insert   cargo t\naction tab_complete\naction kill_word_back\ndelete\nbackspace\nsubmit");
+    assert_eq!(macros::decode(&text).unwrap(), steps);
+    assert_eq!(macros::decode("submit\nkey ctrl+r").unwrap_err(), "line 2: not a macro step: key ctrl+r");
+    assert!(macros::decode("action no_such_action").is_err());
+}
+
+#[test]
+fn macro_commands_parse() {
+    assert_eq!(MacroCommand::parse("record build"), Ok(MacroCommand::Record("build".into())));
+    assert_eq!(MacroCommand::parse("stop"), Ok(MacroCommand::Stop));
+    assert_eq!(MacroCommand::parse("play build"), Ok(MacroCommand::Play { name: "build".into(), times: 1 }));
+    assert_eq!(MacroCommand::parse("play build 3"), Ok(MacroCommand::Play { name: "build".into(), times: 3 }));
+    assert_eq!(MacroCommand::parse(""), Ok(MacroCommand::List));
+    assert_eq!(MacroCommand::parse("show build"), Ok(MacroCommand::Show("build".into())));
+    assert_eq!(MacroCommand::parse("rm build"), Ok(MacroCommand::Rm("build".into())));
+    let too_many = format!("play build {}", MAX_REPEAT + 1);
+    for bad in ["record", "record -x", "play build 0", too_many.as_str(), "play 12", "stop now", "edit build"] {
+        assert_eq!(MacroCommand::parse(bad), Err(MACRO_USAGE.to_string()), "{}", bad);
+    }
+}
+
+/// Play `player` from `start` with the shell busy whenever `busy` says,
+/// stepping time by `step`; the steps played and when, in ms from start.
+fn drive(player: &mut Player, start: Instant, step: Duration, busy: impl Fn(u128) -> bool) -> Vec<(u128, Step)> {
+    let mut played = Vec::new();
+    let mut now = start;
+    for _ in 0..10_000 {
+        let ms = now.duration_since(start).as_millis();
+        match player.tick(now, busy(ms)) {
+            Tick::Step(s) => played.push((ms, s)),
+            Tick::Wait(at) | Tick::Held(at) => assert!(at > now),
+            Tick::Done => return played,
+        }
+        now += step;
+    }
+    panic!("playback never finished");
+}
+
+#[test]
+fn player_spaces_steps_and_settles_after_submit() {
+    let delay = Duration::from_millis(30);
+    let start = Instant::now();
+    let mut player = Player::new("m", vec![insert("ls"), Step::Submit, insert("pwd"), Step::Submit], 1, delay, start);
+    let played = drive(&mut player, start, Duration::from_millis(10), |_| false);
+    let settle = SUBMIT_SETTLE.as_millis();
+    assert_eq!(
+        played,
+        [(0, insert("ls")), (30, Step::Submit), (30 + settle, insert("pwd")), (60 + settle, Step::Submit)]
+    );
+    assert_eq!(player.next_wake(), None);
+}
+
+#[test]
+fn player_repeats_and_reports_progress() {
+    let start = Instant::now();
+    let mut player = Player::new("m", vec![insert("a"), Step::Backspace], 3, Duration::from_millis(5), start);
+    assert_eq!(player.progress(), "m 1/3, step 1/2");
+    let played = drive(&mut player, start, Duration::from_millis(5), |_| false);
+    assert_eq!(played.len(), 6);
+    assert!(played.iter().step_by(2).all(|(_, s)| *s == insert("a")));
+    assert_eq!(Player::new("m", Vec::new(), 3, Duration::ZERO, start).tick(start, false), Tick::Done);
+}
+
+#[test]
+fn player_holds_while_a_command_runs() {
+    let delay = Duration::from_millis(20);
+    let start = Instant::now();
+    let mut player = Player::new("m", vec![insert("make"), Step::Submit, insert("ls"), Step::Submit], 1, delay, start);
+    // `make` runs from 100ms to 900ms; nothing is typed into it.
+    let busy = |ms: u128| (100..900).contains(&ms);
+    let played = drive(&mut player, start, Duration::from_millis(10), busy);
+    let times: Vec<u128> = played.iter().map(|(ms, _)| *ms).collect();
+    assert_eq!(times[..2], [0, 20]);
+    assert!(times[2] >= 900 && times[2] < 900 + BUSY_POLL.as_millis() + 10, "{:?}", times);
+    assert_eq!(played[2].1, insert("ls"));
+
+    // Held reports as such until the shell is free.
+    let mut player = Player::new("m", vec![insert("x")], 1, delay, start);
+    assert!(matches!(player.tick(start, true), Tick::Held(_)));
+    assert!(player.is_held());
+    assert!(matches!(player.tick(start + Duration::from_millis(50), false), Tick::Held(_)));
+    assert_eq!(player.tick(start + BUSY_POLL, false), Tick::Step(insert("x")));
+    assert!(!player.is_held());
+}
@@ -0,0 +1,208 @@
+// positronic-bridge/tests/marks_tests.rs
+//
+// Tests for `!mark`: the mark table, resolution of marks whose blocks have
+// been evicted or cleared, and the Ctrl+O / Ctrl+I jump list.
+
+use std::time::Instant;
+
+use positronic_bridge::attention::ScreenMarks;
+use positronic_bridge::marks::{JumpList, Mark, MarkTable, MarkTarget, Resolved, Spot, JUMP_LIST_LEN};
+use positronic_core::vault::CommandRecord;
+
+fn mark(name: &str, target: MarkTarget, history_id: Option<i64>) -> Mark {
+    Mark { name: name.to_string(), target, command: Some("./deploy.sh".to_string()), history_id, at: 0 }
+}
+
+/// `n` more finished commands on `screen`, each ten lines long.
+fn run(screen: &mut ScreenMarks, n: usize) {
+    let now = Instant::now();
+    for _ in 0..n {
+        let start = screen.last().map_or(0, |b| b.lines.end);
+        screen.sent(&format!("echo {}", start), None);
+        screen.started(start);
+        screen.finished(start + 10, Some(0), now);
+    }
+}
+
+fn screen(n: usize) -> ScreenMarks {
+    let mut screen = ScreenMarks::default();
+    run(&mut screen, n);
+    screen
+}
+
+// ════════════════════════════════════════════════════════════════
+// Mark table
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn marks_are_replaced_by_name_and_removed() {
+    let mut table = MarkTable::default();
+    assert!(table.is_empty());
+    table.set(mark("deploy-start", MarkTarget::Line(40), None));
+    table.set(mark("tests", MarkTarget::Line(90), None));
+    table.set(mark("deploy-start", MarkTarget::Line(55), None));
+
+    let names: Vec<&str> = table.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["tests", "deploy-start"]);
+    assert_eq!(table.get("deploy-start").unwrap().target, MarkTarget::Line(55));
+
+    assert!(table.remove("tests"));
+    assert!(!table.remove("tests"));
+    assert!(table.resolve("tests", |_| None).is_none());
+}
+
+#[test]
+fn a_block_mark_follows_its_block() {
+    let screen = screen(3);
+    let id = screen.last().unwrap().id;
+    let mut table = MarkTable::default();
+    table.set(mark("last", MarkTarget::Block(id), Some(7)));
+
+    let line_of = |id| screen.block(id).map(|b| b.lines.start);
+    assert_eq!(table.resolve("last", line_of), Some(Resolved::Line(20)));
+    assert_eq!(table.name_at(20, line_of), Some("last"));
+    assert_eq!(table.name_at(10, line_of), None);
+    // Still live: resolving didn't change it.
+    assert_eq!(table.get("last").unwrap().target, MarkTarget::Block(id));
+}
+
+#[test]
+fn nearest_command_is_the_block_a_line_is_in() {
+    let screen = screen(3);
+    assert_eq!(screen.nearest(15).and_then(|b| b.command.as_deref()), Some("echo 10"));
+    assert_eq!(screen.nearest(20).and_then(|b| b.command.as_deref()), Some("echo 20"));
+    assert_eq!(screen.nearest(500).and_then(|b| b.command.as_deref()), Some("echo 20"));
+}
+
+#[test]
+fn describe_says_where_a_mark_is() {
+    let m = mark("deploy-start", MarkTarget::Line(41), Some(3));
+    assert!(m.describe(Resolved::Line(41)).contains("line 42"));
+    assert!(m.describe(Resolved::History(3)).contains("history #3"));
+    assert!(m.describe(Resolved::Gone).contains("gone"));
+    assert!(m.describe(Resolved::Line(41)).ends_with("./deploy.sh"));
+}
+
+// ════════════════════════════════════════════════════════════════
+// Evicted and cleared content
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn an_evicted_block_turns_its_mark_history_backed() {
+    let mut screen = screen(1);
+    let first = screen.last().unwrap().id;
+    let mut table = MarkTable::default();
+    table.set(mark("with-row", MarkTarget::Block(first), Some(12)));
+    table.set(mark("without-row", MarkTarget::Block(first), None));
+
+    // Enough commands later, the first block is no longer remembered.
+    run(&mut screen, 300);
+    assert!(screen.block(first).is_none());
+
+    let line_of = |id| screen.block(id).map(|b| b.lines.start);
+    assert_eq!(table.resolve("with-row", line_of), Some(Resolved::History(12)));
+    assert_eq!(table.get("with-row").unwrap().target, MarkTarget::History(12));
+    assert_eq!(table.resolve("without-row", line_of), Some(Resolved::Gone));
+    // Once converted it stays converted, whatever the screen says.
+    assert_eq!(table.resolve("with-row", |_| Some(0)), Some(Resolved::History(12)));
+}
+
+#[test]
+fn a_cleared_screen_detaches_every_mark() {
+    let mut table = MarkTable::default();
+    table.set(mark("block", MarkTarget::Block(4), Some(1)));
+    table.set(mark("line", MarkTarget::Line(80), Some(2)));
+    table.set(mark("line-only", MarkTarget::Line(80), None));
+    table.set(mark("saved", MarkTarget::History(3), Some(3)));
+    table.screen_cleared();
+
+    assert_eq!(table.resolve("block", |_| Some(0)), Some(Resolved::History(1)));
+    assert_eq!(table.resolve("line", |_| None), Some(Resolved::History(2)));
+    assert_eq!(table.resolve("line-only", |_| None), Some(Resolved::Gone));
+    assert_eq!(table.resolve("saved", |_| None), Some(Resolved::History(3)));
+}
+
+#[test]
+fn history_backed_jumps_show_the_stored_output() {
+    let record = CommandRecord {
+        id: Some(9),
+        session_id: "s".to_string(),
+        command: "./deploy.sh".to_string(),
+        output: Some("step 1\nstep 2".to_string()),
+        exit_code: Some(0),
+        directory: "/srv".to_string(),
+        duration_ms: None,
+        timestamp: 0,
+    };
+    let lines = positronic_bridge::marks::recall_lines("deploy-start", &record);
+    assert!(lines[0].starts_with("🔖 deploy-start: #9 ./deploy.sh"), "{:?}", lines);
+    assert_eq!(&lines[2..], ["step 1", "step 2"]);
+}
+
+// ════════════════════════════════════════════════════════════════
+// Jump list
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn back_and_forward_walk_the_places_jumped_from() {
+    let mut jumps = JumpList::default();
+    assert_eq!(jumps.back(Spot::Bottom), None);
+
+    jumps.push(Spot::Bottom);
+    jumps.push(Spot::Line(100));
+    // Now at line 20.
+    assert_eq!(jumps.back(Spot::Line(20)), Some(Spot::Line(100)));
+    assert_eq!(jumps.back(Spot::Line(100)), Some(Spot::Bottom));
+    assert_eq!(jumps.back(Spot::Bottom), None);
+    assert_eq!(jumps.forward(), Some(Spot::Line(100)));
+    // The first step back recorded where it started.
+    assert_eq!(jumps.forward(), Some(Spot::Line(20)));
+    assert_eq!(jumps.forward(), None);
+}
+
+#[test]
+fn jumping_from_the_middle_drops_what_was_ahead() {
+    let mut jumps = JumpList::default();
+    jumps.push(Spot::Line(1));
+    jumps.push(Spot::Line(2));
+    jumps.push(Spot::Line(3));
+    assert_eq!(jumps.back(Spot::Line(4)), Some(Spot::Line(3)));
+    assert_eq!(jumps.back(Spot::Line(3)), Some(Spot::Line(2)));
+
+    // A jump from line 2 forgets 3 and 4.
+    jumps.push(Spot::Line(2));
+    assert_eq!(jumps.len(), 2);
+    assert_eq!(jumps.forward(), None);
+    assert_eq!(jumps.back(Spot::Line(50)), Some(Spot::Line(2)));
+    assert_eq!(jumps.back(Spot::Line(2)), Some(Spot::Line(1)));
+}
+
+#[test]
+fn repeated_places_are_recorded_once() {
+    let mut jumps = JumpList::default();
+    jumps.push(Spot::Bottom);
+    jumps.push(Spot::Bottom);
+    assert_eq!(jumps.len(), 1);
+    // Going back from the one place recorded goes nowhere.
+    assert_eq!(jumps.back(Spot::Bottom), None);
+}
+
+#[test]
+fn the_oldest_places_fall_off_a_full_list() {
+    let mut jumps = JumpList::default();
+    for line in 0..JUMP_LIST_LEN + 10 {
+        jumps.push(Spot::Line(line));
+    }
+    assert_eq!(jumps.len(), JUMP_LIST_LEN);
+    let mut oldest = None;
+    let mut here = Spot::Bottom;
+    while let Some(spot) = jumps.back(here) {
+        oldest = Some(spot);
+        here = spot;
+    }
+    assert_eq!(oldest, Some(Spot::Line(11)));
+
+    jumps.clear();
+    assert!(jumps.is_empty());
+    assert_eq!(jumps.back(Spot::Bottom), None);
+}
@@ -429,6 +429,30 @@ fn test_hardware_panel_device_list() {
     assert_eq!(panel.device_list().len(), 3);
 }
 
+#[test]
+fn test_hardware_panel_labels_named_devices() {
+    use positronic_io::{DeviceId, HardwareEvent};
+
+    let scope = DeviceId::new(0x2341, 0x0043, Some("A1"));
+    let identified = |port: &str, name: Option<&str>| HardwareEvent::Identified {
+        port: port.to_string(),
+        device: scope.clone(),
+        product: None,
+        name: name.map(str::to_string),
+    };
+    let mut panel = HardwarePanel::new();
+    panel.device_discovered("COM1");
+    panel.apply(&identified("COM7", Some("bench-scope")));
+    assert_eq!(panel.devices["COM7"].device.as_ref(), Some(&scope));
+    assert_eq!(panel.label("COM7"), "bench-scope (COM7)");
+    assert_eq!(panel.label("COM1"), "COM1");
+
+    // Replugged elsewhere: the name follows the device.
+    panel.apply(&identified("COM9", Some("bench-scope")));
+    assert_eq!(panel.label("COM9"), "bench-scope (COM9)");
+    assert_eq!(panel.label("COM7"), "COM7");
+}
+
 #[test]
 fn test_hardware_panel_multiple_connections() {
     let mut panel = HardwarePanel::new();
@@ -0,0 +1,146 @@
+// positronic-bridge/tests/pager_tests.rs
+//
+// Tests for the DirectOutput pager: paging, searching and early exit.
+
+use positronic_bridge::pager::{Pager, PagerKey, PagerOutcome, PagerThreshold};
+use positronic_bridge::syntax::{SyntaxSpan, TokenClass};
+
+fn numbered(n: usize) -> Vec<String> {
+    (1..=n).map(|i| format!("line {}", i)).collect()
+}
+
+fn closed(outcome: PagerOutcome) -> Vec<String> {
+    match outcome {
+        PagerOutcome::Closed(lines) => lines,
+        PagerOutcome::Continue => panic!("pager should have closed"),
+    }
+}
+
+#[test]
+fn test_threshold_decides_when_to_page() {
+    assert!(PagerThreshold::Screen.should_page(30, 24));
+    assert!(!PagerThreshold::Screen.should_page(24, 24));
+    assert!(PagerThreshold::Lines(10).should_page(11, 50));
+    assert!(!PagerThreshold::Off.should_page(10_000, 24));
+    assert_eq!(PagerThreshold::parse("auto"), Some(PagerThreshold::Screen));
+    assert_eq!(PagerThreshold::parse("off"), Some(PagerThreshold::Off));
+    assert_eq!(PagerThreshold::parse("40"), Some(PagerThreshold::Lines(40)));
+    assert_eq!(PagerThreshold::parse("lots"), None);
+}
+
+#[test]
+fn test_space_pages_through_and_closes_at_end() {
+    let mut pager = Pager::new(numbered(25), 10);
+    assert_eq!(pager.visible().first().map(String::as_str), Some("line 1"));
+    assert_eq!(pager.footer(), "-- more (40%) -- [space/q/g/G]");
+
+    assert_eq!(pager.handle(PagerKey::Space), PagerOutcome::Continue);
+    assert_eq!(pager.top(), 10);
+    assert_eq!(pager.handle(PagerKey::PageDown), PagerOutcome::Continue);
+    // The last page stays full rather than showing 5 lines.
+    assert_eq!(pager.top(), 15);
+    assert!(pager.at_end());
+    assert!(pager.footer().starts_with("-- (END) --"));
+
+    assert_eq!(closed(pager.handle(PagerKey::Space)), numbered(25));
+}
+
+#[test]
+fn test_quit_keeps_only_what_was_read() {
+    let mut pager = Pager::new(numbered(100), 10);
+    pager.handle(PagerKey::Space);
+    pager.handle(PagerKey::Char('g'));
+    assert_eq!(pager.top(), 0);
+    assert_eq!(closed(pager.handle(PagerKey::Char('q'))), numbered(20));
+}
+
+#[test]
+fn test_g_and_shift_g_jump_to_ends() {
+    let mut pager = Pager::new(numbered(100), 10);
+    pager.handle(PagerKey::Char('G'));
+    assert_eq!(pager.visible().last().map(String::as_str), Some("line 100"));
+    assert_eq!(pager.percent(), 100);
+    pager.handle(PagerKey::Char('g'));
+    assert_eq!(pager.top(), 0);
+    pager.handle(PagerKey::Down);
+    pager.handle(PagerKey::Down);
+    pager.handle(PagerKey::Up);
+    assert_eq!(pager.top(), 1);
+    // Jumping to the end counts as having read everything.
+    pager.handle(PagerKey::Char('G'));
+    pager.handle(PagerKey::Char('g'));
+    assert_eq!(closed(pager.handle(PagerKey::Escape)).len(), 100);
+}
+
+fn search(pager: &mut Pager, query: &str) {
+    pager.handle(PagerKey::Char('/'));
+    for c in query.chars() {
+        pager.handle(PagerKey::Char(c));
+    }
+    assert_eq!(pager.footer(), format!("/{}", query));
+    pager.handle(PagerKey::Enter);
+}
+
+#[test]
+fn test_search_jumps_to_matches() {
+    let mut lines = numbered(60);
+    lines[34] = "  42  cargo Build --release".to_string();
+    lines[51] = "  57  cargo build".to_string();
+    let mut pager = Pager::new(lines, 10);
+
+    search(&mut pager, "cargo build");
+    assert_eq!(pager.query(), Some("cargo build"));
+    assert_eq!(pager.top(), 34);
+    assert_eq!(pager.visible()[0], "  42  cargo Build --release");
+
+    pager.handle(PagerKey::Char('n'));
+    assert_eq!(pager.top(), 50, "a match near the end keeps a full page");
+    pager.handle(PagerKey::Char('n'));
+    assert!(pager.footer().contains("pattern not found"));
+    pager.handle(PagerKey::Char('N'));
+    assert_eq!(pager.top(), 34);
+}
+
+#[test]
+fn test_search_miss_is_reported_and_keeps_position() {
+    let mut pager = Pager::new(numbered(40), 10);
+    pager.handle(PagerKey::Space);
+    search(&mut pager, "qux");
+    assert_eq!(pager.top(), 10);
+    assert!(pager.footer().contains("pattern not found: qux"));
+    pager.handle(PagerKey::Down);
+    assert!(!pager.footer().contains("not found"));
+}
+
+#[test]
+fn test_search_prompt_can_be_cancelled() {
+    let mut pager = Pager::new(numbered(40), 10);
+    pager.handle(PagerKey::Char('/'));
+    pager.handle(PagerKey::Char('q'));
+    assert_eq!(pager.handle(PagerKey::Escape), PagerOutcome::Continue);
+    assert_eq!(pager.query(), None);
+    assert!(pager.footer().starts_with("-- more"));
+}
+
+#[test]
+fn test_resize_keeps_position_in_range() {
+    let mut pager = Pager::new(numbered(30), 10);
+    pager.handle(PagerKey::Char('G'));
+    pager.set_page_height(25);
+    assert_eq!(pager.top(), 5);
+    assert!(pager.at_end());
+}
+
+#[test]
+fn test_view_pager_keeps_only_its_metadata_line() {
+    let spans = |n: usize| (0..n).map(|i| vec![SyntaxSpan { range: 0..1, class: TokenClass::Number }; i % 2]).collect();
+    let mut pager = Pager::view(numbered(30), spans(30), 10);
+    pager.handle(PagerKey::Char('f'));
+    assert_eq!(pager.visible()[0], "line 11");
+    assert_eq!(pager.visible_syntax().len(), 10);
+    assert!(pager.visible_syntax()[0].is_empty() && pager.visible_syntax()[1].len() == 1);
+    assert_eq!(closed(pager.handle(PagerKey::Char('q'))), vec!["line 1"]);
+
+    let plain = Pager::view(numbered(3), Vec::new(), 10);
+    assert!(plain.visible_syntax().is_empty());
+}
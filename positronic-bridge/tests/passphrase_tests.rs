@@ -0,0 +1,41 @@
+// positronic-bridge/tests/passphrase_tests.rs
+//
+// Tests for the masked `!vault` passphrase prompt.
+
+use positronic_bridge::passphrase::{mask, PassphrasePrompt, PromptStep, VaultAction};
+
+#[test]
+fn migrations_ask_twice_and_unlock_once() {
+    let mut prompt = PassphrasePrompt::new(VaultAction::Encrypt);
+    assert!(prompt.label().starts_with("Vault passphrase to encrypt"));
+    assert_eq!(prompt.submit("hunter2".into()), PromptStep::Confirm);
+    assert!(prompt.label().starts_with("Repeat"));
+    assert_eq!(
+        prompt.submit("hunter2".into()),
+        PromptStep::Ready { passphrase: "hunter2".into(), confirm: "hunter2".into() }
+    );
+
+    let mut prompt = PassphrasePrompt::new(VaultAction::Unlock);
+    assert_eq!(
+        prompt.submit("hunter2".into()),
+        PromptStep::Ready { passphrase: "hunter2".into(), confirm: "hunter2".into() }
+    );
+}
+
+#[test]
+fn a_mismatch_starts_over() {
+    let mut prompt = PassphrasePrompt::new(VaultAction::Decrypt);
+    assert_eq!(prompt.submit("one".into()), PromptStep::Confirm);
+    assert_eq!(prompt.submit("two".into()), PromptStep::Mismatch);
+    assert!(prompt.label().starts_with("Vault passphrase"));
+    assert_eq!(prompt.submit("two".into()), PromptStep::Confirm);
+}
+
+#[test]
+fn vault_actions_and_mask() {
+    assert_eq!(VaultAction::parse("unlock"), Some(VaultAction::Unlock));
+    assert_eq!(VaultAction::parse(" encrypt "), Some(VaultAction::Encrypt));
+    assert_eq!(VaultAction::parse("status"), None);
+    assert_eq!(mask("pässword"), "••••••••");
+    assert_eq!(mask(""), "");
+}
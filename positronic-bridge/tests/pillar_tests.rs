@@ -7,6 +7,7 @@
 use positronic_bridge::biolink::{AccessibilityConfig, BioLink, BioLinkEvent};
 use positronic_bridge::hardware::{DeviceStatus, HardwarePanel, SensorStats, WaveformBuffer};
 use positronic_bridge::input::InputEditor;
+use positronic_io::{HardwareEvent, SensorSample};
 
 // ============================================================================
 // BioLink — AccessibilityConfig
@@ -306,6 +307,20 @@ fn test_waveform_ring_buffer_wraps() {
     assert!((samples[0].0 - 0.1).abs() < f64::EPSILON);
 }
 
+#[test]
+fn test_waveform_latest_and_rate() {
+    let mut buf = WaveformBuffer::new(3);
+    assert!(buf.latest().is_none());
+    buf.push(1.0, 5.0);
+    assert!(buf.rate().is_none(), "one sample has no rate");
+    for (t, v) in [(1.5, 6.0), (2.0, 7.0), (2.5, 8.0)] {
+        buf.push(t, v);
+    }
+    assert_eq!(buf.latest(), Some((2.5, 8.0)));
+    // Across the buffered span only: 2 intervals in 1s.
+    assert!((buf.rate().unwrap() - 2.0).abs() < 1e-9);
+}
+
 #[test]
 fn test_waveform_clear() {
     let mut buf = WaveformBuffer::new(100);
@@ -403,6 +418,71 @@ fn test_hardware_panel_multiple_connections() {
     assert_eq!(panel.connected_count(), 2);
 }
 
+#[test]
+fn test_hardware_panel_record_overflow() {
+    let mut panel = HardwarePanel::new();
+    panel.device_connected("COM1", 9600);
+    assert!(panel.devices["COM1"].data_loss.badge().is_none());
+
+    panel.record_overflow("COM1", 2048, 0);
+    panel.record_overflow("COM1", 0, 120);
+    let loss = panel.devices["COM1"].data_loss;
+    assert_eq!(loss.events, 2);
+    assert_eq!(loss.dropped_bytes, 2048);
+    assert_eq!(loss.dropped_samples, 120);
+    assert_eq!(loss.badge().unwrap(), "⚠ lost 2.0 KB / 120 samples");
+
+    // Reconnecting clears the badge
+    panel.device_connected("COM1", 9600);
+    assert!(!panel.devices["COM1"].data_loss.any());
+}
+
+#[test]
+fn test_hardware_panel_records_probe_results() {
+    use positronic_io::PortAccess;
+
+    let mut panel = HardwarePanel::new();
+    panel.apply(&HardwareEvent::DeviceConnected("/dev/ttyUSB0".to_string()));
+    assert!(panel.devices["/dev/ttyUSB0"].access_label().is_none());
+
+    panel.apply(&HardwareEvent::PortAccess { port: "/dev/ttyUSB0".to_string(), access: PortAccess::Busy });
+    let device = &panel.devices["/dev/ttyUSB0"];
+    assert_eq!(device.accessibility, Some(PortAccess::Busy));
+    assert!(device.access_label().unwrap().starts_with("busy"));
+    assert_eq!(device.status, DeviceStatus::Available);
+}
+
+#[test]
+fn test_hardware_panel_records_batches_per_channel() {
+    let mut panel = HardwarePanel::new();
+    let sample = |timestamp, value, channel| SensorSample { timestamp, value, channel };
+    panel.apply(&HardwareEvent::DeviceConnected("COM9".to_string()));
+    assert_eq!(panel.devices["COM9"].status, DeviceStatus::Available, "scans report ports this way too");
+    assert_eq!(panel.default_port(), None);
+
+    panel.apply(&HardwareEvent::DataBatch {
+        port: "COM9".to_string(),
+        samples: vec![sample(0.0, 21.5, 0), sample(0.0, 40.0, 1), sample(0.5, 22.5, 0)],
+    });
+    assert_eq!(panel.devices["COM9"].status, DeviceStatus::Connected);
+    assert_eq!(panel.devices["COM9"].stats.sample_count, 3);
+    let channels = panel.channels_of("COM9");
+    assert_eq!(channels.iter().map(|(ch, _)| *ch).collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(channels[0].1.waveform.samples(), vec![(0.0, 21.5), (0.5, 22.5)]);
+    assert_eq!(channels[0].1.stats.max_value, Some(22.5));
+    assert_eq!(channels[1].1.stats.last_value, Some(40.0));
+
+    // A port with data wins over one without.
+    panel.device_connected("COM1", 9600);
+    assert_eq!(panel.default_port(), Some("COM9"));
+    panel.apply(&HardwareEvent::DeviceDisconnected("COM9".to_string()));
+    assert_eq!(panel.default_port(), Some("COM1"));
+
+    // Reconnecting starts the traces over.
+    panel.device_connected("COM9", 115200);
+    assert!(panel.channels_of("COM9").is_empty());
+}
+
 // ============================================================================
 // InputEditor — Basics (deep tests in input_tests.rs)
 // ============================================================================
@@ -0,0 +1,177 @@
+// positronic-bridge/tests/quad_batch_tests.rs
+//
+// Tests for quad batching: run merging must not change what ends up on
+// screen, layers must order paint, and unchanged frames must skip upload.
+
+use positronic_bridge::quad_batch::{QuadBatch, QuadInstance, QuadLayer, coalesce_runs};
+use positronic_bridge::renderer::Rgba;
+
+const RED: Rgba = Rgba::rgb(1.0, 0.0, 0.0);
+const BLUE: Rgba = Rgba::rgb(0.0, 0.0, 1.0);
+const GREEN: Rgba = Rgba::rgb(0.0, 1.0, 0.0);
+
+fn quad(x: f32, y: f32, w: f32, h: f32, color: Rgba, layer: QuadLayer) -> QuadInstance {
+    QuadInstance { x, y, w, h, color, layer }
+}
+
+/// One row of terminal cells, each `cell_w` wide, colored by `pattern`.
+fn cell_row(y: f32, cell_w: f32, pattern: &[Rgba]) -> Vec<QuadInstance> {
+    pattern
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| quad(i as f32 * cell_w, y, cell_w, 16.0, c, QuadLayer::Background))
+        .collect()
+}
+
+/// Paint quads in order onto a small integer grid (painter's algorithm).
+fn rasterize(quads: &[QuadInstance], w: usize, h: usize) -> Vec<Option<[u32; 4]>> {
+    let mut px = vec![None; w * h];
+    for q in quads {
+        let key = [q.color.r, q.color.g, q.color.b, q.color.a].map(f32::to_bits);
+        let (x0, y0) = (q.x.round() as usize, q.y.round() as usize);
+        let (x1, y1) = ((q.x + q.w).round() as usize, (q.y + q.h).round() as usize);
+        for y in y0..y1.min(h) {
+            for x in x0..x1.min(w) {
+                px[y * w + x] = Some(key);
+            }
+        }
+    }
+    px
+}
+
+fn layered(quads: &[QuadInstance]) -> Vec<QuadInstance> {
+    let mut v = quads.to_vec();
+    v.sort_by_key(|q| q.layer);
+    v
+}
+
+// ============================================================================
+// Run coalescing
+// ============================================================================
+
+#[test]
+fn test_coalesce_merges_same_color_runs() {
+    let mut quads = cell_row(0.0, 8.0, &[RED, RED, RED, BLUE, BLUE, RED]);
+    coalesce_runs(&mut quads);
+    assert_eq!(quads.len(), 3);
+    assert_eq!((quads[0].x, quads[0].w), (0.0, 24.0));
+    assert_eq!((quads[1].x, quads[1].w), (24.0, 16.0));
+    assert_eq!((quads[2].x, quads[2].w), (40.0, 8.0));
+}
+
+#[test]
+fn test_coalesce_keeps_rows_and_gaps_apart() {
+    let mut quads = cell_row(0.0, 8.0, &[RED, RED]);
+    quads.extend(cell_row(16.0, 8.0, &[RED, RED]));
+    // A gap between two same-color cells on the same row.
+    quads.push(quad(100.0, 32.0, 8.0, 16.0, RED, QuadLayer::Background));
+    quads.push(quad(116.0, 32.0, 8.0, 16.0, RED, QuadLayer::Background));
+    coalesce_runs(&mut quads);
+    assert_eq!(quads.len(), 4);
+}
+
+#[test]
+fn test_coalesce_does_not_merge_across_layers() {
+    let mut quads = vec![
+        quad(0.0, 0.0, 8.0, 16.0, RED, QuadLayer::Background),
+        quad(8.0, 0.0, 8.0, 16.0, RED, QuadLayer::Selection),
+    ];
+    coalesce_runs(&mut quads);
+    assert_eq!(quads.len(), 2);
+}
+
+#[test]
+fn test_coalesce_preserves_coverage() {
+    // Several rows of mixed runs, plus overlapping widgets on top.
+    let palette = [RED, RED, BLUE, BLUE, BLUE, GREEN, RED, RED, RED, RED];
+    let mut quads = Vec::new();
+    for row in 0..6 {
+        let pattern: Vec<Rgba> = (0..40).map(|i| palette[(i * (row + 1) / 3) % palette.len()]).collect();
+        quads.extend(cell_row(row as f32 * 16.0, 8.0, &pattern));
+    }
+    quads.push(quad(20.0, 10.0, 100.0, 30.0, GREEN, QuadLayer::Overlay));
+    quads.push(quad(30.0, 40.0, 48.0, 16.0, BLUE, QuadLayer::Selection));
+    quads.push(quad(64.0, 48.0, 2.0, 16.0, RED, QuadLayer::Cursor));
+
+    let expected = rasterize(&layered(&quads), 320, 96);
+
+    let mut batch = QuadBatch::new();
+    for q in &quads {
+        batch.push(*q);
+    }
+    batch.prepare();
+    assert!(batch.instances().len() < quads.len());
+    assert_eq!(rasterize(batch.instances(), 320, 96), expected);
+}
+
+// ============================================================================
+// Layering
+// ============================================================================
+
+#[test]
+fn test_cursor_and_selection_draw_above_background() {
+    let mut batch = QuadBatch::new();
+    batch.push(quad(0.0, 0.0, 2.0, 16.0, GREEN, QuadLayer::Cursor));
+    batch.push(quad(0.0, 0.0, 40.0, 16.0, BLUE, QuadLayer::Selection));
+    batch.push(quad(0.0, 0.0, 800.0, 600.0, RED, QuadLayer::Background));
+    batch.prepare();
+
+    let layers: Vec<QuadLayer> = batch.instances().iter().map(|q| q.layer).collect();
+    assert_eq!(layers, vec![QuadLayer::Background, QuadLayer::Selection, QuadLayer::Cursor]);
+}
+
+#[test]
+fn test_layer_sort_is_stable_within_layer() {
+    let mut batch = QuadBatch::new();
+    batch.push(quad(0.0, 0.0, 10.0, 10.0, RED, QuadLayer::Overlay));
+    batch.push(quad(0.0, 0.0, 10.0, 10.0, BLUE, QuadLayer::Overlay));
+    batch.prepare();
+    assert_eq!(batch.instances()[0].color, RED);
+    assert_eq!(batch.instances()[1].color, BLUE);
+}
+
+// ============================================================================
+// Upload dedup
+// ============================================================================
+
+#[test]
+fn test_unchanged_frame_skips_upload() {
+    let mut batch = QuadBatch::new();
+    let frame = cell_row(0.0, 8.0, &[RED, BLUE, BLUE]);
+
+    for q in &frame {
+        batch.push(*q);
+    }
+    assert!(batch.prepare(), "first frame must upload");
+    assert_eq!(batch.submitted(), 3);
+    batch.clear();
+
+    for q in &frame {
+        batch.push(*q);
+    }
+    assert!(!batch.prepare(), "identical frame must not upload");
+    batch.clear();
+
+    batch.push(quad(0.0, 0.0, 2.0, 16.0, GREEN, QuadLayer::Cursor));
+    assert!(batch.prepare(), "changed frame must upload");
+}
+
+#[test]
+fn test_invalidate_forces_upload() {
+    let mut batch = QuadBatch::new();
+    batch.push(quad(0.0, 0.0, 8.0, 8.0, RED, QuadLayer::Background));
+    assert!(batch.prepare());
+    batch.invalidate();
+    assert!(batch.prepare());
+}
+
+#[test]
+fn test_empty_frame_after_content_uploads_once() {
+    let mut batch = QuadBatch::new();
+    batch.push(quad(0.0, 0.0, 8.0, 8.0, RED, QuadLayer::Background));
+    batch.prepare();
+    batch.clear();
+    assert!(batch.prepare());
+    assert!(batch.instances().is_empty());
+    assert!(!batch.prepare());
+}
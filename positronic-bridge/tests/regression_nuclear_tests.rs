@@ -118,7 +118,7 @@ fn nuclear_clear_resets_everything() {
     // After clear, detection should show either Normal or Running
     // (not Pager or Continuation).
     //
-    // builtins.rs: !clear sends \x03 → \r\n → cls
+    // commands/session.rs: !clear sends \x03 → \r\n → cls
     // update.rs: ClearScreen sends interrupt → sleep → raw(\r\n) → sleep → cls
     let snap = snapshot_with_lines(&["PS C:\\Users\\Doctor>"], 80, 24);
     let mode = detect_terminal_mode(&snap);
@@ -149,10 +149,10 @@ fn nuclear_cascading_bug_sequence() {
                "Bug 3: continuation prompt after pager");
 
     // 4. !clear only cleared UI (Bug 4) — now it sends commands to PTY
-    //    Verified by code review: builtins.rs sends \x03 + \r\n + cls
+    //    Verified by code review: commands/session.rs sends \x03 + \r\n + cls
 
     // 5. No !exit existed (Bug 5) — now it does
-    //    Verified by code review: builtins.rs handles "!exit" | "!quit"
+    //    Verified by code review: commands/session.rs handles "!exit" | "!quit"
 }
 
 #[test]
@@ -0,0 +1,237 @@
+// positronic-bridge/tests/render_path_tests.rs
+//
+// Tests for choosing a renderer: the fallback plan, selection against
+// fake adapters, device-loss recovery, and the software renderer's frames.
+
+use std::time::{Duration, Instant};
+
+use positronic_bridge::render_path::{
+    self, AdapterInfo, AdapterKind, Attempt, Backend, DeviceRecovery, Platform, Probed, RecoveryStep, RenderPath,
+    RenderReport, RendererChoice, RECOVERY_DELAYS,
+};
+use positronic_bridge::soft_frame::{self, SoftFrame, CELL_H, CELL_W};
+
+fn hw(backend: Backend) -> Attempt {
+    Attempt { backend, cpu: false }
+}
+
+fn cpu(backend: Backend) -> Attempt {
+    Attempt { backend, cpu: true }
+}
+
+/// A fake probe: each attempt in `adapters` opens that adapter, anything
+/// else fails. Records what was tried.
+fn fake<'a, 'b>(
+    adapters: &'b [(Attempt, &'a str, AdapterKind)],
+    tried: &'b mut Vec<Attempt>,
+) -> impl FnMut(Attempt) -> Result<Probed<&'a str>, String> + 'b {
+    move |attempt| {
+        tried.push(attempt);
+        adapters
+            .iter()
+            .find(|(a, _, _)| *a == attempt)
+            .map(|(_, name, kind)| Probed {
+                info: AdapterInfo { name: name.to_string(), backend: attempt.backend, kind: *kind },
+                device: *name,
+            })
+            .ok_or_else(|| "no adapter".to_string())
+    }
+}
+
+// ============================================================================
+// Plans
+// ============================================================================
+
+#[test]
+fn test_auto_plan_tries_hardware_then_cpu_per_platform() {
+    assert_eq!(
+        render_path::plan(RendererChoice::Auto, Platform::Windows),
+        vec![hw(Backend::Vulkan), hw(Backend::Dx12), hw(Backend::Gl), cpu(Backend::Vulkan), cpu(Backend::Dx12), cpu(Backend::Gl)]
+    );
+    assert_eq!(
+        render_path::plan(RendererChoice::Auto, Platform::MacOs),
+        vec![hw(Backend::Metal), hw(Backend::Gl), cpu(Backend::Metal), cpu(Backend::Gl)]
+    );
+    assert_eq!(
+        render_path::plan(RendererChoice::Backend(Backend::Gl), Platform::Other),
+        vec![hw(Backend::Gl), cpu(Backend::Gl)]
+    );
+    assert!(render_path::plan(RendererChoice::Software, Platform::Other).is_empty());
+}
+
+#[test]
+fn test_renderer_choice_parse_and_resolve() {
+    assert_eq!(RendererChoice::parse("Vulkan"), Some(RendererChoice::Backend(Backend::Vulkan)));
+    assert_eq!(RendererChoice::parse("opengl"), Some(RendererChoice::Backend(Backend::Gl)));
+    assert_eq!(RendererChoice::parse("software"), Some(RendererChoice::Software));
+    assert_eq!(RendererChoice::parse("directx9"), None);
+
+    let gl = Some(RendererChoice::Backend(Backend::Gl));
+    assert_eq!(RendererChoice::resolve(gl, Some("software")), RendererChoice::Backend(Backend::Gl), "the flag wins");
+    assert_eq!(RendererChoice::resolve(None, Some("software")), RendererChoice::Software);
+    assert_eq!(RendererChoice::resolve(None, Some("bogus")), RendererChoice::Auto);
+    assert_eq!(RendererChoice::resolve(None, None), RendererChoice::Auto);
+    assert_eq!(RendererChoice::Backend(Backend::Dx12).to_string(), "dx12");
+}
+
+// ============================================================================
+// Selection with fake adapters
+// ============================================================================
+
+#[test]
+fn test_select_falls_through_to_the_first_backend_that_opens() {
+    let plan = render_path::plan(RendererChoice::Auto, Platform::Windows);
+    let adapters = [(hw(Backend::Gl), "Intel UHD", AdapterKind::Integrated)];
+    let mut tried = Vec::new();
+    let selection = render_path::select(&plan, fake(&adapters, &mut tried));
+
+    assert_eq!(selection.device, Some("Intel UHD"));
+    assert_eq!(tried, vec![hw(Backend::Vulkan), hw(Backend::Dx12), hw(Backend::Gl)]);
+    assert_eq!(selection.skipped.len(), 2);
+    let RenderPath::Gpu { attempt, adapter } = &selection.path else {
+        panic!("expected a GPU path");
+    };
+    assert_eq!(*attempt, hw(Backend::Gl));
+    assert_eq!(adapter.kind, AdapterKind::Integrated);
+    assert_eq!(selection.path.summary(), "GL · Intel UHD (integrated GPU)");
+}
+
+#[test]
+fn test_select_prefers_hardware_on_a_later_backend_over_a_cpu_adapter() {
+    let plan = render_path::plan(RendererChoice::Auto, Platform::Other);
+    let adapters = [
+        (hw(Backend::Vulkan), "llvmpipe", AdapterKind::Cpu),
+        (hw(Backend::Gl), "Mesa Intel", AdapterKind::Integrated),
+    ];
+    let mut tried = Vec::new();
+    let selection = render_path::select(&plan, fake(&adapters, &mut tried));
+    assert_eq!(selection.device, Some("Mesa Intel"));
+
+    // With no hardware anywhere, the held CPU adapter is used without
+    // trying the CPU attempts.
+    let adapters = [(hw(Backend::Vulkan), "llvmpipe", AdapterKind::Cpu)];
+    let mut tried = Vec::new();
+    let selection = render_path::select(&plan, fake(&adapters, &mut tried));
+    assert_eq!(selection.device, Some("llvmpipe"));
+    assert_eq!(tried, vec![hw(Backend::Vulkan), hw(Backend::Gl)]);
+    assert_eq!(selection.skipped, vec![(hw(Backend::Gl), "no adapter".to_string())]);
+}
+
+#[test]
+fn test_select_ends_in_software_and_reports_why() {
+    let plan = render_path::plan(RendererChoice::Backend(Backend::Vulkan), Platform::Other);
+    let mut tried = Vec::new();
+    let selection = render_path::select(&plan, fake(&[], &mut tried));
+    assert_eq!(selection.path, RenderPath::Software);
+    assert!(selection.device.is_none());
+    assert_eq!(tried, vec![hw(Backend::Vulkan), cpu(Backend::Vulkan)]);
+
+    let report = RenderReport::new(RendererChoice::Backend(Backend::Vulkan), &selection);
+    let lines = report.lines();
+    assert!(lines[0].contains("software") && lines[0].contains("--renderer vulkan"), "{:?}", lines);
+    assert!(lines[1].contains("Vulkan: no adapter"), "{:?}", lines);
+    assert!(lines.last().unwrap().contains("No usable GPU"), "{:?}", lines);
+
+    // Asking for software is not a warning.
+    let selection = render_path::select::<()>(&[], |_| unreachable!());
+    let report = RenderReport::new(RendererChoice::Software, &selection);
+    assert_eq!(report.lines().len(), 1);
+}
+
+// ============================================================================
+// Device loss
+// ============================================================================
+
+#[test]
+fn test_recovery_reopens_with_backoff_then_falls_back() {
+    let start = Instant::now();
+    let mut recovery = DeviceRecovery::default();
+    assert_eq!(recovery.step(start), RecoveryStep::Idle);
+
+    recovery.lost("device removed", start);
+    assert_eq!(recovery.reason(), Some("device removed"));
+    assert_eq!(recovery.step(start), RecoveryStep::Reopen { attempt: 1 });
+
+    recovery.reopen_failed(start);
+    assert_eq!(recovery.step(start), RecoveryStep::Wait(RECOVERY_DELAYS[1]));
+    let later = start + RECOVERY_DELAYS[1];
+    assert_eq!(recovery.step(later), RecoveryStep::Reopen { attempt: 2 });
+
+    recovery.reopen_failed(later);
+    let later = later + RECOVERY_DELAYS[2];
+    assert_eq!(recovery.step(later), RecoveryStep::Reopen { attempt: 3 });
+    recovery.reopen_failed(later);
+    assert_eq!(recovery.step(later + Duration::from_secs(60)), RecoveryStep::FallBack);
+
+    let line = recovery.restored(&RenderPath::Software).unwrap();
+    assert!(line.contains("device removed") && line.contains("software"), "{}", line);
+    assert_eq!(recovery.recoveries(), 1);
+    assert_eq!(recovery.step(later), RecoveryStep::Idle);
+    assert!(recovery.restored(&RenderPath::Software).is_none(), "nothing to restore twice");
+}
+
+#[test]
+fn test_fallback_plan_continues_after_the_lost_attempt() {
+    let plan = render_path::plan(RendererChoice::Auto, Platform::Windows);
+    let rest = render_path::fallback_plan(&plan, hw(Backend::Dx12));
+    assert_eq!(rest[0], hw(Backend::Gl));
+    assert!(!rest.contains(&hw(Backend::Dx12)));
+
+    let adapters = [(cpu(Backend::Vulkan), "WARP", AdapterKind::Cpu)];
+    let mut tried = Vec::new();
+    let selection = render_path::select(&rest, fake(&adapters, &mut tried));
+    assert_eq!(selection.device, Some("WARP"));
+}
+
+// ============================================================================
+// Software frames
+// ============================================================================
+
+#[test]
+fn test_soft_frame_size_and_text() {
+    let (width, height) = (CELL_W * 2 * 10, CELL_H * 2 * 4);
+    let mut frame = SoftFrame::new(width, height, 0);
+    assert_eq!(frame.pixels.len(), width * height);
+    assert_eq!(frame.grid_size(2), (10, 4));
+    assert_eq!(frame.grid_size(1), (20, 8));
+
+    frame.draw_text(0, 0, "I", 0xFF_FFFF, 2);
+    let lit = frame.pixels.iter().filter(|p| **p == 0xFF_FFFF).count();
+    // 'I' is 7 + 2 + 2 lit dots, each 2x2 at scale 2.
+    assert_eq!(lit, 11 * 4);
+
+    // Off the grid, and past its right edge, nothing is drawn.
+    let before = frame.clone();
+    frame.draw_text(0, 4, "x", 0xFF_FFFF, 2);
+    frame.draw_text(10, 0, "x", 0xFF_FFFF, 2);
+    assert_eq!(frame, before);
+
+    // Characters the font lacks draw as '?'.
+    let mut a = SoftFrame::new(width, height, 0);
+    let mut b = SoftFrame::new(width, height, 0);
+    a.draw_text(0, 0, "é", 1, 1);
+    b.draw_text(0, 0, "?", 1, 1);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_screen_lines_keep_the_tail_and_the_prompt() {
+    let lines = soft_frame::screen_lines("a\nb\n", "c\nd\n\n\n", "> ls", 3);
+    assert_eq!(lines, vec!["c", "d", "> ls"]);
+    let lines = soft_frame::screen_lines("", "", "> ", 3);
+    assert_eq!(lines, vec!["", "", "> "]);
+    assert!(soft_frame::screen_lines("a", "", "> ", 0).is_empty());
+
+    let grey = positronic_bridge::renderer::Rgba::new(0.5, 0.5, 0.5, 1.0);
+    assert_eq!(soft_frame::pack(grey), 0x0080_8080);
+}
+
+#[test]
+fn test_set_notice_goes_above_the_prompt() {
+    let mut lines = soft_frame::screen_lines("", "a\nb\n", "> ls", 3);
+    soft_frame::set_notice(&mut lines, "! Copy failed x2");
+    assert_eq!(lines, vec!["a", "! Copy failed x2", "> ls"]);
+    let mut prompt_only = soft_frame::screen_lines("", "a", "> ", 1);
+    soft_frame::set_notice(&mut prompt_only, "! Copy failed");
+    assert_eq!(prompt_only, vec!["> "]);
+}
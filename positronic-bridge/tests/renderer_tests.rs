@@ -5,14 +5,19 @@
 //   Rgba         — constructors, sRGB conversion, clamping, traits
 //   ColoredSpan  — constructors, clone
 //   ThemeName    — enum variants, label/from_str round-trip, theme colors
-//   direct_to_spans()   — emoji-keyed color coding for all prefix patterns
+//   direct_to_spans()   — emoji-keyed color coding for all prefix patterns,
+//                         status colors for !timeline bars, and struck
+//                         and colored words in correction previews
 //   snapshot_to_spans() — PTY snapshot → colored spans with MyColor mapping
 //   snapshot_to_plain() — PTY snapshot → clipboard-ready plain text
 
+use positronic_bridge::block::LineKind;
 use positronic_bridge::renderer::{
-    ColoredSpan, Rgba, ThemeName, direct_to_spans, snapshot_to_plain, snapshot_to_spans,
+    ColoredSpan, Rgba, ThemeName, direct_line_spans, direct_to_spans, line_kind_color,
+    snapshot_to_plain, snapshot_to_spans, timeline_color,
 };
-use positronic_core::state_machine::{MyColor, Snapshot};
+use positronic_core::state_machine::{MyColor, Snapshot, StateMachine};
+use positronic_core::timeline::Status;
 
 // ════════════════════════════════════════════════════════════════════
 // Helpers
@@ -517,6 +522,17 @@ fn test_direct_multiline() {
     assert!(approx_eq(spans[2].color.r, 0.85));
 }
 
+#[test]
+fn test_direct_diff_lines_use_line_kind_colors() {
+    let spans = direct_to_spans("@@ -1,2 +1,2 @@\n │ same\n-│ old\n+│ new\n- plain dash");
+    assert_eq!(spans[0].color, line_kind_color(LineKind::Info));
+    assert_eq!(spans[1].color, line_kind_color(LineKind::Normal));
+    assert_eq!(spans[2].color, line_kind_color(LineKind::Error));
+    assert_eq!(spans[3].color, line_kind_color(LineKind::Success));
+    // Only the gutter marks a diff line.
+    assert_eq!(spans[4].color, line_kind_color(LineKind::Normal));
+}
+
 #[test]
 fn test_direct_each_line_gets_newline() {
     let spans = direct_to_spans("line1\nline2");
@@ -524,6 +540,36 @@ fn test_direct_each_line_gets_newline() {
     assert!(spans[1].text.ends_with('\n'));
 }
 
+#[test]
+fn test_direct_timeline_bar_segments_take_status_colors() {
+    let spans = direct_line_spans("09:40 ▕▒▒██▓┃  ▏   4   4m 30s   2✗  /p/app");
+    let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, ["09:40 ▕", "▒▒", "██", "▓┃", "  ", "▏   4   4m 30s   2✗  /p/app\n"]);
+    assert_eq!(spans[1].color, timeline_color(Status::Unknown));
+    assert_eq!(spans[2].color, timeline_color(Status::Ok));
+    assert_eq!(spans[3].color, line_kind_color(LineKind::Error));
+    assert_eq!(spans[4].color, line_kind_color(LineKind::Normal));
+
+    // The summary and legend lines have no bar.
+    assert_eq!(direct_line_spans("      █ ok  ▓ failed").len(), 1);
+    assert_eq!(direct_to_spans("a\n09:00 ▕█▏\nb").len(), 5);
+}
+
+#[test]
+fn test_direct_correction_preview_strikes_removed_and_colors_added() {
+    let spans = direct_line_spans("🩹 git [-psuh-]{+push+} origin");
+    let texts: Vec<&str> = spans.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, ["🩹 git ", "psuh", "push", " origin", "\n"]);
+    assert_eq!(spans[1].color, line_kind_color(LineKind::Error));
+    assert!(spans[1].style.strikethrough);
+    assert_eq!(spans[2].color, line_kind_color(LineKind::Success));
+    assert!(!spans[2].style.strikethrough);
+    assert!(!spans[0].style.strikethrough);
+
+    // Markers only mean something on a preview line.
+    assert_eq!(direct_line_spans("ls [-a-]").len(), 1);
+}
+
 // ════════════════════════════════════════════════════════════════════
 // snapshot_to_spans — Basic Rendering
 // ════════════════════════════════════════════════════════════════════
@@ -966,6 +1012,21 @@ fn test_plain_all_whitespace_rows_included_after_content() {
     assert!(text.contains("Bottom"));
 }
 
+#[test]
+fn test_plain_rejoins_a_long_line_at_any_width() {
+    let long: String = (0..500).map(|i| char::from(b'a' + (i % 26) as u8)).collect();
+    let sm = StateMachine::new(80, 48);
+    sm.process_bytes(format!("$ echo\r\n{}\r\n$ ", long).as_bytes());
+    for cols in [37u16, 120, 13, 200, 80] {
+        sm.resize(cols, 48);
+        sm.set_display_offset(sm.history_size());
+        let text = snapshot_to_plain(&sm.snapshot());
+        let lines: Vec<&str> = text.lines().take(3).collect();
+        assert_eq!(lines, vec!["$ echo", long.as_str(), "$"], "{} columns", cols);
+        sm.set_display_offset(0);
+    }
+}
+
 // ════════════════════════════════════════════════════════════════════
 // Edge Cases
 // ════════════════════════════════════════════════════════════════════
@@ -0,0 +1,54 @@
+// positronic-bridge/tests/replay_tests.rs
+//
+// Tests for `!record play`: casts replay into their own screen by time.
+
+use std::time::Duration;
+
+use positronic_bridge::renderer::snapshot_to_plain;
+use positronic_bridge::replay::Replay;
+use positronic_core::asciicast::Cast;
+
+const CAST: &str = r#"{"version": 2, "width": 40, "height": 5, "timestamp": 1700000000}
+[0.1, "o", "$ "]
+[1.0, "i", "echo hi\r"]
+[1.0, "o", "echo hi\r\n"]
+[2.0, "o", "hi\r\n$ "]
+[3.0, "r", "60x8"]
+"#;
+
+fn replay(speed: f64) -> Replay {
+    Replay::new("demo.cast", Cast::parse(CAST).unwrap(), speed)
+}
+
+#[test]
+fn test_replay_follows_the_clock() {
+    let mut r = replay(1.0);
+    assert!(!r.advance(Duration::from_millis(50)));
+    assert!(r.advance(Duration::from_millis(1_500)));
+    let screen = snapshot_to_plain(&r.snapshot());
+    assert!(screen.contains("$ echo hi") && !screen.contains("\nhi"), "{}", screen);
+
+    r.advance(Duration::from_secs(5));
+    assert!(r.is_done());
+    assert!(snapshot_to_plain(&r.snapshot()).contains("hi"));
+    assert_eq!(r.snapshot().cols(), 60);
+    assert!(r.footer().starts_with("■ demo.cast"));
+}
+
+#[test]
+fn test_replay_speed_scales_time() {
+    let mut r = replay(4.0);
+    r.advance(Duration::from_millis(800));
+    assert!(r.is_done(), "3s of recording plays in 0.75s at 4x");
+    assert!(r.footer().contains("(4x)"));
+}
+
+#[test]
+fn test_replay_input_does_not_reach_the_screen() {
+    let cast = r#"{"version": 2, "width": 20, "height": 3}
+[0.5, "i", "secret-typed"]
+"#;
+    let mut r = Replay::new("x.cast", Cast::parse(cast).unwrap(), 1.0);
+    assert!(!r.advance(Duration::from_secs(1)));
+    assert!(!snapshot_to_plain(&r.snapshot()).contains("secret"));
+}
@@ -0,0 +1,148 @@
+// positronic-bridge/tests/resize_tests.rs
+//
+// Tests for debounced window resizes: the state machine on an injected
+// clock, a burst of synthetic events reaching the PTY as one resize, and
+// the interim scale that fits the old grid into the new window.
+
+use std::time::{Duration, Instant};
+
+use positronic_bridge::layout::{self, CellMetrics, LayoutSpec};
+use positronic_bridge::resize::{
+    interim_scale, Grid, ResizeDebounce, MAX_INTERIM_SCALE, MIN_INTERIM_SCALE, SETTLE,
+};
+use positronic_bridge::viewport::ScrollMark;
+
+const CELL: CellMetrics = CellMetrics { width: 8.0, height: 18.0 };
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+/// The grid `layout::compute` fits into a window, at a DPI scale.
+fn grid(size: [u32; 2], scale: f32) -> Grid {
+    let lay = layout::compute(size, &LayoutSpec::default(), 10.0, CELL, scale);
+    (lay.cols, lay.rows)
+}
+
+/// Drive `debounce` through resize events at `events` (time, window
+/// size, DPI scale), ticking every 5 ms the way the event loop wakes,
+/// and return what reaches the PTY.
+fn run(debounce: &mut ResizeDebounce, t0: Instant, events: &[(u64, [u32; 2], f32)]) -> Vec<Grid> {
+    let mut pty = Vec::new();
+    let end = events.last().map_or(0, |(at, _, _)| *at) + SETTLE.as_millis() as u64 * 2;
+    let mut window = ([0, 0], 1.0);
+    let mut next = events.iter().peekable();
+    for now in (0..=end).step_by(5) {
+        while let Some((_, size, scale)) = next.next_if(|(at, _, _)| *at <= now) {
+            window = (*size, *scale);
+            debounce.event(t0 + ms(now));
+        }
+        if debounce.take_due(t0 + ms(now)) {
+            pty.extend(debounce.commit(grid(window.0, window.1), ScrollMark::Anchored));
+        }
+    }
+    pty
+}
+
+#[test]
+fn test_events_wait_for_the_drag_to_settle() {
+    let t0 = Instant::now();
+    let mut debounce = ResizeDebounce::default();
+    assert!(!debounce.is_pending());
+    assert_eq!(debounce.next_wake(), None);
+
+    debounce.event(t0);
+    debounce.event(t0 + ms(16));
+    assert!(debounce.is_pending());
+    assert_eq!(debounce.next_wake(), Some(t0 + ms(16) + SETTLE));
+    assert!(!debounce.take_due(t0 + ms(100)));
+    assert!(debounce.take_due(t0 + ms(16) + SETTLE));
+    assert!(!debounce.take_due(t0 + ms(1000)), "once per settle");
+    assert!(!debounce.is_pending());
+}
+
+#[test]
+fn test_commit_skips_the_grid_the_pty_has() {
+    let mut debounce = ResizeDebounce::default();
+    assert_eq!(debounce.commit((120, 30), ScrollMark::Anchored), Some((120, 30)));
+    assert_eq!(debounce.committed(), Some((120, 30)));
+    assert_eq!(debounce.commit((120, 30), ScrollMark::Anchored), None);
+
+    // An immediate commit (a `layout.*` change) ends a pending drag.
+    debounce.event(Instant::now());
+    assert_eq!(debounce.commit((100, 30), ScrollMark::Anchored), Some((100, 30)));
+    assert!(!debounce.is_pending());
+}
+
+#[test]
+fn test_scroll_position_comes_back_once_the_emulator_has_the_grid() {
+    let mut debounce = ResizeDebounce::default();
+    let mark = ScrollMark::Back { top: 40, total: 500 };
+    debounce.commit((100, 40), mark);
+    assert_eq!(debounce.settled((120, 30)), None, "the emulator hasn't followed yet");
+    assert_eq!(debounce.settled((100, 40)), Some(mark));
+    assert_eq!(debounce.settled((100, 40)), None, "once");
+}
+
+#[test]
+fn test_a_drag_reaches_the_pty_as_one_resize() {
+    let t0 = Instant::now();
+    let mut debounce = ResizeDebounce::default();
+    debounce.commit(grid([1280, 800], 1.0), ScrollMark::Anchored);
+
+    // 60 frames of a drag, 16 ms apart, growing the window each time.
+    let drag: Vec<(u64, [u32; 2], f32)> =
+        (0..60u32).map(|i| (i as u64 * 16, [1280 + i * 7, 800 + i * 3], 1.0)).collect();
+    let pty = run(&mut debounce, t0, &drag);
+    assert_eq!(pty, [grid([1280 + 59 * 7, 800 + 59 * 3], 1.0)]);
+}
+
+#[test]
+fn test_maximize_and_restore_in_quick_succession_sends_nothing() {
+    let t0 = Instant::now();
+    let mut debounce = ResizeDebounce::default();
+    debounce.commit(grid([1280, 800], 1.0), ScrollMark::Anchored);
+    let pty = run(&mut debounce, t0, &[(0, [2560, 1400], 1.0), (60, [1280, 800], 1.0)]);
+    assert!(pty.is_empty(), "{:?}", pty);
+
+    // Apart, each is its own resize.
+    let pty = run(&mut debounce, t0 + ms(1000), &[(0, [2560, 1400], 1.0), (400, [1280, 800], 1.0)]);
+    assert_eq!(pty, [grid([2560, 1400], 1.0), grid([1280, 800], 1.0)]);
+}
+
+#[test]
+fn test_a_dpi_change_mid_drag_commits_the_final_metrics() {
+    let t0 = Instant::now();
+    let mut debounce = ResizeDebounce::default();
+    debounce.commit(grid([1600, 900], 1.0), ScrollMark::Anchored);
+    let events = [(0, [1500, 900], 1.0), (16, [1400, 900], 1.0), (32, [1400, 900], 2.0), (48, [1380, 900], 2.0)];
+    let pty = run(&mut debounce, t0, &events);
+    assert_eq!(pty, [grid([1380, 900], 2.0)]);
+    assert_ne!(pty[0], grid([1380, 900], 1.0));
+}
+
+#[test]
+fn test_interim_scale_fits_the_old_grid() {
+    let spec = LayoutSpec::default();
+    let lay = layout::compute([1280, 800], &spec, 10.0, CELL, 1.0);
+    assert_eq!(interim_scale((lay.cols, lay.rows), &lay), 1.0);
+
+    // Half as wide: the old grid is drawn at about half size.
+    let narrow = layout::compute([640, 800], &spec, 10.0, CELL, 1.0);
+    let scale = interim_scale((lay.cols, lay.rows), &narrow);
+    assert!(scale < 0.55 && scale > 0.45, "{}", scale);
+    let width = lay.cols as f32 * lay.cell_w * scale;
+    assert!(width <= narrow.terminal_w - narrow.padding * 2.0 + 0.5);
+
+    // Taller only: the width limits it, so it stays about the same.
+    let tall = layout::compute([1280, 1600], &spec, 10.0, CELL, 1.0);
+    let scale = interim_scale((lay.cols, lay.rows), &tall);
+    assert!((0.95..=1.05).contains(&scale), "{}", scale);
+
+    // Tiny and huge windows stay within the limits.
+    let tiny = layout::compute([200, 200], &spec, 10.0, CELL, 1.0);
+    assert_eq!(interim_scale((400, 200), &tiny), MIN_INTERIM_SCALE);
+    let huge = layout::compute([7680, 4320], &spec, 10.0, CELL, 1.0);
+    assert_eq!(interim_scale((40, 10), &huge), MAX_INTERIM_SCALE);
+    assert_eq!(interim_scale((0, 0), &huge), 1.0);
+}
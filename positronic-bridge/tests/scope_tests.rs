@@ -0,0 +1,147 @@
+// positronic-bridge/tests/scope_tests.rs
+//
+// Tests for the `!scope` pane: command parsing, zoom, value ranges and the
+// waveform-to-pixel geometry.
+
+use positronic_bridge::scope::{
+    auto_range, format_value, format_window, polyline, segment_quads, window_end, ScopeCommand, ScopeView, Viewport,
+    YRange, MAX_WINDOW, MIN_WINDOW, SCOPE_USAGE,
+};
+
+const VP: Viewport = Viewport { x: 100.0, y: 50.0, w: 200.0, h: 100.0 };
+
+// ============================================================================
+// Command and view state
+// ============================================================================
+
+#[test]
+fn scope_command_parses_port_range_and_window() {
+    assert_eq!(ScopeCommand::parse("!scope"), Ok(ScopeCommand::Toggle));
+    assert_eq!(ScopeCommand::parse("!scope off"), Ok(ScopeCommand::Off));
+    assert_eq!(
+        ScopeCommand::parse("!scope COM3 --range -1:2.5 --window 30s"),
+        Ok(ScopeCommand::Open {
+            port: Some("COM3".to_string()),
+            range: Some(YRange::Fixed(-1.0, 2.5)),
+            window: Some(30.0),
+        })
+    );
+    assert_eq!(
+        ScopeCommand::parse("!scope --range auto"),
+        Ok(ScopeCommand::Open { port: None, range: Some(YRange::Auto), window: None })
+    );
+    assert_eq!(
+        ScopeCommand::parse("!scope --window 0.001"),
+        Ok(ScopeCommand::Open { port: None, range: None, window: Some(MIN_WINDOW) })
+    );
+
+    assert!(ScopeCommand::parse("!scope --range 5:1").unwrap_err().contains("5:1"));
+    assert!(ScopeCommand::parse("!scope --window soon").unwrap_err().contains("soon"));
+    assert_eq!(ScopeCommand::parse("!scope COM3 COM4"), Err(SCOPE_USAGE.to_string()));
+    assert_eq!(ScopeCommand::parse("!scope --range"), Err(SCOPE_USAGE.to_string()));
+}
+
+#[test]
+fn scope_view_zooms_within_limits() {
+    let mut view = ScopeView::new("COM3");
+    assert_eq!(view.title(), "COM3 · 10s · auto");
+    assert_eq!(view.title_with("bench-scope (COM3)"), "bench-scope (COM3) · 10s · auto");
+    view.zoom_in();
+    view.zoom_in();
+    assert_eq!(format_window(view.window), "2.5s");
+    for _ in 0..20 {
+        view.zoom_in();
+    }
+    assert_eq!(view.window, MIN_WINDOW);
+    assert_eq!(format_window(view.window), "100ms");
+    for _ in 0..20 {
+        view.zoom_out();
+    }
+    assert_eq!(view.window, MAX_WINDOW);
+
+    view.range = YRange::Fixed(0.0, 5.0);
+    assert_eq!(view.value_range(&[vec![(0.0, 100.0)]], 0.0), (0.0, 5.0));
+}
+
+#[test]
+fn auto_range_fits_visible_samples() {
+    let (lo, hi) = auto_range([0.0, 10.0, 5.0]).unwrap();
+    assert!((lo + 0.5).abs() < 1e-6 && (hi - 10.5).abs() < 1e-6, "5% padding: {} {}", lo, hi);
+    assert_eq!(auto_range([3.0, 3.0]), Some((2.5, 3.5)), "a flat line gets a band");
+    assert_eq!(auto_range([f32::NAN]), None);
+    assert_eq!(auto_range(std::iter::empty()), None);
+
+    // Only the window counts: the spike 20s ago is off screen.
+    let view = ScopeView::new("COM3");
+    let traces = vec![vec![(0.0, 1000.0), (15.0, 1.0), (20.0, 2.0)], vec![(18.0, 3.0)]];
+    assert_eq!(window_end(&traces), Some(20.0));
+    let (lo, hi) = view.value_range(&traces, 20.0);
+    assert!(lo > 0.8 && hi < 3.2, "{} {}", lo, hi);
+    assert_eq!(window_end(&[vec![]]), None);
+}
+
+// ============================================================================
+// Geometry
+// ============================================================================
+
+#[test]
+fn polyline_maps_time_and_value_to_the_viewport() {
+    let samples = [(0.0, 0.0), (5.0, 10.0), (10.0, 5.0)];
+    let points = polyline(&samples, 10.0, 10.0, (0.0, 10.0), VP);
+    assert_eq!(points, vec![(100.0, 150.0), (200.0, 50.0), (300.0, 100.0)]);
+
+    // Out-of-range values stick to the edges.
+    let points = polyline(&[(0.0, -5.0), (10.0, 50.0)], 10.0, 10.0, (0.0, 10.0), VP);
+    assert_eq!(points, vec![(100.0, 150.0), (300.0, 50.0)]);
+}
+
+#[test]
+fn polyline_enters_from_the_left_edge_and_skips_the_rest() {
+    // Window [4, 8]: 0 and 10 are outside; 0 → 4 is interpolated at the edge.
+    let samples = [(0.0, 0.0), (6.0, 6.0), (10.0, 10.0)];
+    let points = polyline(&samples, 8.0, 4.0, (0.0, 10.0), VP);
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].0, VP.x);
+    assert!((points[0].1 - 110.0).abs() < 1e-4, "value 4 at the edge: {:?}", points[0]);
+    assert_eq!(points[1], (200.0, 90.0));
+
+    assert!(polyline(&[], 8.0, 4.0, (0.0, 10.0), VP).is_empty());
+    assert!(polyline(&[(0.0, 1.0)], 8.0, 4.0, (0.0, 10.0), VP).is_empty(), "nothing visible");
+    assert_eq!(polyline(&[(7.0, f32::NAN), (8.0, 0.0)], 8.0, 4.0, (0.0, 10.0), VP), vec![(300.0, 150.0)]);
+}
+
+#[test]
+fn polyline_keeps_two_points_per_pixel_column() {
+    // 10 000 samples of a square-ish wave across 200 px.
+    let samples: Vec<(f64, f32)> = (0..10_000).map(|i| (i as f64 / 1000.0, if i % 7 < 3 { 0.0 } else { 10.0 })).collect();
+    let points = polyline(&samples, 10.0, 10.0, (0.0, 10.0), VP);
+    assert!(points.len() <= 2 * (VP.w as usize + 1), "{} points", points.len());
+    // Every column still shows the full swing.
+    assert!(points.iter().any(|p| p.1 == 50.0) && points.iter().any(|p| p.1 == 150.0));
+    assert!(points.windows(2).all(|w| w[0].0 <= w[1].0), "left to right");
+
+    // Order inside a column follows the samples: up then down.
+    let points = polyline(&[(0.0, 5.0), (0.001, 10.0), (0.002, 0.0)], 10.0, 10.0, (0.0, 10.0), VP);
+    assert_eq!(points.iter().map(|p| p.1).collect::<Vec<_>>(), vec![50.0, 150.0]);
+}
+
+#[test]
+fn segment_quads_step_between_points() {
+    assert!(segment_quads(&[], 2.0).is_empty());
+    assert_eq!(segment_quads(&[(10.0, 10.0)], 2.0), vec![[9.0, 9.0, 2.0, 2.0]]);
+    assert_eq!(
+        segment_quads(&[(10.0, 20.0), (30.0, 5.0)], 2.0),
+        vec![[9.0, 19.0, 22.0, 2.0], [29.0, 4.0, 2.0, 17.0]],
+        "across at the first height, then up at the second x"
+    );
+    // Flat or vertical runs need one quad.
+    assert_eq!(segment_quads(&[(0.0, 5.0), (4.0, 5.0), (4.0, 9.0)], 1.0).len(), 2);
+}
+
+#[test]
+fn values_are_shown_compactly() {
+    assert_eq!(format_value(21.5), "21.5");
+    assert_eq!(format_value(3.0), "3");
+    assert_eq!(format_value(0.123456), "0.1235");
+    assert_eq!(format_value(-0.00001), "0");
+}
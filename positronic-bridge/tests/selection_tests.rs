@@ -0,0 +1,195 @@
+// positronic-bridge/tests/selection_tests.rs
+//
+// Tests for mouse selection, the interrupt chord and Escape: pixel-to-cell
+// mapping, which columns each row covers, the copied text, what Ctrl+C
+// does for every mode, running, selection and `!` command combination,
+// and what Escape closes or cancels first.
+
+use positronic_bridge::keymap::{
+    EscapeRoute, EscapeState, InterruptMode, InterruptRoute, Keymap, INTERRUPT_MODE_KEY,
+};
+use positronic_bridge::selection::{GridPos, Selection};
+use positronic_core::state_machine::{Snapshot, StateMachine};
+
+fn screen(cols: u16, bytes: &str) -> Snapshot {
+    let sm = StateMachine::new(cols, 4);
+    sm.process_bytes(bytes.as_bytes());
+    sm.snapshot()
+}
+
+fn at(row: usize, col: usize) -> GridPos {
+    GridPos { row, col }
+}
+
+fn drag(from: GridPos, to: GridPos) -> Selection {
+    let mut selection = Selection::new(from);
+    selection.extend(to);
+    selection
+}
+
+// ============================================================================
+// Interrupt routing
+// ============================================================================
+
+#[test]
+fn test_smart_mode_routes_every_state() {
+    let smart = InterruptMode::Smart;
+    for native in [true, false] {
+        assert_eq!(smart.route(true, true, native), InterruptRoute::Interrupt);
+        assert_eq!(smart.route(true, false, native), InterruptRoute::Interrupt);
+        assert_eq!(smart.route(false, true, native), InterruptRoute::CopySelection);
+    }
+    assert_eq!(smart.route(false, false, true), InterruptRoute::CancelNative);
+    assert_eq!(smart.route(false, false, false), InterruptRoute::Nothing);
+}
+
+#[test]
+fn test_always_mode_interrupts_in_every_state() {
+    for running in [true, false] {
+        for selection in [true, false] {
+            for native in [true, false] {
+                assert_eq!(InterruptMode::Always.route(running, selection, native), InterruptRoute::Interrupt);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_interrupt_mode_comes_from_config() {
+    assert_eq!(Keymap::default().interrupt_mode(), InterruptMode::Smart);
+
+    let keymap = Keymap::from_config(|key| (key == INTERRUPT_MODE_KEY).then(|| " Always ".to_string()));
+    assert_eq!(keymap.interrupt_mode(), InterruptMode::Always);
+    assert!(keymap.errors().is_empty());
+    assert!(keymap.lines().iter().any(|l| l.contains("Ctrl+C mode: always")));
+
+    let keymap = Keymap::from_config(|key| (key == INTERRUPT_MODE_KEY).then(|| "sometimes".to_string()));
+    assert_eq!(keymap.interrupt_mode(), InterruptMode::Smart);
+    assert_eq!(
+        keymap.errors(),
+        ["keys.interrupt_mode = \"sometimes\": expected smart or always (keeping smart)"]
+    );
+}
+
+#[test]
+fn test_each_route_has_a_status_note() {
+    assert!(InterruptRoute::Interrupt.note().contains("interrupt"));
+    assert!(InterruptRoute::CopySelection.note().contains("copied"));
+    assert!(InterruptRoute::CancelNative.note().contains("cancelled"));
+    assert!(InterruptRoute::Nothing.note().contains("nothing"));
+}
+
+// ============================================================================
+// Escape routing
+// ============================================================================
+
+#[test]
+fn test_escape_goes_to_the_shell_when_nothing_is_open() {
+    assert_eq!(EscapeRoute::pick(EscapeState::default()), EscapeRoute::Shell);
+}
+
+#[test]
+fn test_escape_cancels_a_native_command_before_the_shell() {
+    let state = EscapeState { native: true, ..Default::default() };
+    assert_eq!(EscapeRoute::pick(state), EscapeRoute::CancelNative);
+}
+
+#[test]
+fn test_escape_closes_what_is_open_before_cancelling() {
+    let native = EscapeState { native: true, ..Default::default() };
+    let cases = [
+        (EscapeState { pager: true, ..native }, EscapeRoute::ClosePager),
+        (EscapeState { passphrase: true, ..native }, EscapeRoute::CancelPassphrase),
+        (EscapeState { clipboard_picker: true, ..native }, EscapeRoute::ClosePicker),
+        (EscapeState { redo_offer: true, ..native }, EscapeRoute::DeclineRedo),
+        (EscapeState { correction: true, ..native }, EscapeRoute::DismissCorrection),
+        (EscapeState { suggestions: true, ..native }, EscapeRoute::CloseSuggestions),
+        (EscapeState { block_focus: true, ..native }, EscapeRoute::ClearBlockFocus),
+    ];
+    for (state, route) in cases {
+        assert_eq!(EscapeRoute::pick(state), route, "{:?}", state);
+    }
+}
+
+#[test]
+fn test_escape_closes_the_pager_before_anything_else() {
+    let everything = EscapeState {
+        pager: true,
+        passphrase: true,
+        clipboard_picker: true,
+        redo_offer: true,
+        correction: true,
+        suggestions: true,
+        block_focus: true,
+        native: true,
+    };
+    assert_eq!(EscapeRoute::pick(everything), EscapeRoute::ClosePager);
+    assert_eq!(EscapeRoute::pick(EscapeState { pager: false, ..everything }), EscapeRoute::CancelPassphrase);
+}
+
+// ============================================================================
+// Selection geometry
+// ============================================================================
+
+#[test]
+fn test_pixels_map_to_the_nearest_boundary() {
+    let origin = [10.0, 20.0];
+    let cell = [8.0, 16.0];
+    assert_eq!(GridPos::from_pixel(10.0, 20.0, origin, cell, 24, 80), at(0, 0));
+    // Past the middle of a cell rounds to its right edge.
+    assert_eq!(GridPos::from_pixel(15.0, 40.0, origin, cell, 24, 80), at(1, 1));
+    assert_eq!(GridPos::from_pixel(13.0, 40.0, origin, cell, 24, 80), at(1, 0));
+    // Off the grid clamps to its edges.
+    assert_eq!(GridPos::from_pixel(0.0, 0.0, origin, cell, 24, 80), at(0, 0));
+    assert_eq!(GridPos::from_pixel(5000.0, 5000.0, origin, cell, 24, 80), at(23, 80));
+}
+
+#[test]
+fn test_click_without_drag_selects_nothing() {
+    let selection = Selection::new(at(2, 5));
+    assert!(selection.is_empty());
+    assert_eq!(selection.columns(2, 80), None);
+}
+
+#[test]
+fn test_columns_follow_reading_order_either_way() {
+    let forward = drag(at(1, 4), at(3, 2));
+    let backward = drag(at(3, 2), at(1, 4));
+    for selection in [forward, backward] {
+        assert_eq!(selection.columns(0, 10), None);
+        assert_eq!(selection.columns(1, 10), Some(4..10));
+        assert_eq!(selection.columns(2, 10), Some(0..10));
+        assert_eq!(selection.columns(3, 10), Some(0..2));
+        assert_eq!(selection.columns(4, 10), None);
+    }
+}
+
+// ============================================================================
+// Selected text
+// ============================================================================
+
+#[test]
+fn test_text_spans_rows_and_trims_blanks() {
+    let snapshot = screen(10, "hello you\r\nsecond\r\nthird");
+    assert_eq!(drag(at(0, 6), at(0, 9)).text(&snapshot), "you");
+    assert_eq!(drag(at(0, 6), at(2, 3)).text(&snapshot), "you\nsecond\nthi");
+}
+
+#[test]
+fn test_text_joins_soft_wrapped_rows() {
+    let snapshot = screen(10, "0123456789abcdef ghi\r\nnext");
+    assert!(snapshot.wraps(0) && !snapshot.wraps(1));
+    assert_eq!(drag(at(0, 4), at(1, 6)).text(&snapshot), "456789abcdef");
+    assert_eq!(drag(at(0, 0), at(2, 4)).text(&snapshot), "0123456789abcdef ghi\nnext");
+
+    // A blank where the row wrapped is part of the line.
+    let snapshot = screen(6, "hello world");
+    assert_eq!(drag(at(0, 0), at(1, 5)).text(&snapshot), "hello world");
+}
+
+#[test]
+fn test_text_copies_wide_glyphs_once() {
+    let snapshot = screen(10, "a你好b");
+    assert_eq!(drag(at(0, 0), at(0, 6)).text(&snapshot), "a你好b");
+    assert_eq!(drag(at(0, 1), at(0, 3)).text(&snapshot), "你");
+}
@@ -0,0 +1,116 @@
+// positronic-bridge/tests/session_context_tests.rs
+//
+// Tests for split panes' session state: each pane's search, copy, export
+// and Holodeck see only its own output unless asked for every pane.
+
+use std::time::Duration;
+
+use positronic_bridge::block::{BlockLine, BlockSource};
+use positronic_bridge::marks::{Mark, MarkTarget};
+use positronic_bridge::session_context::{PaneId, Scope, ScrollbackExport, Sessions};
+use positronic_core::blocks::TerminalBlockV2;
+
+/// Run `command` printing `output` in the focused pane, Holodeck and all.
+fn run(sessions: &mut Sessions, command: &str, output: &str) {
+    let pane = sessions.focused_mut();
+    let id = pane.blocks.begin(command, "/w", BlockSource::Shell);
+    pane.blocks.append_line(id, BlockLine::normal(output));
+    pane.blocks.finish(id, Some(0), Duration::from_millis(5));
+    pane.holodeck.ingest(output);
+}
+
+/// Two panes, each having run one command.
+fn split() -> Sessions {
+    let mut sessions = Sessions::new();
+    run(&mut sessions, "cat left.json", r#"{"pane": "left"}"#);
+    assert_eq!(sessions.open(), PaneId(2));
+    run(&mut sessions, "tail server.log", "GET /health 200");
+    sessions
+}
+
+#[test]
+fn panes_start_empty_and_keep_their_own_output() {
+    let mut sessions = split();
+    assert!(sessions.is_split());
+    assert_eq!(sessions.focused_id(), PaneId(2));
+    assert_eq!(sessions.status_label().as_deref(), Some("pane 2/2"));
+
+    let hits = sessions.search("pane", Scope::Focused);
+    assert!(hits.is_empty(), "{:?}", hits);
+    let hits = sessions.search("health", Scope::Focused);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].pane, PaneId(2));
+
+    // Both panes numbered their first block 1; copy takes the focused one.
+    assert!(sessions.copy_block(1).unwrap().starts_with("$ tail server.log\n"));
+    let export = sessions.export(Scope::Focused, false);
+    assert!(export.contains("tail server.log") && !export.contains("left.json"), "{}", export);
+    let entries = sessions.holodeck_entries(Scope::Focused);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1.raw, "GET /health 200");
+
+    assert!(sessions.focus(PaneId(1)));
+    assert!(sessions.copy_block(1).unwrap().starts_with("$ cat left.json\n"));
+    assert_eq!(sessions.focused().holodeck.latest().unwrap().raw, r#"{"pane": "left"}"#);
+    assert!(!sessions.focus(PaneId(9)));
+}
+
+#[test]
+fn all_panes_reaches_every_pane_in_order() {
+    let sessions = split();
+    let panes: Vec<PaneId> = sessions.search("/", Scope::AllPanes).iter().map(|h| h.pane).collect();
+    assert_eq!(panes, [PaneId(2)], "only the log line has a slash");
+    let hits = sessions.search("e", Scope::AllPanes);
+    assert!(hits.iter().any(|h| h.pane == PaneId(1)) && hits.iter().any(|h| h.pane == PaneId(2)));
+
+    let export = sessions.export(Scope::AllPanes, false);
+    let (left, right) = (export.find("═══ pane 1 ═══").unwrap(), export.find("═══ pane 2 ═══").unwrap());
+    assert!(left < export.find("cat left.json").unwrap() && right < export.find("tail server.log").unwrap() && left < right);
+
+    let entries: Vec<PaneId> = sessions.holodeck_entries(Scope::AllPanes).iter().map(|(p, _)| *p).collect();
+    assert_eq!(entries, [PaneId(1), PaneId(2)]);
+    let list = sessions.holodeck_list_lines(Scope::AllPanes);
+    assert_eq!(list.iter().filter(|l| l.starts_with("⚡ Holodeck:")).count(), 2);
+    assert!(list[0].ends_with("(pane 1)"), "{:?}", list);
+}
+
+#[test]
+fn marks_are_per_pane_and_closing_keeps_one_pane() {
+    let mut sessions = split();
+    let mark = Mark { name: "log".into(), target: MarkTarget::Line(3), command: None, history_id: None, at: 0 };
+    sessions.focused_mut().marks.set(mark);
+    sessions.focus(PaneId(1));
+    assert!(sessions.focused().marks.get("log").is_none());
+
+    // Closing the focused pane hands focus to the one opened before it.
+    sessions.focus(PaneId(2));
+    let closed = sessions.close(PaneId(2)).unwrap();
+    assert!(closed.marks.get("log").is_some());
+    assert_eq!(sessions.focused_id(), PaneId(1));
+    assert!(sessions.close(PaneId(1)).is_none(), "the last pane stays");
+    assert_eq!(sessions.status_label(), None);
+    assert_eq!(sessions.export(Scope::AllPanes, false), sessions.export(Scope::Focused, false));
+}
+
+#[test]
+fn recorded_shell_blocks_export_stamped_per_pane() {
+    let mut sessions = Sessions::new();
+    let mut finished = TerminalBlockV2::new_now("./deploy.sh", 1_700_000_000);
+    finished.push_output("deploying\n", 15);
+    finished.exit_code = Some(0);
+    sessions.focused_mut().blocks.record(&finished);
+    sessions.open();
+
+    assert_eq!(sessions.export(Scope::Focused, true), "");
+    let all = sessions.export(Scope::AllPanes, true);
+    assert!(all.contains("[2023-11-14T22:13:20.015Z] deploying\n"), "{}", all);
+
+    let export = ScrollbackExport::parse("out.txt --with-timestamps --all-panes").unwrap();
+    assert_eq!(export, ScrollbackExport { path: "out.txt".into(), scope: Scope::AllPanes });
+    assert_eq!(ScrollbackExport::parse("--with-timestamps out.txt").unwrap().scope, Scope::Focused);
+    // Without the flag it is core's history export.
+    assert_eq!(ScrollbackExport::parse("out.txt --all-panes"), None);
+    assert_eq!(ScrollbackExport::parse("--with-timestamps"), None);
+    assert_eq!(ScrollbackExport::parse("a.txt b.txt --with-timestamps"), None);
+    assert_eq!(ScrollbackExport::parse("out.txt --with-timestamps --pane 2"), None);
+}
@@ -0,0 +1,337 @@
+//! Settings loading and `!profile` parsing.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use positronic_bridge::clipboard_history::ClipboardSettings;
+use positronic_bridge::governor::PowerConfig;
+use positronic_bridge::keymap::{Action, Chord};
+use positronic_bridge::pager::PagerThreshold;
+use positronic_bridge::renderer::{ThemeName, TimestampMode, DEFAULT_SLOW_THRESHOLD};
+use positronic_bridge::settings::{ProfileCommand, ProfileTarget, Settings};
+use positronic_bridge::theme_sync::{Schedule, ThemeMode};
+use positronic_core::history_filter::Verdict;
+use positronic_core::summary::SummarySettings;
+use positronic_core::time_format::{Clock, TimeSettings};
+
+/// Profile overrides over a base config, as the vault layers them.
+fn layered<'a>(
+    profile: &'a [(&'a str, &'a str)],
+    base: &'a [(&'a str, &'a str)],
+) -> impl Fn(&str) -> Option<String> + 'a {
+    let profile: HashMap<&str, &str> = profile.iter().copied().collect();
+    let base: HashMap<&str, &str> = base.iter().copied().collect();
+    move |key| profile.get(key).or_else(|| base.get(key)).map(|v| v.to_string())
+}
+
+// ════════════════════════════════════════════════════════════════
+// Settings::load
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn defaults_without_config() {
+    let (settings, problems) = Settings::load(|_| None);
+    assert!(problems.is_empty());
+    assert_eq!(settings.theme, ThemeName::Default);
+    assert_eq!(settings.slow_threshold, DEFAULT_SLOW_THRESHOLD);
+    assert_eq!(settings.keymap.chord_for(Action::Copy), Chord::parse("ctrl+shift+c").ok());
+}
+
+#[test]
+fn profile_values_win_and_the_rest_falls_through() {
+    let base = [("theme", "monokai"), ("blocks.slow_threshold", "30s"), ("keys.copy", "alt+c")];
+    let profile = [("theme", "dracula"), ("keys.copy", "alt+y")];
+    let (settings, problems) = Settings::load(layered(&profile, &base));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.theme, ThemeName::Dracula);
+    assert_eq!(settings.slow_threshold, Duration::from_secs(30));
+    assert_eq!(settings.keymap.chord_for(Action::Copy), Chord::parse("alt+y").ok());
+}
+
+#[test]
+fn invalid_values_are_reported_and_fall_back() {
+    let profile = [("theme", "neon"), ("blocks.slow_threshold", "soon"), ("keys.paste", "ctrl+banana")];
+    let (settings, problems) = Settings::load(layered(&profile, &[]));
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems[0].contains("neon") && problems[0].contains("dracula"));
+    assert!(problems[1].contains("blocks.slow_threshold"));
+    assert!(problems[2].contains("keys.paste"));
+    assert_eq!(settings.theme, ThemeName::Default);
+    assert_eq!(settings.keymap.chord_for(Action::Paste), Chord::parse("ctrl+shift+v").ok());
+}
+
+#[test]
+fn timestamp_mode_is_a_setting() {
+    let (settings, problems) = Settings::load(layered(&[("blocks.timestamps", "relative")], &[]));
+    assert!(problems.is_empty());
+    assert_eq!(settings.timestamps, TimestampMode::Relative);
+
+    let (settings, problems) = Settings::load(layered(&[("blocks.timestamps", "loud")], &[]));
+    assert!(problems[0].contains("blocks.timestamps"));
+    assert_eq!(settings.timestamps, TimestampMode::Off);
+}
+
+#[test]
+fn pager_threshold_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.pager, PagerThreshold::Screen);
+    let (settings, problems) = Settings::load(layered(&[("pager.threshold", "60")], &[]));
+    assert!(problems.is_empty());
+    assert_eq!(settings.pager, PagerThreshold::Lines(60));
+
+    let (settings, problems) = Settings::load(layered(&[("pager.threshold", "sometimes")], &[]));
+    assert!(problems[0].contains("pager.threshold"));
+    assert_eq!(settings.pager, PagerThreshold::Screen);
+}
+
+#[test]
+fn clipboard_history_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.clipboard, ClipboardSettings::default());
+    let config = [("clipboard.history", "5"), ("clipboard.keep_secrets", "on"), ("clipboard.persist", "yes")];
+    let (settings, problems) = Settings::load(layered(&config, &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.clipboard, ClipboardSettings { size: 5, keep_secrets: true, persist: true });
+
+    let (settings, problems) = Settings::load(layered(&[("clipboard.history", "lots"), ("clipboard.persist", "maybe")], &[]));
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert_eq!(settings.clipboard, ClipboardSettings::default());
+}
+
+#[test]
+fn calc_hint_is_off_unless_set() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(!settings.calc_hint);
+    let (settings, problems) = Settings::load(layered(&[("calc.hint", "on")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(settings.calc_hint);
+
+    let (settings, problems) = Settings::load(layered(&[("calc.hint", "sometimes")], &[]));
+    assert_eq!(problems, vec!["calc.hint = \"sometimes\": expected on or off"]);
+    assert!(!settings.calc_hint);
+}
+
+#[test]
+fn rm_suggestion_is_off_unless_set() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(!settings.suggest_rm);
+    let (settings, problems) = Settings::load(layered(&[("danger.suggest_rm", "yes")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(settings.suggest_rm);
+}
+
+#[test]
+fn directory_hints_are_on_unless_turned_off() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(settings.directory_hints);
+    let (settings, problems) = Settings::load(layered(&[("hints.directory", "off")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(!settings.directory_hints);
+    let (settings, problems) = Settings::load(layered(&[("hints.directory", "maybe")], &[]));
+    assert_eq!(problems, vec!["hints.directory = \"maybe\": expected on or off"]);
+    assert!(settings.directory_hints);
+}
+
+#[test]
+fn marks_stay_in_the_session_unless_persisted() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(!settings.marks_persist);
+    let (settings, problems) = Settings::load(layered(&[("marks.persist", "on")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(settings.marks_persist);
+}
+
+#[test]
+fn block_highlighting_is_on_unless_turned_off() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(settings.highlight);
+    let (settings, problems) = Settings::load(layered(&[("blocks.highlight", "off")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(!settings.highlight);
+    let (settings, problems) = Settings::load(layered(&[("blocks.highlight", "sometimes")], &[]));
+    assert_eq!(problems.len(), 1);
+    assert!(settings.highlight);
+}
+
+#[test]
+fn dimming_keeps_ten_commands_unless_set() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.dim_old, Some(10));
+    let (settings, problems) = Settings::load(layered(&[("blocks.dim_old", "3")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.dim_old, Some(3));
+    let (settings, _) = Settings::load(layered(&[("blocks.dim_old", "off")], &[]));
+    assert_eq!(settings.dim_old, None);
+
+    let (settings, problems) = Settings::load(layered(&[("blocks.dim_old", "lots")], &[]));
+    assert_eq!(problems, vec!["blocks.dim_old = \"lots\": expected a number of commands or off"]);
+    assert_eq!(settings.dim_old, Some(10));
+}
+
+#[test]
+fn power_settings_parse_durations_and_off() {
+    let (settings, problems) =
+        Settings::load(layered(&[("power.idle_after", "90s"), ("power.suspend_after", "15m")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.power.idle_after, Some(Duration::from_secs(90)));
+    assert_eq!(settings.power.suspend_after, Duration::from_secs(15 * 60));
+    let (settings, _) = Settings::load(layered(&[("power.idle_after", "off")], &[]));
+    assert_eq!(settings.power.idle_after, None);
+
+    let (settings, problems) = Settings::load(layered(&[("power.idle_after", "later")], &[]));
+    assert_eq!(problems, vec!["power.idle_after = \"later\": expected e.g. 90s or 5m, or off"]);
+    assert_eq!(settings.power, PowerConfig::default());
+}
+
+#[test]
+fn use_inputrc_is_off_unless_turned_on() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(!settings.use_inputrc);
+    let (settings, problems) = Settings::load(layered(&[("input.use_inputrc", "on")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(settings.use_inputrc);
+
+    let (settings, problems) = Settings::load(layered(&[("input.use_inputrc", "sometimes")], &[]));
+    assert_eq!(problems, vec!["input.use_inputrc = \"sometimes\": expected on or off"]);
+    assert!(!settings.use_inputrc);
+}
+
+#[test]
+fn holodeck_native_tables_are_on_unless_turned_off() {
+    let (settings, _) = Settings::load(|_| None);
+    assert!(settings.holodeck_native);
+    let (settings, problems) = Settings::load(layered(&[("holodeck.native", "off")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert!(!settings.holodeck_native);
+
+    let (settings, problems) = Settings::load(layered(&[("holodeck.native", "tables")], &[]));
+    assert_eq!(problems, vec!["holodeck.native = \"tables\": expected on or off"]);
+    assert!(settings.holodeck_native);
+}
+
+#[test]
+fn history_filter_comes_from_the_profile_layers() {
+    let (settings, problems) = Settings::load(layered(
+        &[("history.ignore_space", "on")],
+        &[("history.ignore_dups", "on"), ("history.ignore_patterns", "[\"^pass \"]")],
+    ));
+    assert!(problems.is_empty(), "{:?}", problems);
+    let filter = &settings.history;
+    assert!(filter.ignore_space && filter.ignore_dups && !filter.ignore_secrets);
+    assert_eq!(filter.check("pass show github", None), Verdict::Pattern("^pass ".to_string()));
+    assert_eq!(filter.check(" pass", None), Verdict::LeadingSpace);
+    assert_eq!(filter.check("ls", Some("ls")), Verdict::Duplicate);
+
+    let (_, problems) = Settings::load(layered(&[("history.ignore_patterns", "(")], &[]));
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].starts_with("history.ignore_patterns = \"(\""));
+}
+
+#[test]
+fn theme_sync_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.theme_sync, None);
+
+    let config = [("theme.mode", "schedule"), ("theme.schedule", "07:00-19:00"), ("theme.dark", "dracula")];
+    let (settings, problems) = Settings::load(layered(&config, &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    let sync = settings.theme_sync.unwrap();
+    assert_eq!(sync.mode, ThemeMode::Schedule(Schedule::parse("07:00-19:00").unwrap()));
+    assert_eq!((sync.light, sync.dark), (ThemeName::Solarized, ThemeName::Dracula));
+
+    let (settings, problems) = Settings::load(layered(&[("theme.mode", "schedule")], &[]));
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert_eq!(settings.theme_sync, None);
+}
+
+#[test]
+fn time_format_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.time, TimeSettings::default());
+
+    let config = [("time.format", "12h"), ("time.relative", "off")];
+    let (settings, problems) = Settings::load(layered(&config, &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.time.clock, Clock::H12);
+    assert!(!settings.time.relative);
+
+    let (settings, problems) = Settings::load(layered(&[("time.format", "noon-ish")], &[]));
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert_eq!(settings.time.clock, Clock::H24);
+}
+
+#[test]
+fn summary_auto_save_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.summary, SummarySettings::default());
+
+    let config = [("summary.auto_save_dir", "/notes/days"), ("summary.use_neural", "on")];
+    let (settings, problems) = Settings::load(layered(&config, &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.summary.auto_save_dir.as_deref(), Some(std::path::Path::new("/notes/days")));
+    assert!(settings.summary.use_neural);
+
+    let (_, problems) = Settings::load(layered(&[("summary.use_neural", "sometimes")], &[]));
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+}
+
+#[test]
+fn macro_delay_is_a_setting() {
+    let (settings, _) = Settings::load(|_| None);
+    assert_eq!(settings.macro_delay, positronic_bridge::macros::DEFAULT_DELAY);
+    let (settings, problems) = Settings::load(layered(&[("macro.delay", "120ms")], &[]));
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(settings.macro_delay, std::time::Duration::from_millis(120));
+    let (_, problems) = Settings::load(layered(&[("macro.delay", "soon")], &[]));
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+}
+
+#[test]
+fn disabled_themes_fall_back_to_default() {
+    let config = [("theme", "dracula"), ("theme.mode", "light"), ("theme.dark", "monokai")];
+    let (mut settings, _) = Settings::load(layered(&config, &[]));
+    let notes = settings.drop_disabled_themes(|theme| theme == "dracula" || theme == "solarized");
+    assert_eq!(settings.theme, ThemeName::Default);
+    let sync = settings.theme_sync.unwrap();
+    assert_eq!((sync.light, sync.dark), (ThemeName::Default, ThemeName::Monokai));
+    assert_eq!(notes.len(), 2, "{:?}", notes);
+    assert!(notes[0].contains("!safe enable theme dracula"), "{}", notes[0]);
+
+    let (mut settings, _) = Settings::load(layered(&[("theme", "monokai")], &[]));
+    assert!(settings.drop_disabled_themes(|_| false).is_empty());
+    assert_eq!(settings.theme, ThemeName::Monokai);
+}
+
+#[test]
+fn key_conflicts_are_not_load_problems() {
+    let (settings, problems) = Settings::load(layered(&[("keys.search_open", "ctrl+l")], &[]));
+    assert!(problems.is_empty());
+    assert!(!settings.keymap.warnings().is_empty());
+}
+
+// ════════════════════════════════════════════════════════════════
+// !profile
+// ════════════════════════════════════════════════════════════════
+
+#[test]
+fn parse_profile_commands() {
+    assert_eq!(ProfileCommand::parse("!profile"), Ok(ProfileCommand::List));
+    assert_eq!(ProfileCommand::parse("!profile list"), Ok(ProfileCommand::List));
+    assert_eq!(ProfileCommand::parse("!profile save embedded"), Ok(ProfileCommand::Save("embedded".into())));
+    assert_eq!(ProfileCommand::parse("!profile rm backend"), Ok(ProfileCommand::Remove("backend".into())));
+    assert_eq!(
+        ProfileCommand::parse("!profile use work.v2"),
+        Ok(ProfileCommand::Use(ProfileTarget::Named("work.v2".into())))
+    );
+    assert_eq!(ProfileCommand::parse("!profile use previous"), Ok(ProfileCommand::Use(ProfileTarget::Previous)));
+    assert_eq!(ProfileCommand::parse("!profile use none"), Ok(ProfileCommand::Use(ProfileTarget::Base)));
+    assert_eq!(ProfileCommand::parse("!profile use base"), Ok(ProfileCommand::Use(ProfileTarget::Base)));
+}
+
+#[test]
+fn reject_bad_profile_commands() {
+    assert!(ProfileCommand::parse("!profile use").unwrap_err().starts_with("Usage"));
+    assert!(ProfileCommand::parse("!profile save a b").unwrap_err().starts_with("Usage"));
+    assert!(ProfileCommand::parse("!profile save previous").unwrap_err().contains("reserved"));
+    assert!(ProfileCommand::parse("!profile save a;b").is_err());
+}
@@ -0,0 +1,284 @@
+// positronic-bridge/tests/span_cache_tests.rs
+//
+// Tests for the retained span cache: reuse across frames, content-hash
+// invalidation, visible-range bounding, and theme invalidation.
+
+use positronic_bridge::block::{BlockLine, BlockManager, BlockSource};
+use positronic_bridge::renderer::{self, BlockStyle, ThemeName};
+use positronic_bridge::span_cache::{OFFSCREEN_MARGIN, SpanCache};
+use positronic_core::blocks::TerminalBlockV2;
+use positronic_core::state_machine::StateMachine;
+use positronic_core::usage::ResourceUsage;
+use std::time::Duration;
+
+fn manager_with_blocks(n: usize) -> BlockManager {
+    let mut mgr = BlockManager::new(n + 10, n * 10);
+    for i in 0..n {
+        let id = mgr.begin(&format!("echo {}", i), "/tmp", BlockSource::Shell);
+        mgr.append(id, vec![
+            BlockLine::classify(format!("line {}", i)),
+            BlockLine::classify("warning: something"),
+        ]);
+        mgr.finish(id, Some(0), Duration::from_millis(5));
+    }
+    mgr
+}
+
+fn texts(spans: &[renderer::ColoredSpan]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+// ============================================================================
+// Blocks
+// ============================================================================
+
+#[test]
+fn test_block_spans_stress_work_is_bounded_by_visible_range() {
+    let mut mgr = manager_with_blocks(5_000);
+    let mut cache = SpanCache::new();
+    cache.set_theme(ThemeName::Default);
+
+    let visible = 4_980..5_000;
+    let max_work = visible.len() + 2 * OFFSCREEN_MARGIN;
+
+    // First frame: only the window (plus margin) is materialized.
+    let spans = cache.block_spans(mgr.blocks(), visible.clone());
+    let stats = cache.last_frame();
+    assert!(stats.built <= max_work, "built {} > {}", stats.built, max_work);
+    assert!(cache.cached_fragments() <= max_work);
+    assert_eq!(spans.len(), visible.len() * 3);
+
+    // Steady state: nothing rebuilt.
+    cache.block_spans(mgr.blocks(), visible.clone());
+    let stats = cache.last_frame();
+    assert_eq!(stats.built, 0);
+    assert!(stats.reused <= max_work);
+
+    // Appending 1,000 more blocks and following the tail only builds the new window.
+    for i in 0..1_000 {
+        let id = mgr.begin(&format!("new {}", i), "/tmp", BlockSource::Shell);
+        mgr.finish(id, Some(0), Duration::ZERO);
+    }
+    let len = mgr.len();
+    cache.block_spans(mgr.blocks(), len - 20..len);
+    assert!(cache.last_frame().built + cache.last_frame().reused <= max_work);
+}
+
+#[test]
+fn test_block_spans_rebuild_only_changed_block() {
+    let mut mgr = manager_with_blocks(10);
+    let mut cache = SpanCache::new();
+    cache.block_spans(mgr.blocks(), 0..10);
+
+    let id = mgr.blocks()[3].id;
+    mgr.append_line(id, BlockLine::error("error: late failure"));
+    let spans = cache.block_spans(mgr.blocks(), 0..10);
+
+    assert_eq!(cache.last_frame().built, 1);
+    assert_eq!(cache.last_frame().reused, 9);
+    assert!(texts(&spans).contains("error: late failure"));
+}
+
+#[test]
+fn test_block_spans_collapse_invalidates() {
+    let mut mgr = manager_with_blocks(3);
+    let mut cache = SpanCache::new();
+    let before = cache.block_spans(mgr.blocks(), 0..3);
+
+    let id = mgr.blocks()[0].id;
+    mgr.toggle_collapse(id);
+    let after = cache.block_spans(mgr.blocks(), 0..3);
+
+    assert_eq!(cache.last_frame().built, 1);
+    assert_eq!(after.len(), before.len() - 2);
+}
+
+#[test]
+fn test_block_spans_use_stored_classification() {
+    let mgr = manager_with_blocks(1);
+    let mut cache = SpanCache::new();
+    let spans = cache.block_spans(mgr.blocks(), 0..1);
+    let warning = spans.iter().find(|s| s.text.starts_with("warning")).unwrap();
+    assert_eq!(
+        warning.color,
+        renderer::line_kind_color(positronic_bridge::block::LineKind::Warning)
+    );
+}
+
+#[test]
+fn test_block_duration_badge_right_aligned_and_colored() {
+    let mut mgr = BlockManager::new(10, 100);
+    let quick = mgr.begin("ls", "/tmp", BlockSource::Shell);
+    mgr.finish(quick, Some(0), Duration::from_millis(40));
+    let build = mgr.begin("cargo build", "/tmp", BlockSource::Shell);
+    mgr.finish(build, Some(0), Duration::from_secs(94));
+    let test = mgr.begin("cargo test", "/tmp", BlockSource::Shell);
+    mgr.finish(test, Some(0), Duration::from_millis(2_500));
+
+    let mut cache = SpanCache::new();
+    let style = BlockStyle { columns: 60, slow_threshold: Duration::from_secs(10), ..BlockStyle::default() };
+    assert!(cache.set_block_style(style));
+    let spans = cache.block_spans(mgr.blocks(), 0..3);
+
+    // Instantaneous commands get no badge.
+    assert_eq!(spans[0].text, "➜ [✅ ok] $ ls\n");
+
+    let header = &spans[1];
+    let badge = &spans[2];
+    assert_eq!(badge.text, "1m 34s\n");
+    assert_eq!(header.text.chars().count() + badge.text.trim_end().chars().count(), 60);
+    let slow_color = badge.color;
+
+    let badge = &spans[4];
+    assert_eq!(badge.text, "2.500s\n");
+    assert_ne!(badge.color, slow_color);
+
+    // Raising the threshold rebuilds the blocks.
+    let style = BlockStyle { slow_threshold: Duration::from_secs(120), ..style };
+    assert!(cache.set_block_style(style));
+    let spans = cache.block_spans(mgr.blocks(), 0..3);
+    assert_eq!(cache.last_frame().built, 3);
+    assert_eq!(spans[2].color, spans[4].color);
+}
+
+#[test]
+fn test_recorded_shell_block_gets_a_badge() {
+    let mut finished = TerminalBlockV2::new_now("cargo build", 1_700_000_000);
+    finished.output = "   Compiling app v0.1.0\n    Finished dev\n".to_string();
+    finished.cwd = Some("/src/app".to_string());
+    finished.exit_code = Some(0);
+    finished.duration_ms = Some(94_000);
+
+    let mut mgr = BlockManager::new(10, 100);
+    let id = mgr.record(&finished);
+    let block = mgr.get(id).unwrap();
+    assert!(!block.running);
+    assert_eq!(block.source, BlockSource::Shell);
+    assert_eq!(block.cwd, "/src/app");
+    assert_eq!(block.timestamp.timestamp(), 1_700_000_000);
+    assert_eq!(block.duration, Some(Duration::from_secs(94)));
+    assert_eq!(block.output.len(), 2);
+
+    let badge = renderer::duration_badge(block, Duration::from_secs(10)).unwrap();
+    assert_eq!(badge.text, "1m 34s");
+}
+
+#[test]
+fn test_running_block_has_no_badge() {
+    let mut mgr = BlockManager::new(10, 100);
+    mgr.begin("sleep 5", "/tmp", BlockSource::Shell);
+    assert!(renderer::duration_badge(&mgr.blocks()[0], Duration::from_secs(1)).is_none());
+}
+
+#[test]
+fn test_usage_joins_the_badge_and_invalidates_the_block() {
+    let mut mgr = BlockManager::new(10, 100);
+    let id = mgr.begin("ls", "/tmp", BlockSource::Shell);
+    mgr.finish(id, Some(0), Duration::from_millis(40));
+    assert!(renderer::duration_badge(&mgr.blocks()[0], Duration::from_secs(1)).is_none());
+
+    let mut cache = SpanCache::new();
+    cache.block_spans(mgr.blocks(), 0..1);
+    mgr.set_usage(id, ResourceUsage { user_ms: 30, sys_ms: 10, max_rss_bytes: 4096 });
+    // Even an instantaneous command shows what it cost once that's known.
+    let badge = renderer::duration_badge(&mgr.blocks()[0], Duration::from_secs(1)).unwrap();
+    assert!(badge.text.starts_with("40ms · cpu 40ms · peak "), "{}", badge.text);
+
+    cache.block_spans(mgr.blocks(), 0..1);
+    assert_eq!(cache.last_frame().built, 1);
+}
+
+#[test]
+fn test_parse_slow_threshold() {
+    assert_eq!(renderer::parse_threshold("10s"), Some(Duration::from_secs(10)));
+    assert_eq!(renderer::parse_threshold("30"), Some(Duration::from_secs(30)));
+    assert_eq!(renderer::parse_threshold("500ms"), Some(Duration::from_millis(500)));
+    assert_eq!(renderer::parse_threshold(" 2m "), Some(Duration::from_secs(120)));
+    assert_eq!(renderer::parse_threshold("1.5s"), Some(Duration::from_millis(1_500)));
+    assert_eq!(renderer::parse_threshold("fast"), None);
+    assert_eq!(renderer::parse_threshold("0"), None);
+    assert_eq!(renderer::parse_threshold("5h"), None);
+}
+
+// ============================================================================
+// Direct output
+// ============================================================================
+
+#[test]
+fn test_direct_spans_match_uncached_renderer() {
+    let text = "📊 Vault Statistics:\n\n❌ Unknown command: !x\nplain";
+    let mut cache = SpanCache::new();
+    let cached = cache.direct_spans(text, 0..usize::MAX);
+    let fresh = renderer::direct_to_spans(text);
+    assert_eq!(texts(&cached), texts(&fresh));
+    let colors: Vec<_> = cached.iter().map(|s| s.color).collect();
+    let expected: Vec<_> = fresh.iter().map(|s| s.color).collect();
+    assert_eq!(colors, expected);
+}
+
+#[test]
+fn test_direct_spans_visible_window_only() {
+    let text: String = (0..5_000).map(|i| format!("line {}\n", i)).collect();
+    let mut cache = SpanCache::new();
+
+    let spans = cache.direct_spans(&text, 100..130);
+    assert_eq!(spans.len(), 30);
+    assert_eq!(spans[0].text, "line 100\n");
+    assert!(cache.last_frame().built <= 30 + 2 * OFFSCREEN_MARGIN);
+
+    cache.direct_spans(&text, 100..130);
+    assert_eq!(cache.last_frame().built, 0);
+}
+
+#[test]
+fn test_direct_spans_survive_front_trim() {
+    let text: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+    let mut cache = SpanCache::new();
+    cache.direct_spans(&text, 0..50);
+
+    // push_direct trims the oldest half; the remaining lines shift up.
+    let boundary = text.find("line 25").unwrap();
+    let trimmed = &text[boundary..];
+    cache.direct_spans(trimmed, 0..25);
+    assert_eq!(cache.last_frame().built, 0);
+    assert_eq!(cache.last_frame().reused, 25);
+}
+
+// ============================================================================
+// Snapshot + theme
+// ============================================================================
+
+#[test]
+fn test_snapshot_spans_reuse_unchanged_rows() {
+    let sm = StateMachine::new(20, 5);
+    sm.process_bytes(b"one\r\ntwo\r\nthree");
+    let mut cache = SpanCache::new();
+
+    let first = cache.snapshot_spans(&sm.snapshot());
+    let uncached = renderer::snapshot_to_spans(&sm.snapshot(), ThemeName::Default);
+    assert_eq!(texts(&first), texts(&uncached));
+
+    sm.process_bytes(b"\r\nfour");
+    cache.snapshot_spans(&sm.snapshot());
+    let stats = cache.last_frame();
+    // Only the row that gained "four" changed (blank rows share one entry).
+    assert_eq!(stats.built, 1);
+}
+
+#[test]
+fn test_theme_change_invalidates_all_caches() {
+    let mgr = manager_with_blocks(5);
+    let mut cache = SpanCache::new();
+    assert!(cache.set_theme(ThemeName::Default));
+    cache.block_spans(mgr.blocks(), 0..5);
+    cache.direct_spans("📊 a\nb", 0..2);
+    assert!(cache.cached_fragments() > 0);
+
+    assert!(!cache.set_theme(ThemeName::Default));
+    assert!(cache.cached_fragments() > 0);
+
+    assert!(cache.set_theme(ThemeName::Dracula));
+    assert_eq!(cache.cached_fragments(), 0);
+    cache.block_spans(mgr.blocks(), 0..5);
+    assert_eq!(cache.last_frame().built, 5);
+}
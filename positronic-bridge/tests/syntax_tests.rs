@@ -0,0 +1,218 @@
+// positronic-bridge/tests/syntax_tests.rs
+//
+// Tests for block output highlighting: language detection from commands
+// and content, golden token output for small fixtures, and how blocks
+// and the renderer use it.
+
+use std::time::Duration;
+
+use positronic_bridge::block::{BlockLine, BlockManager, BlockSource};
+use positronic_bridge::renderer::{self, BlockStyle, ThemeName};
+use positronic_bridge::syntax::{self, Language, TokenClass};
+
+use TokenClass as T;
+
+/// Each highlighted token of `source`, line by line, as (text, class).
+fn tokens(language: Language, source: &str) -> Vec<Vec<(String, TokenClass)>> {
+    let lines: Vec<&str> = source.lines().collect();
+    syntax::highlight(language, &lines)
+        .into_iter()
+        .zip(&lines)
+        .map(|(spans, line)| {
+            let chars: Vec<char> = line.chars().collect();
+            spans.into_iter().map(|s| (chars[s.range].iter().collect(), s.class)).collect()
+        })
+        .collect()
+}
+
+fn t(text: &str, class: TokenClass) -> (String, TokenClass) {
+    (text.to_string(), class)
+}
+
+// ============================================================================
+// Detection
+// ============================================================================
+
+#[test]
+fn test_language_from_command() {
+    assert_eq!(Language::from_command("cat src/main.rs"), Some(Language::Rust));
+    assert_eq!(Language::from_command("type C:\\proj\\setup.py"), Some(Language::Python));
+    assert_eq!(Language::from_command("head -n 20 config.YML"), Some(Language::Yaml));
+    assert_eq!(Language::from_command("bat Cargo.lock"), Some(Language::Toml));
+    assert_eq!(Language::from_command("cat 'my file.json' | head"), Some(Language::Json));
+    assert_eq!(Language::from_command("git diff --cached"), Some(Language::Diff));
+    assert_eq!(Language::from_command("git -c color.ui=never show HEAD~1"), Some(Language::Diff));
+    assert_eq!(Language::from_command("git log -p"), Some(Language::Diff));
+    assert_eq!(Language::from_command("diff -u a b"), Some(Language::Diff));
+    assert_eq!(Language::from_command("jq .items data"), Some(Language::Json));
+
+    assert_eq!(Language::from_command("git log"), None);
+    assert_eq!(Language::from_command("cat notes.txt"), None);
+    assert_eq!(Language::from_command("grep fn main.rs"), None, "only whole-file viewers");
+    assert_eq!(Language::from_command(""), None);
+}
+
+#[test]
+fn test_language_from_content() {
+    let rust = ["use std::io;", "", "fn main() {", "    let x = 1;", "}"];
+    assert_eq!(Language::from_content(&rust), Some(Language::Rust));
+    let python = ["import os", "def main():", "    print(os.getcwd())"];
+    assert_eq!(Language::from_content(&python), Some(Language::Python));
+    let json = ["{", "  \"name\": \"positronic\",", "  \"count\": 3", "}"];
+    assert_eq!(Language::from_content(&json), Some(Language::Json));
+    let toml = ["[package]", "name = \"demo\"", "version = \"0.1.0\""];
+    assert_eq!(Language::from_content(&toml), Some(Language::Toml));
+    let yaml = ["services:", "  web:", "    image: nginx", "    ports:", "      - 80:80"];
+    assert_eq!(Language::from_content(&yaml), Some(Language::Yaml));
+    let diff = ["--- a/x.txt", "+++ b/x.txt", "@@ -1 +1 @@", "-old", "+new"];
+    assert_eq!(Language::from_content(&diff), Some(Language::Diff));
+
+    let prose = ["Compiling demo v0.1.0", "use the --release flag for speed", "Finished dev"];
+    assert_eq!(Language::from_content(&prose), None, "one stray signal is not enough");
+    assert_eq!(Language::from_content(&["total 8", "drwxr-xr-x 2 me me 4096 ."]), None);
+    assert_eq!(Language::from_content(&[]), None);
+}
+
+#[test]
+fn test_command_wins_over_content() {
+    let lines = ["fn main() {}", "use x;"];
+    assert_eq!(syntax::detect("cat build.py", &lines), Some(Language::Python));
+    assert_eq!(syntax::detect("./run", &lines), Some(Language::Rust));
+}
+
+// ============================================================================
+// Golden tokens
+// ============================================================================
+
+#[test]
+fn test_rust_tokens() {
+    let source = "#[derive(Debug)]\npub fn add(a: u32) -> Option<u32> { // sum\n    println!(\"{}\", 'x'); /* a\n b */ 42 }";
+    assert_eq!(
+        tokens(Language::Rust, source),
+        vec![
+            vec![t("#[derive(Debug)]", T::Attribute)],
+            vec![t("pub", T::Keyword), t("fn", T::Keyword), t("Option", T::Type), t("// sum", T::Comment)],
+            vec![t("println!", T::Attribute), t("\"{}\"", T::String), t("'x'", T::String), t("/* a", T::Comment)],
+            vec![t(" b */", T::Comment), t("42", T::Number)],
+        ]
+    );
+    // A lifetime is not a char literal.
+    assert_eq!(tokens(Language::Rust, "&'a str"), vec![Vec::<(String, TokenClass)>::new()]);
+}
+
+#[test]
+fn test_python_tokens() {
+    let source = "@cache\ndef load(path='x', n=-1.5):\n    \"\"\"Doc\n    more\"\"\"  # why\n    return None";
+    assert_eq!(
+        tokens(Language::Python, source),
+        vec![
+            vec![t("@cache", T::Attribute)],
+            vec![t("def", T::Keyword), t("'x'", T::String), t("-1.5", T::Number)],
+            vec![t("\"\"\"Doc", T::String)],
+            vec![t("    more\"\"\"", T::String), t("# why", T::Comment)],
+            vec![t("return", T::Keyword), t("None", T::Keyword)],
+        ]
+    );
+}
+
+#[test]
+fn test_json_tokens() {
+    let source = "{\n  \"name\": \"a \\\"b\\\"\",\n  \"n\": [1, 2.5e3, true, null]\n}";
+    assert_eq!(
+        tokens(Language::Json, source),
+        vec![
+            vec![],
+            vec![t("\"name\"", T::Key), t("\"a \\\"b\\\"\"", T::String)],
+            vec![t("\"n\"", T::Key), t("1", T::Number), t("2.5e3", T::Number), t("true", T::Keyword), t("null", T::Keyword)],
+            vec![],
+        ]
+    );
+}
+
+#[test]
+fn test_toml_and_yaml_tokens() {
+    let toml = "[dependencies.serde]\nversion = \"1.0\" # pinned\nfeatures = [\"derive\"]";
+    assert_eq!(
+        tokens(Language::Toml, toml),
+        vec![
+            vec![t("[dependencies.serde]", T::Heading)],
+            vec![t("version", T::Key), t("\"1.0\"", T::String), t("# pinned", T::Comment)],
+            vec![t("features", T::Key), t("\"derive\"", T::String)],
+        ]
+    );
+
+    let yaml = "server:\n  - port: 8080\n    url: http://x#y # note\n    tls: off";
+    assert_eq!(
+        tokens(Language::Yaml, yaml),
+        vec![
+            vec![t("server", T::Key)],
+            vec![t("port", T::Key), t("8080", T::Number)],
+            vec![t("url", T::Key), t("# note", T::Comment)],
+            vec![t("tls", T::Key), t("off", T::Keyword)],
+        ]
+    );
+}
+
+#[test]
+fn test_diff_tokens() {
+    let diff = "diff --git a/x b/x\nindex 1..2 100644\n--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@ fn main\n-old\n+new\n same";
+    assert_eq!(
+        tokens(Language::Diff, diff),
+        vec![
+            vec![t("diff --git a/x b/x", T::DiffHeader)],
+            vec![t("index 1..2 100644", T::DiffHeader)],
+            vec![t("--- a/x", T::DiffHeader)],
+            vec![t("+++ b/x", T::DiffHeader)],
+            vec![t("@@ -1,2 +1,2 @@", T::Hunk)],
+            vec![t("-old", T::Removed)],
+            vec![t("+new", T::Added)],
+            vec![],
+        ]
+    );
+}
+
+// ============================================================================
+// Blocks and rendering
+// ============================================================================
+
+fn finished_block(command: &str, lines: &[&str]) -> positronic_bridge::block::TerminalBlock {
+    let mut mgr = BlockManager::default();
+    let id = mgr.begin(command, ".", BlockSource::Shell);
+    mgr.append(id, lines.iter().map(|l| BlockLine::classify(*l)).collect());
+    mgr.finish(id, Some(0), Duration::from_millis(5));
+    mgr.get(id).unwrap().clone()
+}
+
+#[test]
+fn test_finished_blocks_get_a_language_unless_too_long() {
+    assert_eq!(finished_block("git diff", &["+a"]).language, Some(Language::Diff));
+    assert_eq!(finished_block("ls", &["a", "b"]).language, None);
+
+    let long = vec!["x"; syntax::MAX_LINES + 1];
+    assert_eq!(finished_block("cat big.rs", &long).language, None);
+}
+
+#[test]
+fn test_block_spans_are_highlighted_with_the_theme() {
+    let block = finished_block("cat lib.rs", &["fn f() {}", "error: not a real error"]);
+    let style = BlockStyle { theme: ThemeName::Dracula, ..BlockStyle::default() };
+    let spans = renderer::block_to_spans(&block, &style);
+    let keyword = spans.iter().find(|s| s.text == "fn").expect("the keyword gets its own span");
+    assert_eq!(keyword.color, ThemeName::Dracula.syntax_color(TokenClass::Keyword));
+    // Inside code, a line is not recolored by its classification.
+    let error = spans.iter().find(|s| s.text.starts_with("error")).unwrap();
+    assert_eq!(error.color, renderer::line_kind_color(positronic_bridge::block::LineKind::Normal));
+
+    let plain = renderer::block_to_spans(&block, &BlockStyle { highlight: false, ..style });
+    assert!(plain.iter().any(|s| s.text == "fn f() {}\n"), "highlighting off: whole lines");
+}
+
+#[test]
+fn test_highlighted_line_keeps_text_and_ends_in_newline() {
+    let text = "let s = \"é\"; // ok";
+    let syntax = &syntax::highlight(Language::Rust, &[text])[0];
+    let spans = renderer::highlighted_line(text, syntax, ThemeName::Default);
+    assert_eq!(spans.iter().map(|s| s.text.as_str()).collect::<String>(), format!("{}\n", text));
+    assert_eq!(spans.last().unwrap().text, "// ok\n");
+    assert_eq!(renderer::highlighted_line("", &[], ThemeName::Default)[0].text, "\n");
+}
@@ -0,0 +1,175 @@
+// positronic-bridge/tests/theme_sync_tests.rs
+//
+// Tests for automatic theme switching: schedule parsing and boundary math
+// (including ranges across midnight), config loading, and how a manual
+// `!theme` overrides each mode until its next boundary or `!theme auto`.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use positronic_bridge::renderer::ThemeName;
+use positronic_bridge::theme_sync::{Appearance, Schedule, ThemeMode, ThemeSwitcher, ThemeSync};
+
+fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn time(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+}
+
+fn switcher(mode: ThemeMode) -> ThemeSwitcher {
+    let mut switcher = ThemeSwitcher::default();
+    switcher.configure(Some(ThemeSync { mode, light: ThemeName::Solarized, dark: ThemeName::Dracula }));
+    switcher
+}
+
+fn day_schedule() -> ThemeMode {
+    ThemeMode::Schedule(Schedule::parse("07:00-19:00").unwrap())
+}
+
+// ============================================================================
+// Schedule
+// ============================================================================
+
+#[test]
+fn schedule_parses_clock_ranges() {
+    let schedule = Schedule::parse(" 7:00-19:30 ").unwrap();
+    assert_eq!(schedule.to_string(), "07:00-19:30");
+    for bad in ["", "07:00", "07:00-07:00", "24:00-06:00", "07:60-08:00", "7-19", "07:0-19:00", "a:b-c:d"] {
+        assert_eq!(Schedule::parse(bad), None, "{:?}", bad);
+    }
+}
+
+#[test]
+fn schedule_is_light_inside_the_range() {
+    let schedule = Schedule::parse("07:00-19:00").unwrap();
+    assert_eq!(schedule.appearance_at(time(6, 59)), Appearance::Dark);
+    assert_eq!(schedule.appearance_at(time(7, 0)), Appearance::Light);
+    assert_eq!(schedule.appearance_at(time(18, 59)), Appearance::Light);
+    assert_eq!(schedule.appearance_at(time(19, 0)), Appearance::Dark);
+}
+
+#[test]
+fn schedule_range_can_cross_midnight() {
+    let schedule = Schedule::parse("20:00-06:00").unwrap();
+    assert_eq!(schedule.appearance_at(time(23, 0)), Appearance::Light);
+    assert_eq!(schedule.appearance_at(time(3, 0)), Appearance::Light);
+    assert_eq!(schedule.appearance_at(time(12, 0)), Appearance::Dark);
+    assert_eq!(schedule.next_boundary(at(1, 23, 0)), at(2, 6, 0));
+}
+
+#[test]
+fn next_boundary_is_strictly_later() {
+    let schedule = Schedule::parse("07:00-19:00").unwrap();
+    assert_eq!(schedule.next_boundary(at(1, 3, 0)), at(1, 7, 0));
+    assert_eq!(schedule.next_boundary(at(1, 7, 0)), at(1, 19, 0));
+    assert_eq!(schedule.next_boundary(at(1, 12, 0)), at(1, 19, 0));
+    // After the last boundary of the day, the first of tomorrow.
+    assert_eq!(schedule.next_boundary(at(1, 19, 0)), at(2, 7, 0));
+    assert_eq!(schedule.next_boundary(at(31, 22, 0)), NaiveDate::from_ymd_opt(2026, 4, 1).unwrap().and_hms_opt(7, 0, 0).unwrap());
+}
+
+// ============================================================================
+// Config
+// ============================================================================
+
+#[test]
+fn load_reads_mode_and_theme_names() {
+    let config = |pairs: &'static [(&'static str, &'static str)]| {
+        move |key: &str| pairs.iter().find(|(k, _)| *k == key).map(|(_, v)| v.to_string())
+    };
+    let mut problems = Vec::new();
+    assert_eq!(ThemeSync::load(config(&[("theme.light", "monokai")]), &mut problems), None);
+
+    let sync = ThemeSync::load(config(&[("theme.mode", "Auto"), ("theme.light", "monokai")]), &mut problems).unwrap();
+    assert_eq!(sync, ThemeSync { mode: ThemeMode::Auto, light: ThemeName::Monokai, dark: ThemeName::Default });
+    assert!(problems.is_empty());
+
+    // A bad name falls back; a bad mode turns syncing off.
+    let sync = ThemeSync::load(config(&[("theme.mode", "dark"), ("theme.dark", "neon")]), &mut problems).unwrap();
+    assert_eq!(sync.dark, ThemeName::Default);
+    assert_eq!(ThemeSync::load(config(&[("theme.mode", "sometimes")]), &mut problems), None);
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+}
+
+// ============================================================================
+// Switching
+// ============================================================================
+
+#[test]
+fn no_mode_leaves_the_theme_alone() {
+    let mut switcher = ThemeSwitcher::default();
+    assert_eq!(switcher.theme(at(1, 12, 0)), None);
+    switcher.set_manual(ThemeName::Monokai, at(1, 12, 0));
+    assert!(!switcher.is_overridden());
+    assert_eq!(switcher.next_change(at(1, 12, 0)), None);
+}
+
+#[test]
+fn schedule_picks_the_theme_by_local_time() {
+    let mut switcher = switcher(day_schedule());
+    assert_eq!(switcher.theme(at(1, 12, 0)), Some(ThemeName::Solarized));
+    assert_eq!(switcher.theme(at(1, 21, 0)), Some(ThemeName::Dracula));
+    assert_eq!(switcher.next_change(at(1, 21, 0)), Some(at(2, 7, 0)));
+}
+
+#[test]
+fn auto_follows_the_os_and_assumes_dark_when_unknown() {
+    let mut switcher = switcher(ThemeMode::Auto);
+    assert!(switcher.follows_os());
+    assert_eq!(switcher.theme(at(1, 12, 0)), Some(ThemeName::Dracula));
+    switcher.set_os(Some(Appearance::Light));
+    assert_eq!(switcher.theme(at(1, 12, 0)), Some(ThemeName::Solarized));
+    assert_eq!(switcher.next_change(at(1, 12, 0)), None);
+
+    let mut fixed = self::switcher(ThemeMode::Light);
+    fixed.set_os(Some(Appearance::Dark));
+    assert!(!fixed.follows_os());
+    assert_eq!(fixed.theme(at(1, 23, 0)), Some(ThemeName::Solarized));
+}
+
+#[test]
+fn manual_theme_lasts_until_the_next_schedule_boundary() {
+    let mut switcher = switcher(day_schedule());
+    switcher.set_manual(ThemeName::Monokai, at(1, 12, 0));
+    assert_eq!(switcher.next_change(at(1, 12, 0)), Some(at(1, 19, 0)));
+    assert_eq!(switcher.theme(at(1, 18, 59)), Some(ThemeName::Monokai));
+    assert_eq!(switcher.theme(at(1, 19, 0)), Some(ThemeName::Dracula));
+    assert!(!switcher.is_overridden());
+
+    // Asleep across several boundaries: still over.
+    switcher.set_manual(ThemeName::Monokai, at(1, 20, 0));
+    assert_eq!(switcher.theme(at(3, 20, 0)), Some(ThemeName::Dracula));
+}
+
+#[test]
+fn manual_theme_lasts_until_the_os_switches() {
+    let mut switcher = switcher(ThemeMode::Auto);
+    switcher.set_os(Some(Appearance::Dark));
+    switcher.set_manual(ThemeName::Monokai, at(1, 12, 0));
+    // Re-reporting the same appearance isn't a switch.
+    switcher.set_os(Some(Appearance::Dark));
+    assert_eq!(switcher.theme(at(2, 12, 0)), Some(ThemeName::Monokai));
+    switcher.set_os(Some(Appearance::Light));
+    assert_eq!(switcher.theme(at(2, 12, 0)), Some(ThemeName::Solarized));
+}
+
+#[test]
+fn manual_theme_in_a_fixed_mode_lasts_until_resumed() {
+    let mut switcher = switcher(ThemeMode::Dark);
+    switcher.set_manual(ThemeName::Monokai, at(1, 12, 0));
+    assert_eq!(switcher.theme(at(9, 12, 0)), Some(ThemeName::Monokai));
+    switcher.resume();
+    assert_eq!(switcher.theme(at(9, 12, 0)), Some(ThemeName::Dracula));
+}
+
+#[test]
+fn reloading_the_same_config_keeps_an_override() {
+    let mut switcher = switcher(ThemeMode::Dark);
+    let sync = *switcher.sync().unwrap();
+    switcher.set_manual(ThemeName::Monokai, at(1, 12, 0));
+    switcher.configure(Some(sync));
+    assert!(switcher.is_overridden());
+    switcher.configure(Some(ThemeSync { mode: ThemeMode::Light, ..sync }));
+    assert!(!switcher.is_overridden());
+    assert_eq!(switcher.theme(at(1, 12, 0)), Some(ThemeName::Solarized));
+}
@@ -0,0 +1,205 @@
+// positronic-bridge/tests/viewer_tests.rs
+//
+// Tests for `!view`: argument parsing, byte windows and line slicing,
+// text/hex selection, rendering through the detector and highlighter, and
+// reading real files (metadata line, errors, symlinks).
+
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use positronic_bridge::syntax::TokenClass;
+use positronic_bridge::viewer::{
+    self, ByteWindow, FileMeta, ViewCommand, ViewMode, ViewRange, MAX_HEX_BYTES, MAX_TEXT_BYTES,
+};
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("positronic_view_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn file(&self, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn view(path: &Path, args: &str) -> Result<viewer::FileView, String> {
+    viewer::load(path, &ViewCommand::parse(args).unwrap(), |_| {})
+}
+
+#[test]
+fn test_parse_view_arguments() {
+    let command = ViewCommand::parse("logs/app.log --tail 50").unwrap();
+    assert_eq!(command.path.as_deref(), Some("logs/app.log"));
+    assert_eq!(command.range, ViewRange::Tail(50));
+    assert!(!command.hex);
+
+    let command = ViewCommand::parse("--hex --head 4 a.bin").unwrap();
+    assert_eq!((command.hex, command.range, command.path.as_deref()), (true, ViewRange::Head(4), Some("a.bin")));
+
+    assert_eq!(ViewCommand::parse("").unwrap(), ViewCommand::default(), "a bare !view re-opens the last file");
+    assert!(ViewCommand::parse("a --head 1 --tail 1").unwrap_err().contains("not both"));
+    assert!(ViewCommand::parse("a --tail").unwrap_err().contains("needs a count"));
+    assert!(ViewCommand::parse("a --head 0").is_err());
+    assert_eq!(ViewCommand::parse("a b").unwrap_err(), viewer::VIEW_USAGE);
+}
+
+#[test]
+fn test_binary_heuristic_selects_hex() {
+    assert_eq!(viewer::choose_mode(b"fn main() {}\n", false), ViewMode::Text);
+    assert_eq!(viewer::choose_mode(b"fn main() {}\n", true), ViewMode::Hex);
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\x01\0\0\0\x01\0\x08\x06\0\0\0\x1f\x15\xc4\x89\0\0\0\x01sRGB\0";
+    assert_eq!(viewer::choose_mode(png, false), ViewMode::Hex);
+    assert_eq!(viewer::choose_mode(b"", false), ViewMode::Text);
+}
+
+#[test]
+fn test_windows_cap_and_align() {
+    let big = 10 * MAX_TEXT_BYTES;
+    assert_eq!(viewer::window(100, ViewRange::All, ViewMode::Text), ByteWindow { start: 0, len: 100 });
+    assert_eq!(viewer::window(big, ViewRange::Head(5), ViewMode::Text), ByteWindow { start: 0, len: MAX_TEXT_BYTES });
+    assert_eq!(
+        viewer::window(big, ViewRange::Tail(5), ViewMode::Text),
+        ByteWindow { start: big - MAX_TEXT_BYTES, len: MAX_TEXT_BYTES }
+    );
+
+    assert_eq!(viewer::window(100, ViewRange::Head(2), ViewMode::Hex), ByteWindow { start: 0, len: 32 });
+    // 100 bytes are 7 rows; the last two start at 80.
+    assert_eq!(viewer::window(100, ViewRange::Tail(2), ViewMode::Hex), ByteWindow { start: 80, len: 20 });
+    assert_eq!(viewer::window(100, ViewRange::Tail(99), ViewMode::Hex), ByteWindow { start: 0, len: 100 });
+    assert_eq!(viewer::window(big, ViewRange::All, ViewMode::Hex), ByteWindow { start: 0, len: MAX_HEX_BYTES });
+    assert_eq!(viewer::window(big, ViewRange::Tail(usize::MAX), ViewMode::Hex).len, MAX_HEX_BYTES);
+}
+
+#[test]
+fn test_slice_lines_drops_cut_lines() {
+    let whole = ByteWindow { start: 0, len: 12 };
+    assert_eq!(viewer::slice_lines("a\nb\nc\nd\ne\nf\n", whole, 12, ViewRange::Head(2)), vec!["a", "b"]);
+    assert_eq!(viewer::slice_lines("a\nb\nc\nd\ne\nf\n", whole, 12, ViewRange::Tail(2)), vec!["e", "f"]);
+    assert_eq!(viewer::slice_lines("a\nb\nc", ByteWindow { start: 0, len: 5 }, 5, ViewRange::All), vec!["a", "b", "c"]);
+
+    // Read from the middle of a file: the first line may be partial, and
+    // so may the last when the window stops short.
+    let middle = ByteWindow { start: 3, len: 7 };
+    assert_eq!(viewer::slice_lines("ha\nfull\nhal", middle, 20, ViewRange::All), vec!["full"]);
+    let head = ByteWindow { start: 0, len: 7 };
+    assert_eq!(viewer::slice_lines("one\ntw", head, 20, ViewRange::All), vec!["one"]);
+    assert_eq!(viewer::slice_lines("one\ntwo\n", ByteWindow { start: 0, len: 8 }, 20, ViewRange::All), vec!["one", "two"]);
+}
+
+#[test]
+fn test_render_text_picks_a_presentation() {
+    let json = viewer::render_text("data.txt", &[r#"{"name":"probe","ok":true}"#]);
+    assert_eq!(json.kind, "json");
+    assert_eq!(json.lines, vec!["{", "  \"name\": \"probe\",", "  \"ok\": true", "}"]);
+    assert!(json.syntax[1].iter().any(|s| s.class == TokenClass::Key));
+
+    let csv = viewer::render_text("runs.csv", &["name,ms", "build,1200", "test,95"]);
+    assert_eq!(csv.kind, "csv");
+    assert_eq!(csv.lines, vec!["name   ms", "─────  ────", "build  1200", "test   95"]);
+    assert_eq!(csv.syntax[0][0].class, TokenClass::Key);
+
+    let rust = viewer::render_text("main.rs", &["fn main() {}"]);
+    assert_eq!(rust.kind, "rust");
+    assert_eq!(rust.syntax.len(), 1);
+
+    let md = viewer::render_text("README.md", &["# Title", "", "- item", "```", "let x = 1;", "```"]);
+    assert_eq!(md.kind, "markdown");
+    assert_eq!(md.syntax[0][0].class, TokenClass::Heading);
+    assert_eq!(md.syntax[2][0].range, 0..1);
+    assert_eq!(md.syntax[4][0].class, TokenClass::String);
+
+    let plain = viewer::render_text("notes", &["just some words"]);
+    assert_eq!((plain.kind, plain.syntax.is_empty()), ("text", true));
+}
+
+#[test]
+fn test_header_golden() {
+    let meta = FileMeta {
+        path: PathBuf::from("/srv/link.md"),
+        target: Some(PathBuf::from("/srv/notes.md")),
+        size: 12_700,
+        modified: NaiveDate::from_ymd_opt(2026, 10, 1).unwrap().and_hms_opt(14, 22, 5),
+    };
+    let whole = ByteWindow { start: 0, len: 12_700 };
+    assert_eq!(
+        viewer::header(&meta, "markdown", ViewMode::Text, ViewRange::All, whole),
+        "📄 /srv/link.md → /srv/notes.md · 12.4 KB · modified 2026-10-01 14:22 · markdown"
+    );
+    assert!(viewer::header(&meta, "binary", ViewMode::Hex, ViewRange::Tail(8), whole).ends_with("· binary · last 8 rows"));
+
+    let meta = FileMeta { path: PathBuf::from("big.log"), target: None, size: 10 * MAX_TEXT_BYTES, modified: None };
+    assert_eq!(
+        viewer::header(&meta, "text", ViewMode::Text, ViewRange::All, ByteWindow { start: 0, len: MAX_TEXT_BYTES }),
+        "📄 big.log · 40.0 MB · text · first 4.0 MB shown (--tail n for the end)"
+    );
+}
+
+#[test]
+fn test_load_text_and_hex_files() {
+    let dir = TempDir::new("load");
+    let lines: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+    let log = dir.file("app.log", lines.as_bytes());
+    let tail = view(&log, "--tail 3").unwrap();
+    assert!(tail.lines[0].starts_with(&format!("📄 {} · 792 B · modified ", log.display())), "{}", tail.lines[0]);
+    assert!(tail.lines[0].ends_with("· text · last 3 lines"), "{}", tail.lines[0]);
+    assert_eq!(&tail.lines[1..], ["line 98", "line 99", "line 100"]);
+
+    let bin = dir.file("blob.bin", &(0u8..=40).collect::<Vec<u8>>());
+    let hex = view(&bin, "--tail 1").unwrap();
+    assert!(hex.lines[0].contains("· binary · last 1 rows"), "{}", hex.lines[0]);
+    assert_eq!(hex.lines[1], "00000020  20 21 22 23 24 25 26 27  28                       | !\"#$%&'(|");
+    assert!(hex.syntax.is_empty());
+
+    let forced = view(&log, "--hex --head 1").unwrap();
+    assert_eq!(forced.lines[1], "00000000  6c 69 6e 65 20 31 0a 6c  69 6e 65 20 32 0a 6c 69  |line 1.line 2.li|");
+    assert!(forced.lines[0].contains("· text · first 1 rows"));
+
+    let json = dir.file("payload.json", br#"{"a":[1,2]}"#);
+    let rendered = view(&json, "").unwrap();
+    assert_eq!(rendered.lines.len(), rendered.syntax.len(), "the metadata line gets empty spans");
+    assert!(rendered.syntax[0].is_empty());
+}
+
+#[test]
+fn test_load_errors_are_precise() {
+    let dir = TempDir::new("errors");
+    let missing = dir.0.join("nope.txt");
+    assert_eq!(view(&missing, "").unwrap_err(), format!("❌ !view: {} does not exist", missing.display()));
+    assert_eq!(view(&dir.0, "").unwrap_err(), format!("❌ !view: {} is a directory", dir.0.display()));
+    assert_eq!(
+        viewer::open_error(Path::new("/root/secret"), &std::io::ErrorKind::PermissionDenied.into()),
+        "❌ !view: permission denied reading /root/secret"
+    );
+    assert_eq!(viewer::resolve("src/main.rs", Path::new("/work")), PathBuf::from("/work/src/main.rs"));
+    assert_eq!(viewer::resolve("/etc/hosts", Path::new("/work")), PathBuf::from("/etc/hosts"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_load_follows_symlinks() {
+    let dir = TempDir::new("links");
+    let target = dir.file("real.txt", b"hello\n");
+    let link = dir.0.join("link.txt");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let shown = view(&link, "").unwrap();
+    let target = std::fs::canonicalize(&target).unwrap();
+    assert!(shown.lines[0].starts_with(&format!("📄 {} → {} · 6 B", link.display(), target.display())), "{}", shown.lines[0]);
+    assert_eq!(shown.lines[1], "hello");
+
+    let dangling = dir.0.join("dangling.txt");
+    std::os::unix::fs::symlink(dir.0.join("gone.txt"), &dangling).unwrap();
+    assert!(view(&dangling, "").unwrap_err().contains("is a broken link to"));
+}
@@ -0,0 +1,184 @@
+// positronic-bridge/tests/viewport_tests.rs
+//
+// Tests for the scrollback viewport: anchoring, keyboard paging, new-output
+// counting and the alt-screen key pass-through.
+
+use positronic_bridge::keymap::{Action, NamedKey};
+use positronic_bridge::viewport::{alt_screen_sequence, Viewport};
+
+/// 100 lines, 20 to a screen.
+fn viewport() -> Viewport {
+    let mut view = Viewport::default();
+    view.set_extent(100, 20);
+    view
+}
+
+#[test]
+fn test_anchored_view_follows_new_output() {
+    let mut view = viewport();
+    assert_eq!(view.window(), 80..100);
+    view.on_new_lines(5);
+    assert!(!view.is_scrolled_back());
+    assert_eq!(view.window(), 85..105);
+    assert_eq!(view.pill(), None);
+    assert_eq!(view.position(), 1.0);
+}
+
+#[test]
+fn test_scrolled_back_view_holds_still_and_counts() {
+    let mut view = viewport();
+    view.page_up();
+    assert_eq!(view.window(), 60..80);
+
+    view.on_new_lines(42);
+    assert_eq!(view.window(), 60..80);
+    assert_eq!(view.unseen(), 42);
+    assert_eq!(view.pill().as_deref(), Some("▼ 42 new lines"));
+
+    view.on_new_lines(1);
+    assert_eq!(view.pill().as_deref(), Some("▼ 43 new lines"));
+}
+
+#[test]
+fn test_returning_to_the_bottom_re_anchors() {
+    let mut view = viewport();
+    view.page_up();
+    view.on_new_lines(10);
+    // One page down still leaves the ten new lines below.
+    view.page_down();
+    assert!(view.is_scrolled_back());
+    assert_eq!(view.unseen(), 10);
+    view.page_down();
+    assert!(!view.is_scrolled_back());
+    assert_eq!(view.pill(), None);
+
+    view.on_new_lines(3);
+    assert_eq!(view.unseen(), 0);
+}
+
+#[test]
+fn test_top_and_bottom_clamp_to_the_content() {
+    let mut view = viewport();
+    view.top();
+    assert_eq!(view.window(), 0..20);
+    assert_eq!(view.position(), 0.0);
+    view.page_up();
+    assert_eq!(view.offset(), 80);
+    view.bottom();
+    assert_eq!(view.offset(), 0);
+
+    // Content shorter than a screen can't scroll.
+    let mut short = Viewport::default();
+    short.set_extent(5, 20);
+    short.page_up();
+    assert!(!short.is_scrolled_back());
+    assert_eq!(short.window(), 0..5);
+    assert_eq!(short.thumb(100.0, 10.0), None);
+}
+
+#[test]
+fn test_shrinking_content_clamps_the_offset() {
+    let mut view = viewport();
+    view.top();
+    view.set_extent(30, 20);
+    assert_eq!(view.offset(), 10);
+    view.set_extent(10, 20);
+    assert!(!view.is_scrolled_back());
+}
+
+#[test]
+fn test_pill_singular_and_hit_test() {
+    let mut view = viewport();
+    view.page_up();
+    view.on_new_lines(1);
+    assert_eq!(view.pill().as_deref(), Some("▼ 1 new line"));
+
+    view.set_pill_rect(Some([10.0, 20.0, 100.0, 24.0]));
+    assert!(view.hit_pill(50.0, 30.0));
+    assert!(!view.hit_pill(5.0, 30.0));
+    // No pill once the view is back at the bottom, whatever was drawn last.
+    view.bottom();
+    assert!(!view.hit_pill(50.0, 30.0));
+}
+
+#[test]
+fn test_thumb_tracks_the_position() {
+    let mut view = viewport();
+    let (start, len) = view.thumb(200.0, 10.0).unwrap();
+    assert_eq!(len, 40.0);
+    assert_eq!(start, 160.0);
+    view.top();
+    assert_eq!(view.thumb(200.0, 10.0), Some((0.0, 40.0)));
+
+    // Long scrollback: the thumb keeps a minimum size.
+    view.set_extent(100_000, 20);
+    assert_eq!(view.thumb(200.0, 16.0).unwrap().1, 16.0);
+}
+
+#[test]
+fn test_alt_screen_apps_get_their_paging_keys() {
+    assert_eq!(alt_screen_sequence(NamedKey::PageUp, false, false), Some("\x1b[5~"));
+    assert_eq!(alt_screen_sequence(NamedKey::PageUp, false, true), Some("\x1b[5;2~"));
+    assert_eq!(alt_screen_sequence(NamedKey::PageDown, false, true), Some("\x1b[6;2~"));
+    assert_eq!(alt_screen_sequence(NamedKey::Home, true, false), Some("\x1b[1;5H"));
+    assert_eq!(alt_screen_sequence(NamedKey::End, true, false), Some("\x1b[1;5F"));
+    // Plain Home and End stay with the input line.
+    assert_eq!(alt_screen_sequence(NamedKey::Home, false, false), None);
+}
+
+#[test]
+fn test_scroll_actions_have_default_chords() {
+    assert_eq!(Action::ScrollPageUp.default_chord(), "shift+pageup");
+    assert_eq!(Action::ScrollPageDown.default_chord(), "shift+pagedown");
+    assert_eq!(Action::ScrollTop.default_chord(), "ctrl+home");
+    assert_eq!(Action::ScrollBottom.default_chord(), "ctrl+end");
+}
+
+#[test]
+fn test_reveal_scrolls_just_enough() {
+    let mut view = viewport();
+    view.reveal(90);
+    assert_eq!(view.window(), 80..100, "already on screen");
+    view.reveal(30);
+    assert_eq!(view.window(), 30..50, "above: it becomes the top line");
+    view.reveal(55);
+    assert_eq!(view.window(), 36..56, "below: it becomes the bottom line");
+    view.reveal(99);
+    assert!(!view.is_scrolled_back());
+}
+
+#[test]
+fn test_mark_and_restore_across_a_resize() {
+    use positronic_bridge::viewport::ScrollMark;
+
+    let mut view = viewport();
+    assert_eq!(view.mark(), ScrollMark::Anchored);
+    view.set_extent(120, 30);
+    view.restore(ScrollMark::Anchored);
+    assert!(!view.is_scrolled_back());
+
+    // Same line count: the top line comes back exactly.
+    let mut view = viewport();
+    view.reveal(30);
+    let mark = view.mark();
+    assert_eq!(mark, ScrollMark::Back { top: 30, total: 100 });
+    view.set_extent(100, 10);
+    view.restore(mark);
+    assert_eq!(view.window(), 30..40);
+
+    // Rewrapped to twice the lines: the same place in the whole.
+    view.set_extent(200, 10);
+    view.restore(mark);
+    assert_eq!(view.window().start, 60);
+    assert!(view.is_scrolled_back());
+}
+
+#[test]
+fn test_scroll_to_line_puts_it_at_the_top() {
+    let mut view = viewport();
+    view.scroll_to_line(40);
+    assert_eq!(view.window(), 40..60);
+    // Too near the bottom to be at the top: the last page, anchored.
+    view.scroll_to_line(90);
+    assert!(!view.is_scrolled_back());
+}
@@ -0,0 +1,86 @@
+// positronic-bridge/tests/wide_char_tests.rs
+//
+// Tests for wide and zero-width characters between the state machine and
+// the view: spacers are neither drawn nor copied, clusters stay with their
+// lead cell, and input-bar geometry counts cells rather than characters.
+
+use positronic_bridge::cwd::update_cwd_from_snapshot;
+use positronic_bridge::helpers::cell_columns;
+use positronic_bridge::renderer::{snapshot_to_plain, snapshot_to_spans, ThemeName};
+use positronic_core::state_machine::{Snapshot, StateMachine};
+
+fn screen(cols: u16, bytes: &str) -> Snapshot {
+    let sm = StateMachine::new(cols, 4);
+    sm.process_bytes(bytes.as_bytes());
+    sm.snapshot()
+}
+
+fn drawn(snapshot: &Snapshot) -> String {
+    snapshot_to_spans(snapshot, ThemeName::Default).iter().map(|s| s.text.as_str()).collect()
+}
+
+// ============================================================================
+// Plain text
+// ============================================================================
+
+#[test]
+fn plain_text_round_trips_mixed_widths() {
+    let text = "ls: 日本語.txt 🦀 café\r\n";
+    let snap = screen(40, text);
+    assert_eq!(snapshot_to_plain(&snap), "ls: 日本語.txt 🦀 café\n\n\n\n");
+}
+
+#[test]
+fn plain_text_keeps_combining_marks_and_joiners() {
+    let snap = screen(20, "e\u{301} 👩\u{200D}💻");
+    assert!(snapshot_to_plain(&snap).starts_with("e\u{301} 👩\u{200D}💻\n"));
+}
+
+#[test]
+fn plain_text_drops_the_spacer_of_a_wrapped_wide_char() {
+    let snap = screen(5, "abcd漢x");
+    assert!(snapshot_to_plain(&snap).starts_with("abcd漢x\n"));
+}
+
+// ============================================================================
+// Spans
+// ============================================================================
+
+#[test]
+fn spans_draw_each_wide_char_once() {
+    let snap = screen(12, "日本|\r\nabcd|");
+    let text = drawn(&snap);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0].trim_end(), "日本|");
+    // Both bars sit in column 4: two wide glyphs, then ASCII.
+    assert_eq!(cell_columns(lines[0], 2), cell_columns(lines[1], 4));
+}
+
+#[test]
+fn spans_split_joiner_sequences_at_cell_boundaries() {
+    let snap = screen(12, "👩\u{200D}💻x");
+    let text = drawn(&snap);
+    assert!(text.starts_with("👩💻x"), "{:?}", text);
+    assert!(!text.contains('\u{200D}'));
+}
+
+// ============================================================================
+// Readers and geometry
+// ============================================================================
+
+#[test]
+fn prompt_readers_see_text_without_spacers() {
+    let snap = screen(40, "PS C:\\ユーザー> ");
+    let mut cwd = String::new();
+    update_cwd_from_snapshot(&snap, &mut cwd);
+    assert_eq!(cwd, "C:\\ユーザー");
+}
+
+#[test]
+fn cell_columns_count_wide_and_zero_width_chars() {
+    assert_eq!(cell_columns("abc", usize::MAX), 3);
+    assert_eq!(cell_columns("日本語", 2), 4);
+    assert_eq!(cell_columns("e\u{301}x", 2), 1);
+    assert_eq!(cell_columns("🦀 ok", usize::MAX), 5);
+    assert_eq!(cell_columns("", 3), 0);
+}
@@ -0,0 +1,155 @@
+// positronic-bridge/tests/window_style_tests.rs
+//
+// Tests for the window and cursor settings: parsing and validation of
+// `window.opacity`, `window.padding`, `cursor.style` and `cursor.blink_ms`,
+// DECSCUSR taking precedence over the configured cursor, and the blink
+// phase.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use positronic_bridge::settings::Settings;
+use positronic_bridge::window_style::{parse_opacity, Blink, WindowStyle, DEFAULT_BLINK};
+use positronic_core::term::modes::{CursorShape, CursorStyle};
+
+fn config<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    let map: HashMap<&str, &str> = pairs.iter().copied().collect();
+    move |key| map.get(key).map(|v| v.to_string())
+}
+
+fn load(pairs: &[(&str, &str)]) -> (WindowStyle, Vec<String>) {
+    let mut problems = Vec::new();
+    let style = WindowStyle::load(config(pairs), &mut problems);
+    (style, problems)
+}
+
+// ============================================================================
+// Config
+// ============================================================================
+
+#[test]
+fn defaults_without_config() {
+    let (style, problems) = load(&[]);
+    assert!(problems.is_empty());
+    assert_eq!(style, WindowStyle::default());
+    assert_eq!(style.opacity, 1.0);
+    assert_eq!(style.cursor, CursorShape::Bar);
+    assert_eq!(style.blink, None);
+}
+
+#[test]
+fn load_reads_all_four_keys() {
+    let (style, problems) = load(&[
+        ("window.opacity", "0.85"),
+        ("window.padding", "24"),
+        ("cursor.style", "Block"),
+        ("cursor.blink_ms", "600"),
+    ]);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(style.opacity, 0.85);
+    assert_eq!(style.padding, 24.0);
+    assert_eq!(style.cursor, CursorShape::Block);
+    assert_eq!(style.blink, Some(Duration::from_millis(600)));
+}
+
+#[test]
+fn opacity_takes_a_fraction_or_a_percentage() {
+    assert_eq!(parse_opacity("0.5"), Some(0.5));
+    assert_eq!(parse_opacity("85%"), Some(0.85));
+    assert_eq!(parse_opacity("1"), Some(1.0));
+    assert_eq!(parse_opacity("0.05"), None);
+    assert_eq!(parse_opacity("1.5"), None);
+    assert_eq!(parse_opacity("half"), None);
+}
+
+#[test]
+fn blink_zero_is_steady() {
+    let (style, problems) = load(&[("cursor.blink_ms", "0")]);
+    assert!(problems.is_empty());
+    assert_eq!(style.blink, None);
+}
+
+#[test]
+fn invalid_values_keep_defaults_and_are_reported() {
+    let (style, problems) = load(&[
+        ("window.opacity", "0"),
+        ("window.padding", "-3"),
+        ("cursor.style", "triangle"),
+        ("cursor.blink_ms", "20"),
+    ]);
+    assert_eq!(style, WindowStyle::default());
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+    assert!(problems[0].starts_with("window.opacity = \"0\""));
+    assert!(problems[1].starts_with("window.padding = \"-3\""));
+    assert!(problems[2].starts_with("cursor.style = \"triangle\""));
+    assert!(problems[3].starts_with("cursor.blink_ms = \"20\""));
+}
+
+#[test]
+fn settings_carry_the_window_style() {
+    let (settings, problems) = Settings::load(config(&[("window.padding", "4"), ("cursor.style", "underline")]));
+    assert!(problems.is_empty());
+    assert_eq!(settings.window.padding, 4.0);
+    assert_eq!(settings.window.cursor, CursorShape::Underline);
+}
+
+// ============================================================================
+// DECSCUSR precedence
+// ============================================================================
+
+#[test]
+fn settings_pick_the_cursor_until_an_application_does() {
+    let (style, _) = load(&[("cursor.style", "underline"), ("cursor.blink_ms", "400")]);
+    assert_eq!(style.cursor(None), (CursorShape::Underline, Some(Duration::from_millis(400))));
+
+    let steady_block = CursorStyle { shape: CursorShape::Block, blinking: false };
+    assert_eq!(style.cursor(Some(steady_block)), (CursorShape::Block, None));
+
+    let blinking_bar = CursorStyle { shape: CursorShape::Bar, blinking: true };
+    assert_eq!(style.cursor(Some(blinking_bar)), (CursorShape::Bar, Some(Duration::from_millis(400))));
+}
+
+#[test]
+fn application_blink_falls_back_to_the_default_rate() {
+    let style = WindowStyle::default();
+    let blinking = CursorStyle { shape: CursorShape::Block, blinking: true };
+    assert_eq!(style.cursor(Some(blinking)), (CursorShape::Block, Some(DEFAULT_BLINK)));
+}
+
+// ============================================================================
+// Blink
+// ============================================================================
+
+#[test]
+fn blink_alternates_each_period_and_restarts_on() {
+    let start = Instant::now();
+    let period = Some(Duration::from_millis(500));
+    let mut blink = Blink::new(start);
+    assert!(blink.visible(period, start));
+    assert!(blink.visible(period, start + Duration::from_millis(499)));
+    assert!(!blink.visible(period, start + Duration::from_millis(500)));
+    assert!(blink.visible(period, start + Duration::from_millis(1000)));
+
+    blink.restart(start + Duration::from_millis(700));
+    assert!(blink.visible(period, start + Duration::from_millis(700)));
+}
+
+#[test]
+fn next_toggle_is_the_next_phase_boundary() {
+    let start = Instant::now();
+    let period = Some(Duration::from_millis(500));
+    let blink = Blink::new(start);
+    assert_eq!(blink.next_toggle(period, start), Some(start + Duration::from_millis(500)));
+    assert_eq!(
+        blink.next_toggle(period, start + Duration::from_millis(750)),
+        Some(start + Duration::from_millis(1000))
+    );
+}
+
+#[test]
+fn steady_cursor_is_always_visible_and_never_wakes() {
+    let start = Instant::now();
+    let blink = Blink::new(start);
+    assert!(blink.visible(None, start + Duration::from_secs(3)));
+    assert_eq!(blink.next_toggle(None, start), None);
+}
@@ -19,6 +19,12 @@ impl Airlock {
     /// Commands are routed through the system shell so that builtins
     /// (echo, cd, etc.), pipes, and redirects work correctly.
     pub async fn run_sandboxed(&self, command: &str) -> Result<String> {
+        self.run_sandboxed_with_env(command, &[]).await
+    }
+
+    /// `run_sandboxed` with `env` (the `!setenv` overlay) set on top of
+    /// the inherited environment.
+    pub async fn run_sandboxed_with_env(&self, command: &str, env: &[(String, String)]) -> Result<String> {
         if !self.enabled {
             return Err(anyhow!("Airlock is disabled."));
         }
@@ -35,12 +41,14 @@ impl Airlock {
         #[cfg(windows)]
         let output = tokio::process::Command::new("cmd")
             .args(["/C", trimmed])
+            .envs(env.iter().map(|(name, value)| (name, value)))
             .output()
             .await?;
 
         #[cfg(not(windows))]
         let output = tokio::process::Command::new("sh")
             .args(["-c", trimmed])
+            .envs(env.iter().map(|(name, value)| (name, value)))
             .output()
             .await?;
 
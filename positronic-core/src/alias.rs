@@ -0,0 +1,328 @@
+//! Alias templates: positional placeholders and nested expansion.
+//!
+//! An expansion without placeholders is a prefix: `gco = git checkout`
+//! turns `gco main` into `git checkout main`. With placeholders the
+//! invocation's arguments go where the template says instead:
+//!
+//! - `{1}`, `{2}`, … — that argument, as typed (quotes kept)
+//! - `{*}` — the arguments after the highest `{n}`, as typed
+//! - `{@}` — every argument, each quoted for the shell
+//!
+//! Numbered placeholders are required; leftover arguments are an error
+//! unless `{*}` or `{@}` takes them. A `{` after `$` is shell syntax and
+//! never a placeholder, and braces that don't start with a digit, `*` or
+//! `@` (`{{.Name}}`, `'{print $1}'`) are left alone.
+//!
+//! An expansion whose first word is another alias is expanded again, up to
+//! `MAX_ALIAS_DEPTH` levels. An alias naming itself (`ls = ls -F`) stops
+//! there like in bash; any other loop is an error.
+
+use std::fmt;
+use std::ops::Range;
+
+/// How many aliases may expand into each other.
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+pub const PLACEHOLDER_HELP: &str = "Placeholders: {1} {2}… one argument each, {*} the rest as typed, {@} all, each quoted";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// `{n}`, 1-based.
+    Arg(usize),
+    /// `{*}`
+    Rest,
+    /// `{@}`
+    All,
+}
+
+impl fmt::Display for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placeholder::Arg(n) => write!(f, "{{{}}}", n),
+            Placeholder::Rest => f.write_str("{*}"),
+            Placeholder::All => f.write_str("{@}"),
+        }
+    }
+}
+
+/// Something in a template that starts like a placeholder but isn't one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BadPlaceholder {
+    at: usize,
+    text: String,
+}
+
+/// Placeholders in `template` with their byte ranges, and the malformed ones.
+fn scan(template: &str) -> (Vec<(Range<usize>, Placeholder)>, Vec<BadPlaceholder>) {
+    let bytes = template.as_bytes();
+    let mut found = Vec::new();
+    let mut bad = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts = bytes[i] == b'{'
+            && (i == 0 || bytes[i - 1] != b'$')
+            && matches!(bytes.get(i + 1), Some(b'0'..=b'9' | b'*' | b'@'));
+        if !starts {
+            i += 1;
+            continue;
+        }
+        let close = template[i..].find(|c: char| c == '}' || c.is_whitespace()).map(|n| i + n);
+        let end = match close {
+            Some(end) if bytes[end] == b'}' => end,
+            _ => {
+                let end = close.unwrap_or(bytes.len());
+                bad.push(BadPlaceholder { at: i, text: template[i..end].to_string() });
+                i = end;
+                continue;
+            }
+        };
+        let inner = &template[i + 1..end];
+        let placeholder = match inner {
+            "*" => Some(Placeholder::Rest),
+            "@" => Some(Placeholder::All),
+            digits => digits.parse().ok().filter(|&n| n > 0).map(Placeholder::Arg),
+        };
+        match placeholder {
+            Some(p) => found.push((i..end + 1, p)),
+            None => bad.push(BadPlaceholder { at: i, text: template[i..=end].to_string() }),
+        }
+        i = end + 1;
+    }
+    (found, bad)
+}
+
+/// The placeholders in `template`, in order, with their byte ranges.
+pub fn placeholders(template: &str) -> Vec<(Range<usize>, Placeholder)> {
+    scan(template).0
+}
+
+pub fn has_placeholders(template: &str) -> bool {
+    !placeholders(template).is_empty()
+}
+
+/// Check a template before it is saved; the error is for the user.
+pub fn validate(template: &str) -> Result<(), String> {
+    let (found, bad) = scan(template);
+    if let Some(b) = bad.first() {
+        return Err(format!(
+            "❌ Bad placeholder '{}' at column {}. {}",
+            b.text,
+            template[..b.at].chars().count() + 1,
+            PLACEHOLDER_HELP
+        ));
+    }
+    let max = highest_arg(&found);
+    for n in 1..max {
+        if !found.iter().any(|(_, p)| *p == Placeholder::Arg(n)) {
+            return Err(format!("❌ The template uses {{{}}} but not {{{}}}", max, n));
+        }
+    }
+    Ok(())
+}
+
+fn highest_arg(found: &[(Range<usize>, Placeholder)]) -> usize {
+    found
+        .iter()
+        .filter_map(|(_, p)| match p {
+            Placeholder::Arg(n) => Some(*n),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// "glog <1> [args…]": how an alias with placeholders is called.
+pub fn usage(name: &str, template: &str) -> String {
+    let found = placeholders(template);
+    let mut out = name.to_string();
+    for n in 1..=highest_arg(&found) {
+        out.push_str(&format!(" <{}>", n));
+    }
+    if found.iter().any(|(_, p)| matches!(p, Placeholder::Rest | Placeholder::All)) {
+        out.push_str(" [args…]");
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Arguments
+// ════════════════════════════════════════════════════════════════════
+
+/// Split an argument string into words the way a shell would, but keep
+/// each word exactly as typed: `a "b c" d\ e` is `a`, `"b c"`, `d\ e`.
+pub fn split_args(args: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = args.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                continue;
+            }
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                word.push(c);
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                }
+                in_word = true;
+                continue;
+            }
+            _ => {}
+        }
+        word.push(c);
+        in_word = true;
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// The value a word stands for: quotes and escapes removed.
+pub fn unquote(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+    let mut quote: Option<char> = None;
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => out.extend(chars.next()),
+            (_, c) => out.push(c),
+        }
+    }
+    out
+}
+
+/// `value` in single quotes for the platform shell.
+pub fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        format!("'{}'", value.replace('\'', r#"'\''"#))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Expansion
+// ════════════════════════════════════════════════════════════════════
+
+/// Fill `template`'s placeholders from `args` (words as typed).
+pub fn substitute(name: &str, template: &str, args: &[String]) -> Result<String, String> {
+    let found = placeholders(template);
+    let needed = highest_arg(&found);
+    let takes_rest = found.iter().any(|(_, p)| matches!(p, Placeholder::Rest | Placeholder::All));
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    if args.len() < needed {
+        return Err(format!(
+            "❌ {} needs {} argument{}, got {}. Usage: {}",
+            name,
+            needed,
+            plural(needed),
+            args.len(),
+            usage(name, template)
+        ));
+    }
+    if args.len() > needed && !takes_rest {
+        return Err(format!(
+            "❌ {} takes {} argument{}, got {}. Usage: {}",
+            name,
+            needed,
+            plural(needed),
+            args.len(),
+            usage(name, template)
+        ));
+    }
+
+    let mut out = String::with_capacity(template.len());
+    let mut last = 0;
+    for (range, placeholder) in found {
+        out.push_str(&template[last..range.start]);
+        match placeholder {
+            Placeholder::Arg(n) => out.push_str(&args[n - 1]),
+            Placeholder::Rest => out.push_str(&args[needed..].join(" ")),
+            Placeholder::All => {
+                let quoted: Vec<String> = args.iter().map(|a| shell_quote(&unquote(a))).collect();
+                out.push_str(&quoted.join(" "));
+            }
+        }
+        last = range.end;
+    }
+    out.push_str(&template[last..]);
+    Ok(out)
+}
+
+/// Expand the alias `line` starts with, following aliases that expand to
+/// aliases. `None` when the first word is not an alias.
+pub fn expand(line: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Option<String>, String> {
+    let mut line = line.trim_start().to_string();
+    let mut chain: Vec<String> = Vec::new();
+    while let Some(first) = line.split_whitespace().next().map(str::to_string) {
+        // `ls = ls -F` names itself; the shell's `ls` is meant.
+        if chain.last() == Some(&first) {
+            break;
+        }
+        let Some(template) = lookup(&first) else {
+            break;
+        };
+        if chain.contains(&first) {
+            chain.push(first);
+            return Err(format!("❌ Alias cycle: {}", chain.join(" → ")));
+        }
+        if chain.len() == MAX_ALIAS_DEPTH {
+            return Err(format!("❌ Aliases nest more than {} deep: {} → …", MAX_ALIAS_DEPTH, chain.join(" → ")));
+        }
+        let rest = &line[first.len()..];
+        let expanded = if has_placeholders(&template) {
+            substitute(&first, &template, &split_args(rest))?
+        } else {
+            format!("{}{}", template, rest)
+        };
+        chain.push(first);
+        line = expanded.trim_start().to_string();
+    }
+    Ok((!chain.is_empty()).then_some(line))
+}
+
+/// `!alias show` lines: the template with its placeholders marked by
+/// carets underneath, and how to call it.
+pub fn show(name: &str, template: &str) -> Vec<String> {
+    let found = placeholders(template);
+    let mut lines = vec![format!("📝 {}", name), format!("  {}", template)];
+    if found.is_empty() {
+        lines.push("  No placeholders: arguments are appended.".to_string());
+        return lines;
+    }
+    let mut marks = String::new();
+    for (range, _) in &found {
+        let start = template[..range.start].chars().count();
+        let width = template[range.clone()].chars().count();
+        marks.push_str(&" ".repeat(start - marks.chars().count()));
+        marks.push_str(&"^".repeat(width));
+    }
+    lines.push(format!("  {}", marks));
+    lines.push(format!("  Usage: {}", usage(name, template)));
+    let mut listed: Vec<Placeholder> = Vec::new();
+    for &(_, placeholder) in &found {
+        if listed.contains(&placeholder) {
+            continue;
+        }
+        listed.push(placeholder);
+        let meaning = match placeholder {
+            Placeholder::Arg(n) => format!("argument {} (required)", n),
+            Placeholder::Rest => "the remaining arguments, as typed".to_string(),
+            Placeholder::All => "every argument, each quoted".to_string(),
+        };
+        lines.push(format!("  {:<4} {}", placeholder.to_string(), meaning));
+    }
+    lines
+}
+
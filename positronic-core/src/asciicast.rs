@@ -0,0 +1,408 @@
+//! Session recording in asciicast v2 (`!record`).
+//!
+//! A cast is a JSON header line followed by one JSON array per event:
+//! `[seconds, "o", data]` for PTY output, `"i"` for input sent to the
+//! shell and `"r"` with `"COLSxROWS"` for a resize. Every event is written
+//! and flushed as it happens, so a crash loses at most the event being
+//! written. Input typed at a password prompt is left out of the `"i"`
+//! events; the echo-free output is recorded as usual.
+//!
+//! `CastPlayer` replays a parsed cast by elapsed time, optionally scaled.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::term::utf8::Utf8Decoder;
+
+/// Vault config key: size cap in MB before a recording stops itself.
+pub const MAX_MB_KEY: &str = "record.max_mb";
+pub const DEFAULT_MAX_MB: f64 = 100.0;
+
+pub const RECORD_USAGE: &str = "Usage: !record start [path] | stop | play <path> [--speed <x>]";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CastHeader {
+    pub version: u32,
+    pub width: u16,
+    pub height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl CastHeader {
+    pub fn new(width: u16, height: u16, timestamp: i64) -> Self {
+        let mut env = BTreeMap::new();
+        if let Ok(shell) = std::env::var("SHELL") {
+            env.insert("SHELL".to_string(), shell);
+        }
+        env.insert("TERM".to_string(), "xterm-256color".to_string());
+        Self { version: 2, width, height, timestamp: Some(timestamp), title: None, env }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Output,
+    Input,
+    Resize,
+    Marker,
+}
+
+impl EventKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            EventKind::Output => "o",
+            EventKind::Input => "i",
+            EventKind::Resize => "r",
+            EventKind::Marker => "m",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<EventKind> {
+        match code {
+            "o" => Some(EventKind::Output),
+            "i" => Some(EventKind::Input),
+            "r" => Some(EventKind::Resize),
+            "m" => Some(EventKind::Marker),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds since the recording started.
+    pub time: f64,
+    pub kind: EventKind,
+    pub data: String,
+}
+
+impl CastEvent {
+    /// `[1.250000, "o", "data"]`, microsecond precision.
+    pub fn to_line(&self) -> String {
+        let time = (self.time * 1_000_000.0).round() / 1_000_000.0;
+        serde_json::to_string(&(time, self.kind.code(), &self.data)).unwrap_or_default()
+    }
+
+    /// `(cols, rows)` of a resize event.
+    pub fn size(&self) -> Option<(u16, u16)> {
+        if self.kind != EventKind::Resize {
+            return None;
+        }
+        let (cols, rows) = self.data.split_once('x')?;
+        Some((cols.parse().ok()?, rows.parse().ok()?))
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Writing
+// ════════════════════════════════════════════════════════════════════
+
+/// Streams a cast to `out`; event times are passed in by the caller.
+#[derive(Debug)]
+pub struct CastWriter<W: Write> {
+    out: W,
+    written: u64,
+    max_bytes: Option<u64>,
+    decoder: Utf8Decoder,
+    input_hidden: bool,
+    hidden_inputs: usize,
+    last_time: f64,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Write the header; `max_bytes` caps the whole file.
+    pub fn new(mut out: W, header: &CastHeader, max_bytes: Option<u64>) -> io::Result<Self> {
+        let line = serde_json::to_string(header).map_err(io::Error::other)?;
+        writeln!(out, "{}", line)?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            written: line.len() as u64 + 1,
+            max_bytes,
+            decoder: Utf8Decoder::new(),
+            input_hidden: false,
+            hidden_inputs: 0,
+            last_time: 0.0,
+        })
+    }
+
+    /// Bytes written so far, header included.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// The size cap has been reached; further events are dropped.
+    pub fn limit_reached(&self) -> bool {
+        self.max_bytes.is_some_and(|max| self.written >= max)
+    }
+
+    /// While set, input events are not recorded (password prompts).
+    pub fn set_input_hidden(&mut self, hidden: bool) {
+        self.input_hidden = hidden;
+    }
+
+    /// Input events left out because input was hidden.
+    pub fn hidden_inputs(&self) -> usize {
+        self.hidden_inputs
+    }
+
+    /// PTY output; a character split across chunks is held for the next one.
+    pub fn output(&mut self, at: Duration, bytes: &[u8]) -> io::Result<()> {
+        let mut text = String::new();
+        self.decoder.decode_into(bytes, &mut text);
+        self.event(at, EventKind::Output, text)
+    }
+
+    /// Input sent to the shell; returns false if it was left out.
+    pub fn input(&mut self, at: Duration, text: &str) -> io::Result<bool> {
+        if self.input_hidden {
+            self.hidden_inputs += 1;
+            return Ok(false);
+        }
+        self.event(at, EventKind::Input, text.to_string())?;
+        Ok(true)
+    }
+
+    pub fn resize(&mut self, at: Duration, cols: u16, rows: u16) -> io::Result<()> {
+        self.event(at, EventKind::Resize, format!("{}x{}", cols, rows))
+    }
+
+    /// Flush a held partial character and the stream.
+    pub fn finish(mut self) -> io::Result<u64> {
+        let mut tail = String::new();
+        self.decoder.finish(&mut tail);
+        let at = Duration::from_secs_f64(self.last_time);
+        self.event(at, EventKind::Output, tail)?;
+        self.out.flush()?;
+        Ok(self.written)
+    }
+
+    fn event(&mut self, at: Duration, kind: EventKind, data: String) -> io::Result<()> {
+        if data.is_empty() || self.limit_reached() {
+            return Ok(());
+        }
+        // Times never go backwards, even if the caller's clock does.
+        let time = at.as_secs_f64().max(self.last_time);
+        self.last_time = time;
+        let line = CastEvent { time, kind, data }.to_line();
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()?;
+        self.written += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// A cast being written to a file, timed from when it started.
+#[derive(Debug)]
+pub struct FileRecording {
+    path: PathBuf,
+    started: Instant,
+    writer: CastWriter<BufWriter<File>>,
+}
+
+impl FileRecording {
+    pub fn start(path: &Path, cols: u16, rows: u16, max_bytes: u64) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let header = CastHeader::new(cols, rows, chrono::Utc::now().timestamp());
+        let writer = CastWriter::new(BufWriter::new(file), &header, Some(max_bytes))?;
+        Ok(Self { path: path.to_path_buf(), started: Instant::now(), writer })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn written(&self) -> u64 {
+        self.writer.written()
+    }
+
+    pub fn limit_reached(&self) -> bool {
+        self.writer.limit_reached()
+    }
+
+    pub fn set_input_hidden(&mut self, hidden: bool) {
+        self.writer.set_input_hidden(hidden);
+    }
+
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.output(self.started.elapsed(), bytes)
+    }
+
+    pub fn input(&mut self, text: &str) -> io::Result<bool> {
+        self.writer.input(self.started.elapsed(), text)
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.writer.resize(self.started.elapsed(), cols, rows)
+    }
+
+    /// Close the file; returns its size.
+    pub fn finish(self) -> io::Result<u64> {
+        self.writer.finish()
+    }
+}
+
+/// A prompt that reads a secret without echo: `Password:`,
+/// `[sudo] password for ann:`, `Enter passphrase for key '...':`.
+pub fn is_password_prompt(line: &str) -> bool {
+    let line = line.trim_end().to_lowercase();
+    if !line.ends_with(':') {
+        return false;
+    }
+    ["password", "passphrase", "passcode", "pin", "secret"]
+        .iter()
+        .any(|word| line.contains(word))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Reading and playback
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    pub header: CastHeader,
+    pub events: Vec<CastEvent>,
+}
+
+impl Cast {
+    /// Parse a v2 cast; errors name the offending line.
+    pub fn parse(text: &str) -> Result<Cast, String> {
+        let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let (_, first) = lines.next().ok_or("empty cast file")?;
+        let header: CastHeader =
+            serde_json::from_str(first).map_err(|e| format!("line 1: bad header: {}", e))?;
+        if header.version != 2 {
+            return Err(format!("line 1: asciicast version {} (only 2 is supported)", header.version));
+        }
+        let mut events = Vec::new();
+        for (i, line) in lines {
+            let value: Value =
+                serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            let event = match value.as_array().map(Vec::as_slice) {
+                Some([Value::Number(t), Value::String(code), Value::String(data)]) => {
+                    let kind = EventKind::from_code(code)
+                        .ok_or_else(|| format!("line {}: unknown event type {:?}", i + 1, code))?;
+                    CastEvent { time: t.as_f64().unwrap_or(0.0), kind, data: data.clone() }
+                }
+                _ => return Err(format!("line {}: expected [time, type, data]", i + 1)),
+            };
+            events.push(event);
+        }
+        Ok(Cast { header, events })
+    }
+
+    /// Time of the last event.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.events.last().map_or(0.0, |e| e.time.max(0.0)))
+    }
+}
+
+/// Hands out a cast's events as playback time passes.
+#[derive(Debug, Clone)]
+pub struct CastPlayer {
+    cast: Cast,
+    speed: f64,
+    next: usize,
+}
+
+impl CastPlayer {
+    /// `speed` 2.0 plays twice as fast; non-positive values mean 1.0.
+    pub fn new(cast: Cast, speed: f64) -> Self {
+        let speed = if speed > 0.0 { speed } else { 1.0 };
+        Self { cast, speed, next: 0 }
+    }
+
+    pub fn header(&self) -> &CastHeader {
+        &self.cast.header
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Events due `elapsed` (wall time) after playback started.
+    pub fn due(&mut self, elapsed: Duration) -> &[CastEvent] {
+        let now = elapsed.as_secs_f64() * self.speed;
+        let start = self.next;
+        while self.next < self.cast.events.len() && self.cast.events[self.next].time <= now {
+            self.next += 1;
+        }
+        &self.cast.events[start..self.next]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.cast.events.len()
+    }
+
+    /// Playback length in wall time at this speed.
+    pub fn duration(&self) -> Duration {
+        self.cast.duration().div_f64(self.speed)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !record
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordCommand {
+    Status,
+    Start(Option<String>),
+    Stop,
+    Play { path: String, speed: f64 },
+}
+
+impl RecordCommand {
+    /// Parse the words after `!record`.
+    pub fn parse(args: &[&str]) -> Result<RecordCommand, String> {
+        match args {
+            [] => Ok(RecordCommand::Status),
+            ["start"] => Ok(RecordCommand::Start(None)),
+            ["start", path] => Ok(RecordCommand::Start(Some(path.to_string()))),
+            ["stop"] => Ok(RecordCommand::Stop),
+            ["play", path] => Ok(RecordCommand::Play { path: path.to_string(), speed: 1.0 }),
+            ["play", path, "--speed", speed] | ["play", "--speed", speed, path] => {
+                let speed: f64 = speed
+                    .trim_end_matches('x')
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| *s > 0.0)
+                    .ok_or_else(|| format!("Bad speed '{}' (e.g. 2 or 0.5)", speed))?;
+                Ok(RecordCommand::Play { path: path.to_string(), speed })
+            }
+            _ => Err(RECORD_USAGE.to_string()),
+        }
+    }
+}
+
+/// Where `!record start` without a path records, under the data
+/// directory (see `data_paths`).
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// `positronic-20260314-093000.cast`, for `!record start` without a path.
+pub fn default_path(now: chrono::DateTime<chrono::Local>) -> String {
+    format!("positronic-{}.cast", now.format("%Y%m%d-%H%M%S"))
+}
+
+/// Bytes for a `record.max_mb` value; unset, invalid or 0 uses the default.
+pub fn max_bytes(value: Option<&str>) -> u64 {
+    let mb = value
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|mb| *mb > 0.0)
+        .unwrap_or(DEFAULT_MAX_MB);
+    (mb * 1024.0 * 1024.0) as u64
+}
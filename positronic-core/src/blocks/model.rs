@@ -1,11 +1,14 @@
 //! Terminal block model (V2).
 //!
 //! This complements the existing `TerminalBlock` in lib.rs.
-//! V2 adds cwd + timing + stable UUID ids.
+//! V2 adds cwd + timing + stable UUID ids, and the arrival time of each
+//! output line as a millisecond offset from the start of the command.
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::term::progress::{self, FoldedLine};
+
 pub type BlockId = Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +21,10 @@ pub struct TerminalBlockV2 {
     pub started_at_unix: i64,
     pub ended_at_unix: i64,
     pub duration_ms: Option<i64>,
+    /// Arrival of each output line, in ms since the command started. Empty
+    /// for blocks recorded before lines were stamped.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub line_offsets_ms: Vec<u64>,
 }
 
 impl TerminalBlockV2 {
@@ -31,6 +38,36 @@ impl TerminalBlockV2 {
             started_at_unix,
             ended_at_unix: started_at_unix,
             duration_ms: None,
+            line_offsets_ms: Vec::new(),
+        }
+    }
+
+    /// Append a chunk of output that arrived `offset_ms` after the start.
+    /// Every line that begins in this chunk is stamped with that offset;
+    /// a line continued from an earlier chunk keeps its first stamp.
+    pub fn push_output(&mut self, text: &str, offset_ms: u64) {
+        let mut at_line_start = self.output.is_empty() || self.output.ends_with('\n');
+        for c in text.chars() {
+            if at_line_start {
+                self.line_offsets_ms.push(offset_ms);
+            }
+            at_line_start = c == '\n';
         }
+        self.output.push_str(text);
+    }
+
+    /// Replace the output with lines folded by a `ProgressFolder`, keeping
+    /// each line's stamp.
+    pub fn set_folded_output(&mut self, lines: &[FoldedLine]) {
+        self.output = progress::join_lines(lines);
+        self.line_offsets_ms = lines.iter().map(|l| l.offset_ms).collect();
+    }
+
+    /// Output lines paired with their arrival offset, if recorded.
+    pub fn stamped_lines(&self) -> impl Iterator<Item = (&str, Option<u64>)> {
+        self.output
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (line, self.line_offsets_ms.get(i).copied()))
     }
 }
@@ -1,19 +1,33 @@
 //! Block recorder.
 //!
 //! Inputs:
-//! - PTY output bytes (to accumulate output)
-//! - OSC events (cwd + prompt boundaries)
-//! - "command sent" (the actual command string we wrote to the PTY)
+//! - PTY output bytes (to accumulate output), from the engine's pump
+//! - OSC events (cwd + prompt boundaries), parsed from those bytes
+//! - "command sent" (the actual command string we wrote to the PTY),
+//!   from the Runner
 //!
 //! Output:
 //! - finalized TerminalBlockV2
-//! - persisted to Vault (history table)
+//! - persisted to Vault (history table), unless logging is off
+//!
+//! A block's output is what arrives between the command's start marker
+//! (OSC 133 `B`/`C`) and its finish marker (`D`), with the marker
+//! sequences cut out. A start marker with no command sent (a `cd` the
+//! engine typed itself) records nothing, and one that arrives while a
+//! block is open (bash marks each command of a pipeline) belongs to it.
+//!
+//! Unless `blocks.fold_progress` is off, output goes through a
+//! `ProgressFolder` so progress bars are stored as their final frame.
 //!
 //! This file intentionally keeps `eprintln!` debug lines for deep tracing.
 
 use chrono::Utc;
+use std::time::Instant;
 
-use crate::term::{OscEvent, OscParser, SemanticEvent, SemanticState};
+use crate::term::osc::{OscEvent, OscParser};
+use crate::term::progress::{self, ProgressFolder};
+use crate::term::semantic::SemanticState;
+use crate::term::utf8::Utf8Decoder;
 use crate::vault::Vault;
 
 use super::model::TerminalBlockV2;
@@ -27,22 +41,42 @@ pub enum RecorderEvent {
 #[derive(Debug)]
 pub struct BlockRecorder {
     vault: Vault,
+    /// Log finished blocks to the vault. Headless mode logs its own.
+    logging: bool,
 
     osc: OscParser,
     sem: SemanticState,
+    utf8: Utf8Decoder,
 
     in_flight: Option<TerminalBlockV2>,
+    /// Monotonic start of the in-flight block, for millisecond durations.
+    started: Option<Instant>,
     pending_command: Option<String>,
+    /// Folds the in-flight output; `None` when folding is off.
+    folder: Option<ProgressFolder>,
+    /// Output held back at the end of a chunk: an OSC sequence (or an ESC
+    /// that may start one) that is cut if it turns out to be a marker.
+    held: Vec<u8>,
+    /// Where the OSC sequence being parsed starts in the output.
+    osc_start: Option<usize>,
+    prev_esc: bool,
 }
 
 impl BlockRecorder {
     pub fn new(vault: Vault) -> Self {
         Self {
             vault,
+            logging: true,
             osc: OscParser::new(),
             sem: SemanticState::new(),
+            utf8: Utf8Decoder::new(),
             in_flight: None,
+            started: None,
             pending_command: None,
+            folder: None,
+            held: Vec::new(),
+            osc_start: None,
+            prev_esc: false,
         }
     }
 
@@ -50,71 +84,135 @@ impl BlockRecorder {
         &self.vault
     }
 
+    /// Whether finished blocks are logged to the vault (on by default).
+    pub fn set_logging(&mut self, logging: bool) {
+        self.logging = logging;
+    }
+
+    /// Forget the shell's state, for a new shell; logging stays as set.
+    pub fn reset(&mut self) {
+        *self = Self { logging: self.logging, ..Self::new(self.vault.clone()) };
+    }
+
     /// Call when Runner sends a command to PTY (after alias expansion).
+    /// A line sent while a block is open is input to that command.
     pub fn on_command_sent(&mut self, cmd: &str) {
         eprintln!("[REC] command_sent: {:?}", cmd);
-        self.pending_command = Some(cmd.to_string());
+        if self.in_flight.is_none() {
+            self.pending_command = Some(cmd.to_string());
+        }
     }
 
     /// Feed PTY output bytes. This:
     /// - parses OSC sequences to keep semantic state current
     /// - appends output to the current in-flight block (if any)
     pub fn on_pty_output(&mut self, bytes: &[u8], out: &mut Vec<RecorderEvent>) {
-        // 1) Parse OSC events
-        let mut osc_events = Vec::new();
-        self.osc.feed(bytes, &mut osc_events);
+        let mut raw = std::mem::take(&mut self.held);
+        for &b in bytes {
+            if self.in_flight.is_some() {
+                if self.prev_esc && b == b']' {
+                    self.osc_start = Some(raw.len() - 1);
+                }
+                self.prev_esc = b == 0x1b;
+                raw.push(b);
+            }
+            for ev in self.osc.feed(&[b]) {
+                // Markers are not output; cut the sequence that carried this one.
+                if let Some(start) = self.osc_start.take() {
+                    raw.truncate(start);
+                }
+                self.sem.apply(&ev);
+                self.on_osc_event(&ev, &mut raw, out);
+            }
+        }
 
-        for ev in &osc_events {
-            if let Some(sem_ev) = self.sem.apply_osc(ev) {
-                self.on_semantic_event(sem_ev, out);
+        // An open sequence waits for the next chunk to be cut or kept.
+        if self.in_flight.is_some() {
+            let open = self.osc_start.or_else(|| self.prev_esc.then(|| raw.len() - 1));
+            if let Some(at) = open {
+                self.held = raw.split_off(at);
+                self.osc_start = self.osc_start.map(|_| 0);
             }
         }
+        self.append(&raw);
+    }
 
-        // 2) Append bytes to in-flight output (lossy OK; terminal snapshot holds truth)
-        if let Some(block) = &mut self.in_flight {
-            let text = String::from_utf8_lossy(bytes);
-            block.output.push_str(&text);
+    /// Append bytes to in-flight output; characters split across reads are
+    /// carried by the decoder instead of becoming U+FFFD. Lines starting in
+    /// this chunk are stamped with its arrival.
+    fn append(&mut self, bytes: &[u8]) {
+        let Some(block) = &mut self.in_flight else {
+            return;
+        };
+        if bytes.is_empty() {
+            return;
+        }
+        let mut text = String::new();
+        self.utf8.decode_into(bytes, &mut text);
+        match &mut self.folder {
+            Some(folder) => folder.feed(&text, elapsed_ms(self.started)),
+            None => block.push_output(&text, elapsed_ms(self.started)),
         }
     }
 
-    fn on_semantic_event(&mut self, ev: SemanticEvent, out: &mut Vec<RecorderEvent>) {
+    fn on_osc_event(&mut self, ev: &OscEvent, raw: &mut Vec<u8>, out: &mut Vec<RecorderEvent>) {
         match ev {
-            SemanticEvent::CwdChanged(cwd) => {
-                if let Some(b) = &mut self.in_flight {
-                    b.cwd = Some(cwd);
+            OscEvent::CommandStart | OscEvent::CommandExecuted => {
+                if self.in_flight.is_some() {
+                    return;
                 }
-            }
-            SemanticEvent::CommandStart => {
                 // Start a new block using the most recent command_sent
-                let cmd = self
-                    .pending_command
-                    .take()
-                    .unwrap_or_else(|| "<unknown>".to_string());
+                let Some(cmd) = self.pending_command.take() else {
+                    return;
+                };
 
                 let now = Utc::now().timestamp();
                 let mut block = TerminalBlockV2::new_now(cmd, now);
                 block.cwd = self.sem.cwd.clone();
+                self.utf8 = Utf8Decoder::new();
+                let fold = self.vault.get_config(progress::FOLD_PROGRESS_KEY).ok().flatten();
+                self.folder = progress::fold_enabled(fold.as_deref()).then(ProgressFolder::new);
                 self.in_flight = Some(block);
+                self.started = Some(Instant::now());
+                self.prev_esc = false;
+                self.osc_start = None;
+                raw.clear();
                 eprintln!("[REC] BlockStarted");
                 out.push(RecorderEvent::BlockStarted);
             }
-            SemanticEvent::CommandEnd { exit_code } => {
+            OscEvent::CommandFinished { exit_code } => {
+                self.append(raw);
+                raw.clear();
                 if let Some(mut block) = self.in_flight.take() {
+                    let mut tail = String::new();
+                    self.utf8.finish(&mut tail);
+                    match self.folder.take() {
+                        Some(mut folder) => {
+                            folder.feed(&tail, elapsed_ms(self.started));
+                            block.set_folded_output(&folder.finish());
+                        }
+                        None => block.push_output(&tail, elapsed_ms(self.started)),
+                    }
                     let end = Utc::now().timestamp();
                     block.ended_at_unix = end;
-                    block.exit_code = exit_code;
-                    let dur = (end - block.started_at_unix) * 1000;
+                    block.exit_code = *exit_code;
+                    let dur = match self.started.take() {
+                        Some(started) => started.elapsed().as_millis() as i64,
+                        None => (end - block.started_at_unix) * 1000,
+                    };
                     block.duration_ms = Some(dur);
 
                     // Persist to vault
-                    let cwd = block.cwd.clone().unwrap_or_else(|| ".".to_string());
-                    let _ = self.vault.log_command(
-                        &block.command,
-                        Some(&block.output),
-                        block.exit_code,
-                        &cwd,
-                        block.duration_ms,
-                    );
+                    if self.logging {
+                        let cwd = block.cwd.clone().unwrap_or_else(|| ".".to_string());
+                        let _ = self.vault.log_command(
+                            &block.command,
+                            Some(&block.output),
+                            block.exit_code,
+                            &cwd,
+                            block.duration_ms,
+                        );
+                    }
 
                     eprintln!(
                         "[REC] BlockFinished cmd={:?} exit={:?} bytes={}",
@@ -126,7 +224,12 @@ impl BlockRecorder {
                     out.push(RecorderEvent::BlockFinished(block));
                 }
             }
-            _ => {}
+            OscEvent::PromptStart | OscEvent::Cwd(_) | OscEvent::Unknown(_) => {}
         }
     }
 }
+
+/// Milliseconds since `started` (0 without a start).
+fn elapsed_ms(started: Option<Instant>) -> u64 {
+    started.map(|s| s.elapsed().as_millis() as u64).unwrap_or(0)
+}
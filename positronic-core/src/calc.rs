@@ -0,0 +1,750 @@
+//! `!calc`: arithmetic and unit conversion, evaluated locally.
+//!
+//! A small recursive-descent parser that evaluates as it goes. Numbers are
+//! decimal (`1.5e3`, `1_000`), hex, binary or octal (`0x1F`, `0b1010`,
+//! `0o17`). Operators, loosest first:
+//!
+//! ```text
+//! |   xor   &   << >>   + -   * / %   unary - + ~   ^ (right-assoc)
+//! ```
+//!
+//! A size or time unit may follow a number or a parenthesized expression
+//! (`3 GiB`, `(1 + 2) ms`), and a trailing `in <unit>` / `to <unit>`
+//! converts the result. Sizes and durations only mix where that makes
+//! sense: adding them to plain numbers, or to each other, is an error.
+//! Errors carry the 1-based column they were found at.
+//!
+//! `hint` is the passive version for lines typed without `!calc`: it
+//! answers only for lines made of numbers and operators.
+
+use std::fmt;
+
+pub const CALC_USAGE: &str = "Usage: !calc <expression> [in <unit>]  e.g. !calc 3 GiB in MB, !calc 0xFF + 0b101";
+
+/// Vault config key: show the result of math typed at the prompt.
+pub const HINT_KEY: &str = "calc.hint";
+
+// ════════════════════════════════════════════════════════════════════
+// Units
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Size,
+    Time,
+}
+
+impl Dimension {
+    fn describe(dim: Option<Dimension>) -> &'static str {
+        match dim {
+            Some(Dimension::Size) => "a size",
+            Some(Dimension::Time) => "a duration",
+            None => "a plain number",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Unit {
+    pub name: &'static str,
+    pub dimension: Dimension,
+    /// Bytes or seconds in one of this unit.
+    pub factor: f64,
+}
+
+const KI: f64 = 1024.0;
+
+pub const UNITS: &[Unit] = &[
+    Unit { name: "B", dimension: Dimension::Size, factor: 1.0 },
+    Unit { name: "KB", dimension: Dimension::Size, factor: 1e3 },
+    Unit { name: "kB", dimension: Dimension::Size, factor: 1e3 },
+    Unit { name: "MB", dimension: Dimension::Size, factor: 1e6 },
+    Unit { name: "GB", dimension: Dimension::Size, factor: 1e9 },
+    Unit { name: "TB", dimension: Dimension::Size, factor: 1e12 },
+    Unit { name: "PB", dimension: Dimension::Size, factor: 1e15 },
+    Unit { name: "KiB", dimension: Dimension::Size, factor: KI },
+    Unit { name: "MiB", dimension: Dimension::Size, factor: KI * KI },
+    Unit { name: "GiB", dimension: Dimension::Size, factor: KI * KI * KI },
+    Unit { name: "TiB", dimension: Dimension::Size, factor: KI * KI * KI * KI },
+    Unit { name: "PiB", dimension: Dimension::Size, factor: KI * KI * KI * KI * KI },
+    Unit { name: "ns", dimension: Dimension::Time, factor: 1e-9 },
+    Unit { name: "us", dimension: Dimension::Time, factor: 1e-6 },
+    Unit { name: "µs", dimension: Dimension::Time, factor: 1e-6 },
+    Unit { name: "ms", dimension: Dimension::Time, factor: 1e-3 },
+    Unit { name: "s", dimension: Dimension::Time, factor: 1.0 },
+    Unit { name: "min", dimension: Dimension::Time, factor: 60.0 },
+    Unit { name: "h", dimension: Dimension::Time, factor: 3600.0 },
+    Unit { name: "d", dimension: Dimension::Time, factor: 86400.0 },
+];
+
+pub fn unit(name: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|u| u.name == name)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Values & errors
+// ════════════════════════════════════════════════════════════════════
+
+/// A result: `value` is in bytes or seconds when `unit` is set, and
+/// `unit` is the one to show it in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Option<&'static Unit>,
+}
+
+impl Quantity {
+    fn plain(value: f64) -> Self {
+        Self { value, unit: None }
+    }
+
+    pub fn dimension(&self) -> Option<Dimension> {
+        self.unit.map(|u| u.dimension)
+    }
+
+    /// The value in its display unit.
+    pub fn shown(&self) -> f64 {
+        match self.unit {
+            Some(unit) => self.value / unit.factor,
+            None => self.value,
+        }
+    }
+
+    /// A whole, unitless number small enough to print exactly.
+    pub fn as_integer(&self) -> Option<i64> {
+        let exact = self.unit.is_none() && self.value.fract() == 0.0 && self.value.abs() <= MAX_EXACT;
+        exact.then_some(self.value as i64)
+    }
+}
+
+/// Integers up to 2^53 survive the round trip through `f64`.
+const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+
+impl fmt::Display for Quantity {
+    /// `3221.225472 MB`, or `260 (0x104, 0b1_0000_0100)` for integers.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(n) = self.as_integer() {
+            return write!(f, "{}  ({}, {})", n, radix(n, 16), radix(n, 2));
+        }
+        write!(f, "{}", format_number(self.shown()))?;
+        if let Some(unit) = self.unit {
+            write!(f, " {}", unit.name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalcError {
+    pub message: String,
+    /// 1-based, in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+fn error<T>(message: impl Into<String>, column: usize) -> Result<T, CalcError> {
+    Err(CalcError { message: message.into(), column })
+}
+
+/// Shortest round-trippable form, without float noise (`0.1 + 0.2` is `0.3`).
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    if value != 0.0 && (value.abs() < 1e-6 || value.abs() >= 1e15) {
+        return format!("{:e}", value);
+    }
+    let fixed = format!("{:.12}", value);
+    fixed.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// `0x1f` / `0b1_0101` style, with a leading `-` for negatives. Binary
+/// digits are grouped in fours.
+pub fn radix(n: i64, base: u32) -> String {
+    let sign = if n < 0 { "-" } else { "" };
+    let magnitude = n.unsigned_abs();
+    match base {
+        16 => format!("{}0x{:X}", sign, magnitude),
+        _ => {
+            let digits = format!("{:b}", magnitude);
+            let mut grouped = String::new();
+            for (i, c) in digits.chars().enumerate() {
+                if i > 0 && (digits.len() - i) % 4 == 0 {
+                    grouped.push('_');
+                }
+                grouped.push(c);
+            }
+            format!("{}0b{}", sign, grouped)
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Tokens
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Ident(String),
+    /// Operator or punctuation: `+ - * / % ^ & | ~ << >> ( ) ,`.
+    Op(&'static str),
+    End,
+}
+
+impl Tok {
+    fn describe(&self) -> String {
+        match self {
+            Tok::Num(n) => format!("number {}", format_number(*n)),
+            Tok::Ident(name) => format!("'{}'", name),
+            Tok::Op(op) => format!("'{}'", op),
+            Tok::End => "end of input".to_string(),
+        }
+    }
+}
+
+const OPERATORS: &[&str] = &["<<", ">>", "+", "-", "*", "/", "%", "^", "&", "|", "~", "(", ")", ","];
+
+fn tokenize(input: &str) -> Result<Vec<(Tok, usize)>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let (value, len) = number(&chars[i..], column)?;
+            tokens.push((Tok::Num(value), column));
+            i += len;
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let len = chars[i..].iter().take_while(|c| c.is_alphanumeric() || **c == '_').count();
+            tokens.push((Tok::Ident(chars[i..i + len].iter().collect()), column));
+            i += len;
+            continue;
+        }
+        let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            Some(op) => {
+                tokens.push((Tok::Op(op), column));
+                i += op.chars().count();
+            }
+            None => return error(format!("unexpected '{}'", c), column),
+        }
+    }
+    tokens.push((Tok::End, chars.len() + 1));
+    Ok(tokens)
+}
+
+/// A numeric literal at the start of `chars`, and how many chars it took.
+fn number(chars: &[char], column: usize) -> Result<(f64, usize), CalcError> {
+    let prefixed = match (chars.first(), chars.get(1)) {
+        (Some('0'), Some('x' | 'X')) => Some(16),
+        (Some('0'), Some('b' | 'B')) => Some(2),
+        (Some('0'), Some('o' | 'O')) => Some(8),
+        _ => None,
+    };
+    if let Some(base) = prefixed {
+        let len = 2 + chars[2..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').count();
+        let digits: String = chars[2..len].iter().filter(|c| **c != '_').collect();
+        return match u64::from_str_radix(&digits, base) {
+            Ok(n) => Ok((n as f64, len)),
+            Err(_) => error(format!("invalid base-{} number '{}'", base, chars[..len].iter().collect::<String>()), column),
+        };
+    }
+
+    let mut len = chars.iter().take_while(|c| c.is_ascii_digit() || **c == '_' || **c == '.').count();
+    // An exponent, only when digits follow (`2e` is 2 then `e`).
+    if matches!(chars.get(len), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(len + 1), Some('+' | '-')));
+        let digits = chars[(len + 1 + sign).min(chars.len())..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if digits > 0 {
+            len += 1 + sign + digits;
+        }
+    }
+    let text: String = chars[..len].iter().filter(|c| **c != '_').collect();
+    match text.parse::<f64>() {
+        Ok(n) => Ok((n, len)),
+        Err(_) => error(format!("invalid number '{}'", chars[..len].iter().collect::<String>()), column),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Parser
+// ════════════════════════════════════════════════════════════════════
+
+/// Evaluate `input`.
+pub fn evaluate(input: &str) -> Result<Quantity, CalcError> {
+    let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+    if parser.peek() == &Tok::End {
+        return error("empty expression", 1);
+    }
+    let mut result = parser.expr()?;
+
+    if let Tok::Ident(word) = parser.peek().clone() {
+        if word == "in" || word == "to" {
+            parser.pos += 1;
+            let column = parser.column();
+            let target = match parser.next() {
+                Tok::Ident(name) => unit(&name).ok_or(CalcError { message: format!("unknown unit '{}'", name), column })?,
+                other => return error(format!("expected a unit after '{}', found {}", word, other.describe()), column),
+            };
+            if result.dimension() != Some(target.dimension) {
+                return error(
+                    format!("can't convert {} to {}", Dimension::describe(result.dimension()), target.name),
+                    column,
+                );
+            }
+            result.unit = Some(target);
+        }
+    }
+
+    match parser.peek() {
+        Tok::End => {}
+        other => return error(format!("unexpected {}", other.describe()), parser.column()),
+    }
+    if !result.value.is_finite() {
+        return error("result is not a finite number", 1);
+    }
+    Ok(result)
+}
+
+struct Parser {
+    tokens: Vec<(Tok, usize)>,
+    pos: usize,
+}
+
+type Binary = fn(Quantity, Quantity, usize) -> Result<Quantity, CalcError>;
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.pos].0
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Tok {
+        let tok = self.tokens[self.pos].0.clone();
+        if tok != Tok::End {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    /// The operator at the cursor, if it is one of `ops`; consumes it.
+    fn eat(&mut self, ops: &[&str]) -> Option<(&'static str, usize)> {
+        match self.peek() {
+            Tok::Op(op) if ops.contains(op) => {
+                let found = (*op, self.column());
+                self.pos += 1;
+                Some(found)
+            }
+            // The only operator spelled as a word.
+            Tok::Ident(word) if word == "xor" && ops.contains(&"xor") => {
+                let found = ("xor", self.column());
+                self.pos += 1;
+                Some(found)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), CalcError> {
+        if self.eat(&[op]).is_some() {
+            return Ok(());
+        }
+        error(format!("expected '{}', found {}", op, self.peek().describe()), self.column())
+    }
+
+    /// Left-associative level: `next (op next)*`.
+    fn level(
+        &mut self,
+        ops: &[&str],
+        next: fn(&mut Parser) -> Result<Quantity, CalcError>,
+        apply: fn(&str) -> Binary,
+    ) -> Result<Quantity, CalcError> {
+        let mut left = next(self)?;
+        while let Some((op, column)) = self.eat(ops) {
+            let right = next(self)?;
+            left = apply(op)(left, right, column)?;
+        }
+        Ok(left)
+    }
+
+    fn expr(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["|"], Parser::xor, |_| bit_or)
+    }
+
+    fn xor(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["xor"], Parser::and, |_| bit_xor)
+    }
+
+    fn and(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["&"], Parser::shift, |_| bit_and)
+    }
+
+    fn shift(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["<<", ">>"], Parser::additive, |op| if op == "<<" { shift_left } else { shift_right })
+    }
+
+    fn additive(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["+", "-"], Parser::term, |op| if op == "+" { add } else { sub })
+    }
+
+    fn term(&mut self) -> Result<Quantity, CalcError> {
+        self.level(&["*", "/", "%"], Parser::unary, |op| match op {
+            "*" => mul,
+            "/" => div,
+            _ => rem,
+        })
+    }
+
+    fn unary(&mut self) -> Result<Quantity, CalcError> {
+        if let Some((op, column)) = self.eat(&["-", "+", "~"]) {
+            let operand = self.unary()?;
+            return match op {
+                "-" => Ok(Quantity { value: -operand.value, ..operand }),
+                "+" => Ok(operand),
+                _ => {
+                    let n = integer(operand, "~", column)?;
+                    Ok(Quantity::plain(!n as f64))
+                }
+            };
+        }
+        self.power()
+    }
+
+    /// `postfix ^ unary`, right-associative: `2^3^2` is `2^9`, `2^-1` is 0.5.
+    fn power(&mut self) -> Result<Quantity, CalcError> {
+        let base = self.postfix()?;
+        if let Some((_, column)) = self.eat(&["^"]) {
+            let exponent = self.unary()?;
+            plain(&[base, exponent], "^", column)?;
+            return Ok(Quantity::plain(base.value.powf(exponent.value)));
+        }
+        Ok(base)
+    }
+
+    /// A primary, optionally followed by a unit (`3 GiB`, `(1 + 2) ms`).
+    fn postfix(&mut self) -> Result<Quantity, CalcError> {
+        let value = self.primary()?;
+        let suffix = match (self.peek(), self.tokens.get(self.pos + 1).map(|t| &t.0)) {
+            (Tok::Ident(name), next) if next != Some(&Tok::Op("(")) => unit(name),
+            _ => None,
+        };
+        let Some(suffix) = suffix else {
+            return Ok(value);
+        };
+        if value.unit.is_some() {
+            return error(format!("'{}' follows a value that already has a unit", suffix.name), self.column());
+        }
+        self.pos += 1;
+        Ok(Quantity { value: value.value * suffix.factor, unit: Some(suffix) })
+    }
+
+    fn primary(&mut self) -> Result<Quantity, CalcError> {
+        let column = self.column();
+        match self.next() {
+            Tok::Num(n) => Ok(Quantity::plain(n)),
+            Tok::Op("(") => {
+                let inner = self.expr()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Tok::Ident(name) => {
+                if self.eat(&["("]).is_some() {
+                    let mut args = Vec::new();
+                    if self.eat(&[")"]).is_none() {
+                        loop {
+                            args.push(self.expr()?);
+                            if self.eat(&[","]).is_some() {
+                                continue;
+                            }
+                            self.expect(")")?;
+                            break;
+                        }
+                    }
+                    return call(&name, &args, column);
+                }
+                match name.as_str() {
+                    "pi" => Ok(Quantity::plain(std::f64::consts::PI)),
+                    "e" => Ok(Quantity::plain(std::f64::consts::E)),
+                    _ if unit(&name).is_some() => error(format!("unit '{}' needs a number before it", name), column),
+                    _ => error(format!("unknown name '{}'", name), column),
+                }
+            }
+            other => error(format!("unexpected {}", other.describe()), column),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Operations
+// ════════════════════════════════════════════════════════════════════
+
+/// Fail unless every operand is a plain number.
+fn plain(operands: &[Quantity], what: &str, column: usize) -> Result<(), CalcError> {
+    match operands.iter().find(|q| q.unit.is_some()) {
+        Some(q) => error(format!("'{}' needs plain numbers, not {}", what, Dimension::describe(q.dimension())), column),
+        None => Ok(()),
+    }
+}
+
+/// A whole number that fits in 64 bits, for the bitwise operators.
+fn integer(q: Quantity, what: &str, column: usize) -> Result<i64, CalcError> {
+    plain(&[q], what, column)?;
+    if q.value.fract() != 0.0 || q.value.abs() > i64::MAX as f64 {
+        return error(format!("'{}' needs whole numbers, got {}", what, format_number(q.value)), column);
+    }
+    Ok(q.value as i64)
+}
+
+/// The unit to show a combination in: the left operand's, else the right's.
+fn shown_unit(left: Quantity, right: Quantity) -> Option<&'static Unit> {
+    left.unit.or(right.unit)
+}
+
+/// `+`, `-` and `%` need both sides in the same dimension.
+fn same_dimension(left: Quantity, right: Quantity, verb: &str, column: usize) -> Result<(), CalcError> {
+    if left.dimension() == right.dimension() {
+        return Ok(());
+    }
+    error(
+        format!(
+            "can't {} {} and {}",
+            verb,
+            Dimension::describe(left.dimension()),
+            Dimension::describe(right.dimension())
+        ),
+        column,
+    )
+}
+
+fn add(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    same_dimension(left, right, "add", column)?;
+    Ok(Quantity { value: left.value + right.value, unit: shown_unit(left, right) })
+}
+
+fn sub(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    same_dimension(left, right, "subtract", column)?;
+    Ok(Quantity { value: left.value - right.value, unit: shown_unit(left, right) })
+}
+
+fn mul(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    if left.unit.is_some() && right.unit.is_some() {
+        return error("can't multiply two values with units", column);
+    }
+    Ok(Quantity { value: left.value * right.value, unit: shown_unit(left, right) })
+}
+
+fn div(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    if right.value == 0.0 {
+        return error("division by zero", column);
+    }
+    let unit = match (left.dimension(), right.dimension()) {
+        (_, None) => left.unit,
+        // A ratio: 1 GiB / 1 MiB is 1024.
+        (Some(l), Some(r)) if l == r => None,
+        (l, r) => {
+            return error(format!("can't divide {} by {}", Dimension::describe(l), Dimension::describe(r)), column)
+        }
+    };
+    Ok(Quantity { value: left.value / right.value, unit })
+}
+
+fn rem(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    same_dimension(left, right, "take the remainder of", column)?;
+    if right.value == 0.0 {
+        return error("division by zero", column);
+    }
+    Ok(Quantity { value: left.value % right.value, unit: shown_unit(left, right) })
+}
+
+fn bitwise(
+    left: Quantity,
+    right: Quantity,
+    what: &str,
+    column: usize,
+    op: fn(i64, i64) -> i64,
+) -> Result<Quantity, CalcError> {
+    let (l, r) = (integer(left, what, column)?, integer(right, what, column)?);
+    Ok(Quantity::plain(op(l, r) as f64))
+}
+
+fn bit_or(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    bitwise(left, right, "|", column, |l, r| l | r)
+}
+
+fn bit_xor(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    bitwise(left, right, "xor", column, |l, r| l ^ r)
+}
+
+fn bit_and(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    bitwise(left, right, "&", column, |l, r| l & r)
+}
+
+fn shift_amount(right: Quantity, what: &str, column: usize) -> Result<u32, CalcError> {
+    let n = integer(right, what, column)?;
+    if !(0..64).contains(&n) {
+        return error(format!("shift by {} is out of range 0 to 63", n), column);
+    }
+    Ok(n as u32)
+}
+
+fn shift_left(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    let (l, n) = (integer(left, "<<", column)?, shift_amount(right, "<<", column)?);
+    Ok(Quantity::plain(l.wrapping_shl(n) as f64))
+}
+
+fn shift_right(left: Quantity, right: Quantity, column: usize) -> Result<Quantity, CalcError> {
+    let (l, n) = (integer(left, ">>", column)?, shift_amount(right, ">>", column)?);
+    Ok(Quantity::plain((l >> n) as f64))
+}
+
+/// Functions by name: `sqrt`, `cbrt`, `ln`, `log` (base 10, or `log(x, b)`),
+/// `log2`, `exp`, `pow`, `abs`, `floor`, `ceil`, `round`, `min`, `max`.
+fn call(name: &str, args: &[Quantity], column: usize) -> Result<Quantity, CalcError> {
+    let arity = |n: usize| -> Result<(), CalcError> {
+        if args.len() == n {
+            return Ok(());
+        }
+        let noun = if n == 1 { "argument" } else { "arguments" };
+        error(format!("{}() takes {} {}, got {}", name, n, noun, args.len()), column)
+    };
+    let unary = |f: fn(f64) -> f64| -> Result<Quantity, CalcError> {
+        arity(1)?;
+        plain(args, name, column)?;
+        Ok(Quantity::plain(f(args[0].value)))
+    };
+    let positive = |f: fn(f64) -> f64| -> Result<Quantity, CalcError> {
+        arity(1)?;
+        plain(args, name, column)?;
+        if args[0].value <= 0.0 {
+            return error(format!("{}() needs a positive number", name), column);
+        }
+        Ok(Quantity::plain(f(args[0].value)))
+    };
+    match name {
+        "sqrt" => {
+            arity(1)?;
+            if args[0].value < 0.0 {
+                return error("sqrt() of a negative number", column);
+            }
+            unary(f64::sqrt)
+        }
+        "cbrt" => unary(f64::cbrt),
+        "exp" => unary(f64::exp),
+        "floor" => unary(f64::floor),
+        "ceil" => unary(f64::ceil),
+        "round" => unary(f64::round),
+        "ln" => positive(f64::ln),
+        "log2" => positive(f64::log2),
+        "log" if args.len() == 2 => {
+            plain(args, name, column)?;
+            if args[0].value <= 0.0 || args[1].value <= 0.0 || args[1].value == 1.0 {
+                return error("log() needs a positive number and a positive base other than 1", column);
+            }
+            Ok(Quantity::plain(args[0].value.log(args[1].value)))
+        }
+        "log" => positive(f64::log10),
+        "pow" => {
+            arity(2)?;
+            plain(args, name, column)?;
+            Ok(Quantity::plain(args[0].value.powf(args[1].value)))
+        }
+        "abs" => {
+            arity(1)?;
+            Ok(Quantity { value: args[0].value.abs(), ..args[0] })
+        }
+        "min" | "max" => {
+            let Some(first) = args.first() else {
+                return error(format!("{}() needs at least one argument", name), column);
+            };
+            if let Some(other) = args.iter().find(|a| a.dimension() != first.dimension()) {
+                return error(
+                    format!(
+                        "{}() can't compare {} and {}",
+                        name,
+                        Dimension::describe(first.dimension()),
+                        Dimension::describe(other.dimension())
+                    ),
+                    column,
+                );
+            }
+            let pick = args.iter().copied().reduce(|best, a| {
+                let better = if name == "min" { a.value < best.value } else { a.value > best.value };
+                if better { a } else { best }
+            });
+            Ok(pick.unwrap_or(*first))
+        }
+        _ => error(format!("unknown function '{}'", name), column),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Front ends
+// ════════════════════════════════════════════════════════════════════
+
+/// `!calc <expression>`: the result, or the error with a caret under
+/// the column it points at.
+pub fn command_lines(expression: &str) -> Vec<String> {
+    if expression.trim().is_empty() {
+        return vec![CALC_USAGE.to_string()];
+    }
+    match evaluate(expression) {
+        Ok(result) => vec![format!("🧮 {} = {}", expression.trim(), result)],
+        Err(e) => vec![
+            format!("❌ {}", e),
+            format!("  {}", expression),
+            format!("  {}^", " ".repeat(e.column.saturating_sub(1))),
+        ],
+    }
+}
+
+/// `= 4` for a line like `2+2` typed at the prompt, or `None` for
+/// anything that isn't plainly arithmetic. Nothing is executed.
+pub fn hint(line: &str) -> Option<String> {
+    let line = line.trim();
+    if !looks_like_math(line) {
+        return None;
+    }
+    let result = evaluate(line).ok()?;
+    Some(format!("= {}", format_number(result.shown())))
+}
+
+/// Numbers (decimal or `0x`/`0b`/`0o`), operators and parentheses, with
+/// at least one binary operator between operands. Units and functions
+/// are left to `!calc`, since they read like command names.
+fn looks_like_math(line: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_hexdigit() || " \t.+-*/%^&|~<>()_xXoO".contains(c);
+    if line.is_empty() || !line.chars().all(allowed) {
+        return false;
+    }
+    if !line.starts_with(|c: char| c.is_ascii_digit() || c == '(' || c == '-' || c == '.') {
+        return false;
+    }
+    // An operator that follows an operand, not just a sign.
+    let mut seen_operand = false;
+    for c in line.chars() {
+        if c.is_ascii_digit() || c == ')' {
+            seen_operand = true;
+        } else if seen_operand && "+-*/%^&|<>".contains(c) {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,194 @@
+//! Cancelling `!` commands in flight.
+//!
+//! The runner registers each native command it dispatches in a
+//! `Cancellations` table under an invocation id, with a
+//! `CancellationToken`, and drops it from the table when the command
+//! returns. Esc (or Ctrl+C at the prompt, with nothing running in the
+//! PTY and nothing selected) cancels the newest; `!abort <id>` a given
+//! one. The token is passed to the command, which gives up at its next
+//! await (`or_cancelled`) and undoes what it half did: `!http` deletes a
+//! partial spill file, `!ai` leaves `!ai last` alone. The runner then
+//! answers `✖ cancelled after 3.2s` in place of whatever it returned.
+//!
+//! A long-running command must take the token. One that doesn't is still
+//! cancelled: `until_done` drops it after `CANCEL_GRACE`.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub use tokio_util::sync::CancellationToken;
+
+pub const ABORT_USAGE: &str = "Usage: !abort [id]  (no id: the newest command in flight)";
+
+/// What a command returns when it gives up; the runner shows
+/// `cancelled_line` instead.
+pub const CANCELLED: &str = "✖ cancelled";
+
+/// How long a cancelled command may take to clean up before it is dropped.
+pub const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// `✖ cancelled after 3.2s`
+pub fn cancelled_line(elapsed: Duration) -> String {
+    format!("{} after {:.1}s", CANCELLED, elapsed.as_secs_f64())
+}
+
+/// Whether `line` is `!abort`, which cancels others and is never
+/// registered itself.
+pub fn is_abort(line: &str) -> bool {
+    line.split_whitespace().next() == Some("!abort")
+}
+
+/// `work`, unless `cancel` fires first: then `Err(CANCELLED)`, and `work`
+/// is dropped where it stood.
+pub async fn or_cancelled<F: Future>(cancel: &CancellationToken, work: F) -> Result<F::Output, String> {
+    tokio::select! {
+        biased;
+        _ = cancel.cancelled() => Err(CANCELLED.to_string()),
+        output = work => Ok(output),
+    }
+}
+
+/// Run `work` to the end, or until `cancel` fires and it either stops on
+/// its own or overstays `CANCEL_GRACE`. None when it was cancelled.
+pub async fn until_done<F: Future>(cancel: &CancellationToken, work: F) -> Option<F::Output> {
+    tokio::pin!(work);
+    let output = tokio::select! {
+        output = &mut work => Some(output),
+        _ = cancel.cancelled() => tokio::time::timeout(CANCEL_GRACE, &mut work).await.ok(),
+    };
+    output.filter(|_| !cancel.is_cancelled())
+}
+
+/// A registered command, as `!abort` lists it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InFlight {
+    pub id: u64,
+    pub line: String,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+struct Entry {
+    id: u64,
+    line: String,
+    started: Instant,
+    token: CancellationToken,
+}
+
+/// The session's native commands in flight.
+#[derive(Debug)]
+pub struct Cancellations {
+    next_id: AtomicU64,
+    running: Mutex<Vec<Entry>>,
+}
+
+impl Default for Cancellations {
+    fn default() -> Self {
+        Self { next_id: AtomicU64::new(1), running: Mutex::new(Vec::new()) }
+    }
+}
+
+impl Cancellations {
+    /// Register `line`; it stays in the table until the `Invocation` is
+    /// dropped.
+    pub fn begin(&self, line: &str) -> Invocation<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancellationToken::new();
+        let started = Instant::now();
+        self.entries().push(Entry { id, line: line.to_string(), started, token: token.clone() });
+        Invocation { table: self, id, started, token }
+    }
+
+    /// Commands in flight, oldest first.
+    pub fn in_flight(&self) -> Vec<InFlight> {
+        self.entries().iter().map(Entry::in_flight).collect()
+    }
+
+    /// The newest command in flight that isn't cancelled yet.
+    pub fn latest(&self) -> Option<InFlight> {
+        self.entries().iter().rev().find(|e| !e.token.is_cancelled()).map(Entry::in_flight)
+    }
+
+    /// Cancel the newest command in flight; what was cancelled.
+    pub fn cancel_latest(&self) -> Option<InFlight> {
+        let entries = self.entries();
+        let entry = entries.iter().rev().find(|e| !e.token.is_cancelled())?;
+        entry.token.cancel();
+        Some(entry.in_flight())
+    }
+
+    /// Cancel command `id`; None if it isn't in flight.
+    pub fn cancel(&self, id: u64) -> Option<InFlight> {
+        let entries = self.entries();
+        let entry = entries.iter().find(|e| e.id == id)?;
+        entry.token.cancel();
+        Some(entry.in_flight())
+    }
+
+    /// `!abort [id]`.
+    pub fn abort_lines(&self, args: &str) -> Vec<String> {
+        let args = args.trim();
+        if args.is_empty() {
+            return match self.cancel_latest() {
+                Some(cancelled) => vec![cancelling(&cancelled)],
+                None => vec!["Nothing to cancel: no ! command is running".to_string()],
+            };
+        }
+        let Ok(id) = args.trim_start_matches('#').parse::<u64>() else {
+            return vec![ABORT_USAGE.to_string()];
+        };
+        if let Some(cancelled) = self.cancel(id) {
+            return vec![cancelling(&cancelled)];
+        }
+        let mut lines = vec![format!("❌ No command #{} in flight", id)];
+        lines.extend(self.in_flight().iter().map(|c| {
+            format!("  #{:<4} {}  ({:.1}s)", c.id, c.line, c.elapsed.as_secs_f64())
+        }));
+        lines
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Vec<Entry>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Entry {
+    fn in_flight(&self) -> InFlight {
+        InFlight { id: self.id, line: self.line.clone(), elapsed: self.started.elapsed() }
+    }
+}
+
+fn cancelling(command: &InFlight) -> String {
+    format!("✖ Cancelling #{} {}", command.id, command.line)
+}
+
+/// A command's place in the table, removed on drop.
+#[derive(Debug)]
+pub struct Invocation<'a> {
+    table: &'a Cancellations,
+    id: u64,
+    started: Instant,
+    token: CancellationToken,
+}
+
+impl Invocation<'_> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+impl Drop for Invocation<'_> {
+    fn drop(&mut self) {
+        self.table.entries().retain(|e| e.id != self.id);
+    }
+}
@@ -0,0 +1,549 @@
+//! `!capture` and `!vars`: values from a command's output, reused by name.
+//!
+//! `!capture <name> [selector]` reads the last run with stored output and
+//! keeps what the selector picks in the session's `Variables`:
+//!
+//! - nothing: the whole output
+//! - `--line n`: line n, 1-based; negative counts from the end
+//! - `--col k|NAME [--row i]`: a column of the output read as a table
+//!   (`docker ps`, `kubectl get pods`, CSV), by number or header; the last
+//!   row unless `--row` says which
+//! - `--regex '…'`: the first match, or its first group if it has one
+//! - `--json path`: a value in JSON output, `.items[0].metadata.name`
+//!
+//! A later line's `%{name}` is replaced with the value just before it
+//! runs. A `!` command says what it ran; the shell echoes its own line.
+//! Inside single quotes, for a POSIX shell and for `!` commands, `%{…}`
+//! is left as typed, as is `\%{…}`. A value spanning lines goes in on
+//! one, the way `$(…)` word-splits.
+//!
+//! Variables last for the session and are never stored. A line whose
+//! values carry a credential is logged to the history as typed, with the
+//! `%{…}` still in it.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::alias::{split_args, unquote};
+use crate::history_filter::has_credential;
+use crate::native::{Cell, ColumnKind, DataFrame};
+
+pub const CAPTURE_USAGE: &str =
+    "Usage: !capture <name> [--line n | --col k|NAME [--row i] | --regex '…' | --json path]";
+pub const VARS_USAGE: &str = "Usage: !vars | !vars rm <name>… | !vars clear";
+
+/// How much of a value `!vars` and `!capture` show.
+pub const PREVIEW_CHARS: usize = 48;
+
+// ════════════════════════════════════════════════════════════════════
+// Selectors
+// ════════════════════════════════════════════════════════════════════
+
+/// A table column, by 1-based number or header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+/// What `!capture` keeps of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    Whole,
+    /// 1-based; negative counts from the end.
+    Line(i64),
+    /// A column of the table; `row` as for `Line`, the last by default.
+    Column { column: ColumnRef, row: Option<i64> },
+    /// Checked when parsed.
+    Regex(String),
+    Json(String),
+}
+
+/// A parsed `!capture` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    pub name: String,
+    pub selector: Selector,
+}
+
+impl Capture {
+    /// Parse the text after `!capture`; quotes group words as in a shell.
+    pub fn parse(args: &str) -> Result<Capture, String> {
+        let words: Vec<String> = split_args(args).iter().map(|w| unquote(w)).collect();
+        let Some((name, rest)) = words.split_first() else {
+            return Err(CAPTURE_USAGE.to_string());
+        };
+        if !is_valid_name(name) {
+            return Err(format!("❌ '{}' can't be a variable name: use letters, digits and '_', not starting with a digit", name));
+        }
+
+        let mut selector = None;
+        let mut row = None;
+        let mut words = rest.iter();
+        while let Some(flag) = words.next() {
+            let value = words.next().ok_or_else(|| format!("❌ {} needs a value. {}", flag, CAPTURE_USAGE))?;
+            let chosen = match flag.as_str() {
+                "--line" => Selector::Line(parse_index(flag, value)?),
+                "--col" => Selector::Column { column: parse_column(value), row: None },
+                "--regex" => {
+                    Regex::new(value).map_err(|e| format!("❌ Invalid regex {:?}: {}", value, e))?;
+                    Selector::Regex(value.clone())
+                }
+                "--json" => Selector::Json(value.clone()),
+                "--row" => {
+                    if row.replace(parse_index(flag, value)?).is_some() {
+                        return Err(format!("❌ --row is given twice. {}", CAPTURE_USAGE));
+                    }
+                    continue;
+                }
+                _ => return Err(CAPTURE_USAGE.to_string()),
+            };
+            if selector.replace(chosen).is_some() {
+                return Err(format!("❌ Choose one of --line, --col, --regex and --json. {}", CAPTURE_USAGE));
+            }
+        }
+
+        let selector = match (selector.unwrap_or(Selector::Whole), row) {
+            (Selector::Column { column, .. }, row) => Selector::Column { column, row },
+            (_, Some(_)) => return Err(format!("❌ --row goes with --col. {}", CAPTURE_USAGE)),
+            (selector, None) => selector,
+        };
+        Ok(Capture { name: name.clone(), selector })
+    }
+}
+
+/// Letters, digits and `_`, not starting with a digit: what `%{…}` can
+/// hold without quoting.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_index(flag: &str, value: &str) -> Result<i64, String> {
+    match value.parse::<i64>() {
+        Ok(n) if n != 0 => Ok(n),
+        _ => Err(format!("❌ {} takes a line number: 1 is the first, -1 the last", flag)),
+    }
+}
+
+fn parse_column(value: &str) -> ColumnRef {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => ColumnRef::Index(n),
+        _ => ColumnRef::Name(value.to_string()),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Extraction
+// ════════════════════════════════════════════════════════════════════
+
+/// What `selector` picks from `output` (plain text, no escapes), trimmed.
+pub fn extract(output: &str, selector: &Selector) -> Result<String, String> {
+    let lines = output_lines(output);
+    let value = match selector {
+        Selector::Whole => lines.join("\n"),
+        Selector::Line(n) => {
+            let line = pick(&lines, *n).ok_or_else(|| out_of_range("line", *n, lines.len()))?;
+            line.to_string()
+        }
+        Selector::Column { column, row } => {
+            let table = parse_table(output).ok_or("❌ The output isn't a table: it needs a header line and rows")?;
+            column_value(&table, column, row.unwrap_or(-1))?
+        }
+        Selector::Regex(pattern) => {
+            let regex = Regex::new(pattern).map_err(|e| format!("❌ Invalid regex {:?}: {}", pattern, e))?;
+            let found = regex.captures(output).ok_or_else(|| format!("❌ Nothing in the output matches {:?}", pattern))?;
+            found.get(1).or_else(|| found.get(0)).map(|m| m.as_str().to_string()).unwrap_or_default()
+        }
+        Selector::Json(path) => {
+            let value: Value =
+                serde_json::from_str(output.trim()).map_err(|e| format!("❌ The output isn't JSON: {}", e))?;
+            let found = json_path(&value, path)?;
+            match found {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }
+        }
+    };
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err("❌ The selector picked an empty value".to_string());
+    }
+    Ok(value)
+}
+
+/// The output's lines, less trailing whitespace and blank lines at the end.
+fn output_lines(output: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Item `n` of `items`: 1-based, or from the end if negative.
+fn pick<T>(items: &[T], n: i64) -> Option<&T> {
+    let index = if n > 0 { n - 1 } else { items.len() as i64 + n };
+    usize::try_from(index).ok().and_then(|i| items.get(i))
+}
+
+fn out_of_range(what: &str, n: i64, count: usize) -> String {
+    format!("❌ No {} {}: the output has {}", what, n, count)
+}
+
+/// Read `output` as a table: a header line, then rows. Fields split on
+/// tabs, commas, or runs of two or more spaces, or else single spaces, as
+/// long as the rows agree with the header; otherwise rows are cut where
+/// the header's columns start, for right-aligned and blank cells.
+pub fn parse_table(output: &str) -> Option<DataFrame> {
+    let lines: Vec<&str> = output_lines(output).into_iter().filter(|l| !l.trim().is_empty()).collect();
+    let (header, rows) = lines.split_first()?;
+    if rows.is_empty() {
+        return None;
+    }
+
+    let splitters: [fn(&str) -> Vec<String>; 4] = [
+        |line| if line.contains('\t') { line.split('\t').map(|f| f.trim().to_string()).collect() } else { Vec::new() },
+        |line| if line.contains(',') { line.split(',').map(|f| f.trim().to_string()).collect() } else { Vec::new() },
+        |line| wide_gaps(line).into_iter().map(|(_, field)| field.to_string()).collect(),
+        |line| line.split_whitespace().map(str::to_string).collect(),
+    ];
+    let agreed = splitters.iter().find_map(|split| {
+        let columns = split(header);
+        let cells: Vec<Vec<String>> = rows.iter().map(|row| split(row)).collect();
+        (columns.len() > 1 && cells.iter().all(|row| row.len() == columns.len())).then_some((columns, cells))
+    });
+    let (columns, cells) = match agreed {
+        Some(found) => found,
+        None => {
+            let starts = wide_gaps(header);
+            if starts.len() < 2 {
+                return None;
+            }
+            let columns = starts.iter().map(|(_, name)| name.to_string()).collect();
+            let cells = rows.iter().map(|row| cut_at(row, &starts)).collect();
+            (columns, cells)
+        }
+    };
+
+    let kinds: Vec<(&str, ColumnKind)> = columns.iter().map(|name: &String| (name.as_str(), ColumnKind::Text)).collect();
+    let mut frame = DataFrame::new(&kinds);
+    for row in cells {
+        frame.push_row(row.into_iter().map(|c| if c.is_empty() { Cell::Empty } else { Cell::Text(c) }).collect());
+    }
+    Some(frame)
+}
+
+/// Fields separated by two or more spaces, with the char column each
+/// starts at. `CONTAINER ID   IMAGE` is two fields.
+fn wide_gaps(line: &str) -> Vec<(usize, &str)> {
+    let mut fields = Vec::new();
+    // The field being read: its column, and its byte range so far.
+    let mut open: Option<(usize, usize, usize)> = None;
+    let mut spaces = 0;
+    for (column, (byte, c)) in line.char_indices().enumerate() {
+        if c == ' ' {
+            spaces += 1;
+            continue;
+        }
+        let end = byte + c.len_utf8();
+        match open.as_mut() {
+            Some(field) if spaces < 2 => field.2 = end,
+            _ => {
+                if let Some((start, from, to)) = open.replace((column, byte, end)) {
+                    fields.push((start, &line[from..to]));
+                }
+            }
+        }
+        spaces = 0;
+    }
+    if let Some((start, from, to)) = open {
+        fields.push((start, &line[from..to]));
+    }
+    fields
+}
+
+/// `row` cut at the char columns in `starts`; a cell runs to the next
+/// column's start.
+fn cut_at(row: &str, starts: &[(usize, &str)]) -> Vec<String> {
+    let chars: Vec<char> = row.chars().collect();
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (start, _))| {
+            let end = starts.get(i + 1).map_or(chars.len(), |(next, _)| *next).min(chars.len());
+            let start = (*start).min(end);
+            chars[start..end].iter().collect::<String>().trim().to_string()
+        })
+        .collect()
+}
+
+fn column_value(table: &DataFrame, column: &ColumnRef, row: i64) -> Result<String, String> {
+    let headers = table.headers();
+    let index = match column {
+        ColumnRef::Index(n) => {
+            if *n > headers.len() {
+                return Err(out_of_range("column", *n as i64, headers.len()));
+            }
+            n - 1
+        }
+        ColumnRef::Name(name) => headers.iter().position(|h| h.eq_ignore_ascii_case(name)).ok_or_else(|| {
+            format!("❌ No column {:?}; the table has: {}", name, headers.join(", "))
+        })?,
+    };
+    let cells = pick(&table.rows, row).ok_or_else(|| out_of_range("row", row, table.rows.len()))?;
+    Ok(match &cells[index] {
+        Cell::Text(text) => text.clone(),
+        _ => String::new(),
+    })
+}
+
+/// Follow `path` into `value`: keys and indexes, `.a.b[0]`, `a.b.0` or
+/// `["a.b"]`. `.` or an empty path is the whole value.
+pub fn json_path<'a>(value: &'a Value, path: &str) -> Result<&'a Value, String> {
+    let mut current = value;
+    for step in json_steps(path)? {
+        let next = match (&step, current) {
+            (JsonStep::Key(key), Value::Object(map)) => map.get(key),
+            (JsonStep::Index(i), Value::Array(items)) => items.get(*i),
+            (JsonStep::Key(key), Value::Array(items)) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        };
+        current = next.ok_or_else(|| format!("❌ Nothing at {} in the JSON output", path))?;
+    }
+    Ok(current)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum JsonStep {
+    Key(String),
+    Index(usize),
+}
+
+fn json_steps(path: &str) -> Result<Vec<JsonStep>, String> {
+    let bad = || format!("❌ Invalid JSON path {:?}: use keys and indexes, like .items[0].name", path);
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut steps = Vec::new();
+    let mut chars = path.chars().peekable();
+    let mut key = String::new();
+    let end_key = |key: &mut String, steps: &mut Vec<JsonStep>| {
+        if !key.is_empty() {
+            steps.push(JsonStep::Key(std::mem::take(key)));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => end_key(&mut key, &mut steps),
+            '[' => {
+                end_key(&mut key, &mut steps);
+                let mut inside = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inside.push(c),
+                        None => return Err(bad()),
+                    }
+                }
+                let inside = inside.trim();
+                let quoted = inside
+                    .strip_prefix('"')
+                    .and_then(|s| s.strip_suffix('"'))
+                    .or_else(|| inside.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')));
+                match (quoted, inside.parse::<usize>()) {
+                    (Some(name), _) => steps.push(JsonStep::Key(name.to_string())),
+                    (None, Ok(i)) => steps.push(JsonStep::Index(i)),
+                    _ => return Err(bad()),
+                }
+            }
+            c => key.push(c),
+        }
+    }
+    end_key(&mut key, &mut steps);
+    Ok(steps)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Variables
+// ════════════════════════════════════════════════════════════════════
+
+/// A captured value and the command it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub value: String,
+    pub from: String,
+}
+
+impl Variable {
+    /// Carries an API key, token or private key (see `history_filter`).
+    pub fn is_secret(&self) -> bool {
+        has_credential(&self.value)
+    }
+}
+
+/// The session's variables, by name.
+#[derive(Debug, Default)]
+pub struct Variables {
+    vars: BTreeMap<String, Variable>,
+}
+
+impl Variables {
+    pub fn set(&mut self, name: &str, value: String, from: &str) {
+        self.vars.insert(name.to_string(), Variable { value, from: from.to_string() });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.vars.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.vars.remove(name).is_some()
+    }
+
+    /// Forget them all; how many there were.
+    pub fn clear(&mut self) -> usize {
+        std::mem::take(&mut self.vars).len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// `!vars`: each variable with a preview of its value.
+    pub fn list_lines(&self) -> Vec<String> {
+        if self.vars.is_empty() {
+            return vec!["No variables yet: !capture <name> keeps a value from the last output".to_string()];
+        }
+        let width = self.vars.keys().map(|name| name.chars().count() + 3).max().unwrap_or(0);
+        let mut lines = vec![format!("📌 Variables ({}), used as %{{name}}:", self.vars.len())];
+        for (name, var) in &self.vars {
+            lines.push(format!("  {:<width$}  {}  ← {}", format!("%{{{}}}", name), preview(var), var.from));
+        }
+        lines
+    }
+
+    /// `!vars rm` and `!vars clear`; the usage for anything else.
+    pub fn command_lines(&mut self, args: &[&str]) -> Vec<String> {
+        match args {
+            [] | ["list"] => self.list_lines(),
+            ["clear"] => match self.clear() {
+                0 => vec!["No variables to clear".to_string()],
+                n => vec![format!("📌 Cleared {} variable{}", n, if n == 1 { "" } else { "s" })],
+            },
+            ["rm", names @ ..] if !names.is_empty() => names
+                .iter()
+                .map(|name| {
+                    let name = name.trim_start_matches("%{").trim_end_matches('}');
+                    if self.remove(name) {
+                        format!("📌 Removed %{{{}}}", name)
+                    } else {
+                        format!("❌ No variable %{{{}}}", name)
+                    }
+                })
+                .collect(),
+            _ => vec![VARS_USAGE.to_string()],
+        }
+    }
+}
+
+/// A value as `!vars` shows it: the first line, shortened, and how many
+/// more there are; never a credential.
+pub fn preview(var: &Variable) -> String {
+    if var.is_secret() {
+        return "•••••• (secret, not logged)".to_string();
+    }
+    let mut lines = var.value.lines();
+    let first = lines.next().unwrap_or("");
+    let mut shown: String = first.chars().take(PREVIEW_CHARS).collect();
+    if first.chars().count() > PREVIEW_CHARS {
+        shown.push('…');
+    }
+    match lines.count() {
+        0 => shown,
+        more => format!("{} (+{} line{})", shown, more, if more == 1 { "" } else { "s" }),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Substitution
+// ════════════════════════════════════════════════════════════════════
+
+/// A line with its `%{…}` replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded {
+    pub line: String,
+    /// The names used, in order, once each.
+    pub names: Vec<String>,
+}
+
+/// Replace `%{name}` in `line` with `lookup(name)`. With `posix_quotes`,
+/// single-quoted text is left alone (a `'` inside double quotes doesn't
+/// start one). `\%{…}` is never replaced. None when the line has no
+/// `%{…}` to replace; an unknown name is an error.
+pub fn substitute(
+    line: &str,
+    posix_quotes: bool,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Option<Expanded>, String> {
+    let mut out = String::with_capacity(line.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut single = false;
+    let mut double = false;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        match c {
+            '\'' if posix_quotes && !double => single = !single,
+            '"' if !single => double = !double,
+            '\\' if !single => {
+                out.push(c);
+                if let Some(next) = after.chars().next() {
+                    out.push(next);
+                    rest = &after[next.len_utf8()..];
+                } else {
+                    rest = after;
+                }
+                continue;
+            }
+            '%' if !single && after.starts_with('{') => {
+                if let Some(close) = after.find('}') {
+                    let name = &after[1..close];
+                    if is_valid_name(name) {
+                        let value = lookup(name)
+                            .ok_or_else(|| format!("❌ No variable %{{{}}}: !capture {} sets it, !vars lists them", name, name))?;
+                        out.push_str(&one_line(&value));
+                        if !names.iter().any(|n| n == name) {
+                            names.push(name.to_string());
+                        }
+                        rest = &after[close + 1..];
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+        rest = after;
+    }
+    Ok((!names.is_empty()).then_some(Expanded { line: out, names }))
+}
+
+/// A multi-line value's lines joined by spaces.
+fn one_line(value: &str) -> String {
+    if !value.contains('\n') {
+        return value.to_string();
+    }
+    value.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// Whether `line` is `!capture` or `!vars`, whose `%{…}` are names, not
+/// uses.
+pub fn is_capture_command(line: &str) -> bool {
+    matches!(line.split_whitespace().next(), Some("!capture" | "!vars"))
+}
@@ -0,0 +1,56 @@
+//! `!chain`: a command's iteration chain, the runs it was edited from.
+//!
+//! Taking a finished block's command back into the input line (a click on
+//! it, or `keys.edit_block_command`) links whatever is run next to that
+//! block: the window's block keeps `derived_from`, and so does the vault
+//! row, noted before the line runs (`Vault::note_derived`). Edit the
+//! result again and the chain grows, so `!chain` can show how
+//! `cargo test` became `cargo test parser -- --nocapture`.
+//!
+//! `!chain <id>` walks history rows back from `id`. A bare `!chain` in the
+//! window walks the focused block's chain; elsewhere the latest row's.
+
+/// Runs `!chain` follows back at most.
+pub const CHAIN_CAP: usize = 100;
+
+pub const CHAIN_USAGE: &str = "Usage: !chain [history-id]";
+
+/// One run in a chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainStep {
+    /// How the run is referred to: `#42` for a history row, `block 7`.
+    pub label: String,
+    pub command: String,
+    pub exit: Option<i32>,
+}
+
+/// The history id a `!chain` argument names, `None` for the latest.
+pub fn parse(args: &str) -> Result<Option<i64>, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(None);
+    }
+    args.trim_start_matches('#').parse::<i64>().map(Some).map_err(|_| CHAIN_USAGE.to_string())
+}
+
+/// `steps`, oldest first, as `!chain` prints them.
+pub fn lines(steps: &[ChainStep]) -> Vec<String> {
+    let Some(last) = steps.last() else {
+        return vec!["⛓ Nothing to show: no command has run yet".to_string()];
+    };
+    if steps.len() == 1 {
+        return vec![format!("⛓ {} `{}` wasn't edited from an earlier command", last.label, last.command)];
+    }
+    let width = steps.iter().map(|s| s.label.chars().count()).max().unwrap_or(0);
+    let mut lines = vec![format!("⛓ {} runs, each edited from the one before:", steps.len())];
+    for (i, step) in steps.iter().enumerate() {
+        let status = match step.exit {
+            Some(0) => "✓".to_string(),
+            Some(code) => format!("✗{}", code),
+            None => "?".to_string(),
+        };
+        let arrow = if i == 0 { "" } else { "↳ " };
+        lines.push(format!("  {:<width$}  {:<4} {}{}", step.label, status, arrow, step.command, width = width));
+    }
+    lines
+}
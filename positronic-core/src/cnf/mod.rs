@@ -0,0 +1,341 @@
+//! Command-not-found hints: which package provides a missing command.
+//!
+//! When a command fails and its output says the shell could not find it,
+//! `missing_command` names it and the advisor looks it up in a table of
+//! common commands (`packages.txt`, extended with `cnf.package.<command>`
+//! settings) for the package managers on this machine, e.g.
+//! "💡 'rg' is provided by 'ripgrep' — install with: sudo apt install ripgrep".
+//!
+//! Which managers are installed is checked once per session. Each missing
+//! command is hinted at most once per session, found or not. With
+//! `cnf.use_neural` on, the local AI is asked about commands the table
+//! doesn't know; its answer is marked as a guess.
+
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// Prefix of settings adding or correcting table entries: the value is
+/// `<package> [manager=package ...]`, like a line of `packages.txt`.
+pub const PACKAGE_KEY_PREFIX: &str = "cnf.package.";
+
+/// Ask the local AI about commands missing from the table (default off).
+pub const USE_NEURAL_KEY: &str = "cnf.use_neural";
+
+const BUILTIN_TABLE: &str = include_str!("packages.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+    Winget,
+    Cargo,
+}
+
+impl PackageManager {
+    pub const ALL: [PackageManager; 6] = [
+        PackageManager::Apt,
+        PackageManager::Dnf,
+        PackageManager::Pacman,
+        PackageManager::Brew,
+        PackageManager::Winget,
+        PackageManager::Cargo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+            PackageManager::Brew => "brew",
+            PackageManager::Winget => "winget",
+            PackageManager::Cargo => "cargo",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PackageManager> {
+        Self::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// The executable whose presence means the manager is installed.
+    pub fn executable(self) -> &'static str {
+        self.name()
+    }
+
+    /// Whether the table's default package name applies to this manager.
+    fn uses_default(self) -> bool {
+        !matches!(self, PackageManager::Winget | PackageManager::Cargo)
+    }
+
+    /// The command that installs `package`.
+    pub fn install_command(self, package: &str) -> String {
+        match self {
+            PackageManager::Apt => format!("sudo apt install {}", package),
+            PackageManager::Dnf => format!("sudo dnf install {}", package),
+            PackageManager::Pacman => format!("sudo pacman -S {}", package),
+            PackageManager::Brew => format!("brew install {}", package),
+            PackageManager::Winget => format!("winget install {}", package),
+            PackageManager::Cargo => format!("cargo install {}", package),
+        }
+    }
+
+    /// Managers worth checking on `os` (`std::env::consts::OS`), most
+    /// preferred first. `cargo install` is the last resort everywhere.
+    pub fn preference(os: &str) -> &'static [PackageManager] {
+        use PackageManager::*;
+        match os {
+            "linux" => &[Apt, Dnf, Pacman, Brew, Cargo],
+            "macos" => &[Brew, Cargo],
+            "windows" => &[Winget, Cargo],
+            _ => &[Brew, Cargo],
+        }
+    }
+}
+
+/// The managers on `os` for which `on_path` finds the executable, in
+/// order of preference.
+pub fn available_managers(os: &str, on_path: impl Fn(&str) -> bool) -> Vec<PackageManager> {
+    PackageManager::preference(os).iter().copied().filter(|m| on_path(m.executable())).collect()
+}
+
+/// Whether an executable called `name` is on PATH (with PATHEXT on Windows).
+pub fn on_path(name: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    std::env::split_paths(&path).any(|dir| extensions.iter().any(|ext| dir.join(format!("{}{}", name, ext)).is_file()))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Package table
+// ════════════════════════════════════════════════════════════════════
+
+/// Where one command comes from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageEntry {
+    /// The apt, dnf, pacman and brew package unless overridden.
+    pub default: Option<String>,
+    /// Per-manager names; `None` means that manager has no package.
+    pub overrides: Vec<(PackageManager, Option<String>)>,
+}
+
+impl PackageEntry {
+    /// Parse `<package> [manager=package ...]`; `manager=-` rules a
+    /// manager out. The error is for the user.
+    pub fn parse(text: &str) -> Result<PackageEntry, String> {
+        let mut entry = PackageEntry::default();
+        for (i, word) in text.split_whitespace().enumerate() {
+            match word.split_once('=') {
+                Some((manager, package)) => {
+                    let manager = PackageManager::from_name(manager).ok_or_else(|| {
+                        let names: Vec<&str> = PackageManager::ALL.iter().map(|m| m.name()).collect();
+                        format!("❌ Unknown package manager '{}' ({})", manager, names.join(", "))
+                    })?;
+                    let package = match package {
+                        "" => return Err(format!("❌ No package after '{}='", manager.name())),
+                        "-" => None,
+                        name => Some(name.to_string()),
+                    };
+                    entry.overrides.retain(|(m, _)| *m != manager);
+                    entry.overrides.push((manager, package));
+                }
+                None if i == 0 => entry.default = Some(word.to_string()),
+                None => return Err(format!("❌ Expected manager=package, got '{}'", word)),
+            }
+        }
+        if entry.default.is_none() && entry.overrides.iter().all(|(_, p)| p.is_none()) {
+            return Err("❌ No package given".to_string());
+        }
+        Ok(entry)
+    }
+
+    /// The package `manager` installs, if it has one.
+    pub fn package(&self, manager: PackageManager) -> Option<&str> {
+        match self.overrides.iter().find(|(m, _)| *m == manager) {
+            Some((_, package)) => package.as_deref(),
+            None if manager.uses_default() => self.default.as_deref(),
+            None => None,
+        }
+    }
+}
+
+/// Command name → package entry.
+#[derive(Debug, Clone, Default)]
+pub struct PackageTable {
+    entries: HashMap<String, PackageEntry>,
+}
+
+impl PackageTable {
+    /// Parse `packages.txt` lines: `command <entry>`, `#` comments. The
+    /// error names the first bad line.
+    pub fn parse(text: &str) -> Result<PackageTable, String> {
+        let mut entries = HashMap::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let entry = PackageEntry::parse(rest).map_err(|e| format!("line {}: {}", n + 1, e))?;
+            entries.insert(command.to_string(), entry);
+        }
+        Ok(PackageTable { entries })
+    }
+
+    /// The table shipped with Positronic.
+    pub fn builtin() -> &'static PackageTable {
+        static TABLE: OnceLock<PackageTable> = OnceLock::new();
+        TABLE.get_or_init(|| PackageTable::parse(BUILTIN_TABLE).unwrap_or_default())
+    }
+
+    pub fn get(&self, command: &str) -> Option<&PackageEntry> {
+        self.entries.get(command)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The first of `available` that packages `entry`, and its package name.
+pub fn choose(entry: &PackageEntry, available: &[PackageManager]) -> Option<(PackageManager, String)> {
+    available.iter().find_map(|&m| entry.package(m).map(|p| (m, p.to_string())))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Detection and hints
+// ════════════════════════════════════════════════════════════════════
+
+fn not_found_patterns() -> &'static [Regex] {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            // bash: `bash: rg: command not found`, `bash: line 1: rg: command not found`
+            r"^(?:\S+: )?(?:line \d+: )?(\S+): command not found$",
+            // zsh
+            r"command not found: (\S+)$",
+            // dash and sh: `sh: 1: rg: not found`
+            r"^\S+: \d+: (\S+): not found$",
+            // fish: `fish: Unknown command: rg`
+            r"Unknown command:? '?([^'\s]+)'?",
+            // PowerShell
+            r"The term '([^']+)' is not recognized",
+            // cmd.exe
+            r"^'([^']+)' is not recognized as an internal or external command",
+            // Ubuntu's command-not-found handler
+            r"^Command '([^']+)' not found",
+        ]
+        .iter()
+        .filter_map(|p| Regex::new(p).ok())
+        .collect()
+    })
+}
+
+/// The command a shell said it could not find, from the last such line
+/// in `output`. Paths (`./build.sh`) are not packages and are skipped.
+pub fn missing_command(output: &str) -> Option<String> {
+    output.lines().rev().find_map(|line| {
+        let line = line.trim();
+        let name = not_found_patterns().iter().find_map(|re| re.captures(line)?.get(1))?.as_str();
+        (!name.is_empty() && !name.contains(['/', '\\'])).then(|| name.to_string())
+    })
+}
+
+/// The answer to "which package provides this command".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageHint {
+    pub command: String,
+    pub package: String,
+    pub manager: PackageManager,
+    /// From the AI rather than the table.
+    pub guessed: bool,
+}
+
+impl fmt::Display for PackageHint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.guessed { "may be provided by" } else { "is provided by" };
+        write!(
+            f,
+            "💡 '{}' {} '{}'{} — install with: {}",
+            self.command,
+            verb,
+            self.package,
+            if self.guessed { " (AI guess)" } else { "" },
+            self.manager.install_command(&self.package)
+        )
+    }
+}
+
+/// A package name from the AI's answer: the first line, one word.
+pub fn parse_guess(answer: &str) -> Option<String> {
+    let line = answer.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let word = line.trim_matches(|c: char| matches!(c, '`' | '"' | '\'' | '.' | ' '));
+    let valid = !word.is_empty()
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '+' | '-' | '@' | '/'));
+    let unknown = ["unknown", "none", "n/a"].contains(&word.to_lowercase().as_str());
+    (valid && !unknown).then(|| word.to_string())
+}
+
+/// Session state: the detected managers and which commands were hinted.
+#[derive(Debug)]
+pub struct CnfAdvisor {
+    os: &'static str,
+    managers: OnceLock<Vec<PackageManager>>,
+    hinted: Mutex<HashSet<String>>,
+}
+
+impl Default for CnfAdvisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CnfAdvisor {
+    pub fn new() -> Self {
+        Self { os: std::env::consts::OS, managers: OnceLock::new(), hinted: Mutex::new(HashSet::new()) }
+    }
+
+    /// An advisor for `os` that has already found `managers`.
+    pub fn with_managers(os: &'static str, managers: Vec<PackageManager>) -> Self {
+        let advisor = Self { os, ..Self::new() };
+        let _ = advisor.managers.set(managers);
+        advisor
+    }
+
+    /// Installed managers, most preferred first; PATH is searched on the
+    /// first call only.
+    pub fn managers(&self) -> &[PackageManager] {
+        self.managers.get_or_init(|| available_managers(self.os, on_path))
+    }
+
+    /// True the first time `command` is seen this session.
+    pub fn first_time(&self, command: &str) -> bool {
+        self.hinted.lock().unwrap_or_else(|p| p.into_inner()).insert(command.to_string())
+    }
+
+    /// The table's hint for `command`. `custom` is its `cnf.package.*`
+    /// setting, which wins over the built-in entry; a malformed one is
+    /// ignored.
+    pub fn lookup(&self, command: &str, custom: Option<&str>) -> Option<PackageHint> {
+        let custom = custom.and_then(|text| PackageEntry::parse(text).ok());
+        let entry = custom.as_ref().or_else(|| PackageTable::builtin().get(command))?;
+        let (manager, package) = choose(entry, self.managers())?;
+        Some(PackageHint { command: command.to_string(), package, manager, guessed: false })
+    }
+}
@@ -0,0 +1,354 @@
+//! Configuration commands: settings (`!set`, `!get`), aliases, hooks,
+//! environment overrides, safe mode and shell integration.
+
+use anyhow::Result;
+
+use crate::alias;
+use crate::error::PositronicError;
+use crate::hooks::{self, HookCommand};
+use crate::integrate::{self, IntegrateCommand, ProfileEnv};
+use crate::native::{Cell, ColumnKind, DataFrame, NativeOutput};
+use crate::runner::{ExecuteResult, Runner};
+use crate::safe_mode::{self, DisabledKind, SafeCommand, Skipped};
+use crate::setenv::{self, SetenvCommand};
+use crate::vault::crypto;
+
+use super::{after_words, CommandSpec, Context};
+
+pub(super) static INTEGRATE: CommandSpec = CommandSpec {
+    name: "integrate",
+    help: &[
+        "  !integrate [bash|zsh|fish|pwsh] [--dry-run|--yes]  Add prompt hooks to a shell profile",
+        "  !integrate status | remove <shell> [--yes]  Where the hooks are installed; take them out",
+    ],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn integrate(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match IntegrateCommand::parse(ctx.rest(1)) {
+        Ok(command) => {
+            let seen = ctx.runner.running.lock().unwrap_or_else(|e| e.into_inner()).is_integrated();
+            Ok(ExecuteResult::DirectOutput(integrate::command_lines(command, seen, &ProfileEnv::current())))
+        }
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static ALIAS: CommandSpec = CommandSpec {
+    name: "alias",
+    help: &[
+        "  !alias             List all aliases",
+        "  !alias [set] <n> <expansion>  Create/update alias; {1} {2}… {*} {@} take arguments",
+        "  !alias show <n>    Show an alias and its placeholders",
+    ],
+    subcommands: &["set", "show", "rm", "list"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn alias(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    if matches!(ctx.args(), [] | ["list"]) {
+        return Ok(alias_list_output(ctx.runner).into());
+    }
+    Ok(ExecuteResult::DirectOutput(alias_lines(ctx.runner, ctx.line)))
+}
+
+pub(super) static UNALIAS: CommandSpec = CommandSpec {
+    name: "unalias",
+    help: &["  !unalias <n>       Remove an alias"],
+    min_args: 1,
+    usage: "Usage: !unalias <name>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn unalias(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let name = ctx.parts[1];
+    match ctx.runner.vault.remove_alias(name) {
+        Ok(true) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("✓ Removed alias: {}", name)
+        ])),
+        Ok(false) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("No alias named '{}'", name)
+        ])),
+        Err(e) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("❌ Error: {}", e)
+        ])),
+    }
+}
+
+pub(super) static HOOK: CommandSpec = CommandSpec {
+    name: "hook",
+    help: &[
+        "  !hook add --match <regex> [--env VAR=val]… [--before <cmd>] [--after <cmd>]",
+        "                     Set env or run commands around matching commands; {exit} in --after",
+        "  !hook list | rm <id>  Show hooks in the order they apply; remove one",
+    ],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn hook(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match HookCommand::parse(ctx.rest(1)) {
+        Ok(command) => Ok(ExecuteResult::DirectOutput(hook_lines(ctx.runner, command))),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static SAFE: CommandSpec = CommandSpec {
+    name: "safe",
+    help: &[
+        "  !safe [list]       What safe mode skipped, and what is disabled",
+        "  !safe disable|enable <alias|hook|theme> <name>  Turn one off for normal starts too, or back on",
+    ],
+    subcommands: &["list", "disable", "enable", "alias", "hook", "theme"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn safe(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match SafeCommand::parse(ctx.rest(1)) {
+        Ok(command) => Ok(safe_result(ctx.runner, command)),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static SETENV: CommandSpec = CommandSpec {
+    name: "setenv",
+    help: &[
+        "  !setenv <NAME> <value> [--global]  Set a variable for every command, this session or all",
+        "  !setenv list | rm <NAME> | clear  Show (secrets masked), remove one, remove all",
+        "                     A hook's --env wins over !setenv; session wins over --global",
+    ],
+    subcommands: &["list", "rm", "clear", "--global"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn setenv(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match SetenvCommand::parse(ctx.rest(1)) {
+        Ok(command) => Ok(setenv_result(ctx.runner, command)),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static SET: CommandSpec = CommandSpec {
+    name: "set",
+    help: &["  !set <key> <value> Change a setting (e.g. neural.verbose true)"],
+    min_args: 1,
+    usage: "Usage: !set <key> <value>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn set(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let (runner, parts) = (ctx.runner, &ctx.parts);
+    if parts[1] == crypto::ENCRYPT_KEY || parts[1] == crypto::KDF_KEY {
+        return Ok(ExecuteResult::DirectOutput(vec![
+            format!("🔐 {} is managed by !vault encrypt and !vault decrypt", parts[1]),
+        ]));
+    }
+    if parts.len() < 3 {
+        return Ok(ExecuteResult::DirectOutput(vec![SET.usage.to_string()]));
+    }
+    let (key, value) = (parts[1], parts[2..].join(" "));
+    match runner.vault.set_config(key, &value) {
+        Ok(_) => {
+            let mut lines = vec![format!("✓ {} = {}", key, value)];
+            if runner.vault.is_overridden(key).unwrap_or(false) {
+                let profile = runner.vault.active_profile().ok().flatten().unwrap_or_default();
+                lines.push(format!(
+                    "  ⚠️ Profile '{}' overrides {}; this applies once it is no longer active",
+                    profile, key
+                ));
+            }
+            Ok(ExecuteResult::ConfigChanged(lines))
+        }
+        // The frontend points at !get/!set for the key.
+        Err(e) => Err(PositronicError::config(key, e).into()),
+    }
+}
+
+pub(super) static GET: CommandSpec = CommandSpec {
+    name: "get",
+    help: &["  !get <key>         Show a setting"],
+    min_args: 1,
+    usage: "Usage: !get <key>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn get(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let (runner, key) = (ctx.runner, ctx.parts[1]);
+    match runner.vault.get_config(key) {
+        Ok(Some(value)) => {
+            let source = match runner.vault.is_overridden(key) {
+                Ok(true) => format!(
+                    "   (profile '{}')",
+                    runner.vault.active_profile().ok().flatten().unwrap_or_default()
+                ),
+                _ => String::new(),
+            };
+            Ok(ExecuteResult::DirectOutput(vec![
+                format!("{} = {}{}", key, value, source)
+            ]))
+        }
+        Ok(None) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("{} is not set", key)
+        ])),
+        Err(e) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("❌ Error: {}", e)
+        ])),
+    }
+}
+
+fn hook_lines(runner: &Runner, command: HookCommand) -> Vec<String> {
+    match command {
+        HookCommand::Add(spec) => match runner.vault.add_hook(&spec) {
+            Ok(id) => vec![format!("🪝 Hook #{} added for /{}/", id, spec.pattern)],
+            Err(e) => vec![format!("❌ Error saving hook: {}", e)],
+        },
+        HookCommand::List => match runner.vault.list_hooks() {
+            Ok(rules) => hooks::list_lines(&rules),
+            Err(e) => vec![format!("❌ Error listing hooks: {}", e)],
+        },
+        HookCommand::Remove(id) => match runner.vault.remove_hook(id) {
+            Ok(true) => vec![format!("✓ Removed hook #{}", id)],
+            Ok(false) => vec![format!("No hook #{}", id)],
+            Err(e) => vec![format!("❌ Error: {}", e)],
+        },
+    }
+}
+
+/// `!setenv`. Changes come back as `ConfigChanged` so the status bar
+/// badge is brought up to date.
+fn setenv_result(runner: &Runner, command: SetenvCommand) -> ExecuteResult {
+    let changed = |line: String| ExecuteResult::ConfigChanged(vec![line]);
+    let failed = |e: rusqlite::Error| ExecuteResult::DirectOutput(vec![format!("❌ Error: {}", e)]);
+    match command {
+        SetenvCommand::Set { name, value, scope } => match runner.vault.set_env(&name, &value, scope) {
+            Ok(()) => changed(format!("🔧 {}={} ({})", name, setenv::shown_value(&name, &value), scope.label())),
+            Err(e) => failed(e),
+        },
+        SetenvCommand::List => match runner.vault.list_env() {
+            Ok(overrides) => ExecuteResult::DirectOutput(setenv::list_lines(&overrides)),
+            Err(e) => failed(e),
+        },
+        SetenvCommand::Remove(name) => match runner.vault.remove_env(&name) {
+            Ok(0) => ExecuteResult::DirectOutput(vec![format!("{} is not overridden", name)]),
+            Ok(_) => changed(format!("✓ Removed {}", name)),
+            Err(e) => failed(e),
+        },
+        SetenvCommand::Clear => match runner.vault.clear_env() {
+            Ok(n) => changed(format!("✓ Cleared {} environment override{}", n, if n == 1 { "" } else { "s" })),
+            Err(e) => failed(e),
+        },
+    }
+}
+
+/// `!safe`. Turning a theme off or on comes back as `ConfigChanged`, so
+/// the UI applies it now.
+fn safe_result(runner: &Runner, command: SafeCommand) -> ExecuteResult {
+    let vault = &runner.vault;
+    let theme = matches!(command, SafeCommand::Disable(DisabledKind::Theme, _) | SafeCommand::Enable(DisabledKind::Theme, _));
+    let lines = match command {
+        SafeCommand::List => {
+            let skipped = Skipped {
+                aliases: vault.list_aliases().map_or(0, |a| a.len()),
+                hooks: vault.list_hooks().map_or(0, |h| h.len()),
+                env: vault.list_env().map_or(0, |e| e.len()),
+            };
+            match vault.disabled_items() {
+                Ok(disabled) => safe_mode::list_lines(runner.safe_mode(), skipped, &disabled),
+                Err(e) => vec![format!("❌ Error reading disabled items: {}", e)],
+            }
+        }
+        SafeCommand::Disable(kind, name) => {
+            let exists = match kind {
+                DisabledKind::Alias => vault.get_alias(&name).map(|a| a.is_some()),
+                DisabledKind::Hook => vault.list_hooks().map(|rules| rules.iter().any(|r| r.id.to_string() == name)),
+                // Themes are the UI's; any name can be turned off.
+                DisabledKind::Theme => Ok(true),
+            };
+            match exists {
+                Ok(false) => vec![format!("❌ No {} '{}'", kind.label(), name)],
+                Err(e) => vec![format!("❌ Error: {}", e)],
+                Ok(true) => match vault.disable_item(kind, &name) {
+                    Ok(true) => vec![
+                        format!("🚫 Disabled {} {}; normal starts skip it, the {} itself is kept", kind.label(), name, kind.label()),
+                        format!("   !safe enable {} {} turns it back on", kind.label(), name),
+                    ],
+                    Ok(false) => vec![format!("{} {} is already disabled", kind.label(), name)],
+                    Err(e) => vec![format!("❌ Error: {}", e)],
+                },
+            }
+        }
+        SafeCommand::Enable(kind, name) => match vault.enable_item(kind, &name) {
+            Ok(true) => vec![format!("✓ Enabled {} {}", kind.label(), name)],
+            Ok(false) => vec![format!("{} {} isn't disabled", kind.label(), name)],
+            Err(e) => vec![format!("❌ Error: {}", e)],
+        },
+    };
+    if theme {
+        ExecuteResult::ConfigChanged(lines)
+    } else {
+        ExecuteResult::DirectOutput(lines)
+    }
+}
+
+/// `!alias` / `!alias list`.
+fn alias_list_output(runner: &Runner) -> NativeOutput {
+    let aliases = match runner.vault.list_aliases() {
+        Ok(aliases) => aliases,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Error: {}", e)]),
+    };
+    if aliases.is_empty() {
+        return NativeOutput::Lines(vec![
+            "No aliases defined.".to_string(),
+            "".to_string(),
+            ALIAS_USAGE.to_string(),
+            alias::PLACEHOLDER_HELP.to_string(),
+        ]);
+    }
+
+    let mut frame = DataFrame::new(&[
+        ("name", ColumnKind::Text),
+        ("expansion", ColumnKind::Text),
+        ("created_at", ColumnKind::Integer),
+    ]);
+    let mut lines = vec!["📝 Aliases:".to_string(), "".to_string()];
+    for a in aliases {
+        lines.push(format!("  {} → {}", a.name, a.expansion));
+        frame.push_row(vec![Cell::text(a.name), Cell::text(a.expansion), Cell::Integer(a.created_at)]);
+    }
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+const ALIAS_USAGE: &str = "Usage: !alias [set] <name> <expansion> | !alias show <name> | !alias rm <name>";
+
+/// `!alias`: list, show, set or remove aliases (see `alias`).
+fn alias_lines(runner: &Runner, cmd: &str) -> Vec<String> {
+    let parts: Vec<&str> = cmd.split_whitespace().collect();
+    match parts[1..] {
+        ["show", name] => match runner.vault.get_alias(name) {
+            Ok(Some(expansion)) => alias::show(name, &expansion),
+            Ok(None) => vec![format!("No alias named '{}'", name)],
+            Err(e) => vec![format!("❌ Error: {}", e)],
+        },
+        ["rm", name] => match runner.vault.remove_alias(name) {
+            Ok(true) => vec![format!("✓ Removed alias: {}", name)],
+            Ok(false) => vec![format!("No alias named '{}'", name)],
+            Err(e) => vec![format!("❌ Error: {}", e)],
+        },
+        ["set", name, _, ..] => set_alias(runner, name, after_words(cmd, 3)),
+        [name, _, ..] if !matches!(name, "set" | "show" | "rm" | "list") => set_alias(runner, name, after_words(cmd, 2)),
+        _ => vec![ALIAS_USAGE.to_string(), alias::PLACEHOLDER_HELP.to_string()],
+    }
+}
+
+fn set_alias(runner: &Runner, name: &str, expansion: &str) -> Vec<String> {
+    if let Err(message) = alias::validate(expansion) {
+        return vec![message];
+    }
+    match runner.vault.set_alias(name, expansion) {
+        Ok(()) if alias::has_placeholders(expansion) => vec![
+            format!("✓ Alias set: {} → {}", name, expansion),
+            format!("  Usage: {}", alias::usage(name, expansion)),
+        ],
+        Ok(()) => vec![format!("✓ Alias set: {} → {}", name, expansion)],
+        Err(e) => vec![format!("❌ Error: {}", e)],
+    }
+}
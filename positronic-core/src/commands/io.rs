@@ -0,0 +1,313 @@
+//! Commands that reach outside the machine: serial devices (`!io`) and
+//! the network (`!http`, `!fetch`).
+
+use positronic_io::device;
+use positronic_io::{OverflowPolicy, SerialConfig};
+
+use crate::cancel::CancellationToken;
+use crate::fetch::{self, FetchCommand, FetchOptions};
+use crate::history_filter::parse_flag;
+use crate::http::{self, HttpCommand, HttpOptions};
+use crate::runner::{ExecuteResult, Runner};
+use crate::serial::{self, IoConnect};
+
+use super::{CommandSpec, Context, Execution};
+
+pub(super) static HTTP: CommandSpec = CommandSpec {
+    name: "http",
+    help: &[
+        "  !http <method> <url> [Name:value…] [--body @file|<text>]  Send a request; JSON/CSV open in the Holodeck",
+        "  !http              Put the last request back in the input line to edit and resend",
+    ],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn http<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match HttpCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(http_result(ctx.runner, command, &ctx.cancel).await),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static FETCH: CommandSpec = CommandSpec {
+    name: "fetch",
+    help: &["  !fetch <url> [--as json|csv|md|auto]  Download into the Holodeck as a table, JSON tree or markdown"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn fetch<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match FetchCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(fetch_result(ctx.runner, command, &ctx.cancel).await),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static IO: CommandSpec = CommandSpec {
+    name: "io",
+    help: &[
+        "  !io scan [--probe] List serial ports; --probe checks each can be opened",
+        "  !io list           Named devices and the ports they are on now",
+        "  !io name <port|name> <name>  Name the USB device on a port; use the name for its port",
+        "  !io connect <port|name> [baud|--auto-baud] [--parser kv|nmea|json --map <src>=<ch>,...] [--reconnect on|off]",
+        "                     Stream a device; its baud, parser and reconnect are remembered",
+        "                     --auto-baud listens at common rates and keeps the one that reads as text",
+        "  !io console <port|name> [baud] [--eol cr|lf|crlf] [--echo]  Type to a device; Ctrl+] returns (handled by UI)",
+    ],
+    subcommands: &["scan", "list", "name", "connect", "console"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn io<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(ExecuteResult::DirectOutput(io_lines(ctx.runner, ctx.args()).await)) })
+}
+
+/// `!io`: scan for serial ports, or connect one with its parser.
+async fn io_lines(runner: &Runner, args: &[&str]) -> Vec<String> {
+    let io = match runner.io() {
+        Ok(io) => io,
+        Err(e) => return vec![format!("❌ Hardware IO unavailable: {}", e)],
+    };
+    match args {
+        ["scan"] => match io.scan_ports().await {
+            Ok(()) => vec!["🔌 Scanning serial ports… (!io scan --probe also checks access)".to_string()],
+            Err(e) => vec![format!("❌ Scan failed: {}", e)],
+        },
+        ["scan", "--probe"] => match io.scan_and_probe().await {
+            Ok(()) => vec![
+                "🔌 Scanning serial ports and opening each one to check access…".to_string(),
+                "   Opening a port toggles DTR: boards such as Arduinos reset when probed.".to_string(),
+            ],
+            Err(e) => vec![format!("❌ Scan failed: {}", e)],
+        },
+        ["list"] => device_list_lines(runner),
+        ["name", target, name] => {
+            let lines = name_device_lines(runner, target, name);
+            // The panel shows names; tell it about the new one.
+            let _ = io.identify_ports().await;
+            lines
+        }
+        ["connect", rest @ ..] => {
+            let request = match IoConnect::parse(rest) {
+                Ok(request) => request,
+                Err(message) => return vec![message],
+            };
+            let devices = runner.vault.list_devices().unwrap_or_default();
+            let resolved = match serial::resolve_port(&devices, &device::system_ports(), &request.port) {
+                Ok(resolved) => resolved,
+                Err(message) => return vec![message],
+            };
+            if let Some(id) = &resolved.device {
+                let id = id.to_string();
+                let product = resolved.record.as_ref().and_then(|r| r.product.as_deref());
+                let saved = runner
+                    .vault
+                    .record_device(&id, &resolved.port, product)
+                    .and_then(|_| runner.vault.set_device_defaults(&id, &request.given()));
+                if let Err(e) = saved {
+                    return vec![format!("❌ Saving the device settings failed: {}", e)];
+                }
+            }
+            let saved = resolved.record.as_ref().map(|r| r.defaults.clone()).unwrap_or_default();
+            let from_device = request.parser.is_none() && saved.parser.is_some();
+            let request = IoConnect { port: resolved.port.clone(), ..request }.with_defaults(&saved);
+            let usb_id = resolved.device.as_ref().map(|d| d.usb_id());
+            let key = serial::parser_key(&request.port, usb_id.as_deref());
+            let (parser, restored) = match serial::resolve_parser(&runner.vault, &key, request.parser) {
+                Ok(resolved) => resolved,
+                Err(e) => return vec![format!("❌ Saving the parser failed: {}", e)],
+            };
+            let baud = request.baud.unwrap_or(serial::DEFAULT_BAUD);
+            let auto = request.auto_baud.then(|| {
+                let config = |key| runner.vault.get_config(key).ok().flatten();
+                serial::auto_baud_from_config(
+                    config(serial::AUTO_BAUD_RATES_KEY).as_deref(),
+                    config(serial::AUTO_BAUD_DWELL_KEY).as_deref(),
+                    config(serial::AUTO_BAUD_THRESHOLD_KEY).as_deref(),
+                )
+            });
+            let rate = match &auto {
+                Some(auto) => format!(
+                    "finding its baud rate ({}, {}ms each)",
+                    auto.candidates.iter().map(u32::to_string).collect::<Vec<_>>().join(", "),
+                    auto.dwell.as_millis()
+                ),
+                None => format!("at {} baud", baud),
+            };
+            let mut lines = vec![format!("🔌 Connecting {} {}, parser: {}", resolved.label(), rate, parser)];
+            if restored || from_device {
+                let source = resolved.name().map(str::to_string).or(usb_id).unwrap_or_else(|| resolved.port.clone());
+                lines[0].push_str(&format!(" (restored for {})", source));
+            }
+            lines.extend(resolved.ambiguity());
+            let reconnect = match (request.reconnect.unwrap_or(false), &resolved.device) {
+                (true, Some(id)) => {
+                    lines.push(format!(
+                        "   Reopens it on any port if it drops and comes back within {}s",
+                        positronic_io::RECONNECT_WINDOW.as_secs()
+                    ));
+                    Some(id.clone())
+                }
+                (true, None) => {
+                    lines.push(format!("⚠️ {} isn't a USB device; it won't be reopened if it drops", resolved.port));
+                    None
+                }
+                (false, _) => None,
+            };
+            let config = SerialConfig {
+                port_name: request.port,
+                baud_rate: baud,
+                data_bits: 8,
+                flow_control: false,
+                overflow: OverflowPolicy::default(),
+                parser,
+                reconnect,
+            };
+            let connected = match auto {
+                Some(auto) => io.connect_auto(config, auto).await,
+                None => io.connect_with(config).await,
+            };
+            match connected {
+                Ok(()) => lines,
+                Err(e) => vec![format!("❌ Connect failed: {}", e)],
+            }
+        }
+        ["console", ..] => vec!["🔌 !io console needs the Positronic window".to_string()],
+        _ => vec![serial::io_usage()],
+    }
+}
+
+/// `!io list`: recorded devices, named ones first, and where they are now.
+fn device_list_lines(runner: &Runner) -> Vec<String> {
+    let devices = match runner.vault.list_devices() {
+        Ok(devices) => devices,
+        Err(e) => return vec![format!("❌ Reading devices failed: {}", e)],
+    };
+    if devices.is_empty() {
+        return vec!["🏷️ No USB devices seen yet; !io scan finds them".to_string()];
+    }
+    let ports = device::system_ports();
+    let mut lines = vec!["🏷️ Devices".to_string()];
+    for record in &devices {
+        let now = match record.id.parse() {
+            Ok(id) => device::ports_of(&ports, &id).join(", "),
+            Err(_) => String::new(),
+        };
+        let now = if now.is_empty() {
+            format!("not plugged in (last on {})", record.last_port)
+        } else {
+            format!("→ {}", now)
+        };
+        lines.push(format!("  {:<16} {:<28} {}", record.name.as_deref().unwrap_or("(unnamed)"), record.id, now));
+        let details: Vec<String> = [
+            record.product.clone(),
+            record.defaults.baud.map(|baud| format!("{} baud", baud)),
+            record
+                .defaults
+                .parser
+                .as_deref()
+                .and_then(|json| serde_json::from_str::<positronic_io::ParserConfig>(json).ok())
+                .map(|p| format!("parser {}", p)),
+            record.defaults.eol.as_ref().map(|eol| format!("eol {}", eol)),
+            record.defaults.reconnect.filter(|on| *on).map(|_| "reconnect".to_string()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !details.is_empty() {
+            lines.push(format!("  {:<16} {}", "", details.join(", ")));
+        }
+    }
+    lines
+}
+
+/// `!io name <port|name> <name>`: name the USB device `target` is.
+fn name_device_lines(runner: &Runner, target: &str, name: &str) -> Vec<String> {
+    if let Err(message) = serial::validate_device_name(name) {
+        return vec![message];
+    }
+    let ports = device::system_ports();
+    if ports.iter().any(|p| p.port == name) {
+        return vec![format!("❌ '{}' is a port; pick a name that isn't", name)];
+    }
+    let devices = match runner.vault.list_devices() {
+        Ok(devices) => devices,
+        Err(e) => return vec![format!("❌ Reading devices failed: {}", e)],
+    };
+    let resolved = match serial::resolve_port(&devices, &ports, target) {
+        Ok(resolved) => resolved,
+        Err(message) => return vec![message],
+    };
+    let Some(id) = resolved.device.as_ref().map(|d| d.to_string()) else {
+        return vec![format!("❌ {} has no USB identity to name; only USB devices can be", resolved.port)];
+    };
+    if let Some(other) = devices.iter().find(|d| d.name.as_deref() == Some(name) && d.id != id) {
+        return vec![format!("❌ {} already names {}; rename that one first", name, other.id)];
+    }
+    let product = device::identify(&ports, &resolved.port).and_then(|p| p.product.as_deref());
+    let named = runner
+        .vault
+        .record_device(&id, &resolved.port, product)
+        .and_then(|_| runner.vault.name_device(&id, Some(name)));
+    match named {
+        Ok(_) => {
+            let mut lines = vec![format!(
+                "🏷️ {} ({}) is {} now; !io connect {} finds it on any port",
+                resolved.port, id, name, name
+            )];
+            lines.extend(resolved.ambiguity());
+            lines
+        }
+        Err(e) => vec![format!("❌ Naming the device failed: {}", e)],
+    }
+}
+
+/// Send a `!http` request, or bring the last one back for editing.
+async fn http_result(runner: &Runner, command: HttpCommand, cancel: &CancellationToken) -> ExecuteResult {
+    let request = match command {
+        HttpCommand::Send(request) => request,
+        HttpCommand::Replay => {
+            return match runner.last_http.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                Some(request) => ExecuteResult::EditCommand(request.command_line()),
+                None => ExecuteResult::DirectOutput(vec![http::HTTP_USAGE.to_string()]),
+            };
+        }
+    };
+    *runner.last_http.lock().unwrap_or_else(|e| e.into_inner()) = Some(request.clone());
+    let options = HttpOptions {
+        timeout: http::timeout_from_config(runner.vault.get_config(http::TIMEOUT_KEY).ok().flatten().as_deref()),
+        cwd: runner.cwd().map(std::path::PathBuf::from).unwrap_or_else(|| HttpOptions::default().cwd),
+        cancel: cancel.clone(),
+        ..HttpOptions::default()
+    };
+    match http::send(&request, &options).await {
+        Ok(exchange) => ExecuteResult::Http(Box::new(exchange)),
+        Err(message) => ExecuteResult::DirectOutput(vec![message]),
+    }
+}
+
+/// Download a `!fetch` URL. What was fetched is logged with the command
+/// only when `fetch.log` is on.
+async fn fetch_result(runner: &Runner, command: FetchCommand, cancel: &CancellationToken) -> ExecuteResult {
+    let config = |key: &str| runner.vault.get_config(key).ok().flatten();
+    let options = FetchOptions {
+        timeout: http::timeout_from_config(config(http::TIMEOUT_KEY).as_deref()),
+        max_size: fetch::max_size_from_config(config(fetch::MAX_SIZE_KEY).as_deref()),
+        cancel: cancel.clone(),
+    };
+    let fetched = match fetch::fetch(&command, &options).await {
+        Ok(fetched) => fetched,
+        Err(message) => return ExecuteResult::DirectOutput(message.lines().map(str::to_string).collect()),
+    };
+    if config(fetch::LOG_KEY).as_deref().and_then(parse_flag).unwrap_or(false) {
+        let cwd = runner.cwd().unwrap_or_else(|| ".".to_string());
+        let line = format!("!fetch {}", fetched.url);
+        if let Err(e) = runner.vault.log_run(&line, fetched.text.as_deref(), Some(0), &cwd, None, None) {
+            tracing::warn!("Logging the fetch failed: {}", e);
+        }
+    }
+    ExecuteResult::Fetch(Box::new(fetched))
+}
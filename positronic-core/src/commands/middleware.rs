@@ -0,0 +1,112 @@
+//! The layers `Registry::builtin` runs every native command through,
+//! outermost first:
+//!
+//! - `RouteErrors`: a command that fails comes back as a typed
+//!   `PositronicError`, which the window's error center collects.
+//! - `Validate`: a locked vault or too few arguments answer without
+//!   running the command.
+//! - `InjectCancel`: registers the run for Esc and `!abort`, hands the
+//!   command its token and answers `✖ cancelled after …` if it fires.
+//! - `RecordTiming`: how long the command took, in the vault.
+//! - `CapOutput`: at most `MAX_OUTPUT_LINES` lines of output.
+
+use std::time::Instant;
+
+use crate::cancel;
+use crate::error::PositronicError;
+use crate::runner::ExecuteResult;
+
+use super::{Context, Execution, Middleware, Next, VAULT_LOCKED};
+
+/// Lines of output a native command may show.
+pub const MAX_OUTPUT_LINES: usize = 50_000;
+
+pub struct RouteErrors;
+
+impl Middleware for RouteErrors {
+    fn call<'a>(&'a self, ctx: Context<'a>, next: Next<'a>) -> Execution<'a> {
+        let name = next.spec().name;
+        Box::pin(async move {
+            next.run(ctx).await.map_err(|e| {
+                let error = PositronicError::from(e);
+                tracing::warn!("!{} failed: {}", name, error);
+                error.into()
+            })
+        })
+    }
+}
+
+pub struct Validate;
+
+impl Middleware for Validate {
+    fn call<'a>(&'a self, ctx: Context<'a>, next: Next<'a>) -> Execution<'a> {
+        let spec = next.spec();
+        let answer = |line: &str| -> Execution<'a> {
+            Box::pin(std::future::ready(Ok(ExecuteResult::DirectOutput(vec![line.to_string()]))))
+        };
+        if spec.reads_history && ctx.runner.vault.is_locked() {
+            return answer(VAULT_LOCKED);
+        }
+        if ctx.args().len() < spec.min_args {
+            return answer(spec.usage);
+        }
+        next.run(ctx)
+    }
+}
+
+/// `!abort` itself is never registered: it cancels the others.
+pub struct InjectCancel;
+
+impl Middleware for InjectCancel {
+    fn call<'a>(&'a self, mut ctx: Context<'a>, next: Next<'a>) -> Execution<'a> {
+        if cancel::is_abort(ctx.line) {
+            return next.run(ctx);
+        }
+        let invocation = ctx.runner.cancels.begin(ctx.line);
+        ctx.cancel = invocation.token().clone();
+        Box::pin(async move {
+            match cancel::until_done(invocation.token(), next.run(ctx)).await {
+                Some(result) => result,
+                None => Ok(ExecuteResult::DirectOutput(vec![cancel::cancelled_line(invocation.elapsed())])),
+            }
+        })
+    }
+}
+
+/// A cancelled run isn't timed: it never finished. Failing to log is
+/// only worth a debug line.
+pub struct RecordTiming;
+
+impl Middleware for RecordTiming {
+    fn call<'a>(&'a self, ctx: Context<'a>, next: Next<'a>) -> Execution<'a> {
+        let (runner, name) = (ctx.runner, next.spec().name);
+        Box::pin(async move {
+            let started = Instant::now();
+            let result = next.run(ctx).await;
+            if let Err(e) = runner.vault.log_native_timing(name, started.elapsed(), result.is_ok()) {
+                tracing::debug!("Timing !{} not recorded: {}", name, e);
+            }
+            result
+        })
+    }
+}
+
+pub struct CapOutput {
+    pub max_lines: usize,
+}
+
+impl Middleware for CapOutput {
+    fn call<'a>(&'a self, ctx: Context<'a>, next: Next<'a>) -> Execution<'a> {
+        Box::pin(async move {
+            Ok(match next.run(ctx).await? {
+                ExecuteResult::DirectOutput(mut lines) if lines.len() > self.max_lines => {
+                    let hidden = lines.len() - self.max_lines;
+                    lines.truncate(self.max_lines);
+                    lines.push(format!("… {} more lines not shown (output is capped at {} lines)", hidden, self.max_lines));
+                    ExecuteResult::DirectOutput(lines)
+                }
+                result => result,
+            })
+        })
+    }
+}
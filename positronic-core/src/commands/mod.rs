@@ -0,0 +1,452 @@
+//! Native `!` commands: a registry of handlers and the middleware that
+//! runs around each of them.
+//!
+//! Every command is a `NativeCommand` with a `CommandSpec`: its name and
+//! aliases, its `!help` lines, its sub-commands for Tab completion and the
+//! fewest arguments it takes. The runner builds `Registry::builtin()` when
+//! it is constructed; each family's handlers and specs live in its own
+//! module. Commands the window handles itself (`!theme`, `!mark`…) are
+//! registered by spec alone, so `!help` and the completer still know them.
+//!
+//! A line is looked up by its first word, then run through the middleware
+//! chain (see `middleware`): the outermost layer first, the command last.
+//! A word that isn't registered answers `❌ Unknown command` with the
+//! closest name, if one is close.
+//!
+//! `!help` is the specs' lines in registration order, with the gaps and
+//! free-standing lines (`Registry::text`) laid out between them.
+
+pub mod config;
+pub mod io;
+pub mod middleware;
+pub mod neural;
+pub mod session;
+pub mod ui;
+pub mod vault;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Result;
+use positronic_neural::reflex::levenshtein_distance;
+
+use crate::cancel::CancellationToken;
+use crate::runner::{ExecuteResult, Runner};
+
+/// What a command, or a layer around it, comes back with.
+pub type Execution<'a> = Pin<Box<dyn Future<Output = Result<ExecuteResult>> + Send + 'a>>;
+
+/// What commands that read history answer on a locked vault.
+pub const VAULT_LOCKED: &str = "🔒 vault locked — !vault unlock to enter the passphrase";
+
+/// Edits an unknown command may be from a registered name and still be
+/// suggested.
+pub const SUGGEST_DISTANCE: usize = 2;
+
+/// What the registry knows about a command.
+#[derive(Debug)]
+pub struct CommandSpec {
+    /// Without the `!`.
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Its `!help` lines, as printed.
+    pub help: &'static [&'static str],
+    /// Words Tab completes after the command.
+    pub subcommands: &'static [&'static str],
+    /// Fewer arguments than this answer `usage` without running.
+    pub min_args: usize,
+    pub usage: &'static str,
+    /// Reads history: answers `VAULT_LOCKED` on a locked vault.
+    pub reads_history: bool,
+}
+
+impl CommandSpec {
+    /// The fields a spec leaves out.
+    pub const DEFAULT: CommandSpec = CommandSpec {
+        name: "",
+        aliases: &[],
+        help: &[],
+        subcommands: &[],
+        min_args: 0,
+        usage: "",
+        reads_history: false,
+    };
+
+    /// Whether `word` (without the `!`) names this command.
+    pub fn answers_to(&self, word: &str) -> bool {
+        self.name == word || self.aliases.contains(&word)
+    }
+}
+
+/// One run of a command.
+pub struct Context<'a> {
+    pub runner: &'a Runner,
+    /// The whole line, the command as typed first.
+    pub line: &'a str,
+    /// Its words.
+    pub parts: Vec<&'a str>,
+    /// Fires when Esc or `!abort` cancels the run (`middleware::InjectCancel`).
+    /// A command that can take long must stop when it does; see `cancel`.
+    pub cancel: CancellationToken,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(runner: &'a Runner, line: &'a str) -> Self {
+        Context { runner, line, parts: line.split_whitespace().collect(), cancel: CancellationToken::new() }
+    }
+
+    /// The command as typed, `!` included.
+    pub fn command(&self) -> &'a str {
+        self.parts.first().copied().unwrap_or_default()
+    }
+
+    /// The words after the command.
+    pub fn args(&self) -> &[&'a str] {
+        self.parts.get(1..).unwrap_or_default()
+    }
+
+    /// The line after its first `n` words, spacing and quotes inside kept.
+    pub fn rest(&self, n: usize) -> &'a str {
+        after_words(self.line, n)
+    }
+}
+
+/// A native `!` command.
+pub trait NativeCommand: Send + Sync {
+    fn spec(&self) -> &'static CommandSpec;
+
+    fn execute<'a>(&'a self, ctx: &'a Context<'_>) -> Execution<'a>;
+}
+
+/// A command run by a function; how the built-in families register theirs.
+pub struct Handler {
+    spec: &'static CommandSpec,
+    run: Run,
+}
+
+enum Run {
+    Now(fn(&Context<'_>) -> Result<ExecuteResult>),
+    Awaiting(for<'a> fn(&'a Context<'_>) -> Execution<'a>),
+}
+
+impl Handler {
+    /// A command that answers without awaiting anything.
+    pub fn now(spec: &'static CommandSpec, run: fn(&Context<'_>) -> Result<ExecuteResult>) -> Self {
+        Handler { spec, run: Run::Now(run) }
+    }
+
+    /// A command that awaits: the PTY, a request, a model, the blocking pool.
+    pub fn awaiting(spec: &'static CommandSpec, run: for<'a> fn(&'a Context<'_>) -> Execution<'a>) -> Self {
+        Handler { spec, run: Run::Awaiting(run) }
+    }
+}
+
+impl NativeCommand for Handler {
+    fn spec(&self) -> &'static CommandSpec {
+        self.spec
+    }
+
+    fn execute<'a>(&'a self, ctx: &'a Context<'_>) -> Execution<'a> {
+        match self.run {
+            Run::Now(run) => Box::pin(std::future::ready(run(ctx))),
+            Run::Awaiting(run) => run(ctx),
+        }
+    }
+}
+
+/// A layer every command runs through.
+pub trait Middleware: Send + Sync {
+    /// Run `ctx` through `next`, or answer instead of it.
+    fn call<'a>(&'a self, ctx: Context<'a>, next: Next<'a>) -> Execution<'a>;
+}
+
+/// The layers still to run, then the command.
+pub struct Next<'a> {
+    command: &'a dyn NativeCommand,
+    layers: &'a [Arc<dyn Middleware>],
+}
+
+impl<'a> Next<'a> {
+    /// The spec of the command being run.
+    pub fn spec(&self) -> &'static CommandSpec {
+        self.command.spec()
+    }
+
+    pub fn run(self, ctx: Context<'a>) -> Execution<'a> {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.call(ctx, Next { command: self.command, layers }),
+            None => Box::pin(async move { self.command.execute(&ctx).await }),
+        }
+    }
+}
+
+/// What the registry holds, in `!help` order.
+enum Entry {
+    Native(Arc<dyn NativeCommand>),
+    /// Handled by the window; known for `!help` and completion.
+    Ui(&'static CommandSpec),
+    /// `!help` lines that aren't one command's.
+    Text(&'static [&'static str]),
+}
+
+impl Entry {
+    fn spec(&self) -> Option<&'static CommandSpec> {
+        match self {
+            Entry::Native(command) => Some(command.spec()),
+            Entry::Ui(spec) => Some(spec),
+            Entry::Text(_) => None,
+        }
+    }
+}
+
+/// The `!` commands and the middleware they run through.
+#[derive(Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+    /// Name or alias → index of a native command in `entries`.
+    native: HashMap<&'static str, usize>,
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Every built-in command and the standard middleware, outermost
+    /// first: errors, validation, cancellation, timing, the output cap.
+    pub fn builtin() -> Self {
+        let mut registry = Registry::new();
+        registry.layer(middleware::RouteErrors);
+        registry.layer(middleware::Validate);
+        registry.layer(middleware::InjectCancel);
+        registry.layer(middleware::RecordTiming);
+        registry.layer(middleware::CapOutput { max_lines: middleware::MAX_OUTPUT_LINES });
+
+        registry.text(session::HEADER);
+        registry.register(Handler::now(&session::HELP, session::help));
+        registry.register(Handler::awaiting(&session::CLEAR, session::clear));
+        registry.register(Handler::now(&session::EXIT, session::exit));
+        registry.register(Handler::now(&vault::HISTORY, vault::history));
+        registry.register(Handler::now(&vault::TIMELINE, vault::timeline));
+        registry.register(Handler::awaiting(&vault::SUMMARY, vault::summary));
+        registry.register(Handler::now(&vault::SEARCH, vault::search));
+        registry.register(Handler::now(&vault::EXPORT, vault::export));
+        registry.register(Handler::awaiting(&vault::SYNC, vault::sync));
+        registry.register(Handler::awaiting(&vault::STATS, vault::stats));
+        registry.register(Handler::awaiting(&vault::TIME, vault::time));
+        registry.register(Handler::now(&session::CALC, session::calc));
+        registry.register(Handler::now(&session::STATUS, session::status));
+        registry.register(Handler::awaiting(&session::DOCTOR, session::doctor));
+        registry.register(Handler::now(&config::INTEGRATE, config::integrate));
+        registry.register(Handler::awaiting(&session::RM, session::rm));
+        registry.register(Handler::awaiting(&session::RENAME, session::rename));
+        registry.register(Handler::awaiting(&io::HTTP, io::http));
+        registry.register(Handler::awaiting(&io::FETCH, io::fetch));
+        registry.register(Handler::now(&session::ABORT, session::abort));
+        registry.register(Handler::now(&session::TASKS, session::tasks));
+        registry.register(Handler::now(&vault::TOP, vault::top));
+        registry.register(Handler::now(&vault::HERE, vault::here));
+        registry.register(Handler::awaiting(&vault::DIFF, vault::diff));
+        registry.ui(&ui::COMPARE);
+        registry.register(Handler::awaiting(&vault::REDO, vault::redo));
+        registry.register(Handler::now(&vault::CHAIN, vault::chain));
+        registry.register(Handler::now(&vault::TAG, vault::tag));
+        registry.register(Handler::now(&vault::RECALL, vault::recall));
+        registry.ui(&ui::MARK);
+        registry.ui(&ui::MARKS);
+        registry.ui(&ui::JUMP);
+        registry.ui(&ui::MACRO);
+        registry.register(Handler::now(&vault::CAPTURE, vault::capture));
+        registry.register(Handler::now(&vault::VARS, vault::vars));
+        registry.register(Handler::awaiting(&session::NEW, session::new));
+        registry.ui(&ui::ERRORS);
+        registry.register(Handler::now(&vault::TESTS, vault::tests));
+        registry.register(Handler::now(&session::OUT, session::out));
+
+        registry.text(&[""]);
+        registry.register(Handler::now(&config::ALIAS, config::alias));
+        registry.register(Handler::now(&config::UNALIAS, config::unalias));
+
+        registry.text(&[""]);
+        registry.register(Handler::now(&vault::BOOKMARK, vault::bookmark));
+        registry.register(Handler::now(&vault::BOOKMARKS, vault::bookmarks));
+        registry.register(Handler::awaiting(&vault::PICK, vault::pick));
+        registry.register(Handler::awaiting(&vault::QUEUE, vault::queue));
+
+        registry.text(&[""]);
+        registry.register(Handler::now(&config::HOOK, config::hook));
+        registry.register(Handler::now(&config::SAFE, config::safe));
+        registry.register(Handler::now(&config::SETENV, config::setenv));
+
+        registry.text(&[""]);
+        registry.register(Handler::now(&config::SET, config::set));
+        registry.register(Handler::now(&config::GET, config::get));
+        registry.text(&["  !set cnf.package.<cmd> <pkg> [winget=<id> ...]  Package hint for a missing command"]);
+        registry.ui(&ui::CONFIG);
+        registry.ui(&ui::PROFILE);
+        registry.register(Handler::awaiting(&vault::VAULT, vault::vault));
+
+        registry.text(&[""]);
+        registry.register(Handler::awaiting(&io::IO, io::io));
+        registry.ui(&ui::SCOPE);
+        registry.text(&["  !vault unlock|encrypt|decrypt  Passphrase prompts (handled by UI)"]);
+
+        registry.text(&[""]);
+        registry.register(Handler::awaiting(&neural::AI, neural::ai));
+        registry.register(Handler::now(&neural::CONTEXT, neural::context));
+        registry.text(&["  # <request>        Generate a command into the input line"]);
+        registry.register(Handler::awaiting(&neural::DEBUG, neural::debug));
+        registry.register(Handler::awaiting(&neural::SUGGEST, neural::suggest));
+        registry.register(Handler::awaiting(&neural::EXPLAIN, neural::explain));
+        registry.register(Handler::awaiting(&neural::FIX, neural::fix));
+        registry.register(Handler::awaiting(&neural::TLDR, neural::tldr));
+        registry.register(Handler::now(&neural::NEURAL, neural::neural));
+
+        registry.text(&[""]);
+        for spec in [
+            &ui::THEME, &ui::PWD, &ui::PERF, &ui::TIMESTAMPS, &ui::BELL, &ui::PRESENT, &ui::KEYS, &ui::REHASH,
+            &ui::PASTE, &ui::FOLLOW, &ui::RECORD, &ui::SAVE, &ui::CHART, &ui::VIEW, &ui::HOLODECK, &ui::VER,
+        ] {
+            registry.ui(spec);
+        }
+        registry.text(session::FOOTER);
+
+        // Known to the completer, not in `!help`.
+        for spec in [&ui::CHAT, &ui::HIVE, &ui::WASM, &ui::RUN] {
+            registry.ui(spec);
+        }
+        registry
+    }
+
+    /// Add a command. A name or alias already taken is taken over.
+    pub fn register(&mut self, command: impl NativeCommand + 'static) {
+        let spec = command.spec();
+        let index = self.entries.len();
+        self.entries.push(Entry::Native(Arc::new(command)));
+        for word in std::iter::once(&spec.name).chain(spec.aliases) {
+            self.native.insert(word, index);
+        }
+    }
+
+    /// A command the window handles: listed, never run here.
+    pub fn ui(&mut self, spec: &'static CommandSpec) {
+        self.entries.push(Entry::Ui(spec));
+    }
+
+    /// `!help` lines that aren't one command's.
+    pub fn text(&mut self, lines: &'static [&'static str]) {
+        self.entries.push(Entry::Text(lines));
+    }
+
+    /// Add a layer inside the ones added before it.
+    pub fn layer(&mut self, middleware: impl Middleware + 'static) {
+        self.layers.push(Arc::new(middleware));
+    }
+
+    /// The native command `word` (without the `!`) names.
+    pub fn find(&self, word: &str) -> Option<&dyn NativeCommand> {
+        match &self.entries[*self.native.get(word)?] {
+            Entry::Native(command) => Some(command.as_ref()),
+            Entry::Ui(_) | Entry::Text(_) => None,
+        }
+    }
+
+    /// The spec of any command `word` names, the window's included.
+    pub fn spec(&self, word: &str) -> Option<&'static CommandSpec> {
+        self.entries.iter().filter_map(Entry::spec).find(|spec| spec.answers_to(word))
+    }
+
+    /// Every name and alias, sorted, for completion and `!ver`.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<&'static str> = self
+            .entries
+            .iter()
+            .filter_map(Entry::spec)
+            .flat_map(|spec| std::iter::once(spec.name).chain(spec.aliases.iter().copied()))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// What Tab completes after `word`.
+    pub fn subcommands(&self, word: &str) -> &'static [&'static str] {
+        self.spec(word).map_or(&[], |spec| spec.subcommands)
+    }
+
+    /// `!help`.
+    pub fn help_lines(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .flat_map(|entry| match entry {
+                Entry::Native(command) => command.spec().help,
+                Entry::Ui(spec) => spec.help,
+                Entry::Text(lines) => lines,
+            })
+            .map(|line| line.to_string())
+            .collect()
+    }
+
+    /// The native command closest to `word`, if it is within
+    /// `SUGGEST_DISTANCE` edits.
+    pub fn suggest(&self, word: &str) -> Option<&'static str> {
+        let mut names: Vec<&'static str> = self.native.keys().copied().collect();
+        names.sort_unstable();
+        names
+            .into_iter()
+            .map(|name| (levenshtein_distance(word, name), name))
+            .filter(|(distance, _)| *distance <= SUGGEST_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, name)| name)
+    }
+
+    /// Run `!` command `line` through the middleware.
+    pub fn run<'a>(&'a self, runner: &'a Runner, line: &'a str) -> Execution<'a> {
+        let ctx = Context::new(runner, line);
+        let typed = ctx.command();
+        match self.find(typed.strip_prefix('!').unwrap_or(typed)) {
+            Some(command) => Next { command, layers: &self.layers }.run(ctx),
+            None => Box::pin(std::future::ready(Ok(ExecuteResult::DirectOutput(self.unknown_lines(typed))))),
+        }
+    }
+
+    fn unknown_lines(&self, typed: &str) -> Vec<String> {
+        let mut lines = vec![format!("❌ Unknown command: {}", typed)];
+        let word = typed.strip_prefix('!').unwrap_or(typed);
+        if let Some(name) = self.suggest(word).filter(|_| !word.is_empty()) {
+            lines.push(format!("   Did you mean !{}?", name));
+        }
+        lines.push("".to_string());
+        lines.push("Type !help for available commands.".to_string());
+        lines
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("commands", &self.native.len())
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+/// The built-in commands' specs, for a frontend's completion and help:
+/// `Registry::builtin()`, built once.
+pub fn catalog() -> &'static Registry {
+    static CATALOG: OnceLock<Registry> = OnceLock::new();
+    CATALOG.get_or_init(Registry::builtin)
+}
+
+/// `text` after its first `n` words, spacing and quotes inside kept.
+pub(crate) fn after_words(text: &str, n: usize) -> &str {
+    let mut rest = text.trim_start();
+    for _ in 0..n {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    rest.trim_end()
+}
@@ -0,0 +1,466 @@
+//! Neural commands: `!ai`, `!context`, `!debug`, `!suggest`, `!explain`,
+//! `!fix`, `!tldr` and `!neural`, and the `# <request>` line.
+//!
+//! Prompts and context are scrubbed by `PrivacyGuard` before they leave
+//! the machine; the last full answer stays available through `!ai last`.
+
+use anyhow::Result;
+use positronic_neural::budget::PromptBuilder;
+use positronic_neural::cortex::{Cancelled, SystemContext, TaskType};
+use positronic_neural::generate::sanitize_command;
+use positronic_neural::health::ModelState;
+use positronic_neural::injection;
+use positronic_neural::privacy::PrivacyGuard;
+use positronic_neural::suggest::{parse_suggestions, suggestion_prompt};
+
+use crate::cancel::{CancellationToken, CANCELLED};
+use crate::diff::{self, CommandDiff};
+use crate::fix::{self, Correction, FIX_USAGE};
+use crate::history_filter::parse_flag;
+use crate::runner::{ExecuteResult, Runner};
+use crate::tldr::{Tldr, TLDR_USAGE};
+
+use super::{CommandSpec, Context, Execution};
+
+pub(super) static AI: CommandSpec = CommandSpec {
+    name: "ai",
+    aliases: &["ask"],
+    help: &[
+        "  !ai <prompt>       Ask the local AI (alias: !ask)",
+        "  !ai last           Show the full text of the last AI answer",
+    ],
+    min_args: 1,
+    usage: "Usage: !ai <prompt>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn ai<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        let runner = ctx.runner;
+        if ctx.args() == ["last"] {
+            let last = runner.last_ai.lock().ok().and_then(|l| l.clone());
+            return Ok(ExecuteResult::DirectOutput(match last {
+                Some(text) => {
+                    let mut lines = vec!["🧠 Last AI answer:".to_string(), "".to_string()];
+                    lines.extend(text.lines().map(|l| format!("  {}", l)));
+                    lines
+                }
+                None => vec!["No AI answer yet this session.".to_string()],
+            }));
+        }
+        let prompt = PrivacyGuard::scrub(&ctx.args().join(" "));
+        let task = TaskType::classify(&prompt, Some("ask"));
+        Ok(ask_neural(runner, &prompt, task, "🧠 AI:", &ctx.cancel).await)
+    })
+}
+
+pub(super) static CONTEXT: CommandSpec = CommandSpec {
+    name: "context",
+    help: &["  !context           Show the context (directory, project, git, history) sent with AI requests"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn context(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(context_lines(ctx.runner)))
+}
+
+pub(super) static DEBUG: CommandSpec = CommandSpec {
+    name: "debug",
+    help: &["  !debug <error>     Diagnose an error with the local AI"],
+    min_args: 1,
+    usage: "Usage: !debug <error output>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn debug<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        // Keep the paste's line breaks; split_whitespace would flatten them.
+        // The paste is terminal output, so it goes in as fenced data.
+        let prompt = format!(
+            "Diagnose this error and suggest a fix:\n\n{}",
+            injection::fence("error output", &PrivacyGuard::scrub(ctx.rest(1)))
+        );
+        Ok(ask_neural(ctx.runner, &prompt, TaskType::Debug, "🩺 Debug:", &ctx.cancel).await)
+    })
+}
+
+pub(super) static SUGGEST: CommandSpec = CommandSpec {
+    name: "suggest",
+    help: &["  !suggest <goal>    Suggest commands; press 1–5 to edit one"],
+    min_args: 1,
+    usage: "Usage: !suggest <what you want to do>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn suggest<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        let runner = ctx.runner;
+        let neural = match runner.neural() {
+            Ok(n) => n,
+            Err(e) => {
+                return Ok(ExecuteResult::DirectOutput(vec![format!("❌ {}", e)]));
+            }
+        };
+        let prompt = suggestion_prompt(&PrivacyGuard::scrub(&ctx.args().join(" ")));
+        let context = shell_context(runner);
+        let reply = match neural.ask_smart_cancellable(&prompt, TaskType::Code, Some(&context), &ctx.cancel).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                return Ok(ExecuteResult::DirectOutput(vec![ai_error(&e)]));
+            }
+        };
+
+        runner.set_last_ai(&reply.text);
+        let suggestions = parse_suggestions(&reply.text);
+        if suggestions.is_empty() {
+            // Nothing command-shaped; show the answer as-is.
+            let mut lines = vec!["💡 Suggestion:".to_string(), "".to_string()];
+            lines.extend(reply.text.lines().map(|l| format!("  {}", l)));
+            return Ok(ExecuteResult::DirectOutput(lines));
+        }
+        let commands: Vec<&str> = suggestions.iter().map(|s| s.command.as_str()).collect();
+        if let Err(e) = runner.vault.log_suggestions(&commands) {
+            tracing::warn!("Failed to record suggestions: {}", e);
+        }
+        Ok(ExecuteResult::Suggestions(suggestions))
+    })
+}
+
+pub(super) static EXPLAIN: CommandSpec = CommandSpec {
+    name: "explain",
+    help: &["  !explain <command> Explain a command (tldr page when the AI is offline)"],
+    min_args: 1,
+    usage: "Usage: !explain <command>",
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn explain<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(ExecuteResult::DirectOutput(explain_lines(ctx.runner, ctx.rest(1)).await)) })
+}
+
+pub(super) static FIX: CommandSpec = CommandSpec {
+    name: "fix",
+    help: &["  !fix [command]     Correct a command (default: the last failure) and preview the change"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn fix<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(fix_result(ctx.runner, ctx.rest(1)).await) })
+}
+
+pub(super) static TLDR: CommandSpec = CommandSpec {
+    name: "tldr",
+    help: &[
+        "  !tldr <command>    Show a command's tldr examples, offline",
+        "  !tldr --update     Download or refresh the tldr pages",
+    ],
+    subcommands: &["--update"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn tldr<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        let tldr = ctx.runner.tldr();
+        let mut lines = Vec::new();
+        match ctx.parts.get(1).copied() {
+            None => {
+                lines.push(TLDR_USAGE.to_string());
+                lines.push(if tldr.is_installed() {
+                    format!("  {} pages in {}", tldr.names().len(), tldr.pages_dir().display())
+                } else {
+                    "  Not downloaded yet; the first lookup fetches them.".to_string()
+                });
+            }
+            Some("--update") => {
+                let report = tldr.update_report(|line| lines.push(line)).await;
+                lines.push(report);
+            }
+            Some(_) => {
+                // First use: fetch the pages, then look up.
+                if !tldr.is_installed() {
+                    let report = tldr.update_report(|line| lines.push(line)).await;
+                    lines.push(report);
+                    lines.push(String::new());
+                }
+                lines.extend(tldr_page_lines(&tldr, &ctx.args().join(" ")));
+            }
+        }
+        Ok(ExecuteResult::DirectOutput(lines))
+    })
+}
+
+pub(super) static NEURAL: CommandSpec = CommandSpec {
+    name: "neural",
+    help: &["  !neural status     Show model health, latency and last errors"],
+    subcommands: &["status"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn neural(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    if ctx.parts.get(1).is_some_and(|s| *s != "status") {
+        return Ok(ExecuteResult::DirectOutput(vec![
+            "Usage: !neural status".to_string(),
+        ]));
+    }
+    let neural = match ctx.runner.neural() {
+        Ok(n) => n,
+        Err(e) => {
+            return Ok(ExecuteResult::DirectOutput(vec![format!("❌ {}", e)]));
+        }
+    };
+    let report = neural.health();
+    let probed = match report.last_probe {
+        Some(t) => format!("{}s ago", t.elapsed().as_secs()),
+        None => "never".to_string(),
+    };
+    let mut lines = vec![
+        format!("🧠 Neural status ({}):", neural.base_url()),
+        format!("  Last probe: {}", probed),
+    ];
+    if !report.reachable && report.last_probe.is_some() {
+        lines.push("  ⚠️ Server unreachable".to_string());
+    } else if !report.listing_supported && report.last_probe.is_some() {
+        lines.push("  ⚠️ /models unavailable — assuming the configured model works".to_string());
+    }
+    lines.push("".to_string());
+    if report.models.is_empty() {
+        lines.push("  No models known yet.".to_string());
+    }
+    for m in &report.models {
+        let icon = match m.state {
+            ModelState::Ready => "✓",
+            ModelState::Untested => "·",
+            ModelState::Broken => "❌",
+        };
+        let latency = m
+            .last_latency
+            .map(|d| format!("{} ms", d.as_millis()))
+            .unwrap_or_else(|| "—".to_string());
+        let ctx = m
+            .context_window
+            .map(|c| format!("  ctx {}", c))
+            .unwrap_or_default();
+        lines.push(format!("  {} {:<28} {:<9} {:>8}{}", icon, m.id, m.state, latency, ctx));
+        if let Some(err) = &m.last_error {
+            lines.push(format!("      last error: {}", err));
+        }
+    }
+    Ok(ExecuteResult::DirectOutput(lines))
+}
+
+/// Recent commands included as context in AI prompts. The prompt builder
+/// trims the oldest of these first when a model's budget is tight.
+const AI_HISTORY_CONTEXT: usize = 20;
+
+/// Ask the neural subsystem with shell context attached and format the
+/// answer under `header`. With `neural.verbose` set, also shows the model
+/// and estimated prompt size.
+async fn ask_neural(
+    runner: &Runner,
+    prompt: &str,
+    task: TaskType,
+    header: &str,
+    cancel: &CancellationToken,
+) -> ExecuteResult {
+    let neural = match runner.neural() {
+        Ok(n) => n,
+        Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ {}", e)]),
+    };
+    let context = shell_context(runner);
+
+    // Cancelled, the half-received reply is dropped: `!ai last` keeps the
+    // answer before.
+    match neural.ask_smart_cancellable(prompt, task, Some(&context), cancel).await {
+        Ok(reply) => {
+            runner.set_last_ai(&reply.text);
+            let mut lines = vec![header.to_string(), "".to_string()];
+            lines.extend(reply.text.lines().map(|l| format!("  {}", l)));
+            let verbose = runner
+                .vault
+                .get_config("neural.verbose")
+                .ok()
+                .flatten()
+                .as_deref()
+                .and_then(parse_flag)
+                .unwrap_or(false);
+            if verbose {
+                lines.push("".to_string());
+                lines.push(format!(
+                    "  📏 {} · ~{}/{} prompt tokens{}",
+                    reply.model,
+                    reply.prompt_tokens,
+                    reply.budget,
+                    if reply.truncated { " · context trimmed" } else { "" }
+                ));
+            }
+            ExecuteResult::DirectOutput(lines)
+        }
+        Err(e) => ExecuteResult::DirectOutput(vec![ai_error(&e)]),
+    }
+}
+
+/// A model call's error as a line; a cancelled call is `CANCELLED`.
+pub(super) fn ai_error(e: &anyhow::Error) -> String {
+    match e.downcast_ref::<Cancelled>() {
+        Some(_) => CANCELLED.to_string(),
+        None => format!("❌ AI error: {}", e),
+    }
+}
+
+/// `!explain`: ask the model, or show the tldr page and what the
+/// command's flags do when the model can't answer.
+async fn explain_lines(runner: &Runner, command: &str) -> Vec<String> {
+    let failure = match runner.neural() {
+        Ok(neural) => {
+            let prompt = format!(
+                "Explain briefly what this shell command does:\n\n{}",
+                injection::fence("command", &PrivacyGuard::scrub(command))
+            );
+            let task = TaskType::classify(&prompt, Some("explain"));
+            match neural.ask_smart_detailed(&prompt, task, Some(&shell_context(runner))).await {
+                Ok(reply) => {
+                    runner.set_last_ai(&reply.text);
+                    let mut lines = vec!["🧠 Explain:".to_string(), "".to_string()];
+                    lines.extend(reply.text.lines().map(|l| format!("  {}", l)));
+                    return lines;
+                }
+                Err(e) => format!("AI error: {}", e),
+            }
+        }
+        Err(e) => e.to_string(),
+    };
+
+    let tldr = runner.tldr();
+    let mut lines = vec![format!("⚠️  {}; showing the tldr page", failure), "".to_string()];
+    lines.extend(tldr_page_lines(&tldr, command));
+    let hints = tldr.flag_hints(command);
+    if !hints.is_empty() {
+        let width = hints.iter().map(|(flag, _)| flag.chars().count()).max().unwrap_or(0);
+        lines.push("".to_string());
+        lines.push("  Flags in your command:".to_string());
+        lines.extend(hints.iter().map(|(flag, what)| format!("    {:<width$}  {}", flag, what, width = width)));
+    }
+    lines
+}
+
+/// A command's tldr page, or the closest page names when there is none.
+fn tldr_page_lines(tldr: &Tldr, command: &str) -> Vec<String> {
+    if !tldr.is_installed() {
+        return vec!["📖 No tldr pages yet. Run !tldr --update to download them.".to_string()];
+    }
+    if let Some(page) = tldr.page(command) {
+        let mut lines = page.render();
+        if page.platform != "common" && page.platform != tldr.platform() {
+            lines[0].push_str(&format!("  ({} page)", page.platform));
+        }
+        return lines;
+    }
+    let mut lines = vec![format!("❓ No tldr page for '{}'", command)];
+    let close = tldr.suggest(command, 5);
+    if !close.is_empty() {
+        lines.push(format!("  Did you mean: {}?", close.join(", ")));
+    }
+    lines
+}
+
+/// Working directory, project and git summary, and scrubbed recent
+/// commands for AI prompts. The workspace comes from the runner's cache,
+/// so a directory seen for the first time has none yet.
+fn shell_context(runner: &Runner) -> SystemContext {
+    let cwd = runner
+        .cwd()
+        .or_else(|| runner.vault.last_directory().ok().flatten())
+        .unwrap_or_else(|| {
+            std::env::current_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_default()
+        });
+    let recent = runner
+        .vault
+        .recent_unique(AI_HISTORY_CONTEXT)
+        .unwrap_or_default()
+        .iter()
+        .map(|c| PrivacyGuard::scrub(c))
+        .collect();
+    let workspace = runner.workspace.get(&cwd);
+    SystemContext::gather(&cwd, recent).with_workspace(workspace.as_ref())
+}
+
+/// `!context`: the context section exactly as AI requests render it,
+/// before a model's budget trims it.
+fn context_lines(runner: &Runner) -> Vec<String> {
+    let context = shell_context(runner);
+    let built = PromptBuilder::new(usize::MAX).system_context(&context).build();
+    let mut lines = vec!["🧭 Context sent with AI requests:".to_string(), "".to_string()];
+    lines.extend(built.system.lines().map(|l| format!("  {}", l)));
+    if context.workspace.is_empty() {
+        lines.push("".to_string());
+        lines.push("  (no project or git details for this directory yet)".to_string());
+    }
+    lines
+}
+
+/// `!fix [command]`: Reflex's correction of `typed`, or of the last
+/// command that failed; the model's when Reflex has none.
+async fn fix_result(runner: &Runner, typed: &str) -> ExecuteResult {
+    let (command, output) = if typed.is_empty() {
+        match runner.vault.last_failure() {
+            Ok(Some(record)) => (record.command, record.output),
+            Ok(None) => return ExecuteResult::DirectOutput(vec![format!("🩹 No failed command to fix. {}", FIX_USAGE)]),
+            Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ Reading history failed: {}", e)]),
+        }
+    } else {
+        (typed.to_string(), None)
+    };
+    if let Some(suggestion) = runner.reflex().fix_command(&command) {
+        return ExecuteResult::Correction(Correction::from_reflex(&command, suggestion));
+    }
+
+    let neural = match runner.neural() {
+        Ok(n) => n,
+        Err(e) => {
+            return ExecuteResult::DirectOutput(vec![format!("🩹 Reflex has no fix for '{}' and {}", command, e)]);
+        }
+    };
+    let prompt = fix::fix_prompt(&command, output.as_deref());
+    let context = shell_context(runner);
+    let reply = match neural.ask_smart_detailed(&prompt, TaskType::Code, Some(&context)).await {
+        Ok(reply) => reply,
+        Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ AI error: {}", e)]),
+    };
+    runner.set_last_ai(&reply.text);
+    match sanitize_command(&reply.text) {
+        Some(fixed) if diff::diff_command(&command, &fixed) == CommandDiff::Same => {
+            ExecuteResult::DirectOutput(vec![format!("🩹 The AI finds nothing to fix in '{}'", command)])
+        }
+        Some(fixed) => ExecuteResult::Correction(Correction::from_ai(&command, &fixed)),
+        None => ExecuteResult::DirectOutput(vec![
+            "❌ The model did not return a command. See !ai last for its answer.".to_string(),
+        ]),
+    }
+}
+
+/// `# <request>`: generate one command for the input line. The request and
+/// context are scrubbed before they leave the machine; the full model
+/// answer stays available through `!ai last`.
+pub async fn generate_command(runner: &Runner, request: &str) -> ExecuteResult {
+    let neural = match runner.neural() {
+        Ok(n) => n,
+        Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ {}", e)]),
+    };
+    let request = PrivacyGuard::scrub(request);
+    let context = shell_context(runner);
+
+    match neural.generate_command(&request, Some(&context)).await {
+        Ok(generated) => {
+            runner.set_last_ai(&generated.raw);
+            match generated.command {
+                Some(command) => ExecuteResult::GeneratedCommand(command),
+                None => ExecuteResult::DirectOutput(vec![
+                    "❌ The model did not return a command. See !ai last for its answer.".to_string(),
+                ]),
+            }
+        }
+        Err(e) => ExecuteResult::DirectOutput(vec![format!("❌ AI error: {}", e)]),
+    }
+}
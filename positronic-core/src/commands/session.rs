@@ -0,0 +1,364 @@
+//! Session commands: `!help`, `!clear`, `!exit`, `!calc`, `!status`,
+//! `!doctor`, `!abort`, `!tasks`, `!new`, `!rm`, `!rename` and `!out`.
+//!
+//! `!clear`/`!cls` sends Ctrl+C, a newline and `cls`/`clear` to the PTY
+//! so the shell itself is reset, not just the UI buffer.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::calc;
+use crate::cancel::{or_cancelled, CancellationToken, CANCELLED};
+use crate::native::NativeOutput;
+use crate::rename::{self, RenameCommand};
+use crate::runner::{ExecuteResult, Runner};
+use crate::scaffold::NewCommand;
+use crate::subsystems::SubsystemState;
+use crate::term::binary;
+use crate::trash::{self, RmCommand, TrashBackend, TRASH_BACKEND_KEY};
+
+use super::vault::vault_lines;
+use super::{CommandSpec, Context, Execution};
+
+/// The top of `!help`.
+pub(super) const HEADER: &[&str] = &[
+    "╔══════════════════════════════════════════════════════════╗",
+    "║          Positronic Built-in Commands                   ║",
+    "╚══════════════════════════════════════════════════════════╝",
+    "",
+];
+
+/// The bottom of `!help`: keyboard shortcuts and the pager.
+pub(super) const FOOTER: &[&str] = &[
+    "",
+    "  ┌─ Keyboard Shortcuts ─────────────────────────────────┐",
+    "  │  Ctrl+C           Send interrupt (break pager/cmd)   │",
+    "  │  Ctrl+Shift+C     Copy to clipboard                  │",
+    "  │  Ctrl+Shift+A     Copy with colors and styles        │",
+    "  │  Ctrl+D           Send EOF                           │",
+    "  │  Ctrl+L           Clear screen                       │",
+    "  │  Ctrl+Shift+E     Open the first error in editor     │",
+    "  │  Ctrl+Shift+S     Show or hide the scope pane        │",
+    "  │  Escape           Cancel ! command, else send escape │",
+    "  │  Tab              Cycle completions                   │",
+    "  │  Up/Down          Navigate command history            │",
+    "  └──────────────────────────────────────────────────────┘",
+    "",
+    "  Output longer than a screen opens a pager: Space next page, / search,",
+    "  g/G start/end, q quit (!set pager.threshold auto|off|<lines>).",
+    "",
+    "  Regular shell commands are sent directly to the PTY.",
+];
+
+pub(super) static HELP: CommandSpec = CommandSpec {
+    name: "help",
+    help: &["  !help              Show this help"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn help(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(ctx.runner.commands.help_lines()))
+}
+
+pub(super) static CLEAR: CommandSpec = CommandSpec {
+    name: "clear",
+    aliases: &["cls"],
+    help: &["  !clear, !cls       Clear screen (breaks pager/continuation)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn clear<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        let mut pty = ctx.runner.pty.lock().await;
+        // Break out of any pager or continuation prompt
+        let _ = pty.write_raw("\x03");
+        let _ = pty.write_raw("\r\n");
+        // Actually clear the PTY
+        if cfg!(windows) {
+            let _ = pty.write_line("cls");
+        } else {
+            let _ = pty.write_line("clear");
+        }
+        Ok(ExecuteResult::ClearScreen)
+    })
+}
+
+pub(super) static EXIT: CommandSpec = CommandSpec {
+    name: "exit",
+    aliases: &["quit"],
+    help: &["  !exit, !quit       Exit Positronic"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn exit(_: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::Exit)
+}
+
+pub(super) static CALC: CommandSpec = CommandSpec {
+    name: "calc",
+    help: &["  !calc <expr> [in <unit>]  Arithmetic, hex/binary and size/time units, e.g. 3 GiB in MB"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn calc(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(calc::command_lines(ctx.rest(1))))
+}
+
+pub(super) static STATUS: CommandSpec = CommandSpec {
+    name: "status",
+    help: &["  !status            Show subsystem readiness, init timing and background indexes"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn status(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(status_lines(ctx.runner)))
+}
+
+pub(super) static DOCTOR: CommandSpec = CommandSpec {
+    name: "doctor",
+    help: &["  !doctor            Wait for startup, then check subsystems, vault and shell"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn doctor<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(ExecuteResult::DirectOutput(doctor_lines(ctx.runner, &ctx.cancel).await)) })
+}
+
+pub(super) static RM: CommandSpec = CommandSpec {
+    name: "rm",
+    help: &[
+        "  !rm <paths…>       Move files to the trash (globs allowed); !rm undo [id] restores",
+        "  !rm list | purge [--older-than 30d] [--yes]  What is in the trash; delete it for good",
+    ],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn rm<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match RmCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(rm_output(ctx.runner, command).await.into()),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static RENAME: CommandSpec = CommandSpec {
+    name: "rename",
+    help: &[
+        "  !rename <glob> <pattern> [--yes]  Preview renames: {stem} {ext} {name} {n} {n:3} {date}",
+        "  !rename <glob> --regex 's/old/new/'  Edit the names instead; !rename apply | undo",
+    ],
+    subcommands: &["apply", "undo", "--regex", "--yes"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn rename<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match RenameCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(rename_output(ctx.runner, command).await.into()),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static ABORT: CommandSpec = CommandSpec {
+    name: "abort",
+    help: &["  !abort [id]        Cancel a running ! command; Esc cancels the newest"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn abort(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(ctx.runner.cancels.abort_lines(ctx.rest(1))))
+}
+
+pub(super) static TASKS: CommandSpec = CommandSpec {
+    name: "tasks",
+    help: &["  !tasks [cancel <id>]  Long operations in progress (imports, checks); cancel one"],
+    subcommands: &["cancel"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn tasks(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(ctx.runner.progress.tasks_lines(ctx.rest(1))))
+}
+
+pub(super) static NEW: CommandSpec = CommandSpec {
+    name: "new",
+    help: &[
+        "  !new <scaffold> <name> [key=value ...] [--keep]  Create a project from a scaffold",
+        "  !new list          Show the available scaffolds",
+    ],
+    subcommands: &["list", "--keep"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn new<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match NewCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(ExecuteResult::DirectOutput(new_lines(ctx.runner, command).await)),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static OUT: CommandSpec = CommandSpec {
+    name: "out",
+    help: &[
+        "  !out [list]        List command output suppressed as binary",
+        "  !out raw <id>      Hex preview of suppressed output",
+    ],
+    subcommands: &["list", "raw"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn out(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(out_lines(ctx.runner, ctx.args())))
+}
+
+fn status_lines(runner: &Runner) -> Vec<String> {
+    let mut lines = vec![
+        "🩺 Subsystem status:".to_string(),
+        "".to_string(),
+    ];
+    for s in runner.subsystems.snapshot() {
+        let icon = match s.state {
+            SubsystemState::Starting => "⏳",
+            SubsystemState::Ready => "✓",
+            SubsystemState::Failed(_) => "❌",
+        };
+        lines.push(format!(
+            "  {} {:<8} {:>7.1} ms  {}",
+            icon,
+            s.name,
+            s.elapsed.as_secs_f64() * 1000.0,
+            s.state
+        ));
+    }
+    if runner.subsystems.is_degraded() {
+        lines.push("".to_string());
+        lines.push("⚠️ Running in degraded mode — failed features are disabled.".to_string());
+    }
+    if let Some(maintenance) = runner.maintenance() {
+        lines.push("".to_string());
+        lines.extend(maintenance.lines(Instant::now()));
+    }
+    lines
+}
+
+/// How long `!doctor` waits for subsystems that are still starting.
+const DOCTOR_WAIT: Duration = Duration::from_secs(10);
+
+/// `!doctor`: `!status` once startup has settled, plus the vault, shell
+/// integration and, in the window, the renderer.
+async fn doctor_lines(runner: &Runner, cancel: &CancellationToken) -> Vec<String> {
+    let settled = tokio::time::timeout(DOCTOR_WAIT, runner.subsystems.wait_settled());
+    if or_cancelled(cancel, settled).await.is_err() {
+        return vec![CANCELLED.to_string()];
+    }
+    let mut lines = status_lines(runner);
+    lines.push("".to_string());
+    lines.push(runner.data.summary());
+    match or_cancelled(cancel, vault_lines(runner, None)).await {
+        Ok(vault) => lines.extend(vault),
+        Err(cancelled) => return vec![cancelled],
+    }
+    let integrated = runner.running.lock().unwrap_or_else(|e| e.into_inner()).is_integrated();
+    lines.push(if integrated {
+        "🐚 Shell integration active: exit codes and durations are recorded.".to_string()
+    } else {
+        "⚠️ No shell integration (OSC 133) seen: exit codes and durations are unknown.".to_string()
+    });
+    lines.extend(runner.display_report.lock().map(|r| r.clone()).unwrap_or_default());
+    lines
+}
+
+/// `!rm`: trash, restore, list or purge off the async runtime, since a
+/// cross-device move copies the whole tree.
+async fn rm_output(runner: &Runner, command: RmCommand) -> NativeOutput {
+    let backend = TrashBackend::from_config(runner.vault.get_config(TRASH_BACKEND_KEY).ok().flatten().as_deref());
+    let cwd = runner
+        .cwd()
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let store = runner.trash.clone();
+    let now = chrono::Utc::now().timestamp();
+    tokio::task::spawn_blocking(move || trash::command_output(&store, command, backend, &cwd, now))
+        .await
+        .unwrap_or_else(|e| NativeOutput::Lines(vec![format!("❌ !rm failed: {}", e)]))
+}
+
+/// `!rename`, in the shell's directory, off the async threads.
+async fn rename_output(runner: &Runner, command: RenameCommand) -> NativeOutput {
+    let cwd = runner
+        .cwd()
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let renames = runner.renames.clone();
+    let now = chrono::Local::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    tokio::task::spawn_blocking(move || rename::command_output(&renames, command, &cwd, now.timestamp(), &date))
+        .await
+        .unwrap_or_else(|e| NativeOutput::Lines(vec![format!("❌ !rename failed: {}", e)]))
+}
+
+/// `!new list`, or `!new <scaffold> <name>` with every step's output.
+async fn new_lines(runner: &Runner, command: NewCommand) -> Vec<String> {
+    let mut lines = Vec::new();
+    match command {
+        NewCommand::List => {
+            let (scaffolds, errors) = runner.scaffolds.load();
+            lines.push("🏗  Scaffolds:".to_string());
+            for scaffold in &scaffolds {
+                let origin = if scaffold.path.is_some() { "" } else { " (built-in)" };
+                lines.push(format!("  {:<14} {}{}", scaffold.name, scaffold.description, origin));
+            }
+            lines.push(format!("  Add your own as *.toml in {}", runner.scaffolds.dir().display()));
+            lines.extend(errors.into_iter().map(|e| format!("⚠️  {}", e)));
+        }
+        NewCommand::Create(request) => {
+            let done = runner.new_project(&request, |block| lines.extend(block)).await;
+            lines.push(done);
+        }
+    }
+    lines
+}
+
+/// `!out`: blocks the binary guard withheld from the grid, and hex
+/// previews of what they captured.
+fn out_lines(runner: &Runner, args: &[&str]) -> Vec<String> {
+    let guard = runner.binary_guard.lock().unwrap_or_else(|e| e.into_inner());
+    match args {
+        [] | ["list"] => {
+            let mut lines: Vec<String> = guard
+                .quarantined()
+                .map(|q| format!("  {:>3}  {:>10}  {}", q.id, binary::format_kb(q.total), q.command))
+                .collect();
+            if lines.is_empty() {
+                return vec!["No binary output has been suppressed.".to_string()];
+            }
+            lines.insert(0, "⛔ Suppressed binary output (!out raw <id> to view):".to_string());
+            lines
+        }
+        ["raw", id] => {
+            let Some(entry) = id.parse().ok().and_then(|id| guard.get(id)) else {
+                return vec![format!("❌ No suppressed output with id {} (see !out list)", id)];
+            };
+            let shown = entry.captured.len().min(binary::PREVIEW_BYTES);
+            let mut lines = vec![
+                format!(
+                    "⛔ {} — {}, first {} bytes:",
+                    entry.command,
+                    binary::format_kb(entry.total),
+                    shown
+                ),
+                String::new(),
+            ];
+            lines.extend(binary::hex_dump(&entry.captured[..shown]).into_iter().map(|l| format!("  {}", l)));
+            lines
+        }
+        _ => vec!["Usage: !out [list] | !out raw <id>".to_string()],
+    }
+}
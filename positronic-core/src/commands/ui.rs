@@ -0,0 +1,209 @@
+//! Commands the window handles itself: the screen, the scrollback, the
+//! theme and everything else that needs a frontend. Their specs are
+//! registered so `!help` lists them and the completer knows them; a
+//! headless runner answers them as unknown.
+
+use super::CommandSpec;
+
+pub(super) static COMPARE: CommandSpec = CommandSpec {
+    name: "compare",
+    help: &["  !compare [<a> <b>]  Two outputs side by side: ids, tags, last or prev (handled by UI)"],
+    subcommands: &["last", "prev"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static MARK: CommandSpec = CommandSpec {
+    name: "mark",
+    help: &["  !mark <name> | rm <name>  Name this place in the scrollback (the focused block, else the top line)"],
+    subcommands: &["rm"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static MARKS: CommandSpec = CommandSpec {
+    name: "marks",
+    help: &["  !marks             List marks and the command near each"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static JUMP: CommandSpec = CommandSpec {
+    name: "jump",
+    help: &["  !jump <name>       Scroll to a mark (Ctrl+O / Ctrl+I go back and forward)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static MACRO: CommandSpec = CommandSpec {
+    name: "macro",
+    help: &[
+        "  !macro record <name> | stop  Record what is typed and run, by action, not by key",
+        "  !macro play <name> [n] Replay a macro n times, waiting out running commands (Esc stops)",
+        "  !macro list | show <name> | rm <name>  Saved macros",
+    ],
+    subcommands: &["record", "stop", "play", "list", "show", "rm"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static ERRORS: CommandSpec = CommandSpec {
+    name: "errors",
+    help: &[
+        "  !errors [open <n>] List or open errors from the last failure (handled by UI)",
+        "  !errors sys [clear] List Positronic's own errors, with counts (handled by UI)",
+    ],
+    subcommands: &["open", "sys"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static CONFIG: CommandSpec = CommandSpec {
+    name: "config",
+    help: &["  !config reload     Re-apply settings from the vault (handled by UI)"],
+    subcommands: &["reload"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static PROFILE: CommandSpec = CommandSpec {
+    name: "profile",
+    help: &["  !profile list|save|use|rm <name>  Named setting bundles (handled by UI)"],
+    subcommands: &["list", "save", "use", "rm"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static SCOPE: CommandSpec = CommandSpec {
+    name: "scope",
+    help: &["  !scope [port] [--range <min>:<max>|auto] [--window <s>] | off  Plot a device's channels (handled by UI)"],
+    subcommands: &["off", "--range", "--window"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static THEME: CommandSpec = CommandSpec {
+    name: "theme",
+    help: &["  !theme <n>|auto    Change color theme, or follow theme.mode again (handled by UI)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static PWD: CommandSpec = CommandSpec {
+    name: "pwd",
+    help: &["  !pwd               Show current directory (handled by UI)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static PERF: CommandSpec = CommandSpec {
+    name: "perf",
+    help: &[
+        "  !perf overlay      Toggle render counters and live latency (handled by UI)",
+        "  !perf report|reset Input-to-render latency per stage, time in each power state (handled by UI)",
+    ],
+    subcommands: &["overlay", "report", "reset"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static TIMESTAMPS: CommandSpec = CommandSpec {
+    name: "timestamps",
+    help: &["  !timestamps on|off|relative  Line arrival gutter (handled by UI)"],
+    subcommands: &["on", "off", "relative"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static BELL: CommandSpec = CommandSpec {
+    name: "bell",
+    help: &["  !bell [test | mute <10m> | unmute]  What a bell does now; silence bells for a while (handled by UI)"],
+    subcommands: &["test", "mute", "unmute"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static PRESENT: CommandSpec = CommandSpec {
+    name: "present",
+    help: &["  !present [on|off]  Hide secrets, user and host names on screen while sharing it (handled by UI)"],
+    subcommands: &["on", "off"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static KEYS: CommandSpec = CommandSpec {
+    name: "keys",
+    help: &[
+        "  !keys [reload]     Show or reload key bindings (handled by UI)",
+        "  !keys show         Grouped, searchable binding reference; also F1 (handled by UI)",
+        "  !keys import-inputrc [path]  Take bindings from ~/.inputrc (handled by UI)",
+    ],
+    subcommands: &["show", "reload", "import-inputrc"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static REHASH: CommandSpec = CommandSpec {
+    name: "rehash",
+    help: &["  !rehash            Rescan PATH for Tab completion (handled by UI)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static PASTE: CommandSpec = CommandSpec {
+    name: "paste",
+    help: &["  !paste list|<n>|clear  Clipboard history of what Positronic copied (handled by UI)"],
+    subcommands: &["list", "clear"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static FOLLOW: CommandSpec = CommandSpec {
+    name: "follow",
+    help: &["  !follow <command>  Tail a command's output; Space pauses, e/w/i filter, / regex (handled by UI)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static RECORD: CommandSpec = CommandSpec {
+    name: "record",
+    help: &[
+        "  !record start [path] | stop  Record the session as asciicast (handled by UI)",
+        "  !record play <path> [--speed <x>]  Replay a recording (handled by UI)",
+    ],
+    subcommands: &["start", "stop", "play"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static SAVE: CommandSpec = CommandSpec {
+    name: "save",
+    help: &["  !save <path> [--format csv|json|raw] [--entry <id>] [--force]  Save the last table/JSON (handled by UI)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static CHART: CommandSpec = CommandSpec {
+    name: "chart",
+    help: &["  !chart [--logy|--liny] [--x time] [--entry <id>]  Plot the last table (handled by UI)"],
+    subcommands: &["--logy", "--liny", "--x", "--entry"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static VIEW: CommandSpec = CommandSpec {
+    name: "view",
+    help: &["  !view [path] [--hex] [--head n] [--tail n]  Page a file, highlighted or as hex (handled by UI)"],
+    subcommands: &["--hex", "--head", "--tail"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static HOLODECK: CommandSpec = CommandSpec {
+    name: "holodeck",
+    help: &[
+        "  !holodeck detectors  Built-in and plugin content detectors, with hits (handled by UI)",
+        "  !holodeck list [--all-panes]  Entries with their type, size and source (handled by UI)",
+    ],
+    subcommands: &["detectors", "list"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static VER: CommandSpec = CommandSpec {
+    name: "ver",
+    aliases: &["version"],
+    help: &["  !ver [--json]      Build, features, vault schema and subsystem versions (handled by UI)"],
+    subcommands: &["--json"],
+    ..CommandSpec::DEFAULT
+};
+
+// Not in `!help`.
+
+pub(super) static CHAT: CommandSpec = CommandSpec { name: "chat", ..CommandSpec::DEFAULT };
+
+pub(super) static HIVE: CommandSpec = CommandSpec {
+    name: "hive",
+    subcommands: &["scan", "status"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) static WASM: CommandSpec = CommandSpec { name: "wasm", ..CommandSpec::DEFAULT };
+
+pub(super) static RUN: CommandSpec = CommandSpec { name: "run", ..CommandSpec::DEFAULT };
@@ -0,0 +1,1724 @@
+//! Vault commands: history and what is kept of it (`!history`, `!search`,
+//! `!stats`, `!diff`, `!tag`, `!capture`, `!bookmark`, `!pick`, `!queue`…)
+//! and `!vault` itself.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use positronic_neural::cortex::TaskType;
+
+use crate::cancel::CancellationToken;
+use crate::capture::{self, Capture};
+use crate::chain::{self, ChainStep};
+use crate::danger::DangerAnalyzer;
+use crate::data_paths;
+use crate::diff::{self, DiffOperand, DiffOptions, DiffRequest};
+use crate::headless::plain_text;
+use crate::heatmap::{Heatmap, HeatmapRequest};
+use crate::here;
+use crate::native::{Cell, ColumnKind, DataFrame, NativeOutput};
+use crate::pick::{self, ItemRef, ListedItem, Listing, PickCommand};
+use crate::progress::Outcome;
+use crate::queue::{self, QueueCommand, QueueRun};
+use crate::redo::{RedoOffer, RedoRequest, RedoTarget};
+use crate::runner::{ExecuteResult, Runner};
+use crate::summary::{self, SummaryRequest};
+use crate::sync;
+use crate::tags::{self, TagCommand};
+use crate::test_report::{self, TestsCommand};
+use crate::time_format::{self, Clock};
+use crate::timeline::{TimelineData, TimelineEvent, TimelineRange};
+use crate::trash;
+use crate::usage::{self, TIME_USAGE};
+use crate::vault::analytics;
+use crate::vault::crypto;
+use crate::vault::recover;
+use crate::vault::timing::{self, TrendDirection};
+use crate::vault::{AnalyticsReport, CommandRecord, LockState, Vault};
+
+use super::neural::ai_error;
+use super::{CommandSpec, Context, Execution, VAULT_LOCKED};
+
+pub(super) static HISTORY: CommandSpec = CommandSpec {
+    name: "history",
+    help: &[
+        "  !history [n] [--all]  Show last n commands (default: 20); --all adds unlogged ones",
+        "  !history --at <id> Show a logged command and the ones around it",
+        "  !history --pane <n> | --all-panes  One split pane's commands, or every pane's (also !search, !export)",
+    ],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn history(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(match pane_scope(ctx.runner, &ctx.parts, HISTORY_USAGE) {
+        Ok((vault, parts)) => history_output(ctx.runner, &vault, &parts).into(),
+        Err(usage) => ExecuteResult::DirectOutput(vec![usage]),
+    })
+}
+
+pub(super) static TIMELINE: CommandSpec = CommandSpec {
+    name: "timeline",
+    help: &["  !timeline [session|today|<YYYY-MM-DD>]  When commands ran, how long, which failed"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn timeline(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match TimelineRange::parse(ctx.rest(1)) {
+        Ok(range) => Ok(timeline_result(ctx.runner, range)),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static SUMMARY: CommandSpec = CommandSpec {
+    name: "summary",
+    help: &["  !summary [today|session|<YYYY-MM-DD>] [--save]  What was done, as Markdown: commands, time, failures"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn summary<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        Ok(match SummaryRequest::parse(ctx.rest(1)) {
+            Ok(request) => summary_output(ctx.runner, request, &ctx.cancel).await.into(),
+            Err(usage) => ExecuteResult::DirectOutput(vec![usage]),
+        })
+    })
+}
+
+pub(super) static SEARCH: CommandSpec = CommandSpec {
+    name: "search",
+    help: &["  !search <query>    Search command history"],
+    min_args: 1,
+    usage: SEARCH_USAGE,
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn search(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let runner = ctx.runner;
+    let (vault, parts) = match pane_scope(runner, &ctx.parts, SEARCH_USAGE) {
+        Ok(scoped) => scoped,
+        Err(usage) => return Ok(ExecuteResult::DirectOutput(vec![usage])),
+    };
+    let query = parts[1..].join(" ");
+    if query.is_empty() {
+        return Ok(ExecuteResult::DirectOutput(vec![SEARCH_USAGE.to_string()]));
+    }
+
+    match vault.search_history(&query) {
+        Ok(results) => {
+            if results.is_empty() {
+                return Ok(ExecuteResult::DirectOutput(vec![
+                    format!("🔍 No results for '{}'", query)
+                ]));
+            }
+
+            let mut lines = vec![
+                format!("🔍 {} results for '{}':", results.len(), query),
+                "".to_string(),
+            ];
+            let mut listing = Listing::new("!search");
+            for r in &results {
+                lines.push(format!(
+                    "  #{:<5} {} (exit {})",
+                    r.id.unwrap_or(0),
+                    r.command,
+                    r.exit_code.unwrap_or(-1)
+                ));
+                if let Some(id) = r.id {
+                    listing.push(id, ItemRef::Record(id), &r.command);
+                }
+            }
+            register_listing(runner, listing);
+            Ok(ExecuteResult::DirectOutput(lines))
+        }
+        Err(e) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("❌ Search error: {}", e)
+        ])),
+    }
+}
+
+pub(super) static EXPORT: CommandSpec = CommandSpec {
+    name: "export",
+    help: &[
+        "  !export <path>     Write history to a file, shell-history style",
+        "  !export <path> --with-timestamps [--all-panes]  Write the scrollback, each line stamped (handled by UI)",
+    ],
+    min_args: 1,
+    usage: EXPORT_USAGE,
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn export(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(match pane_scope(ctx.runner, &ctx.parts, EXPORT_USAGE) {
+        Ok((vault, parts)) if parts.len() == 2 => export_history_lines(ctx.runner, &vault, parts[1]),
+        _ => vec![EXPORT_USAGE.to_string()],
+    }))
+}
+
+pub(super) static SYNC: CommandSpec = CommandSpec {
+    name: "sync",
+    help: &["  !sync export <path> | import <path|dir>  Share history, aliases and bookmarks between machines"],
+    subcommands: &["export", "import"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn sync<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(ExecuteResult::DirectOutput(sync_lines(ctx.runner, ctx.args(), &ctx.cancel).await)) })
+}
+
+pub(super) static STATS: CommandSpec = CommandSpec {
+    name: "stats",
+    help: &[
+        "  !stats             Show vault statistics",
+        "  !stats slow [n]    Slowest recurring commands (default: 10)",
+        "  !stats trend <cmd> Has a command gotten slower lately?",
+        "  !stats export [json|csv] [path]  Export usage analytics",
+        "  !stats resources [n]  Most CPU-hungry captured commands, with peak memory",
+        "  !stats tests       Test pass rate over time, per project",
+        "  !stats heatmap [prefix] [--weeks n] [--calendar] [--json]  When commands run, by weekday and hour or by day",
+    ],
+    subcommands: &["export", "heatmap", "resources", "slow", "tests", "trend"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn stats<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        let (runner, parts) = (ctx.runner, &ctx.parts);
+        let limit = || parts.get(2).and_then(|s| s.parse::<usize>().ok()).unwrap_or(10);
+        match parts.get(1).copied() {
+            Some("slow") => Ok(ExecuteResult::DirectOutput(slow_lines(runner, limit()))),
+            Some("export") => Ok(ExecuteResult::DirectOutput(export_stats(runner, &parts[2..]).await)),
+            Some("trend") if parts.len() < 3 => Ok(ExecuteResult::DirectOutput(vec![
+                "Usage: !stats trend <command>".to_string(),
+            ])),
+            Some("trend") => Ok(ExecuteResult::DirectOutput(trend_lines(runner, &parts[2..].join(" ")))),
+            Some("resources") => Ok(ExecuteResult::DirectOutput(resource_lines(runner, limit()))),
+            Some("heatmap") => Ok(ExecuteResult::DirectOutput(heatmap_lines(runner, &parts[2..]).await)),
+            Some("tests") => match runner.vault.test_runs(test_report::STATS_RUNS) {
+                Ok(runs) => Ok(ExecuteResult::DirectOutput(test_report::stats_lines(&runs))),
+                Err(e) => Ok(ExecuteResult::DirectOutput(vec![format!("❌ Error reading test runs: {}", e)])),
+            },
+            _ => Ok(stats_output(runner).into()),
+        }
+    })
+}
+
+pub(super) static TIME: CommandSpec = CommandSpec {
+    name: "time",
+    help: &["  !time <command>    Run a command captured; report CPU time and peak memory"],
+    min_args: 1,
+    usage: TIME_USAGE,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn time<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move { Ok(ExecuteResult::DirectOutput(time_lines(ctx.runner, ctx.rest(1)).await)) })
+}
+
+pub(super) static TOP: CommandSpec = CommandSpec {
+    name: "top",
+    help: &["  !top [n]           Show most-used commands (default: 10)"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn top(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let limit = ctx.parts.get(1)
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(10);
+    Ok(top_output(ctx.runner, limit).into())
+}
+
+pub(super) static HERE: CommandSpec = CommandSpec {
+    name: "here",
+    help: &["  !here [n]          Commands most often run in this directory (default: 10)"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn here(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let limit = ctx.parts.get(1)
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(10);
+    Ok(ExecuteResult::DirectOutput(here_lines(ctx.runner, limit)))
+}
+
+pub(super) static DIFF: CommandSpec = CommandSpec {
+    name: "diff",
+    help: &[
+        "  !diff              Compare the last output with the previous run",
+        "  !diff <id1> <id2>  Compare two stored outputs (ids from !search, or tags)",
+        "  !diff --watch <cmd>  Run a command and diff it with its last run",
+        "                     (--ignore-space, --context <n>)",
+    ],
+    subcommands: &["--watch", "--ignore-space", "--context"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn diff<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match DiffRequest::parse(ctx.args()) {
+            Ok((request, opts)) => Ok(ExecuteResult::DirectOutput(diff_output(ctx.runner, request, &opts).await)),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static REDO: CommandSpec = CommandSpec {
+    name: "redo",
+    help: &["  !redo <id|search> [--here|--there]  Re-run a history entry, here or where it ran"],
+    subcommands: &["--here", "--there"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn redo<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match RedoRequest::parse(ctx.rest(1)) {
+            Ok(request) => redo_request(ctx.runner, request).await,
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static CHAIN: CommandSpec = CommandSpec {
+    name: "chain",
+    help: &["  !chain [id]        Show the runs a command was edited from (the focused block's in the UI)"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn chain(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(ExecuteResult::DirectOutput(match chain::parse(ctx.rest(1)) {
+        Ok(id) => chain_lines(ctx.runner, id),
+        Err(usage) => vec![usage],
+    }))
+}
+
+pub(super) static TAG: CommandSpec = CommandSpec {
+    name: "tag",
+    help: &[
+        "  !tag <name> [id] [--force]  Name the last output (or entry id) to find it again",
+        "  !tag list | rm <n> List or remove tags",
+    ],
+    subcommands: &["list", "rm"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn tag(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match TagCommand::parse(ctx.rest(1)) {
+        Ok(command) => Ok(ExecuteResult::DirectOutput(tag_lines(ctx.runner, command))),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static RECALL: CommandSpec = CommandSpec {
+    name: "recall",
+    help: &["  !recall <name>     Show a tagged output again"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn recall(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let lines = match ctx.parts.as_slice() {
+        [_, name] => match ctx.runner.vault.get_tag(name) {
+            Ok(Some(tag)) => tags::recall_lines(&tag, &ctx.runner.vault.time_settings()),
+            Ok(None) => vec![format!("❌ No tag named '{}' (!tag list shows them)", name)],
+            Err(e) => vec![format!("❌ Error reading tags: {}", e)],
+        },
+        _ => vec![tags::RECALL_USAGE.to_string()],
+    };
+    Ok(ExecuteResult::DirectOutput(lines))
+}
+
+pub(super) static CAPTURE: CommandSpec = CommandSpec {
+    name: "capture",
+    help: &[
+        "  !capture <name> [--line n | --col k|NAME [--row i] | --regex '…' | --json path]",
+        "                     Keep a value from the last output; %{name} uses it",
+    ],
+    subcommands: &["--line", "--col", "--row", "--regex", "--json"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn capture(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match Capture::parse(ctx.rest(1)) {
+        Ok(capture) => Ok(ExecuteResult::DirectOutput(capture_lines(ctx.runner, capture))),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static VARS: CommandSpec = CommandSpec {
+    name: "vars",
+    help: &["  !vars [rm <name> | clear]  List or forget captured values"],
+    subcommands: &["rm", "clear"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn vars(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let lines = ctx.runner.variables().command_lines(ctx.args());
+    Ok(ExecuteResult::DirectOutput(lines))
+}
+
+pub(super) static TESTS: CommandSpec = CommandSpec {
+    name: "tests",
+    help: &[
+        "  !tests [n]         The last cargo test or pytest summary; n shows a failure's output",
+        "  !tests failed      Put a command re-running only the failed tests in the input line",
+    ],
+    subcommands: &["failed"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn tests(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    match TestsCommand::parse(ctx.rest(1)) {
+        Ok(command) => Ok(tests_result(ctx.runner, command)),
+        Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+    }
+}
+
+pub(super) static BOOKMARK: CommandSpec = CommandSpec {
+    name: "bookmark",
+    aliases: &["bm"],
+    help: &["  !bookmark [label]  Bookmark last command"],
+    subcommands: &["add", "rm"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn bookmark(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    let (runner, parts) = (ctx.runner, &ctx.parts);
+    if parts[1..] == ["list"] {
+        return Ok(bookmarks_output(runner).into());
+    }
+    let label = if parts.len() >= 2 {
+        Some(parts[1..].join(" "))
+    } else {
+        None
+    };
+
+    match runner.vault.recent_unique(1) {
+        Ok(recent) if !recent.is_empty() => {
+            let cmd = &recent[0];
+            match runner.vault.add_bookmark(cmd, label.as_deref()) {
+                Ok(_) => Ok(ExecuteResult::DirectOutput(vec![
+                    format!("🔖 Bookmarked: {}", cmd)
+                ])),
+                Err(e) => Ok(ExecuteResult::DirectOutput(vec![
+                    format!("❌ Error saving bookmark: {}", e)
+                ])),
+            }
+        }
+        Ok(_) => Ok(ExecuteResult::DirectOutput(vec![
+            "No commands to bookmark.".to_string()
+        ])),
+        Err(e) => Ok(ExecuteResult::DirectOutput(vec![
+            format!("❌ Error reading history: {}", e)
+        ])),
+    }
+}
+
+pub(super) static BOOKMARKS: CommandSpec = CommandSpec {
+    name: "bookmarks",
+    help: &["  !bookmarks         List all bookmarks (also !bm list)"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn bookmarks(ctx: &Context<'_>) -> Result<ExecuteResult> {
+    Ok(bookmarks_output(ctx.runner).into())
+}
+
+pub(super) static PICK: CommandSpec = CommandSpec {
+    name: "pick",
+    help: &[
+        "  !pick 2,5,7-9      Select entries of the last !history, !search, !bm list or !tag list",
+        "  !pick run [--keep-going] | bookmark | export <path> | delete  Act on the selection",
+    ],
+    subcommands: &["run", "bookmark", "export", "delete", "clear"],
+    reads_history: true,
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn pick<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match PickCommand::parse(ctx.rest(1)) {
+            Ok(PickCommand::Run { keep_going, yes: true }) => pick_run(ctx.runner, keep_going).await,
+            Ok(command) => Ok(ExecuteResult::DirectOutput(pick_lines(ctx.runner, command))),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static QUEUE: CommandSpec = CommandSpec {
+    name: "queue",
+    help: &[
+        "  !queue add <command> | list | rm <n> | clear  Line up commands to run one after another",
+        "  !queue run [--keep-going] | resume  Run the queue, each once the one before finished",
+    ],
+    subcommands: &["add", "run", "resume", "list", "rm", "clear", "--keep-going"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn queue<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        match QueueCommand::parse(ctx.rest(1)) {
+            Ok(command) => Ok(queue_result(ctx.runner, command).await),
+            Err(usage) => Ok(ExecuteResult::DirectOutput(vec![usage])),
+        }
+    })
+}
+
+pub(super) static VAULT: CommandSpec = CommandSpec {
+    name: "vault",
+    help: &[
+        "  !vault             Show whether history is encrypted or locked",
+        "  !vault check       Run SQLite's integrity check on the vault",
+        "  !vault recover-report  What was salvaged if the vault was found damaged at startup",
+        "  !vault migrate-data <path>  Copy the vault and data to a new data directory, verified",
+    ],
+    subcommands: &["unlock", "encrypt", "decrypt", "check", "recover-report", "migrate-data"],
+    ..CommandSpec::DEFAULT
+};
+
+pub(super) fn vault<'a>(ctx: &'a Context<'_>) -> Execution<'a> {
+    Box::pin(async move {
+        Ok(ExecuteResult::DirectOutput(match ctx.parts.get(1).copied() {
+            Some("migrate-data") => migrate_data_lines(ctx.runner, ctx.rest(2)).await,
+            Some("check") => vault_check_lines(ctx.runner, &ctx.cancel).await,
+            sub => vault_lines(ctx.runner, sub).await,
+        }))
+    })
+}
+
+/// `!timeline`: the window's commands, for the frontend to lay out.
+fn timeline_result(runner: &Runner, range: TimelineRange) -> ExecuteResult {
+    let utc_offset_secs = chrono::Local::now().offset().local_minus_utc() as i64;
+    let window = range.window(chrono::Utc::now().timestamp(), utc_offset_secs);
+    let records = match runner.vault.timeline_records(window) {
+        Ok(records) => records,
+        Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ Error reading history: {}", e)]),
+    };
+    if records.is_empty() {
+        return ExecuteResult::DirectOutput(vec![format!("🕒 No commands {}", range.label())]);
+    }
+    ExecuteResult::Timeline(TimelineData {
+        label: range.label(),
+        events: records.iter().map(TimelineEvent::from_record).collect(),
+        utc_offset_secs,
+        clock: runner.vault.time_settings().clock,
+    })
+}
+
+/// `!summary`: the range's digest, read off the async threads, with a
+/// recap paragraph when `summary.use_neural` is on and a model answers.
+/// `--save` also writes it to `summary.auto_save_dir`.
+async fn summary_output(runner: &Runner, request: SummaryRequest, cancel: &CancellationToken) -> NativeOutput {
+    let settings = runner.vault.summary_settings();
+    if request.save && settings.auto_save_dir.is_none() {
+        return NativeOutput::Lines(vec![format!("❌ Set {} to save summaries", summary::AUTO_SAVE_DIR_KEY)]);
+    }
+    let sources = runner.summary_sources();
+    let gathered = tokio::task::spawn_blocking(move || {
+        let now = chrono::Utc::now().timestamp();
+        let utc_offset_secs = chrono::Local::now().offset().local_minus_utc() as i64;
+        sources.gather(request.range, now, utc_offset_secs)
+    })
+    .await;
+    let summary = match gathered {
+        Ok(Ok(summary)) => summary,
+        Ok(Err(e)) => return NativeOutput::Lines(vec![format!("❌ Error reading history: {}", e)]),
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Summary failed: {}", e)]),
+    };
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(std::path::PathBuf::from);
+    let mut text = summary::markdown(&summary, &chrono::Local, &runner.vault.time_settings(), home.as_deref());
+
+    if settings.use_neural && summary.commands > 0 {
+        let recap = match runner.neural() {
+            Ok(neural) => {
+                let prompt = summary::recap_prompt(&summary);
+                match neural.ask_smart_cancellable(&prompt, TaskType::General, None, cancel).await {
+                    Ok(reply) => reply.text,
+                    Err(e) => format!("_{}_", ai_error(&e)),
+                }
+            }
+            Err(e) => format!("_❌ {}_", e),
+        };
+        text = summary::with_recap(&text, &recap);
+    }
+
+    if let (true, Some(dir)) = (request.save, settings.auto_save_dir) {
+        let name = summary::save_name(&summary, request.range, &chrono::Local);
+        let line = match summary::save(&dir, &name, &text) {
+            Ok(path) => format!("_Saved to {}_", path.display()),
+            Err(e) => format!("_❌ Could not save: {}_", e),
+        };
+        text = format!("{}\n{}\n", text, line);
+    }
+    NativeOutput::Markdown(text)
+}
+
+/// `!chain [id]`: row `id` (or the latest) and the rows it was edited
+/// from.
+fn chain_lines(runner: &Runner, id: Option<i64>) -> Vec<String> {
+    let id = match id.map(Ok).or_else(|| runner.vault.last_run_id(None).transpose()) {
+        Some(Ok(id)) => id,
+        Some(Err(e)) => return vec![format!("❌ Error reading history: {}", e)],
+        None => return chain::lines(&[]),
+    };
+    let records = match runner.vault.derivation_chain(id) {
+        Ok(records) => records,
+        Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+    };
+    if records.is_empty() {
+        return vec![format!("No command #{} in history", id)];
+    }
+    let steps: Vec<ChainStep> = records
+        .into_iter()
+        .map(|r| ChainStep { label: format!("#{}", r.id.unwrap_or_default()), command: r.command, exit: r.exit_code })
+        .collect();
+    chain::lines(&steps)
+}
+
+/// Rows `!history --at` shows on each side of the one asked for.
+const HISTORY_AT_RADIUS: i64 = 3;
+
+/// `!history --at <id>`: a logged command with its neighbours, marked.
+fn history_at(runner: &Runner, id: Option<&str>) -> NativeOutput {
+    let Some(id) = id.and_then(|id| id.trim_start_matches('#').parse::<i64>().ok()) else {
+        return NativeOutput::Lines(vec!["Usage: !history --at <id>".to_string()]);
+    };
+    let records = match runner.vault.history_around(id, HISTORY_AT_RADIUS) {
+        Ok(records) => records,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Error reading history: {}", e)]),
+    };
+    if !records.iter().any(|r| r.id == Some(id)) {
+        return NativeOutput::Lines(vec![format!("No command #{} in history", id)]);
+    }
+    let clock = runner.vault.time_settings().clock;
+    let mut lines = vec![format!("📜 History around #{}:", id)];
+    for record in &records {
+        let marker = if record.id == Some(id) { "➜" } else { " " };
+        let status = match record.exit_code {
+            Some(0) => "✓".to_string(),
+            Some(code) => format!("✗{}", code),
+            None => "?".to_string(),
+        };
+        // Edited from an earlier block: `!chain` shows the rest.
+        let derived = match record.id.map(|id| runner.vault.derived_from(id)) {
+            Some(Ok(Some(from))) => format!("  ↳ from #{}", from),
+            _ => String::new(),
+        };
+        lines.push(format!(
+            "{} #{:<5} {}  {:<4} {:>8}  {}   ({}){}",
+            marker,
+            record.id.unwrap_or_default(),
+            time_format::clock(record.timestamp, &chrono::Local, clock),
+            status,
+            record.duration_ms.map(timing::format_ms).unwrap_or_else(|| "—".to_string()),
+            record.command,
+            record.directory,
+            derived
+        ));
+    }
+    NativeOutput::Lines(lines)
+}
+
+// ── Tables ──
+//
+// These answer with a `DataFrame` whose text is what the command printed
+// before it had typed output, so frontends without tables see no change.
+/// `!history [n] [--all]`: recent unique commands, newest first, then
+/// (with `--all`) the lines kept out of the vault this session.
+/// `--at <id>` shows one logged run in context instead.
+fn history_output(runner: &Runner, vault: &Vault, parts: &[&str]) -> NativeOutput {
+    if let Some(at) = parts.iter().position(|p| *p == "--at") {
+        return history_at(runner, parts.get(at + 1).copied());
+    }
+    let all = parts.contains(&"--all");
+    let limit = parts.iter().skip(1)
+        .find_map(|s| s.parse::<usize>().ok())
+        .unwrap_or(20);
+    let session_only = if all {
+        runner.session_only.lock().map(|s| s.lines().to_vec()).unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let history = match vault.recent_commands(limit) {
+        Ok(history) => history,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Error reading history: {}", e)]),
+    };
+    if history.is_empty() && session_only.is_empty() {
+        return NativeOutput::Lines(vec!["No command history yet.".to_string()]);
+    }
+
+    let mut frame = DataFrame::new(&[
+        ("#", ColumnKind::Integer),
+        ("command", ColumnKind::Text),
+        ("last_run", ColumnKind::Integer),
+        ("runs", ColumnKind::Integer),
+        ("logged", ColumnKind::Bool),
+    ]);
+    let mut lines = vec![
+        format!("📜 Last {} unique commands:", history.len()),
+        "".to_string(),
+    ];
+    let mut listing = Listing::new("!history");
+    for (i, recent) in history.iter().enumerate() {
+        lines.push(format!("  {:>3}. {}", i + 1, recent.command));
+        listing.push(i as i64 + 1, ItemRef::Command, &recent.command);
+        frame.push_row(vec![
+            Cell::Integer(i as i64 + 1),
+            Cell::text(&recent.command),
+            Cell::Integer(recent.last_run),
+            Cell::Integer(recent.runs),
+            Cell::Bool(true),
+        ]);
+    }
+    if !session_only.is_empty() {
+        lines.push("".to_string());
+        lines.push("🔒 Not logged (this session only):".to_string());
+        let skip = session_only.len().saturating_sub(limit);
+        for cmd in session_only.iter().skip(skip).rev() {
+            lines.push(format!("       {}", cmd));
+            frame.push_row(vec![Cell::Empty, Cell::text(cmd), Cell::Empty, Cell::Empty, Cell::Bool(false)]);
+        }
+    }
+    register_listing(runner, listing);
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// `!top [n]`: the most-run commands.
+fn top_output(runner: &Runner, limit: usize) -> NativeOutput {
+    let top = match runner.vault.top_commands(limit) {
+        Ok(top) => top,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Error: {}", e)]),
+    };
+    if top.is_empty() {
+        return NativeOutput::Lines(vec!["No commands in history yet.".to_string()]);
+    }
+
+    let mut frame = DataFrame::new(&[
+        ("#", ColumnKind::Integer),
+        ("command", ColumnKind::Text),
+        ("count", ColumnKind::Integer),
+    ]);
+    let mut lines = vec![
+        format!("🏆 Top {} commands:", top.len()),
+        "".to_string(),
+    ];
+    for (i, t) in top.iter().enumerate() {
+        lines.push(format!("  {:>3}. {} (×{})", i + 1, t.command, t.count));
+        frame.push_row(vec![Cell::Integer(i as i64 + 1), Cell::text(&t.command), Cell::Integer(t.count)]);
+    }
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// `!here`: the directory's frequent commands, ranked as the hint on
+/// entering it ranks them.
+fn here_lines(runner: &Runner, limit: usize) -> Vec<String> {
+    let cwd = runner
+        .cwd()
+        .or_else(|| runner.vault.last_directory().ok().flatten())
+        .unwrap_or_else(|| std::env::current_dir().map(|d| d.display().to_string()).unwrap_or_default());
+    match runner.vault.top_for_directory(&cwd, limit) {
+        Ok(commands) => here::list_lines(&cwd, &commands),
+        Err(e) => vec![format!("❌ Error: {}", e)],
+    }
+}
+
+/// `!stats`: one row per figure. The text leaves out the ones that are
+/// zero or don't apply; the table always has the counts.
+fn stats_output(runner: &Runner) -> NativeOutput {
+    let session_count = runner.vault.session_command_count().unwrap_or(0);
+    let recent = runner.vault.recent_unique(1000).unwrap_or_default();
+    let suggestions = runner.vault.suggestion_counts().unwrap_or_default();
+    let instances = runner.vault.active_instances().unwrap_or(1);
+    let redos = runner.vault.redo_count().unwrap_or(0);
+
+    let mut frame = DataFrame::new(&[("metric", ColumnKind::Text), ("value", ColumnKind::Integer)]);
+    for (metric, value) in [
+        ("session_commands", session_count),
+        ("unique_commands", recent.len() as i64),
+        ("redos", redos),
+        ("active_instances", instances),
+        ("suggestions_offered", suggestions.offered),
+        ("suggestions_accepted", suggestions.accepted),
+    ] {
+        frame.push_row(vec![Cell::text(metric), Cell::Integer(value)]);
+    }
+
+    let mut lines = vec![
+        "📊 Vault Statistics:".to_string(),
+        "".to_string(),
+        format!("  Session commands:  {}", session_count),
+        format!("  Unique commands:   {}", recent.len()),
+    ];
+    if redos > 0 {
+        lines.push(format!("  Re-runs (!redo):   {}", redos));
+    }
+    if instances > 1 {
+        lines.push(format!("  Active instances:  {} (sharing this vault)", instances));
+    }
+    if let Some(rate) = suggestions.acceptance_rate() {
+        lines.push(format!(
+            "  AI suggestions:    {} accepted of {} ({:.0}%)",
+            suggestions.accepted,
+            suggestions.offered,
+            rate * 100.0
+        ));
+    }
+    lines.push(format!("  {}", runner.data.summary()));
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// `!bookmarks` / `!bm list`, newest first.
+fn bookmarks_output(runner: &Runner) -> NativeOutput {
+    let bookmarks = match runner.vault.list_bookmarks() {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ Error listing bookmarks: {}", e)]),
+    };
+    if bookmarks.is_empty() {
+        return NativeOutput::Lines(vec![
+            "No bookmarks saved.".to_string(),
+            "".to_string(),
+            "Usage: !bookmark [label]  (bookmarks last command)".to_string(),
+        ]);
+    }
+
+    let mut frame = DataFrame::new(&[
+        ("id", ColumnKind::Integer),
+        ("label", ColumnKind::Text),
+        ("command", ColumnKind::Text),
+        ("created_at", ColumnKind::Integer),
+    ]);
+    let mut lines = vec![
+        "🔖 Saved bookmarks:".to_string(),
+        "".to_string(),
+    ];
+    let mut listing = Listing::new("!bm list");
+    for bm in bookmarks {
+        let label_str = bm.label.as_ref()
+            .map(|l| format!(" [{}]", l))
+            .unwrap_or_default();
+        lines.push(format!("  #{}{}:", bm.id, label_str));
+        lines.push(format!("    {}", bm.command));
+        listing.push(bm.id, ItemRef::Bookmark(bm.id), &bm.command);
+        let label = bm.label.map_or(Cell::Empty, Cell::Text);
+        frame.push_row(vec![Cell::Integer(bm.id), label, Cell::Text(bm.command), Cell::Integer(bm.created_at)]);
+    }
+    register_listing(runner, listing);
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// Make `listing`'s numbers the ones `!pick` goes by.
+fn register_listing(runner: &Runner, listing: Listing) {
+    runner.picks.lock().unwrap_or_else(|e| e.into_inner()).register(listing);
+}
+
+/// `!pick`: select from the last listing, or act on the selection.
+/// `!pick run --yes` is `pick_run`.
+fn pick_lines(runner: &Runner, command: PickCommand) -> Vec<String> {
+    let mut picks = runner.picks.lock().unwrap_or_else(|e| e.into_inner());
+    if let PickCommand::Select(numbers) = &command {
+        return match picks.select(numbers) {
+            Ok(items) => {
+                let mut lines = vec![format!("📌 Picked {}:", items.len())];
+                lines.extend(pick::item_lines(items));
+                lines.push("  !pick run | bookmark | export <path> | delete".to_string());
+                lines
+            }
+            Err(message) => vec![message],
+        };
+    }
+    if command == PickCommand::Clear {
+        picks.clear();
+        return vec!["📌 Selection cleared".to_string()];
+    }
+    let (source, items) = match picks.selection() {
+        Ok((source, items)) => (source, items.to_vec()),
+        Err(message) => return vec![message],
+    };
+
+    match command {
+        PickCommand::Show => {
+            let mut lines = vec![format!("📌 Picked {} from the last {} listing:", items.len(), source)];
+            lines.extend(pick::item_lines(&items));
+            lines
+        }
+        PickCommand::Run { keep_going, .. } => {
+            if let Err(lines) = runnable(runner, &items) {
+                return lines;
+            }
+            let order = if keep_going { "one after another" } else { "in order, stopping at the first failure" };
+            let mut lines = vec![format!("▶ This runs {} {}:", pick::counted(items.len(), "command", "commands"), order)];
+            lines.extend(pick::item_lines(&items));
+            let flag = if keep_going { " --keep-going" } else { "" };
+            lines.push(format!("Run `!pick run{} --yes` to start", flag));
+            lines
+        }
+        PickCommand::Bookmark => {
+            let mut added = 0;
+            for item in items.iter().filter(|item| !matches!(item.item, ItemRef::Bookmark(_))) {
+                if let Err(e) = runner.vault.add_bookmark(&item.command, None) {
+                    return vec![format!("❌ Error saving bookmark: {}", e)];
+                }
+                added += 1;
+            }
+            let bookmarked = pick::counted(added, "command", "commands");
+            match items.len() - added {
+                0 => vec![format!("🔖 Bookmarked {}", bookmarked)],
+                skipped => vec![format!("🔖 Bookmarked {} ({} already bookmarked)", bookmarked, skipped)],
+            }
+        }
+        PickCommand::Export(path) => {
+            let path = match runner.cwd() {
+                Some(cwd) => std::path::Path::new(&cwd).join(&path),
+                None => std::path::PathBuf::from(&path),
+            };
+            match std::fs::write(&path, pick::export_body(&items)) {
+                Ok(()) => vec![format!("📜 Exported {} to {}", pick::counted(items.len(), "command", "commands"), path.display())],
+                Err(e) => vec![format!("❌ Export failed: {}: {}", path.display(), e)],
+            }
+        }
+        PickCommand::Delete { yes: false } => {
+            let mut lines = vec![format!("⚠️ This permanently deletes {} entries of the last {} listing:", items.len(), source)];
+            lines.extend(pick::item_lines(&items));
+            if items.iter().any(|item| item.item == ItemRef::Command) {
+                lines.push("  (every logged run of each command)".to_string());
+            }
+            lines.push("Run `!pick delete --yes` to delete them".to_string());
+            lines
+        }
+        PickCommand::Delete { yes: true } => {
+            let lines = delete_picked(runner, &items);
+            // The listing's numbers no longer match what is stored.
+            picks.forget();
+            lines
+        }
+        PickCommand::Select(_) | PickCommand::Clear => unreachable!("handled above"),
+    }
+}
+
+/// The selection's commands if all of them may be run; otherwise why not.
+fn runnable(runner: &Runner, items: &[ListedItem]) -> Result<Vec<String>, Vec<String>> {
+    let refusals = pick::run_refusals(items, |c| runner.generation_request(c).is_some());
+    if !refusals.is_empty() {
+        let mut lines = vec!["❌ Not running the selection; run these yourself or pick without them:".to_string()];
+        lines.extend(refusals);
+        return Err(lines);
+    }
+    Ok(items.iter().map(|item| item.command.clone()).collect())
+}
+
+/// `!pick run --yes [--keep-going]`: send the selection to the shell as
+/// one line.
+async fn pick_run(runner: &Runner, keep_going: bool) -> Result<ExecuteResult> {
+    let selected = runner.picks.lock().unwrap_or_else(|e| e.into_inner()).selection().map(|(_, items)| items.to_vec());
+    let items = match selected {
+        Ok(items) => items,
+        Err(message) => return Ok(ExecuteResult::DirectOutput(vec![message])),
+    };
+    match runnable(runner, &items) {
+        Ok(commands) => runner.run_picked(&commands, keep_going).await,
+        Err(lines) => Ok(ExecuteResult::DirectOutput(lines)),
+    }
+}
+
+/// `!queue`: change the list, or start or resume a run.
+async fn queue_result(runner: &Runner, command: QueueCommand) -> ExecuteResult {
+    let lines = match command {
+        QueueCommand::Run { keep_going, yes } => return queue_start(runner, false, keep_going, yes).await,
+        QueueCommand::Resume { yes } => return queue_start(runner, true, false, yes).await,
+        QueueCommand::List => match runner.vault.queue() {
+            Ok(commands) => queue::list_lines(&commands, runner.vault.queue_progress().ok().flatten().as_ref()),
+            Err(e) => vec![format!("❌ Error reading the queue: {}", e)],
+        },
+        QueueCommand::Clear => {
+            // Also the way out of a run whose command never reports its end.
+            let stopped = runner.queue.lock().unwrap_or_else(|e| e.into_inner()).take().is_some();
+            let mut lines = match runner.vault.queue_clear() {
+                Ok(0) => vec!["📋 The queue is already empty".to_string()],
+                Ok(n) => vec![format!("🗑 Cleared {}", pick::counted(n, "queued command", "queued commands"))],
+                Err(e) => vec![format!("❌ Error clearing the queue: {}", e)],
+            };
+            if stopped {
+                lines.insert(0, "⏹ Stopped the queue run".to_string());
+            }
+            lines
+        }
+        _ if runner.queue_waiting().is_some() => {
+            vec!["⏳ The queue is running; change it once the run is over".to_string()]
+        }
+        QueueCommand::Add(command) if runner.generation_request(&command).is_some() => {
+            vec!["❌ A generation request can't be queued; generate the command, then queue it".to_string()]
+        }
+        QueueCommand::Add(command) => match runner.vault.queue_add(&command) {
+            Ok(Some(n)) => vec![format!("➕ Queued as {}: {}", n, command)],
+            Ok(None) => vec![format!("❌ The queue is full ({} commands)", queue::MAX_QUEUED)],
+            Err(e) => vec![format!("❌ Error saving to the queue: {}", e)],
+        },
+        QueueCommand::Remove(n) => match runner.vault.queue_remove(n) {
+            Ok(Some(command)) => vec![format!("🗑 Removed {}: {}", n, command)],
+            Ok(None) => vec![format!("❌ The queue has no entry {}", n)],
+            Err(e) => vec![format!("❌ Error removing from the queue: {}", e)],
+        },
+    };
+    ExecuteResult::DirectOutput(lines)
+}
+
+/// `!queue run` or `!queue resume`. Destructive entries still to run are
+/// listed for confirmation first, unless `yes`.
+async fn queue_start(runner: &Runner, resume: bool, keep_going: bool, yes: bool) -> ExecuteResult {
+    let output = |line: String| ExecuteResult::DirectOutput(vec![line]);
+    if let Some(line) = runner.queue_waiting() {
+        return output(format!("⏳ The queue is already running, at {}", line.trim_start_matches("▶ ")));
+    }
+    let commands = match runner.vault.queue() {
+        Ok(commands) if commands.is_empty() => {
+            return output("📋 The queue is empty; !queue add <command> adds one".to_string());
+        }
+        Ok(commands) => commands,
+        Err(e) => return output(format!("❌ Error reading the queue: {}", e)),
+    };
+    let run = if resume {
+        match runner.vault.queue_progress() {
+            Ok(Some(progress)) if progress.next < commands.len() => QueueRun::resume(commands, progress),
+            Ok(_) => return output("⏸ No stopped queue run to resume; !queue run starts one".to_string()),
+            Err(e) => return output(format!("❌ Error reading the queue: {}", e)),
+        }
+    } else {
+        QueueRun::new(commands, keep_going)
+    };
+
+    let shell = run.remaining().iter().any(|c| !c.starts_with('!'));
+    if shell && !runner.running.lock().unwrap_or_else(|e| e.into_inner()).is_integrated() {
+        return ExecuteResult::DirectOutput(vec![
+            "❌ The queue waits for each shell command's exit code, which needs shell integration".to_string(),
+            "   !integrate sets it up; a queue of ! commands runs without it".to_string(),
+        ]);
+    }
+    let destructive = queue::destructive_lines(&run);
+    if !yes && !destructive.is_empty() {
+        let mut lines = vec!["⚠️ The queue runs destructive commands:".to_string()];
+        lines.extend(destructive);
+        let again = match (resume, keep_going) {
+            (true, _) => "resume",
+            (false, true) => "run --keep-going",
+            (false, false) => "run",
+        };
+        lines.push(format!("Run `!queue {} --yes` to start", again));
+        return ExecuteResult::DirectOutput(lines);
+    }
+    runner.run_queue(run).await
+}
+
+/// `!pick delete --yes`: history rows, bookmarks or tags, by kind.
+fn delete_picked(runner: &Runner, items: &[ListedItem]) -> Vec<String> {
+    let vault = &runner.vault;
+    let commands: Vec<&str> = items.iter().filter(|i| i.item == ItemRef::Command).map(|i| i.command.as_str()).collect();
+    let records: Vec<i64> = items.iter().filter_map(|i| match i.item {
+        ItemRef::Record(id) => Some(id),
+        _ => None,
+    }).collect();
+    let mut lines = Vec::new();
+    if !commands.is_empty() || !records.is_empty() {
+        match vault.delete_commands(&commands).and_then(|runs| Ok(runs + vault.delete_records(&records)?)) {
+            Ok(rows) => lines.push(format!("🗑 Deleted {}", pick::counted(rows, "history entry", "history entries"))),
+            Err(e) => lines.push(format!("❌ Error deleting history: {}", e)),
+        }
+    }
+    let mut bookmarks = 0;
+    let mut tags = 0;
+    for item in items {
+        let removed = match &item.item {
+            ItemRef::Bookmark(id) => vault.remove_bookmark(*id).map(|gone| bookmarks += usize::from(gone)),
+            ItemRef::Tag(name) => vault.remove_tag(name).map(|gone| tags += usize::from(gone)),
+            ItemRef::Command | ItemRef::Record(_) => Ok(()),
+        };
+        if let Err(e) = removed {
+            lines.push(format!("❌ Error deleting {}: {}", item.command, e));
+        }
+    }
+    if bookmarks > 0 {
+        lines.push(format!("🗑 Deleted {}", pick::counted(bookmarks, "bookmark", "bookmarks")));
+    }
+    if tags > 0 {
+        lines.push(format!("🗑 Deleted {}", pick::counted(tags, "tag", "tags")));
+    }
+    lines
+}
+
+const EXPORT_USAGE: &str = "Usage: !export <path> [--pane <n> | --all-panes]";
+const SEARCH_USAGE: &str = "Usage: !search <query> [--pane <n> | --all-panes]";
+const HISTORY_USAGE: &str = "Usage: !history [n] [--all] [--pane <n> | --all-panes]";
+
+/// The history a command reads: this pane's (every pane's outside a
+/// split), split pane `n`'s with `--pane <n>`, every pane's with
+/// `--all-panes`. Returns `parts` without those flags.
+fn pane_scope<'p>(runner: &Runner, parts: &[&'p str], usage: &str) -> Result<(Vault, Vec<&'p str>), String> {
+    let mut vault = runner.vault.clone();
+    let mut rest = Vec::with_capacity(parts.len());
+    let mut iter = parts.iter();
+    while let Some(&part) = iter.next() {
+        match part {
+            "--all-panes" => vault = vault.all_panes(),
+            "--pane" => match iter.next().and_then(|n| n.parse::<u32>().ok()) {
+                Some(pane) => vault = vault.for_pane(pane),
+                None => return Err(usage.to_string()),
+            },
+            _ => rest.push(part),
+        }
+    }
+    Ok((vault, rest))
+}
+
+/// `!export <path>`: the whole history as `# <timestamp>` / command pairs,
+/// relative to the shell's working directory.
+fn export_history_lines(runner: &Runner, vault: &Vault, path: &str) -> Vec<String> {
+    let path = match runner.cwd() {
+        Some(cwd) => std::path::Path::new(&cwd).join(path),
+        None => std::path::PathBuf::from(path),
+    };
+    let lines = match vault.export_history(i64::MAX as usize) {
+        Ok(lines) => lines,
+        Err(e) => return vec![format!("❌ Export failed: {}", e)],
+    };
+    let mut body = lines.join("\n");
+    body.push('\n');
+    match std::fs::write(&path, body) {
+        Ok(()) => vec![format!("📜 Exported {} commands to {}", lines.len(), path.display())],
+        Err(e) => vec![format!("❌ Export failed: {}: {}", path.display(), e)],
+    }
+}
+
+/// `!sync export|import <path>`, relative to the shell's working
+/// directory like `!export`.
+async fn sync_lines(runner: &Runner, args: &[&str], cancel: &CancellationToken) -> Vec<String> {
+    let (sub, path) = match args {
+        [sub @ ("export" | "import"), path] => (*sub, *path),
+        _ => return vec![sync::SYNC_USAGE.to_string()],
+    };
+    let path = match runner.cwd() {
+        Some(cwd) => std::path::Path::new(&cwd).join(path),
+        None => std::path::PathBuf::from(path),
+    };
+    if sub == "export" {
+        match sync::export(&runner.vault, &path) {
+            Ok(report) => sync::export_lines(&report, runner.vault.lock_state()),
+            Err(e) => vec![format!("❌ Sync export failed: {:#}", e)],
+        }
+    } else {
+        sync_import_lines(runner, path, cancel).await
+    }
+}
+
+/// `!sync import` on the blocking pool, as a task: the bar shows how many
+/// rows are written, and cancelling rolls the import back.
+async fn sync_import_lines(runner: &Runner, path: std::path::PathBuf, cancel: &CancellationToken) -> Vec<String> {
+    let task = runner.progress.begin("Sync import", None, cancel.clone());
+    let (vault, reporter, from) = (runner.vault.clone(), task.reporter(), path.clone());
+    let imported = tokio::task::spawn_blocking(move || {
+        sync::import_with(&vault, &from, |done, total| {
+            reporter.set_total(total as u64);
+            reporter.set(done as u64);
+            !reporter.is_cancelled()
+        })
+    })
+    .await;
+    match imported {
+        Ok(Ok(report)) => {
+            task.finish(Outcome::Done(format!("{} commands", report.plan.history.len())));
+            sync::import_lines(&report, &path)
+        }
+        Ok(Err(e)) => {
+            task.finish(Outcome::Failed(format!("{:#}", e)));
+            vec![format!("❌ Sync import failed: {:#}", e)]
+        }
+        Err(e) => {
+            task.finish(Outcome::Failed(e.to_string()));
+            vec![format!("❌ Sync import failed: {}", e)]
+        }
+    }
+}
+
+/// `!vault`: the encryption state, and the recovery notice if the file was
+/// found damaged at startup. Subcommands that take a passphrase are
+/// answered by the UI's masked prompt and only land here without one.
+pub(super) async fn vault_lines(runner: &Runner, sub: Option<&str>) -> Vec<String> {
+    match sub {
+        None | Some("status") => {}
+        Some("recover-report") => {
+            return match runner.vault.recovery() {
+                Some(report) => report.report_lines(),
+                None => vec!["🩹 The vault opened cleanly; nothing was recovered this session".to_string()],
+            };
+        }
+        Some("unlock" | "encrypt" | "decrypt") => {
+            return vec![format!(
+                "🔐 !vault {} asks for the passphrase in the Positronic window (or set {} at startup to unlock)",
+                sub.unwrap_or_default(),
+                crypto::PASSPHRASE_ENV
+            )];
+        }
+        Some(other) => {
+            return vec![format!("Usage: !vault [unlock|encrypt|decrypt|check|recover-report|migrate-data] (unknown: {})", other)];
+        }
+    }
+    let state = match runner.vault.lock_state() {
+        LockState::Plain => "🔓 History is stored in plain text. !vault encrypt to encrypt it.",
+        LockState::Locked => VAULT_LOCKED,
+        LockState::Unlocked => "🔐 History is encrypted and unlocked. !vault decrypt to store it in plain text.",
+    };
+    let mut lines = vec![state.to_string()];
+    lines.extend(runner.vault.recovery().map(|report| report.notice()));
+    lines
+}
+
+/// `!vault migrate-data <path>`, relative to the shell's working
+/// directory like `!export`, on the blocking pool.
+async fn migrate_data_lines(runner: &Runner, path: &str) -> Vec<String> {
+    if path.is_empty() {
+        return vec![data_paths::MIGRATE_USAGE.to_string()];
+    }
+    let to = match runner.cwd() {
+        Some(cwd) => std::path::Path::new(&cwd).join(path),
+        None => std::path::PathBuf::from(path),
+    };
+    let (vault, from) = (runner.vault.clone(), runner.data.clone());
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(std::path::Path::to_path_buf));
+    let migrated = tokio::task::spawn_blocking(move || {
+        data_paths::migrate(&from, &to, exe_dir.as_deref(), |copy| match vault.copy_to(copy) {
+            Ok(problems) if problems.is_empty() => Ok(()),
+            Ok(problems) => Err(std::io::Error::other(format!("the vault copy failed its check: {}", problems.join("; ")))),
+            Err(e) => Err(std::io::Error::other(e)),
+        })
+    })
+    .await;
+    match migrated {
+        Ok(Ok(migration)) => migration.lines(),
+        Ok(Err(e)) => vec![format!("❌ Data migration failed, nothing was switched: {}", e)],
+        Err(e) => vec![format!("❌ Data migration failed, nothing was switched: {}", e)],
+    }
+}
+
+/// `!vault check`: `PRAGMA integrity_check` on the blocking pool, as a
+/// task with a spinner. The window also shows `recover::CHECK_PROGRESS`
+/// while it runs. SQLite can't stop the check midway: cancelled, it
+/// finishes unseen.
+async fn vault_check_lines(runner: &Runner, cancel: &CancellationToken) -> Vec<String> {
+    let task = runner.progress.begin("Vault check", None, cancel.clone());
+    let vault = runner.vault.clone();
+    let problems = match tokio::task::spawn_blocking(move || vault.integrity_check()).await {
+        Ok(Ok(problems)) => problems,
+        Ok(Err(e)) => {
+            task.finish(Outcome::Failed(e.to_string()));
+            return vec![format!("❌ Integrity check failed: {}", e)];
+        }
+        Err(e) => {
+            task.finish(Outcome::Failed(e.to_string()));
+            return vec![format!("❌ Integrity check failed: {}", e)];
+        }
+    };
+    if problems.iter().all(|p| p == "ok") {
+        task.finish(Outcome::Done("ok".to_string()));
+        return vec!["🩺 Vault integrity: ok".to_string()];
+    }
+    task.finish(Outcome::Done(format!("{} problem(s)", problems.len())));
+    let mut lines = vec![format!(
+        "🩺 Vault integrity: {} problem(s){}",
+        problems.len(),
+        if problems.len() >= recover::CHECK_LIMIT { " (first ones only)" } else { "" }
+    )];
+    lines.extend(problems.iter().map(|p| format!("  {}", p)));
+    lines.push("   Back up vault.db now; if it stops opening, Positronic moves it aside and salvages what reads".to_string());
+    lines
+}
+
+/// Runs `!stats trend` fits a line through.
+const TREND_RUNS: usize = 20;
+
+/// `!stats slow [n]`: programs run more than once, slowest mean first.
+fn slow_lines(runner: &Runner, limit: usize) -> Vec<String> {
+    let stats = match runner.vault.duration_stats("") {
+        Ok(stats) => stats,
+        Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+    };
+    let recurring: Vec<_> = stats.into_iter().filter(|s| s.count > 1).take(limit).collect();
+    if recurring.is_empty() {
+        return vec!["No timed commands have run more than once yet.".to_string()];
+    }
+
+    let mut lines = vec![
+        format!("🐢 Slowest {} recurring commands:", recurring.len()),
+        "".to_string(),
+        format!("  {:<16} {:>6} {:>9} {:>9}", "command", "runs", "mean", "p95"),
+    ];
+    for s in &recurring {
+        lines.push(format!(
+            "  {:<16} {:>6} {:>9} {:>9}",
+            s.program,
+            s.count,
+            timing::format_ms(s.mean_ms.round() as i64),
+            timing::format_ms(s.p95_ms)
+        ));
+    }
+    lines
+}
+
+/// `!stats export [json|csv] [path]`: the analytics report as one JSON
+/// file, or one CSV per section in a directory, then a short summary.
+/// Relative paths are taken from the shell's working directory.
+async fn export_stats(runner: &Runner, args: &[&str]) -> Vec<String> {
+    let (csv, path) = match args.first() {
+        Some(&"csv") => (true, &args[1..]),
+        Some(&"json") => (false, &args[1..]),
+        _ => (false, args),
+    };
+    let path = export_path(runner, path, "positronic-stats", if csv { "" } else { ".json" });
+
+    let vault = runner.vault.clone();
+    let target = path.clone();
+    let exported = tokio::task::spawn_blocking(move || -> Result<AnalyticsReport> {
+        let report = vault.analytics_report()?;
+        if csv {
+            analytics::write_csv_dir(&report, &target)?;
+        } else {
+            let file = std::io::BufWriter::new(std::fs::File::create(&target)?);
+            serde_json::to_writer_pretty(file, &report)?;
+        }
+        Ok(report)
+    })
+    .await;
+
+    match exported {
+        Ok(Ok(report)) => {
+            let mut lines = vec![format!("📊 Analytics exported to {}", path.display()), "".to_string()];
+            lines.extend(analytics::summary_lines(&report));
+            lines
+        }
+        Ok(Err(e)) => vec![format!("❌ Export failed: {}", e)],
+        Err(e) => vec![format!("❌ Export failed: {}", e)],
+    }
+}
+
+/// Where `!stats` exports go: `path` (words joined) or today's
+/// `<stem>-YYYY-MM-DD<ext>`, under the working directory.
+fn export_path(runner: &Runner, path: &[&str], stem: &str, ext: &str) -> std::path::PathBuf {
+    let path = if path.is_empty() {
+        format!("{}-{}{}", stem, chrono::Local::now().format("%Y-%m-%d"), ext)
+    } else {
+        path.join(" ")
+    };
+    match runner.cwd() {
+        Some(cwd) => std::path::Path::new(&cwd).join(path),
+        None => std::path::PathBuf::from(path),
+    }
+}
+
+/// `!stats heatmap`: counted and folded off the async runtime, written
+/// out beside `!stats export`'s files with `--json`.
+async fn heatmap_lines(runner: &Runner, args: &[&str]) -> Vec<String> {
+    let request = match HeatmapRequest::parse(args) {
+        Ok(request) => request,
+        Err(usage) => return vec![usage],
+    };
+    let target = request.json.then(|| export_path(runner, &[], "positronic-heatmap", ".json"));
+    let (vault, path) = (runner.vault.clone(), target.clone());
+    let built = tokio::task::spawn_blocking(move || -> Result<Heatmap> {
+        let now = chrono::Utc::now().timestamp();
+        let slots = vault.command_slots(request.prefix.as_deref(), request.since(now, &chrono::Local))?;
+        let heatmap = Heatmap::build(&request, &slots, &chrono::Local, now);
+        if let Some(path) = path {
+            let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+            serde_json::to_writer_pretty(file, &heatmap.export(now))?;
+        }
+        Ok(heatmap)
+    })
+    .await;
+
+    match built {
+        Ok(Ok(heatmap)) => {
+            let mut lines = heatmap.lines();
+            if let Some(path) = target {
+                lines.insert(0, format!("📊 Heatmap exported to {}", path.display()));
+            }
+            lines
+        }
+        Ok(Err(e)) => vec![format!("❌ Error reading history: {}", e)],
+        Err(e) => vec![format!("❌ Error reading history: {}", e)],
+    }
+}
+
+/// `!stats trend <command>`: linear trend over its last `TREND_RUNS` runs.
+fn trend_lines(runner: &Runner, command: &str) -> Vec<String> {
+    let runs = match runner.vault.recent_runs(command, TREND_RUNS) {
+        Ok(runs) => runs,
+        Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+    };
+    let Some(trend) = timing::duration_trend(&runs) else {
+        return vec![format!(
+            "📈 '{}' needs at least {} timed runs for a trend ({} so far).",
+            command,
+            timing::MIN_TREND_RUNS,
+            runs.len()
+        )];
+    };
+
+    let verdict = match trend.direction() {
+        TrendDirection::Slower => "🐢 getting slower",
+        TrendDirection::Faster => "🚀 getting faster",
+        TrendDirection::Steady => "➖ steady",
+    };
+    let mean = runs.iter().map(|r| r.duration_ms).sum::<i64>() / runs.len() as i64;
+    vec![
+        format!("📈 Trend for '{}' over the last {} runs: {}", command, trend.runs, verdict),
+        "".to_string(),
+        format!(
+            "  Fitted:  {} → {} ({:+.0}%)",
+            timing::format_ms(trend.first_ms.round() as i64),
+            timing::format_ms(trend.last_ms.round() as i64),
+            trend.change_pct()
+        ),
+        format!("  Mean:    {}", timing::format_ms(mean)),
+        format!(
+            "  Latest:  {}",
+            timing::format_ms(runs.last().map(|r| r.duration_ms).unwrap_or(0))
+        ),
+    ]
+}
+
+/// `!tests`: the last test run's card, or its failures' re-run for the
+/// input line.
+fn tests_result(runner: &Runner, command: TestsCommand) -> ExecuteResult {
+    let run = match runner.vault.last_test_run() {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return ExecuteResult::DirectOutput(vec![
+                "🧪 No test run yet; cargo test and pytest runs are summarized when they finish".to_string(),
+            ])
+        }
+        Err(e) => return ExecuteResult::DirectOutput(vec![format!("❌ Error reading test runs: {}", e)]),
+    };
+    match command {
+        TestsCommand::Show(expand) => ExecuteResult::DirectOutput(test_report::show_lines(&run, expand, &runner.vault.time_settings())),
+        TestsCommand::Failed => match runner.test_parsers.rerun(&run) {
+            Some(rerun) => ExecuteResult::EditCommand(rerun),
+            None if run.report.failures.is_empty() => {
+                ExecuteResult::DirectOutput(vec![format!("🧪 Nothing failed in the last run ({})", run.report.counts())])
+            }
+            None => ExecuteResult::DirectOutput(vec![format!("🧪 No parser for {} here", run.report.framework)]),
+        },
+    }
+}
+
+/// How long `!diff --watch` lets its command run.
+const WATCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `!diff`: resolve the two outputs to compare, then render the diff.
+async fn diff_output(runner: &Runner, request: DiffRequest, opts: &DiffOptions) -> Vec<String> {
+    let vault = &runner.vault;
+    let pair = match request {
+        DiffRequest::Last => match vault.last_with_output(None, None) {
+            Ok(Some(new)) => match vault.last_with_output(Some(&new.command), new.id) {
+                Ok(Some(old)) => Ok((old, new)),
+                Ok(None) => Err(format!("🔀 '{}' has no earlier run with stored output.", new.command)),
+                Err(e) => Err(format!("❌ Error reading history: {}", e)),
+            },
+            Ok(None) => Err("🔀 No command output stored yet.".to_string()),
+            Err(e) => Err(format!("❌ Error reading history: {}", e)),
+        },
+        DiffRequest::Ids(a, b) => operand_pair(runner, DiffOperand::Id(a), DiffOperand::Id(b)),
+        DiffRequest::Tagged(a, b) => operand_pair(runner, a, b),
+        DiffRequest::Watch(command) => watch_pair(runner, &command).await,
+    };
+    let (old, new) = match pair {
+        Ok(pair) => pair,
+        Err(message) => return vec![message],
+    };
+
+    let diff = diff::diff_lines(
+        old.output.as_deref().unwrap_or_default(),
+        new.output.as_deref().unwrap_or_default(),
+        opts,
+    );
+    let clock = runner.vault.time_settings().clock;
+    let mut lines = vec![
+        format!("🔀 Diff {} → {}", record_label(&old, clock), record_label(&new, clock)),
+        "".to_string(),
+    ];
+    if let Some((old_len, new_len)) = diff.truncated {
+        lines.push(format!(
+            "  ⚠️ Outputs have {} and {} lines; compared the first {} of each",
+            old_len, new_len, opts.max_lines
+        ));
+    }
+    if diff.is_empty() {
+        lines.push(if opts.ignore_space {
+            "  No differences (ignoring whitespace)".to_string()
+        } else {
+            "  No differences".to_string()
+        });
+        return lines;
+    }
+    lines.extend(diff.display_lines());
+    lines.push("".to_string());
+    lines.push(format!("  {} added, {} removed", diff.added, diff.removed));
+    lines
+}
+
+/// `!diff <a> <b>`: both sides by history id or tag.
+fn operand_pair(runner: &Runner, a: DiffOperand, b: DiffOperand) -> Result<(CommandRecord, CommandRecord), String> {
+    let side = |operand| match operand {
+        DiffOperand::Id(id) => stored_output(runner, id),
+        DiffOperand::Tag(name) => tagged_output(runner, &name),
+    };
+    Ok((side(a)?, side(b)?))
+}
+
+/// A tagged output as a record to compare; the command carries the tag
+/// name so the diff header says which side is which.
+fn tagged_output(runner: &Runner, name: &str) -> Result<CommandRecord, String> {
+    match runner.vault.get_tag(name) {
+        Ok(Some(tag)) => Ok(CommandRecord {
+            id: tag.history_id,
+            session_id: String::new(),
+            command: format!("{} [{}]", tag.command, tag.name),
+            output: Some(tag.output),
+            exit_code: tag.exit_code,
+            directory: tag.directory,
+            duration_ms: None,
+            timestamp: tag.ran_at,
+        }),
+        Ok(None) => Err(format!("❌ No tag named '{}'", name)),
+        Err(e) => Err(format!("❌ Error reading tags: {}", e)),
+    }
+}
+
+/// A history entry that has output to compare.
+fn stored_output(runner: &Runner, id: i64) -> Result<CommandRecord, String> {
+    match runner.vault.get_record(id) {
+        Ok(Some(record)) if record.output.is_some() => Ok(record),
+        Ok(Some(_)) => Err(format!("🔀 #{} has no stored output.", id)),
+        Ok(None) => Err(format!("❌ No history entry #{}", id)),
+        Err(e) => Err(format!("❌ Error reading history: {}", e)),
+    }
+}
+
+/// `!diff --watch`: run `command` now, store the run, and pair it with the
+/// run stored before it.
+async fn watch_pair(runner: &Runner, command: &str) -> Result<(CommandRecord, CommandRecord), String> {
+    if DangerAnalyzer::is_destructive(command) {
+        return Err(format!("❌ Refusing to re-run a destructive command: {}", command));
+    }
+    let previous = runner
+        .vault
+        .last_with_output(Some(command), None)
+        .map_err(|e| format!("❌ Error reading history: {}", e))?;
+
+    let cwd = runner.cwd().unwrap_or_else(|| ".".to_string());
+    let env = runner.env_overlay();
+    let run = usage::capture(command, &cwd, &env, Some(WATCH_TIMEOUT)).await.map_err(|e| format!("❌ {}", e))?;
+    log_captured(runner, command, &cwd, &run).map_err(|e| format!("❌ Error saving run: {}", e))?;
+
+    let Some(old) = previous else {
+        return Err(format!(
+            "🔀 Stored the first run of '{}'; run !diff --watch again to compare.",
+            command
+        ));
+    };
+    match runner.vault.last_with_output(Some(command), None) {
+        Ok(Some(new)) => Ok((old, new)),
+        Ok(None) => Err("❌ The new run was not stored.".to_string()),
+        Err(e) => Err(format!("❌ Error reading history: {}", e)),
+    }
+}
+
+/// `!tag`: name a stored output, list the names, or drop one. Pointing
+/// an existing name at a different run needs `--force`.
+fn tag_lines(runner: &Runner, command: TagCommand) -> Vec<String> {
+    let vault = &runner.vault;
+    match command {
+        TagCommand::List => match vault.list_tags() {
+            Ok(list) => {
+                let mut listing = Listing::new("!tag list");
+                for (i, tag) in list.iter().enumerate() {
+                    listing.push(i as i64 + 1, ItemRef::Tag(tag.name.clone()), &tag.command);
+                }
+                register_listing(runner, listing);
+                tags::list_lines(&list, &vault.time_settings())
+            }
+            Err(e) => vec![format!("❌ Error reading tags: {}", e)],
+        },
+        TagCommand::Remove(name) => match vault.remove_tag(&name) {
+            Ok(true) => vec![format!("🏷️ Removed tag '{}'", name)],
+            Ok(false) => vec![format!("No tag named '{}'", name)],
+            Err(e) => vec![format!("❌ Error: {}", e)],
+        },
+        TagCommand::Set { name, id, force } => {
+            let found = match id {
+                Some(id) => vault.get_record(id),
+                None => vault.last_with_output(None, None),
+            };
+            let record = match found {
+                Ok(Some(record)) => record,
+                Ok(None) => {
+                    return vec![match id {
+                        Some(id) => format!("❌ No history entry #{}", id),
+                        None => "🏷️ No command output stored yet.".to_string(),
+                    }];
+                }
+                Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+            };
+            let time = vault.time_settings();
+            match vault.get_tag(&name) {
+                Ok(Some(existing)) if existing.history_id != record.id && !force => {
+                    let target = id.map(|id| format!(" {}", id)).unwrap_or_default();
+                    return vec![
+                        format!("🏷️ '{}' already tags {}", name, tags::origin(&existing, &time)),
+                        format!("   !tag {}{} --force points it at {} instead", name, target, record_label(&record, time.clock)),
+                    ];
+                }
+                Ok(_) => {}
+                Err(e) => return vec![format!("❌ Error reading tags: {}", e)],
+            }
+            match vault.set_tag(&name, &record) {
+                Ok(()) => vec![format!("🏷️ Tagged {} as '{}'", record_label(&record, time.clock), name)],
+                Err(e) => vec![format!("❌ Error saving tag: {}", e)],
+            }
+        }
+    }
+}
+
+/// `!redo`: find the entry, then run it or ask where.
+async fn redo_request(runner: &Runner, request: RedoRequest) -> Result<ExecuteResult> {
+    let found = match &request.target {
+        RedoTarget::Id(id) => runner.vault.get_record(*id),
+        // Newest first; `!` lines can't be re-run.
+        RedoTarget::Search(query) => runner
+            .vault
+            .search_history(query)
+            .map(|records| records.into_iter().find(|r| !r.command.starts_with('!'))),
+    };
+    let record = match found {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            let what = match &request.target {
+                RedoTarget::Id(id) => format!("#{}", id),
+                RedoTarget::Search(query) => format!("'{}'", query),
+            };
+            return Ok(ExecuteResult::DirectOutput(vec![format!("❌ No history entry {}", what)]));
+        }
+        Err(e) => return Ok(ExecuteResult::DirectOutput(vec![format!("❌ Error reading history: {}", e)])),
+    };
+    if record.command.starts_with('!') || runner.generation_request(&record.command).is_some() {
+        return Ok(ExecuteResult::DirectOutput(vec![format!(
+            "❌ !redo re-runs shell commands; run '{}' again yourself",
+            record.command
+        )]));
+    }
+
+    let danger = DangerAnalyzer::analyze(&record.command);
+    let reason = danger.is_destructive().then(|| danger.reason.unwrap_or("destructive").to_string());
+    let offer = RedoOffer::new(record.id.unwrap_or(0), &record.command, &record.directory, runner.cwd(), reason);
+    match offer.decide(request.choice) {
+        Ok(Some(choice)) => runner.redo(&offer, choice).await,
+        Ok(None) => Ok(ExecuteResult::RedoOffer(offer)),
+        Err(message) => Ok(ExecuteResult::DirectOutput(vec![message])),
+    }
+}
+
+/// Store a captured run with what it used.
+fn log_captured(runner: &Runner, command: &str, cwd: &str, run: &usage::Captured) -> rusqlite::Result<()> {
+    runner.vault.log_run(
+        command,
+        Some(&run.output),
+        run.exit.code,
+        cwd,
+        Some(run.wall.as_millis() as i64),
+        run.exit.usage,
+    )
+}
+
+/// `!time <command>`: run it captured instead of at the PTY, show its
+/// output, then exit, wall time, CPU time and peak memory. The run is
+/// logged under the command itself, so `!stats` counts it as usual.
+async fn time_lines(runner: &Runner, command: &str) -> Vec<String> {
+    if DangerAnalyzer::is_destructive(command) {
+        return vec![format!("❌ Refusing to time a destructive command; run it at the prompt: {}", command)];
+    }
+    let cwd = runner.cwd().unwrap_or_else(|| ".".to_string());
+    let run = match usage::capture(command, &cwd, &runner.env_overlay(), None).await {
+        Ok(run) => run,
+        Err(e) => return vec![format!("❌ {}", e)],
+    };
+    let mut lines: Vec<String> = run.output.lines().map(str::to_string).collect();
+    lines.push(usage::summary(&run));
+    if let Err(e) = log_captured(runner, command, &cwd, &run) {
+        lines.push(format!("⚠️ Not saved to history: {}", e));
+    }
+    lines
+}
+
+/// `!stats resources [n]`: captured commands, most total CPU first.
+fn resource_lines(runner: &Runner, limit: usize) -> Vec<String> {
+    let stats = match runner.vault.resource_stats() {
+        Ok(stats) => stats,
+        Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+    };
+    if stats.is_empty() {
+        return vec!["No resource usage recorded yet; run a command with !time <command>.".to_string()];
+    }
+    let shown: Vec<_> = stats.into_iter().take(limit).collect();
+    let mut lines = vec![
+        format!("🔥 Resource usage of {} captured commands:", shown.len()),
+        "".to_string(),
+        format!("  {:<16} {:>6} {:>10} {:>10} {:>10} {:>10}", "command", "runs", "cpu total", "cpu mean", "peak mem", "mean mem"),
+    ];
+    for s in &shown {
+        lines.push(format!(
+            "  {:<16} {:>6} {:>10} {:>10} {:>10} {:>10}",
+            s.program,
+            s.runs,
+            timing::format_ms(s.total_cpu_ms as i64),
+            timing::format_ms(s.mean_cpu_ms.round() as i64),
+            trash::format_bytes(s.peak_rss_bytes),
+            trash::format_bytes(s.mean_rss_bytes.round() as u64)
+        ));
+    }
+    lines
+}
+
+/// `!capture`: keep what the selector picks from the last stored output.
+fn capture_lines(runner: &Runner, capture: Capture) -> Vec<String> {
+    let record = match runner.vault.last_with_output(None, None) {
+        Ok(Some(record)) => record,
+        Ok(None) => return vec!["📌 No command output stored yet.".to_string()],
+        Err(e) => return vec![format!("❌ Error reading history: {}", e)],
+    };
+    let output = plain_text(record.output.as_deref().unwrap_or_default());
+    let value = match capture::extract(&output, &capture.selector) {
+        Ok(value) => value,
+        Err(message) => return vec![message],
+    };
+    let mut variables = runner.variables();
+    variables.set(&capture.name, value, &record.command);
+    let preview = variables.get(&capture.name).map(capture::preview).unwrap_or_default();
+    vec![format!("📌 %{{{}}} = {}  ← {}", capture.name, preview, record_label(&record, runner.vault.time_settings().clock))]
+}
+
+/// `#12 cargo test (14:02:31)`.
+fn record_label(record: &CommandRecord, clock: Clock) -> String {
+    let time = time_format::clock_secs(record.timestamp, &chrono::Local, clock);
+    format!("#{} {} ({})", record.id.unwrap_or(0), record.command, time)
+}
@@ -0,0 +1,197 @@
+//! Completion Index — in-memory, frecency-ranked command lookup.
+//!
+//! Built once at engine start from a single streaming Vault query, then
+//! updated incrementally as commands are submitted. The completer and any
+//! inline suggestor query it with `suggest(prefix, limit)` instead of
+//! scanning history on every keystroke.
+//!
+//! Layout is a Vec sorted by command bytes: a prefix query is one binary
+//! search to the first candidate plus a linear walk over the matching run.
+//! Byte ordering keeps prefix runs contiguous for multibyte text too.
+
+use chrono::Utc;
+
+use crate::vault::Vault;
+
+/// Default cap on indexed unique commands.
+pub const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+#[derive(Debug, Clone)]
+struct Entry {
+    command: String,
+    count: u32,
+    last_used: i64,
+}
+
+impl Entry {
+    /// Frequency weighted by recency buckets (hour / day / week / older).
+    fn frecency(&self, now: i64) -> f64 {
+        let age = (now - self.last_used).max(0);
+        let weight = if age < 3_600 {
+            4.0
+        } else if age < 86_400 {
+            2.0
+        } else if age < 604_800 {
+            1.0
+        } else {
+            0.5
+        };
+        self.count as f64 * weight
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompletionIndex {
+    entries: Vec<Entry>,
+    max_entries: usize,
+}
+
+impl CompletionIndex {
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            max_entries: max_entries.max(1),
+        }
+    }
+
+    /// Build from the Vault's full history with one streaming query.
+    pub fn from_vault(vault: &Vault, max_entries: usize) -> rusqlite::Result<Self> {
+        let mut index = Self::new(max_entries);
+        vault.for_each_command_frequency(|command, count, last_used| {
+            index.entries.push(Entry {
+                command: command.to_string(),
+                count: count.clamp(0, u32::MAX as i64) as u32,
+                last_used,
+            });
+        })?;
+        index.entries.sort_unstable_by(|a, b| a.command.cmp(&b.command));
+        index.entries.dedup_by(|b, a| {
+            if a.command == b.command {
+                a.count = a.count.saturating_add(b.count);
+                a.last_used = a.last_used.max(b.last_used);
+                true
+            } else {
+                false
+            }
+        });
+        index.enforce_cap(Utc::now().timestamp());
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Record one use of `command` now.
+    pub fn record(&mut self, command: &str) {
+        self.record_at(command, Utc::now().timestamp());
+    }
+
+    /// Record one use of `command` at `timestamp` (seconds).
+    pub fn record_at(&mut self, command: &str, timestamp: i64) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        match self.entries.binary_search_by(|e| e.command.as_str().cmp(command)) {
+            Ok(i) => {
+                let e = &mut self.entries[i];
+                e.count = e.count.saturating_add(1);
+                e.last_used = e.last_used.max(timestamp);
+            }
+            Err(i) => {
+                self.entries.insert(
+                    i,
+                    Entry {
+                        command: command.to_string(),
+                        count: 1,
+                        last_used: timestamp,
+                    },
+                );
+                // Trim in batches so steady-state inserts stay O(log n + shift).
+                if self.entries.len() > self.max_entries + self.max_entries / 10 {
+                    self.enforce_cap(timestamp);
+                }
+            }
+        }
+    }
+
+    /// Up to `limit` commands starting with `prefix`, best frecency first.
+    /// An exact match of the prefix itself is skipped — it completes nothing.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
+        self.suggest_at(prefix, limit, Utc::now().timestamp())
+    }
+
+    pub fn suggest_at(&self, prefix: &str, limit: usize, now: i64) -> Vec<String> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let start = self.entries.partition_point(|e| e.command.as_str() < prefix);
+
+        // Bounded top-k: `best` stays sorted by score, descending.
+        let mut best: Vec<(f64, usize)> = Vec::with_capacity(limit + 1);
+        for (i, e) in self.entries[start..].iter().enumerate() {
+            if !e.command.starts_with(prefix) {
+                break;
+            }
+            if e.command.len() == prefix.len() {
+                continue;
+            }
+            let score = e.frecency(now);
+            if best.len() == limit && score <= best[limit - 1].0 {
+                continue;
+            }
+            let pos = best.partition_point(|(s, _)| *s >= score);
+            best.insert(pos, (score, start + i));
+            best.truncate(limit);
+        }
+
+        best.into_iter()
+            .map(|(_, i)| self.entries[i].command.clone())
+            .collect()
+    }
+
+    /// The single best completion for `prefix`, if any.
+    pub fn best(&self, prefix: &str) -> Option<String> {
+        self.suggest(prefix, 1).into_iter().next()
+    }
+
+    /// Drop the lowest-frecency tail beyond `max_entries` (oldest first on ties).
+    fn enforce_cap(&mut self, now: i64) {
+        if self.entries.len() <= self.max_entries {
+            return;
+        }
+        let excess = self.entries.len() - self.max_entries;
+        let mut ranked: Vec<(f64, i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.frecency(now), e.last_used, i))
+            .collect();
+        ranked.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut drop = vec![false; self.entries.len()];
+        for &(_, _, i) in &ranked[..excess] {
+            drop[i] = true;
+        }
+        let mut i = 0;
+        self.entries.retain(|_| {
+            i += 1;
+            !drop[i - 1]
+        });
+    }
+}
+
+impl Default for CompletionIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES)
+    }
+}
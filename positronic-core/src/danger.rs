@@ -0,0 +1,314 @@
+//! Danger analysis for shell commands.
+//!
+//! Classifies a command line by what it could do to the machine, so callers
+//! can flag or confirm it before it reaches the PTY. This is pattern
+//! matching on the command text, not a shell parser: it errs towards
+//! flagging, and a `Safe` verdict is not a guarantee. PowerShell and
+//! cmd.exe lines are covered too; their patterns ignore case, as those
+//! shells do.
+//!
+//! Commands a model wrote (`# …`, `!suggest`) are screened with
+//! `screen_generated`: on top of the patterns above, a deny-list of what a
+//! prompt injection would ask for — running downloaded or encoded
+//! scripts, reverse shells, SSH keys, hidden history — is flagged as
+//! destructive, however the request was worded.
+
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Config key: when on, a destructive plain `rm` gets a hint to use
+/// `!rm`, which moves the paths to the trash instead.
+pub const SUGGEST_RM_KEY: &str = "danger.suggest_rm";
+
+/// Whether `SUGGEST_RM_KEY` is on; it is off unless set.
+pub fn suggest_rm_enabled(value: Option<&str>) -> bool {
+    matches!(value.map(|v| v.trim().to_lowercase()).as_deref(), Some("true" | "on" | "1" | "yes"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DangerLevel {
+    Safe,
+    /// Elevated or hard to undo, but usually intended (sudo, kill -9, …).
+    Caution,
+    /// Can destroy data or the system (rm -rf, mkfs, dd to a device, …).
+    Destructive,
+}
+
+impl fmt::Display for DangerLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DangerLevel::Safe => write!(f, "safe"),
+            DangerLevel::Caution => write!(f, "caution"),
+            DangerLevel::Destructive => write!(f, "destructive"),
+        }
+    }
+}
+
+/// The result of analysing one command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DangerVerdict {
+    pub level: DangerLevel,
+    /// What matched, for display. `None` when `Safe`.
+    pub reason: Option<&'static str>,
+}
+
+impl DangerVerdict {
+    pub fn is_destructive(&self) -> bool {
+        self.level == DangerLevel::Destructive
+    }
+}
+
+struct Pattern {
+    regex: &'static str,
+    level: DangerLevel,
+    reason: &'static str,
+}
+
+/// Checked in order; the highest level among all matches wins. `rm` only
+/// counts in command position, so `grep rm` is not a delete.
+const PATTERNS: &[Pattern] = &[
+    // ── Destructive ──
+    Pattern {
+        regex: r"(?:^\s*|[;&|(]\s*|\b(?:sudo|doas|xargs|exec)\s+)rm\s+(?:-[a-zA-Z]*[rRf][a-zA-Z]*\s+|--(?:recursive|force)\s+)+",
+        level: DangerLevel::Destructive,
+        reason: "recursive or forced delete",
+    },
+    Pattern {
+        regex: r"\bmkfs(?:\.\w+)?\b|\bwipefs\b|\bshred\b",
+        level: DangerLevel::Destructive,
+        reason: "wipes a filesystem or file",
+    },
+    Pattern {
+        regex: r"\bdd\b.*\bof=/dev/",
+        level: DangerLevel::Destructive,
+        reason: "writes directly to a device",
+    },
+    Pattern {
+        regex: r">\s*/dev/(?:sd|nvme|hd|disk|mmcblk)",
+        level: DangerLevel::Destructive,
+        reason: "redirects into a block device",
+    },
+    Pattern {
+        regex: r":\(\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:",
+        level: DangerLevel::Destructive,
+        reason: "fork bomb",
+    },
+    Pattern {
+        regex: r"\bchmod\s+(?:-\w+\s+)*-R\w*\s+[0-7]*7[0-7]*7\s+/(?:\s|$)|\bchown\s+(?:-\w+\s+)*-R\w*\s+\S+\s+/(?:\s|$)",
+        level: DangerLevel::Destructive,
+        reason: "recursive permission change on /",
+    },
+    Pattern {
+        regex: r"\bgit\s+(?:reset\s+--hard|clean\s+-\w*[fdx]|push\s+(?:.*\s)?(?:--force\b|-f\b))",
+        level: DangerLevel::Destructive,
+        reason: "discards git work",
+    },
+    Pattern {
+        regex: r"\bfind\b.*\s-delete\b",
+        level: DangerLevel::Destructive,
+        reason: "bulk delete via find",
+    },
+    Pattern {
+        regex: r"(?i)\bdrop\s+(?:table|database|schema)\b|\btruncate\s+table\b",
+        level: DangerLevel::Destructive,
+        reason: "drops database objects",
+    },
+    // ── Destructive (PowerShell, cmd.exe) ──
+    Pattern {
+        regex: r"(?i)(?:^\s*|[;&|(]\s*)(?:remove-item|ri|del|erase|rd|rmdir)\b[^;&|]*\s-(?:r\b|rec\w*|fo\w*)",
+        level: DangerLevel::Destructive,
+        reason: "recursive or forced delete",
+    },
+    Pattern {
+        regex: r"(?i)(?:^\s*|[;&|(]\s*)(?:del|erase|rd|rmdir)\s[^;&|]*/s\b",
+        level: DangerLevel::Destructive,
+        reason: "recursive delete",
+    },
+    Pattern {
+        regex: r"(?i)(?:^\s*|[;&|]\s*)format(?:\.com)?\s+[a-z]:|\b(?:format-volume|clear-disk|initialize-disk|remove-partition)\b",
+        level: DangerLevel::Destructive,
+        reason: "formats or wipes a drive",
+    },
+    Pattern {
+        regex: r"(?i)\breg(?:\.exe)?\s+delete\b|\bremove-item(?:property)?\b[^;&|]*\bhk(?:lm|cu|cr|u|cc)(?::|\\)",
+        level: DangerLevel::Destructive,
+        reason: "deletes registry keys",
+    },
+    // ── Caution ──
+    Pattern {
+        regex: r"(?:^|[;&|]\s*)(?:sudo|doas|su)\b",
+        level: DangerLevel::Caution,
+        reason: "runs with elevated privileges",
+    },
+    Pattern {
+        regex: r"\b(?:curl|wget)\b[^|]*\|\s*(?:sudo\s+)?(?:ba|z)?sh\b",
+        level: DangerLevel::Caution,
+        reason: "pipes a download into a shell",
+    },
+    Pattern {
+        regex: r"\bkill(?:all)?\s+-(?:9|KILL)\b|\bpkill\s+-9\b",
+        level: DangerLevel::Caution,
+        reason: "force-kills processes",
+    },
+    Pattern {
+        regex: r"(?:^\s*|[;&|(]\s*|\b(?:sudo|doas|xargs|exec)\s+)rm\s",
+        level: DangerLevel::Caution,
+        reason: "deletes files",
+    },
+    Pattern {
+        regex: r"\b(?:shutdown|reboot|halt|poweroff)\b",
+        level: DangerLevel::Caution,
+        reason: "shuts down or restarts the machine",
+    },
+    // ── Caution (PowerShell, cmd.exe) ──
+    Pattern {
+        regex: r"(?i)\bstart-process\b.*-verb\s+runas\b",
+        level: DangerLevel::Caution,
+        reason: "runs with elevated privileges",
+    },
+    Pattern {
+        regex: r"(?i)\b(?:iwr|irm|invoke-webrequest|invoke-restmethod|curl|wget)\b[^|]*\|\s*(?:iex|invoke-expression)\b",
+        level: DangerLevel::Caution,
+        reason: "pipes a download into a shell",
+    },
+    Pattern {
+        regex: r"(?i)\b(?:stop-process|spps|kill)\b.*\s-fo\w*|\btaskkill\b.*\s/f\b",
+        level: DangerLevel::Caution,
+        reason: "force-kills processes",
+    },
+    Pattern {
+        regex: r"(?i)(?:^\s*|[;&|(]\s*)(?:remove-item|ri|del|erase)\s",
+        level: DangerLevel::Caution,
+        reason: "deletes files",
+    },
+    Pattern {
+        regex: r"(?i)\b(?:restart-computer|stop-computer)\b",
+        level: DangerLevel::Caution,
+        reason: "shuts down or restarts the machine",
+    },
+];
+
+/// Flagged in generated commands only; typed by the user, most of these
+/// are merely `Caution` or fine.
+const GENERATED_DENY: &[Pattern] = &[
+    Pattern {
+        regex: r"(?i)\b(?:curl|wget|fetch|iwr|irm|invoke-webrequest|invoke-restmethod)\b[^|]*\|\s*(?:sudo\s+)?(?:\w*sh|python\d*|perl|ruby|node|iex|invoke-expression)\b",
+        level: DangerLevel::Destructive,
+        reason: "runs a downloaded script",
+    },
+    Pattern {
+        regex: r"[$<]\(\s*(?:curl|wget)\b|`\s*(?:curl|wget)\b",
+        level: DangerLevel::Destructive,
+        reason: "runs a downloaded script",
+    },
+    Pattern {
+        regex: r"(?i)\bbase64\s+(?:-d|-D|--decode)\b[^|]*\|\s*(?:sudo\s+)?\w*sh\b|\bfrombase64string\b|\b(?:powershell|pwsh)(?:\.exe)?\b.*\s-e(?:nc|ncodedcommand)?\s+[A-Za-z0-9+/=]{16,}",
+        level: DangerLevel::Destructive,
+        reason: "runs encoded commands",
+    },
+    Pattern {
+        regex: r"/dev/(?:tcp|udp)/|\b(?:nc|ncat|netcat)\b.*\s-[a-z]*[ec]\s",
+        level: DangerLevel::Destructive,
+        reason: "opens a reverse shell",
+    },
+    Pattern {
+        regex: r"(?:>>?|\btee\b(?:\s+-a)?)\s*\S*\.ssh/authorized_keys",
+        level: DangerLevel::Destructive,
+        reason: "changes who can log in over SSH",
+    },
+    Pattern {
+        regex: r"(?i)\b(?:curl|wget|nc|ncat|scp|rsync)\b.*(?:\.ssh/|\.aws/|\.gnupg/|\.netrc\b|\bid_(?:rsa|ed25519|ecdsa)\b|\.env\b)",
+        level: DangerLevel::Destructive,
+        reason: "sends credentials off the machine",
+    },
+    Pattern {
+        regex: r"\bhistory\s+-c\b|\bHISTFILE=/dev/null\b|\bunset\s+HISTFILE\b|\bset\s+\+o\s+history\b",
+        level: DangerLevel::Destructive,
+        reason: "hides shell history",
+    },
+    Pattern {
+        regex: r"\bcrontab\s+-r\b",
+        level: DangerLevel::Destructive,
+        reason: "deletes the crontab",
+    },
+];
+
+static COMPILED: OnceLock<Vec<(Regex, &'static Pattern)>> = OnceLock::new();
+static COMPILED_DENY: OnceLock<Vec<(Regex, &'static Pattern)>> = OnceLock::new();
+
+fn compiled() -> &'static [(Regex, &'static Pattern)] {
+    COMPILED.get_or_init(|| {
+        PATTERNS
+            .iter()
+            .map(|p| (Regex::new(p.regex).expect("Invalid danger pattern"), p))
+            .collect()
+    })
+}
+
+pub struct DangerAnalyzer;
+
+impl DangerAnalyzer {
+    /// Classify `command`. The most severe matching pattern decides.
+    pub fn analyze(command: &str) -> DangerVerdict {
+        let mut verdict = DangerVerdict { level: DangerLevel::Safe, reason: None };
+        for (regex, pattern) in compiled() {
+            if pattern.level > verdict.level && regex.is_match(command) {
+                verdict = DangerVerdict { level: pattern.level, reason: Some(pattern.reason) };
+            }
+        }
+        verdict
+    }
+
+    pub fn is_destructive(command: &str) -> bool {
+        Self::analyze(command).is_destructive()
+    }
+
+    /// Classify a command a model wrote: `analyze`, with a match on the
+    /// generated-command deny-list raised to `Destructive`. Callers flag
+    /// these before the command reaches the input line.
+    pub fn screen_generated(command: &str) -> DangerVerdict {
+        let verdict = Self::analyze(command);
+        if verdict.is_destructive() {
+            return verdict;
+        }
+        let deny = COMPILED_DENY.get_or_init(|| {
+            GENERATED_DENY
+                .iter()
+                .map(|p| (Regex::new(p.regex).expect("Invalid deny pattern"), p))
+                .collect()
+        });
+        deny.iter()
+            .find(|(regex, _)| regex.is_match(command))
+            .map(|(_, p)| DangerVerdict { level: p.level, reason: Some(p.reason) })
+            .unwrap_or(verdict)
+    }
+
+    /// `!rm a b` for a destructive plain `rm -rf a b`: the same paths with
+    /// the flags dropped. `None` for anything else, including `sudo rm`
+    /// and `rm` inside a pipeline or a list of commands.
+    pub fn rm_alternative(command: &str) -> Option<String> {
+        if !Self::is_destructive(command) || command.contains([';', '&', '|', '(', '`', '$', '<', '>']) {
+            return None;
+        }
+        let mut words = command.split_whitespace();
+        if words.next() != Some("rm") {
+            return None;
+        }
+        let mut paths = Vec::new();
+        let mut flags_done = false;
+        for word in words {
+            if !flags_done && word == "--" {
+                flags_done = true;
+            } else if flags_done || !word.starts_with('-') {
+                paths.push(word);
+            }
+        }
+        (!paths.is_empty()).then(|| format!("!rm {}", paths.join(" ")))
+    }
+
+    /// The line shown with a destructive `rm` when `SUGGEST_RM_KEY` is on.
+    pub fn rm_hint(command: &str) -> Option<String> {
+        Self::rm_alternative(command).map(|line| format!("💡 `{}` moves them to the trash instead, with undo", line))
+    }
+}
@@ -0,0 +1,353 @@
+//! Where Positronic keeps its data: the vault, tldr pages, scaffolds,
+//! the `!rm` trash, the `!rename` log, the boot marker, IPC sockets,
+//! recordings, WASM plugins and the clipboard history all live under
+//! one root, and every path to them is joined here.
+//!
+//! The root is, first found: `--data-dir`, `POSITRONIC_DATA_DIR`, a
+//! `positronic-data` directory beside the executable when a `portable`
+//! file sits there too (run from a USB stick), or the platform's data
+//! directory. It is created, private to the user, on first use.
+//!
+//! `!vault migrate-data <path>` copies the data to a new root: the vault
+//! through SQLite so a live one copies consistently, everything else
+//! file by file, each copy checked against its source. The copy is
+//! built beside the target and renamed into place once it checks out;
+//! a target that is the portable directory then gets its marker, which
+//! is what switches the next start over. The old root is left as it is.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use positronic_script::plugins::PLUGINS_DIR;
+use sha2::{Digest, Sha256};
+
+use crate::asciicast::RECORDINGS_DIR;
+use crate::integrate::ProfileOs;
+use crate::ipc::IPC_DIR;
+use crate::rename::RENAME_LOG;
+use crate::safe_mode::BOOT_MARKER;
+use crate::scaffold::SCAFFOLD_DIR;
+use crate::tldr::TLDR_DIR;
+use crate::trash::TRASH_DIR;
+
+pub const DATA_DIR_ENV: &str = "POSITRONIC_DATA_DIR";
+
+/// The file beside the executable that asks for portable mode.
+pub const PORTABLE_MARKER: &str = "portable";
+
+/// The data directory beside the executable in portable mode.
+pub const PORTABLE_DIR: &str = "positronic-data";
+
+/// The vault's file name under the root.
+pub const VAULT_FILE: &str = "positronic.db";
+
+pub const MIGRATE_USAGE: &str = "Usage: !vault migrate-data <path>";
+
+/// Which rule picked the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSource {
+    Flag,
+    Env,
+    Portable,
+    Default,
+    /// Named by its vault file (`for_vault`).
+    Vault,
+}
+
+impl DataSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DataSource::Flag => "--data-dir",
+            DataSource::Env => DATA_DIR_ENV,
+            DataSource::Portable => "portable",
+            DataSource::Default => "platform default",
+            DataSource::Vault => "beside the vault",
+        }
+    }
+}
+
+/// The data root and the paths under it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataPaths {
+    root: PathBuf,
+    vault: PathBuf,
+    source: DataSource,
+}
+
+impl DataPaths {
+    pub fn at(root: impl Into<PathBuf>, source: DataSource) -> Self {
+        let root = root.into();
+        Self { vault: root.join(VAULT_FILE), root, source }
+    }
+
+    /// The paths around the vault file at `vault`, its directory being the
+    /// root. `:memory:` keeps the vault in memory and the rest in the
+    /// working directory.
+    pub fn for_vault(vault: impl Into<PathBuf>) -> Self {
+        let vault = vault.into();
+        let root = vault.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self { root, vault, source: DataSource::Vault }
+    }
+
+    /// Pick the root: `flag`, then `env`, then the portable directory if
+    /// `exe_dir` holds the marker, then `default`. Empty values don't
+    /// count; relative ones are made absolute so they hold wherever the
+    /// shell `cd`s.
+    pub fn resolve(flag: Option<PathBuf>, env: Option<OsString>, exe_dir: Option<&Path>, default: PathBuf) -> Self {
+        let given = |path: PathBuf| std::path::absolute(&path).unwrap_or(path);
+        if let Some(flag) = flag.filter(|p| !p.as_os_str().is_empty()) {
+            return Self::at(given(flag), DataSource::Flag);
+        }
+        if let Some(env) = env.filter(|v| !v.is_empty()) {
+            return Self::at(given(PathBuf::from(env)), DataSource::Env);
+        }
+        if let Some(dir) = exe_dir.filter(|dir| dir.join(PORTABLE_MARKER).is_file()) {
+            return Self::at(dir.join(PORTABLE_DIR), DataSource::Portable);
+        }
+        Self::at(default, DataSource::Default)
+    }
+
+    /// `resolve` for this process, with `--data-dir`'s value.
+    pub fn current(flag: Option<PathBuf>) -> Self {
+        let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let os = if cfg!(windows) {
+            ProfileOs::Windows
+        } else if cfg!(target_os = "macos") {
+            ProfileOs::MacOs
+        } else {
+            ProfileOs::Linux
+        };
+        let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+        Self::resolve(flag, std::env::var_os(DATA_DIR_ENV), exe_dir.as_deref(), platform_default(os, var))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn source(&self) -> DataSource {
+        self.source
+    }
+
+    pub fn vault(&self) -> &Path {
+        &self.vault
+    }
+
+    pub fn tldr(&self) -> PathBuf {
+        self.root.join(TLDR_DIR)
+    }
+
+    pub fn scaffolds(&self) -> PathBuf {
+        self.root.join(SCAFFOLD_DIR)
+    }
+
+    pub fn trash(&self) -> PathBuf {
+        self.root.join(TRASH_DIR)
+    }
+
+    pub fn rename_log(&self) -> PathBuf {
+        self.root.join(RENAME_LOG)
+    }
+
+    pub fn boot_marker(&self) -> PathBuf {
+        self.root.join(BOOT_MARKER)
+    }
+
+    /// Where `!record start` without a path records.
+    pub fn recordings(&self) -> PathBuf {
+        self.root.join(RECORDINGS_DIR)
+    }
+
+    /// Where WASM plugins are loaded from (see `positronic_script::plugins`).
+    pub fn plugins(&self) -> PathBuf {
+        self.root.join(PLUGINS_DIR)
+    }
+
+    /// The parent of the IPC sockets (see `ipc::Endpoint::for_session`).
+    pub fn ipc_parent(&self) -> &Path {
+        &self.root
+    }
+
+    /// Another file under the root, for data the UI keeps.
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Create the root if it is missing: only the user may enter it.
+    pub fn ensure(&self) -> io::Result<()> {
+        if self.root.as_os_str().is_empty() || self.root.is_dir() {
+            return Ok(());
+        }
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder.create(&self.root)
+    }
+
+    /// `📁 Data: <root> (<source>)`, for `!doctor` and `!stats`.
+    pub fn summary(&self) -> String {
+        format!("📁 Data: {} ({})", self.root.display(), self.source.label())
+    }
+}
+
+/// The platform's data directory: `%APPDATA%\Positronic`, `~/Library/
+/// Application Support/Positronic` or `$XDG_DATA_HOME/positronic`
+/// (`~/.local/share/positronic`). Without a home, the working directory.
+pub fn platform_default(os: ProfileOs, var: impl Fn(&str) -> Option<PathBuf>) -> PathBuf {
+    let home = var("HOME").or_else(|| var("USERPROFILE"));
+    let dir = match os {
+        ProfileOs::Windows => var("APPDATA").or_else(|| home.map(|h| h.join("AppData").join("Roaming"))).map(|d| d.join("Positronic")),
+        ProfileOs::MacOs => home.map(|h| h.join("Library").join("Application Support").join("Positronic")),
+        ProfileOs::Linux => var("XDG_DATA_HOME").or_else(|| home.map(|h| h.join(".local").join("share"))).map(|d| d.join("positronic")),
+    };
+    dir.unwrap_or_else(|| PathBuf::from("."))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !vault migrate-data
+// ════════════════════════════════════════════════════════════════════
+
+/// What `migrate` copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub to: PathBuf,
+    /// Files other than the vault.
+    pub files: usize,
+    pub bytes: u64,
+    /// The portable marker was written: the next start uses `to`.
+    pub switched: bool,
+}
+
+impl Migration {
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "📦 Copied the vault and {} file(s) ({} bytes) to {}, all verified",
+            self.files,
+            self.bytes,
+            self.to.display()
+        )];
+        if self.switched {
+            lines.push("   The portable marker is in place: the next start uses it. The old data is untouched.".to_string());
+        } else {
+            lines.push(format!(
+                "   Start with --data-dir {} or set {} to use it; the old data is untouched.",
+                self.to.display(),
+                DATA_DIR_ENV
+            ));
+        }
+        lines
+    }
+}
+
+/// Copy the data under `from` to the new root `to`. `copy_vault` writes
+/// a checked copy of the vault to the path it is given. `exe_dir` is
+/// where the portable marker goes when `to` is the portable directory.
+pub fn migrate(
+    from: &DataPaths,
+    to: &Path,
+    exe_dir: Option<&Path>,
+    copy_vault: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<Migration> {
+    let to = std::path::absolute(to)?;
+    let root = std::path::absolute(from.root())?;
+    if to == root {
+        return Err(io::Error::other("that is already the data directory"));
+    }
+    if to.starts_with(&root) {
+        return Err(io::Error::other("the target is inside the data directory"));
+    }
+    if to.exists() && fs::read_dir(&to)?.next().is_some() {
+        return Err(io::Error::other(format!("{} exists and is not empty", to.display())));
+    }
+    let name = to.file_name().ok_or_else(|| io::Error::other("the target has no name"))?;
+    let parent = to.parent().unwrap_or(Path::new("."));
+    let mut staging_name = OsString::from(".");
+    staging_name.push(name);
+    staging_name.push(".migrating");
+    let staging = parent.join(staging_name);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    DataPaths::at(&staging, DataSource::Flag).ensure()?;
+
+    let copied = copy_vault(&staging.join(VAULT_FILE)).and_then(|()| {
+        let mut totals = (0, 0);
+        copy_tree(&root, &staging, &skipped_names(from), &mut totals).map(|()| totals)
+    });
+    let (files, bytes) = match copied {
+        Ok(totals) => totals,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    if to.exists() {
+        fs::remove_dir(&to)?;
+    }
+    fs::rename(&staging, &to)?;
+    let switched = match exe_dir {
+        Some(dir) if to == dir.join(PORTABLE_DIR) => {
+            fs::write(dir.join(PORTABLE_MARKER), "")?;
+            true
+        }
+        _ => false,
+    };
+    Ok(Migration { to, files, bytes, switched })
+}
+
+/// Names at the root `copy_tree` leaves alone: the vault, copied on its
+/// own, and the live IPC sockets.
+fn skipped_names(from: &DataPaths) -> Vec<OsString> {
+    let mut skip = vec![OsString::from(IPC_DIR)];
+    if let Some(vault) = from.vault().file_name() {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut name = vault.to_os_string();
+            name.push(suffix);
+            skip.push(name);
+        }
+    }
+    skip
+}
+
+/// Copy the files under `from` into `to`, checking each copy's hash
+/// against its source.
+fn copy_tree(from: &Path, to: &Path, skip: &[OsString], totals: &mut (usize, u64)) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if skip.contains(&name) || name.to_string_lossy().ends_with(".migrating") {
+            continue;
+        }
+        let (source, target) = (entry.path(), to.join(&name));
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_tree(&source, &target, &[], totals)?;
+        } else if kind.is_file() {
+            let bytes = fs::copy(&source, &target)?;
+            if file_hash(&source)? != file_hash(&target)? {
+                return Err(io::Error::other(format!("{} changed while copying", source.display())));
+            }
+            totals.0 += 1;
+            totals.1 += bytes;
+        }
+    }
+    Ok(())
+}
+
+fn file_hash(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finalize().to_vec());
+        }
+        hasher.update(&buf[..n]);
+    }
+}
@@ -0,0 +1,324 @@
+//! Error locations extracted from compiler and test-runner output.
+//!
+//! The extractor is a table of line patterns, one or more per tool. A
+//! pattern's regex names what it captures (`file`, `line`, `col`,
+//! `message`, `code`, `severity`); a pattern whose location sits on a later
+//! line (rustc's `--> src/x.rs:12:5`) adds a second regex searched in the
+//! next few lines. Cargo's JSON diagnostics are parsed as JSON instead.
+//! More patterns can be registered at runtime for other tools.
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Lines after a header searched for its location.
+pub const LOCATION_LOOKAHEAD: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn from_label(label: &str) -> Severity {
+        match label.to_lowercase().as_str() {
+            "error" | "failed" => Severity::Error,
+            "warning" | "warn" => Severity::Warning,
+            _ => Severity::Note,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: String,
+    /// 1-based; `None` when the tool reports only the file (pytest summary).
+    pub line: Option<u32>,
+    pub col: Option<u32>,
+    pub message: String,
+    /// Tool-specific id: `E0308`, `TS2345`, or the pytest test name.
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// `src/x.rs:12:5`, as far as it is known.
+    pub fn location(&self) -> String {
+        match (self.line, self.col) {
+            (Some(line), Some(col)) => format!("{}:{}:{}", self.file, line, col),
+            (Some(line), None) => format!("{}:{}", self.file, line),
+            _ => self.file.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} {}[{}]: {}", self.location(), self.severity, code, self.message),
+            None => write!(f, "{} {}: {}", self.location(), self.severity, self.message),
+        }
+    }
+}
+
+/// `3 errors, 1 warning` (notes are not counted). Empty without errors or
+/// warnings.
+pub fn summary(diagnostics: &[Diagnostic]) -> String {
+    let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    let mut parts = Vec::new();
+    if errors > 0 {
+        parts.push(plural(errors, "error"));
+    }
+    if warnings > 0 {
+        parts.push(plural(warnings, "warning"));
+    }
+    parts.join(", ")
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Pattern table
+// ════════════════════════════════════════════════════════════════════
+
+/// One entry of the pattern table.
+#[derive(Debug, Clone, Copy)]
+pub struct PatternSpec {
+    pub tool: &'static str,
+    /// Matched against each output line.
+    pub line: &'static str,
+    /// Searched in the next `LOCATION_LOOKAHEAD` lines for the captures
+    /// `line` lacks; the match is dropped if none is found.
+    pub location: Option<&'static str>,
+    /// Used when the pattern has no `severity` group.
+    pub severity: Severity,
+}
+
+/// Patterns every extractor starts with.
+pub const BUILTIN_PATTERNS: &[PatternSpec] = &[
+    // error[E0308]: mismatched types
+    //   --> src/main.rs:12:5
+    PatternSpec {
+        tool: "rustc",
+        line: r"^(?P<severity>error|warning)(?:\[(?P<code>[A-Za-z0-9_:]+)\])?: (?P<message>.+)$",
+        location: Some(r"^\s*--> (?P<file>.+?):(?P<line>\d+):(?P<col>\d+)\s*$"),
+        severity: Severity::Error,
+    },
+    // src/app.ts(10,5): error TS2345: Argument of type ...
+    PatternSpec {
+        tool: "tsc",
+        line: r"^(?P<file>[^\s(][^(]*)\((?P<line>\d+),(?P<col>\d+)\): (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$",
+        location: None,
+        severity: Severity::Error,
+    },
+    // src/app.ts:10:5 - error TS2345: Argument of type ... (--pretty)
+    PatternSpec {
+        tool: "tsc",
+        line: r"^(?P<file>\S+):(?P<line>\d+):(?P<col>\d+) - (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$",
+        location: None,
+        severity: Severity::Error,
+    },
+    // FAILED tests/test_api.py::test_create - AssertionError: assert 500 == 201
+    PatternSpec {
+        tool: "pytest",
+        line: r"^FAILED (?P<file>[^\s:]+)::(?P<code>\S+)(?: - (?P<message>.+))?$",
+        location: None,
+        severity: Severity::Error,
+    },
+];
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    tool: String,
+    line: Regex,
+    location: Option<Regex>,
+    severity: Severity,
+}
+
+/// Runs the pattern table over output text.
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    patterns: Vec<Pattern>,
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        let mut extractor = Extractor { patterns: Vec::new() };
+        for spec in BUILTIN_PATTERNS {
+            extractor
+                .register(spec.tool, spec.line, spec.location, spec.severity)
+                .expect("builtin diagnostic pattern");
+        }
+        extractor
+    }
+}
+
+impl Extractor {
+    /// An extractor with only `tool`'s built-in patterns.
+    pub fn builtin(tool: &str) -> Self {
+        let mut extractor = Extractor { patterns: Vec::new() };
+        for spec in BUILTIN_PATTERNS.iter().filter(|spec| spec.tool == tool) {
+            extractor
+                .register(spec.tool, spec.line, spec.location, spec.severity)
+                .expect("builtin diagnostic pattern");
+        }
+        extractor
+    }
+
+    /// Add a pattern; see `PatternSpec` for the fields. Patterns are tried
+    /// in registration order and the first match wins.
+    pub fn register(
+        &mut self,
+        tool: &str,
+        line: &str,
+        location: Option<&str>,
+        severity: Severity,
+    ) -> Result<(), regex::Error> {
+        self.patterns.push(Pattern {
+            tool: tool.to_string(),
+            line: Regex::new(line)?,
+            location: location.map(Regex::new).transpose()?,
+            severity,
+        });
+        Ok(())
+    }
+
+    /// Tools with at least one pattern, in registration order.
+    pub fn tools(&self) -> Vec<&str> {
+        let mut tools: Vec<&str> = Vec::new();
+        for p in &self.patterns {
+            if !tools.contains(&p.tool.as_str()) {
+                tools.push(&p.tool);
+            }
+        }
+        tools
+    }
+
+    /// Every diagnostic in `text`, in output order.
+    pub fn extract(&self, text: &str) -> Vec<Diagnostic> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut found = Vec::new();
+        for (i, raw) in lines.iter().enumerate() {
+            let line = raw.trim_end();
+            if line.starts_with('{') {
+                if let Some(d) = cargo_json(line) {
+                    found.push(d);
+                }
+                continue;
+            }
+            if let Some(d) = self.match_line(&lines, i, line) {
+                found.push(d);
+            }
+        }
+        found
+    }
+
+    fn match_line(&self, lines: &[&str], i: usize, line: &str) -> Option<Diagnostic> {
+        for pattern in &self.patterns {
+            let Some(caps) = pattern.line.captures(line) else {
+                continue;
+            };
+            let location = match &pattern.location {
+                None => None,
+                Some(loc) => match self.find_location(loc, lines, i) {
+                    Some(found) => Some(found),
+                    None => continue,
+                },
+            };
+            let get = |name: &str| {
+                caps.name(name)
+                    .or_else(|| location.as_ref().and_then(|l: &Captures<'_>| l.name(name)))
+                    .map(|m| m.as_str().trim().to_string())
+            };
+            let Some(file) = get("file") else {
+                continue;
+            };
+            return Some(Diagnostic {
+                severity: get("severity")
+                    .map(|s| Severity::from_label(&s))
+                    .unwrap_or(pattern.severity),
+                file,
+                line: get("line").and_then(|n| n.parse().ok()),
+                col: get("col").and_then(|n| n.parse().ok()),
+                message: get("message").unwrap_or_else(|| "failed".to_string()),
+                code: get("code"),
+            });
+        }
+        None
+    }
+
+    /// The location for the header at `i`, stopping at the next header.
+    fn find_location<'t>(&self, loc: &Regex, lines: &[&'t str], i: usize) -> Option<Captures<'t>> {
+        for next in lines.iter().skip(i + 1).take(LOCATION_LOOKAHEAD) {
+            if let Some(caps) = loc.captures(next) {
+                return Some(caps);
+            }
+            if self.patterns.iter().any(|p| p.line.is_match(next)) {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Cargo JSON (--message-format=json)
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+struct CargoLine {
+    reason: String,
+    message: Option<CargoMessage>,
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    message: String,
+    level: String,
+    code: Option<CargoCode>,
+    #[serde(default)]
+    spans: Vec<CargoSpan>,
+}
+
+#[derive(Deserialize)]
+struct CargoCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct CargoSpan {
+    file_name: String,
+    line_start: u32,
+    column_start: u32,
+    is_primary: bool,
+}
+
+/// A `compiler-message` line with a primary span; anything else is `None`.
+fn cargo_json(line: &str) -> Option<Diagnostic> {
+    let parsed: CargoLine = serde_json::from_str(line).ok()?;
+    if parsed.reason != "compiler-message" {
+        return None;
+    }
+    let message = parsed.message?;
+    let span = message.spans.iter().find(|s| s.is_primary)?;
+    Some(Diagnostic {
+        severity: Severity::from_label(&message.level),
+        file: span.file_name.clone(),
+        line: Some(span.line_start),
+        col: Some(span.column_start),
+        message: message.message,
+        code: message.code.map(|c| c.code),
+    })
+}
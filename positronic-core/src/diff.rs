@@ -0,0 +1,657 @@
+//! Line diff between two command outputs, for `!diff`.
+//!
+//! A plain Myers O(ND) diff over lines, grouped into unified-diff hunks.
+//! Inputs longer than `DiffOptions::max_lines` are cut to that many lines
+//! and the result is marked truncated, and an edit script that would cost
+//! more than `MAX_EDIT_COST` steps is replaced by "remove all, add all" so a
+//! pathological pair of outputs cannot stall the UI.
+//!
+//! The same edit script lines two outputs up side by side (`align`), for
+//! `!compare`: each run of changes becomes rows pairing removed lines with
+//! added ones, the shorter side padded with gaps, so unchanged regions
+//! stay level on both sides.
+//!
+//! The same search diffs a command against its correction word by word
+//! (`diff_command`), for the previews `!fix` and Reflex show.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::tags;
+
+/// Default number of unchanged lines shown around each change.
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// Default cap on lines compared from each side.
+pub const DEFAULT_MAX_LINES: usize = 5000;
+
+/// Edit distance beyond which the search gives up on a minimal diff.
+pub const MAX_EDIT_COST: usize = 2000;
+
+/// Gutters for display lines; the renderer colors by them.
+pub const ADDED_GUTTER: &str = "+│ ";
+pub const REMOVED_GUTTER: &str = "-│ ";
+pub const CONTEXT_GUTTER: &str = " │ ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub context: usize,
+    /// Treat lines that differ only in whitespace runs as equal.
+    pub ignore_space: bool,
+    pub max_lines: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            context: DEFAULT_CONTEXT,
+            ignore_space: false,
+            max_lines: DEFAULT_MAX_LINES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Equal,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// A run of changes with surrounding context. Starts are 0-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl Hunk {
+    /// `@@ -l,s +l,s @@`, numbered the way `diff -u` does.
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{} +{} @@",
+            range(self.old_start, self.old_len),
+            range(self.new_start, self.new_len)
+        )
+    }
+}
+
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    pub hunks: Vec<Hunk>,
+    pub added: usize,
+    pub removed: usize,
+    /// Line counts of the inputs when either exceeded `max_lines`.
+    pub truncated: Option<(usize, usize)>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Standard unified diff text.
+    pub fn unified(&self, old_label: &str, new_label: &str) -> Vec<String> {
+        let mut out = vec![format!("--- {}", old_label), format!("+++ {}", new_label)];
+        for hunk in &self.hunks {
+            out.push(hunk.header());
+            for line in &hunk.lines {
+                let sign = match line.op {
+                    DiffOp::Equal => ' ',
+                    DiffOp::Removed => '-',
+                    DiffOp::Added => '+',
+                };
+                out.push(format!("{}{}", sign, line.text));
+            }
+        }
+        out
+    }
+
+    /// Hunks with gutters instead of bare signs, for the terminal.
+    pub fn display_lines(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for hunk in &self.hunks {
+            out.push(hunk.header());
+            for line in &hunk.lines {
+                let gutter = match line.op {
+                    DiffOp::Equal => CONTEXT_GUTTER,
+                    DiffOp::Removed => REMOVED_GUTTER,
+                    DiffOp::Added => ADDED_GUTTER,
+                };
+                out.push(format!("{}{}", gutter, line.text));
+            }
+        }
+        out
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !diff arguments
+// ════════════════════════════════════════════════════════════════════
+
+pub const DIFF_USAGE: &str =
+    "Usage: !diff [--ignore-space] [--context <n>] [<id|tag> <id|tag> | --watch <command>]";
+
+/// What `!diff` compares.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffRequest {
+    /// The latest stored output against the previous run of that command.
+    Last,
+    /// Two history entries by id, older side first.
+    Ids(i64, i64),
+    /// Two stored outputs, at least one named by a `!tag`.
+    Tagged(DiffOperand, DiffOperand),
+    /// Run the command now and compare with its last stored run.
+    Watch(String),
+}
+
+/// One side of `!diff <a> <b>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOperand {
+    Id(i64),
+    Tag(String),
+}
+
+impl DiffOperand {
+    /// `12` or `#12` is a history id; anything else that can name a tag
+    /// is a tag.
+    fn parse(arg: &str) -> Option<DiffOperand> {
+        match arg.trim_start_matches('#').parse() {
+            Ok(id) => Some(DiffOperand::Id(id)),
+            Err(_) if tags::is_valid_name(arg) => Some(DiffOperand::Tag(arg.to_string())),
+            Err(_) => None,
+        }
+    }
+}
+
+impl DiffRequest {
+    /// Parse the arguments after `!diff`. Flags come before `--watch`;
+    /// everything after it is the command. The error is the usage text.
+    pub fn parse(args: &[&str]) -> Result<(DiffRequest, DiffOptions), String> {
+        let mut opts = DiffOptions::default();
+        let mut operands = Vec::new();
+        let mut iter = args.iter().enumerate();
+        while let Some((i, arg)) = iter.next() {
+            match *arg {
+                "--ignore-space" | "-w" => opts.ignore_space = true,
+                "--context" | "-U" => {
+                    opts.context = iter
+                        .next()
+                        .and_then(|(_, n)| n.parse().ok())
+                        .ok_or_else(|| DIFF_USAGE.to_string())?;
+                }
+                "--watch" => {
+                    let command = args[i + 1..].join(" ");
+                    if command.is_empty() || !operands.is_empty() {
+                        return Err(DIFF_USAGE.to_string());
+                    }
+                    return Ok((DiffRequest::Watch(command), opts));
+                }
+                operand => operands.push(DiffOperand::parse(operand).ok_or_else(|| DIFF_USAGE.to_string())?),
+            }
+        }
+        match <[DiffOperand; 2]>::try_from(operands) {
+            Ok([DiffOperand::Id(a), DiffOperand::Id(b)]) => Ok((DiffRequest::Ids(a, b), opts)),
+            Ok([a, b]) => Ok((DiffRequest::Tagged(a, b), opts)),
+            Err(operands) if operands.is_empty() => Ok((DiffRequest::Last, opts)),
+            Err(_) => Err(DIFF_USAGE.to_string()),
+        }
+    }
+}
+
+/// Diff `old` against `new` line by line.
+pub fn diff_lines(old: &str, new: &str, opts: &DiffOptions) -> Diff {
+    let old_all: Vec<&str> = old.lines().collect();
+    let new_all: Vec<&str> = new.lines().collect();
+    let truncated = (old_all.len() > opts.max_lines || new_all.len() > opts.max_lines)
+        .then_some((old_all.len(), new_all.len()));
+    let a = &old_all[..old_all.len().min(opts.max_lines)];
+    let b = &new_all[..new_all.len().min(opts.max_lines)];
+
+    let ops = line_script(a, b, opts.ignore_space);
+    let added = ops.iter().filter(|op| **op == DiffOp::Added).count();
+    let removed = ops.iter().filter(|op| **op == DiffOp::Removed).count();
+
+    Diff {
+        hunks: group_hunks(&ops, a, b, opts.context),
+        added,
+        removed,
+        truncated,
+    }
+}
+
+/// The edit script between two sets of lines, compared by `line_key`.
+fn line_script(a: &[&str], b: &[&str], ignore_space: bool) -> Vec<DiffOp> {
+    let ka: Vec<Cow<'_, str>> = a.iter().map(|l| line_key(l, ignore_space)).collect();
+    let kb: Vec<Cow<'_, str>> = b.iter().map(|l| line_key(l, ignore_space)).collect();
+    edit_script(&ka, &kb)
+}
+
+/// What lines are compared by: the line itself, or its words joined by
+/// single spaces.
+fn line_key(line: &str, ignore_space: bool) -> Cow<'_, str> {
+    if ignore_space {
+        Cow::Owned(line.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Myers
+// ════════════════════════════════════════════════════════════════════
+
+/// Shortest edit script turning `a` into `b`. The common prefix and suffix
+/// are peeled off first; they are most of the input for re-run outputs.
+fn edit_script<T: PartialEq>(a: &[T], b: &[T]) -> Vec<DiffOp> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops = vec![DiffOp::Equal; prefix];
+    match myers(mid_a, mid_b, MAX_EDIT_COST) {
+        Some(mid) => ops.extend(mid),
+        None => {
+            ops.extend(std::iter::repeat_n(DiffOp::Removed, mid_a.len()));
+            ops.extend(std::iter::repeat_n(DiffOp::Added, mid_b.len()));
+        }
+    }
+    ops.extend(std::iter::repeat_n(DiffOp::Equal, suffix));
+    ops
+}
+
+/// Myers' greedy forward search, keeping the frontier of every round for
+/// the backtrack. `None` if the edit distance exceeds `max_cost`.
+fn myers<T: PartialEq>(a: &[T], b: &[T], max_cost: usize) -> Option<Vec<DiffOp>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m) as usize;
+    if max == 0 {
+        return Some(Vec::new());
+    }
+    let off = max as isize;
+    let mut v = vec![0isize; 2 * max + 2];
+    // trace[d] holds v[-d..=d] as it was before round d.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max.min(max_cost) as isize {
+        trace.push(v[(off - d) as usize..=(off + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[(off + k - 1) as usize] < v[(off + k + 1) as usize]);
+            let mut x = if down {
+                v[(off + k + 1) as usize]
+            } else {
+                v[(off + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(off + k) as usize] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<DiffOp> {
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, before) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        if d == 0 {
+            ops.extend(std::iter::repeat_n(DiffOp::Equal, x as usize));
+            break;
+        }
+        let at = |k: isize| before[(k + d) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && at(k - 1) < at(k + 1));
+        let prev_k = if down { k + 1 } else { k - 1 };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(DiffOp::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        ops.push(if down { DiffOp::Added } else { DiffOp::Removed });
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Hunks
+// ════════════════════════════════════════════════════════════════════
+
+/// Group an edit script into hunks, merging changes whose unchanged gap
+/// is at most twice the context.
+fn group_hunks(ops: &[DiffOp], a: &[&str], b: &[&str], context: usize) -> Vec<Hunk> {
+    // Position in both inputs before each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut i, mut j) = (0, 0);
+    for op in ops {
+        positions.push((i, j));
+        match op {
+            DiffOp::Equal => {
+                i += 1;
+                j += 1;
+            }
+            DiffOp::Removed => i += 1,
+            DiffOp::Added => j += 1,
+        }
+    }
+    positions.push((i, j));
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&p| ops[p] != DiffOp::Equal).collect();
+    let mut hunks = Vec::new();
+    let mut c = 0;
+    while c < changes.len() {
+        let first = changes[c];
+        let mut last = first;
+        while c + 1 < changes.len() && changes[c + 1] - last <= 2 * context + 1 {
+            c += 1;
+            last = changes[c];
+        }
+        c += 1;
+
+        let start = first.saturating_sub(context);
+        let end = (last + 1 + context).min(ops.len());
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        let lines = (start..end)
+            .map(|p| {
+                let (oi, nj) = positions[p];
+                // Unchanged lines read from the old side, as `diff -w` does.
+                let text = match ops[p] {
+                    DiffOp::Equal | DiffOp::Removed => a[oi],
+                    DiffOp::Added => b[nj],
+                };
+                DiffLine { op: ops[p], text: text.to_string() }
+            })
+            .collect();
+        hunks.push(Hunk {
+            old_start,
+            old_len: old_end - old_start,
+            new_start,
+            new_len: new_end - new_start,
+            lines,
+        });
+    }
+    hunks
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Side by side
+// ════════════════════════════════════════════════════════════════════
+
+/// What a row of a side-by-side view holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowOp {
+    /// The same line on both sides.
+    Equal,
+    /// A line on each side, in a run of changes.
+    Changed,
+    /// A line only the left has; the right is a gap.
+    Removed,
+    /// A line only the right has; the left is a gap.
+    Added,
+}
+
+/// One row of a side-by-side view: the line shown on each side, by
+/// 0-based index, `None` for a gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlignedRow {
+    pub op: RowOp,
+    pub left: Option<usize>,
+    pub right: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub rows: Vec<AlignedRow>,
+    /// The rows of each run of changes, in order.
+    pub regions: Vec<Range<usize>>,
+    /// Line counts of the inputs when either exceeded `max_lines`.
+    pub truncated: Option<(usize, usize)>,
+}
+
+impl Alignment {
+    /// First row of the first region that starts after `row`.
+    pub fn next_region(&self, row: usize) -> Option<usize> {
+        self.regions.iter().map(|r| r.start).find(|&start| start > row)
+    }
+
+    /// First row of the last region that starts before `row`.
+    pub fn prev_region(&self, row: usize) -> Option<usize> {
+        self.regions.iter().rev().map(|r| r.start).find(|&start| start < row)
+    }
+}
+
+/// Line `old` and `new` up row by row. Within a run of changes the k-th
+/// removed line sits beside the k-th added one; whichever side has fewer
+/// gets gaps below its lines.
+pub fn align(old: &str, new: &str, opts: &DiffOptions) -> Alignment {
+    let old_all: Vec<&str> = old.lines().collect();
+    let new_all: Vec<&str> = new.lines().collect();
+    let truncated = (old_all.len() > opts.max_lines || new_all.len() > opts.max_lines)
+        .then_some((old_all.len(), new_all.len()));
+    let a = &old_all[..old_all.len().min(opts.max_lines)];
+    let b = &new_all[..new_all.len().min(opts.max_lines)];
+    let ops = line_script(a, b, opts.ignore_space);
+
+    let mut rows = Vec::with_capacity(a.len().max(b.len()));
+    let mut regions = Vec::new();
+    let (mut i, mut j, mut p) = (0, 0, 0);
+    while p < ops.len() {
+        if ops[p] == DiffOp::Equal {
+            rows.push(AlignedRow { op: RowOp::Equal, left: Some(i), right: Some(j) });
+            (i, j, p) = (i + 1, j + 1, p + 1);
+            continue;
+        }
+        let end = ops[p..].iter().position(|op| *op == DiffOp::Equal).map_or(ops.len(), |n| p + n);
+        let removed = ops[p..end].iter().filter(|op| **op == DiffOp::Removed).count();
+        let added = end - p - removed;
+        let start = rows.len();
+        for k in 0..removed.max(added) {
+            let (left, right) = ((k < removed).then_some(i + k), (k < added).then_some(j + k));
+            let op = match (left, right) {
+                (Some(_), Some(_)) => RowOp::Changed,
+                (Some(_), None) => RowOp::Removed,
+                _ => RowOp::Added,
+            };
+            rows.push(AlignedRow { op, left, right });
+        }
+        regions.push(start..rows.len());
+        (i, j, p) = (i + removed, j + added, end);
+    }
+    Alignment { rows, regions, truncated }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Command word diff
+// ════════════════════════════════════════════════════════════════════
+
+/// Markers around removed and added words in `CommandDiff::marked`, as
+/// `git diff --word-diff=plain` writes them; the frontend strikes and
+/// colors what is between.
+pub const REMOVED_OPEN: &str = "[-";
+pub const REMOVED_CLOSE: &str = "-]";
+pub const ADDED_OPEN: &str = "{+";
+pub const ADDED_CLOSE: &str = "+}";
+
+/// Character similarity below which a correction counts as a rewrite and
+/// is shown whole instead of word by word.
+pub const MIN_SIMILARITY: f64 = 0.5;
+
+/// A run of words with the same fate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordChange {
+    pub op: DiffOp,
+    /// The words, joined by single spaces.
+    pub text: String,
+}
+
+/// How a command and its correction differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandDiff {
+    /// The same words.
+    Same,
+    /// Close enough to show inline.
+    Words(Vec<WordChange>),
+    /// Too different for a word diff to help; show both.
+    Rewrite { old: String, new: String },
+}
+
+impl CommandDiff {
+    /// One line with the changes marked: `git [-psuh-]{+push+} origin`,
+    /// or `[-old-] → {+new+}` for a rewrite.
+    pub fn marked(&self) -> String {
+        match self {
+            CommandDiff::Same => String::new(),
+            CommandDiff::Rewrite { old, new } => {
+                format!("{}{}{} → {}{}{}", REMOVED_OPEN, old, REMOVED_CLOSE, ADDED_OPEN, new, ADDED_CLOSE)
+            }
+            CommandDiff::Words(changes) => {
+                let mut out = String::new();
+                let mut previous = None;
+                for change in changes {
+                    // A replacement reads as one unit: `[-a-]{+b+}`.
+                    let replacement = previous == Some(DiffOp::Removed) && change.op == DiffOp::Added;
+                    if !out.is_empty() && !replacement {
+                        out.push(' ');
+                    }
+                    match change.op {
+                        DiffOp::Equal => out.push_str(&change.text),
+                        DiffOp::Removed => out.extend([REMOVED_OPEN, &change.text, REMOVED_CLOSE]),
+                        DiffOp::Added => out.extend([ADDED_OPEN, &change.text, ADDED_CLOSE]),
+                    }
+                    previous = Some(change.op);
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Diff `old` against `new` word by word. Below `MIN_SIMILARITY` the
+/// change is a `Rewrite`: a word diff of two unrelated commands is noise.
+pub fn diff_command(old: &str, new: &str) -> CommandDiff {
+    let a: Vec<&str> = old.split_whitespace().collect();
+    let b: Vec<&str> = new.split_whitespace().collect();
+    if a == b {
+        return CommandDiff::Same;
+    }
+    if similarity(&a.join(" "), &b.join(" ")) < MIN_SIMILARITY {
+        return CommandDiff::Rewrite { old: a.join(" "), new: b.join(" ") };
+    }
+
+    // Each changed region becomes its removed words, then its added ones.
+    let mut changes = Vec::new();
+    let (mut removed, mut added, mut equal) = (Vec::new(), Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    for op in edit_script(&a, &b) {
+        match op {
+            DiffOp::Equal => {
+                flush_region(&mut changes, &mut removed, &mut added);
+                equal.push(a[i]);
+                i += 1;
+                j += 1;
+                continue;
+            }
+            DiffOp::Removed => {
+                removed.push(a[i]);
+                i += 1;
+            }
+            DiffOp::Added => {
+                added.push(b[j]);
+                j += 1;
+            }
+        }
+        if !equal.is_empty() {
+            changes.push(WordChange { op: DiffOp::Equal, text: equal.join(" ") });
+            equal.clear();
+        }
+    }
+    if !equal.is_empty() {
+        changes.push(WordChange { op: DiffOp::Equal, text: equal.join(" ") });
+    }
+    flush_region(&mut changes, &mut removed, &mut added);
+    CommandDiff::Words(changes)
+}
+
+fn flush_region<'a>(changes: &mut Vec<WordChange>, removed: &mut Vec<&'a str>, added: &mut Vec<&'a str>) {
+    for (op, words) in [(DiffOp::Removed, removed), (DiffOp::Added, added)] {
+        if !words.is_empty() {
+            changes.push(WordChange { op, text: words.join(" ") });
+            words.clear();
+        }
+    }
+}
+
+/// Share of characters two strings have in common, 0 to 1: twice the
+/// matched characters over both lengths.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let same = edit_script(&a, &b).iter().filter(|op| **op == DiffOp::Equal).count();
+    2.0 * same as f64 / (a.len() + b.len()) as f64
+}
+
+/// Split a line written by `CommandDiff::marked` back into runs, markers
+/// removed. Text without markers is one `Equal` run; an opener without
+/// its closer stays text.
+pub fn split_marked(line: &str) -> Vec<(DiffOp, &str)> {
+    let mut runs = Vec::new();
+    let mut rest = line;
+    loop {
+        let next = [(REMOVED_OPEN, REMOVED_CLOSE, DiffOp::Removed), (ADDED_OPEN, ADDED_CLOSE, DiffOp::Added)]
+            .into_iter()
+            .filter_map(|(open, close, op)| {
+                let start = rest.find(open)?;
+                let end = rest[start + open.len()..].find(close)? + start + open.len();
+                Some((start, end, open.len(), close.len(), op))
+            })
+            .min_by_key(|(start, ..)| *start);
+        let Some((start, end, open, close, op)) = next else {
+            break;
+        };
+        if start > 0 {
+            runs.push((DiffOp::Equal, &rest[..start]));
+        }
+        runs.push((op, &rest[start + open..end]));
+        rest = &rest[end + close..];
+    }
+    if !rest.is_empty() {
+        runs.push((DiffOp::Equal, rest));
+    }
+    runs
+}
@@ -1,73 +1,280 @@
 //! Positronic Engine — the core coordinator.
 //!
 //! Owns the PTY, state machine, runner, airlock and all subsystem handles.
+//! Only the PTY, state machine and Vault are opened before `start` returns;
+//! Neural, WASM, Hive and IO come up concurrently afterwards (see
+//! `subsystems`), so a slow or broken one never delays first paint.
 //! After the pager-trap bugfix, this module also exposes low-level PTY
 //! control signals (`send_interrupt`, `send_escape`, `send_eof`, `send_raw`)
 //! so the UI can break out of pagers and continuation prompts.
+//!
+//! `start_with` takes `EngineOptions`; headless runs (see `headless`) use
+//! them to pick the data directory (see `data_paths`) and leave Hive and
+//! IO off, since both echo their events into the shell. `EngineOptions::safe_mode` leaves WASM
+//! off and tells the Runner to skip the user's aliases, hooks and
+//! `!setenv` overrides (see `safe_mode`). `EngineOptions::inspect` opens
+//! a file sealed instead of the vault (see `inspect`): the same is left
+//! out, Hive, IO and IPC stay off, and no heartbeat is written.
+//!
+//! IO events are also queued for the UI (`drain_hardware_events`), which
+//! feeds the bridge's hardware panel and scope; parsed samples only go
+//! there, never into the shell. Neither does text from a port attached to
+//! `!io console` (`attach_console`).
+//!
+//! Every PTY chunk passes through the `CwdProbe` first, so probe answers
+//! are removed before the state machine or the UI sees them, and then
+//! through the `BinaryGuard`, which withholds binary command output.
+//! What is left also feeds the `BlockRecorder` (see `blocks`), which logs
+//! each shell command to the vault as it finishes and queues the block
+//! for the UI (`drain_finished_blocks`).
+//!
+//! With `EngineOptions::ipc` on, the engine listens for editors and
+//! scripts (see `ipc`): the endpoint is bound before the shell starts and
+//! handed to it in its environment, the server answers once the Runner
+//! exists, and `drain_ipc_events` hands the window what only it can do.
+//!
+//! When the shell dies, `drain_events` yields `PtyEvent::ShellExited` and
+//! `shell_exit` says how (see `respawn`); `restart_shell` brings up a new
+//! one in the same terminal. Each BEL the emulator sees is a
+//! `PtyEvent::Bell` there too.
+//!
+//! The public methods fail with `PositronicError`: PTY trouble is a
+//! `PtyFailure` the UI can answer with a restart.
 
 use crate::airlock::Airlock;
+use crate::blocks::{BlockRecorder, RecorderEvent, TerminalBlockV2};
+use crate::data_paths::DataPaths;
+use crate::error::{PositronicError, NEURAL_ENDPOINT};
+use crate::inspect::{self, Inspection};
+use crate::ipc::{Endpoint, IpcEvent, IpcListener, IpcServer};
+use crate::maintenance::Activity;
 use crate::pty_manager::PtyManager;
+use crate::respawn::{ShellExit, ShellLife};
 use crate::runner::Runner;
+use crate::safe_mode;
 use crate::state_machine::StateMachine;
-use crate::vault::Vault;
+use crate::subsystems::{Subsystem, Subsystems};
+use crate::term::binary::BinaryGuard;
+use crate::term::latency::LatencyProbe;
+use crate::term::running::{RunningInfo, RunningTracker};
+use crate::term::probe::{CwdProbe, ProbeMode, CWD_PROBE_KEY, PROBE_KEY};
+use crate::scaffold::Scaffolds;
+use crate::serial;
+use crate::rename::Renames;
+use crate::trash::Trash;
+use crate::tldr::Tldr;
+use crate::vault::{crypto, Vault, HEARTBEAT_INTERVAL};
+use crate::PtyEvent;
 
 use anyhow::{Context, Result};
+use bytes::Bytes;
 use positronic_hive::{HiveEvent, HiveNode};
-use positronic_io::{HardwareEvent, HardwareMonitor};
+use positronic_io::{HardwareEvent, HardwareMonitor, Platform};
 use positronic_neural::cortex::NeuralClient;
 use positronic_script::wasm_host::WasmHost;
 
-use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
+use std::collections::{HashSet, VecDeque};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify, broadcast, mpsc};
 
 // Re-export so `positronic_core::engine::ExecuteResult` keeps working.
 pub use crate::runner::ExecuteResult;
 
+/// What `PositronicEngine::start_with` brings up.
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    pub cols: u16,
+    pub rows: u16,
+    /// Where the vault and the rest of the data live (see `data_paths`);
+    /// `DataPaths::for_vault(":memory:")` keeps no history.
+    pub data: DataPaths,
+    /// Start Hive and IO. Their events are echoed into the shell, which
+    /// would interleave with a headless command's output.
+    pub peripherals: bool,
+    /// The shell program; `None` picks the platform default.
+    pub shell: Option<String>,
+    /// Listen for local IPC requests (see `ipc`). Only the window does.
+    pub ipc: bool,
+    /// Leave out aliases, hooks, `!setenv` and WASM plugins (see
+    /// `safe_mode`).
+    pub safe_mode: bool,
+    /// Browse this vault, `!sync` bundle or `!export` file read-only in
+    /// place of the vault (see `inspect`).
+    pub inspect: Option<PathBuf>,
+    /// The split pane this engine's shell runs in: its history rows carry
+    /// it, and `!history`, `!search` and `!export` read only those. None
+    /// for an unsplit window, which logs untagged and reads every row.
+    pub pane: Option<u32>,
+    /// Log shell commands to the vault as they finish (see `blocks`).
+    /// Headless mode logs its own, under the line as typed.
+    pub log_commands: bool,
+}
+
+impl EngineOptions {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        Self {
+            cols,
+            rows,
+            data: DataPaths::current(None),
+            peripherals: true,
+            shell: None,
+            ipc: false,
+            safe_mode: false,
+            inspect: None,
+            pane: None,
+            log_commands: true,
+        }
+    }
+}
+
+/// Hardware events kept for the UI between drains; the oldest go first.
+pub const HARDWARE_EVENT_CAP: usize = 4096;
+
+/// Finished shell blocks kept for the UI between drains; the oldest go first.
+pub const FINISHED_BLOCK_CAP: usize = 256;
+
 #[derive(Debug)]
 pub struct PositronicEngine {
     pub pty: Arc<Mutex<PtyManager>>,
     pub state: Arc<StateMachine>,
     pub runner: Arc<Runner>,
     pub airlock: Arc<Airlock>,
-    pub pty_output_buf: Arc<std::sync::Mutex<Vec<u8>>>,
+    pub pty_output_buf: Arc<std::sync::Mutex<Vec<Bytes>>>,
+    /// Input-to-render timing; the UI stamps keys, submits and frames.
+    pub latency: Arc<LatencyProbe>,
+    cwd_probe: Arc<StdMutex<CwdProbe>>,
+    running: Arc<StdMutex<RunningTracker>>,
+    hardware_events: Arc<StdMutex<VecDeque<HardwareEvent>>>,
+    /// Ports whose text goes to `!io console` rather than the shell.
+    console_ports: Arc<StdMutex<HashSet<String>>>,
+    shell: Option<String>,
+    /// Set in every shell's environment: the IPC endpoint, if bound.
+    shell_env: Vec<(OsString, OsString)>,
+    /// What `EngineOptions::inspect` opened.
+    inspection: Option<Inspection>,
+    /// Taken by `stop_ipc`.
+    ipc: StdMutex<Option<IpcServer>>,
+    pump: ShellPump,
     redraw_notifier: mpsc::Sender<()>,
 }
 
 impl PositronicEngine {
-    pub async fn start(cols: u16, rows: u16, redraw_tx: mpsc::Sender<()>) -> Result<Self> {
-        let mut pty_manager = PtyManager::new(cols, rows).context("Failed to create PTY")?;
-        let mut rx_ptr = pty_manager
+    pub async fn start(cols: u16, rows: u16, redraw_tx: mpsc::Sender<()>) -> Result<Self, PositronicError> {
+        Self::start_with(EngineOptions::new(cols, rows), redraw_tx).await
+    }
+
+    pub async fn start_with(options: EngineOptions, redraw_tx: mpsc::Sender<()>) -> Result<Self, PositronicError> {
+        let EngineOptions { cols, rows, data, peripherals, shell, ipc, safe_mode, inspect, pane, log_commands } = options;
+        // The inspected file's aliases, hooks and plugins are someone
+        // else's: leave them out as safe mode does.
+        let (peripherals, ipc, safe_mode) =
+            if inspect.is_some() { (false, false, true) } else { (peripherals, ipc, safe_mode) };
+        // A root that can't be created fails the vault open below, which
+        // falls back to in-memory history.
+        if let Err(e) = data.ensure() {
+            eprintln!("[ENGINE] Creating {} failed: {}", data.root().display(), e);
+        }
+        // Bound first, so the shell is only told of an endpoint that exists.
+        let ipc_listener =
+            ipc.then(|| IpcListener::bind(Endpoint::for_session(data.ipc_parent(), std::process::id())));
+        let shell_env = match &ipc_listener {
+            Some(Ok(listener)) => listener.endpoint().env(),
+            _ => Vec::new(),
+        };
+        let mut pty_manager = PtyManager::with_shell(cols, rows, shell.as_deref(), &shell_env)
+            .context("Failed to create PTY")
+            .map_err(PositronicError::pty)?;
+        let rx_ptr = pty_manager
             .start_reader()
-            .context("Failed to start PTY reader")?;
+            .context("Failed to start PTY reader")
+            .map_err(PositronicError::pty)?;
+        let cwd_probe = Arc::new(StdMutex::new(CwdProbe::new(pty_manager.cwd_probe_supported())));
 
         let pty = Arc::new(Mutex::new(pty_manager));
         let state = Arc::new(StateMachine::new(cols, rows));
-        let pty_output_buf: Arc<std::sync::Mutex<Vec<u8>>> =
-            Arc::new(std::sync::Mutex::new(Vec::with_capacity(8192)));
+        let pty_output_buf: Arc<std::sync::Mutex<Vec<Bytes>>> =
+            Arc::new(std::sync::Mutex::new(Vec::with_capacity(64)));
+        let binary_guard = Arc::new(StdMutex::new(BinaryGuard::new()));
+        let running = Arc::new(StdMutex::new(RunningTracker::new()));
+        let hardware_events = Arc::new(StdMutex::new(VecDeque::new()));
+        let console_ports = Arc::new(StdMutex::new(HashSet::new()));
+        let latency = Arc::new(LatencyProbe::new());
+        let activity = Arc::new(Activity::new());
 
-        // PTY reader pump — feeds bytes into state machine and output buffer
-        {
-            let state_clone = state.clone();
-            let buf_clone = pty_output_buf.clone();
-            let notifier = redraw_tx.clone();
-            tokio::spawn(async move {
-                while let Some(bytes) = rx_ptr.recv().await {
-                    if let Ok(mut buf) = buf_clone.lock() {
-                        buf.extend_from_slice(&bytes);
-                    }
-                    state_clone.process_bytes(&bytes);
-
-                    // Drain any immediately-available follow-up chunks
-                    while let Ok(more) = rx_ptr.try_recv() {
-                        if let Ok(mut buf) = buf_clone.lock() {
-                            buf.extend_from_slice(&more);
-                        }
-                        state_clone.process_bytes(&more);
-                    }
-                    let _ = notifier.try_send(());
+        let airlock = Arc::new(Airlock::new());
+        let subsystems = Subsystems::new();
+
+        // Vault stays on the critical path (history, aliases, completions),
+        // but a failure only costs persistence: fall back to in-memory.
+        subsystems.begin("vault");
+        let (vault, inspection) = match &inspect {
+            // The file was named: failing beats showing an empty vault
+            // in its place.
+            Some(path) => {
+                let (vault, inspection) = inspect::open(path)?;
+                subsystems.ready("vault");
+                (vault, Some(inspection))
+            }
+            None => match Vault::open(data.vault()) {
+                Ok(v) => {
+                    subsystems.ready("vault");
+                    (v, None)
                 }
-            });
+                Err(e) => {
+                    eprintln!("[ENGINE] Vault open failed, using in-memory history: {}", e);
+                    subsystems.fail("vault", format!("{} (history is in-memory)", e));
+                    (Vault::open(":memory:")?, None)
+                }
+            },
+        };
+        // An encrypted vault boots locked unless the passphrase is in the
+        // environment; `!vault unlock` asks for it later.
+        if vault.is_locked() {
+            if let Ok(passphrase) = std::env::var(crypto::PASSPHRASE_ENV) {
+                if let Err(e) = vault.unlock(&passphrase) {
+                    eprintln!("[ENGINE] Vault stays locked: {}", e);
+                }
+            }
+        }
+        let vault = match pane {
+            Some(pane) => vault.for_pane(pane),
+            None => vault,
+        };
+        if let Some(report) = vault.recovery() {
+            eprintln!("[ENGINE] {}", report.notice());
+        }
+        if vault.stale_sessions_closed() > 0 {
+            eprintln!("[ENGINE] Closed {} session(s) left open by crashed instances", vault.stale_sessions_closed());
         }
+        if !vault.is_inspect() {
+            spawn_heartbeat(vault.clone());
+        }
+
+        // The pump starts once the vault is open: the recorder logs to it.
+        let mut recorder = BlockRecorder::new(vault.clone());
+        recorder.set_logging(log_commands);
+        let recorder = Arc::new(StdMutex::new(recorder));
+        let pump = ShellPump {
+            pty: pty.clone(),
+            state: state.clone(),
+            output: pty_output_buf.clone(),
+            cwd_probe: cwd_probe.clone(),
+            binary_guard: binary_guard.clone(),
+            running: running.clone(),
+            recorder: recorder.clone(),
+            finished: Arc::new(StdMutex::new(VecDeque::new())),
+            latency: latency.clone(),
+            activity: activity.clone(),
+            life: Arc::new(StdMutex::new(ShellLife::new(Instant::now()))),
+            events: Arc::new(StdMutex::new(VecDeque::new())),
+            generation: Arc::new(AtomicU64::new(0)),
+            notifier: redraw_tx.clone(),
+        };
+        pump.spawn(rx_ptr);
 
         // Kick the shell so the initial prompt appears
         {
@@ -76,74 +283,44 @@ impl PositronicEngine {
         }
         let _ = redraw_tx.try_send(());
 
-        let airlock = Arc::new(Airlock::new());
-
-        let neural = Arc::new(NeuralClient::new(
-            "http://localhost:8000/api/v1",
-            "auto",
-        ));
+        // ── Everything below initializes concurrently; none of it blocks boot ──
 
-        let vault = Vault::open("positronic.db").context("Failed to open Vault")?;
-        let wasm_host = Arc::new(WasmHost::new().context("Failed to init WASM host")?);
+        let neural = subsystems.spawn("neural", async { Ok(connect_neural(NEURAL_ENDPOINT).await?) });
 
-        let (hive_node, mut hive_rx) = HiveNode::new("PositronicUser");
-        let hive = Arc::new(hive_node);
+        let wasm_host = if safe_mode {
+            Subsystem::failed("wasm", safe_mode::WASM_OFF)
+        } else {
+            subsystems.spawn("wasm", async {
+                tokio::task::spawn_blocking(WasmHost::new)
+                    .await
+                    .context("WASM init task panicked")?
+                    .context("Failed to init WASM host")
+            })
+        };
 
-        // Hive event pump — echo peer events into the PTY
-        {
+        let (hive, io) = if peripherals {
             let pty_for_hive = pty.clone();
-            let (tx, mut rx) = mpsc::channel::<String>(32);
-
-            tokio::spawn(async move {
-                while let Some(cmd) = rx.recv().await {
-                    let mut p = pty_for_hive.lock().await;
-                    let _ = p.write_line(&cmd);
-                }
-            });
-
-            tokio::spawn(async move {
-                while let Ok(event) = hive_rx.recv().await {
-                    let msg = match event {
-                        HiveEvent::PeerDiscovered { peer_id, name } => {
-                            format!("📡 Peer: {} ({})", name, peer_id)
-                        }
-                        HiveEvent::PeerLost { peer_id } => {
-                            format!("📡 Peer Lost: {}", peer_id)
-                        }
-                        HiveEvent::BlockReceived { from, content } => {
-                            let text = String::from_utf8_lossy(&content);
-                            format!("💬 [{}]: {}", from, text)
-                        }
-                        HiveEvent::LiveSessionInvite { from, session_id } => {
-                            format!("📞 Invite from {}: {}", from, session_id)
-                        }
-                        HiveEvent::Error(e) => format!("⚠️ Hive: {}", e),
-                    };
-                    let _ = tx.send(shell_echo_cmd(&msg)).await;
-                }
+            let hive = subsystems.spawn("hive", async move {
+                let (hive_node, hive_rx) = HiveNode::new("PositronicUser");
+                spawn_hive_pump(pty_for_hive, hive_rx);
+                Ok(hive_node)
             });
-        }
 
-        // Hardware I/O pump
-        let (hardware_monitor, mut io_rx) = HardwareMonitor::start();
-        let io = Arc::new(hardware_monitor);
-
-        {
             let pty_for_io = pty.clone();
-            tokio::spawn(async move {
-                while let Some(event) = io_rx.recv().await {
-                    let msg = match event {
-                        HardwareEvent::DeviceConnected(n) => format!("🔌 Connected: {}", n),
-                        HardwareEvent::DeviceDisconnected(n) => format!("🔌 Disconnected: {}", n),
-                        HardwareEvent::DataBatch(_) => continue,
-                        HardwareEvent::SerialOutput(s) => s,
-                        HardwareEvent::Error(e) => format!("⚠️ IO: {}", e),
-                    };
-                    let mut p = pty_for_io.lock().await;
-                    let _ = p.write_line(&shell_echo_cmd(&msg));
-                }
+            let queue = hardware_events.clone();
+            let consoles = console_ports.clone();
+            let notifier = redraw_tx.clone();
+            let devices = vault.clone();
+            let io = subsystems.spawn("io", async move {
+                let (hardware_monitor, io_rx) = HardwareMonitor::start();
+                spawn_io_pump(pty_for_io, io_rx, queue, consoles, notifier, devices);
+                Ok(hardware_monitor)
             });
-        }
+            (hive, io)
+        } else {
+            // Not registered: switched off is not degraded.
+            (Subsystem::failed("hive", PERIPHERALS_OFF), Subsystem::failed("io", PERIPHERALS_OFF))
+        };
 
         let runner = Arc::new(Runner::new(
             pty.clone(),
@@ -153,7 +330,22 @@ impl PositronicEngine {
             wasm_host,
             hive,
             io,
+            subsystems,
+            binary_guard,
+            running.clone(),
+            recorder,
+            Arc::new(Tldr::new(data.tldr())),
+            Scaffolds::new(data.scaffolds()),
+            Trash::new(data.trash()),
+            Renames::new(data.rename_log()),
+            latency.clone(),
+            data,
+            safe_mode,
         ));
+        runner.load_shell_commands(shell.as_deref());
+        // The completion and search indexes build in the background.
+        runner.start_maintenance(activity);
+        let ipc = ipc_listener.and_then(|listener| start_ipc(listener, &runner, redraw_tx.clone()));
 
         Ok(Self {
             pty,
@@ -161,6 +353,16 @@ impl PositronicEngine {
             runner,
             airlock,
             pty_output_buf,
+            latency,
+            cwd_probe,
+            running,
+            hardware_events,
+            console_ports,
+            shell,
+            shell_env,
+            inspection,
+            ipc: StdMutex::new(ipc),
+            pump,
             redraw_notifier: redraw_tx,
         })
     }
@@ -169,11 +371,33 @@ impl PositronicEngine {
     // High-level command interface
     // ────────────────────────────────────────────────────────────────
 
-    pub async fn send_input(&self, data: &str) -> Result<ExecuteResult> {
+    pub async fn send_input(&self, data: &str) -> Result<ExecuteResult, PositronicError> {
         self.runner.execute(data).await
     }
 
-    pub fn drain_pty_output(&self) -> Vec<u8> {
+    /// History completions for `prefix`, best first. Never touches the Vault.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
+        self.runner.suggest(prefix, limit)
+    }
+
+    /// The file being inspected, when started with `EngineOptions::inspect`.
+    pub fn inspection(&self) -> Option<&Inspection> {
+        self.inspection.as_ref()
+    }
+
+    /// Per-subsystem readiness and init timing.
+    pub fn subsystems(&self) -> &Subsystems {
+        self.runner.subsystems()
+    }
+
+    /// The command running in the shell, if any (see `term::running`).
+    /// Consult this rather than tracking commands separately.
+    pub fn running_command(&self) -> Option<RunningInfo> {
+        lock_running(&self.running).running(Instant::now())
+    }
+
+    /// Take the PTY chunks received since the last call, in order.
+    pub fn drain_pty_output(&self) -> Vec<Bytes> {
         match self.pty_output_buf.lock() {
             Ok(mut buf) => std::mem::take(&mut *buf),
             Err(poisoned) => {
@@ -183,9 +407,142 @@ impl PositronicEngine {
         }
     }
 
-    pub async fn resize(&self, cols: u16, rows: u16) -> Result<()> {
+    /// Take the hardware events received since the last call, in order.
+    pub fn drain_hardware_events(&self) -> Vec<HardwareEvent> {
+        let mut queue = self.hardware_events.lock().unwrap_or_else(|p| p.into_inner());
+        queue.drain(..).collect()
+    }
+
+    /// `notify` and `ingest` requests received over IPC since the last call.
+    pub fn drain_ipc_events(&self) -> Vec<IpcEvent> {
+        let ipc = self.ipc.lock().unwrap_or_else(|p| p.into_inner());
+        ipc.as_ref().map(IpcServer::drain_events).unwrap_or_default()
+    }
+
+    /// Where this session listens for IPC, if it does.
+    pub fn ipc_endpoint(&self) -> Option<Endpoint> {
+        let ipc = self.ipc.lock().unwrap_or_else(|p| p.into_inner());
+        ipc.as_ref().map(|server| server.endpoint().clone())
+    }
+
+    /// Stop listening and remove the socket and token files.
+    pub fn stop_ipc(&self) {
+        self.ipc.lock().unwrap_or_else(|p| p.into_inner()).take();
+    }
+
+    /// Take the engine events since the last call, in order.
+    pub fn drain_events(&self) -> Vec<PtyEvent> {
+        lock_events(&self.pump.events).drain(..).collect()
+    }
+
+    /// Take the shell commands finished since the last call, in order.
+    pub fn drain_finished_blocks(&self) -> Vec<TerminalBlockV2> {
+        self.pump.finished.lock().unwrap_or_else(|p| p.into_inner()).drain(..).collect()
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Shell restart
+    // ────────────────────────────────────────────────────────────────
+
+    /// How the shell exited, while it is dead.
+    pub fn shell_exit(&self) -> Option<ShellExit> {
+        lock_life(&self.pump.life).exit().cloned()
+    }
+
+    /// How long `restart_shell` would wait to back off a crash loop.
+    pub fn restart_delay(&self) -> Duration {
+        lock_life(&self.pump.life).restart_delay(Instant::now())
+    }
+
+    /// Replace a dead shell with a fresh one from the same config, after
+    /// any crash-loop backoff. The screen is cleared into the scrollback,
+    /// the Vault session starts over, and the new shell `cd`s to `cwd`.
+    pub async fn restart_shell(&self, cwd: &str) -> Result<(), PositronicError> {
+        let delay = self.restart_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let (cols, rows) = self.state.size();
+        let mut manager = PtyManager::with_shell(cols, rows, self.shell.as_deref(), &self.shell_env)
+            .context("Failed to create PTY")
+            .map_err(PositronicError::pty)?;
+        let rx = manager.start_reader().context("Failed to start PTY reader").map_err(PositronicError::pty)?;
+        let probe = CwdProbe::new(manager.cwd_probe_supported());
+        {
+            let mut pty = self.pty.lock().await;
+            // The old exit watch stands down once it sees the new generation.
+            self.pump.generation.fetch_add(1, Ordering::AcqRel);
+            *pty = manager;
+            *lock_probe(&self.cwd_probe) = probe;
+            *lock_running(&self.running) = RunningTracker::new();
+            lock_recorder(&self.pump.recorder).reset();
+            *self.pump.binary_guard.lock().unwrap_or_else(|p| p.into_inner()) = BinaryGuard::new();
+            lock_life(&self.pump.life).restarted(Instant::now());
+            self.state.clear_screen();
+            if Path::new(cwd).is_dir() {
+                pty.write_line(&shell_cd_cmd(cwd)).map_err(PositronicError::pty)?;
+                self.runner.set_cwd(cwd);
+            } else {
+                pty.write_line("").map_err(PositronicError::pty)?;
+            }
+        }
+        self.pump.spawn(rx);
+        if let Err(e) = self.runner.vault().new_session() {
+            eprintln!("[ENGINE] Starting a new vault session failed: {}", e);
+        }
+        let _ = self.redraw_notifier.try_send(());
+        Ok(())
+    }
+
+    /// Stop echoing `port`'s text into the shell: `!io console` shows it.
+    pub fn attach_console(&self, port: &str) {
+        self.console_ports.lock().unwrap_or_else(|p| p.into_inner()).insert(port.to_string());
+    }
+
+    /// Echo `port`'s text into the shell again.
+    pub fn detach_console(&self, port: &str) {
+        self.console_ports.lock().unwrap_or_else(|p| p.into_inner()).remove(port);
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Working directory probe
+    // ────────────────────────────────────────────────────────────────
+
+    /// Ask the shell for its working directory (see `term::probe`).
+    ///
+    /// With `at_next_prompt`, the probe waits for the prompt after the
+    /// command being submitted, so call it *before* `send_input`. Returns
+    /// false if probing is off (`cwd.probe = off`) or unsupported by the
+    /// shell. The answer arrives later through `take_probed_cwd`.
+    pub async fn probe_cwd(&self, at_next_prompt: bool) -> Result<bool, PositronicError> {
+        let mode = ProbeMode::from_config(
+            self.runner.vault().get_config(CWD_PROBE_KEY).ok().flatten().as_deref(),
+        );
+        let due = {
+            let mut probe = lock_probe(&self.cwd_probe);
+            probe.set_mode(mode);
+            if !probe.request(at_next_prompt) {
+                return Ok(false);
+            }
+            probe.take_due(Instant::now())
+        };
+        if due {
+            self.pty.lock().await.write_raw(PROBE_KEY).map_err(PositronicError::pty)?;
+        }
+        Ok(true)
+    }
+
+    /// The shell-reported working directory, if a probe answered since the
+    /// last call. Also updates the Runner's cwd.
+    pub fn take_probed_cwd(&self) -> Option<String> {
+        let cwd = lock_probe(&self.cwd_probe).take_cwd()?;
+        self.runner.set_cwd(&cwd);
+        Some(cwd)
+    }
+
+    pub async fn resize(&self, cols: u16, rows: u16) -> Result<(), PositronicError> {
         let mut pty = self.pty.lock().await;
-        pty.resize(cols, rows)?;
+        pty.resize(cols, rows).map_err(PositronicError::pty)?;
         self.state.resize(cols, rows);
         let _ = self.redraw_notifier.try_send(());
         Ok(())
@@ -199,38 +556,371 @@ impl PositronicEngine {
     // ────────────────────────────────────────────────────────────────
 
     /// Send Ctrl+C (ETX / 0x03) — interrupt the running process / exit pager.
-    pub async fn send_interrupt(&self) -> Result<()> {
+    pub async fn send_interrupt(&self) -> Result<(), PositronicError> {
         let mut pty = self.pty.lock().await;
-        pty.write_raw("\x03")?;
+        pty.write_raw("\x03").map_err(PositronicError::pty)?;
         let _ = self.redraw_notifier.try_send(());
         Ok(())
     }
 
     /// Send Escape (0x1b) — exit vi-style pagers, cancel prompts.
-    pub async fn send_escape(&self) -> Result<()> {
+    pub async fn send_escape(&self) -> Result<(), PositronicError> {
         let mut pty = self.pty.lock().await;
-        pty.write_raw("\x1b")?;
+        pty.write_raw("\x1b").map_err(PositronicError::pty)?;
         let _ = self.redraw_notifier.try_send(());
         Ok(())
     }
 
     /// Send Ctrl+D (EOT / 0x04) — signal end-of-input.
-    pub async fn send_eof(&self) -> Result<()> {
+    pub async fn send_eof(&self) -> Result<(), PositronicError> {
         let mut pty = self.pty.lock().await;
-        pty.write_raw("\x04")?;
+        pty.write_raw("\x04").map_err(PositronicError::pty)?;
         let _ = self.redraw_notifier.try_send(());
         Ok(())
     }
 
     /// Send arbitrary raw data to the PTY (no newline appended).
-    pub async fn send_raw(&self, data: &str) -> Result<()> {
+    pub async fn send_raw(&self, data: &str) -> Result<(), PositronicError> {
         let mut pty = self.pty.lock().await;
-        pty.write_raw(data)?;
+        pty.write_raw(data).map_err(PositronicError::pty)?;
         let _ = self.redraw_notifier.try_send(());
         Ok(())
     }
 }
 
+/// Bring up the IPC server as the "ipc" subsystem. Without it the window
+/// works as before, so a failure to bind is only reported.
+fn start_ipc(listener: anyhow::Result<IpcListener>, runner: &Arc<Runner>, notifier: mpsc::Sender<()>) -> Option<IpcServer> {
+    let subsystems = &runner.subsystems;
+    subsystems.begin("ipc");
+    match listener {
+        Ok(listener) => {
+            let server = listener.serve(runner.clone(), move || {
+                let _ = notifier.try_send(());
+            });
+            subsystems.ready("ipc");
+            Some(server)
+        }
+        Err(e) => {
+            eprintln!("[ENGINE] IPC unavailable: {:#}", e);
+            subsystems.fail("ipc", format!("{:#}", e));
+            None
+        }
+    }
+}
+
+/// Why Hive and IO are unavailable when `EngineOptions::peripherals` is off.
+const PERIPHERALS_OFF: &str = "off in headless mode";
+
+/// How long the neural probe waits for Lemonade before marking it unavailable.
+const NEURAL_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Reach the model server at `endpoint` and list its models. Full
+/// per-model probing continues in the background.
+pub async fn connect_neural(endpoint: &str) -> Result<NeuralClient, PositronicError> {
+    let client = NeuralClient::new(endpoint, "auto");
+    match tokio::time::timeout(NEURAL_PROBE_TIMEOUT, client.refresh_listing()).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(PositronicError::neural(endpoint, format!("no answer from Lemonade: {:#}", e))),
+        Err(_) => return Err(PositronicError::neural(endpoint, "no response from Lemonade")),
+    }
+    client.spawn_prober();
+    Ok(client)
+}
+
+fn lock_probe(probe: &StdMutex<CwdProbe>) -> MutexGuard<'_, CwdProbe> {
+    probe.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_running(tracker: &StdMutex<RunningTracker>) -> MutexGuard<'_, RunningTracker> {
+    tracker.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_recorder(recorder: &StdMutex<BlockRecorder>) -> MutexGuard<'_, BlockRecorder> {
+    recorder.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn guard_output(guard: &StdMutex<BinaryGuard>, chunk: Bytes) -> Bytes {
+    guard.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).process(chunk)
+}
+
+fn lock_life(life: &StdMutex<ShellLife>) -> MutexGuard<'_, ShellLife> {
+    life.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn lock_events(events: &StdMutex<VecDeque<PtyEvent>>) -> MutexGuard<'_, VecDeque<PtyEvent>> {
+    events.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// How often a live shell is checked for exit. Reader EOF checks at once,
+/// then every `EOF_POLL_INTERVAL` until the child can be reaped.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const EOF_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How long an exit seen before reader EOF waits for the remaining output.
+const EOF_GRACE: Duration = Duration::from_millis(200);
+
+/// Bells kept for the UI between drains.
+const MAX_QUEUED_BELLS: usize = 64;
+
+/// What the PTY reader pump and the exit watch share with the engine.
+#[derive(Debug, Clone)]
+struct ShellPump {
+    pty: Arc<Mutex<PtyManager>>,
+    state: Arc<StateMachine>,
+    output: Arc<StdMutex<Vec<Bytes>>>,
+    cwd_probe: Arc<StdMutex<CwdProbe>>,
+    binary_guard: Arc<StdMutex<BinaryGuard>>,
+    running: Arc<StdMutex<RunningTracker>>,
+    recorder: Arc<StdMutex<BlockRecorder>>,
+    /// Blocks the recorder finished, until the UI takes them.
+    finished: Arc<StdMutex<VecDeque<TerminalBlockV2>>>,
+    latency: Arc<LatencyProbe>,
+    /// When the shell last printed, for the maintenance worker's gate.
+    activity: Arc<Activity>,
+    life: Arc<StdMutex<ShellLife>>,
+    events: Arc<StdMutex<VecDeque<PtyEvent>>>,
+    /// Bumped by each restart; tasks of an older shell stop.
+    generation: Arc<AtomicU64>,
+    notifier: mpsc::Sender<()>,
+}
+
+impl ShellPump {
+    /// Start the reader pump and exit watch for the shell now in `pty`.
+    fn spawn(&self, rx: mpsc::Receiver<Bytes>) {
+        let eof = Arc::new(Notify::new());
+        self.spawn_reader(rx, eof.clone());
+        self.spawn_exit_watch(eof);
+    }
+
+    /// PTY reader pump — strips probe answers and binary output, then
+    /// feeds bytes into the recorder, state machine and output buffer. Chunks are
+    /// refcounted `Bytes`, so the UI-side queue shares them.
+    fn spawn_reader(&self, mut rx: mpsc::Receiver<Bytes>, eof: Arc<Notify>) {
+        let pump = self.clone();
+        tokio::spawn(async move {
+            while let Some(bytes) = rx.recv().await {
+                let mut chunk = Some(bytes);
+                // Drain any immediately-available follow-up chunks
+                while let Some(bytes) = chunk.take().or_else(|| rx.try_recv().ok()) {
+                    let visible = lock_probe(&pump.cwd_probe).process(bytes);
+                    let visible = guard_output(&pump.binary_guard, visible);
+                    if visible.is_empty() {
+                        continue;
+                    }
+                    let read_at = Instant::now();
+                    pump.latency.output_read(&visible, read_at);
+                    pump.activity.output_seen(read_at);
+                    lock_running(&pump.running).feed(&visible, read_at);
+                    pump.record(&visible);
+                    lock_life(&pump.life).feed(&visible);
+                    pump.state.process_bytes(&visible);
+                    pump.latency.output_applied(Instant::now());
+                    pump.queue_bells();
+                    if let Ok(mut buf) = pump.output.lock() {
+                        buf.push(visible);
+                    }
+                }
+
+                // A requested probe goes out once the shell is back at its prompt.
+                let due = lock_probe(&pump.cwd_probe).take_due(Instant::now());
+                if due {
+                    let _ = pump.pty.lock().await.write_raw(PROBE_KEY);
+                }
+                let _ = pump.notifier.try_send(());
+            }
+            eof.notify_one();
+        });
+    }
+
+    /// Pass on the bells the emulator has seen as `PtyEvent::Bell`, up to
+    /// `MAX_QUEUED_BELLS` waiting at once; the UI coalesces them anyway.
+    /// Feed the recorder and queue the blocks it finishes.
+    fn record(&self, chunk: &[u8]) {
+        let mut events = Vec::new();
+        lock_recorder(&self.recorder).on_pty_output(chunk, &mut events);
+        if events.is_empty() {
+            return;
+        }
+        let mut queue = self.finished.lock().unwrap_or_else(|p| p.into_inner());
+        for event in events {
+            if let RecorderEvent::BlockFinished(block) = event {
+                if queue.len() >= FINISHED_BLOCK_CAP {
+                    queue.pop_front();
+                }
+                queue.push_back(block);
+            }
+        }
+    }
+
+    fn queue_bells(&self) {
+        let bells = self.state.take_bells();
+        if bells == 0 {
+            return;
+        }
+        let mut events = lock_events(&self.events);
+        let queued = events.iter().filter(|e| matches!(e, PtyEvent::Bell)).count();
+        for _ in queued..(queued + bells).min(MAX_QUEUED_BELLS) {
+            events.push_back(PtyEvent::Bell);
+        }
+    }
+
+    /// Report the shell's exit once, as `PtyEvent::ShellExited`.
+    fn spawn_exit_watch(&self, eof: Arc<Notify>) {
+        let pump = self.clone();
+        let generation = self.generation.load(Ordering::Acquire);
+        tokio::spawn(async move {
+            let mut closed = false;
+            loop {
+                if closed {
+                    tokio::time::sleep(EOF_POLL_INTERVAL).await;
+                } else {
+                    closed = tokio::time::timeout(EXIT_POLL_INTERVAL, eof.notified()).await.is_ok();
+                }
+                let code = {
+                    let mut pty = pump.pty.lock().await;
+                    if pump.generation.load(Ordering::Acquire) != generation {
+                        return;
+                    }
+                    pty.exit_code()
+                };
+                let Some(code) = code else {
+                    continue;
+                };
+                // Let the reader pass on the shell's last words first.
+                if !closed {
+                    let _ = tokio::time::timeout(EOF_GRACE, eof.notified()).await;
+                }
+                eprintln!("[ENGINE] Shell exited with code {}", code);
+                if lock_life(&pump.life).exited(code, Instant::now()).is_some() {
+                    lock_events(&pump.events).push_back(PtyEvent::ShellExited(code));
+                    let _ = pump.notifier.try_send(());
+                }
+                return;
+            }
+        });
+    }
+}
+
+/// Keep this instance's session alive for `!stats` and other instances.
+fn spawn_heartbeat(vault: Vault) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(HEARTBEAT_INTERVAL);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            if let Err(e) = vault.heartbeat() {
+                eprintln!("[ENGINE] Vault heartbeat failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Hive event pump — echo peer events into the PTY.
+fn spawn_hive_pump(pty: Arc<Mutex<PtyManager>>, mut hive_rx: broadcast::Receiver<HiveEvent>) {
+    let (tx, mut rx) = mpsc::channel::<String>(32);
+
+    tokio::spawn(async move {
+        while let Some(cmd) = rx.recv().await {
+            let mut p = pty.lock().await;
+            let _ = p.write_line(&cmd);
+        }
+    });
+
+    tokio::spawn(async move {
+        while let Ok(event) = hive_rx.recv().await {
+            let msg = match event {
+                HiveEvent::PeerDiscovered { peer_id, name } => {
+                    format!("📡 Peer: {} ({})", name, peer_id)
+                }
+                HiveEvent::PeerLost { peer_id } => {
+                    format!("📡 Peer Lost: {}", peer_id)
+                }
+                HiveEvent::BlockReceived { from, content } => {
+                    let text = String::from_utf8_lossy(&content);
+                    format!("💬 [{}]: {}", from, text)
+                }
+                HiveEvent::LiveSessionInvite { from, session_id } => {
+                    format!("📞 Invite from {}: {}", from, session_id)
+                }
+                HiveEvent::Error(e) => format!("⚠️ Hive: {}", e),
+            };
+            let _ = tx.send(shell_echo_cmd(&msg)).await;
+        }
+    });
+}
+
+/// Hardware I/O pump — echo device events into the PTY, except text from
+/// ports attached to a console. Identified devices are recorded in the
+/// vault and passed on with the user's name for them, and a detected baud
+/// rate becomes the device's saved one.
+fn spawn_io_pump(
+    pty: Arc<Mutex<PtyManager>>,
+    mut io_rx: mpsc::Receiver<HardwareEvent>,
+    queue: Arc<StdMutex<VecDeque<HardwareEvent>>>,
+    consoles: Arc<StdMutex<HashSet<String>>>,
+    notifier: mpsc::Sender<()>,
+    vault: Vault,
+) {
+    tokio::spawn(async move {
+        while let Some(mut event) = io_rx.recv().await {
+            if let HardwareEvent::Identified { port, device, product, name } = &mut event {
+                match vault.record_device(&device.to_string(), port, product.as_deref()) {
+                    Ok(saved) => *name = saved,
+                    Err(e) => eprintln!("[ENGINE] Recording device {} failed: {}", device, e),
+                }
+            }
+            if let HardwareEvent::BaudDetected { port, baud: Some(baud), .. } = &event {
+                if let Err(e) = serial::save_detected_baud(&vault, &positronic_io::device::system_ports(), port, *baud) {
+                    eprintln!("[ENGINE] Saving the baud rate of {} failed: {}", port, e);
+                }
+            }
+            {
+                let mut queue = queue.lock().unwrap_or_else(|p| p.into_inner());
+                if queue.len() >= HARDWARE_EVENT_CAP {
+                    queue.pop_front();
+                }
+                queue.push_back(event.clone());
+            }
+            let _ = notifier.try_send(());
+            let msg = match event {
+                HardwareEvent::DeviceConnected(n) => format!("🔌 Connected: {}", n),
+                HardwareEvent::DeviceDisconnected(n) => format!("🔌 Disconnected: {}", n),
+                HardwareEvent::DataBatch { .. } => continue,
+                HardwareEvent::SerialOutput { port, text } => {
+                    if consoles.lock().unwrap_or_else(|p| p.into_inner()).contains(&port) {
+                        continue;
+                    }
+                    text
+                }
+                HardwareEvent::Error(e) => format!("⚠️ IO: {}", e),
+                HardwareEvent::Overflow { port, dropped_bytes, dropped_samples, .. } => {
+                    format!(
+                        "⚠️ IO: {} fell behind — dropped {} bytes, {} samples",
+                        port, dropped_bytes, dropped_samples
+                    )
+                }
+                HardwareEvent::PortAccess { port, access } => {
+                    format!("🔌 {}: {}", port, access.message(Platform::current()))
+                }
+                HardwareEvent::Identified { .. } => continue,
+                HardwareEvent::BaudDetected { port, baud, guesses } => serial::baud_report(&port, baud, &guesses),
+            };
+            let mut p = pty.lock().await;
+            let _ = p.write_line(&shell_echo_cmd(&msg));
+        }
+    });
+}
+
+/// `cd` for the platform's shell, quoted.
+fn shell_cd_cmd(dir: &str) -> String {
+    if cfg!(windows) {
+        format!("Set-Location -LiteralPath '{}'", dir.replace('\'', "''"))
+    } else {
+        format!("cd -- '{}'", dir.replace('\'', r#"'"'"'"#))
+    }
+}
+
 fn shell_echo_cmd(text: &str) -> String {
     if cfg!(windows) {
         let escaped = text.replace('\'', "''");
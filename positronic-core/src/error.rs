@@ -0,0 +1,249 @@
+//! `PositronicError`: what the engine's public API fails with.
+//!
+//! Internals use `anyhow` and convert at the boundary (`PositronicEngine`,
+//! `Runner::execute`, `Vault::open`, the Runner's subsystem handles). The
+//! variants carry what a frontend needs to choose a reaction — offer a
+//! shell restart, ask for the vault passphrase, point at `!doctor` — so it
+//! matches on them instead of parsing messages. `action` and `hint` are
+//! the shared defaults.
+
+use crate::inspect::is_inspect_error;
+use crate::vault::crypto::{is_locked_error, Undecryptable, VaultCryptError};
+use positronic_io::IoError;
+
+/// Where the engine looks for Lemonade.
+pub const NEURAL_ENDPOINT: &str = "http://localhost:8000/api/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum PositronicError {
+    /// The shell's PTY could not be created, written or resized.
+    #[error("shell unavailable: {reason}")]
+    PtyFailure { reason: String },
+    #[error("vault {kind}: {reason}")]
+    VaultError { kind: VaultErrorKind, reason: String },
+    /// The model server did not answer, or the neural subsystem is down.
+    #[error("{reason} ({endpoint})")]
+    NeuralUnavailable { endpoint: String, reason: String },
+    /// Serial IO failed; `port` when the failure concerns one.
+    #[error("{}{reason}", port_prefix(.port))]
+    HardwareError { port: Option<String>, reason: String },
+    #[error("script error: {reason}")]
+    ScriptError { reason: String },
+    /// A setting could not be read or written.
+    #[error("{key}: {reason}")]
+    ConfigError { key: String, reason: String },
+    /// Anything else, with its context chain.
+    #[error("{0}")]
+    Other(String),
+}
+
+fn port_prefix(port: &Option<String>) -> String {
+    port.as_ref().map(|port| format!("{}: ", port)).unwrap_or_default()
+}
+
+/// What went wrong with the vault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VaultErrorKind {
+    /// The database file can't be opened or created.
+    Open,
+    /// Encrypted and the passphrase hasn't been given.
+    Locked,
+    /// Not a database, or rows that don't decrypt.
+    Corrupt,
+    /// Another writer holds the database.
+    Busy,
+    /// A passphrase was wrong, empty or mistyped.
+    Passphrase,
+    /// Already (or not) encrypted.
+    Encryption,
+    /// Opened with `--inspect`, which never writes (see `inspect`).
+    ReadOnly,
+    /// Any other failed statement.
+    Query,
+}
+
+impl std::fmt::Display for VaultErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VaultErrorKind::Open => "can't be opened",
+            VaultErrorKind::Locked => "locked",
+            VaultErrorKind::Corrupt => "corrupt",
+            VaultErrorKind::Busy => "busy",
+            VaultErrorKind::Passphrase => "passphrase rejected",
+            VaultErrorKind::Encryption => "not changed",
+            VaultErrorKind::ReadOnly => "read-only in inspect mode",
+            VaultErrorKind::Query => "query failed",
+        })
+    }
+}
+
+/// What a frontend can offer after an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorAction {
+    /// Start a new shell.
+    RestartShell,
+    /// Ask for the vault passphrase.
+    Unlock,
+    /// The same request may work in a moment.
+    Retry,
+    /// `!doctor` explains the subsystem's state.
+    Doctor,
+    /// The setting to look at.
+    Settings { key: String },
+    /// `!io scan` lists the ports.
+    ScanPorts,
+    None,
+}
+
+impl PositronicError {
+    pub fn pty(reason: impl std::fmt::Display) -> Self {
+        PositronicError::PtyFailure { reason: format!("{:#}", reason) }
+    }
+
+    pub fn vault(kind: VaultErrorKind, reason: impl std::fmt::Display) -> Self {
+        PositronicError::VaultError { kind, reason: reason.to_string() }
+    }
+
+    /// A failure while opening the vault: `Open` unless it says more.
+    pub fn vault_open(err: rusqlite::Error) -> Self {
+        match PositronicError::from(err) {
+            PositronicError::VaultError { kind: VaultErrorKind::Query, reason } => {
+                PositronicError::VaultError { kind: VaultErrorKind::Open, reason }
+            }
+            other => other,
+        }
+    }
+
+    pub fn neural(endpoint: &str, reason: impl std::fmt::Display) -> Self {
+        PositronicError::NeuralUnavailable { endpoint: endpoint.to_string(), reason: format!("{:#}", reason) }
+    }
+
+    pub fn script(reason: impl std::fmt::Display) -> Self {
+        PositronicError::ScriptError { reason: format!("{:#}", reason) }
+    }
+
+    pub fn config(key: &str, reason: impl std::fmt::Display) -> Self {
+        PositronicError::ConfigError { key: key.to_string(), reason: reason.to_string() }
+    }
+
+    /// The vault failure kind, if this is one.
+    pub fn vault_kind(&self) -> Option<VaultErrorKind> {
+        match self {
+            PositronicError::VaultError { kind, .. } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    pub fn action(&self) -> ErrorAction {
+        match self {
+            PositronicError::PtyFailure { .. } => ErrorAction::RestartShell,
+            PositronicError::VaultError { kind: VaultErrorKind::Locked | VaultErrorKind::Passphrase, .. } => {
+                ErrorAction::Unlock
+            }
+            PositronicError::VaultError { kind: VaultErrorKind::Busy, .. } => ErrorAction::Retry,
+            PositronicError::VaultError { kind: VaultErrorKind::ReadOnly, .. } => ErrorAction::None,
+            PositronicError::VaultError { .. } | PositronicError::NeuralUnavailable { .. } => ErrorAction::Doctor,
+            PositronicError::ScriptError { .. } => ErrorAction::Doctor,
+            PositronicError::HardwareError { .. } => ErrorAction::ScanPorts,
+            PositronicError::ConfigError { key, .. } => ErrorAction::Settings { key: key.clone() },
+            PositronicError::Other(_) => ErrorAction::None,
+        }
+    }
+
+    /// One line on what to do about it.
+    pub fn hint(&self) -> Option<String> {
+        Some(match self {
+            PositronicError::PtyFailure { .. } => "Press Enter to start a new shell".to_string(),
+            PositronicError::VaultError { kind: VaultErrorKind::Locked, .. } => {
+                "!vault unlock to enter the passphrase".to_string()
+            }
+            PositronicError::VaultError { kind: VaultErrorKind::Busy, .. } => {
+                "Another Positronic is writing; try again in a moment".to_string()
+            }
+            PositronicError::VaultError { kind: VaultErrorKind::ReadOnly, .. } => {
+                "Inspect mode only browses; start Positronic without --inspect to make changes".to_string()
+            }
+            PositronicError::VaultError { kind: VaultErrorKind::Open, .. } => {
+                "History is kept in memory until the vault file opens; !doctor shows its state".to_string()
+            }
+            PositronicError::VaultError { .. } => "!doctor shows the vault's state".to_string(),
+            PositronicError::NeuralUnavailable { endpoint, .. } => {
+                format!("Is Lemonade running at {}? !doctor checks it", endpoint)
+            }
+            PositronicError::HardwareError { .. } => "!io scan lists the serial ports".to_string(),
+            PositronicError::ScriptError { .. } => "!doctor shows whether the WASM host started".to_string(),
+            PositronicError::ConfigError { key, .. } => format!("!get {} shows the value; !set {} <value> fixes it", key, key),
+            PositronicError::Other(_) => return None,
+        })
+    }
+}
+
+impl From<rusqlite::Error> for PositronicError {
+    fn from(err: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        if is_locked_error(&err) {
+            return PositronicError::vault(VaultErrorKind::Locked, "no passphrase given yet");
+        }
+        if is_inspect_error(&err) {
+            return PositronicError::vault(VaultErrorKind::ReadOnly, err);
+        }
+        let kind = match &err {
+            rusqlite::Error::ToSqlConversionFailure(e) if e.is::<Undecryptable>() => VaultErrorKind::Corrupt,
+            rusqlite::Error::SqliteFailure(e, _) => match e.code {
+                ErrorCode::CannotOpen | ErrorCode::PermissionDenied | ErrorCode::ReadOnly => VaultErrorKind::Open,
+                ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt => VaultErrorKind::Corrupt,
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => VaultErrorKind::Busy,
+                _ => VaultErrorKind::Query,
+            },
+            _ => VaultErrorKind::Query,
+        };
+        PositronicError::vault(kind, err)
+    }
+}
+
+impl From<VaultCryptError> for PositronicError {
+    fn from(err: VaultCryptError) -> Self {
+        let kind = match err {
+            VaultCryptError::Sqlite(e) => return e.into(),
+            VaultCryptError::Mismatch | VaultCryptError::EmptyPassphrase | VaultCryptError::WrongPassphrase => {
+                VaultErrorKind::Passphrase
+            }
+            VaultCryptError::AlreadyEncrypted | VaultCryptError::NotEncrypted => VaultErrorKind::Encryption,
+            VaultCryptError::Busy => VaultErrorKind::Busy,
+        };
+        PositronicError::vault(kind, err)
+    }
+}
+
+impl From<IoError> for PositronicError {
+    fn from(err: IoError) -> Self {
+        PositronicError::HardwareError { port: err.port().map(str::to_string), reason: err.to_string() }
+    }
+}
+
+/// Internal failures keep their type when they have one: a
+/// `PositronicError` raised deep inside comes back out as itself.
+impl From<anyhow::Error> for PositronicError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<PositronicError>() {
+            Ok(e) => return e,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<rusqlite::Error>() {
+            Ok(e) => return e.into(),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<VaultCryptError>() {
+            Ok(e) => return e.into(),
+            Err(err) => err,
+        };
+        match err.downcast::<IoError>() {
+            Ok(e) => e.into(),
+            Err(err) => PositronicError::Other(format!("{:#}", err)),
+        }
+    }
+}
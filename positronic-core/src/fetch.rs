@@ -0,0 +1,436 @@
+//! `!fetch`: download a URL and hand it to the Holodeck.
+//!
+//! `!fetch <url> [--as json|csv|md|auto]` GETs the URL with reqwest,
+//! following up to `MAX_REDIRECTS` redirects, and answers with a `Fetched`:
+//! the decoded text (or the bytes, when they aren't text), its media type
+//! and where it came from. The frontend picks the rich form: `--as` if
+//! given, else the `Content-Type`, else what detection finds.
+//!
+//! - The body is capped at `fetch.max_size` (`DEFAULT_MAX_SIZE`), before
+//!   and after decompression; a download over it is aborted, not cut.
+//! - `gzip` and `deflate` are asked for and decoded here.
+//! - Text in another charset is converted to UTF-8, with a notice; so is
+//!   text that isn't valid UTF-8 and names no charset (read as
+//!   windows-1252, as browsers do).
+//! - Credentials in the URL are sent as Basic auth with a warning, and
+//!   left out of the URL recorded as the entry's source.
+//! - A status other than 2xx is an error showing the start of the body.
+//! - Cancelling (`FetchOptions::cancel`) drops the download where it is;
+//!   nothing of it was written anywhere.
+//! - Like every `!` line, a fetch stays out of the vault's history; its
+//!   data is logged with it only when `fetch.log` is on.
+
+use std::io::Read as _;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use reqwest::Url;
+
+use crate::alias;
+use crate::cancel::{or_cancelled, CancellationToken};
+use crate::http::{self, Redirect};
+use crate::term::binary;
+use crate::trash::format_bytes;
+
+pub const FETCH_USAGE: &str = "Usage: !fetch <url> [--as json|csv|md|auto]";
+
+/// Vault config key: the largest body fetched, in bytes (`10MB`, `512KB`).
+pub const MAX_SIZE_KEY: &str = "fetch.max_size";
+
+/// Vault config key: log what was fetched with the command (off by default).
+pub const LOG_KEY: &str = "fetch.log";
+
+pub const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+pub const MAX_REDIRECTS: usize = 5;
+
+/// Characters of the body shown under a failed status.
+const PREVIEW_CHARS: usize = 400;
+
+/// What `--as` asks the Holodeck to read the body as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FetchAs {
+    #[default]
+    Auto,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl FetchAs {
+    pub fn parse(name: &str) -> Option<FetchAs> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Some(FetchAs::Auto),
+            "json" => Some(FetchAs::Json),
+            "csv" => Some(FetchAs::Csv),
+            "md" | "markdown" => Some(FetchAs::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FetchAs::Auto => "auto",
+            FetchAs::Json => "json",
+            FetchAs::Csv => "csv",
+            FetchAs::Markdown => "md",
+        }
+    }
+
+    /// The form a media type names, if it names one.
+    pub fn from_content_type(content_type: &str) -> FetchAs {
+        match content_type {
+            ct if ct.ends_with("json") => FetchAs::Json,
+            "text/csv" | "application/csv" | "text/tab-separated-values" => FetchAs::Csv,
+            "text/markdown" | "text/x-markdown" => FetchAs::Markdown,
+            _ => FetchAs::Auto,
+        }
+    }
+}
+
+/// A parsed `!fetch` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchCommand {
+    pub url: String,
+    pub format: FetchAs,
+}
+
+impl FetchCommand {
+    /// Parse what follows `!fetch`; the error is the message to show. A
+    /// URL without a scheme gets `https://`.
+    pub fn parse(args: &str) -> Result<FetchCommand, String> {
+        let words: Vec<String> = alias::split_args(args).iter().map(|w| alias::unquote(w)).collect();
+        let mut url = None;
+        let mut format = FetchAs::Auto;
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            match word.as_str() {
+                "--as" => {
+                    let name = words.next().ok_or(FETCH_USAGE)?;
+                    format = FetchAs::parse(name)
+                        .ok_or_else(|| format!("❌ Unknown format '{}' (json, csv, md or auto)", name))?;
+                }
+                w if url.is_none() && !w.starts_with("--") => url = Some(w.to_string()),
+                _ => return Err(FETCH_USAGE.to_string()),
+            }
+        }
+        let url = url.ok_or(FETCH_USAGE)?;
+        let url = if url.contains("://") { url } else { format!("https://{}", url) };
+        let parsed = Url::parse(&url).map_err(|e| format!("❌ Bad URL '{}': {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("❌ Only http and https URLs, not {}", parsed.scheme()));
+        }
+        Ok(FetchCommand { url, format })
+    }
+}
+
+/// The largest body `MAX_SIZE_KEY` allows: bytes, or a number with
+/// `KB`, `MB` or `GB` (1024-based).
+pub fn max_size_from_config(value: Option<&str>) -> u64 {
+    let Some(value) = value.map(|v| v.trim().to_ascii_uppercase()) else {
+        return DEFAULT_MAX_SIZE;
+    };
+    let (number, unit) = match value.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(at) => value.split_at(at),
+        None => (value.as_str(), ""),
+    };
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => return DEFAULT_MAX_SIZE,
+    };
+    match number.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 1.0 => (n * multiplier as f64) as u64,
+        _ => DEFAULT_MAX_SIZE,
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Decoding
+// ════════════════════════════════════════════════════════════════════
+
+/// `body` undone from its `Content-Encoding`, stopping with an error
+/// once it passes `max_size`. HTTP's `deflate` is zlib-wrapped, but
+/// servers sending it raw are common enough to accept too.
+pub fn decompress(encoding: Option<&str>, body: &[u8], max_size: u64) -> Result<Vec<u8>, String> {
+    let encoding = encoding.map(|e| e.trim().to_ascii_lowercase()).unwrap_or_default();
+    let inflate = |reader: &mut dyn std::io::Read| -> std::io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        reader.take(max_size + 1).read_to_end(&mut out)?;
+        Ok(out)
+    };
+    let out = match encoding.as_str() {
+        "" | "identity" => return Ok(body.to_vec()),
+        "gzip" | "x-gzip" => inflate(&mut GzDecoder::new(body)),
+        "deflate" => inflate(&mut ZlibDecoder::new(body)).or_else(|_| inflate(&mut DeflateDecoder::new(body))),
+        other => return Err(format!("❌ Unsupported Content-Encoding '{}'", other)),
+    }
+    .map_err(|e| format!("❌ Could not decode the {} body: {}", encoding, e))?;
+    if out.len() as u64 > max_size {
+        return Err(too_large(max_size));
+    }
+    Ok(out)
+}
+
+/// The `charset` parameter of a `Content-Type` value.
+pub fn charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset").then(|| value.trim().trim_matches('"').to_ascii_lowercase())
+    })
+}
+
+/// `body` as UTF-8 text, and a notice when it had to be converted: from
+/// the declared `charset`, or from windows-1252 when none was declared
+/// and the bytes aren't UTF-8.
+pub fn decode_text(body: &[u8], charset: Option<&str>) -> (String, Option<String>) {
+    let declared = charset.and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()));
+    let encoding = match declared {
+        Some(encoding) => encoding,
+        None => match std::str::from_utf8(body) {
+            Ok(text) => return (text.to_string(), None),
+            Err(_) => encoding_rs::WINDOWS_1252,
+        },
+    };
+    if encoding == encoding_rs::UTF_8 {
+        let (text, _, malformed) = encoding.decode(body);
+        let notice = malformed.then(|| "⚠️ Invalid UTF-8 replaced with �".to_string());
+        return (text.into_owned(), notice);
+    }
+    let (text, _, _) = encoding.decode(body);
+    let notice = match declared {
+        Some(_) => format!("🔤 Converted from {} to UTF-8", encoding.name()),
+        None => format!("🔤 Not UTF-8 and no charset given; read as {}", encoding.name()),
+    };
+    (text.into_owned(), Some(notice))
+}
+
+/// The first `PREVIEW_CHARS` of a body, for an error.
+pub fn preview(body: &[u8]) -> Vec<String> {
+    if body.is_empty() || binary::looks_binary(body) {
+        return Vec::new();
+    }
+    let text = String::from_utf8_lossy(body);
+    let mut shown: String = text.chars().take(PREVIEW_CHARS).collect();
+    if shown.len() < text.len() {
+        shown.push('…');
+    }
+    shown.lines().map(|l| format!("  │ {}", l)).collect()
+}
+
+fn too_large(max_size: u64) -> String {
+    format!("❌ Over the {} limit; download aborted (!set {} <size>)", format_bytes(max_size), MAX_SIZE_KEY)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Result
+// ════════════════════════════════════════════════════════════════════
+
+/// A downloaded body, decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetched {
+    /// The URL asked for, without credentials: the entry's source.
+    pub url: String,
+    /// Where the body came from, after redirects.
+    pub final_url: String,
+    pub redirects: Vec<Redirect>,
+    pub status: u16,
+    /// The media type, without parameters: `application/json`.
+    pub content_type: Option<String>,
+    /// What `--as` asked for.
+    pub format: FetchAs,
+    /// The body, decompressed.
+    pub body: Vec<u8>,
+    /// The body as UTF-8; None when it isn't text.
+    pub text: Option<String>,
+    /// Warnings and conversions to show with it.
+    pub notices: Vec<String>,
+}
+
+impl Fetched {
+    /// The form to read the body as: `--as`, else the media type's.
+    pub fn hint(&self) -> FetchAs {
+        match self.format {
+            FetchAs::Auto => self.content_type.as_deref().map_or(FetchAs::Auto, FetchAs::from_content_type),
+            format => format,
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.content_type.as_deref().is_some_and(|ct| ct.starts_with("image/"))
+    }
+
+    /// `🌐 Fetched 1.2 KB application/json from https://…`
+    pub fn summary(&self) -> String {
+        let mut line = format!("🌐 Fetched {}", format_bytes(self.body.len() as u64));
+        if let Some(ct) = &self.content_type {
+            line.push_str(&format!(" {}", ct));
+        }
+        line.push_str(&format!(" from {}", self.final_url));
+        line
+    }
+
+    /// The summary, the redirects taken and the notices.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.summary()];
+        lines.extend(self.redirects.iter().map(|hop| format!("↪ {} → {}", hop.status, hop.to)));
+        lines.extend(self.notices.iter().cloned());
+        lines
+    }
+
+    /// `lines`, then the text, for a frontend without a Holodeck.
+    pub fn plain_lines(&self) -> Vec<String> {
+        let mut lines = self.lines();
+        match &self.text {
+            Some(text) => lines.extend(text.lines().map(str::to_string)),
+            None => lines.push(format!("📦 Binary content, {}", format_bytes(self.body.len() as u64))),
+        }
+        lines
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Fetching
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub timeout: Duration,
+    pub max_size: u64,
+    /// Fired to give up on the download (see `cancel`).
+    pub cancel: CancellationToken,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self { timeout: http::DEFAULT_TIMEOUT, max_size: DEFAULT_MAX_SIZE, cancel: CancellationToken::new() }
+    }
+}
+
+/// Download `command.url`. The error is the message to show: a failed
+/// status brings the start of its body along.
+pub async fn fetch(command: &FetchCommand, options: &FetchOptions) -> Result<Fetched, String> {
+    let mut url = Url::parse(&command.url).map_err(|e| format!("❌ Bad URL '{}': {}", command.url, e))?;
+    let host = url.host_str().unwrap_or_default().to_string();
+    let mut notices = Vec::new();
+    let credentials = (!url.username().is_empty() || url.password().is_some())
+        .then(|| (url.username().to_string(), url.password().map(str::to_string)));
+    if credentials.is_some() {
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        notices.push(format!(
+            "⚠️ The URL carries credentials: sent to {} as Basic auth, and left out of the entry's source",
+            host
+        ));
+    }
+    let timed_out = || {
+        format!(
+            "❌ Timed out after {} waiting for {} (!set {} <seconds>)",
+            http::format_duration(options.timeout),
+            host,
+            http::TIMEOUT_KEY
+        )
+    };
+
+    let hops = Arc::new(Mutex::new(Vec::new()));
+    let policy = {
+        let hops = hops.clone();
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+            }
+            let hop = Redirect { status: attempt.status().as_u16(), to: attempt.url().to_string() };
+            hops.lock().unwrap_or_else(|e| e.into_inner()).push(hop);
+            attempt.follow()
+        })
+    };
+    let client = reqwest::Client::builder()
+        .redirect(policy)
+        .timeout(options.timeout)
+        .build()
+        .map_err(|e| format!("❌ {}", e))?;
+    let mut call = client.get(url.clone()).header("Accept-Encoding", "gzip, deflate");
+    if let Some((user, password)) = &credentials {
+        call = call.basic_auth(user, password.as_deref());
+    }
+
+    let mut response = or_cancelled(&options.cancel, call.send()).await?.map_err(|e| {
+        if e.is_timeout() {
+            timed_out()
+        } else if e.is_redirect() {
+            format!("❌ Redirect failed: {}", http::error_chain(&e))
+        } else if e.is_connect() {
+            format!("❌ Could not connect to {}: {}", host, http::root_cause(&e))
+        } else {
+            format!("❌ Request failed: {}", http::error_chain(&e))
+        }
+    })?;
+    let status = response.status();
+    let header = |name: &str| {
+        response.headers().get(name).map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+    };
+    let content_type_header = header("content-type");
+    let encoding = header("content-encoding");
+    let final_url = response.url().to_string();
+    if response.content_length().is_some_and(|length| length > options.max_size) {
+        return Err(too_large(options.max_size));
+    }
+
+    let mut body = Vec::new();
+    loop {
+        let chunk = match or_cancelled(&options.cancel, response.chunk()).await? {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) if e.is_timeout() => return Err(timed_out()),
+            Err(e) => return Err(format!("❌ Reading the body failed: {}", http::error_chain(&e))),
+        };
+        if (body.len() + chunk.len()) as u64 > options.max_size {
+            return Err(too_large(options.max_size));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = decompress(encoding.as_deref(), &body, options.max_size)?;
+
+    if !status.is_success() {
+        let mut lines = vec![format!(
+            "❌ {} {} from {}",
+            status.as_u16(),
+            status.canonical_reason().unwrap_or(""),
+            final_url
+        )];
+        lines.extend(preview(&body));
+        return Err(lines.join("\n"));
+    }
+
+    let content_type = content_type_header
+        .as_deref()
+        .map(|value| value.split(';').next().unwrap_or(value).trim().to_ascii_lowercase())
+        .filter(|ct| !ct.is_empty());
+    let textual = content_type.as_deref().is_none_or(http::is_textual);
+    let text = if textual {
+        let (text, notice) = decode_text(&body, content_type_header.as_deref().and_then(charset).as_deref());
+        // Decoded, a real charset's text has no stray bytes left; binary does.
+        (!binary::looks_binary(text.as_bytes())).then(|| {
+            notices.extend(notice);
+            text
+        })
+    } else {
+        None
+    };
+
+    let redirects = std::mem::take(&mut *hops.lock().unwrap_or_else(|e| e.into_inner()));
+    Ok(Fetched {
+        url: url.to_string(),
+        final_url,
+        redirects,
+        status: status.as_u16(),
+        content_type,
+        format: command.format,
+        body,
+        text,
+        notices,
+    })
+}
@@ -0,0 +1,553 @@
+//! Project files for the Ctrl+P finder, indexed on the maintenance worker.
+//!
+//! The project root is the nearest directory holding `.git`, else the
+//! outermost holding `Cargo.toml`, else the working directory itself.
+//! Each root gets a `FileIndex`: a listing per directory, read breadth
+//! first a batch of directories per step (`FILES_BATCH`), so the top of a
+//! huge monorepo is searchable long before its deepest folders are read;
+//! `prioritize` moves a subtree the query names to the front of the
+//! queue. `.git` is never read, and `.gitignore` files (plus the root's
+//! `.git/info/exclude`) are honoured as a common subset: `*`, `?`,
+//! `[…]` and `**` through the `glob` crate, `!` to re-include, a trailing
+//! `/` for directories only, a leading or inner `/` to anchor the pattern
+//! to the `.gitignore`'s folder. The `ignore` crate would cover the rest
+//! of git's rules, but the subset keeps the dependency tree as it is.
+//!
+//! At most `finder.max_files` files are kept (`DEFAULT_MAX_FILES`); past
+//! that the walk stops and the index says it was truncated.
+//!
+//! Listings keep their directory's mtime. Opening the finder again marks
+//! the index due, and the next step only stats the directories, rereading
+//! the ones whose entries changed (a file created, removed or renamed),
+//! and dropping subtrees that are gone. Indexes for `MAX_ROOTS` roots are
+//! kept; the one wanted longest ago is dropped first.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use crate::maintenance::{Freshness, Step};
+
+/// Vault config key: the most files one root's index keeps.
+pub const MAX_FILES_KEY: &str = "finder.max_files";
+
+pub const DEFAULT_MAX_FILES: usize = 100_000;
+
+/// Directories read per maintenance step.
+pub const FILES_BATCH: usize = 64;
+
+/// Roots whose indexes are kept at once.
+pub const MAX_ROOTS: usize = 4;
+
+/// The project `cwd` is in: the nearest directory with `.git` (a folder,
+/// or a worktree's file), else the outermost with `Cargo.toml`, else
+/// `cwd`.
+pub fn project_root(cwd: &Path) -> PathBuf {
+    if let Some(dir) = cwd.ancestors().find(|dir| dir.join(".git").exists()) {
+        return dir.to_path_buf();
+    }
+    match cwd.ancestors().filter(|dir| dir.join("Cargo.toml").is_file()).last() {
+        Some(dir) => dir.to_path_buf(),
+        None => cwd.to_path_buf(),
+    }
+}
+
+/// `name` under the root-relative directory `dir` ("" for the root).
+fn join(dir: &str, name: &str) -> String {
+    match dir {
+        "" => name.to_string(),
+        _ => format!("{}/{}", dir, name),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Ignore rules
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    /// The folder of the file the rule came from, root-relative.
+    base: String,
+    pattern: glob::Pattern,
+    negate: bool,
+    dir_only: bool,
+    /// Matched against the path under `base`, not only the name.
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(base: &str, line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+        let pattern = glob::Pattern::new(line).ok()?;
+        Some(Rule { base: base.to_string(), pattern, negate, dir_only, anchored })
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let under = match self.base.as_str() {
+            "" => path,
+            base => match path.strip_prefix(base).and_then(|rest| rest.strip_prefix('/')) {
+                Some(rest) => rest,
+                None => return false,
+            },
+        };
+        let options = glob::MatchOptions { require_literal_separator: true, ..glob::MatchOptions::new() };
+        match self.anchored {
+            true => self.pattern.matches_with(under, options),
+            false => self.pattern.matches_with(under.rsplit('/').next().unwrap_or(under), options),
+        }
+    }
+}
+
+/// The `.gitignore` rules in force in one directory: its own and its
+/// parents', nearest last.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// The rules of a `.gitignore` in the root-relative folder `base`.
+    pub fn parse(base: &str, text: &str) -> Self {
+        Self { rules: text.lines().filter_map(|line| Rule::parse(base, line)).collect() }
+    }
+
+    /// These rules with a nested folder's after them, so its win.
+    pub fn with(&self, nested: IgnoreRules) -> Self {
+        let mut rules = self.rules.clone();
+        rules.extend(nested.rules);
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether the root-relative `path` is ignored: the last rule that
+    /// matches it decides.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.rules.iter().rev().find(|rule| rule.matches(path, is_dir)).is_some_and(|rule| !rule.negate)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// One root
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+struct DirListing {
+    mtime: Option<SystemTime>,
+    /// The parents' rules, as the directory was queued with them.
+    inherited: Arc<IgnoreRules>,
+    /// `inherited` plus the directory's own `.gitignore`.
+    rules: Arc<IgnoreRules>,
+    /// Names, sorted.
+    files: Vec<String>,
+    subdirs: Vec<String>,
+}
+
+/// The files under one project root.
+#[derive(Debug)]
+pub struct FileIndex {
+    root: PathBuf,
+    max_files: usize,
+    /// Root-relative directory ("" for the root) to its listing.
+    dirs: BTreeMap<String, DirListing>,
+    /// Directories still to read, with the rules they inherit.
+    pending: VecDeque<(String, Arc<IgnoreRules>)>,
+    queued: HashSet<String>,
+    files: usize,
+    truncated: bool,
+    /// Directories are statted before the next step.
+    refresh_due: bool,
+    /// Bumped whenever the file list changes.
+    generation: u64,
+    paths: Option<Arc<Vec<String>>>,
+}
+
+impl FileIndex {
+    /// An unread index of `root`, keeping at most `max_files` files.
+    pub fn new(root: &Path, max_files: usize) -> Self {
+        let mut top = IgnoreRules::default();
+        if let Ok(text) = std::fs::read_to_string(root.join(".git").join("info").join("exclude")) {
+            top = IgnoreRules::parse("", &text);
+        }
+        let mut index = Self {
+            root: root.to_path_buf(),
+            max_files: max_files.max(1),
+            dirs: BTreeMap::new(),
+            pending: VecDeque::new(),
+            queued: HashSet::new(),
+            files: 0,
+            truncated: false,
+            refresh_due: false,
+            generation: 0,
+            paths: None,
+        };
+        index.queue(String::new(), Arc::new(top), false);
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn max_files(&self) -> usize {
+        self.max_files
+    }
+
+    /// Files indexed so far.
+    pub fn len(&self) -> usize {
+        self.files
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files == 0
+    }
+
+    /// Every directory found has been read.
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// The walk stopped at `max_files`.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Changes whenever a step or refresh changed the file list.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Something for the worker to do: directories to read or stat.
+    pub fn is_due(&self) -> bool {
+        !self.pending.is_empty() || self.refresh_due
+    }
+
+    /// Stat the directories on the next step.
+    pub fn mark_due(&mut self) {
+        self.refresh_due = true;
+    }
+
+    /// Every file, root-relative with `/` separators, directory by
+    /// directory. Shared until the list next changes.
+    pub fn paths(&mut self) -> Arc<Vec<String>> {
+        if let Some(paths) = &self.paths {
+            return paths.clone();
+        }
+        let paths: Vec<String> = self
+            .dirs
+            .iter()
+            .flat_map(|(dir, listing)| listing.files.iter().map(move |name| join(dir, name)))
+            .collect();
+        let paths = Arc::new(paths);
+        self.paths = Some(paths.clone());
+        paths
+    }
+
+    /// Read up to `budget` directories, statting all of them first if a
+    /// refresh is due. Returns how many were read.
+    pub fn step(&mut self, budget: usize) -> usize {
+        if std::mem::take(&mut self.refresh_due) {
+            self.refresh();
+        }
+        let mut read = 0;
+        while read < budget {
+            let Some((dir, inherited)) = self.pending.pop_front() else {
+                break;
+            };
+            self.queued.remove(&dir);
+            self.read(&dir, inherited);
+            read += 1;
+        }
+        read
+    }
+
+    /// Step until every directory is read.
+    pub fn build(&mut self) {
+        while self.is_due() {
+            self.step(FILES_BATCH);
+        }
+    }
+
+    /// Stat every listed directory and queue the ones whose entries
+    /// changed for a reread, ahead of the rest. Returns how many.
+    pub fn refresh(&mut self) -> usize {
+        let changed: Vec<(String, Arc<IgnoreRules>)> = self
+            .dirs
+            .iter()
+            .filter(|(dir, listing)| mtime(&self.root.join(dir)) != listing.mtime)
+            .map(|(dir, listing)| (dir.clone(), listing.inherited.clone()))
+            .collect();
+        for (dir, inherited) in changed.iter().rev() {
+            self.queue(dir.clone(), inherited.clone(), true);
+        }
+        changed.len()
+    }
+
+    /// Read the directories under `prefix` (root-relative) before the
+    /// rest, for a query that names where it is looking. Returns how many
+    /// were waiting.
+    pub fn prioritize(&mut self, prefix: &str) -> usize {
+        let prefix = prefix.trim_matches('/');
+        if prefix.is_empty() {
+            return 0;
+        }
+        let under = |dir: &str| dir == prefix || dir.starts_with(&format!("{}/", prefix));
+        let (mut first, rest): (VecDeque<_>, VecDeque<_>) = self.pending.drain(..).partition(|(dir, _)| under(dir));
+        let moved = first.len();
+        first.extend(rest);
+        self.pending = first;
+        moved
+    }
+
+    fn queue(&mut self, dir: String, inherited: Arc<IgnoreRules>, front: bool) {
+        if !self.queued.insert(dir.clone()) {
+            return;
+        }
+        match front {
+            true => self.pending.push_front((dir, inherited)),
+            false => self.pending.push_back((dir, inherited)),
+        }
+    }
+
+    /// (Re)read `dir`: its files, and its subdirectories queued unless
+    /// already listed under the same rules.
+    fn read(&mut self, dir: &str, inherited: Arc<IgnoreRules>) {
+        let path = self.root.join(dir);
+        let mtime = mtime(&path);
+        let mut rules = inherited.clone();
+        if let Ok(text) = std::fs::read_to_string(path.join(".gitignore")) {
+            rules = Arc::new(inherited.with(IgnoreRules::parse(dir, &text)));
+        }
+
+        let (mut files, mut subdirs) = (Vec::new(), Vec::new());
+        if let Ok(entries) = std::fs::read_dir(&path) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name == ".git" {
+                    continue;
+                }
+                // Symlinks count as files, so a link loop is never walked.
+                let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                if rules.is_ignored(&join(dir, &name), is_dir) {
+                    continue;
+                }
+                match is_dir {
+                    true => subdirs.push(name),
+                    false => files.push(name),
+                }
+            }
+        }
+        files.sort();
+        subdirs.sort();
+
+        let old = self.dirs.remove(dir);
+        let rules_changed = old.as_ref().is_some_and(|old| old.rules != rules);
+        if let Some(old) = &old {
+            self.files -= old.files.len();
+            for gone in old.subdirs.iter().filter(|s| rules_changed || !subdirs.contains(s)) {
+                self.forget(&join(dir, gone));
+            }
+        }
+        let room = self.max_files.saturating_sub(self.files);
+        if files.len() > room {
+            files.truncate(room);
+            self.truncated = true;
+        }
+        self.files += files.len();
+        for sub in &subdirs {
+            let sub = join(dir, sub);
+            if !self.dirs.contains_key(&sub) {
+                self.queue(sub, rules.clone(), false);
+            }
+        }
+        if self.truncated {
+            // Full: nothing further would be kept.
+            self.pending.clear();
+            self.queued.clear();
+        }
+        self.dirs.insert(dir.to_string(), DirListing { mtime, inherited, rules, files, subdirs });
+        self.generation += 1;
+        self.paths = None;
+    }
+
+    /// Drop `dir` and everything listed or queued under it.
+    fn forget(&mut self, dir: &str) {
+        let below = format!("{}/", dir);
+        let gone: Vec<String> =
+            self.dirs.keys().filter(|d| *d == dir || d.starts_with(&below)).cloned().collect();
+        for d in gone {
+            if let Some(listing) = self.dirs.remove(&d) {
+                self.files -= listing.files.len();
+            }
+        }
+        self.pending.retain(|(d, _)| d != dir && !d.starts_with(&below));
+        self.queued.retain(|d| d != dir && !d.starts_with(&below));
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Every root
+// ════════════════════════════════════════════════════════════════════
+
+/// What the finder sees of one root's index.
+#[derive(Debug, Clone)]
+pub struct FileSnapshot {
+    pub root: PathBuf,
+    pub paths: Arc<Vec<String>>,
+    pub generation: u64,
+    pub complete: bool,
+    pub truncated: bool,
+    pub max_files: usize,
+}
+
+impl FileSnapshot {
+    /// Status words for the finder: "1,204 files", "indexing… 312 files",
+    /// "first 100,000 files only".
+    pub fn status(&self) -> String {
+        let count = format!("{} files", group(self.paths.len()));
+        if self.truncated {
+            format!("first {} files only (finder.max_files)", group(self.max_files))
+        } else if self.complete {
+            count
+        } else {
+            format!("indexing… {}", count)
+        }
+    }
+}
+
+fn group(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+struct Roots {
+    /// Least recently wanted first.
+    indexes: Vec<FileIndex>,
+    /// The root the finder last asked for; the worker builds it.
+    wanted: Option<PathBuf>,
+}
+
+/// The cached indexes, one per root, shared by the finder and the
+/// maintenance worker.
+#[derive(Debug, Default)]
+pub struct FileIndexes {
+    roots: Mutex<Roots>,
+}
+
+impl FileIndexes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn roots(&self) -> MutexGuard<'_, Roots> {
+        self.roots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The finder opened on `root`: build its index next, or refresh the
+    /// cached one. A different `max_files` starts the index over.
+    pub fn want(&self, root: &Path, max_files: usize) {
+        let mut roots = self.roots();
+        let cached = roots.indexes.iter().position(|i| i.root == root);
+        let mut index = match cached.map(|at| roots.indexes.remove(at)) {
+            Some(index) if index.max_files == max_files.max(1) => index,
+            _ => FileIndex::new(root, max_files),
+        };
+        index.mark_due();
+        roots.indexes.push(index);
+        if roots.indexes.len() > MAX_ROOTS {
+            roots.indexes.remove(0);
+        }
+        roots.wanted = Some(root.to_path_buf());
+    }
+
+    /// The roots indexed, least recently wanted first.
+    pub fn roots_cached(&self) -> Vec<PathBuf> {
+        self.roots().indexes.iter().map(|i| i.root.clone()).collect()
+    }
+
+    /// `root`'s files as indexed so far.
+    pub fn snapshot(&self, root: &Path) -> Option<FileSnapshot> {
+        let mut roots = self.roots();
+        let index = roots.indexes.iter_mut().find(|i| i.root == root)?;
+        Some(FileSnapshot {
+            root: index.root.clone(),
+            paths: index.paths(),
+            generation: index.generation,
+            complete: index.is_complete(),
+            truncated: index.truncated,
+            max_files: index.max_files,
+        })
+    }
+
+    /// See `FileIndex::prioritize`.
+    pub fn prioritize(&self, root: &Path, prefix: &str) -> usize {
+        let mut roots = self.roots();
+        roots.indexes.iter_mut().find(|i| i.root == root).map_or(0, |i| i.prioritize(prefix))
+    }
+
+    /// The maintenance check: stale while the wanted root has directories
+    /// to read or stat.
+    pub fn freshness(&self) -> Freshness {
+        let roots = self.roots();
+        let due = roots
+            .wanted
+            .as_ref()
+            .and_then(|root| roots.indexes.iter().find(|i| &i.root == root))
+            .is_some_and(FileIndex::is_due);
+        match due {
+            true => Freshness::Stale,
+            false => Freshness::Fresh,
+        }
+    }
+
+    /// One maintenance step on the wanted root.
+    pub fn step(&self, budget: usize) -> Step {
+        let mut roots = self.roots();
+        let Some(root) = roots.wanted.clone() else {
+            return Step::Done("nothing wanted".to_string());
+        };
+        let Some(index) = roots.indexes.iter_mut().find(|i| i.root == root) else {
+            return Step::Done("nothing wanted".to_string());
+        };
+        index.step(budget);
+        if index.is_due() {
+            return Step::More { done: index.len() as u64, total: 0 };
+        }
+        let capped = if index.truncated { " (capped)" } else { "" };
+        Step::Done(format!("{} files in {}{}", group(index.len()), root.display(), capped))
+    }
+}
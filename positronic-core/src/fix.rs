@@ -0,0 +1,129 @@
+//! Command corrections, and how they are previewed.
+//!
+//! Reflex (`positronic_neural::reflex`) fixes a mistyped command name from
+//! its typo table and edit distance; `!fix` asks the model when Reflex has
+//! nothing. Either way the suggestion is shown as a word diff against what
+//! was typed (`diff::diff_command`), so the one changed word in a long
+//! line stands out, and a correction that rewrites the line is shown as
+//! before and after. Nothing runs: the window puts an accepted correction
+//! in the input line.
+
+use positronic_neural::injection;
+use positronic_neural::privacy::PrivacyGuard;
+use positronic_neural::reflex::{Suggestion, SuggestionSource};
+
+use crate::danger::DangerAnalyzer;
+use crate::diff::{self, CommandDiff};
+
+pub const FIX_USAGE: &str = "Usage: !fix [command]   (without one: the last command that failed)";
+
+/// Starts the preview line; the frontend colors the diff markers on it.
+pub const PREVIEW_PREFIX: &str = "🩹 ";
+
+/// Lines of the failed command's output sent with `!fix`.
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// Where a correction came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixSource {
+    Reflex(SuggestionSource),
+    Ai,
+}
+
+/// A corrected command offered for the input line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub original: String,
+    pub corrected: String,
+    pub source: FixSource,
+    /// Reflex's confidence, 0 to 1; the model gives none.
+    pub confidence: Option<f64>,
+    /// Why it is offered, shown above the preview.
+    pub reason: Option<String>,
+}
+
+impl Correction {
+    pub fn from_reflex(original: &str, suggestion: Suggestion) -> Self {
+        Self {
+            original: original.to_string(),
+            corrected: suggestion.corrected,
+            source: FixSource::Reflex(suggestion.source),
+            confidence: Some(suggestion.confidence),
+            reason: None,
+        }
+    }
+
+    pub fn from_ai(original: &str, corrected: &str) -> Self {
+        Self {
+            original: original.to_string(),
+            corrected: corrected.to_string(),
+            source: FixSource::Ai,
+            confidence: None,
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(self, reason: impl Into<String>) -> Self {
+        Self { reason: Some(reason.into()), ..self }
+    }
+
+    pub fn diff(&self) -> CommandDiff {
+        diff::diff_command(&self.original, &self.corrected)
+    }
+
+    /// `Reflex · known typo · 95%`, or `AI`.
+    pub fn provenance(&self) -> String {
+        let mut parts = vec![match &self.source {
+            FixSource::Reflex(_) => "Reflex",
+            FixSource::Ai => "AI",
+        }
+        .to_string()];
+        if let FixSource::Reflex(how) = &self.source {
+            parts.push(
+                match how {
+                    SuggestionSource::KnownTypo => "known typo",
+                    SuggestionSource::Levenshtein => "closest command",
+                    SuggestionSource::Transposition => "swapped letters",
+                }
+                .to_string(),
+            );
+        }
+        if let Some(confidence) = self.confidence {
+            parts.push(format!("{:.0}%", confidence * 100.0));
+        }
+        parts.join(" · ")
+    }
+
+    /// The reason, the diff with its changes marked, where it came from,
+    /// and a warning if the corrected command is destructive.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.reason.iter().cloned().collect();
+        lines.push(format!("{}{}", PREVIEW_PREFIX, self.diff().marked()));
+        lines.push(format!("   {}", self.provenance()));
+        let danger = DangerAnalyzer::screen_generated(&self.corrected);
+        if danger.is_destructive() {
+            lines.push(format!(
+                "   ⚠️ destructive: {}",
+                danger.reason.unwrap_or("matches a destructive pattern")
+            ));
+        }
+        lines
+    }
+}
+
+/// What `!fix` asks the model. The command and its output are scrubbed
+/// and fenced as data.
+pub fn fix_prompt(command: &str, output: Option<&str>) -> String {
+    let mut prompt = format!(
+        "This shell command failed or is mistyped. Reply with ONLY the corrected command on one line, \
+         changing as little as possible.\n\n{}",
+        injection::fence("command", &PrivacyGuard::scrub(command))
+    );
+    if let Some(output) = output.filter(|o| !o.trim().is_empty()) {
+        let lines: Vec<&str> = output.lines().collect();
+        let tail = lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n");
+        prompt.push_str("\n\n");
+        prompt.push_str(&injection::fence("its output", &PrivacyGuard::scrub(&tail)));
+    }
+    prompt
+}
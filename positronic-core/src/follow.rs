@@ -0,0 +1,168 @@
+//! `!follow`: a command run in the background with its output captured.
+//!
+//! `tail -f` and `kubectl logs -f` never finish, so instead of the PTY
+//! they run here, through the system shell, with stdout and stderr read
+//! line by line into a channel the UI's follow pane drains. Each event
+//! also calls a wake callback so the window redraws without polling.
+//! The command is reaped through `usage::collector`, so its CPU time and
+//! peak memory arrive just before it is reported as exited.
+//!
+//! Stopping has to reach a whole pipeline, not just the shell in front of
+//! it: on Unix the command leads its own process group, which gets SIGTERM
+//! and, if anything is left after `STOP_GRACE`, SIGKILL. On Windows the
+//! process tree is killed with `taskkill /T`. Dropping a `FollowProcess`
+//! stops it too.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+
+use crate::usage::{self, ResourceUsage};
+
+pub const FOLLOW_USAGE: &str = "Usage: !follow <command>";
+
+/// How long a stopped command has to exit before it is killed.
+pub const STOP_GRACE: Duration = Duration::from_secs(2);
+
+/// Output still in the pipes after the command exits is read for at most
+/// this long; a background grandchild may keep them open forever.
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FollowEvent {
+    /// A line of stdout or stderr, without its line ending.
+    Line(String),
+    /// What the command used, sent just before `Exited` where the
+    /// platform reports it.
+    Usage(ResourceUsage),
+    /// The command ended: its exit code, `None` if a signal killed it.
+    Exited(Option<i32>),
+}
+
+#[derive(Debug)]
+pub struct FollowProcess {
+    pub command: String,
+    pid: u32,
+    exited: Arc<AtomicBool>,
+}
+
+impl FollowProcess {
+    /// Start `command` in `cwd` with `env` set. Must be called inside a
+    /// Tokio runtime.
+    /// `wake` runs after every event sent on the receiver.
+    pub fn spawn(
+        command: &str,
+        cwd: &str,
+        env: &[(String, String)],
+        wake: impl Fn() + Send + Sync + 'static,
+    ) -> Result<(FollowProcess, mpsc::UnboundedReceiver<FollowEvent>)> {
+        let mut shell = usage::shell(command, env);
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+        shell
+            .current_dir(cwd)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let mut child = shell.spawn().with_context(|| format!("could not start '{}'", command))?;
+        let pid = child.id();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let wake: Arc<dyn Fn() + Send + Sync> = Arc::new(wake);
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            let stdout = tokio::process::ChildStdout::from_std(stdout)?;
+            readers.push(tokio::spawn(forward(stdout, tx.clone(), wake.clone())));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            let stderr = tokio::process::ChildStderr::from_std(stderr)?;
+            readers.push(tokio::spawn(forward(stderr, tx.clone(), wake.clone())));
+        }
+
+        let exited = Arc::new(AtomicBool::new(false));
+        let done = exited.clone();
+        tokio::spawn(async move {
+            let exit = tokio::task::spawn_blocking(move || usage::collector().wait(child)).await;
+            let exit = exit.ok().and_then(|e| e.ok());
+            done.store(true, Ordering::SeqCst);
+            for reader in readers {
+                let _ = tokio::time::timeout(DRAIN_TIMEOUT, reader).await;
+            }
+            if let Some(usage) = exit.and_then(|e| e.usage) {
+                let _ = tx.send(FollowEvent::Usage(usage));
+            }
+            let _ = tx.send(FollowEvent::Exited(exit.and_then(|e| e.code)));
+            wake();
+        });
+
+        Ok((FollowProcess { command: command.to_string(), pid, exited }, rx))
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.exited.load(Ordering::SeqCst)
+    }
+
+    /// Ask the command to stop; `FollowEvent::Exited` follows once it has.
+    pub fn stop(&self) {
+        if !self.is_running() {
+            return;
+        }
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::{killpg, Signal};
+            use nix::unistd::Pid;
+
+            let group = Pid::from_raw(self.pid as i32);
+            let _ = killpg(group, Signal::SIGTERM);
+            let exited = self.exited.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(STOP_GRACE);
+                if !exited.load(Ordering::SeqCst) {
+                    let _ = killpg(group, Signal::SIGKILL);
+                }
+            });
+        }
+        #[cfg(windows)]
+        {
+            let _ = std::process::Command::new("taskkill")
+                .args(["/T", "/F", "/PID", &self.pid.to_string()])
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null())
+                .spawn();
+        }
+    }
+}
+
+impl Drop for FollowProcess {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Send each line `reader` yields until it closes. Invalid UTF-8 is
+/// replaced rather than ending the stream.
+async fn forward(
+    reader: impl AsyncRead + Unpin,
+    tx: mpsc::UnboundedSender<FollowEvent>,
+    wake: Arc<dyn Fn() + Send + Sync>,
+) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\n', '\r']).to_string();
+                if tx.send(FollowEvent::Line(line)).is_err() {
+                    break;
+                }
+                wake();
+            }
+        }
+    }
+}
@@ -0,0 +1,411 @@
+//! Headless mode: the engine without a window, for scripts and CI.
+//!
+//! `positronic --exec <line>` sends one line through the Runner (aliases,
+//! builtins, vault logging) and exits with the command's exit code;
+//! `--history-search`, `--export-history` and `--doctor` run `!search`,
+//! `!export` and `!doctor` and print their lines. Hive and IO stay off:
+//! both echo their events into the shell.
+//!
+//! `CommandCapture` is the output consumer. It splits the PTY stream at the
+//! OSC 133 markers: bytes between command start (`B`/`C`) and finish (`D`)
+//! are the command's output, and the finish marker carries the exit code.
+//! Without shell integration there is no way to tell when a command ends,
+//! so `--exec` gives up if no prompt marker arrives within `READY_TIMEOUT`.
+//!
+//! Danger analysis has no one to ask, so destructive lines are refused
+//! unless the caller allows them; cautionary ones are run with a warning.
+//! Ctrl+C is forwarded to the running command; a second one abandons it.
+//! A run that fails outright reports the `PositronicError` with
+//! `error_lines`, which swaps the window's affordances for what can be
+//! done from a script.
+
+use crate::danger::{suggest_rm_enabled, DangerAnalyzer, SUGGEST_RM_KEY};
+use crate::engine::{EngineOptions, PositronicEngine};
+use crate::error::{ErrorAction, PositronicError};
+use crate::runner::ExecuteResult;
+use crate::sync;
+use crate::term::osc::{OscEvent, OscParser};
+use crate::term::progress;
+use crate::timeline;
+use crate::vault::crypto;
+
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How long `--exec` waits for the shell's first prompt marker.
+pub const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A destructive line was refused.
+pub const EXIT_REFUSED: i32 = 126;
+/// Abandoned after a second Ctrl+C.
+pub const EXIT_INTERRUPTED: i32 = 130;
+/// The finish marker carried no exit code, or a native command failed.
+pub const EXIT_FAILED: i32 = 1;
+
+/// One non-interactive run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeadlessTask {
+    Exec {
+        command: String,
+        /// Print the output once the command finishes, folded and without
+        /// escape sequences, instead of streaming it as it arrives.
+        capture: bool,
+        /// Run lines danger analysis calls destructive.
+        allow_destructive: bool,
+    },
+    HistorySearch(String),
+    ExportHistory(String),
+    Doctor,
+}
+
+impl HeadlessTask {
+    /// The native command a non-`Exec` task runs.
+    pub fn native_command(&self) -> Option<String> {
+        match self {
+            HeadlessTask::Exec { .. } => None,
+            HeadlessTask::HistorySearch(query) => Some(format!("!search {}", query)),
+            HeadlessTask::ExportHistory(path) => Some(format!("!export {}", path)),
+            HeadlessTask::Doctor => Some("!doctor".to_string()),
+        }
+    }
+}
+
+/// Start an engine without Hive and IO, run `task`, auto-export (see
+/// `sync`), close the vault session and return the process exit code. Output goes to stdout,
+/// diagnostics to stderr.
+pub async fn run(task: HeadlessTask, mut options: EngineOptions) -> Result<i32, PositronicError> {
+    options.peripherals = false;
+    // `exec` logs the line as typed, with its own timing.
+    options.log_commands = false;
+    let (redraw_tx, redraw_rx) = mpsc::channel(1);
+    let engine = PositronicEngine::start_with(options, redraw_tx).await?;
+    let code = run_task(&engine, redraw_rx, &task).await;
+    if let Err(e) = sync::auto_export(engine.runner.vault()) {
+        eprintln!("[HEADLESS] Sync auto-export failed: {:#}", e);
+    }
+    if let Err(e) = engine.runner.vault().close_session() {
+        eprintln!("[HEADLESS] Closing the vault session failed: {}", e);
+    }
+    Ok(code?)
+}
+
+async fn run_task(engine: &PositronicEngine, redraw_rx: mpsc::Receiver<()>, task: &HeadlessTask) -> Result<i32> {
+    let mut out = std::io::stdout();
+    let code = match task {
+        HeadlessTask::Exec { command, capture, allow_destructive } => {
+            exec(engine, redraw_rx, command, *capture, *allow_destructive, &mut out).await
+        }
+        _ => {
+            let command = task.native_command().unwrap_or_default();
+            let code = match engine.send_input(&command).await {
+                Ok(result) => print_result(result, &mut out),
+                Err(e) => Err(e.into()),
+            };
+            // A degraded engine is a failed check.
+            if *task == HeadlessTask::Doctor && engine.subsystems().is_degraded() {
+                code.map(|_| EXIT_FAILED)
+            } else {
+                code
+            }
+        }
+    };
+    out.flush()?;
+    code
+}
+
+/// What `positronic` prints on stderr when a headless run fails: the
+/// error, and what to do about it without a window to offer it in.
+pub fn error_lines(err: &PositronicError) -> Vec<String> {
+    let hint = match err.action() {
+        ErrorAction::Unlock => Some(format!("Set {} to unlock the vault", crypto::PASSPHRASE_ENV)),
+        ErrorAction::RestartShell => Some("Check that the shell starts on its own ($SHELL, or COMSPEC on Windows)".to_string()),
+        _ => err.hint(),
+    };
+    let mut lines = vec![format!("positronic: {}", err)];
+    lines.extend(hint.map(|hint| format!("  💡 {}", hint)));
+    lines
+}
+
+/// Run one line and wait for it to finish.
+pub async fn exec(
+    engine: &PositronicEngine,
+    mut redraw_rx: mpsc::Receiver<()>,
+    command: &str,
+    capture: bool,
+    allow_destructive: bool,
+    out: &mut dyn Write,
+) -> Result<i32> {
+    let danger = DangerAnalyzer::analyze(command);
+    if danger.is_destructive() && !allow_destructive {
+        eprintln!(
+            "⛔ Refusing a destructive command ({}); pass --yes to run it",
+            danger.reason.unwrap_or("matches a destructive pattern")
+        );
+        let suggest = engine.runner.vault().get_config(SUGGEST_RM_KEY).ok().flatten();
+        if let Some(hint) = DangerAnalyzer::rm_hint(command).filter(|_| suggest_rm_enabled(suggest.as_deref())) {
+            eprintln!("{}", hint);
+        }
+        return Ok(EXIT_REFUSED);
+    }
+    if let Some(reason) = danger.reason {
+        eprintln!("⚠️ {}: {}", danger.level, reason);
+    }
+
+    let mut capture_state = CommandCapture::new();
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while !capture_state.is_ready() {
+        for chunk in engine.drain_pty_output() {
+            capture_state.feed(&chunk);
+        }
+        if capture_state.is_ready() {
+            break;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            bail!(
+                "no prompt marker from the shell within {}s; headless mode needs shell integration (bash or PowerShell)",
+                READY_TIMEOUT.as_secs()
+            );
+        }
+        let _ = tokio::time::timeout(left, redraw_rx.recv()).await;
+    }
+
+    match engine.send_input(command).await? {
+        ExecuteResult::SentToPty => {}
+        ExecuteResult::SentToPtyWith(lines) => {
+            for line in lines {
+                eprintln!("{}", line);
+            }
+        }
+        other => return print_result(other, out),
+    }
+    capture_state.command_sent();
+
+    let started = Instant::now();
+    let mut interrupted = false;
+    loop {
+        for chunk in engine.drain_pty_output() {
+            let live = capture_state.feed(&chunk);
+            if !capture && !live.is_empty() {
+                out.write_all(&live)?;
+                out.flush()?;
+            }
+        }
+        if capture_state.finished().is_some() {
+            break;
+        }
+        tokio::select! {
+            _ = redraw_rx.recv() => {}
+            _ = tokio::signal::ctrl_c() => {
+                if interrupted {
+                    eprintln!("\n⛔ Abandoned after a second Ctrl+C");
+                    return Ok(EXIT_INTERRUPTED);
+                }
+                interrupted = true;
+                engine.send_interrupt().await?;
+            }
+        }
+    }
+
+    let exit_code = capture_state.finished().flatten();
+    let output = String::from_utf8_lossy(capture_state.output()).into_owned();
+    if exit_code != Some(0) {
+        if let Some(hint) = engine.runner.not_found_hint(&plain_text(&output), Some(command)).await {
+            for line in result_lines(hint) {
+                eprintln!("{}", line);
+            }
+        }
+    }
+    if capture {
+        out.write_all(plain_text(&progress::fold_progress(&output)).as_bytes())?;
+    }
+    // A test run's summary and native after-hooks print here; a shell
+    // after-hook is sent, not waited for.
+    for result in engine.runner.commands_finished(&plain_text(&output)).await {
+        let mut hook_out = Vec::new();
+        print_result(result, &mut hook_out)?;
+        eprint!("{}", String::from_utf8_lossy(&hook_out));
+    }
+    let fold = engine.runner.vault().get_config(progress::FOLD_PROGRESS_KEY).ok().flatten();
+    let stored = if progress::fold_enabled(fold.as_deref()) { progress::fold_progress(&output) } else { output };
+    let cwd = engine
+        .runner
+        .cwd()
+        .or_else(|| std::env::current_dir().ok().map(|d| d.display().to_string()))
+        .unwrap_or_else(|| ".".to_string());
+    let elapsed = started.elapsed().as_millis() as i64;
+    // `--exec '!redo 42'` ran the line it noted; log that line.
+    let logged = engine.runner.vault().noted_redo().unwrap_or_else(|| command.to_string());
+    if let Err(e) = engine.runner.vault().log_command(&logged, Some(&stored), exit_code, &cwd, Some(elapsed)) {
+        eprintln!("[HEADLESS] Logging to the vault failed: {}", e);
+    }
+    Ok(exit_code.unwrap_or(EXIT_FAILED))
+}
+
+/// Print what a native command returned. Lines starting with ❌ fail.
+fn print_result(result: ExecuteResult, out: &mut dyn Write) -> Result<i32> {
+    let lines = result_lines(result);
+    for line in &lines {
+        writeln!(out, "{}", line)?;
+    }
+    let failed = lines.first().is_some_and(|l| l.starts_with('❌'));
+    Ok(if failed { EXIT_FAILED } else { 0 })
+}
+
+/// What a result says as plain lines; nothing for a line sent to the PTY.
+pub fn result_lines(result: ExecuteResult) -> Vec<String> {
+    match result {
+        ExecuteResult::DirectOutput(lines)
+        | ExecuteResult::ConfigChanged(lines)
+        | ExecuteResult::SentToPtyWith(lines) => lines,
+        ExecuteResult::Table(frame) => frame.to_lines(),
+        ExecuteResult::Suggestions(suggestions) => suggestions.into_iter().map(|s| s.command).collect(),
+        ExecuteResult::GeneratedCommand(command) | ExecuteResult::EditCommand(command) => vec![command],
+        ExecuteResult::Http(exchange) => exchange.lines(),
+        ExecuteResult::Fetch(fetched) => fetched.plain_lines(),
+        ExecuteResult::Timeline(data) => data.lines(timeline::DEFAULT_WIDTH),
+        ExecuteResult::RedoOffer(offer) => offer.lines(),
+        ExecuteResult::Correction(correction) => correction.lines(),
+        ExecuteResult::SentToPty | ExecuteResult::ClearScreen | ExecuteResult::Exit => Vec::new(),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Output capture
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Before the line is sent; only prompt markers matter.
+    Idle,
+    /// Sent; waiting for its start marker.
+    Sent,
+    Running,
+    /// Finished, with the exit code if the marker had one.
+    Done(Option<i32>),
+}
+
+/// Splits the PTY stream into one command's output, by OSC 133 markers.
+#[derive(Debug)]
+pub struct CommandCapture {
+    osc: OscParser,
+    ready: bool,
+    phase: Phase,
+    output: Vec<u8>,
+    /// Where the OSC sequence being parsed started in `output`.
+    osc_start: Option<usize>,
+    prev_esc: bool,
+}
+
+impl Default for CommandCapture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandCapture {
+    pub fn new() -> Self {
+        Self { osc: OscParser::new(), ready: false, phase: Phase::Idle, output: Vec::new(), osc_start: None, prev_esc: false }
+    }
+
+    /// A prompt marker has been seen: the shell takes input.
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// The line was written to the shell; its start marker is next.
+    pub fn command_sent(&mut self) {
+        self.phase = Phase::Sent;
+        self.output.clear();
+    }
+
+    /// The exit code once the finish marker arrived (`Some(None)` if it
+    /// carried none).
+    pub fn finished(&self) -> Option<Option<i32>> {
+        match self.phase {
+            Phase::Done(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Everything the command printed, markers removed.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Feed a PTY chunk; returns the part of it that is command output.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut live = Vec::new();
+        for &b in bytes {
+            if self.phase == Phase::Running {
+                if self.prev_esc && b == b']' {
+                    self.osc_start = Some(self.output.len() - 1);
+                }
+                self.prev_esc = b == 0x1b;
+                self.output.push(b);
+                live.push(b);
+            }
+            for ev in self.osc.feed(&[b]) {
+                self.apply(&ev, &mut live);
+            }
+        }
+        live
+    }
+
+    fn apply(&mut self, ev: &OscEvent, live: &mut Vec<u8>) {
+        // Markers are not output; cut the sequence that carried this one.
+        if let Some(start) = self.osc_start.take() {
+            let cut = self.output.len() - start;
+            self.output.truncate(start);
+            live.truncate(live.len().saturating_sub(cut));
+        }
+        match ev {
+            OscEvent::PromptStart => self.ready = true,
+            OscEvent::CommandStart | OscEvent::CommandExecuted if self.phase == Phase::Sent => {
+                self.phase = Phase::Running;
+                self.prev_esc = false;
+            }
+            OscEvent::CommandFinished { exit_code } if self.phase == Phase::Running => {
+                self.phase = Phase::Done(*exit_code);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `text` without escape sequences (CSI, OSC, charset and two-byte escapes) or
+/// carriage returns before newlines.
+pub fn plain_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // Charset designations like `ESC ( B` take one more byte.
+                Some('\u{20}'..='\u{2f}') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' if chars.peek() == Some(&'\n') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
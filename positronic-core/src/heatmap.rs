@@ -0,0 +1,505 @@
+//! `!stats heatmap`: when commands run, by weekday and hour or by day.
+//!
+//! The Vault counts commands per `BUCKET_SECS` slot in SQL, keeping only
+//! slots that have any, off the async runtime. Every zone's offset is a
+//! whole number of slots, so each slot falls in one local hour, and
+//! folding them here into local time (`grid`, `calendar`) follows DST.
+//! `Ramp::quantiles` picks the shade thresholds from the counts that
+//! aren't zero, so one frantic afternoon doesn't wash out the rest.
+//!
+//! The text is the plain rendering: each cell is `CELL_WIDTH` shade
+//! glyphs and each row of cells sits between `HEAT_OPEN` and `HEAT_CLOSE`.
+//! Headless runs and the software renderer show it as it is; the GPU
+//! frontend blanks the glyphs and paints the cells `cells` finds in the
+//! theme's gradient instead.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Timelike};
+use serde::Serialize;
+
+use crate::time_format::{self, local};
+use crate::vault::analytics::DayCount;
+
+pub const HEATMAP_USAGE: &str = "Usage: !stats heatmap [command-prefix] [--weeks n] [--calendar] [--json]";
+
+/// Slot the Vault counts commands in: a quarter hour, the finest step
+/// any zone's UTC offset takes.
+pub const BUCKET_SECS: i64 = 900;
+
+/// Weeks `--calendar` covers without `--weeks`.
+pub const DEFAULT_WEEKS: u32 = 26;
+
+/// Most weeks `--weeks` accepts.
+pub const MAX_WEEKS: u32 = 520;
+
+/// Brackets around a row of cells; the frontend paints what is between.
+pub const HEAT_OPEN: char = '▐';
+pub const HEAT_CLOSE: char = '▌';
+
+/// A cell's glyph, by level: none, then the four quantile bands.
+pub const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Columns per cell, so cells come out about square.
+pub const CELL_WIDTH: usize = 2;
+
+/// `Mon ` before each row.
+const LABEL_WIDTH: usize = 4;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Hours between the labels over the grid.
+const HOUR_LABEL_STEP: usize = 3;
+
+const SECS_PER_WEEK: i64 = 7 * 86_400;
+
+/// What follows `!stats heatmap`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeatmapRequest {
+    /// Only commands starting with this.
+    pub prefix: Option<String>,
+    pub weeks: Option<u32>,
+    /// Days in weekly columns instead of weekday × hour.
+    pub calendar: bool,
+    /// Also write the counts to a JSON file.
+    pub json: bool,
+}
+
+impl HeatmapRequest {
+    /// Parse the words after `heatmap`; the error is the message to show.
+    /// Words that aren't flags make up the prefix.
+    pub fn parse(args: &[&str]) -> Result<HeatmapRequest, String> {
+        let mut request = HeatmapRequest::default();
+        let mut prefix: Vec<&str> = Vec::new();
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            match arg {
+                "--calendar" => request.calendar = true,
+                "--json" => request.json = true,
+                "--weeks" => {
+                    let weeks = args.next().and_then(|n| n.parse::<u32>().ok());
+                    match weeks {
+                        Some(n @ 1..=MAX_WEEKS) => request.weeks = Some(n),
+                        _ => return Err(format!("--weeks takes 1 to {}. {}", MAX_WEEKS, HEATMAP_USAGE)),
+                    }
+                }
+                flag if flag.starts_with("--") => return Err(HEATMAP_USAGE.to_string()),
+                word => prefix.push(word),
+            }
+        }
+        if !prefix.is_empty() {
+            request.prefix = Some(prefix.join(" "));
+        }
+        Ok(request)
+    }
+
+    /// Weeks covered: as asked, `DEFAULT_WEEKS` for a calendar, or all
+    /// history for the grid.
+    pub fn span_weeks(&self) -> Option<u32> {
+        self.weeks.or(self.calendar.then_some(DEFAULT_WEEKS))
+    }
+
+    /// The first instant counted as of `now`: the grid's goes back whole
+    /// weeks from now, the calendar's to local midnight on the Monday
+    /// its first column starts.
+    pub fn since<Tz: TimeZone>(&self, now: i64, tz: &Tz) -> Option<i64> {
+        let weeks = self.span_weeks()?;
+        if !self.calendar {
+            return Some(now - weeks as i64 * SECS_PER_WEEK);
+        }
+        let first = calendar_start(local(now, tz)?.date_naive(), weeks);
+        let midnight = first.and_time(NaiveTime::MIN);
+        // A zone that skips midnight on a DST change: its first hour.
+        let start = tz
+            .from_local_datetime(&midnight)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(midnight + Duration::hours(1))).earliest())?;
+        Some(start.timestamp())
+    }
+}
+
+/// The Monday a calendar of `weeks` columns ending with `today`'s starts.
+pub fn calendar_start(today: NaiveDate, weeks: u32) -> NaiveDate {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    monday - Duration::weeks(weeks.saturating_sub(1) as i64)
+}
+
+/// Commands logged in one `BUCKET_SECS` slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    /// Unix seconds, a multiple of `BUCKET_SECS`.
+    pub start: i64,
+    pub count: u64,
+}
+
+/// Commands per local weekday (Monday first) and hour.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grid {
+    pub counts: [[u64; 24]; 7],
+}
+
+/// Fold slots into weekday × hour in `tz`.
+pub fn grid<Tz: TimeZone>(slots: &[Slot], tz: &Tz) -> Grid {
+    let mut grid = Grid::default();
+    for slot in slots {
+        if let Some(t) = local(slot.start, tz) {
+            grid.counts[t.weekday().num_days_from_monday() as usize][t.hour() as usize] += slot.count;
+        }
+    }
+    grid
+}
+
+/// Commands per local day, `first` (a Monday) through `today`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calendar {
+    pub first: NaiveDate,
+    pub today: NaiveDate,
+    /// One per day from `first`, today included.
+    pub counts: Vec<u64>,
+}
+
+impl Calendar {
+    pub fn date(&self, index: usize) -> NaiveDate {
+        self.first + Duration::days(index as i64)
+    }
+}
+
+/// Fold slots into the days of the `weeks` weekly columns ending with
+/// `today`, in `tz`. Slots outside them are left out.
+pub fn calendar<Tz: TimeZone>(slots: &[Slot], tz: &Tz, today: NaiveDate, weeks: u32) -> Calendar {
+    let first = calendar_start(today, weeks);
+    let days = (today - first).num_days().max(0) as usize + 1;
+    let mut counts = vec![0; days];
+    for slot in slots {
+        let Some(day) = local(slot.start, tz).map(|t| (t.date_naive() - first).num_days()) else {
+            continue;
+        };
+        if let Some(count) = usize::try_from(day).ok().and_then(|day| counts.get_mut(day)) {
+            *count += slot.count;
+        }
+    }
+    Calendar { first, today, counts }
+}
+
+/// Shade thresholds. Zero is level 0; a count up to `cuts[0]` is level
+/// 1, up to `cuts[1]` level 2, up to `cuts[2]` level 3, and above level 4.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Ramp {
+    pub cuts: [u64; 3],
+    /// The largest count, the top of the last band.
+    pub max: u64,
+}
+
+impl Ramp {
+    /// Cut at the quartiles of the counts that aren't zero (nearest
+    /// rank), so about as many cells fall in each band. Counts that are
+    /// all the same come out level 1.
+    pub fn quantiles(counts: impl IntoIterator<Item = u64>) -> Ramp {
+        let mut counts: Vec<u64> = counts.into_iter().filter(|&c| c > 0).collect();
+        if counts.is_empty() {
+            return Ramp::default();
+        }
+        counts.sort_unstable();
+        let at = |quarter: usize| counts[(counts.len() - 1) * quarter / 4];
+        Ramp { cuts: [at(1), at(2), at(3)], max: counts[counts.len() - 1] }
+    }
+
+    pub fn level(&self, count: u64) -> u8 {
+        if count == 0 {
+            return 0;
+        }
+        1 + self.cuts.iter().filter(|&&cut| count > cut).count() as u8
+    }
+
+    /// `(level, lowest, highest)` of each band some count falls in,
+    /// level 0 first.
+    pub fn bands(&self) -> Vec<(u8, u64, u64)> {
+        let mut bands = vec![(0, 0, 0)];
+        let mut low = 1;
+        for (level, high) in (1..).zip(self.cuts.iter().copied().chain([self.max])) {
+            if low <= high {
+                bands.push((level, low, high));
+            }
+            low = low.max(high + 1);
+        }
+        bands
+    }
+
+    fn glyph(&self, count: u64) -> char {
+        SHADES[self.level(count) as usize]
+    }
+}
+
+/// The counts `!stats heatmap` shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeatmapData {
+    Grid(Box<Grid>),
+    Calendar(Calendar),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    pub request: HeatmapRequest,
+    pub since: Option<i64>,
+    pub data: HeatmapData,
+    pub ramp: Ramp,
+}
+
+impl Heatmap {
+    /// Fold the Vault's slots (counted from `request.since(now, tz)`)
+    /// as the request asks.
+    pub fn build<Tz: TimeZone>(request: &HeatmapRequest, slots: &[Slot], tz: &Tz, now: i64) -> Heatmap {
+        let data = match (request.calendar, local(now, tz)) {
+            (true, Some(today)) => HeatmapData::Calendar(calendar(
+                slots,
+                tz,
+                today.date_naive(),
+                request.span_weeks().unwrap_or(DEFAULT_WEEKS),
+            )),
+            _ => HeatmapData::Grid(Box::new(grid(slots, tz))),
+        };
+        let ramp = Ramp::quantiles(data.counts());
+        Heatmap { request: request.clone(), since: request.since(now, tz), data, ramp }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.data.counts().sum()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let scope = self.scope();
+        if self.total() == 0 {
+            return vec![format!("📊 No commands to map{}.", scope)];
+        }
+        let mut lines = match &self.data {
+            HeatmapData::Grid(grid) => {
+                let mut lines = vec![
+                    format!("📊 Commands by weekday and hour{}: {}", scope, self.total()),
+                    hour_axis(),
+                ];
+                for (day, hours) in WEEKDAYS.iter().zip(&grid.counts) {
+                    let cells: String = hours.iter().map(|&c| self.cell(c)).collect();
+                    lines.push(format!("{:<w$}{}{}{} {}", day, HEAT_OPEN, cells, HEAT_CLOSE, hours.iter().sum::<u64>(), w = LABEL_WIDTH));
+                }
+                lines
+            }
+            HeatmapData::Calendar(cal) => {
+                let weeks = cal.counts.len().div_ceil(7);
+                let mut lines = vec![
+                    format!("📅 Commands per day{}: {}", scope, self.total()),
+                    month_axis(cal, weeks),
+                ];
+                for (weekday, day) in WEEKDAYS.iter().enumerate() {
+                    let cells: String = (0..weeks)
+                        .map(|week| match cal.counts.get(week * 7 + weekday) {
+                            Some(&count) => self.cell(count),
+                            None => " ".repeat(CELL_WIDTH),
+                        })
+                        .collect();
+                    lines.push(format!("{:<w$}{}{}{}", day, HEAT_OPEN, cells, HEAT_CLOSE, w = LABEL_WIDTH));
+                }
+                lines
+            }
+        };
+        lines.push(self.legend());
+        lines.push(self.busiest());
+        lines
+    }
+
+    /// `, 'git' only, last 4 weeks` and the like.
+    fn scope(&self) -> String {
+        let mut scope = String::new();
+        if let Some(prefix) = &self.request.prefix {
+            scope.push_str(&format!(", '{}' only", prefix));
+        }
+        if let Some(weeks) = self.request.span_weeks() {
+            scope.push_str(&format!(", last {} week{}", weeks, if weeks == 1 { "" } else { "s" }));
+        }
+        scope
+    }
+
+    fn cell(&self, count: u64) -> String {
+        self.ramp.glyph(count).to_string().repeat(CELL_WIDTH)
+    }
+
+    /// One bracketed swatch per band, with the counts it stands for.
+    fn legend(&self) -> String {
+        let unit = match self.data {
+            HeatmapData::Grid(_) => "per hour",
+            HeatmapData::Calendar(_) => "per day",
+        };
+        let swatches: Vec<String> = self
+            .ramp
+            .bands()
+            .into_iter()
+            .map(|(level, low, high)| {
+                let range = if low == high { low.to_string() } else { format!("{}–{}", low, high) };
+                format!("{}{}{} {}", HEAT_OPEN, SHADES[level as usize].to_string().repeat(CELL_WIDTH), HEAT_CLOSE, range)
+            })
+            .collect();
+        format!("{:w$}{}  {}", "", swatches.join("  "), unit, w = LABEL_WIDTH)
+    }
+
+    fn busiest(&self) -> String {
+        match &self.data {
+            HeatmapData::Grid(grid) => {
+                let (day, hour, count) = (0..7)
+                    .flat_map(|day| (0..24).map(move |hour| (day, hour)))
+                    .map(|(day, hour)| (day, hour, grid.counts[day][hour]))
+                    .max_by_key(|&(day, hour, count)| (count, std::cmp::Reverse((day, hour))))
+                    .unwrap_or_default();
+                format!("Busiest: {} {:02}:00–{:02}:00, {} commands", WEEKDAYS[day], hour, (hour + 1) % 24, count)
+            }
+            HeatmapData::Calendar(cal) => {
+                let (index, count) = cal
+                    .counts
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|&(index, &count)| (count, std::cmp::Reverse(index)))
+                    .map(|(index, &count)| (index, count))
+                    .unwrap_or_default();
+                format!("Busiest: {}, {} commands", cal.date(index).format("%a %Y-%m-%d"), count)
+            }
+        }
+    }
+
+    /// What `--json` writes, as of `now`.
+    pub fn export(&self, now: i64) -> HeatmapExport {
+        let (hours, days) = match &self.data {
+            HeatmapData::Grid(grid) => (
+                Some(
+                    WEEKDAYS
+                        .iter()
+                        .zip(&grid.counts)
+                        .map(|(day, counts)| WeekdayHours { weekday: day, hours: counts.to_vec() })
+                        .collect(),
+                ),
+                None,
+            ),
+            HeatmapData::Calendar(cal) => (
+                None,
+                Some(
+                    cal.counts
+                        .iter()
+                        .enumerate()
+                        .map(|(index, &count)| DayCount {
+                            date: cal.date(index).format("%Y-%m-%d").to_string(),
+                            count: count as i64,
+                        })
+                        .collect(),
+                ),
+            ),
+        };
+        HeatmapExport {
+            generated_at: now,
+            prefix: self.request.prefix.clone(),
+            since: self.since,
+            total: self.total(),
+            ramp: self.ramp,
+            hours,
+            days,
+        }
+    }
+}
+
+impl HeatmapData {
+    fn counts(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        match self {
+            HeatmapData::Grid(grid) => Box::new(grid.counts.iter().flatten().copied()),
+            HeatmapData::Calendar(cal) => Box::new(cal.counts.iter().copied()),
+        }
+    }
+}
+
+/// `!stats heatmap --json`: the matrix behind the picture, instants in
+/// ISO 8601 like `!stats export`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeatmapExport {
+    #[serde(serialize_with = "time_format::serialize_iso8601")]
+    pub generated_at: i64,
+    pub prefix: Option<String>,
+    /// `None` for all history.
+    #[serde(serialize_with = "time_format::serialize_iso8601_opt")]
+    pub since: Option<i64>,
+    pub total: u64,
+    pub ramp: Ramp,
+    /// The weekday × hour grid, Monday first, local time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hours: Option<Vec<WeekdayHours>>,
+    /// The calendar, oldest first, local dates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<Vec<DayCount>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeekdayHours {
+    pub weekday: &'static str,
+    /// Index is the hour.
+    pub hours: Vec<u64>,
+}
+
+/// `0     3     6 …` over the hour columns.
+fn hour_axis() -> String {
+    let labels: String = (0..24)
+        .step_by(HOUR_LABEL_STEP)
+        .map(|hour| format!("{:<w$}", hour, w = HOUR_LABEL_STEP * CELL_WIDTH))
+        .collect();
+    format!("{:w$}{}", "", labels, w = LABEL_WIDTH + 1).trim_end().to_string()
+}
+
+/// Month names over the week columns that hold the 1st.
+fn month_axis(cal: &Calendar, weeks: usize) -> String {
+    let mut axis = " ".repeat(LABEL_WIDTH + 1 + weeks * CELL_WIDTH);
+    let mut free = 0;
+    for week in 0..weeks {
+        let monday = cal.date(week * 7);
+        let Some(first) = (0..7).map(|d| monday + Duration::days(d)).find(|d| d.day() == 1) else {
+            continue;
+        };
+        let col = LABEL_WIDTH + 1 + week * CELL_WIDTH;
+        let name = first.format("%b").to_string();
+        if col >= free && col + name.len() <= axis.len() {
+            axis.replace_range(col..col + name.len(), &name);
+            free = col + name.len() + 1;
+        }
+    }
+    axis.trim_end().to_string()
+}
+
+/// One shaded cell of a heat line, to paint behind the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeatCell {
+    /// Column of its first glyph.
+    pub col: usize,
+    pub width: usize,
+    pub level: u8,
+}
+
+/// The cells between `HEAT_OPEN` and `HEAT_CLOSE` in `line`; blank
+/// cells (days still to come) have none. Columns count characters:
+/// heat lines are otherwise ASCII.
+pub fn cells(line: &str) -> Vec<HeatCell> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut cells = Vec::new();
+    let mut col = 0;
+    while col < chars.len() {
+        if chars[col] != HEAT_OPEN {
+            col += 1;
+            continue;
+        }
+        let start = col + 1;
+        let Some(len) = chars[start..].iter().position(|&c| c == HEAT_CLOSE) else {
+            break;
+        };
+        for (i, chunk) in chars[start..start + len].chunks(CELL_WIDTH).enumerate() {
+            if let Some(level) = SHADES.iter().position(|&s| s == chunk[0]) {
+                cells.push(HeatCell { col: start + i * CELL_WIDTH, width: chunk.len(), level: level as u8 });
+            }
+        }
+        col = start + len + 1;
+    }
+    cells
+}
+
+/// Whether `line` is a row of heat cells or a legend.
+pub fn is_heat_line(line: &str) -> bool {
+    line.find(HEAT_OPEN).zip(line.rfind(HEAT_CLOSE)).is_some_and(|(open, close)| open < close)
+}
@@ -0,0 +1,54 @@
+//! `!here` and the directory hint: the commands usually run in a directory.
+//!
+//! History already records where each command ran, so
+//! `Vault::top_for_directory` groups it by directory and ranks commands by
+//! frecency, each run counting more the more recent it is. Commands run
+//! fewer than `MIN_RUNS` times there are left out, as are `cd` and `!`
+//! builtins, which say nothing about the project.
+//!
+//! The UI shows `hint_line` once per directory per session, the first time
+//! the shell moves into it, and only when there are `HINT_COMMANDS` of them
+//! (`hints.directory = off` turns it off). `!here` lists them on demand.
+
+use crate::vault::DirectoryCommand;
+
+/// Vault config key: `off` stops the hint on entering a directory.
+pub const HINTS_KEY: &str = "hints.directory";
+
+/// Commands named in the hint; with fewer, there's no hint.
+pub const HINT_COMMANDS: usize = 3;
+
+/// Runs in a directory before a command counts as frequent there.
+pub const MIN_RUNS: i64 = 3;
+
+/// Commands that move around or are Positronic's own.
+const NOISE: &[&str] = &["cd", "pushd", "popd", "chdir", "set-location", "sl", "clear", "cls", "exit"];
+
+/// Whether `command` is left out of the ranking.
+pub fn is_noise(command: &str) -> bool {
+    let command = command.trim();
+    let first = command.split_whitespace().next().unwrap_or("");
+    command.is_empty() || command.starts_with('!') || NOISE.contains(&first.to_lowercase().as_str())
+}
+
+/// `↳ frequent here: cargo build · cargo test · git pull (Tab to insert)`,
+/// or `None` with fewer than `HINT_COMMANDS` commands.
+pub fn hint_line<S: AsRef<str>>(commands: &[S]) -> Option<String> {
+    if commands.len() < HINT_COMMANDS {
+        return None;
+    }
+    let names: Vec<&str> = commands[..HINT_COMMANDS].iter().map(AsRef::as_ref).collect();
+    Some(format!("↳ frequent here: {} (Tab to insert)", names.join(" · ")))
+}
+
+/// The `!here` output for `directory`.
+pub fn list_lines(directory: &str, commands: &[DirectoryCommand]) -> Vec<String> {
+    if commands.is_empty() {
+        return vec![format!("📍 Nothing run {}+ times in {} yet", MIN_RUNS, directory)];
+    }
+    let mut lines = vec![format!("📍 Frequent in {}:", directory), String::new()];
+    for (i, c) in commands.iter().enumerate() {
+        lines.push(format!("  {:>3}. {} (×{})", i + 1, c.command, c.runs));
+    }
+    lines
+}
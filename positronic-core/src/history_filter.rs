@@ -0,0 +1,164 @@
+//! Which command lines stay out of the history: `history.ignore_dups`,
+//! `history.ignore_space`, `history.ignore_patterns` and the
+//! `history.ignore_secrets` preset.
+//!
+//! One filter guards both places a line is remembered, the Vault's
+//! `log_command` and the UI's Up-arrow list. A line kept out still runs;
+//! the runner holds it in a `SessionOnly` list so `!history --all` can
+//! show it until the session ends. A duplicate is only collapsed into the
+//! line before it, so it is not "unlogged".
+
+use regex::Regex;
+
+use positronic_neural::privacy::{PrivacyGuard, ScrubCategory};
+
+/// Vault config keys.
+pub const IGNORE_DUPS_KEY: &str = "history.ignore_dups";
+pub const IGNORE_SPACE_KEY: &str = "history.ignore_space";
+pub const IGNORE_PATTERNS_KEY: &str = "history.ignore_patterns";
+pub const IGNORE_SECRETS_KEY: &str = "history.ignore_secrets";
+
+/// Shown in the input bar while the typed line would not be logged.
+pub const NOT_LOGGED: &str = "not logged";
+
+/// Session-only lines kept for `!history --all`; older ones are dropped.
+pub const MAX_SESSION_ONLY: usize = 200;
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Collapse a line identical to the one logged just before it.
+    pub ignore_dups: bool,
+    /// Keep out lines typed with a leading space.
+    pub ignore_space: bool,
+    /// Keep out lines matching any of these.
+    pub patterns: Vec<Regex>,
+    /// Keep out lines carrying a credential `PrivacyGuard` recognizes.
+    pub ignore_secrets: bool,
+}
+
+/// What the filter decided for one line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Keep,
+    /// Same as the previous line; collapsed into it.
+    Duplicate,
+    LeadingSpace,
+    /// Matched the `history.ignore_patterns` entry shown.
+    Pattern(String),
+    /// Carries an API key, token or private key.
+    Secret,
+}
+
+impl Verdict {
+    pub fn is_kept(&self) -> bool {
+        *self == Verdict::Keep
+    }
+
+    /// Kept out of the history altogether, rather than collapsed.
+    pub fn is_private(&self) -> bool {
+        matches!(self, Verdict::LeadingSpace | Verdict::Pattern(_) | Verdict::Secret)
+    }
+}
+
+impl HistoryFilter {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> HistoryFilter {
+        let mut filter = HistoryFilter::default();
+        for (key, flag) in [
+            (IGNORE_DUPS_KEY, &mut filter.ignore_dups),
+            (IGNORE_SPACE_KEY, &mut filter.ignore_space),
+            (IGNORE_SECRETS_KEY, &mut filter.ignore_secrets),
+        ] {
+            if let Some(value) = lookup(key) {
+                match parse_flag(&value) {
+                    Some(on) => *flag = on,
+                    None => problems.push(format!("{} = \"{}\": expected on or off", key, value)),
+                }
+            }
+        }
+        if let Some(value) = lookup(IGNORE_PATTERNS_KEY) {
+            match parse_patterns(&value) {
+                Ok(patterns) => filter.patterns = patterns,
+                Err(e) => problems.push(format!("{} = \"{}\": {}", IGNORE_PATTERNS_KEY, value, e)),
+            }
+        }
+        filter
+    }
+
+    /// Judge `line` as typed, leading space included. `previous` is the
+    /// last line that was kept; a line kept out never becomes it.
+    pub fn check(&self, line: &str, previous: Option<&str>) -> Verdict {
+        if self.ignore_space && line.starts_with(' ') {
+            return Verdict::LeadingSpace;
+        }
+        let line = line.trim();
+        if let Some(pattern) = self.patterns.iter().find(|p| p.is_match(line)) {
+            return Verdict::Pattern(pattern.as_str().to_string());
+        }
+        if self.ignore_secrets && has_credential(line) {
+            return Verdict::Secret;
+        }
+        if self.ignore_dups && previous.map(str::trim) == Some(line) {
+            return Verdict::Duplicate;
+        }
+        Verdict::Keep
+    }
+}
+
+/// `history.ignore_patterns`: a JSON array of regexes, or a single regex.
+pub fn parse_patterns(value: &str) -> Result<Vec<Regex>, String> {
+    let value = value.trim();
+    let sources: Vec<String> = if value.starts_with('[') {
+        serde_json::from_str(value).map_err(|_| "expected a JSON array of regexes".to_string())?
+    } else if value.is_empty() {
+        Vec::new()
+    } else {
+        vec![value.to_string()]
+    };
+    sources
+        .iter()
+        .map(|source| Regex::new(source).map_err(|_| format!("invalid regex {:?}", source)))
+        .collect()
+}
+
+/// True for the credential categories: an IP or email address alone is
+/// ordinary in a command line (`ssh 10.0.0.2`, `git config user.email`).
+pub fn has_credential(line: &str) -> bool {
+    let (_, report) = PrivacyGuard::scrub_with_report(line);
+    ScrubCategory::ALL
+        .iter()
+        .any(|c| !matches!(c, ScrubCategory::Ip | ScrubCategory::Email) && report.count(*c) > 0)
+}
+
+pub(crate) fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" | "on" | "1" | "yes" => Some(true),
+        "false" | "off" | "0" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Lines run this session but kept out of the Vault, oldest first.
+#[derive(Debug, Default)]
+pub struct SessionOnly {
+    lines: Vec<String>,
+}
+
+impl SessionOnly {
+    /// Remember `line`; a repeat of the last one is collapsed when
+    /// `ignore_dups` is set.
+    pub fn push(&mut self, line: &str, ignore_dups: bool) {
+        if ignore_dups && self.lines.last().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.lines.len() == MAX_SESSION_ONLY {
+            self.lines.remove(0);
+        }
+        self.lines.push(line.to_string());
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
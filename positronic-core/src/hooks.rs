@@ -0,0 +1,234 @@
+//! `!hook`: per-command environment and before/after commands.
+//!
+//! A rule matches command lines with a regex and can set environment
+//! variables for them, run a command before them, and run one after them.
+//! Rules live in the vault and apply in the order they were created. The
+//! Runner checks every line it runs: a shell line after alias expansion,
+//! or a `!` command (never `!hook` itself, so a bad rule can always be
+//! removed).
+//!
+//! - `--env VAR=val` goes in front of a shell line: `VAR=val cmd` in POSIX
+//!   shells, `$env:VAR='val'; cmd` in PowerShell, where it stays set for
+//!   the session. Native commands have no environment to set. It wins
+//!   over a `!setenv` override of the same variable.
+//! - `--before` runs first, native or shell.
+//! - `--after` runs once the command has finished, with `{exit}` replaced
+//!   by its exit code: a native command's at once (0, or 1 if it failed),
+//!   a shell command's when the shell reports its end (OSC 133;D), so
+//!   shell after-hooks need shell integration.
+//!
+//! Hook commands are hooked too, at most `MAX_DEPTH` levels deep, so rules
+//! that trigger each other stop. Each hooked run starts with a trace line
+//! naming the rules that fired, unless `hooks.trace` is off.
+
+use regex::Regex;
+
+use crate::alias;
+use crate::redo::RedoShell;
+
+pub const HOOK_USAGE: &str =
+    "Usage: !hook add --match <regex> [--env VAR=val]… [--before <cmd>] [--after <cmd>] | list | rm <id>";
+
+/// Vault config key: show which hooks fired (default on).
+pub const TRACE_KEY: &str = "hooks.trace";
+
+/// How deep hook commands may trigger further hooks.
+pub const MAX_DEPTH: usize = 3;
+
+/// Shell commands whose after-hooks wait at once; older ones are dropped.
+pub const MAX_PENDING_AFTER: usize = 16;
+
+/// Replaced by the exit code in after-hooks.
+pub const EXIT_PLACEHOLDER: &str = "{exit}";
+
+/// What a rule does, as given to `!hook add`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookSpec {
+    /// Regex on the command line.
+    pub pattern: String,
+    pub env: Vec<(String, String)>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// A stored rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRule {
+    pub id: i64,
+    pub spec: HookSpec,
+    pub created_at: i64,
+}
+
+/// A parsed `!hook` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookCommand {
+    Add(HookSpec),
+    List,
+    Remove(i64),
+}
+
+impl HookCommand {
+    /// Parse what follows `!hook`; the error is the message to show.
+    /// Quote a `--match` pattern with backslashes in single quotes.
+    pub fn parse(args: &str) -> Result<HookCommand, String> {
+        let words: Vec<String> = alias::split_args(args).iter().map(|w| alias::unquote(w)).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["list"] => Ok(HookCommand::List),
+            ["rm" | "remove", id] => {
+                let id = id.trim_start_matches('#');
+                id.parse().map(HookCommand::Remove).map_err(|_| format!("❌ Bad hook id '{}'", id))
+            }
+            ["add", rest @ ..] => parse_add(rest).map(HookCommand::Add),
+            _ => Err(HOOK_USAGE.to_string()),
+        }
+    }
+}
+
+fn parse_add(words: &[&str]) -> Result<HookSpec, String> {
+    let mut spec = HookSpec::default();
+    let mut words = words.iter();
+    while let Some(flag) = words.next() {
+        let value = words.next().ok_or_else(|| format!("❌ {} needs a value", flag))?;
+        match *flag {
+            "--match" => spec.pattern = value.to_string(),
+            "--env" => {
+                let (name, val) = value.split_once('=').ok_or_else(|| format!("❌ --env wants VAR=value, not '{}'", value))?;
+                if !is_env_name(name) {
+                    return Err(format!("❌ '{}' is not a variable name", name));
+                }
+                spec.env.push((name.to_string(), val.to_string()));
+            }
+            "--before" => spec.before = Some(value.to_string()),
+            "--after" => spec.after = Some(value.to_string()),
+            _ => return Err(HOOK_USAGE.to_string()),
+        }
+    }
+    if spec.pattern.is_empty() {
+        return Err("❌ A hook needs --match <regex>".to_string());
+    }
+    if let Err(e) = Regex::new(&spec.pattern) {
+        return Err(format!("❌ Bad --match pattern: {}", e));
+    }
+    if spec.env.is_empty() && spec.before.is_none() && spec.after.is_none() {
+        return Err("❌ A hook needs --env, --before or --after".to_string());
+    }
+    Ok(spec)
+}
+
+pub(crate) fn is_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `!hook …` lines are never hooked.
+pub fn is_hook_command(line: &str) -> bool {
+    line.split_whitespace().next() == Some("!hook")
+}
+
+/// The rules whose pattern matches `line`, in creation order. A pattern
+/// that no longer compiles matches nothing.
+pub fn matching<'a>(rules: &'a [HookRule], line: &str) -> Vec<&'a HookRule> {
+    if is_hook_command(line) {
+        return Vec::new();
+    }
+    rules
+        .iter()
+        .filter(|rule| Regex::new(&rule.spec.pattern).is_ok_and(|re| re.is_match(line)))
+        .collect()
+}
+
+/// `command` with `env` set for it in `shell`. A later entry for the same
+/// variable wins, as it would typed out.
+pub fn with_env(env: &[(String, String)], shell: RedoShell, command: &str) -> String {
+    if env.is_empty() {
+        return command.to_string();
+    }
+    let mut line = String::new();
+    for (name, value) in env {
+        match shell {
+            RedoShell::Posix => line.push_str(&format!("{}={} ", name, posix_word(value))),
+            RedoShell::PowerShell => line.push_str(&format!("$env:{}='{}'; ", name, value.replace('\'', "''"))),
+        }
+    }
+    line.push_str(command);
+    line
+}
+
+/// `value` as one POSIX word: as is when that is safe, else single-quoted.
+fn posix_word(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':' | ',' | '@' | '+' | '%'));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r#"'"'"'"#))
+    }
+}
+
+/// `template` with `{exit}` replaced; an unknown code reads `?`.
+pub fn substitute_exit(template: &str, exit: Option<i32>) -> String {
+    let code = exit.map_or_else(|| "?".to_string(), |code| code.to_string());
+    template.replace(EXIT_PLACEHOLDER, &code)
+}
+
+/// Whether `TRACE_KEY` leaves the trace on; it is on unless turned off.
+pub fn trace_enabled(value: Option<&str>) -> bool {
+    !matches!(value.map(|v| v.trim().to_lowercase()).as_deref(), Some("false" | "off" | "0" | "no"))
+}
+
+/// `🪝 hooks #1 (env AWS_PROFILE), #3 (before, after)`
+pub fn trace_line(fired: &[&HookRule]) -> String {
+    let parts: Vec<String> = fired.iter().map(|rule| format!("#{} ({})", rule.id, actions(&rule.spec))).collect();
+    format!("🪝 hooks {}", parts.join(", "))
+}
+
+fn actions(spec: &HookSpec) -> String {
+    let mut actions = Vec::new();
+    if !spec.env.is_empty() {
+        let names: Vec<&str> = spec.env.iter().map(|(name, _)| name.as_str()).collect();
+        actions.push(format!("env {}", names.join(" ")));
+    }
+    if spec.before.is_some() {
+        actions.push("before".to_string());
+    }
+    if spec.after.is_some() {
+        actions.push("after".to_string());
+    }
+    actions.join(", ")
+}
+
+/// `!hook list` lines.
+pub fn list_lines(rules: &[HookRule]) -> Vec<String> {
+    if rules.is_empty() {
+        return vec![
+            "🪝 No hooks".to_string(),
+            "   !hook add --match \"^terraform\" --env AWS_PROFILE=dev".to_string(),
+        ];
+    }
+    let mut lines = vec![format!("🪝 Hooks ({}), applied in this order:", rules.len())];
+    for rule in rules {
+        lines.push(format!("  #{:<3} /{}/", rule.id, rule.spec.pattern));
+        for (name, value) in &rule.spec.env {
+            lines.push(format!("         env    {}={}", name, value));
+        }
+        if let Some(before) = &rule.spec.before {
+            lines.push(format!("         before {}", before));
+        }
+        if let Some(after) = &rule.spec.after {
+            lines.push(format!("         after  {}", after));
+        }
+    }
+    lines
+}
+
+/// After-hooks waiting for a shell command to finish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingAfter {
+    /// The line as written to the shell.
+    pub line: String,
+    pub commands: Vec<String>,
+    /// The depth the after-hooks run at.
+    pub depth: usize,
+}
@@ -0,0 +1,634 @@
+//! `!http`: send one HTTP request and show the response.
+//!
+//! `!http <method> <url> [Name:value…] [--body @file|<text>]` sends the
+//! request with reqwest, following up to `MAX_REDIRECTS` redirects, and
+//! answers with an `HttpExchange`: a status line, the response headers, a
+//! timing line and the body. Frontends hand the body to the Holodeck, so
+//! JSON and CSV get their rich views, and keep the whole exchange for
+//! `!save`.
+//!
+//! - Header values that look like credentials, by their name or by the
+//!   privacy scrubber, are masked in everything an exchange shows or keeps.
+//!   `!` lines never reach the vault's history; the last request is kept
+//!   in memory only, for `!http` with no arguments, which puts it back in
+//!   the input line to edit and send again.
+//! - A body longer than the cap keeps its first `BODY_CAP` bytes; the
+//!   whole body is written to a file in the temp directory.
+//! - The timing line is for the first hop. DNS is a lookup done here (the
+//!   client then uses its answer) and connect a separate TCP connection to
+//!   the same address; TTFB and total time the request itself, to its
+//!   response headers and to the end of its body.
+//! - Cancelling (`HttpOptions::cancel`) stops it at the next step; a
+//!   partial spill file is deleted.
+
+use std::io::Write as _;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::{Method, Url};
+
+use crate::alias;
+use crate::cancel::{or_cancelled, CancellationToken};
+use crate::history_filter;
+use crate::term::binary;
+use crate::trash::format_bytes;
+
+pub const HTTP_USAGE: &str =
+    "Usage: !http <method> <url> [Name:value…] [--body @file|<text>]  (no arguments: edit the last request)";
+
+/// Vault config key: seconds a request may take, redirects and body included.
+pub const TIMEOUT_KEY: &str = "http.timeout";
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Body bytes kept with an exchange; the rest goes to the spill file.
+pub const BODY_CAP: usize = 256 * 1024;
+
+pub const MAX_REDIRECTS: usize = 10;
+
+const MASK: &str = "••••";
+
+/// Header names whose values are always masked.
+const SECRET_HEADERS: &[&str] = &["authorization", "proxy-authorization", "cookie", "set-cookie"];
+
+/// Header names containing one of these are masked too (`x-api-key`,
+/// `x-auth-token`…).
+const SECRET_NAME_PARTS: &[&str] = &["token", "secret", "key", "auth", "password", "session"];
+
+/// A parsed `!http` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpCommand {
+    Send(HttpRequest),
+    /// No arguments: bring back the last request.
+    Replay,
+}
+
+/// A request body as given: read from a file when sent, or inline text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestBody {
+    File(String),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// Upper case, e.g. `GET`.
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<RequestBody>,
+}
+
+impl HttpCommand {
+    /// Parse what follows `!http`; the error is the message to show. A
+    /// header is one word, so quote values with spaces:
+    /// `'Authorization: Bearer x'`. A URL without a scheme gets `http://`.
+    pub fn parse(args: &str) -> Result<HttpCommand, String> {
+        let words: Vec<String> = alias::split_args(args).iter().map(|w| alias::unquote(w)).collect();
+        let Some((method, rest)) = words.split_first() else {
+            return Ok(HttpCommand::Replay);
+        };
+        let method = method.to_ascii_uppercase();
+        if Method::from_bytes(method.as_bytes()).is_err() {
+            return Err(format!("❌ '{}' is not an HTTP method", method));
+        }
+        let Some((url, rest)) = rest.split_first() else {
+            return Err(HTTP_USAGE.to_string());
+        };
+        let url = if url.contains("://") { url.clone() } else { format!("http://{}", url) };
+        let parsed = Url::parse(&url).map_err(|e| format!("❌ Bad URL '{}': {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("❌ Only http and https URLs, not {}", parsed.scheme()));
+        }
+
+        let mut request = HttpRequest { method, url, headers: Vec::new(), body: None };
+        let mut words = rest.iter();
+        while let Some(word) = words.next() {
+            if word == "--body" {
+                let value = words.next().ok_or("❌ --body needs @file or the body text")?;
+                request.body = Some(match value.strip_prefix('@') {
+                    Some(path) => RequestBody::File(path.to_string()),
+                    None => RequestBody::Text(value.clone()),
+                });
+                continue;
+            }
+            let Some((name, value)) = word.split_once(':') else {
+                return Err(format!("❌ '{}' is not a header (Name:value) or --body", word));
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+                return Err(format!("❌ '{}' is not a header name", name));
+            }
+            request.headers.push((name.to_string(), value.trim().to_string()));
+        }
+        Ok(HttpCommand::Send(request))
+    }
+}
+
+impl HttpRequest {
+    /// The `!http` line that sends this request again.
+    pub fn command_line(&self) -> String {
+        let mut line = format!("!http {} {}", self.method, word(&self.url));
+        for (name, value) in &self.headers {
+            line.push(' ');
+            line.push_str(&word(&format!("{}: {}", name, value)));
+        }
+        match &self.body {
+            Some(RequestBody::File(path)) => line.push_str(&format!(" --body {}", word(&format!("@{}", path)))),
+            Some(RequestBody::Text(text)) => line.push_str(&format!(" --body {}", word(text))),
+            None => {}
+        }
+        line
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+}
+
+/// `text` as one word for `alias::split_args`: as is when that is safe,
+/// else single-quoted.
+fn word(text: &str) -> String {
+    let plain = !text.is_empty() && !text.chars().any(|c| c.is_whitespace() || matches!(c, '\'' | '"' | '\\'));
+    if plain {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r#"'\''"#))
+    }
+}
+
+/// The timeout `TIMEOUT_KEY` asks for, in seconds (fractions allowed).
+pub fn timeout_from_config(value: Option<&str>) -> Duration {
+    value
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map_or(DEFAULT_TIMEOUT, Duration::from_secs_f64)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Masking
+// ════════════════════════════════════════════════════════════════════
+
+/// Whether a header's value is a secret: by its name, or because the
+/// privacy scrubber finds a credential in it.
+pub fn is_secret_header(name: &str, value: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    if lower.ends_with("-authenticate") {
+        // A challenge (`WWW-Authenticate`), not a credential.
+        return history_filter::has_credential(value);
+    }
+    SECRET_HEADERS.contains(&lower.as_str())
+        || SECRET_NAME_PARTS.iter().any(|part| lower.contains(part))
+        || history_filter::has_credential(value)
+}
+
+/// `value` as shown: secrets masked, keeping what says what they are (an
+/// auth scheme, cookie names and attributes).
+pub fn mask_header(name: &str, value: &str) -> String {
+    if !is_secret_header(name, value) {
+        return value.to_string();
+    }
+    let masked_pair = |pair: &str| match pair.split_once('=') {
+        Some((key, _)) => format!("{}={}", key.trim(), MASK),
+        None => MASK.to_string(),
+    };
+    match name.to_ascii_lowercase().as_str() {
+        "cookie" => value.split(';').map(masked_pair).collect::<Vec<_>>().join("; "),
+        "set-cookie" => {
+            let mut parts = value.split(';');
+            let first = parts.next().map(masked_pair).unwrap_or_default();
+            std::iter::once(first).chain(parts.map(|p| p.trim().to_string())).collect::<Vec<_>>().join("; ")
+        }
+        _ => match value.split_once(' ') {
+            Some((scheme, _)) if scheme.chars().all(|c| c.is_ascii_alphabetic()) => format!("{} {}", scheme, MASK),
+            _ => MASK.to_string(),
+        },
+    }
+}
+
+fn masked(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers.iter().map(|(name, value)| (name.clone(), mask_header(name, value))).collect()
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Exchange
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub status: u16,
+    /// Where it sent the request.
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timing {
+    /// None when the URL names an address.
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub ttfb: Duration,
+    pub total: Duration,
+}
+
+/// A request and its response, with header secrets already masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpExchange {
+    pub method: String,
+    /// The URL asked for.
+    pub url: String,
+    /// Where the response came from, after redirects.
+    pub final_url: String,
+    pub redirects: Vec<Redirect>,
+    pub request_headers: Vec<(String, String)>,
+    pub status: u16,
+    pub reason: String,
+    /// `HTTP/1.1`, `HTTP/2.0`…
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    /// The body, or its first bytes if `spill` is set.
+    pub body: Vec<u8>,
+    /// The whole body's size.
+    pub body_size: u64,
+    /// Where the whole body went when it was over the cap.
+    pub spill: Option<PathBuf>,
+    pub timing: Timing,
+}
+
+impl HttpExchange {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    /// The media type, without parameters: `application/json`.
+    pub fn content_type(&self) -> Option<String> {
+        let value = self.header("content-type")?;
+        Some(value.split(';').next().unwrap_or(value).trim().to_ascii_lowercase())
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        (self.body.len() as u64) < self.body_size
+    }
+
+    /// Whether the body is not text, by its type or by its bytes.
+    pub fn is_binary(&self) -> bool {
+        let textual = self.content_type().is_none_or(|ct| is_textual(&ct));
+        !textual || binary::looks_binary(&self.body)
+    }
+
+    /// The body as text for display and the Holodeck; None when it is
+    /// binary or empty.
+    pub fn body_text(&self) -> Option<String> {
+        if self.body.is_empty() || self.is_binary() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&self.body).into_owned())
+    }
+
+    /// `🌐 HTTP/1.1 200 OK · GET https://… · 1.2 KB application/json`
+    pub fn status_line(&self) -> String {
+        let mut line = format!("🌐 {} {} {} · {} {}", self.version, self.status, self.reason, self.method, self.final_url);
+        line.push_str(&format!(" · {}", format_bytes(self.body_size)));
+        if let Some(ct) = self.content_type() {
+            line.push_str(&format!(" {}", ct));
+        }
+        line
+    }
+
+    /// `⏱ dns 2 ms · connect 1 ms · ttfb 40 ms · total 43 ms`
+    pub fn timing_line(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(dns) = self.timing.dns {
+            parts.push(format!("dns {}", format_duration(dns)));
+        }
+        if let Some(connect) = self.timing.connect {
+            parts.push(format!("connect {}", format_duration(connect)));
+        }
+        parts.push(format!("ttfb {}", format_duration(self.timing.ttfb)));
+        parts.push(format!("total {}", format_duration(self.timing.total)));
+        format!("⏱ {}", parts.join(" · "))
+    }
+
+    /// What `!http` shows: status, redirects, headers, timing, then the
+    /// body (JSON pretty-printed).
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![self.status_line()];
+        for hop in &self.redirects {
+            lines.push(format!("↪ {} → {}", hop.status, hop.to));
+        }
+        for (name, value) in &self.headers {
+            lines.push(format!("  {}: {}", name, value));
+        }
+        lines.push(self.timing_line());
+        lines.push(String::new());
+        lines.extend(self.body_lines());
+        if let Some(spill) = &self.spill {
+            lines.push(format!(
+                "✂ Showing the first {} of {}; the whole body is in {}",
+                format_bytes(self.body.len() as u64),
+                format_bytes(self.body_size),
+                spill.display()
+            ));
+        }
+        lines
+    }
+
+    fn body_lines(&self) -> Vec<String> {
+        if self.body_size == 0 {
+            return vec!["(empty body)".to_string()];
+        }
+        let Some(text) = self.body_text() else {
+            return vec![format!(
+                "📦 Binary body, {} ({})",
+                format_bytes(self.body_size),
+                self.content_type().unwrap_or_else(|| "no content type".to_string())
+            )];
+        };
+        let pretty = (!self.is_truncated())
+            .then(|| serde_json::from_str::<serde_json::Value>(&text).ok())
+            .flatten()
+            .and_then(|value| serde_json::to_string_pretty(&value).ok());
+        pretty.unwrap_or(text).lines().map(str::to_string).collect()
+    }
+
+    /// The whole exchange as text, secrets masked: what `!save` writes.
+    pub fn transcript(&self) -> String {
+        let mut out = format!("> {} {}\n", self.method, self.url);
+        for (name, value) in &self.request_headers {
+            out.push_str(&format!("> {}: {}\n", name, value));
+        }
+        for hop in &self.redirects {
+            out.push_str(&format!("< {} → {}\n", hop.status, hop.to));
+        }
+        out.push_str(&format!("< {} {} {}\n", self.version, self.status, self.reason));
+        for (name, value) in &self.headers {
+            out.push_str(&format!("< {}: {}\n", name, value));
+        }
+        out.push_str(&format!("* {}\n\n", self.timing_line().trim_start_matches("⏱ ")));
+        match self.body_text() {
+            Some(text) => out.push_str(&text),
+            None if self.body_size > 0 => out.push_str(&format!("[binary body, {} bytes]\n", self.body_size)),
+            None => {}
+        }
+        if let Some(spill) = &self.spill {
+            out.push_str(&format!("\n[truncated; whole body in {}]\n", spill.display()));
+        }
+        out
+    }
+}
+
+/// Whether a media type (`application/json`, lower case, no parameters)
+/// is text.
+pub fn is_textual(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || content_type.ends_with("json")
+        || content_type.ends_with("xml")
+        || content_type.ends_with("csv")
+        || content_type.ends_with("yaml")
+        || content_type.contains("javascript")
+        || content_type == "application/x-www-form-urlencoded"
+}
+
+/// `850 µs`, `12 ms`, `1.40 s`.
+pub fn format_duration(d: Duration) -> String {
+    match d.as_micros() {
+        us if us < 1000 => format!("{} µs", us),
+        us if us < 1_000_000 => format!("{} ms", us / 1000),
+        _ => format!("{:.2} s", d.as_secs_f64()),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Sending
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    pub body_cap: usize,
+    /// Where bodies over the cap are written, created on first use.
+    pub spill_dir: PathBuf,
+    /// What `--body @file` is relative to.
+    pub cwd: PathBuf,
+    /// Fired to give up on the request (see `cancel`).
+    pub cancel: CancellationToken,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            body_cap: BODY_CAP,
+            spill_dir: std::env::temp_dir().join(format!("positronic-http-{}", std::process::id())),
+            cwd: std::env::current_dir().unwrap_or_default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+}
+
+static NEXT_SPILL: AtomicU64 = AtomicU64::new(1);
+
+/// Send `request`. The error is the message to show: a timeout, a TLS
+/// failure or a refused connection each say so plainly.
+pub async fn send(request: &HttpRequest, options: &HttpOptions) -> Result<HttpExchange, String> {
+    let url = Url::parse(&request.url).map_err(|e| format!("❌ Bad URL '{}': {}", request.url, e))?;
+    let host = url.host_str().ok_or_else(|| format!("❌ No host in '{}'", request.url))?.to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let timed_out = || {
+        format!(
+            "❌ Timed out after {} waiting for {} (!set {} <seconds>)",
+            format_duration(options.timeout),
+            host,
+            TIMEOUT_KEY
+        )
+    };
+
+    let started = Instant::now();
+    let bare = host.trim_start_matches('[').trim_end_matches(']').to_string();
+    let literal = bare.parse::<std::net::IpAddr>().is_ok();
+    let lookup = {
+        let target = format!("{}:{}", bare, port);
+        tokio::time::timeout(options.timeout, tokio::task::spawn_blocking(move || target.to_socket_addrs()))
+    };
+    let cancel = &options.cancel;
+    let addr: SocketAddr = match or_cancelled(cancel, lookup).await? {
+        Err(_) => return Err(timed_out()),
+        Ok(Err(e)) => return Err(format!("❌ Lookup failed: {}", e)),
+        Ok(Ok(Err(e))) => return Err(format!("❌ Could not resolve {}: {}", host, e)),
+        Ok(Ok(Ok(mut addrs))) => addrs.next().ok_or_else(|| format!("❌ {} has no addresses", host))?,
+    };
+    let dns = (!literal).then(|| started.elapsed());
+
+    let probe_started = Instant::now();
+    let timeout = options.timeout;
+    let probe = tokio::task::spawn_blocking(move || std::net::TcpStream::connect_timeout(&addr, timeout));
+    let probe = or_cancelled(cancel, probe).await?;
+    match probe {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::TimedOut => return Err(timed_out()),
+        Ok(Err(e)) => return Err(format!("❌ Could not connect to {} ({}): {}", host, addr, e)),
+        Err(e) => return Err(format!("❌ Connect failed: {}", e)),
+    }
+    let connect = Some(probe_started.elapsed());
+
+    let hops = Arc::new(Mutex::new(Vec::new()));
+    let policy = {
+        let hops = hops.clone();
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error(format!("more than {} redirects", MAX_REDIRECTS));
+            }
+            let hop = Redirect { status: attempt.status().as_u16(), to: attempt.url().to_string() };
+            hops.lock().unwrap_or_else(|e| e.into_inner()).push(hop);
+            attempt.follow()
+        })
+    };
+    let mut builder = reqwest::Client::builder().redirect(policy).timeout(options.timeout);
+    if !literal {
+        builder = builder.resolve(&host, addr);
+    }
+    let client = builder.build().map_err(|e| format!("❌ {}", e))?;
+
+    let method = Method::from_bytes(request.method.as_bytes()).map_err(|_| HTTP_USAGE.to_string())?;
+    let mut call = client.request(method, url.clone());
+    for (name, value) in &request.headers {
+        call = call.header(name.as_str(), value.as_str());
+    }
+    if let Some(body) = &request.body {
+        let bytes = match body {
+            RequestBody::Text(text) => text.clone().into_bytes(),
+            RequestBody::File(path) => {
+                let path = options.cwd.join(path);
+                std::fs::read(&path).map_err(|e| format!("❌ Could not read {}: {}", path.display(), e))?
+            }
+        };
+        let looks_json = matches!(bytes.trim_ascii_start().first(), Some(b'{' | b'['));
+        if looks_json && !request.has_header("content-type") {
+            call = call.header("Content-Type", "application/json");
+        }
+        call = call.body(bytes);
+    }
+
+    let sent = Instant::now();
+    let mut response = or_cancelled(cancel, call.send()).await?.map_err(|e| send_error(&e, &url, &timed_out))?;
+    let ttfb = sent.elapsed();
+    let status = response.status();
+    let version = format!("{:?}", response.version());
+    let final_url = response.url().to_string();
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect();
+
+    let mut body = Vec::new();
+    let mut body_size = 0u64;
+    let mut spill: Option<(PathBuf, std::fs::File)> = None;
+    loop {
+        let chunk = match or_cancelled(cancel, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => chunk,
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) if e.is_timeout() => return Err(discard(spill, timed_out())),
+            Ok(Err(e)) => return Err(discard(spill, format!("❌ Reading the body failed: {}", error_chain(&e)))),
+            Err(cancelled) => return Err(discard(spill, cancelled)),
+        };
+        body_size += chunk.len() as u64;
+        let room = options.body_cap.saturating_sub(body.len());
+        if spill.is_none() && chunk.len() > room {
+            spill = open_spill(&options.spill_dir, &body);
+        }
+        if let Some((_, file)) = &mut spill {
+            if let Err(e) = file.write_all(&chunk) {
+                tracing::warn!("HTTP body spill failed: {}", e);
+            }
+        }
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+
+    let redirects = std::mem::take(&mut *hops.lock().unwrap_or_else(|e| e.into_inner()));
+    Ok(HttpExchange {
+        method: request.method.clone(),
+        url: request.url.clone(),
+        final_url,
+        redirects,
+        request_headers: masked(&request.headers),
+        status: status.as_u16(),
+        reason: status.canonical_reason().unwrap_or("").to_string(),
+        version,
+        headers: masked(&headers),
+        body,
+        body_size,
+        spill: spill.map(|(path, _)| path),
+        timing: Timing { dns, connect, ttfb, total: sent.elapsed() },
+    })
+}
+
+/// A new spill file in `dir`, holding `kept` so far.
+fn open_spill(dir: &Path, kept: &[u8]) -> Option<(PathBuf, std::fs::File)> {
+    let path = dir.join(format!("{}.body", NEXT_SPILL.fetch_add(1, Ordering::Relaxed)));
+    let opened = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::File::create(&path))
+        .and_then(|mut file| file.write_all(kept).map(|_| file));
+    match opened {
+        Ok(file) => Some((path, file)),
+        Err(e) => {
+            tracing::warn!("HTTP body spill failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Delete the spill file of a body that didn't arrive whole; `message`
+/// passes through.
+fn discard(spill: Option<(PathBuf, std::fs::File)>, message: String) -> String {
+    if let Some((path, file)) = spill {
+        drop(file);
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!("Removing {} failed: {}", path.display(), e);
+        }
+    }
+    message
+}
+
+/// A send error as a message. The TCP probe already connected, so a
+/// connect error on https is the TLS handshake.
+fn send_error(e: &reqwest::Error, url: &Url, timed_out: &dyn Fn() -> String) -> String {
+    let chain = error_chain(e);
+    let lower = chain.to_lowercase();
+    let host = url.host_str().unwrap_or_default();
+    if e.is_timeout() {
+        timed_out()
+    } else if lower.contains("certificate") || (e.is_connect() && url.scheme() == "https") {
+        format!("❌ TLS error with {}: {}", host, root_cause(e))
+    } else if e.is_connect() {
+        format!("❌ Could not connect to {}: {}", host, root_cause(e))
+    } else if e.is_redirect() {
+        format!("❌ Redirect failed: {}", chain)
+    } else {
+        format!("❌ Request failed: {}", chain)
+    }
+}
+
+/// The innermost source: for a failed connection, the part that says why.
+pub(crate) fn root_cause(e: &(dyn std::error::Error + 'static)) -> String {
+    let mut err = e;
+    while let Some(source) = err.source() {
+        err = source;
+    }
+    err.to_string()
+}
+
+/// An error and its sources, `a: b: c`, without repeats.
+pub(crate) fn error_chain(e: &(dyn std::error::Error + 'static)) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut next = Some(e);
+    while let Some(err) = next {
+        let text = err.to_string();
+        if !parts.iter().any(|p| p.contains(&text)) {
+            parts.push(text);
+        }
+        next = err.source();
+    }
+    parts.join(": ")
+}
@@ -0,0 +1,206 @@
+//! `positronic --inspect <file>`: browse a vault someone sent, or a bundle
+//! they exported, without mixing it into your own history or writing to it.
+//!
+//! A vault file is read through an immutable, read-only SQLite URI and
+//! copied into memory (`Vault::open_inspect`); a `!sync` bundle or an
+//! `!export` history file is imported into an empty in-memory vault. Either
+//! way the vault is then sealed: no session row is made, and every write
+//! method fails with `InspectMode`. The seal is set when the vault is
+//! opened and nothing clears it, so no setting can turn inspect mode off.
+//!
+//! The Runner only takes the `!` commands in `INSPECT_COMMANDS` from a
+//! sealed vault (`check_line`); shell input is refused, and the inspected
+//! vault's aliases, hooks and `!setenv` overrides are left out as in safe
+//! mode.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::PositronicError;
+use crate::sync::{self, LocalState, SyncEntry};
+use crate::vault::Vault;
+
+/// The commands inspect mode takes. Ones that would write fail with
+/// `InspectMode`; none of them runs anything.
+pub const INSPECT_COMMANDS: &[&str] = &[
+    "!help", "!history", "!chain", "!search", "!stats", "!timeline", "!top", "!diff", "!tag", "!here", "!tests",
+    "!alias", "!bookmarks", "!status", "!vault", "!chart", "!errors", "!clear", "!cls", "!ver", "!version", "!quit",
+    "!exit",
+];
+
+/// Machine and session of the rows an `!export` file is loaded as.
+const EXPORT_ORIGIN: &str = "export";
+
+/// SQLite's file header.
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Raised (wrapped in `rusqlite::Error::ToSqlConversionFailure`) by every
+/// write to a sealed vault.
+#[derive(Debug, thiserror::Error)]
+#[error("inspect mode: the vault is read-only")]
+pub struct InspectMode;
+
+pub(crate) fn inspect_error() -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(InspectMode))
+}
+
+/// Whether `err` is inspect mode refusing a write.
+pub fn is_inspect_error(err: &rusqlite::Error) -> bool {
+    matches!(err, rusqlite::Error::ToSqlConversionFailure(e) if e.is::<InspectMode>())
+}
+
+/// What was opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectKind {
+    Vault,
+    /// A `!sync` bundle.
+    Bundle,
+    /// An `!export` history file.
+    History,
+}
+
+impl InspectKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            InspectKind::Vault => "vault",
+            InspectKind::Bundle => "!sync bundle",
+            InspectKind::History => "!export history",
+        }
+    }
+}
+
+/// The file being inspected, and what opening it turned up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspection {
+    pub path: PathBuf,
+    pub kind: InspectKind,
+    /// Caveats for the banner: skipped lines, a `-wal` left unread.
+    pub notes: Vec<String>,
+}
+
+impl Inspection {
+    pub fn label(&self) -> String {
+        label(&self.path)
+    }
+
+    /// Shown at the top of an inspect session.
+    pub fn banner(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("🔍 INSPECT MODE — {} ({}), read-only", self.path.display(), self.kind.label()),
+            "   Browse with !history, !search, !stats, !timeline and !top. Shell input is off,".to_string(),
+            "   and nothing is written to this file or to your own vault.".to_string(),
+        ];
+        lines.extend(self.notes.iter().map(|note| format!("   ⚠️ {}", note)));
+        lines
+    }
+}
+
+/// `path`'s file name, for the title and status bars.
+pub fn label(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Open `path` sealed: a vault file, a `!sync` bundle or an `!export` file.
+pub fn open(path: &Path) -> Result<(Vault, Inspection), PositronicError> {
+    let bytes = fs::read(path).map_err(|e| PositronicError::Other(format!("reading {}: {}", path.display(), e)))?;
+    let mut inspection = Inspection { path: path.to_path_buf(), kind: InspectKind::Vault, notes: Vec::new() };
+    if bytes.starts_with(SQLITE_MAGIC) {
+        if unread_wal(path) {
+            inspection.notes.push(format!(
+                "{}-wal is beside it: its newest rows aren't shown (close the Positronic that wrote it first)",
+                path.display()
+            ));
+        }
+        return Ok((Vault::open_inspect(path)?, inspection));
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let (mut entries, malformed) = sync::parse_bundle(&text);
+    inspection.kind = InspectKind::Bundle;
+    if entries.is_empty() {
+        entries = export_entries(&text);
+        inspection.kind = InspectKind::History;
+    }
+    if entries.is_empty() {
+        return Err(PositronicError::Other(format!(
+            "{} is not a vault, a !sync bundle or an !export file",
+            path.display()
+        )));
+    }
+    if inspection.kind == InspectKind::Bundle && malformed > 0 {
+        inspection.notes.push(format!("{} line(s) were not bundle entries and were skipped", malformed));
+    }
+    Ok((load(entries)?, inspection))
+}
+
+/// A sealed in-memory vault holding `entries`.
+fn load(entries: Vec<SyncEntry>) -> Result<Vault, PositronicError> {
+    let vault = Vault::scratch()?;
+    let local = LocalState::load(&vault)?;
+    vault.apply_sync(&sync::plan(&local, entries))?;
+    Ok(vault.seal()?)
+}
+
+/// The history of an `!export` file: `# <timestamp>` lines, each followed
+/// by its command (which may run over several lines).
+pub fn export_entries(text: &str) -> Vec<SyncEntry> {
+    let mut runs: Vec<(i64, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        let stamp = line.strip_prefix("# ").and_then(|ts| ts.trim().parse::<i64>().ok());
+        match (stamp, runs.last_mut()) {
+            (Some(timestamp), _) => runs.push((timestamp, Vec::new())),
+            (None, Some((_, command))) => command.push(line),
+            (None, None) => return Vec::new(),
+        }
+    }
+    runs.into_iter()
+        .filter(|(_, command)| !command.is_empty())
+        .map(|(timestamp, command)| {
+            SyncEntry::history(EXPORT_ORIGIN, EXPORT_ORIGIN, &command.join("\n"), "", None, timestamp, None)
+        })
+        .collect()
+}
+
+/// A non-empty `-wal` file next to `path`, which an immutable open skips.
+fn unread_wal(path: &Path) -> bool {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    fs::metadata(wal).is_ok_and(|m| m.len() > 0)
+}
+
+/// `file:` URI opening `path` read-only and immutable: SQLite takes no
+/// locks and makes no `-wal` or `-shm` files.
+pub fn immutable_uri(path: &Path) -> String {
+    let text = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file:");
+    // `C:/x` is `file:/C:/x`.
+    if text.as_bytes().get(1) == Some(&b':') {
+        uri.push('/');
+    }
+    for ch in text.chars() {
+        match ch {
+            '?' => uri.push_str("%3f"),
+            '#' => uri.push_str("%23"),
+            '%' => uri.push_str("%25"),
+            ch => uri.push(ch),
+        }
+    }
+    uri.push_str("?mode=ro&immutable=1");
+    uri
+}
+
+/// Whether inspect mode takes `line`; `Err` says why not.
+pub fn check_line(line: &str) -> Result<(), String> {
+    let line = line.trim();
+    if !line.starts_with('!') {
+        return Err("🔍 Inspect mode: shell input is off. Browse with !history, !search or !stats".to_string());
+    }
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    // Copies the inspected vault into your data directory.
+    let migrates = command == "!vault" && words.next() == Some("migrate-data");
+    if !INSPECT_COMMANDS.contains(&command) || migrates {
+        return Err(format!("🔍 Inspect mode: {} isn't available while inspecting. Commands: {}", command, INSPECT_COMMANDS.join(" ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,477 @@
+//! `!integrate`: install the prompt hooks into a shell's own profile.
+//!
+//! Positronic injects its hooks into the bash it starts itself, but any
+//! other shell (zsh, fish, pwsh, or bash started some other way) only
+//! reports its prompt and working directory if its profile emits OSC 7
+//! and OSC 133 `A`/`B`/`C`/`D`. `!integrate <shell>` shows the lines it
+//! would append and appends them once confirmed with `--yes`;
+//! `!integrate remove <shell>` takes exactly those lines out again.
+//!
+//! The snippet sits between `BLOCK_BEGIN` and `BLOCK_END` comment lines,
+//! which is how an installed snippet is found, left alone on a second
+//! install and removed. Before a profile changes its old contents go to
+//! `<profile>.positronic.bak`, and the new contents are written to a
+//! temporary file beside it and renamed over it, so a failed write never
+//! leaves half a profile.
+//!
+//! Path discovery and the edits are pure functions of a `ProfileEnv` and
+//! the file text; only `command_lines` touches the filesystem.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+pub const INTEGRATE_USAGE: &str =
+    "Usage: !integrate [bash|zsh|fish|pwsh] [--dry-run|--yes] | !integrate remove <shell> [--dry-run|--yes] | !integrate status";
+
+/// First line of an installed snippet.
+pub const BLOCK_BEGIN: &str = "# >>> positronic shell integration >>>";
+/// Last line of an installed snippet.
+pub const BLOCK_END: &str = "# <<< positronic shell integration <<<";
+
+/// Appended to a profile's name for the copy made before changing it.
+pub const BACKUP_SUFFIX: &str = ".positronic.bak";
+
+// ════════════════════════════════════════════════════════════════════
+// Shells and profiles
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrateShell {
+    Bash,
+    Zsh,
+    Fish,
+    Pwsh,
+}
+
+impl IntegrateShell {
+    pub const ALL: [IntegrateShell; 4] =
+        [IntegrateShell::Bash, IntegrateShell::Zsh, IntegrateShell::Fish, IntegrateShell::Pwsh];
+
+    pub fn parse(name: &str) -> Option<IntegrateShell> {
+        match name.to_ascii_lowercase().as_str() {
+            "bash" => Some(IntegrateShell::Bash),
+            "zsh" => Some(IntegrateShell::Zsh),
+            "fish" => Some(IntegrateShell::Fish),
+            "pwsh" | "powershell" => Some(IntegrateShell::Pwsh),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntegrateShell::Bash => "bash",
+            IntegrateShell::Zsh => "zsh",
+            IntegrateShell::Fish => "fish",
+            IntegrateShell::Pwsh => "pwsh",
+        }
+    }
+
+    /// The shell a path such as `$SHELL` names, if it is one of ours.
+    pub fn from_path(path: &str) -> Option<IntegrateShell> {
+        let file = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        IntegrateShell::parse(file.trim_end_matches(".exe"))
+    }
+
+    /// The shell the PTY runs: PowerShell on Windows, `$SHELL` elsewhere.
+    pub fn detect() -> Option<IntegrateShell> {
+        if cfg!(windows) {
+            return Some(IntegrateShell::Pwsh);
+        }
+        std::env::var("SHELL").ok().and_then(|shell| IntegrateShell::from_path(&shell))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileOs {
+    Linux,
+    MacOs,
+    Windows,
+}
+
+/// What profile discovery depends on, gathered once so the lookup itself
+/// is pure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileEnv {
+    pub os: ProfileOs,
+    pub home: PathBuf,
+    /// `$ZDOTDIR`, where zsh looks for `.zshrc` instead of the home.
+    pub zdotdir: Option<PathBuf>,
+    /// `$XDG_CONFIG_HOME`, for fish and PowerShell off Windows.
+    pub xdg_config_home: Option<PathBuf>,
+    /// Windows Documents folders, OneDrive's first when it redirects them.
+    pub documents: Vec<PathBuf>,
+}
+
+impl ProfileEnv {
+    /// The environment this process runs in.
+    pub fn current() -> ProfileEnv {
+        let var = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let os = if cfg!(windows) {
+            ProfileOs::Windows
+        } else if cfg!(target_os = "macos") {
+            ProfileOs::MacOs
+        } else {
+            ProfileOs::Linux
+        };
+        let home = var("HOME").or_else(|| var("USERPROFILE")).unwrap_or_default();
+        let documents = if os == ProfileOs::Windows {
+            var("OneDrive").map(|d| d.join("Documents")).into_iter().chain([home.join("Documents")]).collect()
+        } else {
+            Vec::new()
+        };
+        ProfileEnv { os, home, zdotdir: var("ZDOTDIR"), xdg_config_home: var("XDG_CONFIG_HOME"), documents }
+    }
+
+    fn config_dir(&self) -> PathBuf {
+        self.xdg_config_home.clone().unwrap_or_else(|| self.home.join(".config"))
+    }
+}
+
+/// The profiles `shell` reads, the preferred one first.
+///
+/// On macOS Terminal starts bash as a login shell, which reads
+/// `.bash_profile` rather than `.bashrc`. On Windows PowerShell 7 and
+/// Windows PowerShell 5.1 keep separate profiles under each Documents
+/// folder (`$PROFILE` in either).
+pub fn profile_paths(shell: IntegrateShell, env: &ProfileEnv) -> Vec<PathBuf> {
+    match shell {
+        IntegrateShell::Bash if env.os == ProfileOs::MacOs => {
+            vec![env.home.join(".bash_profile"), env.home.join(".bashrc")]
+        }
+        IntegrateShell::Bash => vec![env.home.join(".bashrc")],
+        IntegrateShell::Zsh => vec![env.zdotdir.as_ref().unwrap_or(&env.home).join(".zshrc")],
+        IntegrateShell::Fish => vec![env.config_dir().join("fish").join("config.fish")],
+        IntegrateShell::Pwsh if env.os == ProfileOs::Windows => env
+            .documents
+            .iter()
+            .flat_map(|docs| {
+                ["PowerShell", "WindowsPowerShell"]
+                    .map(|dir| docs.join(dir).join("Microsoft.PowerShell_profile.ps1"))
+            })
+            .collect(),
+        IntegrateShell::Pwsh => vec![env.config_dir().join("powershell").join("Microsoft.PowerShell_profile.ps1")],
+    }
+}
+
+/// The profile to install into: the first candidate that exists, or the
+/// preferred one, to be created.
+pub fn choose_profile(candidates: &[PathBuf], exists: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    candidates.iter().find(|p| exists(p)).or_else(|| candidates.first()).cloned()
+}
+
+/// Where a profile's backup goes.
+pub fn backup_path(profile: &Path) -> PathBuf {
+    let mut name = profile.as_os_str().to_owned();
+    name.push(BACKUP_SUFFIX);
+    PathBuf::from(name)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Snippets
+// ════════════════════════════════════════════════════════════════════
+
+const BASH_HOOKS: &str = r#"if [[ $- == *i* && -z ${__positronic_hooked-} ]]; then
+  __positronic_hooked=1
+  __positronic_prompt() {
+    printf '\e]133;D;%s\a' "$__positronic_status"
+    printf '\e]7;file://localhost%s\a' "$PWD"
+    printf '\e]133;A\a'
+    __positronic_armed=1
+  }
+  PROMPT_COMMAND="__positronic_status=\$?;${PROMPT_COMMAND:+$PROMPT_COMMAND;}__positronic_prompt"
+  PS1="$PS1"'\[\e]133;B\a\]'
+  trap '[[ -n ${__positronic_armed-} ]] && { __positronic_armed=; printf "\e]133;C\a"; }' DEBUG
+fi"#;
+
+const ZSH_HOOKS: &str = r#"if [[ -o interactive && -z ${__positronic_hooked-} ]]; then
+  __positronic_hooked=1
+  __positronic_precmd() {
+    local ec=$?
+    printf '\e]133;D;%s\a' "$ec"
+    printf '\e]7;file://localhost%s\a' "$PWD"
+    printf '\e]133;A\a'
+  }
+  __positronic_preexec() { printf '\e]133;C\a' }
+  precmd_functions=(__positronic_precmd $precmd_functions)
+  preexec_functions+=(__positronic_preexec)
+  PS1="$PS1"$'%{\e]133;B\a%}'
+fi"#;
+
+const FISH_HOOKS: &str = r#"if status is-interactive; and not set -q __positronic_hooked
+    set -g __positronic_hooked 1
+    function __positronic_prompt --on-event fish_prompt
+        set -l ec $status
+        printf '\e]133;D;%s\a' $ec
+        printf '\e]7;file://localhost%s\a' $PWD
+        printf '\e]133;A\a'
+    end
+    function __positronic_preexec --on-event fish_preexec
+        printf '\e]133;C\a'
+    end
+    if functions -q fish_prompt
+        functions -c fish_prompt __positronic_user_prompt
+        function fish_prompt
+            __positronic_user_prompt
+            printf '\e]133;B\a'
+        end
+    end
+end"#;
+
+const PWSH_HOOKS: &str = r#"if (-not $global:__PositronicHooked) {
+  $global:__PositronicHooked = $true
+  $global:__PositronicUserPrompt = $function:prompt
+  function global:prompt {
+    $ok = $?
+    $ec = if ($ok) { 0 } elseif ($global:LASTEXITCODE) { $global:LASTEXITCODE } else { 1 }
+    [Console]::Write("`e]133;D;$ec`a")
+    $uri = $PWD.ProviderPath -replace '\\', '/'
+    [Console]::Write("`e]7;file://localhost/$($uri.TrimStart('/'))`a")
+    [Console]::Write("`e]133;A`a")
+    $text = & $global:__PositronicUserPrompt
+    "$text`e]133;B`a"
+  }
+  if (Get-Module PSReadLine) {
+    Set-PSReadLineKeyHandler -Chord Enter -ScriptBlock {
+      [Microsoft.PowerShell.PSConsoleReadLine]::AcceptLine()
+      [Console]::Write("`e]133;C`a")
+    }
+  }
+}"#;
+
+/// The guarded block for `shell`, ending in a newline.
+pub fn snippet(shell: IntegrateShell) -> String {
+    let hooks = match shell {
+        IntegrateShell::Bash => BASH_HOOKS,
+        IntegrateShell::Zsh => ZSH_HOOKS,
+        IntegrateShell::Fish => FISH_HOOKS,
+        IntegrateShell::Pwsh => PWSH_HOOKS,
+    };
+    format!(
+        "{}\n# Added by `!integrate {}`; remove with `!integrate remove {}`.\n{}\n{}\n",
+        BLOCK_BEGIN,
+        shell.name(),
+        shell.name(),
+        hooks,
+        BLOCK_END
+    )
+}
+
+/// Byte range of the installed block in `text`: from the start of the
+/// `BLOCK_BEGIN` line to the end of the `BLOCK_END` line and its newline.
+fn block_range(text: &str) -> Option<std::ops::Range<usize>> {
+    let mut offset = 0;
+    let mut start = None;
+    for line in text.split_inclusive('\n') {
+        let end = offset + line.len();
+        match line.trim_end() {
+            BLOCK_BEGIN if start.is_none() => start = Some(offset),
+            BLOCK_END if start.is_some() => return start.map(|start| start..end),
+            _ => {}
+        }
+        offset = end;
+    }
+    None
+}
+
+pub fn has_block(text: &str) -> bool {
+    block_range(text).is_some()
+}
+
+/// `text` with the block for `shell` appended after a blank line, or
+/// `None` if a block is already there.
+pub fn with_block(text: &str, shell: IntegrateShell) -> Option<String> {
+    if has_block(text) {
+        return None;
+    }
+    let mut out = text.to_string();
+    if !out.is_empty() {
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out.push_str(&snippet(shell));
+    Some(out)
+}
+
+/// `text` without the installed block (and the blank line `with_block`
+/// put before it), or `None` if there is no block.
+pub fn without_block(text: &str) -> Option<String> {
+    let range = block_range(text)?;
+    let mut start = range.start;
+    if text[..start].ends_with("\n\n") {
+        start -= 1;
+    } else if start == 1 && text.starts_with('\n') {
+        start = 0;
+    }
+    Some(format!("{}{}", &text[..start], &text[range.end..]))
+}
+
+/// A unified-style diff of `old` to `new` as one hunk: the lines both
+/// share at the start and end are left out.
+pub fn diff_lines(path: &Path, old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+
+    let mut lines = vec![
+        format!("--- {}", path.display()),
+        format!("+++ {}", path.display()),
+        format!("@@ -{},{} +{},{} @@", prefix + 1, removed.len(), prefix + 1, added.len()),
+    ];
+    lines.extend(removed.iter().map(|l| format!("-{}", l)));
+    lines.extend(added.iter().map(|l| format!("+{}", l)));
+    lines
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Writing
+// ════════════════════════════════════════════════════════════════════
+
+/// Replace `profile` with `text`: back up the old file (if any), write a
+/// temporary file beside it and rename it into place. A symlinked profile
+/// is written through the link, keeping the link.
+pub fn write_profile(profile: &Path, text: &str) -> Result<()> {
+    let target = match std::fs::canonicalize(profile) {
+        Ok(real) => real,
+        Err(_) => profile.to_path_buf(),
+    };
+    let old = std::fs::metadata(&target).ok();
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    if old.is_some() {
+        std::fs::copy(&target, backup_path(profile))
+            .with_context(|| format!("Failed to back up {}", target.display()))?;
+    }
+
+    let mut temp = target.clone().into_os_string();
+    temp.push(".positronic.tmp");
+    let temp = PathBuf::from(temp);
+    let written = std::fs::write(&temp, text)
+        .and_then(|_| match &old {
+            Some(meta) => std::fs::set_permissions(&temp, meta.permissions()),
+            None => Ok(()),
+        })
+        .and_then(|_| std::fs::rename(&temp, &target));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e).with_context(|| format!("Failed to write {}", target.display()));
+    }
+    Ok(())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Command
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrateCommand {
+    /// Append the hooks to the shell's profile (`None`: the PTY's shell).
+    /// Without `yes` only the diff is shown.
+    Install { shell: Option<IntegrateShell>, dry_run: bool, yes: bool },
+    Remove { shell: IntegrateShell, dry_run: bool, yes: bool },
+    Status,
+}
+
+impl IntegrateCommand {
+    /// Parse the words after `!integrate`; the error is for the user.
+    pub fn parse(args: &str) -> Result<IntegrateCommand, String> {
+        let mut dry_run = false;
+        let mut yes = false;
+        let mut words = Vec::new();
+        for word in args.split_whitespace() {
+            match word {
+                "--dry-run" | "-n" => dry_run = true,
+                "--yes" | "-y" => yes = true,
+                _ => words.push(word),
+            }
+        }
+        let shell = |name: &str| IntegrateShell::parse(name).ok_or_else(|| INTEGRATE_USAGE.to_string());
+        match words.as_slice() {
+            _ if dry_run && yes => Err(INTEGRATE_USAGE.to_string()),
+            ["status"] if !dry_run && !yes => Ok(IntegrateCommand::Status),
+            ["remove", name] => Ok(IntegrateCommand::Remove { shell: shell(name)?, dry_run, yes }),
+            [] => Ok(IntegrateCommand::Install { shell: None, dry_run, yes }),
+            [name] => Ok(IntegrateCommand::Install { shell: Some(shell(name)?), dry_run, yes }),
+            _ => Err(INTEGRATE_USAGE.to_string()),
+        }
+    }
+}
+
+/// Run `command` against the profiles `env` points at. `markers_seen`
+/// says whether the shell has emitted OSC 133 this session.
+pub fn command_lines(command: IntegrateCommand, markers_seen: bool, env: &ProfileEnv) -> Vec<String> {
+    match command {
+        IntegrateCommand::Status => status_lines(markers_seen, env),
+        IntegrateCommand::Install { shell, dry_run, yes } => {
+            let Some(shell) = shell.or_else(IntegrateShell::detect) else {
+                return vec!["🐚 Can't tell which shell this is; name one.".to_string(), INTEGRATE_USAGE.to_string()];
+            };
+            let Some(profile) = choose_profile(&profile_paths(shell, env), |p| p.exists()) else {
+                return vec![format!("🐚 No profile location known for {}", shell.name())];
+            };
+            let old = std::fs::read_to_string(&profile).unwrap_or_default();
+            let Some(new) = with_block(&old, shell) else {
+                return vec![format!("🐚 {} integration is already in {}", shell.name(), profile.display())];
+            };
+            change_lines(&profile, &old, &new, dry_run, yes, &format!("!integrate {}", shell.name()))
+        }
+        IntegrateCommand::Remove { shell, dry_run, yes } => {
+            let installed = profile_paths(shell, env)
+                .into_iter()
+                .filter_map(|p| std::fs::read_to_string(&p).ok().map(|text| (p, text)))
+                .find(|(_, text)| has_block(text));
+            let Some((profile, old)) = installed else {
+                return vec![format!("🐚 No {} integration block found", shell.name())];
+            };
+            let new = without_block(&old).unwrap_or_else(|| old.clone());
+            change_lines(&profile, &old, &new, dry_run, yes, &format!("!integrate remove {}", shell.name()))
+        }
+    }
+}
+
+/// The diff, then either the write or how to confirm it.
+fn change_lines(profile: &Path, old: &str, new: &str, dry_run: bool, yes: bool, again: &str) -> Vec<String> {
+    let mut lines = diff_lines(profile, old, new);
+    if dry_run {
+        lines.push("🐚 Dry run: nothing written".to_string());
+    } else if !yes {
+        lines.push(format!("🐚 Nothing written yet. Run `{} --yes` to apply this change.", again));
+    } else {
+        let existed = profile.exists();
+        match write_profile(profile, new) {
+            Ok(()) if existed => lines.push(format!(
+                "✅ Updated {} (backup: {}). Open a new shell to pick it up.",
+                profile.display(),
+                backup_path(profile).display()
+            )),
+            Ok(()) => lines.push(format!("✅ Created {}. Open a new shell to pick it up.", profile.display())),
+            Err(e) => lines.push(format!("❌ {:#}", e)),
+        }
+    }
+    lines
+}
+
+fn status_lines(markers_seen: bool, env: &ProfileEnv) -> Vec<String> {
+    let mut lines = vec![if markers_seen {
+        "🐚 Prompt markers (OSC 133) seen this session".to_string()
+    } else {
+        "🐚 No prompt markers (OSC 133) seen this session".to_string()
+    }];
+    for shell in IntegrateShell::ALL {
+        for profile in profile_paths(shell, env) {
+            let state = match std::fs::read_to_string(&profile) {
+                Ok(text) if has_block(&text) => "installed",
+                Ok(_) => "not installed",
+                Err(_) => "no profile",
+            };
+            lines.push(format!("  {:<5} {:<14} {}", shell.name(), state, profile.display()));
+        }
+    }
+    lines
+}
@@ -0,0 +1,543 @@
+//! Local IPC: editors and scripts talking to a running Positronic.
+//!
+//! The window listens on a Unix domain socket under the data dir
+//! (`ipc/positronic-<pid>.sock`, beside the vault) or, on Windows, on a
+//! named pipe. `IPC_ENV` carries the address and `IPC_TOKEN_ENV` the
+//! token file into the shell, so anything started from it can find its
+//! way back; `positronic --ipc <request>` is the client.
+//!
+//! The protocol is line-delimited JSON. A request is one object holding
+//! the session token and an `op`:
+//!
+//! ```text
+//! {"token":"…","op":"run","command":"cargo test"}
+//! {"token":"…","op":"cwd"}
+//! {"token":"…","op":"history-search","query":"docker"}
+//! {"token":"…","op":"notify","text":"deploy finished"}
+//! {"token":"…","op":"ingest","content":"{\"ok\":true}"}
+//! ```
+//!
+//! and is answered by any number of `{"type":"line","text":…}` and then
+//! one `{"type":"done"}` or `{"type":"error","message":…}`. A connection
+//! may carry many requests.
+//!
+//! The token is random per session and sits in a file only the user can
+//! read (on Windows, in the user's data dir). Requests past the
+//! `RateLimiter` budget are refused, and one over `MAX_REQUEST_BYTES`
+//! ends its connection. `run` is off unless `ALLOW_RUN_KEY` is set, and
+//! even then destructive lines are refused, as nobody is there to confirm
+//! them. It goes through the Runner like a typed line: builtins reply
+//! with their output, while a shell line is typed into the terminal and
+//! its output appears there. `notify` and `ingest` are for the window,
+//! so they are handed on as `IpcEvent`s.
+
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::danger::DangerAnalyzer;
+use crate::headless;
+use crate::runner::Runner;
+
+/// The socket or pipe of the Positronic a shell runs in.
+pub const IPC_ENV: &str = "POSITRONIC_IPC";
+
+/// The file holding that session's token.
+pub const IPC_TOKEN_ENV: &str = "POSITRONIC_IPC_TOKEN";
+
+/// Vault config key that lets `run` execute commands; off unless set.
+pub const ALLOW_RUN_KEY: &str = "ipc.allow_run";
+
+/// Sockets and token files, beside the vault.
+pub const IPC_DIR: &str = "ipc";
+
+/// Longest request line, newline included.
+pub const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// Requests accepted at once after a quiet spell…
+pub const RATE_BURST: u32 = 20;
+
+/// …and per second after that, across all connections.
+pub const RATE_PER_SEC: f64 = 5.0;
+
+pub const IPC_USAGE: &str = "Usage: positronic --ipc <run COMMAND | cwd | history-search QUERY | notify TEXT | ingest [TEXT]>";
+
+/// One request, without its token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+pub enum Request {
+    /// Execute a line through the Runner.
+    Run { command: String },
+    /// The shell's working directory.
+    Cwd,
+    /// Commands in history containing `query`, newest first.
+    HistorySearch { query: String },
+    /// Announce `text` and raise a desktop notification.
+    Notify { text: String },
+    /// Show `content` in the Holodeck, as detection sees it.
+    Ingest { content: String },
+}
+
+impl Request {
+    /// The client's request from its command line: `run cargo test`,
+    /// `cwd`, `history-search docker`, `notify done`, `ingest {…}`.
+    /// `ingest` without text gives empty content for the caller to fill,
+    /// e.g. from stdin.
+    pub fn parse(line: &str) -> Result<Request, String> {
+        let line = line.trim();
+        let (op, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim().to_string();
+        match (op, rest.is_empty()) {
+            ("run", false) => Ok(Request::Run { command: rest }),
+            ("cwd", true) => Ok(Request::Cwd),
+            ("history-search", false) => Ok(Request::HistorySearch { query: rest }),
+            ("notify", false) => Ok(Request::Notify { text: rest }),
+            ("ingest", _) => Ok(Request::Ingest { content: rest }),
+            _ => Err(IPC_USAGE.to_string()),
+        }
+    }
+}
+
+/// A request on the wire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope {
+    token: String,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// One line of a reply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    Line { text: String },
+    /// The request succeeded; nothing more follows for it.
+    Done,
+    /// The request failed; nothing more follows for it.
+    Error { message: String },
+}
+
+/// What the window has to do for a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcEvent {
+    Notify(String),
+    Ingest(String),
+}
+
+/// A token bucket: `burst` requests at once, refilled at `per_sec`.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    burst: f64,
+    per_sec: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(burst: u32, per_sec: f64, now: Instant) -> Self {
+        Self { burst: f64::from(burst), per_sec, tokens: f64::from(burst), last: now }
+    }
+
+    /// Whether a request arriving at `now` may go ahead; takes a token if so.
+    pub fn allow(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.burst);
+        self.last = now.max(self.last);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Where a session listens, and where its token is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// The socket path, or the pipe name on Windows.
+    pub address: PathBuf,
+    pub token_file: PathBuf,
+}
+
+impl Endpoint {
+    /// The endpoint of session `id` (the process id) with its files under
+    /// `data_dir`, made absolute so it holds wherever the shell `cd`s.
+    pub fn for_session(data_dir: &Path, id: u32) -> Endpoint {
+        let dir = std::path::absolute(data_dir).unwrap_or_else(|_| data_dir.to_path_buf()).join(IPC_DIR);
+        let name = format!("positronic-{}", id);
+        let address =
+            if cfg!(windows) { PathBuf::from(format!(r"\\.\pipe\{}", name)) } else { dir.join(format!("{}.sock", name)) };
+        Endpoint { address, token_file: dir.join(format!("{}.token", name)) }
+    }
+
+    /// The endpoint of the Positronic this process runs under, if any.
+    pub fn from_env() -> Option<Endpoint> {
+        Some(Endpoint {
+            address: std::env::var_os(IPC_ENV)?.into(),
+            token_file: std::env::var_os(IPC_TOKEN_ENV)?.into(),
+        })
+    }
+
+    /// The variables that lead a shell back here, for its environment
+    /// (see `PtyManager::with_shell`).
+    pub fn env(&self) -> Vec<(OsString, OsString)> {
+        vec![
+            (IPC_ENV.into(), self.address.clone().into_os_string()),
+            (IPC_TOKEN_ENV.into(), self.token_file.clone().into_os_string()),
+        ]
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Server
+// ════════════════════════════════════════════════════════════════════
+
+#[cfg(unix)]
+type Listener = tokio::net::UnixListener;
+
+#[cfg(windows)]
+type Listener = tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// An endpoint that is bound and has its token, but answers nothing until
+/// `serve`. Connections made before then wait. Dropping it removes the
+/// socket and token files.
+#[derive(Debug)]
+pub struct IpcListener {
+    files: EndpointFiles,
+    token: String,
+    listener: Listener,
+}
+
+/// A listening IPC endpoint. Dropping it stops listening and removes the
+/// socket and token files.
+#[derive(Debug)]
+pub struct IpcServer {
+    files: EndpointFiles,
+    events: StdMutex<mpsc::UnboundedReceiver<IpcEvent>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+/// Removes an endpoint's files when dropped.
+#[derive(Debug)]
+struct EndpointFiles(Endpoint);
+
+impl Drop for EndpointFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0.token_file);
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.0.address);
+    }
+}
+
+struct Shared {
+    runner: Arc<Runner>,
+    token: String,
+    limiter: StdMutex<RateLimiter>,
+    events: mpsc::UnboundedSender<IpcEvent>,
+    wake: Box<dyn Fn() + Send + Sync>,
+}
+
+impl IpcListener {
+    /// Write a fresh token and bind `endpoint`. Must be called inside a
+    /// Tokio runtime.
+    pub fn bind(endpoint: Endpoint) -> Result<IpcListener> {
+        if let Some(dir) = endpoint.token_file.parent() {
+            create_private_dir(dir).with_context(|| format!("could not create {}", dir.display()))?;
+        }
+        let token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        write_private(&endpoint.token_file, &token)
+            .with_context(|| format!("could not write {}", endpoint.token_file.display()))?;
+        let files = EndpointFiles(endpoint);
+        let listener =
+            bind(&files.0.address).with_context(|| format!("could not listen on {}", files.0.address.display()))?;
+        Ok(IpcListener { files, token, listener })
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.files.0
+    }
+
+    /// Start answering requests with `runner`. `wake` runs after every
+    /// event queued for `drain_events`.
+    pub fn serve(self, runner: Arc<Runner>, wake: impl Fn() + Send + Sync + 'static) -> IpcServer {
+        let IpcListener { files, token, listener } = self;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let shared = Arc::new(Shared {
+            runner,
+            token,
+            limiter: StdMutex::new(RateLimiter::new(RATE_BURST, RATE_PER_SEC, Instant::now())),
+            events: tx,
+            wake: Box::new(wake),
+        });
+        let task = accept(listener, &files.0.address, shared);
+        IpcServer { files, events: StdMutex::new(rx), task }
+    }
+}
+
+impl IpcServer {
+    /// `IpcListener::bind` and `serve` in one go.
+    pub fn start(endpoint: Endpoint, runner: Arc<Runner>, wake: impl Fn() + Send + Sync + 'static) -> Result<IpcServer> {
+        Ok(IpcListener::bind(endpoint)?.serve(runner, wake))
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.files.0
+    }
+
+    /// `notify` and `ingest` requests received since the last drain.
+    pub fn drain_events(&self) -> Vec<IpcEvent> {
+        let mut events = Vec::new();
+        if let Ok(mut rx) = self.events.lock() {
+            while let Ok(event) = rx.try_recv() {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        // The files go after, with `files`.
+        self.task.abort();
+    }
+}
+
+#[cfg(unix)]
+fn bind(address: &Path) -> io::Result<Listener> {
+    // A socket left by a crashed session with our pid.
+    let _ = std::fs::remove_file(address);
+    tokio::net::UnixListener::bind(address)
+}
+
+#[cfg(windows)]
+fn bind(address: &Path) -> io::Result<Listener> {
+    tokio::net::windows::named_pipe::ServerOptions::new().first_pipe_instance(true).create(address)
+}
+
+#[cfg(unix)]
+fn accept(listener: Listener, _address: &Path, shared: Arc<Shared>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, shared.clone()));
+                }
+                Err(e) => {
+                    eprintln!("[IPC] Accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(windows)]
+fn accept(mut server: Listener, address: &Path, shared: Arc<Shared>) -> tokio::task::JoinHandle<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let address = address.to_path_buf();
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                eprintln!("[IPC] Accept failed: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            // The next instance has to exist before this one is handed
+            // off, or a client could find no pipe at all.
+            let next = match ServerOptions::new().create(&address) {
+                Ok(next) => next,
+                Err(e) => {
+                    eprintln!("[IPC] Stopped listening: {}", e);
+                    return;
+                }
+            };
+            tokio::spawn(serve(std::mem::replace(&mut server, next), shared.clone()));
+        }
+    })
+}
+
+/// Answer requests on one connection until it closes or sends too much.
+async fn serve<S: AsyncRead + AsyncWrite + Send + 'static>(stream: S, shared: Arc<Shared>) {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let limit = MAX_REQUEST_BYTES as u64 + 1;
+        match (&mut reader).take(limit).read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+        if line.len() > MAX_REQUEST_BYTES && line.last() != Some(&b'\n') {
+            let message = format!("request over {} bytes", MAX_REQUEST_BYTES);
+            let _ = send(&mut write, &[Response::Error { message }]).await;
+            return;
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        let replies = respond(&shared, &line).await;
+        if send(&mut write, &replies).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn send(write: &mut (impl AsyncWrite + Unpin), replies: &[Response]) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    for reply in replies {
+        serde_json::to_writer(&mut bytes, reply)?;
+        bytes.push(b'\n');
+    }
+    write.write_all(&bytes).await?;
+    write.flush().await
+}
+
+async fn respond(shared: &Shared, line: &[u8]) -> Vec<Response> {
+    let allowed = shared.limiter.lock().map(|mut limiter| limiter.allow(Instant::now())).unwrap_or(false);
+    if !allowed {
+        return vec![Response::Error { message: "too many requests; try again shortly".to_string() }];
+    }
+    let envelope: Envelope = match serde_json::from_slice(line) {
+        Ok(envelope) => envelope,
+        Err(e) => return vec![Response::Error { message: format!("malformed request: {}", e) }],
+    };
+    if !same_token(&envelope.token, &shared.token) {
+        return vec![Response::Error { message: "bad token".to_string() }];
+    }
+    match handle(shared, envelope.request).await {
+        Ok(lines) => lines.into_iter().map(|text| Response::Line { text }).chain([Response::Done]).collect(),
+        Err(message) => vec![Response::Error { message }],
+    }
+}
+
+async fn handle(shared: &Shared, request: Request) -> Result<Vec<String>, String> {
+    match request {
+        Request::Run { command } => run(&shared.runner, &command).await,
+        Request::Cwd => shared
+            .runner
+            .cwd()
+            .map(|cwd| vec![cwd])
+            .ok_or_else(|| "the shell has not reported its directory yet".to_string()),
+        Request::HistorySearch { query } => shared
+            .runner
+            .vault()
+            .search_history(&query)
+            .map(|records| records.into_iter().map(|r| r.command).collect())
+            .map_err(|e| e.to_string()),
+        Request::Notify { text } => shared.queue(IpcEvent::Notify(text)),
+        Request::Ingest { content } => shared.queue(IpcEvent::Ingest(content)),
+    }
+}
+
+impl Shared {
+    fn queue(&self, event: IpcEvent) -> Result<Vec<String>, String> {
+        self.events.send(event).map_err(|_| "the window is not listening".to_string())?;
+        (self.wake)();
+        Ok(Vec::new())
+    }
+}
+
+async fn run(runner: &Runner, command: &str) -> Result<Vec<String>, String> {
+    let allowed = runner.vault().get_config(ALLOW_RUN_KEY).ok().flatten();
+    if !matches!(allowed.map(|v| v.trim().to_lowercase()).as_deref(), Some("true" | "on" | "1" | "yes")) {
+        return Err(format!("run is off; `!set {} on` allows it", ALLOW_RUN_KEY));
+    }
+    let danger = DangerAnalyzer::analyze(command);
+    if danger.is_destructive() {
+        return Err(format!("refused: {}", danger.reason.unwrap_or("matches a destructive pattern")));
+    }
+    let mut lines: Vec<String> = danger.reason.map(|reason| format!("⚠️ {}: {}", danger.level, reason)).into_iter().collect();
+    let result = runner.execute(command).await.map_err(|e| e.to_string())?;
+    lines.extend(headless::result_lines(result));
+    Ok(lines)
+}
+
+/// Compares in a time that doesn't depend on where the tokens differ.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700).create(dir)?;
+        // `mode` only applies to a directory it creates.
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+    }
+    #[cfg(not(unix))]
+    builder.create(dir)
+}
+
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    // Replaced rather than truncated, so the mode applies.
+    let _ = std::fs::remove_file(path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Client
+// ════════════════════════════════════════════════════════════════════
+
+/// Send `request` to the session at `endpoint`, calling `on_line` with
+/// each line of the reply. A failed request is an error carrying the
+/// server's message.
+pub async fn request(endpoint: &Endpoint, request: Request, mut on_line: impl FnMut(&str)) -> Result<()> {
+    let token = std::fs::read_to_string(&endpoint.token_file)
+        .with_context(|| format!("could not read the token in {}", endpoint.token_file.display()))?;
+    let stream = connect(&endpoint.address)
+        .await
+        .with_context(|| format!("could not connect to {}", endpoint.address.display()))?;
+    let (read, mut write) = tokio::io::split(stream);
+
+    let mut body = serde_json::to_vec(&Envelope { token: token.trim().to_string(), request })?;
+    body.push(b'\n');
+    write.write_all(&body).await?;
+    write.flush().await?;
+
+    let mut lines = BufReader::new(read).lines();
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str(&line).context("malformed reply")? {
+            Response::Line { text } => on_line(&text),
+            Response::Done => return Ok(()),
+            Response::Error { message } => bail!(message),
+        }
+    }
+    bail!("the connection closed before the reply ended")
+}
+
+#[cfg(unix)]
+async fn connect(address: &Path) -> io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(address).await
+}
+
+#[cfg(windows)]
+async fn connect(address: &Path) -> io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+    const ERROR_PIPE_BUSY: i32 = 231;
+
+    let mut attempts = 0;
+    loop {
+        match ClientOptions::new().open(address) {
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY) && attempts < 20 => attempts += 1,
+            result => return result,
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
@@ -1,20 +1,73 @@
 pub mod airlock;
-pub mod builtins;
+pub mod alias;
+pub mod asciicast;
+pub mod blocks;
+pub mod calc;
+pub mod cancel;
+pub mod capture;
+pub mod chain;
+pub mod cnf;
+pub mod commands;
+pub mod completion;
+pub mod danger;
+pub mod data_paths;
+pub mod diagnostics;
+pub mod diff;
 pub mod engine;
+pub mod error;
+pub mod fetch;
+pub mod file_index;
+pub mod fix;
+pub mod follow;
+pub mod headless;
+pub mod heatmap;
+pub mod here;
+pub mod history_filter;
+pub mod hooks;
+pub mod http;
+pub mod inspect;
+pub mod integrate;
+pub mod ipc;
+pub mod maintenance;
+pub mod native;
+pub mod pick;
+pub mod progress;
 pub mod pty_manager;
+pub mod queue;
+pub mod redo;
+pub mod rename;
+pub mod respawn;
 pub mod runner;
 pub mod runtime;
+pub mod safe_mode;
+pub mod scaffold;
+pub mod serial;
+pub mod setenv;
+pub mod shell_commands;
 pub mod state_machine;
+pub mod subsystems;
+pub mod summary;
+pub mod sync;
+pub mod tags;
 pub mod term;
+pub mod test_report;
+pub mod time_format;
+pub mod timeline;
+pub mod tldr;
+pub mod trash;
+pub mod usage;
 pub mod vault;
+pub mod version;
 pub mod watcher;
 
 // Re-export the main struct so users can just use `positronic_core::PositronicEngine`
 pub use engine::PositronicEngine;
+pub use error::PositronicError;
 
 // Re-export the simpler types for the UI
 pub use state_machine::MyColor;
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
 /// A single "Block" of terminal output. (Legacy type; still OK to keep.)
@@ -30,9 +83,10 @@ pub struct TerminalBlock {
 /// The event stream from the PTY. The Bridge listens to this to know when to redraw.
 #[derive(Debug, Clone)]
 pub enum PtyEvent {
-    Output(Vec<u8>),              // Raw bytes from shell
+    Output(Bytes),                // Raw bytes from shell (shared, zero-copy)
     BlockFinished(TerminalBlock), // A command finished
     Bell,                         // Ding!
+    ShellExited(i32),             // The shell died (see `respawn`)
 }
 
 /// The command stream to the PTY. The Bridge sends this to the Core.
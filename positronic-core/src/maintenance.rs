@@ -0,0 +1,533 @@
+//! Background maintenance — indexes built once and kept fresh cheaply.
+//!
+//! The history search index, the completion index and friends each want
+//! an expensive first build and cheap upkeep. Spawned ad hoc they would
+//! all start at boot and fight the shell for the disk; instead each is a
+//! `MaintenanceTask` and one low-priority worker thread runs them, one
+//! step at a time, under `Scheduler`'s rules:
+//!
+//! - nothing `Cost::Heavy` in the first `BOOT_QUIET` after boot, or while
+//!   the shell is printing (output in the last `ACTIVE_QUIET`);
+//! - one task at a time; a task started is finished before the next, and
+//!   a heavy one pauses between steps while output flows;
+//! - higher priority first, then the task that ran longest ago;
+//! - a failed task waits `RETRY_AFTER` before trying again;
+//! - a task `trigger`ed on demand (the first Ctrl+R and the search index)
+//!   skips all of that and goes next.
+//!
+//! Each step is short (one batch of rows), so the worker sees shutdown
+//! and new output between steps. `Scheduler::next` is pure, clock and
+//! shell activity passed in, so the rules are tested without a thread.
+//! Progress and last runs are kept for `!status` and `!doctor`.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Heavy tasks wait this long after boot.
+pub const BOOT_QUIET: Duration = Duration::from_secs(10);
+
+/// Heavy tasks pause while the shell printed within this long.
+pub const ACTIVE_QUIET: Duration = Duration::from_secs(2);
+
+/// A failed task is retried after this long.
+pub const RETRY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// How often an idle worker checks its tasks for staleness again.
+pub const IDLE_RECHECK: Duration = Duration::from_secs(30);
+
+/// Pause between the steps of a heavy task nobody is waiting for.
+const STEP_PAUSE: Duration = Duration::from_millis(20);
+
+/// Shortest wait, so a gate about to open doesn't spin the worker.
+const MIN_WAIT: Duration = Duration::from_millis(50);
+
+/// The completion index task (`Runner::start_maintenance`).
+pub const COMPLETIONS_TASK: &str = "completions";
+
+/// The history search index task; the first Ctrl+R triggers it.
+pub const FTS_TASK: &str = "fts";
+
+/// History rows the search index takes per step.
+pub const FTS_BATCH: usize = 500;
+
+/// The Ctrl+P finder's file index (`file_index`); opening the finder
+/// triggers it.
+pub const FILES_TASK: &str = "files";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+/// What a task costs the machine while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cost {
+    /// Runs whenever it is due.
+    Light,
+    /// Kept out of the boot and away from a printing shell.
+    Heavy,
+}
+
+/// What a task's staleness check found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    Stale,
+    /// Nothing to keep here, and why (an encrypted vault has no search index).
+    Off(String),
+}
+
+/// What one step of a task did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// More to do; `done` of `total` so far (`total` 0 when unknown).
+    More { done: u64, total: u64 },
+    /// Finished, with a note for `!status` such as "5,000 commands".
+    Done(String),
+}
+
+type Check = Box<dyn FnMut() -> Freshness + Send>;
+type Run = Box<dyn FnMut() -> Result<Step, String> + Send>;
+
+/// A registered index or cache: how to tell it is stale, and how to
+/// bring it up to date one short step at a time.
+pub struct MaintenanceTask {
+    pub name: &'static str,
+    /// How `!status` names it: "FTS index".
+    pub label: &'static str,
+    pub priority: Priority,
+    pub cost: Cost,
+    check: Check,
+    run: Run,
+}
+
+impl MaintenanceTask {
+    pub fn new(
+        name: &'static str,
+        label: &'static str,
+        priority: Priority,
+        cost: Cost,
+        check: impl FnMut() -> Freshness + Send + 'static,
+        run: impl FnMut() -> Result<Step, String> + Send + 'static,
+    ) -> Self {
+        Self { name, label, priority, cost, check: Box::new(check), run: Box::new(run) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskState {
+    /// Not running; `TaskStatus::freshness` says whether it is due.
+    Idle,
+    /// Started: between steps, or paused while the shell prints.
+    Running { done: u64, total: u64 },
+    /// The last run failed; retried after `RETRY_AFTER`.
+    Failed(String),
+}
+
+/// Where a task stands, as the scheduler and `!status` see it.
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub priority: Priority,
+    pub cost: Cost,
+    pub freshness: Freshness,
+    pub state: TaskState,
+    /// Triggered on demand: runs next, past the gates.
+    pub forced: bool,
+    /// When the last run finished or failed.
+    pub last_run: Option<Instant>,
+    /// The last finished run's note.
+    pub note: Option<String>,
+}
+
+impl TaskStatus {
+    pub fn new(name: &'static str, label: &'static str, priority: Priority, cost: Cost) -> Self {
+        Self {
+            name,
+            label,
+            priority,
+            cost,
+            freshness: Freshness::Stale,
+            state: TaskState::Idle,
+            forced: false,
+            last_run: None,
+            note: None,
+        }
+    }
+
+    fn of(task: &MaintenanceTask) -> Self {
+        Self::new(task.name, task.label, task.priority, task.cost)
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, TaskState::Running { .. })
+    }
+
+    /// Failed, and not yet due to try again.
+    fn cooling(&self, now: Instant, retry_after: Duration) -> bool {
+        matches!(self.state, TaskState::Failed(_))
+            && self.last_run.is_some_and(|at| now.saturating_duration_since(at) < retry_after)
+    }
+
+    /// Record what a step did.
+    pub fn apply(&mut self, step: Result<Step, String>, now: Instant) {
+        match step {
+            Ok(Step::More { done, total }) => self.state = TaskState::Running { done, total },
+            Ok(Step::Done(note)) => {
+                self.state = TaskState::Idle;
+                self.freshness = Freshness::Fresh;
+                self.forced = false;
+                self.last_run = Some(now);
+                self.note = Some(note);
+            }
+            Err(e) => {
+                self.state = TaskState::Failed(e);
+                self.forced = false;
+                self.last_run = Some(now);
+            }
+        }
+    }
+
+    /// What `!status` says after the label: "fresh (2 min ago) · 5,000 commands".
+    pub fn describe(&self, now: Instant) -> String {
+        let when = |at: Option<Instant>| {
+            at.map(|at| format!(" ({})", ago(now.saturating_duration_since(at)))).unwrap_or_default()
+        };
+        match &self.state {
+            TaskState::Running { done, total } if *total > 0 => {
+                format!("building {}%", (done * 100 / total).min(100))
+            }
+            TaskState::Running { .. } => "building".to_string(),
+            TaskState::Failed(e) => format!("failed{}: {}", when(self.last_run), e),
+            TaskState::Idle => match &self.freshness {
+                Freshness::Off(why) => format!("off ({})", why),
+                Freshness::Stale if self.forced => "queued".to_string(),
+                Freshness::Stale if self.last_run.is_some() => {
+                    format!("stale, last built{}", when(self.last_run))
+                }
+                Freshness::Stale => "not built yet".to_string(),
+                Freshness::Fresh => {
+                    let mut line = format!("fresh{}", when(self.last_run));
+                    if let Some(note) = &self.note {
+                        line.push_str(&format!(" · {}", note));
+                    }
+                    line
+                }
+            },
+        }
+    }
+}
+
+/// "just now", "40 s ago", "2 min ago", "3 h ago".
+fn ago(elapsed: Duration) -> String {
+    match elapsed.as_secs() {
+        0..=4 => "just now".to_string(),
+        s @ 5..=59 => format!("{} s ago", s),
+        s @ 60..=3599 => format!("{} min ago", s / 60),
+        s => format!("{} h ago", s / 3600),
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Scheduler — the rules, without the thread
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Run one step of the task at this index.
+    Run(usize),
+    /// Something is due but gated; look again after this long.
+    Wait(Duration),
+    /// Nothing is due.
+    Idle,
+    /// Shutting down: stop, leaving started tasks where they are.
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Scheduler {
+    pub booted: Instant,
+    pub boot_quiet: Duration,
+    pub active_quiet: Duration,
+    pub retry_after: Duration,
+}
+
+impl Scheduler {
+    pub fn new(booted: Instant) -> Self {
+        Self { booted, boot_quiet: BOOT_QUIET, active_quiet: ACTIVE_QUIET, retry_after: RETRY_AFTER }
+    }
+
+    /// What the worker does next, given the tasks, the time, when the
+    /// shell last printed and whether the engine is shutting down.
+    pub fn next(&self, tasks: &[TaskStatus], now: Instant, last_output: Option<Instant>, shutdown: bool) -> Decision {
+        if shutdown {
+            return Decision::Stop;
+        }
+        // When each gate on heavy work opens, if it is closed.
+        let boot_open = self.booted + self.boot_quiet;
+        let active_open = last_output.map(|at| at + self.active_quiet).filter(|&open| open > now);
+        let heavy_open = [Some(boot_open).filter(|&open| open > now), active_open].into_iter().flatten().max();
+
+        let mut runnable = Vec::new();
+        let mut wake: Option<Instant> = None;
+        let mut wake_at = |at: Instant| wake = Some(wake.map_or(at, |w| w.min(at)));
+        for (i, task) in tasks.iter().enumerate() {
+            let due = task.forced || task.is_running() || task.freshness == Freshness::Stale;
+            if !due {
+                continue;
+            }
+            if !task.forced && !task.is_running() && task.cooling(now, self.retry_after) {
+                if let Some(at) = task.last_run {
+                    wake_at(at + self.retry_after);
+                }
+                continue;
+            }
+            match heavy_open {
+                Some(open) if task.cost == Cost::Heavy && !task.forced => wake_at(open),
+                _ => runnable.push(i),
+            }
+        }
+
+        let rank = |&i: &usize| {
+            let task = &tasks[i];
+            (
+                !task.forced,
+                // One at a time: a started task finishes first.
+                !task.is_running(),
+                std::cmp::Reverse(task.priority),
+                // Never run sorts before any run, then oldest first.
+                task.last_run,
+                i,
+            )
+        };
+        // A started task held at a gate holds the others too, unless
+        // they were asked for.
+        let paused = tasks.iter().enumerate().any(|(i, t)| t.is_running() && !runnable.contains(&i));
+        let wait = wake.map(|at| Decision::Wait(at.saturating_duration_since(now).max(MIN_WAIT)));
+        match runnable.into_iter().min_by_key(rank) {
+            Some(i) if tasks[i].forced || !paused => Decision::Run(i),
+            _ => wait.unwrap_or(Decision::Idle),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Shell activity
+// ════════════════════════════════════════════════════════════════════
+
+/// When the shell last printed, stamped by the PTY pump and read by the
+/// worker.
+#[derive(Debug)]
+pub struct Activity {
+    epoch: Instant,
+    /// Milliseconds after `epoch`, plus one; 0 for never.
+    last_output: AtomicU64,
+}
+
+impl Activity {
+    pub fn new() -> Self {
+        Self { epoch: Instant::now(), last_output: AtomicU64::new(0) }
+    }
+
+    pub fn output_seen(&self, at: Instant) {
+        let ms = at.saturating_duration_since(self.epoch).as_millis() as u64 + 1;
+        self.last_output.fetch_max(ms, Ordering::Relaxed);
+    }
+
+    pub fn last_output(&self) -> Option<Instant> {
+        match self.last_output.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(self.epoch + Duration::from_millis(ms - 1)),
+        }
+    }
+}
+
+impl Default for Activity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Worker
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug)]
+struct Board {
+    tasks: Vec<TaskStatus>,
+    shutdown: bool,
+    /// Bumped on every change, so `wait` never misses one.
+    version: u64,
+}
+
+#[derive(Debug)]
+struct Shared {
+    board: Mutex<Board>,
+    /// Wakes the worker: a trigger or shutdown.
+    wake: Condvar,
+    /// Wakes `wait`ers: a task's status changed.
+    changed: Condvar,
+    activity: Arc<Activity>,
+}
+
+impl Shared {
+    fn board(&self) -> MutexGuard<'_, Board> {
+        self.board.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn publish(&self, mut board: MutexGuard<'_, Board>) {
+        board.version += 1;
+        drop(board);
+        self.changed.notify_all();
+    }
+}
+
+/// The worker thread and the board of task statuses it keeps.
+#[derive(Debug)]
+pub struct Maintenance {
+    shared: Arc<Shared>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Maintenance {
+    /// Start the worker on `tasks`, gated by `scheduler` and `activity`.
+    pub fn start(tasks: Vec<MaintenanceTask>, scheduler: Scheduler, activity: Arc<Activity>) -> Self {
+        let board = Board { tasks: tasks.iter().map(TaskStatus::of).collect(), shutdown: false, version: 0 };
+        let shared = Arc::new(Shared { board: Mutex::new(board), wake: Condvar::new(), changed: Condvar::new(), activity });
+        let worker = {
+            let shared = shared.clone();
+            std::thread::Builder::new()
+                .name("positronic-maintenance".to_string())
+                .spawn(move || work(&shared, tasks, scheduler))
+                .map_err(|e| eprintln!("[MAINTENANCE] Worker failed to start: {}", e))
+                .ok()
+        };
+        Self { shared, worker: Mutex::new(worker) }
+    }
+
+    /// Run `name` next, whatever the gates say. `false` if there is no
+    /// such task.
+    pub fn trigger(&self, name: &str) -> bool {
+        let mut board = self.shared.board();
+        let Some(task) = board.tasks.iter_mut().find(|t| t.name == name) else {
+            return false;
+        };
+        task.forced = true;
+        self.shared.publish(board);
+        self.shared.wake.notify_all();
+        true
+    }
+
+    pub fn status(&self, name: &str) -> Option<TaskStatus> {
+        self.shared.board().tasks.iter().find(|t| t.name == name).cloned()
+    }
+
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.shared.board().tasks.clone()
+    }
+
+    /// Block until `name` is neither triggered nor running, calling
+    /// `progress` with each status on the way. `None` if there is no such
+    /// task.
+    pub fn wait(&self, name: &str, mut progress: impl FnMut(&TaskStatus)) -> Option<TaskStatus> {
+        let mut seen = None;
+        loop {
+            let (status, shutdown) = {
+                let mut board = self.shared.board();
+                while seen == Some(board.version) && !board.shutdown {
+                    board = self.shared.changed.wait(board).unwrap_or_else(|e| e.into_inner());
+                }
+                seen = Some(board.version);
+                (board.tasks.iter().find(|t| t.name == name)?.clone(), board.shutdown)
+            };
+            progress(&status);
+            if shutdown || (!status.forced && !status.is_running()) {
+                return Some(status);
+            }
+        }
+    }
+
+    /// Stop the worker after its current step and wait for it.
+    pub fn shutdown(&self) {
+        let mut board = self.shared.board();
+        board.shutdown = true;
+        self.shared.publish(board);
+        self.shared.wake.notify_all();
+        if let Some(worker) = self.worker.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            let _ = worker.join();
+        }
+    }
+
+    /// The `!status` section: one line per task.
+    pub fn lines(&self, now: Instant) -> Vec<String> {
+        let tasks = self.statuses();
+        let width = tasks.iter().map(|t| t.label.chars().count()).max().unwrap_or(0);
+        let mut lines = vec!["🧹 Background maintenance:".to_string()];
+        for task in &tasks {
+            lines.push(format!("  {:<width$}  {}", format!("{}:", task.label), task.describe(now), width = width + 1));
+        }
+        lines
+    }
+}
+
+impl Drop for Maintenance {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn work(shared: &Shared, mut tasks: Vec<MaintenanceTask>, scheduler: Scheduler) {
+    loop {
+        // Staleness checks run outside the lock; they may read the vault.
+        let idle: Vec<bool> = shared.board().tasks.iter().map(|t| !t.is_running()).collect();
+        let checked: Vec<Option<Freshness>> =
+            tasks.iter_mut().zip(idle).map(|(task, idle)| idle.then(|| (task.check)())).collect();
+
+        let mut board = shared.board();
+        for (status, freshness) in board.tasks.iter_mut().zip(checked) {
+            if let Some(freshness) = freshness.filter(|_| !status.is_running()) {
+                status.freshness = freshness;
+            }
+        }
+        let now = Instant::now();
+        match scheduler.next(&board.tasks, now, shared.activity.last_output(), board.shutdown) {
+            Decision::Run(i) => {
+                if !board.tasks[i].is_running() {
+                    board.tasks[i].state = TaskState::Running { done: 0, total: 0 };
+                }
+                let pause = !board.tasks[i].forced && board.tasks[i].cost == Cost::Heavy;
+                shared.publish(board);
+                let step = panic::catch_unwind(AssertUnwindSafe(|| (tasks[i].run)()))
+                    .unwrap_or_else(|_| Err("panicked".to_string()));
+                let mut board = shared.board();
+                board.tasks[i].apply(step, Instant::now());
+                let running = board.tasks[i].is_running();
+                shared.publish(board);
+                if pause && running {
+                    std::thread::sleep(STEP_PAUSE);
+                }
+            }
+            Decision::Wait(wait) => {
+                let _ = shared.wake.wait_timeout(board, wait.min(IDLE_RECHECK));
+            }
+            Decision::Idle => {
+                let _ = shared.wake.wait_timeout(board, IDLE_RECHECK);
+            }
+            Decision::Stop => {
+                // A started task starts over next session; its steps have
+                // kept what they finished.
+                for task in board.tasks.iter_mut().filter(|t| t.is_running()) {
+                    task.state = TaskState::Idle;
+                }
+                shared.publish(board);
+                return;
+            }
+        }
+    }
+}
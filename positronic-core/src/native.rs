@@ -0,0 +1,163 @@
+//! Typed results of native `!` commands.
+//!
+//! A native command answers with `NativeOutput`: plain lines, a table, or
+//! Markdown. Tables keep their values typed (timestamps and counts are
+//! integers) so a frontend can sort them, hand them to the Holodeck and
+//! `!save` them; each also carries the text the command has always
+//! printed, which is what a frontend without table rendering shows.
+
+use std::fmt::Write as _;
+
+use crate::runner::ExecuteResult;
+
+/// Vault config key: render native tables in the Holodeck (default on).
+pub const HOLODECK_KEY: &str = "holodeck.native";
+
+/// What a native command produced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeOutput {
+    Lines(Vec<String>),
+    Table(DataFrame),
+    /// Help-style content, shown line by line where Markdown isn't rendered.
+    Markdown(String),
+}
+
+impl From<NativeOutput> for ExecuteResult {
+    fn from(output: NativeOutput) -> ExecuteResult {
+        match output {
+            NativeOutput::Lines(lines) => ExecuteResult::DirectOutput(lines),
+            NativeOutput::Table(frame) => ExecuteResult::Table(frame),
+            NativeOutput::Markdown(text) => ExecuteResult::DirectOutput(text.lines().map(str::to_string).collect()),
+        }
+    }
+}
+
+/// What a column holds; every cell is of this kind or `Cell::Empty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Integer,
+    Float,
+    Bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Empty,
+}
+
+impl Cell {
+    pub fn text(value: impl Into<String>) -> Cell {
+        Cell::Text(value.into())
+    }
+
+    fn kind(&self) -> Option<ColumnKind> {
+        match self {
+            Cell::Text(_) => Some(ColumnKind::Text),
+            Cell::Integer(_) => Some(ColumnKind::Integer),
+            Cell::Float(_) => Some(ColumnKind::Float),
+            Cell::Bool(_) => Some(ColumnKind::Bool),
+            Cell::Empty => None,
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Cell::Text(s) => s.clone(),
+            Cell::Integer(i) => i.to_string(),
+            Cell::Float(f) => f.to_string(),
+            Cell::Bool(b) => b.to_string(),
+            Cell::Empty => String::new(),
+        }
+    }
+}
+
+/// Rows of typed cells under named columns, plus the command's own text
+/// rendering of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataFrame {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Cell>>,
+    /// The plain rendering; empty means a generic aligned grid.
+    text: Vec<String>,
+}
+
+impl DataFrame {
+    pub fn new(columns: &[(&str, ColumnKind)]) -> DataFrame {
+        DataFrame {
+            columns: columns.iter().map(|(name, kind)| Column { name: name.to_string(), kind: *kind }).collect(),
+            rows: Vec::new(),
+            text: Vec::new(),
+        }
+    }
+
+    /// Append a row. Cells must match the columns in number and kind.
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.columns.len(), "row width");
+        debug_assert!(
+            row.iter().zip(&self.columns).all(|(cell, column)| cell.kind().is_none_or(|kind| kind == column.kind)),
+            "cell kinds"
+        );
+        self.rows.push(row);
+    }
+
+    /// Use `lines` as the plain rendering.
+    pub fn with_text(mut self, lines: Vec<String>) -> DataFrame {
+        self.text = lines;
+        self
+    }
+
+    pub fn headers(&self) -> Vec<&str> {
+        self.columns.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// The frame as text lines: the command's rendering, or an aligned grid.
+    pub fn to_lines(&self) -> Vec<String> {
+        if !self.text.is_empty() {
+            return self.text.clone();
+        }
+        let cells: Vec<Vec<String>> = self.rows.iter().map(|row| row.iter().map(Cell::display).collect()).collect();
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.name.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |values: Vec<&str>| {
+            let mut out = String::new();
+            for (i, value) in values.iter().enumerate() {
+                let pad = widths[i];
+                let _ = match self.columns[i].kind {
+                    ColumnKind::Integer | ColumnKind::Float => write!(out, "{:>pad$}  ", value),
+                    _ => write!(out, "{:<pad$}  ", value),
+                };
+            }
+            out.trim_end().to_string()
+        };
+        let mut lines = vec![line(self.headers())];
+        lines.extend(cells.iter().map(|row| line(row.iter().map(String::as_str).collect())));
+        lines
+    }
+
+    /// `to_lines` joined, for frontends that show one string.
+    pub fn to_table_string(&self) -> String {
+        self.to_lines().join("\n")
+    }
+}
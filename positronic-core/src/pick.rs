@@ -0,0 +1,248 @@
+//! `!pick`: act on several entries of a listing at once.
+//!
+//! `!history`, `!search`, `!bm list` and `!tag list` register the numbers
+//! they show with the runner as a `Listing`. `!pick 2,5,7-9` selects
+//! entries by those numbers, and `!pick run`, `bookmark`, `export <path>`
+//! or `delete` acts on the selection. A new listing replaces the old one
+//! and drops the selection, so a number always means what was last on
+//! screen.
+
+use crate::danger::DangerAnalyzer;
+use crate::redo::RedoShell;
+
+pub const PICK_USAGE: &str =
+    "Usage: !pick <2,5,7-9> | run [--keep-going] [--yes] | bookmark | export <path> | delete [--yes] | clear";
+
+/// Most entries one `!pick` may name.
+const MAX_PICKED: usize = 1000;
+
+/// What a listed entry stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ItemRef {
+    /// Every logged run of the command (`!history` lists each once).
+    Command,
+    /// One history row (`!search`).
+    Record(i64),
+    Bookmark(i64),
+    Tag(String),
+}
+
+/// One numbered entry of a listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedItem {
+    /// The number shown for it.
+    pub number: i64,
+    pub item: ItemRef,
+    pub command: String,
+}
+
+/// The entries a listing showed, by their numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    /// The command that produced it, for messages: `!history`.
+    pub source: &'static str,
+    pub items: Vec<ListedItem>,
+}
+
+impl Listing {
+    pub fn new(source: &'static str) -> Self {
+        Self { source, items: Vec::new() }
+    }
+
+    pub fn push(&mut self, number: i64, item: ItemRef, command: impl Into<String>) {
+        self.items.push(ListedItem { number, item, command: command.into() });
+    }
+
+    /// The entries shown as `numbers`, in that order, each once.
+    pub fn resolve(&self, numbers: &[i64]) -> Result<Vec<ListedItem>, String> {
+        let missing: Vec<String> = numbers
+            .iter()
+            .filter(|n| !self.items.iter().any(|item| item.number == **n))
+            .map(i64::to_string)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("❌ The last {} listing has no {}", self.source, missing.join(", ")));
+        }
+        let mut picked: Vec<ListedItem> = Vec::with_capacity(numbers.len());
+        for number in numbers {
+            if let Some(item) = self.items.iter().find(|item| item.number == *number) {
+                if !picked.contains(item) {
+                    picked.push(item.clone());
+                }
+            }
+        }
+        Ok(picked)
+    }
+}
+
+/// The runner's last listing and what was picked from it.
+#[derive(Debug, Default)]
+pub struct PickState {
+    listing: Option<Listing>,
+    selection: Vec<ListedItem>,
+}
+
+impl PickState {
+    /// A listing was shown: its numbers replace the old ones, and the
+    /// old selection goes.
+    pub fn register(&mut self, listing: Listing) {
+        self.listing = Some(listing);
+        self.selection.clear();
+    }
+
+    /// Select `numbers` from the last listing.
+    pub fn select(&mut self, numbers: &[i64]) -> Result<&[ListedItem], String> {
+        let listing = self.listing.as_ref().ok_or(NO_LISTING)?;
+        self.selection = listing.resolve(numbers)?;
+        Ok(&self.selection)
+    }
+
+    /// The selection and the listing it came from.
+    pub fn selection(&self) -> Result<(&'static str, &[ListedItem]), String> {
+        let listing = self.listing.as_ref().ok_or(NO_LISTING)?;
+        if self.selection.is_empty() {
+            return Err(format!("📌 Nothing picked; !pick <numbers> selects from the last {} listing", listing.source));
+        }
+        Ok((listing.source, &self.selection))
+    }
+
+    /// Drop the selection; the listing stays.
+    pub fn clear(&mut self) {
+        self.selection.clear();
+    }
+
+    /// Drop the listing too, once its numbers no longer match the vault.
+    pub fn forget(&mut self) {
+        self.listing = None;
+        self.selection.clear();
+    }
+}
+
+const NO_LISTING: &str = "📌 Nothing to pick from; list entries with !history, !search, !bm list or !tag list first";
+
+/// What follows `!pick`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickCommand {
+    /// `!pick` alone: show the selection.
+    Show,
+    Select(Vec<i64>),
+    Run { keep_going: bool, yes: bool },
+    Bookmark,
+    Export(String),
+    Delete { yes: bool },
+    Clear,
+}
+
+impl PickCommand {
+    /// Parse what follows `!pick`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<PickCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(PickCommand::Show),
+            ["run", flags @ ..] => {
+                let (mut keep_going, mut yes) = (false, false);
+                for flag in flags {
+                    match *flag {
+                        "--keep-going" | "-k" => keep_going = true,
+                        "--yes" | "-y" => yes = true,
+                        _ => return Err(PICK_USAGE.to_string()),
+                    }
+                }
+                Ok(PickCommand::Run { keep_going, yes })
+            }
+            ["bookmark"] => Ok(PickCommand::Bookmark),
+            ["export", path] => Ok(PickCommand::Export(path.to_string())),
+            ["delete"] => Ok(PickCommand::Delete { yes: false }),
+            ["delete", "--yes" | "-y"] => Ok(PickCommand::Delete { yes: true }),
+            ["clear"] => Ok(PickCommand::Clear),
+            ["bookmark" | "export" | "delete" | "clear", ..] => Err(PICK_USAGE.to_string()),
+            _ => parse_numbers(args).map(PickCommand::Select),
+        }
+    }
+}
+
+/// `2,5,7-9` as `[2, 5, 7, 8, 9]`. Commas and spaces both separate; a
+/// leading `#` is allowed, as `!bm list` shows ids with one.
+pub fn parse_numbers(text: &str) -> Result<Vec<i64>, String> {
+    let mut numbers = Vec::new();
+    for part in text.split(|c: char| c == ',' || c.is_whitespace()).filter(|p| !p.is_empty()) {
+        let bad = || format!("❌ Bad number or range '{}' (e.g. 2,5,7-9)", part);
+        let number = |s: &str| s.trim_start_matches('#').parse::<i64>().ok().filter(|n| *n > 0);
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = number(from).zip(number(to)).filter(|(from, to)| from <= to).ok_or_else(bad)?;
+                if (to - from) as usize >= MAX_PICKED {
+                    return Err(format!("❌ {} picks too many; at most {} at once", part, MAX_PICKED));
+                }
+                numbers.extend(from..=to);
+            }
+            None => numbers.push(number(part).ok_or_else(bad)?),
+        }
+        if numbers.len() > MAX_PICKED {
+            return Err(format!("❌ At most {} entries at once", MAX_PICKED));
+        }
+    }
+    if numbers.is_empty() {
+        return Err(PICK_USAGE.to_string());
+    }
+    Ok(numbers)
+}
+
+/// `  2. cargo test` for each picked entry.
+pub fn item_lines(items: &[ListedItem]) -> Vec<String> {
+    items.iter().map(|item| format!("  {:>3}. {}", item.number, item.command)).collect()
+}
+
+/// Why `!pick run` won't run the selection: entries that aren't shell
+/// commands (`!` lines, and any `not_shell` says), and destructive ones,
+/// which are left to be run by hand.
+pub fn run_refusals(items: &[ListedItem], not_shell: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut refusals = Vec::new();
+    for item in items {
+        if item.command.starts_with('!') || not_shell(&item.command) {
+            refusals.push(format!("  {:>3}. {} — not a shell command", item.number, item.command));
+            continue;
+        }
+        let danger = DangerAnalyzer::analyze(&item.command);
+        if danger.is_destructive() {
+            refusals.push(format!(
+                "  {:>3}. {} — destructive ({})",
+                item.number,
+                item.command,
+                danger.reason.unwrap_or("matches a destructive pattern")
+            ));
+        }
+    }
+    refusals
+}
+
+/// One line that runs `commands` in order: each only if the one before
+/// it succeeded, or all of them with `keep_going`.
+pub fn chain(shell: RedoShell, commands: &[String], keep_going: bool) -> String {
+    match (shell, keep_going) {
+        (RedoShell::Posix, false) => commands.join(" && "),
+        (_, true) => commands.join("; "),
+        // Windows PowerShell has no `&&`; `$?` is false after a failure.
+        (RedoShell::PowerShell, false) => {
+            let mut line = String::new();
+            for (i, command) in commands.iter().enumerate() {
+                if i > 0 {
+                    line.push_str("; if ($?) { ");
+                }
+                line.push_str(command);
+            }
+            line.push_str(&" }".repeat(commands.len().saturating_sub(1)));
+            line
+        }
+    }
+}
+
+/// `1 bookmark`, `3 bookmarks`.
+pub fn counted(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// `!pick export`: the picked commands, one per line.
+pub fn export_body(items: &[ListedItem]) -> String {
+    items.iter().map(|item| format!("{}\n", item.command)).collect()
+}
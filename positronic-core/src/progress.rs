@@ -0,0 +1,352 @@
+//! Progress of long native operations.
+//!
+//! An operation asks the session's `ProgressBoard` (`Runner::progress`)
+//! for a `Task`: a label, a total when it knows one (else a spinner), and
+//! the `CancellationToken` it already obeys, usually its command's. Work
+//! on the blocking pool takes a `Reporter`, a cheap `Send` copy of the
+//! handle. Updates are two atomic stores; at most one event per
+//! `UPDATE_INTERVAL` is queued for the window, and an update the window
+//! hasn't taken yet is replaced rather than queued behind.
+//!
+//! The window drains the events (`drain`), shows running tasks in the
+//! status bar (`TaskSnapshot::status_entry`) and each finished one as a
+//! single line (`Finished::summary_line`). `!tasks` lists them and
+//! `!tasks cancel <id>` fires the task's token, so a task started by a
+//! `!` command is cancelled exactly as Esc or `!abort` would.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancellationToken;
+
+pub const TASKS_USAGE: &str = "Usage: !tasks [cancel <id>]";
+
+/// Most often a task's progress is queued for the window (10 Hz).
+pub const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Events kept for a window that isn't draining them (a headless runner);
+/// the oldest go first.
+const MAX_QUEUED: usize = 256;
+
+/// Frames for a task without a total, one per `UPDATE_INTERVAL`.
+const SPINNER: [char; 4] = ['◐', '◓', '◑', '◒'];
+
+/// A task as it stood when asked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskSnapshot {
+    pub id: u64,
+    pub label: String,
+    pub done: u64,
+    /// `None` while the task can't say how much there is.
+    pub total: Option<u64>,
+    pub elapsed: Duration,
+    pub cancelled: bool,
+}
+
+impl TaskSnapshot {
+    pub fn percent(&self) -> Option<u64> {
+        self.total.map(|total| (self.done.saturating_mul(100) / total.max(1)).min(100))
+    }
+
+    /// `⏳ Sync import 42% 3s`, or a spinner in place of the percentage.
+    pub fn status_entry(&self) -> String {
+        let secs = self.elapsed.as_secs();
+        match self.percent() {
+            Some(percent) => format!("⏳ {} {}% {}s", self.label, percent, secs),
+            None => {
+                let frame = (self.elapsed.as_millis() / UPDATE_INTERVAL.as_millis()) as usize % SPINNER.len();
+                format!("{} {} {}s", SPINNER[frame], self.label, secs)
+            }
+        }
+    }
+}
+
+/// How a task ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Finished; what it did, for the summary, or empty.
+    Done(String),
+    Failed(String),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finished {
+    pub id: u64,
+    pub label: String,
+    pub elapsed: Duration,
+    pub outcome: Outcome,
+}
+
+impl Finished {
+    /// `✓ Sync import finished in 3.2s: 1204 commands`
+    pub fn summary_line(&self) -> String {
+        let secs = self.elapsed.as_secs_f64();
+        match &self.outcome {
+            Outcome::Done(detail) if detail.is_empty() => format!("✓ {} finished in {:.1}s", self.label, secs),
+            Outcome::Done(detail) => format!("✓ {} finished in {:.1}s: {}", self.label, secs, detail),
+            Outcome::Failed(error) => format!("❌ {} failed after {:.1}s: {}", self.label, secs, error),
+            Outcome::Cancelled => format!("✖ {} cancelled after {:.1}s", self.label, secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    Started(TaskSnapshot),
+    Updated(TaskSnapshot),
+    Finished(Finished),
+}
+
+#[derive(Debug)]
+struct TaskState {
+    id: u64,
+    label: String,
+    done: AtomicU64,
+    /// 0 while there is no total.
+    total: AtomicU64,
+    started: Instant,
+    /// When an update was last queued.
+    published: Mutex<Instant>,
+    finished: AtomicBool,
+    token: CancellationToken,
+}
+
+impl TaskState {
+    fn snapshot(&self) -> TaskSnapshot {
+        let total = self.total.load(Ordering::Relaxed);
+        TaskSnapshot {
+            id: self.id,
+            label: self.label.clone(),
+            done: self.done.load(Ordering::Relaxed),
+            total: (total > 0).then_some(total),
+            elapsed: self.started.elapsed(),
+            cancelled: self.token.is_cancelled(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    next_id: AtomicU64,
+    running: Mutex<Vec<Arc<TaskState>>>,
+    events: Mutex<VecDeque<ProgressEvent>>,
+}
+
+impl Shared {
+    fn running(&self) -> MutexGuard<'_, Vec<Arc<TaskState>>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn events(&self) -> MutexGuard<'_, VecDeque<ProgressEvent>> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue `event`. An update replaces the task's update still queued.
+    fn push(&self, event: ProgressEvent) {
+        let mut events = self.events();
+        if let ProgressEvent::Updated(update) = &event {
+            let queued = events.iter_mut().rev().find(|e| matches!(e, ProgressEvent::Updated(u) if u.id == update.id));
+            if let Some(queued) = queued {
+                *queued = event;
+                return;
+            }
+        }
+        if events.len() == MAX_QUEUED {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+}
+
+/// The session's long operations in flight.
+#[derive(Debug, Clone)]
+pub struct ProgressBoard {
+    shared: Arc<Shared>,
+}
+
+impl Default for ProgressBoard {
+    fn default() -> Self {
+        let shared = Shared { next_id: AtomicU64::new(1), running: Mutex::default(), events: Mutex::default() };
+        Self { shared: Arc::new(shared) }
+    }
+}
+
+impl ProgressBoard {
+    /// Start a task; it runs until `Task::finish` or until it is dropped.
+    pub fn begin(&self, label: &str, total: Option<u64>, cancel: CancellationToken) -> Task {
+        let now = Instant::now();
+        let state = Arc::new(TaskState {
+            id: self.shared.next_id.fetch_add(1, Ordering::Relaxed),
+            label: label.to_string(),
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(total.unwrap_or(0)),
+            started: now,
+            published: Mutex::new(now),
+            finished: AtomicBool::new(false),
+            token: cancel,
+        });
+        self.shared.running().push(state.clone());
+        self.shared.push(ProgressEvent::Started(state.snapshot()));
+        Task { reporter: Reporter { state, shared: self.shared.clone() } }
+    }
+
+    /// Tasks in flight, oldest first.
+    pub fn active(&self) -> Vec<TaskSnapshot> {
+        self.shared.running().iter().map(|task| task.snapshot()).collect()
+    }
+
+    /// Fire task `id`'s token; None if it isn't running.
+    pub fn cancel(&self, id: u64) -> Option<TaskSnapshot> {
+        let running = self.shared.running();
+        let task = running.iter().find(|task| task.id == id)?;
+        task.token.cancel();
+        Some(task.snapshot())
+    }
+
+    /// The events queued since the last call, oldest first.
+    pub fn drain(&self) -> Vec<ProgressEvent> {
+        self.shared.events().drain(..).collect()
+    }
+
+    /// `!tasks [cancel <id>]`.
+    pub fn tasks_lines(&self, args: &str) -> Vec<String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["list"] => {}
+            ["cancel", id] => {
+                let Ok(id) = id.trim_start_matches('#').parse::<u64>() else {
+                    return vec![TASKS_USAGE.to_string()];
+                };
+                return match self.cancel(id) {
+                    Some(task) => vec![format!("✖ Cancelling task #{} {}", task.id, task.label)],
+                    None => vec![format!("❌ No task #{} running", id)],
+                };
+            }
+            _ => return vec![TASKS_USAGE.to_string()],
+        }
+        let active = self.active();
+        if active.is_empty() {
+            return vec!["⏳ No long operations running".to_string()];
+        }
+        let mut lines = vec!["⏳ Running:".to_string()];
+        lines.extend(active.iter().map(|task| {
+            let progress = match (task.percent(), task.total) {
+                (Some(percent), Some(total)) => format!("{}% ({}/{})", percent, task.done, total),
+                _ => "…".to_string(),
+            };
+            let state = if task.cancelled { "  cancelling" } else { "" };
+            format!("  #{:<4} {}  {}  ({:.1}s){}", task.id, task.label, progress, task.elapsed.as_secs_f64(), state)
+        }));
+        lines.push("  !tasks cancel <id> stops one".to_string());
+        lines
+    }
+}
+
+/// A running task, owned by the operation. Dropped without `finish`, it
+/// counts as cancelled if its token fired, else as failed.
+#[derive(Debug)]
+pub struct Task {
+    reporter: Reporter,
+}
+
+impl Task {
+    pub fn id(&self) -> u64 {
+        self.reporter.state.id
+    }
+
+    /// A handle for the thread doing the work.
+    pub fn reporter(&self) -> Reporter {
+        self.reporter.clone()
+    }
+
+    pub fn set(&self, done: u64) {
+        self.reporter.set(done);
+    }
+
+    pub fn set_total(&self, total: u64) {
+        self.reporter.set_total(total);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.reporter.is_cancelled()
+    }
+
+    /// End the task. A task whose token fired ends cancelled, whatever
+    /// `outcome` says.
+    pub fn finish(self, outcome: Outcome) {
+        self.reporter.finish(outcome);
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.reporter.finish(Outcome::Failed("stopped before it finished".to_string()));
+    }
+}
+
+/// Progress updates for a task, from any thread. Updates after the task
+/// finished are ignored.
+#[derive(Debug, Clone)]
+pub struct Reporter {
+    state: Arc<TaskState>,
+    shared: Arc<Shared>,
+}
+
+impl Reporter {
+    pub fn set(&self, done: u64) {
+        self.state.done.store(done, Ordering::Relaxed);
+        self.publish(false);
+    }
+
+    pub fn advance(&self, by: u64) {
+        self.state.done.fetch_add(by, Ordering::Relaxed);
+        self.publish(false);
+    }
+
+    /// Once the task knows how much there is.
+    pub fn set_total(&self, total: u64) {
+        if self.state.total.swap(total, Ordering::Relaxed) != total {
+            self.publish(true);
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.token.is_cancelled()
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.state.token
+    }
+
+    fn publish(&self, now: bool) {
+        if self.state.finished.load(Ordering::Relaxed) {
+            return;
+        }
+        {
+            let mut published = self.state.published.lock().unwrap_or_else(|e| e.into_inner());
+            let at = Instant::now();
+            if !now && at.duration_since(*published) < UPDATE_INTERVAL {
+                return;
+            }
+            *published = at;
+        }
+        self.shared.push(ProgressEvent::Updated(self.state.snapshot()));
+    }
+
+    fn finish(&self, outcome: Outcome) {
+        if self.state.finished.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        self.shared.running().retain(|task| task.id != self.state.id);
+        let outcome = if self.is_cancelled() { Outcome::Cancelled } else { outcome };
+        self.shared.push(ProgressEvent::Finished(Finished {
+            id: self.state.id,
+            label: self.state.label.clone(),
+            elapsed: self.state.started.elapsed(),
+            outcome,
+        }));
+    }
+}
@@ -4,9 +4,15 @@
 //! Unix: `nix` PTY + fork/exec
 //!
 //! Includes minimal shell integration to emit OSC 133 (prompt markers)
-//! and OSC 7 (cwd) so Intelli-Input can automatically gate itself.
+//! and OSC 7 (cwd) so Intelli-Input can automatically gate itself, and
+//! binds the cwd probe key (see `term::probe`).
+//!
+//! `exit_code` reaps the child without blocking; the engine polls it once
+//! the reader hits EOF (see `respawn`).
 
 use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use std::ffi::OsString;
 use tokio::sync::mpsc;
 
 #[cfg(windows)]
@@ -29,13 +35,20 @@ impl std::fmt::Debug for PtyManager {
 
 impl PtyManager {
     pub fn new(cols: u16, rows: u16) -> Result<Self> {
+        Self::with_shell(cols, rows, None, &[])
+    }
+
+    /// Run `shell` (a program, no arguments) instead of the default:
+    /// `$SHELL` on Unix, PowerShell with our profile on Windows. `env` is
+    /// set in the shell's environment on top of ours.
+    pub fn with_shell(cols: u16, rows: u16, shell: Option<&str>, env: &[(OsString, OsString)]) -> Result<Self> {
         eprintln!(
             "[PTY_MANAGER] Creating new PTY ({}x{}) on {}",
             cols,
             rows,
             std::env::consts::OS
         );
-        let inner = PlatformPty::new(cols, rows)?;
+        let inner = PlatformPty::new(cols, rows, shell, env)?;
         eprintln!("[PTY_MANAGER] PTY created successfully");
         Ok(Self { inner })
     }
@@ -65,18 +78,79 @@ impl PtyManager {
     }
 
     pub fn child_is_alive(&mut self) -> bool {
-        self.inner.child_is_alive()
+        self.exit_code().is_none()
+    }
+
+    /// The shell's exit status once it has exited (128 + the signal number
+    /// if it was killed). Never blocks; the status is kept once reaped.
+    pub fn exit_code(&mut self) -> Option<i32> {
+        self.inner.exit_code()
     }
 
-    pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Vec<u8>>> {
+    /// True if the shell runs our integration, so `term::probe::PROBE_KEY`
+    /// is bound. Other shells would insert the key as text.
+    pub fn cwd_probe_supported(&self) -> bool {
+        self.inner.integrated
+    }
+
+    pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Bytes>> {
         eprintln!("[PTY_MANAGER] Starting reader pump");
         self.inner.start_reader()
     }
 }
 
+// ════════════════════════════════════════════════════════════════════
+// Read pool
+// ════════════════════════════════════════════════════════════════════
+
+/// Bytes requested per PTY read.
+const READ_CHUNK: usize = 8192;
+
+/// Backing allocation shared by consecutive reads.
+const POOL_CAPACITY: usize = 64 * 1024;
+
+/// Reusable read buffer for the reader threads.
+///
+/// Each read lands in the spare capacity of one `BytesMut` and is split off
+/// as a frozen, reference-counted `Bytes`, so downstream consumers share the
+/// same memory instead of copying it. Once every chunk from an allocation
+/// has been dropped, `reserve` reclaims it for the next reads.
+pub(crate) struct ReadPool {
+    buf: BytesMut,
+}
+
+impl ReadPool {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: BytesMut::with_capacity(POOL_CAPACITY),
+        }
+    }
+
+    /// Run one read into the pool and return the filled bytes.
+    pub(crate) fn read_with<E>(
+        &mut self,
+        read: impl FnOnce(&mut [u8]) -> std::result::Result<usize, E>,
+    ) -> std::result::Result<Bytes, E> {
+        if self.buf.capacity() < READ_CHUNK {
+            self.buf.reserve(POOL_CAPACITY);
+        }
+        self.buf.resize(READ_CHUNK, 0);
+        let n = match read(&mut self.buf[..]) {
+            Ok(n) => n.min(READ_CHUNK),
+            Err(e) => {
+                self.buf.clear();
+                return Err(e);
+            }
+        };
+        self.buf.truncate(n);
+        Ok(self.buf.split().freeze())
+    }
+}
+
 #[cfg(windows)]
 mod windows_impl {
     use super::*;
+    use crate::term::probe;
     use anyhow::Context;
     use std::io::{Read, Write};
     use std::path::PathBuf;
@@ -132,7 +206,15 @@ function global:prompt {
 
   return "PS $p> "
 }
-"#;
+
+# CWD probe: Ctrl+F12 reports the location in a private OSC (no echo, no prompt)
+try {
+  Set-PSReadLineKeyHandler -Chord Ctrl+F12 -ScriptBlock {
+    [Console]::Write("`e]7770;positronic-cwd;{TOKEN};$($PWD.Path)`a")
+  }
+} catch {}
+"#
+        .replace("{TOKEN}", probe::session_token());
 
         std::fs::write(&path, script).context("Failed to write PowerShell profile")?;
         let _ = PS_PROFILE.set(path.clone());
@@ -142,22 +224,31 @@ function global:prompt {
     pub struct WindowsPty {
         writer: Arc<Mutex<conpty::io::PipeWriter>>,
         reader: Option<conpty::io::PipeReader>,
-        _process: Arc<Mutex<SendProcess>>,
+        process: Arc<Mutex<SendProcess>>,
         _cols: u16,
         _rows: u16,
+        pub(super) integrated: bool,
     }
 
     impl WindowsPty {
-        pub fn new(cols: u16, rows: u16) -> Result<Self> {
+        pub fn new(cols: u16, rows: u16, shell: Option<&str>, env: &[(OsString, OsString)]) -> Result<Self> {
             eprintln!("[WINDOWS_PTY] Initializing ConPTY");
 
-            let profile = ensure_ps_profile()?;
-            let cmd = format!(
-                "powershell.exe -NoLogo -NoExit -ExecutionPolicy Bypass -File \"{}\"",
-                profile.display()
-            );
+            let cmd = match shell {
+                Some(shell) => shell.to_string(),
+                None => format!(
+                    "powershell.exe -NoLogo -NoExit -ExecutionPolicy Bypass -File \"{}\"",
+                    ensure_ps_profile()?.display()
+                ),
+            };
 
-            let mut process = conpty::spawn(&cmd).context("Failed to spawn ConPTY")?;
+            // As `conpty::spawn` does. An environment given to the command
+            // replaces ours rather than adding to it.
+            let mut command = std::process::Command::new(format!("cmd /C {}", cmd));
+            if !env.is_empty() {
+                command.envs(std::env::vars_os()).envs(env.iter().cloned());
+            }
+            let mut process = conpty::Process::spawn(command).context("Failed to spawn ConPTY")?;
 
             eprintln!("[WINDOWS_PTY] Getting I/O handles");
             let reader = process.output().context("Failed to get reader")?;
@@ -168,9 +259,10 @@ function global:prompt {
             Ok(Self {
                 writer: Arc::new(Mutex::new(writer)),
                 reader: Some(reader),
-                _process: Arc::new(Mutex::new(SendProcess(process))),
+                process: Arc::new(Mutex::new(SendProcess(process))),
                 _cols: cols,
                 _rows: rows,
+                integrated: shell.is_none(),
             })
         }
 
@@ -201,23 +293,27 @@ function global:prompt {
             self.write_line(&cmd)
         }
 
-        pub fn child_is_alive(&mut self) -> bool {
-            true
+        pub fn exit_code(&mut self) -> Option<i32> {
+            let process = self.process.lock().unwrap_or_else(|p| p.into_inner());
+            if process.0.is_alive() {
+                return None;
+            }
+            process.0.wait(Some(0)).ok().map(|code| code as i32)
         }
 
-        pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Vec<u8>>> {
+        pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Bytes>> {
             let mut reader = self.reader.take().context("Reader already started")?;
             let (tx, rx) = mpsc::channel(256);
 
             std::thread::spawn(move || {
                 eprintln!("[PTY_READER] Windows ConPTY reader started");
-                let mut buf = vec![0u8; 8192];
+                let mut pool = ReadPool::new();
 
                 loop {
-                    match reader.read(&mut buf) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                    match pool.read_with(|buf| reader.read(buf)) {
+                        Ok(chunk) if chunk.is_empty() => break,
+                        Ok(chunk) => {
+                            if tx.blocking_send(chunk).is_err() {
                                 break;
                             }
                         }
@@ -242,10 +338,12 @@ function global:prompt {
 #[cfg(unix)]
 mod unix_impl {
     use super::*;
+    use crate::term::probe;
     use anyhow::Context;
     use nix::pty::openpty;
     use nix::unistd::{fork, ForkResult};
     use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
     use std::path::PathBuf;
     use std::sync::OnceLock;
     use tokio::sync::mpsc;
@@ -271,9 +369,16 @@ __positronic_prompt() {
 }
 PROMPT_COMMAND="__positronic_prompt"
 
-# CommandStart before each command
-trap 'printf "\e]133;B\a"' DEBUG
-"#;
+# CommandStart before each command (not for our own hooks)
+trap '[[ $BASH_COMMAND == __positronic_* ]] || printf "\e]133;B\a"' DEBUG
+
+# CWD probe: Ctrl+F12 reports $PWD in a private OSC (no echo, no prompt)
+__positronic_cwd_probe() {
+  printf "\e]7770;positronic-cwd;{TOKEN};%s\a" "$PWD"
+}
+bind -x '"\e[24;5~": __positronic_cwd_probe'
+"#
+        .replace("{TOKEN}", probe::session_token());
 
         std::fs::write(&path, script).context("Failed to write bash rc")?;
         let _ = BASH_RC.set(path.clone());
@@ -283,12 +388,29 @@ trap 'printf "\e]133;B\a"' DEBUG
     pub struct UnixPty {
         master_fd: i32,
         child_pid: nix::unistd::Pid,
+        /// Set once the child is reaped; its pid may be reused after that.
+        exit: Option<i32>,
         _cols: u16,
         _rows: u16,
+        pub(super) integrated: bool,
+    }
+
+    /// Our environment with `extra` set on top, as `execve` wants it.
+    fn child_env(extra: &[(OsString, OsString)]) -> Vec<CString> {
+        std::env::vars_os()
+            .filter(|(key, _)| !extra.iter().any(|(k, _)| k == key))
+            .chain(extra.iter().cloned())
+            .filter_map(|(key, value)| {
+                let mut pair = key.as_bytes().to_vec();
+                pair.push(b'=');
+                pair.extend_from_slice(value.as_bytes());
+                CString::new(pair).ok()
+            })
+            .collect()
     }
 
     impl UnixPty {
-        pub fn new(cols: u16, rows: u16) -> Result<Self> {
+        pub fn new(cols: u16, rows: u16, shell: Option<&str>, env: &[(OsString, OsString)]) -> Result<Self> {
             eprintln!("[UNIX_PTY] Opening PTY");
 
             let winsize = nix::pty::Winsize {
@@ -298,6 +420,15 @@ trap 'printf "\e]133;B\a"' DEBUG
                 ws_ypixel: 0,
             };
 
+            // Resolved before forking so the parent knows whether the
+            // integration (and with it the cwd probe) is active.
+            let shell = match shell {
+                Some(shell) => shell.to_string(),
+                None => std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string()),
+            };
+            let rc = if shell.ends_with("bash") { ensure_bash_rc().ok() } else { None };
+            let envp = child_env(env);
+
             let pty_result = openpty(Some(&winsize), None).context("Failed to open PTY")?;
             let master_fd = pty_result.master;
             let slave_fd = pty_result.slave;
@@ -308,8 +439,10 @@ trap 'printf "\e]133;B\a"' DEBUG
                     Ok(Self {
                         master_fd,
                         child_pid: child,
+                        exit: None,
                         _cols: cols,
                         _rows: rows,
+                        integrated: rc.is_some(),
                     })
                 }
                 ForkResult::Child => {
@@ -323,26 +456,24 @@ trap 'printf "\e]133;B\a"' DEBUG
                         nix::unistd::close(slave_fd).ok();
                     }
 
-                    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
-
-                    if shell.ends_with("bash") {
-                        if let Ok(rc) = ensure_bash_rc() {
-                            let bash = CString::new(shell.as_str()).unwrap();
-                            let arg0 = bash.clone();
-                            let i = CString::new("-i").unwrap();
-                            let noprofile = CString::new("--noprofile").unwrap();
-                            let rcfile = CString::new("--rcfile").unwrap();
-                            let rcpath = CString::new(rc.to_string_lossy().to_string()).unwrap();
-                            nix::unistd::execv(
-                                &bash,
-                                &[&arg0, &i, &noprofile, &rcfile, &rcpath],
-                            )
-                                .expect("exec bash failed");
-                        }
+                    if let Some(rc) = &rc {
+                        let bash = CString::new(shell.as_str()).unwrap();
+                        let arg0 = bash.clone();
+                        let i = CString::new("-i").unwrap();
+                        let noprofile = CString::new("--noprofile").unwrap();
+                        let rcfile = CString::new("--rcfile").unwrap();
+                        let rcpath = CString::new(rc.to_string_lossy().to_string()).unwrap();
+                        // Long options must come before `-i`.
+                        nix::unistd::execve(
+                            &bash,
+                            &[&arg0, &noprofile, &rcfile, &rcpath, &i],
+                            &envp,
+                        )
+                            .expect("exec bash failed");
                     }
 
                     let c_shell = CString::new(shell.as_str()).unwrap();
-                    nix::unistd::execv(&c_shell, &[&c_shell]).expect("exec failed");
+                    nix::unistd::execve(&c_shell, &[&c_shell], &envp).expect("exec failed");
                     std::process::exit(1);
                 }
             }
@@ -372,25 +503,29 @@ trap 'printf "\e]133;B\a"' DEBUG
             self.write_line(&cmd)
         }
 
-        pub fn child_is_alive(&mut self) -> bool {
-            use nix::sys::wait::{waitpid, WaitPidFlag};
-            matches!(
-                waitpid(self.child_pid, Some(WaitPidFlag::WNOHANG)),
-                Ok(nix::sys::wait::WaitStatus::StillAlive)
-            )
+        pub fn exit_code(&mut self) -> Option<i32> {
+            use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+            if self.exit.is_none() {
+                self.exit = match waitpid(self.child_pid, Some(WaitPidFlag::WNOHANG)) {
+                    Ok(WaitStatus::Exited(_, code)) => Some(code),
+                    Ok(WaitStatus::Signaled(_, signal, _)) => Some(128 + signal as i32),
+                    _ => None,
+                };
+            }
+            self.exit
         }
 
-        pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Vec<u8>>> {
+        pub fn start_reader(&mut self) -> Result<mpsc::Receiver<Bytes>> {
             let fd = self.master_fd;
             let (tx, rx) = mpsc::channel(256);
 
             std::thread::spawn(move || {
-                let mut buf = vec![0u8; 8192];
+                let mut pool = ReadPool::new();
                 loop {
-                    match nix::unistd::read(fd, &mut buf) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                    match pool.read_with(|buf| nix::unistd::read(fd, buf)) {
+                        Ok(chunk) if chunk.is_empty() => break,
+                        Ok(chunk) => {
+                            if tx.blocking_send(chunk).is_err() {
                                 break;
                             }
                         }
@@ -406,7 +541,9 @@ trap 'printf "\e]133;B\a"' DEBUG
     impl Drop for UnixPty {
         fn drop(&mut self) {
             nix::unistd::close(self.master_fd).ok();
-            let _ = nix::sys::signal::kill(self.child_pid, nix::sys::signal::SIGTERM);
+            if self.exit_code().is_none() {
+                let _ = nix::sys::signal::kill(self.child_pid, nix::sys::signal::SIGTERM);
+            }
         }
     }
 }
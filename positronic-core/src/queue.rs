@@ -0,0 +1,228 @@
+//! `!queue`: commands to run one after another, each once the one before
+//! has finished.
+//!
+//! `!queue add` appends to a list kept in the vault, and `!queue run`
+//! works through it in order. A `!` entry is done when it returns; a shell
+//! entry is sent through the usual pipeline (aliases, hooks, `!setenv`)
+//! and is done when shell integration reports its exit code, which
+//! `Runner::commands_finished` passes on. The run stops at the first
+//! failure unless `--keep-going`.
+//!
+//! Where a run is up to is saved before each entry starts, so one cut
+//! short by a failure or by closing Positronic can be picked up with
+//! `!queue resume`, starting again at the entry that didn't finish.
+//!
+//! `QueueRun` is that bookkeeping alone: it names the entry to run next
+//! and takes each one's exit code back, and knows nothing of the PTY.
+
+use crate::danger::DangerAnalyzer;
+use crate::pick::counted;
+
+pub const QUEUE_USAGE: &str =
+    "Usage: !queue add <command> | run [--keep-going] [--yes] | resume [--yes] | list | rm <n> | clear";
+
+/// Most commands the queue holds.
+pub const MAX_QUEUED: usize = 100;
+
+/// What follows `!queue`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueCommand {
+    /// `!queue` alone, or `!queue list`.
+    List,
+    Add(String),
+    Run { keep_going: bool, yes: bool },
+    Resume { yes: bool },
+    /// Remove entry `n`, counting from 1.
+    Remove(usize),
+    Clear,
+}
+
+impl QueueCommand {
+    /// Parse what follows `!queue`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<QueueCommand, String> {
+        let args = args.trim();
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["list"] => Ok(QueueCommand::List),
+            ["add", ..] => {
+                let command = args["add".len()..].trim();
+                if command.is_empty() {
+                    return Err(QUEUE_USAGE.to_string());
+                }
+                if command.split_whitespace().next() == Some("!queue") {
+                    return Err("❌ A queued command can't be !queue itself".to_string());
+                }
+                Ok(QueueCommand::Add(command.to_string()))
+            }
+            ["run", flags @ ..] => {
+                let (mut keep_going, mut yes) = (false, false);
+                for flag in flags {
+                    match *flag {
+                        "--keep-going" | "-k" => keep_going = true,
+                        "--yes" | "-y" => yes = true,
+                        _ => return Err(QUEUE_USAGE.to_string()),
+                    }
+                }
+                Ok(QueueCommand::Run { keep_going, yes })
+            }
+            ["resume"] => Ok(QueueCommand::Resume { yes: false }),
+            ["resume", "--yes" | "-y"] => Ok(QueueCommand::Resume { yes: true }),
+            ["rm", n] => match n.trim_start_matches('#').parse::<usize>() {
+                Ok(n) if n > 0 => Ok(QueueCommand::Remove(n)),
+                _ => Err(format!("❌ Bad entry number '{}'", n)),
+            },
+            ["clear"] => Ok(QueueCommand::Clear),
+            _ => Err(QUEUE_USAGE.to_string()),
+        }
+    }
+}
+
+/// How far a run has got. Saved before each entry starts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueProgress {
+    /// The entry running, or to run next, counting from 0.
+    pub next: usize,
+    pub keep_going: bool,
+    /// Entries that failed under `--keep-going`, counting from 0.
+    pub failed: Vec<usize>,
+}
+
+/// What a run needs done next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueStep {
+    /// Run entry `index`, once its progress is saved; `QueueRun::finished`
+    /// takes its exit code.
+    Run { index: usize, command: String },
+    /// The run is over. `resumable`: it stopped at a failure, and the saved
+    /// progress stays for `!queue resume`; otherwise it goes.
+    Done { lines: Vec<String>, resumable: bool },
+}
+
+/// A run through the queue's commands.
+#[derive(Debug, Clone)]
+pub struct QueueRun {
+    commands: Vec<String>,
+    progress: QueueProgress,
+    /// An entry has been handed out and hasn't finished.
+    running: bool,
+}
+
+impl QueueRun {
+    pub fn new(commands: Vec<String>, keep_going: bool) -> Self {
+        Self::resume(commands, QueueProgress { keep_going, ..QueueProgress::default() })
+    }
+
+    /// Carry on from saved `progress`, with the entry it was at.
+    pub fn resume(commands: Vec<String>, progress: QueueProgress) -> Self {
+        Self { commands, progress, running: false }
+    }
+
+    pub fn progress(&self) -> &QueueProgress {
+        &self.progress
+    }
+
+    /// The entries from the one to run next on.
+    pub fn remaining(&self) -> &[String] {
+        self.commands.get(self.progress.next..).unwrap_or(&[])
+    }
+
+    /// The entry handed out and not finished yet.
+    pub fn running(&self) -> Option<usize> {
+        self.running.then_some(self.progress.next)
+    }
+
+    /// `▶ 2/3: cargo test`.
+    pub fn progress_line(&self, index: usize) -> String {
+        format!("▶ {}/{}: {}", index + 1, self.commands.len(), self.commands[index])
+    }
+
+    /// Start the run, or go on with it: the next entry, or the summary.
+    pub fn next_step(&mut self) -> QueueStep {
+        match self.commands.get(self.progress.next) {
+            Some(command) => {
+                self.running = true;
+                QueueStep::Run { index: self.progress.next, command: command.clone() }
+            }
+            None => {
+                self.running = false;
+                QueueStep::Done { lines: vec![self.summary()], resumable: false }
+            }
+        }
+    }
+
+    /// The entry handed out ended with `exit`. No exit code (a shell that
+    /// didn't say) counts as success.
+    pub fn finished(&mut self, exit: Option<i32>) -> QueueStep {
+        self.running = false;
+        let index = self.progress.next;
+        if let Some(code) = exit.filter(|code| *code != 0) {
+            if !self.progress.keep_going {
+                return QueueStep::Done {
+                    lines: vec![
+                        format!("❌ Queue stopped at {}/{}: exit {}", index + 1, self.commands.len(), code),
+                        "   !queue resume runs it again and carries on".to_string(),
+                    ],
+                    resumable: true,
+                };
+            }
+            self.progress.failed.push(index);
+        }
+        self.progress.next += 1;
+        self.next_step()
+    }
+
+    fn summary(&self) -> String {
+        let ran = counted(self.commands.len(), "command", "commands");
+        if self.progress.failed.is_empty() {
+            return format!("✅ Queue done: {} ran", ran);
+        }
+        let failed: Vec<String> = self.progress.failed.iter().map(|i| (i + 1).to_string()).collect();
+        format!("⚠️ Queue done: {} ran, {} failed ({})", ran, failed.len(), failed.join(", "))
+    }
+}
+
+/// `  2. rm -rf build — destructive (…)` for each destructive entry still
+/// to run.
+pub fn destructive_lines(run: &QueueRun) -> Vec<String> {
+    let first = run.progress.next;
+    let mut lines = Vec::new();
+    for (i, command) in run.remaining().iter().enumerate() {
+        let danger = DangerAnalyzer::analyze(command);
+        if danger.is_destructive() {
+            lines.push(format!(
+                "  {:>3}. {} — destructive ({})",
+                first + i + 1,
+                command,
+                danger.reason.unwrap_or("matches a destructive pattern")
+            ));
+        }
+    }
+    lines
+}
+
+/// The `!queue list` output, with where a stopped run is up to.
+pub fn list_lines(commands: &[String], progress: Option<&QueueProgress>) -> Vec<String> {
+    if commands.is_empty() {
+        return vec!["📋 The queue is empty; !queue add <command> adds one".to_string()];
+    }
+    let mut lines = vec![format!("📋 Queue ({}):", commands.len())];
+    for (i, command) in commands.iter().enumerate() {
+        lines.push(format!("  {:>3}. {}", i + 1, command));
+    }
+    if let Some(offer) = progress.and_then(|p| resume_offer(commands, p)) {
+        lines.push(String::new());
+        lines.push(offer);
+    }
+    lines
+}
+
+/// The offer to pick up a stopped run, if it has anything left to run.
+pub fn resume_offer(commands: &[String], progress: &QueueProgress) -> Option<String> {
+    let command = commands.get(progress.next)?;
+    Some(format!(
+        "⏸ A queue run stopped at {}/{} ({}); !queue resume carries on",
+        progress.next + 1,
+        commands.len(),
+        command
+    ))
+}
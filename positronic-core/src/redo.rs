@@ -0,0 +1,223 @@
+//! `!redo`: run a history entry again, optionally in the directory it
+//! originally ran in.
+//!
+//! `!redo 42` or `!redo cargo test` (newest match) looks the entry up.
+//! When it ran somewhere other than the current directory the user picks:
+//! run it here, `cd` there first, or cancel, with one key or with
+//! `--here`/`--there`. A directory that no longer exists leaves only
+//! "here". Destructive commands always ask.
+//!
+//! The line that runs is noted in the vault, so its history row points at
+//! the original through `redo_of` and `!stats` can count re-runs.
+
+use std::path::Path;
+
+pub const REDO_USAGE: &str = "Usage: !redo <history-id|search> [--here|--there]";
+
+/// Which shell the compound `cd` line is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedoShell {
+    /// bash, zsh and other POSIX-style shells: `cd "dir" && cmd`.
+    Posix,
+    /// `cd -LiteralPath "dir" -ErrorAction Stop; cmd`.
+    PowerShell,
+}
+
+impl RedoShell {
+    /// The shell the PTY runs: PowerShell on Windows, `$SHELL` elsewhere.
+    pub fn detect() -> Self {
+        if cfg!(windows) {
+            return RedoShell::PowerShell;
+        }
+        match std::env::var("SHELL") {
+            Ok(shell) if shell.ends_with("pwsh") || shell.ends_with("powershell") => RedoShell::PowerShell,
+            _ => RedoShell::Posix,
+        }
+    }
+
+    /// `dir` as a double-quoted word for this shell.
+    pub fn quote(self, dir: &str) -> String {
+        let mut out = String::with_capacity(dir.len() + 2);
+        out.push('"');
+        for c in dir.chars() {
+            match (self, c) {
+                (RedoShell::Posix, '"' | '\\' | '$' | '`') => out.push('\\'),
+                (RedoShell::PowerShell, '"' | '$' | '`') => out.push('`'),
+                _ => {}
+            }
+            out.push(c);
+        }
+        out.push('"');
+        out
+    }
+
+    /// Change to `dir`, then run `command` only if that worked.
+    pub fn cd_and_run(self, dir: &str, command: &str) -> String {
+        match self {
+            RedoShell::Posix => format!("cd {} && {}", self.quote(dir), command),
+            // `;` is PowerShell's separator; a terminating cd error stops the line.
+            RedoShell::PowerShell => format!("cd -LiteralPath {} -ErrorAction Stop; {}", self.quote(dir), command),
+        }
+    }
+}
+
+/// Where a re-run goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedoChoice {
+    /// The current directory.
+    Here,
+    /// `cd` to the original directory first.
+    There,
+}
+
+/// What to re-run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedoTarget {
+    Id(i64),
+    /// The newest history entry containing this text.
+    Search(String),
+}
+
+/// A parsed `!redo` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedoRequest {
+    pub target: RedoTarget,
+    pub choice: Option<RedoChoice>,
+}
+
+impl RedoRequest {
+    /// Parse the words after `!redo`; the error is for the user.
+    pub fn parse(args: &str) -> Result<RedoRequest, String> {
+        let mut choice = None;
+        let mut words = Vec::new();
+        for word in args.split_whitespace() {
+            let flag = match word {
+                "--here" => Some(RedoChoice::Here),
+                "--there" => Some(RedoChoice::There),
+                _ => None,
+            };
+            match flag {
+                Some(_) if choice.is_some() => return Err(REDO_USAGE.to_string()),
+                Some(flag) => choice = Some(flag),
+                None => words.push(word),
+            }
+        }
+        let target = match words.as_slice() {
+            [] => return Err(REDO_USAGE.to_string()),
+            [id] if id.trim_start_matches('#').parse::<i64>().is_ok() => {
+                RedoTarget::Id(id.trim_start_matches('#').parse().unwrap_or_default())
+            }
+            _ => RedoTarget::Search(words.join(" ")),
+        };
+        Ok(RedoRequest { target, choice })
+    }
+}
+
+/// A history entry about to run again, and what the user may choose.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedoOffer {
+    pub id: i64,
+    pub command: String,
+    /// Where it ran.
+    pub directory: String,
+    /// The tracked working directory, if known.
+    pub current: Option<String>,
+    pub directory_exists: bool,
+    /// Why the command is destructive, if it is.
+    pub danger: Option<String>,
+}
+
+impl RedoOffer {
+    pub fn new(id: i64, command: &str, directory: &str, current: Option<String>, danger: Option<String>) -> Self {
+        Self {
+            id,
+            command: command.to_string(),
+            directory: directory.to_string(),
+            current,
+            directory_exists: Path::new(directory).is_dir(),
+            danger,
+        }
+    }
+
+    /// Whether it ran where we are now. An unknown original directory
+    /// (`.`) counts as here: there is nowhere else to go.
+    pub fn is_here(&self) -> bool {
+        self.directory.is_empty()
+            || self.directory == "."
+            || self.current.as_deref().is_some_and(|cwd| same_directory(cwd, &self.directory))
+    }
+
+    /// Whether `choice` can run: `cd` into a directory that is gone can't.
+    pub fn check(&self, choice: RedoChoice) -> Result<(), String> {
+        if choice == RedoChoice::There && !self.is_here() && !self.directory_exists {
+            return Err(format!(
+                "❌ {} no longer exists; !redo {} --here runs it in the current directory",
+                self.directory, self.id
+            ));
+        }
+        Ok(())
+    }
+
+    /// The choice to act on now, or `None` when the user has to pick.
+    /// `requested` comes from `--here`/`--there`; a destructive command
+    /// asks anyway.
+    pub fn decide(&self, requested: Option<RedoChoice>) -> Result<Option<RedoChoice>, String> {
+        if let Some(choice) = requested {
+            self.check(choice)?;
+        }
+        if self.danger.is_some() {
+            return Ok(None);
+        }
+        Ok(requested.or(self.is_here().then_some(RedoChoice::Here)))
+    }
+
+    /// What pressing `key` means: a choice, or `Some(None)` to cancel.
+    /// Keys that mean nothing here give `None`.
+    pub fn choice_for_key(&self, key: &str) -> Option<Option<RedoChoice>> {
+        match key {
+            "a" | "A" => Some(Some(RedoChoice::Here)),
+            "b" | "B" if self.directory_exists && !self.is_here() => Some(Some(RedoChoice::There)),
+            "c" | "C" => Some(None),
+            _ => None,
+        }
+    }
+
+    /// The line to send for `choice`.
+    pub fn line(&self, choice: RedoChoice, shell: RedoShell) -> String {
+        match choice {
+            RedoChoice::There if !self.is_here() => shell.cd_and_run(&self.directory, &self.command),
+            _ => self.command.clone(),
+        }
+    }
+
+    /// The prompt.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("↻ #{}  {}", self.id, self.command)];
+        if let Some(reason) = &self.danger {
+            lines.push(format!("  ⚠️ Destructive ({}); check before running it again", reason));
+        }
+        let here = self.current.as_deref().unwrap_or("an unknown directory");
+        if self.is_here() {
+            lines.push(format!("  Ran here ({})", here));
+            lines.push("  [a] run it   [c] cancel".to_string());
+        } else if self.directory_exists {
+            lines.push(format!("  Ran in {} (you are in {})", self.directory, here));
+            lines.push("  [a] run here   [b] cd there first   [c] cancel".to_string());
+        } else {
+            lines.push(format!("  Ran in {}, which no longer exists", self.directory));
+            lines.push("  [a] run here   [c] cancel".to_string());
+        }
+        lines
+    }
+}
+
+/// Whether two directory strings name the same place: trailing separators
+/// are ignored, and on Windows so are case and `/` versus `\`.
+pub fn same_directory(a: &str, b: &str) -> bool {
+    let normalize = |dir: &str| {
+        let dir = if cfg!(windows) { dir.replace('/', "\\").to_lowercase() } else { dir.to_string() };
+        let trimmed = dir.trim_end_matches(['/', '\\']);
+        if trimmed.is_empty() || trimmed.ends_with(':') { dir } else { trimmed.to_string() }
+    };
+    normalize(a) == normalize(b)
+}
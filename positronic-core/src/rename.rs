@@ -0,0 +1,656 @@
+//! `!rename`: bulk renames, previewed before anything moves.
+//!
+//! `!rename <glob> <pattern>` matches names in the shell's working
+//! directory (a glob may reach into subdirectories, never above it) and
+//! works out each new name from the pattern: `{name}`, `{stem}`, `{ext}`,
+//! `{date}` (today, `2026-10-17`) and `{n}`, a counter from 1 in match
+//! order that `{n:3}` pads to three digits. `--regex 's/old/new/'` edits
+//! each name instead, with `g` and `i` flags and `$1` or `\1` groups. A
+//! file keeps its directory; a new name with a separator in it is refused.
+//!
+//! The preview is a table of old → new, with collisions (two files given
+//! one name, or a name that is already taken) and unchanged names marked.
+//! `!rename apply` carries it out, or `--yes` on the first line when there
+//! is nothing to flag. Every file moves to a temporary name first and
+//! then to its new one, so chains and swaps (`a → b`, `b → a`) work, as
+//! do case-only renames on file systems that ignore case.
+//!
+//! What was renamed goes into `renames.json` next to the vault, and
+//! `!rename undo` puts the last batch back. Errors are per file: the rest
+//! of a batch still goes ahead.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::alias::{split_args, unquote};
+use crate::native::{Cell, ColumnKind, DataFrame, NativeOutput};
+
+pub const RENAME_USAGE: &str =
+    "Usage: !rename <glob> <pattern> [--yes] | <glob> --regex 's/old/new/' [--yes] | apply | undo";
+
+/// The undo log, next to the vault.
+pub const RENAME_LOG: &str = "renames.json";
+
+/// Batches the undo log keeps; older ones are dropped.
+const MAX_LOGGED: usize = 20;
+
+/// Renamed files listed under a result before "… and N more".
+const SHOWN: usize = 20;
+
+// ════════════════════════════════════════════════════════════════════
+// Command
+// ════════════════════════════════════════════════════════════════════
+
+/// A parsed `!rename` line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameCommand {
+    Preview { glob: String, rule: RenameRule, yes: bool },
+    Apply,
+    Undo,
+}
+
+impl RenameCommand {
+    /// Parse what follows `!rename`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<RenameCommand, String> {
+        let words: Vec<String> = split_args(args).iter().map(|w| unquote(w)).collect();
+        match words.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+            ["apply"] => return Ok(RenameCommand::Apply),
+            ["undo"] => return Ok(RenameCommand::Undo),
+            [] | ["apply" | "undo", ..] => return Err(RENAME_USAGE.to_string()),
+            _ => {}
+        }
+        let mut positional = Vec::new();
+        let mut regex = None;
+        let mut yes = false;
+        let mut words = words.into_iter();
+        while let Some(word) = words.next() {
+            match word.as_str() {
+                "--yes" | "-y" => yes = true,
+                "--regex" | "-r" => regex = Some(words.next().ok_or(RENAME_USAGE)?),
+                _ => positional.push(word),
+            }
+        }
+        let rule = match (regex, positional.len()) {
+            (Some(expr), 1) => RenameRule::parse_sed(&expr)?,
+            (None, 2) => RenameRule::parse_pattern(&positional[1])?,
+            _ => return Err(RENAME_USAGE.to_string()),
+        };
+        Ok(RenameCommand::Preview { glob: positional.remove(0), rule, yes })
+    }
+}
+
+/// One part of a `{…}` pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece {
+    Text(String),
+    Name,
+    Stem,
+    Ext,
+    Date,
+    /// `{n}`, or `{n:3}` zero-padded to `width`.
+    Counter { width: usize },
+}
+
+/// How new names are made.
+#[derive(Debug, Clone)]
+pub enum RenameRule {
+    Pattern(Vec<Piece>),
+    /// `s/old/new/[gi]`: `replacement` in regex-crate syntax.
+    Regex { regex: Regex, replacement: String, global: bool },
+}
+
+impl PartialEq for RenameRule {
+    fn eq(&self, other: &RenameRule) -> bool {
+        match (self, other) {
+            (RenameRule::Pattern(a), RenameRule::Pattern(b)) => a == b,
+            (
+                RenameRule::Regex { regex: a, replacement: ra, global: ga },
+                RenameRule::Regex { regex: b, replacement: rb, global: gb },
+            ) => a.as_str() == b.as_str() && ra == rb && ga == gb,
+            _ => false,
+        }
+    }
+}
+
+impl RenameRule {
+    /// `{stem}-{n:2}.{ext}`. `{{` and `}}` are literal braces.
+    pub fn parse_pattern(text: &str) -> Result<RenameRule, String> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("❌ Unclosed {{ in '{}'", text)),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(parse_placeholder(&name)?);
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Text(literal));
+        }
+        if pieces.is_empty() {
+            return Err(RENAME_USAGE.to_string());
+        }
+        Ok(RenameRule::Pattern(pieces))
+    }
+
+    /// `s/old/new/` with optional `g` and `i` flags; any punctuation may
+    /// stand in for `/`, and `\/` is a literal one.
+    pub fn parse_sed(text: &str) -> Result<RenameRule, String> {
+        let bad = || format!("❌ Bad substitution '{}' (e.g. 's/old/new/g')", text);
+        let mut chars = text.chars();
+        if chars.next() != Some('s') {
+            return Err(bad());
+        }
+        let delimiter = chars.next().filter(|c| c.is_ascii_punctuation() && *c != '\\').ok_or_else(bad)?;
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                    Some(next) => parts.last_mut().unwrap().extend(['\\', next]),
+                    None => return Err(bad()),
+                },
+                c if c == delimiter => parts.push(String::new()),
+                c => parts.last_mut().unwrap().push(c),
+            }
+        }
+        let [find, replace, flags] = <[String; 3]>::try_from(parts).map_err(|_| bad())?;
+        if find.is_empty() || flags.chars().any(|f| !matches!(f, 'g' | 'i')) {
+            return Err(bad());
+        }
+        let source = if flags.contains('i') { format!("(?i){}", find) } else { find };
+        let regex = Regex::new(&source).map_err(|e| format!("❌ Bad regex: {}", e))?;
+        Ok(RenameRule::Regex { regex, replacement: sed_groups(&replace), global: flags.contains('g') })
+    }
+
+    /// The new name for `name`, the `n`th file matched (from 1).
+    pub fn apply(&self, name: &str, n: usize, date: &str) -> String {
+        match self {
+            RenameRule::Pattern(pieces) => {
+                let path = Path::new(name);
+                let stem = path.file_stem().map_or(name.into(), |s| s.to_string_lossy());
+                let ext = path.extension().map(|e| e.to_string_lossy()).unwrap_or_default();
+                let mut out = String::new();
+                for piece in pieces {
+                    match piece {
+                        Piece::Text(text) => out.push_str(text),
+                        Piece::Name => out.push_str(name),
+                        Piece::Stem => out.push_str(&stem),
+                        // `{stem}.{ext}` on a name without one: no trailing dot.
+                        Piece::Ext if ext.is_empty() && out.ends_with('.') => {
+                            out.pop();
+                        }
+                        Piece::Ext => out.push_str(&ext),
+                        Piece::Date => out.push_str(date),
+                        Piece::Counter { width } => out.push_str(&format!("{:0width$}", n, width = *width)),
+                    }
+                }
+                out
+            }
+            RenameRule::Regex { regex, replacement, global: true } => {
+                regex.replace_all(name, replacement.as_str()).into_owned()
+            }
+            RenameRule::Regex { regex, replacement, global: false } => {
+                regex.replace(name, replacement.as_str()).into_owned()
+            }
+        }
+    }
+}
+
+fn parse_placeholder(name: &str) -> Result<Piece, String> {
+    match name {
+        "name" => Ok(Piece::Name),
+        "stem" => Ok(Piece::Stem),
+        "ext" => Ok(Piece::Ext),
+        "date" => Ok(Piece::Date),
+        "n" => Ok(Piece::Counter { width: 1 }),
+        _ => match name.strip_prefix("n:").map(str::parse::<usize>) {
+            Some(Ok(width)) if (1..=12).contains(&width) => Ok(Piece::Counter { width }),
+            Some(_) => Err(format!("❌ Bad counter {{{}}}; {{n:3}} pads to 3 digits", name)),
+            None => Err(format!("❌ Unknown placeholder {{{}}}; use {{name}}, {{stem}}, {{ext}}, {{n}} or {{date}}", name)),
+        },
+    }
+}
+
+/// sed's `\1` as the regex crate's `${1}`; a `$` stays as written.
+fn sed_groups(replacement: &str) -> String {
+    let mut out = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(d)) if d.is_ascii_digit() => {
+                out.push_str(&format!("${{{}}}", d));
+                chars.next();
+            }
+            ('\\', Some(_)) => out.extend(chars.next()),
+            (c, _) => out.push(c),
+        }
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Plan
+// ════════════════════════════════════════════════════════════════════
+
+/// What the preview says about one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Rename,
+    Unchanged,
+    /// Another file gets the same name, or it is taken; why.
+    Collision(String),
+    /// The pattern made something that isn't a file name.
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub outcome: Outcome,
+}
+
+/// A previewed `!rename`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenamePlan {
+    pub entries: Vec<PlannedRename>,
+}
+
+impl RenamePlan {
+    /// Work out each file's new name. `exists` says whether a path is
+    /// taken on disk.
+    pub fn new(sources: &[PathBuf], rule: &RenameRule, date: &str, exists: impl Fn(&Path) -> bool) -> RenamePlan {
+        let mut entries: Vec<PlannedRename> = sources
+            .iter()
+            .enumerate()
+            .map(|(i, from)| {
+                let Some(name) = from.file_name().and_then(|n| n.to_str()) else {
+                    return PlannedRename {
+                        from: from.clone(),
+                        to: from.clone(),
+                        outcome: Outcome::Invalid("the name isn't valid UTF-8".to_string()),
+                    };
+                };
+                let new = rule.apply(name, i + 1, date);
+                let to = from.with_file_name(&new);
+                let outcome = if new.is_empty() || new == "." || new == ".." || new.chars().any(std::path::is_separator) {
+                    Outcome::Invalid(format!("'{}' isn't a file name", new))
+                } else if new == name {
+                    Outcome::Unchanged
+                } else {
+                    Outcome::Rename
+                };
+                PlannedRename { from: from.clone(), to, outcome }
+            })
+            .collect();
+
+        // Every name the batch leaves behind, and what it frees up.
+        let targets: Vec<(usize, String)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| matches!(e.outcome, Outcome::Rename | Outcome::Unchanged))
+            .map(|(i, e)| (i, fold(&e.to)))
+            .collect();
+        let freed: Vec<String> =
+            entries.iter().filter(|e| e.outcome == Outcome::Rename).map(|e| fold(&e.from)).collect();
+        for i in 0..entries.len() {
+            if entries[i].outcome != Outcome::Rename {
+                continue;
+            }
+            let target = fold(&entries[i].to);
+            let clash = targets.iter().find(|(j, t)| *j != i && *t == target).map(|(j, _)| *j);
+            let collision = match clash {
+                Some(j) if entries[j].outcome == Outcome::Unchanged => {
+                    Some(format!("{} keeps this name", display_name(&entries[j].from)))
+                }
+                Some(j) => Some(format!("{} gets this name too", display_name(&entries[j].from))),
+                // Renaming in place to another case is the same file.
+                None if exists(&entries[i].to) && !freed.contains(&target) => {
+                    Some(format!("{} already exists", display_name(&entries[i].to)))
+                }
+                None => None,
+            };
+            if let Some(reason) = collision {
+                entries[i].outcome = Outcome::Collision(reason);
+            }
+        }
+        RenamePlan { entries }
+    }
+
+    /// The entries to carry out.
+    pub fn renames(&self) -> impl Iterator<Item = &PlannedRename> {
+        self.entries.iter().filter(|e| e.outcome == Outcome::Rename)
+    }
+
+    /// Collisions and invalid names; any one holds up the whole batch.
+    pub fn problems(&self) -> usize {
+        self.entries.iter().filter(|e| matches!(e.outcome, Outcome::Collision(_) | Outcome::Invalid(_))).count()
+    }
+
+    pub fn unchanged(&self) -> usize {
+        self.entries.iter().filter(|e| e.outcome == Outcome::Unchanged).count()
+    }
+}
+
+/// Case-folded where the usual file systems ignore case.
+fn fold(path: &Path) -> String {
+    let text = path.to_string_lossy();
+    if cfg!(any(windows, target_os = "macos")) {
+        text.to_lowercase()
+    } else {
+        text.into_owned()
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// The files `glob` matches under `cwd`, in name order. Hidden files need
+/// a leading `.` in the glob, as in a shell; nothing outside `cwd` can match.
+pub fn expand_glob(glob: &str, cwd: &Path) -> Result<Vec<PathBuf>, String> {
+    let path = Path::new(glob);
+    if path.is_absolute() || path.components().any(|c| matches!(c, Component::ParentDir | Component::Prefix(_))) {
+        return Err(format!("❌ '{}' reaches outside the working directory; !rename stays in it", glob));
+    }
+    let pattern = Path::new(&glob::Pattern::escape(&cwd.to_string_lossy())).join(glob);
+    let options = glob::MatchOptions { require_literal_leading_dot: true, ..glob::MatchOptions::new() };
+    let mut found: Vec<PathBuf> = glob::glob_with(&pattern.to_string_lossy(), options)
+        .map_err(|e| format!("❌ Bad pattern '{}': {}", glob, e.msg))?
+        .filter_map(|entry| entry.ok())
+        .collect();
+    found.sort();
+    found.dedup();
+    if found.is_empty() {
+        return Err(format!("❌ Nothing matches '{}'", glob));
+    }
+    Ok(found)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Undo log
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameLog {
+    /// The id the next batch gets.
+    pub next_id: u64,
+    /// Oldest first.
+    pub operations: Vec<RenameOp>,
+}
+
+/// One applied `!rename`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenameOp {
+    pub id: u64,
+    /// Unix seconds.
+    pub renamed_at: i64,
+    pub items: Vec<RenamedItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenamedItem {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl RenameLog {
+    /// A missing log is an empty one.
+    pub fn load(path: &Path) -> Result<RenameLog> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("{} is damaged", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(RenameLog::default()),
+            Err(e) => Err(e).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    /// Write through a temporary file so a crash leaves the old log.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Renames
+// ════════════════════════════════════════════════════════════════════
+
+/// The result of applying a batch, or undoing one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renamed {
+    pub id: u64,
+    /// What moved, as `from → to`.
+    pub items: Vec<RenamedItem>,
+    /// Why the rest didn't; those files are where they were.
+    pub failed: Vec<String>,
+}
+
+/// The undo log and the preview waiting for `!rename apply`. Clones
+/// share both.
+#[derive(Debug, Clone)]
+pub struct Renames {
+    log: PathBuf,
+    lock: Arc<Mutex<()>>,
+    pending: Arc<Mutex<Option<RenamePlan>>>,
+}
+
+impl Renames {
+    pub fn new(log: impl Into<PathBuf>) -> Self {
+        Self { log: log.into(), lock: Arc::new(Mutex::new(())), pending: Arc::new(Mutex::new(None)) }
+    }
+
+    pub fn log(&self) -> Result<RenameLog> {
+        RenameLog::load(&self.log)
+    }
+
+    /// Keep `plan` for `!rename apply`, or with `None` drop the last one.
+    pub fn set_pending(&self, plan: Option<RenamePlan>) {
+        *self.pending.lock().unwrap_or_else(|e| e.into_inner()) = plan;
+    }
+
+    pub fn take_pending(&self) -> Option<RenamePlan> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    /// Carry out `plan`'s renames and log them as one batch.
+    pub fn apply(&self, plan: &RenamePlan, now: i64) -> Result<Renamed> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.log()?;
+        let id = log.next_id.max(1);
+        let pairs: Vec<RenamedItem> =
+            plan.renames().map(|e| RenamedItem { from: e.from.clone(), to: e.to.clone() }).collect();
+        let (items, failed) = rename_all(&pairs);
+        if !items.is_empty() {
+            log.next_id = id + 1;
+            log.operations.push(RenameOp { id, renamed_at: now, items: items.clone() });
+            let excess = log.operations.len().saturating_sub(MAX_LOGGED);
+            log.operations.drain(..excess);
+            log.save(&self.log)?;
+        }
+        Ok(Renamed { id, items, failed })
+    }
+
+    /// Put the last batch back. Files that can't go back stay in the log
+    /// for another try.
+    pub fn undo(&self) -> Result<Renamed> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut log = self.log()?;
+        let Some(op) = log.operations.pop() else {
+            bail!("nothing to undo");
+        };
+        let back: Vec<RenamedItem> =
+            op.items.iter().rev().map(|item| RenamedItem { from: item.to.clone(), to: item.from.clone() }).collect();
+        let (items, failed) = rename_all(&back);
+        let remaining: Vec<RenamedItem> =
+            op.items.iter().filter(|item| !items.iter().any(|done| done.to == item.from)).cloned().collect();
+        if !remaining.is_empty() {
+            log.operations.push(RenameOp { items: remaining, ..op.clone() });
+        }
+        log.save(&self.log)?;
+        Ok(Renamed { id: op.id, items, failed })
+    }
+}
+
+/// Rename each `from` to `to`: all of them to a temporary name beside
+/// them first, then each to its new name, so no rename in the batch lands
+/// on a name another has yet to leave. A file whose new name turns out to
+/// be taken goes back where it was.
+fn rename_all(pairs: &[RenamedItem]) -> (Vec<RenamedItem>, Vec<String>) {
+    let mut failed = Vec::new();
+    let mut staged = Vec::new();
+    for (i, pair) in pairs.iter().enumerate() {
+        let temp = pair.from.with_file_name(format!(".positronic-rename-{}-{}", std::process::id(), i));
+        match fs::rename(&pair.from, &temp) {
+            Ok(()) => staged.push((pair, temp)),
+            Err(e) => failed.push(format!("{}: {}", pair.from.display(), e)),
+        }
+    }
+    let mut done = Vec::new();
+    for (pair, temp) in staged {
+        let result = if fs::symlink_metadata(&pair.to).is_ok() {
+            Err(format!("{} already exists", pair.to.display()))
+        } else {
+            fs::rename(&temp, &pair.to).map_err(|e| format!("{}: {}", pair.to.display(), e))
+        };
+        match result {
+            Ok(()) => done.push(pair.clone()),
+            Err(reason) => {
+                let back = fs::rename(&temp, &pair.from);
+                failed.push(match back {
+                    Ok(()) => format!("{} was left as is: {}", pair.from.display(), reason),
+                    Err(e) => format!("{} is now {}: {} ({})", pair.from.display(), temp.display(), reason, e),
+                });
+            }
+        }
+    }
+    (done, failed)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Output
+// ════════════════════════════════════════════════════════════════════
+
+/// Run a parsed `!rename` with paths relative to `cwd`; `date` fills
+/// `{date}`.
+pub fn command_output(renames: &Renames, command: RenameCommand, cwd: &Path, now: i64, date: &str) -> NativeOutput {
+    match command {
+        RenameCommand::Preview { glob, rule, yes } => {
+            let sources = match expand_glob(&glob, cwd) {
+                Ok(sources) => sources,
+                Err(message) => return NativeOutput::Lines(vec![message]),
+            };
+            let plan = RenamePlan::new(&sources, &rule, date, |p| fs::symlink_metadata(p).is_ok());
+            if yes && plan.problems() == 0 && plan.renames().next().is_some() {
+                renames.set_pending(None);
+                return NativeOutput::Lines(applied_lines(renames.apply(&plan, now), false, cwd));
+            }
+            let output = preview_output(&plan, cwd);
+            let ready = plan.problems() == 0 && plan.renames().next().is_some();
+            renames.set_pending(ready.then_some(plan));
+            output
+        }
+        RenameCommand::Apply => match renames.take_pending() {
+            Some(plan) => NativeOutput::Lines(applied_lines(renames.apply(&plan, now), false, cwd)),
+            None => NativeOutput::Lines(vec!["✏️ Nothing to apply; preview with !rename <glob> <pattern> first".to_string()]),
+        },
+        RenameCommand::Undo => NativeOutput::Lines(applied_lines(renames.undo(), true, cwd)),
+    }
+}
+
+/// `path` relative to `cwd` when it is under it.
+fn shown(path: &Path, cwd: &Path) -> String {
+    path.strip_prefix(cwd).unwrap_or(path).display().to_string()
+}
+
+fn files(n: usize) -> String {
+    format!("{} {}", n, if n == 1 { "file" } else { "files" })
+}
+
+fn preview_output(plan: &RenamePlan, cwd: &Path) -> NativeOutput {
+    let renames = plan.renames().count();
+    let mut summary = vec![files(renames)];
+    if plan.unchanged() > 0 {
+        summary.push(format!("{} unchanged", plan.unchanged()));
+    }
+    if plan.problems() > 0 {
+        summary.push(format!("{} to fix", plan.problems()));
+    }
+    let mut lines = vec![format!("✏️ Rename preview: {}", summary.join(", ")), String::new()];
+    let mut frame =
+        DataFrame::new(&[("old", ColumnKind::Text), ("new", ColumnKind::Text), ("status", ColumnKind::Text)]);
+    for entry in &plan.entries {
+        let (status, note) = match &entry.outcome {
+            Outcome::Rename => ("rename", String::new()),
+            Outcome::Unchanged => ("unchanged", "  (unchanged)".to_string()),
+            Outcome::Collision(reason) => ("collision", format!("  ⚠️ {}", reason)),
+            Outcome::Invalid(reason) => ("invalid", format!("  ⚠️ {}", reason)),
+        };
+        let (old, new) = (shown(&entry.from, cwd), shown(&entry.to, cwd));
+        lines.push(format!("  {} → {}{}", old, new, note));
+        frame.push_row(vec![Cell::text(old), Cell::text(new), Cell::text(status)]);
+    }
+    lines.push(String::new());
+    lines.push(match (plan.problems(), renames) {
+        (0, 0) => "✏️ Nothing to rename".to_string(),
+        (0, _) => "Run `!rename apply` to rename them".to_string(),
+        (n, _) => format!("❌ Nothing is renamed until the {} marked {} fixed", n, if n == 1 { "is" } else { "are" }),
+    });
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// What `Renames::apply` did, or `undo` with `undo`.
+fn applied_lines(result: Result<Renamed>, undo: bool, cwd: &Path) -> Vec<String> {
+    let renamed = match result {
+        Ok(renamed) => renamed,
+        Err(e) => return vec![format!("❌ {:#}", e)],
+    };
+    let mut lines = match (renamed.items.len(), undo) {
+        (0, _) => vec!["❌ Nothing was renamed".to_string()],
+        (n, false) => vec![format!("✏️ Renamed {} as #{}", files(n), renamed.id)],
+        (n, true) => vec![format!("↩️ Put back {} from #{}", files(n), renamed.id)],
+    };
+    for item in renamed.items.iter().take(SHOWN) {
+        lines.push(format!("  {} → {}", shown(&item.from, cwd), shown(&item.to, cwd)));
+    }
+    if renamed.items.len() > SHOWN {
+        lines.push(format!("  … and {} more", renamed.items.len() - SHOWN));
+    }
+    lines.extend(renamed.failed.iter().map(|failure| format!("  ⚠️ {}", failure)));
+    if !undo && !renamed.items.is_empty() {
+        lines.push("  Undo with !rename undo".to_string());
+    }
+    lines
+}
@@ -0,0 +1,149 @@
+//! What happens when the shell dies.
+//!
+//! The engine watches the PTY child (reader EOF, then `waitpid`) and
+//! reports `PtyEvent::ShellExited`. `ShellLife` keeps what the UI needs to
+//! explain it: the exit code, how long the shell lived, and the tail of
+//! its output, since a shell that can't start only says why on the
+//! terminal. A shell that dies within `QUICK_EXIT` of starting, again and
+//! again, is a crash loop: restarts back off, and the banner shows the
+//! output tail.
+
+use std::time::{Duration, Instant};
+
+use crate::headless::plain_text;
+
+/// A shell that exits sooner than this after starting counts as a crash.
+pub const QUICK_EXIT: Duration = Duration::from_secs(1);
+
+/// Quick exits in a row before restarts back off.
+pub const CRASH_LOOP_AFTER: u32 = 2;
+
+/// The first backoff; each further quick exit doubles it.
+pub const BACKOFF_BASE: Duration = Duration::from_secs(1);
+pub const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// Output kept from the running shell, and lines of it shown on a crash.
+const TAIL_BYTES: usize = 4096;
+pub const TAIL_LINES: usize = 10;
+
+/// A shell that has exited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellExit {
+    /// The exit status; 128 + the signal number if it was killed.
+    pub code: i32,
+    pub uptime: Duration,
+    /// Quick exits in a row, this one included.
+    pub quick_exits: u32,
+    /// How long a restart waits.
+    pub backoff: Duration,
+    /// The last lines the shell printed (stdout and stderr share the PTY).
+    pub output: Vec<String>,
+}
+
+impl ShellExit {
+    pub fn crash_loop(&self) -> bool {
+        self.quick_exits >= CRASH_LOOP_AFTER
+    }
+
+    /// The banner the UI shows.
+    pub fn banner_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "💀 Shell exited with code {} — press Enter to restart or !quit to close",
+            self.code
+        )];
+        if self.crash_loop() {
+            lines.push(format!(
+                "⚠️ It exited within {}s of starting {} times in a row; the next start waits {}s",
+                QUICK_EXIT.as_secs(),
+                self.quick_exits,
+                self.backoff.as_secs()
+            ));
+            if !self.output.is_empty() {
+                lines.push("Its last output:".to_string());
+                lines.extend(self.output.iter().map(|line| format!("  {}", line)));
+            }
+        }
+        lines
+    }
+}
+
+/// The running shell's start time and output tail, and its exit once it
+/// has one.
+#[derive(Debug)]
+pub struct ShellLife {
+    started: Instant,
+    quick_exits: u32,
+    tail: Vec<u8>,
+    exit: Option<(ShellExit, Instant)>,
+}
+
+impl ShellLife {
+    pub fn new(now: Instant) -> Self {
+        Self { started: now, quick_exits: 0, tail: Vec::new(), exit: None }
+    }
+
+    /// Keep the end of the shell's output.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.tail.extend_from_slice(bytes);
+        if self.tail.len() > TAIL_BYTES {
+            self.tail.drain(..self.tail.len() - TAIL_BYTES);
+        }
+    }
+
+    /// The shell exited with `code`. `None` if that was already reported.
+    pub fn exited(&mut self, code: i32, now: Instant) -> Option<ShellExit> {
+        if self.exit.is_some() {
+            return None;
+        }
+        let uptime = now.saturating_duration_since(self.started);
+        self.quick_exits = if uptime < QUICK_EXIT { self.quick_exits + 1 } else { 0 };
+        let exit = ShellExit {
+            code,
+            uptime,
+            quick_exits: self.quick_exits,
+            backoff: backoff(self.quick_exits),
+            output: tail_lines(&self.tail),
+        };
+        self.exit = Some((exit.clone(), now));
+        Some(exit)
+    }
+
+    /// The exit, while the shell is dead.
+    pub fn exit(&self) -> Option<&ShellExit> {
+        self.exit.as_ref().map(|(exit, _)| exit)
+    }
+
+    /// How long a restart at `now` has to wait.
+    pub fn restart_delay(&self, now: Instant) -> Duration {
+        match &self.exit {
+            Some((exit, at)) => (*at + exit.backoff).saturating_duration_since(now),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// A new shell started at `now`. The quick-exit count carries over.
+    pub fn restarted(&mut self, now: Instant) {
+        self.started = now;
+        self.tail.clear();
+        self.exit = None;
+    }
+}
+
+fn backoff(quick_exits: u32) -> Duration {
+    if quick_exits < CRASH_LOOP_AFTER {
+        return Duration::ZERO;
+    }
+    let doublings = (quick_exits - CRASH_LOOP_AFTER).min(16);
+    (BACKOFF_BASE * 2u32.pow(doublings)).min(BACKOFF_MAX)
+}
+
+/// The last `TAIL_LINES` non-blank lines of `tail`, without escapes.
+fn tail_lines(tail: &[u8]) -> Vec<String> {
+    let text = plain_text(&String::from_utf8_lossy(tail));
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.trim_end().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines[lines.len().saturating_sub(TAIL_LINES)..].to_vec()
+}
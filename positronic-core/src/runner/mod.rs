@@ -5,18 +5,60 @@
 //!   (previously only cleared the UI buffer).
 //! - `!exit`/`!quit` are new built-in commands for graceful shutdown.
 
-use crate::builtins;
+use crate::alias;
+use crate::blocks::BlockRecorder;
+use crate::cancel::Cancellations;
+use crate::capture::{self, Expanded, Variables};
+use crate::cnf::{self, CnfAdvisor, PackageHint};
+use crate::commands::{self, Registry};
+use crate::completion::{self, CompletionIndex};
+use crate::data_paths::DataPaths;
+use crate::error::{PositronicError, NEURAL_ENDPOINT};
+use crate::file_index::{self, FileIndexes};
+use crate::fix::Correction;
+use crate::history_filter::{parse_flag, SessionOnly};
+use crate::hooks::{self, PendingAfter};
+use crate::fetch::Fetched;
+use crate::http::{HttpExchange, HttpRequest};
+use crate::inspect;
+use crate::maintenance::{self, Activity, Cost, Freshness, Maintenance, MaintenanceTask, Priority, Scheduler, Step};
+use crate::native::DataFrame;
+use crate::pick::{self, PickState};
+use crate::progress::ProgressBoard;
+use crate::queue::{QueueRun, QueueStep};
 use crate::airlock::Airlock;
 use crate::pty_manager::PtyManager;
+use crate::redo::{RedoChoice, RedoOffer, RedoShell};
+use crate::rename::Renames;
+use crate::safe_mode::DisabledKind;
+use crate::subsystems::{Subsystem, Subsystems};
+use crate::term::binary::{guard_enabled, BinaryGuard, BINARY_GUARD_KEY};
+use crate::term::latency::LatencyProbe;
+use crate::term::running::RunningTracker;
+use crate::test_report::TestParsers;
+use crate::scaffold::{NewRequest, Scaffolds};
+use crate::setenv;
+use crate::shell_commands;
+use crate::timeline::{self, TimelineData};
+use crate::tldr::Tldr;
+use crate::summary::SummarySources;
+use crate::trash::Trash;
 
 use anyhow::Result;
 use positronic_hive::HiveNode;
 use positronic_io::HardwareMonitor;
 use positronic_neural::cortex::NeuralClient;
+use positronic_neural::reflex::{CommandInventory, ReflexEngine, ShellKind};
+use positronic_neural::suggest::Suggestion;
+use positronic_neural::workspace::WorkspaceCache;
 use positronic_script::wasm_host::WasmHost;
-use crate::vault::Vault;
+use crate::vault::{Vault, VaultCryptError};
 
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, OnceLock, RwLock};
+use std::time::Instant;
 use tokio::sync::Mutex;
 
 // ────────────────────────────────────────────────────────────────
@@ -27,14 +69,47 @@ use tokio::sync::Mutex;
 pub enum ExecuteResult {
     /// Command was forwarded to the PTY shell.
     SentToPty,
+    /// Forwarded to the PTY, with lines to show: the hook trace and what
+    /// before-hooks printed (see `hooks`).
+    SentToPtyWith(Vec<String>),
     /// Built-in command produced direct output lines.
     DirectOutput(Vec<String>),
+    /// Built-in command produced a table (`!history`, `!top`, `!stats`…);
+    /// `DataFrame::to_lines` is its plain rendering.
+    Table(DataFrame),
+    /// Commands the user can pick into the input line (`!suggest`).
+    Suggestions(Vec<Suggestion>),
+    /// An AI-generated command to place in the input line for review,
+    /// never executed directly (`# <request>`).
+    GeneratedCommand(String),
+    /// A command to place in the input line to edit and run again
+    /// (`!http` with no arguments).
+    EditCommand(String),
+    /// `!http`'s request and response.
+    Http(Box<HttpExchange>),
+    /// `!fetch`'s download, for the Holodeck.
+    Fetch(Box<Fetched>),
+    /// `!timeline`'s commands, laid out at the frontend's width.
+    Timeline(TimelineData),
+    /// A setting changed; show the lines and re-apply settings.
+    ConfigChanged(Vec<String>),
+    /// `!redo` needs the user to pick where to run (`Runner::redo`).
+    RedoOffer(RedoOffer),
+    /// A corrected command to preview as a diff and offer for the input
+    /// line (`!fix`, or Reflex after a command is not found).
+    Correction(Correction),
     /// Screen should be cleared.
     ClearScreen,
     /// Application should exit.
     Exit,
 }
 
+/// Default input prefix that turns a line into a command-generation request.
+pub const DEFAULT_GENERATE_PREFIX: &str = "#";
+
+/// Vault config key overriding `DEFAULT_GENERATE_PREFIX`.
+pub const GENERATE_PREFIX_KEY: &str = "neural.generate_prefix";
+
 // ────────────────────────────────────────────────────────────────
 // Runner
 // ────────────────────────────────────────────────────────────────
@@ -43,22 +118,93 @@ pub enum ExecuteResult {
 pub struct Runner {
     pub(crate) pty: Arc<Mutex<PtyManager>>,
     pub(crate) airlock: Arc<Airlock>,
-    pub(crate) neural: Arc<NeuralClient>,
+    pub(crate) neural: Subsystem<NeuralClient>,
     pub(crate) vault: Vault,
-    pub(crate) wasm_host: Arc<WasmHost>,
-    pub(crate) hive: Arc<HiveNode>,
-    pub(crate) io: Arc<HardwareMonitor>,
+    pub(crate) wasm_host: Subsystem<WasmHost>,
+    pub(crate) hive: Subsystem<HiveNode>,
+    pub(crate) io: Subsystem<HardwareMonitor>,
+    /// Built by the maintenance worker, then kept up to date as commands run.
+    pub(crate) completions: Arc<RwLock<CompletionIndex>>,
+    /// Project files for the Ctrl+P finder, built by the maintenance worker.
+    pub(crate) files: Arc<FileIndexes>,
+    pub(crate) subsystems: Subsystems,
+    /// Full text of the last AI answer, for `!ai last`.
+    pub(crate) last_ai: StdMutex<Option<String>>,
+    /// Shell-reported working directory, from the last cwd probe.
+    pub(crate) cwd: StdMutex<Option<String>>,
+    /// Shared with the PTY pump; holds suppressed binary output.
+    pub(crate) binary_guard: Arc<StdMutex<BinaryGuard>>,
+    /// Shared with the PTY pump; knows which command is running.
+    pub(crate) running: Arc<StdMutex<RunningTracker>>,
+    /// Shared with the PTY pump; logs shell commands as they finish.
+    pub(crate) recorder: Arc<StdMutex<BlockRecorder>>,
+    /// Offline tldr pages for `!tldr` and the `!explain` fallback.
+    pub(crate) tldr: Arc<Tldr>,
+    /// Built-in and user scaffolds for `!new`.
+    pub(crate) scaffolds: Scaffolds,
+    /// Where `!rm` puts things, and its manifest.
+    pub(crate) trash: Trash,
+    /// `!rename`'s undo log and the preview waiting to be applied.
+    pub(crate) renames: Renames,
+    /// Package hints for missing commands, once each per session.
+    pub(crate) cnf: CnfAdvisor,
+    /// Shared with the PTY pump and the UI; stamps lines as written.
+    pub(crate) latency: Arc<LatencyProbe>,
+    /// Project and git summary for AI context, gathered in the background.
+    pub(crate) workspace: WorkspaceCache,
+    /// Lines the history filter kept out of the vault, for `!history --all`.
+    pub(crate) session_only: StdMutex<SessionOnly>,
+    /// Typo corrections for the shell the PTY runs.
+    pub(crate) reflex: RwLock<Arc<ReflexEngine>>,
+    /// `!hook` after-hooks of shell commands still running.
+    pub(crate) after_hooks: StdMutex<VecDeque<PendingAfter>>,
+    /// The last `!http` request, for `!http` alone; never stored.
+    pub(crate) last_http: StdMutex<Option<HttpRequest>>,
+    /// `!` commands in flight, for Esc and `!abort` to cancel.
+    pub(crate) cancels: Cancellations,
+    /// Long operations reporting progress, for the status bar and `!tasks`.
+    pub(crate) progress: ProgressBoard,
+    /// The native `!` commands and the middleware they run through.
+    pub(crate) commands: Registry,
+    /// `!capture`'s values, for `%{name}`; never stored.
+    pub(crate) variables: StdMutex<Variables>,
+    /// How the UI draws the window, for `!doctor`; empty when headless.
+    pub(crate) display_report: StdMutex<Vec<String>>,
+    /// The last numbered listing and the `!pick` selection from it.
+    pub(crate) picks: StdMutex<PickState>,
+    /// The `!queue` run waiting on a shell command to finish.
+    pub(crate) queue: StdMutex<Option<QueueRun>>,
+    /// Reads test runs from what finished commands printed.
+    pub(crate) test_parsers: TestParsers,
+    /// The background index worker, once `start_maintenance` has run.
+    pub(crate) maintenance: OnceLock<Maintenance>,
+    /// Where the vault and the rest of the data live.
+    pub(crate) data: DataPaths,
+    /// Started with `--safe-mode`: aliases, hooks and `!setenv` are left
+    /// out (see `safe_mode`).
+    pub(crate) safe_mode: bool,
 }
 
 impl Runner {
     pub fn new(
         pty: Arc<Mutex<PtyManager>>,
         airlock: Arc<Airlock>,
-        neural: Arc<NeuralClient>,
+        neural: Subsystem<NeuralClient>,
         vault: Vault,
-        wasm_host: Arc<WasmHost>,
-        hive: Arc<HiveNode>,
-        io: Arc<HardwareMonitor>,
+        wasm_host: Subsystem<WasmHost>,
+        hive: Subsystem<HiveNode>,
+        io: Subsystem<HardwareMonitor>,
+        subsystems: Subsystems,
+        binary_guard: Arc<StdMutex<BinaryGuard>>,
+        running: Arc<StdMutex<RunningTracker>>,
+        recorder: Arc<StdMutex<BlockRecorder>>,
+        tldr: Arc<Tldr>,
+        scaffolds: Scaffolds,
+        trash: Trash,
+        renames: Renames,
+        latency: Arc<LatencyProbe>,
+        data: DataPaths,
+        safe_mode: bool,
     ) -> Self {
         Self {
             pty,
@@ -68,6 +214,36 @@ impl Runner {
             wasm_host,
             hive,
             io,
+            completions: Arc::new(RwLock::new(CompletionIndex::default())),
+            files: Arc::new(FileIndexes::new()),
+            subsystems,
+            last_ai: StdMutex::new(None),
+            cwd: StdMutex::new(None),
+            binary_guard,
+            running,
+            recorder,
+            tldr,
+            scaffolds,
+            trash,
+            renames,
+            cnf: CnfAdvisor::new(),
+            latency,
+            workspace: WorkspaceCache::new(),
+            session_only: StdMutex::new(SessionOnly::default()),
+            reflex: RwLock::new(Arc::new(ReflexEngine::new())),
+            after_hooks: StdMutex::new(VecDeque::new()),
+            last_http: StdMutex::new(None),
+            cancels: Cancellations::default(),
+            progress: ProgressBoard::default(),
+            commands: Registry::builtin(),
+            variables: StdMutex::new(Variables::default()),
+            display_report: StdMutex::new(Vec::new()),
+            picks: StdMutex::new(PickState::default()),
+            queue: StdMutex::new(None),
+            test_parsers: TestParsers::default(),
+            maintenance: OnceLock::new(),
+            data,
+            safe_mode,
         }
     }
 
@@ -75,47 +251,804 @@ impl Runner {
         &self.vault
     }
 
+    /// What `!summary` reads, for a summary written off this thread (see
+    /// `summary::save_on_close`).
+    pub fn summary_sources(&self) -> SummarySources {
+        SummarySources { vault: self.vault.clone(), trash: self.trash.clone(), renames: self.renames.clone() }
+    }
+
+    /// Where the vault and the rest of the data live (see `data_paths`).
+    pub fn data(&self) -> &DataPaths {
+        &self.data
+    }
+
+    /// `line` with its `%{name}`s replaced (see `capture`); None if it
+    /// has none. `!capture` and `!vars` lines are left alone.
+    fn expand_variables(&self, line: &str) -> Result<Option<Expanded>, String> {
+        if capture::is_capture_command(line) || !line.contains("%{") {
+            return Ok(None);
+        }
+        let posix = line.starts_with('!') || RedoShell::detect() == RedoShell::Posix;
+        let variables = self.variables.lock().unwrap_or_else(|e| e.into_inner());
+        capture::substitute(line, posix, |name| variables.get(name).map(|v| v.value.clone()))
+    }
+
+    /// Whether any of the variables `names` holds a credential.
+    fn any_secret(&self, names: &[String]) -> bool {
+        let variables = self.variables.lock().unwrap_or_else(|e| e.into_inner());
+        names.iter().any(|name| variables.get(name).is_some_and(|v| v.is_secret()))
+    }
+
+    /// The session's `!capture` variables.
+    pub fn variables(&self) -> std::sync::MutexGuard<'_, Variables> {
+        self.variables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The `!` commands in flight (see `cancel`).
+    pub fn cancels(&self) -> &Cancellations {
+        &self.cancels
+    }
+
+    /// Long operations in flight (see `progress`).
+    pub fn progress(&self) -> &ProgressBoard {
+        &self.progress
+    }
+
+    /// Whether this session started in safe mode.
+    pub fn safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    /// The shell's working directory, if a cwd probe has reported one.
+    pub fn cwd(&self) -> Option<String> {
+        self.cwd.lock().ok().and_then(|cwd| cwd.clone())
+    }
+
+    /// Record the shell's working directory. The probe answers at each
+    /// prompt, so this is also when a finished command may have changed
+    /// the workspace: its AI context is gathered again.
+    pub fn set_cwd(&self, cwd: &str) {
+        if let Ok(mut current) = self.cwd.lock() {
+            *current = Some(cwd.to_string());
+        }
+        self.workspace.refresh(cwd);
+    }
+
+    /// Lines `!doctor` shows about the display: the renderer in use and
+    /// how it was chosen. The window sets them; headless runs have none.
+    pub fn set_display_report(&self, lines: Vec<String>) {
+        if let Ok(mut report) = self.display_report.lock() {
+            *report = lines;
+        }
+    }
+
+    /// The tldr dataset; `!tldr --update` runs on the UI's own task.
+    pub fn tldr(&self) -> Arc<Tldr> {
+        self.tldr.clone()
+    }
+
+    /// Run `!new <scaffold> <name>` in the shell's directory, each step's
+    /// block through `report`. Returns the closing line.
+    pub async fn new_project(&self, request: &NewRequest, report: impl FnMut(Vec<String>)) -> String {
+        let Some(scaffold) = self.scaffolds.find(&request.scaffold) else {
+            return format!("❌ No scaffold '{}'; !new list shows them", request.scaffold);
+        };
+        let parent = match self.cwd().map(PathBuf::from).map_or_else(std::env::current_dir, Ok) {
+            Ok(dir) => dir,
+            Err(e) => return format!("❌ No working directory: {}", e),
+        };
+        match scaffold.create(&parent, request, report).await {
+            Ok(root) => format!("✅ Created {} from {}", root.display(), scaffold.name),
+            Err(e) => format!("❌ {}", e),
+        }
+    }
+
+    /// Correct typos for the shell `shell` (the PTY's spawn config) runs.
+    /// The first PowerShell session also asks it which commands this
+    /// machine has, in the background; the answer is kept in the vault.
+    pub fn load_shell_commands(self: &Arc<Self>, shell: Option<&str>) {
+        let kind = ShellKind::detect(shell);
+        self.set_reflex(ReflexEngine::with_inventory(shell_commands::inventory(&self.vault, kind)));
+        if !shell_commands::needs_discovery(&self.vault, kind) {
+            return;
+        }
+        let program = shell_commands::powershell_program(shell);
+        let runner = Arc::clone(self);
+        std::thread::spawn(move || {
+            let Some(names) = shell_commands::discover(program) else {
+                eprintln!("[REFLEX] {} command discovery failed; using the built-in table", program);
+                return;
+            };
+            if let Err(e) = runner.vault.set_shell_commands(kind.label(), &names) {
+                eprintln!("[REFLEX] Caching discovered commands failed: {}", e);
+            }
+            let mut inventory = CommandInventory::builtin(kind);
+            inventory.extend(names);
+            runner.set_reflex(ReflexEngine::with_inventory(inventory));
+        });
+    }
+
+    /// Start the maintenance worker on the completion and search indexes;
+    /// `activity` is when the shell last printed.
+    pub fn start_maintenance(&self, activity: Arc<Activity>) {
+        let tasks =
+            vec![completions_task(&self.vault, &self.completions), fts_task(&self.vault), files_task(&self.files)];
+        let _ = self.maintenance.set(Maintenance::start(tasks, Scheduler::new(Instant::now()), activity));
+    }
+
+    /// The maintenance worker, for `!status` and on-demand triggers.
+    pub fn maintenance(&self) -> Option<&Maintenance> {
+        self.maintenance.get()
+    }
+
+    fn set_reflex(&self, reflex: ReflexEngine) {
+        if let Ok(mut current) = self.reflex.write() {
+            *current = Arc::new(reflex);
+        }
+    }
+
+    /// The active shell's typo corrector.
+    pub fn reflex(&self) -> Arc<ReflexEngine> {
+        self.reflex.read().map(|r| r.clone()).unwrap_or_else(|p| p.into_inner().clone())
+    }
+
+    /// "💡 'rg' is provided by 'ripgrep' — install with: …" if `output`
+    /// says a command was not found and this session hasn't hinted it yet.
+    /// A Reflex fix comes back as a `Correction` of `line`, the line that
+    /// failed, when it starts with the missing command.
+    pub async fn not_found_hint(&self, output: &str, line: Option<&str>) -> Option<ExecuteResult> {
+        let command = cnf::missing_command(output)?;
+        if !self.cnf.first_time(&command) {
+            return None;
+        }
+        let key = format!("{}{}", cnf::PACKAGE_KEY_PREFIX, command);
+        let custom = self.vault.get_config(&key).ok().flatten();
+        if let Some(hint) = self.cnf.lookup(&command, custom.as_deref()) {
+            return Some(ExecuteResult::DirectOutput(vec![hint.to_string()]));
+        }
+        let line = line
+            .map(str::trim)
+            .filter(|l| l.split_whitespace().next() == Some(command.as_str()))
+            .unwrap_or(&command);
+        if let Some(fix) = self.reflex().fix_command(line) {
+            let correction = Correction::from_reflex(line, fix)
+                .with_reason(format!("💡 '{}' isn't a command — did you mean:", command));
+            return Some(ExecuteResult::Correction(correction));
+        }
+
+        let use_neural = self
+            .vault
+            .get_config(cnf::USE_NEURAL_KEY)
+            .ok()
+            .flatten()
+            .as_deref()
+            .and_then(parse_flag)
+            .unwrap_or(false);
+        if !use_neural {
+            return None;
+        }
+        let manager = *self.cnf.managers().first()?;
+        let prompt = format!(
+            "Which {} package provides the `{}` command? Reply with the package name only, or `unknown`.",
+            manager.name(),
+            command
+        );
+        let answer = self.neural().ok()?.ask(&prompt).await.ok()?;
+        let package = cnf::parse_guess(&answer)?;
+        Some(ExecuteResult::DirectOutput(vec![PackageHint { command, package, manager, guessed: true }.to_string()]))
+    }
+
+    /// Readiness of the asynchronously started subsystems.
+    pub fn subsystems(&self) -> &Subsystems {
+        &self.subsystems
+    }
+
+    // ── Degradable subsystems: each errors with "<name> unavailable: <reason>" ──
+
+    pub fn neural(&self) -> std::result::Result<Arc<NeuralClient>, PositronicError> {
+        self.neural.get().map_err(|e| PositronicError::neural(NEURAL_ENDPOINT, e))
+    }
+
+    pub fn wasm_host(&self) -> std::result::Result<Arc<WasmHost>, PositronicError> {
+        self.wasm_host.get().map_err(PositronicError::script)
+    }
+
+    pub fn hive(&self) -> Result<Arc<HiveNode>> {
+        self.hive.get()
+    }
+
+    pub fn io(&self) -> std::result::Result<Arc<HardwareMonitor>, PositronicError> {
+        self.io.get().map_err(|e| PositronicError::HardwareError { port: None, reason: e.to_string() })
+    }
+
+    /// Shared handle to the completion index (cheap to clone for UI threads).
+    pub fn completions(&self) -> Arc<RwLock<CompletionIndex>> {
+        self.completions.clone()
+    }
+
+    /// The finder's file indexes, one per project root.
+    pub fn files(&self) -> Arc<FileIndexes> {
+        self.files.clone()
+    }
+
+    /// The finder opened in `cwd`: index its project root (capped at
+    /// `finder.max_files`) on the maintenance worker, or on a thread of
+    /// its own before the worker starts. Returns the root.
+    pub fn want_files(&self, cwd: &Path) -> PathBuf {
+        let root = file_index::project_root(cwd);
+        let max_files = self
+            .vault
+            .get_config(file_index::MAX_FILES_KEY)
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(file_index::DEFAULT_MAX_FILES);
+        self.files.want(&root, max_files);
+        if let Some(worker) = self.maintenance() {
+            worker.trigger(maintenance::FILES_TASK);
+            return root;
+        }
+        let files = self.files.clone();
+        std::thread::spawn(move || {
+            while files.freshness() == Freshness::Stale {
+                files.step(file_index::FILES_BATCH);
+            }
+        });
+        root
+    }
+
+    /// Unlock an encrypted vault and load the history completions it was
+    /// holding back.
+    pub fn unlock_vault(&self, passphrase: &str) -> std::result::Result<(), VaultCryptError> {
+        self.vault.unlock(passphrase)?;
+        if let Some(worker) = self.maintenance() {
+            worker.trigger(maintenance::COMPLETIONS_TASK);
+            return Ok(());
+        }
+        let max_entries = self.suggest_index_size();
+        if let Ok(index) = CompletionIndex::from_vault(&self.vault, max_entries) {
+            match self.completions.write() {
+                Ok(mut current) => *current = index,
+                Err(poisoned) => *poisoned.into_inner() = index,
+            }
+        }
+        Ok(())
+    }
+
+    fn suggest_index_size(&self) -> usize {
+        match self.completions.read() {
+            Ok(index) => index.max_entries(),
+            Err(poisoned) => poisoned.into_inner().max_entries(),
+        }
+    }
+
+    /// Frecency-ranked history completions for `prefix`.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
+        match self.completions.read() {
+            Ok(index) => index.suggest(prefix, limit),
+            Err(poisoned) => poisoned.into_inner().suggest(prefix, limit),
+        }
+    }
+
     /// Main dispatch: built-in commands (`!` prefix), alias expansion, or PTY passthrough.
-    pub async fn execute(&self, data: &str) -> Result<ExecuteResult> {
+    pub async fn execute(&self, data: &str) -> std::result::Result<ExecuteResult, PositronicError> {
+        Ok(self.execute_line(data).await?)
+    }
+
+    async fn execute_line(&self, data: &str) -> Result<ExecuteResult> {
         let trimmed = data.trim();
 
         if trimmed.is_empty() {
             return Ok(ExecuteResult::SentToPty);
         }
 
-        // Built-in commands
-        if trimmed.starts_with('!') {
-            return self.handle_builtin(trimmed).await;
+        // Inspect mode browses: a few `!` commands, without hooks.
+        if self.vault.is_inspect() {
+            return match inspect::check_line(trimmed) {
+                Ok(()) => self.run_unhooked(trimmed, true).await,
+                Err(message) => Ok(ExecuteResult::DirectOutput(vec![message])),
+            };
+        }
+
+        // Judged as typed: `history.ignore_space` needs the leading space.
+        let filter = self.vault.history_filter();
+        let private = filter.check(data, None).is_private();
+        if !private {
+            if let Ok(mut index) = self.completions.write() {
+                index.record(trimmed);
+            }
+        }
+
+        // `%{name}` → its `!capture`d value
+        let expanded = match self.expand_variables(trimmed) {
+            Ok(expanded) => expanded,
+            Err(message) => return Ok(ExecuteResult::DirectOutput(vec![message])),
+        };
+        let line = expanded.as_ref().map_or(trimmed, |e| e.line.as_str());
+
+        // Built-in commands; with variables, the line they ran first.
+        if line.starts_with('!') {
+            let result = self.run_hooked(line, 0).await?;
+            return Ok(match &expanded {
+                Some(expanded) => surround(vec![format!("↳ {}", expanded.line)], result, Vec::new()),
+                None => result,
+            });
+        }
+
+        // Natural language → command
+        if let Some(request) = self.generation_request(trimmed) {
+            return Ok(commands::neural::generate_command(self, &request).await);
         }
 
         // Alias expansion
-        let final_command = if let Some(expanded) = self.expand_alias(trimmed) {
-            expanded
+        let final_command = match self.expand_alias_logged(line) {
+            Ok(command) => command,
+            Err(message) => return Ok(ExecuteResult::DirectOutput(vec![message])),
+        };
+        if expanded.as_ref().is_some_and(|e| self.any_secret(&e.names)) {
+            self.vault.note_logged_as(&final_command, trimmed);
+        }
+
+        if private {
+            // Logged under the typed line by headless mode, the expanded
+            // one by the block recorder.
+            self.vault.note_unlogged(&[trimmed, line, &final_command]);
+            if let Ok(mut session_only) = self.session_only.lock() {
+                session_only.push(trimmed, filter.ignore_dups);
+            }
+        }
+        self.run_hooked(&final_command, 0).await
+    }
+
+    /// Run `line`, a `!` command or a shell line ready for the PTY, with
+    /// the `!hook` rules that match it. `depth` counts hooks run by hooks.
+    async fn run_hooked(&self, line: &str, depth: usize) -> Result<ExecuteResult> {
+        let native = line.starts_with('!');
+        let rules = self.active_hooks();
+        let fired = hooks::matching(&rules, line);
+        if fired.is_empty() {
+            return self.run_unhooked(line, native).await;
+        }
+        if depth >= hooks::MAX_DEPTH {
+            let note = format!("⚠️ Hooks nest over {} deep; `{}` ran without its own", hooks::MAX_DEPTH, line);
+            let result = self.run_unhooked(line, native).await?;
+            return Ok(surround(vec![note], result, Vec::new()));
+        }
+
+        let mut before = Vec::new();
+        if hooks::trace_enabled(self.vault.get_config(hooks::TRACE_KEY).ok().flatten().as_deref()) {
+            before.push(hooks::trace_line(&fired));
+        }
+        for command in fired.iter().filter_map(|rule| rule.spec.before.as_deref()) {
+            let result = Box::pin(self.run_hook_command(command, depth + 1)).await?;
+            before.extend(result_lines(result));
+        }
+        let afters: Vec<String> = fired.iter().filter_map(|rule| rule.spec.after.clone()).collect();
+
+        if native {
+            // A native command is done when it returns.
+            let result = self.handle_builtin(line).await;
+            let exit = Some(if result.is_ok() { 0 } else { 1 });
+            let mut after = Vec::new();
+            for command in &afters {
+                let command = hooks::substitute_exit(command, exit);
+                let hooked = Box::pin(self.run_hook_command(&command, depth + 1)).await?;
+                after.extend(result_lines(hooked));
+            }
+            return Ok(surround(before, result?, after));
+        }
+
+        let env: Vec<(String, String)> = fired.iter().flat_map(|rule| rule.spec.env.clone()).collect();
+        if !afters.is_empty() {
+            let command = hooks::with_env(&env, RedoShell::detect(), line);
+            let mut queue = self.after_hooks.lock().unwrap_or_else(|e| e.into_inner());
+            if queue.len() >= hooks::MAX_PENDING_AFTER {
+                queue.pop_front();
+            }
+            queue.push_back(PendingAfter { line: command, commands: afters, depth: depth + 1 });
+        }
+        let result = self.send_with_env(line, &env).await?;
+        Ok(surround(before, result, Vec::new()))
+    }
+
+    /// The hook rules that apply: none in safe mode, and never one
+    /// `!safe disable` turned off.
+    fn active_hooks(&self) -> Vec<hooks::HookRule> {
+        if self.safe_mode {
+            return Vec::new();
+        }
+        let mut rules = self.vault.list_hooks().unwrap_or_default();
+        if let Ok(disabled) = self.vault.disabled_items() {
+            rules.retain(|rule| {
+                !disabled.iter().any(|item| item.kind == DisabledKind::Hook && item.name == rule.id.to_string())
+            });
+        }
+        rules
+    }
+
+    /// A hook's command: a `!` command, or a shell line after alias expansion.
+    async fn run_hook_command(&self, command: &str, depth: usize) -> Result<ExecuteResult> {
+        if command.starts_with('!') {
+            return self.run_hooked(command, depth).await;
+        }
+        match self.expand_alias_logged(command) {
+            Ok(expanded) => self.run_hooked(&expanded, depth).await,
+            Err(message) => Ok(ExecuteResult::DirectOutput(vec![message])),
+        }
+    }
+
+    async fn run_unhooked(&self, line: &str, native: bool) -> Result<ExecuteResult> {
+        if native {
+            self.handle_builtin(line).await
         } else {
-            trimmed.to_string()
+            self.send_to_pty(line).await
+        }
+    }
+
+    /// Shell commands have finished since the last call: summarize the
+    /// last one's test run if `output`, what it printed, is one, run their
+    /// after-hooks with their exit codes, then carry on with a `!queue`
+    /// run waiting on one. The UI calls this when the shell reports a
+    /// command's end; the results are for showing.
+    pub async fn commands_finished(&self, output: &str) -> Vec<ExecuteResult> {
+        let finished = self.running.lock().unwrap_or_else(|e| e.into_inner()).take_finished();
+        let mut results = Vec::new();
+        if let Some((line, _)) = finished.last() {
+            if let Some(lines) = self.test_run_finished(line, output) {
+                results.push(ExecuteResult::DirectOutput(lines));
+            }
+        }
+        for (line, exit) in &finished {
+            let pending = {
+                let mut queue = self.after_hooks.lock().unwrap_or_else(|e| e.into_inner());
+                queue.iter().position(|p| &p.line == line).and_then(|i| queue.remove(i))
+            };
+            let Some(pending) = pending else {
+                continue;
+            };
+            for command in &pending.commands {
+                let command = hooks::substitute_exit(command, *exit);
+                match self.run_hook_command(&command, pending.depth).await {
+                    Ok(result) => results.push(result),
+                    Err(e) => results.push(ExecuteResult::DirectOutput(vec![format!(
+                        "❌ After-hook `{}` failed: {:#}",
+                        command, e
+                    )])),
+                }
+            }
+        }
+        // The run sends nothing else while it waits, so the first command
+        // to finish is its entry.
+        if let Some((_, exit)) = finished.first() {
+            let waiting = self.queue.lock().unwrap_or_else(|e| e.into_inner()).take();
+            if let Some(mut run) = waiting {
+                let step = run.finished(*exit);
+                results.push(self.drive_queue(run, step).await);
+            }
+        }
+        results
+    }
+
+    /// The card for `command`'s test run, logged to the vault unless it
+    /// is locked; `None` if `output` isn't one.
+    fn test_run_finished(&self, command: &str, output: &str) -> Option<Vec<String>> {
+        let report = self.test_parsers.parse(command, output)?;
+        let directory = self.cwd().unwrap_or_else(|| ".".to_string());
+        let mut lines = report.card_lines(&[]);
+        if self.vault.is_locked() {
+            return Some(lines);
+        }
+        if let Err(e) = self.vault.log_test_run(command, &directory, &report) {
+            lines.push(format!("⚠️ Logging the test run failed: {}", e));
+        }
+        Some(lines)
+    }
+
+    /// Start `run` from its next entry (see `queue`).
+    pub(crate) async fn run_queue(&self, mut run: QueueRun) -> ExecuteResult {
+        let step = run.next_step();
+        self.drive_queue(run, step).await
+    }
+
+    /// The entry a `!queue` run is waiting on, as its progress line.
+    pub(crate) fn queue_waiting(&self) -> Option<String> {
+        let queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let run = queue.as_ref()?;
+        Some(run.progress_line(run.running()?))
+    }
+
+    /// Work through a `!queue` run from `step`: `!` entries here, one
+    /// after another, until a shell entry has been sent, when the run
+    /// waits in `self.queue` for `commands_finished`, or the run is over.
+    /// Its place is saved before each entry.
+    async fn drive_queue(&self, mut run: QueueRun, mut step: QueueStep) -> ExecuteResult {
+        let mut lines = Vec::new();
+        loop {
+            let (index, command) = match step {
+                QueueStep::Run { index, command } => (index, command),
+                QueueStep::Done { lines: summary, resumable } => {
+                    lines.extend(summary);
+                    if !resumable {
+                        if let Err(e) = self.vault.save_queue_progress(None) {
+                            lines.push(format!("⚠️ Clearing the queue's saved place failed: {}", e));
+                        }
+                    }
+                    return ExecuteResult::DirectOutput(lines);
+                }
+            };
+            lines.push(run.progress_line(index));
+            if let Err(e) = self.vault.save_queue_progress(Some(run.progress())) {
+                lines.push(format!("⚠️ Saving the queue's place failed: {}", e));
+            }
+            let result = Box::pin(self.execute_line(&command)).await;
+            let exit = match result {
+                // Sent to the shell, by a shell entry or a `!` one like
+                // `!redo`: wait for it to finish.
+                Ok(sent @ (ExecuteResult::SentToPty | ExecuteResult::SentToPtyWith(_))) => {
+                    *self.queue.lock().unwrap_or_else(|e| e.into_inner()) = Some(run);
+                    return surround(lines, sent, Vec::new());
+                }
+                // A `!` command is done when it returns; a shell line that
+                // came back unsent (a bad alias) failed.
+                Ok(result) => {
+                    lines.extend(result_lines(result));
+                    Some(if command.starts_with('!') { 0 } else { 1 })
+                }
+                Err(e) => {
+                    lines.push(format!("❌ {:#}", e));
+                    Some(1)
+                }
+            };
+            step = run.finished(exit);
+        }
+    }
+
+    /// Run a `!redo` offer: the original command, after a `cd` to where it
+    /// ran for `RedoChoice::There`. The line is noted in the vault so its
+    /// history row points back at the original.
+    pub async fn redo(&self, offer: &RedoOffer, choice: RedoChoice) -> Result<ExecuteResult> {
+        if let Err(message) = offer.check(choice) {
+            return Ok(ExecuteResult::DirectOutput(vec![message]));
+        }
+        let command = match self.expand_alias_logged(&offer.command) {
+            Ok(command) => command,
+            Err(message) => return Ok(ExecuteResult::DirectOutput(vec![message])),
         };
+        let expanded = RedoOffer { command, ..offer.clone() };
+        let line = expanded.line(choice, RedoShell::detect());
+        self.vault.note_redo(&line, offer.id);
+        self.send_to_pty(&line).await
+    }
 
-        // Send to PTY
+    /// Run `!pick run`'s commands as one shell line, in order: each after
+    /// the one before succeeded, or regardless with `keep_going`.
+    pub(crate) async fn run_picked(&self, commands: &[String], keep_going: bool) -> Result<ExecuteResult> {
+        let mut expanded = Vec::with_capacity(commands.len());
+        for command in commands {
+            match self.expand_alias_logged(command) {
+                Ok(command) => expanded.push(command),
+                Err(message) => return Ok(ExecuteResult::DirectOutput(vec![message])),
+            }
+        }
+        self.send_to_pty(&pick::chain(RedoShell::detect(), &expanded, keep_going)).await
+    }
+
+    /// `line` with its alias expanded, recording the alias use.
+    fn expand_alias_logged(&self, line: &str) -> Result<String, String> {
+        match self.expand_alias(line)? {
+            Some(expanded) => {
+                // Usage analytics only; a failed write doesn't hold up the command.
+                let alias = line.split_whitespace().next().unwrap_or(line);
+                let _ = self.vault.log_alias_use(alias, line, &expanded);
+                Ok(expanded)
+            }
+            None => Ok(line.to_string()),
+        }
+    }
+
+    async fn send_to_pty(&self, line: &str) -> Result<ExecuteResult> {
+        self.send_with_env(line, &[]).await
+    }
+
+    /// Write `line` to the shell with the `!setenv` overlay and a hook's
+    /// `env` in front of it. The output block and after-hooks know it by
+    /// `line` with the hook's env alone, so an override doesn't change
+    /// what a finished command is matched by.
+    async fn send_with_env(&self, line: &str, env: &[(String, String)]) -> Result<ExecuteResult> {
+        let shell = RedoShell::detect();
+        let tracked = hooks::with_env(env, shell, line);
+        // A line typed into a running program is its input, not a command.
+        let at_prompt = !self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .running(Instant::now())
+            .is_some_and(|info| info.confirmed);
+        let overlay = if at_prompt { self.env_overlay() } else { Vec::new() };
+        let written = setenv::command_line(&overlay, env, shell, line);
+
+        self.begin_output_block(&tracked);
         let mut pty = self.pty.lock().await;
-        pty.write_line(&final_command)?;
+        pty.write_line(&written).map_err(PositronicError::pty)?;
+        self.latency.written(Instant::now());
 
         Ok(ExecuteResult::SentToPty)
     }
 
-    /// Check whether `cmd` starts with a known alias and expand it.
-    pub(crate) fn expand_alias(&self, cmd: &str) -> Option<String> {
-        let first_word = cmd.split_whitespace().next()?;
+    /// Start watching the output of `command` for binary content, and
+    /// note it as the command about to run and to record.
+    fn begin_output_block(&self, command: &str) {
+        let enabled = guard_enabled(self.vault.get_config(BINARY_GUARD_KEY).ok().flatten().as_deref());
+        let mut guard = self.binary_guard.lock().unwrap_or_else(|e| e.into_inner());
+        guard.set_enabled(enabled);
+        guard.begin_block(command);
+        drop(guard);
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .command_sent(command, Instant::now());
+        self.recorder.lock().unwrap_or_else(|e| e.into_inner()).on_command_sent(command);
+    }
 
-        if let Ok(Some(expansion)) = self.vault.get_alias(first_word) {
-            let rest = cmd.strip_prefix(first_word).unwrap_or("");
-            Some(format!("{}{}", expansion, rest))
-        } else {
-            None
+    /// The `!setenv` variables for a command run now, in the order to set
+    /// them. Empty if the vault can't be read, or in safe mode.
+    pub fn env_overlay(&self) -> Vec<(String, String)> {
+        if self.safe_mode {
+            return Vec::new();
         }
+        setenv::overlay(&self.vault.list_env().unwrap_or_default())
     }
 
-    /// Route `!` commands to the appropriate handler in `builtins`.
+    /// The request text if `input` starts with the generation prefix.
+    pub(crate) fn generation_request(&self, input: &str) -> Option<String> {
+        let prefix = self
+            .vault
+            .get_config(GENERATE_PREFIX_KEY)
+            .ok()
+            .flatten()
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| DEFAULT_GENERATE_PREFIX.to_string());
+        let request = input.strip_prefix(prefix.as_str())?.trim();
+        (!request.is_empty()).then(|| request.to_string())
+    }
+
+    /// Remember the full text of an AI answer for `!ai last`.
+    pub(crate) fn set_last_ai(&self, text: &str) {
+        if let Ok(mut last) = self.last_ai.lock() {
+            *last = Some(text.to_string());
+        }
+    }
+
+    /// Check whether `cmd` starts with a known alias and expand it (see
+    /// `alias`); the error is for the user. Safe mode expands nothing, and
+    /// an alias `!safe disable` turned off is not known.
+    pub(crate) fn expand_alias(&self, cmd: &str) -> Result<Option<String>, String> {
+        if self.safe_mode {
+            return Ok(None);
+        }
+        alias::expand(cmd, |name| {
+            let expansion = self.vault.get_alias(name).ok().flatten()?;
+            let disabled = self.vault.is_disabled(DisabledKind::Alias, name).unwrap_or(false);
+            (!disabled).then_some(expansion)
+        })
+    }
+
+    /// Route `!` commands to their handler in `commands`, through the
+    /// registry's middleware: a locked vault, missing arguments,
+    /// cancelling, timing and the output cap are handled there.
     pub(crate) async fn handle_builtin(&self, cmd: &str) -> Result<ExecuteResult> {
-        builtins::dispatch(self, cmd).await
+        self.commands.run(self, cmd).await
+    }
+}
+
+/// The lines `result` shows, for folding a hook's output into another.
+fn result_lines(result: ExecuteResult) -> Vec<String> {
+    match result {
+        ExecuteResult::DirectOutput(lines)
+        | ExecuteResult::SentToPtyWith(lines)
+        | ExecuteResult::ConfigChanged(lines) => lines,
+        ExecuteResult::Table(frame) => frame.to_lines(),
+        ExecuteResult::Http(exchange) => exchange.lines(),
+        ExecuteResult::Fetch(fetched) => fetched.plain_lines(),
+        ExecuteResult::Timeline(data) => data.lines(timeline::DEFAULT_WIDTH),
+        _ => Vec::new(),
     }
-}
\ No newline at end of file
+}
+
+/// `result` with hook lines shown before and after it. Results that
+/// aren't lines (a suggestion list, a generated command…) stay as they are.
+fn surround(before: Vec<String>, result: ExecuteResult, after: Vec<String>) -> ExecuteResult {
+    if before.is_empty() && after.is_empty() {
+        return result;
+    }
+    let wrap = |mut before: Vec<String>, lines: Vec<String>| {
+        before.extend(lines);
+        before.extend(after);
+        before
+    };
+    match result {
+        ExecuteResult::SentToPty => ExecuteResult::SentToPtyWith(wrap(before, Vec::new())),
+        ExecuteResult::SentToPtyWith(lines) => ExecuteResult::SentToPtyWith(wrap(before, lines)),
+        ExecuteResult::DirectOutput(lines) => ExecuteResult::DirectOutput(wrap(before, lines)),
+        ExecuteResult::Table(frame) => ExecuteResult::DirectOutput(wrap(before, frame.to_lines())),
+        ExecuteResult::ConfigChanged(lines) => ExecuteResult::ConfigChanged(wrap(before, lines)),
+        other => other,
+    }
+}
+/// The completion index, built from the vault once per session (and again
+/// after `!vault unlock`), then kept up to date by `record`. It used to be
+/// built on the boot path.
+fn completions_task(vault: &Vault, completions: &Arc<RwLock<CompletionIndex>>) -> MaintenanceTask {
+    let built = Arc::new(AtomicBool::new(false));
+    let (check_vault, check_built) = (vault.clone(), built.clone());
+    let (vault, completions) = (vault.clone(), completions.clone());
+    MaintenanceTask::new(
+        maintenance::COMPLETIONS_TASK,
+        "Completion index",
+        Priority::High,
+        Cost::Light,
+        move || {
+            if check_vault.is_locked() {
+                Freshness::Off("vault locked".to_string())
+            } else if check_built.load(Ordering::Acquire) {
+                Freshness::Fresh
+            } else {
+                Freshness::Stale
+            }
+        },
+        move || {
+            let max_entries =
+                completions.read().map(|i| i.max_entries()).unwrap_or(completion::DEFAULT_MAX_ENTRIES);
+            let index = CompletionIndex::from_vault(&vault, max_entries).map_err(|e| e.to_string())?;
+            let note = format!("{} commands", index.len());
+            match completions.write() {
+                Ok(mut current) => *current = index,
+                Err(poisoned) => *poisoned.into_inner() = index,
+            }
+            built.store(true, Ordering::Release);
+            Ok(Step::Done(note))
+        },
+    )
+}
+
+/// The finder's file index for the root it last opened on,
+/// `FILES_BATCH` directories a step. Stale until that root is read, and
+/// again each time the finder opens so changed directories are reread.
+fn files_task(files: &Arc<FileIndexes>) -> MaintenanceTask {
+    let (check_files, files) = (files.clone(), files.clone());
+    MaintenanceTask::new(
+        maintenance::FILES_TASK,
+        "File index",
+        Priority::Normal,
+        Cost::Heavy,
+        move || check_files.freshness(),
+        move || Ok(files.step(file_index::FILES_BATCH)),
+    )
+}
+
+/// The history search index (`Vault::index_history`), `FTS_BATCH` rows a
+/// step. Stale whenever commands were logged since the last run.
+fn fts_task(vault: &Vault) -> MaintenanceTask {
+    let (check_vault, vault) = (vault.clone(), vault.clone());
+    MaintenanceTask::new(
+        maintenance::FTS_TASK,
+        "FTS index",
+        Priority::Normal,
+        Cost::Heavy,
+        move || match check_vault.fts_pending() {
+            Ok(Some(0)) => Freshness::Fresh,
+            Ok(None) if check_vault.is_inspect() => Freshness::Off("read-only vault".to_string()),
+            Ok(None) => Freshness::Off("encrypted vault, search scans".to_string()),
+            // An error shows when the run meets it.
+            Ok(Some(_)) | Err(_) => Freshness::Stale,
+        },
+        move || {
+            let progress = vault.index_history(maintenance::FTS_BATCH).map_err(|e| e.to_string())?;
+            if progress.pending == 0 {
+                return Ok(Step::Done(format!("{} commands", progress.indexed)));
+            }
+            Ok(Step::More { done: progress.indexed, total: progress.indexed + progress.pending })
+        },
+    )
+}
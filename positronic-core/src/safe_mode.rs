@@ -0,0 +1,244 @@
+//! Safe mode: start without the user's aliases, hooks, `!setenv`
+//! overrides, WASM plugins, settings, theme and `.inputrc`, to get back
+//! into a terminal one of them has broken.
+//!
+//! `positronic --safe-mode` asks for it, and the window offers it after
+//! crashed starts: each normal boot leaves a `BootMarker` beside the
+//! vault saying it is booting, and removes it once it has been Active for
+//! `STABLE_AFTER`. A marker still there at the next start is a boot that
+//! never got that far; after `OFFER_AFTER_CRASHES` of them in a row the
+//! window asks before booting.
+//!
+//! Nothing skipped is changed on disk. Instead `!safe disable` marks the
+//! one alias, hook or theme at fault as disabled in the vault; normal
+//! boots skip it too until `!safe enable` takes the mark off.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::pick::counted;
+
+pub const SAFE_USAGE: &str = "Usage: !safe [list] | disable <alias|hook|theme> <name> | enable <alias|hook|theme> <name>";
+
+/// The boot marker, next to the vault.
+pub const BOOT_MARKER: &str = "boot.json";
+
+/// Crashed boots in a row before the window offers safe mode.
+pub const OFFER_AFTER_CRASHES: u32 = 2;
+
+/// How long a normal boot stays Active before it counts as a good one.
+pub const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// Why the WASM subsystem isn't there.
+pub const WASM_OFF: &str = "off in safe mode";
+
+/// Shown at the top of a safe-mode session.
+pub const BANNER: [&str; 3] = [
+    "🛟 SAFE MODE — aliases, hooks, !setenv, plugins, settings, theme and .inputrc are not loaded",
+    "   They are untouched on disk. !safe list shows what was skipped;",
+    "   !safe disable <alias|hook|theme> <name> turns off the culprit, then restart normally.",
+];
+
+/// The question asked before booting after `crashes` crashed starts.
+pub fn crash_prompt(crashes: u32) -> String {
+    format!(
+        "⚠️ The last {} starts crashed before they were up. Start in safe mode? [y/N]",
+        crashes
+    )
+}
+
+/// Whether an answer to `crash_prompt` is yes.
+pub fn accepts(answer: &str) -> bool {
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Boot marker
+// ════════════════════════════════════════════════════════════════════
+
+/// What the boot marker holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BootState {
+    /// A boot started and hasn't been Active for `STABLE_AFTER` yet.
+    pub booting: bool,
+    /// Crashed boots in a row before that one.
+    pub crashed: u32,
+}
+
+/// The file that tells a start whether the ones before it crashed.
+#[derive(Debug, Clone)]
+pub struct BootMarker {
+    path: PathBuf,
+}
+
+impl BootMarker {
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The marker for the vault at `vault_path`.
+    pub fn beside(vault_path: &Path) -> Self {
+        Self::at(vault_path.parent().map_or_else(|| PathBuf::from(BOOT_MARKER), |dir| dir.join(BOOT_MARKER)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The marker's state; none, or one that can't be read, is a clean
+    /// slate.
+    pub fn state(&self) -> BootState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Boots in a row that crashed, counting one still marked booting.
+    pub fn crashes(&self) -> u32 {
+        let state = self.state();
+        state.crashed + u32::from(state.booting)
+    }
+
+    /// A normal boot is starting.
+    pub fn begin(&self) -> io::Result<()> {
+        self.write(BootState { booting: true, crashed: self.crashes() })
+    }
+
+    /// The boot has been Active for `STABLE_AFTER`: the count starts over.
+    pub fn stable(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&self, state: BootState) -> io::Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string(&state)?)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Disabled items
+// ════════════════════════════════════════════════════════════════════
+
+/// What `!safe disable` can turn off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisabledKind {
+    Alias,
+    /// Named by its id, as `!hook list` shows it.
+    Hook,
+    /// Falls back to the default theme.
+    Theme,
+}
+
+impl DisabledKind {
+    pub fn parse(word: &str) -> Option<DisabledKind> {
+        match word {
+            "alias" => Some(DisabledKind::Alias),
+            "hook" => Some(DisabledKind::Hook),
+            "theme" => Some(DisabledKind::Theme),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisabledKind::Alias => "alias",
+            DisabledKind::Hook => "hook",
+            DisabledKind::Theme => "theme",
+        }
+    }
+}
+
+/// An item `!safe disable` turned off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisabledItem {
+    pub kind: DisabledKind,
+    pub name: String,
+    pub disabled_at: i64,
+}
+
+/// What follows `!safe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SafeCommand {
+    List,
+    Disable(DisabledKind, String),
+    Enable(DisabledKind, String),
+}
+
+impl SafeCommand {
+    /// Parse what follows `!safe`; the error is the message to show. A
+    /// hook's name is its id, `#` or not; a theme's is lowercased.
+    pub fn parse(args: &str) -> Result<SafeCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] | ["list"] => Ok(SafeCommand::List),
+            [verb @ ("disable" | "enable"), kind, name] => {
+                let kind = DisabledKind::parse(kind).ok_or_else(|| SAFE_USAGE.to_string())?;
+                let name = match kind {
+                    DisabledKind::Alias => name.to_string(),
+                    DisabledKind::Hook => match name.trim_start_matches('#').parse::<i64>() {
+                        Ok(id) => id.to_string(),
+                        Err(_) => return Err(format!("❌ Bad hook id '{}'; !hook list shows them", name)),
+                    },
+                    DisabledKind::Theme => name.to_lowercase(),
+                };
+                Ok(if *verb == "disable" { SafeCommand::Disable(kind, name) } else { SafeCommand::Enable(kind, name) })
+            }
+            _ => Err(SAFE_USAGE.to_string()),
+        }
+    }
+}
+
+/// How much a safe-mode start left out, counted from the vault.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Skipped {
+    pub aliases: usize,
+    pub hooks: usize,
+    pub env: usize,
+}
+
+/// The `!safe list` output: what this session skipped, if it is a safe
+/// one, then what is disabled.
+pub fn list_lines(safe_mode: bool, skipped: Skipped, disabled: &[DisabledItem]) -> Vec<String> {
+    let mut lines = Vec::new();
+    if safe_mode {
+        lines.push("🛟 Safe mode skipped, and left as they are on disk:".to_string());
+        lines.push(format!("  {} — not expanded", counted(skipped.aliases, "alias", "aliases")));
+        lines.push(format!("  {} — not run", counted(skipped.hooks, "hook", "hooks")));
+        lines.push(format!("  {} — not applied", counted(skipped.env, "!setenv override", "!setenv overrides")));
+        lines.push("  WASM plugins — not started".to_string());
+        lines.push("  Settings, profile and theme — built-in defaults and keybindings instead".to_string());
+        lines.push("  .inputrc — not imported".to_string());
+        lines.push("  A stopped !queue run — not offered for resuming".to_string());
+    } else {
+        lines.push("Not in safe mode; positronic --safe-mode starts in it".to_string());
+    }
+    if disabled.is_empty() {
+        lines.push("Nothing is disabled; !safe disable <alias|hook|theme> <name> turns one off".to_string());
+        return lines;
+    }
+    lines.push(format!("Disabled, normal starts skip these too ({}):", disabled.len()));
+    for item in disabled {
+        let since = Local
+            .timestamp_opt(item.disabled_at, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        let name = match item.kind {
+            DisabledKind::Hook => format!("#{}", item.name),
+            _ => item.name.clone(),
+        };
+        lines.push(format!("  {:<6} {:<20} since {}", item.kind.label(), name, since));
+    }
+    lines.push("!safe enable <kind> <name> turns one back on".to_string());
+    lines
+}
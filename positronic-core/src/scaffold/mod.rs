@@ -0,0 +1,413 @@
+//! `!new`: create a project from a scaffold definition.
+//!
+//! A scaffold is a TOML file with a name, a description, default
+//! variables and a list of steps. Each step runs a command, writes a
+//! file or creates a directory:
+//!
+//! ```toml
+//! name = "rust-bin"
+//! description = "Cargo binary crate"
+//!
+//! [vars]
+//! edition = "2021"
+//!
+//! [[steps]]
+//! run = "cargo init --bin --name {{name}} --edition {{edition}}"
+//!
+//! [[steps]]
+//! mkdir = "docs"
+//!
+//! [[steps]]
+//! file = "docs/template.hbs"
+//! content = "{{title}} stays as written"
+//! raw = true
+//! ```
+//!
+//! `{{name}}` and the variables (defaults overridden by `key=value`
+//! arguments) are substituted into commands, paths and file contents,
+//! except the contents of `raw` files. Everything is substituted and
+//! checked before the first step runs: an unknown variable, a path that
+//! leaves the project, or a command `DangerAnalyzer` calls destructive
+//! stops the scaffold with nothing created.
+//!
+//! Steps run in order inside `<cwd>/<name>`. If one fails the directory
+//! is removed, unless `--keep` asked to leave it for inspection.
+//!
+//! The built-in scaffolds are embedded; `*.toml` files in `SCAFFOLD_DIR`
+//! next to the vault add more, and replace a built-in of the same name.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::danger::DangerAnalyzer;
+
+/// User scaffolds, next to `positronic.db`.
+pub const SCAFFOLD_DIR: &str = "positronic-scaffolds";
+
+pub const NEW_USAGE: &str = "Usage: !new <scaffold> <name> [key=value ...] [--keep] | !new list";
+
+/// A command step is killed after this long.
+const STEP_TIMEOUT: Duration = Duration::from_secs(600);
+
+const BUILTINS: &[&str] = &[
+    include_str!("rust-bin.toml"),
+    include_str!("rust-lib.toml"),
+    include_str!("python-venv.toml"),
+];
+
+// ════════════════════════════════════════════════════════════════════
+// Definitions
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScaffoldStep {
+    /// A shell command, run in the project directory.
+    Run(String),
+    /// Write `content` to `path`; `raw` content is written as is.
+    Write { path: String, content: String, raw: bool },
+    /// Create a directory and its parents.
+    Mkdir(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    pub name: String,
+    pub description: String,
+    /// Default variable values.
+    pub vars: BTreeMap<String, String>,
+    pub steps: Vec<ScaffoldStep>,
+    /// The file it was loaded from; `None` for a built-in.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScaffoldFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    vars: BTreeMap<String, String>,
+    #[serde(default)]
+    steps: Vec<StepFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StepFile {
+    run: Option<String>,
+    file: Option<String>,
+    content: Option<String>,
+    #[serde(default)]
+    raw: bool,
+    mkdir: Option<String>,
+}
+
+impl Scaffold {
+    /// Parse a scaffold definition.
+    pub fn parse(text: &str) -> Result<Scaffold> {
+        let file: ScaffoldFile = toml::from_str(text)?;
+        if !is_plain_name(&file.name) {
+            bail!("'{}' is not a usable scaffold name", file.name);
+        }
+        let mut steps = Vec::with_capacity(file.steps.len());
+        for (i, step) in file.steps.into_iter().enumerate() {
+            let step = match (step.run, step.file, step.mkdir) {
+                (Some(command), None, None) if step.content.is_none() && !step.raw => ScaffoldStep::Run(command),
+                (None, Some(path), None) => {
+                    ScaffoldStep::Write { path, content: step.content.unwrap_or_default(), raw: step.raw }
+                }
+                (None, None, Some(path)) if step.content.is_none() && !step.raw => ScaffoldStep::Mkdir(path),
+                _ => bail!("step {} needs exactly one of run, file (with content, raw) or mkdir", i + 1),
+            };
+            steps.push(step);
+        }
+        if steps.is_empty() {
+            bail!("no steps");
+        }
+        Ok(Scaffold { name: file.name, description: file.description, vars: file.vars, steps, path: None })
+    }
+
+    /// The scaffolds shipped with Positronic.
+    pub fn builtins() -> Vec<Scaffold> {
+        BUILTINS.iter().map(|text| Scaffold::parse(text).expect("built-in scaffold parses")).collect()
+    }
+
+    /// The steps with variables substituted, paths checked and commands
+    /// analysed; nothing is touched yet.
+    pub fn plan(&self, name: &str, overrides: &[(String, String)]) -> Result<Vec<ScaffoldStep>> {
+        let mut vars = self.vars.clone();
+        vars.extend(overrides.iter().cloned());
+        vars.insert("name".to_string(), name.to_string());
+
+        let mut plan = Vec::with_capacity(self.steps.len());
+        for (i, step) in self.steps.iter().enumerate() {
+            let planned = match step {
+                ScaffoldStep::Run(command) => {
+                    let command = substitute(command, &vars)?;
+                    let danger = DangerAnalyzer::analyze(&command);
+                    if danger.is_destructive() {
+                        bail!(
+                            "step {} is destructive ({}): {}",
+                            i + 1,
+                            danger.reason.unwrap_or("destructive"),
+                            command
+                        );
+                    }
+                    ScaffoldStep::Run(command)
+                }
+                ScaffoldStep::Write { path, content, raw } => ScaffoldStep::Write {
+                    path: project_path(&substitute(path, &vars)?)?,
+                    content: if *raw { content.clone() } else { substitute(content, &vars)? },
+                    raw: *raw,
+                },
+                ScaffoldStep::Mkdir(path) => ScaffoldStep::Mkdir(project_path(&substitute(path, &vars)?)?),
+            };
+            plan.push(planned);
+        }
+        Ok(plan)
+    }
+
+    /// Create `parent/<name>` and run the steps in it. `report` gets each
+    /// step's label as it starts, then a command's output as one indented
+    /// block. Returns the project directory.
+    pub async fn create(
+        &self,
+        parent: &Path,
+        request: &NewRequest,
+        mut report: impl FnMut(Vec<String>),
+    ) -> Result<PathBuf> {
+        let plan = self.plan(&request.name, &request.vars)?;
+        let root = parent.join(&request.name);
+        if root.exists() {
+            bail!("{} already exists", root.display());
+        }
+        std::fs::create_dir(&root).with_context(|| format!("Failed to create {}", root.display()))?;
+
+        let total = plan.len();
+        for (i, step) in plan.iter().enumerate() {
+            report(vec![format!("▶ [{}/{}] {}", i + 1, total, step_label(step))]);
+            if let Err(e) = run_step(step, &root, &mut report).await {
+                let mut block = vec![format!("❌ Step {} failed: {}", i + 1, e)];
+                if request.keep {
+                    block.push(format!("  Kept {} (--keep)", root.display()));
+                } else if std::fs::remove_dir_all(&root).is_ok() {
+                    block.push(format!("  Removed {}", root.display()));
+                }
+                report(block);
+                return Err(anyhow!("'{}' stopped at step {}", self.name, i + 1));
+            }
+        }
+        Ok(root)
+    }
+}
+
+/// `$ cargo init`, `write src/main.rs`, `mkdir tests`.
+pub fn step_label(step: &ScaffoldStep) -> String {
+    match step {
+        ScaffoldStep::Run(command) => format!("$ {}", command),
+        ScaffoldStep::Write { path, raw: false, .. } => format!("write {}", path),
+        ScaffoldStep::Write { path, raw: true, .. } => format!("write {} (raw)", path),
+        ScaffoldStep::Mkdir(path) => format!("mkdir {}", path),
+    }
+}
+
+async fn run_step(step: &ScaffoldStep, root: &Path, report: &mut impl FnMut(Vec<String>)) -> Result<()> {
+    match step {
+        ScaffoldStep::Run(command) => {
+            let mut child = if cfg!(windows) {
+                let mut c = tokio::process::Command::new("cmd");
+                c.args(["/C", command]);
+                c
+            } else {
+                let mut c = tokio::process::Command::new("sh");
+                c.args(["-c", command]);
+                c
+            };
+            child.current_dir(root).kill_on_drop(true);
+            let output = tokio::time::timeout(STEP_TIMEOUT, child.output())
+                .await
+                .map_err(|_| anyhow!("did not finish within {}s", STEP_TIMEOUT.as_secs()))??;
+            let text = String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr);
+            if !text.trim().is_empty() {
+                report(text.lines().map(|line| format!("    {}", line)).collect());
+            }
+            if !output.status.success() {
+                bail!("exit code {}", output.status.code().unwrap_or(-1));
+            }
+        }
+        ScaffoldStep::Write { path, content, .. } => {
+            let target = root.join(path);
+            if let Some(dir) = target.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&target, content).with_context(|| format!("Failed to write {}", path))?;
+        }
+        ScaffoldStep::Mkdir(path) => {
+            std::fs::create_dir_all(root.join(path)).with_context(|| format!("Failed to create {}", path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace each `{{key}}` with its value. Braces around anything that
+/// isn't a variable name are left alone; an unknown name is an error.
+pub fn substitute(text: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let key = after.find("}}").map(|end| &after[..end]).filter(|key| is_var_name(key.trim()));
+        match key {
+            Some(key) => {
+                let value = vars.get(key.trim()).ok_or_else(|| anyhow!("unknown variable {{{{{}}}}}", key.trim()))?;
+                out.push_str(value);
+                rest = &after[key.len() + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_var_name(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A scaffold or project name: one path component, no separators.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':'])
+        && !name.chars().any(char::is_control)
+}
+
+/// `path` if it stays inside the project directory.
+fn project_path(path: &str) -> Result<String> {
+    let inside = !path.trim().is_empty()
+        && Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        bail!("path '{}' leaves the project directory", path);
+    }
+    Ok(path.to_string())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Command
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewRequest {
+    pub scaffold: String,
+    pub name: String,
+    pub vars: Vec<(String, String)>,
+    /// Leave the directory behind when a step fails.
+    pub keep: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NewCommand {
+    List,
+    Create(NewRequest),
+}
+
+impl NewCommand {
+    /// Parse the arguments after `!new`.
+    pub fn parse(args: &str) -> Result<NewCommand, String> {
+        let mut words = Vec::new();
+        let mut vars = Vec::new();
+        let mut keep = false;
+        for word in args.split_whitespace() {
+            if word == "--keep" {
+                keep = true;
+            } else if word.starts_with("--") {
+                return Err(NEW_USAGE.to_string());
+            } else if let Some((key, value)) = word.split_once('=') {
+                if !is_var_name(key) {
+                    return Err(format!("❌ '{}' is not a variable name", key));
+                }
+                vars.push((key.to_string(), value.to_string()));
+            } else {
+                words.push(word);
+            }
+        }
+        match words.as_slice() {
+            ["list"] if vars.is_empty() && !keep => Ok(NewCommand::List),
+            [scaffold, name] => {
+                if !is_plain_name(name) {
+                    return Err(format!("❌ '{}' must be a plain directory name", name));
+                }
+                Ok(NewCommand::Create(NewRequest {
+                    scaffold: scaffold.to_string(),
+                    name: name.to_string(),
+                    vars,
+                    keep,
+                }))
+            }
+            _ => Err(NEW_USAGE.to_string()),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Library
+// ════════════════════════════════════════════════════════════════════
+
+/// The built-in scaffolds plus the user's `*.toml` files.
+#[derive(Debug, Clone)]
+pub struct Scaffolds {
+    dir: PathBuf,
+}
+
+impl Scaffolds {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every scaffold by name, and a message for each user file that
+    /// doesn't parse.
+    pub fn load(&self) -> (Vec<Scaffold>, Vec<String>) {
+        let mut scaffolds = Scaffold::builtins();
+        let mut errors = Vec::new();
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        for path in files {
+            let parsed = std::fs::read_to_string(&path).map_err(anyhow::Error::from).and_then(|t| Scaffold::parse(&t));
+            match parsed {
+                Ok(mut scaffold) => {
+                    scaffold.path = Some(path);
+                    scaffolds.retain(|s| s.name != scaffold.name);
+                    scaffolds.push(scaffold);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        scaffolds.sort_by(|a, b| a.name.cmp(&b.name));
+        (scaffolds, errors)
+    }
+
+    pub fn find(&self, name: &str) -> Option<Scaffold> {
+        self.load().0.into_iter().find(|s| s.name == name)
+    }
+}
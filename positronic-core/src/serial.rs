@@ -0,0 +1,290 @@
+//! `!io`: serial connections and the line parser each device uses.
+//!
+//! `!io connect COM3 115200 --parser kv --map T=0,H=1` picks a
+//! `positronic_io::ParserConfig` for the connection and remembers it in the
+//! vault under the port and, for USB devices, its VID:PID. Connecting the
+//! same device again without `--parser` restores it; `--parser raw` goes
+//! back to plain text.
+//!
+//! A USB device can be named (`!io name COM7 bench-scope`); the vault keeps
+//! the name and the baud, parser and reconnect setting last given for it,
+//! and `!io connect bench-scope` opens whichever port it is on now with
+//! those. `resolve_port` does the lookup against a port listing, so it is
+//! tested with made-up ones.
+//!
+//! `!io connect COM7 --auto-baud` leaves the rate to
+//! `positronic_io::baud`, which listens at each rate in
+//! `io.auto_baud.rates` for `io.auto_baud.dwell_ms`. The rate it settles on
+//! becomes the device's saved baud, so the next connect doesn't probe.
+
+use std::time::Duration;
+
+use positronic_io::device::{self, DeviceId, PortInfo};
+use positronic_io::parser::PARSER_USAGE;
+use positronic_io::{AutoBaud, BaudGuess, ParserConfig};
+
+use crate::vault::{DeviceDefaults, DeviceRecord, Vault};
+
+/// Vault config key prefix for saved parsers.
+pub const PARSER_KEY_PREFIX: &str = "io.parser.";
+
+pub const DEFAULT_BAUD: u32 = 115_200;
+
+/// Rates `--auto-baud` tries, in order: `115200,9600,57600`.
+pub const AUTO_BAUD_RATES_KEY: &str = "io.auto_baud.rates";
+/// How long `--auto-baud` listens at each rate, in milliseconds.
+pub const AUTO_BAUD_DWELL_KEY: &str = "io.auto_baud.dwell_ms";
+/// Score, 0 to 1, a rate needs for `--auto-baud` to pick it.
+pub const AUTO_BAUD_THRESHOLD_KEY: &str = "io.auto_baud.threshold";
+
+pub fn io_usage() -> String {
+    format!(
+        "Usage: !io scan [--probe] | !io list | !io name <port|name> <name> | \
+         !io connect <port|name> [baud|--auto-baud] [{}] [--reconnect on|off] | !io console <port|name> [baud]",
+        PARSER_USAGE
+    )
+}
+
+/// A parsed `!io connect`. `None` fields were not given: the device's
+/// saved settings, then the defaults, apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoConnect {
+    /// A port, or a device's name until `resolve_port` replaces it.
+    pub port: String,
+    pub baud: Option<u32>,
+    pub parser: Option<ParserConfig>,
+    /// Reopen the device if it drops.
+    pub reconnect: Option<bool>,
+    /// Find the baud rate by listening (`--auto-baud`).
+    pub auto_baud: bool,
+}
+
+impl IoConnect {
+    /// Parse the words after `!io connect`; the error is for the user.
+    pub fn parse(args: &[&str]) -> Result<IoConnect, String> {
+        let mut port = None;
+        let mut baud = None;
+        let mut kind = None;
+        let mut map = None;
+        let mut sentence = None;
+        let mut reconnect = None;
+        let mut auto_baud = false;
+        let mut args = args.iter();
+        while let Some(&arg) = args.next() {
+            match arg {
+                "--parser" => kind = Some(*args.next().ok_or_else(io_usage)?),
+                "--map" => map = Some(*args.next().ok_or_else(io_usage)?),
+                "--sentence" => sentence = Some(*args.next().ok_or_else(io_usage)?),
+                "--reconnect" => {
+                    reconnect = Some(match *args.next().ok_or_else(io_usage)? {
+                        "on" => true,
+                        "off" => false,
+                        other => return Err(format!("❌ --reconnect takes on or off, not '{}'", other)),
+                    });
+                }
+                "--auto-baud" => auto_baud = true,
+                a if a.starts_with("--") => return Err(io_usage()),
+                a if port.is_none() => port = Some(a.to_string()),
+                a if baud.is_none() => {
+                    baud = Some(a.parse().map_err(|_| format!("❌ Bad baud rate '{}'", a))?);
+                }
+                _ => return Err(io_usage()),
+            }
+        }
+        let port = port.ok_or_else(io_usage)?;
+        if let (true, Some(baud)) = (auto_baud, baud) {
+            return Err(format!("❌ --auto-baud finds the baud rate; drop {} or the flag", baud));
+        }
+        let parser = match kind {
+            Some(kind) => Some(ParserConfig::from_args(kind, map, sentence).map_err(|e| format!("❌ {}", e))?),
+            None if map.is_some() || sentence.is_some() => return Err("❌ --map and --sentence need --parser".to_string()),
+            None => None,
+        };
+        Ok(IoConnect { port, baud, parser, reconnect, auto_baud })
+    }
+
+    /// Fill what wasn't given from a device's saved settings; with
+    /// `--auto-baud` the saved baud is what gets replaced.
+    pub fn with_defaults(self, saved: &DeviceDefaults) -> IoConnect {
+        IoConnect {
+            baud: if self.auto_baud { None } else { self.baud.or(saved.baud) },
+            parser: self.parser.or_else(|| saved.parser.as_deref().and_then(|json| serde_json::from_str(json).ok())),
+            reconnect: self.reconnect.or(saved.reconnect),
+            ..self
+        }
+    }
+
+    /// What was given, to save as the device's settings.
+    pub fn given(&self) -> DeviceDefaults {
+        DeviceDefaults {
+            baud: self.baud,
+            parser: self.parser.as_ref().and_then(|parser| serde_json::to_string(parser).ok()),
+            eol: None,
+            reconnect: self.reconnect,
+        }
+    }
+}
+
+/// Where a `!io` target is: the port, the device behind it and what the
+/// vault knows about that device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPort {
+    pub port: String,
+    pub device: Option<DeviceId>,
+    pub record: Option<DeviceRecord>,
+    /// Other ports the same device is on (composite devices, or identical
+    /// boards without serial numbers); `port` is the first.
+    pub also_on: Vec<String>,
+}
+
+impl ResolvedPort {
+    pub fn name(&self) -> Option<&str> {
+        self.record.as_ref()?.name.as_deref()
+    }
+
+    /// `bench-scope (COM7)`, or just the port for unnamed devices.
+    pub fn label(&self) -> String {
+        port_label(&self.port, self.name())
+    }
+
+    /// The note to show when the device is on several ports.
+    pub fn ambiguity(&self) -> Option<String> {
+        if self.also_on.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "⚠️ {} is also on {}; using {}",
+            self.name().unwrap_or(&self.port),
+            self.also_on.join(", "),
+            self.port
+        ))
+    }
+}
+
+/// `bench-scope (COM7)`, or `COM7` without a name.
+pub fn port_label(port: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{} ({})", name, port),
+        None => port.to_string(),
+    }
+}
+
+/// Resolve `target`, a device name or a port, against the recorded
+/// `devices` and the `ports` listed now. A named device that isn't plugged
+/// in is an error; anything else is taken as a port name.
+pub fn resolve_port(devices: &[DeviceRecord], ports: &[PortInfo], target: &str) -> Result<ResolvedPort, String> {
+    if let Some(record) = devices.iter().find(|d| d.name.as_deref() == Some(target)) {
+        let id: DeviceId = record.id.parse().map_err(|e| format!("❌ {}", e))?;
+        let mut found = device::ports_of(ports, &id).into_iter().map(str::to_string);
+        let port = found.next().ok_or_else(|| {
+            format!("❌ {} ({}) isn't plugged in; it was last on {}", target, record.id, record.last_port)
+        })?;
+        return Ok(ResolvedPort { port, device: Some(id), record: Some(record.clone()), also_on: found.collect() });
+    }
+    let device = device::identify(ports, target).and_then(|info| info.device.clone());
+    let record = device.as_ref().and_then(|id| {
+        let id = id.to_string();
+        devices.iter().find(|d| d.id == id).cloned()
+    });
+    Ok(ResolvedPort { port: target.to_string(), device, record, also_on: Vec::new() })
+}
+
+/// Device names are single words that can't be mistaken for a flag or a
+/// path.
+pub fn validate_device_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('-')
+        || name.contains(|c: char| c.is_whitespace() || c == '/' || c == '\\' || c == ':')
+    {
+        return Err(format!("❌ '{}' can't be a device name: use one word without '/', '\\' or ':'", name));
+    }
+    Ok(())
+}
+
+/// `io.parser.COM3@2341:0043`, or `io.parser.COM3` without a USB id.
+pub fn parser_key(port: &str, usb_id: Option<&str>) -> String {
+    match usb_id {
+        Some(id) => format!("{}{}@{}", PARSER_KEY_PREFIX, port, id),
+        None => format!("{}{}", PARSER_KEY_PREFIX, port),
+    }
+}
+
+/// The parser saved under `key`; unreadable entries count as none.
+pub fn load_parser(vault: &Vault, key: &str) -> Option<ParserConfig> {
+    let json = vault.get_config(key).ok().flatten()?;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn save_parser(vault: &Vault, key: &str, parser: &ParserConfig) -> anyhow::Result<()> {
+    vault.set_config(key, &serde_json::to_string(parser)?)?;
+    Ok(())
+}
+
+/// The parser to connect with: the explicit one (saved for next time) or
+/// the saved one. The flag is true when it was restored.
+pub fn resolve_parser(vault: &Vault, key: &str, explicit: Option<ParserConfig>) -> anyhow::Result<(ParserConfig, bool)> {
+    match explicit {
+        Some(parser) => {
+            save_parser(vault, key, &parser)?;
+            Ok((parser, false))
+        }
+        None => Ok(match load_parser(vault, key) {
+            Some(parser) => (parser, true),
+            None => (ParserConfig::Raw, false),
+        }),
+    }
+}
+
+/// The `--auto-baud` probe the config keys ask for; missing or unreadable
+/// values keep the defaults.
+pub fn auto_baud_from_config(rates: Option<&str>, dwell_ms: Option<&str>, threshold: Option<&str>) -> AutoBaud {
+    let defaults = AutoBaud::default();
+    let candidates = rates
+        .map(|rates| rates.split(',').filter_map(|r| r.trim().parse::<u32>().ok()).filter(|r| *r > 0).collect())
+        .filter(|rates: &Vec<u32>| !rates.is_empty())
+        .unwrap_or(defaults.candidates);
+    let dwell = dwell_ms
+        .and_then(|ms| ms.trim().parse::<u64>().ok())
+        .filter(|ms| *ms > 0)
+        .map_or(defaults.dwell, Duration::from_millis);
+    let threshold = threshold
+        .and_then(|t| t.trim().parse::<f64>().ok())
+        .filter(|t| (0.0..=1.0).contains(t))
+        .unwrap_or(defaults.threshold);
+    AutoBaud { candidates, dwell, threshold, ..defaults }
+}
+
+/// What `--auto-baud` found on `port`, for the terminal.
+pub fn baud_report(port: &str, baud: Option<u32>, guesses: &[BaudGuess]) -> String {
+    if let Some(baud) = baud {
+        let score = guesses.iter().find(|g| g.baud == baud).map_or(0.0, |g| g.score);
+        return format!("🔌 {}: detected {} baud (score {:.2})", port, baud, score);
+    }
+    if guesses.is_empty() {
+        return format!("❌ {} refused every rate tried; give one: !io connect {} <baud>", port, port);
+    }
+    if guesses.iter().all(|g| g.bytes == 0) {
+        return format!(
+            "❌ {} sent nothing at any rate; --auto-baud needs a device that talks on its own. Give the rate: !io connect {} <baud>",
+            port, port
+        );
+    }
+    let best: Vec<String> = guesses.iter().take(3).map(BaudGuess::to_string).collect();
+    format!(
+        "⚠️ {}: no rate is clearly right; best guesses {}. Try one: !io connect {} <baud>",
+        port,
+        best.join(", "),
+        port
+    )
+}
+
+/// Make `baud` the saved baud of the USB device on `port`. False for
+/// ports without a USB identity to save it under.
+pub fn save_detected_baud(vault: &Vault, ports: &[PortInfo], port: &str, baud: u32) -> anyhow::Result<bool> {
+    let Some(id) = device::identify(ports, port).and_then(|info| info.device.as_ref()) else {
+        return Ok(false);
+    };
+    let defaults = DeviceDefaults { baud: Some(baud), ..DeviceDefaults::default() };
+    vault.set_device_defaults(&id.to_string(), &defaults)?;
+    Ok(true)
+}
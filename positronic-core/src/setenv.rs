@@ -0,0 +1,195 @@
+//! `!setenv`: environment variables set for every command.
+//!
+//! An override lives in the vault, either for this session (it survives a
+//! shell restart, not a new Positronic) or with `--global` for every
+//! session. The Runner puts the overlay in front of each line it sends to
+//! the shell, with the same prefix `!hook --env` uses (`VAR=val cmd`, or
+//! `$env:VAR='val'; cmd` in PowerShell), and hands it to the processes it
+//! starts itself (`!time`, `!diff --watch`, `!follow`, the airlock) through
+//! the process builder.
+//!
+//! Precedence, lowest first: the shell's own environment, `--global`
+//! overrides, session overrides, then a matching hook's `--env`. A hook
+//! names the commands it is for, so it wins over an overlay that applies
+//! to all of them.
+//!
+//! In a POSIX shell the prefix reaches the line's first command, as one
+//! typed out would, and lines opening with `if`, `for`, a `( … )` group
+//! and the like get none (see `takes_prefix`). Lines typed into a running
+//! program (a prompt, a REPL) get none either.
+//! PowerShell keeps a `$env:` assignment for the rest of the session, so
+//! there a removed override stays set until the shell restarts.
+
+use crate::alias;
+use crate::history_filter;
+use crate::hooks;
+use crate::redo::RedoShell;
+
+pub const SETENV_USAGE: &str = "Usage: !setenv <NAME> <value> [--global] | list | rm <NAME> | clear";
+
+/// Shown in listings in place of a secret value.
+const MASK: &str = "••••";
+
+/// Name parts that mark a variable as a secret.
+const SECRET_NAME_PARTS: &[&str] = &["token", "secret", "key", "password", "passwd", "auth", "credential"];
+
+/// Where an override applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvScope {
+    /// This session, across shell restarts.
+    Session,
+    /// Every session.
+    Global,
+}
+
+impl EnvScope {
+    pub fn label(self) -> &'static str {
+        match self {
+            EnvScope::Session => "session",
+            EnvScope::Global => "global",
+        }
+    }
+}
+
+/// A stored override.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvOverride {
+    pub name: String,
+    pub value: String,
+    pub scope: EnvScope,
+    pub set_at: i64,
+}
+
+/// A parsed `!setenv` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetenvCommand {
+    Set { name: String, value: String, scope: EnvScope },
+    List,
+    /// From both scopes.
+    Remove(String),
+    /// Everything, both scopes.
+    Clear,
+}
+
+impl SetenvCommand {
+    /// Parse what follows `!setenv`; the error is the message to show.
+    /// The value is the rest of the line, or follows `=` in `NAME=value`.
+    pub fn parse(args: &str) -> Result<SetenvCommand, String> {
+        let words: Vec<String> = alias::split_args(args).iter().map(|w| alias::unquote(w)).collect();
+        let global = words.iter().any(|w| w == "--global" || w == "-g");
+        let words: Vec<&str> = words.iter().map(String::as_str).filter(|w| *w != "--global" && *w != "-g").collect();
+        let scope = if global { EnvScope::Global } else { EnvScope::Session };
+        // `--global` only means something to a set.
+        match words.as_slice() {
+            [] | ["list"] => Ok(SetenvCommand::List),
+            ["rm" | "remove" | "unset", name] => {
+                check_name(name)?;
+                Ok(SetenvCommand::Remove(name.to_string()))
+            }
+            ["rm" | "remove" | "unset", ..] => Err(SETENV_USAGE.to_string()),
+            ["clear"] => Ok(SetenvCommand::Clear),
+            [first, rest @ ..] => {
+                let (name, value) = match first.split_once('=') {
+                    Some((name, value)) if rest.is_empty() => (name, value.to_string()),
+                    Some(_) => return Err(SETENV_USAGE.to_string()),
+                    None if rest.is_empty() => return Err(format!("❌ {} needs a value", first)),
+                    None => (*first, rest.join(" ")),
+                };
+                check_name(name)?;
+                Ok(SetenvCommand::Set { name: name.to_string(), value, scope })
+            }
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if hooks::is_env_name(name) {
+        Ok(())
+    } else {
+        Err(format!("❌ '{}' is not a variable name", name))
+    }
+}
+
+/// The variables to set, in the order to set them: global overrides,
+/// then session ones, a session override replacing a global one.
+pub fn overlay(overrides: &[EnvOverride]) -> Vec<(String, String)> {
+    let session = |name: &str| overrides.iter().any(|o| o.scope == EnvScope::Session && o.name == name);
+    let globals = overrides.iter().filter(|o| o.scope == EnvScope::Global && !session(&o.name));
+    let sessions = overrides.iter().filter(|o| o.scope == EnvScope::Session);
+    globals.chain(sessions).map(|o| (o.name.clone(), o.value.clone())).collect()
+}
+
+/// Words a POSIX line can't have assignments in front of.
+const COMPOUND_WORDS: &[&str] = &["if", "for", "while", "until", "case", "function", "select", "{", "(", "((", "[[", "!"];
+
+/// `command` as written to `shell`: the overlay, then the hook's env,
+/// so a hook setting the same variable wins.
+pub fn command_line(overlay: &[(String, String)], hook_env: &[(String, String)], shell: RedoShell, command: &str) -> String {
+    let overlay = if takes_prefix(shell, command) { overlay } else { &[] };
+    let env: Vec<(String, String)> = overlay
+        .iter()
+        .filter(|(name, _)| !hook_env.iter().any(|(hooked, _)| hooked == name))
+        .chain(hook_env)
+        .cloned()
+        .collect();
+    hooks::with_env(&env, shell, command)
+}
+
+/// Whether the overlay can go in front of `command`. In a POSIX shell an
+/// assignment before `if`, `for` or a `( … )` group is a syntax error, so
+/// those lines run without it.
+pub fn takes_prefix(shell: RedoShell, command: &str) -> bool {
+    if shell == RedoShell::PowerShell {
+        return true;
+    }
+    let trimmed = command.trim_start();
+    let first = trimmed.split_whitespace().next().unwrap_or("");
+    !(COMPOUND_WORDS.contains(&first) || trimmed.starts_with('(') || trimmed.starts_with('{'))
+}
+
+/// Whether a value shouldn't be listed: by the variable's name, or
+/// because the privacy scrubber finds a credential in it.
+pub fn is_secret(name: &str, value: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    SECRET_NAME_PARTS.iter().any(|part| lower.contains(part)) || history_filter::has_credential(value)
+}
+
+/// `value` as listed.
+pub fn shown_value(name: &str, value: &str) -> String {
+    if is_secret(name, value) {
+        MASK.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// The `!setenv list` output.
+pub fn list_lines(overrides: &[EnvOverride]) -> Vec<String> {
+    if overrides.is_empty() {
+        return vec![
+            "🔧 No environment overrides".to_string(),
+            "   !setenv KUBECONFIG ~/.kube/dev [--global]".to_string(),
+        ];
+    }
+    let mut lines = vec![format!("🔧 Environment overrides ({}):", overrides.len())];
+    let width = overrides.iter().map(|o| o.name.len()).max().unwrap_or(0);
+    for o in overrides {
+        let shadowed = o.scope == EnvScope::Global
+            && overrides.iter().any(|other| other.scope == EnvScope::Session && other.name == o.name);
+        lines.push(format!(
+            "  {:<width$}  {:<7}  {}{}",
+            o.name,
+            o.scope.label(),
+            shown_value(&o.name, &o.value),
+            if shadowed { "   (session value wins)" } else { "" },
+            width = width
+        ));
+    }
+    lines.push("   A hook's --env for the same variable wins over these".to_string());
+    lines
+}
+
+/// The status bar badge: `🔧 3 env`, or nothing with no overrides.
+pub fn badge(count: usize) -> Option<String> {
+    (count > 0).then(|| format!("🔧 {} env", count))
+}
@@ -0,0 +1,67 @@
+//! The active shell's command inventory, for Reflex corrections.
+//!
+//! The shell kind comes from the PTY's spawn config (`ShellKind::detect`).
+//! PowerShell's static table is extended with whatever `Get-Command` lists
+//! on this machine, so installed modules count as real commands. That run
+//! happens once, in the background; its names are cached in the vault and
+//! later sessions read them back instead of starting another PowerShell.
+//! cmd.exe and POSIX shells use their static tables.
+
+use std::process::{Command, Stdio};
+
+use positronic_neural::reflex::{CommandInventory, ShellKind};
+
+use crate::vault::Vault;
+
+/// Lists every alias, cmdlet and function, one name per line.
+pub const DISCOVERY_SCRIPT: &str =
+    "Get-Command -CommandType Alias,Cmdlet,Function -ErrorAction SilentlyContinue | ForEach-Object Name";
+
+/// The static table for `shell` plus the names discovered earlier.
+pub fn inventory(vault: &Vault, shell: ShellKind) -> CommandInventory {
+    let mut inventory = CommandInventory::builtin(shell);
+    match vault.shell_commands(shell.label()) {
+        Ok(names) => inventory.extend(names),
+        Err(e) => eprintln!("[REFLEX] Reading discovered {} commands failed: {}", shell.label(), e),
+    }
+    inventory
+}
+
+/// True for PowerShell until a discovery run has been cached.
+pub fn needs_discovery(vault: &Vault, shell: ShellKind) -> bool {
+    shell == ShellKind::PowerShell && vault.shell_commands(shell.label()).is_ok_and(|names| names.is_empty())
+}
+
+/// The PowerShell to ask: `pwsh` if that is the configured shell, else
+/// Windows PowerShell.
+pub fn powershell_program(shell: Option<&str>) -> &'static str {
+    match shell {
+        Some(shell) if shell.to_lowercase().contains("pwsh") => "pwsh",
+        _ => "powershell",
+    }
+}
+
+/// Run `DISCOVERY_SCRIPT` in `program`. `None` if it couldn't run.
+pub fn discover(program: &str) -> Option<Vec<String>> {
+    let output = Command::new(program)
+        .args(["-NoLogo", "-NoProfile", "-NonInteractive", "-Command", DISCOVERY_SCRIPT])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let names = parse_discovered(&String::from_utf8_lossy(&output.stdout));
+    (!names.is_empty()).then_some(names)
+}
+
+/// Command names from `DISCOVERY_SCRIPT` output.
+pub fn parse_discovered(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains(char::is_whitespace))
+        .map(str::to_string)
+        .collect()
+}
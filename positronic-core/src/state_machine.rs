@@ -1,9 +1,12 @@
 use alacritty_terminal::event::{Event, EventListener};
-use alacritty_terminal::grid::Dimensions;
+use alacritty_terminal::grid::{Dimensions, Scroll};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::{Config as TermConfig, Term};
 use alacritty_terminal::vte::ansi;
 
 use std::ops::Index;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 // ════════════════════════════════════════════════════════════════════
@@ -33,15 +36,21 @@ impl Dimensions for TermSize {
 }
 
 // ════════════════════════════════════════════════════════════════════
-// Event Proxy (Required Stub)
+// Event Proxy
 // ════════════════════════════════════════════════════════════════════
 
-#[derive(Clone)]
-pub struct EventProxy;
+/// Counts the emulator's bells until `StateMachine::take_bells`; its other
+/// events aren't needed.
+#[derive(Clone, Default)]
+pub struct EventProxy {
+    bells: Arc<AtomicUsize>,
+}
 
 impl EventListener for EventProxy {
-    fn send_event(&self, _event: Event) {
-        // Headless: No events needed
+    fn send_event(&self, event: Event) {
+        if let Event::Bell = event {
+            self.bells.fetch_add(1, Ordering::Relaxed);
+        }
     }
 }
 
@@ -49,7 +58,7 @@ impl EventListener for EventProxy {
 // Color Handling
 // ════════════════════════════════════════════════════════════════════
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MyColor {
     Default,
     Black,
@@ -123,11 +132,111 @@ impl MyColor {
 
 pub type SnapshotCell = (char, MyColor);
 
+/// SGR text attributes of a cell, as a bitfield.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CellAttrs(u8);
+
+impl CellAttrs {
+    /// SGR 1, reset by 22.
+    pub const BOLD: CellAttrs = CellAttrs(1);
+    /// SGR 2, reset by 22.
+    pub const DIM: CellAttrs = CellAttrs(1 << 1);
+    /// SGR 3, reset by 23.
+    pub const ITALIC: CellAttrs = CellAttrs(1 << 2);
+    /// SGR 4 (and 4:x, 21 double), reset by 24.
+    pub const UNDERLINE: CellAttrs = CellAttrs(1 << 3);
+    /// SGR 7, reset by 27.
+    pub const INVERSE: CellAttrs = CellAttrs(1 << 4);
+    /// SGR 9, reset by 29.
+    pub const STRIKETHROUGH: CellAttrs = CellAttrs(1 << 5);
+
+    pub const fn empty() -> Self {
+        CellAttrs(0)
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[inline]
+    pub const fn contains(self, other: CellAttrs) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    #[inline]
+    pub fn insert(&mut self, other: CellAttrs) {
+        self.0 |= other.0;
+    }
+
+    fn from_flags(flags: Flags) -> Self {
+        let mut attrs = CellAttrs::empty();
+        let pairs = [
+            (Flags::BOLD, CellAttrs::BOLD),
+            (Flags::DIM, CellAttrs::DIM),
+            (Flags::ITALIC, CellAttrs::ITALIC),
+            (Flags::ALL_UNDERLINES, CellAttrs::UNDERLINE),
+            (Flags::INVERSE, CellAttrs::INVERSE),
+            (Flags::STRIKEOUT, CellAttrs::STRIKETHROUGH),
+        ];
+        for (flag, attr) in pairs {
+            if flags.intersects(flag) {
+                attrs.insert(attr);
+            }
+        }
+        attrs
+    }
+}
+
+impl std::ops::BitOr for CellAttrs {
+    type Output = CellAttrs;
+
+    fn bitor(self, rhs: CellAttrs) -> CellAttrs {
+        CellAttrs(self.0 | rhs.0)
+    }
+}
+
+/// Everything about a cell besides its character and foreground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellStyle {
+    pub attrs: CellAttrs,
+    pub bg: MyColor,
+    /// SGR 58; `None` underlines in the foreground color.
+    pub underline_color: Option<MyColor>,
+}
+
+impl Default for CellStyle {
+    fn default() -> Self {
+        Self { attrs: CellAttrs::empty(), bg: MyColor::Default, underline_color: None }
+    }
+}
+
+/// Fills the cell after a wide character (CJK, most emoji), and the last
+/// column when a wide character didn't fit and wrapped. The lead cell
+/// carries the character; spacers are neither drawn nor copied.
+pub const WIDE_SPACER: char = '\0';
+
 #[derive(Debug, Clone)]
 pub struct Snapshot {
     cols: usize,
     rows: usize,
     pub cells: Vec<SnapshotCell>, // row-major: row * cols + col
+    /// Attributes and background, parallel to `cells`.
+    pub styles: Vec<CellStyle>,
+    /// Zero-width characters (combining marks, joiners, variation
+    /// selectors) that follow a cell's character, by cell index, in order.
+    pub clusters: Vec<(usize, String)>,
+    /// Per row: whether it is soft-wrapped, its line running on into the
+    /// next row. Copying joins such rows without a newline, and resizing
+    /// re-flows them to the new width (except on the alternate screen).
+    pub wraps: Vec<bool>,
+    /// Cursor (row, col) on the visible screen.
+    pub cursor: (usize, usize),
 }
 
 impl Snapshot {
@@ -137,6 +246,10 @@ impl Snapshot {
             cols,
             rows,
             cells: vec![(' ', MyColor::Default); len],
+            styles: vec![CellStyle::default(); len],
+            clusters: Vec::new(),
+            wraps: vec![false; rows],
+            cursor: (0, 0),
         }
     }
 
@@ -162,6 +275,55 @@ impl Snapshot {
         self.rows == 0 || self.cols == 0
     }
 
+    #[inline]
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// The styles of `row`'s cells, parallel to `self[row]`.
+    pub fn row_styles(&self, row: usize) -> &[CellStyle] {
+        let start = row * self.cols;
+        &self.styles[start..start + self.cols]
+    }
+
+    /// The zero-width characters attached to cells of `row`, as (column, text).
+    pub fn row_clusters(&self, row: usize) -> &[(usize, String)] {
+        let start = row * self.cols;
+        let end = start + self.cols;
+        let from = self.clusters.partition_point(|(i, _)| *i < start);
+        let to = self.clusters.partition_point(|(i, _)| *i < end);
+        &self.clusters[from..to]
+    }
+
+    /// Whether `row`'s line continues on the next row.
+    #[inline]
+    pub fn wraps(&self, row: usize) -> bool {
+        self.wraps.get(row).copied().unwrap_or(false)
+    }
+
+    /// Whether `row` continues the line of the row above it.
+    #[inline]
+    pub fn continues(&self, row: usize) -> bool {
+        row > 0 && self.wraps(row - 1)
+    }
+
+    /// The text of `row` as it reads: one character per wide glyph, with
+    /// its combining marks, and no spacers. Trailing blanks are kept.
+    pub fn row_text(&self, row: usize) -> String {
+        let start = row * self.cols;
+        let mut clusters = self.row_clusters(row).iter().peekable();
+        let mut text = String::with_capacity(self.cols);
+        for (col, (ch, _)) in self[row].iter().enumerate() {
+            if *ch != WIDE_SPACER {
+                text.push(*ch);
+            }
+            if let Some((_, extra)) = clusters.next_if(|(i, _)| *i == start + col) {
+                text.push_str(extra);
+            }
+        }
+        text
+    }
+
     #[inline]
     fn ensure_size(&mut self, cols: usize, rows: usize) {
         if self.cols == cols && self.rows == rows {
@@ -173,11 +335,17 @@ impl Snapshot {
 
         let len = cols.saturating_mul(rows);
         self.cells.resize(len, (' ', MyColor::Default));
+        self.styles.resize(len, CellStyle::default());
+        self.wraps.resize(rows, false);
     }
 
     #[inline]
     fn clear(&mut self) {
         self.cells.fill((' ', MyColor::Default));
+        self.styles.fill(CellStyle::default());
+        self.clusters.clear();
+        self.wraps.fill(false);
+        self.cursor = (0, 0);
     }
 }
 
@@ -234,6 +402,7 @@ struct Inner {
 
 pub struct StateMachine {
     inner: Arc<Mutex<Inner>>,
+    bells: Arc<AtomicUsize>,
 }
 
 impl std::fmt::Debug for StateMachine {
@@ -252,13 +421,16 @@ impl StateMachine {
         };
 
         let config = TermConfig::default();
-        let term = Term::new(config, &size, EventProxy);
+        let proxy = EventProxy::default();
+        let bells = proxy.bells.clone();
+        let term = Term::new(config, &size, proxy);
         let parser = ansi::Processor::new();
 
         eprintln!("[STATE_MACHINE] Created with {} cols x {} rows", cols, rows);
 
         Self {
             inner: Arc::new(Mutex::new(Inner { term, parser })),
+            bells,
         }
     }
 
@@ -277,31 +449,28 @@ impl StateMachine {
             return;
         }
 
-        // Debug: Show what we're processing
-        let preview = if bytes.len() <= 100 {
-            String::from_utf8_lossy(bytes).to_string()
-        } else {
-            format!("{}... ({} more bytes)",
-                    String::from_utf8_lossy(&bytes[..100]),
-                    bytes.len() - 100)
-        };
-        eprintln!("[STATE_MACHINE] Processing {} bytes: '{}'", bytes.len(), preview);
+        // Debug: Show what we're processing (borrowed preview, no copy of the chunk)
+        let head = &bytes[..bytes.len().min(100)];
+        eprintln!(
+            "[STATE_MACHINE] Processing {} bytes: '{}'{}",
+            bytes.len(),
+            String::from_utf8_lossy(head),
+            if bytes.len() > head.len() { "..." } else { "" }
+        );
 
         let mut inner = self.lock_inner();
 
         // Split the mutable borrow safely.
         let Inner { term, parser } = &mut *inner;
 
-        // Process all bytes at once - the parser handles buffering internally
+        // Process all bytes at once. The parser keeps its own UTF-8 state, so
+        // a character split across two chunks is reassembled, not replaced.
         parser.advance(term, bytes);
+    }
 
-        // Debug: Show terminal state after processing
-        let grid = term.grid();
-        let mut content_preview = String::new();
-        for indexed in grid.display_iter().take(80) {
-            content_preview.push(indexed.cell.c);
-        }
-        eprintln!("[STATE_MACHINE] Terminal content (first 80 chars): '{}'", content_preview.trim());
+    /// BELs processed since the last call.
+    pub fn take_bells(&self) -> usize {
+        self.bells.swap(0, Ordering::Relaxed)
     }
 
     /// Allocate a fresh snapshot.
@@ -323,14 +492,16 @@ impl StateMachine {
         out.clear();
 
         let grid = inner.term.grid();
+        let offset = grid.display_offset() as i32;
 
         // IMPORTANT: We must use `display_iter()` which accounts for scrollback and viewport
         for indexed in grid.display_iter() {
             let col = indexed.point.column.0;
 
             // IMPORTANT: Line is often i32. Guard negatives.
-            // Also, `display_iter` returns lines relative to the visible screen.
-            let line_i32 = indexed.point.line.0;
+            // `display_iter` yields grid lines, negative in the scrollback;
+            // shift them by the display offset to get screen rows.
+            let line_i32 = indexed.point.line.0 + offset;
             if line_i32 < 0 {
                 continue;
             }
@@ -343,10 +514,108 @@ impl StateMachine {
             let cell = indexed.cell;
             let idx = line * cols + col;
 
-            out.cells[idx] = (cell.c, MyColor::from_alacritty(cell.fg));
+            let spacer = cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER);
+            let c = if spacer { WIDE_SPACER } else { cell.c };
+            out.cells[idx] = (c, MyColor::from_alacritty(cell.fg));
+            out.styles[idx] = CellStyle {
+                attrs: CellAttrs::from_flags(cell.flags),
+                bg: MyColor::from_alacritty(cell.bg),
+                underline_color: cell.underline_color().map(MyColor::from_alacritty),
+            };
+            if let Some(extra) = cell.zerowidth() {
+                out.clusters.push((idx, extra.iter().collect()));
+            }
+            if cell.flags.contains(Flags::WRAPLINE) {
+                out.wraps[line] = true;
+            }
+        }
+
+        let cursor = grid.cursor.point;
+        let cursor_line = cursor.line.0 + offset;
+        if cursor_line >= 0 && (cursor_line as usize) < rows {
+            out.cursor = (cursor_line as usize, cursor.column.0.min(cols.saturating_sub(1)));
+        }
+    }
+
+    /// Columns and rows of the visible screen.
+    pub fn size(&self) -> (u16, u16) {
+        let inner = self.lock_inner();
+        (inner.term.columns() as u16, inner.term.screen_lines() as u16)
+    }
+
+    /// Lines in the scrollback above the screen.
+    pub fn history_size(&self) -> usize {
+        self.lock_inner().term.grid().history_size()
+    }
+
+    /// The cursor's line counted from the top of the scrollback, whatever
+    /// the display offset. Stops growing once the scrollback is full.
+    pub fn cursor_line(&self) -> usize {
+        let inner = self.lock_inner();
+        let grid = inner.term.grid();
+        grid.history_size() + grid.cursor.point.line.0.max(0) as usize
+    }
+
+    /// The text of `lines`, counted as `cursor_line` counts them, with
+    /// trailing blanks trimmed and wrapped rows joined into one line.
+    /// Lines past the bottom of the screen are left out.
+    pub fn text_lines(&self, lines: std::ops::Range<usize>) -> Vec<String> {
+        let inner = self.lock_inner();
+        let grid = inner.term.grid();
+        let history = grid.history_size();
+        let end = lines.end.min(history + grid.screen_lines());
+        let mut out = Vec::new();
+        let mut current = String::new();
+        for absolute in lines.start.min(end)..end {
+            let row = &grid[Line(absolute as i32 - history as i32)];
+            let mut wrapped = false;
+            for col in 0..grid.columns() {
+                let cell = &row[Column(col)];
+                if !cell.flags.intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER) {
+                    current.push(cell.c);
+                }
+                wrapped = cell.flags.contains(Flags::WRAPLINE);
+            }
+            if !wrapped {
+                out.push(current.trim_end().to_string());
+                current.clear();
+            }
         }
+        if !current.is_empty() {
+            out.push(current.trim_end().to_string());
+        }
+        out
+    }
+
+    /// How many lines back into the scrollback the snapshot starts.
+    pub fn display_offset(&self) -> usize {
+        self.lock_inner().term.grid().display_offset()
+    }
+
+    /// Show the screen `offset` lines back into the scrollback; 0 is the
+    /// live screen. Clamped to the scrollback there is.
+    pub fn set_display_offset(&self, offset: usize) {
+        let mut inner = self.lock_inner();
+        let current = inner.term.grid().display_offset() as i32;
+        let target = offset.min(i32::MAX as usize) as i32;
+        if target != current {
+            inner.term.scroll_display(Scroll::Delta(target - current));
+        }
+    }
+
+    /// Start over for a new shell: leave the alternate screen, reset
+    /// attributes and any half-parsed sequence, and clear the screen. What
+    /// was on it moves into the scrollback rather than being lost.
+    pub fn clear_screen(&self) {
+        let mut inner = self.lock_inner();
+        let Inner { term, parser } = &mut *inner;
+        *parser = ansi::Processor::new();
+        parser.advance(term, b"\x1b[?1049l\x1b[0m\x1b[?25h\x1b[H\x1b[2J");
     }
 
+    /// Resize the screen. Soft-wrapped lines, on the screen and in the
+    /// scrollback, re-flow to the new width; the alternate screen belongs
+    /// to an app that lays itself out, so it is cut or padded instead.
     pub fn resize(&self, cols: u16, rows: u16) {
         eprintln!("[STATE_MACHINE] Resizing to {} cols x {} rows", cols, rows);
 
@@ -0,0 +1,258 @@
+//! Subsystem registry — concurrent, failure-isolated engine boot.
+//!
+//! Only the PTY, state machine and Vault sit on the critical path of
+//! `PositronicEngine::start`. Everything else (Neural, WASM, Hive, IO) is
+//! handed to `Subsystems::spawn`, which runs the init future on its own task
+//! and records the outcome. Callers reach a subsystem through its
+//! `Subsystem<T>` handle: `get()` returns the live instance, or an error such
+//! as "neural unavailable: <reason>" so one broken feature degrades only
+//! itself instead of failing the whole boot.
+
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+// ════════════════════════════════════════════════════════════════════
+// Status
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubsystemState {
+    Starting,
+    Ready,
+    Failed(String),
+}
+
+impl SubsystemState {
+    pub fn is_settled(&self) -> bool {
+        !matches!(self, SubsystemState::Starting)
+    }
+}
+
+impl fmt::Display for SubsystemState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubsystemState::Starting => write!(f, "starting…"),
+            SubsystemState::Ready => write!(f, "ready"),
+            SubsystemState::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+/// One row of the status map.
+#[derive(Debug, Clone)]
+pub struct SubsystemStatus {
+    pub name: &'static str,
+    pub state: SubsystemState,
+    /// Init duration once settled; time spent so far while starting.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+struct Slot {
+    name: &'static str,
+    state: SubsystemState,
+    started: Instant,
+    elapsed: Option<Duration>,
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Registry
+// ════════════════════════════════════════════════════════════════════
+
+/// Shared, cloneable status map. Rows keep registration order.
+#[derive(Debug, Clone, Default)]
+pub struct Subsystems {
+    slots: Arc<Mutex<Vec<Slot>>>,
+    settled: Arc<Notify>,
+}
+
+impl Subsystems {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark `name` as starting now.
+    pub fn begin(&self, name: &'static str) {
+        let mut slots = self.lock();
+        let slot = Slot {
+            name,
+            state: SubsystemState::Starting,
+            started: Instant::now(),
+            elapsed: None,
+        };
+        match slots.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = slot,
+            None => slots.push(slot),
+        }
+    }
+
+    pub fn ready(&self, name: &'static str) {
+        self.settle(name, SubsystemState::Ready);
+    }
+
+    pub fn fail(&self, name: &'static str, reason: impl Into<String>) {
+        self.settle(name, SubsystemState::Failed(reason.into()));
+    }
+
+    /// Run `init` on its own task and publish the result through the
+    /// returned handle. Panics inside `init` are recorded as failures.
+    pub fn spawn<T, F>(&self, name: &'static str, init: F) -> Subsystem<T>
+    where
+        T: Send + Sync + 'static,
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        let handle = Subsystem::new(name);
+        self.begin(name);
+
+        let registry = self.clone();
+        let cell = handle.cell.clone();
+        tokio::spawn(async move {
+            let outcome = match tokio::spawn(init).await {
+                Ok(result) => result.map_err(|e| format!("{:#}", e)),
+                Err(join) => Err(format!("init task aborted: {}", join)),
+            };
+            match outcome {
+                Ok(value) => {
+                    let _ = cell.set(Ok(Arc::new(value)));
+                    registry.ready(name);
+                }
+                Err(reason) => {
+                    eprintln!("[ENGINE] {} unavailable: {}", name, reason);
+                    let _ = cell.set(Err(reason.clone()));
+                    registry.fail(name, reason);
+                }
+            }
+        });
+
+        handle
+    }
+
+    pub fn state(&self, name: &str) -> Option<SubsystemState> {
+        self.lock().iter().find(|s| s.name == name).map(|s| s.state.clone())
+    }
+
+    pub fn snapshot(&self) -> Vec<SubsystemStatus> {
+        self.lock()
+            .iter()
+            .map(|s| SubsystemStatus {
+                name: s.name,
+                state: s.state.clone(),
+                elapsed: s.elapsed.unwrap_or_else(|| s.started.elapsed()),
+            })
+            .collect()
+    }
+
+    /// True once every registered subsystem has finished initializing.
+    pub fn all_settled(&self) -> bool {
+        self.lock().iter().all(|s| s.state.is_settled())
+    }
+
+    /// True if any subsystem failed — the engine is running without it.
+    pub fn is_degraded(&self) -> bool {
+        self.lock()
+            .iter()
+            .any(|s| matches!(s.state, SubsystemState::Failed(_)))
+    }
+
+    /// Wait until nothing is left starting.
+    pub async fn wait_settled(&self) {
+        loop {
+            let notified = self.settled.notified();
+            if self.all_settled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn settle(&self, name: &'static str, state: SubsystemState) {
+        {
+            let mut slots = self.lock();
+            match slots.iter_mut().find(|s| s.name == name) {
+                Some(slot) => {
+                    slot.elapsed = Some(slot.started.elapsed());
+                    slot.state = state;
+                }
+                None => slots.push(Slot {
+                    name,
+                    state,
+                    started: Instant::now(),
+                    elapsed: Some(Duration::ZERO),
+                }),
+            }
+        }
+        self.settled.notify_waiters();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Slot>> {
+        match self.slots.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Handle
+// ════════════════════════════════════════════════════════════════════
+
+/// Late-bound access to a subsystem that may still be starting or may
+/// have failed.
+#[derive(Debug)]
+pub struct Subsystem<T> {
+    name: &'static str,
+    cell: Arc<OnceLock<std::result::Result<Arc<T>, String>>>,
+}
+
+impl<T> Clone for Subsystem<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            cell: self.cell.clone(),
+        }
+    }
+}
+
+impl<T> Subsystem<T> {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            cell: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// A handle that is already initialized (tests, embedders).
+    pub fn ready(name: &'static str, value: Arc<T>) -> Self {
+        let handle = Self::new(name);
+        let _ = handle.cell.set(Ok(value));
+        handle
+    }
+
+    /// A handle that is already failed with `reason`.
+    pub fn failed(name: &'static str, reason: impl Into<String>) -> Self {
+        let handle = Self::new(name);
+        let _ = handle.cell.set(Err(reason.into()));
+        handle
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The live instance, or why it isn't available.
+    pub fn get(&self) -> Result<Arc<T>> {
+        match self.cell.get() {
+            Some(Ok(value)) => Ok(value.clone()),
+            Some(Err(reason)) => Err(anyhow!("{} unavailable: {}", self.name, reason)),
+            None => Err(anyhow!("{} is still starting", self.name)),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(self.cell.get(), Some(Ok(_)))
+    }
+}
@@ -0,0 +1,565 @@
+//! `!summary [today|session|<YYYY-MM-DD>]`: what was done, as Markdown.
+//!
+//! The Vault supplies the window's commands (as `!timeline` selects
+//! them) and `summarize` reduces them: how many ran and which most,
+//! where the time went, what failed and whether a later run fixed it.
+//! Time is inferred from timestamps: a command's span plus the gap to
+//! the next one counts as active in its directory, unless the gap is
+//! `IDLE_GAP_SECS` or longer, which is idle. `!rm` and `!rename` logs add
+//! the files moved in the window.
+//!
+//! With `summary.auto_save_dir` set, closing a session writes its summary
+//! there (`save_on_close`); `summary.use_neural` adds a model-written
+//! recap paragraph to `!summary`, from scrubbed figures only.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use chrono::TimeZone;
+use positronic_neural::injection;
+use positronic_neural::privacy::PrivacyGuard;
+
+use crate::history_filter::parse_flag;
+use crate::rename::{RenameLog, Renames};
+use crate::time_format::{self, TimeSettings};
+use crate::timeline::{TimelineRange, IDLE_GAP_SECS};
+use crate::trash::{Manifest, Trash};
+use crate::vault::timing::format_ms;
+use crate::vault::{CommandRecord, Vault};
+use crate::version::home_relative;
+
+pub const SUMMARY_USAGE: &str = "Usage: !summary [today|session|<YYYY-MM-DD>] [--save]";
+
+pub const AUTO_SAVE_DIR_KEY: &str = "summary.auto_save_dir";
+pub const USE_NEURAL_KEY: &str = "summary.use_neural";
+
+/// The newest commands a summary reads.
+pub const SUMMARY_CAP: usize = 50_000;
+
+/// Commands listed under "Most run".
+const TOP_COMMANDS: usize = 5;
+/// Directories listed under "Where the time went"; the rest are summed.
+const TOP_DIRECTORIES: usize = 8;
+/// Failures listed; the rest are counted.
+const LISTED_FAILURES: usize = 10;
+/// Files named per `!rm` or `!rename` line.
+const LISTED_FILES: usize = 5;
+
+/// How long closing waits for the summary; past it, the write finishes
+/// on its own thread while the session closes.
+pub const CLOSE_BUDGET: Duration = Duration::from_millis(1500);
+
+/// `summary.*`, as read from the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SummarySettings {
+    /// Where closing a session writes its summary; off when unset.
+    pub auto_save_dir: Option<PathBuf>,
+    /// Whether `!summary` asks the model for a recap paragraph.
+    pub use_neural: bool,
+}
+
+impl SummarySettings {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> SummarySettings {
+        let mut settings = SummarySettings::default();
+        if let Some(value) = lookup(AUTO_SAVE_DIR_KEY) {
+            let dir = value.trim();
+            if !dir.is_empty() {
+                settings.auto_save_dir = Some(PathBuf::from(dir));
+            }
+        }
+        if let Some(value) = lookup(USE_NEURAL_KEY) {
+            match parse_flag(&value) {
+                Some(on) => settings.use_neural = on,
+                None => problems.push(format!("{} = \"{}\": expected on or off", USE_NEURAL_KEY, value)),
+            }
+        }
+        settings
+    }
+}
+
+/// What follows `!summary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SummaryRequest {
+    pub range: TimelineRange,
+    /// Also write it to `summary.auto_save_dir`.
+    pub save: bool,
+}
+
+impl SummaryRequest {
+    /// Parse the arguments; today when there are none. The error is the
+    /// message to show.
+    pub fn parse(args: &str) -> Result<SummaryRequest, String> {
+        let mut save = false;
+        let mut range = None;
+        for arg in args.split_whitespace() {
+            match arg {
+                "--save" => save = true,
+                _ if range.is_none() => {
+                    range = Some(TimelineRange::parse(arg).map_err(|_| SUMMARY_USAGE.to_string())?);
+                }
+                _ => return Err(SUMMARY_USAGE.to_string()),
+            }
+        }
+        Ok(SummaryRequest { range: range.unwrap_or(TimelineRange::Today), save })
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Aggregation
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandCount {
+    pub command: String,
+    pub runs: usize,
+}
+
+/// Time spent in one directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryTime {
+    pub directory: String,
+    pub active_secs: i64,
+    pub commands: usize,
+}
+
+/// A command that failed in a directory, and whether a later run of it
+/// there succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub command: String,
+    pub directory: String,
+    pub failures: usize,
+    /// Unix seconds.
+    pub last_failed_at: i64,
+    /// When it first succeeded after its last failure.
+    pub resolved_at: Option<i64>,
+}
+
+/// Files `!rm` and `!rename` moved in the window, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileActivity {
+    pub trashed: Vec<PathBuf>,
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+impl FileActivity {
+    /// The operations in `manifest` and `log` within `[from, to)`.
+    pub fn between(manifest: &Manifest, log: &RenameLog, from: i64, to: i64) -> FileActivity {
+        let within = |ts: i64| (from..to).contains(&ts);
+        FileActivity {
+            trashed: manifest
+                .operations
+                .iter()
+                .filter(|op| within(op.deleted_at))
+                .flat_map(|op| op.items.iter().map(|item| item.original.clone()))
+                .collect(),
+            renamed: log
+                .operations
+                .iter()
+                .filter(|op| within(op.renamed_at))
+                .flat_map(|op| op.items.iter().map(|item| (item.from.clone(), item.to.clone())))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trashed.is_empty() && self.renamed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    /// `today`, `this session` or the date.
+    pub label: String,
+    /// When the first command started and the last one ended.
+    pub span: Option<(i64, i64)>,
+    pub commands: usize,
+    /// Runs that exited non-zero.
+    pub failed_runs: usize,
+    /// Most run first.
+    pub top: Vec<CommandCount>,
+    /// Most time first.
+    pub directories: Vec<DirectoryTime>,
+    /// Still failing first, then most recent.
+    pub failures: Vec<Failure>,
+    pub files: FileActivity,
+    pub active_secs: i64,
+    pub idle_secs: i64,
+}
+
+/// A command as runs of it are grouped: its words, single-spaced.
+fn normalize(command: &str) -> String {
+    command.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// When `record` started: history rows are stamped when the command ends.
+fn started(record: &CommandRecord) -> i64 {
+    record.timestamp - record.duration_ms.unwrap_or(0).max(0) / 1000
+}
+
+/// Reduce `records` (oldest first, as `Vault::summary_records` returns
+/// them) and the window's `files`.
+pub fn summarize(label: &str, records: &[CommandRecord], files: FileActivity) -> SessionSummary {
+    let mut ordered: Vec<&CommandRecord> = records.iter().collect();
+    ordered.sort_by_key(|r| (started(r), r.id));
+
+    let mut runs: HashMap<String, usize> = HashMap::new();
+    for record in &ordered {
+        *runs.entry(normalize(&record.command)).or_default() += 1;
+    }
+    let mut top: Vec<CommandCount> = runs.into_iter().map(|(command, runs)| CommandCount { command, runs }).collect();
+    top.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.command.cmp(&b.command)));
+    top.truncate(TOP_COMMANDS);
+
+    let (directories, active_secs, idle_secs) = time_spent(&ordered);
+    let span = ordered
+        .first()
+        .map(|first| (started(first), ordered.iter().map(|r| r.timestamp).max().unwrap_or(first.timestamp)));
+
+    SessionSummary {
+        label: label.to_string(),
+        span,
+        commands: ordered.len(),
+        failed_runs: ordered.iter().filter(|r| matches!(r.exit_code, Some(code) if code != 0)).count(),
+        top,
+        directories,
+        failures: failures(records),
+        files,
+        active_secs,
+        idle_secs,
+    }
+}
+
+/// Each directory's active time, and the active and idle totals. From a
+/// command's start to the next one's start is active in the command's
+/// directory when the gap after it ended is under `IDLE_GAP_SECS`;
+/// otherwise only the command's own span is, and the gap is idle.
+/// Overlapping commands count their shared time once.
+fn time_spent(ordered: &[&CommandRecord]) -> (Vec<DirectoryTime>, i64, i64) {
+    let mut by_dir: HashMap<&str, DirectoryTime> = HashMap::new();
+    let (mut active, mut idle) = (0, 0);
+    // Time before this is already counted.
+    let mut covered = i64::MIN;
+    for (i, record) in ordered.iter().enumerate() {
+        let (start, end) = (started(record), record.timestamp.max(started(record)));
+        let until = match ordered.get(i + 1).map(|next| started(next)) {
+            Some(next) if next - end < IDLE_GAP_SECS => next.max(end),
+            Some(next) => {
+                idle += (next - end.max(covered)).max(0);
+                end
+            }
+            None => end,
+        };
+        let secs = (until - start.max(covered)).max(0);
+        covered = covered.max(until);
+        active += secs;
+        let dir = by_dir.entry(&record.directory).or_insert_with(|| DirectoryTime {
+            directory: record.directory.clone(),
+            active_secs: 0,
+            commands: 0,
+        });
+        dir.active_secs += secs;
+        dir.commands += 1;
+    }
+    let mut directories: Vec<DirectoryTime> = by_dir.into_values().collect();
+    directories.sort_by(|a, b| {
+        b.active_secs.cmp(&a.active_secs).then(b.commands.cmp(&a.commands)).then_with(|| a.directory.cmp(&b.directory))
+    });
+    (directories, active, idle)
+}
+
+/// Failures by command and directory. A success after the last failure
+/// resolves it; a failure after that reopens it.
+fn failures(records: &[CommandRecord]) -> Vec<Failure> {
+    let mut ordered: Vec<&CommandRecord> = records.iter().collect();
+    ordered.sort_by_key(|r| (r.timestamp, r.id));
+    let mut found: Vec<Failure> = Vec::new();
+    let mut index: HashMap<(String, &str), usize> = HashMap::new();
+    for record in ordered {
+        let key = (normalize(&record.command), record.directory.as_str());
+        match record.exit_code {
+            Some(0) => {
+                if let Some(failure) = index.get(&key).map(|&i| &mut found[i]) {
+                    failure.resolved_at.get_or_insert(record.timestamp);
+                }
+            }
+            Some(_) => {
+                let i = *index.entry(key).or_insert_with_key(|(command, directory)| {
+                    found.push(Failure {
+                        command: command.clone(),
+                        directory: directory.to_string(),
+                        failures: 0,
+                        last_failed_at: record.timestamp,
+                        resolved_at: None,
+                    });
+                    found.len() - 1
+                });
+                let failure = &mut found[i];
+                failure.failures += 1;
+                failure.last_failed_at = record.timestamp;
+                failure.resolved_at = None;
+            }
+            None => {}
+        }
+    }
+    found.sort_by(|a, b| {
+        a.resolved_at.is_some().cmp(&b.resolved_at.is_some()).then(b.last_failed_at.cmp(&a.last_failed_at))
+    });
+    found
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Markdown
+// ════════════════════════════════════════════════════════════════════
+
+/// `42s`, `3m 05s`, `2h 10m`.
+fn duration(secs: i64) -> String {
+    match secs.max(0) {
+        secs if secs < 60 => format!("{}s", secs),
+        secs => format_ms(secs * 1000),
+    }
+}
+
+fn path_text(path: &str, home: Option<&Path>) -> String {
+    home_relative(Path::new(path), home)
+}
+
+/// The first few of `names`, and how many more.
+fn listed(names: impl ExactSizeIterator<Item = String>) -> String {
+    let total = names.len();
+    let mut text = names.take(LISTED_FILES).collect::<Vec<_>>().join(", ");
+    if total > LISTED_FILES {
+        text.push_str(&format!(" and {} more", total - LISTED_FILES));
+    }
+    text
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// The summary as a Markdown document. Times are in `tz`, on `time`'s
+/// clock; paths under `home` start with `~`.
+pub fn markdown<Tz: TimeZone>(summary: &SessionSummary, tz: &Tz, time: &TimeSettings, home: Option<&Path>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let title = match summary.label.as_str() {
+        "today" | "this session" => format!("# What I did {}", summary.label),
+        date => format!("# What I did on {}", date),
+    };
+    let mut out = vec![title, String::new()];
+    let Some((first, last)) = summary.span else {
+        out.push("No commands were run.".to_string());
+        return out.join("\n") + "\n";
+    };
+    let day = time_format::local(first, tz).map(|t| t.format("%A %Y-%m-%d").to_string()).unwrap_or_default();
+    out.push(format!(
+        "{} · {}–{} · {} active, {} idle",
+        day,
+        time_format::clock(first, tz, time.clock),
+        time_format::clock(last, tz, time.clock),
+        duration(summary.active_secs),
+        duration(summary.idle_secs)
+    ));
+
+    out.extend([String::new(), "## Commands".to_string(), String::new()]);
+    let failed = match summary.failed_runs {
+        0 => "none failed".to_string(),
+        1 => "1 failed".to_string(),
+        n => format!("{} failed", n),
+    };
+    let plural = if summary.commands == 1 { "" } else { "s" };
+    out.push(format!("{} command{}, {}. Most run:", summary.commands, plural, failed));
+    out.push(String::new());
+    out.extend(summary.top.iter().map(|c| format!("- `{}` ×{}", c.command, c.runs)));
+
+    out.extend([String::new(), "## Where the time went".to_string(), String::new()]);
+    for dir in summary.directories.iter().take(TOP_DIRECTORIES) {
+        let plural = if dir.commands == 1 { "" } else { "s" };
+        out.push(format!(
+            "- `{}`: {}, {} command{}",
+            path_text(&dir.directory, home),
+            duration(dir.active_secs),
+            dir.commands,
+            plural
+        ));
+    }
+    let rest = &summary.directories[summary.directories.len().min(TOP_DIRECTORIES)..];
+    if !rest.is_empty() {
+        let secs: i64 = rest.iter().map(|d| d.active_secs).sum();
+        out.push(format!("- {} other directories: {}", rest.len(), duration(secs)));
+    }
+
+    if !summary.failures.is_empty() {
+        out.extend([String::new(), "## Failures".to_string(), String::new()]);
+        for failure in summary.failures.iter().take(LISTED_FAILURES) {
+            let times = if failure.failures == 1 { "once".to_string() } else { format!("{}×", failure.failures) };
+            let (mark, outcome) = match failure.resolved_at {
+                Some(at) => ("✅", format!("fixed at {}", time_format::clock(at, tz, time.clock))),
+                None => (
+                    "❌",
+                    format!("last at {}, still failing", time_format::clock(failure.last_failed_at, tz, time.clock)),
+                ),
+            };
+            out.push(format!(
+                "- {} `{}` in `{}`: failed {}, {}",
+                mark,
+                failure.command,
+                path_text(&failure.directory, home),
+                times,
+                outcome
+            ));
+        }
+        if summary.failures.len() > LISTED_FAILURES {
+            out.push(format!("- and {} more", summary.failures.len() - LISTED_FAILURES));
+        }
+    }
+
+    let files = &summary.files;
+    if !files.is_empty() {
+        out.extend([String::new(), "## Files".to_string(), String::new()]);
+        if !files.renamed.is_empty() {
+            let names = files.renamed.iter().map(|(from, to)| format!("`{}` → `{}`", file_name(from), file_name(to)));
+            out.push(format!("- {} renamed with `!rename`: {}", files.renamed.len(), listed(names)));
+        }
+        if !files.trashed.is_empty() {
+            let names = files.trashed.iter().map(|path| format!("`{}`", file_name(path)));
+            out.push(format!("- {} moved to the trash with `!rm`: {}", files.trashed.len(), listed(names)));
+        }
+    }
+    out.join("\n") + "\n"
+}
+
+/// `markdown` with the model's `recap` as a closing section.
+pub fn with_recap(markdown: &str, recap: &str) -> String {
+    format!("{}\n## Recap\n\n{}\n", markdown, recap.trim())
+}
+
+/// The request for a recap paragraph: the summary's figures, scrubbed and
+/// fenced as data. Paths go in as their last component only.
+pub fn recap_prompt(summary: &SessionSummary) -> String {
+    let mut facts = vec![
+        format!("Period: {}", summary.label),
+        format!("Commands: {} ({} failed)", summary.commands, summary.failed_runs),
+        format!("Active: {}, idle: {}", duration(summary.active_secs), duration(summary.idle_secs)),
+    ];
+    facts.extend(summary.top.iter().map(|c| format!("Ran {} times: {}", c.runs, c.command)));
+    let base = |dir: &str| file_name(Path::new(dir));
+    facts.extend(
+        summary.directories.iter().take(TOP_DIRECTORIES).map(|d| format!("In {}: {}", base(&d.directory), duration(d.active_secs))),
+    );
+    facts.extend(summary.failures.iter().take(LISTED_FAILURES).map(|f| {
+        let state = if f.resolved_at.is_some() { "fixed later" } else { "still failing" };
+        format!("Failed {}x, {}: {}", f.failures, state, f.command)
+    }));
+    if !summary.files.is_empty() {
+        facts.push(format!(
+            "Files renamed: {}, moved to trash: {}",
+            summary.files.renamed.len(),
+            summary.files.trashed.len()
+        ));
+    }
+    format!(
+        "Write one short paragraph (at most four sentences) recapping this stretch of terminal work for the \
+         user's notes, in the second person. Use only these figures:\n\n{}",
+        injection::fence("summary", &PrivacyGuard::scrub(&facts.join("\n")))
+    )
+}
+
+/// The file a summary is saved as: `summary-2026-03-08.md` for a day,
+/// `summary-2026-03-08-1402-session.md` for a session started at 14:02.
+pub fn save_name<Tz: TimeZone>(summary: &SessionSummary, range: TimelineRange, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let local = |ts: i64, pattern: &str| time_format::local(ts, tz).map(|t| t.format(pattern).to_string());
+    match range {
+        TimelineRange::Day(date) => format!("summary-{}.md", date.format("%Y-%m-%d")),
+        TimelineRange::Today => {
+            let now = chrono::Utc::now().timestamp();
+            format!("summary-{}.md", local(now, "%Y-%m-%d").unwrap_or_default())
+        }
+        TimelineRange::Session => {
+            let start = summary.span.map_or_else(|| chrono::Utc::now().timestamp(), |(first, _)| first);
+            format!("summary-{}-session.md", local(start, "%Y-%m-%d-%H%M").unwrap_or_default())
+        }
+    }
+}
+
+/// Write `markdown` to `dir/name`, creating `dir`.
+pub fn save(dir: &Path, name: &str, markdown: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    std::fs::write(&path, markdown)?;
+    Ok(path)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Sources
+// ════════════════════════════════════════════════════════════════════
+
+/// What a summary is read from; cheap to clone onto another thread.
+#[derive(Debug, Clone)]
+pub struct SummarySources {
+    pub vault: Vault,
+    pub trash: Trash,
+    pub renames: Renames,
+}
+
+impl SummarySources {
+    /// `range`'s summary as of `now`. A damaged `!rm` or `!rename` log
+    /// leaves out its files rather than the summary.
+    pub fn gather(&self, range: TimelineRange, now: i64, utc_offset_secs: i64) -> Result<SessionSummary> {
+        let window = range.window(now, utc_offset_secs);
+        let records = self.vault.summary_records(window)?;
+        let (from, to) = window.unwrap_or_else(|| (records.first().map_or(now, started), now + 1));
+        let files = FileActivity::between(
+            &self.trash.manifest().unwrap_or_default(),
+            &self.renames.log().unwrap_or_default(),
+            from,
+            to,
+        );
+        Ok(summarize(&range.label(), &records, files))
+    }
+
+    /// Write this session's summary to `dir`, in local time; None when no
+    /// commands ran.
+    pub fn save_session(&self, dir: &Path) -> Result<Option<PathBuf>> {
+        let tz = chrono::Local;
+        let now = chrono::Utc::now().timestamp();
+        let offset = tz.timestamp_opt(now, 0).single().map_or(0, |t| t.offset().local_minus_utc() as i64);
+        let summary = self.gather(TimelineRange::Session, now, offset)?;
+        if summary.commands == 0 {
+            return Ok(None);
+        }
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).map(PathBuf::from);
+        let text = markdown(&summary, &tz, &self.vault.time_settings(), home.as_deref());
+        save(dir, &save_name(&summary, TimelineRange::Session, &tz), &text).map(Some)
+    }
+}
+
+/// On session close: write the session's summary to
+/// `summary.auto_save_dir` if set, waiting at most `CLOSE_BUDGET`. The
+/// write runs on its own thread, so a slow disk finishes it after the
+/// session is gone instead of holding the close. No neural recap here:
+/// closing doesn't wait on a model.
+pub fn save_on_close(sources: SummarySources, settings: &SummarySettings) -> Result<Option<PathBuf>> {
+    let Some(dir) = settings.auto_save_dir.clone() else {
+        return Ok(None);
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("positronic-summary".to_string())
+        .spawn(move || {
+            let _ = tx.send(sources.save_session(&dir));
+        })?;
+    match rx.recv_timeout(CLOSE_BUDGET) {
+        Ok(saved) => saved,
+        Err(_) => bail!("still writing after {}; left to finish in the background", format_ms(CLOSE_BUDGET.as_millis() as i64)),
+    }
+}
@@ -0,0 +1,592 @@
+//! `!sync`: share history, aliases and bookmarks between machines through
+//! a file, with no server.
+//!
+//! A bundle is ndjson, one `SyncEntry` per line. Each entry carries the
+//! machine it came from (`Vault::machine_id`) and a hash of its content,
+//! so a bundle can be appended to forever: `export` only adds entries the
+//! file doesn't have, and `import` only takes entries the vault doesn't
+//! have. Importing the same or overlapping bundles again changes nothing.
+//!
+//! History rows are identified by hash. Aliases merge by name and
+//! bookmarks by command; when both sides have one and they differ, the
+//! newer wins (ties go to the larger hash, so both machines settle on
+//! the same one) and the import reports it as a conflict. Deletions don't
+//! travel: an alias removed here comes back with the next import that
+//! has it. Entries from this machine are never imported back.
+//!
+//! With `sync.auto_export` set to a directory (a Dropbox or Syncthing
+//! folder, say), each closed session appends to
+//! `<dir>/positronic-<machine id>.ndjson`; `!sync import <dir>` reads
+//! every bundle in it.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cancel::CANCELLED;
+use crate::vault::{Alias, Bookmark, LockState, Vault};
+
+pub const SYNC_USAGE: &str = "Usage: !sync export <path> | !sync import <path|dir>";
+
+/// Vault config key holding this vault's machine id, made on first use.
+pub const MACHINE_ID_KEY: &str = "sync.machine_id";
+
+/// Vault config key: a directory to export a bundle to when a session closes.
+pub const AUTO_EXPORT_KEY: &str = "sync.auto_export";
+
+/// Extension of the files `!sync import <dir>` reads.
+pub const BUNDLE_EXTENSION: &str = "ndjson";
+
+/// Hex digits of SHA-256 kept in a hash.
+const HASH_LEN: usize = 32;
+
+/// One line of a bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncEntry {
+    History(HistoryEntry),
+    Alias(AliasEntry),
+    Bookmark(BookmarkEntry),
+}
+
+/// A history row, without its output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub hash: String,
+    /// Where the command ran, not where it was exported from.
+    pub machine: String,
+    pub session: String,
+    pub command: String,
+    pub directory: String,
+    pub exit_code: Option<i32>,
+    pub timestamp: i64,
+    pub duration_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    pub hash: String,
+    pub machine: String,
+    pub name: String,
+    pub expansion: String,
+    /// When it was last set.
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkEntry {
+    pub hash: String,
+    pub machine: String,
+    pub command: String,
+    pub label: Option<String>,
+    pub created_at: i64,
+}
+
+/// SHA-256 of the JSON of `fields`, shortened.
+fn content_hash(fields: impl Serialize) -> String {
+    let json = serde_json::to_string(&fields).expect("tuples of strings and numbers serialize");
+    let mut hash = format!("{:x}", Sha256::digest(json.as_bytes()));
+    hash.truncate(HASH_LEN);
+    hash
+}
+
+/// A history row's hash covers its machine and session: the same command
+/// run at the same second on two machines is two rows.
+pub fn history_hash(
+    machine: &str,
+    session: &str,
+    command: &str,
+    directory: &str,
+    exit_code: Option<i32>,
+    timestamp: i64,
+    duration_ms: Option<i64>,
+) -> String {
+    content_hash(("history", machine, session, command, directory, exit_code, timestamp, duration_ms))
+}
+
+/// Alias and bookmark hashes cover their content only, so the same alias
+/// exported by either machine is the same entry.
+pub fn alias_hash(name: &str, expansion: &str, updated_at: i64) -> String {
+    content_hash(("alias", name, expansion, updated_at))
+}
+
+pub fn bookmark_hash(command: &str, label: Option<&str>, created_at: i64) -> String {
+    content_hash(("bookmark", command, label, created_at))
+}
+
+impl SyncEntry {
+    pub fn history(
+        machine: &str,
+        session: &str,
+        command: &str,
+        directory: &str,
+        exit_code: Option<i32>,
+        timestamp: i64,
+        duration_ms: Option<i64>,
+    ) -> SyncEntry {
+        SyncEntry::History(HistoryEntry {
+            hash: history_hash(machine, session, command, directory, exit_code, timestamp, duration_ms),
+            machine: machine.to_string(),
+            session: session.to_string(),
+            command: command.to_string(),
+            directory: directory.to_string(),
+            exit_code,
+            timestamp,
+            duration_ms,
+        })
+    }
+
+    pub fn alias(machine: &str, name: &str, expansion: &str, updated_at: i64) -> SyncEntry {
+        SyncEntry::Alias(AliasEntry {
+            hash: alias_hash(name, expansion, updated_at),
+            machine: machine.to_string(),
+            name: name.to_string(),
+            expansion: expansion.to_string(),
+            updated_at,
+        })
+    }
+
+    pub fn bookmark(machine: &str, command: &str, label: Option<&str>, created_at: i64) -> SyncEntry {
+        SyncEntry::Bookmark(BookmarkEntry {
+            hash: bookmark_hash(command, label, created_at),
+            machine: machine.to_string(),
+            command: command.to_string(),
+            label: label.map(str::to_string),
+            created_at,
+        })
+    }
+
+    pub fn hash(&self) -> &str {
+        match self {
+            SyncEntry::History(e) => &e.hash,
+            SyncEntry::Alias(e) => &e.hash,
+            SyncEntry::Bookmark(e) => &e.hash,
+        }
+    }
+
+    pub fn machine(&self) -> &str {
+        match self {
+            SyncEntry::History(e) => &e.machine,
+            SyncEntry::Alias(e) => &e.machine,
+            SyncEntry::Bookmark(e) => &e.machine,
+        }
+    }
+
+    /// The hash the content calls for; an entry whose stored hash differs
+    /// was edited or damaged.
+    pub fn expected_hash(&self) -> String {
+        match self {
+            SyncEntry::History(e) => history_hash(
+                &e.machine,
+                &e.session,
+                &e.command,
+                &e.directory,
+                e.exit_code,
+                e.timestamp,
+                e.duration_ms,
+            ),
+            SyncEntry::Alias(e) => alias_hash(&e.name, &e.expansion, e.updated_at),
+            SyncEntry::Bookmark(e) => bookmark_hash(&e.command, e.label.as_deref(), e.created_at),
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(self).expect("entries serialize")
+    }
+}
+
+/// The entries of a bundle's text, and how many lines were not entries
+/// or had the wrong hash.
+pub fn parse_bundle(text: &str) -> (Vec<SyncEntry>, usize) {
+    let mut entries = Vec::new();
+    let mut malformed = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<SyncEntry>(line) {
+            Ok(entry) if entry.hash() == entry.expected_hash() => entries.push(entry),
+            _ => malformed += 1,
+        }
+    }
+    (entries, malformed)
+}
+
+/// What a vault would export: its history, aliases and bookmarks, oldest
+/// history first. Rows imported from elsewhere keep their machine.
+pub fn vault_entries(vault: &Vault) -> Result<Vec<SyncEntry>> {
+    let me = vault.machine_id()?;
+    let mut entries = Vec::new();
+    for (record, origin) in vault.synced_history()? {
+        entries.push(SyncEntry::history(
+            origin.as_deref().unwrap_or(&me),
+            &record.session_id,
+            &record.command,
+            &record.directory,
+            record.exit_code,
+            record.timestamp,
+            record.duration_ms,
+        ));
+    }
+    for alias in vault.list_aliases()? {
+        entries.push(SyncEntry::alias(&me, &alias.name, &alias.expansion, alias.created_at));
+    }
+    let mut bookmarks = vault.list_bookmarks()?;
+    bookmarks.reverse();
+    for bookmark in bookmarks {
+        entries.push(SyncEntry::bookmark(&me, &bookmark.command, bookmark.label.as_deref(), bookmark.created_at));
+    }
+    Ok(entries)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Merge
+// ════════════════════════════════════════════════════════════════════
+
+/// What an import sees of the vault.
+#[derive(Debug, Clone)]
+pub struct LocalState {
+    pub machine: String,
+    pub history: HashSet<String>,
+    pub aliases: Vec<Alias>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl LocalState {
+    pub fn load(vault: &Vault) -> Result<LocalState> {
+        let history = vault_entries(vault)?
+            .into_iter()
+            .filter(|e| matches!(e, SyncEntry::History(_)))
+            .map(|e| e.hash().to_string())
+            .collect();
+        Ok(LocalState {
+            machine: vault.machine_id()?,
+            history,
+            aliases: vault.list_aliases()?,
+            bookmarks: vault.list_bookmarks()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    Alias,
+    Bookmark,
+}
+
+/// An alias or bookmark both sides had, differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    /// The alias name or bookmarked command.
+    pub key: String,
+    pub local: String,
+    pub incoming: String,
+    pub machine: String,
+    /// The incoming one was newer and replaced the local one.
+    pub took_incoming: bool,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ConflictKind::Alias => "alias",
+            ConflictKind::Bookmark => "bookmark",
+        };
+        let machine = short_machine(&self.machine);
+        if self.took_incoming {
+            write!(f, "{} {}: took '{}' from {} over '{}'", kind, self.key, self.incoming, machine, self.local)
+        } else {
+            write!(f, "{} {}: kept '{}' over older '{}' from {}", kind, self.key, self.local, self.incoming, machine)
+        }
+    }
+}
+
+fn short_machine(machine: &str) -> &str {
+    machine.get(..8).unwrap_or(machine)
+}
+
+/// What an import writes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// New rows, oldest first.
+    pub history: Vec<HistoryEntry>,
+    /// Aliases to set, new or replacing.
+    pub aliases: Vec<AliasEntry>,
+    /// New bookmarks, and the local bookmark (by id) a newer one relabels.
+    pub bookmarks: Vec<(Option<i64>, BookmarkEntry)>,
+    pub conflicts: Vec<Conflict>,
+    /// Entries already here, from this machine, or superseded by a newer
+    /// one in the same bundle.
+    pub unchanged: usize,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty() && self.aliases.is_empty() && self.bookmarks.is_empty()
+    }
+}
+
+/// `(timestamp, hash)` orders versions: newest wins, ties by hash.
+fn newer(a: (i64, &str), b: (i64, &str)) -> bool {
+    a > b
+}
+
+/// Merge `incoming` into `local` (see the module doc).
+pub fn plan(local: &LocalState, incoming: Vec<SyncEntry>) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    let mut seen = HashSet::new();
+    let mut aliases: BTreeMap<String, AliasEntry> = BTreeMap::new();
+    let mut bookmarks: BTreeMap<String, BookmarkEntry> = BTreeMap::new();
+
+    for entry in incoming {
+        if entry.machine() == local.machine || !seen.insert(entry.hash().to_string()) {
+            plan.unchanged += 1;
+            continue;
+        }
+        match entry {
+            SyncEntry::History(e) if local.history.contains(&e.hash) => plan.unchanged += 1,
+            SyncEntry::History(e) => plan.history.push(e),
+            SyncEntry::Alias(e) => {
+                let replaced = match aliases.get(&e.name) {
+                    Some(have) if !newer((e.updated_at, &e.hash), (have.updated_at, &have.hash)) => {
+                        plan.unchanged += 1;
+                        continue;
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+                plan.unchanged += replaced as usize;
+                aliases.insert(e.name.clone(), e);
+            }
+            SyncEntry::Bookmark(e) => {
+                let replaced = match bookmarks.get(&e.command) {
+                    Some(have) if !newer((e.created_at, &e.hash), (have.created_at, &have.hash)) => {
+                        plan.unchanged += 1;
+                        continue;
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+                plan.unchanged += replaced as usize;
+                bookmarks.insert(e.command.clone(), e);
+            }
+        }
+    }
+    plan.history.sort_by_key(|e| e.timestamp);
+
+    for (name, entry) in aliases {
+        let Some(have) = local.aliases.iter().find(|a| a.name == name) else {
+            plan.aliases.push(entry);
+            continue;
+        };
+        if have.expansion == entry.expansion {
+            plan.unchanged += 1;
+            continue;
+        }
+        let have_hash = alias_hash(&have.name, &have.expansion, have.created_at);
+        let took_incoming = newer((entry.updated_at, &entry.hash), (have.created_at, &have_hash));
+        plan.conflicts.push(Conflict {
+            kind: ConflictKind::Alias,
+            key: name,
+            local: have.expansion.clone(),
+            incoming: entry.expansion.clone(),
+            machine: entry.machine.clone(),
+            took_incoming,
+        });
+        if took_incoming {
+            plan.aliases.push(entry);
+        } else {
+            plan.unchanged += 1;
+        }
+    }
+
+    for (command, entry) in bookmarks {
+        let same_command: Vec<&Bookmark> = local.bookmarks.iter().filter(|b| b.command == command).collect();
+        if same_command.iter().any(|b| b.label == entry.label) {
+            plan.unchanged += 1;
+            continue;
+        }
+        let Some(have) = same_command.into_iter().max_by_key(|b| (b.created_at, b.id)) else {
+            plan.bookmarks.push((None, entry));
+            continue;
+        };
+        let have_hash = bookmark_hash(&have.command, have.label.as_deref(), have.created_at);
+        let took_incoming = newer((entry.created_at, &entry.hash), (have.created_at, &have_hash));
+        plan.conflicts.push(Conflict {
+            kind: ConflictKind::Bookmark,
+            key: command,
+            local: label_text(have.label.as_deref()),
+            incoming: label_text(entry.label.as_deref()),
+            machine: entry.machine.clone(),
+            took_incoming,
+        });
+        if took_incoming {
+            plan.bookmarks.push((Some(have.id), entry));
+        } else {
+            plan.unchanged += 1;
+        }
+    }
+    plan
+}
+
+fn label_text(label: Option<&str>) -> String {
+    label.unwrap_or("(no label)").to_string()
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Files
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportReport {
+    pub path: PathBuf,
+    /// Entries appended.
+    pub written: usize,
+    /// Entries the file already had.
+    pub present: usize,
+}
+
+/// Append the vault's entries that `path` doesn't have yet, creating it
+/// if needed.
+pub fn export(vault: &Vault, path: &Path) -> Result<ExportReport> {
+    let entries = vault_entries(vault)?;
+    let existing = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let have: HashSet<String> = parse_bundle(&existing).0.into_iter().map(|e| e.hash().to_string()).collect();
+
+    let mut out = String::new();
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        out.push('\n');
+    }
+    let mut written = 0;
+    for entry in entries.iter().filter(|e| !have.contains(e.hash())) {
+        out.push_str(&entry.to_line());
+        out.push('\n');
+        written += 1;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("writing {}", path.display()))?;
+    if written > 0 {
+        file.write_all(out.as_bytes()).with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(ExportReport { path: path.to_path_buf(), written, present: entries.len() - written })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    pub plan: SyncPlan,
+    /// Bundles read.
+    pub files: usize,
+    /// Lines that were not entries or failed their hash.
+    pub malformed: usize,
+}
+
+/// Merge the bundle at `path`, or every bundle in the directory `path`.
+pub fn import(vault: &Vault, path: &Path) -> Result<ImportReport> {
+    import_with(vault, path, |_, _| true)
+}
+
+/// `import`, calling `progress(done, total)` as history rows are written.
+/// When it answers false the import stops and nothing of it is kept.
+pub fn import_with(vault: &Vault, path: &Path, mut progress: impl FnMut(usize, usize) -> bool) -> Result<ImportReport> {
+    let files = bundle_files(path)?;
+    let mut entries = Vec::new();
+    let mut malformed = 0;
+    for file in &files {
+        let text = std::fs::read_to_string(file).with_context(|| format!("reading {}", file.display()))?;
+        let (found, bad) = parse_bundle(&text);
+        entries.extend(found);
+        malformed += bad;
+    }
+    let plan = plan(&LocalState::load(vault)?, entries);
+    let total = plan.history.len();
+    if !progress(0, total) || (!plan.is_empty() && !vault.apply_sync_with(&plan, |done| progress(done, total))?) {
+        bail!(CANCELLED);
+    }
+    Ok(ImportReport { plan, files: files.len(), malformed })
+}
+
+fn bundle_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path).with_context(|| format!("reading {}", path.display()))? {
+        let file = entry?.path();
+        if file.is_file() && file.extension().is_some_and(|ext| ext == BUNDLE_EXTENSION) {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// This machine's bundle in an auto-export directory.
+pub fn bundle_path(dir: &Path, machine: &str) -> PathBuf {
+    dir.join(format!("positronic-{}.{}", machine, BUNDLE_EXTENSION))
+}
+
+/// Export to `sync.auto_export`, if it is set. Called when a session closes.
+pub fn auto_export(vault: &Vault) -> Result<Option<ExportReport>> {
+    let Some(dir) = vault.get_config(AUTO_EXPORT_KEY)?.filter(|dir| !dir.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let dir = PathBuf::from(dir.trim());
+    std::fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    export(vault, &bundle_path(&dir, &vault.machine_id()?)).map(Some)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Text
+// ════════════════════════════════════════════════════════════════════
+
+pub fn export_lines(report: &ExportReport, lock: LockState) -> Vec<String> {
+    let mut lines = vec![format!(
+        "🔁 Exported {} new entries to {} ({} already there)",
+        report.written,
+        report.path.display(),
+        report.present
+    )];
+    if lock != LockState::Plain {
+        lines.push("⚠️ The bundle is plain text; the vault's encryption doesn't cover it".to_string());
+    }
+    lines
+}
+
+pub fn import_lines(report: &ImportReport, path: &Path) -> Vec<String> {
+    let plan = &report.plan;
+    let replaced = plan.conflicts.iter().filter(|c| c.took_incoming).count();
+    let new_aliases = plan.aliases.len() - plan.conflicts.iter().filter(|c| c.took_incoming && c.kind == ConflictKind::Alias).count();
+    let new_bookmarks = plan.bookmarks.iter().filter(|(id, _)| id.is_none()).count();
+    let files = match report.files {
+        1 => String::new(),
+        n => format!(" ({} bundles)", n),
+    };
+    let mut lines = vec![format!(
+        "🔁 Imported from {}{}: {} commands, {} aliases, {} bookmarks; {} replaced, {} unchanged",
+        path.display(),
+        files,
+        plan.history.len(),
+        new_aliases,
+        new_bookmarks,
+        replaced,
+        plan.unchanged
+    )];
+    if !plan.conflicts.is_empty() {
+        lines.push(format!("⚠️ {} conflicts, newest wins:", plan.conflicts.len()));
+        lines.extend(plan.conflicts.iter().map(|c| format!("  {}", c)));
+    }
+    if report.malformed > 0 {
+        lines.push(format!("⚠️ Skipped {} malformed or altered lines", report.malformed));
+    }
+    lines
+}
@@ -0,0 +1,92 @@
+//! `!tag` and `!recall`: named saved outputs.
+//!
+//! `!tag <name> [id]` copies a history entry (by default the last run with
+//! stored output) into the vault under a name, so "the run where it
+//! worked" can be found again however much history piles up after it.
+//! Names are unique: pointing an existing name at another run takes
+//! `--force`. `!recall <name>` shows the copy again, and names work as
+//! `!diff` operands.
+
+use crate::time_format::{self, TimeSettings};
+use crate::vault::Tag;
+
+pub const TAG_USAGE: &str = "Usage: !tag <name> [history-id] [--force] | !tag list | !tag rm <name>";
+pub const RECALL_USAGE: &str = "Usage: !recall <name>";
+
+/// A parsed `!tag` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagCommand {
+    /// Tag history entry `id`, or the last run with output.
+    Set { name: String, id: Option<i64>, force: bool },
+    List,
+    Remove(String),
+}
+
+impl TagCommand {
+    /// Parse the text after `!tag`. The error is the usage text, or why
+    /// the name can't be used.
+    pub fn parse(args: &str) -> Result<TagCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            ["list"] => Ok(TagCommand::List),
+            ["rm", name] => Ok(TagCommand::Remove(name.to_string())),
+            [name, rest @ ..] if !matches!(*name, "list" | "rm") => {
+                if !is_valid_name(name) {
+                    return Err(format!(
+                        "❌ '{}' can't be a tag name: use letters, digits, '-', '_' and '.', not only digits",
+                        name
+                    ));
+                }
+                let mut id = None;
+                let mut force = false;
+                for word in rest {
+                    match *word {
+                        "--force" => force = true,
+                        _ if id.is_none() => {
+                            id = Some(word.trim_start_matches('#').parse().map_err(|_| TAG_USAGE.to_string())?)
+                        }
+                        _ => return Err(TAG_USAGE.to_string()),
+                    }
+                }
+                Ok(TagCommand::Set { name: name.to_string(), id, force })
+            }
+            _ => Err(TAG_USAGE.to_string()),
+        }
+    }
+}
+
+/// Letters, digits, `-`, `_` and `.`, not starting with `-` and not only
+/// digits, so a name never reads as a flag or a history id.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Where a tag came from, in one line.
+pub fn origin(tag: &Tag, time: &TimeSettings) -> String {
+    let id = tag.history_id.map(|id| format!("#{} ", id)).unwrap_or_default();
+    let exit = tag.exit_code.map(|code| format!(", exit {}", code)).unwrap_or_default();
+    format!("{}{} (ran {}{}, in {})", id, tag.command, time_format::when_local(tag.ran_at, time), exit, tag.directory)
+}
+
+/// `!recall`: a header, then the saved output.
+pub fn recall_lines(tag: &Tag, time: &TimeSettings) -> Vec<String> {
+    let mut lines = vec![format!("🏷️ {}: {}", tag.name, origin(tag, time)), "".to_string()];
+    lines.extend(tag.output.lines().map(str::to_string));
+    lines
+}
+
+/// `!tag list`, newest first and numbered for `!pick`.
+pub fn list_lines(tags: &[Tag], time: &TimeSettings) -> Vec<String> {
+    if tags.is_empty() {
+        return vec!["🏷️ No tags yet.".to_string(), "".to_string(), TAG_USAGE.to_string()];
+    }
+    let mut lines = vec!["🏷️ Tags:".to_string(), "".to_string()];
+    for (i, tag) in tags.iter().enumerate() {
+        lines.push(format!("  {:>3}. {}  tagged {}", i + 1, tag.name, time_format::when_local(tag.created_at, time)));
+        lines.push(format!("    {}", origin(tag, time)));
+    }
+    lines
+}
@@ -0,0 +1,382 @@
+//! Binary output quarantine.
+//!
+//! `cat`-ing an image or an executable feeds the emulator a stream of
+//! control bytes: stray DCS strings, title changes and charset switches
+//! that leave the terminal drawing line-art until it is reset. The guard
+//! sits between the PTY and the state machine and watches the first
+//! `DETECT_WINDOW` bytes of each command's output. Once they look binary
+//! (`looks_binary`), the rest of the block is withheld: it is counted and
+//! its head kept for `!out raw <id>`, and when the next prompt arrives a
+//! single notice line takes its place. Whatever slipped through before
+//! detection is neutralised with `RESET_SEQUENCE`.
+//!
+//! A block starts when a command is sent and ends at the shell's next
+//! OSC 133 prompt marker (`A` or `D`). Without shell integration it ends
+//! when the following command is sent.
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+
+/// Vault config key; `false`, `off` or `0` lets binary output through.
+pub const BINARY_GUARD_KEY: &str = "blocks.binary_guard";
+
+/// Bytes of each block inspected before it is trusted as text.
+pub const DETECT_WINDOW: usize = 8 * 1024;
+
+/// Suspicious bytes needed before a block can be judged binary, so a short
+/// output with a couple of BELs never trips the guard.
+pub const MIN_SUSPICIOUS: usize = 16;
+
+/// Head of a suppressed block kept for `!out raw`.
+pub const CAPTURE_LIMIT: usize = 64 * 1024;
+
+/// Bytes shown by `!out raw`.
+pub const PREVIEW_BYTES: usize = 512;
+
+/// Suppressed blocks remembered, oldest dropped first.
+pub const MAX_QUARANTINED: usize = 8;
+
+/// Sample size used by `looks_binary`.
+const SAMPLE: usize = 1024;
+
+/// Cancels any half-parsed escape or string (CAN, ST), selects ASCII for
+/// G0/G1 and shifts back into G0, clears attributes and resets the title.
+pub const RESET_SEQUENCE: &[u8] = b"\x18\x1b\\\x1b(B\x1b)B\x0f\x1b[0m\x1b]2;\x07";
+
+/// Prompt markers that end a block: `ESC ] 133 ;` followed by `A` or `D`.
+const MARKER_PREFIX: &[u8] = b"\x1b]133;";
+
+/// Whether the guard is on for a `BINARY_GUARD_KEY` value (on when unset).
+pub fn guard_enabled(value: Option<&str>) -> bool {
+    !matches!(
+        value.map(|v| v.trim().to_lowercase()).as_deref(),
+        Some("false" | "off" | "0" | "no")
+    )
+}
+
+/// Control bytes other than `\n`, `\r`, `\t` and ESC (which text uses
+/// freely), plus bytes of invalid UTF-8 sequences.
+fn count_suspicious(bytes: &[u8]) -> usize {
+    let controls = bytes
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x1b))
+        .count();
+    let mut invalid = 0;
+    let mut rest = bytes;
+    while let Err(e) = std::str::from_utf8(rest) {
+        // `None` is a sequence cut off at the end of the sample.
+        let Some(len) = e.error_len() else {
+            break;
+        };
+        invalid += len;
+        rest = &rest[e.valid_up_to() + len..];
+    }
+    controls + invalid
+}
+
+/// True when more than one byte in ten of the first KB is suspicious.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(SAMPLE)];
+    if sample.is_empty() {
+        return false;
+    }
+    count_suspicious(sample) * 10 > sample.len()
+}
+
+/// `1536` → `1.5 KB`.
+pub fn format_kb(bytes: usize) -> String {
+    format!("{:.1} KB", bytes as f64 / 1024.0)
+}
+
+/// Classic 16-bytes-per-row hex dump with an ASCII column.
+pub fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    hex_dump_at(bytes, 0)
+}
+
+/// `hex_dump` of bytes read from `offset` in a file, which the offset
+/// column counts from (`!view --hex --tail`).
+pub fn hex_dump_at(bytes: &[u8], offset: u64) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let mut hex = String::with_capacity(50);
+            for i in 0..16 {
+                if i == 8 {
+                    hex.push(' ');
+                }
+                match chunk.get(i) {
+                    Some(b) => hex.push_str(&format!("{:02x} ", b)),
+                    None => hex.push_str("   "),
+                }
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {} |{}|", offset + row as u64 * 16, hex, ascii)
+        })
+        .collect()
+}
+
+/// A block whose output was withheld from the grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedOutput {
+    pub id: u64,
+    pub command: String,
+    /// The first `CAPTURE_LIMIT` bytes.
+    pub captured: Vec<u8>,
+    /// Every byte the block produced after detection.
+    pub total: usize,
+    /// The block has ended (the notice was shown).
+    pub finished: bool,
+}
+
+impl QuarantinedOutput {
+    /// The line shown in place of the output.
+    pub fn notice(&self) -> String {
+        format!(
+            "⛔ binary output suppressed — {}, use !out raw {} to view",
+            format_kb(self.total),
+            self.id
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct Block {
+    command: String,
+    /// Bytes inspected so far, up to `DETECT_WINDOW`.
+    seen: usize,
+    suspicious: usize,
+    /// Id of the quarantine entry once suppressed.
+    suppressed: Option<u64>,
+    /// Suppression began mid-line, so the notice needs a line break first.
+    mid_line: bool,
+}
+
+/// The stream filter; see the module docs.
+#[derive(Debug)]
+pub struct BinaryGuard {
+    enabled: bool,
+    block: Option<Block>,
+    /// Bytes of `MARKER_PREFIX` matched so far (carried across chunks).
+    marker: usize,
+    /// Output held for the next chunk (a notice for a block ended by
+    /// `begin_block`).
+    pending: Vec<u8>,
+    last_byte: Option<u8>,
+    next_id: u64,
+    quarantined: VecDeque<QuarantinedOutput>,
+}
+
+impl Default for BinaryGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinaryGuard {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            block: None,
+            marker: 0,
+            pending: Vec::new(),
+            last_byte: None,
+            next_id: 1,
+            quarantined: VecDeque::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// A command was sent to the shell: its output starts a new block.
+    pub fn begin_block(&mut self, command: &str) {
+        self.end_block();
+        self.block = Some(Block { command: command.to_string(), ..Block::default() });
+    }
+
+    /// True while the current block's output is being withheld.
+    pub fn is_suppressing(&self) -> bool {
+        self.block.as_ref().is_some_and(|b| b.suppressed.is_some())
+    }
+
+    /// Suppressed blocks, oldest first.
+    pub fn quarantined(&self) -> impl Iterator<Item = &QuarantinedOutput> {
+        self.quarantined.iter()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&QuarantinedOutput> {
+        self.quarantined.iter().find(|q| q.id == id)
+    }
+
+    /// Filter one PTY chunk; returns what the emulator should see.
+    pub fn process(&mut self, chunk: Bytes) -> Bytes {
+        let watching = self.block.as_ref().is_some_and(|b| {
+            b.suppressed.is_some() || (self.enabled && b.seen < DETECT_WINDOW)
+        });
+        if !watching && self.pending.is_empty() {
+            // Text past the window can't be suppressed any more; only the
+            // end of the block matters.
+            if self.block.is_some() && self.find_marker_end(&chunk).is_some() {
+                self.block = None;
+            }
+            self.note_output(&chunk);
+            return chunk;
+        }
+
+        let mut out = std::mem::take(&mut self.pending);
+        if !watching {
+            out.extend_from_slice(&chunk);
+            self.note_output(&out);
+            return Bytes::from(out);
+        }
+
+        // Split at the prompt marker that ends the block, if it's here.
+        let end = self.find_marker_end(&chunk);
+        let (body, tail) = match end {
+            Some(at) => {
+                let start = at.saturating_sub(MARKER_PREFIX.len());
+                (&chunk[..start], Some((&chunk[start..], MARKER_PREFIX.len() - (at - start))))
+            }
+            None => (&chunk[..], None),
+        };
+
+        if self.is_suppressing() {
+            self.capture(body);
+        } else {
+            self.inspect(body, &mut out);
+            if tail.is_none() && !self.is_suppressing() && out.len() == chunk.len() {
+                self.note_output(&chunk);
+                return chunk;
+            }
+        }
+
+        if let Some((tail, carried)) = tail {
+            let was_suppressing = self.is_suppressing();
+            self.end_block_into(&mut out);
+            if was_suppressing {
+                // The start of a marker split across chunks went to the
+                // quarantine; restore it.
+                out.extend_from_slice(&MARKER_PREFIX[..carried]);
+            }
+            out.extend_from_slice(tail);
+        }
+        self.note_output(&out);
+        Bytes::from(out)
+    }
+
+    /// Pass `body` through while it looks like text; on detection,
+    /// withhold from the start of the offending line.
+    fn inspect(&mut self, body: &[u8], out: &mut Vec<u8>) {
+        let Some(block) = self.block.as_mut() else {
+            out.extend_from_slice(body);
+            return;
+        };
+        if block.seen >= DETECT_WINDOW {
+            out.extend_from_slice(body);
+            return;
+        }
+        let window = &body[..body.len().min(DETECT_WINDOW - block.seen)];
+        block.seen += window.len();
+        block.suspicious += count_suspicious(window);
+        if block.suspicious < MIN_SUSPICIOUS || block.suspicious * 10 <= block.seen {
+            out.extend_from_slice(body);
+            return;
+        }
+
+        let first_bad = body
+            .iter()
+            .position(|&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x1b) || b >= 0x80)
+            .unwrap_or(0);
+        let split = body[..first_bad].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        out.extend_from_slice(&body[..split]);
+        let before = out.last().copied().or(self.last_byte);
+        block.mid_line = before.is_some_and(|b| b != b'\n');
+        out.extend_from_slice(RESET_SEQUENCE);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        block.suppressed = Some(id);
+        let command = block.command.clone();
+        if self.quarantined.len() >= MAX_QUARANTINED {
+            self.quarantined.pop_front();
+        }
+        self.quarantined.push_back(QuarantinedOutput {
+            id,
+            command,
+            captured: Vec::new(),
+            total: 0,
+            finished: false,
+        });
+        self.capture(&body[split..]);
+    }
+
+    fn capture(&mut self, bytes: &[u8]) {
+        let Some(id) = self.block.as_ref().and_then(|b| b.suppressed) else {
+            return;
+        };
+        if let Some(entry) = self.quarantined.iter_mut().find(|q| q.id == id) {
+            let room = CAPTURE_LIMIT.saturating_sub(entry.captured.len());
+            entry.captured.extend_from_slice(&bytes[..bytes.len().min(room)]);
+            entry.total += bytes.len();
+        }
+    }
+
+    /// Index of the `A` or `D` after `ESC ] 133 ;`.
+    fn find_marker_end(&mut self, chunk: &[u8]) -> Option<usize> {
+        for (i, &b) in chunk.iter().enumerate() {
+            if self.marker == MARKER_PREFIX.len() {
+                self.marker = 0;
+                if b == b'A' || b == b'D' {
+                    return Some(i);
+                }
+            }
+            if b == MARKER_PREFIX[self.marker] {
+                self.marker += 1;
+            } else {
+                self.marker = usize::from(b == MARKER_PREFIX[0]);
+            }
+        }
+        None
+    }
+
+    fn end_block(&mut self) {
+        let mut out = std::mem::take(&mut self.pending);
+        self.end_block_into(&mut out);
+        self.pending = out;
+    }
+
+    /// Close the current block, writing its notice if it was suppressed.
+    fn end_block_into(&mut self, out: &mut Vec<u8>) {
+        self.marker = 0;
+        let Some(block) = self.block.take() else {
+            return;
+        };
+        let Some(id) = block.suppressed else {
+            return;
+        };
+        let Some(entry) = self.quarantined.iter_mut().find(|q| q.id == id) else {
+            return;
+        };
+        entry.finished = true;
+        if block.mid_line {
+            out.extend_from_slice(b"\r\n");
+        }
+        out.extend_from_slice(entry.notice().as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+
+    fn note_output(&mut self, out: &[u8]) {
+        if let Some(&b) = out.last() {
+            self.last_byte = Some(b);
+        }
+    }
+}
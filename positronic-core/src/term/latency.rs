@@ -0,0 +1,365 @@
+//! Input-to-render latency, for `!perf report`.
+//!
+//! A printable key is drawn by the UI's own input line, so its path is
+//! key → frame. A submitted line takes the long way: Enter → written to
+//! the PTY → the shell's echo read back → applied to the grid → the frame
+//! that shows it. The first bytes after the echoed line are the command's
+//! first output. Each hop is a `Stage` with a ring of recent samples.
+//!
+//! Everything is atomics over preallocated rings, cheap enough to leave
+//! on: the UI thread stamps keys, submits and presented frames, the Runner
+//! stamps writes and the PTY pump stamps reads and applies. Keys are
+//! matched to frames by sequence number. The line in flight moves through
+//! its stages by compare-and-swap, so a stamp that arrives out of turn is
+//! ignored. Anything left waiting past its timeout (a password prompt
+//! doesn't echo) is dropped and counted, never recorded.
+
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Samples kept per stage.
+pub const RING_CAPACITY: usize = 1024;
+
+/// Keys that can wait for a frame at once; older ones are dropped.
+const KEY_SLOTS: usize = 64;
+
+/// A key or written line not seen by then never will be.
+pub const ECHO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Commands quiet for longer than this aren't timed for first output.
+pub const FIRST_OUTPUT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Where the line in flight is.
+const IDLE: u8 = 0;
+const SUBMITTED: u8 = 1;
+const WRITTEN: u8 = 2;
+const ECHOED: u8 = 3;
+const APPLIED: u8 = 4;
+
+// Where its first output is.
+const OUTPUT_IDLE: u8 = 0;
+/// Reading the echoed line; output starts after its newline.
+const OUTPUT_ECHO_LINE: u8 = 1;
+const OUTPUT_NEXT: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Printable key received → the frame showing it presented.
+    KeyToFrame,
+    /// Enter → the line written to the PTY.
+    SubmitToWrite,
+    /// Written → first bytes read back (the shell's echo).
+    WriteToEcho,
+    /// Read → applied to the state machine.
+    EchoToApply,
+    /// Applied → the frame showing it presented.
+    ApplyToFrame,
+    /// Enter → the first output after the echoed line.
+    FirstOutput,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::KeyToFrame,
+        Stage::SubmitToWrite,
+        Stage::WriteToEcho,
+        Stage::EchoToApply,
+        Stage::ApplyToFrame,
+        Stage::FirstOutput,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::KeyToFrame => "key → frame",
+            Stage::SubmitToWrite => "submit → write",
+            Stage::WriteToEcho => "write → echo",
+            Stage::EchoToApply => "echo → grid",
+            Stage::ApplyToFrame => "grid → frame",
+            Stage::FirstOutput => "submit → output",
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Samples
+// ════════════════════════════════════════════════════════════════════
+
+/// The most recent samples of one stage, in microseconds.
+#[derive(Debug)]
+pub struct SampleRing {
+    slots: Box<[AtomicU32]>,
+    /// Samples recorded since the last reset; the next slot is this
+    /// modulo the capacity.
+    recorded: AtomicUsize,
+}
+
+impl SampleRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            recorded: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn record(&self, sample: Duration) {
+        let micros = u32::try_from(sample.as_micros()).unwrap_or(u32::MAX);
+        let n = self.recorded.fetch_add(1, Ordering::Relaxed);
+        self.slots[n % self.slots.len()].store(micros, Ordering::Relaxed);
+    }
+
+    /// Samples held: those since the last reset, up to the capacity.
+    pub fn len(&self) -> usize {
+        self.recorded.load(Ordering::Relaxed).min(self.slots.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn reset(&self) {
+        self.recorded.store(0, Ordering::Relaxed);
+    }
+
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        let samples = self.slots[..self.len()].iter().map(|s| s.load(Ordering::Relaxed)).collect();
+        Percentiles::of(samples)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub count: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `samples` (microseconds); `None` when
+    /// there are none.
+    pub fn of(mut samples: Vec<u32>) -> Option<Percentiles> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        let at = |p: usize| {
+            let rank = (p * samples.len()).div_ceil(100).max(1);
+            Duration::from_micros(u64::from(samples[rank - 1]))
+        };
+        Some(Percentiles { count: samples.len(), p50: at(50), p95: at(95), p99: at(99), max: at(100) })
+    }
+}
+
+/// `d` in milliseconds with one decimal.
+pub fn millis(d: Duration) -> String {
+    format!("{:.1}", d.as_secs_f64() * 1000.0)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Probe
+// ════════════════════════════════════════════════════════════════════
+
+/// Shared by the UI, the Runner and the PTY pump. Timestamps are stored
+/// as microseconds since `epoch`, plus one so zero means "none".
+#[derive(Debug)]
+pub struct LatencyProbe {
+    epoch: Instant,
+    rings: [SampleRing; 6],
+    /// The last key handed a sequence number, and the last one presented.
+    key_seq: AtomicU64,
+    key_presented: AtomicU64,
+    key_times: Box<[AtomicU64]>,
+    line: AtomicU8,
+    output: AtomicU8,
+    submitted_at: AtomicU64,
+    written_at: AtomicU64,
+    echoed_at: AtomicU64,
+    applied_at: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+impl Default for LatencyProbe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyProbe {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            rings: std::array::from_fn(|_| SampleRing::new(RING_CAPACITY)),
+            key_seq: AtomicU64::new(0),
+            key_presented: AtomicU64::new(0),
+            key_times: (0..KEY_SLOTS).map(|_| AtomicU64::new(0)).collect(),
+            line: AtomicU8::new(IDLE),
+            output: AtomicU8::new(OUTPUT_IDLE),
+            submitted_at: AtomicU64::new(0),
+            written_at: AtomicU64::new(0),
+            echoed_at: AtomicU64::new(0),
+            applied_at: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+        }
+    }
+
+    fn stamp(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.epoch).as_micros() as u64 + 1
+    }
+
+    fn since(&self, stamp: &AtomicU64, now: Instant) -> Duration {
+        Duration::from_micros(self.stamp(now).saturating_sub(stamp.load(Ordering::Relaxed)))
+    }
+
+    fn record(&self, stage: Stage, sample: Duration) {
+        self.rings[stage as usize].record(sample);
+    }
+
+    pub fn ring(&self, stage: Stage) -> &SampleRing {
+        &self.rings[stage as usize]
+    }
+
+    /// Keys and lines dropped for waiting too long.
+    pub fn timed_out(&self) -> u64 {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    /// A printable key arrived; returns its sequence number.
+    pub fn key(&self, now: Instant) -> u64 {
+        let seq = self.key_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        self.key_times[seq as usize % KEY_SLOTS].store(self.stamp(now), Ordering::Relaxed);
+        seq
+    }
+
+    /// A line is about to be sent to the shell. A previous line still
+    /// waiting for its echo never got one.
+    pub fn submitted(&self, now: Instant) {
+        self.submitted_at.store(self.stamp(now), Ordering::Relaxed);
+        self.output.store(OUTPUT_IDLE, Ordering::Relaxed);
+        if self.line.swap(SUBMITTED, Ordering::AcqRel) == WRITTEN {
+            self.timed_out.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The submitted line reached the PTY.
+    pub fn written(&self, now: Instant) {
+        if self.line.load(Ordering::Acquire) != SUBMITTED {
+            return;
+        }
+        self.written_at.store(self.stamp(now), Ordering::Relaxed);
+        if self.line.compare_exchange(SUBMITTED, WRITTEN, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+            self.record(Stage::SubmitToWrite, self.since(&self.submitted_at, now));
+        }
+    }
+
+    /// `bytes` were read from the PTY, before the state machine saw them.
+    pub fn output_read(&self, bytes: &[u8], now: Instant) {
+        if self.line.load(Ordering::Acquire) == WRITTEN {
+            if self.since(&self.written_at, now) > ECHO_TIMEOUT {
+                if self.line.compare_exchange(WRITTEN, IDLE, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+                    self.timed_out.fetch_add(1, Ordering::Relaxed);
+                }
+            } else {
+                self.echoed_at.store(self.stamp(now), Ordering::Relaxed);
+                if self.line.compare_exchange(WRITTEN, ECHOED, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+                    self.record(Stage::WriteToEcho, self.since(&self.written_at, now));
+                    self.output.store(OUTPUT_ECHO_LINE, Ordering::Relaxed);
+                }
+            }
+        }
+
+        let output_starts = match self.output.load(Ordering::Relaxed) {
+            OUTPUT_ECHO_LINE => match bytes.iter().position(|&b| b == b'\n') {
+                Some(end) if end + 1 < bytes.len() => true,
+                Some(_) => {
+                    self.output.store(OUTPUT_NEXT, Ordering::Relaxed);
+                    false
+                }
+                None => false,
+            },
+            OUTPUT_NEXT => !bytes.is_empty(),
+            _ => false,
+        };
+        if output_starts {
+            self.output.store(OUTPUT_IDLE, Ordering::Relaxed);
+            let waited = self.since(&self.submitted_at, now);
+            if waited > FIRST_OUTPUT_TIMEOUT {
+                self.timed_out.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.record(Stage::FirstOutput, waited);
+            }
+        }
+    }
+
+    /// The bytes last read are in the grid.
+    pub fn output_applied(&self, now: Instant) {
+        if self.line.load(Ordering::Acquire) != ECHOED {
+            return;
+        }
+        self.applied_at.store(self.stamp(now), Ordering::Relaxed);
+        if self.line.compare_exchange(ECHOED, APPLIED, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+            self.record(Stage::EchoToApply, self.since(&self.echoed_at, now));
+        }
+    }
+
+    /// A frame was presented: it shows every key and grid change so far.
+    pub fn presented(&self, now: Instant) {
+        let last = self.key_seq.load(Ordering::Relaxed);
+        let first = self.key_presented.swap(last, Ordering::Relaxed) + 1;
+        // Keys whose slots were reused have no stamp left to compare.
+        for seq in first.max(last.saturating_sub(KEY_SLOTS as u64 - 1))..=last {
+            let waited = self.since(&self.key_times[seq as usize % KEY_SLOTS], now);
+            if waited > ECHO_TIMEOUT {
+                self.timed_out.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.record(Stage::KeyToFrame, waited);
+            }
+        }
+
+        if self.line.compare_exchange(APPLIED, IDLE, Ordering::AcqRel, Ordering::Relaxed).is_ok() {
+            self.record(Stage::ApplyToFrame, self.since(&self.applied_at, now));
+        }
+    }
+
+    /// `!perf reset`: forget the samples; a line in flight carries on.
+    pub fn reset(&self) {
+        for ring in &self.rings {
+            ring.reset();
+        }
+        self.timed_out.store(0, Ordering::Relaxed);
+    }
+
+    /// Percentiles per stage, in `Stage::ALL` order.
+    pub fn summary(&self) -> [Option<Percentiles>; 6] {
+        Stage::ALL.map(|stage| self.ring(stage).percentiles())
+    }
+
+    /// `!perf report`.
+    pub fn report_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("📈 Input latency (last {} samples per stage, ms)", RING_CAPACITY),
+            "".to_string(),
+            format!("  {:<16} {:>6} {:>8} {:>8} {:>8} {:>8}", "stage", "n", "p50", "p95", "p99", "max"),
+        ];
+        for (stage, summary) in Stage::ALL.into_iter().zip(self.summary()) {
+            lines.push(match summary {
+                Some(p) => format!(
+                    "  {:<16} {:>6} {:>8} {:>8} {:>8} {:>8}",
+                    stage.label(),
+                    p.count,
+                    millis(p.p50),
+                    millis(p.p95),
+                    millis(p.p99),
+                    millis(p.max)
+                ),
+                None => format!("  {:<16} {:>6}", stage.label(), "-"),
+            });
+        }
+        let timed_out = self.timed_out();
+        if timed_out > 0 {
+            lines.push("".to_string());
+            lines.push(format!("  {} dropped: no echo or output in time (e.g. password prompts)", timed_out));
+        }
+        lines
+    }
+}
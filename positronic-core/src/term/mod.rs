@@ -1,9 +1,21 @@
 //! Terminal-side parsing helpers that sit *next to* the emulator.
 //!
+//! - `binary`: withholds binary output from the emulator (`!out raw`)
 //! - `osc`: streaming OSC parser (OSC 7 cwd, OSC 133 prompt markers, etc.)
 //! - `modes`: lightweight CSI mode tracker (alt-screen, mouse reporting, bracketed paste)
 //! - `semantic`: prompt/command state derived from OSC markers
+//! - `utf8`: incremental decoder that carries split characters between chunks
+//! - `probe`: asks the shell for its cwd and strips the answers from the stream
+//! - `running`: the command currently running, from OSC 133 markers
+//! - `progress`: folds `\r` progress redraws so recorded output keeps the final frame
+//! - `latency`: key-to-frame and submit-to-output timing for `!perf report`
 
+pub mod binary;
+pub mod latency;
 pub mod modes;
 pub mod osc;
+pub mod probe;
+pub mod progress;
+pub mod running;
 pub mod semantic;
+pub mod utf8;
@@ -5,12 +5,44 @@
 /// - Mouse: ?1000/?1002/?1003/?1006/?1015
 /// - Bracketed paste: ?2004
 /// - App cursor: ?1
+///
+/// plus the cursor style applications pick with DECSCUSR (`CSI Ps SP q`).
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ModeSnapshot {
     pub alt_screen: bool,
     pub mouse_reporting: bool,
     pub bracketed_paste: bool,
     pub app_cursor: bool,
+    /// `None` until an application sets one, and again after it resets
+    /// to the default (`CSI 0 SP q`); the user's setting applies then.
+    pub cursor_style: Option<CursorStyle>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorShape {
+    Block,
+    Underline,
+    Bar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+impl CursorStyle {
+    /// The style for DECSCUSR parameter `ps`; `None` for 0 (the default)
+    /// and for values it doesn't define.
+    pub fn from_decscusr(ps: u16) -> Option<CursorStyle> {
+        let shape = match ps {
+            1 | 2 => CursorShape::Block,
+            3 | 4 => CursorShape::Underline,
+            5 | 6 => CursorShape::Bar,
+            _ => return None,
+        };
+        Some(CursorStyle { shape, blinking: ps % 2 == 1 })
+    }
 }
 
 impl ModeSnapshot {
@@ -29,6 +61,8 @@ pub struct ModeTracker {
     cur_num: Option<u16>,
     nums: Vec<u16>,
     private: bool,
+    /// Intermediate byte (0x20–0x2f) seen in this sequence.
+    intermediate: Option<u8>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -65,6 +99,7 @@ impl ModeTracker {
                     if b == b'[' {
                         self.state = ParseState::Csi;
                         self.private = false;
+                        self.intermediate = None;
                         self.cur_num = None;
                         self.nums.clear();
                     } else {
@@ -93,6 +128,11 @@ impl ModeTracker {
                         continue;
                     }
 
+                    if (0x20..=0x2f).contains(&b) {
+                        self.intermediate = Some(b);
+                        continue;
+                    }
+
                     // final byte
                     if let Some(n) = self.cur_num.take() {
                         self.nums.push(n);
@@ -103,6 +143,17 @@ impl ModeTracker {
                         self.apply_private_modes(set);
                     }
 
+                    // DECSCUSR; 0 or a missing parameter resets to the
+                    // default, undefined values are ignored.
+                    if !self.private && self.intermediate == Some(b' ') && b == b'q' {
+                        let ps = self.nums.first().copied().unwrap_or(0);
+                        if ps == 0 {
+                            self.snap.cursor_style = None;
+                        } else if let Some(style) = CursorStyle::from_decscusr(ps) {
+                            self.snap.cursor_style = Some(style);
+                        }
+                    }
+
                     self.state = ParseState::Ground;
                 }
             }
@@ -0,0 +1,310 @@
+//! Authoritative working-directory queries.
+//!
+//! The bridge tracks the cwd heuristically (parsing `cd` commands and the
+//! prompt line), which goes wrong after `cd -`, `popd`, or a sourced script
+//! that changes directory. `CwdProbe` asks the shell instead: it sends a key
+//! chord the shell integration binds to a handler that prints `$PWD` inside
+//! a private OSC,
+//!
+//! ```text
+//! ESC ] 7770 ; positronic-cwd ; <token> ; <path> BEL
+//! ```
+//!
+//! and strips that sequence out of the PTY stream before anything else sees
+//! it. The handler runs inside the line editor (bash `bind -x`, PSReadLine
+//! key handler), so nothing is echoed and no new prompt is drawn.
+//!
+//! Probes are only sent while the shell sits at its prompt: never while a
+//! foreground command runs (it would read the keystrokes) or while the
+//! alternate screen is active.
+
+use super::modes::ModeTracker;
+use super::osc::{OscEvent, OscParser};
+use super::semantic::SemanticState;
+
+use bytes::Bytes;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Key chord the shell integration binds to the probe handler (Ctrl+F12).
+pub const PROBE_KEY: &str = "\x1b[24;5~";
+
+/// Vault config key: `auto` (default) or `off`.
+pub const CWD_PROBE_KEY: &str = "cwd.probe";
+
+/// Start of every probe response, up to the token.
+const RESPONSE_PREFIX: &[u8] = b"\x1b]7770;positronic-cwd;";
+
+/// Longest response payload accepted before the sequence is abandoned.
+const MAX_PAYLOAD: usize = 4096;
+
+/// Minimum time between two probes.
+pub const MIN_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long an unanswered probe blocks the next one.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+static SESSION_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Per-process token the shell echoes back, so program output cannot forge
+/// a response.
+pub fn session_token() -> &'static str {
+    SESSION_TOKEN.get_or_init(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Stream filter
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterState {
+    Ground,
+    /// Matched this many bytes of `RESPONSE_PREFIX`.
+    Prefix(usize),
+    Payload,
+    /// ESC seen inside the payload.
+    PayloadEsc,
+}
+
+/// Removes probe responses from a PTY byte stream (chunk-safe).
+///
+/// Bytes that could still be the start of a response are held back until
+/// they can be decided, so a response split across reads never leaks. Any
+/// sequence in the probe namespace is swallowed, even with a wrong token.
+#[derive(Debug, Clone)]
+pub struct ProbeFilter {
+    token: String,
+    state: FilterState,
+    payload: Vec<u8>,
+}
+
+impl ProbeFilter {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self { token: token.into(), state: FilterState::Ground, payload: Vec::new() }
+    }
+
+    /// True when no partial response is held back.
+    pub fn is_idle(&self) -> bool {
+        self.state == FilterState::Ground
+    }
+
+    /// Append the visible part of `bytes` to `out`; return the paths from
+    /// any complete responses with a matching token.
+    pub fn feed(&mut self, bytes: &[u8], out: &mut Vec<u8>) -> Vec<String> {
+        let mut found = Vec::new();
+        for &b in bytes {
+            self.step(b, out, &mut found);
+        }
+        found
+    }
+
+    fn step(&mut self, b: u8, out: &mut Vec<u8>, found: &mut Vec<String>) {
+        match self.state {
+            FilterState::Ground => {
+                if b == RESPONSE_PREFIX[0] {
+                    self.state = FilterState::Prefix(1);
+                } else {
+                    out.push(b);
+                }
+            }
+            FilterState::Prefix(n) => {
+                if b == RESPONSE_PREFIX[n] {
+                    if n + 1 == RESPONSE_PREFIX.len() {
+                        self.payload.clear();
+                        self.state = FilterState::Payload;
+                    } else {
+                        self.state = FilterState::Prefix(n + 1);
+                    }
+                } else {
+                    // Not ours: release what was held and re-read this byte.
+                    out.extend_from_slice(&RESPONSE_PREFIX[..n]);
+                    self.state = FilterState::Ground;
+                    self.step(b, out, found);
+                }
+            }
+            FilterState::Payload => match b {
+                0x07 => self.finish(found),
+                0x1b => self.state = FilterState::PayloadEsc,
+                _ if self.payload.len() >= MAX_PAYLOAD => {
+                    // Never terminated: give up on it rather than hide output.
+                    self.payload.clear();
+                    self.state = FilterState::Ground;
+                    self.step(b, out, found);
+                }
+                _ => self.payload.push(b),
+            },
+            FilterState::PayloadEsc => {
+                if b == b'\\' {
+                    self.finish(found);
+                } else {
+                    // ESC aborts the OSC and starts a new sequence.
+                    self.payload.clear();
+                    self.state = FilterState::Prefix(1);
+                    self.step(b, out, found);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, found: &mut Vec<String>) {
+        self.state = FilterState::Ground;
+        let payload = String::from_utf8_lossy(&self.payload).into_owned();
+        self.payload.clear();
+        if let Some((token, path)) = payload.split_once(';') {
+            if token == self.token && !path.is_empty() {
+                found.push(path.to_string());
+            }
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Probe
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProbeMode {
+    #[default]
+    Auto,
+    Off,
+}
+
+impl ProbeMode {
+    /// Parse the `cwd.probe` config value; anything unrecognised is `Auto`.
+    pub fn from_config(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("off" | "false" | "0" | "no") => ProbeMode::Off,
+            _ => ProbeMode::Auto,
+        }
+    }
+}
+
+/// Decides when to probe and collects the answers.
+///
+/// Everything read from the PTY goes through `process`, which strips probe
+/// responses and tracks prompt state and terminal modes from what is left.
+/// `request` asks for a probe; `take_due` says when to actually send
+/// `PROBE_KEY`.
+#[derive(Debug)]
+pub struct CwdProbe {
+    mode: ProbeMode,
+    /// The shell has the probe key bound (our integration is loaded).
+    supported: bool,
+    filter: ProbeFilter,
+    modes: ModeTracker,
+    osc: OscParser,
+    semantic: SemanticState,
+    /// Prompts seen so far.
+    prompts: u64,
+    /// Pending request, satisfied once `prompts` reaches this value.
+    wanted: Option<u64>,
+    last_sent: Option<Instant>,
+    awaiting: bool,
+    cwd: Option<String>,
+}
+
+impl CwdProbe {
+    pub fn new(supported: bool) -> Self {
+        Self::with_token(session_token(), supported)
+    }
+
+    pub fn with_token(token: &str, supported: bool) -> Self {
+        Self {
+            mode: ProbeMode::Auto,
+            supported,
+            filter: ProbeFilter::new(token),
+            modes: ModeTracker::new(),
+            osc: OscParser::new(),
+            semantic: SemanticState::new(),
+            prompts: 0,
+            wanted: None,
+            last_sent: None,
+            awaiting: false,
+            cwd: None,
+        }
+    }
+
+    pub fn mode(&self) -> ProbeMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ProbeMode) {
+        self.mode = mode;
+        if mode == ProbeMode::Off {
+            self.wanted = None;
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        self.supported
+    }
+
+    /// Strip probe responses from `chunk` and return what should be shown.
+    pub fn process(&mut self, chunk: Bytes) -> Bytes {
+        let visible = if self.filter.is_idle() && !chunk.contains(&0x1b) {
+            chunk
+        } else {
+            let mut out = Vec::with_capacity(chunk.len());
+            if let Some(cwd) = self.filter.feed(&chunk, &mut out).pop() {
+                self.cwd = Some(cwd);
+                self.awaiting = false;
+            }
+            Bytes::from(out)
+        };
+
+        self.modes.feed(&visible);
+        for ev in self.osc.feed(&visible) {
+            if ev == OscEvent::PromptStart {
+                self.prompts += 1;
+            }
+            self.semantic.apply(&ev);
+        }
+        visible
+    }
+
+    /// Ask for a probe. With `at_next_prompt`, wait for the prompt that
+    /// follows the command about to be sent; otherwise probe as soon as
+    /// the shell is idle.
+    pub fn request(&mut self, at_next_prompt: bool) -> bool {
+        if self.mode == ProbeMode::Off || !self.supported {
+            return false;
+        }
+        let after = if at_next_prompt { self.prompts + 1 } else { self.prompts };
+        self.wanted = Some(self.wanted.map_or(after, |w| w.max(after)));
+        true
+    }
+
+    /// True if the shell is at an idle prompt, outside the alternate screen.
+    pub fn shell_idle(&self) -> bool {
+        self.semantic.in_prompt && !self.semantic.in_command && !self.modes.snapshot().alt_screen
+    }
+
+    /// Whether to send `PROBE_KEY` now. Marks the probe as sent if so.
+    pub fn take_due(&mut self, now: Instant) -> bool {
+        let Some(after) = self.wanted else {
+            return false;
+        };
+        if self.mode == ProbeMode::Off || !self.supported {
+            self.wanted = None;
+            return false;
+        }
+        if self.prompts < after || !self.shell_idle() {
+            return false;
+        }
+        if let Some(sent) = self.last_sent {
+            let since = now.saturating_duration_since(sent);
+            if since < MIN_PROBE_INTERVAL || (self.awaiting && since < PROBE_TIMEOUT) {
+                return false;
+            }
+        }
+        self.wanted = None;
+        self.awaiting = true;
+        self.last_sent = Some(now);
+        true
+    }
+
+    /// The most recent answer, if it has not been taken yet.
+    pub fn take_cwd(&mut self) -> Option<String> {
+        self.cwd.take()
+    }
+}
@@ -0,0 +1,250 @@
+//! Carriage-return folding for recorded command output.
+//!
+//! Progress bars redraw one line in place: `\r` without `\n` moves back to
+//! column 0 and the next write replaces what was there. Recording those
+//! bytes verbatim stores every frame (a `pip install` easily produces
+//! thousands), so `ProgressFolder` keeps the block the way the terminal
+//! showed it: a bare `\r` makes the next write overwrite the in-progress
+//! line. Multi-line redraws (docker buildkit) move up with `CSI n A` and
+//! draw the frame again; the lines moved over are dropped, keeping the last
+//! rendering only.
+//!
+//! Lines that were overwritten or look like progress (`45%`, `[===>  ]`,
+//! `━━━━`, braille spinners) are marked, and when the block is finished a
+//! run of marked lines that differ only in their numbers and bars collapses
+//! to its last line. The live view is the emulator grid, which animates on
+//! its own; this only decides what the block and the vault keep.
+//!
+//! Overwrites replace the whole line rather than the characters under the
+//! cursor: progress tools redraw the full line (padding with spaces when it
+//! gets shorter), and column bookkeeping would go wrong on color codes.
+
+/// Vault config key; `false`, `off` or `0` keeps output verbatim.
+pub const FOLD_PROGRESS_KEY: &str = "blocks.fold_progress";
+
+/// Whether folding is on for a `FOLD_PROGRESS_KEY` value (on when unset).
+pub fn fold_enabled(value: Option<&str>) -> bool {
+    !matches!(
+        value.map(|v| v.trim().to_lowercase()).as_deref(),
+        Some("false" | "off" | "0" | "no")
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldedLine {
+    pub text: String,
+    /// Arrival of the line's final state, in ms since the command started.
+    pub offset_ms: u64,
+    /// Overwritten in place, or looks like a progress indicator.
+    pub progress: bool,
+}
+
+/// Streaming folder; feed it decoded output as it arrives.
+#[derive(Debug, Default, Clone)]
+pub struct ProgressFolder {
+    lines: Vec<FoldedLine>,
+    current: String,
+    current_offset: u64,
+    current_overwritten: bool,
+    /// A bare `\r` was seen: the next write replaces `current`.
+    carriage: bool,
+    /// An escape sequence split across chunks.
+    escape: Option<String>,
+    overwrites: usize,
+}
+
+impl ProgressFolder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chunk that arrived `offset_ms` after the command started.
+    pub fn feed(&mut self, text: &str, offset_ms: u64) {
+        for c in text.chars() {
+            if let Some(seq) = &mut self.escape {
+                // ESC [ params final: the final byte is in 0x40..=0x7E.
+                let done = match seq.as_str() {
+                    "\u{1b}" => c != '[',
+                    _ => ('\u{40}'..='\u{7e}').contains(&c),
+                };
+                seq.push(c);
+                if done {
+                    let seq = self.escape.take().unwrap_or_default();
+                    self.escape_sequence(seq, offset_ms);
+                }
+                continue;
+            }
+            match c {
+                '\u{1b}' => self.escape = Some(c.to_string()),
+                '\r' => self.carriage = true,
+                '\n' => self.commit(),
+                _ => self.write(c.encode_utf8(&mut [0; 4]), offset_ms),
+            }
+        }
+    }
+
+    /// Completed lines so far.
+    pub fn lines(&self) -> &[FoldedLine] {
+        &self.lines
+    }
+
+    /// The line being drawn, as it currently reads.
+    pub fn live_line(&self) -> &str {
+        &self.current
+    }
+
+    /// How many times a line was overwritten in place.
+    pub fn overwrites(&self) -> usize {
+        self.overwrites
+    }
+
+    /// The folded lines, with each progress sequence reduced to its final
+    /// state.
+    pub fn finish(mut self) -> Vec<FoldedLine> {
+        if let Some(seq) = self.escape.take() {
+            self.write(&seq, self.current_offset);
+        }
+        if !self.current.is_empty() {
+            self.commit();
+        }
+        let mut kept: Vec<FoldedLine> = Vec::with_capacity(self.lines.len());
+        for line in self.lines {
+            if let Some(last) = kept.last_mut() {
+                if last.progress && line.progress && same_sequence(&last.text, &line.text) {
+                    *last = line;
+                    continue;
+                }
+            }
+            kept.push(line);
+        }
+        kept
+    }
+
+    fn write(&mut self, text: &str, offset_ms: u64) {
+        if self.carriage {
+            self.carriage = false;
+            if !self.current.is_empty() {
+                self.current.clear();
+                self.current_overwritten = true;
+                self.overwrites += 1;
+            }
+            self.current_offset = offset_ms;
+        } else if self.current.is_empty() {
+            self.current_offset = offset_ms;
+        }
+        self.current.push_str(text);
+    }
+
+    fn commit(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let progress = self.current_overwritten || is_progress(&text);
+        self.lines.push(FoldedLine { text, offset_ms: self.current_offset, progress });
+        self.current_overwritten = false;
+        self.carriage = false;
+    }
+
+    fn escape_sequence(&mut self, seq: String, offset_ms: u64) {
+        let Some(params) = seq.strip_prefix("\u{1b}[") else {
+            self.write(&seq, offset_ms);
+            return;
+        };
+        let (params, last) = params.split_at(params.len() - 1);
+        let count = params.parse::<usize>().unwrap_or(1).max(1);
+        match last {
+            // Cursor up / previous line: the frame is about to be redrawn.
+            "A" | "F" => {
+                let keep = self.lines.len().saturating_sub(count);
+                self.lines.truncate(keep);
+                self.current.clear();
+                self.current_overwritten = false;
+                self.carriage = false;
+                self.overwrites += 1;
+            }
+            // Erase in line: the overwrite already cleared it.
+            "K" => {}
+            // Column 1 is a carriage return in disguise.
+            "G" if count == 1 => self.carriage = true,
+            _ => self.write(&seq, offset_ms),
+        }
+    }
+}
+
+/// Fold a complete output at once; see `ProgressFolder`.
+pub fn fold_progress(text: &str) -> String {
+    let mut folder = ProgressFolder::new();
+    folder.feed(text, 0);
+    join_lines(&folder.finish())
+}
+
+/// Lines back to output text, newline-terminated.
+pub fn join_lines(lines: &[FoldedLine]) -> String {
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&line.text);
+        out.push('\n');
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Progress heuristics
+// ════════════════════════════════════════════════════════════════════
+
+const BAR_CHARS: &[char] = &['━', '█', '▓', '▒', '░', '■', '▇', '▆', '▅', '▄', '▃', '▂', '▁', '╸', '╺'];
+
+fn is_braille(c: char) -> bool {
+    ('\u{2801}'..='\u{28ff}').contains(&c)
+}
+
+/// A percentage, an ASCII or block-character bar, or a leading braille
+/// spinner.
+pub fn is_progress(line: &str) -> bool {
+    let text = strip_csi(line);
+    let trimmed = text.trim_start();
+    if trimmed.chars().next().is_some_and(is_braille) {
+        return true;
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let percent = chars.windows(2).any(|w| w[0].is_ascii_digit() && w[1] == '%');
+    let ascii_bar = ["==>", "[=>", "[>", "[==", "[##"].iter().any(|bar| text.contains(bar));
+    let block_bar = chars.windows(3).any(|w| w.iter().all(|c| BAR_CHARS.contains(c)));
+    percent || ascii_bar || block_bar
+}
+
+/// Two progress lines belong to the same sequence when they match once
+/// numbers, bars and spinners are taken out.
+fn same_sequence(a: &str, b: &str) -> bool {
+    shape(a) == shape(b)
+}
+
+fn shape(line: &str) -> String {
+    strip_csi(line)
+        .chars()
+        .filter(|c| {
+            !(c.is_ascii_digit()
+                || c.is_whitespace()
+                || is_braille(*c)
+                || BAR_CHARS.contains(c)
+                || matches!(c, '%' | '.' | ',' | ':' | '/' | '=' | '>' | '#' | '-' | '|' | '\\'))
+        })
+        .collect()
+}
+
+/// `line` without CSI sequences (colors, erases).
+fn strip_csi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' && chars.peek() == Some(&'[') {
+            chars.next();
+            for c in chars.by_ref() {
+                if ('\u{40}'..='\u{7e}').contains(&c) {
+                    break;
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,148 @@
+//! Which command is running, and since when.
+//!
+//! The Runner reports every line it sends to the shell (`command_sent`);
+//! the PTY pump feeds the output (`feed`). With shell integration the
+//! OSC 133 markers are authoritative: `B`/`C` start the oldest line still
+//! waiting, `D`/`A` end it. Lines sent while a command is running are
+//! input to that program, not new commands, and a prompt that arrives
+//! without a start marker (a syntax error, a bare comment) consumes the
+//! line that produced it. Lines a `D` ends are kept with their exit
+//! codes until `take_finished`, for `!hook` after-hooks.
+//!
+//! Shells without OSC 133 never say when a command ends. Until a marker
+//! has been seen, the last line sent counts as "may be running" once it
+//! has gone on for `QUIET_AFTER` and for as long as output keeps arriving.
+
+use super::osc::{OscEvent, OscParser};
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Without shell integration, a command counts as finished once its
+/// output has been quiet this long.
+pub const QUIET_AFTER: Duration = Duration::from_secs(3);
+
+/// Lines waiting for their start marker, and finished commands not yet
+/// taken; older ones are dropped.
+const MAX_PENDING: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunningInfo {
+    pub command: String,
+    pub started_at: Instant,
+    /// Confirmed by a start marker. `false` is the no-integration guess:
+    /// a line was sent and output is still arriving.
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct RunningTracker {
+    osc: OscParser,
+    /// The shell has emitted an OSC 133 marker this session.
+    integrated: bool,
+    pending: VecDeque<(String, Instant)>,
+    current: Option<RunningInfo>,
+    /// A start marker arrived since the last prompt.
+    started_since_prompt: bool,
+    /// No-integration fallback: the last line sent, and the last output.
+    last_sent: Option<(String, Instant)>,
+    last_output: Option<Instant>,
+    /// Commands a `D` marker ended, with their exit codes, for `take_finished`.
+    finished: VecDeque<(String, Option<i32>)>,
+}
+
+impl RunningTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// True once the shell has emitted an OSC 133 marker.
+    pub fn is_integrated(&self) -> bool {
+        self.integrated
+    }
+
+    /// The Runner wrote `command` to the shell.
+    pub fn command_sent(&mut self, command: &str, now: Instant) {
+        self.last_sent = Some((command.to_string(), now));
+        self.last_output = None;
+        if self.current.is_some() {
+            // Typed into the running program.
+            return;
+        }
+        if self.pending.len() >= MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((command.to_string(), now));
+    }
+
+    /// Output from the shell, after probe answers are stripped.
+    pub fn feed(&mut self, bytes: &[u8], now: Instant) {
+        for ev in self.osc.feed(bytes) {
+            self.apply(&ev, now);
+        }
+        if !bytes.is_empty() {
+            self.last_output = Some(now);
+        }
+    }
+
+    pub fn apply(&mut self, ev: &OscEvent, now: Instant) {
+        match ev {
+            OscEvent::CommandStart | OscEvent::CommandExecuted => {
+                self.integrated = true;
+                self.started_since_prompt = true;
+                // `B` then `C` for the same command.
+                if self.current.is_some() {
+                    return;
+                }
+                // Nothing queued: the line was sent just as the previous
+                // command finished, and was taken for its input.
+                let command = self
+                    .pending
+                    .pop_front()
+                    .or_else(|| self.last_sent.clone())
+                    .map(|(cmd, _)| cmd)
+                    .unwrap_or_default();
+                self.current = Some(RunningInfo { command, started_at: now, confirmed: true });
+            }
+            OscEvent::CommandFinished { exit_code } => {
+                self.integrated = true;
+                if let Some(info) = self.current.take() {
+                    if self.finished.len() >= MAX_PENDING {
+                        self.finished.pop_front();
+                    }
+                    self.finished.push_back((info.command, *exit_code));
+                }
+            }
+            OscEvent::PromptStart => {
+                self.integrated = true;
+                self.current = None;
+                if !self.started_since_prompt {
+                    // The shell read a line and ran nothing.
+                    self.pending.pop_front();
+                }
+                self.started_since_prompt = false;
+            }
+            OscEvent::Cwd(_) | OscEvent::Unknown(_) => {}
+        }
+    }
+
+    /// The commands that finished since the last call, oldest first, with
+    /// their exit codes. Only integrated shells report these.
+    pub fn take_finished(&mut self) -> Vec<(String, Option<i32>)> {
+        self.finished.drain(..).collect()
+    }
+
+    /// The running command as of `now`, if any.
+    pub fn running(&self, now: Instant) -> Option<RunningInfo> {
+        if self.integrated {
+            return self.current.clone();
+        }
+        // Every command echoes and prints something at once; only one that
+        // is still producing output after `QUIET_AFTER` counts.
+        let (command, started_at) = self.last_sent.clone()?;
+        let output = self.last_output?;
+        let quiet = now.saturating_duration_since(output) >= QUIET_AFTER;
+        let young = now.saturating_duration_since(started_at) < QUIET_AFTER;
+        (!quiet && !young).then_some(RunningInfo { command, started_at, confirmed: false })
+    }
+}
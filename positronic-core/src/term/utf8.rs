@@ -0,0 +1,109 @@
+//! Incremental UTF-8 decoding for chunked PTY output.
+//!
+//! A PTY read can end in the middle of a multibyte character. Running
+//! `String::from_utf8_lossy` on each chunk turns both halves into U+FFFD;
+//! `Utf8Decoder` instead carries the incomplete tail (at most 3 bytes) into
+//! the next call. Genuinely invalid bytes are still replaced with U+FFFD
+//! using the same "maximal subpart" rule as the standard library, so the
+//! output for any chunking equals `from_utf8_lossy` of the whole stream.
+
+use std::borrow::Cow;
+
+const REPLACEMENT: char = '\u{FFFD}';
+
+/// Expected sequence length for a lead byte (0 = never valid as a lead).
+fn sequence_len(lead: u8) -> usize {
+    match lead {
+        0x00..=0x7F => 1,
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Utf8Decoder {
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl Utf8Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bytes of an incomplete character held back from the last chunk.
+    pub fn pending(&self) -> &[u8] {
+        &self.pending[..self.pending_len]
+    }
+
+    /// Decode `bytes`, borrowing when the chunk is self-contained valid UTF-8.
+    pub fn decode<'a>(&mut self, bytes: &'a [u8]) -> Cow<'a, str> {
+        if self.pending_len == 0 {
+            if let Ok(s) = std::str::from_utf8(bytes) {
+                return Cow::Borrowed(s);
+            }
+        }
+        let mut out = String::with_capacity(bytes.len() + 3);
+        self.decode_into(bytes, &mut out);
+        Cow::Owned(out)
+    }
+
+    /// Append the decoded form of `bytes` to `out`, holding back a trailing
+    /// incomplete character for the next call.
+    pub fn decode_into(&mut self, bytes: &[u8], out: &mut String) {
+        let mut input = bytes;
+
+        // 1) Finish the character carried over from the previous chunk.
+        if self.pending_len > 0 {
+            let need = sequence_len(self.pending[0]);
+            let take = (need - self.pending_len).min(input.len());
+            let mut tmp = [0u8; 4];
+            tmp[..self.pending_len].copy_from_slice(self.pending());
+            tmp[self.pending_len..self.pending_len + take].copy_from_slice(&input[..take]);
+            let tmp = &tmp[..self.pending_len + take];
+
+            let chunk = tmp.utf8_chunks().next().expect("tmp is non-empty");
+            if !chunk.valid().is_empty() {
+                out.push_str(chunk.valid());
+                input = &input[take..];
+                self.pending_len = 0;
+            } else if chunk.invalid().len() == tmp.len() && tmp.len() < need {
+                // Still incomplete: the whole chunk was consumed.
+                self.pending[..tmp.len()].copy_from_slice(tmp);
+                self.pending_len = tmp.len();
+                return;
+            } else {
+                out.push(REPLACEMENT);
+                input = &input[chunk.invalid().len() - self.pending_len..];
+                self.pending_len = 0;
+            }
+        }
+
+        // 2) Decode the rest; an incomplete tail becomes the new carry.
+        let mut chunks = input.utf8_chunks().peekable();
+        while let Some(chunk) = chunks.next() {
+            out.push_str(chunk.valid());
+            let invalid = chunk.invalid();
+            if invalid.is_empty() {
+                continue;
+            }
+            let is_tail = chunks.peek().is_none();
+            if is_tail && invalid.len() < sequence_len(invalid[0]) {
+                self.pending[..invalid.len()].copy_from_slice(invalid);
+                self.pending_len = invalid.len();
+            } else {
+                out.push(REPLACEMENT);
+            }
+        }
+    }
+
+    /// End of stream: a dangling partial character becomes U+FFFD.
+    pub fn finish(&mut self, out: &mut String) {
+        if self.pending_len > 0 {
+            out.push(REPLACEMENT);
+            self.pending_len = 0;
+        }
+    }
+}
@@ -0,0 +1,549 @@
+//! Test-run summaries: `cargo test` and `pytest` output read into counts
+//! and failures.
+//!
+//! Each runner has a `TestParser` that knows its command line and reads
+//! its output; `TestParsers` is the registry the Runner asks when a shell
+//! command finishes. The command's own runner is tried first, then any
+//! whose result lines are in the output (`make test` running cargo).
+//! Counts come from the runner's closing result lines, never from the
+//! per-test lines, so the order tests finished in, or their own output
+//! interleaved with the runner's, doesn't change them. pytest's failed
+//! test ids are read with the diagnostics extractor's pytest pattern.
+//!
+//! A report is logged to the vault with its command: `!tests` shows the
+//! last one, `!tests failed` puts a command that re-runs only its failures
+//! in the input line, and `!stats tests` tracks the pass rate.
+
+use std::fmt;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::alias::shell_quote;
+use crate::diagnostics::{Extractor, Severity};
+use crate::time_format::{self, TimeSettings};
+
+pub const TESTS_USAGE: &str = "Usage: !tests [<n> | failed]";
+
+/// Captured output lines kept per failure.
+pub const MAX_FAILURE_LINES: usize = 40;
+
+/// Runs `!stats tests` looks back over.
+pub const STATS_RUNS: usize = 200;
+
+/// Runs shown as ✓/✗ per project in `!stats tests`.
+const STRIP_RUNS: usize = 20;
+
+/// One failed test.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestFailure {
+    /// As the runner names it for a filter: `tests::parse_empty`, or
+    /// pytest's node id `tests/test_api.py::test_create`.
+    pub name: String,
+    /// What the test printed, its assertion message among it; at most
+    /// `MAX_FAILURE_LINES`.
+    pub output: Vec<String>,
+}
+
+/// What a test run came to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestReport {
+    /// The parser's name: `cargo test`, `pytest`.
+    pub framework: String,
+    pub passed: usize,
+    /// Failed tests, with pytest's errors.
+    pub failed: usize,
+    /// Ignored, skipped or expected to fail.
+    pub ignored: usize,
+    /// In the order the runner listed them.
+    pub failures: Vec<TestFailure>,
+}
+
+impl TestReport {
+    fn new(framework: &str) -> Self {
+        TestReport { framework: framework.to_string(), ..TestReport::default() }
+    }
+
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.ignored
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// `2 failed, 41 passed, 1 ignored`, leaving out a zero failed or
+    /// ignored.
+    pub fn counts(&self) -> String {
+        if self.total() == 0 {
+            return "no tests ran".to_string();
+        }
+        let mut parts = Vec::new();
+        if self.failed > 0 {
+            parts.push(format!("{} failed", self.failed));
+        }
+        parts.push(format!("{} passed", self.passed));
+        if self.ignored > 0 {
+            parts.push(format!("{} ignored", self.ignored));
+        }
+        parts.join(", ")
+    }
+
+    /// `🧪 cargo test: 2 failed, 41 passed`.
+    pub fn headline(&self) -> String {
+        format!("🧪 {}: {}", self.framework, self.counts())
+    }
+
+    /// The summary card: the headline and a numbered line per failure,
+    /// the ones in `expanded` (0-based) followed by their output.
+    pub fn card_lines(&self, expanded: &[usize]) -> Vec<String> {
+        let mut lines = vec![self.headline()];
+        for (i, failure) in self.failures.iter().enumerate() {
+            lines.push(format!("  {:>2}. ✗ {}", i + 1, failure.name));
+            if expanded.contains(&i) {
+                if failure.output.is_empty() {
+                    lines.push("      (no output captured)".to_string());
+                }
+                lines.extend(failure.output.iter().map(|l| format!("      {}", l)));
+            }
+        }
+        if !self.failures.is_empty() {
+            lines.push("  !tests <n> shows a failure's output; !tests failed re-runs them".to_string());
+        }
+        lines
+    }
+}
+
+/// A report as the vault keeps it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRun {
+    pub command: String,
+    pub directory: String,
+    pub report: TestReport,
+    pub ran_at: i64,
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Parsers
+// ════════════════════════════════════════════════════════════════════
+
+/// One test runner's output format.
+pub trait TestParser: fmt::Debug + Send + Sync {
+    /// Names the reports this parser makes.
+    fn framework(&self) -> &'static str;
+
+    /// Whether `command` runs this runner.
+    fn runs(&self, command: &str) -> bool;
+
+    /// The report in `output`, or `None` without this runner's result
+    /// lines.
+    fn parse(&self, output: &str) -> Option<TestReport>;
+
+    /// A command running only `failures` again, based on `command` when
+    /// this runner ran it.
+    fn rerun(&self, command: &str, failures: &[TestFailure]) -> String;
+}
+
+/// The parsers asked about finished commands.
+#[derive(Debug)]
+pub struct TestParsers {
+    parsers: Vec<Box<dyn TestParser>>,
+}
+
+impl Default for TestParsers {
+    fn default() -> Self {
+        let mut parsers = TestParsers { parsers: Vec::new() };
+        parsers.register(Box::new(CargoTest::new()));
+        parsers.register(Box::new(Pytest::new()));
+        parsers
+    }
+}
+
+impl TestParsers {
+    /// Add a parser; it is tried after the ones before it.
+    pub fn register(&mut self, parser: Box<dyn TestParser>) {
+        self.parsers.push(parser);
+    }
+
+    /// Frameworks with a parser, in registration order.
+    pub fn frameworks(&self) -> Vec<&'static str> {
+        self.parsers.iter().map(|p| p.framework()).collect()
+    }
+
+    /// The report in what `command` printed: its own runner's parser
+    /// first, then any that finds its result lines.
+    pub fn parse(&self, command: &str, output: &str) -> Option<TestReport> {
+        let (own, others): (Vec<_>, Vec<_>) = self.parsers.iter().partition(|p| p.runs(command));
+        own.into_iter().chain(others).find_map(|p| p.parse(output))
+    }
+
+    /// The command re-running `run`'s failures; `None` if nothing failed
+    /// or its framework has no parser here.
+    pub fn rerun(&self, run: &TestRun) -> Option<String> {
+        if run.report.failures.is_empty() {
+            return None;
+        }
+        let parser = self.parsers.iter().find(|p| p.framework() == run.report.framework)?;
+        Some(parser.rerun(&run.command, &run.report.failures))
+    }
+}
+
+/// Whether `word` runs the program `name`, by path or with `.exe`.
+fn is_program(word: &str, name: &str) -> bool {
+    let file = Path::new(word).file_name().and_then(|f| f.to_str()).unwrap_or(word);
+    file == name || file.strip_suffix(".exe") == Some(name)
+}
+
+/// `name` as one shell word, quoted only when it needs it.
+fn shell_word(name: &str) -> String {
+    let plain = name.chars().all(|c| c.is_ascii_alphanumeric() || "_-:./=".contains(c));
+    if plain && !name.is_empty() {
+        name.to_string()
+    } else {
+        shell_quote(name)
+    }
+}
+
+/// Failure output without its trailing blank lines, cut at
+/// `MAX_FAILURE_LINES`.
+fn captured(mut lines: Vec<String>) -> Vec<String> {
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    if lines.len() > MAX_FAILURE_LINES {
+        let more = lines.len() - (MAX_FAILURE_LINES - 1);
+        lines.truncate(MAX_FAILURE_LINES - 1);
+        lines.push(format!("… {} more lines", more));
+    }
+    lines
+}
+
+fn push_unique(names: &mut Vec<String>, name: &str) {
+    if !names.iter().any(|n| n == name) {
+        names.push(name.to_string());
+    }
+}
+
+/// libtest's output, as `cargo test` prints it for each test binary and
+/// the doctests.
+#[derive(Debug)]
+pub struct CargoTest {
+    /// `test result: FAILED. 41 passed; 2 failed; 1 ignored; ...`
+    result: Regex,
+    /// `test tests::parse ... ok`
+    test: Regex,
+    /// `---- tests::parse stdout ----`
+    section: Regex,
+}
+
+impl CargoTest {
+    pub fn new() -> Self {
+        CargoTest {
+            result: Regex::new(r"^test result: (?:ok|FAILED)\. (\d+) passed; (\d+) failed; (\d+) ignored;").expect("cargo result"),
+            test: Regex::new(r"^test (.+?) \.\.\. (ok|FAILED|ignored)\b").expect("cargo test line"),
+            section: Regex::new(r"^---- (.+) std(?:out|err) ----$").expect("cargo section"),
+        }
+    }
+}
+
+impl Default for CargoTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestParser for CargoTest {
+    fn framework(&self) -> &'static str {
+        "cargo test"
+    }
+
+    fn runs(&self, command: &str) -> bool {
+        let mut words = command.split_whitespace().skip_while(|w| !is_program(w, "cargo"));
+        words.next().is_some() && words.find(|w| !w.starts_with('+')).is_some_and(|w| w == "test" || w == "t")
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let mut report = TestReport::new(self.framework());
+        let mut results = 0;
+        // Per-test lines, only counted when no binary got to its result.
+        let mut tests: Vec<(String, String)> = Vec::new();
+        let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+        let mut in_section = false;
+        // The names under the last `failures:`, the one before the result.
+        let mut listed: Vec<String> = Vec::new();
+        let mut in_list = false;
+        for line in output.lines().map(str::trim_end) {
+            if let Some(caps) = self.result.captures(line) {
+                results += 1;
+                report.passed += caps[1].parse::<usize>().unwrap_or(0);
+                report.failed += caps[2].parse::<usize>().unwrap_or(0);
+                report.ignored += caps[3].parse::<usize>().unwrap_or(0);
+                (in_section, in_list) = (false, false);
+            } else if let Some(caps) = self.section.captures(line) {
+                sections.push((caps[1].to_string(), Vec::new()));
+                (in_section, in_list) = (true, false);
+            } else if line == "failures:" {
+                (in_section, in_list) = (false, true);
+            } else if in_list {
+                match line.strip_prefix("    ") {
+                    Some(name) if !name.trim().is_empty() => push_unique(&mut listed, name.trim()),
+                    _ => in_list = false,
+                }
+            } else if in_section {
+                if let Some((_, lines)) = sections.last_mut() {
+                    lines.push(line.to_string());
+                }
+            } else if let Some(caps) = self.test.captures(line) {
+                tests.push((caps[1].to_string(), caps[2].to_string()));
+            }
+        }
+
+        if results == 0 {
+            if tests.is_empty() {
+                return None;
+            }
+            for (_, status) in &tests {
+                match status.as_str() {
+                    "ok" => report.passed += 1,
+                    "FAILED" => report.failed += 1,
+                    _ => report.ignored += 1,
+                }
+            }
+        }
+        let mut names = listed;
+        if names.is_empty() {
+            for (name, _) in &sections {
+                push_unique(&mut names, name);
+            }
+        }
+        if names.is_empty() {
+            for (name, _) in tests.iter().filter(|(_, status)| status == "FAILED") {
+                push_unique(&mut names, name);
+            }
+        }
+        report.failures = names
+            .into_iter()
+            .map(|name| {
+                let output = sections.iter().find(|(n, _)| *n == name).map(|(_, l)| l.clone()).unwrap_or_default();
+                TestFailure { name, output: captured(output) }
+            })
+            .collect();
+        Some(report)
+    }
+
+    /// `cargo test <options> -- --exact <names>`: the filters are exact
+    /// names, so a doctest's `src/lib.rs - add (line 5)` is one of them.
+    fn rerun(&self, command: &str, failures: &[TestFailure]) -> String {
+        let names: Vec<String> = failures.iter().map(|f| shell_word(&f.name)).collect();
+        let base = if self.runs(command) { command.trim() } else { "cargo test" };
+        let separator = if base.split_whitespace().any(|w| w == "--") { "" } else { " --" };
+        format!("{}{} --exact {}", base, separator, names.join(" "))
+    }
+}
+
+/// pytest's output, plain or `-q`.
+#[derive(Debug)]
+pub struct Pytest {
+    /// `==== 1 failed, 3 passed, 1 skipped in 0.12s ====`
+    outcome: Regex,
+    count: Regex,
+    /// `==== FAILURES ====`
+    banner: Regex,
+    /// `____ test_create ____`
+    header: Regex,
+    /// The short test summary's `FAILED` and `ERROR` lines.
+    failed: Extractor,
+}
+
+impl Pytest {
+    pub fn new() -> Self {
+        let mut failed = Extractor::builtin("pytest");
+        failed
+            .register("pytest", r"^ERROR (?P<file>[^\s:]+)::(?P<code>\S+)(?: - (?P<message>.+))?$", None, Severity::Error)
+            .expect("pytest error pattern");
+        Pytest {
+            outcome: Regex::new(
+                r"^=*\s*(no tests ran|\d+ (?:passed|failed|skipped|xfailed|xpassed|errors?|deselected|warnings?|rerun)(?:, \d+ [a-z]+)*) in [\d.]+s\b",
+            )
+            .expect("pytest outcome"),
+            count: Regex::new(r"(\d+) ([a-z]+)").expect("pytest count"),
+            banner: Regex::new(r"^=+ (.+?) =+$").expect("pytest banner"),
+            header: Regex::new(r"^_{3,} (.+?) _{3,}$").expect("pytest header"),
+            failed,
+        }
+    }
+}
+
+impl Default for Pytest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestParser for Pytest {
+    fn framework(&self) -> &'static str {
+        "pytest"
+    }
+
+    fn runs(&self, command: &str) -> bool {
+        command.split_whitespace().any(|w| is_program(w, "pytest") || is_program(w, "py.test"))
+    }
+
+    fn parse(&self, output: &str) -> Option<TestReport> {
+        let outcome = output.lines().rev().find_map(|line| self.outcome.captures(line.trim_end()))?;
+        let mut report = TestReport::new(self.framework());
+        for caps in self.count.captures_iter(&outcome[1]) {
+            let n: usize = caps[1].parse().unwrap_or(0);
+            match &caps[2] {
+                "passed" | "xpassed" => report.passed += n,
+                "failed" | "error" | "errors" => report.failed += n,
+                "skipped" | "xfailed" => report.ignored += n,
+                _ => {}
+            }
+        }
+
+        let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+        let mut in_failures = false;
+        for line in output.lines().map(str::trim_end) {
+            if let Some(caps) = self.banner.captures(line) {
+                in_failures = matches!(&caps[1], "FAILURES" | "ERRORS");
+            } else if !in_failures {
+                continue;
+            } else if let Some(caps) = self.header.captures(line) {
+                sections.push((caps[1].to_string(), Vec::new()));
+            } else if let Some((_, lines)) = sections.last_mut() {
+                lines.push(line.to_string());
+            }
+        }
+
+        let mut names = Vec::new();
+        for d in self.failed.extract(output) {
+            if let Some(code) = &d.code {
+                push_unique(&mut names, &format!("{}::{}", d.file, code));
+            }
+        }
+        if names.is_empty() {
+            for (name, _) in &sections {
+                push_unique(&mut names, name);
+            }
+        }
+        report.failures = names
+            .into_iter()
+            .map(|name| {
+                // The section is headed by the id's test part, `Class.test`;
+                // an error's by `ERROR at setup of test`.
+                let key = name.split_once("::").map_or(name.clone(), |(_, test)| test.replace("::", "."));
+                let output = sections
+                    .iter()
+                    .find(|(header, _)| *header == key || header.ends_with(&format!(" {}", key)))
+                    .map(|(_, lines)| lines.clone())
+                    .unwrap_or_default();
+                TestFailure { name, output: captured(output) }
+            })
+            .collect();
+        Some(report)
+    }
+
+    /// The command up to `pytest` and the failed node ids; failures only
+    /// known by name go in one `-k`.
+    fn rerun(&self, command: &str, failures: &[TestFailure]) -> String {
+        let mut words: Vec<String> = Vec::new();
+        if self.runs(command) {
+            for word in command.split_whitespace() {
+                words.push(word.to_string());
+                if is_program(word, "pytest") || is_program(word, "py.test") {
+                    break;
+                }
+            }
+        } else {
+            words.push("pytest".to_string());
+        }
+        let (ids, names): (Vec<&TestFailure>, Vec<&TestFailure>) = failures.iter().partition(|f| f.name.contains("::"));
+        words.extend(ids.iter().map(|f| shell_word(&f.name)));
+        if !names.is_empty() {
+            let expression: Vec<&str> = names.iter().map(|f| f.name.as_str()).collect();
+            words.push("-k".to_string());
+            words.push(shell_quote(&expression.join(" or ")));
+        }
+        words.join(" ")
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// !tests and !stats tests
+// ════════════════════════════════════════════════════════════════════
+
+/// What follows `!tests`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestsCommand {
+    /// The last report, with failure `n` (1-based) expanded if given.
+    Show(Option<usize>),
+    /// Put the re-run of the last report's failures in the input line.
+    Failed,
+}
+
+impl TestsCommand {
+    pub fn parse(args: &str) -> Result<TestsCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(TestsCommand::Show(None)),
+            ["failed"] => Ok(TestsCommand::Failed),
+            [n] => match n.parse::<usize>() {
+                Ok(n) if n >= 1 => Ok(TestsCommand::Show(Some(n))),
+                _ => Err(TESTS_USAGE.to_string()),
+            },
+            _ => Err(TESTS_USAGE.to_string()),
+        }
+    }
+}
+
+/// `!tests`: the card for `run`, with failure `expand` (1-based) opened.
+pub fn show_lines(run: &TestRun, expand: Option<usize>, time: &TimeSettings) -> Vec<String> {
+    if let Some(n) = expand {
+        if n > run.report.failures.len() {
+            return vec![format!("🧪 No failure #{} in the last run (it had {})", n, run.report.failures.len())];
+        }
+    }
+    let expanded: Vec<usize> = expand.map(|n| n - 1).into_iter().collect();
+    let mut lines = run.report.card_lines(&expanded);
+    let when = time_format::when_local(run.ran_at, time);
+    lines.insert(1, format!("  `{}` in {}, {}", run.command, run.directory, when));
+    lines
+}
+
+/// `!stats tests`: pass rate per framework and directory, over `runs`
+/// (newest first).
+pub fn stats_lines(runs: &[TestRun]) -> Vec<String> {
+    if runs.is_empty() {
+        return vec!["🧪 No test runs logged yet; cargo test and pytest runs are logged when they finish".to_string()];
+    }
+    // Projects in order of their latest run.
+    let mut projects: Vec<(&str, &str, Vec<&TestRun>)> = Vec::new();
+    for run in runs {
+        let key = (run.report.framework.as_str(), run.directory.as_str());
+        match projects.iter_mut().find(|(f, d, _)| (*f, *d) == key) {
+            Some((_, _, project)) => project.push(run),
+            None => projects.push((key.0, key.1, vec![run])),
+        }
+    }
+    let mut lines = vec![format!("🧪 Test pass rate over the last {} runs:", runs.len())];
+    for (framework, directory, project) in projects {
+        let passed: usize = project.iter().map(|r| r.report.passed).sum();
+        let failed: usize = project.iter().map(|r| r.report.failed).sum();
+        let green = project.iter().filter(|r| r.report.succeeded()).count();
+        let rate = if passed + failed == 0 { 100.0 } else { passed as f64 * 100.0 / (passed + failed) as f64 };
+        lines.push(format!(
+            "  {} in {}: {:.1}% of tests passed, {} of {} runs green",
+            framework,
+            directory,
+            rate,
+            green,
+            project.len()
+        ));
+        let strip: String =
+            project.iter().take(STRIP_RUNS).rev().map(|r| if r.report.succeeded() { '✓' } else { '✗' }).collect();
+        lines.push(format!("    {} latest: {}", strip, project[0].report.counts()));
+    }
+    lines
+}
@@ -0,0 +1,243 @@
+//! How timestamps are shown: history listings, tags, test runs, the
+//! timeline, line timestamps on blocks and the status bar all format
+//! through here.
+//!
+//! The vault keeps storing UTC epoch seconds; only presentation happens
+//! here. Every function is pure, taking the zone and the "now" it formats
+//! against: callers pass `chrono::Local` and the clock, tests pass a zone
+//! with DST rules and a fixed instant.
+//!
+//! `time.relative` (on by default) shows a recent stamp as "just now",
+//! "3 min ago", "2 h ago", "today 09:14" or "yesterday 14:02"; older
+//! stamps, and every stamp with it off, are absolute. `time.format` is
+//! `24h` (the default), `12h`, or a strftime pattern for absolute stamps.
+//! Exports and JSON use `iso8601` whatever these say.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, NaiveTime, SecondsFormat, TimeZone, Utc};
+use serde::Serializer;
+
+use crate::history_filter::parse_flag;
+
+/// Vault config keys.
+pub const FORMAT_KEY: &str = "time.format";
+pub const RELATIVE_KEY: &str = "time.relative";
+
+/// Stamps this recent read "N min ago" / "N h ago".
+pub const RECENT_SECS: i64 = 6 * 3600;
+
+/// A stamp up to this far in the future (another machine's clock, a
+/// synced entry) still reads "just now".
+const FUTURE_SLACK_SECS: i64 = 60;
+
+/// Absolute stamps without a `time.format` pattern.
+const DATE: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clock {
+    #[default]
+    H24,
+    H12,
+}
+
+impl Clock {
+    fn pattern(self, seconds: bool) -> &'static str {
+        match (self, seconds) {
+            (Clock::H24, false) => "%H:%M",
+            (Clock::H24, true) => "%H:%M:%S",
+            (Clock::H12, false) => "%-I:%M %p",
+            (Clock::H12, true) => "%-I:%M:%S %p",
+        }
+    }
+}
+
+/// `time.*`, as read from the config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeSettings {
+    /// Times of day; follows a `time.format` pattern's `%I`/`%p`.
+    pub clock: Clock,
+    /// `time.format` when it is a strftime pattern: absolute stamps.
+    pub pattern: Option<String>,
+    pub relative: bool,
+}
+
+impl Default for TimeSettings {
+    fn default() -> Self {
+        Self { clock: Clock::H24, pattern: None, relative: true }
+    }
+}
+
+impl TimeSettings {
+    /// Read the keys through `lookup`. Invalid values keep their defaults
+    /// and are described in `problems`.
+    pub fn load(lookup: impl Fn(&str) -> Option<String>, problems: &mut Vec<String>) -> TimeSettings {
+        let mut settings = TimeSettings::default();
+        if let Some(value) = lookup(FORMAT_KEY) {
+            match parse_format(&value) {
+                Some((clock, pattern)) => {
+                    settings.clock = clock;
+                    settings.pattern = pattern;
+                }
+                None => problems.push(format!(
+                    "{} = \"{}\": expected 24h, 12h or a strftime pattern such as %d.%m.%Y %H:%M",
+                    FORMAT_KEY, value
+                )),
+            }
+        }
+        if let Some(value) = lookup(RELATIVE_KEY) {
+            match parse_flag(&value) {
+                Some(on) => settings.relative = on,
+                None => problems.push(format!("{} = \"{}\": expected on or off", RELATIVE_KEY, value)),
+            }
+        }
+        settings
+    }
+}
+
+/// `24h`, `12h`, or a pattern with at least one `%` field that chrono
+/// understands.
+fn parse_format(value: &str) -> Option<(Clock, Option<String>)> {
+    match value.trim().to_lowercase().as_str() {
+        "24h" | "24" => return Some((Clock::H24, None)),
+        "12h" | "12" => return Some((Clock::H12, None)),
+        _ => {}
+    }
+    let pattern = value.trim();
+    let items: Vec<Item> = StrftimeItems::new(pattern).collect();
+    if !pattern.contains('%') || items.iter().any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    let twelve = ["%I", "%l", "%p", "%P", "%r"].iter().any(|field| pattern.contains(field));
+    Some((if twelve { Clock::H12 } else { Clock::H24 }, Some(pattern.to_string())))
+}
+
+/// `ts` in `tz`.
+pub fn local<Tz: TimeZone>(ts: i64, tz: &Tz) -> Option<DateTime<Tz>> {
+    DateTime::from_timestamp(ts, 0).map(|t| t.with_timezone(tz))
+}
+
+/// `14:02` or `2:02 PM`.
+pub fn clock<Tz: TimeZone>(ts: i64, tz: &Tz, clock: Clock) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    local(ts, tz).map(|t| t.format(clock.pattern(false)).to_string()).unwrap_or_default()
+}
+
+/// `14:02:31` or `2:02:31 PM`.
+pub fn clock_secs<Tz: TimeZone>(ts: i64, tz: &Tz, clock: Clock) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    local(ts, tz).map(|t| t.format(clock.pattern(true)).to_string()).unwrap_or_default()
+}
+
+/// `2026-03-08 14:02`, `2026-03-08 2:02 PM`, or the `time.format` pattern.
+pub fn absolute<Tz: TimeZone>(ts: i64, tz: &Tz, settings: &TimeSettings) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let Some(t) = local(ts, tz) else {
+        return String::new();
+    };
+    match &settings.pattern {
+        Some(pattern) => t.format(pattern).to_string(),
+        None => format!("{} {}", t.format(DATE), t.format(settings.clock.pattern(false))),
+    }
+}
+
+/// `ts` as of `now`: relative while recent (see the module docs), else
+/// `absolute`.
+pub fn when<Tz: TimeZone>(ts: i64, now: i64, tz: &Tz, settings: &TimeSettings) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if settings.relative {
+        let elapsed = now - ts;
+        if (-FUTURE_SLACK_SECS..RECENT_SECS).contains(&elapsed) {
+            return ago(elapsed);
+        }
+        if let (Some(then), Some(today)) = (local(ts, tz), local(now, tz)) {
+            let (then_day, today) = (then.date_naive(), today.date_naive());
+            if elapsed > 0 && then_day == today {
+                return format!("today {}", then.format(settings.clock.pattern(false)));
+            }
+            if elapsed > 0 && then_day.succ_opt() == Some(today) {
+                return format!("yesterday {}", then.format(settings.clock.pattern(false)));
+            }
+        }
+    }
+    absolute(ts, tz, settings)
+}
+
+/// `when` in the local zone, as of the clock.
+pub fn when_local(ts: i64, settings: &TimeSettings) -> String {
+    when(ts, Utc::now().timestamp(), &Local, settings)
+}
+
+/// "just now", "3 min ago", "2 h ago"; under a minute (or slightly in
+/// the future) is "just now".
+pub fn ago(elapsed_secs: i64) -> String {
+    match elapsed_secs {
+        s if s < 60 => "just now".to_string(),
+        s if s < 3600 => format!("{} min ago", s / 60),
+        s => format!("{} h ago", s / 3600),
+    }
+}
+
+/// Seconds from `now` until `when(ts, now, ..)` reads differently, for a
+/// stamp shown persistently; `None` once it no longer changes.
+pub fn refresh_in<Tz: TimeZone>(ts: i64, now: i64, tz: &Tz, settings: &TimeSettings) -> Option<i64> {
+    if !settings.relative {
+        return None;
+    }
+    let elapsed = now - ts;
+    match elapsed {
+        e if e < -FUTURE_SLACK_SECS => Some(-FUTURE_SLACK_SECS - e),
+        e if e < 60 => Some(60 - e),
+        e if e < 3600 => Some(60 - e % 60),
+        e if e < RECENT_SECS => Some(3600 - e % 3600),
+        // "today …" becomes "yesterday …" at midnight, then absolute at
+        // the one after.
+        _ => {
+            let (then, today) = (local(ts, tz)?.date_naive(), local(now, tz)?.date_naive());
+            if then.succ_opt()?.succ_opt()? <= today {
+                return None;
+            }
+            let midnight = today.succ_opt()?.and_time(NaiveTime::MIN);
+            // A zone that skips midnight on a DST change: its first hour.
+            let next = tz
+                .from_local_datetime(&midnight)
+                .earliest()
+                .or_else(|| tz.from_local_datetime(&(midnight + chrono::Duration::hours(1))).earliest())?;
+            Some((next.timestamp() - now).max(1))
+        }
+    }
+}
+
+/// `2026-03-08T19:02:31Z`: what exports and JSON carry.
+pub fn iso8601(ts: i64) -> String {
+    DateTime::<Utc>::from_timestamp(ts, 0)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+/// `2026-03-08T19:02:31.207Z`, for millisecond stamps.
+pub fn iso8601_ms(ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+/// `#[serde(serialize_with = ...)]` for an epoch field.
+pub fn serialize_iso8601<S: Serializer>(ts: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&iso8601(*ts))
+}
+
+/// `serialize_iso8601` for an optional epoch; `None` stays null.
+pub fn serialize_iso8601_opt<S: Serializer>(ts: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+    match ts {
+        Some(ts) => serializer.serialize_str(&iso8601(*ts)),
+        None => serializer.serialize_none(),
+    }
+}
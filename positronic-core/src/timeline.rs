@@ -0,0 +1,476 @@
+//! `!timeline`: when commands ran, how long they took, which failed.
+//!
+//! The Vault supplies the commands of a window (this session, today, or
+//! a given day) and `layout` turns them into rows: one per time bucket,
+//! the bucket size picked so the whole window fits in `MAX_ROWS`. Each
+//! row has a bar with one segment per command, as long as the command
+//! ran and drawn in its exit status's glyph; the busiest bucket fills the
+//! bar. Stretches of `IDLE_GAP_SECS` or more without a command collapse
+//! into one `· 42 min idle ·` row. Rows logged before durations were
+//! recorded have none and show as one-cell ticks.
+//!
+//! `layout` is pure over `TimelineEvent`s so the frontend can lay the
+//! same data out at its own width, and find the command under a click.
+//! Row times follow `time.format`'s clock (`layout_with`).
+
+use chrono::{FixedOffset, NaiveDate};
+
+use crate::time_format::{self, Clock};
+use crate::vault::timing::format_ms;
+use crate::vault::CommandRecord;
+
+pub const TIMELINE_USAGE: &str = "Usage: !timeline [session|today|<YYYY-MM-DD>]";
+
+/// Gaps at least this long collapse into an idle row.
+pub const IDLE_GAP_SECS: i64 = 15 * 60;
+
+/// Rows a timeline aims to fit in; the bucket grows until it does.
+pub const MAX_ROWS: usize = 24;
+
+/// The newest commands a timeline shows.
+pub const TIMELINE_CAP: usize = 5000;
+
+/// Width for text without a screen to fit (headless runs, hook output).
+pub const DEFAULT_WIDTH: usize = 100;
+
+/// Bucket sizes tried, smallest first.
+const BUCKETS: [i64; 11] = [60, 300, 600, 900, 1800, 3600, 7200, 10800, 21600, 43200, 86400];
+
+/// Brackets around the bar; the frontend colors what is between.
+pub const BAR_OPEN: char = '▕';
+pub const BAR_CLOSE: char = '▏';
+
+/// `HH:MM ` before the bar, and the count, busy time and failures after.
+const TIME_WIDTH: usize = 6;
+/// `12:30 PM ` before the bar on a 12-hour clock.
+const TIME_WIDTH_12H: usize = 9;
+const STATS_WIDTH: usize = 18;
+const MIN_BAR: usize = 10;
+const MAX_BAR: usize = 60;
+/// Room the directory gets before the bar takes the rest.
+const DIR_WIDTH: usize = 20;
+
+/// Which commands `!timeline` shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineRange {
+    Session,
+    Today,
+    Day(NaiveDate),
+}
+
+impl TimelineRange {
+    /// Parse what follows `!timeline`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<TimelineRange, String> {
+        match args.trim() {
+            "" | "session" => Ok(TimelineRange::Session),
+            "today" => Ok(TimelineRange::Today),
+            date => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(TimelineRange::Day)
+                .map_err(|_| TIMELINE_USAGE.to_string()),
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            TimelineRange::Session => "this session".to_string(),
+            TimelineRange::Today => "today".to_string(),
+            TimelineRange::Day(date) => date.format("%Y-%m-%d").to_string(),
+        }
+    }
+
+    /// The `[from, to)` timestamps of a day range, local midnight to
+    /// midnight; `None` for the session, which the Vault selects by id.
+    pub fn window(self, now: i64, utc_offset_secs: i64) -> Option<(i64, i64)> {
+        let day = match self {
+            TimelineRange::Session => return None,
+            TimelineRange::Today => (now + utc_offset_secs).div_euclid(86_400),
+            TimelineRange::Day(date) => date.signed_duration_since(NaiveDate::default()).num_days(),
+        };
+        let from = day * 86_400 - utc_offset_secs;
+        Some((from, from + 86_400))
+    }
+}
+
+/// One command, as the timeline sees it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEvent {
+    /// History row id, for `!history --at`.
+    pub id: i64,
+    /// Unix seconds.
+    pub start: i64,
+    pub duration_ms: Option<i64>,
+    pub exit: Option<i32>,
+    pub cwd: String,
+}
+
+impl TimelineEvent {
+    /// History rows are stamped when the command ends, so it started its
+    /// duration earlier.
+    pub fn from_record(record: &CommandRecord) -> Self {
+        let duration_ms = record.duration_ms.map(|ms| ms.max(0));
+        Self {
+            id: record.id.unwrap_or_default(),
+            start: record.timestamp - duration_ms.unwrap_or(0) / 1000,
+            duration_ms,
+            exit: record.exit_code,
+            cwd: record.directory.clone(),
+        }
+    }
+
+    fn end(&self) -> i64 {
+        self.start + self.duration_ms.unwrap_or(0) / 1000
+    }
+
+    pub fn status(&self) -> Status {
+        match self.exit {
+            Some(0) => Status::Ok,
+            Some(_) => Status::Failed,
+            None => Status::Unknown,
+        }
+    }
+}
+
+/// How a command ended, which picks its glyph (and the frontend its color).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Failed,
+    Unknown,
+}
+
+impl Status {
+    /// The glyph of a bar segment.
+    pub fn bar(self) -> char {
+        match self {
+            Status::Ok => '█',
+            Status::Failed => '▓',
+            Status::Unknown => '▒',
+        }
+    }
+
+    /// The glyph of a command with no recorded duration.
+    pub fn tick(self) -> char {
+        match self {
+            Status::Ok => '│',
+            Status::Failed => '┃',
+            Status::Unknown => '╎',
+        }
+    }
+
+    /// The status a bar glyph stands for.
+    pub fn of_glyph(c: char) -> Option<Status> {
+        [Status::Ok, Status::Failed, Status::Unknown].into_iter().find(|s| s.bar() == c || s.tick() == c)
+    }
+}
+
+/// What `!timeline` found, for the frontend to lay out at its width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineData {
+    pub label: String,
+    pub events: Vec<TimelineEvent>,
+    pub utc_offset_secs: i64,
+    pub clock: Clock,
+}
+
+impl TimelineData {
+    pub fn layout(&self, width: usize) -> Timeline {
+        layout_with(&self.events, width, self.utc_offset_secs, self.clock)
+    }
+
+    pub fn lines(&self, width: usize) -> Vec<String> {
+        self.layout(width).lines(&self.label)
+    }
+}
+
+/// A command's part of a bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub id: i64,
+    pub cells: usize,
+    pub status: Status,
+    /// No duration: a one-cell tick.
+    pub instant: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    Bucket {
+        /// Unix seconds at the bucket's start.
+        start: i64,
+        commands: usize,
+        failed: usize,
+        busy_ms: i64,
+        /// Where most of the bucket's commands ran.
+        top_dir: Option<String>,
+        segments: Vec<Segment>,
+    },
+    Idle {
+        secs: i64,
+    },
+}
+
+/// A laid-out timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub bucket_secs: i64,
+    pub bar_width: usize,
+    pub dir_width: usize,
+    pub utc_offset_secs: i64,
+    pub clock: Clock,
+    pub rows: Vec<Row>,
+    pub commands: usize,
+    pub failed: usize,
+    pub busy_ms: i64,
+}
+
+/// Lay `events` out in rows `width` columns wide, clock times
+/// `utc_offset_secs` from UTC.
+pub fn layout(events: &[TimelineEvent], width: usize, utc_offset_secs: i64) -> Timeline {
+    layout_with(events, width, utc_offset_secs, Clock::H24)
+}
+
+/// `layout`, with row times on `clock`.
+pub fn layout_with(events: &[TimelineEvent], width: usize, utc_offset_secs: i64, clock: Clock) -> Timeline {
+    let time_width = time_width(clock);
+    let mut events: Vec<&TimelineEvent> = events.iter().collect();
+    events.sort_by_key(|e| (e.start, e.id));
+
+    let bucket_secs = BUCKETS
+        .into_iter()
+        .find(|&b| walk(&events, b, utc_offset_secs).len() <= MAX_ROWS)
+        .unwrap_or(BUCKETS[BUCKETS.len() - 1]);
+    let bar_width = width.saturating_sub(time_width + 2 + STATS_WIDTH + DIR_WIDTH).clamp(MIN_BAR, MAX_BAR);
+    let dir_width = width.saturating_sub(time_width + 2 + STATS_WIDTH + bar_width);
+
+    let walked = walk(&events, bucket_secs, utc_offset_secs);
+    let busiest = walked
+        .iter()
+        .filter_map(|step| match step {
+            Step::Bucket(_, group) => Some(group.iter().filter_map(|e| e.duration_ms).sum::<i64>()),
+            Step::Idle(_) => None,
+        })
+        .max()
+        .unwrap_or(0);
+
+    let rows = walked
+        .into_iter()
+        .map(|step| match step {
+            Step::Idle(secs) => Row::Idle { secs },
+            Step::Bucket(index, group) => Row::Bucket {
+                start: index * bucket_secs - utc_offset_secs,
+                commands: group.len(),
+                failed: group.iter().filter(|e| e.status() == Status::Failed).count(),
+                busy_ms: group.iter().filter_map(|e| e.duration_ms).sum(),
+                top_dir: top_dir(&group),
+                segments: segments(&group, busiest, bar_width),
+            },
+        })
+        .collect();
+
+    Timeline {
+        bucket_secs,
+        bar_width,
+        dir_width,
+        utc_offset_secs,
+        clock,
+        rows,
+        commands: events.len(),
+        failed: events.iter().filter(|e| e.status() == Status::Failed).count(),
+        busy_ms: events.iter().filter_map(|e| e.duration_ms).sum(),
+    }
+}
+
+enum Step<'a> {
+    /// Bucket index (local time / bucket size) and the commands that started in it.
+    Bucket(i64, Vec<&'a TimelineEvent>),
+    Idle(i64),
+}
+
+/// The rows for `events` (sorted) at bucket size `b`: buckets from the
+/// first command to the last, empty ones included, except across a gap
+/// of `IDLE_GAP_SECS` that also skips a whole bucket.
+fn walk<'a>(events: &[&'a TimelineEvent], b: i64, utc_offset_secs: i64) -> Vec<Step<'a>> {
+    let mut steps: Vec<Step<'a>> = Vec::new();
+    let mut last: Option<(i64, i64)> = None; // (bucket index, latest end)
+    for event in events {
+        let index = (event.start + utc_offset_secs).div_euclid(b);
+        if let Some((previous, end)) = last {
+            if index > previous + 1 && event.start - end >= IDLE_GAP_SECS {
+                steps.push(Step::Idle(event.start - end));
+            } else {
+                steps.extend((previous + 1..index).map(|i| Step::Bucket(i, Vec::new())));
+            }
+        }
+        match steps.last_mut() {
+            Some(Step::Bucket(i, group)) if *i == index => group.push(event),
+            _ => steps.push(Step::Bucket(index, vec![event])),
+        }
+        let end = last.map_or(event.end(), |(_, end)| end.max(event.end()));
+        last = Some((index, end));
+    }
+    steps
+}
+
+/// The bar of one bucket: each command its share of the busiest bucket's
+/// time, rounded on the running total so short commands add up; ticks for
+/// commands without a duration. A bucket that ran anything shows at least
+/// one cell; a bar that would overflow is cut.
+fn segments(group: &[&TimelineEvent], busiest: i64, width: usize) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut total = 0i64;
+    let scaled = |ms: i64| if busiest == 0 { 0 } else { ((ms * width as i64) as f64 / busiest as f64).round() as usize };
+    for event in group {
+        let (cells, instant) = match event.duration_ms {
+            None => (1, true),
+            Some(ms) => {
+                let before = scaled(total);
+                total += ms;
+                (scaled(total) - before, false)
+            }
+        };
+        if cells > 0 {
+            segments.push(Segment { id: event.id, cells, status: event.status(), instant });
+        }
+    }
+    if segments.is_empty() {
+        if let Some(longest) = group.iter().max_by_key(|e| e.duration_ms) {
+            segments.push(Segment { id: longest.id, cells: 1, status: longest.status(), instant: false });
+        }
+    }
+    let mut room = width;
+    for segment in &mut segments {
+        segment.cells = segment.cells.min(room);
+        room -= segment.cells;
+    }
+    segments.retain(|s| s.cells > 0);
+    segments
+}
+
+/// The directory most of `group` ran in; the later one on a tie.
+fn top_dir(group: &[&TimelineEvent]) -> Option<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for event in group {
+        match counts.iter_mut().find(|(dir, _)| *dir == event.cwd) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((&event.cwd, 1)),
+        }
+    }
+    let last_seen = |dir: &str| group.iter().rposition(|e| e.cwd == dir);
+    counts.into_iter().max_by_key(|(dir, n)| (*n, last_seen(dir))).map(|(dir, _)| dir.to_string())
+}
+
+/// `HH:MM` of `ts`, `utc_offset_secs` from UTC.
+pub fn clock(ts: i64, utc_offset_secs: i64) -> String {
+    let secs = (ts + utc_offset_secs).rem_euclid(86_400);
+    format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+fn time_width(clock: Clock) -> usize {
+    match clock {
+        Clock::H24 => TIME_WIDTH,
+        Clock::H12 => TIME_WIDTH_12H,
+    }
+}
+
+/// `42 min`, `3 h 05 min`.
+fn idle_label(secs: i64) -> String {
+    let minutes = secs / 60;
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {:02} min", minutes / 60, minutes % 60)
+    }
+}
+
+fn bucket_label(secs: i64) -> String {
+    match secs {
+        s if s >= 3600 => format!("{}-h", s / 3600),
+        s => format!("{}-min", s / 60),
+    }
+}
+
+/// `dir` cut to `width` columns from the left, keeping its end.
+fn fit_dir(dir: &str, width: usize) -> String {
+    let count = dir.chars().count();
+    if count <= width {
+        return dir.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let tail: String = dir.chars().skip(count - (width - 1)).collect();
+    format!("…{}", tail)
+}
+
+impl Timeline {
+    /// The timeline as text: a summary, the rows, and a legend.
+    pub fn lines(&self, label: &str) -> Vec<String> {
+        let mut lines = vec![format!(
+            "🕒 Timeline, {}: {} commands, {} failed, {} busy ({} rows)",
+            label,
+            self.commands,
+            self.failed,
+            format_ms(self.busy_ms),
+            bucket_label(self.bucket_secs)
+        )];
+        lines.extend(self.rows.iter().map(|row| self.row_line(row)));
+        lines.push(format!(
+            "{}{} ok  {} failed  {} no exit code  {} no duration · click a bar or !history --at <id>",
+            " ".repeat(time_width(self.clock)),
+            Status::Ok.bar(),
+            Status::Failed.bar(),
+            Status::Unknown.bar(),
+            Status::Ok.tick()
+        ));
+        lines
+    }
+
+    fn row_line(&self, row: &Row) -> String {
+        match row {
+            Row::Idle { secs } => format!("{}· {} idle ·", " ".repeat(time_width(self.clock) + 1), idle_label(*secs)),
+            Row::Bucket { start, commands, failed, busy_ms, top_dir, segments } => {
+                let mut bar = String::new();
+                for segment in segments {
+                    let glyph = if segment.instant { segment.status.tick() } else { segment.status.bar() };
+                    bar.extend(std::iter::repeat_n(glyph, segment.cells));
+                }
+                let used = bar.chars().count();
+                bar.extend(std::iter::repeat_n(' ', self.bar_width - used));
+                let mut line = format!("{} {}{}{}", self.row_time(*start), BAR_OPEN, bar, BAR_CLOSE);
+                if *commands > 0 {
+                    let failed = if *failed > 0 { format!("{}✗", failed) } else { String::new() };
+                    line.push_str(&format!(" {:>3} {:>8} {:>4}", commands, format_ms(*busy_ms), failed));
+                    if let Some(dir) = top_dir.as_deref().filter(|_| self.dir_width >= 8) {
+                        line.push_str(&format!("  {}", fit_dir(dir, self.dir_width - 2)));
+                    }
+                }
+                line.trim_end().to_string()
+            }
+        }
+    }
+
+    /// `09:00`, or `9:00 AM` padded to the 12-hour width.
+    fn row_time(&self, start: i64) -> String {
+        match (self.clock, FixedOffset::east_opt(self.utc_offset_secs as i32)) {
+            (Clock::H12, Some(offset)) => format!("{:>8}", time_format::clock(start, &offset, Clock::H12)),
+            _ => clock(start, self.utc_offset_secs),
+        }
+    }
+
+    /// The history id of the command drawn at column `col` (in chars) of
+    /// `line`, if `line` is one of this timeline's bars.
+    pub fn command_at(&self, line: &str, col: usize) -> Option<i64> {
+        let row = self.rows.iter().find(|row| matches!(row, Row::Bucket { .. }) && self.row_line(row) == line.trim_end())?;
+        let Row::Bucket { segments, .. } = row else {
+            return None;
+        };
+        let mut cell = col.checked_sub(time_width(self.clock) + 1)?;
+        for segment in segments {
+            if cell < segment.cells {
+                return Some(segment.id);
+            }
+            cell -= segment.cells;
+        }
+        None
+    }
+}
@@ -0,0 +1,577 @@
+//! `!tldr`: offline command examples from the tldr-pages dataset.
+//!
+//! The English pages are downloaded once (`!tldr --update`, or the first
+//! `!tldr <command>`) into `TLDR_DIR` next to the vault, laid out as the
+//! project ships them: `pages/<platform>/<command>.md`. After that every
+//! lookup is local. A command is looked up on the current platform first,
+//! then `common`, then any other platform.
+//!
+//! Pages are parsed on first use and kept in memory, together with an
+//! index of the flags their examples use, so `!explain` can say what a
+//! flag does when the model is not available.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use positronic_neural::reflex::levenshtein_distance;
+use sha2::{Digest, Sha256};
+
+/// The dataset directory, next to `positronic.db`.
+pub const TLDR_DIR: &str = "positronic-tldr";
+
+pub const ARCHIVE_NAME: &str = "tldr-pages.en.zip";
+pub const ARCHIVE_URL: &str = "https://github.com/tldr-pages/tldr/releases/latest/download/tldr-pages.en.zip";
+pub const CHECKSUMS_URL: &str = "https://github.com/tldr-pages/tldr/releases/latest/download/tldr.sha256sums";
+
+pub const TLDR_USAGE: &str = "Usage: !tldr <command> | !tldr --update";
+
+const ETAG_FILE: &str = "etag";
+/// Download progress is reported every this many bytes.
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// The tldr platform directory for this build.
+pub fn current_platform() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "osx"
+    } else if cfg!(target_os = "android") {
+        "android"
+    } else if cfg!(target_os = "freebsd") {
+        "freebsd"
+    } else if cfg!(target_os = "openbsd") {
+        "openbsd"
+    } else if cfg!(target_os = "netbsd") {
+        "netbsd"
+    } else {
+        "linux"
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Pages
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TldrExample {
+    /// What the example does, mnemonic brackets removed.
+    pub description: String,
+    /// The command, `{{placeholders}}` as written in the page.
+    pub command: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TldrPage {
+    /// The title: `tar`, `git commit`.
+    pub name: String,
+    /// The directory the page came from.
+    pub platform: String,
+    pub summary: Vec<String>,
+    pub more_info: Option<String>,
+    pub examples: Vec<TldrExample>,
+    /// Flags the examples use, each with the first example's description.
+    pub flags: Vec<(String, String)>,
+}
+
+impl TldrPage {
+    /// Parse a page in the tldr markdown format.
+    pub fn parse(platform: &str, text: &str) -> TldrPage {
+        let mut name = String::new();
+        let mut summary = Vec::new();
+        let mut more_info = None;
+        let mut examples = Vec::new();
+        let mut description: Option<String> = None;
+        for line in text.lines().map(str::trim) {
+            if let Some(title) = line.strip_prefix("# ") {
+                name = title.trim().to_string();
+            } else if let Some(quote) = line.strip_prefix('>') {
+                let quote = quote.trim();
+                match quote.strip_prefix("More information:") {
+                    Some(link) => {
+                        let link = link.trim().trim_end_matches('.');
+                        more_info = Some(link.trim_start_matches('<').trim_end_matches('>').to_string());
+                    }
+                    None => summary.push(quote.to_string()),
+                }
+            } else if let Some(item) = line.strip_prefix("- ") {
+                description = Some(plain_description(item));
+            } else if line.len() > 1 && line.starts_with('`') && line.ends_with('`') {
+                examples.push(TldrExample {
+                    description: description.take().unwrap_or_default(),
+                    command: line[1..line.len() - 1].to_string(),
+                });
+            }
+        }
+        let mut flags: Vec<(String, String)> = Vec::new();
+        for example in &examples {
+            for flag in command_flags(&example.command) {
+                if !flags.iter().any(|(f, _)| *f == flag) {
+                    flags.push((flag, example.description.clone()));
+                }
+            }
+        }
+        TldrPage { name, platform: platform.to_string(), summary, more_info, examples, flags }
+    }
+
+    /// What `flag` does, from the first example using it.
+    pub fn flag(&self, flag: &str) -> Option<&str> {
+        let flag = flag.split('=').next().unwrap_or(flag);
+        self.flags.iter().find(|(f, _)| f == flag).map(|(_, d)| d.as_str())
+    }
+
+    /// The page as output lines.
+    pub fn render(&self) -> Vec<String> {
+        let mut summary = self.summary.iter();
+        let mut lines = vec![match summary.next() {
+            Some(first) => format!("📖 {} — {}", self.name, first),
+            None => format!("📖 {}", self.name),
+        }];
+        lines.extend(summary.map(|l| format!("  {}", l)));
+        if let Some(link) = &self.more_info {
+            lines.push(format!("  More information: {}", link));
+        }
+        for example in &self.examples {
+            lines.push(String::new());
+            lines.push(format!("  {}", example.description));
+            lines.push(format!("      {}", render_command(&example.command)));
+        }
+        lines
+    }
+}
+
+/// `[c]reate a g[z]ipped archive:` → `Create a gzipped archive`.
+fn plain_description(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.trim().trim_end_matches(':');
+    while let Some(open) = rest.find('[') {
+        let close = rest[open..].find(']').map(|n| open + n);
+        match close {
+            Some(close) if close > open + 1 && rest[open + 1..close].chars().all(char::is_alphanumeric) => {
+                out.push_str(&rest[..open]);
+                out.push_str(&rest[open + 1..close]);
+                rest = &rest[close + 1..];
+            }
+            _ => {
+                out.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    let mut chars = out.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => out,
+    }
+}
+
+/// `{{path/to/file}}` → `<path/to/file>`; an option placeholder
+/// `{{[-m|--message]}}` shows its long form, `--message`.
+pub fn render_command(command: &str) -> String {
+    let mut out = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}").map(|n| open + 2 + n) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let inner = &rest[open + 2..close];
+        match option_forms(inner) {
+            Some(forms) => out.push_str(forms.last().copied().unwrap_or(inner)),
+            None => {
+                out.push('<');
+                out.push_str(inner);
+                out.push('>');
+            }
+        }
+        rest = &rest[close + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `[-m|--message]` → `["-m", "--message"]`.
+fn option_forms(placeholder: &str) -> Option<Vec<&str>> {
+    let inner = placeholder.strip_prefix('[')?.strip_suffix(']')?;
+    inner.contains('|').then(|| inner.split('|').collect())
+}
+
+/// The flags an example command uses, in order: plain `-x`/`--long`
+/// words and the forms of option placeholders. Other placeholders are
+/// values, not flags.
+fn command_flags(command: &str) -> Vec<String> {
+    let mut words: Vec<&str> = Vec::new();
+    let mut rest = command;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}").map(|n| open + 2 + n) else {
+            break;
+        };
+        words.extend(rest[..open].split_whitespace());
+        if let Some(forms) = option_forms(&rest[open + 2..close]) {
+            words.extend(forms.iter().flat_map(|f| f.split_whitespace()));
+        }
+        rest = &rest[close + 2..];
+    }
+    words.extend(rest.split_whitespace());
+    let mut flags: Vec<String> = Vec::new();
+    for word in words {
+        let flag = word.split('=').next().unwrap_or(word);
+        let is_flag = flag.len() > 1
+            && flag.starts_with('-')
+            && flag.trim_start_matches('-').chars().next().is_some_and(char::is_alphanumeric);
+        if is_flag && !flags.iter().any(|f| f == flag) {
+            flags.push(flag.to_string());
+        }
+    }
+    flags
+}
+
+// ════════════════════════════════════════════════════════════════════
+// The dataset
+// ════════════════════════════════════════════════════════════════════
+
+/// What `!tldr --update` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    UpToDate,
+    Installed { pages: usize },
+}
+
+/// The downloaded pages, parsed and cached on demand.
+#[derive(Debug)]
+pub struct Tldr {
+    root: PathBuf,
+    platform: String,
+    /// Parsed pages by file name; `None` records a miss.
+    cache: Mutex<HashMap<String, Option<Arc<TldrPage>>>>,
+    names: Mutex<Option<Arc<Vec<String>>>>,
+}
+
+impl Tldr {
+    /// The dataset under `root`, for the current platform.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            platform: current_platform().to_string(),
+            cache: Mutex::new(HashMap::new()),
+            names: Mutex::new(None),
+        }
+    }
+
+    /// Prefer another platform's pages.
+    pub fn with_platform(mut self, platform: &str) -> Self {
+        self.platform = platform.to_string();
+        self
+    }
+
+    pub fn platform(&self) -> &str {
+        &self.platform
+    }
+
+    pub fn pages_dir(&self) -> PathBuf {
+        self.root.join("pages")
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.pages_dir().is_dir()
+    }
+
+    /// When the installed pages were unpacked (`!ver`).
+    pub fn installed_at(&self) -> Option<std::time::SystemTime> {
+        std::fs::metadata(self.pages_dir()).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Platform directories in lookup order.
+    fn platforms(&self) -> Vec<String> {
+        let mut others: Vec<String> = std::fs::read_dir(self.pages_dir())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.path().is_dir())
+                    .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                    .filter(|p| *p != self.platform && p != "common")
+                    .collect()
+            })
+            .unwrap_or_default();
+        others.sort();
+        let mut order = vec![self.platform.clone(), "common".to_string()];
+        order.extend(others);
+        order
+    }
+
+    /// The page for `command`: `git commit` tries `git-commit`, then `git`.
+    pub fn page(&self, command: &str) -> Option<Arc<TldrPage>> {
+        page_names(command).iter().find_map(|name| self.load(name))
+    }
+
+    fn load(&self, name: &str) -> Option<Arc<TldrPage>> {
+        if let Some(cached) = self.cache.lock().ok()?.get(name) {
+            return cached.clone();
+        }
+        let dir = self.pages_dir();
+        let page = self.platforms().into_iter().find_map(|platform| {
+            let text = std::fs::read_to_string(dir.join(&platform).join(format!("{}.md", name))).ok()?;
+            Some(Arc::new(TldrPage::parse(&platform, &text)))
+        });
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(name.to_string(), page.clone());
+        }
+        page
+    }
+
+    /// Every page name across platforms, sorted.
+    pub fn names(&self) -> Arc<Vec<String>> {
+        if let Some(names) = self.names.lock().ok().and_then(|n| n.clone()) {
+            return names;
+        }
+        let dir = self.pages_dir();
+        let mut names: Vec<String> = self
+            .platforms()
+            .iter()
+            .filter_map(|platform| std::fs::read_dir(dir.join(platform)).ok())
+            .flat_map(|entries| entries.flatten())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_str()?.strip_suffix(".md")?.to_string();
+                Some(name)
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        let names = Arc::new(names);
+        if let Ok(mut cached) = self.names.lock() {
+            *cached = Some(names.clone());
+        }
+        names
+    }
+
+    /// Page names close to `command`, closest first.
+    pub fn suggest(&self, command: &str, limit: usize) -> Vec<String> {
+        let Some(wanted) = page_names(command).into_iter().next() else {
+            return Vec::new();
+        };
+        let max = (wanted.chars().count() / 2).clamp(1, 3);
+        let mut close: Vec<(usize, String)> = self
+            .names()
+            .iter()
+            .filter_map(|name| {
+                let distance = if name.starts_with(&wanted) { 0 } else { levenshtein_distance(&wanted, name) };
+                (distance <= max).then(|| (distance, name.clone()))
+            })
+            .collect();
+        close.sort();
+        close.into_iter().take(limit).map(|(_, name)| name).collect()
+    }
+
+    /// What each flag in `command_line` does, for the flags its page knows.
+    /// Bundled short flags (`-la`) are looked up one letter at a time.
+    pub fn flag_hints(&self, command_line: &str) -> Vec<(String, String)> {
+        let Some(page) = self.page(command_line) else {
+            return Vec::new();
+        };
+        let mut hints: Vec<(String, String)> = Vec::new();
+        let mut add = |flag: String| match page.flag(&flag) {
+            Some(description) => {
+                if !hints.iter().any(|(f, _)| *f == flag) {
+                    hints.push((flag, description.to_string()));
+                }
+                true
+            }
+            None => false,
+        };
+        for word in command_line.split_whitespace().skip(1) {
+            if word == "--" {
+                break;
+            }
+            if !word.starts_with('-') || word.len() < 2 {
+                continue;
+            }
+            let flag = word.split('=').next().unwrap_or(word);
+            if add(flag.to_string()) || flag.starts_with("--") {
+                continue;
+            }
+            for letter in flag.chars().skip(1) {
+                add(format!("-{}", letter));
+            }
+        }
+        hints
+    }
+
+    /// Forget parsed pages; the dataset changed.
+    pub fn invalidate(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+        if let Ok(mut names) = self.names.lock() {
+            *names = None;
+        }
+    }
+
+    /// Download the pages unless the server says they are unchanged
+    /// (ETag), check the archive against the published SHA-256 and install
+    /// it. `progress` gets a line per step.
+    pub async fn update(&self, mut progress: impl FnMut(String) + Send) -> Result<UpdateOutcome> {
+        let client = reqwest::Client::new();
+        let etag_path = self.root.join(ETAG_FILE);
+        let etag = if self.is_installed() { std::fs::read_to_string(&etag_path).ok() } else { None };
+
+        progress(format!("⬇️  Fetching {}", ARCHIVE_URL));
+        let mut request = client.get(ARCHIVE_URL);
+        if let Some(etag) = &etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag.trim());
+        }
+        let mut response = request.send().await.context("Download failed")?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(UpdateOutcome::UpToDate);
+        }
+        if !response.status().is_success() {
+            bail!("Download failed: HTTP {}", response.status());
+        }
+        let new_etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let total = response.content_length();
+        let mut archive = Vec::with_capacity(total.unwrap_or(0) as usize);
+        let mut next_report = PROGRESS_STEP;
+        while let Some(chunk) = response.chunk().await.context("Download interrupted")? {
+            archive.extend_from_slice(&chunk);
+            if archive.len() as u64 >= next_report {
+                next_report += PROGRESS_STEP;
+                progress(match total {
+                    Some(total) => format!("  {} / {}", format_size(archive.len() as u64), format_size(total)),
+                    None => format!("  {}", format_size(archive.len() as u64)),
+                });
+            }
+        }
+        progress(format!("  Downloaded {}", format_size(archive.len() as u64)));
+
+        let sums = client
+            .get(CHECKSUMS_URL)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .context("Could not fetch the checksums")?
+            .text()
+            .await
+            .context("Could not fetch the checksums")?;
+        let expected = checksum_for(&sums, ARCHIVE_NAME)
+            .with_context(|| format!("No checksum published for {}", ARCHIVE_NAME))?;
+        let pages = self.install(&archive, &expected)?;
+        progress(format!("  Checksum ok (sha256 {}…)", &expected[..12.min(expected.len())]));
+
+        match new_etag {
+            Some(etag) => {
+                if let Err(e) = std::fs::write(&etag_path, etag) {
+                    tracing::warn!("Failed to save the tldr ETag: {}", e);
+                }
+            }
+            None => {
+                let _ = std::fs::remove_file(&etag_path);
+            }
+        }
+        Ok(UpdateOutcome::Installed { pages })
+    }
+
+    /// `update` as the lines `!tldr --update` prints last.
+    pub async fn update_report(&self, progress: impl FnMut(String) + Send) -> String {
+        match self.update(progress).await {
+            Ok(UpdateOutcome::UpToDate) => "✓ tldr pages are up to date".to_string(),
+            Ok(UpdateOutcome::Installed { pages }) => {
+                format!("📖 Installed {} tldr pages in {}", pages, self.pages_dir().display())
+            }
+            Err(e) => format!("❌ tldr update failed: {:#}", e),
+        }
+    }
+
+    /// Check `archive` against `sha256` (hex) and replace the installed
+    /// pages with its `.md` files. Returns how many pages it holds.
+    pub fn install(&self, archive: &[u8], sha256: &str) -> Result<usize> {
+        let actual = sha256_hex(archive);
+        if !actual.eq_ignore_ascii_case(sha256.trim()) {
+            bail!("Checksum mismatch: expected {}, got {}", sha256.trim(), actual);
+        }
+        let staging = self.root.join("pages.new");
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+        let pages = extract_pages(archive, &staging)?;
+        if pages == 0 {
+            let _ = std::fs::remove_dir_all(&staging);
+            bail!("The archive holds no pages");
+        }
+        let target = self.pages_dir();
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::rename(&staging, &target)?;
+        self.invalidate();
+        Ok(pages)
+    }
+}
+
+/// File names a command's page may have, most specific first.
+fn page_names(command: &str) -> Vec<String> {
+    let mut words = command.split_whitespace();
+    let Some(first) = words.next() else {
+        return Vec::new();
+    };
+    let first = Path::new(first).file_name().and_then(|n| n.to_str()).unwrap_or(first);
+    let first = first.strip_suffix(".exe").unwrap_or(first).to_lowercase();
+    let mut names = Vec::new();
+    if let Some(sub) = words.next().filter(|w| !w.starts_with('-')) {
+        names.push(format!("{}-{}", first, sub.to_lowercase()));
+    }
+    names.push(first);
+    names
+}
+
+/// The entry for `file` in a `sha256sum`-style list.
+pub fn checksum_for(sums: &str, file: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        (name == file).then(|| hash.to_lowercase())
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Write the archive's `<platform>/<page>.md` entries under `dir`. Both
+/// the English-only layout and the full one (`pages/<platform>/…`) work.
+fn extract_pages(archive: &[u8], dir: &Path) -> Result<usize> {
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).context("Not a zip archive")?;
+    let mut pages = 0;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let Some(path) = entry.enclosed_name() else {
+            continue;
+        };
+        let path = path.strip_prefix("pages").unwrap_or(&path).to_path_buf();
+        let is_page = path.components().count() == 2 && path.extension().is_some_and(|e| e == "md");
+        if !entry.is_file() || !is_page {
+            continue;
+        }
+        let out = dir.join(&path);
+        if let Some(parent) = out.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::File::create(&out)?;
+        std::io::copy(&mut entry, &mut file)?;
+        pages += 1;
+    }
+    Ok(pages)
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{} KB", bytes.div_ceil(1024))
+    }
+}
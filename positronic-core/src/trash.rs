@@ -0,0 +1,834 @@
+//! `!rm`: deletes that can be undone.
+//!
+//! `!rm <paths…>` moves its targets out of the way instead of unlinking
+//! them. Where the platform has a trash that can also be listed and
+//! restored from (Windows, and the freedesktop trash on Linux and the
+//! BSDs) they go there through the `trash` crate; elsewhere, or with
+//! `rm.trash = positronic`, they go to a folder next to the vault, one
+//! directory per operation under a dated one: `trash/2026-10-16/7/`.
+//!
+//! Either way every operation is recorded in `trash/manifest.json` with
+//! the original paths and sizes, which is what `!rm undo`, `!rm list` and
+//! `!rm purge` work from. Paths are resolved against the shell's working
+//! directory and globs are expanded there; symlinks are moved, never
+//! followed. When the folder is on another device a rename can't work,
+//! so the target is copied and then removed, with progress lines for big
+//! trees. A restore whose original path has been taken since goes beside
+//! it as `name (restored).ext` rather than over it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::native::{Cell, ColumnKind, DataFrame, NativeOutput};
+
+pub const RM_USAGE: &str = "Usage: !rm <paths…> | undo [id] | list | purge [--older-than 30d] [--yes]";
+
+/// The folder, next to the vault, that holds the manifest and the
+/// folder backend's files.
+pub const TRASH_DIR: &str = "trash";
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Vault config key: `system` (the default where supported) or `positronic`.
+pub const TRASH_BACKEND_KEY: &str = "rm.trash";
+
+/// Copies across devices report progress when the target is at least this big.
+const PROGRESS_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Whether the platform trash can be listed and restored from.
+pub const SYSTEM_TRASH: bool = cfg!(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+));
+
+/// Where `!rm` puts things.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashBackend {
+    System,
+    Folder,
+}
+
+impl TrashBackend {
+    /// From the `rm.trash` setting; unset picks the system trash where
+    /// there is a usable one.
+    pub fn from_config(value: Option<&str>) -> TrashBackend {
+        match value.map(|v| v.trim().to_lowercase()).as_deref() {
+            Some("positronic" | "folder") => TrashBackend::Folder,
+            _ if SYSTEM_TRASH => TrashBackend::System,
+            _ => TrashBackend::Folder,
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Command
+// ════════════════════════════════════════════════════════════════════
+
+/// A parsed `!rm` line. A file literally named `undo`, `list` or `purge`
+/// is trashed as `./undo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RmCommand {
+    Trash(Vec<String>),
+    Undo(Option<u64>),
+    List,
+    Purge { older_than: Option<Duration>, yes: bool },
+}
+
+impl RmCommand {
+    /// Parse what follows `!rm`; the error is the message to show.
+    pub fn parse(args: &str) -> Result<RmCommand, String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Err(RM_USAGE.to_string()),
+            ["undo"] => Ok(RmCommand::Undo(None)),
+            ["undo", id] => {
+                let id = id.trim_start_matches('#');
+                id.parse().map(|id| RmCommand::Undo(Some(id))).map_err(|_| format!("❌ Bad trash id '{}'", id))
+            }
+            ["list"] => Ok(RmCommand::List),
+            ["purge", rest @ ..] => {
+                let mut older_than = None;
+                let mut yes = false;
+                let mut rest = rest.iter();
+                while let Some(word) = rest.next() {
+                    match *word {
+                        "--yes" | "-y" => yes = true,
+                        "--older-than" => {
+                            let age = rest.next().ok_or(RM_USAGE)?;
+                            older_than = Some(parse_age(age).ok_or_else(|| format!("❌ Bad age '{}' (e.g. 30d, 12h, 2w)", age))?);
+                        }
+                        _ => return Err(RM_USAGE.to_string()),
+                    }
+                }
+                Ok(RmCommand::Purge { older_than, yes })
+            }
+            ["undo" | "list", ..] => Err(RM_USAGE.to_string()),
+            paths => Ok(RmCommand::Trash(paths.iter().map(|p| p.to_string()).collect())),
+        }
+    }
+}
+
+/// `30d`, `12h`, `2w`, `90m` or `45s`.
+pub fn parse_age(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let unit = text.chars().last()?;
+    let count: u64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(count.checked_mul(seconds)?))
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Manifest
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The id the next operation gets.
+    pub next_id: u64,
+    /// Oldest first.
+    pub operations: Vec<TrashOp>,
+}
+
+/// One `!rm`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashOp {
+    pub id: u64,
+    /// Unix seconds.
+    pub deleted_at: i64,
+    pub items: Vec<TrashItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub original: PathBuf,
+    /// Where the folder backend put it, relative to the trash folder;
+    /// `None` for the system trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored: Option<PathBuf>,
+    pub bytes: u64,
+    pub is_dir: bool,
+}
+
+impl TrashOp {
+    pub fn bytes(&self) -> u64 {
+        self.items.iter().map(|item| item.bytes).sum()
+    }
+
+    /// Deleted at least `age` before `now`.
+    pub fn is_older_than(&self, age: Duration, now: i64) -> bool {
+        now.saturating_sub(self.deleted_at) >= age.as_secs() as i64
+    }
+}
+
+impl Manifest {
+    /// A missing manifest is an empty one.
+    pub fn load(path: &Path) -> Result<Manifest> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("{} is damaged", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e).with_context(|| format!("could not read {}", path.display())),
+        }
+    }
+
+    /// Write through a temporary file so a crash leaves the old manifest.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// The operation `id`, or the newest.
+    pub fn find(&self, id: Option<u64>) -> Option<&TrashOp> {
+        match id {
+            Some(id) => self.operations.iter().find(|op| op.id == id),
+            None => self.operations.last(),
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Paths
+// ════════════════════════════════════════════════════════════════════
+
+/// Resolve `args` against `cwd`, expanding globs there, in the order
+/// given without duplicates. Refuses what isn't there, the working
+/// directory and its parents, and the trash folder or anything in it.
+pub fn expand_targets(args: &[String], cwd: &Path, trash_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for arg in args {
+        let matches = if arg.contains(['*', '?', '[']) {
+            let pattern = if Path::new(arg).is_absolute() {
+                arg.clone()
+            } else {
+                Path::new(&glob::Pattern::escape(&cwd.to_string_lossy())).join(arg).to_string_lossy().into_owned()
+            };
+            let found: Vec<PathBuf> = glob::glob(&pattern)
+                .map_err(|e| format!("❌ Bad pattern '{}': {}", arg, e.msg))?
+                .filter_map(|entry| entry.ok())
+                .collect();
+            if found.is_empty() {
+                return Err(format!("❌ Nothing matches '{}'", arg));
+            }
+            found
+        } else {
+            let path = cwd.join(arg);
+            if fs::symlink_metadata(&path).is_err() {
+                return Err(format!("❌ No such file or directory: {}", arg));
+            }
+            vec![path]
+        };
+        for path in matches {
+            let path = normalize(&path);
+            if path.file_name().is_none() || normalize(cwd).starts_with(&path) {
+                return Err(format!("❌ Refusing to trash '{}': it holds the working directory", arg));
+            }
+            if path.starts_with(trash_dir) || trash_dir.starts_with(&path) {
+                return Err(format!("❌ Refusing to trash '{}': it holds the trash", arg));
+            }
+            if !targets.contains(&path) {
+                targets.push(path);
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Drop `.` components and apply `..` lexically; symlinks stay as named.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Where to restore `original`: itself if free, else `name (restored).ext`,
+/// `name (restored 2).ext`, … beside it.
+pub fn restore_path(original: &Path, exists: impl Fn(&Path) -> bool) -> PathBuf {
+    if !exists(original) {
+        return original.to_path_buf();
+    }
+    let stem = original.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = original.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| {
+            let suffix = if n == 1 { " (restored)".to_string() } else { format!(" (restored {})", n) };
+            original.with_file_name(format!("{}{}{}", stem, suffix, ext))
+        })
+        .find(|candidate| !exists(candidate))
+        .expect("an unused name")
+}
+
+/// Bytes under `path`, not following symlinks.
+pub fn tree_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| tree_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Rename `from` to `to`, or copy then remove when they are on different
+/// devices. `progress` gets a line at each tenth of a big copy.
+pub fn move_path(from: &Path, to: &Path, progress: &mut dyn FnMut(String)) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+        Err(e) => return Err(e).with_context(|| format!("could not move {}", from.display())),
+    }
+    let total = tree_size(from);
+    let mut copy = Copy { total, copied: 0, reported: 0, name: file_name(from), progress };
+    copy.tree(from, to).with_context(|| format!("could not copy {}", from.display()))?;
+    let removed = if fs::symlink_metadata(from)?.is_dir() { fs::remove_dir_all(from) } else { fs::remove_file(from) };
+    removed.with_context(|| format!("copied {} but could not remove it", from.display()))
+}
+
+struct Copy<'a> {
+    total: u64,
+    copied: u64,
+    /// Tenths reported so far.
+    reported: u64,
+    name: String,
+    progress: &'a mut dyn FnMut(String),
+}
+
+impl Copy<'_> {
+    fn tree(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        let meta = fs::symlink_metadata(from)?;
+        if meta.file_type().is_symlink() {
+            return copy_link(from, to);
+        }
+        if meta.is_dir() {
+            fs::create_dir(to)?;
+            for entry in fs::read_dir(from)? {
+                let entry = entry?;
+                self.tree(&entry.path(), &to.join(entry.file_name()))?;
+            }
+            return Ok(());
+        }
+        self.copied += fs::copy(from, to)?;
+        let tenths = (self.copied * 10).checked_div(self.total).unwrap_or(10);
+        if self.total >= PROGRESS_BYTES && tenths > self.reported && tenths < 10 {
+            self.reported = tenths;
+            (self.progress)(format!(
+                "  📦 {}: {} of {} copied to the trash",
+                self.name,
+                format_bytes(self.copied),
+                format_bytes(self.total)
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn copy_link(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(windows)]
+fn copy_link(from: &Path, to: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    if fs::metadata(from).is_ok_and(|m| m.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Trash
+// ════════════════════════════════════════════════════════════════════
+
+/// What `Trash::undo` did with one item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Restored {
+    pub original: PathBuf,
+    /// Where it went; differs from `original` when that was taken.
+    pub to: PathBuf,
+}
+
+/// What `Trash::undo` did with an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Undone {
+    pub id: u64,
+    pub restored: Vec<Restored>,
+    /// Why the rest were not restored; they stay in the trash.
+    pub failed: Vec<String>,
+}
+
+/// The trash folder and its manifest. Clones share a lock, so two `!rm`s
+/// at once don't lose each other's manifest entries.
+#[derive(Debug, Clone)]
+pub struct Trash {
+    dir: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl Trash {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), lock: Arc::new(Mutex::new(())) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join(MANIFEST_FILE)
+    }
+
+    pub fn manifest(&self) -> Result<Manifest> {
+        Manifest::load(&self.manifest_path())
+    }
+
+    /// Move `targets` (from `expand_targets`) into the trash as one
+    /// operation. It stops at the first target that can't be moved: what
+    /// moved before it is recorded, and the second value says why.
+    pub fn trash(
+        &self,
+        targets: &[PathBuf],
+        backend: TrashBackend,
+        now: i64,
+        progress: &mut dyn FnMut(String),
+    ) -> Result<(TrashOp, Option<String>)> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir).with_context(|| format!("could not create {}", self.dir.display()))?;
+        let mut manifest = self.manifest()?;
+        let id = manifest.next_id.max(1);
+        let mut op = TrashOp { id, deleted_at: now, items: Vec::new() };
+
+        let date = chrono::DateTime::from_timestamp(now, 0).unwrap_or_default().format("%Y-%m-%d").to_string();
+        let folder = Path::new(&date).join(id.to_string());
+        let mut failure = None;
+        for (n, target) in targets.iter().enumerate() {
+            let stored = match backend {
+                TrashBackend::System => None,
+                TrashBackend::Folder => Some(folder.join(format!("{}-{}", n, file_name(target)))),
+            };
+            match self.move_in(target, stored.as_deref(), progress) {
+                Ok(item) => op.items.push(TrashItem { stored, ..item }),
+                Err(e) => {
+                    failure = Some(e.context(format!("{} was not trashed", target.display())));
+                    break;
+                }
+            }
+        }
+
+        if op.items.is_empty() {
+            return Err(failure.unwrap_or_else(|| anyhow::anyhow!("nothing to trash")));
+        }
+        manifest.next_id = id + 1;
+        manifest.operations.push(op.clone());
+        manifest.save(&self.manifest_path())?;
+        Ok((op, failure.map(|e| format!("{:#}", e))))
+    }
+
+    /// Move one target to `stored` in the folder, or to the system trash.
+    fn move_in(&self, target: &Path, stored: Option<&Path>, progress: &mut dyn FnMut(String)) -> Result<TrashItem> {
+        let meta = fs::symlink_metadata(target)?;
+        let item = TrashItem { original: target.to_path_buf(), stored: None, bytes: tree_size(target), is_dir: meta.is_dir() };
+        match stored {
+            Some(stored) => {
+                let to = self.dir.join(stored);
+                fs::create_dir_all(to.parent().unwrap_or(&self.dir))?;
+                move_path(target, &to, progress)?;
+            }
+            None => system::delete(target)?,
+        }
+        Ok(item)
+    }
+
+    /// Put back operation `id`, or the newest. Items whose original path
+    /// is taken go beside it (see `restore_path`).
+    pub fn undo(&self, id: Option<u64>) -> Result<Undone> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut manifest = self.manifest()?;
+        let Some(op) = manifest.find(id).cloned() else {
+            match id {
+                Some(id) => bail!("no trash operation #{}", id),
+                None => bail!("the trash is empty"),
+            }
+        };
+
+        let mut restored = Vec::new();
+        let mut failed = Vec::new();
+        for item in &op.items {
+            let to = restore_path(&item.original, |p| fs::symlink_metadata(p).is_ok());
+            let result = match &item.stored {
+                Some(stored) => fs::create_dir_all(to.parent().unwrap_or(Path::new(".")))
+                    .map_err(anyhow::Error::from)
+                    .and_then(|()| move_path(&self.dir.join(stored), &to, &mut |_| {})),
+                None if to == item.original => system::restore(&item.original, op.deleted_at),
+                None => Err(anyhow::anyhow!("{} is taken; left in the system trash", item.original.display())),
+            };
+            match result {
+                Ok(()) => restored.push(Restored { original: item.original.clone(), to }),
+                Err(e) => failed.push(format!("{:#}", e)),
+            }
+        }
+
+        // Keep what couldn't be restored, so a second undo can retry it.
+        let remaining: Vec<TrashItem> =
+            op.items.iter().filter(|item| !restored.iter().any(|r| r.original == item.original)).cloned().collect();
+        match manifest.operations.iter_mut().position(|o| o.id == op.id) {
+            Some(i) if remaining.is_empty() => {
+                manifest.operations.remove(i);
+                self.remove_folder(&op);
+            }
+            Some(i) => manifest.operations[i].items = remaining,
+            None => {}
+        }
+        manifest.save(&self.manifest_path())?;
+        Ok(Undone { id: op.id, restored, failed })
+    }
+
+    /// The operations `!rm purge` would delete.
+    pub fn purge_plan(&self, older_than: Option<Duration>, now: i64) -> Result<Vec<TrashOp>> {
+        let manifest = self.manifest()?;
+        Ok(manifest
+            .operations
+            .into_iter()
+            .filter(|op| older_than.is_none_or(|age| op.is_older_than(age, now)))
+            .collect())
+    }
+
+    /// Delete those operations for good; returns them.
+    pub fn purge(&self, older_than: Option<Duration>, now: i64) -> Result<Vec<TrashOp>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut manifest = self.manifest()?;
+        let (purged, kept): (Vec<TrashOp>, Vec<TrashOp>) = manifest
+            .operations
+            .drain(..)
+            .partition(|op| older_than.is_none_or(|age| op.is_older_than(age, now)));
+        manifest.operations = kept;
+        for op in &purged {
+            for item in &op.items {
+                let result = match &item.stored {
+                    Some(stored) => remove_tree(&self.dir.join(stored)),
+                    None => system::purge(&item.original, op.deleted_at),
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Purging {} failed: {:#}", item.original.display(), e);
+                }
+            }
+            self.remove_folder(op);
+        }
+        manifest.save(&self.manifest_path())?;
+        Ok(purged)
+    }
+
+    /// Remove an operation's folder and its date folder once empty.
+    fn remove_folder(&self, op: &TrashOp) {
+        let Some(folder) = op.items.iter().find_map(|item| item.stored.as_ref()?.parent()) else {
+            return;
+        };
+        let folder = self.dir.join(folder);
+        let _ = fs::remove_dir(&folder);
+        if let Some(date) = folder.parent() {
+            let _ = fs::remove_dir(date);
+        }
+    }
+}
+
+fn remove_tree(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path)?,
+        Ok(_) => fs::remove_file(path)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(())
+}
+
+/// The platform trash, through the `trash` crate. Items are found again
+/// by original path and a deletion time no earlier than the operation's.
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+mod system {
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use trash::os_limited;
+
+    /// Allowance for the trash's own clock rounding.
+    const SLACK_SECS: i64 = 2;
+
+    pub fn delete(path: &Path) -> Result<()> {
+        trash::delete(path).with_context(|| format!("could not move {} to the system trash", path.display()))
+    }
+
+    fn find(original: &Path, deleted_at: i64) -> Result<trash::TrashItem> {
+        os_limited::list()?
+            .into_iter()
+            .filter(|item| item.original_path() == original && item.time_deleted >= deleted_at - SLACK_SECS)
+            .max_by_key(|item| item.time_deleted)
+            .with_context(|| format!("{} is no longer in the system trash", original.display()))
+    }
+
+    pub fn restore(original: &Path, deleted_at: i64) -> Result<()> {
+        os_limited::restore_all([find(original, deleted_at)?])
+            .with_context(|| format!("could not restore {}", original.display()))
+    }
+
+    pub fn purge(original: &Path, deleted_at: i64) -> Result<()> {
+        Ok(os_limited::purge_all([find(original, deleted_at)?])?)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+mod system {
+    use std::path::Path;
+
+    use anyhow::{bail, Result};
+
+    pub fn delete(_: &Path) -> Result<()> {
+        bail!("this platform's trash can't be restored from; set rm.trash to positronic")
+    }
+
+    pub fn restore(original: &Path, _: i64) -> Result<()> {
+        bail!("{} is in a system trash Positronic can't restore from", original.display())
+    }
+
+    pub fn purge(_: &Path, _: i64) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Output
+// ════════════════════════════════════════════════════════════════════
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// `45s`, `12m`, `3h` or `9d`.
+pub fn format_age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86_400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86_400),
+    }
+}
+
+/// Run a parsed `!rm` against `trash`, with paths relative to `cwd`.
+pub fn command_output(trash: &Trash, command: RmCommand, backend: TrashBackend, cwd: &Path, now: i64) -> NativeOutput {
+    match command {
+        RmCommand::Trash(args) => NativeOutput::Lines(trash_lines(trash, &args, backend, cwd, now)),
+        RmCommand::Undo(id) => NativeOutput::Lines(undo_lines(trash, id, cwd)),
+        RmCommand::List => list_output(trash, cwd, now),
+        RmCommand::Purge { older_than, yes } => NativeOutput::Lines(purge_lines(trash, older_than, yes, cwd, now)),
+    }
+}
+
+/// `path` relative to `cwd` when it is under it, a trailing `/` for directories.
+fn shown(path: &Path, is_dir: bool, cwd: &Path) -> String {
+    let mut text = path.strip_prefix(cwd).unwrap_or(path).display().to_string();
+    if is_dir {
+        text.push('/');
+    }
+    text
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        "item"
+    } else {
+        "items"
+    }
+}
+
+fn trash_lines(trash: &Trash, args: &[String], backend: TrashBackend, cwd: &Path, now: i64) -> Vec<String> {
+    let targets = match expand_targets(args, cwd, trash.dir()) {
+        Ok(targets) => targets,
+        Err(message) => return vec![message],
+    };
+    let mut progress = Vec::new();
+    let result = trash.trash(&targets, backend, now, &mut |line| progress.push(line));
+    let (op, failure) = match result {
+        Ok(done) => done,
+        Err(e) => {
+            progress.push(format!("❌ {:#}", e));
+            return progress;
+        }
+    };
+    let place = match backend {
+        TrashBackend::System => "the system trash",
+        TrashBackend::Folder => "the trash",
+    };
+    let mut lines = vec![format!(
+        "🗑 Moved {} {} ({}) to {} as #{}",
+        op.items.len(),
+        plural(op.items.len()),
+        format_bytes(op.bytes()),
+        place,
+        op.id
+    )];
+    lines.extend(progress);
+    for item in &op.items {
+        lines.push(format!("  {} ({})", shown(&item.original, item.is_dir, cwd), format_bytes(item.bytes)));
+    }
+    if let Some(failure) = failure {
+        lines.push(format!("⚠️ {}", failure));
+    }
+    lines.push("  Undo with !rm undo".to_string());
+    lines
+}
+
+fn undo_lines(trash: &Trash, id: Option<u64>, cwd: &Path) -> Vec<String> {
+    let undone = match trash.undo(id) {
+        Ok(undone) => undone,
+        Err(e) => return vec![format!("❌ {:#}", e)],
+    };
+    let mut lines = if undone.restored.is_empty() {
+        vec![format!("❌ Nothing restored from #{}", undone.id)]
+    } else {
+        vec![format!("♻️ Restored #{}: {} {}", undone.id, undone.restored.len(), plural(undone.restored.len()))]
+    };
+    for restored in &undone.restored {
+        if restored.to == restored.original {
+            lines.push(format!("  {}", shown(&restored.to, false, cwd)));
+        } else {
+            lines.push(format!(
+                "  {} → {} (the original path is taken)",
+                shown(&restored.original, false, cwd),
+                shown(&restored.to, false, cwd)
+            ));
+        }
+    }
+    lines.extend(undone.failed.iter().map(|failure| format!("  ⚠️ {}", failure)));
+    lines
+}
+
+/// `!rm list`: one row per item, newest operation first.
+fn list_output(trash: &Trash, cwd: &Path, now: i64) -> NativeOutput {
+    let manifest = match trash.manifest() {
+        Ok(manifest) => manifest,
+        Err(e) => return NativeOutput::Lines(vec![format!("❌ {:#}", e)]),
+    };
+    if manifest.operations.is_empty() {
+        return NativeOutput::Lines(vec!["🗑 The trash is empty".to_string()]);
+    }
+    let items: usize = manifest.operations.iter().map(|op| op.items.len()).sum();
+    let bytes: u64 = manifest.operations.iter().map(TrashOp::bytes).sum();
+    let mut frame = DataFrame::new(&[
+        ("id", ColumnKind::Integer),
+        ("path", ColumnKind::Text),
+        ("bytes", ColumnKind::Integer),
+        ("deleted_at", ColumnKind::Integer),
+        ("trash", ColumnKind::Text),
+    ]);
+    let mut lines = vec![format!("🗑 Trash: {} {} ({})", items, plural(items), format_bytes(bytes)), String::new()];
+    for op in manifest.operations.iter().rev() {
+        for item in &op.items {
+            lines.push(format!(
+                "  #{:<4} {:>5} ago  {:>9}  {}",
+                op.id,
+                format_age(now - op.deleted_at),
+                format_bytes(item.bytes),
+                shown(&item.original, item.is_dir, cwd)
+            ));
+            frame.push_row(vec![
+                Cell::Integer(op.id as i64),
+                Cell::text(item.original.display().to_string()),
+                Cell::Integer(item.bytes as i64),
+                Cell::Integer(op.deleted_at),
+                Cell::text(if item.stored.is_some() { "positronic" } else { "system" }),
+            ]);
+        }
+    }
+    lines.push(String::new());
+    lines.push("  !rm undo [id] restores · !rm purge [--older-than 30d] deletes for good".to_string());
+    NativeOutput::Table(frame.with_text(lines))
+}
+
+/// Items listed before `!rm purge` asks for `--yes`.
+const PURGE_PREVIEW: usize = 10;
+
+fn purge_lines(trash: &Trash, older_than: Option<Duration>, yes: bool, cwd: &Path, now: i64) -> Vec<String> {
+    let age = older_than.map(|age| format_age(age.as_secs() as i64));
+    let plan = match trash.purge_plan(older_than, now) {
+        Ok(plan) => plan,
+        Err(e) => return vec![format!("❌ {:#}", e)],
+    };
+    let items: Vec<&TrashItem> = plan.iter().flat_map(|op| &op.items).collect();
+    let bytes: u64 = plan.iter().map(TrashOp::bytes).sum();
+    if items.is_empty() {
+        return vec![match &age {
+            Some(age) => format!("🗑 Nothing in the trash is older than {}", age),
+            None => "🗑 The trash is empty".to_string(),
+        }];
+    }
+    if !yes {
+        let mut lines = vec![format!(
+            "⚠️ This permanently deletes {} {} ({}){}:",
+            items.len(),
+            plural(items.len()),
+            format_bytes(bytes),
+            age.as_ref().map(|age| format!(" trashed over {} ago", age)).unwrap_or_default()
+        )];
+        for item in items.iter().take(PURGE_PREVIEW) {
+            lines.push(format!("  {} ({})", shown(&item.original, item.is_dir, cwd), format_bytes(item.bytes)));
+        }
+        if items.len() > PURGE_PREVIEW {
+            lines.push(format!("  … and {} more", items.len() - PURGE_PREVIEW));
+        }
+        let flag = age.map(|age| format!(" --older-than {}", age)).unwrap_or_default();
+        lines.push(format!("Run `!rm purge{} --yes` to delete them", flag));
+        return lines;
+    }
+    match trash.purge(older_than, now) {
+        Ok(purged) => {
+            let count: usize = purged.iter().map(|op| op.items.len()).sum();
+            let bytes: u64 = purged.iter().map(TrashOp::bytes).sum();
+            vec![format!("🔥 Permanently deleted {} {} ({})", count, plural(count), format_bytes(bytes))]
+        }
+        Err(e) => vec![format!("❌ {:#}", e)],
+    }
+}
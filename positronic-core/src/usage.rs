@@ -0,0 +1,270 @@
+//! Resource usage of commands Positronic runs itself.
+//!
+//! `!follow`, `!time` and `!diff --watch` start their command as a child
+//! of ours, so when it exits we can ask the OS what it cost: CPU time in
+//! user and kernel mode and the peak resident set. A `Collector` reaps the
+//! child and reads those figures — `wait4` on Unix, `GetProcessTimes` and
+//! the process memory counters on Windows — and the stub used elsewhere
+//! just waits and reports nothing.
+//!
+//! On Unix the figures include the descendants the shell waited for, so
+//! `sh -c 'make'` counts the whole build. On Windows they cover the shell
+//! process only.
+//!
+//! Commands typed at the PTY belong to the shell, not to us; `!time`
+//! routes one through `capture` instead.
+
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::trash::format_bytes;
+use crate::vault::timing::format_ms;
+
+pub const TIME_USAGE: &str = "Usage: !time <command>";
+
+/// CPU time and peak memory of one finished command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub user_ms: u64,
+    pub sys_ms: u64,
+    /// Peak resident set size.
+    pub max_rss_bytes: u64,
+}
+
+impl ResourceUsage {
+    pub fn cpu_ms(&self) -> u64 {
+        self.user_ms + self.sys_ms
+    }
+
+    /// `cpu 38.0s · peak 2.1 GB`.
+    pub fn badge(&self) -> String {
+        format!("cpu {} · peak {}", format_ms(self.cpu_ms() as i64), format_bytes(self.max_rss_bytes))
+    }
+}
+
+/// How a child ended, and what it used if the platform says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exit {
+    /// `None` if a signal ended it.
+    pub code: Option<i32>,
+    pub usage: Option<ResourceUsage>,
+}
+
+/// Waits for a child and collects its resource usage.
+pub trait Collector: Send + Sync {
+    /// Block until `child` exits and reap it.
+    fn wait(&self, child: Child) -> io::Result<Exit>;
+}
+
+/// Platforms without a collector: wait, report no usage.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unsupported;
+
+impl Collector for Unsupported {
+    fn wait(&self, mut child: Child) -> io::Result<Exit> {
+        Ok(Exit { code: child.wait()?.code(), usage: None })
+    }
+}
+
+/// `wait4(2)`: exit status and `rusage` in one call.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rusage;
+
+#[cfg(unix)]
+impl Collector for Rusage {
+    fn wait(&self, child: Child) -> io::Result<Exit> {
+        let pid = child.id() as libc::pid_t;
+        let mut status = 0;
+        // SAFETY: rusage is plain integers; all-zero is a valid value.
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        loop {
+            // SAFETY: `pid` is our unreaped child and both pointers are
+            // valid for the call.
+            if unsafe { libc::wait4(pid, &mut status, 0, &mut usage) } >= 0 {
+                break;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+        let millis = |t: libc::timeval| t.tv_sec as u64 * 1000 + t.tv_usec as u64 / 1000;
+        // Linux and the BSDs report kilobytes, Apple platforms bytes.
+        let rss_unit = if cfg!(target_vendor = "apple") { 1 } else { 1024 };
+        Ok(Exit {
+            code: libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)),
+            usage: Some(ResourceUsage {
+                user_ms: millis(usage.ru_utime),
+                sys_ms: millis(usage.ru_stime),
+                max_rss_bytes: usage.ru_maxrss.max(0) as u64 * rss_unit,
+            }),
+        })
+    }
+}
+
+/// `GetProcessTimes` and the peak working set, read once the child has
+/// exited and before its handle is closed.
+#[cfg(windows)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProcessCounters;
+
+#[cfg(windows)]
+impl Collector for ProcessCounters {
+    fn wait(&self, mut child: Child) -> io::Result<Exit> {
+        use std::os::windows::io::AsRawHandle;
+        use windows::Win32::Foundation::{FILETIME, HANDLE};
+        use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+        use windows::Win32::System::Threading::GetProcessTimes;
+
+        let code = child.wait()?.code();
+        let handle = HANDLE(child.as_raw_handle());
+        let (mut created, mut exited, mut kernel, mut user) =
+            (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+        let mut counters = PROCESS_MEMORY_COUNTERS {
+            cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+            ..Default::default()
+        };
+        // SAFETY: the handle stays open while `child` lives; the out
+        // pointers are valid and `cb` is the struct's size.
+        let read = unsafe {
+            GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user)
+                .and_then(|_| GetProcessMemoryInfo(handle, &mut counters, counters.cb))
+        };
+        // FILETIME counts 100ns intervals.
+        let millis = |t: FILETIME| ((u64::from(t.dwHighDateTime) << 32) | u64::from(t.dwLowDateTime)) / 10_000;
+        Ok(Exit {
+            code,
+            usage: read.ok().map(|_| ResourceUsage {
+                user_ms: millis(user),
+                sys_ms: millis(kernel),
+                max_rss_bytes: counters.PeakWorkingSetSize as u64,
+            }),
+        })
+    }
+}
+
+/// The collector for this platform.
+pub fn collector() -> &'static dyn Collector {
+    #[cfg(unix)]
+    return &Rusage;
+    #[cfg(windows)]
+    return &ProcessCounters;
+    #[cfg(not(any(unix, windows)))]
+    return &Unsupported;
+}
+
+/// `command` run through the system shell, with `env` set on top of ours.
+pub fn shell(command: &str, env: &[(String, String)]) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.args(["/C", command]);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.args(["-c", command]);
+        c
+    };
+    shell.envs(env.iter().map(|(name, value)| (name, value)));
+    shell.stdin(Stdio::null());
+    shell
+}
+
+/// End a child we have not reaped yet, and everything it started.
+fn kill(pid: u32) {
+    #[cfg(unix)]
+    {
+        use nix::sys::signal::{killpg, Signal};
+        use nix::unistd::Pid;
+        let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+/// A command run to completion by `capture`.
+#[derive(Debug, Clone)]
+pub struct Captured {
+    /// Stdout, then stderr.
+    pub output: String,
+    pub exit: Exit,
+    pub wall: Duration,
+}
+
+/// Run `command` through the system shell in `cwd`, with `env` set, and
+/// collect its output, exit and resource usage. With a `limit`, a command still running after
+/// it is killed and an error returned. Must be called inside a Tokio
+/// runtime.
+pub async fn capture(command: &str, cwd: &str, env: &[(String, String)], limit: Option<Duration>) -> Result<Captured> {
+    let started = Instant::now();
+    let mut shell = shell(command, env);
+    // Its own group, so a timeout takes down the whole pipeline and no
+    // grandchild is left holding the output pipes open.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    let mut child = shell
+        .current_dir(cwd)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("could not start '{}'", command))?;
+    let pid = child.id();
+    let stdout = child.stdout.take().map(drain);
+    let stderr = child.stderr.take().map(drain);
+
+    let finished = tokio::task::spawn_blocking(move || -> io::Result<(Exit, String)> {
+        let exit = collector().wait(child)?;
+        let mut output = String::new();
+        for reader in [stdout, stderr].into_iter().flatten() {
+            output.push_str(&String::from_utf8_lossy(&reader.join().unwrap_or_default()));
+        }
+        Ok((exit, output))
+    });
+    let (exit, output) = match limit {
+        Some(limit) => match tokio::time::timeout(limit, finished).await {
+            Ok(joined) => joined,
+            Err(_) => {
+                kill(pid);
+                anyhow::bail!("'{}' did not finish within {}s", command, limit.as_secs());
+            }
+        },
+        None => finished.await,
+    }
+    .context("the wait was cancelled")??;
+    let wall = started.elapsed();
+    Ok(Captured { output, exit, wall })
+}
+
+/// Read `pipe` to the end on its own thread, so a full stderr pipe can't
+/// stall a child we are waiting on.
+fn drain(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+/// The `!time` summary line: `⏱ exit 0 · 4m 12s · cpu 38.0s · peak 2.1 GB`.
+pub fn summary(captured: &Captured) -> String {
+    let exit = match captured.exit.code {
+        Some(code) => format!("exit {}", code),
+        None => "killed by a signal".to_string(),
+    };
+    let mut line = format!("⏱ {} · {}", exit, format_ms(captured.wall.as_millis() as i64));
+    match captured.exit.usage {
+        Some(usage) => line.push_str(&format!(" · {}", usage.badge())),
+        None => line.push_str(" · resource usage not available on this platform"),
+    }
+    line
+}
@@ -0,0 +1,413 @@
+// positronic-core/src/vault/analytics.rs
+//
+// `!stats export`: usage analytics aggregated in SQL, one query per
+// section. Grouped rows are read one at a time, so the report costs memory
+// in proportion to distinct commands and days, not to history size.
+// Instants are epochs in memory and ISO 8601 in the JSON and CSV.
+
+use super::crypto::RowCipher;
+use super::{reveal_text, timing};
+use crate::heatmap::{Slot, BUCKET_SECS};
+use crate::time_format::{self, iso8601};
+use chrono::{DateTime, NaiveDate};
+use rusqlite::{Connection, Result, params};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Days covered by the per-day counts and the session list, today included.
+pub const REPORT_DAYS: i64 = 90;
+
+/// Commands listed in `top_commands`.
+pub const TOP_LIMIT: usize = 50;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalyticsReport {
+    #[serde(serialize_with = "time_format::serialize_iso8601")]
+    pub generated_at: i64,
+    /// Offset of local time from UTC the days and hours are bucketed in.
+    pub utc_offset_secs: i64,
+    /// Oldest first, one entry per day including days without commands.
+    pub daily: Vec<DayCount>,
+    pub top_commands: Vec<CommandUsage>,
+    /// Commands with a recorded exit code, by first token; highest rate first.
+    pub failure_rates: Vec<FailureRate>,
+    /// Commands per local hour of day, all history; index is the hour.
+    pub hours: Vec<i64>,
+    /// Expansions per alias, defined aliases never used included.
+    pub aliases: Vec<AliasUsage>,
+    /// Sessions started within `REPORT_DAYS`, oldest first.
+    pub sessions: Vec<SessionLength>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayCount {
+    /// `YYYY-MM-DD`, local time.
+    pub date: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandUsage {
+    pub command: String,
+    pub count: i64,
+    /// Over the timed runs only; `None` if none were timed.
+    pub mean_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailureRate {
+    pub program: String,
+    pub runs: i64,
+    pub failures: i64,
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AliasUsage {
+    pub alias: String,
+    pub count: i64,
+    #[serde(serialize_with = "time_format::serialize_iso8601_opt")]
+    pub last_used: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionLength {
+    pub id: String,
+    #[serde(serialize_with = "time_format::serialize_iso8601")]
+    pub start_time: i64,
+    /// Up to `end_time`, or the last heartbeat of a session still open.
+    pub seconds: i64,
+    pub commands: i64,
+    pub open: bool,
+}
+
+/// Aggregate the report as of `now`, bucketing days and hours at
+/// `utc_offset_secs` from UTC.
+pub(super) fn build(conn: &Connection, cipher: Option<&RowCipher>, now: i64, utc_offset_secs: i64) -> Result<AnalyticsReport> {
+    let today = (now + utc_offset_secs).div_euclid(SECS_PER_DAY);
+    let first_day = today - (REPORT_DAYS - 1);
+    let since = first_day * SECS_PER_DAY - utc_offset_secs;
+    Ok(AnalyticsReport {
+        generated_at: now,
+        utc_offset_secs,
+        daily: daily_counts(conn, first_day, today, utc_offset_secs)?,
+        top_commands: top_commands(conn, cipher)?,
+        failure_rates: failure_rates(conn, cipher)?,
+        hours: hour_histogram(conn, utc_offset_secs)?,
+        aliases: alias_usage(conn)?,
+        sessions: session_lengths(conn, since, now)?,
+    })
+}
+
+fn daily_counts(conn: &Connection, first_day: i64, today: i64, offset: i64) -> Result<Vec<DayCount>> {
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+    let mut stmt = conn.prepare_cached(
+        "SELECT (timestamp + ?1) / 86400 AS day, COUNT(*) FROM history
+         WHERE timestamp + ?1 >= ?2 * 86400 AND timestamp + ?1 < (?3 + 1) * 86400
+         GROUP BY day",
+    )?;
+    let mut rows = stmt.query(params![offset, first_day, today])?;
+    while let Some(row) = rows.next()? {
+        counts.insert(row.get(0)?, row.get(1)?);
+    }
+    Ok((first_day..=today)
+        .map(|day| DayCount { date: day_label(day), count: counts.get(&day).copied().unwrap_or(0) })
+        .collect())
+}
+
+fn day_label(day: i64) -> String {
+    DateTime::from_timestamp(day * SECS_PER_DAY, 0)
+        .map(|t| t.date_naive())
+        .unwrap_or(NaiveDate::MIN)
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+/// Sealed commands group like plain ones: sealing is deterministic.
+fn top_commands(conn: &Connection, cipher: Option<&RowCipher>) -> Result<Vec<CommandUsage>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT command, COUNT(*) AS cnt, AVG(duration_ms) FROM history
+         GROUP BY command
+         ORDER BY cnt DESC, command
+         LIMIT ?1",
+    )?;
+    let mut rows = stmt.query(params![TOP_LIMIT as i64])?;
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        results.push(CommandUsage {
+            command: reveal_text(cipher, row.get(0)?)?,
+            count: row.get(1)?,
+            mean_ms: row.get(2)?,
+        });
+    }
+    if cipher.is_some() {
+        // The tie order above was of sealed text.
+        results.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.command.cmp(&b.command)));
+    }
+    Ok(results)
+}
+
+/// Grouped per command in SQL, then per first token here, since the
+/// token can't be cut out of a sealed command.
+fn failure_rates(conn: &Connection, cipher: Option<&RowCipher>) -> Result<Vec<FailureRate>> {
+    let mut totals: HashMap<String, (i64, i64)> = HashMap::new();
+    let mut stmt = conn.prepare_cached(
+        "SELECT command, COUNT(*), SUM(exit_code <> 0) FROM history
+         WHERE exit_code IS NOT NULL
+         GROUP BY command",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let command = reveal_text(cipher, row.get(0)?)?;
+        let entry = totals.entry(timing::program(&command)).or_default();
+        entry.0 += row.get::<_, i64>(1)?;
+        entry.1 += row.get::<_, i64>(2)?;
+    }
+    let mut results: Vec<FailureRate> = totals
+        .into_iter()
+        .filter(|(program, _)| !program.is_empty())
+        .map(|(program, (runs, failures))| FailureRate { program, runs, failures, rate: failures as f64 / runs as f64 })
+        .collect();
+    results.sort_by(|a, b| {
+        b.rate
+            .total_cmp(&a.rate)
+            .then_with(|| b.runs.cmp(&a.runs))
+            .then_with(|| a.program.cmp(&b.program))
+    });
+    Ok(results)
+}
+
+fn hour_histogram(conn: &Connection, offset: i64) -> Result<Vec<i64>> {
+    let mut hours = vec![0; 24];
+    let mut stmt = conn.prepare_cached(
+        "SELECT ((timestamp + ?1) % 86400) / 3600 AS hour, COUNT(*) FROM history
+         GROUP BY hour",
+    )?;
+    let mut rows = stmt.query(params![offset])?;
+    while let Some(row) = rows.next()? {
+        let hour: i64 = row.get(0)?;
+        if let Some(slot) = hours.get_mut(hour.rem_euclid(24) as usize) {
+            *slot += row.get::<_, i64>(1)?;
+        }
+    }
+    Ok(hours)
+}
+
+/// `!stats heatmap`: commands per `heatmap::BUCKET_SECS` slot from
+/// `since`, oldest first, only slots with any. A prefix is compared in
+/// SQL on a plain vault; a sealed one groups per command too, and the
+/// revealed commands are compared here.
+pub(super) fn command_slots(
+    conn: &Connection,
+    cipher: Option<&RowCipher>,
+    prefix: Option<&str>,
+    since: Option<i64>,
+) -> Result<Vec<Slot>> {
+    let since = since.unwrap_or(i64::MIN);
+    let mut slots: BTreeMap<i64, u64> = BTreeMap::new();
+    match (cipher, prefix) {
+        (Some(cipher), Some(prefix)) => {
+            let mut stmt = conn.prepare_cached(
+                "SELECT command, (timestamp / ?1) * ?1 AS slot, COUNT(*) FROM history
+                 WHERE timestamp >= ?2
+                 GROUP BY command, slot",
+            )?;
+            let mut rows = stmt.query(params![BUCKET_SECS, since])?;
+            while let Some(row) = rows.next()? {
+                if reveal_text(Some(cipher), row.get(0)?)?.starts_with(prefix) {
+                    *slots.entry(row.get(1)?).or_default() += row.get::<_, i64>(2)? as u64;
+                }
+            }
+        }
+        _ => {
+            let mut stmt = conn.prepare_cached(
+                "SELECT (timestamp / ?1) * ?1 AS slot, COUNT(*) FROM history
+                 WHERE timestamp >= ?2 AND (?3 IS NULL OR substr(command, 1, length(?3)) = ?3)
+                 GROUP BY slot",
+            )?;
+            let mut rows = stmt.query(params![BUCKET_SECS, since, prefix])?;
+            while let Some(row) = rows.next()? {
+                slots.insert(row.get(0)?, row.get::<_, i64>(1)? as u64);
+            }
+        }
+    }
+    Ok(slots.into_iter().map(|(start, count)| Slot { start, count }).collect())
+}
+
+fn alias_usage(conn: &Connection) -> Result<Vec<AliasUsage>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT alias, COUNT(timestamp), MAX(timestamp) FROM (
+             SELECT alias, timestamp FROM alias_uses
+             UNION ALL
+             SELECT name, NULL FROM aliases
+         )
+         GROUP BY alias
+         ORDER BY COUNT(timestamp) DESC, alias",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(AliasUsage {
+            alias: row.get(0)?,
+            count: row.get(1)?,
+            last_used: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn session_lengths(conn: &Connection, since: i64, now: i64) -> Result<Vec<SessionLength>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT s.id, s.start_time,
+                COALESCE(s.end_time, s.heartbeat, s.start_time) - s.start_time,
+                (SELECT COUNT(*) FROM history h WHERE h.session_id = s.id),
+                s.end_time IS NULL
+         FROM session s
+         WHERE s.start_time >= ?1 AND s.start_time <= ?2
+         ORDER BY s.start_time, s.id",
+    )?;
+    let rows = stmt.query_map(params![since, now], |row| {
+        Ok(SessionLength {
+            id: row.get(0)?,
+            start_time: row.get(1)?,
+            seconds: row.get::<_, i64>(2)?.max(0),
+            commands: row.get(3)?,
+            open: row.get(4)?,
+        })
+    })?;
+    rows.collect()
+}
+
+// ────────────────────────────────────────────────────────────────
+// Output
+// ────────────────────────────────────────────────────────────────
+
+/// Write one CSV per section into `dir` (created if missing). Returns
+/// the files written.
+pub fn write_csv_dir(report: &AnalyticsReport, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    let mut section = |name: &str, header: &str, rows: Vec<Vec<String>>| -> io::Result<()> {
+        let path = dir.join(name);
+        let mut out = io::BufWriter::new(std::fs::File::create(&path)?);
+        writeln!(out, "{}", header)?;
+        for row in rows {
+            let fields: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+            writeln!(out, "{}", fields.join(","))?;
+        }
+        out.flush()?;
+        written.push(path);
+        Ok(())
+    };
+
+    section(
+        "daily.csv",
+        "date,count",
+        report.daily.iter().map(|d| vec![d.date.clone(), d.count.to_string()]).collect(),
+    )?;
+    section(
+        "top_commands.csv",
+        "command,count,mean_ms",
+        report
+            .top_commands
+            .iter()
+            .map(|c| vec![c.command.clone(), c.count.to_string(), c.mean_ms.map(|m| format!("{:.1}", m)).unwrap_or_default()])
+            .collect(),
+    )?;
+    section(
+        "failure_rates.csv",
+        "program,runs,failures,rate",
+        report
+            .failure_rates
+            .iter()
+            .map(|f| vec![f.program.clone(), f.runs.to_string(), f.failures.to_string(), format!("{:.4}", f.rate)])
+            .collect(),
+    )?;
+    section(
+        "hours.csv",
+        "hour,count",
+        report.hours.iter().enumerate().map(|(h, n)| vec![h.to_string(), n.to_string()]).collect(),
+    )?;
+    section(
+        "aliases.csv",
+        "alias,count,last_used",
+        report
+            .aliases
+            .iter()
+            .map(|a| vec![a.alias.clone(), a.count.to_string(), a.last_used.map(iso8601).unwrap_or_default()])
+            .collect(),
+    )?;
+    section(
+        "sessions.csv",
+        "id,start_time,seconds,commands,open",
+        report
+            .sessions
+            .iter()
+            .map(|s| vec![s.id.clone(), iso8601(s.start_time), s.seconds.to_string(), s.commands.to_string(), s.open.to_string()])
+            .collect(),
+    )?;
+    Ok(written)
+}
+
+/// RFC 4180 quoting: only fields with a comma, quote or line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The per-day counts as one block character per day.
+pub fn sparkline(values: &[i64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| {
+            if v <= 0 {
+                ' '
+            } else {
+                BLOCKS[((v * (BLOCKS.len() as i64 - 1) + max / 2) / max) as usize]
+            }
+        })
+        .collect()
+}
+
+/// A few lines for the terminal after an export.
+pub fn summary_lines(report: &AnalyticsReport) -> Vec<String> {
+    let total: i64 = report.daily.iter().map(|d| d.count).sum();
+    let counts: Vec<i64> = report.daily.iter().map(|d| d.count).collect();
+    let mut lines = vec![
+        format!("  Last {} days:  {} commands", report.daily.len(), total),
+        format!("  │{}│", sparkline(&counts)),
+    ];
+    if let Some((hour, &count)) = report.hours.iter().enumerate().max_by_key(|(h, n)| (**n, std::cmp::Reverse(*h))) {
+        if count > 0 {
+            lines.push(format!("  Busiest hour:  {:02}:00 ({} commands)", hour, count));
+        }
+    }
+    let top: Vec<String> = report
+        .top_commands
+        .iter()
+        .take(3)
+        .map(|c| format!("{} ×{}", c.command, c.count))
+        .collect();
+    if !top.is_empty() {
+        lines.push(format!("  Top commands:  {}", top.join(", ")));
+    }
+    if let Some(f) = report.failure_rates.iter().find(|f| f.failures > 0) {
+        lines.push(format!("  Most failing:  {} ({:.0}% of {} runs)", f.program, f.rate * 100.0, f.runs));
+    }
+    if !report.sessions.is_empty() {
+        let mean = report.sessions.iter().map(|s| s.seconds).sum::<i64>() / report.sessions.len() as i64;
+        lines.push(format!(
+            "  Sessions:      {} (mean {})",
+            report.sessions.len(),
+            timing::format_ms(mean * 1000)
+        ));
+    }
+    lines
+}
@@ -0,0 +1,303 @@
+// positronic-core/src/vault/cache.rs
+//
+// In-memory read caches and write batching for the Vault.
+//
+// - `TableCache` mirrors a small key/value table (aliases, config). It is
+//   tagged with the generation it was loaded at; every write through any
+//   Vault handle on the same file bumps the shared generation, and the next
+//   read reloads. With no aliases defined, `Runner::expand_alias` resolves
+//   with a few atomic loads and never touches a lock.
+// - `ExternalWatch` notices commits from other processes (a second
+//   Positronic window on the same file) via `PRAGMA data_version`, polled
+//   at most every `EXTERNAL_CHECK_INTERVAL`.
+// - `WriteBuffer` holds `log_command` rows issued in quick succession so a
+//   burst lands in one transaction. Pending rows are flushed before any other
+//   Vault operation, so callers never observe the delay. Rows are sealed
+//   on the way in when the vault is encrypted (see `crypto`).
+
+use rusqlite::{Connection, Result, params};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+use super::crypto::Crypt;
+use crate::usage::ResourceUsage;
+
+// ════════════════════════════════════════════════════════════════════
+// Shared generation
+// ════════════════════════════════════════════════════════════════════
+
+/// One counter per database file, shared by every handle in this process.
+static GENERATIONS: OnceLock<Mutex<HashMap<PathBuf, Weak<AtomicU64>>>> = OnceLock::new();
+
+/// Generation counter for `path`. In-memory databases get a private one.
+pub(crate) fn generation_for(path: &Path) -> Arc<AtomicU64> {
+    let key = match std::fs::canonicalize(path) {
+        Ok(p) => p,
+        Err(_) => return Arc::new(AtomicU64::new(0)),
+    };
+    let mut map = GENERATIONS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|p| p.into_inner());
+    map.retain(|_, weak| weak.strong_count() > 0);
+    if let Some(existing) = map.get(&key).and_then(Weak::upgrade) {
+        return existing;
+    }
+    let fresh = Arc::new(AtomicU64::new(0));
+    map.insert(key, Arc::downgrade(&fresh));
+    fresh
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Table cache
+// ════════════════════════════════════════════════════════════════════
+
+const NEVER_LOADED: u64 = u64::MAX;
+
+#[derive(Debug)]
+pub(crate) struct TableCache {
+    loaded_gen: AtomicU64,
+    len_hint: AtomicUsize,
+    map: RwLock<HashMap<String, String>>,
+}
+
+impl TableCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            loaded_gen: AtomicU64::new(NEVER_LOADED),
+            len_hint: AtomicUsize::new(0),
+            map: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Cached value for `key` if the cache is current for `generation`.
+    /// `Some(None)` means "known absent"; `None` means "reload first".
+    pub(crate) fn get(&self, generation: u64, key: &str) -> Option<Option<String>> {
+        if self.loaded_gen.load(Ordering::Acquire) != generation {
+            return None;
+        }
+        // Lock-free fast path: an empty table answers every lookup.
+        if self.len_hint.load(Ordering::Acquire) == 0 {
+            return Some(None);
+        }
+        let map = self.map.read().unwrap_or_else(|p| p.into_inner());
+        Some(map.get(key).cloned())
+    }
+
+    /// Replace the contents, tagging them with the generation read *before*
+    /// the table was queried so a concurrent write forces another reload.
+    pub(crate) fn store(&self, generation: u64, entries: HashMap<String, String>) {
+        let mut map = self.map.write().unwrap_or_else(|p| p.into_inner());
+        self.len_hint.store(entries.len(), Ordering::Release);
+        *map = entries;
+        self.loaded_gen.store(generation, Ordering::Release);
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// External writes
+// ════════════════════════════════════════════════════════════════════
+
+/// Least time between two `PRAGMA data_version` checks.
+pub(crate) const EXTERNAL_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Last `data_version` seen, and when the next check is due.
+#[derive(Debug)]
+pub(crate) struct ExternalWatch {
+    started: Instant,
+    /// Milliseconds since `started`.
+    next_check_ms: AtomicU64,
+    version: AtomicI64,
+}
+
+impl ExternalWatch {
+    pub(crate) fn new(version: i64) -> Self {
+        Self {
+            started: Instant::now(),
+            next_check_ms: AtomicU64::new(EXTERNAL_CHECK_INTERVAL.as_millis() as u64),
+            version: AtomicI64::new(version),
+        }
+    }
+
+    /// Whether a check is due; the caller that gets `true` does it.
+    pub(crate) fn due(&self) -> bool {
+        let now = self.started.elapsed().as_millis() as u64;
+        let next = self.next_check_ms.load(Ordering::Acquire);
+        now >= next
+            && self
+                .next_check_ms
+                .compare_exchange(next, now + EXTERNAL_CHECK_INTERVAL.as_millis() as u64, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+    }
+
+    /// Record `version`; true if it moved since the last check.
+    pub(crate) fn observe(&self, version: i64) -> bool {
+        self.version.swap(version, Ordering::AcqRel) != version
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Write batching
+// ════════════════════════════════════════════════════════════════════
+
+/// `log_command` calls closer together than this are batched.
+pub(crate) const BATCH_WINDOW: Duration = Duration::from_millis(50);
+
+/// Upper bound on rows held in one batch.
+pub(crate) const BATCH_MAX: usize = 256;
+
+#[derive(Debug)]
+pub(crate) struct PendingLog {
+    pub session_id: String,
+    pub command: String,
+    pub output: Option<String>,
+    pub exit_code: Option<i32>,
+    pub timestamp: i64,
+    pub directory: String,
+    pub duration_ms: Option<i64>,
+    /// The row this one re-runs (`!redo`).
+    pub redo_of: Option<i64>,
+    /// The row whose command this one was edited from (a block's
+    /// command taken back into the input line).
+    pub derived_from: Option<i64>,
+    /// What a captured run used (`!time`).
+    pub usage: Option<ResourceUsage>,
+    /// The split pane it ran in (`Vault::for_pane`).
+    pub pane: Option<u32>,
+}
+
+#[derive(Debug)]
+struct BufferState {
+    rows: Vec<PendingLog>,
+    last_write: Option<Instant>,
+}
+
+/// Pending history rows plus the connection they flush into.
+/// Dropping the last handle writes out whatever is left.
+#[derive(Debug)]
+pub(crate) struct WriteBuffer {
+    conn: Arc<Mutex<Connection>>,
+    crypt: Arc<RwLock<Crypt>>,
+    pending: AtomicUsize,
+    state: Mutex<BufferState>,
+}
+
+impl WriteBuffer {
+    pub(crate) fn new(conn: Arc<Mutex<Connection>>, crypt: Arc<RwLock<Crypt>>) -> Self {
+        Self {
+            conn,
+            crypt,
+            pending: AtomicUsize::new(0),
+            state: Mutex::new(BufferState {
+                rows: Vec::new(),
+                last_write: None,
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, BufferState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Queue a row. Returns it back if the batch should be written now:
+    /// the first write after a quiet period goes straight through, and a
+    /// full batch is written together with the new row.
+    pub(crate) fn offer(&self, row: PendingLog) -> Option<PendingLog> {
+        let mut state = self.state();
+        let now = Instant::now();
+        let in_burst = state
+            .last_write
+            .is_some_and(|t| now.duration_since(t) < BATCH_WINDOW);
+        state.last_write = Some(now);
+
+        if in_burst && state.rows.len() < BATCH_MAX {
+            state.rows.push(row);
+            self.pending.store(state.rows.len(), Ordering::Release);
+            None
+        } else {
+            Some(row)
+        }
+    }
+
+    /// Write pending rows (and `extra`, if any) in one transaction.
+    /// The caller must hold the connection lock.
+    pub(crate) fn flush_locked(&self, conn: &mut Connection, extra: Option<PendingLog>) -> Result<()> {
+        if self.pending.load(Ordering::Acquire) == 0 && extra.is_none() {
+            return Ok(());
+        }
+        let mut rows = {
+            let mut state = self.state();
+            self.pending.store(0, Ordering::Release);
+            std::mem::take(&mut state.rows)
+        };
+        rows.extend(extra);
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        if let Crypt::Unlocked(_, cipher) = &*self.crypt.read().unwrap_or_else(|p| p.into_inner()) {
+            for row in &mut rows {
+                row.command = cipher.seal_command(&row.command);
+                row.output = row.output.as_deref().map(|o| cipher.seal(o));
+            }
+        }
+
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO history (session_id, command, output, exit_code, timestamp, directory, duration_ms, redo_of,
+                                      derived_from, cpu_user_ms, cpu_sys_ms, max_rss_bytes, pane)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for row in &rows {
+                stmt.execute(params![
+                    row.session_id,
+                    row.command,
+                    row.output,
+                    row.exit_code,
+                    row.timestamp,
+                    row.directory,
+                    row.duration_ms,
+                    row.redo_of,
+                    row.derived_from,
+                    row.usage.map(|u| u.user_ms as i64),
+                    row.usage.map(|u| u.sys_ms as i64),
+                    row.usage.map(|u| u.max_rss_bytes as i64),
+                    row.pane
+                ])?;
+            }
+        }
+        tx.commit()
+    }
+
+    pub(crate) fn lock_conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// The connection, unless another operation holds it.
+    pub(crate) fn try_lock_conn(&self) -> Option<MutexGuard<'_, Connection>> {
+        self.conn.try_lock().ok()
+    }
+
+    /// The connection, unless another operation holds it or rows are
+    /// waiting to be written.
+    pub(crate) fn try_lock_idle(&self) -> Option<MutexGuard<'_, Connection>> {
+        if self.pending.load(Ordering::Acquire) > 0 {
+            return None;
+        }
+        self.conn.try_lock().ok()
+    }
+}
+
+impl Drop for WriteBuffer {
+    fn drop(&mut self) {
+        let conn = self.conn.clone();
+        let mut guard = conn.lock().unwrap_or_else(|p| p.into_inner());
+        if let Err(e) = self.flush_locked(&mut guard, None) {
+            eprintln!("[VAULT] Failed to flush pending history: {}", e);
+        }
+    }
+}
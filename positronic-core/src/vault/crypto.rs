@@ -0,0 +1,252 @@
+// positronic-core/src/vault/crypto.rs
+//
+// Application-level encryption of `history.command` and `history.output`.
+//
+// The database file itself stays plain SQLite (no SQLCipher build, so the
+// bundled sqlite and a pure-Rust toolchain keep working); with
+// `vault.encrypt` on, those two columns hold `enc1:<base64(nonce|ciphertext)>`
+// sealed with ChaCha20-Poly1305 under a key derived from the passphrase
+// (PBKDF2-HMAC-SHA256). Everything else (directories, timestamps, exit
+// codes, aliases, bookmarks, config) is stored as before.
+//
+// Reduced query capability:
+// - Commands are sealed deterministically (the nonce is an HMAC of the
+//   text), so equal commands have equal ciphertexts: `GROUP BY command`,
+//   `COUNT(DISTINCT command)` and exact lookups still run in SQL. This
+//   reveals which rows share a command, not what the command is.
+// - Outputs get a random nonce and can't be compared at all.
+// - `LIKE` can't see through ciphertext. Substring and prefix queries
+//   (`!search`, `!stats slow`, `!stats trend`) decrypt and scan the newest
+//   `SCAN_CAP` rows instead; older matches are not found.
+//
+// Values without the prefix are returned as they are, so a database caught
+// halfway through `!vault encrypt`/`!vault decrypt` still reads.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Config key: `true` once `!vault encrypt` has run.
+pub const ENCRYPT_KEY: &str = "vault.encrypt";
+
+/// Config key holding the KDF parameters and the passphrase check value.
+pub const KDF_KEY: &str = "vault.kdf";
+
+/// Environment variable read at startup to unlock an encrypted vault.
+pub const PASSPHRASE_ENV: &str = "POSITRONIC_VAULT_PASSPHRASE";
+
+/// Rows decrypted by a substring or prefix query on an encrypted vault.
+pub const SCAN_CAP: usize = 5000;
+
+/// Rows rewritten per transaction by `!vault encrypt`/`!vault decrypt`.
+pub const MIGRATE_BATCH: usize = 500;
+
+const PREFIX: &str = "enc1:";
+const KDF_NAME: &str = "pbkdf2-sha256";
+const DEFAULT_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Sealed into the check value; unlocking must reproduce it.
+const CHECK_TEXT: &str = "positronic-vault";
+
+/// Whether `value` is a sealed column value.
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Whether a `vault.encrypt` value turns encryption on.
+pub fn encrypt_enabled(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_lowercase()).as_deref(),
+        Some("true" | "on" | "1" | "yes")
+    )
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Errors
+// ════════════════════════════════════════════════════════════════════
+
+/// Raised (wrapped in `rusqlite::Error::ToSqlConversionFailure`) by history
+/// queries while the passphrase hasn't been given.
+#[derive(Debug, thiserror::Error)]
+#[error("vault locked")]
+pub struct VaultLocked;
+
+/// A sealed value that doesn't open with the current key.
+#[derive(Debug, thiserror::Error)]
+#[error("vault row can't be decrypted (corrupt, or sealed with another key)")]
+pub struct Undecryptable;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VaultCryptError {
+    #[error("passphrases don't match")]
+    Mismatch,
+    #[error("passphrase can't be empty")]
+    EmptyPassphrase,
+    #[error("wrong passphrase")]
+    WrongPassphrase,
+    #[error("vault is already encrypted")]
+    AlreadyEncrypted,
+    #[error("vault is not encrypted")]
+    NotEncrypted,
+    #[error("other writes are in flight; try again when the vault is idle")]
+    Busy,
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+pub(crate) fn locked_error() -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(VaultLocked))
+}
+
+pub(crate) fn undecryptable_error() -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(Undecryptable))
+}
+
+/// Whether `err` is the locked-vault refusal.
+pub fn is_locked_error(err: &rusqlite::Error) -> bool {
+    matches!(err, rusqlite::Error::ToSqlConversionFailure(e) if e.is::<VaultLocked>())
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Key derivation
+// ════════════════════════════════════════════════════════════════════
+
+/// `pbkdf2-sha256$<iterations>$<salt>$<check>`, as stored under `KDF_KEY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KdfParams {
+    iterations: u32,
+    salt: Vec<u8>,
+    check: String,
+}
+
+impl KdfParams {
+    /// Fresh parameters for `passphrase`, with the cipher they derive.
+    pub(crate) fn create(passphrase: &str) -> (Self, RowCipher) {
+        let iterations = DEFAULT_ITERATIONS;
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = RowCipher::derive(passphrase, &salt, iterations);
+        let check = cipher.seal(CHECK_TEXT);
+        (Self { iterations, salt, check }, cipher)
+    }
+
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split('$');
+        if parts.next()? != KDF_NAME {
+            return None;
+        }
+        let iterations = parts.next()?.parse().ok()?;
+        let salt = STANDARD.decode(parts.next()?).ok()?;
+        let check = parts.next()?.to_string();
+        if parts.next().is_some() || !is_sealed(&check) {
+            return None;
+        }
+        Some(Self { iterations, salt, check })
+    }
+
+    pub(crate) fn to_config(&self) -> String {
+        format!("{}${}${}${}", KDF_NAME, self.iterations, STANDARD.encode(&self.salt), self.check)
+    }
+
+    /// The cipher for `passphrase`, if it is the right one.
+    pub(crate) fn unlock(&self, passphrase: &str) -> Option<RowCipher> {
+        let cipher = RowCipher::derive(passphrase, &self.salt, self.iterations);
+        (cipher.open(&self.check).as_deref() == Some(CHECK_TEXT)).then_some(cipher)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Row cipher
+// ════════════════════════════════════════════════════════════════════
+
+pub(crate) struct RowCipher {
+    aead: ChaCha20Poly1305,
+    /// Derives deterministic nonces for commands.
+    nonce_key: [u8; 32],
+}
+
+impl std::fmt::Debug for RowCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RowCipher(..)")
+    }
+}
+
+impl RowCipher {
+    fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Self {
+        let mut keys = [0u8; 64];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, &mut keys);
+        let mut nonce_key = [0u8; 32];
+        nonce_key.copy_from_slice(&keys[32..]);
+        Self {
+            aead: ChaCha20Poly1305::new(Key::from_slice(&keys[..32])),
+            nonce_key,
+        }
+    }
+
+    /// Seal with a random nonce.
+    pub(crate) fn seal(&self, text: &str) -> String {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        self.seal_with(&nonce, text)
+    }
+
+    /// Seal so that equal texts give equal output (for `command`).
+    pub(crate) fn seal_command(&self, text: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.nonce_key)
+            .expect("HMAC accepts any key length");
+        mac.update(text.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        self.seal_with(Nonce::from_slice(&digest[..NONCE_LEN]), text)
+    }
+
+    fn seal_with(&self, nonce: &Nonce, text: &str) -> String {
+        let sealed = self
+            .aead
+            .encrypt(nonce, text.as_bytes())
+            .expect("ChaCha20-Poly1305 encryption of an in-memory buffer");
+        let mut raw = nonce.to_vec();
+        raw.extend_from_slice(&sealed);
+        format!("{}{}", PREFIX, STANDARD.encode(raw))
+    }
+
+    /// The plain text of `value`; unsealed values pass through. `None` if
+    /// the value is sealed but doesn't open with this key.
+    pub(crate) fn open(&self, value: &str) -> Option<String> {
+        let Some(encoded) = value.strip_prefix(PREFIX) else {
+            return Some(value.to_string());
+        };
+        let raw = STANDARD.decode(encoded).ok()?;
+        if raw.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, sealed) = raw.split_at(NONCE_LEN);
+        let plain = self.aead.decrypt(Nonce::from_slice(nonce), sealed).ok()?;
+        String::from_utf8(plain).ok()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Vault state
+// ════════════════════════════════════════════════════════════════════
+
+/// Encryption state shared by every clone of a `Vault`.
+#[derive(Debug, Default)]
+pub(crate) enum Crypt {
+    #[default]
+    Plain,
+    /// Encrypted, and the passphrase hasn't been given.
+    Locked(KdfParams),
+    Unlocked(KdfParams, std::sync::Arc<RowCipher>),
+}
+
+/// What `Vault::lock_state` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    Plain,
+    Locked,
+    Unlocked,
+}
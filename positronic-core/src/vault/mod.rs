@@ -1,12 +1,44 @@
 // positronic-core/src/vault/mod.rs
 
-use chrono::Utc;
-use rusqlite::{Connection, Result, params};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use chrono::{Local, Utc};
+use rusqlite::{Connection, Result, TransactionBehavior, params};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::error::PositronicError;
+use crate::here;
+use crate::history_filter::HistoryFilter;
+use crate::heatmap::Slot;
+use crate::inspect;
+use crate::sync::{SyncPlan, MACHINE_ID_KEY};
+use crate::chain::CHAIN_CAP;
+use crate::time_format::TimeSettings;
+use crate::summary::{SummarySettings, SUMMARY_CAP};
+use crate::timeline::TIMELINE_CAP;
+use crate::usage::ResourceUsage;
+use crate::hooks::{HookRule, HookSpec};
+use crate::setenv::{EnvOverride, EnvScope};
+use crate::queue::{QueueProgress, MAX_QUEUED};
+use crate::safe_mode::{DisabledItem, DisabledKind};
+use crate::test_report::{TestReport, TestRun};
+
+pub mod analytics;
+mod cache;
+pub mod crypto;
+pub mod recover;
 pub mod schema;
+pub mod timing;
+
+use cache::{ExternalWatch, PendingLog, TableCache, WriteBuffer};
+use crypto::{Crypt, KdfParams, RowCipher};
+pub use analytics::AnalyticsReport;
+pub use crypto::{LockState, VaultCryptError};
+pub use recover::RecoveryReport;
+pub use timing::{DurationStats, ResourceStats, TimedRun};
 
 // ════════════════════════════════════════════════════════════════════
 // Data types
@@ -31,6 +63,44 @@ pub struct Alias {
     pub created_at: i64,
 }
 
+/// A finished `!io console` session.
+#[derive(Debug, Clone)]
+pub struct DeviceSession {
+    pub id: i64,
+    pub port: String,
+    pub baud: u32,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+    /// What the device sent, at most `DEVICE_TRANSCRIPT_CAP` bytes (the tail).
+    pub transcript: String,
+}
+
+/// A USB device seen on a port, by its `positronic_io::DeviceId` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceRecord {
+    pub id: String,
+    /// The user's name for it (`!io name`).
+    pub name: Option<String>,
+    pub product: Option<String>,
+    pub last_port: String,
+    pub seen_at: i64,
+    pub defaults: DeviceDefaults,
+}
+
+/// Settings a device is opened with when the command doesn't give them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceDefaults {
+    pub baud: Option<u32>,
+    /// A `ParserConfig`, as JSON.
+    pub parser: Option<String>,
+    /// `cr`, `lf` or `crlf`.
+    pub eol: Option<String>,
+    /// Reopen it when it comes back after dropping.
+    pub reconnect: Option<bool>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Bookmark {
     pub id: i64,
@@ -39,6 +109,58 @@ pub struct Bookmark {
     pub created_at: i64,
 }
 
+/// The unsent input line last checkpointed, and the session typing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draft {
+    pub session_id: String,
+    pub text: String,
+    pub saved_at: i64,
+}
+
+/// A named saved output (`!tag`): a copy of one run, kept until the tag
+/// is removed.
+#[derive(Debug, Clone)]
+pub struct Tag {
+    pub name: String,
+    /// The history row it was taken from, which may since be gone.
+    pub history_id: Option<i64>,
+    pub command: String,
+    pub output: String,
+    pub exit_code: Option<i32>,
+    pub directory: String,
+    /// When the run happened.
+    pub ran_at: i64,
+    /// When it was tagged.
+    pub created_at: i64,
+}
+
+/// A `!mark` kept across sessions: a name for a history row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedMark {
+    pub name: String,
+    pub history_id: i64,
+    pub created_at: i64,
+}
+
+/// A `!macro` recording: its steps as the UI encoded them, one per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedMacro {
+    pub name: String,
+    pub steps: String,
+    pub created_at: i64,
+}
+
+/// One run of a native `!` command, as `RecordTiming` logged it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeTiming {
+    /// The command's registered name, without the `!`.
+    pub name: String,
+    pub duration_ms: i64,
+    /// False when it failed with an error rather than answering.
+    pub ok: bool,
+    pub ran_at: i64,
+}
+
 #[derive(Debug, Clone)]
 pub struct VaultStats {
     pub total_commands: i64,
@@ -49,6 +171,77 @@ pub struct VaultStats {
     pub bookmark_count: i64,
     pub earliest_timestamp: Option<i64>,
     pub db_size_bytes: i64,
+    /// Open sessions with a recent heartbeat, this one included.
+    pub active_instances: i64,
+}
+
+/// How often AI suggestions were offered and picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuggestionCounts {
+    pub offered: i64,
+    pub accepted: i64,
+}
+
+impl SuggestionCounts {
+    /// Accepted / offered, or `None` before anything was offered.
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        (self.offered > 0).then(|| self.accepted as f64 / self.offered as f64)
+    }
+}
+
+/// A named bundle of config overrides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// `(key, value)` pairs, sorted by key.
+    pub settings: Vec<(String, String)>,
+}
+
+/// Outcome of `Vault::use_profile`; `None` stands for the base config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSwitch {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// Config key naming the active profile (absent: base config only).
+pub const ACTIVE_PROFILE_KEY: &str = "profile.active";
+
+/// Config key naming the profile active before the last switch
+/// (empty: the base config).
+pub const PREVIOUS_PROFILE_KEY: &str = "profile.previous";
+
+/// Bookkeeping keys under this prefix are never part of a profile.
+const PROFILE_KEY_PREFIX: &str = "profile.";
+
+/// Overrides of the active profile, as `(key, value)` rows.
+const ACTIVE_OVERRIDES_SQL: &str = "SELECT p.key, p.value FROM profiles p
+     JOIN config c ON c.key = 'profile.active' AND c.value = p.name";
+
+/// How often a running instance refreshes its session heartbeat.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// An open session whose heartbeat is older than this belongs to an
+/// instance that crashed or was killed.
+pub const STALE_AFTER_SECS: i64 = 3 * HEARTBEAT_INTERVAL.as_secs() as i64;
+
+/// Bytes of a console transcript `log_device_session` keeps.
+pub const DEVICE_TRANSCRIPT_CAP: usize = 256 * 1024;
+
+/// Native command runs `Vault::log_native_timing` keeps.
+pub const NATIVE_TIMINGS_KEPT: i64 = 10_000;
+
+/// How long `open` waits for another instance's migration or write.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Recent unique commands an encrypted vault searches by prefix.
+const SEALED_PREFIX_SCAN: usize = 5000;
+
+/// How far `Vault::index_history` has got.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FtsProgress {
+    pub indexed: u64,
+    pub pending: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -57,50 +250,518 @@ pub struct TopCommand {
     pub count: i64,
 }
 
+/// A distinct command with when it last ran and how often.
+#[derive(Debug, Clone)]
+pub struct RecentCommand {
+    pub command: String,
+    pub last_run: i64,
+    pub runs: i64,
+}
+
+/// A command often run in one directory (see `here`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryCommand {
+    pub command: String,
+    pub runs: i64,
+    pub last_run: i64,
+    /// Runs weighted by age: 4 within the hour, 2 the day, 1 the week,
+    /// 0.5 before that.
+    pub score: f64,
+}
+
 // ════════════════════════════════════════════════════════════════════
 // Vault
 // ════════════════════════════════════════════════════════════════════
 
+/// Handle to the SQLite store. Clones share one connection, one write
+/// buffer and one set of read caches.
+///
+/// Alias and config reads are served from memory and reloaded whenever the
+/// per-file generation moves (any `set_*`/`remove_*` through any handle in
+/// this process). Config reads are layered: the active profile's overrides
+/// win, everything else falls through to the base `config` table. Bursts of `log_command` are committed as one transaction;
+/// every other operation flushes them first, so reads always see them.
+///
+/// Several instances may share one file. Each keeps its own session and
+/// heartbeat (`heartbeat`, every `HEARTBEAT_INTERVAL`); `open` closes the
+/// sessions of instances that stopped beating, and runs migrations inside
+/// an immediate transaction so two instances upgrading at once take turns.
+/// Writes from other processes are noticed through `PRAGMA data_version`
+/// and invalidate the alias/config caches.
+///
+/// An encrypted vault (`!vault encrypt`, see `crypto`) opens locked: history
+/// reads and writes fail with `VaultLocked` until `unlock` is given the
+/// passphrase. Aliases, bookmarks and config keep working.
+///
+/// A file SQLite reports as corrupt is moved aside at `open` and what
+/// still reads is carried into a fresh one (see `recover`).
+///
+/// `open_inspect` copies a vault into memory for `--inspect` (see
+/// `crate::inspect`); that handle starts no session and refuses every
+/// write with `InspectMode`.
 #[derive(Debug, Clone)]
 pub struct Vault {
-    conn: Arc<Mutex<Connection>>,
-    session_id: String,
+    writes: Arc<WriteBuffer>,
+    crypt: Arc<RwLock<Crypt>>,
+    generation: Arc<AtomicU64>,
+    aliases: Arc<TableCache>,
+    config: Arc<TableCache>,
+    /// Overrides of the active profile.
+    overrides: Arc<TableCache>,
+    external: Arc<ExternalWatch>,
+    /// The `!redo` line about to run and the history id it repeats.
+    redo: Arc<Mutex<Option<(String, i64)>>>,
+    /// The line about to run that was edited from a block, and the history
+    /// id of that block's command.
+    derived: Arc<Mutex<Option<(String, i64)>>>,
+    /// `history.ignore_*` state for `log_command`.
+    history: Arc<Mutex<HistoryLog>>,
+    /// Sessions of dead instances closed by `open`.
+    stale_closed: usize,
+    /// Replaced by `new_session` when the shell restarts.
+    session: Arc<RwLock<Session>>,
+    /// The database file, for `integrity_check`'s own connection.
+    path: PathBuf,
+    /// What `open` salvaged from a damaged file (see `recover`).
+    recovery: Option<Arc<RecoveryReport>>,
+    /// Made by `open_inspect` or `seal`: writes fail with `InspectMode`.
+    /// Nothing turns it back off.
+    inspect: bool,
+    /// The split pane this handle logs for and reads (`for_pane`); None
+    /// reads every pane's rows.
+    pane: Option<u32>,
+}
+
+/// The filter loaded at a config generation, the line it should drop
+/// next, the line it should log in another form, and the last line
+/// logged, for `history.ignore_dups`.
+#[derive(Debug, Default)]
+struct HistoryLog {
+    filter: Option<(u64, Arc<HistoryFilter>)>,
+    unlogged: Vec<String>,
+    logged_as: Option<(String, String)>,
+    last: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Session {
+    id: String,
     start_time: i64,
 }
 
 impl Vault {
     /// Open the Vault at the specified path.
     /// Creates the database file and runs all migrations if needed.
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let conn = Connection::open(&path)?;
+    /// A damaged file is moved aside and its readable rows carried over
+    /// into a fresh one; `recovery` then says what happened.
+    pub fn open<P: AsRef<Path>>(path: P) -> std::result::Result<Self, PositronicError> {
+        let path = path.as_ref();
+        let opened = match Self::open_at(path) {
+            Err(e) if recover::is_corruption(&e) && path.is_file() => Self::recover_at(path, e),
+            opened => opened,
+        };
+        opened.map_err(PositronicError::vault_open)
+    }
+
+    /// Quarantine the damaged file at `path`, salvage it into a fresh one
+    /// and open that. If the file can't be moved, `err` stands.
+    fn recover_at(path: &Path, err: rusqlite::Error) -> Result<Self> {
+        let now = Local::now();
+        let Ok(damaged) = recover::quarantine(path, now) else {
+            return Err(err);
+        };
+        let mut fresh = Connection::open(path)?;
+        fresh.pragma_update(None, "journal_mode", "WAL")?;
+        migrate(&mut fresh)?;
+        let report = recover::salvage(&damaged, &mut fresh, err.to_string(), now.timestamp())?;
+        drop(fresh);
+
+        let mut vault = Self::open_at(path)?;
+        vault.recovery = Some(Arc::new(report));
+        Ok(vault)
+    }
+
+    fn open_at(path: &Path) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+
+        // Another instance may be mid-migration or mid-write: wait for it.
+        conn.busy_timeout(BUSY_TIMEOUT)?;
 
         // WAL mode for better concurrency
         conn.pragma_update(None, "journal_mode", "WAL")?;
 
-        // Run migrations in order
-        conn.execute_batch(schema::MIGRATION_INIT)?;
-        conn.execute_batch(schema::MIGRATION_V2)?;
+        migrate(&mut conn)?;
+        recover::probe(&conn)?;
+        let tx = conn.transaction()?;
+        let stale_closed = close_stale_sessions(&tx, Utc::now().timestamp())?;
+        // Session overrides end with their session.
+        tx.execute(
+            "DELETE FROM session_env WHERE session_id <> ''
+             AND session_id IN (SELECT id FROM session WHERE end_time IS NOT NULL)",
+            [],
+        )?;
+        tx.commit()?;
 
-        let session_id = Uuid::new_v4().to_string();
-        let start_time = Utc::now().timestamp();
+        let vault = Self::assemble(conn, path, stale_closed)?;
+        vault.start_session()?;
+        Ok(vault)
+    }
 
-        let vault = Self {
-            conn: Arc::new(Mutex::new(conn)),
-            session_id,
-            start_time,
-        };
+    /// `--inspect`: copy the vault at `path` into memory through an
+    /// immutable, read-only connection and open the copy sealed. The file
+    /// is never written, not even a `-wal` or `-shm` beside it, and no
+    /// session is started.
+    pub fn open_inspect<P: AsRef<Path>>(path: P) -> std::result::Result<Self, PositronicError> {
+        Self::inspect_at(path.as_ref()).map_err(PositronicError::vault_open)
+    }
 
-        vault.start_session()?;
+    fn inspect_at(path: &Path) -> Result<Self> {
+        let flags = rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
+            | rusqlite::OpenFlags::SQLITE_OPEN_URI
+            | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let source = Connection::open_with_flags(inspect::immutable_uri(path), flags)?;
+        let mut conn = Connection::open_in_memory()?;
+        rusqlite::backup::Backup::new(&source, &mut conn)?.run_to_completion(256, Duration::ZERO, None)?;
+        drop(source);
+        // An older vault is brought up to date in the copy only.
+        migrate(&mut conn)?;
+        Self::assemble(conn, Path::new(":memory:"), 0)?.seal()
+    }
 
-        Ok(vault)
+    /// An empty in-memory vault with no session, for `inspect` to import
+    /// a bundle into before sealing it.
+    pub(crate) fn scratch() -> Result<Self> {
+        let mut conn = Connection::open_in_memory()?;
+        migrate(&mut conn)?;
+        Self::assemble(conn, Path::new(":memory:"), 0)
+    }
+
+    /// This handle, read-only for good: every write method fails with
+    /// `InspectMode`, and SQLite's `query_only` catches any that doesn't
+    /// check.
+    pub(crate) fn seal(mut self) -> Result<Self> {
+        self.conn()?.pragma_update(None, "query_only", true)?;
+        self.inspect = true;
+        Ok(self)
+    }
+
+    /// Opened for `--inspect`: browsing only.
+    pub fn is_inspect(&self) -> bool {
+        self.inspect
     }
 
-    pub fn session_id(&self) -> &str {
-        &self.session_id
+    fn assemble(conn: Connection, path: &Path, stale_closed: usize) -> Result<Self> {
+        let data_version: i64 = conn.query_row("PRAGMA data_version", [], |row| row.get(0))?;
+
+        let crypt = Arc::new(RwLock::new(read_crypt(&conn)?));
+        let generation = cache::generation_for(path);
+
+        Ok(Self {
+            writes: Arc::new(WriteBuffer::new(Arc::new(Mutex::new(conn)), crypt.clone())),
+            crypt,
+            generation,
+            aliases: Arc::new(TableCache::new()),
+            config: Arc::new(TableCache::new()),
+            overrides: Arc::new(TableCache::new()),
+            external: Arc::new(ExternalWatch::new(data_version)),
+            redo: Arc::new(Mutex::new(None)),
+            derived: Arc::new(Mutex::new(None)),
+            history: Arc::new(Mutex::new(HistoryLog::default())),
+            stale_closed,
+            session: Arc::new(RwLock::new(Session {
+                id: Uuid::new_v4().to_string(),
+                start_time: Utc::now().timestamp(),
+            })),
+            path: path.to_path_buf(),
+            recovery: None,
+            inspect: false,
+            pane: None,
+        })
+    }
+
+    fn session(&self) -> Session {
+        self.session.read().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    pub fn session_id(&self) -> String {
+        self.session().id
     }
 
     pub fn start_time(&self) -> i64 {
-        self.start_time
+        self.session().start_time
+    }
+
+    /// A handle for split pane `pane`: the commands it logs carry the
+    /// pane, and `!history`, `!search` and `!export` through it see only
+    /// that pane's rows. It shares everything else with this one.
+    pub fn for_pane(&self, pane: u32) -> Vault {
+        Vault { pane: Some(pane), ..self.clone() }
+    }
+
+    /// A handle that reads every pane's rows, for `--all-panes`.
+    pub fn all_panes(&self) -> Vault {
+        Vault { pane: None, ..self.clone() }
+    }
+
+    /// The pane `for_pane` gave this handle.
+    pub fn pane(&self) -> Option<u32> {
+        self.pane
+    }
+
+    /// Commit any buffered history rows now.
+    pub fn flush(&self) -> Result<()> {
+        self.conn().map(|_| ())
+    }
+
+    /// Lock the connection, writing out buffered history first so every
+    /// statement sees a consistent view.
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        let mut conn = self.writes.lock_conn();
+        self.writes.flush_locked(&mut conn, None)?;
+        Ok(conn)
+    }
+
+    /// `conn` for a statement that writes; refused in inspect mode.
+    fn write_conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.writable()?;
+        self.conn()
+    }
+
+    fn writable(&self) -> Result<()> {
+        if self.inspect {
+            return Err(inspect::inspect_error());
+        }
+        Ok(())
+    }
+
+    /// Mark every cache for this database file as stale.
+    fn bump_generation(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Treat the caches as stale if another process committed since the
+    /// last check. Runs at most every `EXTERNAL_CHECK_INTERVAL`, and not at
+    /// all while the connection is busy.
+    fn check_external_writes(&self) {
+        if !self.external.due() {
+            return;
+        }
+        let Some(conn) = self.writes.try_lock_conn() else {
+            return;
+        };
+        if let Ok(version) = conn.query_row("PRAGMA data_version", [], |row| row.get::<_, i64>(0)) {
+            if self.external.observe(version) {
+                self.bump_generation();
+            }
+        }
+    }
+
+    /// Serve `key` from `cache`, reloading the whole table if it is stale.
+    fn cached_lookup(&self, cache: &TableCache, sql: &str, key: &str) -> Result<Option<String>> {
+        self.check_external_writes();
+        let generation = self.generation.load(Ordering::Acquire);
+        if let Some(hit) = cache.get(generation, key) {
+            return Ok(hit);
+        }
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut entries = HashMap::new();
+        for row in rows {
+            let (k, v) = row?;
+            entries.insert(k, v);
+        }
+        let value = entries.get(key).cloned();
+        cache.store(generation, entries);
+        Ok(value)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Encryption
+    // ────────────────────────────────────────────────────────────────
+
+    fn crypt(&self) -> std::sync::RwLockReadGuard<'_, Crypt> {
+        self.crypt.read().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn set_crypt(&self, crypt: Crypt) {
+        *self.crypt.write().unwrap_or_else(|p| p.into_inner()) = crypt;
+    }
+
+    pub fn lock_state(&self) -> LockState {
+        match &*self.crypt() {
+            Crypt::Plain => LockState::Plain,
+            Crypt::Locked(_) => LockState::Locked,
+            Crypt::Unlocked(..) => LockState::Unlocked,
+        }
+    }
+
+    /// Encrypted and still waiting for the passphrase.
+    pub fn is_locked(&self) -> bool {
+        self.lock_state() == LockState::Locked
+    }
+
+    /// The row cipher: `None` for a plain vault, `VaultLocked` if locked.
+    fn cipher(&self) -> Result<Option<Arc<RowCipher>>> {
+        match &*self.crypt() {
+            Crypt::Plain => Ok(None),
+            Crypt::Locked(_) => Err(crypto::locked_error()),
+            Crypt::Unlocked(_, cipher) => Ok(Some(cipher.clone())),
+        }
+    }
+
+    /// Unlock an encrypted vault. Unlocking an unlocked vault only checks
+    /// the passphrase.
+    pub fn unlock(&self, passphrase: &str) -> std::result::Result<(), VaultCryptError> {
+        let params = match &*self.crypt() {
+            Crypt::Plain => return Err(VaultCryptError::NotEncrypted),
+            Crypt::Locked(params) | Crypt::Unlocked(params, _) => params.clone(),
+        };
+        let cipher = params.unlock(passphrase).ok_or(VaultCryptError::WrongPassphrase)?;
+        self.set_crypt(Crypt::Unlocked(params, Arc::new(cipher)));
+        Ok(())
+    }
+
+    /// `!vault encrypt`: seal every history row under a key derived from
+    /// `passphrase`, `MIGRATE_BATCH` rows per transaction, calling
+    /// `progress(done, total)` after each batch. Returns the rows rewritten.
+    ///
+    /// Refuses unless `confirm` repeats the passphrase and no other write is
+    /// in flight. Encryption is switched on before the first batch, so an
+    /// interrupted run leaves an encrypted vault with some plain rows
+    /// (readable, and sealed by running it again after `unlock`).
+    pub fn encrypt_history<F>(&self, passphrase: &str, confirm: &str, progress: F) -> std::result::Result<usize, VaultCryptError>
+    where
+        F: FnMut(usize, usize),
+    {
+        self.writable()?;
+        check_passphrases(passphrase, confirm)?;
+        let mut conn = self.writes.try_lock_idle().ok_or(VaultCryptError::Busy)?;
+        let (params, cipher) = match &*self.crypt() {
+            Crypt::Plain => {
+                let (params, cipher) = KdfParams::create(passphrase);
+                (params, Arc::new(cipher))
+            }
+            Crypt::Locked(_) => return Err(crypto::locked_error().into()),
+            Crypt::Unlocked(params, cipher) => {
+                // Finishing an interrupted run: same passphrase, same key.
+                if params.unlock(passphrase).is_none() {
+                    return Err(VaultCryptError::AlreadyEncrypted);
+                }
+                (params.clone(), cipher.clone())
+            }
+        };
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+            params![crypto::KDF_KEY, params.to_config()],
+        )?;
+        tx.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, 'true')",
+            params![crypto::ENCRYPT_KEY],
+        )?;
+        tx.commit()?;
+        self.set_crypt(Crypt::Unlocked(params, cipher.clone()));
+        self.bump_generation();
+
+        let rewritten = rewrite_history(&mut conn, progress, |command, output| {
+            let command = (!crypto::is_sealed(command)).then(|| cipher.seal_command(command));
+            let output = output.filter(|o| !crypto::is_sealed(o)).map(|o| cipher.seal(o));
+            Ok((command, output))
+        })?;
+        rewrite_alias_uses(&mut conn, |text| Ok((!crypto::is_sealed(text)).then(|| cipher.seal(text))))?;
+        rewrite_transcripts(&mut conn, |text| Ok((!crypto::is_sealed(text)).then(|| cipher.seal(text))))?;
+        rewrite_tags(&mut conn, |text| Ok((!crypto::is_sealed(text)).then(|| cipher.seal(text))))?;
+        rewrite_macros(&mut conn, |text| Ok((!crypto::is_sealed(text)).then(|| cipher.seal(text))))?;
+        rewrite_test_runs(&mut conn, |text| Ok((!crypto::is_sealed(text)).then(|| cipher.seal(text))))?;
+        // The draft is only a checkpoint; the next one is written sealed.
+        conn.execute("DELETE FROM input_draft", [])?;
+        Ok(rewritten)
+    }
+
+    /// `!vault decrypt`: the reverse of `encrypt_history`. Encryption is
+    /// switched off only after the last batch.
+    pub fn decrypt_history<F>(&self, passphrase: &str, confirm: &str, progress: F) -> std::result::Result<usize, VaultCryptError>
+    where
+        F: FnMut(usize, usize),
+    {
+        self.writable()?;
+        check_passphrases(passphrase, confirm)?;
+        let mut conn = self.writes.try_lock_idle().ok_or(VaultCryptError::Busy)?;
+        let params = match &*self.crypt() {
+            Crypt::Plain => return Err(VaultCryptError::NotEncrypted),
+            Crypt::Locked(params) | Crypt::Unlocked(params, _) => params.clone(),
+        };
+        let cipher = params.unlock(passphrase).ok_or(VaultCryptError::WrongPassphrase)?;
+        self.set_crypt(Crypt::Unlocked(params, Arc::new(cipher)));
+        let Some(cipher) = self.cipher()? else {
+            return Err(VaultCryptError::NotEncrypted);
+        };
+
+        let rewritten = rewrite_history(&mut conn, progress, |command, output| {
+            let open = |value: &str| cipher.open(value).ok_or_else(crypto::undecryptable_error);
+            let command = crypto::is_sealed(command).then(|| open(command)).transpose()?;
+            let output = output.filter(|o| crypto::is_sealed(o)).map(open).transpose()?;
+            Ok((command, output))
+        })?;
+        let open = |text: &str| {
+            crypto::is_sealed(text)
+                .then(|| cipher.open(text).ok_or_else(crypto::undecryptable_error))
+                .transpose()
+        };
+        rewrite_alias_uses(&mut conn, open)?;
+        rewrite_transcripts(&mut conn, open)?;
+        rewrite_tags(&mut conn, open)?;
+        rewrite_macros(&mut conn, open)?;
+        rewrite_test_runs(&mut conn, open)?;
+        conn.execute("DELETE FROM input_draft", [])?;
+
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM config WHERE key = ?1", params![crypto::KDF_KEY])?;
+        tx.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, 'false')",
+            params![crypto::ENCRYPT_KEY],
+        )?;
+        tx.commit()?;
+        self.set_crypt(Crypt::Plain);
+        self.bump_generation();
+        Ok(rewritten)
+    }
+
+    /// Decrypt-and-scan for the queries `LIKE` can't answer on sealed rows:
+    /// the newest `SCAN_CAP` rows, newest first, up to `limit` matches.
+    /// Output is only decrypted for the rows kept; `pane` keeps one pane's.
+    fn scan_history<F>(&self, cipher: &RowCipher, pane: Option<u32>, mut keep: F, limit: usize) -> Result<Vec<CommandRecord>>
+    where
+        F: FnMut(&CommandRecord) -> bool,
+    {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE ?2 IS NULL OR pane = ?2
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![crypto::SCAN_CAP as i64, pane], record_from_row)?;
+        let mut results = Vec::new();
+        for row in rows {
+            let mut record = row?;
+            record.command = reveal_text(Some(cipher), record.command)?;
+            if !keep(&record) {
+                continue;
+            }
+            record.output = record.output.map(|o| reveal_text(Some(cipher), o)).transpose()?;
+            results.push(record);
+            if results.len() >= limit {
+                break;
+            }
+        }
+        Ok(results)
     }
 
     // ────────────────────────────────────────────────────────────────
@@ -108,21 +769,110 @@ impl Vault {
     // ────────────────────────────────────────────────────────────────
 
     fn start_session(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO session (id, start_time) VALUES (?1, ?2)",
-            params![self.session_id, self.start_time],
-        )?;
+        let Session { id, start_time } = self.session();
+        let conn = self.conn()?;
+        conn.prepare_cached("INSERT INTO session (id, start_time, heartbeat) VALUES (?1, ?2, ?2)")?
+            .execute(params![id, start_time])?;
+        Ok(())
+    }
+
+    /// Close the current session and start another, e.g. for a restarted
+    /// shell. Buffered history goes to the old one. It's one transaction:
+    /// a crash part way can't end the old session without starting the new.
+    pub fn new_session(&self) -> Result<()> {
+        let previous = self.session_id();
+        let next = Session { id: Uuid::new_v4().to_string(), start_time: Utc::now().timestamp() };
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        tx.prepare_cached("UPDATE session SET end_time = ?1 WHERE id = ?2")?
+            .execute(params![next.start_time, previous])?;
+        tx.prepare_cached("INSERT INTO session (id, start_time, heartbeat) VALUES (?1, ?2, ?2)")?
+            .execute(params![next.id, next.start_time])?;
+        // The input line outlives the shell; so do its draft and `!setenv`.
+        tx.prepare_cached("UPDATE input_draft SET session_id = ?1 WHERE session_id = ?2")?
+            .execute(params![next.id, previous])?;
+        tx.prepare_cached("UPDATE session_env SET session_id = ?1 WHERE session_id = ?2")?
+            .execute(params![next.id, previous])?;
+        tx.commit()?;
+        *self.session.write().unwrap_or_else(|p| p.into_inner()) = next;
+        Ok(())
+    }
+
+    /// Mark this instance alive; call every `HEARTBEAT_INTERVAL`.
+    pub fn heartbeat(&self) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached("UPDATE session SET heartbeat = ?1 WHERE id = ?2")?
+            .execute(params![Utc::now().timestamp(), self.session_id()])?;
         Ok(())
     }
 
+    /// Open sessions that beat within `STALE_AFTER_SECS`, this one included.
+    pub fn active_instances(&self) -> Result<i64> {
+        let conn = self.conn()?;
+        let count = conn
+            .prepare_cached(
+                "SELECT COUNT(*) FROM session
+                 WHERE end_time IS NULL AND COALESCE(heartbeat, start_time) >= ?1",
+            )?
+            .query_row(params![Utc::now().timestamp() - STALE_AFTER_SECS], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Sessions of crashed instances that `open` closed.
+    pub fn stale_sessions_closed(&self) -> usize {
+        self.stale_closed
+    }
+
+    /// What `open` salvaged, when it found the file damaged.
+    pub fn recovery(&self) -> Option<&RecoveryReport> {
+        self.recovery.as_deref()
+    }
+
+    /// `!vault check`: `PRAGMA integrity_check`, up to `recover::CHECK_LIMIT`
+    /// problems, or `["ok"]`. Reads through a connection of its own so
+    /// history keeps being written meanwhile; call it off the UI thread.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let sql = format!("PRAGMA integrity_check({})", recover::CHECK_LIMIT);
+        let check = |conn: &Connection| -> Result<Vec<String>> {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect()
+        };
+        if self.path.is_file() {
+            let conn = Connection::open_with_flags(&self.path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            conn.busy_timeout(BUSY_TIMEOUT)?;
+            return check(&conn);
+        }
+        check(&*self.conn()?)
+    }
+
+    /// `!vault migrate-data`: write a consistent copy of the database to
+    /// `to` (`VACUUM INTO`), then check the copy the way `integrity_check`
+    /// does and that it holds as many commands. The problems found, or
+    /// none.
+    pub fn copy_to(&self, to: &Path) -> Result<Vec<String>> {
+        let count = |conn: &Connection| -> Result<i64> { conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0)) };
+        let expected = {
+            let conn = self.conn()?;
+            conn.execute("VACUUM INTO ?1", params![to.to_string_lossy()])?;
+            count(&conn)?
+        };
+        let copy = Connection::open_with_flags(to, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = copy.prepare(&format!("PRAGMA integrity_check({})", recover::CHECK_LIMIT))?;
+        let mut problems: Vec<String> = stmt.query_map([], |row| row.get(0))?.collect::<Result<_>>()?;
+        problems.retain(|p| p != "ok");
+        let copied = count(&copy)?;
+        if copied != expected {
+            problems.push(format!("the copy holds {} commands, not {}", copied, expected));
+        }
+        Ok(problems)
+    }
+
     /// Mark the current session as ended.
     pub fn close_session(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "UPDATE session SET end_time = ?1 WHERE id = ?2",
-            params![Utc::now().timestamp(), self.session_id],
-        )?;
+        let conn = self.write_conn()?;
+        conn.prepare_cached("UPDATE session SET end_time = ?1 WHERE id = ?2")?
+            .execute(params![Utc::now().timestamp(), self.session_id()])?;
         Ok(())
     }
 
@@ -139,217 +889,1579 @@ impl Vault {
         cwd: &str,
         duration_ms: Option<i64>,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO history (session_id, command, output, exit_code, timestamp, directory, duration_ms)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![
-                self.session_id,
-                cmd,
-                output,
-                exit_code,
-                Utc::now().timestamp(),
-                cwd,
-                duration_ms
-            ],
-        )?;
-        Ok(())
+        self.log_run(cmd, output, exit_code, cwd, duration_ms, None)
     }
 
-    /// Search history for commands matching the query.
-    pub fn search_history(&self, query: &str) -> Result<Vec<CommandRecord>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
-             FROM history
-             WHERE command LIKE ?1
-             ORDER BY timestamp DESC
-             LIMIT 50",
-        )?;
+    /// Log a command execution along with what it used, for runs
+    /// Positronic captured itself (see `usage`).
+    pub fn log_run(
+        &self,
+        cmd: &str,
+        output: Option<&str>,
+        exit_code: Option<i32>,
+        cwd: &str,
+        duration_ms: Option<i64>,
+        usage: Option<ResourceUsage>,
+    ) -> Result<()> {
+        self.writable()?;
+        self.cipher()?;
+        // Notes below match the line as sent; a `%{…}` line may be
+        // stored as typed.
+        let logged = self.logged_form(cmd);
+        if !self.keep_in_history(&logged) {
+            return Ok(());
+        }
+        // A noted `!redo` applies to the next logged command only.
+        let redo_of = self
+            .redo
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .take()
+            .and_then(|(line, id)| (line == cmd).then_some(id));
+        let derived_from = self
+            .derived
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .take()
+            .and_then(|(line, id)| (line == cmd).then_some(id));
+        let row = PendingLog {
+            session_id: self.session_id(),
+            command: logged,
+            output: output.map(str::to_string),
+            exit_code,
+            timestamp: Utc::now().timestamp(),
+            directory: cwd.to_string(),
+            duration_ms,
+            redo_of,
+            derived_from,
+            usage,
+            pane: self.pane,
+        };
+        // Held rows are written by the next flush; otherwise write now.
+        if let Some(row) = self.writes.offer(row) {
+            let mut conn = self.writes.lock_conn();
+            self.writes.flush_locked(&mut conn, Some(row))?;
+        }
+        Ok(())
+    }
 
-        let search_term = format!("%{}%", query);
-        let rows = stmt.query_map(params![search_term], |row| {
-            Ok(CommandRecord {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                command: row.get(2)?,
-                output: row.get(3)?,
-                exit_code: row.get(4)?,
-                timestamp: row.get(5)?,
-                directory: row.get(6)?,
-                duration_ms: row.get(7)?,
-            })
-        })?;
+    /// Apply the `history.ignore_*` rules to a line about to be logged,
+    /// consuming any `note_unlogged` lines.
+    fn keep_in_history(&self, cmd: &str) -> bool {
+        let filter = self.history_filter();
+        let mut history = self.history.lock().unwrap_or_else(|p| p.into_inner());
+        if std::mem::take(&mut history.unlogged).iter().any(|line| line == cmd) {
+            return false;
+        }
+        if !filter.check(cmd, history.last.as_deref()).is_kept() {
+            return false;
+        }
+        history.last = Some(cmd.to_string());
+        true
+    }
 
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
+    /// The `history.ignore_*` settings, reloaded when the config changes.
+    pub fn history_filter(&self) -> Arc<HistoryFilter> {
+        let generation = self.generation.load(Ordering::Acquire);
+        if let Some((loaded, filter)) = &self.history.lock().unwrap_or_else(|p| p.into_inner()).filter {
+            if *loaded == generation {
+                return filter.clone();
+            }
         }
-        Ok(results)
+        // Invalid values are reported by the UI's settings load.
+        let filter = Arc::new(HistoryFilter::load(|key| self.get_config(key).ok().flatten(), &mut Vec::new()));
+        self.history.lock().unwrap_or_else(|p| p.into_inner()).filter = Some((generation, filter.clone()));
+        filter
     }
 
-    /// Get the last N unique commands (deduplicated, most recent first).
-    pub fn recent_unique(&self, limit: usize) -> Result<Vec<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT command FROM history
-             GROUP BY command
-             ORDER BY MAX(timestamp) DESC
-             LIMIT ?1",
-        )?;
-        let rows = stmt.query_map(params![limit as i64], |row| row.get::<_, String>(0))?;
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
+    /// The `time.*` display settings. Invalid values are reported by the
+    /// UI's settings load.
+    pub fn time_settings(&self) -> TimeSettings {
+        TimeSettings::load(|key| self.get_config(key).ok().flatten(), &mut Vec::new())
+    }
+
+    /// The `summary.*` settings; invalid values are reported by the UI's
+    /// settings load.
+    pub fn summary_settings(&self) -> SummarySettings {
+        SummarySettings::load(|key| self.get_config(key).ok().flatten(), &mut Vec::new())
+    }
+
+    /// Keep the next `log_command` from recording any of `lines`: a line
+    /// about to run that the filter can only judge as typed (a leading
+    /// space is gone by the time it is logged), in each form it may be
+    /// logged under.
+    pub fn note_unlogged(&self, lines: &[&str]) {
+        self.history.lock().unwrap_or_else(|p| p.into_inner()).unlogged = lines.iter().map(|l| l.to_string()).collect();
+    }
+
+    /// Have the next `log_command` of `line`, about to run, record `typed`
+    /// instead: a line whose `%{…}` values carry a credential is kept as
+    /// typed (see `capture`).
+    pub fn note_logged_as(&self, line: &str, typed: &str) {
+        self.history.lock().unwrap_or_else(|p| p.into_inner()).logged_as = Some((line.to_string(), typed.to_string()));
+    }
+
+    /// `cmd` as it should be logged, consuming a `note_logged_as` for it.
+    fn logged_form(&self, cmd: &str) -> String {
+        let noted = self.history.lock().unwrap_or_else(|p| p.into_inner()).logged_as.take();
+        match noted {
+            Some((line, typed)) if line.trim() == cmd.trim() => typed,
+            _ => cmd.to_string(),
         }
-        Ok(results)
     }
 
-    /// Get top N most-used commands.
-    pub fn top_commands(&self, limit: usize) -> Result<Vec<TopCommand>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT command, COUNT(*) as cnt FROM history
-             GROUP BY command
-             ORDER BY cnt DESC
+    /// Mark `line`, about to run, as a re-run of history row `id`; the
+    /// next `log_command` of that line records it in `redo_of`.
+    pub fn note_redo(&self, line: &str, id: i64) {
+        *self.redo.lock().unwrap_or_else(|p| p.into_inner()) = Some((line.to_string(), id));
+    }
+
+    /// Mark `line`, about to run, as edited from the command of history
+    /// row `id`; the next `log_command` of that line records it in
+    /// `derived_from`.
+    pub fn note_derived(&self, line: &str, id: i64) {
+        *self.derived.lock().unwrap_or_else(|p| p.into_inner()) = Some((line.to_string(), id));
+    }
+
+    /// The line a `!redo` is about to run, if one is noted.
+    pub fn noted_redo(&self) -> Option<String> {
+        self.redo.lock().unwrap_or_else(|p| p.into_inner()).as_ref().map(|(line, _)| line.clone())
+    }
+
+    /// How many history rows are `!redo` re-runs.
+    pub fn redo_count(&self) -> Result<i64> {
+        let conn = self.conn()?;
+        let count: i64 = conn
+            .prepare_cached("SELECT COUNT(*) FROM history WHERE redo_of IS NOT NULL")?
+            .query_row([], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// The history row a re-run repeated, if `id` is one.
+    pub fn redo_of(&self, id: i64) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT redo_of FROM history WHERE id = ?1")?;
+        match stmt.query_row(params![id], |row| row.get(0)) {
+            Ok(redo_of) => Ok(redo_of),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The history row whose command `id` was edited from, if it was.
+    pub fn derived_from(&self, id: i64) -> Result<Option<i64>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT derived_from FROM history WHERE id = ?1")?;
+        match stmt.query_row(params![id], |row| row.get(0)) {
+            Ok(derived_from) => Ok(derived_from),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Row `id` and the rows it was edited from in turn, oldest first: the
+    /// iteration chain `!chain` shows. Links only point back, so it ends;
+    /// at most `CHAIN_CAP` rows, the newest.
+    pub fn derivation_chain(&self, id: i64) -> Result<Vec<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "WITH RECURSIVE chain(id, depth) AS (
+                 SELECT ?1, 0
+                 UNION ALL
+                 SELECT h.derived_from, c.depth + 1 FROM history h JOIN chain c ON h.id = c.id
+                 WHERE h.derived_from < h.id AND c.depth + 1 < ?2
+             )
+             SELECT h.id, h.session_id, h.command, NULL, h.exit_code, h.timestamp, h.directory, h.duration_ms
+             FROM chain c JOIN history h ON h.id = c.id
+             ORDER BY c.depth DESC",
+        )?;
+        let rows = stmt.query_map(params![id, CHAIN_CAP as i64], record_from_row)?;
+        rows.map(|row| reveal(cipher.as_deref(), row?)).collect()
+    }
+
+    /// The id of the latest logged run, of `command` if given.
+    pub fn last_run_id(&self, command: Option<&str>) -> Result<Option<i64>> {
+        let command = match (command, self.cipher()?) {
+            (Some(c), Some(cipher)) => Some(cipher.seal_command(c.trim())),
+            (Some(c), None) => Some(c.trim().to_string()),
+            (None, _) => None,
+        };
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id FROM history WHERE ?1 IS NULL OR command = ?1 ORDER BY id DESC LIMIT 1",
+        )?;
+        match stmt.query_row(params![command], |row| row.get(0)) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Search history for commands matching the query.
+    /// On an encrypted vault this scans the newest `SCAN_CAP` rows only.
+    pub fn search_history(&self, query: &str) -> Result<Vec<CommandRecord>> {
+        if let Some(cipher) = self.cipher()? {
+            let query = query.to_lowercase();
+            return self.scan_history(&cipher, self.pane, |r| r.command.to_lowercase().contains(&query), 50);
+        }
+        // Rows the search index has taken are found through it; newer
+        // ones by scanning, until the maintenance worker catches up.
+        let conn = self.conn()?;
+        let indexed_through = fts_indexed_through(&conn)?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE id IN (SELECT rowid FROM history_fts WHERE command LIKE ?1
+                          UNION ALL
+                          SELECT id FROM history WHERE id > ?2 AND command LIKE ?1)
+               AND (?3 IS NULL OR pane = ?3)
+             ORDER BY timestamp DESC, id DESC
+             LIMIT 50",
+        )?;
+
+        let search_term = format!("%{}%", query);
+        let rows = stmt.query_map(params![search_term, indexed_through, self.pane], record_from_row)?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// The schema this vault was last migrated to (`schema::SCHEMA_VERSION`
+    /// once this build has opened it).
+    pub fn schema_version(&self) -> Result<i64> {
+        self.conn()?.query_row("PRAGMA user_version", [], |row| row.get(0))
+    }
+
+    /// `schema_version` of the vault at `path` without opening it for
+    /// use: read-only, no migration, no session. `None` when there is no
+    /// vault there yet.
+    pub fn schema_version_at(path: &Path) -> Result<Option<i64>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map(Some)
+    }
+
+    /// History rows the search index hasn't taken yet, or `None` when
+    /// this vault has no index: encrypted, or opened read-only.
+    pub fn fts_pending(&self) -> Result<Option<u64>> {
+        if self.inspect || self.lock_state() != LockState::Plain {
+            return Ok(None);
+        }
+        let conn = self.conn()?;
+        let indexed_through = fts_indexed_through(&conn)?;
+        let pending: i64 =
+            conn.query_row("SELECT COUNT(*) FROM history WHERE id > ?1", params![indexed_through], |row| row.get(0))?;
+        Ok(Some(pending as u64))
+    }
+
+    /// Add up to `batch` more history rows to the search index, oldest
+    /// first. An encrypted vault adds none.
+    pub fn index_history(&self, batch: usize) -> Result<FtsProgress> {
+        self.writable()?;
+        if self.lock_state() != LockState::Plain {
+            return Ok(FtsProgress::default());
+        }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let mut indexed_through = fts_indexed_through(&tx)?;
+        {
+            let mut select = tx.prepare_cached("SELECT id, command FROM history WHERE id > ?1 ORDER BY id LIMIT ?2")?;
+            let rows: Vec<(i64, String)> = select
+                .query_map(params![indexed_through, batch as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_>>()?;
+            let mut insert = tx.prepare_cached("INSERT INTO history_fts (rowid, command) VALUES (?1, ?2)")?;
+            for (id, command) in &rows {
+                insert.execute(params![id, command])?;
+                indexed_through = *id;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO history_fts_state (id, indexed_through) VALUES (1, ?1)",
+            params![indexed_through],
+        )?;
+        let count = |sql: &str| tx.query_row(sql, params![indexed_through], |row| row.get::<_, i64>(0));
+        let progress = FtsProgress {
+            indexed: count("SELECT COUNT(*) FROM history WHERE id <= ?1")? as u64,
+            pending: count("SELECT COUNT(*) FROM history WHERE id > ?1")? as u64,
+        };
+        tx.commit()?;
+        Ok(progress)
+    }
+
+    /// One history row by id.
+    pub fn get_record(&self, id: i64) -> Result<Option<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![id], record_from_row)?;
+        rows.next().transpose()?.map(|r| reveal(cipher.as_deref(), r)).transpose()
+    }
+
+    /// The latest run with stored output, of `command` if given. With
+    /// `before`, only runs logged before that id.
+    pub fn last_with_output(&self, command: Option<&str>, before: Option<i64>) -> Result<Option<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let command = command.map(|c| match &cipher {
+            Some(cipher) => cipher.seal_command(c.trim()),
+            None => c.trim().to_string(),
+        });
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE output IS NOT NULL
+               AND (?1 IS NULL OR command = ?1)
+               AND (?2 IS NULL OR id < ?2)
+             ORDER BY id DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map(params![command, before], record_from_row)?;
+        rows.next().transpose()?.map(|r| reveal(cipher.as_deref(), r)).transpose()
+    }
+
+    /// The latest run that failed (non-zero exit) with stored output.
+    pub fn last_failure(&self) -> Result<Option<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, output, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE output IS NOT NULL AND exit_code IS NOT NULL AND exit_code != 0
+             ORDER BY id DESC
+             LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map([], record_from_row)?;
+        rows.next().transpose()?.map(|r| reveal(cipher.as_deref(), r)).transpose()
+    }
+
+    /// Commands for `!timeline`, oldest first and without their text or
+    /// output: this session's, or those logged in `[from, to)`. At most
+    /// `TIMELINE_CAP`, the newest.
+    pub fn timeline_records(&self, window: Option<(i64, i64)>) -> Result<Vec<CommandRecord>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, '', NULL, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE CASE WHEN ?1 IS NULL THEN session_id = ?3 ELSE timestamp >= ?1 AND timestamp < ?2 END
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?4",
+        )?;
+        let (from, to) = window.unzip();
+        let rows = stmt.query_map(params![from, to, self.session_id(), TIMELINE_CAP as i64], record_from_row)?;
+        let mut records = rows.collect::<Result<Vec<_>>>()?;
+        records.reverse();
+        Ok(records)
+    }
+
+    /// Commands for `!summary`, oldest first, with their text but not
+    /// their output; the window as `timeline_records` takes it. At most
+    /// `SUMMARY_CAP`, the newest.
+    pub fn summary_records(&self, window: Option<(i64, i64)>) -> Result<Vec<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, NULL, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE CASE WHEN ?1 IS NULL THEN session_id = ?3 ELSE timestamp >= ?1 AND timestamp < ?2 END
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?4",
+        )?;
+        let (from, to) = window.unzip();
+        let rows = stmt.query_map(params![from, to, self.session_id(), SUMMARY_CAP as i64], record_from_row)?;
+        let mut records = rows.map(|row| reveal(cipher.as_deref(), row?)).collect::<Result<Vec<_>>>()?;
+        records.reverse();
+        Ok(records)
+    }
+
+    /// The history rows within `radius` ids of `id`, in order, for
+    /// `!history --at`.
+    pub fn history_around(&self, id: i64, radius: i64) -> Result<Vec<CommandRecord>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, NULL, exit_code, timestamp, directory, duration_ms
+             FROM history
+             WHERE id BETWEEN ?1 - ?2 AND ?1 + ?2
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![id, radius], record_from_row)?;
+        rows.map(|row| reveal(cipher.as_deref(), row?)).collect()
+    }
+
+    /// Get the last N unique commands (deduplicated, most recent first).
+    pub fn recent_unique(&self, limit: usize) -> Result<Vec<String>> {
+        Ok(self.recent_commands(limit)?.into_iter().map(|r| r.command).collect())
+    }
+
+    /// The last N unique commands starting with `prefix` (case-sensitive),
+    /// most recent first. Sealed commands can't be compared in SQL, so an
+    /// encrypted vault filters its most recent `SEALED_PREFIX_SCAN` instead.
+    pub fn recent_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<String>> {
+        if self.cipher()?.is_some() {
+            let recent = self.recent_unique(SEALED_PREFIX_SCAN)?;
+            return Ok(recent.into_iter().filter(|c| c.starts_with(prefix)).take(limit).collect());
+        }
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command FROM history
+             WHERE substr(command, 1, length(?1)) = ?1
+             GROUP BY command
+             ORDER BY MAX(timestamp) DESC, MAX(id) DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![prefix, limit as i64], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Delete history rows by id; returns how many were there.
+    pub fn delete_records(&self, ids: &[i64]) -> Result<usize> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        let mut deleted = 0;
+        {
+            let mut delete = tx.prepare_cached("DELETE FROM history WHERE id = ?1")?;
+            for id in ids {
+                deleted += delete.execute(params![id])?;
+            }
+        }
+        tx.commit()?;
+        self.bump_generation();
+        Ok(deleted)
+    }
+
+    /// Delete every logged run of each of `commands`; returns how many
+    /// rows went.
+    pub fn delete_commands<S: AsRef<str>>(&self, commands: &[S]) -> Result<usize> {
+        let cipher = self.cipher()?;
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        let mut deleted = 0;
+        {
+            let mut delete = tx.prepare_cached("DELETE FROM history WHERE command = ?1")?;
+            for command in commands {
+                let command = match &cipher {
+                    Some(cipher) => cipher.seal_command(command.as_ref()),
+                    None => command.as_ref().to_string(),
+                };
+                deleted += delete.execute(params![command])?;
+            }
+        }
+        tx.commit()?;
+        self.bump_generation();
+        Ok(deleted)
+    }
+
+    /// Like `recent_unique`, with each command's last run and run count.
+    pub fn recent_commands(&self, limit: usize) -> Result<Vec<RecentCommand>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, MAX(timestamp), COUNT(*) FROM history
+             WHERE ?2 IS NULL OR pane = ?2
+             GROUP BY command
+             ORDER BY MAX(timestamp) DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64, self.pane], |row| {
+            Ok(RecentCommand { command: row.get(0)?, last_run: row.get(1)?, runs: row.get(2)? })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            let recent = row?;
+            results.push(RecentCommand { command: reveal_text(cipher.as_deref(), recent.command)?, ..recent });
+        }
+        Ok(results)
+    }
+
+    /// Get top N most-used commands.
+    pub fn top_commands(&self, limit: usize) -> Result<Vec<TopCommand>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, COUNT(*) as cnt FROM history
+             GROUP BY command
+             ORDER BY cnt DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(TopCommand {
+                command: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            let top = row?;
+            results.push(TopCommand {
+                command: reveal_text(cipher.as_deref(), top.command)?,
+                ..top
+            });
+        }
+        Ok(results)
+    }
+
+    /// Up to `limit` commands run at least `here::MIN_RUNS` times in
+    /// `directory`, best frecency first, `cd` and builtins left out.
+    pub fn top_for_directory(&self, directory: &str, limit: usize) -> Result<Vec<DirectoryCommand>> {
+        self.top_for_directory_at(directory, limit, Utc::now().timestamp())
+    }
+
+    /// `top_for_directory` as of `now`.
+    pub fn top_for_directory_at(&self, directory: &str, limit: usize, now: i64) -> Result<Vec<DirectoryCommand>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        // Sealed commands compare equal, so this groups them too; the
+        // noise filter needs the text, so it runs after.
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, COUNT(*), MAX(timestamp),
+                    SUM(CASE WHEN ?2 - timestamp < 3600 THEN 4.0
+                             WHEN ?2 - timestamp < 86400 THEN 2.0
+                             WHEN ?2 - timestamp < 604800 THEN 1.0
+                             ELSE 0.5 END) AS score
+             FROM history
+             WHERE directory = ?1
+             GROUP BY command
+             HAVING COUNT(*) >= ?3
+             ORDER BY score DESC, MAX(timestamp) DESC",
+        )?;
+        let mut rows = stmt.query(params![directory, now, here::MIN_RUNS])?;
+        let mut results = Vec::new();
+        while results.len() < limit {
+            let Some(row) = rows.next()? else {
+                break;
+            };
+            let command = reveal_text(cipher.as_deref(), row.get(0)?)?;
+            if here::is_noise(&command) {
+                continue;
+            }
+            results.push(DirectoryCommand { command, runs: row.get(1)?, last_run: row.get(2)?, score: row.get(3)? });
+        }
+        Ok(results)
+    }
+
+    /// Stream every unique command with its use count and last-used timestamp.
+    /// Rows are handed to `f` one at a time instead of being collected.
+    pub fn for_each_command_frequency<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&str, i64, i64),
+    {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, COUNT(*), MAX(timestamp) FROM history
+             GROUP BY command",
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let command = reveal_text(cipher.as_deref(), row.get(0)?)?;
+            f(&command, row.get(1)?, row.get(2)?);
+        }
+        Ok(())
+    }
+
+    /// Count commands in the current session.
+    pub fn session_command_count(&self) -> Result<i64> {
+        let conn = self.conn()?;
+        let count: i64 = conn
+            .prepare_cached("SELECT COUNT(*) FROM history WHERE session_id = ?1")?
+            .query_row(params![self.session_id()], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Get the last command's directory (best guess for CWD).
+    pub fn last_directory(&self) -> Result<Option<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT directory FROM history
+             WHERE session_id = ?1
+             ORDER BY timestamp DESC LIMIT 1",
+        )?;
+        let result = stmt.query_row(params![self.session_id()], |row| row.get::<_, String>(0));
+        match result {
+            Ok(dir) => Ok(Some(dir)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Aliases
+    // ────────────────────────────────────────────────────────────────
+
+    /// Set (create or update) an alias.
+    pub fn set_alias(&self, name: &str, expansion: &str) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO aliases (name, expansion, created_at) VALUES (?1, ?2, ?3)",
+        )?
+        .execute(params![name, expansion, Utc::now().timestamp()])?;
+        self.bump_generation();
+        Ok(())
+    }
+
+    /// Remove an alias.
+    pub fn remove_alias(&self, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn
+            .prepare_cached("DELETE FROM aliases WHERE name = ?1")?
+            .execute(params![name])?;
+        if affected > 0 {
+            self.bump_generation();
+        }
+        Ok(affected > 0)
+    }
+
+    /// Get a specific alias expansion.
+    /// Served from memory; the table is reloaded only after a change.
+    pub fn get_alias(&self, name: &str) -> Result<Option<String>> {
+        self.cached_lookup(&self.aliases, "SELECT name, expansion FROM aliases", name)
+    }
+
+    /// List all aliases.
+    pub fn list_aliases(&self) -> Result<Vec<Alias>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, expansion, created_at FROM aliases ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Alias {
+                name: row.get(0)?,
+                expansion: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Record that `original` was expanded through `alias` into `expanded`.
+    /// Both lines are sealed on an encrypted vault.
+    pub fn log_alias_use(&self, alias: &str, original: &str, expanded: &str) -> Result<()> {
+        let cipher = self.cipher()?;
+        let seal = |text: &str| cipher.as_ref().map_or_else(|| text.to_string(), |c| c.seal(text));
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT INTO alias_uses (session_id, alias, original, expanded, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?
+        .execute(params![self.session_id(), alias, seal(original), seal(expanded), Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Device history
+    // ────────────────────────────────────────────────────────────────
+
+    /// Record a finished `!io console` session (its `id` is ignored).
+    /// Only the last `DEVICE_TRANSCRIPT_CAP` bytes of the transcript are
+    /// kept, sealed on an encrypted vault.
+    pub fn log_device_session(&self, session: &DeviceSession) -> Result<i64> {
+        let transcript = &session.transcript;
+        let mut start = transcript.len().saturating_sub(DEVICE_TRANSCRIPT_CAP);
+        while !transcript.is_char_boundary(start) {
+            start += 1;
+        }
+        let transcript = match self.cipher()? {
+            Some(cipher) => cipher.seal(&transcript[start..]),
+            None => transcript[start..].to_string(),
+        };
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT INTO device_sessions
+                (session_id, port, baud, started_at, ended_at, tx_bytes, rx_bytes, transcript)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?
+        .execute(params![
+            self.session_id(),
+            session.port,
+            session.baud,
+            session.started_at,
+            session.ended_at,
+            session.tx_bytes as i64,
+            session.rx_bytes as i64,
+            transcript
+        ])?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Console sessions, newest first, on `port` if given.
+    pub fn device_sessions(&self, port: Option<&str>, limit: usize) -> Result<Vec<DeviceSession>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, port, baud, started_at, ended_at, tx_bytes, rx_bytes, transcript
+             FROM device_sessions
+             WHERE ?1 IS NULL OR port = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![port, limit as i64], |row| {
+            Ok(DeviceSession {
+                id: row.get(0)?,
+                port: row.get(1)?,
+                baud: row.get(2)?,
+                started_at: row.get(3)?,
+                ended_at: row.get(4)?,
+                tx_bytes: row.get::<_, i64>(5)? as u64,
+                rx_bytes: row.get::<_, i64>(6)? as u64,
+                transcript: row.get(7)?,
+            })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            let mut session = row?;
+            session.transcript = reveal_text(cipher.as_deref(), session.transcript)?;
+            results.push(session);
+        }
+        Ok(results)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Bookmarks
+    // ────────────────────────────────────────────────────────────────
+
+    /// Add a bookmark.
+    pub fn add_bookmark(&self, command: &str, label: Option<&str>) -> Result<i64> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT INTO bookmarks (command, label, created_at) VALUES (?1, ?2, ?3)")?
+            .execute(params![command, label, Utc::now().timestamp()])?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Remove a bookmark by id.
+    pub fn remove_bookmark(&self, id: i64) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn
+            .prepare_cached("DELETE FROM bookmarks WHERE id = ?1")?
+            .execute(params![id])?;
+        Ok(affected > 0)
+    }
+
+    /// List all bookmarks.
+    pub fn list_bookmarks(&self) -> Result<Vec<Bookmark>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, command, label, created_at FROM bookmarks ORDER BY created_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Bookmark {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                label: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Devices
+    // ────────────────────────────────────────────────────────────────
+
+    /// Note that device `id` is on `port` now; returns its name, if any.
+    pub fn record_device(&self, id: &str, port: &str, product: Option<&str>) -> Result<Option<String>> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        tx.prepare_cached(
+            "INSERT INTO devices (id, product, last_port, seen_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET
+                 product = COALESCE(excluded.product, product),
+                 last_port = excluded.last_port,
+                 seen_at = excluded.seen_at",
+        )?
+        .execute(params![id, product, port, Utc::now().timestamp()])?;
+        let name = tx
+            .prepare_cached("SELECT name FROM devices WHERE id = ?1")?
+            .query_row(params![id], |row| row.get(0))?;
+        tx.commit()?;
+        Ok(name)
+    }
+
+    pub fn device(&self, id: &str) -> Result<Option<DeviceRecord>> {
+        self.device_where("id = ?1", id)
+    }
+
+    pub fn device_named(&self, name: &str) -> Result<Option<DeviceRecord>> {
+        self.device_where("name = ?1", name)
+    }
+
+    fn device_where(&self, condition: &str, value: &str) -> Result<Option<DeviceRecord>> {
+        let conn = self.conn()?;
+        let sql = format!("{} WHERE {}", DEVICE_COLUMNS, condition);
+        let device = match conn.prepare_cached(&sql)?.query_row(params![value], device_from_row) {
+            Ok(device) => Some(device),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+        Ok(device)
+    }
+
+    /// Every device seen, named ones first.
+    pub fn list_devices(&self) -> Result<Vec<DeviceRecord>> {
+        let conn = self.conn()?;
+        let sql = format!("{} ORDER BY name IS NULL, name, id", DEVICE_COLUMNS);
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map([], device_from_row)?;
+        rows.collect()
+    }
+
+    /// Name a recorded device; `None` clears the name. Fails if another
+    /// device has the name.
+    pub fn name_device(&self, id: &str, name: Option<&str>) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn
+            .prepare_cached("UPDATE devices SET name = ?1 WHERE id = ?2")?
+            .execute(params![name, id])?;
+        Ok(affected > 0)
+    }
+
+    /// Save the settings given in `defaults`; the rest stay as they were.
+    pub fn set_device_defaults(&self, id: &str, defaults: &DeviceDefaults) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "UPDATE devices SET
+                 baud = COALESCE(?2, baud),
+                 parser = COALESCE(?3, parser),
+                 eol = COALESCE(?4, eol),
+                 reconnect = COALESCE(?5, reconnect)
+             WHERE id = ?1",
+        )?
+        .execute(params![id, defaults.baud, defaults.parser, defaults.eol, defaults.reconnect])?;
+        Ok(())
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Tags
+    // ────────────────────────────────────────────────────────────────
+
+    /// Save `record` under `name`, replacing what the name pointed at.
+    pub fn set_tag(&self, name: &str, record: &CommandRecord) -> Result<()> {
+        let cipher = self.cipher()?;
+        let seal = |text: &str| match &cipher {
+            Some(cipher) => cipher.seal(text),
+            None => text.to_string(),
+        };
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO tags
+                (name, history_id, command, output, exit_code, directory, ran_at, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?
+        .execute(params![
+            name,
+            record.id,
+            seal(&record.command),
+            seal(record.output.as_deref().unwrap_or_default()),
+            record.exit_code,
+            record.directory,
+            record.timestamp,
+            Utc::now().timestamp()
+        ])?;
+        Ok(())
+    }
+
+    /// The tag called `name`.
+    pub fn get_tag(&self, name: &str) -> Result<Option<Tag>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, history_id, command, output, exit_code, directory, ran_at, created_at
+             FROM tags
+             WHERE name = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![name], tag_from_row)?;
+        rows.next().transpose()?.map(|t| reveal_tag(cipher.as_deref(), t)).transpose()
+    }
+
+    /// All tags, newest first.
+    pub fn list_tags(&self) -> Result<Vec<Tag>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, history_id, command, output, exit_code, directory, ran_at, created_at
+             FROM tags
+             ORDER BY created_at DESC, name",
+        )?;
+        let rows = stmt.query_map([], tag_from_row)?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(reveal_tag(cipher.as_deref(), row?)?);
+        }
+        Ok(results)
+    }
+
+    /// Tag names in order, for completion. Works on a locked vault.
+    pub fn tag_names(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT name FROM tags ORDER BY name")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Remove a tag. Returns false if there was none.
+    pub fn remove_tag(&self, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn.prepare_cached("DELETE FROM tags WHERE name = ?1")?.execute(params![name])?;
+        Ok(affected > 0)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Marks
+    // ────────────────────────────────────────────────────────────────
+
+    /// Point mark `name` at history row `history_id`, replacing what the
+    /// name pointed at.
+    pub fn set_mark(&self, name: &str, history_id: i64) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT OR REPLACE INTO marks (name, history_id, created_at) VALUES (?1, ?2, ?3)")?
+            .execute(params![name, history_id, Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    /// All saved marks, oldest first.
+    pub fn list_marks(&self) -> Result<Vec<SavedMark>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT name, history_id, created_at FROM marks ORDER BY created_at, name")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SavedMark { name: row.get(0)?, history_id: row.get(1)?, created_at: row.get(2)? })
+        })?;
+        rows.collect()
+    }
+
+    /// Remove a saved mark. Returns false if there was none.
+    pub fn remove_mark(&self, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn.prepare_cached("DELETE FROM marks WHERE name = ?1")?.execute(params![name])?;
+        Ok(affected > 0)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Macros
+    // ────────────────────────────────────────────────────────────────
+
+    /// Save macro `name`, replacing one of the same name.
+    pub fn set_macro(&self, name: &str, steps: &str) -> Result<()> {
+        let steps = match self.cipher()? {
+            Some(cipher) => cipher.seal(steps),
+            None => steps.to_string(),
+        };
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT OR REPLACE INTO macros (name, steps, created_at) VALUES (?1, ?2, ?3)")?
+            .execute(params![name, steps, Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    /// The macro called `name`.
+    pub fn get_macro(&self, name: &str) -> Result<Option<SavedMacro>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT name, steps, created_at FROM macros WHERE name = ?1")?;
+        let mut rows = stmt.query_map(params![name], macro_from_row)?;
+        rows.next().transpose()?.map(|m| reveal_macro(cipher.as_deref(), m)).transpose()
+    }
+
+    /// All saved macros, by name.
+    pub fn list_macros(&self) -> Result<Vec<SavedMacro>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT name, steps, created_at FROM macros ORDER BY name")?;
+        let rows = stmt.query_map([], macro_from_row)?;
+        rows.map(|m| reveal_macro(cipher.as_deref(), m?)).collect()
+    }
+
+    /// Remove a macro. Returns false if there was none.
+    pub fn remove_macro(&self, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn.prepare_cached("DELETE FROM macros WHERE name = ?1")?.execute(params![name])?;
+        Ok(affected > 0)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Native command timings
+    // ────────────────────────────────────────────────────────────────
+
+    /// Record that native command `name` ran for `duration`, keeping the
+    /// newest `NATIVE_TIMINGS_KEPT` runs.
+    pub fn log_native_timing(&self, name: &str, duration: Duration, ok: bool) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT INTO native_timings (name, duration_ms, ok, ran_at) VALUES (?1, ?2, ?3, ?4)")?
+            .execute(params![name, duration.as_millis() as i64, ok, Utc::now().timestamp()])?;
+        let id = conn.last_insert_rowid();
+        conn.prepare_cached("DELETE FROM native_timings WHERE id <= ?1")?
+            .execute(params![id - NATIVE_TIMINGS_KEPT])?;
+        Ok(())
+    }
+
+    /// The newest `limit` native command runs, newest first.
+    pub fn native_timings(&self, limit: usize) -> Result<Vec<NativeTiming>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT name, duration_ms, ok, ran_at FROM native_timings ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(NativeTiming { name: row.get(0)?, duration_ms: row.get(1)?, ok: row.get(2)?, ran_at: row.get(3)? })
+        })?;
+        rows.collect()
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Hooks
+    // ────────────────────────────────────────────────────────────────
+
+    /// Store a `!hook` rule; returns its id.
+    pub fn add_hook(&self, spec: &HookSpec) -> Result<i64> {
+        let env: Vec<String> = spec.env.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT INTO hooks (pattern, env, before, after, created_at) VALUES (?1, ?2, ?3, ?4, ?5)")?
+            .execute(params![spec.pattern, env.join("\n"), spec.before, spec.after, Utc::now().timestamp()])?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Remove a hook rule. Returns false if there was none.
+    pub fn remove_hook(&self, id: i64) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn.prepare_cached("DELETE FROM hooks WHERE id = ?1")?.execute(params![id])?;
+        Ok(affected > 0)
+    }
+
+    /// All hook rules, oldest first (the order they apply in).
+    pub fn list_hooks(&self) -> Result<Vec<HookRule>> {
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT id, pattern, env, before, after, created_at FROM hooks ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            let env: String = row.get(2)?;
+            Ok(HookRule {
+                id: row.get(0)?,
+                spec: HookSpec {
+                    pattern: row.get(1)?,
+                    env: env
+                        .lines()
+                        .filter_map(|line| line.split_once('='))
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                    before: row.get(3)?,
+                    after: row.get(4)?,
+                },
+                created_at: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Environment overrides
+    // ────────────────────────────────────────────────────────────────
+
+    /// Set a `!setenv` override, replacing one of the same name and scope.
+    pub fn set_env(&self, name: &str, value: &str, scope: EnvScope) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO session_env (session_id, name, value, set_at) VALUES (?1, ?2, ?3, ?4)",
+        )?
+        .execute(params![self.env_scope_id(scope), name, value, Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    /// The overrides that apply here: global ones, then this session's,
+    /// each by name.
+    pub fn list_env(&self) -> Result<Vec<EnvOverride>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT session_id, name, value, set_at FROM session_env
+             WHERE session_id IN ('', ?1) ORDER BY session_id <> '', name",
+        )?;
+        let rows = stmt.query_map(params![self.session_id()], |row| {
+            let session_id: String = row.get(0)?;
+            Ok(EnvOverride {
+                name: row.get(1)?,
+                value: row.get(2)?,
+                scope: if session_id.is_empty() { EnvScope::Global } else { EnvScope::Session },
+                set_at: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Remove `name` from both scopes. Returns how many went.
+    pub fn remove_env(&self, name: &str) -> Result<usize> {
+        let conn = self.write_conn()?;
+        let removed = conn
+            .prepare_cached("DELETE FROM session_env WHERE session_id IN ('', ?1) AND name = ?2")?
+            .execute(params![self.session_id(), name])?;
+        Ok(removed)
+    }
+
+    /// Remove every override that applies here. Returns how many went.
+    pub fn clear_env(&self) -> Result<usize> {
+        let conn = self.write_conn()?;
+        let removed = conn
+            .prepare_cached("DELETE FROM session_env WHERE session_id IN ('', ?1)")?
+            .execute(params![self.session_id()])?;
+        Ok(removed)
+    }
+
+    fn env_scope_id(&self, scope: EnvScope) -> String {
+        match scope {
+            EnvScope::Session => self.session_id(),
+            EnvScope::Global => String::new(),
+        }
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Command queue
+    // ────────────────────────────────────────────────────────────────
+
+    /// Append `command` to the `!queue`. Returns its number, or `None`
+    /// when the queue already holds `MAX_QUEUED`.
+    pub fn queue_add(&self, command: &str) -> Result<Option<usize>> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let queued: i64 = tx.query_row("SELECT COUNT(*) FROM command_queue", [], |row| row.get(0))?;
+        if queued as usize >= MAX_QUEUED {
+            return Ok(None);
+        }
+        tx.execute(
+            "INSERT INTO command_queue (command, added_at) VALUES (?1, ?2)",
+            params![command, Utc::now().timestamp()],
+        )?;
+        tx.commit()?;
+        Ok(Some(queued as usize + 1))
+    }
+
+    /// The queued commands, in the order they run.
+    pub fn queue(&self) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT command FROM command_queue ORDER BY id")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Remove entry `n` (from 1) and return it. The numbers after it
+    /// shift, so a stopped run's place goes too.
+    pub fn queue_remove(&self, n: usize) -> Result<Option<String>> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let entry = tx.query_row(
+            "SELECT id, command FROM command_queue ORDER BY id LIMIT 1 OFFSET ?1",
+            params![n as i64 - 1],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+        );
+        let (id, command) = match entry {
+            Ok(entry) => entry,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        tx.execute("DELETE FROM command_queue WHERE id = ?1", params![id])?;
+        tx.execute("DELETE FROM queue_run", [])?;
+        tx.commit()?;
+        Ok(Some(command))
+    }
+
+    /// Empty the queue and forget a stopped run. Returns how many went.
+    pub fn queue_clear(&self) -> Result<usize> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        let removed = tx.execute("DELETE FROM command_queue", [])?;
+        tx.execute("DELETE FROM queue_run", [])?;
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Save where a run is up to, or with `None` that there is no run.
+    pub fn save_queue_progress(&self, progress: Option<&QueueProgress>) -> Result<()> {
+        let conn = self.write_conn()?;
+        let Some(progress) = progress else {
+            conn.execute("DELETE FROM queue_run", [])?;
+            return Ok(());
+        };
+        let failed: Vec<String> = progress.failed.iter().map(usize::to_string).collect();
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO queue_run (id, next, keep_going, failed, saved_at) VALUES (1, ?1, ?2, ?3, ?4)",
+        )?
+        .execute(params![progress.next as i64, progress.keep_going, failed.join(","), Utc::now().timestamp()])?;
+        Ok(())
+    }
+
+    /// The saved place of a run that didn't finish, if any.
+    pub fn queue_progress(&self) -> Result<Option<QueueProgress>> {
+        let conn = self.conn()?;
+        let progress = conn.query_row("SELECT next, keep_going, failed FROM queue_run WHERE id = 1", [], |row| {
+            let failed: String = row.get(2)?;
+            Ok(QueueProgress {
+                next: row.get::<_, i64>(0)?.max(0) as usize,
+                keep_going: row.get(1)?,
+                failed: failed.split(',').filter_map(|i| i.parse().ok()).collect(),
+            })
+        });
+        match progress {
+            Ok(progress) => Ok(Some(progress)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Disabled items
+    // ────────────────────────────────────────────────────────────────
+
+    /// Turn off an alias, hook or theme (`!safe disable`); the item itself
+    /// stays as it is. Returns false if it was already off.
+    pub fn disable_item(&self, kind: DisabledKind, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let added = conn
+            .prepare_cached("INSERT OR IGNORE INTO disabled_items (kind, name, disabled_at) VALUES (?1, ?2, ?3)")?
+            .execute(params![kind.label(), name, Utc::now().timestamp()])?;
+        Ok(added > 0)
+    }
+
+    /// Turn a disabled item back on. Returns false if it wasn't off.
+    pub fn enable_item(&self, kind: DisabledKind, name: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let removed = conn
+            .prepare_cached("DELETE FROM disabled_items WHERE kind = ?1 AND name = ?2")?
+            .execute(params![kind.label(), name])?;
+        Ok(removed > 0)
+    }
+
+    pub fn is_disabled(&self, kind: DisabledKind, name: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let disabled = conn
+            .prepare_cached("SELECT COUNT(*) > 0 FROM disabled_items WHERE kind = ?1 AND name = ?2")?
+            .query_row(params![kind.label(), name], |row| row.get(0))?;
+        Ok(disabled)
+    }
+
+    /// Everything disabled, by kind and name.
+    pub fn disabled_items(&self) -> Result<Vec<DisabledItem>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT kind, name, disabled_at FROM disabled_items ORDER BY kind, name")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (kind, name, disabled_at) = row?;
+            // A kind this version doesn't know is left for the one that does.
+            if let Some(kind) = DisabledKind::parse(&kind) {
+                items.push(DisabledItem { kind, name, disabled_at });
+            }
+        }
+        Ok(items)
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Test runs
+    // ────────────────────────────────────────────────────────────────
+
+    /// Log the report of a test run `command` made in `directory`.
+    pub fn log_test_run(&self, command: &str, directory: &str, report: &TestReport) -> Result<()> {
+        let cipher = self.cipher()?;
+        let seal = |text: &str| match &cipher {
+            Some(cipher) => cipher.seal(text),
+            None => text.to_string(),
+        };
+        let failures = serde_json::to_string(&report.failures).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT INTO test_runs
+                (session_id, framework, command, directory, passed, failed, ignored, failures, ran_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?
+        .execute(params![
+            self.session_id(),
+            report.framework,
+            seal(command),
+            directory,
+            report.passed as i64,
+            report.failed as i64,
+            report.ignored as i64,
+            seal(&failures),
+            Utc::now().timestamp()
+        ])?;
+        Ok(())
+    }
+
+    /// The newest `limit` test runs, newest first.
+    pub fn test_runs(&self, limit: usize) -> Result<Vec<TestRun>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT framework, command, directory, passed, failed, ignored, failures, ran_at
+             FROM test_runs
+             ORDER BY ran_at DESC, id DESC
              LIMIT ?1",
         )?;
         let rows = stmt.query_map(params![limit as i64], |row| {
-            Ok(TopCommand {
-                command: row.get(0)?,
-                count: row.get(1)?,
-            })
+            Ok((
+                TestRun {
+                    command: row.get(1)?,
+                    directory: row.get(2)?,
+                    report: TestReport {
+                        framework: row.get(0)?,
+                        passed: row.get::<_, i64>(3)? as usize,
+                        failed: row.get::<_, i64>(4)? as usize,
+                        ignored: row.get::<_, i64>(5)? as usize,
+                        failures: Vec::new(),
+                    },
+                    ran_at: row.get(7)?,
+                },
+                row.get::<_, String>(6)?,
+            ))
         })?;
-        let mut results = Vec::new();
+        let mut runs = Vec::new();
         for row in rows {
-            results.push(row?);
+            let (mut run, failures) = row?;
+            run.command = reveal_text(cipher.as_deref(), run.command)?;
+            // Failures that don't read back leave the counts.
+            run.report.failures = serde_json::from_str(&reveal_text(cipher.as_deref(), failures)?).unwrap_or_default();
+            runs.push(run);
         }
-        Ok(results)
+        Ok(runs)
     }
 
-    /// Count commands in the current session.
-    pub fn session_command_count(&self) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
-        let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM history WHERE session_id = ?1",
-            params![self.session_id],
-            |row| row.get(0),
-        )?;
-        Ok(count)
+    pub fn last_test_run(&self) -> Result<Option<TestRun>> {
+        Ok(self.test_runs(1)?.pop())
     }
 
-    /// Get the last command's directory (best guess for CWD).
-    pub fn last_directory(&self) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
-            "SELECT directory FROM history
-             WHERE session_id = ?1
-             ORDER BY timestamp DESC LIMIT 1",
-            params![self.session_id],
-            |row| row.get::<_, String>(0),
-        );
-        match result {
-            Ok(dir) => Ok(Some(dir)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e),
+    // ────────────────────────────────────────────────────────────────
+    // Suggestions
+    // ────────────────────────────────────────────────────────────────
+
+    /// Record suggestions shown to the user in this session.
+    pub fn log_suggestions<S: AsRef<str>>(&self, commands: &[S]) -> Result<()> {
+        let mut conn = self.write_conn()?;
+        let now = Utc::now().timestamp();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO suggestions (session_id, command, offered_at) VALUES (?1, ?2, ?3)",
+            )?;
+            for command in commands {
+                stmt.execute(params![self.session_id(), command.as_ref(), now])?;
+            }
         }
+        tx.commit()
+    }
+
+    /// Mark the most recent unaccepted offer of `command` in this session as
+    /// accepted. Returns false if it was never offered.
+    pub fn accept_suggestion(&self, command: &str) -> Result<bool> {
+        let conn = self.write_conn()?;
+        let affected = conn
+            .prepare_cached(
+                "UPDATE suggestions SET accepted_at = ?1
+                 WHERE id = (
+                     SELECT id FROM suggestions
+                     WHERE session_id = ?2 AND command = ?3 AND accepted_at IS NULL
+                     ORDER BY id DESC LIMIT 1
+                 )",
+            )?
+            .execute(params![Utc::now().timestamp(), self.session_id(), command])?;
+        Ok(affected > 0)
+    }
+
+    /// Offered/accepted totals across all sessions.
+    pub fn suggestion_counts(&self) -> Result<SuggestionCounts> {
+        let conn = self.conn()?;
+        let counts = conn
+            .prepare_cached("SELECT COUNT(*), COUNT(accepted_at) FROM suggestions")?
+            .query_row([], |row| {
+                Ok(SuggestionCounts {
+                    offered: row.get(0)?,
+                    accepted: row.get(1)?,
+                })
+            })?;
+        Ok(counts)
     }
 
     // ────────────────────────────────────────────────────────────────
-    // Aliases
+    // Input draft
     // ────────────────────────────────────────────────────────────────
 
-    /// Set (create or update) an alias.
-    pub fn set_alias(&self, name: &str, expansion: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO aliases (name, expansion, created_at) VALUES (?1, ?2, ?3)",
-            params![name, expansion, Utc::now().timestamp()],
-        )?;
+    /// Checkpoint the unsent input line for this session, replacing any
+    /// earlier draft.
+    pub fn save_draft(&self, text: &str) -> Result<()> {
+        let text = match self.cipher()? {
+            Some(cipher) => cipher.seal(text),
+            None => text.to_string(),
+        };
+        let conn = self.write_conn()?;
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO input_draft (id, session_id, text, saved_at) VALUES (1, ?1, ?2, ?3)",
+        )?
+        .execute(params![self.session_id(), text, Utc::now().timestamp()])?;
         Ok(())
     }
 
-    /// Remove an alias.
-    pub fn remove_alias(&self, name: &str) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
-        let affected = conn.execute(
-            "DELETE FROM aliases WHERE name = ?1",
-            params![name],
-        )?;
-        Ok(affected > 0)
-    }
-
-    /// Get a specific alias expansion.
-    pub fn get_alias(&self, name: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
-            "SELECT expansion FROM aliases WHERE name = ?1",
-            params![name],
-            |row| row.get::<_, String>(0),
-        );
-        match result {
-            Ok(exp) => Ok(Some(exp)),
+    /// The checkpointed draft, whichever session saved it.
+    pub fn draft(&self) -> Result<Option<Draft>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let draft = conn.query_row("SELECT session_id, text, saved_at FROM input_draft WHERE id = 1", [], |row| {
+            Ok(Draft { session_id: row.get(0)?, text: row.get(1)?, saved_at: row.get(2)? })
+        });
+        match draft {
+            Ok(mut draft) => {
+                draft.text = reveal_text(cipher.as_deref(), draft.text)?;
+                Ok(Some(draft))
+            }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e),
         }
     }
 
-    /// List all aliases.
-    pub fn list_aliases(&self) -> Result<Vec<Alias>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT name, expansion, created_at FROM aliases ORDER BY name",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(Alias {
-                name: row.get(0)?,
-                expansion: row.get(1)?,
-                created_at: row.get(2)?,
-            })
-        })?;
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
-        }
-        Ok(results)
+    /// Drop this session's draft: it was sent, cleared or the session
+    /// closed cleanly. Another session's draft is left alone.
+    pub fn clear_draft(&self) -> Result<()> {
+        let conn = self.write_conn()?;
+        conn.prepare_cached("DELETE FROM input_draft WHERE session_id = ?1")?
+            .execute(params![self.session_id()])?;
+        Ok(())
+    }
+
+    /// Drop the draft whoever saved it, e.g. once it has been restored.
+    pub fn discard_draft(&self) -> Result<()> {
+        self.write_conn()?.execute("DELETE FROM input_draft", [])?;
+        Ok(())
     }
 
     // ────────────────────────────────────────────────────────────────
-    // Bookmarks
+    // Shell commands
     // ────────────────────────────────────────────────────────────────
 
-    /// Add a bookmark.
-    pub fn add_bookmark(&self, command: &str, label: Option<&str>) -> Result<i64> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO bookmarks (command, label, created_at) VALUES (?1, ?2, ?3)",
-            params![command, label, Utc::now().timestamp()],
-        )?;
-        Ok(conn.last_insert_rowid())
+    /// Commands discovered for `shell` (a `ShellKind` label), empty if it
+    /// was never asked.
+    pub fn shell_commands(&self, shell: &str) -> Result<Vec<String>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached("SELECT name FROM shell_commands WHERE shell = ?1 ORDER BY name")?;
+        let rows = stmt.query_map(params![shell], |row| row.get(0))?;
+        rows.collect()
     }
 
-    /// Remove a bookmark by id.
-    pub fn remove_bookmark(&self, id: i64) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
-        let affected = conn.execute(
-            "DELETE FROM bookmarks WHERE id = ?1",
-            params![id],
+    /// Replace the commands discovered for `shell`.
+    pub fn set_shell_commands<S: AsRef<str>>(&self, shell: &str, names: &[S]) -> Result<()> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM shell_commands WHERE shell = ?1", params![shell])?;
+        {
+            let mut stmt = tx.prepare_cached("INSERT OR IGNORE INTO shell_commands (shell, name) VALUES (?1, ?2)")?;
+            for name in names {
+                stmt.execute(params![shell, name.as_ref()])?;
+            }
+        }
+        tx.commit()
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Timing
+    // ────────────────────────────────────────────────────────────────
+
+    /// Count, mean and p95 run time of timed commands starting with
+    /// `command_prefix` (empty for all), grouped by first token and
+    /// slowest mean first.
+    pub fn duration_stats(&self, command_prefix: &str) -> Result<Vec<DurationStats>> {
+        if let Some(cipher) = self.cipher()? {
+            let prefix = command_prefix.trim_start().to_ascii_lowercase();
+            let runs = self.scan_history(
+                &cipher,
+                None,
+                |r| r.duration_ms.is_some() && r.command.trim_start().to_ascii_lowercase().starts_with(&prefix),
+                usize::MAX,
+            )?;
+            return Ok(timing::summarize(
+                runs.iter().map(|r| (timing::program(&r.command), r.duration_ms.unwrap_or(0))),
+            ));
+        }
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "WITH runs AS (
+                 SELECT CASE WHEN instr(ltrim(command), ' ') > 0
+                             THEN substr(ltrim(command), 1, instr(ltrim(command), ' ') - 1)
+                             ELSE ltrim(command) END AS program,
+                        duration_ms
+                 FROM history
+                 WHERE duration_ms IS NOT NULL AND ltrim(command) LIKE ?1 ESCAPE '\\'
+             ),
+             ranked AS (
+                 SELECT program, duration_ms,
+                        ROW_NUMBER() OVER (PARTITION BY program ORDER BY duration_ms) AS rn,
+                        COUNT(*) OVER (PARTITION BY program) AS n
+                 FROM runs
+             )
+             SELECT program, n, AVG(duration_ms),
+                    MIN(CASE WHEN rn * 100 >= n * 95 THEN duration_ms END)
+             FROM ranked
+             GROUP BY program
+             ORDER BY AVG(duration_ms) DESC, program",
         )?;
-        Ok(affected > 0)
+        let pattern = format!("{}%", escape_like(command_prefix.trim_start()));
+        let rows = stmt.query_map(params![pattern], |row| {
+            Ok(DurationStats {
+                program: row.get(0)?,
+                count: row.get(1)?,
+                mean_ms: row.get(2)?,
+                p95_ms: row.get(3)?,
+            })
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
     }
 
-    /// List all bookmarks.
-    pub fn list_bookmarks(&self) -> Result<Vec<Bookmark>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, command, label, created_at FROM bookmarks ORDER BY created_at DESC",
+    /// The last `limit` timed runs of `command` (exactly, or followed by
+    /// more arguments), oldest first.
+    pub fn recent_runs(&self, command: &str, limit: usize) -> Result<Vec<TimedRun>> {
+        if let Some(cipher) = self.cipher()? {
+            let command = command.trim();
+            let prefix = format!("{} ", command).to_ascii_lowercase();
+            let matches = |r: &CommandRecord| {
+                r.command == command || r.command.to_ascii_lowercase().starts_with(&prefix)
+            };
+            let mut runs: Vec<TimedRun> = self
+                .scan_history(&cipher, None, |r| r.duration_ms.is_some() && matches(r), limit)?
+                .into_iter()
+                .map(|r| TimedRun { timestamp: r.timestamp, duration_ms: r.duration_ms.unwrap_or(0) })
+                .collect();
+            runs.reverse();
+            return Ok(runs);
+        }
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT timestamp, duration_ms FROM (
+                 SELECT id, timestamp, duration_ms FROM history
+                 WHERE duration_ms IS NOT NULL
+                   AND (command = ?1 OR command LIKE ?2 ESCAPE '\\')
+                 ORDER BY timestamp DESC, id DESC
+                 LIMIT ?3
+             )
+             ORDER BY timestamp ASC, id ASC",
         )?;
-        let rows = stmt.query_map([], |row| {
-            Ok(Bookmark {
-                id: row.get(0)?,
-                command: row.get(1)?,
-                label: row.get(2)?,
-                created_at: row.get(3)?,
+        let command = command.trim();
+        let pattern = format!("{} %", escape_like(command));
+        let rows = stmt.query_map(params![command, pattern, limit as i64], |row| {
+            Ok(TimedRun {
+                timestamp: row.get(0)?,
+                duration_ms: row.get(1)?,
             })
         })?;
         let mut results = Vec::new();
@@ -359,13 +2471,39 @@ impl Vault {
         Ok(results)
     }
 
+    /// CPU time and peak memory of captured runs, grouped by first token
+    /// and most total CPU first. Only runs logged with `log_run` count.
+    pub fn resource_stats(&self) -> Result<Vec<ResourceStats>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, cpu_user_ms, cpu_sys_ms, max_rss_bytes FROM history
+             WHERE cpu_user_ms IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let usage = ResourceUsage {
+                user_ms: row.get::<_, i64>(1)?.max(0) as u64,
+                sys_ms: row.get::<_, Option<i64>>(2)?.unwrap_or(0).max(0) as u64,
+                max_rss_bytes: row.get::<_, Option<i64>>(3)?.unwrap_or(0).max(0) as u64,
+            };
+            Ok((row.get::<_, String>(0)?, usage))
+        })?;
+        let mut runs = Vec::new();
+        for row in rows {
+            let (command, usage) = row?;
+            let command = reveal_text(cipher.as_deref(), command)?;
+            runs.push((timing::program(&command), usage));
+        }
+        Ok(timing::summarize_resources(runs))
+    }
+
     // ────────────────────────────────────────────────────────────────
     // Statistics
     // ────────────────────────────────────────────────────────────────
 
     /// Comprehensive stats about the vault.
     pub fn stats(&self) -> Result<VaultStats> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.conn()?;
 
         let total_commands: i64 = conn.query_row(
             "SELECT COUNT(*) FROM history", [], |row| row.get(0),
@@ -373,7 +2511,7 @@ impl Vault {
 
         let session_commands: i64 = conn.query_row(
             "SELECT COUNT(*) FROM history WHERE session_id = ?1",
-            params![self.session_id],
+            params![self.session_id()],
             |row| row.get(0),
         )?;
 
@@ -393,6 +2531,13 @@ impl Vault {
             "SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0),
         )?;
 
+        let active_instances: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM session
+             WHERE end_time IS NULL AND COALESCE(heartbeat, start_time) >= ?1",
+            params![Utc::now().timestamp() - STALE_AFTER_SECS],
+            |row| row.get(0),
+        )?;
+
         let earliest_timestamp: Option<i64> = conn.query_row(
             "SELECT MIN(timestamp) FROM history", [], |row| row.get(0),
         ).ok();
@@ -414,55 +2559,705 @@ impl Vault {
             bookmark_count,
             earliest_timestamp,
             db_size_bytes: page_count * page_size,
+            active_instances,
         })
     }
 
+    /// `!stats export`: usage analytics as of now, days and hours in
+    /// local time. Every section is one aggregate query; run it off the
+    /// async runtime.
+    pub fn analytics_report(&self) -> Result<AnalyticsReport> {
+        let offset = Local::now().offset().local_minus_utc() as i64;
+        self.analytics_report_at(Utc::now().timestamp(), offset)
+    }
+
+    /// `analytics_report` as of `now`, bucketed `utc_offset_secs` from UTC.
+    pub fn analytics_report_at(&self, now: i64, utc_offset_secs: i64) -> Result<AnalyticsReport> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        analytics::build(&conn, cipher.as_deref(), now, utc_offset_secs)
+    }
+
+    /// `!stats heatmap`: commands per quarter-hour slot from `since`
+    /// (all history if `None`), those starting with `prefix` if given.
+    /// One grouped query; run it off the async runtime.
+    pub fn command_slots(&self, prefix: Option<&str>, since: Option<i64>) -> Result<Vec<Slot>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        analytics::command_slots(&conn, cipher.as_deref(), prefix, since)
+    }
+
     // ────────────────────────────────────────────────────────────────
     // Export
     // ────────────────────────────────────────────────────────────────
 
     /// Export history as lines of text suitable for a shell history file.
     pub fn export_history(&self, limit: usize) -> Result<Vec<String>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT command, timestamp FROM history ORDER BY timestamp ASC LIMIT ?1",
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT command, timestamp FROM history WHERE ?2 IS NULL OR pane = ?2 ORDER BY timestamp ASC LIMIT ?1",
         )?;
-        let rows = stmt.query_map(params![limit as i64], |row| {
-            let cmd: String = row.get(0)?;
-            let ts: i64 = row.get(1)?;
-            Ok(format!("# {}\n{}", ts, cmd))
+        let rows = stmt.query_map(params![limit as i64, self.pane], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
         })?;
         let mut lines = Vec::new();
         for row in rows {
-            lines.push(row?);
+            let (cmd, ts) = row?;
+            lines.push(format!("# {}\n{}", ts, reveal_text(cipher.as_deref(), cmd)?));
         }
         Ok(lines)
     }
 
+    // ────────────────────────────────────────────────────────────────
+    // Sync
+    // ────────────────────────────────────────────────────────────────
+
+    /// The id this vault's entries carry in sync bundles, made on first
+    /// use. Read from the base config: a profile can't change it.
+    pub fn machine_id(&self) -> Result<String> {
+        if let Some(id) = self.get_base_config(MACHINE_ID_KEY)? {
+            return Ok(id);
+        }
+        let conn = self.write_conn()?;
+        // Another instance may have just made one; keep the first.
+        conn.prepare_cached("INSERT OR IGNORE INTO config (key, value) VALUES (?1, ?2)")?
+            .execute(params![MACHINE_ID_KEY, Uuid::new_v4().to_string()])?;
+        let id = conn
+            .prepare_cached("SELECT value FROM config WHERE key = ?1")?
+            .query_row(params![MACHINE_ID_KEY], |row| row.get(0))?;
+        self.bump_generation();
+        Ok(id)
+    }
+
+    /// Every history row, oldest first and without output, with the
+    /// machine it was imported from (`None`: logged here).
+    pub fn synced_history(&self) -> Result<Vec<(CommandRecord, Option<String>)>> {
+        let cipher = self.cipher()?;
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, session_id, command, NULL, exit_code, timestamp, directory, duration_ms, origin
+             FROM history
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((record_from_row(row)?, row.get::<_, Option<String>>(8)?)))?;
+        let mut results = Vec::new();
+        for row in rows {
+            let (record, origin) = row?;
+            results.push((reveal(cipher.as_deref(), record)?, origin));
+        }
+        Ok(results)
+    }
+
+    /// Write an import (`sync::plan`) in one transaction. Imported rows
+    /// keep their session, which is recorded as ended.
+    pub fn apply_sync(&self, plan: &SyncPlan) -> Result<()> {
+        self.apply_sync_with(plan, |_| true).map(|_| ())
+    }
+
+    /// `apply_sync`, calling `each(rows written)` after each history row.
+    /// When it answers false the transaction is rolled back, nothing is
+    /// imported, and the result is `Ok(false)`.
+    pub fn apply_sync_with(&self, plan: &SyncPlan, mut each: impl FnMut(usize) -> bool) -> Result<bool> {
+        let cipher = self.cipher()?;
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        {
+            let mut session = tx.prepare_cached(
+                "INSERT INTO session (id, start_time, end_time, heartbeat) VALUES (?1, ?2, ?2, ?2)
+                 ON CONFLICT(id) DO UPDATE SET
+                     start_time = MIN(start_time, excluded.start_time),
+                     end_time = MAX(COALESCE(end_time, excluded.end_time), excluded.end_time)",
+            )?;
+            let mut history = tx.prepare_cached(
+                "INSERT INTO history (session_id, command, exit_code, timestamp, directory, duration_ms, origin)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for (n, row) in plan.history.iter().enumerate() {
+                let command = match &cipher {
+                    Some(cipher) => cipher.seal_command(&row.command),
+                    None => row.command.clone(),
+                };
+                session.execute(params![row.session, row.timestamp])?;
+                history.execute(params![
+                    row.session,
+                    command,
+                    row.exit_code,
+                    row.timestamp,
+                    row.directory,
+                    row.duration_ms,
+                    row.machine
+                ])?;
+                if !each(n + 1) {
+                    return Ok(false);
+                }
+            }
+            let mut alias =
+                tx.prepare_cached("INSERT OR REPLACE INTO aliases (name, expansion, created_at) VALUES (?1, ?2, ?3)")?;
+            for row in &plan.aliases {
+                alias.execute(params![row.name, row.expansion, row.updated_at])?;
+            }
+            let mut add = tx.prepare_cached("INSERT INTO bookmarks (command, label, created_at) VALUES (?1, ?2, ?3)")?;
+            let mut relabel = tx.prepare_cached("UPDATE bookmarks SET label = ?1, created_at = ?2 WHERE id = ?3")?;
+            for (id, row) in &plan.bookmarks {
+                match id {
+                    Some(id) => relabel.execute(params![row.label, row.created_at, id])?,
+                    None => add.execute(params![row.command, row.label, row.created_at])?,
+                };
+            }
+        }
+        tx.commit()?;
+        self.bump_generation();
+        Ok(true)
+    }
+
     // ────────────────────────────────────────────────────────────────
     // Config (key-value settings)
     // ────────────────────────────────────────────────────────────────
 
     pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
-            params![key, value],
-        )?;
+        let conn = self.write_conn()?;
+        conn.prepare_cached("INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)")?
+            .execute(params![key, value])?;
+        self.bump_generation();
         Ok(())
     }
 
+    /// Effective value: the active profile's override, else the base config.
     pub fn get_config(&self, key: &str) -> Result<Option<String>> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
+        if !key.starts_with(PROFILE_KEY_PREFIX) {
+            if let Some(value) = self.cached_lookup(&self.overrides, ACTIVE_OVERRIDES_SQL, key)? {
+                return Ok(Some(value));
+            }
+        }
+        self.get_base_config(key)
+    }
+
+    /// True if the active profile overrides `key`, hiding its base value.
+    pub fn is_overridden(&self, key: &str) -> Result<bool> {
+        Ok(!key.starts_with(PROFILE_KEY_PREFIX)
+            && self.cached_lookup(&self.overrides, ACTIVE_OVERRIDES_SQL, key)?.is_some())
+    }
+
+    /// Value in the base config, ignoring the active profile.
+    pub fn get_base_config(&self, key: &str) -> Result<Option<String>> {
+        self.cached_lookup(&self.config, "SELECT key, value FROM config", key)
+    }
+
+    /// Every effective setting (base config overlaid with the active
+    /// profile), sorted by key. Profile bookkeeping keys are left out.
+    pub fn effective_config(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn()?;
+        let mut settings = BTreeMap::new();
+        for sql in ["SELECT key, value FROM config", ACTIVE_OVERRIDES_SQL] {
+            let mut stmt = conn.prepare_cached(sql)?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            for row in rows {
+                let (key, value) = row?;
+                if !key.starts_with(PROFILE_KEY_PREFIX) {
+                    settings.insert(key, value);
+                }
+            }
+        }
+        Ok(settings.into_iter().collect())
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Profiles
+    // ────────────────────────────────────────────────────────────────
+
+    /// Capture the current effective settings as profile `name`, replacing
+    /// any previous contents. Returns the number of settings saved.
+    pub fn save_profile(&self, name: &str) -> Result<usize> {
+        let settings = self.effective_config()?;
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM profiles WHERE name = ?1", params![name])?;
+        {
+            let mut stmt =
+                tx.prepare_cached("INSERT INTO profiles (name, key, value) VALUES (?1, ?2, ?3)")?;
+            for (key, value) in &settings {
+                stmt.execute(params![name, key, value])?;
+            }
+        }
+        tx.commit()?;
+        self.bump_generation();
+        Ok(settings.len())
+    }
+
+    /// All profiles, by name.
+    pub fn list_profiles(&self) -> Result<Vec<Profile>> {
+        let conn = self.conn()?;
+        let mut stmt =
+            conn.prepare_cached("SELECT name, key, value FROM profiles ORDER BY name, key")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+        let mut profiles: Vec<Profile> = Vec::new();
+        for row in rows {
+            let (name, key, value) = row?;
+            match profiles.last_mut() {
+                Some(p) if p.name == name => p.settings.push((key, value)),
+                _ => profiles.push(Profile { name, settings: vec![(key, value)] }),
+            }
+        }
+        Ok(profiles)
+    }
+
+    pub fn get_profile(&self, name: &str) -> Result<Option<Profile>> {
+        Ok(self.list_profiles()?.into_iter().find(|p| p.name == name))
+    }
+
+    /// Delete profile `name`; if it was active, the base config takes over.
+    pub fn remove_profile(&self, name: &str) -> Result<bool> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        let removed = tx.execute("DELETE FROM profiles WHERE name = ?1", params![name])?;
+        tx.execute(
+            "DELETE FROM config WHERE key = ?1 AND value = ?2",
+            params![ACTIVE_PROFILE_KEY, name],
+        )?;
+        tx.commit()?;
+        self.bump_generation();
+        Ok(removed > 0)
+    }
+
+    /// Name of the active profile, if any.
+    pub fn active_profile(&self) -> Result<Option<String>> {
+        self.get_base_config(ACTIVE_PROFILE_KEY)
+    }
+
+    /// The profile active before the last switch: `Some(None)` for the base
+    /// config, `None` if there was no switch yet.
+    pub fn previous_profile(&self) -> Result<Option<Option<String>>> {
+        Ok(self
+            .get_base_config(PREVIOUS_PROFILE_KEY)?
+            .map(|name| Some(name).filter(|n| !n.is_empty())))
+    }
+
+    /// Make `name` the active profile (`None`: base config only) in one
+    /// transaction, remembering the current one for `!profile use previous`.
+    /// Returns `None`, changing nothing, if the profile does not exist.
+    pub fn use_profile(&self, name: Option<&str>) -> Result<Option<ProfileSwitch>> {
+        let mut conn = self.write_conn()?;
+        let tx = conn.transaction()?;
+        if let Some(name) = name {
+            let rows: i64 = tx.query_row(
+                "SELECT COUNT(*) FROM profiles WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )?;
+            if rows == 0 {
+                return Ok(None);
+            }
+        }
+        let from: Option<String> = match tx.query_row(
             "SELECT value FROM config WHERE key = ?1",
-            params![key],
-            |row| row.get::<_, String>(0),
-        );
-        match result {
-            Ok(val) => Ok(Some(val)),
+            params![ACTIVE_PROFILE_KEY],
+            |row| row.get(0),
+        ) {
+            Ok(active) => Some(active),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+        tx.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+            params![PREVIOUS_PROFILE_KEY, from.as_deref().unwrap_or("")],
+        )?;
+        match name {
+            Some(name) => tx.execute(
+                "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+                params![ACTIVE_PROFILE_KEY, name],
+            )?,
+            None => tx.execute("DELETE FROM config WHERE key = ?1", params![ACTIVE_PROFILE_KEY])?,
+        };
+        tx.commit()?;
+        self.bump_generation();
+        Ok(Some(ProfileSwitch { from, to: name.map(str::to_string) }))
+    }
+}
+/// Escape `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern.
+fn record_from_row(row: &rusqlite::Row<'_>) -> Result<CommandRecord> {
+    Ok(CommandRecord {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        command: row.get(2)?,
+        output: row.get(3)?,
+        exit_code: row.get(4)?,
+        timestamp: row.get(5)?,
+        directory: row.get(6)?,
+        duration_ms: row.get(7)?,
+    })
+}
+
+/// Run every migration in one immediate transaction: the write lock is
+/// taken up front, so a second instance opening at the same moment waits
+/// (up to `BUSY_TIMEOUT`) and then finds the schema already current.
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    tx.execute_batch(schema::MIGRATION_INIT)?;
+    tx.execute_batch(schema::MIGRATION_V2)?;
+    tx.execute_batch(schema::MIGRATION_V3)?;
+    tx.execute_batch(schema::MIGRATION_V4)?;
+    let has_heartbeat: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('session') WHERE name = 'heartbeat'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_heartbeat {
+        tx.execute_batch(schema::MIGRATION_V5)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V6)?;
+    let has_redo_of: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'redo_of'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_redo_of {
+        tx.execute_batch(schema::MIGRATION_V7)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V8)?;
+    tx.execute_batch(schema::MIGRATION_V9)?;
+    tx.execute_batch(schema::MIGRATION_V10)?;
+    tx.execute_batch(schema::MIGRATION_V11)?;
+    tx.execute_batch(schema::MIGRATION_V12)?;
+    let has_origin: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'origin'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_origin {
+        tx.execute_batch(schema::MIGRATION_V13)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V14)?;
+    let has_usage: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'cpu_user_ms'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_usage {
+        tx.execute_batch(schema::MIGRATION_V15)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V16)?;
+    tx.execute_batch(schema::MIGRATION_V17)?;
+    tx.execute_batch(schema::MIGRATION_V18)?;
+    tx.execute_batch(schema::MIGRATION_V19)?;
+    let has_derived_from: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'derived_from'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_derived_from {
+        tx.execute_batch(schema::MIGRATION_V20)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V21)?;
+    tx.execute_batch(schema::MIGRATION_V22)?;
+    tx.execute_batch(schema::MIGRATION_V23)?;
+    let has_pane: bool = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('history') WHERE name = 'pane'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_pane {
+        tx.execute_batch(schema::MIGRATION_V24)?;
+    }
+    tx.execute_batch(schema::MIGRATION_V25)?;
+    tx.pragma_update(None, "user_version", schema::SCHEMA_VERSION)?;
+    tx.commit()
+}
+
+/// The last history id the search index has taken; 0 before the first.
+fn fts_indexed_through(conn: &Connection) -> Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE((SELECT indexed_through FROM history_fts_state WHERE id = 1), 0)",
+        [],
+        |row| row.get(0),
+    )
+}
+
+/// End the open sessions whose heartbeat (or start, before heartbeats)
+/// is older than `STALE_AFTER_SECS`, at their last sign of life.
+fn close_stale_sessions(conn: &Connection, now: i64) -> Result<usize> {
+    conn.execute(
+        "UPDATE session SET end_time = COALESCE(heartbeat, start_time)
+         WHERE end_time IS NULL AND COALESCE(heartbeat, start_time) < ?1",
+        params![now - STALE_AFTER_SECS],
+    )
+}
+
+/// Plain text of a `history` column value.
+fn reveal_text(cipher: Option<&RowCipher>, value: String) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.open(&value).ok_or_else(crypto::undecryptable_error),
+        None => Ok(value),
+    }
+}
+
+const DEVICE_COLUMNS: &str =
+    "SELECT id, name, product, last_port, seen_at, baud, parser, eol, reconnect FROM devices";
+
+fn device_from_row(row: &rusqlite::Row<'_>) -> Result<DeviceRecord> {
+    Ok(DeviceRecord {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        product: row.get(2)?,
+        last_port: row.get(3)?,
+        seen_at: row.get(4)?,
+        defaults: DeviceDefaults {
+            baud: row.get(5)?,
+            parser: row.get(6)?,
+            eol: row.get(7)?,
+            reconnect: row.get(8)?,
+        },
+    })
+}
+
+fn tag_from_row(row: &rusqlite::Row<'_>) -> Result<Tag> {
+    Ok(Tag {
+        name: row.get(0)?,
+        history_id: row.get(1)?,
+        command: row.get(2)?,
+        output: row.get(3)?,
+        exit_code: row.get(4)?,
+        directory: row.get(5)?,
+        ran_at: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+fn reveal_tag(cipher: Option<&RowCipher>, mut tag: Tag) -> Result<Tag> {
+    tag.command = reveal_text(cipher, tag.command)?;
+    tag.output = reveal_text(cipher, tag.output)?;
+    Ok(tag)
+}
+
+fn macro_from_row(row: &rusqlite::Row<'_>) -> Result<SavedMacro> {
+    Ok(SavedMacro { name: row.get(0)?, steps: row.get(1)?, created_at: row.get(2)? })
+}
+
+fn reveal_macro(cipher: Option<&RowCipher>, mut saved: SavedMacro) -> Result<SavedMacro> {
+    saved.steps = reveal_text(cipher, saved.steps)?;
+    Ok(saved)
+}
+
+fn reveal(cipher: Option<&RowCipher>, mut record: CommandRecord) -> Result<CommandRecord> {
+    record.command = reveal_text(cipher, record.command)?;
+    record.output = record.output.map(|o| reveal_text(cipher, o)).transpose()?;
+    Ok(record)
+}
+
+/// Encryption state recorded in the base config: locked if a passphrase
+/// was established and `vault.encrypt` is on.
+fn read_crypt(conn: &Connection) -> Result<Crypt> {
+    let value = |key: &str| -> Result<Option<String>> {
+        match conn.query_row("SELECT value FROM config WHERE key = ?1", params![key], |row| row.get(0)) {
+            Ok(value) => Ok(Some(value)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e),
         }
+    };
+    if !crypto::encrypt_enabled(value(crypto::ENCRYPT_KEY)?.as_deref()) {
+        return Ok(Crypt::Plain);
+    }
+    Ok(match value(crypto::KDF_KEY)?.as_deref().and_then(KdfParams::parse) {
+        Some(params) => Crypt::Locked(params),
+        None => Crypt::Plain,
+    })
+}
+
+fn check_passphrases(passphrase: &str, confirm: &str) -> std::result::Result<(), VaultCryptError> {
+    if passphrase.is_empty() {
+        return Err(VaultCryptError::EmptyPassphrase);
+    }
+    if passphrase != confirm {
+        return Err(VaultCryptError::Mismatch);
+    }
+    Ok(())
+}
+
+/// Rewrite `command`/`output` of every history row in `MIGRATE_BATCH`-row
+/// transactions. `convert` returns the new values, `None` for unchanged.
+fn rewrite_history<P, C>(conn: &mut Connection, mut progress: P, mut convert: C) -> Result<usize>
+where
+    P: FnMut(usize, usize),
+    C: FnMut(&str, Option<&str>) -> Result<(Option<String>, Option<String>)>,
+{
+    // The search index holds plain commands: it starts over, and stays
+    // empty while the vault is encrypted.
+    conn.execute_batch("DELETE FROM history_fts; DELETE FROM history_fts_state;")?;
+    let total: i64 = conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
+    let total = total as usize;
+    let mut last_id = 0i64;
+    let mut done = 0;
+    let mut rewritten = 0;
+    loop {
+        let tx = conn.transaction()?;
+        let batch: Vec<(i64, String, Option<String>)> = {
+            let mut stmt = tx.prepare_cached(
+                "SELECT id, command, output FROM history WHERE id > ?1 ORDER BY id LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![last_id, crypto::MIGRATE_BATCH as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?;
+            rows.collect::<Result<_>>()?
+        };
+        if batch.is_empty() {
+            break;
+        }
+        {
+            let mut update_command = tx.prepare_cached("UPDATE history SET command = ?1 WHERE id = ?2")?;
+            let mut update_output = tx.prepare_cached("UPDATE history SET output = ?1 WHERE id = ?2")?;
+            for (id, command, output) in &batch {
+                let (command, output) = convert(command, output.as_deref())?;
+                if let Some(command) = &command {
+                    update_command.execute(params![command, id])?;
+                }
+                if let Some(output) = &output {
+                    update_output.execute(params![output, id])?;
+                }
+                if command.is_some() || output.is_some() {
+                    rewritten += 1;
+                }
+            }
+        }
+        tx.commit()?;
+        last_id = batch.last().map_or(last_id, |(id, _, _)| *id);
+        done += batch.len();
+        progress(done, total.max(done));
+    }
+    Ok(rewritten)
+}
+
+/// Rewrite `original`/`expanded` of every `alias_uses` row in one
+/// transaction; the table is small next to history. `convert` returns
+/// `None` for unchanged.
+fn rewrite_alias_uses<C>(conn: &mut Connection, mut convert: C) -> Result<()>
+where
+    C: FnMut(&str) -> Result<Option<String>>,
+{
+    let tx = conn.transaction()?;
+    {
+        let rows: Vec<(i64, String, String)> = tx
+            .prepare("SELECT id, original, expanded FROM alias_uses")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_>>()?;
+        let mut update = tx.prepare("UPDATE alias_uses SET original = ?1, expanded = ?2 WHERE id = ?3")?;
+        for (id, original, expanded) in rows {
+            let new_original = convert(&original)?;
+            let new_expanded = convert(&expanded)?;
+            if new_original.is_some() || new_expanded.is_some() {
+                update.execute(params![
+                    new_original.unwrap_or(original),
+                    new_expanded.unwrap_or(expanded),
+                    id
+                ])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// `rewrite_alias_uses` for `device_sessions.transcript`.
+fn rewrite_transcripts<C>(conn: &mut Connection, mut convert: C) -> Result<()>
+where
+    C: FnMut(&str) -> Result<Option<String>>,
+{
+    let tx = conn.transaction()?;
+    {
+        let rows: Vec<(i64, String)> = tx
+            .prepare("SELECT id, transcript FROM device_sessions")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+        let mut update = tx.prepare("UPDATE device_sessions SET transcript = ?1 WHERE id = ?2")?;
+        for (id, transcript) in rows {
+            if let Some(converted) = convert(&transcript)? {
+                update.execute(params![converted, id])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// `rewrite_alias_uses` for `tags.command` and `tags.output`.
+fn rewrite_tags<C>(conn: &mut Connection, mut convert: C) -> Result<()>
+where
+    C: FnMut(&str) -> Result<Option<String>>,
+{
+    let tx = conn.transaction()?;
+    {
+        let rows: Vec<(String, String, String)> = tx
+            .prepare("SELECT name, command, output FROM tags")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_>>()?;
+        let mut update_command = tx.prepare("UPDATE tags SET command = ?1 WHERE name = ?2")?;
+        let mut update_output = tx.prepare("UPDATE tags SET output = ?1 WHERE name = ?2")?;
+        for (name, command, output) in rows {
+            if let Some(converted) = convert(&command)? {
+                update_command.execute(params![converted, name])?;
+            }
+            if let Some(converted) = convert(&output)? {
+                update_output.execute(params![converted, name])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// `rewrite_alias_uses` for `macros.steps`.
+fn rewrite_macros<C>(conn: &mut Connection, mut convert: C) -> Result<()>
+where
+    C: FnMut(&str) -> Result<Option<String>>,
+{
+    let tx = conn.transaction()?;
+    {
+        let rows: Vec<(String, String)> = tx
+            .prepare("SELECT name, steps FROM macros")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+        let mut update = tx.prepare("UPDATE macros SET steps = ?1 WHERE name = ?2")?;
+        for (name, steps) in rows {
+            if let Some(converted) = convert(&steps)? {
+                update.execute(params![converted, name])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+/// `rewrite_alias_uses` for `test_runs.command` and `test_runs.failures`.
+fn rewrite_test_runs<C>(conn: &mut Connection, mut convert: C) -> Result<()>
+where
+    C: FnMut(&str) -> Result<Option<String>>,
+{
+    let tx = conn.transaction()?;
+    {
+        let rows: Vec<(i64, String, String)> = tx
+            .prepare("SELECT id, command, failures FROM test_runs")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_>>()?;
+        let mut update_command = tx.prepare("UPDATE test_runs SET command = ?1 WHERE id = ?2")?;
+        let mut update_failures = tx.prepare("UPDATE test_runs SET failures = ?1 WHERE id = ?2")?;
+        for (id, command, failures) in rows {
+            if let Some(converted) = convert(&command)? {
+                update_command.execute(params![converted, id])?;
+            }
+            if let Some(converted) = convert(&failures)? {
+                update_failures.execute(params![converted, id])?;
+            }
+        }
+    }
+    tx.commit()
+}
+
+fn escape_like(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
     }
-}
\ No newline at end of file
+    out
+}
@@ -0,0 +1,331 @@
+// positronic-core/src/vault/recover.rs
+//
+// Getting a damaged vault file back into service.
+//
+// A power cut mid-checkpoint or a full disk can leave `vault.db` failing
+// with SQLITE_CORRUPT or SQLITE_NOTADB. `Vault::open` then moves the file
+// (and its `-wal`/`-shm`) aside as `vault.db.corrupt-<timestamp>`, creates
+// a fresh database in its place and copies in whatever rows still read,
+// table by table: forward by rowid until a damaged page stops the scan,
+// then backward from the end to pick up what lies beyond it. A file whose
+// 100-byte header was wiped is read through a copy with the header
+// rebuilt. The damaged file is left untouched for a real `.recover`.
+//
+// Boot carries on with the salvaged vault; the `RecoveryReport` is what
+// the notice and `!vault recover-report` show. `!vault check` runs
+// `PRAGMA integrity_check` on demand.
+
+use chrono::{DateTime, Local};
+use rusqlite::types::Value;
+use rusqlite::{Connection, ErrorCode, OpenFlags, Result, Row, params_from_iter};
+use std::path::{Path, PathBuf};
+
+/// Problems `!vault check` lists before giving up on the rest.
+pub const CHECK_LIMIT: usize = 100;
+
+/// Shown while `!vault check` runs.
+pub const CHECK_PROGRESS: &str = "🩺 Checking vault integrity… (a large vault takes a while)";
+
+/// SQLite's magic string, the first 16 bytes of every database file.
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+
+/// Page sizes to try for a file whose header is gone, SQLite's default first.
+const PAGE_SIZES: [usize; 8] = [4096, 512, 1024, 2048, 8192, 16384, 32768, 65536];
+
+/// What was rescued from one table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableSalvage {
+    pub name: String,
+    pub rows: usize,
+    /// Every row read back; `false` when damage cut a scan short.
+    pub complete: bool,
+}
+
+/// What `Vault::open` did with a damaged file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Where the damaged file was moved.
+    pub damaged: PathBuf,
+    /// The error opening it.
+    pub reason: String,
+    /// When, as a Unix timestamp.
+    pub at: i64,
+    /// The header was wiped and had to be rebuilt to read anything.
+    pub header_rebuilt: bool,
+    /// Tables in the fresh schema, in order, with what was copied.
+    pub tables: Vec<TableSalvage>,
+}
+
+impl RecoveryReport {
+    pub fn history_rows(&self) -> usize {
+        self.tables.iter().filter(|t| t.name == "history").map(|t| t.rows).sum()
+    }
+
+    /// The line shown at boot.
+    pub fn notice(&self) -> String {
+        format!(
+            "⚠️  The vault was damaged and has been rebuilt: {} history row(s) recovered. \
+             The damaged file is kept at {} — !vault recover-report for details",
+            self.history_rows(),
+            self.damaged.display()
+        )
+    }
+
+    /// The `!vault recover-report` output.
+    pub fn report_lines(&self) -> Vec<String> {
+        let when = DateTime::from_timestamp(self.at, 0)
+            .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let mut lines = vec![
+            format!("🩹 Vault recovered at {}", when),
+            format!("   Error: {}", self.reason),
+            format!("   Damaged file: {}", self.damaged.display()),
+        ];
+        if self.header_rebuilt {
+            lines.push("   The file header was wiped; rows were read with it rebuilt".to_string());
+        }
+        lines.push(String::new());
+        let width = self.tables.iter().map(|t| t.name.len()).max().unwrap_or(0);
+        for t in &self.tables {
+            lines.push(format!(
+                "  {:<width$}  {:>7} row(s){}",
+                t.name,
+                t.rows,
+                if t.complete { "" } else { "   (some unreadable)" },
+                width = width
+            ));
+        }
+        lines.push(String::new());
+        lines.push("   Nothing else reads the damaged file; sqlite3's .recover may get more out of it".to_string());
+        lines
+    }
+}
+
+/// Whether `err` means the file is damaged rather than busy or missing.
+pub fn is_corruption(err: &rusqlite::Error) -> bool {
+    matches!(
+        err,
+        rusqlite::Error::SqliteFailure(e, _) if matches!(e.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// A cheap read of the newest history row, the last leaf of the table,
+/// so a file cut short fails at open rather than on the first search.
+pub(super) fn probe(conn: &Connection) -> Result<()> {
+    conn.query_row("SELECT command FROM history ORDER BY id DESC LIMIT 1", [], |_| Ok(()))
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(()),
+            e => Err(e),
+        })
+}
+
+/// Move `path` and its `-wal`/`-shm` to `<path>.corrupt-<timestamp>`,
+/// returning the new name of the database file.
+pub fn quarantine(path: &Path, now: DateTime<Local>) -> std::io::Result<PathBuf> {
+    let stamp = now.format("%Y%m%d-%H%M%S");
+    let mut moved = sibling(path, &format!(".corrupt-{}", stamp));
+    let mut n = 1;
+    while moved.exists() {
+        n += 1;
+        moved = sibling(path, &format!(".corrupt-{}-{}", stamp, n));
+    }
+    std::fs::rename(path, &moved)?;
+    for suffix in ["-wal", "-shm"] {
+        let side = sibling(path, suffix);
+        if side.exists() {
+            std::fs::rename(&side, sibling(&moved, suffix))?;
+        }
+    }
+    Ok(moved)
+}
+
+/// Copy the readable rows of `damaged` into `fresh`, whose schema is
+/// already current. Tables and columns the damaged file lacks are skipped,
+/// and so is the history search index: the maintenance worker rebuilds it
+/// from the salvaged rows.
+pub fn salvage(damaged: &Path, fresh: &mut Connection, reason: String, at: i64) -> Result<RecoveryReport> {
+    let mut report = RecoveryReport { damaged: damaged.to_path_buf(), reason, at, header_rebuilt: false, tables: Vec::new() };
+    let names: Vec<String> = {
+        let mut stmt = fresh.prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+               AND name NOT LIKE 'history\\_fts%' ESCAPE '\\' ORDER BY rootpage",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect::<Result<_>>()?
+    };
+
+    let rebuilt = rebuilt_copy(damaged);
+    report.header_rebuilt = rebuilt.is_some();
+    let source = rebuilt.as_deref().unwrap_or(damaged);
+    let opened = open_damaged(source);
+
+    // A history row whose session didn't survive is still worth keeping.
+    fresh.pragma_update(None, "foreign_keys", "OFF")?;
+    let tx = fresh.transaction()?;
+    for name in names {
+        let (rows, complete) = match &opened {
+            Ok(from) => copy_table(from, &tx, &name)?,
+            Err(_) => (0, false),
+        };
+        report.tables.push(TableSalvage { name, rows, complete });
+    }
+    tx.commit()?;
+    fresh.pragma_update(None, "foreign_keys", "ON")?;
+
+    drop(opened);
+    if let Some(copy) = rebuilt {
+        let _ = std::fs::remove_file(copy);
+    }
+    Ok(report)
+}
+
+/// Open a damaged file to read from. With `writable_schema` on, SQLite
+/// reads a file shorter than its header says instead of refusing it;
+/// the connection is read-only, so nothing is written either way.
+fn open_damaged(path: &Path) -> Result<Connection> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.pragma_update(None, "writable_schema", "ON")?;
+    Ok(conn)
+}
+
+/// Copy `table`'s rows from `from` into `into`. Read errors in `from`
+/// end a scan; write errors in `into` are returned.
+fn copy_table(from: &Connection, into: &Connection, table: &str) -> Result<(usize, bool)> {
+    let Ok(columns) = common_columns(from, into, table) else {
+        return Ok((0, false));
+    };
+    if columns.is_empty() {
+        return Ok((0, true));
+    }
+    let list = columns.iter().map(String::as_str).map(quote).collect::<Vec<_>>().join(", ");
+    let slots = (1..=columns.len()).map(|i| format!("?{}", i)).collect::<Vec<_>>().join(", ");
+    let mut insert = into.prepare(&format!("INSERT OR IGNORE INTO {} ({}) VALUES ({})", quote(table), list, slots))?;
+    let mut copied = 0;
+    let mut insert_row = |values: Vec<Value>| -> Result<()> {
+        copied += insert.execute(params_from_iter(values))?;
+        Ok(())
+    };
+
+    let forward = format!("SELECT rowid, {} FROM {} ORDER BY rowid", list, quote(table));
+    let (last, forward_done) = scan(from, &forward, columns.len(), None, &mut insert_row)?;
+    if forward_done {
+        return Ok((copied, true));
+    }
+    // Something stopped the forward scan; come back from the end to it.
+    let backward = format!("SELECT rowid, {} FROM {} WHERE rowid > ?1 ORDER BY rowid DESC", list, quote(table));
+    scan(from, &backward, columns.len(), Some(last.unwrap_or(i64::MIN)), &mut insert_row)?;
+    Ok((copied, false))
+}
+
+/// Run `sql` over `from`, handing each row's values to `insert`. Returns
+/// the last rowid read and whether the scan ran to the end.
+fn scan<F>(from: &Connection, sql: &str, width: usize, after: Option<i64>, insert: &mut F) -> Result<(Option<i64>, bool)>
+where
+    F: FnMut(Vec<Value>) -> Result<()>,
+{
+    let mut last = None;
+    let Ok(mut stmt) = from.prepare(sql) else {
+        return Ok((last, false));
+    };
+    let rows = match after {
+        Some(after) => stmt.query([after]),
+        None => stmt.query([]),
+    };
+    let Ok(mut rows) = rows else {
+        return Ok((last, false));
+    };
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok((last, true)),
+            Err(_) => return Ok((last, false)),
+        };
+        let Ok((rowid, values)) = read_row(row, width) else {
+            return Ok((last, false));
+        };
+        last = Some(rowid);
+        insert(values)?;
+    }
+}
+
+/// A scanned row: its rowid, then `width` column values.
+fn read_row(row: &Row<'_>, width: usize) -> Result<(i64, Vec<Value>)> {
+    let values = (1..=width).map(|i| row.get(i)).collect::<Result<_>>()?;
+    Ok((row.get(0)?, values))
+}
+
+/// Columns `table` has in both databases, in the fresh schema's order.
+fn common_columns(from: &Connection, into: &Connection, table: &str) -> Result<Vec<String>> {
+    let columns = |conn: &Connection| -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        let rows = stmt.query_map([table], |row| row.get(0))?;
+        rows.collect()
+    };
+    let old = columns(from)?;
+    Ok(columns(into)?.into_iter().filter(|c| old.contains(c)).collect())
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `path` with `suffix` appended to the file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// When `damaged` no longer starts with SQLite's magic string, a copy of
+/// it with the header rebuilt, at the first page size whose schema and
+/// history read back. `None` when the header is intact or nothing works.
+fn rebuilt_copy(damaged: &Path) -> Option<PathBuf> {
+    let bytes = std::fs::read(damaged).ok()?;
+    if bytes.len() < 100 || bytes.starts_with(MAGIC) {
+        return None;
+    }
+    // Page 1 holds the schema: a table b-tree page right after the header.
+    if !matches!(bytes[100], 0x05 | 0x0D) {
+        return None;
+    }
+    let copy = sibling(damaged, ".salvage");
+    for size in PAGE_SIZES.into_iter().filter(|size| bytes.len() % size == 0) {
+        let mut patched = bytes.clone();
+        patched[..100].copy_from_slice(&header(size, bytes.len() / size));
+        if std::fs::write(&copy, &patched).is_err() {
+            return None;
+        }
+        let readable = open_damaged(&copy).and_then(|conn| {
+            conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+            conn.query_row("SELECT MIN(rowid) FROM history", [], |row| row.get::<_, Option<i64>>(0))
+        });
+        if readable.is_ok() {
+            return Some(copy);
+        }
+    }
+    let _ = std::fs::remove_file(&copy);
+    None
+}
+
+/// A database header for a rollback-journal file of `pages` pages of
+/// `size` bytes, UTF-8, no free list, no reserved bytes.
+fn header(size: usize, pages: usize) -> [u8; 100] {
+    let mut h = [0u8; 100];
+    h[..16].copy_from_slice(MAGIC);
+    // 65536 is stored as 1.
+    let stored = if size == 65536 { 1 } else { size as u16 };
+    h[16..18].copy_from_slice(&stored.to_be_bytes());
+    h[18] = 1; // write version: legacy
+    h[19] = 1; // read version
+    h[21] = 64; // max embedded payload fraction
+    h[22] = 32; // min embedded payload fraction
+    h[23] = 32; // leaf payload fraction
+    h[24..28].copy_from_slice(&1u32.to_be_bytes()); // change counter
+    h[28..32].copy_from_slice(&(pages as u32).to_be_bytes());
+    h[40..44].copy_from_slice(&1u32.to_be_bytes()); // schema cookie
+    h[44..48].copy_from_slice(&4u32.to_be_bytes()); // schema format
+    h[56..60].copy_from_slice(&1u32.to_be_bytes()); // UTF-8
+    h[92..96].copy_from_slice(&1u32.to_be_bytes()); // version-valid-for
+    h[96..100].copy_from_slice(&3_045_000u32.to_be_bytes());
+    h
+}
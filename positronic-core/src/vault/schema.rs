@@ -1,4 +1,8 @@
 /// positronic-core/src/vault/schema.rs
+/// The last migration: `migrate` writes it to `PRAGMA user_version`, and
+/// `!ver` compares the two.
+pub const SCHEMA_VERSION: i64 = 25;
+
 /// The initial schema for the Positronic Vault.
 pub const MIGRATION_INIT: &str = r#"
 CREATE TABLE IF NOT EXISTS session (
@@ -49,4 +53,292 @@ CREATE TABLE IF NOT EXISTS config (
 -- Index for frequency queries on history
 CREATE INDEX IF NOT EXISTS idx_history_session ON history(session_id);
 CREATE INDEX IF NOT EXISTS idx_history_directory ON history(directory);
-"#;
\ No newline at end of file
+"#;
+
+/// V3 migration: AI command suggestions, for acceptance-rate stats.
+pub const MIGRATION_V3: &str = r#"
+-- One row per suggestion shown; accepted_at is set when the user picks it
+CREATE TABLE IF NOT EXISTS suggestions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    command TEXT NOT NULL,
+    offered_at INTEGER NOT NULL,
+    accepted_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_suggestions_session ON suggestions(session_id);
+"#;
+
+/// V4 migration: named profiles, each a bundle of config overrides.
+pub const MIGRATION_V4: &str = r#"
+-- One row per overridden setting; the active profile is config 'profile.active'
+CREATE TABLE IF NOT EXISTS profiles (
+    name TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (name, key)
+);
+"#;
+
+/// V5 migration: session liveness. Each instance refreshes its session's
+/// heartbeat; sessions that stop beating are closed by the next startup.
+/// `ALTER TABLE` can't be repeated, so `Vault::open` applies this only when
+/// the column is missing.
+pub const MIGRATION_V5: &str = r#"
+ALTER TABLE session ADD COLUMN heartbeat INTEGER;
+"#;
+
+/// V6 migration: alias expansions, for `!stats export` alias usage. The
+/// typed and expanded lines are command text, sealed like history.
+pub const MIGRATION_V6: &str = r#"
+CREATE TABLE IF NOT EXISTS alias_uses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    alias TEXT NOT NULL,
+    original TEXT NOT NULL,
+    expanded TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_alias_uses_alias ON alias_uses(alias);
+"#;
+
+/// V7 migration: `!redo` re-runs point at the row they repeated. Applied
+/// only when the column is missing, like V5.
+pub const MIGRATION_V7: &str = r#"
+ALTER TABLE history ADD COLUMN redo_of INTEGER;
+"#;
+
+/// V8 migration: device history. One row per `!io console` session; the
+/// transcript is the device's side of it, sealed like history.
+pub const MIGRATION_V8: &str = r#"
+CREATE TABLE IF NOT EXISTS device_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    port TEXT NOT NULL,
+    baud INTEGER NOT NULL,
+    started_at INTEGER NOT NULL,
+    ended_at INTEGER NOT NULL,
+    tx_bytes INTEGER NOT NULL,
+    rx_bytes INTEGER NOT NULL,
+    transcript TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_device_sessions_port ON device_sessions(port);
+"#;
+
+/// V9 migration: named saved outputs (`!tag`). A tag keeps its own copy of
+/// the run, so it outlives the history row it was taken from; command and
+/// output are sealed like history.
+pub const MIGRATION_V9: &str = r#"
+CREATE TABLE IF NOT EXISTS tags (
+    name TEXT PRIMARY KEY,
+    history_id INTEGER,
+    command TEXT NOT NULL,
+    output TEXT NOT NULL,
+    exit_code INTEGER,
+    directory TEXT NOT NULL,
+    ran_at INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+"#;
+
+/// V10 migration: commands a shell reported having, discovered once (e.g.
+/// PowerShell's `Get-Command`) and kept for command correction.
+pub const MIGRATION_V10: &str = r#"
+CREATE TABLE IF NOT EXISTS shell_commands (
+    shell TEXT NOT NULL,
+    name TEXT NOT NULL,
+    PRIMARY KEY (shell, name)
+);
+"#;
+
+/// V11 migration: the input line not yet sent, checkpointed while typing
+/// so a crash doesn't lose it. One row at most; the text is sealed like
+/// history.
+pub const MIGRATION_V11: &str = r#"
+CREATE TABLE IF NOT EXISTS input_draft (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    session_id TEXT NOT NULL,
+    text TEXT NOT NULL,
+    saved_at INTEGER NOT NULL
+);
+"#;
+
+/// V12 migration: `!hook` rules, applied in id order. `env` holds one
+/// `NAME=value` per line.
+pub const MIGRATION_V12: &str = r#"
+CREATE TABLE IF NOT EXISTS hooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    pattern TEXT NOT NULL,
+    env TEXT NOT NULL DEFAULT '',
+    before TEXT,
+    after TEXT,
+    created_at INTEGER NOT NULL
+);
+"#;
+
+/// V13 migration: the machine a history row was imported from (`!sync`);
+/// NULL for rows logged here. Applied only when the column is missing,
+/// like V5.
+pub const MIGRATION_V13: &str = r#"
+ALTER TABLE history ADD COLUMN origin TEXT;
+"#;
+
+/// V14 migration: USB devices seen on a port (`!io`), keyed by
+/// `VID:PID[:serial]`, with the user's name for one and the settings
+/// `!io connect` and `!io console` last used with it.
+pub const MIGRATION_V14: &str = r#"
+CREATE TABLE IF NOT EXISTS devices (
+    id TEXT PRIMARY KEY,
+    name TEXT UNIQUE,
+    product TEXT,
+    last_port TEXT NOT NULL,
+    seen_at INTEGER NOT NULL,
+    baud INTEGER,
+    parser TEXT,
+    eol TEXT,
+    reconnect INTEGER
+);
+"#;
+
+/// V15 migration: what a captured run used (`!time`, `!diff --watch`):
+/// CPU time and peak resident set. NULL for commands run at the PTY.
+/// Applied only when the columns are missing, like V5.
+pub const MIGRATION_V15: &str = r#"
+ALTER TABLE history ADD COLUMN cpu_user_ms INTEGER;
+ALTER TABLE history ADD COLUMN cpu_sys_ms INTEGER;
+ALTER TABLE history ADD COLUMN max_rss_bytes INTEGER;
+"#;
+
+/// V16 migration: `!setenv` overrides. `session_id` is the session one
+/// applies to, or '' for `--global`.
+pub const MIGRATION_V16: &str = r#"
+CREATE TABLE IF NOT EXISTS session_env (
+    session_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    set_at INTEGER NOT NULL,
+    PRIMARY KEY (session_id, name)
+);
+"#;
+
+/// V17 migration: `!queue`. `queue_run` is the one stopped or running
+/// run's place, `failed` its failed entries as comma-separated indexes.
+pub const MIGRATION_V17: &str = r#"
+CREATE TABLE IF NOT EXISTS command_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT NOT NULL,
+    added_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS queue_run (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    next INTEGER NOT NULL,
+    keep_going INTEGER NOT NULL,
+    failed TEXT NOT NULL,
+    saved_at INTEGER NOT NULL
+);
+"#;
+
+/// V18 migration: what `!safe disable` turned off. `kind` is alias, hook
+/// or theme; a hook's `name` is its id.
+pub const MIGRATION_V18: &str = r#"
+CREATE TABLE IF NOT EXISTS disabled_items (
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    disabled_at INTEGER NOT NULL,
+    PRIMARY KEY (kind, name)
+);
+"#;
+
+/// V19 migration: test runs summarized when they finished (`!tests`,
+/// `!stats tests`). `failures` is the failed tests as JSON; it and
+/// `command` are sealed in an encrypted vault, the counts are not.
+pub const MIGRATION_V19: &str = r#"
+CREATE TABLE IF NOT EXISTS test_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id TEXT NOT NULL,
+    framework TEXT NOT NULL,
+    command TEXT NOT NULL,
+    directory TEXT NOT NULL,
+    passed INTEGER NOT NULL,
+    failed INTEGER NOT NULL,
+    ignored INTEGER NOT NULL,
+    failures TEXT NOT NULL,
+    ran_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_test_runs_ran_at ON test_runs(ran_at);
+"#;
+
+/// V20 migration: a command edited from an earlier block points at that
+/// block's row. Applied only when the column is missing, like V7.
+pub const MIGRATION_V20: &str = r#"
+ALTER TABLE history ADD COLUMN derived_from INTEGER;
+"#;
+
+/// V21 migration: the history search index (`Vault::index_history`).
+/// `history_fts` rows share their `history` row's id; the maintenance
+/// worker adds them in batches, `history_fts_state` keeps how far it got.
+/// Trigram tokens let `LIKE '%…%'` use the index. A deleted or rewritten
+/// command drops out; an encrypted vault has no index.
+pub const MIGRATION_V21: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(command, tokenize = 'trigram');
+
+CREATE TABLE IF NOT EXISTS history_fts_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    indexed_through INTEGER NOT NULL
+);
+
+CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history BEGIN
+    DELETE FROM history_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF command ON history BEGIN
+    DELETE FROM history_fts WHERE rowid = old.id;
+END;
+"#;
+
+/// V22 migration: `!mark` names kept across sessions (`marks.persist`).
+/// A mark only points at its history row, so it has nothing to seal; a
+/// mark whose row has since been deleted is reported as gone.
+pub const MIGRATION_V22: &str = r#"
+CREATE TABLE IF NOT EXISTS marks (
+    name TEXT PRIMARY KEY,
+    history_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+"#;
+
+/// V23 migration: how long each native `!` command took
+/// (`commands::middleware::RecordTiming`). Names only, never arguments,
+/// so there is nothing to seal; the oldest rows go past
+/// `NATIVE_TIMINGS_KEPT`.
+pub const MIGRATION_V23: &str = r#"
+CREATE TABLE IF NOT EXISTS native_timings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    ok INTEGER NOT NULL,
+    ran_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_native_timings_name ON native_timings(name);
+"#;
+
+/// V24 migration: the split pane a command ran in (`Vault::for_pane`);
+/// NULL for rows logged before panes, or outside one.
+pub const MIGRATION_V24: &str = r#"
+ALTER TABLE history ADD COLUMN pane INTEGER;
+CREATE INDEX IF NOT EXISTS idx_history_pane ON history(pane);
+"#;
+
+/// V25 migration: `!macro` recordings, one step per line of `steps`
+/// (sealed in an encrypted vault: they hold what was typed).
+pub const MIGRATION_V25: &str = r#"
+CREATE TABLE IF NOT EXISTS macros (
+    name TEXT PRIMARY KEY,
+    steps TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+"#;
@@ -0,0 +1,198 @@
+// positronic-core/src/vault/timing.rs
+//
+// Run-time analytics over `history.duration_ms`: per-program summaries for
+// `!stats slow` and a least-squares trend for `!stats trend`. Also the
+// per-program CPU and memory totals for `!stats resources`.
+
+use crate::usage::ResourceUsage;
+
+/// Run-time summary for every command sharing a first token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationStats {
+    /// First token of the command (`cargo` for `cargo build --release`).
+    pub program: String,
+    pub count: i64,
+    pub mean_ms: f64,
+    /// Nearest-rank 95th percentile.
+    pub p95_ms: i64,
+}
+
+/// CPU and memory summary for every captured run sharing a first token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceStats {
+    pub program: String,
+    pub runs: i64,
+    pub total_cpu_ms: u64,
+    pub mean_cpu_ms: f64,
+    /// Largest peak resident set of any run.
+    pub peak_rss_bytes: u64,
+    pub mean_rss_bytes: f64,
+}
+
+/// One timed run of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedRun {
+    pub timestamp: i64,
+    pub duration_ms: i64,
+}
+
+/// Fewest runs a trend is computed from.
+pub const MIN_TREND_RUNS: usize = 3;
+
+/// Relative change (first fitted run → last) below which a trend is steady.
+pub const TREND_STEADY_PCT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrendDirection {
+    Slower,
+    Faster,
+    Steady,
+}
+
+/// Straight-line fit of duration against time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DurationTrend {
+    pub runs: usize,
+    /// Slope in milliseconds per day (per run if all runs share a timestamp).
+    pub slope_ms_per_day: f64,
+    /// Fitted duration at the first and the last run.
+    pub first_ms: f64,
+    pub last_ms: f64,
+}
+
+impl DurationTrend {
+    /// Change from the first to the last fitted run, in percent.
+    pub fn change_pct(&self) -> f64 {
+        if self.first_ms <= 0.0 {
+            return 0.0;
+        }
+        (self.last_ms - self.first_ms) / self.first_ms * 100.0
+    }
+
+    pub fn direction(&self) -> TrendDirection {
+        let pct = self.change_pct();
+        if pct > TREND_STEADY_PCT {
+            TrendDirection::Slower
+        } else if pct < -TREND_STEADY_PCT {
+            TrendDirection::Faster
+        } else {
+            TrendDirection::Steady
+        }
+    }
+}
+
+/// Least-squares trend of `runs` (any order). `None` with fewer than
+/// `MIN_TREND_RUNS` runs. Runs that all share one timestamp (a burst logged
+/// within a second) are fitted against their order instead.
+pub fn duration_trend(runs: &[TimedRun]) -> Option<DurationTrend> {
+    if runs.len() < MIN_TREND_RUNS {
+        return None;
+    }
+    let mut sorted = runs.to_vec();
+    sorted.sort_by_key(|r| r.timestamp);
+
+    let same_time = sorted.first()?.timestamp == sorted.last()?.timestamp;
+    let origin = sorted[0].timestamp;
+    let xs: Vec<f64> = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, r)| if same_time { i as f64 } else { (r.timestamp - origin) as f64 / 86_400.0 })
+        .collect();
+    let ys: Vec<f64> = sorted.iter().map(|r| r.duration_ms as f64).collect();
+
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (x, y) in xs.iter().zip(&ys) {
+        sxy += (x - mean_x) * (y - mean_y);
+        sxx += (x - mean_x) * (x - mean_x);
+    }
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    let fit = |x: f64| mean_y + slope * (x - mean_x);
+
+    Some(DurationTrend {
+        runs: sorted.len(),
+        slope_ms_per_day: slope,
+        first_ms: fit(xs[0]),
+        last_ms: fit(xs[xs.len() - 1]),
+    })
+}
+
+/// `Vault::duration_stats` done in memory, for encrypted vaults where the
+/// SQL can't group by program: `(program, duration_ms)` pairs summarized
+/// the same way (nearest-rank p95, slowest mean first).
+pub(crate) fn summarize<I>(runs: I) -> Vec<DurationStats>
+where
+    I: IntoIterator<Item = (String, i64)>,
+{
+    let mut by_program: std::collections::BTreeMap<String, Vec<i64>> = Default::default();
+    for (program, duration_ms) in runs {
+        by_program.entry(program).or_default().push(duration_ms);
+    }
+    let mut stats: Vec<DurationStats> = by_program
+        .into_iter()
+        .map(|(program, mut durations)| {
+            durations.sort_unstable();
+            let n = durations.len();
+            let rank = (n * 95).div_ceil(100).max(1);
+            DurationStats {
+                program,
+                count: n as i64,
+                mean_ms: durations.iter().sum::<i64>() as f64 / n as f64,
+                p95_ms: durations[rank - 1],
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| b.mean_ms.total_cmp(&a.mean_ms).then_with(|| a.program.cmp(&b.program)));
+    stats
+}
+
+/// `(program, usage)` pairs summarized per program, most total CPU first.
+pub(crate) fn summarize_resources<I>(runs: I) -> Vec<ResourceStats>
+where
+    I: IntoIterator<Item = (String, ResourceUsage)>,
+{
+    let mut by_program: std::collections::BTreeMap<String, Vec<ResourceUsage>> = Default::default();
+    for (program, usage) in runs {
+        by_program.entry(program).or_default().push(usage);
+    }
+    let mut stats: Vec<ResourceStats> = by_program
+        .into_iter()
+        .map(|(program, usages)| {
+            let n = usages.len();
+            let total_cpu_ms: u64 = usages.iter().map(|u| u.cpu_ms()).sum();
+            ResourceStats {
+                program,
+                runs: n as i64,
+                total_cpu_ms,
+                mean_cpu_ms: total_cpu_ms as f64 / n as f64,
+                peak_rss_bytes: usages.iter().map(|u| u.max_rss_bytes).max().unwrap_or(0),
+                mean_rss_bytes: usages.iter().map(|u| u.max_rss_bytes as f64).sum::<f64>() / n as f64,
+            }
+        })
+        .collect();
+    stats.sort_by(|a, b| b.total_cpu_ms.cmp(&a.total_cpu_ms).then_with(|| a.program.cmp(&b.program)));
+    stats
+}
+
+/// First token of a command, as `duration_stats` groups it.
+pub(crate) fn program(command: &str) -> String {
+    let command = command.trim_start_matches(' ');
+    command.split(' ').next().unwrap_or("").to_string()
+}
+
+/// Compact duration: `42ms`, `3.5s`, `2m 05s`, `1h 01m`.
+pub fn format_ms(ms: i64) -> String {
+    let ms = ms.max(0);
+    let secs = ms / 1000;
+    if secs == 0 {
+        format!("{}ms", ms)
+    } else if secs < 60 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
@@ -0,0 +1,191 @@
+//! `!ver` and `positronic --version`: what this build is, and what it is
+//! running with.
+//!
+//! Nothing here is written down by hand, so it can't drift from release
+//! to release. The build half (`BuildInfo`: version, `git describe`, build
+//! date, target, profile, cargo features) is stamped at compile time by
+//! the binary's build script. The runtime half comes from
+//! `VersionSources`: the themes the renderer has, the commands the
+//! completer knows, the vault's `user_version`, the renderer in use, the
+//! neural endpoint, the reflex table and the tldr pages. The window, the
+//! CLI (which boots no engine) and tests each supply their own.
+//!
+//! `!ver --json` is the same report as one object to attach to a bug
+//! report: the home directory is written `~`, and every string passes
+//! `PrivacyGuard`.
+
+use std::path::{Path, PathBuf};
+
+use positronic_neural::privacy::PrivacyGuard;
+use serde_json::{json, Value};
+
+use crate::vault::schema::SCHEMA_VERSION;
+
+pub const VER_USAGE: &str = "Usage: !ver [--json]";
+
+/// What the build script stamped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// `git describe --tags --always --dirty`, "unknown" outside a checkout.
+    pub git: &'static str,
+    /// UTC, `YYYY-MM-DD`.
+    pub date: &'static str,
+    pub target: &'static str,
+    pub profile: &'static str,
+    /// Enabled cargo features, comma-separated.
+    pub features: &'static str,
+}
+
+impl BuildInfo {
+    pub fn features(&self) -> Vec<&'static str> {
+        self.features.split(',').map(str::trim).filter(|f| !f.is_empty()).collect()
+    }
+}
+
+/// The neural endpoint, and the model asked for once connected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeuralInfo {
+    pub endpoint: String,
+    pub model: Option<String>,
+}
+
+/// Where the runtime half of the report comes from.
+pub trait VersionSources {
+    /// Theme names, as `theme` accepts them.
+    fn themes(&self) -> Vec<String>;
+    /// `!` commands the completer knows.
+    fn native_commands(&self) -> usize;
+    /// The vault's `user_version`; `None` when there is no vault yet.
+    fn schema_version(&self) -> Option<i64>;
+    /// The renderer in use; `None` without a window.
+    fn renderer(&self) -> Option<String>;
+    fn neural(&self) -> NeuralInfo;
+    /// Commands the typo corrector draws on for the active shell.
+    fn reflex_commands(&self) -> usize;
+    /// When the tldr pages were installed, `YYYY-MM-DD`.
+    fn tldr_date(&self) -> Option<String>;
+    fn data_root(&self) -> PathBuf;
+    /// Written `~` in `--json`.
+    fn home(&self) -> Option<PathBuf>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReport {
+    pub build: BuildInfo,
+    pub themes: Vec<String>,
+    pub native_commands: usize,
+    pub schema_version: Option<i64>,
+    pub renderer: Option<String>,
+    pub neural: NeuralInfo,
+    pub reflex_commands: usize,
+    pub tldr_date: Option<String>,
+    pub data_root: PathBuf,
+    pub home: Option<PathBuf>,
+}
+
+impl VersionReport {
+    pub fn assemble(build: BuildInfo, sources: &dyn VersionSources) -> Self {
+        Self {
+            build,
+            themes: sources.themes(),
+            native_commands: sources.native_commands(),
+            schema_version: sources.schema_version(),
+            renderer: sources.renderer(),
+            neural: sources.neural(),
+            reflex_commands: sources.reflex_commands(),
+            tldr_date: sources.tldr_date(),
+            data_root: sources.data_root(),
+            home: sources.home(),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let b = &self.build;
+        let features = b.features();
+        let rows = [
+            ("Target", format!("{} ({})", b.target, b.profile)),
+            ("Features", if features.is_empty() { "none".to_string() } else { features.join(", ") }),
+            ("Renderer", self.renderer.clone().unwrap_or_else(|| "no window".to_string())),
+            ("Themes", self.themes.join(", ")),
+            ("Native commands", self.native_commands.to_string()),
+            ("Vault schema", self.schema_line()),
+            ("Neural", self.neural_line()),
+            ("Reflex table", format!("{} commands", self.reflex_commands)),
+            (
+                "tldr pages",
+                match &self.tldr_date {
+                    Some(date) => format!("installed {}", date),
+                    None => "not installed (!tldr --update)".to_string(),
+                },
+            ),
+            ("Data", self.data_root.display().to_string()),
+        ];
+        let mut lines = vec![format!("⚡ Positronic {} ({}, built {})", b.version, b.git, b.date)];
+        for (label, value) in rows {
+            lines.push(format!("  {:<16} {}", format!("{}:", label), value));
+        }
+        lines
+    }
+
+    /// The report as one object: the home directory written `~`, and
+    /// secrets, addresses and emails scrubbed.
+    pub fn to_json(&self) -> Value {
+        let b = &self.build;
+        let home = self.home.as_ref().map(|home| home.display().to_string()).filter(|home| !home.is_empty());
+        let scrub = |text: &str| match &home {
+            Some(home) => PrivacyGuard::scrub(&text.replace(home.as_str(), "~")),
+            None => PrivacyGuard::scrub(text),
+        };
+        json!({
+            "version": b.version,
+            "git": b.git,
+            "built": b.date,
+            "target": b.target,
+            "profile": b.profile,
+            "features": b.features(),
+            "renderer": self.renderer.as_deref().map(scrub),
+            "themes": self.themes,
+            "native_commands": self.native_commands,
+            "vault_schema": { "found": self.schema_version, "expected": SCHEMA_VERSION },
+            "neural": { "endpoint": scrub(&self.neural.endpoint), "model": self.neural.model },
+            "reflex_commands": self.reflex_commands,
+            "tldr_installed": self.tldr_date,
+            "data_dir": scrub(&home_relative(&self.data_root, self.home.as_deref())),
+        })
+    }
+
+    fn schema_line(&self) -> String {
+        match self.schema_version {
+            None => format!("no vault yet (this build writes v{})", SCHEMA_VERSION),
+            Some(v) if v == SCHEMA_VERSION => format!("v{}", v),
+            Some(v) if v < SCHEMA_VERSION => format!("v{} (migrates to v{} when opened)", v, SCHEMA_VERSION),
+            Some(v) => format!("v{} (newer than this build's v{})", v, SCHEMA_VERSION),
+        }
+    }
+
+    fn neural_line(&self) -> String {
+        match &self.neural.model {
+            Some(model) => format!("{} · model {}", self.neural.endpoint, model),
+            None => format!("{} (not connected)", self.neural.endpoint),
+        }
+    }
+}
+
+/// `path` with `home` written `~`.
+pub fn home_relative(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~{}{}", std::path::MAIN_SEPARATOR, rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// Whether `!ver` was asked for JSON.
+pub fn parse(args: &str) -> Result<bool, String> {
+    match args.trim() {
+        "" => Ok(false),
+        "--json" => Ok(true),
+        _ => Err(VER_USAGE.to_string()),
+    }
+}
@@ -3,6 +3,8 @@
 //! The P2P Networking Layer for Local-First Collaboration.
 //! Handles Mesh Discovery, CRDT Sync, and Real-time WebRTC Streaming.
 
+pub mod transfer;
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -0,0 +1,620 @@
+//! File transfer between peers: chunked, acknowledged and resumable.
+//!
+//! The protocol is two state machines, `Sender` and `Receiver`, that trade
+//! `Packet`s and never touch a socket: the transport moves the packets,
+//! the caller supplies the clock. That keeps them testable by piping one
+//! into the other with packets dropped and links cut.
+//!
+//! 1. The sender offers a `Manifest`: name, size, SHA-256 and chunk size.
+//! 2. The receiver checks it (`decide`): too large, a name with nothing
+//!    left after `sanitize_file_name`, or too many transfers running is a
+//!    `Reject`. Otherwise it asks the user, or takes it at once from a
+//!    trusted peer with `hive.auto_accept_files` on, and answers
+//!    `Resume { next: 0 }`.
+//! 3. The sender keeps up to `WINDOW` chunks in flight. The receiver takes
+//!    chunks in order only and acknowledges cumulatively (`Ack { next }`:
+//!    everything before `next` is in). With no progress for
+//!    `RETRY_AFTER`, the sender goes back to the first unacknowledged
+//!    chunk.
+//! 4. After a reconnect the receiver reports its last contiguous chunk
+//!    (`Receiver::resume`), or answers the sender's repeated offer with
+//!    it, and the transfer continues from there rather than restarting.
+//! 5. With the last chunk in, the receiver checks the hash and answers
+//!    `Complete { verified }`.
+//!
+//! The Hive has no network transport yet: discovery is simulated and
+//! `HiveNode::broadcast_block` loops back. Until it has one nothing sends
+//! these packets, and `!send` waits on it.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Bytes per chunk this side sends.
+pub const CHUNK_SIZE: u32 = 64 * 1024;
+
+/// Largest chunk a receiver takes.
+pub const MAX_CHUNK_SIZE: u32 = 1024 * 1024;
+
+/// Chunks in flight before the sender waits for an ack.
+pub const WINDOW: u64 = 16;
+
+/// Without progress for this long, the sender sends again what wasn't
+/// acknowledged (or the offer).
+pub const RETRY_AFTER: Duration = Duration::from_secs(2);
+
+/// Default `hive.max_file_size`.
+pub const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Default bound on transfers running at once, each way.
+pub const MAX_ACTIVE: usize = 4;
+
+/// Longest file name kept, in bytes.
+const MAX_NAME_BYTES: usize = 200;
+
+/// What a transfer carries, sent before any data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub id: String,
+    /// The name on the sender's side; the receiver sanitizes it.
+    pub name: String,
+    pub size: u64,
+    /// SHA-256 of the content, lowercase hex.
+    pub sha256: String,
+    pub chunk_size: u32,
+}
+
+impl Manifest {
+    /// The manifest for `content` as one buffer (see `Sender::hash` for
+    /// files too large to read at once).
+    pub fn for_bytes(id: impl Into<String>, name: impl Into<String>, content: &[u8]) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            size: content.len() as u64,
+            sha256: hex(&Sha256::digest(content)),
+            chunk_size: CHUNK_SIZE,
+        }
+    }
+
+    pub fn chunks(&self) -> u64 {
+        self.size.div_ceil(u64::from(self.chunk_size.max(1)))
+    }
+
+    /// Bytes in chunk `index`: the chunk size, or what's left for the last.
+    pub fn chunk_len(&self, index: u64) -> usize {
+        let start = index * u64::from(self.chunk_size);
+        self.size
+            .saturating_sub(start)
+            .min(u64::from(self.chunk_size)) as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Packet {
+    Offer(Manifest),
+    /// From the receiver: send from chunk `next` on. The acceptance, and
+    /// the answer to an offer repeated after a reconnect.
+    Resume {
+        id: String,
+        next: u64,
+    },
+    Chunk {
+        id: String,
+        index: u64,
+        data: Vec<u8>,
+    },
+    /// Every chunk before `next` is in.
+    Ack {
+        id: String,
+        next: u64,
+    },
+    /// All chunks are in; `verified` if the hash matched.
+    Complete {
+        id: String,
+        verified: bool,
+    },
+    Reject {
+        id: String,
+        reason: String,
+    },
+}
+
+impl Packet {
+    pub fn id(&self) -> &str {
+        match self {
+            Packet::Offer(manifest) => &manifest.id,
+            Packet::Resume { id, .. }
+            | Packet::Chunk { id, .. }
+            | Packet::Ack { id, .. }
+            | Packet::Complete { id, .. }
+            | Packet::Reject { id, .. } => id,
+        }
+    }
+}
+
+/// How a transfer ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Verified,
+    /// Every byte arrived but the hash didn't match.
+    HashMismatch,
+    Rejected(String),
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Sending
+// ════════════════════════════════════════════════════════════════════
+
+/// Where the sender reads chunks: a file, or a buffer in tests.
+pub trait ChunkSource {
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+}
+
+impl ChunkSource for Vec<u8> {
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let start = (offset as usize).min(self.len());
+        Ok(self[start..(start + len).min(self.len())].to_vec())
+    }
+}
+
+impl ChunkSource for std::fs::File {
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        use std::io::{Read, Seek, SeekFrom};
+        self.seek(SeekFrom::Start(offset))?;
+        let mut buf = vec![0; len];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SendState {
+    /// Waiting for `Resume`.
+    Offered,
+    Sending,
+    Done(Outcome),
+}
+
+pub struct Sender<S> {
+    manifest: Manifest,
+    source: S,
+    state: SendState,
+    /// Chunks before this are acknowledged.
+    acked: u64,
+    /// The next chunk to put on the wire.
+    next: u64,
+    /// When the offer last went out, or the ack count last moved.
+    progress_at: Option<Instant>,
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for Sender<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sender")
+            .field("id", &self.manifest.id)
+            .field("state", &self.state)
+            .field("acked", &self.acked)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<S: ChunkSource> Sender<S> {
+    pub fn new(manifest: Manifest, source: S) -> Self {
+        Self {
+            manifest,
+            source,
+            state: SendState::Offered,
+            acked: 0,
+            next: 0,
+            progress_at: None,
+        }
+    }
+
+    /// SHA-256 of everything `source` holds, `chunk_size` at a time.
+    pub fn hash(source: &mut S, size: u64, chunk_size: u32) -> io::Result<String> {
+        let mut hasher = Sha256::new();
+        let mut offset = 0;
+        while offset < size {
+            let len = (size - offset).min(u64::from(chunk_size)) as usize;
+            hasher.update(source.read_at(offset, len)?);
+            offset += len as u64;
+        }
+        Ok(hex(&hasher.finalize()))
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Bytes the receiver has acknowledged, and the total.
+    pub fn progress(&self) -> (u64, u64) {
+        let done = (self.acked * u64::from(self.manifest.chunk_size)).min(self.manifest.size);
+        (done, self.manifest.size)
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        match &self.state {
+            SendState::Done(outcome) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    /// The link dropped and is back: offer again, and let the receiver
+    /// say where to continue.
+    pub fn reconnected(&mut self) {
+        if !matches!(self.state, SendState::Done(_)) {
+            self.state = SendState::Offered;
+            self.progress_at = None;
+        }
+    }
+
+    /// What to put on the wire at `now`.
+    pub fn poll(&mut self, now: Instant) -> io::Result<Vec<Packet>> {
+        let stalled = self
+            .progress_at
+            .is_none_or(|at| now.duration_since(at) >= RETRY_AFTER);
+        match self.state {
+            SendState::Done(_) => Ok(Vec::new()),
+            SendState::Offered => {
+                if !stalled {
+                    return Ok(Vec::new());
+                }
+                self.progress_at = Some(now);
+                Ok(vec![Packet::Offer(self.manifest.clone())])
+            }
+            SendState::Sending => {
+                let chunks = self.manifest.chunks();
+                if stalled {
+                    self.progress_at = Some(now);
+                    if self.acked >= chunks {
+                        // The `Complete` went missing: an offer asks again.
+                        return Ok(vec![Packet::Offer(self.manifest.clone())]);
+                    }
+                    self.next = self.acked;
+                }
+                let mut packets = Vec::new();
+                while self.next < chunks && self.next < self.acked + WINDOW {
+                    let index = self.next;
+                    let offset = index * u64::from(self.manifest.chunk_size);
+                    let data = self
+                        .source
+                        .read_at(offset, self.manifest.chunk_len(index))?;
+                    packets.push(Packet::Chunk {
+                        id: self.manifest.id.clone(),
+                        index,
+                        data,
+                    });
+                    self.next += 1;
+                }
+                Ok(packets)
+            }
+        }
+    }
+
+    /// Take a packet from the receiver.
+    pub fn on_packet(&mut self, packet: Packet, now: Instant) {
+        if packet.id() != self.manifest.id || matches!(self.state, SendState::Done(_)) {
+            return;
+        }
+        let chunks = self.manifest.chunks();
+        match packet {
+            Packet::Resume { next, .. } => {
+                self.state = SendState::Sending;
+                self.acked = next.min(chunks);
+                self.next = self.acked;
+                self.progress_at = Some(now);
+            }
+            Packet::Ack { next, .. } if self.state == SendState::Sending && next > self.acked => {
+                self.acked = next.min(chunks);
+                self.next = self.next.max(self.acked);
+                self.progress_at = Some(now);
+            }
+            Packet::Complete { verified, .. } => {
+                self.acked = chunks;
+                self.state = SendState::Done(if verified {
+                    Outcome::Verified
+                } else {
+                    Outcome::HashMismatch
+                });
+            }
+            Packet::Reject { reason, .. } => {
+                self.state = SendState::Done(Outcome::Rejected(reason))
+            }
+            _ => {}
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Receiving
+// ════════════════════════════════════════════════════════════════════
+
+/// Limits on what a receiver takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferPolicy {
+    /// `hive.auto_accept_files`: take offers from trusted peers unasked.
+    pub auto_accept: bool,
+    pub max_size: u64,
+    pub max_active: usize,
+}
+
+impl Default for TransferPolicy {
+    fn default() -> Self {
+        Self {
+            auto_accept: false,
+            max_size: MAX_FILE_SIZE,
+            max_active: MAX_ACTIVE,
+        }
+    }
+}
+
+/// What to do with an offer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Ask the user (`!hive accept-file <id>`).
+    Ask,
+    Accept,
+    Reject(String),
+}
+
+/// Decide on `offer` from a peer (`trusted` or not) with `active`
+/// transfers already running.
+pub fn decide(offer: &Manifest, trusted: bool, policy: &TransferPolicy, active: usize) -> Decision {
+    if offer.size > policy.max_size {
+        return Decision::Reject(format!(
+            "{} bytes is over the {} byte limit",
+            offer.size, policy.max_size
+        ));
+    }
+    if offer.chunk_size == 0 || offer.chunk_size > MAX_CHUNK_SIZE {
+        return Decision::Reject(format!("chunk size {} is out of range", offer.chunk_size));
+    }
+    if offer.sha256.len() != 64 || !offer.sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Decision::Reject("no SHA-256 in the manifest".to_string());
+    }
+    if sanitize_file_name(&offer.name).is_none() {
+        return Decision::Reject(format!("unusable file name {:?}", offer.name));
+    }
+    if active >= policy.max_active {
+        return Decision::Reject(format!("{} transfers are already running", active));
+    }
+    if trusted && policy.auto_accept {
+        Decision::Accept
+    } else {
+        Decision::Ask
+    }
+}
+
+/// `name` as a file name safe to create in the downloads directory: the
+/// last path component only, no control or reserved characters, no
+/// leading dots, no Windows device names. `None` if nothing is left.
+pub fn sanitize_file_name(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned: String = base
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let trimmed = cleaned
+        .trim_start_matches(['.', ' '])
+        .trim_end_matches(['.', ' ']);
+    if trimmed.is_empty() {
+        return None;
+    }
+    let mut safe = String::new();
+    for c in trimmed.chars() {
+        if safe.len() + c.len_utf8() > MAX_NAME_BYTES {
+            break;
+        }
+        safe.push(c);
+    }
+    let stem = safe
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_uppercase();
+    let device = matches!(stem.as_str(), "CON" | "PRN" | "AUX" | "NUL")
+        || (stem.len() == 4
+            && (stem.starts_with("COM") || stem.starts_with("LPT"))
+            && stem.as_bytes()[3].is_ascii_digit());
+    Some(if device { format!("_{}", safe) } else { safe })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RecvState {
+    Receiving,
+    Done(Outcome),
+}
+
+/// The receiving end, writing chunks in order into `sink`.
+pub struct Receiver<W> {
+    manifest: Manifest,
+    sink: W,
+    hasher: Sha256,
+    /// Chunks before this are written.
+    next: u64,
+    state: RecvState,
+}
+
+impl<W> std::fmt::Debug for Receiver<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("id", &self.manifest.id)
+            .field("state", &self.state)
+            .field("next", &self.next)
+            .finish()
+    }
+}
+
+impl<W: Write> Receiver<W> {
+    /// Accept `manifest` into `sink`; send `resume()` to start.
+    pub fn new(manifest: Manifest, sink: W) -> Self {
+        Self {
+            manifest,
+            sink,
+            hasher: Sha256::new(),
+            next: 0,
+            state: RecvState::Receiving,
+        }
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Bytes written, and the total.
+    pub fn progress(&self) -> (u64, u64) {
+        let done = (self.next * u64::from(self.manifest.chunk_size)).min(self.manifest.size);
+        (done, self.manifest.size)
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        match &self.state {
+            RecvState::Done(outcome) => Some(outcome),
+            RecvState::Receiving => None,
+        }
+    }
+
+    /// The sink, once the transfer is over (to rename a verified file
+    /// into place, or remove a bad one).
+    pub fn into_sink(self) -> W {
+        self.sink
+    }
+
+    /// Where to continue: the acceptance, and what to send on reconnect.
+    pub fn resume(&mut self) -> io::Result<Packet> {
+        if self.manifest.chunks() == 0 && self.state == RecvState::Receiving {
+            self.finish()?;
+        }
+        Ok(match &self.state {
+            RecvState::Done(outcome) => self.complete(outcome),
+            RecvState::Receiving => Packet::Resume {
+                id: self.manifest.id.clone(),
+                next: self.next,
+            },
+        })
+    }
+
+    /// Take a packet from the sender; the replies go back.
+    pub fn on_packet(&mut self, packet: Packet) -> io::Result<Vec<Packet>> {
+        if packet.id() != self.manifest.id {
+            return Ok(Vec::new());
+        }
+        if let RecvState::Done(outcome) = &self.state {
+            // The sender missed the news.
+            return Ok(match packet {
+                Packet::Offer(_) | Packet::Chunk { .. } => vec![self.complete(outcome)],
+                _ => Vec::new(),
+            });
+        }
+        match packet {
+            Packet::Offer(_) => Ok(vec![self.resume()?]),
+            Packet::Chunk { index, data, .. } => {
+                if index == self.next {
+                    if data.len() != self.manifest.chunk_len(index) {
+                        let reason = format!(
+                            "chunk {} is {} bytes, expected {}",
+                            index,
+                            data.len(),
+                            self.manifest.chunk_len(index)
+                        );
+                        self.state = RecvState::Done(Outcome::Rejected(reason.clone()));
+                        return Ok(vec![Packet::Reject {
+                            id: self.manifest.id.clone(),
+                            reason,
+                        }]);
+                    }
+                    self.sink.write_all(&data)?;
+                    self.hasher.update(&data);
+                    self.next += 1;
+                    if self.next == self.manifest.chunks() {
+                        let outcome = self.finish()?;
+                        return Ok(vec![self.complete(&outcome)]);
+                    }
+                }
+                // In order, a duplicate, or past a gap: say what's in.
+                Ok(vec![Packet::Ack {
+                    id: self.manifest.id.clone(),
+                    next: self.next,
+                }])
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<Outcome> {
+        self.sink.flush()?;
+        let actual = hex(&std::mem::take(&mut self.hasher).finalize());
+        let outcome = if actual == self.manifest.sha256 {
+            Outcome::Verified
+        } else {
+            Outcome::HashMismatch
+        };
+        self.state = RecvState::Done(outcome.clone());
+        Ok(outcome)
+    }
+
+    fn complete(&self, outcome: &Outcome) -> Packet {
+        let id = self.manifest.id.clone();
+        match outcome {
+            Outcome::Rejected(reason) => Packet::Reject {
+                id,
+                reason: reason.clone(),
+            },
+            outcome => Packet::Complete {
+                id,
+                verified: *outcome == Outcome::Verified,
+            },
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Bookkeeping
+// ════════════════════════════════════════════════════════════════════
+
+/// Offers waiting for `!hive accept-file <id>`, oldest first.
+#[derive(Debug, Default)]
+pub struct PendingOffers {
+    offers: Vec<(String, Manifest)>,
+}
+
+impl PendingOffers {
+    /// Queue `offer` from `peer`; a repeated offer replaces the first.
+    pub fn push(&mut self, peer: impl Into<String>, offer: Manifest) {
+        self.offers.retain(|(_, queued)| queued.id != offer.id);
+        self.offers.push((peer.into(), offer));
+    }
+
+    /// Take the offer `id` to accept or decline it, with its peer.
+    pub fn take(&mut self, id: &str) -> Option<(String, Manifest)> {
+        let at = self.offers.iter().position(|(_, offer)| offer.id == id)?;
+        Some(self.offers.remove(at))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Manifest)> {
+        self.offers
+            .iter()
+            .map(|(peer, offer)| (peer.as_str(), offer))
+    }
+
+    pub fn len(&self) -> usize {
+        self.offers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offers.is_empty()
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -244,3 +244,350 @@ fn test_hive_event_serialization() {
     let deserialized: HiveEvent = serde_json::from_str(&json).unwrap();
     assert!(matches!(deserialized, HiveEvent::Error(_)));
 }
+
+// ============================================================================
+// File Transfer Tests
+// ============================================================================
+
+use positronic_hive::transfer::{
+    self, Decision, Manifest, Outcome, Packet, Receiver, Sender, TransferPolicy, sanitize_file_name,
+};
+use std::time::{Duration, Instant};
+
+/// A link that drops `percent` of packets, the same ones every run.
+struct Link {
+    seed: u64,
+    percent: u64,
+}
+
+impl Link {
+    fn delivers(&mut self) -> bool {
+        self.seed = self
+            .seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.seed >> 33) % 100 >= self.percent
+    }
+}
+
+fn content(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn small_chunks(manifest: Manifest) -> Manifest {
+    Manifest {
+        chunk_size: 1000,
+        ..manifest
+    }
+}
+
+/// Pipe `sender` and `receiver` at each other over `link`, 100 ms a tick,
+/// cutting the link every `cut_every` ticks. Returns the chunks sent.
+fn pipe(
+    sender: &mut Sender<Vec<u8>>,
+    receiver: &mut Receiver<Vec<u8>>,
+    link: &mut Link,
+    cut_every: Option<u32>,
+) -> usize {
+    let mut now = Instant::now();
+    let mut chunks_sent = 0;
+    for tick in 1..20_000u32 {
+        now += Duration::from_millis(100);
+        if cut_every.is_some_and(|every| tick % every == 0) {
+            // Whatever was in flight is gone; both ends say where they are.
+            sender.reconnected();
+            let resume = receiver.resume().unwrap();
+            if link.delivers() {
+                sender.on_packet(resume, now);
+            }
+        }
+        for packet in sender.poll(now).unwrap() {
+            chunks_sent += matches!(packet, Packet::Chunk { .. }) as usize;
+            if !link.delivers() {
+                continue;
+            }
+            for reply in receiver.on_packet(packet).unwrap() {
+                if link.delivers() {
+                    sender.on_packet(reply, now);
+                }
+            }
+        }
+        if sender.outcome().is_some() {
+            return chunks_sent;
+        }
+    }
+    panic!("transfer never finished: {:?} / {:?}", sender, receiver);
+}
+
+fn start(data: &[u8]) -> (Sender<Vec<u8>>, Receiver<Vec<u8>>) {
+    let manifest = small_chunks(Manifest::for_bytes("t1", "notes.txt", data));
+    (
+        Sender::new(manifest.clone(), data.to_vec()),
+        Receiver::new(manifest, Vec::new()),
+    )
+}
+
+#[test]
+fn test_transfer_over_a_clean_link() {
+    let data = content(50_500);
+    let (mut sender, mut receiver) = start(&data);
+    let sent = pipe(
+        &mut sender,
+        &mut receiver,
+        &mut Link {
+            seed: 1,
+            percent: 0,
+        },
+        None,
+    );
+    assert_eq!(sent, 51, "each chunk once");
+    assert_eq!(sender.outcome(), Some(&Outcome::Verified));
+    assert_eq!(receiver.outcome(), Some(&Outcome::Verified));
+    assert_eq!(sender.progress(), (50_500, 50_500));
+    assert_eq!(receiver.into_sink(), data);
+}
+
+#[test]
+fn test_transfer_survives_packet_loss() {
+    for (seed, percent) in [(7, 10), (42, 30), (99, 50)] {
+        let data = content(40_000);
+        let (mut sender, mut receiver) = start(&data);
+        pipe(
+            &mut sender,
+            &mut receiver,
+            &mut Link { seed, percent },
+            None,
+        );
+        assert_eq!(
+            sender.outcome(),
+            Some(&Outcome::Verified),
+            "{}% loss",
+            percent
+        );
+        assert_eq!(receiver.into_sink(), data, "{}% loss", percent);
+    }
+}
+
+#[test]
+fn test_transfer_survives_disconnects_and_loss() {
+    let data = content(120_000);
+    let (mut sender, mut receiver) = start(&data);
+    pipe(
+        &mut sender,
+        &mut receiver,
+        &mut Link {
+            seed: 3,
+            percent: 20,
+        },
+        Some(7),
+    );
+    assert_eq!(sender.outcome(), Some(&Outcome::Verified));
+    assert_eq!(receiver.into_sink(), data);
+}
+
+#[test]
+fn test_transfer_resumes_where_the_receiver_left_off() {
+    let data = content(100_000);
+    let (mut sender, mut receiver) = start(&data);
+    let now = Instant::now();
+    let offer = sender.poll(now).unwrap();
+    sender.on_packet(receiver.on_packet(offer[0].clone()).unwrap().remove(0), now);
+    // Half the file arrives, then the link drops with chunks in flight.
+    while receiver.progress().0 < 50_000 {
+        for packet in sender.poll(now).unwrap() {
+            for reply in receiver.on_packet(packet).unwrap() {
+                sender.on_packet(reply, now);
+            }
+        }
+    }
+    let in_flight = sender.poll(now).unwrap();
+    assert!(!in_flight.is_empty());
+
+    sender.reconnected();
+    let resume = receiver.resume().unwrap();
+    let next = receiver.progress().0 / 1000;
+    assert_eq!(
+        resume,
+        Packet::Resume {
+            id: "t1".to_string(),
+            next
+        }
+    );
+    sender.on_packet(resume, now);
+    let Packet::Chunk { index, .. } = &sender.poll(now).unwrap()[0] else {
+        panic!("expected a chunk")
+    };
+    assert_eq!(*index, next, "continues, doesn't restart");
+}
+
+#[test]
+fn test_transfer_reports_a_hash_mismatch() {
+    let data = content(5_000);
+    let mut manifest = small_chunks(Manifest::for_bytes("t2", "x.bin", &data));
+    manifest.sha256 = "0".repeat(64);
+    let mut sender = Sender::new(manifest.clone(), data);
+    let mut receiver = Receiver::new(manifest, Vec::new());
+    pipe(
+        &mut sender,
+        &mut receiver,
+        &mut Link {
+            seed: 5,
+            percent: 0,
+        },
+        None,
+    );
+    assert_eq!(sender.outcome(), Some(&Outcome::HashMismatch));
+    assert_eq!(receiver.outcome(), Some(&Outcome::HashMismatch));
+}
+
+#[test]
+fn test_transfer_rejects_a_chunk_of_the_wrong_size() {
+    let data = content(3_000);
+    let manifest = small_chunks(Manifest::for_bytes("t3", "x.bin", &data));
+    let mut receiver = Receiver::new(manifest, Vec::new());
+    let replies = receiver
+        .on_packet(Packet::Chunk {
+            id: "t3".to_string(),
+            index: 0,
+            data: vec![0; 4000],
+        })
+        .unwrap();
+    assert!(matches!(&replies[..], [Packet::Reject { .. }]));
+    assert!(matches!(receiver.outcome(), Some(Outcome::Rejected(_))));
+    assert!(receiver.into_sink().is_empty(), "nothing written");
+}
+
+#[test]
+fn test_transfer_of_an_empty_file() {
+    let manifest = Manifest::for_bytes("t4", "empty", b"");
+    let mut receiver = Receiver::new(manifest, Vec::new());
+    assert_eq!(
+        receiver.resume().unwrap(),
+        Packet::Complete {
+            id: "t4".to_string(),
+            verified: true
+        }
+    );
+}
+
+#[test]
+fn test_transfer_sanitizes_received_names() {
+    assert_eq!(
+        sanitize_file_name("../../etc/passwd").as_deref(),
+        Some("passwd")
+    );
+    assert_eq!(
+        sanitize_file_name("..\\..\\boot.ini").as_deref(),
+        Some("boot.ini")
+    );
+    assert_eq!(
+        sanitize_file_name("/abs/path/report.pdf").as_deref(),
+        Some("report.pdf")
+    );
+    assert_eq!(
+        sanitize_file_name("a\u{7}b:c?.txt").as_deref(),
+        Some("a_b_c_.txt")
+    );
+    assert_eq!(sanitize_file_name(".bashrc").as_deref(), Some("bashrc"));
+    assert_eq!(sanitize_file_name("CON.txt").as_deref(), Some("_CON.txt"));
+    assert_eq!(sanitize_file_name("com1").as_deref(), Some("_com1"));
+    for nothing in ["", "..", ".", "dir/", "../..", " . "] {
+        assert_eq!(sanitize_file_name(nothing), None, "{:?}", nothing);
+    }
+    let long = sanitize_file_name(&"é".repeat(300)).unwrap();
+    assert!(long.len() <= 200 && long.chars().all(|c| c == 'é'));
+}
+
+#[test]
+fn test_transfer_decides_on_offers() {
+    let offer = Manifest::for_bytes("t5", "notes.txt", b"hello");
+    let policy = TransferPolicy::default();
+    assert_eq!(transfer::decide(&offer, false, &policy, 0), Decision::Ask);
+    assert_eq!(
+        transfer::decide(&offer, true, &policy, 0),
+        Decision::Ask,
+        "auto-accept is off"
+    );
+    let auto = TransferPolicy {
+        auto_accept: true,
+        ..policy
+    };
+    assert_eq!(transfer::decide(&offer, true, &auto, 0), Decision::Accept);
+    assert_eq!(
+        transfer::decide(&offer, false, &auto, 0),
+        Decision::Ask,
+        "only trusted peers"
+    );
+
+    let reject = |offer: &Manifest, policy: &TransferPolicy, active| {
+        matches!(
+            transfer::decide(offer, true, policy, active),
+            Decision::Reject(_)
+        )
+    };
+    assert!(reject(&offer, &auto, auto.max_active), "too many running");
+    assert!(reject(
+        &Manifest {
+            size: 11,
+            ..offer.clone()
+        },
+        &TransferPolicy {
+            max_size: 10,
+            ..auto
+        },
+        0
+    ));
+    assert!(reject(
+        &Manifest {
+            name: "../".to_string(),
+            ..offer.clone()
+        },
+        &auto,
+        0
+    ));
+    assert!(reject(
+        &Manifest {
+            sha256: "abc".to_string(),
+            ..offer.clone()
+        },
+        &auto,
+        0
+    ));
+    assert!(reject(
+        &Manifest {
+            chunk_size: 0,
+            ..offer.clone()
+        },
+        &auto,
+        0
+    ));
+}
+
+#[test]
+fn test_transfer_pending_offers_queue() {
+    let mut pending = transfer::PendingOffers::default();
+    pending.push("alice", Manifest::for_bytes("a", "one", b"1"));
+    pending.push("bob", Manifest::for_bytes("b", "two", b"2"));
+    pending.push("alice", Manifest::for_bytes("a", "one", b"1"));
+    assert_eq!(pending.len(), 2, "a repeated offer replaces the first");
+    assert_eq!(
+        pending.iter().map(|(peer, _)| peer).collect::<Vec<_>>(),
+        vec!["bob", "alice"]
+    );
+    assert_eq!(
+        pending.take("a").map(|(peer, offer)| (peer, offer.name)),
+        Some(("alice".to_string(), "one".to_string()))
+    );
+    assert!(pending.take("a").is_none());
+}
+
+#[test]
+fn test_transfer_packets_round_trip_as_json() {
+    let packet = Packet::Chunk {
+        id: "t6".to_string(),
+        index: 3,
+        data: vec![1, 2, 3],
+    };
+    let json = serde_json::to_string(&packet).unwrap();
+    assert_eq!(serde_json::from_str::<Packet>(&json).unwrap(), packet);
+}
@@ -0,0 +1,185 @@
+//! Baud rate detection for `!io connect --auto-baud`.
+//!
+//! A UART read at the wrong rate still delivers bytes, just not the ones
+//! sent: bits land in the wrong places, stop bits go missing and the
+//! result is control characters, invalid UTF-8 and runs of `0x00` or
+//! `0xFF`. At the right rate a chatty device's output looks like text.
+//! `detect` listens at each candidate rate in turn on one open port and
+//! ranks the rates by how text-like their bytes are.
+//!
+//! The port stays open throughout and only its rate changes, so a board
+//! that resets when DTR rises resets once, not once per rate. A device
+//! that says nothing unprompted can't be detected this way.
+
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+/// Common rates, most likely first; earlier ones win ties.
+pub const DEFAULT_CANDIDATES: [u32; 7] = [115_200, 9600, 57_600, 38_400, 19_200, 230_400, 1_000_000];
+
+/// How long each rate is listened to.
+pub const DEFAULT_DWELL: Duration = Duration::from_millis(400);
+
+/// Score a rate needs to be picked without asking.
+pub const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Bytes a rate needs to be picked at all; a few stray bytes can look
+/// like anything.
+pub const DEFAULT_MIN_BYTES: usize = 16;
+
+/// Most bytes kept from one rate.
+const LISTEN_LIMIT: usize = 4096;
+
+/// Lines longer than this without a break count against the text.
+const UNBROKEN_LINE: usize = 160;
+
+/// Rates how plausible bytes are as a device's output, 0.0 to 1.0.
+pub type Scorer = fn(&[u8]) -> f64;
+
+/// How `detect` probes: which rates, for how long and how bytes are judged.
+#[derive(Debug, Clone)]
+pub struct AutoBaud {
+    pub candidates: Vec<u32>,
+    pub dwell: Duration,
+    pub threshold: f64,
+    pub min_bytes: usize,
+    pub scorer: Scorer,
+}
+
+impl Default for AutoBaud {
+    fn default() -> Self {
+        Self {
+            candidates: DEFAULT_CANDIDATES.to_vec(),
+            dwell: DEFAULT_DWELL,
+            threshold: DEFAULT_THRESHOLD,
+            min_bytes: DEFAULT_MIN_BYTES,
+            scorer: score,
+        }
+    }
+}
+
+impl AutoBaud {
+    /// The rate to use from `ranked` (best first): the best one if it
+    /// clears the threshold on enough bytes.
+    pub fn pick(&self, ranked: &[BaudGuess]) -> Option<u32> {
+        let best = ranked.first()?;
+        (best.score >= self.threshold && best.bytes >= self.min_bytes).then_some(best.baud)
+    }
+}
+
+/// What listening at one rate turned up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaudGuess {
+    pub baud: u32,
+    pub score: f64,
+    /// Bytes received while listening.
+    pub bytes: usize,
+}
+
+impl std::fmt::Display for BaudGuess {
+    /// `9600 (0.52)`, or `9600 (silent)` when nothing came.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.bytes == 0 {
+            write!(f, "{} (silent)", self.baud)
+        } else {
+            write!(f, "{} ({:.2})", self.baud, self.score)
+        }
+    }
+}
+
+/// The default scorer. Printable text (ASCII or valid UTF-8, plus tab,
+/// CR and LF) counts for, line breaks count for, and bytes in runs of
+/// `0x00` or `0xFF` count against: those are what framing errors at the
+/// wrong rate produce. Nothing at all scores 0.
+pub fn score(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut printable = 0;
+    let mut breaks = 0;
+    let mut longest_line = 0;
+    let mut line = 0;
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if matches!(c, '\n' | '\r') {
+                printable += 1;
+                breaks += 1;
+                line = 0;
+                continue;
+            }
+            if c == '\t' || !c.is_control() {
+                printable += c.len_utf8();
+            }
+            line += 1;
+            longest_line = longest_line.max(line);
+        }
+    }
+
+    let framing: usize = bytes
+        .chunk_by(|a, b| a == b)
+        .filter(|run| run.len() > 1 && matches!(run[0], 0x00 | 0xFF))
+        .map(<[u8]>::len)
+        .sum();
+
+    let total = bytes.len() as f64;
+    let text = printable as f64 / total;
+    // Short bursts may not have reached a line break yet.
+    let lines = if breaks > 0 && longest_line <= UNBROKEN_LINE {
+        1.0
+    } else if breaks > 0 || bytes.len() <= UNBROKEN_LINE {
+        0.5
+    } else {
+        0.0
+    };
+    let score = (0.8 * text + 0.2 * lines) * (1.0 - framing as f64 / total);
+    score.clamp(0.0, 1.0)
+}
+
+/// A port whose rate can change while it stays open; the system's serial
+/// ports, or a fake in tests.
+pub trait RateSwitch {
+    fn set_rate(&mut self, baud: u32) -> Result<(), serialport::Error>;
+    /// Drop anything pending, then collect what arrives within `dwell`.
+    fn listen(&mut self, dwell: Duration) -> std::io::Result<Vec<u8>>;
+}
+
+impl RateSwitch for Box<dyn serialport::SerialPort> {
+    fn set_rate(&mut self, baud: u32) -> Result<(), serialport::Error> {
+        self.set_baud_rate(baud)
+    }
+
+    fn listen(&mut self, dwell: Duration) -> std::io::Result<Vec<u8>> {
+        // Bytes already buffered arrived at the previous rate.
+        let _ = self.clear(serialport::ClearBuffer::Input);
+        let deadline = Instant::now() + dwell;
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; 512];
+        while Instant::now() < deadline && bytes.len() < LISTEN_LIMIT {
+            match self.read(&mut buf) {
+                Ok(n) => bytes.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(e),
+            }
+        }
+        bytes.truncate(LISTEN_LIMIT);
+        Ok(bytes)
+    }
+}
+
+/// Listen at each of `config`'s rates and rank them, best first. Rates the
+/// port refuses are left out; a read error (the device went away) ends
+/// the probe. The port is left at the last rate tried.
+pub fn detect(port: &mut dyn RateSwitch, config: &AutoBaud) -> std::io::Result<Vec<BaudGuess>> {
+    let mut guesses = Vec::with_capacity(config.candidates.len());
+    for &baud in &config.candidates {
+        if let Err(e) = port.set_rate(baud) {
+            tracing::debug!("skipping {} baud: {}", baud, e);
+            continue;
+        }
+        let bytes = port.listen(config.dwell)?;
+        guesses.push(BaudGuess { baud, score: (config.scorer)(&bytes), bytes: bytes.len() });
+    }
+    // Stable, so equal scores keep the candidates' order.
+    guesses.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(guesses)
+}
@@ -0,0 +1,109 @@
+//! Which device is behind a port.
+//!
+//! Port names are handed out at enumeration: the board that was `COM7`
+//! yesterday is `COM9` today, `/dev/ttyUSB3` after a replug. A USB
+//! device's vendor id, product id and serial number don't change, so
+//! `DeviceId` is what the rest of Positronic remembers a device by, and
+//! `ports_of` finds where it is now. Boards without a serial number are
+//! told apart by VID:PID only, so two identical ones look the same.
+//!
+//! A composite device (a debug probe with a UART and a trace port, say)
+//! enumerates as several ports with one identity; `ports_of` lists them
+//! in enumeration order and callers take the first.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A USB device: `VID:PID`, and its serial number when it reports one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeviceId {
+    pub vid: u16,
+    pub pid: u16,
+    pub serial: Option<String>,
+}
+
+impl DeviceId {
+    pub fn new(vid: u16, pid: u16, serial: Option<&str>) -> DeviceId {
+        let serial = serial.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+        DeviceId { vid, pid, serial }
+    }
+
+    /// `2341:0043`, as `HardwareMonitor::usb_id` reports it.
+    pub fn usb_id(&self) -> String {
+        format!("{:04x}:{:04x}", self.vid, self.pid)
+    }
+}
+
+/// `2341:0043:75833353934351E0152`, or `2341:0043` without a serial.
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.serial {
+            Some(serial) => write!(f, "{}:{}", self.usb_id(), serial),
+            None => f.write_str(&self.usb_id()),
+        }
+    }
+}
+
+impl FromStr for DeviceId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DeviceId, String> {
+        let mut parts = s.splitn(3, ':');
+        let hex = |part: Option<&str>| part.and_then(|p| u16::from_str_radix(p, 16).ok());
+        match (hex(parts.next()), hex(parts.next())) {
+            (Some(vid), Some(pid)) => Ok(DeviceId::new(vid, pid, parts.next())),
+            _ => Err(format!("'{}' is not a VID:PID[:serial] device id", s)),
+        }
+    }
+}
+
+/// A listed port and, for USB ports, the device behind it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortInfo {
+    pub port: String,
+    pub device: Option<DeviceId>,
+    /// The product string the device reports, e.g. `Arduino Uno`.
+    pub product: Option<String>,
+}
+
+impl PortInfo {
+    pub fn plain(port: &str) -> PortInfo {
+        PortInfo { port: port.to_string(), device: None, product: None }
+    }
+
+    pub fn usb(port: &str, device: DeviceId, product: Option<&str>) -> PortInfo {
+        PortInfo { port: port.to_string(), device: Some(device), product: product.map(str::to_string) }
+    }
+}
+
+impl From<serialport::SerialPortInfo> for PortInfo {
+    fn from(info: serialport::SerialPortInfo) -> PortInfo {
+        match info.port_type {
+            serialport::SerialPortType::UsbPort(usb) => PortInfo {
+                port: info.port_name,
+                device: Some(DeviceId::new(usb.vid, usb.pid, usb.serial_number.as_deref())),
+                product: usb.product,
+            },
+            _ => PortInfo::plain(&info.port_name),
+        }
+    }
+}
+
+/// The ports the OS lists now; none if it can't list them.
+pub fn system_ports() -> Vec<PortInfo> {
+    serialport::available_ports()
+        .map(|ports| ports.into_iter().map(PortInfo::from).collect())
+        .unwrap_or_default()
+}
+
+/// The device behind `port`, if it is a USB port that is still there.
+pub fn identify<'a>(ports: &'a [PortInfo], port: &str) -> Option<&'a PortInfo> {
+    ports.iter().find(|p| p.port == port && p.device.is_some())
+}
+
+/// Where `device` enumerates now, in enumeration order.
+pub fn ports_of<'a>(ports: &'a [PortInfo], device: &DeviceId) -> Vec<&'a str> {
+    ports.iter().filter(|p| p.device.as_ref() == Some(device)).map(|p| p.port.as_str()).collect()
+}
@@ -4,11 +4,24 @@
 //! Bypasses PTY for high-frequency Serial/USB communication.
 //! Critical for "Oscilloscope Mode" and Embedded Development.
 
-use serialport::SerialPort;
+pub mod baud;
+pub mod device;
+pub mod overflow;
+pub mod parser;
+pub mod probe;
+
+use std::collections::HashMap;
+use std::io::Write;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc; // Requires 'serialport' crate
 
+pub use baud::{AutoBaud, BaudGuess};
+pub use device::{DeviceId, PortInfo};
+pub use overflow::{OverflowPolicy, SpillBuffer};
+pub use parser::{LineDecoder, LineParser, ParserConfig};
+pub use probe::{Platform, PortAccess, PortIo};
+
 /// High-frequency data point for the Oscilloscope
 #[derive(Debug, Clone, Copy)]
 pub struct SensorSample {
@@ -22,9 +35,30 @@ pub struct SensorSample {
 pub enum HardwareEvent {
     DeviceConnected(String),
     DeviceDisconnected(String),
-    DataBatch(Vec<SensorSample>),
-    SerialOutput(String),
+    /// Parsed samples from one port's reader.
+    DataBatch { port: String, samples: Vec<SensorSample> },
+    /// Text from one port's reader that didn't parse as samples.
+    SerialOutput { port: String, text: String },
     Error(String),
+    /// A port's reader had to discard data because the consumer fell behind.
+    /// Emitted once the channel drains; counts cover everything since the
+    /// previous report. `sample_range` is the (min, max) of dropped samples.
+    Overflow {
+        port: String,
+        dropped_bytes: usize,
+        dropped_samples: usize,
+        sample_range: Option<(f32, f32)>,
+    },
+    /// Whether a scanned port could be opened (`!io scan --probe`).
+    PortAccess { port: String, access: PortAccess },
+    /// The USB device behind a scanned or connected port. `name` is the
+    /// user's name for it; this crate leaves it `None` for the engine to
+    /// fill in.
+    Identified { port: String, device: DeviceId, product: Option<String>, name: Option<String> },
+    /// How `--auto-baud` went: every rate tried, best first, and the one
+    /// picked. With a pick, `DeviceConnected` follows at that rate;
+    /// without one the port was closed again.
+    BaudDetected { port: String, baud: Option<u32>, guesses: Vec<BaudGuess> },
 }
 
 /// Configuration for a Serial Connection
@@ -34,20 +68,69 @@ pub struct SerialConfig {
     pub baud_rate: u32,
     pub data_bits: u8,
     pub flow_control: bool,
+    /// What the reader does when the event channel is full.
+    pub overflow: OverflowPolicy,
+    /// How lines become samples; raw sends everything as `SerialOutput`.
+    pub parser: ParserConfig,
+    /// When the port drops, wait up to `RECONNECT_WINDOW` for this device
+    /// to come back, on whatever port, and open it again.
+    pub reconnect: Option<DeviceId>,
 }
 
+/// How long a dropped port with `reconnect` set waits for its device.
+pub const RECONNECT_WINDOW: Duration = Duration::from_secs(60);
+
+/// How often the OS port list is checked while waiting.
+const RECONNECT_POLL: Duration = Duration::from_millis(500);
+
 /// Commands sent to the IO Thread
 enum IOCommand {
     Connect(SerialConfig),
+    /// Connect at whichever rate `AutoBaud` finds; `baud_rate` is ignored.
+    AutoConnect(SerialConfig, AutoBaud),
     Disconnect(String),
-    Scan,
+    /// List ports; with `probe`, also try opening each one.
+    Scan { probe: bool },
+    /// Report the USB device behind each listed port, and nothing else.
+    Identify,
     Stop,
 }
 
+/// Write halves of open ports, cloned from the reader's handle.
+type Writers = Arc<Mutex<HashMap<String, Box<dyn serialport::SerialPort>>>>;
+
+/// Why a `HardwareMonitor` call failed.
+#[derive(Debug, thiserror::Error)]
+pub enum IoError {
+    /// The IO thread has exited; nothing more can be sent to it.
+    #[error("IO Thread Dead")]
+    ThreadDead,
+    #[error("{port} is not connected")]
+    NotConnected { port: String },
+    #[error("writing to {port} failed: {source}")]
+    Write {
+        port: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl IoError {
+    /// The port the failure concerns, if it is about one.
+    pub fn port(&self) -> Option<&str> {
+        match self {
+            IoError::ThreadDead => None,
+            IoError::NotConnected { port } | IoError::Write { port, .. } => Some(port),
+        }
+    }
+}
+
 /// The Hardware Monitor Engine
 pub struct HardwareMonitor {
     /// Active ports are tracked by name for UI status
     active_ports: Arc<Mutex<Vec<String>>>,
+    /// Where `write` sends bytes; a port leaves when its reader exits.
+    writers: Writers,
     /// Command channel to the I/O thread
     cmd_tx: mpsc::Sender<IOCommand>,
 }
@@ -64,10 +147,14 @@ impl HardwareMonitor {
         let (event_tx, event_rx) = mpsc::channel(1024);
         let (cmd_tx, mut cmd_rx) = mpsc::channel(32);
 
+        let writers: Writers = Arc::new(Mutex::new(HashMap::new()));
         let monitor = Self {
             active_ports: Arc::new(Mutex::new(Vec::new())),
-            cmd_tx,
+            writers: writers.clone(),
+            cmd_tx: cmd_tx.clone(),
         };
+        // Weak, so dropping the monitor still ends the thread.
+        let cmd_tx = cmd_tx.downgrade();
 
         // The Dedicated IO Thread
         tokio::spawn(async move {
@@ -79,45 +166,39 @@ impl HardwareMonitor {
             while let Some(cmd) = cmd_rx.recv().await {
                 match cmd {
                     IOCommand::Connect(config) => {
-                        let port_name = config.port_name.clone();
-                        match serialport::new(&port_name, config.baud_rate)
+                        match serialport::new(&config.port_name, config.baud_rate)
                             .timeout(Duration::from_millis(10))
                             .open()
                         {
-                            Ok(port) => {
+                            Ok(port) => attach(port, config, &event_tx, &writers, &cmd_tx).await,
+                            Err(e) => {
                                 let _ = event_tx
-                                    .send(HardwareEvent::DeviceConnected(port_name.clone()))
+                                    .send(HardwareEvent::Error(format!(
+                                        "Failed to open {}: {}",
+                                        config.port_name, e
+                                    )))
                                     .await;
-                                // Spawn a dedicated reader for this port
-                                let tx_clone = event_tx.clone();
-                                let mut owned_port = port; // Move ownership
-
-                                tokio::task::spawn_blocking(move || {
-                                    let mut buffer: Vec<u8> = vec![0; 1024];
-                                    loop {
-                                        match owned_port.read(&mut buffer) {
-                                            Ok(bytes_read) if bytes_read > 0 => {
-                                                let s =
-                                                    String::from_utf8_lossy(&buffer[..bytes_read])
-                                                        .to_string();
-                                                // Send via blocking calls or channel
-                                                let _ = tx_clone
-                                                    .blocking_send(HardwareEvent::SerialOutput(s));
-                                            }
-                                            Ok(_) => {} // Zero bytes
-                                            Err(ref e)
-                                                if e.kind() == std::io::ErrorKind::TimedOut => {}
-                                            Err(_) => break, // Port closed/error
-                                        }
-                                        std::thread::sleep(Duration::from_millis(1));
-                                    }
+                            }
+                        }
+                    }
+                    IOCommand::AutoConnect(config, auto) => {
+                        let first = auto.candidates.first().copied().unwrap_or(config.baud_rate);
+                        match serialport::new(&config.port_name, first)
+                            .timeout(Duration::from_millis(10))
+                            .open()
+                        {
+                            // Off the command loop: listening takes a dwell per rate.
+                            Ok(port) => {
+                                let (event_tx, writers, cmd_tx) = (event_tx.clone(), writers.clone(), cmd_tx.clone());
+                                tokio::spawn(async move {
+                                    auto_connect(port, config, auto, &event_tx, &writers, &cmd_tx).await;
                                 });
                             }
                             Err(e) => {
                                 let _ = event_tx
                                     .send(HardwareEvent::Error(format!(
                                         "Failed to open {}: {}",
-                                        port_name, e
+                                        config.port_name, e
                                     )))
                                     .await;
                             }
@@ -125,16 +206,41 @@ impl HardwareMonitor {
                     }
                     IOCommand::Disconnect(port) => {
                         // In a real impl, we'd signal the reader thread to stop via a cancellation token map.
+                        writers.lock().unwrap().remove(&port);
                         let _ = event_tx.send(HardwareEvent::DeviceDisconnected(port)).await;
                     }
-                    IOCommand::Scan => {
+                    IOCommand::Scan { probe } => {
                         match serialport::available_ports() {
                             Ok(ports) => {
-                                for p in ports {
+                                let ports: Vec<PortInfo> = ports.into_iter().map(PortInfo::from).collect();
+                                let names: Vec<String> = ports.iter().map(|p| p.port.clone()).collect();
+                                for name in &names {
                                     // Advertise available ports
                                     let _ = event_tx
-                                        .send(HardwareEvent::DeviceConnected(p.port_name))
+                                        .send(HardwareEvent::DeviceConnected(name.clone()))
                                         .await;
+                                    if let Some(event) = identified(&ports, name) {
+                                        let _ = event_tx.send(event).await;
+                                    }
+                                }
+                                if probe {
+                                    // Off the command loop: probes can take up to the timeout.
+                                    let tx = event_tx.clone();
+                                    tokio::spawn(async move {
+                                        let results = tokio::task::spawn_blocking(move || {
+                                            probe::probe_ports(
+                                                Arc::new(probe::SystemPorts),
+                                                &names,
+                                                probe::PROBE_TIMEOUT,
+                                                Platform::current(),
+                                            )
+                                        })
+                                        .await
+                                        .unwrap_or_default();
+                                        for (port, access) in results {
+                                            let _ = tx.send(HardwareEvent::PortAccess { port, access }).await;
+                                        }
+                                    });
                                 }
                             }
                             Err(e) => {
@@ -144,6 +250,14 @@ impl HardwareMonitor {
                             }
                         }
                     }
+                    IOCommand::Identify => {
+                        let ports = device::system_ports();
+                        for port in &ports {
+                            if let Some(event) = identified(&ports, &port.port) {
+                                let _ = event_tx.send(event).await;
+                            }
+                        }
+                    }
                     IOCommand::Stop => break,
                 }
             }
@@ -152,23 +266,175 @@ impl HardwareMonitor {
         (monitor, event_rx)
     }
 
-    pub async fn connect(&self, port: &str, baud: u32) -> anyhow::Result<()> {
+    pub async fn connect(&self, port: &str, baud: u32) -> Result<(), IoError> {
         let config = SerialConfig {
             port_name: port.to_string(),
             baud_rate: baud,
             data_bits: 8,
             flow_control: false,
+            overflow: OverflowPolicy::default(),
+            parser: ParserConfig::default(),
+            reconnect: None,
         };
-        self.cmd_tx
-            .send(IOCommand::Connect(config))
-            .await
-            .map_err(|_| anyhow::anyhow!("IO Thread Dead"))
+        self.connect_with(config).await
+    }
+
+    /// Connect with a full configuration (e.g. a non-default overflow policy).
+    pub async fn connect_with(&self, config: SerialConfig) -> Result<(), IoError> {
+        self.cmd_tx.send(IOCommand::Connect(config)).await.map_err(|_| IoError::ThreadDead)
+    }
+
+    /// Connect at the rate `auto` detects. `HardwareEvent::BaudDetected`
+    /// reports the result; the port opens once and only its rate changes.
+    pub async fn connect_auto(&self, config: SerialConfig, auto: AutoBaud) -> Result<(), IoError> {
+        self.cmd_tx.send(IOCommand::AutoConnect(config, auto)).await.map_err(|_| IoError::ThreadDead)
+    }
+
+    /// `VID:PID` of a USB serial port (`2341:0043`), to recognize the
+    /// device when it comes back. `None` for other ports or if it is gone.
+    pub fn usb_id(port: &str) -> Option<String> {
+        device::identify(&device::system_ports(), port)?.device.as_ref().map(DeviceId::usb_id)
+    }
+
+    /// Send `data` to an open port, e.g. keystrokes from `!io console`.
+    pub fn write(&self, port: &str, data: &[u8]) -> Result<(), IoError> {
+        let mut writers = self.writers.lock().unwrap();
+        let writer = writers
+            .get_mut(port)
+            .ok_or_else(|| IoError::NotConnected { port: port.to_string() })?;
+        writer
+            .write_all(data)
+            .and_then(|()| writer.flush())
+            .map_err(|source| IoError::Write { port: port.to_string(), source })
+    }
+
+    /// Whether `port` is open and can be written to.
+    pub fn is_writable(&self, port: &str) -> bool {
+        self.writers.lock().unwrap().contains_key(port)
+    }
+
+    pub async fn scan_ports(&self) -> Result<(), IoError> {
+        self.cmd_tx.send(IOCommand::Scan { probe: false }).await.map_err(|_| IoError::ThreadDead)
+    }
+
+    /// Send `HardwareEvent::Identified` for each USB port listed now, e.g.
+    /// after a device was renamed.
+    pub async fn identify_ports(&self) -> Result<(), IoError> {
+        self.cmd_tx.send(IOCommand::Identify).await.map_err(|_| IoError::ThreadDead)
+    }
+
+    /// Scan, then open and close each port found to report whether it is
+    /// usable (`HardwareEvent::PortAccess`). Opening resets some boards.
+    pub async fn scan_and_probe(&self) -> Result<(), IoError> {
+        self.cmd_tx.send(IOCommand::Scan { probe: true }).await.map_err(|_| IoError::ThreadDead)
+    }
+}
+
+/// Register an open port and start its reader: the rest of a connect.
+async fn attach(
+    port: Box<dyn serialport::SerialPort>,
+    config: SerialConfig,
+    event_tx: &mpsc::Sender<HardwareEvent>,
+    writers: &Writers,
+    cmd_tx: &mpsc::WeakSender<IOCommand>,
+) {
+    let port_name = config.port_name.clone();
+    match port.try_clone() {
+        Ok(writer) => {
+            writers.lock().unwrap().insert(port_name.clone(), writer);
+        }
+        Err(e) => tracing::warn!("{} is read-only: {}", port_name, e),
+    }
+    let _ = event_tx.send(HardwareEvent::DeviceConnected(port_name.clone())).await;
+    let ports = device::system_ports();
+    if let Some(event) = identified(&ports, &port_name) {
+        let _ = event_tx.send(event).await;
     }
+    // Spawn a dedicated reader for this port
+    let tx_clone = event_tx.clone();
+    let mut owned_port = port; // Move ownership
+    let policy = config.overflow;
+    let decoder = config.parser.build().map(LineDecoder::new);
+    let writers = writers.clone();
+    let cmd_tx = cmd_tx.clone();
+
+    tokio::task::spawn_blocking(move || {
+        overflow::pump_parsed_reader(
+            &port_name,
+            &mut owned_port,
+            &tx_clone,
+            policy,
+            overflow::DEFAULT_SPILL_LIMIT,
+            decoder,
+        );
+        // The port closed or was unplugged.
+        writers.lock().unwrap().remove(&port_name);
+        let _ = tx_clone.blocking_send(HardwareEvent::DeviceDisconnected(port_name));
+        if let Some(port) = config.reconnect.as_ref().and_then(wait_for_device)
+            && let Some(cmd_tx) = cmd_tx.upgrade()
+        {
+            let _ = cmd_tx.blocking_send(IOCommand::Connect(SerialConfig { port_name: port, ..config }));
+        }
+    });
+}
+
+/// Find the rate of the open `port`, report it, and attach at that rate;
+/// a reconnect later reopens at it directly.
+async fn auto_connect(
+    port: Box<dyn serialport::SerialPort>,
+    config: SerialConfig,
+    auto: AutoBaud,
+    event_tx: &mpsc::Sender<HardwareEvent>,
+    writers: &Writers,
+    cmd_tx: &mpsc::WeakSender<IOCommand>,
+) {
+    let detected = tokio::task::spawn_blocking(move || {
+        let mut port = port;
+        let guesses = baud::detect(&mut port, &auto)?;
+        let pick = auto.pick(&guesses);
+        if let Some(rate) = pick {
+            port.set_baud_rate(rate)?;
+        }
+        Ok::<_, std::io::Error>((port, guesses, pick))
+    })
+    .await;
+    let port_name = config.port_name.clone();
+    let (port, guesses, pick) = match detected {
+        Ok(Ok(detected)) => detected,
+        Ok(Err(e)) => {
+            let _ = event_tx.send(HardwareEvent::Error(format!("Baud detection on {} failed: {}", port_name, e))).await;
+            return;
+        }
+        Err(_) => return,
+    };
+    let _ = event_tx
+        .send(HardwareEvent::BaudDetected { port: port_name, baud: pick, guesses })
+        .await;
+    if let Some(baud_rate) = pick {
+        attach(port, SerialConfig { baud_rate, ..config }, event_tx, writers, cmd_tx).await;
+    }
+}
+
+/// `Identified` for `port`, if a USB device is behind it.
+fn identified(ports: &[PortInfo], port: &str) -> Option<HardwareEvent> {
+    let info = device::identify(ports, port)?;
+    Some(HardwareEvent::Identified {
+        port: info.port.clone(),
+        device: info.device.clone()?,
+        product: info.product.clone(),
+        name: None,
+    })
+}
 
-    pub async fn scan_ports(&self) -> anyhow::Result<()> {
-        self.cmd_tx
-            .send(IOCommand::Scan)
-            .await
-            .map_err(|_| anyhow::anyhow!("IO Thread Dead"))
+/// Poll the port list until `device` is back; the first port it shows up
+/// on, or `None` after `RECONNECT_WINDOW`.
+fn wait_for_device(device: &DeviceId) -> Option<String> {
+    let deadline = std::time::Instant::now() + RECONNECT_WINDOW;
+    while std::time::Instant::now() < deadline {
+        std::thread::sleep(RECONNECT_POLL);
+        if let Some(port) = device::ports_of(&device::system_ports(), device).first() {
+            return Some(port.to_string());
+        }
     }
+    None
 }
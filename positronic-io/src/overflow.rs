@@ -0,0 +1,312 @@
+//! Backpressure handling for per-port reader tasks.
+//!
+//! Readers never block on the shared event channel (unless the connection
+//! asks for `OverflowPolicy::Block`). Events that don't fit are parked in a
+//! bounded `SpillBuffer`; consecutive serial text is merged and consecutive
+//! sample batches are concatenated so the spill stays compact. Whatever the
+//! policy forces out of the spill is counted, and once the channel has room
+//! again a single `HardwareEvent::Overflow` reports the loss.
+
+use std::collections::VecDeque;
+use tokio::sync::mpsc;
+
+use crate::parser::LineDecoder;
+use crate::{HardwareEvent, SensorSample};
+
+/// Default spill budget per port, in bytes of buffered payload.
+pub const DEFAULT_SPILL_LIMIT: usize = 64 * 1024;
+
+/// What a port reader does when the UI can't keep up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block the reader until the channel has room (may lose data in OS buffers).
+    Block,
+    /// Keep the newest data; evict the oldest spilled data.
+    #[default]
+    DropOldest,
+    /// Keep the oldest data; discard new data while the spill is full.
+    DropNewest,
+}
+
+/// Running tally of discarded data since the last `Overflow` report.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DropStats {
+    pub bytes: usize,
+    pub samples: usize,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl DropStats {
+    pub fn is_empty(&self) -> bool {
+        self.bytes == 0 && self.samples == 0
+    }
+
+    fn drop_text(&mut self, text: &str) {
+        self.bytes += text.len();
+    }
+
+    fn drop_samples(&mut self, samples: &[SensorSample]) {
+        self.samples += samples.len();
+        for s in samples {
+            self.min = Some(self.min.map_or(s.value, |m| m.min(s.value)));
+            self.max = Some(self.max.map_or(s.value, |m| m.max(s.value)));
+        }
+    }
+}
+
+/// Bounded holding area for events that didn't fit in the channel.
+#[derive(Debug)]
+pub struct SpillBuffer {
+    port: String,
+    policy: OverflowPolicy,
+    limit: usize,
+    pending: VecDeque<HardwareEvent>,
+    pending_cost: usize,
+    dropped: DropStats,
+}
+
+/// Approximate memory cost of a spilled event.
+fn cost(event: &HardwareEvent) -> usize {
+    match event {
+        HardwareEvent::SerialOutput { text, .. } => text.len(),
+        HardwareEvent::DataBatch { samples: b, .. } => b.len() * std::mem::size_of::<SensorSample>(),
+        _ => 64,
+    }
+}
+
+impl SpillBuffer {
+    pub fn new(port: impl Into<String>, policy: OverflowPolicy, limit: usize) -> Self {
+        Self {
+            port: port.into(),
+            policy,
+            limit: limit.max(1),
+            pending: VecDeque::new(),
+            pending_cost: 0,
+            dropped: DropStats::default(),
+        }
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Current spill size in cost units (bytes for text).
+    pub fn pending_cost(&self) -> usize {
+        self.pending_cost
+    }
+
+    pub fn dropped(&self) -> DropStats {
+        self.dropped
+    }
+
+    /// Queue an event that couldn't be sent, applying the drop policy.
+    pub fn push(&mut self, event: HardwareEvent) {
+        let incoming = cost(&event);
+
+        if self.policy == OverflowPolicy::DropNewest && self.pending_cost + incoming > self.limit {
+            self.account_drop(&event);
+            return;
+        }
+
+        // Merge into the tail when the shapes match.
+        match (self.pending.back_mut(), event) {
+            (Some(HardwareEvent::SerialOutput { text: tail, .. }), HardwareEvent::SerialOutput { text, .. }) => {
+                tail.push_str(&text);
+            }
+            (Some(HardwareEvent::DataBatch { samples: tail, .. }), HardwareEvent::DataBatch { samples: b, .. }) => {
+                tail.extend(b);
+            }
+            (_, event) => self.pending.push_back(event),
+        }
+        self.pending_cost += incoming;
+
+        if self.policy == OverflowPolicy::DropOldest {
+            self.evict_oldest();
+        }
+    }
+
+    /// Try to hand spilled events (then the overflow report) to the channel.
+    /// Returns true once the spill is empty and nothing is left to report.
+    pub fn flush(&mut self, tx: &mpsc::Sender<HardwareEvent>) -> bool {
+        while let Some(event) = self.pending.pop_front() {
+            let c = cost(&event);
+            match tx.try_send(event) {
+                Ok(()) => self.pending_cost -= c,
+                Err(mpsc::error::TrySendError::Full(event))
+                | Err(mpsc::error::TrySendError::Closed(event)) => {
+                    self.pending.push_front(event);
+                    return false;
+                }
+            }
+        }
+
+        if !self.dropped.is_empty() {
+            let report = self.overflow_event();
+            if tx.try_send(report).is_err() {
+                return false;
+            }
+            self.dropped = DropStats::default();
+        }
+        true
+    }
+
+    /// Send or spill: the non-blocking entry point for reader loops.
+    pub fn offer(&mut self, tx: &mpsc::Sender<HardwareEvent>, event: HardwareEvent) {
+        if self.flush(tx) {
+            match tx.try_send(event) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(event))
+                | Err(mpsc::error::TrySendError::Closed(event)) => self.push(event),
+            }
+        } else {
+            self.push(event);
+        }
+    }
+
+    fn overflow_event(&self) -> HardwareEvent {
+        HardwareEvent::Overflow {
+            port: self.port.clone(),
+            dropped_bytes: self.dropped.bytes,
+            dropped_samples: self.dropped.samples,
+            sample_range: self.dropped.min.zip(self.dropped.max),
+        }
+    }
+
+    fn account_drop(&mut self, event: &HardwareEvent) {
+        match event {
+            HardwareEvent::SerialOutput { text, .. } => self.dropped.drop_text(text),
+            HardwareEvent::DataBatch { samples: b, .. } => self.dropped.drop_samples(b),
+            _ => {}
+        }
+    }
+
+    /// Trim from the front until the spill fits, splitting the oldest
+    /// text/batch when only part of it has to go.
+    fn evict_oldest(&mut self) {
+        while self.pending_cost > self.limit {
+            let over = self.pending_cost - self.limit;
+            let Some(front) = self.pending.front_mut() else {
+                break;
+            };
+            let front_cost = cost(front);
+            if front_cost <= over || front_cost == 0 {
+                let event = self.pending.pop_front().expect("front exists");
+                self.pending_cost -= front_cost;
+                self.account_drop(&event);
+                continue;
+            }
+            match front {
+                HardwareEvent::SerialOutput { text: s, .. } => {
+                    let mut cut = over.min(s.len());
+                    while !s.is_char_boundary(cut) {
+                        cut += 1;
+                    }
+                    let removed: String = s.drain(..cut).collect();
+                    self.pending_cost -= removed.len();
+                    self.dropped.drop_text(&removed);
+                }
+                HardwareEvent::DataBatch { samples: b, .. } => {
+                    let per = std::mem::size_of::<SensorSample>();
+                    let n = over.div_ceil(per).min(b.len());
+                    let removed: Vec<SensorSample> = b.drain(..n).collect();
+                    self.pending_cost -= n * per;
+                    self.dropped.drop_samples(&removed);
+                }
+                _ => {
+                    let event = self.pending.pop_front().expect("front exists");
+                    self.pending_cost -= front_cost;
+                    self.account_drop(&event);
+                }
+            }
+        }
+    }
+}
+
+/// Reader loop shared by real serial ports and test doubles.
+///
+/// Pulls from `reader` until EOF-like errors, forwarding text through the
+/// spill according to `policy`. Timeouts are treated as idle ticks and used
+/// to drain the spill. On exit, leftover spill and the final overflow report
+/// are delivered with blocking sends so nothing is silently lost.
+pub fn pump_reader<R: std::io::Read>(
+    port: &str,
+    reader: &mut R,
+    tx: &mpsc::Sender<HardwareEvent>,
+    policy: OverflowPolicy,
+    spill_limit: usize,
+) {
+    pump_parsed_reader(port, reader, tx, policy, spill_limit, None);
+}
+
+/// `pump_reader` with a line decoder: parsed samples go out as
+/// `DataBatch`es (stamped in seconds since the reader started), lines the
+/// parser can't read as `SerialOutput`.
+pub fn pump_parsed_reader<R: std::io::Read>(
+    port: &str,
+    reader: &mut R,
+    tx: &mpsc::Sender<HardwareEvent>,
+    policy: OverflowPolicy,
+    spill_limit: usize,
+    mut decoder: Option<LineDecoder>,
+) {
+    let mut spill = SpillBuffer::new(port, policy, spill_limit);
+    let mut buffer: Vec<u8> = vec![0; 1024];
+    let started = std::time::Instant::now();
+
+    let send = |spill: &mut SpillBuffer, event: HardwareEvent| -> bool {
+        if policy == OverflowPolicy::Block {
+            tx.blocking_send(event).is_ok()
+        } else {
+            spill.offer(tx, event);
+            true
+        }
+    };
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(n) if n > 0 => {
+                let text = String::from_utf8_lossy(&buffer[..n]).into_owned();
+                let events = match decoder.as_mut() {
+                    Some(decoder) => decoder.feed(&text, started.elapsed().as_secs_f64()).into_events(port),
+                    None => vec![HardwareEvent::SerialOutput { port: port.to_string(), text }],
+                };
+                for event in events {
+                    if !send(&mut spill, event) {
+                        return;
+                    }
+                }
+            }
+            Ok(_) => {
+                spill.flush(tx);
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                spill.flush(tx);
+            }
+            Err(_) => break, // Port closed/error
+        }
+        if tx.is_closed() {
+            return;
+        }
+    }
+
+    // Final drain: block now that the source is gone.
+    if let Some(decoder) = decoder.as_mut() {
+        for event in decoder.finish(started.elapsed().as_secs_f64()).into_events(port) {
+            spill.push(event);
+        }
+    }
+    while let Some(event) = spill.pending.pop_front() {
+        if tx.blocking_send(event).is_err() {
+            return;
+        }
+    }
+    if !spill.dropped.is_empty() {
+        let _ = tx.blocking_send(spill.overflow_event());
+    }
+}
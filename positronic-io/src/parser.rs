@@ -0,0 +1,423 @@
+//! Per-device line parsers: serial text to `SensorSample`s.
+//!
+//! Devices print telemetry in their own formats (`T:23.4 H:60`, NMEA
+//! sentences, JSON lines), so the format is chosen per connection rather
+//! than hardcoded. A `ParserConfig` (serde-configurable, stored per device
+//! by the caller) builds a `LineParser`; a `LineDecoder` cuts the byte
+//! stream into lines and runs them through it. Lines a parser can't read
+//! come back as text, so the reader still surfaces them as `SerialOutput`.
+//!
+//! Every parser accepts decimal commas (`23,4`) where they can't be
+//! mistaken for a separator.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{HardwareEvent, SensorSample};
+
+/// A partial line longer than this is flushed as text, newline or not.
+pub const MAX_LINE: usize = 4096;
+
+/// Reads one line of device output.
+pub trait LineParser: Send + fmt::Debug {
+    /// The samples in `line` (without its newline), channels assigned and
+    /// timestamps left at zero. Empty if the line isn't telemetry.
+    fn parse(&self, line: &str) -> Vec<SensorSample>;
+}
+
+/// One `source=channel` pair of a `--map`: a key, a field index or a
+/// JSON path, depending on the parser.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mapping {
+    pub source: String,
+    pub channel: u8,
+}
+
+/// Which parser a connection uses, and how it is configured.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ParserConfig {
+    /// No parsing: everything is `SerialOutput`, chunk by chunk.
+    #[default]
+    Raw,
+    /// `key:value` or `key=value` pairs. Without a map every key is read,
+    /// numbered by its position in the line.
+    Kv {
+        #[serde(default)]
+        map: Vec<Mapping>,
+    },
+    /// Comma sentences like `$GPRMC,...*6A`; the map is field index (1 is
+    /// the first field after the sentence id) to channel.
+    Nmea {
+        /// Only read sentences with this id (`GPRMC`); others are text.
+        #[serde(default)]
+        sentence: Option<String>,
+        map: Vec<Mapping>,
+    },
+    /// JSON objects; the map is dotted path (`env.temp`, `axes.0`) to channel.
+    Json { map: Vec<Mapping> },
+}
+
+pub const PARSER_USAGE: &str = "--parser raw|kv|nmea|json [--map <source>=<channel>,...] [--sentence <id>]";
+
+impl ParserConfig {
+    /// Build a config from `!io connect` options; the error is for the user.
+    pub fn from_args(kind: &str, map: Option<&str>, sentence: Option<&str>) -> Result<Self, String> {
+        let map = match map {
+            Some(spec) => parse_map(spec)?,
+            None => Vec::new(),
+        };
+        let config = match kind.to_ascii_lowercase().as_str() {
+            "raw" | "none" => ParserConfig::Raw,
+            "kv" => ParserConfig::Kv { map },
+            "nmea" => {
+                for m in &map {
+                    match m.source.parse::<usize>() {
+                        Ok(i) if i > 0 => {}
+                        _ => return Err(format!("nmea maps field indices (1 and up), not '{}'", m.source)),
+                    }
+                }
+                ParserConfig::Nmea { sentence: sentence.map(|s| s.trim_start_matches(['$', '!']).to_string()), map }
+            }
+            "json" => ParserConfig::Json { map },
+            other => return Err(format!("unknown parser '{}' (raw, kv, nmea or json)", other)),
+        };
+        if sentence.is_some() && !matches!(config, ParserConfig::Nmea { .. }) {
+            return Err("--sentence only applies to --parser nmea".to_string());
+        }
+        if matches!(&config, ParserConfig::Nmea { map, .. } | ParserConfig::Json { map } if map.is_empty()) {
+            return Err(format!("--parser {} needs a --map", config.name()));
+        }
+        Ok(config)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParserConfig::Raw => "raw",
+            ParserConfig::Kv { .. } => "kv",
+            ParserConfig::Nmea { .. } => "nmea",
+            ParserConfig::Json { .. } => "json",
+        }
+    }
+
+    pub fn is_raw(&self) -> bool {
+        *self == ParserConfig::Raw
+    }
+
+    /// The parser, or `None` for raw.
+    pub fn build(&self) -> Option<Box<dyn LineParser>> {
+        match self {
+            ParserConfig::Raw => None,
+            ParserConfig::Kv { map } => Some(Box::new(KvParser { map: map.clone() })),
+            ParserConfig::Nmea { sentence, map } => Some(Box::new(NmeaParser {
+                sentence: sentence.clone(),
+                fields: map.iter().filter_map(|m| Some((m.source.parse().ok()?, m.channel))).collect(),
+            })),
+            ParserConfig::Json { map } => Some(Box::new(JsonParser {
+                paths: map.iter().map(|m| (m.source.split('.').map(str::to_string).collect(), m.channel)).collect(),
+            })),
+        }
+    }
+}
+
+/// `kv --map T=0,H=1`, as typed.
+impl fmt::Display for ParserConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        let (sentence, map) = match self {
+            ParserConfig::Raw => return Ok(()),
+            ParserConfig::Kv { map } | ParserConfig::Json { map } => (None, map),
+            ParserConfig::Nmea { sentence, map } => (sentence.as_deref(), map),
+        };
+        if !map.is_empty() {
+            let pairs: Vec<String> = map.iter().map(|m| format!("{}={}", m.source, m.channel)).collect();
+            write!(f, " --map {}", pairs.join(","))?;
+        }
+        if let Some(s) = sentence {
+            write!(f, " --sentence {}", s)?;
+        }
+        Ok(())
+    }
+}
+
+/// `T=0,H=1` to mappings.
+pub fn parse_map(spec: &str) -> Result<Vec<Mapping>, String> {
+    spec.split(',')
+        .filter(|p| !p.trim().is_empty())
+        .map(|pair| {
+            let (source, channel) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("bad --map entry '{}' (expected <source>=<channel>)", pair))?;
+            let channel = channel
+                .trim()
+                .parse()
+                .map_err(|_| format!("bad channel '{}' in --map (0-255)", channel))?;
+            Ok(Mapping { source: source.trim().to_string(), channel })
+        })
+        .collect()
+}
+
+/// A number with a decimal point or a single decimal comma.
+pub fn parse_decimal(text: &str) -> Option<f32> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let value = if !text.contains('.') && text.matches(',').count() == 1 {
+        text.replace(',', ".").parse::<f32>().ok()?
+    } else {
+        text.parse::<f32>().ok()?
+    };
+    value.is_finite().then_some(value)
+}
+
+fn sample(value: f32, channel: u8) -> SensorSample {
+    SensorSample { timestamp: 0.0, value, channel }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// key:value
+// ════════════════════════════════════════════════════════════════════
+
+/// `T:23.4 H:60`, `temp=23,4; hum=60`. A comma followed by a digit is a
+/// decimal comma; any other comma separates pairs.
+#[derive(Debug, Clone)]
+pub struct KvParser {
+    map: Vec<Mapping>,
+}
+
+impl KvParser {
+    /// The `(key, value)` pairs of a line, in order.
+    fn pairs(line: &str) -> Vec<(&str, f32)> {
+        let bytes = line.as_bytes();
+        let mut pairs = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if !(bytes[i].is_ascii_alphabetic() || bytes[i] == b'_') {
+                i += 1;
+                continue;
+            }
+            let key_start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            let key = &line[key_start..i];
+            while i < bytes.len() && bytes[i] == b' ' {
+                i += 1;
+            }
+            if i >= bytes.len() || !(bytes[i] == b':' || bytes[i] == b'=') {
+                continue;
+            }
+            i += 1;
+            while i < bytes.len() && bytes[i] == b' ' {
+                i += 1;
+            }
+            let value_start = i;
+            if i < bytes.len() && (bytes[i] == b'-' || bytes[i] == b'+') {
+                i += 1;
+            }
+            let mut separator = false;
+            while i < bytes.len() {
+                let b = bytes[i];
+                let decimal = (b == b'.' || b == b',')
+                    && !separator
+                    && bytes.get(i + 1).is_some_and(u8::is_ascii_digit);
+                if decimal {
+                    separator = true;
+                } else if !b.is_ascii_digit() {
+                    break;
+                }
+                i += 1;
+            }
+            // `e`-notation and trailing units (`23.4C`) end the number.
+            if let Some(value) = parse_decimal(&line[value_start..i]) {
+                pairs.push((key, value));
+            }
+        }
+        pairs
+    }
+}
+
+impl LineParser for KvParser {
+    fn parse(&self, line: &str) -> Vec<SensorSample> {
+        let pairs = Self::pairs(line);
+        if self.map.is_empty() {
+            return pairs.iter().enumerate().map(|(i, (_, v))| sample(*v, i.min(u8::MAX as usize) as u8)).collect();
+        }
+        pairs
+            .iter()
+            .filter_map(|(key, value)| {
+                let m = self.map.iter().find(|m| m.source.eq_ignore_ascii_case(key))?;
+                Some(sample(*value, m.channel))
+            })
+            .collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// NMEA-style sentences
+// ════════════════════════════════════════════════════════════════════
+
+/// `$GPRMC,123519,A,4807.038,N*6A`. A checksum, if present, must match.
+#[derive(Debug, Clone)]
+pub struct NmeaParser {
+    sentence: Option<String>,
+    /// Field index to channel.
+    fields: Vec<(usize, u8)>,
+}
+
+impl NmeaParser {
+    /// The fields (id first) of a well-formed sentence.
+    fn fields(line: &str) -> Option<Vec<&str>> {
+        let body = line.trim().strip_prefix(['$', '!'])?;
+        let body = match body.rsplit_once('*') {
+            Some((body, checksum)) => {
+                let expected = u8::from_str_radix(checksum.trim(), 16).ok()?;
+                let actual = body.bytes().fold(0u8, |acc, b| acc ^ b);
+                if expected != actual {
+                    return None;
+                }
+                body
+            }
+            None => body,
+        };
+        let fields: Vec<&str> = body.split(',').collect();
+        (fields.len() > 1 && !fields[0].is_empty()).then_some(fields)
+    }
+}
+
+impl LineParser for NmeaParser {
+    fn parse(&self, line: &str) -> Vec<SensorSample> {
+        let Some(fields) = Self::fields(line) else {
+            return Vec::new();
+        };
+        if self.sentence.as_deref().is_some_and(|s| s != fields[0]) {
+            return Vec::new();
+        }
+        self.fields
+            .iter()
+            .filter_map(|&(index, channel)| Some(sample(parse_decimal(fields.get(index)?)?, channel)))
+            .collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// JSON lines
+// ════════════════════════════════════════════════════════════════════
+
+/// `{"env": {"temp": 23.4}, "axes": [1, 2]}`. Numbers, booleans (0/1)
+/// and numeric strings (`"23,4"`) are read; other values are skipped.
+#[derive(Debug, Clone)]
+pub struct JsonParser {
+    paths: Vec<(Vec<String>, u8)>,
+}
+
+impl JsonParser {
+    fn lookup<'a>(value: &'a serde_json::Value, path: &[String]) -> Option<&'a serde_json::Value> {
+        path.iter().try_fold(value, |v, key| match v {
+            serde_json::Value::Object(map) => map.get(key),
+            serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+
+    fn number(value: &serde_json::Value) -> Option<f32> {
+        match value {
+            serde_json::Value::Number(n) => n.as_f64().map(|n| n as f32).filter(|n| n.is_finite()),
+            serde_json::Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+            serde_json::Value::String(s) => parse_decimal(s),
+            _ => None,
+        }
+    }
+}
+
+impl LineParser for JsonParser {
+    fn parse(&self, line: &str) -> Vec<SensorSample> {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return Vec::new();
+        }
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            return Vec::new();
+        };
+        self.paths
+            .iter()
+            .filter_map(|(path, channel)| Some(sample(Self::number(Self::lookup(&value, path)?)?, *channel)))
+            .collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Line assembly
+// ════════════════════════════════════════════════════════════════════
+
+/// Cuts reader chunks into lines for a `LineParser`.
+///
+/// A partial line is held until its newline arrives (or it passes
+/// `MAX_LINE`), so a sample split across reads is still parsed.
+#[derive(Debug)]
+pub struct LineDecoder {
+    parser: Box<dyn LineParser>,
+    partial: String,
+}
+
+/// What one chunk decoded to.
+#[derive(Debug, Default, Clone)]
+pub struct Decoded {
+    pub samples: Vec<SensorSample>,
+    /// Lines no sample came from, newlines kept.
+    pub text: String,
+}
+
+impl Decoded {
+    /// A `DataBatch` for the samples, then `SerialOutput` for the text.
+    pub fn into_events(self, port: &str) -> Vec<HardwareEvent> {
+        let mut events = Vec::new();
+        if !self.samples.is_empty() {
+            events.push(HardwareEvent::DataBatch { port: port.to_string(), samples: self.samples });
+        }
+        if !self.text.is_empty() {
+            events.push(HardwareEvent::SerialOutput { port: port.to_string(), text: self.text });
+        }
+        events
+    }
+}
+
+impl LineDecoder {
+    pub fn new(parser: Box<dyn LineParser>) -> Self {
+        Self { parser, partial: String::new() }
+    }
+
+    /// Feed a chunk; samples are stamped with `timestamp`.
+    pub fn feed(&mut self, chunk: &str, timestamp: f64) -> Decoded {
+        self.partial.push_str(chunk);
+        let mut out = Decoded::default();
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            self.decode(line, timestamp, &mut out);
+        }
+        if self.partial.len() > MAX_LINE {
+            out.text.push_str(&std::mem::take(&mut self.partial));
+        }
+        out
+    }
+
+    /// The held partial line, parsed as if it had ended (at EOF).
+    pub fn finish(&mut self, timestamp: f64) -> Decoded {
+        let mut out = Decoded::default();
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.decode(line, timestamp, &mut out);
+        }
+        out
+    }
+
+    fn decode(&self, line: String, timestamp: f64, out: &mut Decoded) {
+        let samples = self.parser.parse(line.trim_end_matches(['\n', '\r']));
+        if samples.is_empty() {
+            out.text.push_str(&line);
+        } else {
+            out.samples.extend(samples.into_iter().map(|s| SensorSample { timestamp, ..s }));
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Port access checks for `!io scan --probe`.
+//!
+//! Listing a port says nothing about whether it can be opened: on Linux
+//! `/dev/ttyUSB0` shows up but belongs to the `dialout` group, on Windows a
+//! port whose driver failed is listed and then can't be opened. The probe
+//! opens each port and closes it straight away, and turns the failure into
+//! a `PortAccess` with a hint for the platform.
+//!
+//! Opening is not free: it raises DTR, and many boards (Arduinos among
+//! them) reset when it does. That's why probing is opt-in. Each port is
+//! probed on its own thread under one overall deadline, so a device whose
+//! open never returns is reported as timed out instead of stalling the scan.
+
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long all probes of one scan may take together.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Baud rate the probe opens with; nothing is sent.
+const PROBE_BAUD: u32 = 9600;
+
+/// Whether a listed port could be opened, and if not, why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortAccess {
+    Ok,
+    PermissionDenied,
+    /// Another process has it open.
+    Busy,
+    /// The OS listed it but its driver failed; carries the OS message.
+    DriverError(String),
+    /// Gone between listing and probing, e.g. unplugged.
+    Missing,
+    /// The open didn't return before the scan's deadline.
+    TimedOut,
+}
+
+/// Decides how open errors read and which remediation hints apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    MacOs,
+    Windows,
+    Other,
+}
+
+impl Platform {
+    pub fn current() -> Platform {
+        if cfg!(target_os = "linux") {
+            Platform::Linux
+        } else if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Other
+        }
+    }
+}
+
+impl PortAccess {
+    /// What a failed open on `platform` means. Windows reports a port held
+    /// by another process as access denied, and a port without a working
+    /// driver as not found.
+    pub fn classify(error: &serialport::Error, platform: Platform) -> PortAccess {
+        use serialport::ErrorKind;
+        use std::io::ErrorKind as Io;
+
+        let description = error.description.trim();
+        let lower = description.to_lowercase();
+        if lower.contains("busy") || matches!(error.kind, ErrorKind::Io(Io::AddrInUse | Io::WouldBlock)) {
+            return PortAccess::Busy;
+        }
+        let denied = matches!(error.kind, ErrorKind::Io(Io::PermissionDenied)) || lower.contains("denied");
+        let not_found = matches!(error.kind, ErrorKind::NoDevice | ErrorKind::Io(Io::NotFound));
+        match (platform, denied, not_found) {
+            (Platform::Windows, true, _) => PortAccess::Busy,
+            (_, true, _) => PortAccess::PermissionDenied,
+            (Platform::Windows, _, true) => PortAccess::DriverError(description.to_string()),
+            (_, _, true) => PortAccess::Missing,
+            _ if matches!(error.kind, ErrorKind::Io(Io::TimedOut)) => PortAccess::TimedOut,
+            _ => PortAccess::DriverError(description.to_string()),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        *self == PortAccess::Ok
+    }
+
+    /// The state with what to do about it on `platform`.
+    pub fn message(&self, platform: Platform) -> String {
+        match (self, platform) {
+            (PortAccess::Ok, _) => "OK".to_string(),
+            (PortAccess::PermissionDenied, Platform::Linux) => {
+                "permission denied (add user to dialout group: sudo usermod -aG dialout $USER)".to_string()
+            }
+            (PortAccess::PermissionDenied, Platform::MacOs) => {
+                "permission denied (check the device's owner and mode: ls -l /dev/cu.*)".to_string()
+            }
+            (PortAccess::PermissionDenied, _) => "permission denied".to_string(),
+            (PortAccess::Busy, Platform::Windows) => {
+                "busy (in use by another process; close serial monitors such as the Arduino IDE)".to_string()
+            }
+            (PortAccess::Busy, Platform::Other) => "busy (in use by another process)".to_string(),
+            (PortAccess::Busy, _) => "busy (in use by another process; lsof shows which)".to_string(),
+            (PortAccess::DriverError(detail), platform) => {
+                let hint = match platform {
+                    Platform::Windows => "check Device Manager for a missing or failed driver",
+                    Platform::Linux => "check dmesg for the USB serial driver",
+                    Platform::MacOs => "the adapter may need its vendor driver (CH340, CP210x)",
+                    Platform::Other => "check the device driver",
+                };
+                if detail.is_empty() {
+                    format!("driver error ({})", hint)
+                } else {
+                    format!("driver error ({}: {})", hint, detail)
+                }
+            }
+            (PortAccess::Missing, _) => "gone (unplugged since it was listed?)".to_string(),
+            (PortAccess::TimedOut, _) => "no answer (opening it didn't finish in time)".to_string(),
+        }
+    }
+}
+
+/// Opens ports for probing; the system's serial ports, or a fake in tests.
+pub trait PortIo: Send + Sync + 'static {
+    /// Open `port` and close it again.
+    fn open_close(&self, port: &str) -> Result<(), serialport::Error>;
+}
+
+/// The real serial ports.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemPorts;
+
+impl PortIo for SystemPorts {
+    fn open_close(&self, port: &str) -> Result<(), serialport::Error> {
+        serialport::new(port, PROBE_BAUD)
+            .timeout(Duration::from_millis(50))
+            .open()
+            .map(drop)
+    }
+}
+
+/// Probe every port concurrently, all within `timeout`. Results come back
+/// in the order of `ports`; a probe still running at the deadline is
+/// `TimedOut` and its thread is left to finish on its own.
+pub fn probe_ports(
+    io: Arc<dyn PortIo>,
+    ports: &[String],
+    timeout: Duration,
+    platform: Platform,
+) -> Vec<(String, PortAccess)> {
+    let (tx, rx) = mpsc::channel();
+    for (index, port) in ports.iter().enumerate() {
+        let (io, tx, port) = (io.clone(), tx.clone(), port.clone());
+        thread::spawn(move || {
+            let access = match io.open_close(&port) {
+                Ok(()) => PortAccess::Ok,
+                Err(e) => PortAccess::classify(&e, platform),
+            };
+            let _ = tx.send((index, access));
+        });
+    }
+    drop(tx);
+
+    let mut results = vec![PortAccess::TimedOut; ports.len()];
+    let deadline = Instant::now() + timeout;
+    for _ in 0..ports.len() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(left) {
+            Ok((index, access)) => results[index] = access,
+            Err(_) => break,
+        }
+    }
+    ports.iter().cloned().zip(results).collect()
+}
@@ -1,4 +1,10 @@
-use positronic_io::{HardwareEvent, HardwareMonitor, SensorSample, SerialConfig};
+use positronic_io::overflow::{pump_parsed_reader, pump_reader, DEFAULT_SPILL_LIMIT};
+use positronic_io::parser::{parse_decimal, parse_map, Mapping};
+use positronic_io::LineDecoder;
+use positronic_io::{
+    HardwareEvent, HardwareMonitor, IoError, OverflowPolicy, ParserConfig, SensorSample,
+    SerialConfig, SpillBuffer,
+};
 
 // ============================================================================
 // SensorSample Tests
@@ -88,18 +94,24 @@ fn test_hardware_event_data_batch() {
             channel: 0,
         },
     ];
-    let event = HardwareEvent::DataBatch(samples);
+    let event = HardwareEvent::DataBatch { port: "COM3".to_string(), samples };
     match event {
-        HardwareEvent::DataBatch(batch) => assert_eq!(batch.len(), 2),
+        HardwareEvent::DataBatch { port, samples } => {
+            assert_eq!(port, "COM3");
+            assert_eq!(samples.len(), 2);
+        }
         _ => panic!("Wrong variant"),
     }
 }
 
 #[test]
 fn test_hardware_event_serial_output() {
-    let event = HardwareEvent::SerialOutput("Hello from Arduino\n".to_string());
+    let event = HardwareEvent::SerialOutput { port: "/dev/ttyUSB0".to_string(), text: "Hello from Arduino\n".to_string() };
     match event {
-        HardwareEvent::SerialOutput(s) => assert!(s.contains("Arduino")),
+        HardwareEvent::SerialOutput { port, text } => {
+            assert_eq!(port, "/dev/ttyUSB0");
+            assert!(text.contains("Arduino"));
+        }
         _ => panic!("Wrong variant"),
     }
 }
@@ -131,6 +143,9 @@ fn test_serial_config_creation() {
         baud_rate: 115200,
         data_bits: 8,
         flow_control: false,
+        overflow: OverflowPolicy::default(),
+        parser: ParserConfig::default(),
+        reconnect: None,
     };
     assert_eq!(config.port_name, "COM3");
     assert_eq!(config.baud_rate, 115200);
@@ -145,6 +160,9 @@ fn test_serial_config_clone() {
         baud_rate: 9600,
         data_bits: 8,
         flow_control: true,
+        overflow: OverflowPolicy::default(),
+        parser: ParserConfig::default(),
+        reconnect: None,
     };
     let cloned = config.clone();
     assert_eq!(cloned.port_name, "/dev/ttyACM0");
@@ -159,6 +177,9 @@ fn test_serial_config_debug() {
         baud_rate: 57600,
         data_bits: 8,
         flow_control: false,
+        overflow: OverflowPolicy::default(),
+        parser: ParserConfig::default(),
+        reconnect: None,
     };
     let debug = format!("{:?}", config);
     assert!(debug.contains("COM1"));
@@ -176,6 +197,9 @@ fn test_serial_config_various_baud_rates() {
             baud_rate: baud,
             data_bits: 8,
             flow_control: false,
+            overflow: OverflowPolicy::default(),
+            parser: ParserConfig::default(),
+            reconnect: None,
         };
         assert_eq!(config.baud_rate, baud);
     }
@@ -224,3 +248,686 @@ async fn test_hardware_monitor_connect_nonexistent() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_hardware_monitor_write_to_unconnected_port() {
+    let (monitor, _rx) = HardwareMonitor::start();
+    let err = monitor.write("/dev/nonexistent_positronic_port", b"hi").unwrap_err();
+    assert!(matches!(err, IoError::NotConnected { .. }), "{:?}", err);
+    assert_eq!(err.port(), Some("/dev/nonexistent_positronic_port"));
+    assert_eq!(err.to_string(), "/dev/nonexistent_positronic_port is not connected");
+}
+
+// ============================================================================
+// Overflow / Backpressure Tests
+// ============================================================================
+
+/// Mock serial port: yields `chunks` reads of `chunk_len` ASCII bytes, then
+/// reports the port as gone.
+struct MockPort {
+    chunks: usize,
+    chunk_len: usize,
+}
+
+impl std::io::Read for MockPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.chunks == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "unplugged"));
+        }
+        self.chunks -= 1;
+        let n = self.chunk_len.min(buf.len());
+        buf[..n].fill(b'x');
+        Ok(n)
+    }
+}
+
+fn text(n: usize) -> HardwareEvent {
+    text_of("y", n)
+}
+
+fn text_of(c: &str, n: usize) -> HardwareEvent {
+    HardwareEvent::SerialOutput { port: "MOCK".to_string(), text: c.repeat(n) }
+}
+
+fn batch(values: &[f32]) -> HardwareEvent {
+    HardwareEvent::DataBatch {
+        port: "MOCK".to_string(),
+        samples: values
+            .iter()
+            .map(|&value| SensorSample { timestamp: 0.0, value, channel: 0 })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_overflow_policy_default_is_drop_oldest() {
+    assert_eq!(OverflowPolicy::default(), OverflowPolicy::DropOldest);
+}
+
+#[test]
+fn test_spill_merges_consecutive_text() {
+    let mut spill = SpillBuffer::new("COM1", OverflowPolicy::DropOldest, 1024);
+    spill.push(text(10));
+    spill.push(text(20));
+    assert_eq!(spill.pending_len(), 1);
+    assert_eq!(spill.pending_cost(), 30);
+}
+
+#[test]
+fn test_spill_merges_consecutive_batches() {
+    let mut spill = SpillBuffer::new("COM1", OverflowPolicy::DropOldest, 1024);
+    spill.push(batch(&[1.0, 2.0]));
+    spill.push(batch(&[3.0]));
+    spill.push(text(4));
+    assert_eq!(spill.pending_len(), 2);
+}
+
+#[test]
+fn test_spill_drop_oldest_is_bounded() {
+    let mut spill = SpillBuffer::new("COM1", OverflowPolicy::DropOldest, 4096);
+    for _ in 0..1000 {
+        spill.push(text(1000));
+        assert!(spill.pending_cost() <= 4096);
+    }
+    assert_eq!(spill.dropped().bytes + spill.pending_cost(), 1_000_000);
+}
+
+#[test]
+fn test_spill_drop_newest_keeps_head() {
+    let mut spill = SpillBuffer::new("COM1", OverflowPolicy::DropNewest, 100);
+    spill.push(text_of("a", 80));
+    spill.push(text_of("b", 80));
+    assert_eq!(spill.pending_cost(), 80);
+    assert_eq!(spill.dropped().bytes, 80);
+}
+
+#[test]
+fn test_spill_tracks_dropped_sample_range() {
+    let per = std::mem::size_of::<SensorSample>();
+    let mut spill = SpillBuffer::new("COM1", OverflowPolicy::DropOldest, 2 * per);
+    spill.push(batch(&[-5.0, 9.0, 1.0, 2.0]));
+    let dropped = spill.dropped();
+    assert_eq!(dropped.samples, 2);
+    assert_eq!(dropped.min, Some(-5.0));
+    assert_eq!(dropped.max, Some(9.0));
+}
+
+#[tokio::test]
+async fn test_spill_reports_overflow_after_drain() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    let mut spill = SpillBuffer::new("COM9", OverflowPolicy::DropOldest, 10);
+    spill.offer(&tx, text(5)); // fills the channel
+    spill.offer(&tx, text(8)); // spilled
+    spill.offer(&tx, text(8)); // merged, 6 bytes evicted
+
+    assert!(matches!(rx.recv().await, Some(HardwareEvent::SerialOutput { text: s, .. }) if s.len() == 5));
+    assert!(!spill.flush(&tx)); // spilled text goes out, report waits for room
+    assert!(matches!(rx.recv().await, Some(HardwareEvent::SerialOutput { text: s, .. }) if s.len() == 10));
+    assert!(spill.flush(&tx));
+    match rx.recv().await {
+        Some(HardwareEvent::Overflow { port, dropped_bytes, dropped_samples, .. }) => {
+            assert_eq!(port, "COM9");
+            assert_eq!(dropped_bytes, 6);
+            assert_eq!(dropped_samples, 0);
+        }
+        other => panic!("expected Overflow, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pump_reader_slow_consumer_accounting() {
+    const CHUNKS: usize = 2000;
+    const CHUNK_LEN: usize = 512;
+    let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+
+    let reader = tokio::task::spawn_blocking(move || {
+        let mut port = MockPort { chunks: CHUNKS, chunk_len: CHUNK_LEN };
+        pump_reader("MOCK", &mut port, &tx, OverflowPolicy::DropOldest, DEFAULT_SPILL_LIMIT);
+    });
+
+    let mut received = 0usize;
+    let mut dropped = 0usize;
+    let mut reports = 0usize;
+    while let Some(event) = rx.recv().await {
+        match event {
+            HardwareEvent::SerialOutput { text: s, .. } => received += s.len(),
+            HardwareEvent::Overflow { dropped_bytes, .. } => {
+                dropped += dropped_bytes;
+                reports += 1;
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        // Deliberately slow consumer
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+    }
+    reader.await.unwrap();
+
+    assert!(reports > 0, "slow consumer should have caused drops");
+    assert_eq!(received + dropped, CHUNKS * CHUNK_LEN);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pump_reader_block_policy_loses_nothing() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(2);
+    let reader = tokio::task::spawn_blocking(move || {
+        let mut port = MockPort { chunks: 200, chunk_len: 64 };
+        pump_reader("MOCK", &mut port, &tx, OverflowPolicy::Block, DEFAULT_SPILL_LIMIT);
+    });
+
+    let mut received = 0usize;
+    while let Some(event) = rx.recv().await {
+        match event {
+            HardwareEvent::SerialOutput { text: s, .. } => received += s.len(),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+    reader.await.unwrap();
+    assert_eq!(received, 200 * 64);
+}
+
+// ============================================================================
+// Line Parser Tests
+// ============================================================================
+
+fn parse(config: &ParserConfig, line: &str) -> Vec<(u8, f32)> {
+    config.build().expect("not raw").parse(line).iter().map(|s| (s.channel, s.value)).collect()
+}
+
+fn kv(map: &str) -> ParserConfig {
+    ParserConfig::from_args("kv", Some(map), None).unwrap()
+}
+
+#[test]
+fn test_parse_decimal_accepts_decimal_commas() {
+    assert_eq!(parse_decimal("23.4"), Some(23.4));
+    assert_eq!(parse_decimal(" 23,4 "), Some(23.4));
+    assert_eq!(parse_decimal("-0,5"), Some(-0.5));
+    assert_eq!(parse_decimal("1,234,5"), None);
+    assert_eq!(parse_decimal("1.5,2"), None);
+    assert_eq!(parse_decimal(""), None);
+    assert_eq!(parse_decimal("abc"), None);
+    assert_eq!(parse_decimal("inf"), None);
+    assert_eq!(parse_decimal("NaN"), None);
+}
+
+#[test]
+fn test_parse_map() {
+    assert_eq!(
+        parse_map("T=0, H=1").unwrap(),
+        vec![Mapping { source: "T".into(), channel: 0 }, Mapping { source: "H".into(), channel: 1 }]
+    );
+    assert!(parse_map("T").unwrap_err().contains("<source>=<channel>"));
+    assert!(parse_map("T=256").unwrap_err().contains("bad channel"));
+    assert!(parse_map("T=x").is_err());
+}
+
+#[test]
+fn test_kv_parser_maps_keys_to_channels() {
+    let config = kv("T=0,H=1");
+    assert_eq!(parse(&config, "T:23.4 H:60"), vec![(0, 23.4), (1, 60.0)]);
+    assert_eq!(parse(&config, "H=55, T=-1.5"), vec![(1, 55.0), (0, -1.5)]);
+    assert_eq!(parse(&config, "t: 20 h : 40"), vec![(0, 20.0), (1, 40.0)], "keys ignore case, spaces allowed");
+    assert_eq!(parse(&config, "T:23.4C H:60%"), vec![(0, 23.4), (1, 60.0)], "units end the number");
+    assert_eq!(parse(&config, "T:21 P:1013 H:50"), vec![(0, 21.0), (1, 50.0)], "unmapped keys are skipped");
+}
+
+#[test]
+fn test_kv_parser_decimal_commas() {
+    let config = kv("T=0,H=1");
+    assert_eq!(parse(&config, "T:23,4 H:60,5"), vec![(0, 23.4), (1, 60.5)]);
+    assert_eq!(parse(&config, "T:23,H:60"), vec![(0, 23.0), (1, 60.0)], "a comma before a key separates");
+    assert_eq!(parse(&config, "T=23,4;H=60"), vec![(0, 23.4), (1, 60.0)]);
+}
+
+#[test]
+fn test_kv_parser_without_map_numbers_keys_in_order() {
+    let config = ParserConfig::from_args("kv", None, None).unwrap();
+    assert_eq!(parse(&config, "a:1 b:2 c:3"), vec![(0, 1.0), (1, 2.0), (2, 3.0)]);
+}
+
+#[test]
+fn test_kv_parser_malformed_lines() {
+    let config = kv("T=0");
+    for line in ["", "booting...", "T:", "T: abc", "T:-", "T 23.4", "ERROR: sensor T missing", ":23"] {
+        assert!(parse(&config, line).is_empty(), "{:?}", line);
+    }
+}
+
+#[test]
+fn test_nmea_parser_reads_mapped_fields() {
+    let config = ParserConfig::from_args("nmea", Some("3=0,5=1"), Some("$GPRMC")).unwrap();
+    let line = "$GPRMC,123519,A,4807.038,N,01131.000,E";
+    assert_eq!(parse(&config, line), vec![(0, 4807.038), (1, 1131.0)]);
+    assert_eq!(parse(&config, "$GPGGA,123519,A,4807.038,N,01131.000,E"), vec![], "other sentences are text");
+
+    // Without a sentence filter every sentence is read; empty fields are skipped.
+    let any = ParserConfig::from_args("nmea", Some("1=0,2=1"), None).unwrap();
+    assert_eq!(parse(&any, "$PSENS,,17.5"), vec![(1, 17.5)]);
+    assert_eq!(parse(&any, "!AIVDM,3,x"), vec![(0, 3.0)]);
+}
+
+#[test]
+fn test_nmea_parser_checks_checksums() {
+    let config = ParserConfig::from_args("nmea", Some("1=0"), None).unwrap();
+    let body = "PSENS,42.5";
+    let checksum = body.bytes().fold(0u8, |a, b| a ^ b);
+    assert_eq!(parse(&config, &format!("${}*{:02X}", body, checksum)), vec![(0, 42.5)]);
+    assert!(parse(&config, &format!("${}*{:02X}", body, checksum ^ 1)).is_empty(), "bad checksum");
+    assert!(parse(&config, "$PSENS,42.5*ZZ").is_empty(), "unreadable checksum");
+}
+
+#[test]
+fn test_nmea_parser_malformed_lines() {
+    let config = ParserConfig::from_args("nmea", Some("1=0"), None).unwrap();
+    for line in ["", "PSENS,1", "$", "$PSENS", "$,1", "$PSENS,abc", "$PSENS,1;2"] {
+        assert!(parse(&config, line).is_empty(), "{:?}", line);
+    }
+    // Decimal commas can't exist in comma sentences.
+    assert_eq!(parse(&config, "$PSENS,23,4"), vec![(0, 23.0)]);
+}
+
+#[test]
+fn test_json_parser_reads_paths() {
+    let config = ParserConfig::from_args("json", Some("temp=0,env.hum=1,axes.1=2,ok=3"), None).unwrap();
+    assert_eq!(
+        parse(&config, r#"{"temp": 23.4, "env": {"hum": 60}, "axes": [1, -2.5], "ok": true}"#),
+        vec![(0, 23.4), (1, 60.0), (2, -2.5), (3, 1.0)]
+    );
+    assert_eq!(parse(&config, r#"{"temp": "23,4", "env": {"hum": "n/a"}}"#), vec![(0, 23.4)], "numeric strings");
+}
+
+#[test]
+fn test_json_parser_malformed_lines() {
+    let config = ParserConfig::from_args("json", Some("temp=0"), None).unwrap();
+    for line in ["", "temp: 5", r#"{"temp": 5"#, "[1, 2]", r#"{"temp": null}"#, r#"{"temp": {"c": 5}}"#, r#"{"other": 5}"#] {
+        assert!(parse(&config, line).is_empty(), "{:?}", line);
+    }
+}
+
+#[test]
+fn test_parser_config_from_args_and_display() {
+    assert_eq!(ParserConfig::from_args("raw", None, None).unwrap(), ParserConfig::Raw);
+    assert!(ParserConfig::Raw.build().is_none());
+    assert_eq!(kv("T=0,H=1").to_string(), "kv --map T=0,H=1");
+    assert_eq!(
+        ParserConfig::from_args("nmea", Some("3=0"), Some("GPRMC")).unwrap().to_string(),
+        "nmea --map 3=0 --sentence GPRMC"
+    );
+    assert!(ParserConfig::from_args("csv", None, None).unwrap_err().contains("unknown parser"));
+    assert!(ParserConfig::from_args("json", None, None).unwrap_err().contains("needs a --map"));
+    assert!(ParserConfig::from_args("nmea", Some("T=0"), None).unwrap_err().contains("field indices"));
+    assert!(ParserConfig::from_args("kv", None, Some("GPRMC")).is_err());
+}
+
+#[test]
+fn test_parser_config_serde_roundtrip() {
+    let config = ParserConfig::from_args("nmea", Some("3=0"), Some("GPRMC")).unwrap();
+    let json = serde_json::to_string(&config).unwrap();
+    assert!(json.contains(r#""kind":"nmea""#));
+    assert_eq!(serde_json::from_str::<ParserConfig>(&json).unwrap(), config);
+    assert_eq!(serde_json::from_str::<ParserConfig>(r#"{"kind":"kv"}"#).unwrap(), ParserConfig::Kv { map: vec![] });
+}
+
+#[test]
+fn test_line_decoder_joins_split_lines() {
+    let mut decoder = LineDecoder::new(kv("T=0").build().unwrap());
+    let first = decoder.feed("boot ok\r\nT:2", 1.0);
+    assert!(first.samples.is_empty());
+    assert_eq!(first.text, "boot ok\r\n");
+    let second = decoder.feed("3.5\nT:24\n", 2.0);
+    let values: Vec<(f64, f32)> = second.samples.iter().map(|s| (s.timestamp, s.value)).collect();
+    assert_eq!(values, vec![(2.0, 23.5), (2.0, 24.0)]);
+    assert!(second.text.is_empty());
+
+    assert!(decoder.feed("no newline", 3.0).text.is_empty(), "held until the line ends");
+    assert_eq!(decoder.finish(4.0).text, "no newline");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pump_parsed_reader_splits_samples_and_text() {
+    struct Lines(Vec<&'static [u8]>);
+    impl std::io::Read for Lines {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(std::io::Error::new(std::io::ErrorKind::BrokenPipe, "unplugged"));
+            }
+            let chunk = self.0.remove(0);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let reader = tokio::task::spawn_blocking(move || {
+        let mut port = Lines(vec![b"T:1 H:2\nhello\nT:", b"3\n", b"T:4"]);
+        let decoder = kv("T=0,H=1").build().map(LineDecoder::new);
+        pump_parsed_reader("MOCK", &mut port, &tx, OverflowPolicy::Block, DEFAULT_SPILL_LIMIT, decoder);
+    });
+
+    let mut samples = Vec::new();
+    let mut text = String::new();
+    while let Some(event) = rx.recv().await {
+        match event {
+            HardwareEvent::DataBatch { port, samples: batch } => {
+                assert_eq!(port, "MOCK");
+                samples.extend(batch.iter().map(|s| (s.channel, s.value)));
+            }
+            HardwareEvent::SerialOutput { text: s, .. } => text.push_str(&s),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+    reader.await.unwrap();
+    assert_eq!(samples, vec![(0, 1.0), (1, 2.0), (0, 3.0), (0, 4.0)]);
+    assert_eq!(text, "hello\n");
+}
+
+// ============================================================================
+// Port Probe Tests
+// ============================================================================
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use positronic_io::probe::{probe_ports, Platform, PortAccess, PortIo};
+
+/// Fails each port the way it is told to; "wedged" ports never return in time.
+struct FakePorts(HashMap<&'static str, Option<serialport::Error>>);
+
+impl PortIo for FakePorts {
+    fn open_close(&self, port: &str) -> Result<(), serialport::Error> {
+        if port == "wedged" {
+            std::thread::sleep(Duration::from_secs(5));
+        }
+        match self.0.get(port) {
+            Some(Some(e)) => Err(e.clone()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn io_error(kind: std::io::ErrorKind, description: &str) -> serialport::Error {
+    serialport::Error::new(serialport::ErrorKind::Io(kind), description)
+}
+
+#[test]
+fn test_probe_classifies_posix_failures() {
+    use std::io::ErrorKind;
+    let denied = io_error(ErrorKind::PermissionDenied, "Permission denied");
+    assert_eq!(PortAccess::classify(&denied, Platform::Linux), PortAccess::PermissionDenied);
+    // EBUSY has no io::ErrorKind of its own in serialport.
+    let busy = serialport::Error::new(serialport::ErrorKind::Unknown, "Device or resource busy");
+    assert_eq!(PortAccess::classify(&busy, Platform::Linux), PortAccess::Busy);
+    let gone = io_error(ErrorKind::NotFound, "No such file or directory");
+    assert_eq!(PortAccess::classify(&gone, Platform::MacOs), PortAccess::Missing);
+    let io = serialport::Error::new(serialport::ErrorKind::Unknown, "Input/output error");
+    assert_eq!(
+        PortAccess::classify(&io, Platform::Linux),
+        PortAccess::DriverError("Input/output error".to_string())
+    );
+}
+
+#[test]
+fn test_probe_classifies_windows_failures() {
+    // A port another program holds reads as access denied on Windows.
+    let held = serialport::Error::new(serialport::ErrorKind::NoDevice, "Access is denied.");
+    assert_eq!(PortAccess::classify(&held, Platform::Windows), PortAccess::Busy);
+    let phantom = serialport::Error::new(serialport::ErrorKind::NoDevice, "The system cannot find the file specified.");
+    assert!(matches!(PortAccess::classify(&phantom, Platform::Windows), PortAccess::DriverError(_)));
+}
+
+#[test]
+fn test_probe_messages_carry_platform_hints() {
+    assert_eq!(PortAccess::Ok.message(Platform::Linux), "OK");
+    assert_eq!(
+        PortAccess::PermissionDenied.message(Platform::Linux),
+        "permission denied (add user to dialout group: sudo usermod -aG dialout $USER)"
+    );
+    assert!(PortAccess::Busy.message(Platform::Windows).starts_with("busy (in use by another process"));
+    let driver = PortAccess::DriverError("A device attached to the system is not functioning.".to_string());
+    assert!(driver.message(Platform::Windows).contains("Device Manager"));
+    assert!(driver.message(Platform::Windows).contains("not functioning"));
+    assert!(PortAccess::DriverError(String::new()).message(Platform::Linux).contains("dmesg"));
+}
+
+#[test]
+fn test_probe_ports_reports_each_failure_class() {
+    use std::io::ErrorKind;
+    let io = FakePorts(HashMap::from([
+        ("/dev/ttyUSB0", None),
+        ("/dev/ttyUSB1", Some(io_error(ErrorKind::PermissionDenied, "Permission denied"))),
+        ("/dev/ttyACM0", Some(serialport::Error::new(serialport::ErrorKind::Unknown, "Device or resource busy"))),
+    ]));
+    let ports: Vec<String> = ["/dev/ttyUSB0", "/dev/ttyUSB1", "/dev/ttyACM0"].map(String::from).to_vec();
+    let results = probe_ports(Arc::new(io), &ports, Duration::from_secs(2), Platform::Linux);
+    assert_eq!(
+        results,
+        vec![
+            ("/dev/ttyUSB0".to_string(), PortAccess::Ok),
+            ("/dev/ttyUSB1".to_string(), PortAccess::PermissionDenied),
+            ("/dev/ttyACM0".to_string(), PortAccess::Busy),
+        ]
+    );
+}
+
+#[test]
+fn test_probe_ports_wedged_device_does_not_stall_the_scan() {
+    let io = FakePorts(HashMap::new());
+    let ports: Vec<String> = ["COM3", "wedged", "COM4"].map(String::from).to_vec();
+    let started = Instant::now();
+    let results = probe_ports(Arc::new(io), &ports, Duration::from_millis(200), Platform::Windows);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(results[0].1, PortAccess::Ok);
+    assert_eq!(results[1].1, PortAccess::TimedOut);
+    assert_eq!(results[2].1, PortAccess::Ok);
+}
+
+// ============================================================================
+// Device Identity Tests
+// ============================================================================
+
+use positronic_io::device::{self, DeviceId, PortInfo};
+
+fn uno(serial: Option<&str>) -> DeviceId {
+    DeviceId::new(0x2341, 0x0043, serial)
+}
+
+#[test]
+fn test_device_id_round_trips_through_its_key() {
+    let id = uno(Some("7583335393435"));
+    assert_eq!(id.to_string(), "2341:0043:7583335393435");
+    assert_eq!(id.usb_id(), "2341:0043");
+    assert_eq!("2341:0043:7583335393435".parse::<DeviceId>(), Ok(id));
+    assert_eq!("2341:0043".parse::<DeviceId>(), Ok(uno(None)));
+    // Serial numbers may contain colons; blank ones count as none.
+    assert_eq!("0403:6001:A:B".parse::<DeviceId>().unwrap().serial.as_deref(), Some("A:B"));
+    assert_eq!(DeviceId::new(0x0403, 0x6001, Some("  ")).serial, None);
+    assert!("COM3".parse::<DeviceId>().is_err());
+    assert!("2341".parse::<DeviceId>().is_err());
+}
+
+#[test]
+fn test_device_ports_follow_the_device_not_the_name() {
+    let bench = uno(Some("A1"));
+    let other = uno(Some("B2"));
+    let probe = DeviceId::new(0x0483, 0x374b, Some("ST1"));
+    let ports = vec![
+        PortInfo::plain("/dev/ttyS0"),
+        PortInfo::usb("/dev/ttyACM1", probe.clone(), Some("STLink")),
+        PortInfo::usb("/dev/ttyUSB3", bench.clone(), Some("Arduino Uno")),
+        PortInfo::usb("/dev/ttyACM0", probe.clone(), Some("STLink")),
+        PortInfo::usb("/dev/ttyUSB0", other, None),
+    ];
+    assert_eq!(device::ports_of(&ports, &bench), vec!["/dev/ttyUSB3"]);
+    assert_eq!(device::ports_of(&ports, &probe), vec!["/dev/ttyACM1", "/dev/ttyACM0"], "enumeration order");
+    assert!(device::ports_of(&ports, &uno(Some("gone"))).is_empty());
+    assert!(device::ports_of(&ports, &uno(None)).is_empty(), "a serial number tells boards apart");
+
+    let info = device::identify(&ports, "/dev/ttyUSB3").unwrap();
+    assert_eq!((info.device.as_ref(), info.product.as_deref()), (Some(&bench), Some("Arduino Uno")));
+    assert_eq!(device::identify(&ports, "/dev/ttyS0"), None, "no USB identity");
+    assert_eq!(device::identify(&ports, "/dev/ttyUSB9"), None);
+}
+
+// ============================================================================
+// Baud Detection Tests
+// ============================================================================
+
+use positronic_io::baud::{self, AutoBaud, BaudGuess, RateSwitch, DEFAULT_CANDIDATES, DEFAULT_THRESHOLD};
+
+const BOOT_LOG: &str = "ets Jun  8 2016 00:22:57\r\nrst:0x1 (POWERON_RESET),boot:0x13 (SPI_FAST_FLASH_BOOT)\r\n\
+I (29) boot: ESP-IDF v4.4.2 2nd stage bootloader\r\nI (312) wifi: connected, rssi=-61\r\n\
+temp=21.5 hum=40.2 pressure=1013.2\r\ntemp=21.6 hum=40.1 pressure=1013.1\r\n";
+
+/// `text` sent at `sent` baud as 8N1 frames, as a UART reading at `read`
+/// baud would see it: it waits for a falling edge, samples mid-bit at its
+/// own rate, and on a low stop bit (a framing error) waits for the line to
+/// go high again before looking for the next start bit.
+fn uart(text: &[u8], sent: u32, read: u32) -> Vec<u8> {
+    let bits: Vec<bool> = text
+        .iter()
+        .flat_map(|&byte| {
+            std::iter::once(false)
+                .chain((0..8).map(move |i| byte >> i & 1 == 1))
+                .chain(std::iter::once(true))
+        })
+        .collect();
+    let (sent_bit, read_bit) = (1.0 / sent as f64, 1.0 / read as f64);
+    let end = bits.len() as f64 * sent_bit;
+    let level = |t: f64| if t < 0.0 || t >= end { true } else { bits[(t / sent_bit) as usize] };
+    let step = read_bit / 16.0;
+    let mut received = Vec::new();
+    let mut t = 0.0;
+    while t < end {
+        if level(t) {
+            t += step;
+            continue;
+        }
+        let byte = (0..8).fold(0u8, |byte, i| byte | (level(t + read_bit * (1.5 + i as f64)) as u8) << i);
+        received.push(byte);
+        t += read_bit * 9.5;
+        while t < end && !level(t) {
+            t += step;
+        }
+    }
+    received
+}
+
+#[test]
+fn test_uart_fixture_reads_back_at_the_right_rate() {
+    assert_eq!(uart(BOOT_LOG.as_bytes(), 115_200, 115_200), BOOT_LOG.as_bytes());
+    assert_eq!(uart(b"hi\n", 9600, 9600), b"hi\n");
+}
+
+#[test]
+fn test_score_real_text_clears_the_threshold() {
+    assert!(baud::score(BOOT_LOG.as_bytes()) > 0.95);
+    assert!(baud::score("température: 21,5 °C ✓\n".as_bytes()) > 0.95, "UTF-8 counts as text");
+    assert!(baud::score(b"OK") >= DEFAULT_THRESHOLD, "a short burst before its line break");
+    assert_eq!(baud::score(b""), 0.0);
+}
+
+#[test]
+fn test_score_garbage_at_the_wrong_rate_stays_below_the_threshold() {
+    for &sent in &DEFAULT_CANDIDATES {
+        for &read in DEFAULT_CANDIDATES.iter().filter(|&&read| read != sent) {
+            let garbage = uart(BOOT_LOG.as_bytes(), sent, read);
+            let score = baud::score(&garbage);
+            assert!(score < DEFAULT_THRESHOLD, "sent {} read {}: {:.2} for {:?}", sent, read, score, garbage);
+        }
+    }
+}
+
+#[test]
+fn test_score_penalizes_framing_runs_and_unbroken_noise() {
+    let text = b"value=12\n".repeat(4);
+    let mut framed = text.clone();
+    framed.extend([0x00; 12]);
+    framed.extend([0xFF; 12]);
+    assert!(baud::score(&framed) < baud::score(&text));
+    // Single 0x00 and 0xFF bytes are merely unprintable, not runs.
+    assert!(baud::score(&[0x00; 32]) < baud::score(&[0x00, 0x41].repeat(16)));
+    // Printable but never breaking a line: long binary that happens to be ASCII.
+    let unbroken = b"x".repeat(400);
+    assert!(baud::score(&unbroken) < baud::score(&b"x".repeat(399).into_iter().chain([b'\n']).collect::<Vec<_>>()));
+    assert!(baud::score(&[0x80, 0xF8, 0xE0, 0x1C, 0x03, 0xFE, 0x78, 0x86].repeat(8)) < 0.3);
+}
+
+/// A device sending `text` at `sent` baud, heard at whatever rate is set.
+struct FakeLine {
+    text: Vec<u8>,
+    sent: u32,
+    rate: u32,
+    refuses: Option<u32>,
+    tried: Vec<u32>,
+}
+
+impl FakeLine {
+    fn new(text: &[u8], sent: u32) -> Self {
+        FakeLine { text: text.to_vec(), sent, rate: 0, refuses: None, tried: Vec::new() }
+    }
+}
+
+impl RateSwitch for FakeLine {
+    fn set_rate(&mut self, baud: u32) -> Result<(), serialport::Error> {
+        if self.refuses == Some(baud) {
+            return Err(serialport::Error::new(serialport::ErrorKind::InvalidInput, "unsupported baud rate"));
+        }
+        self.rate = baud;
+        Ok(())
+    }
+
+    fn listen(&mut self, _dwell: Duration) -> std::io::Result<Vec<u8>> {
+        self.tried.push(self.rate);
+        if self.text.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(uart(&self.text, self.sent, self.rate))
+    }
+}
+
+#[test]
+fn test_detect_finds_the_rate_without_reopening() {
+    let auto = AutoBaud::default();
+    for sent in [9600, 57_600, 1_000_000] {
+        let mut line = FakeLine::new(BOOT_LOG.as_bytes(), sent);
+        let ranked = baud::detect(&mut line, &auto).unwrap();
+        assert_eq!(auto.pick(&ranked), Some(sent));
+        assert_eq!(line.tried, DEFAULT_CANDIDATES.to_vec(), "every rate, in priority order, on one port");
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score), "best first");
+    }
+}
+
+#[test]
+fn test_detect_reports_guesses_when_nothing_is_conclusive() {
+    let auto = AutoBaud::default();
+    let mut silent = FakeLine::new(b"", 9600);
+    let ranked = baud::detect(&mut silent, &auto).unwrap();
+    assert_eq!(auto.pick(&ranked), None);
+    assert_eq!(ranked[0], BaudGuess { baud: 115_200, score: 0.0, bytes: 0 }, "ties keep priority order");
+    assert_eq!(ranked[0].to_string(), "115200 (silent)");
+
+    // Too few bytes to trust, however clean.
+    let mut terse = FakeLine::new(b"ok\n", 9600);
+    let ranked = baud::detect(&mut terse, &auto).unwrap();
+    assert_eq!(ranked[0].baud, 9600);
+    assert_eq!(auto.pick(&ranked), None);
+}
+
+#[test]
+fn test_detect_is_configurable() {
+    let auto = AutoBaud {
+        candidates: vec![4800, 9600],
+        dwell: Duration::from_millis(1),
+        // Prefers whatever has the most zero bytes.
+        scorer: |bytes| bytes.iter().filter(|&&b| b == 0).count() as f64 / bytes.len().max(1) as f64,
+        ..AutoBaud::default()
+    };
+    let mut line = FakeLine::new(BOOT_LOG.as_bytes(), 9600);
+    line.refuses = Some(4800);
+    let ranked = baud::detect(&mut line, &auto).unwrap();
+    assert_eq!(line.tried, vec![9600], "a refused rate is skipped");
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0].score, 0.0);
+    assert_eq!(format!("{}", ranked[0]), "9600 (0.00)");
+}
@@ -0,0 +1,407 @@
+// positronic-neural/src/budget.rs
+//
+// Token accounting for chat prompts.
+//
+// Small local models have context windows of a few thousand tokens, and a
+// long `!debug` paste plus recent history is enough to overflow them. The
+// server then fails with an opaque error. `PromptBuilder` assembles the
+// system and user messages under a token budget instead: it drops the oldest
+// history first, then context details, and marks what it removed. The user's
+// own text is never trimmed.
+//
+// Token counts are estimates. `CharEstimator` uses a characters-per-token
+// ratio; a real tokenizer can be plugged in through `TokenEstimator`.
+//
+// History and the details `system_context` reads off the disk are
+// untrusted (see `injection`): history goes out fenced, the rest
+// neutralized.
+
+use crate::cortex::SystemContext;
+use crate::injection;
+
+/// Assumed context window for models that don't advertise one.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 2048;
+
+/// Marker left in the prompt when older history entries were dropped.
+pub const HISTORY_TRUNCATED: &str = "(history truncated)";
+/// Marker left in the prompt when context details were dropped.
+pub const CONTEXT_TRUNCATED: &str = "(context truncated)";
+
+/// Tokens the chat template adds around each message (role, separators).
+const MESSAGE_OVERHEAD: usize = 4;
+
+// ════════════════════════════════════════════════════════════════════
+// Estimation
+// ════════════════════════════════════════════════════════════════════
+
+pub trait TokenEstimator: Send + Sync {
+    /// Approximate number of tokens `text` encodes to.
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// Characters-per-token ratios for model families whose tokenizers are
+/// known to run denser than the default. First substring match wins.
+const MODEL_RATIOS: &[(&str, f32)] = &[
+    ("coder", 3.2),
+    ("qwen", 3.6),
+    ("phi", 3.8),
+];
+
+/// The chars/4 heuristic, with per-model ratio overrides.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharEstimator {
+    pub chars_per_token: f32,
+}
+
+impl Default for CharEstimator {
+    fn default() -> Self {
+        Self { chars_per_token: 4.0 }
+    }
+}
+
+impl CharEstimator {
+    pub fn new(chars_per_token: f32) -> Self {
+        Self { chars_per_token: chars_per_token.max(0.5) }
+    }
+
+    /// Estimator tuned for `model`, falling back to chars/4.
+    pub fn for_model(model: &str) -> Self {
+        let lower = model.to_lowercase();
+        MODEL_RATIOS
+            .iter()
+            .find(|(family, _)| lower.contains(family))
+            .map(|&(_, ratio)| Self::new(ratio))
+            .unwrap_or_default()
+    }
+}
+
+impl TokenEstimator for CharEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        (text.chars().count() as f32 / self.chars_per_token).ceil() as usize
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Builder
+// ════════════════════════════════════════════════════════════════════
+
+/// The assembled prompt and how it was fitted to the budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuiltPrompt {
+    pub system: String,
+    pub user: String,
+    /// Estimated tokens for both messages, including template overhead.
+    pub estimated_tokens: usize,
+    pub budget: usize,
+    /// Oldest history entries removed to fit.
+    pub history_dropped: usize,
+    /// Context details removed to fit.
+    pub context_dropped: usize,
+}
+
+impl BuiltPrompt {
+    pub fn truncated(&self) -> bool {
+        self.history_dropped > 0 || self.context_dropped > 0
+    }
+
+    /// True if the prompt still doesn't fit after trimming everything
+    /// trimmable, i.e. the instructions and user text alone are too large.
+    pub fn over_budget(&self) -> bool {
+        self.estimated_tokens > self.budget
+    }
+}
+
+/// Assembles system + context + history + user sections under a budget.
+///
+/// Trimming order: oldest history entries, then context details from the
+/// last added to the first. Instructions and the user text are kept whole.
+pub struct PromptBuilder {
+    budget: usize,
+    estimator: Box<dyn TokenEstimator>,
+    instructions: String,
+    /// Highest priority first.
+    context: Vec<String>,
+    /// Oldest first.
+    history: Vec<String>,
+    user: String,
+}
+
+impl PromptBuilder {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            estimator: Box::new(CharEstimator::default()),
+            instructions: String::new(),
+            context: Vec::new(),
+            history: Vec::new(),
+            user: String::new(),
+        }
+    }
+
+    pub fn estimator(mut self, estimator: impl TokenEstimator + 'static) -> Self {
+        self.estimator = Box::new(estimator);
+        self
+    }
+
+    /// Fixed system instructions. Never trimmed.
+    pub fn instructions(mut self, text: impl Into<String>) -> Self {
+        self.instructions = text.into();
+        self
+    }
+
+    /// One context detail. Add the most important first; the last added
+    /// are dropped first.
+    pub fn context(mut self, line: impl Into<String>) -> Self {
+        self.context.push(line.into());
+        self
+    }
+
+    /// Recent commands, oldest first.
+    pub fn history<I, S>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.history.extend(entries.into_iter().map(Into::into));
+        self
+    }
+
+    /// Context lines and recent commands from a `SystemContext`. The
+    /// workspace lines rank below the directory and above the rest.
+    pub fn system_context(self, ctx: &SystemContext) -> Self {
+        let builder = self.context(format!("Working directory: {}", injection::neutralize(&ctx.cwd)));
+        ctx.workspace
+            .iter()
+            .fold(builder, |builder, line| builder.context(injection::neutralize(line)))
+            .context(format!("OS: {}, Shell: {}", ctx.os, ctx.shell))
+            .context(format!("Current date/time: {}", ctx.datetime))
+            .history(ctx.recent_commands.iter().rev().cloned())
+    }
+
+    /// The user's message. Never trimmed.
+    pub fn user(mut self, text: impl Into<String>) -> Self {
+        self.user = text.into();
+        self
+    }
+
+    pub fn build(self) -> BuiltPrompt {
+        let user_tokens = self.estimator.estimate(&self.user) + MESSAGE_OVERHEAD;
+        let mut keep_history = self.history.len();
+        let mut keep_context = self.context.len();
+
+        loop {
+            let system = self.render_system(keep_context, keep_history);
+            let estimated_tokens =
+                self.estimator.estimate(&system) + MESSAGE_OVERHEAD + user_tokens;
+
+            let fits = estimated_tokens <= self.budget;
+            if fits || (keep_history == 0 && keep_context == 0) {
+                return BuiltPrompt {
+                    system,
+                    user: self.user,
+                    estimated_tokens,
+                    budget: self.budget,
+                    history_dropped: self.history.len() - keep_history,
+                    context_dropped: self.context.len() - keep_context,
+                };
+            }
+
+            if keep_history > 0 {
+                keep_history -= 1;
+            } else {
+                keep_context -= 1;
+            }
+        }
+    }
+
+    fn render_system(&self, keep_context: usize, keep_history: usize) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if !self.instructions.is_empty() {
+            parts.push(self.instructions.clone());
+        }
+
+        let mut details: Vec<&str> = self.context[..keep_context].iter().map(String::as_str).collect();
+        if keep_context < self.context.len() {
+            details.push(CONTEXT_TRUNCATED);
+        }
+        if !details.is_empty() {
+            parts.push(details.join("\n"));
+        }
+
+        let dropped = self.history.len() - keep_history;
+        if keep_history > 0 {
+            let mut lines = Vec::new();
+            if dropped > 0 {
+                lines.push(format!("  {}", HISTORY_TRUNCATED));
+            }
+            lines.extend(self.history[dropped..].iter().map(|c| format!("  $ {}", c)));
+            parts.push(format!("Recent commands:\n{}", injection::fence("history", &lines.join("\n"))));
+        } else if dropped > 0 {
+            parts.push(HISTORY_TRUNCATED.to_string());
+        }
+
+        parts.join("\n\n")
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTRUCTIONS: &str = "You are a helpful terminal assistant.";
+
+    fn history(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("cargo build --package crate-{:03}", i)).collect()
+    }
+
+    fn builder(budget: usize) -> PromptBuilder {
+        PromptBuilder::new(budget)
+            .instructions(INSTRUCTIONS)
+            .context("Working directory: /home/user/project")
+            .context("OS: Linux, Shell: bash")
+            .context("Current date/time: Monday, January 01, 2024 at 09:00")
+            .history(history(40))
+            .user("why does my build fail with E0382?")
+    }
+
+    #[test]
+    fn test_char_estimator() {
+        let est = CharEstimator::default();
+        assert_eq!(est.estimate(""), 0);
+        assert_eq!(est.estimate("abcd"), 1);
+        assert_eq!(est.estimate("abcde"), 2);
+        // Counts characters, not bytes.
+        assert_eq!(est.estimate("éééé"), 1);
+    }
+
+    #[test]
+    fn test_model_overrides() {
+        assert_eq!(CharEstimator::for_model("Qwen2.5-Coder-7B").chars_per_token, 3.2);
+        assert_eq!(CharEstimator::for_model("Phi-3-mini").chars_per_token, 3.8);
+        assert_eq!(CharEstimator::for_model("Llama-3.2-1B"), CharEstimator::default());
+    }
+
+    #[test]
+    fn test_fits_without_trimming() {
+        let built = builder(10_000).build();
+        assert!(!built.truncated());
+        assert!(!built.system.contains(HISTORY_TRUNCATED));
+        assert!(built.system.contains("crate-000"));
+        assert!(built.system.contains("crate-039"));
+    }
+
+    #[test]
+    fn test_budget_adherence_across_sizes() {
+        let floor = builder(0).build().estimated_tokens;
+        for budget in [floor, floor + 10, 150, 200, 300, 400, 600, 1000] {
+            let built = builder(budget).build();
+            assert!(
+                built.estimated_tokens <= budget.max(floor),
+                "budget {}: estimated {}",
+                budget,
+                built.estimated_tokens
+            );
+            assert_eq!(built.user, "why does my build fail with E0382?");
+        }
+    }
+
+    #[test]
+    fn test_history_trimmed_oldest_first() {
+        let full = builder(10_000).build().estimated_tokens;
+        let built = builder(full - 30).build();
+        assert!(built.history_dropped > 0);
+        assert_eq!(built.context_dropped, 0);
+        assert!(built.system.contains(HISTORY_TRUNCATED));
+        assert!(!built.system.contains("crate-000"));
+        assert!(built.system.contains("crate-039"));
+        assert!(built.system.contains("Current date/time"));
+    }
+
+    #[test]
+    fn test_context_trimmed_after_history() {
+        let no_history = PromptBuilder::new(10_000)
+            .instructions(INSTRUCTIONS)
+            .context("Working directory: /home/user/project")
+            .context("OS: Linux, Shell: bash")
+            .context("Current date/time: Monday, January 01, 2024 at 09:00")
+            .user("why does my build fail with E0382?")
+            .build()
+            .estimated_tokens;
+
+        let built = builder(no_history - 5).build();
+        assert_eq!(built.history_dropped, 40);
+        assert!(built.context_dropped > 0);
+        assert!(built.system.contains(HISTORY_TRUNCATED));
+        assert!(built.system.contains(CONTEXT_TRUNCATED));
+        // Least important detail goes first.
+        assert!(!built.system.contains("Current date/time"));
+        assert!(built.system.contains("Working directory"));
+    }
+
+    #[test]
+    fn test_user_text_never_trimmed() {
+        let paste = "error[E0382]: borrow of moved value\n".repeat(200);
+        let built = builder(100).user(paste.clone()).build();
+        assert_eq!(built.user, paste);
+        assert!(built.over_budget());
+        assert!(built.system.starts_with(INSTRUCTIONS));
+        assert_eq!(built.history_dropped, 40);
+        assert_eq!(built.context_dropped, 3);
+    }
+
+    #[test]
+    fn test_workspace_lines_rank_below_the_directory() {
+        let ctx = SystemContext {
+            datetime: "now".to_string(),
+            os: "Linux".to_string(),
+            shell: "bash".to_string(),
+            cwd: "/src/app".to_string(),
+            workspace: vec![
+                "Project: Rust crate app (edition 2021)".to_string(),
+                "Git: branch main, no upstream, clean".to_string(),
+            ],
+            recent_commands: Vec::new(),
+        };
+        let full = PromptBuilder::new(10_000).system_context(&ctx).build();
+        let at = |needle: &str| full.system.find(needle).unwrap();
+        assert!(at("Working directory") < at("Project:"));
+        assert!(at("Project:") < at("Git:"));
+        assert!(at("Git:") < at("OS:"));
+
+        // Date and OS go before the workspace when the budget is tight.
+        let tight = (1..full.estimated_tokens)
+            .rev()
+            .map(|budget| PromptBuilder::new(budget).system_context(&ctx).build())
+            .find(|built| !built.system.contains("OS:"))
+            .unwrap();
+        assert_eq!(tight.context_dropped, 2);
+        assert!(tight.system.contains("Git:"));
+    }
+
+    #[test]
+    fn test_system_context_history_order() {
+        let ctx = SystemContext {
+            datetime: "now".to_string(),
+            os: "Linux".to_string(),
+            shell: "bash".to_string(),
+            cwd: "/tmp".to_string(),
+            workspace: Vec::new(),
+            // Most recent first.
+            recent_commands: vec![
+                "newest: cargo test --workspace --all-features".to_string(),
+                "oldest: cargo build --workspace --release".to_string(),
+            ],
+        };
+        let full = PromptBuilder::new(10_000).system_context(&ctx).build();
+        assert!(full.system.find("oldest") < full.system.find("newest"));
+
+        let tight = PromptBuilder::new(full.estimated_tokens - 1).system_context(&ctx).build();
+        assert_eq!(tight.history_dropped, 1);
+        assert!(tight.system.contains("newest"));
+        assert!(!tight.system.contains("oldest"));
+    }
+}
@@ -2,10 +2,21 @@
 //
 // Neural client that talks to Lemonade (or any OpenAI-compatible local LLM).
 // Supports smart model selection: routes code tasks to Coder models and
-// general tasks to lighter/faster models.
+// general tasks to lighter/faster models, skipping models the health prober
+// (see `health`) has found broken.
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::budget::{BuiltPrompt, CharEstimator, PromptBuilder, DEFAULT_CONTEXT_WINDOW};
+use crate::generate::{generation_instructions, sanitize_command, ShellFlavor};
+use crate::health::{HealthCache, HealthReport, ProbeConfig, ProbeGate};
+use crate::injection;
+use crate::workspace::Workspace;
 
 /// The types of task we can route to different models.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -80,6 +91,10 @@ pub struct SystemContext {
     pub os: String,
     pub shell: String,
     pub cwd: String,
+    /// Project and git lines from `Workspace::context_lines`, already
+    /// scrubbed; empty until a workspace is attached.
+    pub workspace: Vec<String>,
+    /// Most recent first.
     pub recent_commands: Vec<String>,
 }
 
@@ -88,16 +103,11 @@ impl SystemContext {
     pub fn gather(cwd: &str, recent_commands: Vec<String>) -> Self {
         let datetime = chrono::Local::now().format("%A, %B %d, %Y at %H:%M").to_string();
 
-        let os = if cfg!(windows) {
-            "Windows".to_string()
-        } else if cfg!(target_os = "macos") {
-            "macOS".to_string()
-        } else {
-            "Linux".to_string()
-        };
+        let os = os_name();
 
+        // The Windows PTY always runs PowerShell, whatever COMSPEC says.
         let shell = if cfg!(windows) {
-            std::env::var("COMSPEC").unwrap_or_else(|_| "PowerShell".to_string())
+            "PowerShell".to_string()
         } else {
             std::env::var("SHELL").unwrap_or_else(|_| "bash".to_string())
         };
@@ -107,28 +117,60 @@ impl SystemContext {
             os,
             shell,
             cwd: cwd.to_string(),
+            workspace: Vec::new(),
             recent_commands,
         }
     }
 
+    /// Attach the project and git summary of the working directory.
+    pub fn with_workspace(mut self, workspace: Option<&Workspace>) -> Self {
+        self.workspace = workspace.map(Workspace::context_lines).unwrap_or_default();
+        self
+    }
+
     /// Format as a system prompt prefix.
     pub fn to_system_prompt(&self) -> String {
         let mut parts = vec![
             format!("Current date/time: {}", self.datetime),
             format!("OS: {}, Shell: {}", self.os, self.shell),
-            format!("Working directory: {}", self.cwd),
+            format!("Working directory: {}", injection::neutralize(&self.cwd)),
         ];
+        parts.extend(self.workspace.iter().map(|line| injection::neutralize(line)));
         if !self.recent_commands.is_empty() {
             let cmds = self.recent_commands.iter()
                 .map(|c| format!("  $ {}", c))
                 .collect::<Vec<_>>()
                 .join("\n");
-            parts.push(format!("Recent commands:\n{}", cmds));
+            parts.push(format!("Recent commands:\n{}", injection::fence("history", &cmds)));
         }
         parts.join("\n")
     }
 }
 
+/// The OS family, with the distribution on Linux (`Linux (Ubuntu 24.04 LTS)`).
+fn os_name() -> String {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        if cfg!(windows) {
+            "Windows".to_string()
+        } else if cfg!(target_os = "macos") {
+            "macOS".to_string()
+        } else {
+            let pretty = std::fs::read_to_string("/etc/os-release").ok().and_then(|release| {
+                release.lines().find_map(|line| {
+                    let value = line.strip_prefix("PRETTY_NAME=")?.trim_matches('"');
+                    (!value.is_empty()).then(|| value.to_string())
+                })
+            });
+            match pretty {
+                Some(distro) => format!("Linux ({})", distro),
+                None => "Linux".to_string(),
+            }
+        }
+    })
+    .clone()
+}
+
 // ════════════════════════════════════════════════════════════════════
 // Client
 // ════════════════════════════════════════════════════════════════════
@@ -140,8 +182,11 @@ pub struct NeuralClient {
     /// Default model name (used for display / fallback).
     default_model: String,
     client: reqwest::Client,
-    /// Cached list of available models (refreshed on first use).
-    cached_models: std::sync::Arc<tokio::sync::Mutex<Option<Vec<String>>>>,
+    /// Model list and per-model health, kept fresh by the background prober.
+    health: HealthCache,
+    probe: ProbeConfig,
+    /// Closed while the terminal is idle; see `pause_probes`.
+    gate: ProbeGate,
 }
 
 #[derive(Serialize)]
@@ -178,6 +223,65 @@ struct ModelsResponse {
 #[derive(Deserialize)]
 struct ModelInfo {
     id: String,
+    // Servers advertise the context window under different names.
+    #[serde(default)]
+    context_length: Option<u32>,
+    #[serde(default)]
+    max_context_length: Option<u32>,
+    #[serde(default)]
+    context_window: Option<u32>,
+    #[serde(default)]
+    max_model_len: Option<u32>,
+}
+
+impl ModelInfo {
+    fn context_window(&self) -> Option<u32> {
+        self.context_length
+            .or(self.max_context_length)
+            .or(self.context_window)
+            .or(self.max_model_len)
+    }
+}
+
+/// Instructions sent as the system message of every `ask_smart` request.
+const ASSISTANT_INSTRUCTIONS: &str = "You are a helpful terminal assistant. Be concise and practical. \
+     Give exact commands when applicable. Answer the user's question \
+     directly, then stop. Do NOT simulate follow-up questions or \
+     generate fake User/Assistant dialogue.";
+
+/// An `ask_smart` answer plus how the prompt was sized.
+#[derive(Debug, Clone)]
+pub struct SmartReply {
+    pub text: String,
+    pub model: String,
+    /// Estimated prompt tokens sent.
+    pub prompt_tokens: usize,
+    /// Prompt budget for the model.
+    pub budget: usize,
+    /// History or context was trimmed to fit.
+    pub truncated: bool,
+}
+
+/// The error of a request given up on (`ask_smart_cancellable`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// A command generated from a natural-language request.
+#[derive(Debug, Clone)]
+pub struct GeneratedCommand {
+    /// First plausible command line in the answer, if any.
+    pub command: Option<String>,
+    /// The model's full answer.
+    pub raw: String,
+    pub model: String,
 }
 
 /// Stop sequences that prevent small models from hallucinating multi-turn dialogue.
@@ -200,49 +304,214 @@ impl NeuralClient {
                 .timeout(std::time::Duration::from_secs(60))
                 .build()
                 .unwrap_or_default(),
-            cached_models: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
+            health: HealthCache::new(),
+            probe: ProbeConfig::from_env(),
+            gate: ProbeGate::default(),
         }
     }
 
-    /// List available models from the server. Caches after first call.
+    /// Replace the probe schedule and timeouts.
+    pub fn with_probe_config(mut self, probe: ProbeConfig) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The model asked for when a task doesn't pick one ("auto").
+    pub fn default_model(&self) -> &str {
+        &self.default_model
+    }
+
+    pub fn probe_config(&self) -> &ProbeConfig {
+        &self.probe
+    }
+
+    /// Snapshot of the cached model health.
+    pub fn health(&self) -> HealthReport {
+        self.health.report()
+    }
+
+    /// List available models. Served from the health cache once it is filled.
     pub async fn list_models(&self) -> Result<Vec<String>> {
-        {
-            let cache = self.cached_models.lock().await;
-            if let Some(ref models) = *cache {
-                return Ok(models.clone());
-            }
+        if self.health.is_stale(self.probe.stale_after) {
+            self.refresh_listing().await?;
         }
+        Ok(self.health.report().models.into_iter().map(|m| m.id).collect())
+    }
+
+    /// Invalidate the model cache (call after model hot-swap).
+    pub async fn refresh_models(&self) {
+        self.health.clear();
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Health probing
+    // ────────────────────────────────────────────────────────────────
 
+    /// Re-read `/models` into the health cache.
+    ///
+    /// Errors only if the server can't be reached at all. A server that
+    /// answers but has no usable `/models` falls back to the configured
+    /// model, which is assumed to work until a request says otherwise.
+    pub async fn refresh_listing(&self) -> Result<()> {
         let url = format!("{}/models", self.base_url);
-        let resp = self.client.get(&url).send().await?;
-        let body: ModelsResponse = resp.json().await?;
-        let models: Vec<String> = body.data.iter().map(|m| m.id.clone()).collect();
+        let resp = match self.client.get(&url).timeout(self.probe.timeout).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let reason = format!("Lemonade not reachable: {}", e);
+                self.health.set_unreachable(&self.default_model, &reason);
+                return Err(anyhow!(reason));
+            }
+        };
+
+        let status = resp.status();
+        let parsed = if status.is_success() {
+            resp.json::<ModelsResponse>()
+                .await
+                .map_err(|e| format!("unreadable /models response: {}", e))
+        } else {
+            Err(format!("/models returned {}", status))
+        };
+
+        match parsed {
+            Ok(body) => {
+                let listed = body
+                    .data
+                    .iter()
+                    .map(|m| (m.id.clone(), m.context_window()))
+                    .collect();
+                self.health.set_listing(listed, true, None);
+            }
+            Err(reason) => {
+                self.health
+                    .set_listing(vec![(self.default_model.clone(), None)], false, Some(reason));
+            }
+        }
+        Ok(())
+    }
 
-        {
-            let mut cache = self.cached_models.lock().await;
-            *cache = Some(models.clone());
+    /// List models, then (if configured) send each a one-token completion.
+    /// Never called on a user request path; see `spawn_prober`.
+    pub async fn probe_now(&self) -> Result<HealthReport> {
+        self.refresh_listing().await?;
+
+        if self.probe.completion_probe {
+            let ids: Vec<String> = self.health.report().models.into_iter().map(|m| m.id).collect();
+            for id in ids {
+                let started = Instant::now();
+                match self.probe_completion(&id).await {
+                    Ok(()) => self.health.record_success(&id, started.elapsed()),
+                    Err(e) => self.health.record_failure(&id, format!("{:#}", e)),
+                }
+            }
         }
 
-        Ok(models)
+        Ok(self.health.report())
     }
 
-    /// Invalidate the model cache (call after model hot-swap).
-    pub async fn refresh_models(&self) {
-        let mut cache = self.cached_models.lock().await;
-        *cache = None;
+    /// Probe immediately, then again every `interval` (± jitter), forever.
+    /// A probe that falls due while probes are paused waits for `resume_probes`.
+    pub fn spawn_prober(&self) -> tokio::task::JoinHandle<()> {
+        let client = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = client.probe_now().await {
+                    tracing::warn!("Neural probe failed: {:#}", e);
+                }
+                tokio::time::sleep(client.probe.next_delay()).await;
+                client.gate.open().await;
+            }
+        })
     }
 
-    /// Select the best model for a task type from available models.
+    /// Hold background probes while the terminal is idle. Requests still
+    /// refresh a stale cache on their own.
+    pub fn pause_probes(&self) {
+        self.gate.pause();
+    }
+
+    pub fn resume_probes(&self) {
+        self.gate.resume();
+    }
+
+    pub fn probes_paused(&self) -> bool {
+        self.gate.is_paused()
+    }
+
+    async fn probe_completion(&self, model: &str) -> Result<()> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: "ping".to_string(),
+            }],
+            max_tokens: 1,
+            temperature: 0.0,
+            stop: None,
+        };
+        let resp = self
+            .client
+            .post(&url)
+            .json(&request)
+            .timeout(self.probe.timeout)
+            .send()
+            .await
+            .context("probe request failed")?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("{}: {}", status, &body[..body.len().min(200)]));
+        }
+        let body: ChatResponse = resp.json().await.context("unreadable probe response")?;
+        if body.choices.is_empty() {
+            return Err(anyhow!("probe returned no choices"));
+        }
+        Ok(())
+    }
+
+    // ────────────────────────────────────────────────────────────────
+    // Model selection
+    // ────────────────────────────────────────────────────────────────
+
+    /// Select the best model for a task type from the models not known to
+    /// be broken. The cache is refreshed first only if it has gone stale.
     pub async fn select_model(&self, task_type: TaskType) -> Result<String> {
-        let models = self.list_models().await?;
+        let mut refresh_error = None;
+        if self.health.is_stale(self.probe.stale_after) {
+            refresh_error = self.refresh_listing().await.err();
+        }
+
+        let report = self.health.report();
+        let mut models = report.usable();
+        if models.is_empty() {
+            if let Some(e) = refresh_error {
+                return Err(e);
+            }
+            if !report.reachable {
+                let reason = report.last_error.unwrap_or_else(|| "Lemonade not reachable".to_string());
+                return Err(anyhow!(reason));
+            }
+            // Everything failed last time; give them all another chance
+            // rather than refusing outright.
+            models = report.models.into_iter().map(|m| m.id).collect();
+        }
 
         if models.is_empty() {
             return Err(anyhow!("No models available from Lemonade"));
         }
 
+        Ok(Self::choose_model(&models, task_type))
+    }
+
+    /// Pick from `models` (non-empty) by name heuristics.
+    pub fn choose_model(models: &[String], task_type: TaskType) -> String {
         // Single model? No choice.
         if models.len() == 1 {
-            return Ok(models[0].clone());
+            return models[0].clone();
         }
 
         let chosen = match task_type {
@@ -275,7 +544,7 @@ impl NeuralClient {
             }
         };
 
-        Ok(chosen.cloned().unwrap_or_else(|| models[0].clone()))
+        chosen.cloned().unwrap_or_else(|| models[0].clone())
     }
 
     /// Rough heuristic to estimate model size from its name.
@@ -304,6 +573,40 @@ impl NeuralClient {
         }
     }
 
+    /// Tokens available for the prompt of a request to `model`: its context
+    /// window (or `DEFAULT_CONTEXT_WINDOW` if unknown) minus the reply.
+    pub fn prompt_budget(&self, model: &str, max_tokens: u32) -> usize {
+        let window = self
+            .health
+            .report()
+            .get(model)
+            .and_then(|m| m.context_window)
+            .map(|w| w as usize)
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW);
+        window.saturating_sub(max_tokens as usize)
+    }
+
+    /// Assemble the system and user messages for `model`, trimmed to its
+    /// budget. `injection::DATA_RULE` follows the instructions.
+    pub fn build_prompt(
+        &self,
+        model: &str,
+        instructions: &str,
+        prompt: &str,
+        task_type: TaskType,
+        context: Option<&SystemContext>,
+    ) -> BuiltPrompt {
+        let budget = self.prompt_budget(model, Self::max_tokens_for(task_type));
+        let mut builder = PromptBuilder::new(budget)
+            .estimator(CharEstimator::for_model(model))
+            .instructions(format!("{}\n\n{}", instructions, injection::DATA_RULE))
+            .user(prompt);
+        if let Some(ctx) = context {
+            builder = builder.system_context(ctx);
+        }
+        builder.build()
+    }
+
     /// Send a prompt with automatic model selection.
     /// Injects system context and selects the best model for the task.
     pub async fn ask_smart(
@@ -312,26 +615,92 @@ impl NeuralClient {
         task_type: TaskType,
         context: Option<&SystemContext>,
     ) -> Result<String> {
-        let model = self.select_model(task_type).await?;
+        Ok(self.ask_smart_detailed(prompt, task_type, context).await?.text)
+    }
 
-        let system_msg = if let Some(ctx) = context {
-            format!(
-                "You are a helpful terminal assistant. Be concise and practical. \
-                 Give exact commands when applicable. Answer the user's question \
-                 directly, then stop. Do NOT simulate follow-up questions or \
-                 generate fake User/Assistant dialogue.\n\n{}",
-                ctx.to_system_prompt()
-            )
-        } else {
-            "You are a helpful terminal assistant. Be concise and practical. \
-             Give exact commands when applicable. Answer the user's question \
-             directly, then stop. Do NOT simulate follow-up questions or \
-             generate fake User/Assistant dialogue."
-                .to_string()
+    /// `ask_smart`, also reporting the model used and the prompt size.
+    /// Fails before sending if the user text alone exceeds the model's budget.
+    pub async fn ask_smart_detailed(
+        &self,
+        prompt: &str,
+        task_type: TaskType,
+        context: Option<&SystemContext>,
+    ) -> Result<SmartReply> {
+        self.ask_instructed(ASSISTANT_INSTRUCTIONS, prompt, task_type, context).await
+    }
+
+    /// `ask_smart_detailed`, given up when `cancel` fires: the request is
+    /// dropped mid-flight, closing its connection, and the error is
+    /// `Cancelled`. A cancelled request counts against no model's health.
+    pub async fn ask_smart_cancellable(
+        &self,
+        prompt: &str,
+        task_type: TaskType,
+        context: Option<&SystemContext>,
+        cancel: &CancellationToken,
+    ) -> Result<SmartReply> {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(Cancelled.into()),
+            reply = self.ask_smart_detailed(prompt, task_type, context) => reply,
+        }
+    }
+
+    /// Turn a natural-language request into one shell command for the
+    /// context's shell (or this platform's default).
+    pub async fn generate_command(
+        &self,
+        request: &str,
+        context: Option<&SystemContext>,
+    ) -> Result<GeneratedCommand> {
+        let flavor = match context {
+            Some(ctx) => ShellFlavor::detect(&ctx.shell),
+            None if cfg!(windows) => ShellFlavor::PowerShell,
+            None => ShellFlavor::Posix,
         };
+        let reply = self
+            .ask_instructed(&generation_instructions(flavor), request, TaskType::Code, context)
+            .await?;
+        Ok(GeneratedCommand {
+            command: sanitize_command(&reply.text),
+            raw: reply.text,
+            model: reply.model,
+        })
+    }
+
+    /// Select a model, fit the prompt to its budget and send it.
+    async fn ask_instructed(
+        &self,
+        instructions: &str,
+        prompt: &str,
+        task_type: TaskType,
+        context: Option<&SystemContext>,
+    ) -> Result<SmartReply> {
+        let model = self.select_model(task_type).await?;
+        let built = self.build_prompt(&model, instructions, prompt, task_type, context);
+        if built.over_budget() {
+            return Err(anyhow!(
+                "Prompt too large for {}: ~{} tokens, budget {}",
+                model,
+                built.estimated_tokens,
+                built.budget
+            ));
+        }
 
         let max_tokens = Self::max_tokens_for(task_type);
-        self.send_chat_with_stops(&model, &system_msg, prompt, max_tokens).await
+        let started = Instant::now();
+        let result = self.send_chat_with_stops(&model, &built.system, &built.user, max_tokens).await;
+        match &result {
+            Ok(_) => self.health.record_success(&model, started.elapsed()),
+            Err(e) => self.health.record_failure(&model, format!("{:#}", e)),
+        }
+        Ok(SmartReply {
+            text: result?,
+            model,
+            prompt_tokens: built.estimated_tokens,
+            budget: built.budget,
+            truncated: built.truncated(),
+        })
     }
 
     /// Original simple ask — uses first available model, no context injection.
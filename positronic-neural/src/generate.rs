@@ -0,0 +1,212 @@
+// positronic-neural/src/generate.rs
+//
+// Natural language → one shell command (`# find big files` in the input bar).
+//
+// The model is told to answer with only the command, in the dialect of the
+// user's shell. Small models still wrap it in prose, fences or several
+// alternatives, so `sanitize_command` digs out the first plausible command
+// line. The caller keeps the raw answer for `!ai last`.
+
+/// Shell dialect the generated command must be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellFlavor {
+    Posix,
+    PowerShell,
+    Cmd,
+}
+
+impl ShellFlavor {
+    /// Guess from a shell name or path (`/bin/zsh`, `PowerShell`, `cmd.exe`).
+    pub fn detect(shell: &str) -> Self {
+        let lower = shell.to_lowercase();
+        if lower.contains("pwsh") || lower.contains("powershell") {
+            ShellFlavor::PowerShell
+        } else if lower.ends_with("cmd.exe") || lower == "cmd" {
+            ShellFlavor::Cmd
+        } else {
+            ShellFlavor::Posix
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ShellFlavor::Posix => "a POSIX shell (bash/zsh)",
+            ShellFlavor::PowerShell => "PowerShell",
+            ShellFlavor::Cmd => "Windows cmd.exe",
+        }
+    }
+}
+
+/// System instructions for command generation.
+pub fn generation_instructions(flavor: ShellFlavor) -> String {
+    format!(
+        "You translate requests into a single shell command for {}. \
+         Output ONLY the command on one line: no explanation, no markdown, \
+         no code fences, no alternatives. Use the working directory and \
+         recent commands below when they help.",
+        flavor.name()
+    )
+}
+
+/// Words that start an explanatory sentence rather than a command.
+const PROSE_OPENERS: &[&str] = &[
+    "here", "here's", "heres", "this", "that", "these", "to", "sure", "sure,", "sure!",
+    "certainly", "certainly!", "you", "the", "i", "i'm", "it", "note", "note:",
+    "explanation", "explanation:", "okay", "ok", "alternatively", "alternatively,",
+    "assuming", "below", "above", "replace", "make", "use", "try", "run", "a",
+    "an", "or", "and", "which", "where", "will", "then",
+];
+
+/// Labels models put before the command.
+const COMMAND_LABELS: &[&str] = &["command:", "cmd:", "shell:", "bash:", "powershell:"];
+
+/// Reduce a model answer to the first plausible command line.
+pub fn sanitize_command(answer: &str) -> Option<String> {
+    // A fenced block is the strongest signal.
+    let mut in_fence = false;
+    for line in answer.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            if in_fence {
+                break;
+            }
+            in_fence = true;
+            continue;
+        }
+        if in_fence && !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with("//") {
+            return Some(clean_line(trimmed));
+        }
+    }
+
+    for line in answer.lines() {
+        let line = strip_list_marker(line.trim());
+        if line.is_empty() {
+            continue;
+        }
+
+        // Prose with the command in backticks.
+        if let Some(inline) = line.split('`').nth(1).filter(|c| !c.trim().is_empty())
+            && line.matches('`').count() >= 2
+        {
+            return Some(clean_line(inline));
+        }
+
+        let unlabeled = strip_label(line);
+        if unlabeled.is_empty() || is_prose(unlabeled) {
+            continue;
+        }
+        return Some(clean_line(unlabeled));
+    }
+    None
+}
+
+fn strip_list_marker(line: &str) -> &str {
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < line.len()
+        && let Some(r) = rest.strip_prefix(['.', ')'])
+    {
+        return r.trim_start();
+    }
+    line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).unwrap_or(line)
+}
+
+fn strip_label(line: &str) -> &str {
+    let lower = line.to_lowercase();
+    COMMAND_LABELS
+        .iter()
+        .find(|l| lower.starts_with(*l))
+        .map(|l| line[l.len()..].trim())
+        .unwrap_or(line)
+}
+
+fn is_prose(line: &str) -> bool {
+    if line.ends_with(':') {
+        return true;
+    }
+    let first = line.split_whitespace().next().unwrap_or("").to_lowercase();
+    PROSE_OPENERS.contains(&first.as_str()) && line.split_whitespace().count() > 2
+}
+
+/// Drop shell prompt markers and stray quoting around the command.
+fn clean_line(line: &str) -> String {
+    let line = line.trim().trim_matches('`').trim();
+    let line = ["PS> ", "PS>", "$ ", "> ", "% "]
+        .iter()
+        .find_map(|p| line.strip_prefix(p))
+        .unwrap_or(line);
+    line.trim().to_string()
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_flavor() {
+        assert_eq!(ShellFlavor::detect("/bin/bash"), ShellFlavor::Posix);
+        assert_eq!(ShellFlavor::detect("/usr/bin/zsh"), ShellFlavor::Posix);
+        assert_eq!(ShellFlavor::detect("PowerShell"), ShellFlavor::PowerShell);
+        assert_eq!(ShellFlavor::detect("C:\\Program Files\\PowerShell\\7\\pwsh.exe"), ShellFlavor::PowerShell);
+        assert_eq!(ShellFlavor::detect("C:\\Windows\\system32\\cmd.exe"), ShellFlavor::Cmd);
+    }
+
+    #[test]
+    fn test_clean_answer_passes_through() {
+        assert_eq!(
+            sanitize_command("find . -name '*.rs' -mtime -7").as_deref(),
+            Some("find . -name '*.rs' -mtime -7")
+        );
+    }
+
+    #[test]
+    fn test_fenced_answer_with_chatter() {
+        let answer = "Sure! Here's the command:\n\n```bash\n# rust files changed this week\nfind . -name '*.rs' -mtime -7\n```\n\nThis searches recursively.";
+        assert_eq!(sanitize_command(answer).as_deref(), Some("find . -name '*.rs' -mtime -7"));
+    }
+
+    #[test]
+    fn test_inline_code_in_sentence() {
+        let answer = "You can use `du -sh * | sort -h` to list sizes in order.";
+        assert_eq!(sanitize_command(answer).as_deref(), Some("du -sh * | sort -h"));
+    }
+
+    #[test]
+    fn test_prose_then_command_line() {
+        let answer = "To find the process listening on port 8080, run the following:\nlsof -i :8080\nThis shows the PID.";
+        assert_eq!(sanitize_command(answer).as_deref(), Some("lsof -i :8080"));
+    }
+
+    #[test]
+    fn test_multiple_alternatives_take_first() {
+        let answer = "1. git log --since='1 week ago' --oneline\n2. git log -7 --oneline";
+        assert_eq!(sanitize_command(answer).as_deref(), Some("git log --since='1 week ago' --oneline"));
+
+        let answer = "```\nls -la\n```\nor\n```\nls -lah\n```";
+        assert_eq!(sanitize_command(answer).as_deref(), Some("ls -la"));
+    }
+
+    #[test]
+    fn test_labels_and_prompts_stripped() {
+        assert_eq!(sanitize_command("Command: $ docker ps -a").as_deref(), Some("docker ps -a"));
+        assert_eq!(
+            sanitize_command("PS> Get-ChildItem -Recurse -Filter *.rs").as_deref(),
+            Some("Get-ChildItem -Recurse -Filter *.rs")
+        );
+    }
+
+    #[test]
+    fn test_powershell_command_not_mistaken_for_prose() {
+        let answer = "Get-Process | Sort-Object CPU -Descending | Select-Object -First 5";
+        assert_eq!(sanitize_command(answer).as_deref(), Some(answer));
+    }
+
+    #[test]
+    fn test_no_command_in_refusal() {
+        assert_eq!(sanitize_command("I'm sorry, I can't help with that request."), None);
+        assert_eq!(sanitize_command("   \n\n"), None);
+    }
+}
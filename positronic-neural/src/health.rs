@@ -0,0 +1,319 @@
+// positronic-neural/src/health.rs
+//
+// Cached model health for the Lemonade endpoint.
+//
+// A background prober (see `NeuralClient::spawn_prober`) periodically lists
+// the server's models and sends each one a one-token completion. The results
+// land here: which models actually load, their advertised context window,
+// measured latency and the last error. Model selection reads this cache
+// instead of discovering broken models one failed chat request at a time.
+// While the terminal is idle the UI closes the `ProbeGate`, and the
+// prober waits there instead of waking the server every few minutes.
+//
+// Endpoints without a working `/models` fall back to the configured model,
+// which is assumed healthy until a request proves otherwise.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+// ════════════════════════════════════════════════════════════════════
+// Config
+// ════════════════════════════════════════════════════════════════════
+
+/// Environment override for the probe interval, in minutes.
+pub const PROBE_INTERVAL_ENV: &str = "POSITRONIC_NEURAL_PROBE_MINUTES";
+
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+    /// Time between background probes.
+    pub interval: Duration,
+    /// Each wait is randomized by up to ± this much.
+    pub jitter: Duration,
+    /// Per-request timeout for probe calls.
+    pub timeout: Duration,
+    /// Cached results older than this are refreshed before the next request.
+    pub stale_after: Duration,
+    /// Send a one-token completion to each model, not just list them.
+    pub completion_probe: bool,
+}
+
+impl Default for ProbeConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5 * 60),
+            jitter: Duration::from_secs(30),
+            timeout: Duration::from_secs(5),
+            stale_after: Duration::from_secs(10 * 60),
+            completion_probe: true,
+        }
+    }
+}
+
+impl ProbeConfig {
+    /// Defaults, with the interval taken from `POSITRONIC_NEURAL_PROBE_MINUTES`
+    /// when set.
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(mins) = std::env::var(PROBE_INTERVAL_ENV)
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|m| *m > 0)
+        {
+            config.interval = Duration::from_secs(mins * 60);
+            config.stale_after = config.interval * 2;
+        }
+        config
+    }
+
+    /// The next wait: `interval` shifted by a random amount within `jitter`,
+    /// so several clients don't probe in lockstep.
+    pub fn next_delay(&self) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return self.interval;
+        }
+        let r = RandomState::new().hash_one(Instant::now()) % (2 * jitter_ms + 1);
+        let base = self.interval.as_millis() as u64;
+        Duration::from_millis((base + r).saturating_sub(jitter_ms).max(1))
+    }
+}
+
+/// Holds the background prober between probes while the terminal is idle.
+/// Shared by clones of a client; a probe that fell due while paused runs
+/// as soon as it resumes.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeGate {
+    inner: Arc<GateState>,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+    paused: AtomicBool,
+    resumed: Notify,
+}
+
+impl ProbeGate {
+    pub fn pause(&self) {
+        self.inner.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        if self.inner.paused.swap(false, Ordering::SeqCst) {
+            self.inner.resumed.notify_waiters();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the gate is open; returns at once if it is.
+    pub async fn open(&self) {
+        loop {
+            // Registered before the check, so a resume in between isn't missed.
+            let resumed = self.inner.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            resumed.await;
+        }
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Records
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelState {
+    /// Not probed yet; assumed usable.
+    Untested,
+    /// Last probe or request succeeded.
+    Ready,
+    /// Last probe or request failed.
+    Broken,
+}
+
+impl std::fmt::Display for ModelState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelState::Untested => write!(f, "untested"),
+            ModelState::Ready => write!(f, "ready"),
+            ModelState::Broken => write!(f, "broken"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelHealth {
+    pub id: String,
+    pub state: ModelState,
+    /// Context window, if the server advertises one.
+    pub context_window: Option<u32>,
+    pub last_latency: Option<Duration>,
+    pub last_error: Option<String>,
+    pub checked_at: Option<Instant>,
+}
+
+impl ModelHealth {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            state: ModelState::Untested,
+            context_window: None,
+            last_latency: None,
+            last_error: None,
+            checked_at: None,
+        }
+    }
+
+    pub fn is_usable(&self) -> bool {
+        self.state != ModelState::Broken
+    }
+}
+
+/// Everything the prober knows about the endpoint.
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub models: Vec<ModelHealth>,
+    /// False when `/models` is missing or unparseable; `models` then holds
+    /// only the configured model.
+    pub listing_supported: bool,
+    /// False if the last attempt to reach the server failed outright.
+    pub reachable: bool,
+    pub last_probe: Option<Instant>,
+    /// Why the last listing failed, if it did.
+    pub last_error: Option<String>,
+}
+
+impl HealthReport {
+    pub fn get(&self, id: &str) -> Option<&ModelHealth> {
+        self.models.iter().find(|m| m.id == id)
+    }
+
+    /// Ids of models not known to be broken, in server order.
+    pub fn usable(&self) -> Vec<String> {
+        self.models
+            .iter()
+            .filter(|m| m.is_usable())
+            .map(|m| m.id.clone())
+            .collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Cache
+// ════════════════════════════════════════════════════════════════════
+
+/// Shared, cloneable health cache. Reads never wait on network I/O.
+#[derive(Debug, Clone, Default)]
+pub struct HealthCache {
+    inner: Arc<RwLock<HealthReport>>,
+}
+
+impl HealthCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&self) -> HealthReport {
+        self.read().clone()
+    }
+
+    /// True if nothing has been probed yet or the last probe is older than `max_age`.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.read()
+            .last_probe
+            .is_none_or(|t| t.elapsed() > max_age)
+    }
+
+    /// Forget everything (e.g. after a model hot-swap).
+    pub fn clear(&self) {
+        *self.write() = HealthReport::default();
+    }
+
+    /// Replace the model list, keeping what is already known about models
+    /// that are still listed.
+    pub fn set_listing(&self, listed: Vec<(String, Option<u32>)>, supported: bool, error: Option<String>) {
+        let mut report = self.write();
+        // Failures recorded while the server was down say nothing about the models.
+        let recovering = !report.reachable && report.last_probe.is_some();
+        let previous = std::mem::take(&mut report.models);
+        report.models = listed
+            .into_iter()
+            .map(|(id, ctx)| {
+                let mut entry = previous
+                    .iter()
+                    .find(|m| m.id == id && !recovering)
+                    .cloned()
+                    .unwrap_or_else(|| ModelHealth::new(id));
+                entry.context_window = ctx.or(entry.context_window);
+                entry
+            })
+            .collect();
+        report.listing_supported = supported;
+        report.reachable = true;
+        report.last_error = error;
+        report.last_probe = Some(Instant::now());
+    }
+
+    /// The server could not be reached: every known model (or `fallback`,
+    /// if none are known yet) is marked broken with `reason`.
+    pub fn set_unreachable(&self, fallback: &str, reason: &str) {
+        let mut report = self.write();
+        if report.models.is_empty() {
+            report.models.push(ModelHealth::new(fallback));
+        }
+        let now = Instant::now();
+        for m in &mut report.models {
+            m.state = ModelState::Broken;
+            m.last_error = Some(reason.to_string());
+            m.checked_at = Some(now);
+        }
+        report.reachable = false;
+        report.last_error = Some(reason.to_string());
+        report.last_probe = Some(now);
+    }
+
+    pub fn record_success(&self, id: &str, latency: Duration) {
+        self.update(id, |m| {
+            m.state = ModelState::Ready;
+            m.last_latency = Some(latency);
+            m.last_error = None;
+        });
+    }
+
+    pub fn record_failure(&self, id: &str, error: impl Into<String>) {
+        let error = error.into();
+        self.update(id, |m| {
+            m.state = ModelState::Broken;
+            m.last_error = Some(error);
+        });
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut ModelHealth)) {
+        let mut report = self.write();
+        let idx = match report.models.iter().position(|m| m.id == id) {
+            Some(i) => i,
+            None => {
+                report.models.push(ModelHealth::new(id));
+                report.models.len() - 1
+            }
+        };
+        let entry = &mut report.models[idx];
+        f(entry);
+        entry.checked_at = Some(Instant::now());
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, HealthReport> {
+        self.inner.read().unwrap_or_else(|p| p.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HealthReport> {
+        self.inner.write().unwrap_or_else(|p| p.into_inner())
+    }
+}
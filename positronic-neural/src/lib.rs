@@ -3,9 +3,15 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::{json, Value};
 
+pub mod budget;
 pub mod cortex;
+pub mod generate;
+pub mod health;
+pub mod injection;
 pub mod privacy;
 pub mod reflex;
+pub mod suggest;
+pub mod workspace;
 
 /// The interface for any NPU backend.
 #[async_trait]
@@ -1,40 +1,229 @@
-use regex::Regex;
+use regex::{Captures, Regex};
+use std::ops::Range;
+use std::fmt;
 use std::sync::OnceLock;
 
 /// Sanitize sensitive information from strings before sending to AI.
 pub struct PrivacyGuard;
 
-static IP_REGEX: OnceLock<Regex> = OnceLock::new();
-static EMAIL_REGEX: OnceLock<Regex> = OnceLock::new();
-static API_KEY_REGEX: OnceLock<Regex> = OnceLock::new();
+/// Every pattern lives in one alternation so the input is walked once.
+/// Branch order matters: the regex engine prefers the leftmost branch at a
+/// given position, so the most specific (and longest) shapes come first —
+/// a PEM block must win over the base64 inside it, a bearer header over the
+/// JWT it carries, and a JWT over the dotted-number IP heuristic.
+static SCRUB_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn scrub_regex() -> &'static Regex {
+    SCRUB_REGEX.get_or_init(|| {
+        Regex::new(concat!(
+            // PEM private key blocks (RSA, EC, OPENSSH, PKCS#8, ...)
+            r"(?P<pem>-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----)",
+            // Authorization: Bearer <token>
+            r"|(?P<bearer>(?i:bearer)\s+[A-Za-z0-9\-._~+/]{16,}=*)",
+            // JWTs: base64url header.payload.signature, header always starts with {"
+            r"|(?P<jwt>\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,})",
+            // AWS access key IDs
+            r"|(?P<aws>\b(?:AKIA|ASIA)[0-9A-Z]{16}\b)",
+            // Generic API keys (sk-..., gh_...) — heuristic, not exhaustive
+            r"|(?P<key>\b(?:sk-[a-zA-Z0-9]{20,}|gh[pousr]_[a-zA-Z0-9]{20,})\b)",
+            r"|(?P<email>\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b)",
+            r"|(?P<ip>\b(?:\d{1,3}\.){3}\d{1,3}\b)",
+        ))
+        .expect("Invalid scrub regex")
+    })
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Categories & Report
+// ════════════════════════════════════════════════════════════════════
+
+/// The kind of secret a redaction replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrubCategory {
+    Ip,
+    Email,
+    ApiKey,
+    AwsKey,
+    BearerToken,
+    PrivateKey,
+    Jwt,
+}
+
+impl ScrubCategory {
+    pub const ALL: [ScrubCategory; 7] = [
+        ScrubCategory::Ip,
+        ScrubCategory::Email,
+        ScrubCategory::ApiKey,
+        ScrubCategory::AwsKey,
+        ScrubCategory::BearerToken,
+        ScrubCategory::PrivateKey,
+        ScrubCategory::Jwt,
+    ];
+
+    /// The placeholder written in place of the secret.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            ScrubCategory::Ip => "[REDACTED_IP]",
+            ScrubCategory::Email => "[REDACTED_EMAIL]",
+            ScrubCategory::ApiKey => "[REDACTED_KEY]",
+            ScrubCategory::AwsKey => "[REDACTED_AWS_KEY]",
+            ScrubCategory::BearerToken => "Bearer [REDACTED_TOKEN]",
+            ScrubCategory::PrivateKey => "[REDACTED_PRIVATE_KEY]",
+            ScrubCategory::Jwt => "[REDACTED_JWT]",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ScrubCategory::Ip => "ip",
+            ScrubCategory::Email => "email",
+            ScrubCategory::ApiKey => "api key",
+            ScrubCategory::AwsKey => "aws key",
+            ScrubCategory::BearerToken => "bearer token",
+            ScrubCategory::PrivateKey => "private key",
+            ScrubCategory::Jwt => "jwt",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Per-category redaction counts. Carries no content, so it is safe to log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrubReport {
+    counts: [usize; 7],
+}
+
+impl ScrubReport {
+    pub fn count(&self, category: ScrubCategory) -> usize {
+        self.counts[category.index()]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Fold another report into this one (for chunked scrubbing).
+    pub fn merge(&mut self, other: &ScrubReport) {
+        for (a, b) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *a += b;
+        }
+    }
+
+    fn record(&mut self, category: ScrubCategory) {
+        self.counts[category.index()] += 1;
+    }
+}
+
+impl fmt::Display for ScrubReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        write!(f, "redacted {} item{}", total, if total == 1 { "" } else { "s" })?;
+        let parts: Vec<String> = ScrubCategory::ALL
+            .iter()
+            .filter(|c| self.count(**c) > 0)
+            .map(|c| format!("{} {}", self.count(*c), c.label()))
+            .collect();
+        if !parts.is_empty() {
+            write!(f, " ({})", parts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Scrubbing
+// ════════════════════════════════════════════════════════════════════
 
 impl PrivacyGuard {
     /// Scrub PII from the input string.
     pub fn scrub(input: &str) -> String {
-        let mut scrubbed = input.to_string();
-
-        // 1. Scrub IPv4 Addresses
-        let ip_re = IP_REGEX
-            .get_or_init(|| Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").expect("Invalid IP Regex"));
-        scrubbed = ip_re.replace_all(&scrubbed, "[REDACTED_IP]").to_string();
-
-        // 2. Scrub Emails
-        let email_re = EMAIL_REGEX.get_or_init(|| {
-            Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Z|a-z]{2,}\b")
-                .expect("Invalid Email Regex")
-        });
-        scrubbed = email_re
-            .replace_all(&scrubbed, "[REDACTED_EMAIL]")
-            .to_string();
-
-        // 3. Scrub Generic API Keys (sk-..., gh_...)
-        // This is a heuristic and not exhaustive.
-        let key_re = API_KEY_REGEX.get_or_init(|| {
-            Regex::new(r"\b(sk-[a-zA-Z0-9]{20,}|gh[pousr]_[a-zA-Z0-9]{20,})\b")
-                .expect("Invalid Key Regex")
-        });
-        scrubbed = key_re.replace_all(&scrubbed, "[REDACTED_KEY]").to_string();
-
-        scrubbed
+        Self::scrub_with_report(input).0
+    }
+
+    /// Scrub PII and report how many items of each category were removed.
+    pub fn scrub_with_report(input: &str) -> (String, ScrubReport) {
+        let mut out = String::with_capacity(input.len());
+        let report = Self::scrub_into(input, &mut out);
+        (out, report)
+    }
+
+    /// Append the scrubbed form of `input` to `out` in a single pass.
+    ///
+    /// Nothing is allocated besides growth of `out`, so callers processing
+    /// large outputs can reuse one buffer across chunks. Chunks should be
+    /// split on line boundaries — a secret straddling two chunks is not seen.
+    pub fn scrub_into(input: &str, out: &mut String) -> ScrubReport {
+        let mut report = ScrubReport::default();
+        let mut last = 0;
+
+        for caps in scrub_regex().captures_iter(input) {
+            let whole = caps.get(0).expect("group 0 always matches");
+            let Some(category) = classify(&caps) else {
+                continue;
+            };
+            out.push_str(&input[last..whole.start()]);
+            out.push_str(category.placeholder());
+            report.record(category);
+            last = whole.end();
+        }
+
+        out.push_str(&input[last..]);
+        report
+    }
+
+    /// Byte ranges of every secret in `input`, in order, without scrubbing.
+    ///
+    /// For redacting in place (`!present`), where text keeps its width. A
+    /// near-miss rejected by the category check doesn't hide what overlaps
+    /// it: the search resumes one character in, so the `10.0.0.1` in
+    /// `300.10.0.0.1` is still found.
+    pub fn spans(input: &str) -> Vec<(Range<usize>, ScrubCategory)> {
+        let re = scrub_regex();
+        let mut spans = Vec::new();
+        let mut at = 0;
+        while at <= input.len() {
+            let Some(caps) = re.captures_at(input, at) else {
+                break;
+            };
+            let whole = caps.get(0).expect("group 0 always matches");
+            match classify(&caps) {
+                Some(category) => {
+                    spans.push((whole.range(), category));
+                    at = whole.end();
+                }
+                None => at = whole.start() + input[whole.start()..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+        spans
+    }
+}
+
+/// Map a match to its category, rejecting near-misses the regex can't
+/// express (e.g. dotted quads with an octet above 255).
+fn classify(caps: &Captures<'_>) -> Option<ScrubCategory> {
+    if caps.name("pem").is_some() {
+        Some(ScrubCategory::PrivateKey)
+    } else if caps.name("bearer").is_some() {
+        Some(ScrubCategory::BearerToken)
+    } else if caps.name("jwt").is_some() {
+        Some(ScrubCategory::Jwt)
+    } else if caps.name("aws").is_some() {
+        Some(ScrubCategory::AwsKey)
+    } else if caps.name("key").is_some() {
+        Some(ScrubCategory::ApiKey)
+    } else if caps.name("email").is_some() {
+        Some(ScrubCategory::Email)
+    } else if let Some(ip) = caps.name("ip") {
+        let valid = ip.as_str().split('.').all(|o| o.parse::<u16>().is_ok_and(|n| n <= 255));
+        valid.then_some(ScrubCategory::Ip)
+    } else {
+        None
     }
 }
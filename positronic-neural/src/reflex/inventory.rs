@@ -0,0 +1,172 @@
+//! Per-shell command inventories for the Reflex Engine.
+//!
+//! The names a shell accepts are both the candidates a typo is corrected
+//! to and a whitelist: a name in the inventory is never "corrected", so
+//! PowerShell's `sl` (Set-Location) stays `sl`. PowerShell and cmd.exe
+//! resolve names case-insensitively; a correction is always written in
+//! the inventory's own casing (`Get-ChildItem`, `ls`).
+
+use std::collections::HashSet;
+
+const POWERSHELL_TABLE: &str = include_str!("powershell.txt");
+const CMD_TABLE: &str = include_str!("cmd.txt");
+
+/// Unix tools, also the whole candidate set for POSIX shells.
+const POSIX_COMMANDS: &[&str] = &[
+    "git", "cargo", "rustup", "npm", "node", "python", "pip", "docker", "kubectl", "ls",
+    "cd", "cat", "grep", "find", "mkdir", "rmdir", "rm", "cp", "mv", "touch", "chmod",
+    "chown", "echo", "less", "more", "head", "tail", "sort", "uniq", "wc", "sed", "awk",
+    "curl", "wget", "ssh", "scp", "tar", "zip", "unzip", "make", "cmake", "gcc", "clear",
+    "history", "man", "which", "whereis", "sudo", "apt", "brew", "pacman", "dnf", "yum",
+];
+
+/// Developer tools commonly installed on Windows, whichever shell runs them.
+const WINDOWS_TOOLS: &[&str] = &[
+    "git", "cargo", "rustup", "npm", "npx", "node", "python", "py", "pip", "docker",
+    "kubectl", "code", "ssh", "scp", "make", "cmake", "winget", "choco", "scoop", "pwsh",
+];
+
+/// The shell a command line is typed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShellKind {
+    /// bash, zsh, fish, sh and friends.
+    Posix,
+    PowerShell,
+    Cmd,
+}
+
+impl ShellKind {
+    /// The kind of `program`, a path or bare name (`pwsh`,
+    /// `C:\Windows\System32\cmd.exe`, `/bin/zsh`). Arguments after the
+    /// program are ignored.
+    pub fn from_program(program: &str) -> ShellKind {
+        let program = program.trim().trim_matches('"');
+        let lower = program.to_lowercase();
+        // Windows paths split on `\` on every platform.
+        let exe = lower.split(".exe").next().unwrap_or(&lower);
+        let name = exe.rsplit(['/', '\\']).next().unwrap_or(exe);
+        match name.split_whitespace().next().unwrap_or_default() {
+            "pwsh" | "powershell" => ShellKind::PowerShell,
+            "cmd" => ShellKind::Cmd,
+            _ => ShellKind::Posix,
+        }
+    }
+
+    /// The shell a PTY spawns for `program`: the program if one was
+    /// configured, else the platform default (PowerShell on Windows,
+    /// `$SHELL` elsewhere).
+    pub fn detect(program: Option<&str>) -> ShellKind {
+        match program {
+            Some(program) => ShellKind::from_program(program),
+            None if cfg!(windows) => ShellKind::PowerShell,
+            None => std::env::var("SHELL").map_or(ShellKind::Posix, |shell| ShellKind::from_program(&shell)),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ShellKind::Posix => "posix",
+            ShellKind::PowerShell => "powershell",
+            ShellKind::Cmd => "cmd",
+        }
+    }
+}
+
+/// The commands one shell accepts, in their canonical casing.
+#[derive(Debug, Clone)]
+pub struct CommandInventory {
+    shell: ShellKind,
+    names: Vec<String>,
+    /// Lowercased names, for the whitelist.
+    known: HashSet<String>,
+}
+
+impl CommandInventory {
+    /// The static table for `shell`.
+    pub fn builtin(shell: ShellKind) -> CommandInventory {
+        let mut inventory = CommandInventory { shell, names: Vec::new(), known: HashSet::new() };
+        match shell {
+            ShellKind::Posix => inventory.extend(POSIX_COMMANDS.iter().copied()),
+            ShellKind::PowerShell => {
+                inventory.extend(table_names(POWERSHELL_TABLE));
+                inventory.extend(WINDOWS_TOOLS.iter().copied());
+            }
+            ShellKind::Cmd => {
+                inventory.extend(table_names(CMD_TABLE));
+                inventory.extend(WINDOWS_TOOLS.iter().copied());
+            }
+        }
+        inventory
+    }
+
+    /// Add names, e.g. the ones found by running `Get-Command`. Names
+    /// already present keep their first casing.
+    pub fn extend<S: AsRef<str>>(&mut self, names: impl IntoIterator<Item = S>) {
+        for name in names {
+            let name = name.as_ref().trim();
+            if !name.is_empty() && self.known.insert(name.to_lowercase()) {
+                self.names.push(name.to_string());
+            }
+        }
+    }
+
+    pub fn shell(&self) -> ShellKind {
+        self.shell
+    }
+
+    /// True if the shell accepts `word` as a command.
+    pub fn contains(&self, word: &str) -> bool {
+        self.known.contains(&word.to_lowercase())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Names from an inventory table: one per line, `#` comments.
+fn table_names(table: &str) -> impl Iterator<Item = &str> {
+    table.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_kind_from_program() {
+        assert_eq!(ShellKind::from_program("/usr/bin/pwsh"), ShellKind::PowerShell);
+        assert_eq!(ShellKind::from_program("powershell.exe -NoLogo -NoExit"), ShellKind::PowerShell);
+        assert_eq!(ShellKind::from_program(r"C:\Windows\System32\cmd.exe"), ShellKind::Cmd);
+        assert_eq!(ShellKind::from_program("/bin/zsh"), ShellKind::Posix);
+        assert_eq!(ShellKind::detect(Some("bash")), ShellKind::Posix);
+    }
+
+    #[test]
+    fn test_tables_parse_without_comments() {
+        let ps = CommandInventory::builtin(ShellKind::PowerShell);
+        assert!(ps.contains("get-childitem") && ps.contains("sl") && ps.contains("%"));
+        assert!(!ps.names().any(|n| n.starts_with('#')));
+        let cmd = CommandInventory::builtin(ShellKind::Cmd);
+        assert!(cmd.contains("robocopy") && cmd.contains("git"));
+        assert!(!cmd.contains("Get-ChildItem"));
+    }
+
+    #[test]
+    fn test_extend_keeps_the_first_casing() {
+        let mut inventory = CommandInventory::builtin(ShellKind::PowerShell);
+        let before = inventory.len();
+        inventory.extend(["get-childitem", "Get-AzContext", ""]);
+        assert_eq!(inventory.len(), before + 1);
+        assert!(inventory.names().any(|n| n == "Get-ChildItem"));
+        assert!(inventory.contains("GET-AZCONTEXT"));
+    }
+}
@@ -4,17 +4,23 @@
 //! Uses Levenshtein distance and pattern matching for common typos.
 //! Zero-ML fallback that works without NPU or network connectivity.
 //!
+//! Corrections are shell-aware: the engine draws candidates from the
+//! active shell's `CommandInventory`, and never corrects a name that shell
+//! accepts (see `inventory`).
+//!
 //! Eventually this will also wrap `ort` (ONNX Runtime) to run a
 //! quantized SLM locally for Tier 2 inference.
 
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
-/// Known command corrections: common typos -> correct commands
-static KNOWN_TYPOS: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+pub mod inventory;
 
-/// Common shell commands used for Levenshtein matching
-static COMMON_COMMANDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+pub use inventory::{CommandInventory, ShellKind};
+
+/// Known command corrections: common typos -> correct commands. An entry
+/// only applies where the shell has the corrected command.
+static KNOWN_TYPOS: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
 
 fn known_typos() -> &'static HashMap<&'static str, &'static str> {
     KNOWN_TYPOS.get_or_init(|| {
@@ -80,18 +86,6 @@ fn known_typos() -> &'static HashMap<&'static str, &'static str> {
     })
 }
 
-fn common_commands() -> &'static Vec<&'static str> {
-    COMMON_COMMANDS.get_or_init(|| {
-        vec![
-            "git", "cargo", "rustup", "npm", "node", "python", "pip", "docker", "kubectl", "ls",
-            "cd", "cat", "grep", "find", "mkdir", "rmdir", "rm", "cp", "mv", "touch", "chmod",
-            "chown", "echo", "less", "more", "head", "tail", "sort", "uniq", "wc", "sed", "awk",
-            "curl", "wget", "ssh", "scp", "tar", "zip", "unzip", "make", "cmake", "gcc", "clear",
-            "history", "man", "which", "whereis", "sudo", "apt", "brew", "pacman", "dnf", "yum",
-        ]
-    })
-}
-
 /// A suggestion from the Reflex Engine
 #[derive(Debug, Clone, PartialEq)]
 pub struct Suggestion {
@@ -115,18 +109,34 @@ pub enum SuggestionSource {
 }
 
 /// The Reflex Engine: zero-ML heuristic command correction.
-#[derive(Debug)]
-pub struct ReflexEngine {    /// Maximum Levenshtein distance to consider a match
+#[derive(Debug, Clone)]
+pub struct ReflexEngine {
+    /// Maximum Levenshtein distance to consider a match
     max_distance: usize,
     /// Minimum confidence threshold to return a suggestion
     min_confidence: f64,
+    /// Candidates and whitelist for the active shell
+    inventory: CommandInventory,
 }
 
 impl ReflexEngine {
+    /// An engine for POSIX shells.
     pub fn new() -> Self {
+        Self::for_shell(ShellKind::Posix)
+    }
+
+    /// An engine for `shell`'s built-in command table.
+    pub fn for_shell(shell: ShellKind) -> Self {
+        Self::with_inventory(CommandInventory::builtin(shell))
+    }
+
+    /// An engine over `inventory`, e.g. a built-in table extended with
+    /// the commands discovered on this machine.
+    pub fn with_inventory(inventory: CommandInventory) -> Self {
         Self {
             max_distance: 3,
             min_confidence: 0.4,
+            inventory,
         }
     }
 
@@ -135,9 +145,14 @@ impl ReflexEngine {
         Self {
             max_distance,
             min_confidence,
+            ..Self::new()
         }
     }
 
+    pub fn inventory(&self) -> &CommandInventory {
+        &self.inventory
+    }
+
     /// Attempt to fix a mistyped command.
     /// Returns `Some(Suggestion)` if a correction is found above the confidence threshold.
     pub fn fix_command(&self, input: &str) -> Option<Suggestion> {
@@ -151,6 +166,12 @@ impl ReflexEngine {
             return Some(suggestion);
         }
 
+        // A command the shell accepts is never a typo
+        let first_word = trimmed.split_whitespace().next()?;
+        if self.inventory.contains(first_word) {
+            return None;
+        }
+
         // Strategy 2: Try to fix just the first word (the command itself)
         if let Some(suggestion) = self.fix_first_word(trimmed) {
             if suggestion.confidence >= self.min_confidence {
@@ -165,9 +186,15 @@ impl ReflexEngine {
     fn check_known_typos(&self, input: &str) -> Option<Suggestion> {
         let lower = input.to_lowercase();
         let typos = known_typos();
-
-        // Try full command match first
-        if let Some(corrected) = typos.get(lower.as_str()) {
+        let applies = |corrected: &str| {
+            corrected.split_whitespace().next().is_some_and(|cmd| self.inventory.contains(cmd))
+        };
+
+        // Try full command match first (`cd..` is valid in PowerShell and cmd.exe)
+        if let Some(corrected) = typos.get(lower.as_str()).filter(|c| applies(c)) {
+            if self.inventory.contains(&lower) {
+                return None;
+            }
             return Some(Suggestion {
                 corrected: corrected.to_string(),
                 confidence: 1.0,
@@ -177,7 +204,10 @@ impl ReflexEngine {
 
         // Try matching just the first word against known single-word typos
         let first_word = lower.split_whitespace().next()?;
-        if let Some(corrected) = typos.get(first_word) {
+        if self.inventory.contains(first_word) {
+            return None;
+        }
+        if let Some(corrected) = typos.get(first_word).filter(|c| applies(c)) {
             let rest: String = input.trim().chars().skip(first_word.len()).collect();
             return Some(Suggestion {
                 corrected: format!("{}{}", corrected, rest),
@@ -208,8 +238,8 @@ impl ReflexEngine {
         // Levenshtein distance matching
         let mut best_match: Option<(&str, usize)> = None;
 
-        for cmd in common_commands() {
-            let dist = levenshtein_distance(&first_word, cmd);
+        for cmd in self.inventory.names() {
+            let dist = levenshtein_distance(&first_word, &cmd.to_lowercase());
             if dist <= self.max_distance && dist > 0 {
                 match best_match {
                     None => best_match = Some((cmd, dist)),
@@ -236,14 +266,14 @@ impl ReflexEngine {
     }
 
     /// Detect if the input is a character transposition of a known command.
-    fn detect_transposition(&self, word: &str) -> Option<&'static str> {
+    fn detect_transposition(&self, word: &str) -> Option<&str> {
         let chars: Vec<char> = word.chars().collect();
         if chars.len() < 2 {
             return None;
         }
 
-        for cmd in common_commands() {
-            let cmd_chars: Vec<char> = cmd.chars().collect();
+        for cmd in self.inventory.names() {
+            let cmd_chars: Vec<char> = cmd.to_lowercase().chars().collect();
             if chars.len() != cmd_chars.len() {
                 continue;
             }
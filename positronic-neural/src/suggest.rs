@@ -0,0 +1,336 @@
+// positronic-neural/src/suggest.rs
+//
+// Structured command suggestions for `!suggest`.
+//
+// The model is asked for a JSON array of `{command, reason}` objects, but
+// small local models often ignore format instructions. `parse_suggestions`
+// therefore accepts, in order of preference: JSON (bare or fenced), a
+// numbered list, and finally whatever command-looking lines it can find.
+
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// Most suggestions kept from one answer (keys 1–5 in the UI).
+pub const MAX_SUGGESTIONS: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub command: String,
+    /// Short explanation; empty if the model gave none.
+    pub reason: String,
+}
+
+impl Suggestion {
+    pub fn new(command: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self { command: command.into(), reason: reason.into() }
+    }
+}
+
+/// The user message for a suggestion request.
+pub fn suggestion_prompt(goal: &str) -> String {
+    format!(
+        "Suggest up to 3 shell commands to: {}\n\n\
+         Reply with ONLY a JSON array, most likely command first, like:\n\
+         [{{\"command\": \"ls -la\", \"reason\": \"list all files with details\"}}]",
+        goal
+    )
+}
+
+/// Parse a model answer into suggestions, tolerating non-JSON output.
+pub fn parse_suggestions(text: &str) -> Vec<Suggestion> {
+    let parsed = parse_json(text)
+        .filter(|s| !s.is_empty())
+        .or_else(|| Some(parse_numbered(text)).filter(|s| !s.is_empty()))
+        .unwrap_or_else(|| parse_loose(text));
+    finish(parsed)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// JSON
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonItem {
+    Object {
+        #[serde(alias = "cmd")]
+        command: String,
+        #[serde(default, alias = "explanation", alias = "description")]
+        reason: String,
+    },
+    Plain(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonAnswer {
+    List(Vec<JsonItem>),
+    Wrapped {
+        #[serde(alias = "commands")]
+        suggestions: Vec<JsonItem>,
+    },
+    Single(JsonItem),
+}
+
+fn parse_json(text: &str) -> Option<Vec<Suggestion>> {
+    let start = text.find(['[', '{'])?;
+    let close = if text[start..].starts_with('[') { ']' } else { '}' };
+    let end = text.rfind(close)?;
+    if end < start {
+        return None;
+    }
+
+    let answer: JsonAnswer = serde_json::from_str(&text[start..=end]).ok()?;
+    let items = match answer {
+        JsonAnswer::List(items) | JsonAnswer::Wrapped { suggestions: items } => items,
+        JsonAnswer::Single(item) => vec![item],
+    };
+    Some(
+        items
+            .into_iter()
+            .map(|item| match item {
+                JsonItem::Object { command, reason } => Suggestion::new(command, reason),
+                JsonItem::Plain(command) => Suggestion::new(command, ""),
+            })
+            .collect(),
+    )
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Numbered list
+// ════════════════════════════════════════════════════════════════════
+
+static NUMBERED: OnceLock<Regex> = OnceLock::new();
+
+/// `1. …`, `2) …`, `3: …`, optionally bulleted or bolded.
+fn numbered_regex() -> &'static Regex {
+    NUMBERED.get_or_init(|| {
+        Regex::new(r"^\s*(?:[-*]\s*)?(?:\*\*)?\d{1,2}[.):](?:\*\*)?\s+(.+)$").expect("Invalid numbered regex")
+    })
+}
+
+/// Separators between a command and its explanation on one line.
+const REASON_SEPARATORS: &[&str] = &[" — ", " – ", " - ", " # ", " -- "];
+
+fn parse_numbered(text: &str) -> Vec<Suggestion> {
+    let mut out: Vec<Suggestion> = Vec::new();
+    for line in text.lines() {
+        if let Some(caps) = numbered_regex().captures(line) {
+            if let Some(s) = split_item(&caps[1]) {
+                out.push(s);
+            }
+        } else if let Some(last) = out.last_mut() {
+            // An indented line under an item without a reason explains it.
+            let trimmed = line.trim();
+            if last.reason.is_empty() && !trimmed.is_empty() && !trimmed.starts_with("```") {
+                last.reason = trimmed.trim_start_matches(['-', '*', ' ']).to_string();
+            }
+        }
+    }
+    out
+}
+
+/// Split one list item into command and reason.
+fn split_item(item: &str) -> Option<Suggestion> {
+    let item = item.trim();
+
+    // `command` reason
+    if let Some(open) = item.find('`') {
+        let rest = &item[open + 1..];
+        let close = rest.find('`')?;
+        let command = &rest[..close];
+        let reason = format!("{} {}", &item[..open], &rest[close + 1..]);
+        return Some(Suggestion::new(command, trim_reason(&reason)));
+    }
+
+    for sep in REASON_SEPARATORS {
+        if let Some((command, reason)) = item.split_once(sep) {
+            return Some(Suggestion::new(command, trim_reason(reason)));
+        }
+    }
+    Some(Suggestion::new(item, ""))
+}
+
+fn trim_reason(reason: &str) -> String {
+    reason
+        .trim_start_matches(|c: char| c.is_whitespace() || "-—–:#*".contains(c))
+        .trim_end_matches(|c: char| c.is_whitespace() || c == '*')
+        .to_string()
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Free text
+// ════════════════════════════════════════════════════════════════════
+
+/// Last resort: fenced code lines, then `$ ` lines, then inline code,
+/// then a lone short line.
+fn parse_loose(text: &str) -> Vec<Suggestion> {
+    let mut fenced = Vec::new();
+    let mut in_fence = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence && !trimmed.is_empty() && !trimmed.starts_with('#') {
+            fenced.push(Suggestion::new(trimmed, ""));
+        }
+    }
+    if !fenced.is_empty() {
+        return fenced;
+    }
+
+    let prompted: Vec<Suggestion> = text
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("$ "))
+        .map(|c| Suggestion::new(c, ""))
+        .collect();
+    if !prompted.is_empty() {
+        return prompted;
+    }
+
+    let inline: Vec<Suggestion> = text
+        .split('`')
+        .skip(1)
+        .step_by(2)
+        .filter(|c| !c.trim().is_empty())
+        .map(|c| Suggestion::new(c, ""))
+        .collect();
+    if !inline.is_empty() {
+        return inline;
+    }
+
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    match lines.as_slice() {
+        [only] if only.len() <= 120 && !only.ends_with(['.', '?', '!', ':']) => vec![Suggestion::new(*only, "")],
+        _ => Vec::new(),
+    }
+}
+
+/// Clean up commands, drop empties and duplicates, cap the count.
+fn finish(parsed: Vec<Suggestion>) -> Vec<Suggestion> {
+    let mut out: Vec<Suggestion> = Vec::new();
+    for mut s in parsed {
+        s.command = s
+            .command
+            .trim()
+            .trim_matches('`')
+            .trim_start_matches("$ ")
+            .trim()
+            .to_string();
+        s.reason = s.reason.trim().to_string();
+        if s.command.is_empty() || out.iter().any(|o| o.command == s.command) {
+            continue;
+        }
+        out.push(s);
+        if out.len() == MAX_SUGGESTIONS {
+            break;
+        }
+    }
+    out
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(s: &[Suggestion]) -> Vec<&str> {
+        s.iter().map(|s| s.command.as_str()).collect()
+    }
+
+    #[test]
+    fn test_json_array() {
+        let out = parse_suggestions(
+            r#"[{"command": "du -sh *", "reason": "size of each entry"},
+                {"command": "ncdu", "reason": "interactive browser"}]"#,
+        );
+        assert_eq!(out, vec![
+            Suggestion::new("du -sh *", "size of each entry"),
+            Suggestion::new("ncdu", "interactive browser"),
+        ]);
+    }
+
+    #[test]
+    fn test_json_fenced_with_chatter_and_aliases() {
+        let out = parse_suggestions(
+            "Sure! Here you go:\n```json\n[{\"cmd\": \"git log --oneline -5\", \"explanation\": \"last five commits\"}]\n```\nHope that helps.",
+        );
+        assert_eq!(out, vec![Suggestion::new("git log --oneline -5", "last five commits")]);
+    }
+
+    #[test]
+    fn test_json_wrapped_object_and_strings() {
+        let out = parse_suggestions(r#"{"suggestions": ["ls -la", {"command": "tree", "reason": "as a tree"}]}"#);
+        assert_eq!(commands(&out), vec!["ls -la", "tree"]);
+        assert_eq!(out[1].reason, "as a tree");
+    }
+
+    #[test]
+    fn test_numbered_with_backticks() {
+        let out = parse_suggestions(
+            "Here are some options:\n\
+             1. `find . -name '*.rs'` — find Rust files\n\
+             2. `fd -e rs` - faster alternative\n\
+             3) **`rg --files -g '*.rs'`**: list via ripgrep",
+        );
+        assert_eq!(commands(&out), vec!["find . -name '*.rs'", "fd -e rs", "rg --files -g '*.rs'"]);
+        assert_eq!(out[0].reason, "find Rust files");
+        assert_eq!(out[1].reason, "faster alternative");
+        assert_eq!(out[2].reason, "list via ripgrep");
+    }
+
+    #[test]
+    fn test_numbered_plain_with_reason_on_next_line() {
+        let out = parse_suggestions(
+            "1. docker ps -a\n   Lists all containers, including stopped ones.\n2. docker compose ps # services in this project",
+        );
+        assert_eq!(out, vec![
+            Suggestion::new("docker ps -a", "Lists all containers, including stopped ones."),
+            Suggestion::new("docker compose ps", "services in this project"),
+        ]);
+    }
+
+    #[test]
+    fn test_numbered_keeps_braces_in_commands() {
+        let out = parse_suggestions("1. `find . -name '*.tmp' -exec rm {} +` — delete temp files");
+        assert_eq!(commands(&out), vec!["find . -name '*.tmp' -exec rm {} +"]);
+    }
+
+    #[test]
+    fn test_messy_fenced_block() {
+        let out = parse_suggestions(
+            "You can check disk usage with the following:\n\n```bash\n# human readable\ndf -h\n$ du -sh ~\n```\nThe first shows filesystems.",
+        );
+        assert_eq!(commands(&out), vec!["df -h", "du -sh ~"]);
+    }
+
+    #[test]
+    fn test_messy_inline_code_and_prompts() {
+        let out = parse_suggestions("Try `ps aux | grep node` or maybe `pgrep -fl node` to find it.");
+        assert_eq!(commands(&out), vec!["ps aux | grep node", "pgrep -fl node"]);
+
+        let out = parse_suggestions("Run this:\n$ sudo systemctl restart nginx\nthen check the status.");
+        assert_eq!(commands(&out), vec!["sudo systemctl restart nginx"]);
+    }
+
+    #[test]
+    fn test_messy_bare_command_and_prose() {
+        assert_eq!(commands(&parse_suggestions("  htop  \n")), vec!["htop"]);
+        assert!(parse_suggestions("I'm not sure what you mean. Could you clarify?").is_empty());
+        assert!(parse_suggestions("").is_empty());
+    }
+
+    #[test]
+    fn test_dedup_and_cap() {
+        let text = (1..=9).map(|i| format!("{}. `cmd{}`", i, i % 7)).collect::<Vec<_>>().join("\n");
+        let out = parse_suggestions(&text);
+        assert_eq!(out.len(), MAX_SUGGESTIONS);
+        assert_eq!(commands(&out), vec!["cmd1", "cmd2", "cmd3", "cmd4", "cmd5"]);
+    }
+}
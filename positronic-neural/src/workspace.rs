@@ -0,0 +1,599 @@
+// positronic-neural/src/workspace.rs
+//
+// What the model should know about the directory the user is in: the kind
+// of project (from its manifest) and a summary of the git state.
+//
+// Gathering runs `git status`, which can take seconds in a large repo, so
+// it never happens on the caller's thread. `WorkspaceCache` hands out the
+// last result, stale or not, and gathers again in the background: for a
+// directory it hasn't seen, for an entry older than `STALE_AFTER`, and on
+// `refresh` (the runner calls it as each command completes). Every part is
+// size-bounded; a repo with thousands of changes is reported as the first
+// `MAX_CHANGED_FILES` paths and a count.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::privacy::PrivacyGuard;
+
+/// Changed paths listed in the context; the rest are only counted.
+pub const MAX_CHANGED_FILES: usize = 10;
+
+/// `package.json` scripts listed in the context.
+pub const MAX_SCRIPTS: usize = 8;
+
+/// A cached workspace older than this is gathered again when asked for.
+pub const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Parent directories searched for a manifest, above the working directory.
+const MAX_ANCESTORS: usize = 8;
+
+/// Manifests larger than this are not read.
+const MAX_MANIFEST_BYTES: u64 = 256 * 1024;
+
+/// Longest name, version or path kept from a manifest or git.
+const MAX_FIELD_CHARS: usize = 80;
+
+// ════════════════════════════════════════════════════════════════════
+// Project detection
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Rust,
+    Node,
+    Python,
+    Go,
+}
+
+impl ProjectKind {
+    /// Manifests in the order they are checked.
+    const MANIFESTS: [(&'static str, ProjectKind); 4] = [
+        ("Cargo.toml", ProjectKind::Rust),
+        ("package.json", ProjectKind::Node),
+        ("pyproject.toml", ProjectKind::Python),
+        ("go.mod", ProjectKind::Go),
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProjectKind::Rust => "Rust crate",
+            ProjectKind::Node => "Node package",
+            ProjectKind::Python => "Python project",
+            ProjectKind::Go => "Go module",
+        }
+    }
+}
+
+/// The project a directory belongs to, from its manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub kind: ProjectKind,
+    pub name: Option<String>,
+    /// Short facts such as `edition 2021` or `scripts: build, test`.
+    pub details: Vec<String>,
+    /// The directory holding the manifest.
+    pub root: PathBuf,
+}
+
+impl Project {
+    /// The project of `dir` or its nearest ancestor with a manifest.
+    pub fn detect(dir: &Path) -> Option<Project> {
+        dir.ancestors().take(MAX_ANCESTORS + 1).find_map(Project::detect_in)
+    }
+
+    /// The project whose manifest is directly in `dir`.
+    pub fn detect_in(dir: &Path) -> Option<Project> {
+        ProjectKind::MANIFESTS.iter().find_map(|(file, kind)| {
+            let text = read_manifest(&dir.join(file))?;
+            let (name, details) = match kind {
+                ProjectKind::Rust => cargo_manifest(&text),
+                ProjectKind::Node => node_manifest(&text),
+                ProjectKind::Python => python_manifest(&text),
+                ProjectKind::Go => go_manifest(&text),
+            };
+            Some(Project { kind: *kind, name, details, root: dir.to_path_buf() })
+        })
+    }
+
+    /// One line, e.g. `Project: Rust crate positronic-core (edition 2021)`.
+    pub fn summary(&self) -> String {
+        let mut line = format!("Project: {}", self.kind.label());
+        if let Some(name) = &self.name {
+            line.push(' ');
+            line.push_str(name);
+        }
+        if !self.details.is_empty() {
+            line.push_str(&format!(" ({})", self.details.join("; ")));
+        }
+        line
+    }
+}
+
+fn read_manifest(path: &Path) -> Option<String> {
+    let meta = fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_MANIFEST_BYTES {
+        return None;
+    }
+    fs::read_to_string(path).ok()
+}
+
+fn clip(value: &str) -> String {
+    match value.char_indices().nth(MAX_FIELD_CHARS) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+/// A `key = "value"` string from `[section]` of a TOML file. Enough for
+/// the flat fields manifests keep their name and version in.
+fn toml_value(text: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == format!("[{}]", section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((k, v)) = line.split_once('=') else {
+            continue;
+        };
+        if k.trim() != key {
+            continue;
+        }
+        let v = v.trim();
+        let quote = v.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = v[1..].split(quote).next()?;
+        return Some(clip(value));
+    }
+    None
+}
+
+fn has_section(text: &str, section: &str) -> bool {
+    text.lines().any(|line| line.trim() == format!("[{}]", section))
+}
+
+fn cargo_manifest(text: &str) -> (Option<String>, Vec<String>) {
+    let name = toml_value(text, "package", "name");
+    let mut details = Vec::new();
+    if let Some(edition) = toml_value(text, "package", "edition") {
+        details.push(format!("edition {}", edition));
+    }
+    if has_section(text, "workspace") {
+        details.push("workspace".to_string());
+    }
+    (name, details)
+}
+
+fn node_manifest(text: &str) -> (Option<String>, Vec<String>) {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return (None, Vec::new());
+    };
+    let name = json["name"].as_str().map(clip);
+    let mut details = Vec::new();
+    if let Some(scripts) = json["scripts"].as_object().filter(|s| !s.is_empty()) {
+        let mut names: Vec<String> = scripts.keys().take(MAX_SCRIPTS).map(|k| clip(k)).collect();
+        if scripts.len() > MAX_SCRIPTS {
+            names.push(format!("+{} more", scripts.len() - MAX_SCRIPTS));
+        }
+        details.push(format!("scripts: {}", names.join(", ")));
+    }
+    (name, details)
+}
+
+fn python_manifest(text: &str) -> (Option<String>, Vec<String>) {
+    let name = toml_value(text, "project", "name").or_else(|| toml_value(text, "tool.poetry", "name"));
+    let mut details = Vec::new();
+    if let Some(python) = toml_value(text, "project", "requires-python") {
+        details.push(format!("python {}", python));
+    }
+    if has_section(text, "tool.poetry") {
+        details.push("poetry".to_string());
+    }
+    (name, details)
+}
+
+fn go_manifest(text: &str) -> (Option<String>, Vec<String>) {
+    let field = |key: &str| {
+        text.lines()
+            .find_map(|line| line.trim().strip_prefix(key)?.strip_prefix(' ').map(|v| clip(v.trim())))
+    };
+    let details = field("go").map(|v| format!("go {}", v)).into_iter().collect();
+    (field("module"), details)
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Git state
+// ════════════════════════════════════════════════════════════════════
+
+/// Branch, upstream distance and changed files, from
+/// `git status --porcelain=v2 --branch`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitState {
+    /// The branch name, or `detached` when HEAD isn't on one.
+    pub branch: String,
+    /// Commits ahead of and behind the upstream, if there is one.
+    pub upstream: Option<(u32, u32)>,
+    /// The first `MAX_CHANGED_FILES` changed or untracked paths.
+    pub changed: Vec<String>,
+    pub changed_total: usize,
+}
+
+impl GitState {
+    /// Run `git status` in `dir`. `None` outside a repository or without git.
+    pub fn gather(dir: &Path) -> Option<GitState> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["status", "--porcelain=v2", "--branch"])
+            // A background status must not hold the index lock.
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(GitState::parse_porcelain(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    pub fn parse_porcelain(text: &str) -> GitState {
+        let mut state = GitState::default();
+        for line in text.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                if let Some(head) = header.strip_prefix("branch.head ") {
+                    state.branch = if head == "(detached)" { "detached".to_string() } else { clip(head) };
+                } else if let Some(ab) = header.strip_prefix("branch.ab ") {
+                    let mut counts = ab.split_whitespace().map(|n| n.trim_start_matches(['+', '-']).parse().unwrap_or(0));
+                    state.upstream = Some((counts.next().unwrap_or(0), counts.next().unwrap_or(0)));
+                }
+                continue;
+            }
+            let path = match line.split_once(' ') {
+                // Ordinary change: 8 fields before the path.
+                Some(("1", rest)) => rest.splitn(8, ' ').nth(7),
+                // Rename or copy: 9 fields, then `path<TAB>original`.
+                Some(("2", rest)) => rest.splitn(9, ' ').nth(8).map(|p| p.split('\t').next().unwrap_or(p)),
+                // Unmerged: 10 fields before the path.
+                Some(("u", rest)) => rest.splitn(10, ' ').nth(9),
+                Some(("?", path)) => Some(path),
+                _ => None,
+            };
+            if let Some(path) = path {
+                state.changed_total += 1;
+                if state.changed.len() < MAX_CHANGED_FILES {
+                    state.changed.push(clip(path));
+                }
+            }
+        }
+        state
+    }
+
+    /// One line, e.g. `Git: branch main, 2 ahead and 1 behind upstream, 14 changed files`.
+    pub fn summary(&self) -> String {
+        let mut line = format!("Git: branch {}", self.branch);
+        match self.upstream {
+            Some((0, 0)) => line.push_str(", up to date with upstream"),
+            Some((ahead, behind)) => line.push_str(&format!(", {} ahead and {} behind upstream", ahead, behind)),
+            None => line.push_str(", no upstream"),
+        }
+        match self.changed_total {
+            0 => line.push_str(", clean"),
+            1 => line.push_str(", 1 changed file"),
+            n => line.push_str(&format!(", {} changed files", n)),
+        }
+        line
+    }
+
+    /// `Changed files: a, b (+N more)`, or `None` when clean.
+    pub fn changed_line(&self) -> Option<String> {
+        if self.changed.is_empty() {
+            return None;
+        }
+        let mut line = format!("Changed files: {}", self.changed.join(", "));
+        if self.changed_total > self.changed.len() {
+            line.push_str(&format!(" (+{} more)", self.changed_total - self.changed.len()));
+        }
+        Some(line)
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Workspace
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Workspace {
+    pub project: Option<Project>,
+    pub git: Option<GitState>,
+}
+
+impl Workspace {
+    /// Detect the project and read the git state of `dir`. Blocks on git;
+    /// callers on a UI path go through `WorkspaceCache`.
+    pub fn gather(dir: &Path) -> Workspace {
+        Workspace { project: Project::detect(dir), git: GitState::gather(dir) }
+    }
+
+    /// Prompt context lines, most important first, scrubbed by `PrivacyGuard`.
+    pub fn context_lines(&self) -> Vec<String> {
+        let lines = [
+            self.project.as_ref().map(Project::summary),
+            self.git.as_ref().map(GitState::summary),
+            self.git.as_ref().and_then(GitState::changed_line),
+        ];
+        lines.into_iter().flatten().map(|line| PrivacyGuard::scrub(&line)).collect()
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Cache
+// ════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// The last gathered workspace: its directory, and when.
+    entry: Option<(String, Workspace, Instant)>,
+    /// A background gather is running.
+    gathering: bool,
+    /// Directory to gather next, once the running gather finishes.
+    queued: Option<String>,
+}
+
+/// The last gathered `Workspace`, refreshed in the background.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl WorkspaceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The workspace last gathered for `cwd`, however old. Never blocks;
+    /// starts a gather when there is none yet or it has gone stale.
+    pub fn get(&self, cwd: &str) -> Option<Workspace> {
+        let (cached, stale) = match &self.lock().entry {
+            Some((dir, workspace, at)) if dir == cwd => (Some(workspace.clone()), at.elapsed() >= STALE_AFTER),
+            _ => (None, true),
+        };
+        if stale {
+            self.refresh(cwd);
+        }
+        cached
+    }
+
+    /// Gather `cwd` again in the background, e.g. after a command finished.
+    /// While a gather runs, only the latest request is kept.
+    pub fn refresh(&self, cwd: &str) {
+        {
+            let mut state = self.lock();
+            if state.gathering {
+                state.queued = Some(cwd.to_string());
+                return;
+            }
+            state.gathering = true;
+        }
+        let cache = self.clone();
+        let mut dir = cwd.to_string();
+        let spawned = std::thread::Builder::new().name("workspace-context".to_string()).spawn(move || loop {
+            let workspace = Workspace::gather(Path::new(&dir));
+            let mut state = cache.lock();
+            state.entry = Some((dir, workspace, Instant::now()));
+            match state.queued.take() {
+                Some(next) => dir = next,
+                None => {
+                    state.gathering = false;
+                    return;
+                }
+            }
+        });
+        if let Err(e) = spawned {
+            tracing::warn!("Workspace context thread failed to start: {}", e);
+            self.lock().gathering = false;
+        }
+    }
+
+    /// Wait up to `timeout` for a running gather to finish. For tests and
+    /// one-off tools; the UI never waits.
+    pub fn settle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.lock().gathering {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+}
+
+// ════════════════════════════════════════════════════════════════════
+// Tests
+// ════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh fixture directory holding `files`.
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("positronic-workspace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_detects_cargo_crate() {
+        let dir = fixture("cargo", &[(
+            "Cargo.toml",
+            "[package]\nname = \"positronic-core\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nname = \"not-this\"\n",
+        )]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.kind, ProjectKind::Rust);
+        assert_eq!(project.name.as_deref(), Some("positronic-core"));
+        assert_eq!(project.details, vec!["edition 2021"]);
+        assert_eq!(project.summary(), "Project: Rust crate positronic-core (edition 2021)");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_cargo_workspace_without_package() {
+        let dir = fixture("cargo-ws", &[("Cargo.toml", "[workspace]\nmembers = [\"a\", \"b\"]\n")]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.name, None);
+        assert_eq!(project.details, vec!["workspace"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_node_package_and_bounds_scripts() {
+        let scripts: Vec<String> = (0..12).map(|i| format!("\"s{:02}\": \"echo {}\"", i, i)).collect();
+        let json = format!("{{\"name\": \"web-ui\", \"scripts\": {{{}}}}}", scripts.join(", "));
+        let dir = fixture("node", &[("package.json", &json)]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.kind, ProjectKind::Node);
+        assert_eq!(project.name.as_deref(), Some("web-ui"));
+        assert_eq!(project.details, vec!["scripts: s00, s01, s02, s03, s04, s05, s06, s07, +4 more"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_pyproject_and_poetry() {
+        let dir = fixture("python", &[(
+            "pyproject.toml",
+            "[project]\nname = 'scraper'\nrequires-python = \">=3.11\"\n",
+        )]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.kind, ProjectKind::Python);
+        assert_eq!(project.name.as_deref(), Some("scraper"));
+        assert_eq!(project.details, vec!["python >=3.11"]);
+        fs::remove_dir_all(dir).unwrap();
+
+        let dir = fixture("poetry", &[("pyproject.toml", "[tool.poetry]\nname = \"legacy\"\n")]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.name.as_deref(), Some("legacy"));
+        assert_eq!(project.details, vec!["poetry"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_go_module() {
+        let dir = fixture("go", &[("go.mod", "module github.com/acme/tool\n\ngo 1.22\n\nrequire golang.org/x/sys v0.1.0\n")]);
+        let project = Project::detect(&dir).unwrap();
+        assert_eq!(project.kind, ProjectKind::Go);
+        assert_eq!(project.name.as_deref(), Some("github.com/acme/tool"));
+        assert_eq!(project.details, vec!["go 1.22"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_detects_from_a_subdirectory() {
+        let dir = fixture("nested", &[("Cargo.toml", "[package]\nname = \"outer\"\n"), ("src/bin/keep", "")]);
+        let project = Project::detect(&dir.join("src/bin")).unwrap();
+        assert_eq!(project.name.as_deref(), Some("outer"));
+        assert_eq!(project.root, dir);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_manifest_is_no_project() {
+        let dir = fixture("plain", &[("notes.txt", "hello")]);
+        assert_eq!(Project::detect_in(&dir), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parses_porcelain_branch_and_changes() {
+        let status = "\
+# branch.oid 1234567890abcdef
+# branch.head feature/login
+# branch.upstream origin/feature/login
+# branch.ab +2 -1
+1 .M N... 100644 100644 100644 abc abc src/main.rs
+1 A. N... 000000 100644 100644 000 def docs/new file.md
+2 R. N... 100644 100644 100644 abc abc R100 src/lib.rs\tsrc/old.rs
+u UU N... 100644 100644 100644 100644 a b c Cargo.lock
+? scratch.txt
+";
+        let git = GitState::parse_porcelain(status);
+        assert_eq!(git.branch, "feature/login");
+        assert_eq!(git.upstream, Some((2, 1)));
+        assert_eq!(git.changed, vec!["src/main.rs", "docs/new file.md", "src/lib.rs", "Cargo.lock", "scratch.txt"]);
+        assert_eq!(git.summary(), "Git: branch feature/login, 2 ahead and 1 behind upstream, 5 changed files");
+    }
+
+    #[test]
+    fn test_porcelain_detached_and_clean() {
+        let git = GitState::parse_porcelain("# branch.oid abc\n# branch.head (detached)\n");
+        assert_eq!(git.branch, "detached");
+        assert_eq!(git.upstream, None);
+        assert_eq!(git.changed_line(), None);
+        assert_eq!(git.summary(), "Git: branch detached, no upstream, clean");
+    }
+
+    #[test]
+    fn test_thousands_of_changes_are_truncated() {
+        let mut status = "# branch.head main\n# branch.ab +0 -0\n".to_string();
+        for i in 0..5000 {
+            status.push_str(&format!("? build/out{}.o\n", i));
+        }
+        let git = GitState::parse_porcelain(&status);
+        assert_eq!(git.changed.len(), MAX_CHANGED_FILES);
+        assert_eq!(git.changed_total, 5000);
+        let line = git.changed_line().unwrap();
+        assert!(line.ends_with("(+4990 more)"));
+        assert!(line.len() < 300);
+    }
+
+    #[test]
+    fn test_context_lines_are_scrubbed() {
+        let workspace = Workspace {
+            project: Some(Project {
+                kind: ProjectKind::Node,
+                name: Some("tom@example.com-tools".to_string()),
+                details: Vec::new(),
+                root: PathBuf::new(),
+            }),
+            git: Some(GitState::parse_porcelain("# branch.head main\n? notes/10.0.0.1.txt\n")),
+        };
+        let lines = workspace.context_lines();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("[REDACTED_EMAIL]"));
+        assert!(lines[2].contains("[REDACTED_IP]"));
+        assert!(lines.iter().all(|l| !l.contains("tom@example.com") && !l.contains("10.0.0.1")));
+    }
+
+    #[test]
+    fn test_cache_never_blocks_and_fills_in_the_background() {
+        let dir = fixture("cache", &[("go.mod", "module cached\n")]);
+        let cwd = dir.display().to_string();
+        let cache = WorkspaceCache::new();
+        assert_eq!(cache.get(&cwd), None);
+        assert!(cache.settle(Duration::from_secs(10)));
+        let workspace = cache.get(&cwd).unwrap();
+        assert_eq!(workspace.project.unwrap().name.as_deref(), Some("cached"));
+
+        // Another directory isn't answered from this one's entry.
+        assert_eq!(cache.get("/"), None);
+        assert!(cache.settle(Duration::from_secs(10)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! Content detectors from WASM plugins.
+//!
+//! A detector plugin teaches the Holodeck a format it doesn't know (a
+//! protobuf text dump, a team's log schema) without a Positronic release.
+//! The ABI is four exports and no imports:
+//!
+//! - `memory`: where the host writes the text and reads the answer.
+//! - `alloc(len: i32) -> i32`: room for `len` bytes; the host writes the
+//!   text, UTF-8, at the address returned.
+//! - `detect(ptr: i32, len: i32) -> i32`: how sure the plugin is, 0 to
+//!   100, that the text is its format. Anything under `MIN_SCORE` is a no.
+//! - `parse(ptr: i32, len: i32) -> i64`: the text as a JSON document,
+//!   returned as `ptr << 32 | len` of UTF-8 in `memory`. A length of 0
+//!   declines after all.
+//!
+//! Every call gets a fresh instance with its own fuel (the time limit),
+//! memory limit and output cap, so a plugin that loops, allocates without
+//! end or answers in megabytes can't hang ingestion. The first call that
+//! faults disables the plugin for the session (`DetectorSet`).
+
+use std::fmt;
+
+use wasmtime::{ExternType, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, TypedFunc};
+
+pub const ALLOC_EXPORT: &str = "alloc";
+pub const DETECT_EXPORT: &str = "detect";
+pub const PARSE_EXPORT: &str = "parse";
+pub const MEMORY_EXPORT: &str = "memory";
+
+/// Fuel a `detect` call may burn, about one unit per instruction.
+pub const DETECT_FUEL: u64 = 5_000_000;
+
+/// Fuel a `parse` call may burn.
+pub const PARSE_FUEL: u64 = 50_000_000;
+
+/// Most text a plugin is handed; longer text is cut at a char boundary.
+pub const MAX_INPUT_BYTES: usize = 1024 * 1024;
+
+/// Most JSON a `parse` may answer with.
+pub const MAX_OUTPUT_BYTES: usize = 256 * 1024;
+
+/// Most linear memory one call may grow to.
+pub const MAX_MEMORY_BYTES: usize = 32 * 1024 * 1024;
+
+/// Scores under this are a no.
+pub const MIN_SCORE: u8 = 50;
+
+/// How a plugin call went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginFault {
+    /// Out of fuel: the call ran past its time limit.
+    OutOfFuel,
+    Trap(String),
+    OutputTooLarge(usize),
+    /// An answer out of bounds, not UTF-8 or not JSON.
+    BadOutput(String),
+}
+
+impl fmt::Display for PluginFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginFault::OutOfFuel => write!(f, "timed out (out of fuel)"),
+            PluginFault::Trap(reason) => write!(f, "trapped: {}", reason),
+            PluginFault::OutputTooLarge(len) => {
+                write!(f, "answered with {} bytes, over the {} KiB cap", len, MAX_OUTPUT_BYTES / 1024)
+            }
+            PluginFault::BadOutput(reason) => write!(f, "bad output: {}", reason),
+        }
+    }
+}
+
+/// A compiled plugin that exports the detector ABI.
+#[derive(Clone)]
+pub struct DetectorPlugin {
+    name: String,
+    module: Module,
+}
+
+impl fmt::Debug for DetectorPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DetectorPlugin").field("name", &self.name).finish()
+    }
+}
+
+/// One call's instance: the store, the plugin's memory and where the text went.
+struct Call {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    detect: TypedFunc<(i32, i32), i32>,
+    parse: TypedFunc<(i32, i32), i64>,
+    ptr: i32,
+    len: i32,
+}
+
+impl DetectorPlugin {
+    /// `module` as a detector, or `None` when it doesn't export the ABI.
+    /// Its engine must meter fuel (see `WasmHost::detector`).
+    pub fn new(name: impl Into<String>, module: Module) -> Option<Self> {
+        is_detector(&module).then(|| Self { name: name.into(), module })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How sure the plugin is that `text` is its format, 0 to 100.
+    pub fn detect(&self, text: &str) -> Result<u8, PluginFault> {
+        let mut call = self.call(text, DETECT_FUEL)?;
+        let score = call.detect.call(&mut call.store, (call.ptr, call.len)).map_err(fault)?;
+        Ok(score.clamp(0, 100) as u8)
+    }
+
+    /// `text` as JSON, or `None` if the plugin declined.
+    pub fn parse(&self, text: &str) -> Result<Option<String>, PluginFault> {
+        let mut call = self.call(text, PARSE_FUEL)?;
+        let packed = call.parse.call(&mut call.store, (call.ptr, call.len)).map_err(fault)?;
+        let (ptr, len) = unpack(packed);
+        if len == 0 {
+            return Ok(None);
+        }
+        let len = len as usize;
+        if len > MAX_OUTPUT_BYTES {
+            return Err(PluginFault::OutputTooLarge(len));
+        }
+        let start = ptr as usize;
+        let bytes = call
+            .memory
+            .data(&call.store)
+            .get(start..start + len)
+            .ok_or_else(|| PluginFault::BadOutput(format!("{} bytes at {:#x} are out of bounds", len, ptr)))?;
+        let json = std::str::from_utf8(bytes).map_err(|_| PluginFault::BadOutput("not UTF-8".to_string()))?;
+        serde_json::from_str::<serde_json::Value>(json).map_err(|e| PluginFault::BadOutput(format!("not JSON ({})", e)))?;
+        Ok(Some(json.to_string()))
+    }
+
+    /// A fresh instance with `fuel` to burn, `text` written into its memory.
+    fn call(&self, text: &str, fuel: u64) -> Result<Call, PluginFault> {
+        let text = clip(text, MAX_INPUT_BYTES);
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).instances(1).build();
+        let mut store = Store::new(self.module.engine(), limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(fuel).map_err(fault)?;
+        let instance = wasmtime::Instance::new(&mut store, &self.module, &[]).map_err(fault)?;
+        let missing = |name: &str| PluginFault::Trap(format!("missing export `{}`", name));
+        let memory = instance.get_memory(&mut store, MEMORY_EXPORT).ok_or_else(|| missing(MEMORY_EXPORT))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, ALLOC_EXPORT).map_err(fault)?;
+        let detect = instance.get_typed_func::<(i32, i32), i32>(&mut store, DETECT_EXPORT).map_err(fault)?;
+        let parse = instance.get_typed_func::<(i32, i32), i64>(&mut store, PARSE_EXPORT).map_err(fault)?;
+
+        let len = text.len() as i32;
+        let ptr = alloc.call(&mut store, len).map_err(fault)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, text.as_bytes())
+            .map_err(|_| PluginFault::BadOutput(format!("`alloc` returned {:#x}, out of bounds", ptr)))?;
+        Ok(Call { store, memory, detect, parse, ptr, len })
+    }
+}
+
+/// Split `parse`'s answer into its pointer and length.
+pub fn unpack(packed: i64) -> (u32, u32) {
+    let packed = packed as u64;
+    ((packed >> 32) as u32, packed as u32)
+}
+
+/// Does `module` export the detector ABI, and import nothing?
+fn is_detector(module: &Module) -> bool {
+    let func = |name: &str, params: &[fn(&wasmtime::ValType) -> bool], result: fn(&wasmtime::ValType) -> bool| {
+        let Some(ExternType::Func(ty)) = module.get_export(name) else {
+            return false;
+        };
+        let results: Vec<_> = ty.results().collect();
+        ty.params().len() == params.len()
+            && ty.params().zip(params).all(|(p, is)| is(&p))
+            && results.len() == 1
+            && result(&results[0])
+    };
+    let i32 = wasmtime::ValType::is_i32;
+    let i64 = wasmtime::ValType::is_i64;
+    module.imports().len() == 0
+        && matches!(module.get_export(MEMORY_EXPORT), Some(ExternType::Memory(_)))
+        && func(ALLOC_EXPORT, &[i32], i32)
+        && func(DETECT_EXPORT, &[i32, i32], i32)
+        && func(PARSE_EXPORT, &[i32, i32], i64)
+}
+
+fn fault(e: wasmtime::Error) -> PluginFault {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => PluginFault::OutOfFuel,
+        Some(trap) => PluginFault::Trap(trap.to_string()),
+        None => PluginFault::Trap(format!("{:#}", e)),
+    }
+}
+
+fn clip(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+// ════════════════════════════════════════════════════════════════════
+// The session's detectors
+// ════════════════════════════════════════════════════════════════════
+
+/// What a plugin made of some text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detection {
+    pub plugin: String,
+    pub score: u8,
+    pub json: String,
+}
+
+#[derive(Debug)]
+struct Slot {
+    plugin: DetectorPlugin,
+    disabled: Option<PluginFault>,
+}
+
+/// The detector plugins, in load order, and which are disabled.
+#[derive(Debug, Default)]
+pub struct DetectorSet {
+    slots: Vec<Slot>,
+    /// Disabled since `take_disabled` last asked, for the toast.
+    newly_disabled: Vec<(String, PluginFault)>,
+}
+
+impl DetectorSet {
+    pub fn new(plugins: Vec<DetectorPlugin>) -> Self {
+        let slots = plugins.into_iter().map(|plugin| Slot { plugin, disabled: None }).collect();
+        Self { slots, newly_disabled: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Ask every enabled plugin about `text`, then parse with the most
+    /// sure one (ties go to the first loaded). One that declines passes
+    /// to the next; one that faults is disabled and passes too.
+    pub fn detect(&mut self, text: &str) -> Option<Detection> {
+        let mut scored = Vec::new();
+        for i in 0..self.slots.len() {
+            if self.slots[i].disabled.is_some() {
+                continue;
+            }
+            match self.slots[i].plugin.detect(text) {
+                Ok(score) if score >= MIN_SCORE => scored.push((score, i)),
+                Ok(_) => {}
+                Err(fault) => self.disable(i, fault),
+            }
+        }
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        for (score, i) in scored {
+            match self.slots[i].plugin.parse(text) {
+                Ok(Some(json)) => return Some(Detection { plugin: self.slots[i].plugin.name.clone(), score, json }),
+                Ok(None) => {}
+                Err(fault) => self.disable(i, fault),
+            }
+        }
+        None
+    }
+
+    /// Plugins disabled since the last call, and why.
+    pub fn take_disabled(&mut self) -> Vec<(String, PluginFault)> {
+        std::mem::take(&mut self.newly_disabled)
+    }
+
+    /// Each plugin's name and, if it is disabled, why.
+    pub fn states(&self) -> impl Iterator<Item = (&str, Option<&PluginFault>)> {
+        self.slots.iter().map(|slot| (slot.plugin.name(), slot.disabled.as_ref()))
+    }
+
+    fn disable(&mut self, i: usize, fault: PluginFault) {
+        let slot = &mut self.slots[i];
+        self.newly_disabled.push((slot.plugin.name.clone(), fault.clone()));
+        slot.disabled = Some(fault);
+    }
+}
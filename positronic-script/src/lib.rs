@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
+pub mod detector;
+pub mod plugins;
 pub mod wasm_host;
 
 /// Executes a Rust script file using the installed `rust-script` binary.
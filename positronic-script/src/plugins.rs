@@ -0,0 +1,71 @@
+//! The WASM plugins under the data directory's `PLUGINS_DIR`.
+//!
+//! Each `*.wasm` file there is one plugin, named by its file stem. The
+//! registry compiles them all at startup and records what each can do;
+//! for now that is being a content detector (see `detector`). A file
+//! that doesn't compile is listed in `errors` and left out.
+
+use std::path::{Path, PathBuf};
+
+use crate::detector::{DetectorPlugin, DetectorSet};
+use crate::wasm_host::WasmHost;
+
+/// The plugins' directory under the data root.
+pub const PLUGINS_DIR: &str = "positronic-plugins";
+
+/// A loaded plugin and its capabilities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub name: String,
+    pub path: PathBuf,
+    /// Exports the content detector ABI.
+    pub detector: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<PluginInfo>,
+    detectors: Vec<DetectorPlugin>,
+    errors: Vec<String>,
+}
+
+impl PluginRegistry {
+    /// Compile every plugin in `dir`, by name. A missing directory has none.
+    pub fn load(host: &WasmHost, dir: &Path) -> Self {
+        let mut registry = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return registry;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "wasm"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            let loaded = std::fs::read(&path).map_err(anyhow::Error::from).and_then(|bytes| host.detector(&name, &bytes));
+            match loaded {
+                Ok(detector) => {
+                    registry.plugins.push(PluginInfo { name, path, detector: detector.is_some() });
+                    registry.detectors.extend(detector);
+                }
+                Err(e) => registry.errors.push(format!("{}: {:#}", path.display(), e)),
+            }
+        }
+        registry
+    }
+
+    pub fn plugins(&self) -> &[PluginInfo] {
+        &self.plugins
+    }
+
+    /// Why the plugins left out were left out.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// The detector-capable plugins, in name order.
+    pub fn detector_set(&self) -> DetectorSet {
+        DetectorSet::new(self.detectors.clone())
+    }
+}
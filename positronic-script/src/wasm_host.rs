@@ -1,8 +1,12 @@
 use anyhow::Result;
-use wasmtime::{Engine, Linker, Module, Store};
+use wasmtime::{Config, Engine, Linker, Module, Store};
+
+use crate::detector::DetectorPlugin;
 
 pub struct WasmHost {
     engine: Engine,
+    /// Meters fuel, for plugins whose calls must be bounded (`detector`).
+    metered: Engine,
 }
 
 impl std::fmt::Debug for WasmHost {
@@ -14,7 +18,17 @@ impl std::fmt::Debug for WasmHost {
 impl WasmHost {
     pub fn new() -> Result<Self> {
         let engine = Engine::default();
-        Ok(Self { engine })
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let metered = Engine::new(&config)?;
+        Ok(Self { engine, metered })
+    }
+
+    /// Compile a plugin for the content detector ABI (see `detector`);
+    /// `None` when it doesn't export it.
+    pub fn detector(&self, name: &str, wasm_bytes: &[u8]) -> Result<Option<DetectorPlugin>> {
+        let module = Module::new(&self.metered, wasm_bytes)?;
+        Ok(DetectorPlugin::new(name, module))
     }
 
     /// Run a WASM plugin.
@@ -0,0 +1,6 @@
+;; Sure of everything, then answers with 1 MB of output.
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "detect") (param i32 i32) (result i32) (i32.const 100))
+  (func (export "parse") (param i32 i32) (result i64) (i64.const 0x100000)))
@@ -0,0 +1,36 @@
+;; A well-behaved detector: text that starts `kv:` scores 90, and parses
+;; to {"kv":"<the rest>"}.
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "{\"kv\":\"")
+  (data (i32.const 8) "\"}")
+  (global $next (mut i32) (i32.const 1024))
+
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (local.get $ptr))
+
+  (func (export "detect") (param $ptr i32) (param $len i32) (result i32)
+    (if (result i32) (i32.lt_u (local.get $len) (i32.const 3))
+      (then (i32.const 0))
+      (else
+        (select (i32.const 90) (i32.const 0)
+          (i32.and
+            (i32.and
+              (i32.eq (i32.load8_u (local.get $ptr)) (i32.const 107))
+              (i32.eq (i32.load8_u offset=1 (local.get $ptr)) (i32.const 118)))
+            (i32.eq (i32.load8_u offset=2 (local.get $ptr)) (i32.const 58)))))))
+
+  (func (export "parse") (param $ptr i32) (param $len i32) (result i64)
+    (local $rest i32)
+    (local $out i32)
+    (local.set $rest (i32.sub (local.get $len) (i32.const 3)))
+    (local.set $out (call $alloc (i32.add (local.get $rest) (i32.const 9))))
+    (memory.copy (local.get $out) (i32.const 0) (i32.const 7))
+    (memory.copy (i32.add (local.get $out) (i32.const 7)) (i32.add (local.get $ptr) (i32.const 3)) (local.get $rest))
+    (memory.copy (i32.add (i32.add (local.get $out) (i32.const 7)) (local.get $rest)) (i32.const 8) (i32.const 2))
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $out)) (i64.const 32))
+      (i64.extend_i32_u (i32.add (local.get $rest) (i32.const 9))))))
@@ -0,0 +1,8 @@
+;; Never answers: `detect` loops until its fuel runs out.
+(module
+  (memory (export "memory") 1)
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "detect") (param i32 i32) (result i32)
+    (loop $forever (br $forever))
+    (i32.const 100))
+  (func (export "parse") (param i32 i32) (result i64) (i64.const 0)))
@@ -64,3 +64,118 @@ fn test_wasm_host_valid_minimal_module() {
     let result = host.run_plugin(&minimal_wasm);
     assert!(result.is_err());
 }
+
+// ============================================================================
+// Detector Plugin Tests
+// ============================================================================
+
+use positronic_script::detector::{self, DetectorPlugin, DetectorSet, PluginFault};
+use positronic_script::plugins::PluginRegistry;
+use positronic_script::wasm_host::WasmHost;
+
+/// A fixture under `tests/fixtures`, compiled to WASM.
+fn fixture_wasm(name: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(format!("{}.wat", name));
+    wat::parse_file(&path).unwrap()
+}
+
+fn fixture(host: &WasmHost, name: &str) -> DetectorPlugin {
+    host.detector(name, &fixture_wasm(name)).unwrap().expect("exports the detector ABI")
+}
+
+#[test]
+fn test_detector_marshals_text_and_json() {
+    let host = WasmHost::new().unwrap();
+    let kv = fixture(&host, "kv_detector");
+    assert_eq!(kv.name(), "kv_detector");
+    assert_eq!(kv.detect("kv:hello"), Ok(90));
+    assert_eq!(kv.detect("hello"), Ok(0));
+    assert_eq!(kv.parse("kv:héllo wörld").unwrap().as_deref(), Some(r#"{"kv":"héllo wörld"}"#));
+    assert!(matches!(kv.parse(r#"kv:a"b"#), Err(PluginFault::BadOutput(reason)) if reason.starts_with("not JSON")));
+}
+
+#[test]
+fn test_detector_abi_is_checked_on_load() {
+    let host = WasmHost::new().unwrap();
+    assert!(host.detector("empty", b"(module)").unwrap().is_none());
+    let no_parse = br#"(module
+        (memory (export "memory") 1)
+        (func (export "alloc") (param i32) (result i32) (i32.const 0))
+        (func (export "detect") (param i32 i32) (result i32) (i32.const 0)))"#;
+    assert!(host.detector("no_parse", no_parse).unwrap().is_none());
+    let wrong_type = br#"(module
+        (memory (export "memory") 1)
+        (func (export "alloc") (param i32) (result i32) (i32.const 0))
+        (func (export "detect") (param i32 i32) (result i32) (i32.const 0))
+        (func (export "parse") (param i32 i32) (result i32) (i32.const 0)))"#;
+    assert!(host.detector("wrong_type", wrong_type).unwrap().is_none());
+    assert!(host.detector("garbage", b"not wasm").is_err());
+}
+
+#[test]
+fn test_detector_unpack() {
+    assert_eq!(detector::unpack(0x0000_0400_0000_0010), (0x400, 0x10));
+    assert_eq!(detector::unpack(-1), (u32::MAX, u32::MAX));
+    assert_eq!(detector::unpack(0), (0, 0));
+}
+
+#[test]
+fn test_detector_limits_fuel_and_output() {
+    let host = WasmHost::new().unwrap();
+    assert_eq!(fixture(&host, "spin_detector").detect("anything"), Err(PluginFault::OutOfFuel));
+    assert!(matches!(fixture(&host, "trap_detector").detect("anything"), Err(PluginFault::Trap(_))));
+
+    let flood = fixture(&host, "flood_detector");
+    assert_eq!(flood.detect("anything"), Ok(100));
+    assert_eq!(flood.parse("anything"), Err(PluginFault::OutputTooLarge(1024 * 1024)));
+}
+
+#[test]
+fn test_detector_set_disables_faulting_plugins() {
+    let host = WasmHost::new().unwrap();
+    let names = ["spin_detector", "trap_detector", "flood_detector", "kv_detector"];
+    let mut set = DetectorSet::new(names.iter().map(|name| fixture(&host, name)).collect());
+
+    // The flood plugin is surest but its answer is over the cap, so the
+    // next surest parses.
+    let found = set.detect("kv:value").unwrap();
+    assert_eq!((found.plugin.as_str(), found.score, found.json.as_str()), ("kv_detector", 90, r#"{"kv":"value"}"#));
+
+    let disabled = set.take_disabled();
+    let faults: Vec<(&str, &PluginFault)> = disabled.iter().map(|(name, fault)| (name.as_str(), fault)).collect();
+    assert_eq!(faults.len(), 3);
+    assert_eq!(faults[0], ("spin_detector", &PluginFault::OutOfFuel));
+    assert_eq!(faults[1].0, "trap_detector");
+    assert_eq!(faults[2], ("flood_detector", &PluginFault::OutputTooLarge(1024 * 1024)));
+    assert!(set.take_disabled().is_empty(), "each is reported once");
+
+    // Disabled plugins aren't asked again.
+    assert!(set.detect("plain text").is_none());
+    assert!(set.take_disabled().is_empty());
+    let enabled: Vec<&str> = set.states().filter(|(_, fault)| fault.is_none()).map(|(name, _)| name).collect();
+    assert_eq!(enabled, vec!["kv_detector"]);
+}
+
+#[test]
+fn test_plugin_registry_records_capabilities() {
+    let dir = std::env::temp_dir().join(format!("positronic_plugins_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("kv.wasm"), fixture_wasm("kv_detector")).unwrap();
+    std::fs::write(dir.join("inert.wasm"), wat::parse_str("(module)").unwrap()).unwrap();
+    std::fs::write(dir.join("broken.wasm"), b"\0asm garbage").unwrap();
+    std::fs::write(dir.join("README.txt"), b"not a plugin").unwrap();
+
+    let host = WasmHost::new().unwrap();
+    let registry = PluginRegistry::load(&host, &dir);
+    let plugins: Vec<(&str, bool)> = registry.plugins().iter().map(|p| (p.name.as_str(), p.detector)).collect();
+    assert_eq!(plugins, vec![("inert", false), ("kv", true)]);
+    assert_eq!(registry.errors().len(), 1);
+    assert!(registry.errors()[0].contains("broken.wasm"));
+    let mut set = registry.detector_set();
+    assert_eq!(set.len(), 1);
+    assert_eq!(set.detect("kv:x").unwrap().plugin, "kv");
+
+    let _ = std::fs::remove_dir_all(&dir);
+    assert!(PluginRegistry::load(&host, &dir).plugins().is_empty(), "no directory, no plugins");
+}